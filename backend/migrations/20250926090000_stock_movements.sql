@@ -0,0 +1,25 @@
+-- Stock movements ledger
+
+CREATE TABLE warehouse.stock_movements (
+    movement_id BIGSERIAL PRIMARY KEY,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    movement_type VARCHAR(30) NOT NULL,
+
+    -- Signed quantity: positive into stock, negative out of stock
+    quantity DECIMAL(15,4) NOT NULL CHECK (quantity <> 0),
+    unit_cost DECIMAL(15,4),
+
+    -- Source document (receipt, issue slip, transfer, ...)
+    reference_type VARCHAR(50),
+    reference_number VARCHAR(100),
+    notes TEXT,
+
+    movement_date TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    created_by INTEGER
+);
+
+-- Newest-first keyset browsing (WHERE ... AND movement_id < $after ORDER BY movement_id DESC)
+CREATE INDEX idx_movements_warehouse_history ON warehouse.stock_movements(warehouse_id, movement_id DESC);
+CREATE INDEX idx_movements_item_history ON warehouse.stock_movements(item_id, movement_id DESC);
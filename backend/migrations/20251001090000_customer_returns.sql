@@ -0,0 +1,48 @@
+-- Customer returns (RMA) and quarantined stock
+
+-- Stock bucket a movement applies to; quarantined stock is on site but not available to issue
+ALTER TABLE warehouse.stock_movements
+    ADD COLUMN stock_status VARCHAR(20) NOT NULL DEFAULT 'AVAILABLE';
+
+ALTER TABLE warehouse.stock_inventory
+    ADD COLUMN quantity_quarantined DECIMAL(15,4) NOT NULL DEFAULT 0 CHECK (quantity_quarantined >= 0);
+
+CREATE TABLE warehouse.return_authorizations (
+    return_id SERIAL PRIMARY KEY,
+    rma_number VARCHAR(30) UNIQUE NOT NULL,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    customer_name VARCHAR(255) NOT NULL,
+    customer_reference VARCHAR(100),
+    reason VARCHAR(30) NOT NULL,
+    -- AUTHORIZED -> RECEIVED -> COMPLETED, or AUTHORIZED -> CANCELLED
+    status VARCHAR(20) NOT NULL DEFAULT 'AUTHORIZED',
+    notes TEXT,
+
+    received_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    created_by INTEGER
+);
+
+CREATE TABLE warehouse.return_lines (
+    line_id SERIAL PRIMARY KEY,
+    return_id INTEGER NOT NULL REFERENCES warehouse.return_authorizations(return_id) ON DELETE CASCADE,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    reason VARCHAR(30),
+
+    quantity_expected DECIMAL(15,4) NOT NULL CHECK (quantity_expected > 0),
+    quantity_received DECIMAL(15,4) NOT NULL DEFAULT 0,
+
+    -- Inspection outcomes; together they never exceed what was received
+    quantity_restocked DECIMAL(15,4) NOT NULL DEFAULT 0,
+    quantity_scrapped DECIMAL(15,4) NOT NULL DEFAULT 0,
+    quantity_repair DECIMAL(15,4) NOT NULL DEFAULT 0,
+    inspection_notes TEXT,
+
+    CHECK (quantity_received <= quantity_expected),
+    CHECK (quantity_restocked + quantity_scrapped + quantity_repair <= quantity_received)
+);
+
+CREATE INDEX idx_returns_status ON warehouse.return_authorizations(status, return_id DESC);
+CREATE INDEX idx_return_lines_return ON warehouse.return_lines(return_id);
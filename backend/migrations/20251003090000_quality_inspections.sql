@@ -0,0 +1,26 @@
+-- Quality inspection of goods held in quarantine
+
+CREATE TABLE warehouse.quality_inspections (
+    inspection_id SERIAL PRIMARY KEY,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+
+    -- Lot held in quarantine until the inspection is recorded
+    quantity DECIMAL(15,4) NOT NULL CHECK (quantity > 0),
+    reference_type VARCHAR(50),
+    reference_number VARCHAR(100),
+
+    -- PENDING -> PASSED (released to available stock) or FAILED (rejected out of stock)
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING',
+    sample_size INTEGER CHECK (sample_size > 0),
+    defects_found INTEGER CHECK (defects_found >= 0),
+    notes TEXT,
+
+    inspected_at TIMESTAMPTZ,
+    inspected_by INTEGER,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    created_by INTEGER
+);
+
+CREATE INDEX idx_inspections_pending ON warehouse.quality_inspections(warehouse_id, inspection_id)
+    WHERE status = 'PENDING';
@@ -0,0 +1,110 @@
+-- Outbound orders, shipments, inbound receipts (ASNs) and the EDI document log
+
+CREATE TABLE warehouse.outbound_orders (
+    order_id SERIAL PRIMARY KEY,
+    order_number VARCHAR(50) UNIQUE NOT NULL,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    customer_name VARCHAR(255) NOT NULL,
+    -- Customer's own order / PO number
+    customer_reference VARCHAR(100),
+    ship_to_name VARCHAR(255),
+    ship_to_address TEXT,
+    ship_to_city VARCHAR(100),
+    ship_to_state VARCHAR(100),
+    ship_to_postal_code VARCHAR(20),
+    ship_to_country VARCHAR(100),
+    requested_ship_date DATE,
+    -- OPEN -> PARTIALLY_SHIPPED -> SHIPPED, or OPEN -> CANCELLED
+    status VARCHAR(20) NOT NULL DEFAULT 'OPEN',
+    -- API or EDI; EDI orders get a 945 shipping advice when shipped
+    source VARCHAR(10) NOT NULL DEFAULT 'API',
+    edi_partner_id VARCHAR(35),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    created_by INTEGER
+);
+
+CREATE TABLE warehouse.outbound_order_lines (
+    line_id SERIAL PRIMARY KEY,
+    order_id INTEGER NOT NULL REFERENCES warehouse.outbound_orders(order_id) ON DELETE CASCADE,
+    line_number INTEGER NOT NULL,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity_ordered DECIMAL(15,4) NOT NULL CHECK (quantity_ordered > 0),
+    quantity_shipped DECIMAL(15,4) NOT NULL DEFAULT 0,
+    CHECK (quantity_shipped <= quantity_ordered),
+    UNIQUE (order_id, line_number)
+);
+
+CREATE TABLE warehouse.shipments (
+    shipment_id SERIAL PRIMARY KEY,
+    shipment_number VARCHAR(30) UNIQUE NOT NULL,
+    order_id INTEGER NOT NULL REFERENCES warehouse.outbound_orders(order_id),
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    carrier_code VARCHAR(20),
+    tracking_number VARCHAR(100),
+    status VARCHAR(20) NOT NULL DEFAULT 'SHIPPED',
+    shipped_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by INTEGER
+);
+
+CREATE TABLE warehouse.shipment_lines (
+    shipment_line_id SERIAL PRIMARY KEY,
+    shipment_id INTEGER NOT NULL REFERENCES warehouse.shipments(shipment_id) ON DELETE CASCADE,
+    order_line_id INTEGER NOT NULL REFERENCES warehouse.outbound_order_lines(line_id),
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity DECIMAL(15,4) NOT NULL CHECK (quantity > 0)
+);
+
+CREATE TABLE warehouse.inbound_receipts (
+    receipt_id SERIAL PRIMARY KEY,
+    -- Supplier's advance ship notice number
+    asn_number VARCHAR(50) NOT NULL,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    supplier_name VARCHAR(255),
+    purchase_order_number VARCHAR(100),
+    carrier_code VARCHAR(20),
+    expected_date DATE,
+    -- EXPECTED -> PARTIALLY_RECEIVED -> RECEIVED
+    status VARCHAR(20) NOT NULL DEFAULT 'EXPECTED',
+    source VARCHAR(10) NOT NULL DEFAULT 'API',
+    received_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    created_by INTEGER,
+    UNIQUE (warehouse_id, asn_number)
+);
+
+CREATE TABLE warehouse.inbound_receipt_lines (
+    line_id SERIAL PRIMARY KEY,
+    receipt_id INTEGER NOT NULL REFERENCES warehouse.inbound_receipts(receipt_id) ON DELETE CASCADE,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity_expected DECIMAL(15,4) NOT NULL CHECK (quantity_expected > 0),
+    quantity_received DECIMAL(15,4) NOT NULL DEFAULT 0 CHECK (quantity_received >= 0)
+);
+
+CREATE TABLE warehouse.edi_documents (
+    document_id BIGSERIAL PRIMARY KEY,
+    -- INBOUND or OUTBOUND
+    direction VARCHAR(10) NOT NULL,
+    -- X12 transaction set: 856, 940, 945
+    transaction_set VARCHAR(3) NOT NULL,
+    partner_id VARCHAR(35),
+    control_number VARCHAR(20),
+    -- PROCESSED, FAILED (inbound) or READY (outbound, awaiting pickup)
+    status VARCHAR(20) NOT NULL,
+    error TEXT,
+    reference_type VARCHAR(50),
+    reference_id INTEGER,
+    content TEXT NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+-- Interchange control numbers (ISA13) for outbound documents
+CREATE SEQUENCE warehouse.edi_control_number_seq;
+
+CREATE INDEX idx_orders_status ON warehouse.outbound_orders(status, order_id DESC);
+CREATE INDEX idx_order_lines_order ON warehouse.outbound_order_lines(order_id);
+CREATE INDEX idx_shipments_order ON warehouse.shipments(order_id);
+CREATE INDEX idx_receipts_status ON warehouse.inbound_receipts(status, receipt_id DESC);
+CREATE INDEX idx_receipt_lines_receipt ON warehouse.inbound_receipt_lines(receipt_id);
+CREATE INDEX idx_edi_documents_direction ON warehouse.edi_documents(direction, document_id DESC);
@@ -0,0 +1,10 @@
+-- Carrier service, cost and label bought at ship-confirm
+
+ALTER TABLE warehouse.shipments
+    ADD COLUMN service_level VARCHAR(50),
+    ADD COLUMN shipping_cost DECIMAL(12,2),
+    ADD COLUMN currency VARCHAR(3),
+    -- Carrier's id for the label, needed to void it
+    ADD COLUMN label_id VARCHAR(100),
+    ADD COLUMN label_url TEXT,
+    ADD COLUMN label_format VARCHAR(10);
@@ -0,0 +1,33 @@
+-- Carrier tracking timeline per shipment, and the notification log
+
+ALTER TABLE warehouse.shipments ADD COLUMN delivered_at TIMESTAMPTZ;
+
+CREATE INDEX idx_shipments_tracking ON warehouse.shipments (carrier_code, tracking_number);
+
+CREATE TABLE warehouse.shipment_tracking_events (
+    event_id BIGSERIAL PRIMARY KEY,
+    shipment_id INTEGER NOT NULL REFERENCES warehouse.shipments(shipment_id) ON DELETE CASCADE,
+    status VARCHAR(30) NOT NULL,
+    description TEXT,
+    location VARCHAR(255),
+    occurred_at TIMESTAMPTZ NOT NULL,
+    -- WEBHOOK or POLL
+    source VARCHAR(10) NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Carriers resend their whole history on every poll and webhook retry
+    UNIQUE (shipment_id, status, occurred_at)
+);
+
+CREATE TABLE warehouse.notifications (
+    notification_id BIGSERIAL PRIMARY KEY,
+    kind VARCHAR(50) NOT NULL,
+    severity VARCHAR(10) NOT NULL DEFAULT 'INFO',
+    subject VARCHAR(255) NOT NULL,
+    message TEXT,
+    reference_type VARCHAR(30),
+    reference_id INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    acknowledged_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_notifications_open ON warehouse.notifications (notification_id) WHERE acknowledged_at IS NULL;
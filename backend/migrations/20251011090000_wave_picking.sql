@@ -0,0 +1,88 @@
+-- Zones and pick locations, and wave picking: open orders grouped into one picking
+-- run, picked as consolidated tasks and split back into per-order cartons at packing
+
+CREATE TABLE warehouse.zones (
+    zone_id SERIAL PRIMARY KEY,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    zone_code VARCHAR(20) NOT NULL,
+    zone_name VARCHAR(100) NOT NULL,
+    -- Order pickers walk the zones in
+    pick_sequence INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (warehouse_id, zone_code)
+);
+
+CREATE TABLE warehouse.locations (
+    location_id SERIAL PRIMARY KEY,
+    zone_id INTEGER NOT NULL REFERENCES warehouse.zones(zone_id),
+    location_code VARCHAR(30) NOT NULL,
+    pick_sequence INTEGER NOT NULL DEFAULT 0,
+    -- Item this location is the pick face for
+    item_id INTEGER REFERENCES warehouse.items(item_id),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (zone_id, location_code)
+);
+
+CREATE INDEX idx_locations_item ON warehouse.locations (item_id) WHERE item_id IS NOT NULL;
+
+CREATE TABLE warehouse.waves (
+    wave_id SERIAL PRIMARY KEY,
+    wave_number VARCHAR(30) UNIQUE NOT NULL,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    -- Criteria the orders were selected by
+    zone_id INTEGER REFERENCES warehouse.zones(zone_id),
+    carrier_code VARCHAR(20),
+    cutoff_date DATE,
+    status VARCHAR(20) NOT NULL DEFAULT 'PLANNED',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    released_at TIMESTAMPTZ,
+    picked_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    created_by INTEGER
+);
+
+ALTER TABLE warehouse.outbound_orders
+    -- Carrier the customer asked for; waves can be cut per carrier
+    ADD COLUMN carrier_code VARCHAR(20),
+    ADD COLUMN wave_id INTEGER REFERENCES warehouse.waves(wave_id);
+
+ALTER TABLE warehouse.outbound_order_lines
+    ADD COLUMN quantity_packed DECIMAL(15,4) NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_outbound_orders_wave ON warehouse.outbound_orders (wave_id);
+
+-- One task per item per wave, in pick-path order
+CREATE TABLE warehouse.pick_tasks (
+    task_id SERIAL PRIMARY KEY,
+    wave_id INTEGER NOT NULL REFERENCES warehouse.waves(wave_id) ON DELETE CASCADE,
+    sequence INTEGER NOT NULL,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    location_id INTEGER REFERENCES warehouse.locations(location_id),
+    quantity DECIMAL(15,4) NOT NULL CHECK (quantity > 0),
+    quantity_picked DECIMAL(15,4) NOT NULL DEFAULT 0 CHECK (quantity_picked >= 0 AND quantity_picked <= quantity),
+    status VARCHAR(20) NOT NULL DEFAULT 'OPEN',
+    picked_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_pick_tasks_wave ON warehouse.pick_tasks (wave_id, sequence);
+
+CREATE TABLE warehouse.cartons (
+    carton_id SERIAL PRIMARY KEY,
+    carton_number VARCHAR(30) UNIQUE NOT NULL,
+    wave_id INTEGER REFERENCES warehouse.waves(wave_id),
+    order_id INTEGER NOT NULL REFERENCES warehouse.outbound_orders(order_id),
+    packed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by INTEGER
+);
+
+CREATE TABLE warehouse.carton_lines (
+    carton_line_id SERIAL PRIMARY KEY,
+    carton_id INTEGER NOT NULL REFERENCES warehouse.cartons(carton_id) ON DELETE CASCADE,
+    order_line_id INTEGER NOT NULL REFERENCES warehouse.outbound_order_lines(line_id),
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity DECIMAL(15,4) NOT NULL CHECK (quantity > 0)
+);
+
+CREATE INDEX idx_cartons_wave ON warehouse.cartons (wave_id);
+CREATE INDEX idx_cartons_order ON warehouse.cartons (order_id);
@@ -0,0 +1,33 @@
+-- Unified operator task queue (putaway, pick, count, move)
+
+CREATE TABLE warehouse.tasks (
+    task_id SERIAL PRIMARY KEY,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    task_type VARCHAR(20) NOT NULL,
+    -- 0-100, higher is more urgent
+    priority INTEGER NOT NULL DEFAULT 50 CHECK (priority BETWEEN 0 AND 100),
+    status VARCHAR(20) NOT NULL DEFAULT 'OPEN',
+    item_id INTEGER REFERENCES warehouse.items(item_id),
+    quantity DECIMAL(15,4),
+    from_location_id INTEGER REFERENCES warehouse.locations(location_id),
+    to_location_id INTEGER REFERENCES warehouse.locations(location_id),
+    -- Document the task was generated for, e.g. PICK_TASK for wave picks
+    reference_type VARCHAR(30),
+    reference_id INTEGER,
+    notes TEXT,
+    due_at TIMESTAMPTZ,
+    assigned_to INTEGER,
+    assigned_at TIMESTAMPTZ,
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    completed_by INTEGER,
+    quantity_done DECIMAL(15,4),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by INTEGER
+);
+
+CREATE INDEX idx_tasks_queue ON warehouse.tasks (warehouse_id, priority DESC, task_id)
+    WHERE status IN ('OPEN', 'ASSIGNED');
+CREATE INDEX idx_tasks_operator ON warehouse.tasks (assigned_to, status);
+CREATE UNIQUE INDEX idx_tasks_reference ON warehouse.tasks (reference_type, reference_id)
+    WHERE reference_type IS NOT NULL;
@@ -0,0 +1,4 @@
+-- Labor productivity reporting over completed operator tasks
+
+CREATE INDEX idx_tasks_completed ON warehouse.tasks (completed_at, completed_by)
+    WHERE status = 'COMPLETED';
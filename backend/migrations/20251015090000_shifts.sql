@@ -0,0 +1,35 @@
+-- Warehouse shifts and operator rosters
+
+-- Shift times are local to the warehouse
+ALTER TABLE warehouse.warehouses ADD COLUMN timezone VARCHAR(50) DEFAULT 'Asia/Jakarta';
+
+CREATE TABLE warehouse.shifts (
+    shift_id SERIAL PRIMARY KEY,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    shift_code VARCHAR(20) NOT NULL,
+    shift_name VARCHAR(100) NOT NULL,
+    -- A shift whose end is not after its start runs past midnight
+    starts_at TIME NOT NULL,
+    ends_at TIME NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (warehouse_id, shift_code)
+);
+
+CREATE TABLE warehouse.shift_operators (
+    shift_id INTEGER NOT NULL REFERENCES warehouse.shifts(shift_id),
+    operator_id INTEGER NOT NULL,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (shift_id, operator_id)
+);
+
+CREATE INDEX idx_shift_operators_operator ON warehouse.shift_operators (operator_id);
+
+-- Whether a local time of day falls inside a shift window
+CREATE FUNCTION warehouse.in_shift(local_time TIME, starts_at TIME, ends_at TIME) RETURNS BOOLEAN
+LANGUAGE SQL IMMUTABLE AS $$
+    SELECT CASE WHEN starts_at < ends_at
+                THEN local_time >= starts_at AND local_time < ends_at
+                ELSE local_time >= starts_at OR local_time < ends_at
+           END
+$$;
@@ -0,0 +1,63 @@
+-- Delta sync for handheld devices: change log, task versions and applied device mutations
+
+ALTER TABLE warehouse.tasks ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+CREATE FUNCTION warehouse.bump_task_version() RETURNS TRIGGER
+LANGUAGE plpgsql AS $$
+BEGIN
+    NEW.version := OLD.version + 1;
+    RETURN NEW;
+END
+$$;
+
+CREATE TRIGGER tasks_version BEFORE UPDATE ON warehouse.tasks
+    FOR EACH ROW EXECUTE FUNCTION warehouse.bump_task_version();
+
+-- One row per write to a synced entity. `txid` is the writing transaction, so a reader
+-- can hand out the oldest still-running transaction as its cursor and never skip a
+-- change that commits late.
+CREATE TABLE warehouse.sync_changes (
+    change_id BIGSERIAL PRIMARY KEY,
+    entity_type VARCHAR(20) NOT NULL,
+    entity_id INTEGER NOT NULL,
+    warehouse_id INTEGER NOT NULL,
+    txid BIGINT NOT NULL DEFAULT pg_current_xact_id()::TEXT::BIGINT,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_sync_changes_cursor ON warehouse.sync_changes (warehouse_id, txid);
+
+CREATE FUNCTION warehouse.log_task_change() RETURNS TRIGGER
+LANGUAGE plpgsql AS $$
+BEGIN
+    INSERT INTO warehouse.sync_changes (entity_type, entity_id, warehouse_id)
+    VALUES ('TASK', NEW.task_id, NEW.warehouse_id);
+    RETURN NEW;
+END
+$$;
+
+CREATE TRIGGER tasks_sync AFTER INSERT OR UPDATE ON warehouse.tasks
+    FOR EACH ROW EXECUTE FUNCTION warehouse.log_task_change();
+
+CREATE FUNCTION warehouse.log_location_change() RETURNS TRIGGER
+LANGUAGE plpgsql AS $$
+BEGIN
+    INSERT INTO warehouse.sync_changes (entity_type, entity_id, warehouse_id)
+    SELECT 'LOCATION', NEW.location_id, z.warehouse_id FROM warehouse.zones z WHERE z.zone_id = NEW.zone_id;
+    RETURN NEW;
+END
+$$;
+
+CREATE TRIGGER locations_sync AFTER INSERT OR UPDATE ON warehouse.locations
+    FOR EACH ROW EXECUTE FUNCTION warehouse.log_location_change();
+
+-- Outcome of every mutation a device submitted, so resubmitted batches are not applied twice
+CREATE TABLE warehouse.sync_mutations (
+    device_id VARCHAR(100) NOT NULL,
+    mutation_id VARCHAR(64) NOT NULL,
+    task_id INTEGER NOT NULL,
+    status VARCHAR(20) NOT NULL,
+    message TEXT,
+    applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (device_id, mutation_id)
+);
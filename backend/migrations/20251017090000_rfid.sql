@@ -0,0 +1,41 @@
+-- RFID portals, tag registry and de-duplicated portal reads
+
+CREATE TABLE warehouse.rfid_portals (
+    portal_id SERIAL PRIMARY KEY,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    portal_code VARCHAR(30) NOT NULL,
+    portal_name VARCHAR(100) NOT NULL,
+    -- IN portals bring tagged stock into the warehouse, OUT portals take it out
+    direction VARCHAR(3) NOT NULL CHECK (direction IN ('IN', 'OUT')),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (warehouse_id, portal_code)
+);
+
+CREATE TABLE warehouse.rfid_tags (
+    epc VARCHAR(64) PRIMARY KEY,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    serial_number VARCHAR(100),
+    lpn VARCHAR(50),
+    -- Units carried by the tag: 1 for a serialized unit, the case or pallet count for an LPN
+    quantity DECIMAL(15,4) NOT NULL DEFAULT 1 CHECK (quantity > 0),
+    -- Warehouse the tagged stock is in; NULL once it has left through an OUT portal
+    warehouse_id INTEGER REFERENCES warehouse.warehouses(warehouse_id),
+    last_portal_id INTEGER REFERENCES warehouse.rfid_portals(portal_id),
+    last_seen_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_rfid_tags_serial ON warehouse.rfid_tags (serial_number) WHERE serial_number IS NOT NULL;
+CREATE INDEX idx_rfid_tags_lpn ON warehouse.rfid_tags (lpn) WHERE lpn IS NOT NULL;
+
+-- First read of a tag at a portal; repeat reads inside the de-duplication window are dropped
+CREATE TABLE warehouse.rfid_reads (
+    read_id BIGSERIAL PRIMARY KEY,
+    portal_id INTEGER NOT NULL REFERENCES warehouse.rfid_portals(portal_id),
+    epc VARCHAR(64) NOT NULL,
+    read_at TIMESTAMPTZ NOT NULL,
+    crossed BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX idx_rfid_reads_portal_epc ON warehouse.rfid_reads (portal_id, epc, read_at DESC);
@@ -0,0 +1,54 @@
+-- Zone environment sensors, thresholds and cold-chain excursions
+
+-- Acceptable range per zone; a NULL bound is not checked
+CREATE TABLE warehouse.zone_thresholds (
+    zone_id INTEGER PRIMARY KEY REFERENCES warehouse.zones(zone_id),
+    min_temperature DECIMAL(6,2),
+    max_temperature DECIMAL(6,2),
+    min_humidity DECIMAL(5,2),
+    max_humidity DECIMAL(5,2),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (min_temperature IS NULL OR max_temperature IS NULL OR min_temperature <= max_temperature),
+    CHECK (min_humidity IS NULL OR max_humidity IS NULL OR min_humidity <= max_humidity)
+);
+
+CREATE TABLE warehouse.sensors (
+    sensor_id SERIAL PRIMARY KEY,
+    sensor_code VARCHAR(50) NOT NULL UNIQUE,
+    zone_id INTEGER NOT NULL REFERENCES warehouse.zones(zone_id),
+    -- Newest reading seen; older readings arriving late are kept for history only
+    last_reading_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Temperature in degrees Celsius, humidity in percent relative humidity
+CREATE TABLE warehouse.sensor_readings (
+    reading_id BIGSERIAL PRIMARY KEY,
+    sensor_id INTEGER NOT NULL REFERENCES warehouse.sensors(sensor_id),
+    zone_id INTEGER NOT NULL REFERENCES warehouse.zones(zone_id),
+    recorded_at TIMESTAMPTZ NOT NULL,
+    temperature DECIMAL(6,2),
+    humidity DECIMAL(5,2),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (sensor_id, recorded_at)
+);
+
+CREATE INDEX idx_sensor_readings_zone ON warehouse.sensor_readings (zone_id, recorded_at);
+
+-- A stretch of out-of-range readings from one sensor; open until a reading is back in range
+CREATE TABLE warehouse.excursions (
+    excursion_id SERIAL PRIMARY KEY,
+    sensor_id INTEGER NOT NULL REFERENCES warehouse.sensors(sensor_id),
+    zone_id INTEGER NOT NULL REFERENCES warehouse.zones(zone_id),
+    measure VARCHAR(20) NOT NULL CHECK (measure IN ('TEMPERATURE', 'HUMIDITY')),
+    limit_min DECIMAL(6,2),
+    limit_max DECIMAL(6,2),
+    -- Most extreme value seen outside the range
+    peak_value DECIMAL(6,2) NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL,
+    ended_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_excursions_open ON warehouse.excursions (sensor_id, measure) WHERE ended_at IS NULL;
+CREATE INDEX idx_excursions_zone ON warehouse.excursions (zone_id, started_at);
@@ -0,0 +1,13 @@
+-- Dangerous goods classification on items and hazard classes approved per zone
+
+ALTER TABLE warehouse.items
+    ADD COLUMN un_number VARCHAR(6),
+    ADD COLUMN hazard_class VARCHAR(4),
+    ADD COLUMN packing_group VARCHAR(3),
+    ADD COLUMN proper_shipping_name VARCHAR(200),
+    ADD CONSTRAINT items_hazmat_complete CHECK ((un_number IS NULL) = (hazard_class IS NULL));
+
+CREATE INDEX idx_items_hazard_class ON warehouse.items (hazard_class) WHERE hazard_class IS NOT NULL;
+
+-- Hazard classes a zone may store; an empty list keeps dangerous goods out altogether
+ALTER TABLE warehouse.zones ADD COLUMN hazard_classes VARCHAR(4)[] NOT NULL DEFAULT '{}';
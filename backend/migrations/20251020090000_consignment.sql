@@ -0,0 +1,58 @@
+-- Consignment stock: supplier-owned inventory held in our warehouses until we consume it
+
+CREATE TABLE warehouse.consignors (
+    consignor_id SERIAL PRIMARY KEY,
+    consignor_code VARCHAR(30) NOT NULL UNIQUE,
+    consignor_name VARCHAR(255) NOT NULL,
+    currency VARCHAR(3) NOT NULL DEFAULT 'IDR',
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Agreed price per unit; consumption is billed at the price in force when it happens
+CREATE TABLE warehouse.consignment_prices (
+    consignor_id INTEGER NOT NULL REFERENCES warehouse.consignors(consignor_id),
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    unit_price DECIMAL(15,4) NOT NULL CHECK (unit_price >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (consignor_id, item_id)
+);
+
+-- Ownership dimension: NULL is our own stock, otherwise the consignor that owns it
+ALTER TABLE warehouse.stock_inventory
+    ADD COLUMN consignor_id INTEGER REFERENCES warehouse.consignors(consignor_id),
+    DROP CONSTRAINT stock_inventory_item_id_warehouse_id_key,
+    ADD CONSTRAINT stock_inventory_item_warehouse_owner_key UNIQUE NULLS NOT DISTINCT (item_id, warehouse_id, consignor_id);
+
+ALTER TABLE warehouse.inbound_receipts
+    ADD COLUMN consignor_id INTEGER REFERENCES warehouse.consignors(consignor_id);
+
+-- Self-billing documents raised for consumed consignment stock
+CREATE TABLE warehouse.consignment_settlements (
+    settlement_id SERIAL PRIMARY KEY,
+    settlement_number VARCHAR(30) NOT NULL UNIQUE,
+    consignor_id INTEGER NOT NULL REFERENCES warehouse.consignors(consignor_id),
+    -- Consumption up to this moment is covered
+    period_end TIMESTAMPTZ NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    total_amount DECIMAL(18,4) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by INTEGER
+);
+
+CREATE TABLE warehouse.consignment_settlement_lines (
+    line_id SERIAL PRIMARY KEY,
+    settlement_id INTEGER NOT NULL REFERENCES warehouse.consignment_settlements(settlement_id) ON DELETE CASCADE,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity DECIMAL(15,4) NOT NULL,
+    unit_price DECIMAL(15,4) NOT NULL,
+    amount DECIMAL(18,4) NOT NULL
+);
+
+ALTER TABLE warehouse.stock_movements
+    ADD COLUMN consignor_id INTEGER REFERENCES warehouse.consignors(consignor_id),
+    -- Set on consumption movements once they are billed
+    ADD COLUMN settlement_id INTEGER REFERENCES warehouse.consignment_settlements(settlement_id);
+
+CREATE INDEX idx_stock_movements_consignor ON warehouse.stock_movements (consignor_id, movement_date)
+    WHERE consignor_id IS NOT NULL;
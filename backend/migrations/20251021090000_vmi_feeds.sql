@@ -0,0 +1,42 @@
+-- Vendor-managed inventory: scheduled stock-level and consumption feeds to suppliers
+
+CREATE TABLE warehouse.vmi_feeds (
+    feed_id SERIAL PRIMARY KEY,
+    supplier_code VARCHAR(30) NOT NULL UNIQUE,
+    supplier_name VARCHAR(255) NOT NULL,
+    transport VARCHAR(10) NOT NULL CHECK (transport IN ('API', 'SFTP')),
+    -- API: https URL the feed is POSTed to; SFTP: sftp://user@host[:port]/directory
+    destination VARCHAR(500) NOT NULL,
+    api_key VARCHAR(255),
+    frequency_minutes INTEGER NOT NULL CHECK (frequency_minutes >= 5),
+    -- NULL reports every warehouse
+    warehouse_id INTEGER REFERENCES warehouse.warehouses(warehouse_id),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    -- End of the period covered by the last delivered feed; consumption is reported since then
+    last_sent_at TIMESTAMPTZ,
+    next_run_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_vmi_feeds_due ON warehouse.vmi_feeds (next_run_at) WHERE is_active;
+
+CREATE TABLE warehouse.vmi_feed_items (
+    feed_id INTEGER NOT NULL REFERENCES warehouse.vmi_feeds(feed_id) ON DELETE CASCADE,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    PRIMARY KEY (feed_id, item_id)
+);
+
+CREATE TABLE warehouse.vmi_feed_runs (
+    run_id BIGSERIAL PRIMARY KEY,
+    feed_id INTEGER NOT NULL REFERENCES warehouse.vmi_feeds(feed_id) ON DELETE CASCADE,
+    period_start TIMESTAMPTZ NOT NULL,
+    period_end TIMESTAMPTZ NOT NULL,
+    status VARCHAR(10) NOT NULL CHECK (status IN ('SENT', 'FAILED')),
+    line_count INTEGER NOT NULL,
+    file_name VARCHAR(255),
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_vmi_feed_runs_feed ON warehouse.vmi_feed_runs (feed_id, run_id DESC);
@@ -0,0 +1,14 @@
+-- Dropship orders: fulfilled by a supplier straight to the customer, never touching our stock
+
+ALTER TABLE warehouse.outbound_orders
+    ADD COLUMN order_type VARCHAR(10) NOT NULL DEFAULT 'STANDARD' CHECK (order_type IN ('STANDARD', 'DROPSHIP')),
+    -- Supplier the order is routed to
+    ADD COLUMN supplier_code VARCHAR(30),
+    -- Supplier's own order number, set when they confirm
+    ADD COLUMN supplier_order_number VARCHAR(100),
+    ADD COLUMN supplier_confirmed_at TIMESTAMPTZ,
+    ADD CONSTRAINT outbound_orders_dropship_supplier CHECK ((order_type = 'DROPSHIP') = (supplier_code IS NOT NULL));
+
+-- Shipments the supplier made for a dropship order; no stock was issued for them
+ALTER TABLE warehouse.shipments
+    ADD COLUMN shipped_by_supplier BOOLEAN NOT NULL DEFAULT FALSE;
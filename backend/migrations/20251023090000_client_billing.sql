@@ -0,0 +1,78 @@
+-- 3PL client billing: clients own items, billable events are captured from warehouse
+-- activity and priced monthly against each client's rate card
+
+CREATE TABLE warehouse.billing_clients (
+    client_id SERIAL PRIMARY KEY,
+    client_code VARCHAR(30) NOT NULL UNIQUE,
+    client_name VARCHAR(255) NOT NULL,
+    currency VARCHAR(3) NOT NULL DEFAULT 'IDR',
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- An item belongs to at most one client; activity before the assignment is not billed
+CREATE TABLE warehouse.billing_client_items (
+    item_id INTEGER PRIMARY KEY REFERENCES warehouse.items(item_id),
+    client_id INTEGER NOT NULL REFERENCES warehouse.billing_clients(client_id),
+    assigned_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_billing_client_items_client ON warehouse.billing_client_items (client_id);
+
+-- Rate card: STORAGE is charged per unit-day, RECEIPT and PICK per unit, value-added
+-- services (any other charge code) per unit of service recorded
+CREATE TABLE warehouse.billing_rates (
+    client_id INTEGER NOT NULL REFERENCES warehouse.billing_clients(client_id),
+    charge_code VARCHAR(30) NOT NULL,
+    description VARCHAR(255),
+    unit_rate DECIMAL(15,4) NOT NULL CHECK (unit_rate >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (client_id, charge_code)
+);
+
+CREATE TABLE warehouse.billing_invoices (
+    invoice_id SERIAL PRIMARY KEY,
+    invoice_number VARCHAR(30) NOT NULL UNIQUE,
+    client_id INTEGER NOT NULL REFERENCES warehouse.billing_clients(client_id),
+    period_start DATE NOT NULL,
+    period_end DATE NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    total_amount DECIMAL(18,4) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by INTEGER,
+    UNIQUE (client_id, period_start)
+);
+
+CREATE TABLE warehouse.billing_invoice_lines (
+    line_id SERIAL PRIMARY KEY,
+    invoice_id INTEGER NOT NULL REFERENCES warehouse.billing_invoices(invoice_id) ON DELETE CASCADE,
+    charge_code VARCHAR(30) NOT NULL,
+    description VARCHAR(255),
+    quantity DECIMAL(18,4) NOT NULL,
+    unit_rate DECIMAL(15,4) NOT NULL,
+    amount DECIMAL(18,4) NOT NULL
+);
+
+CREATE TABLE warehouse.billable_events (
+    event_id BIGSERIAL PRIMARY KEY,
+    client_id INTEGER NOT NULL REFERENCES warehouse.billing_clients(client_id),
+    event_type VARCHAR(20) NOT NULL CHECK (event_type IN ('STORAGE', 'RECEIPT', 'PICK', 'SERVICE')),
+    charge_code VARCHAR(30) NOT NULL,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    item_id INTEGER REFERENCES warehouse.items(item_id),
+    quantity DECIMAL(15,4) NOT NULL CHECK (quantity > 0),
+    event_date DATE NOT NULL,
+    -- Receipts and picks are captured once per stock movement
+    movement_id BIGINT UNIQUE REFERENCES warehouse.stock_movements(movement_id),
+    notes TEXT,
+    invoice_id INTEGER REFERENCES warehouse.billing_invoices(invoice_id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by INTEGER
+);
+
+-- One storage snapshot per item, warehouse and day
+CREATE UNIQUE INDEX idx_billable_events_storage_day ON warehouse.billable_events (client_id, item_id, warehouse_id, event_date)
+    WHERE event_type = 'STORAGE';
+
+CREATE INDEX idx_billable_events_unbilled ON warehouse.billable_events (client_id, event_date)
+    WHERE invoice_id IS NULL;
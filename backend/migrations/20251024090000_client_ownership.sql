@@ -0,0 +1,54 @@
+-- Multi-owner inventory: stock and movements carry the 3PL client that owns the goods,
+-- and clients reach their own data through API keys scoped to them
+
+ALTER TABLE warehouse.stock_inventory
+    ADD COLUMN client_id INTEGER REFERENCES warehouse.billing_clients(client_id);
+
+ALTER TABLE warehouse.stock_movements
+    ADD COLUMN client_id INTEGER REFERENCES warehouse.billing_clients(client_id);
+
+CREATE INDEX idx_stock_inventory_client ON warehouse.stock_inventory (client_id) WHERE client_id IS NOT NULL;
+CREATE INDEX idx_movements_client_history ON warehouse.stock_movements (client_id, movement_id DESC)
+    WHERE client_id IS NOT NULL;
+
+-- The owner follows the item's client assignment, whichever code path writes the row
+CREATE FUNCTION warehouse.stamp_client_owner() RETURNS TRIGGER
+LANGUAGE plpgsql AS $$
+BEGIN
+    NEW.client_id := (SELECT client_id FROM warehouse.billing_client_items WHERE item_id = NEW.item_id);
+    RETURN NEW;
+END
+$$;
+
+CREATE TRIGGER stock_inventory_owner BEFORE INSERT ON warehouse.stock_inventory
+    FOR EACH ROW EXECUTE FUNCTION warehouse.stamp_client_owner();
+
+CREATE TRIGGER stock_movements_owner BEFORE INSERT ON warehouse.stock_movements
+    FOR EACH ROW EXECUTE FUNCTION warehouse.stamp_client_owner();
+
+-- Stock already on hand passes to the client when the item is assigned; earlier
+-- movements stay unowned
+CREATE FUNCTION warehouse.assign_stock_owner() RETURNS TRIGGER
+LANGUAGE plpgsql AS $$
+BEGIN
+    UPDATE warehouse.stock_inventory SET client_id = NEW.client_id WHERE item_id = NEW.item_id;
+    RETURN NEW;
+END
+$$;
+
+CREATE TRIGGER billing_client_items_owner AFTER INSERT ON warehouse.billing_client_items
+    FOR EACH ROW EXECUTE FUNCTION warehouse.assign_stock_owner();
+
+-- Only the SHA-256 of a key is kept; the key itself is shown once when issued
+CREATE TABLE warehouse.client_api_keys (
+    key_id SERIAL PRIMARY KEY,
+    client_id INTEGER NOT NULL REFERENCES warehouse.billing_clients(client_id),
+    key_prefix VARCHAR(12) NOT NULL,
+    key_hash VARCHAR(64) NOT NULL UNIQUE,
+    label VARCHAR(100),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_client_api_keys_client ON warehouse.client_api_keys (client_id);
@@ -0,0 +1,21 @@
+-- Cartonization: each warehouse's shipping boxes, used to suggest cartons at packing
+
+CREATE TABLE warehouse.box_sizes (
+    box_id SERIAL PRIMARY KEY,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    box_code VARCHAR(30) NOT NULL,
+    -- Inside dimensions
+    length_cm DECIMAL(10,2) NOT NULL CHECK (length_cm > 0),
+    width_cm DECIMAL(10,2) NOT NULL CHECK (width_cm > 0),
+    height_cm DECIMAL(10,2) NOT NULL CHECK (height_cm > 0),
+    -- Heaviest load the box is rated for, contents only
+    max_weight_kg DECIMAL(10,3) NOT NULL CHECK (max_weight_kg > 0),
+    tare_weight_kg DECIMAL(10,3) NOT NULL DEFAULT 0 CHECK (tare_weight_kg >= 0),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (warehouse_id, box_code)
+);
+
+-- Box the packer used, when it was one of the configured sizes
+ALTER TABLE warehouse.cartons ADD COLUMN box_id INTEGER REFERENCES warehouse.box_sizes(box_id);
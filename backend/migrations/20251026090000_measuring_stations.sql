@@ -0,0 +1,40 @@
+-- Scales and dimensioners at packing and receiving stations; captures update carton and item measurements
+
+CREATE TABLE warehouse.measuring_stations (
+    station_id SERIAL PRIMARY KEY,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    station_code VARCHAR(30) NOT NULL,
+    station_name VARCHAR(100) NOT NULL,
+    -- PACKING stations measure packed cartons, RECEIVING stations measure items
+    station_type VARCHAR(10) NOT NULL CHECK (station_type IN ('PACKING', 'RECEIVING')),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    last_capture_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (warehouse_id, station_code)
+);
+
+-- Every accepted capture, normalised to kg and cm; the raw scale frame is kept for audit
+CREATE TABLE warehouse.station_captures (
+    capture_id BIGSERIAL PRIMARY KEY,
+    station_id INTEGER NOT NULL REFERENCES warehouse.measuring_stations(station_id),
+    carton_id INTEGER REFERENCES warehouse.cartons(carton_id),
+    item_id INTEGER REFERENCES warehouse.items(item_id),
+    weight_kg DECIMAL(10,3),
+    length_cm DECIMAL(10,2),
+    width_cm DECIMAL(10,2),
+    height_cm DECIMAL(10,2),
+    scale_output VARCHAR(100),
+    captured_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((carton_id IS NULL) <> (item_id IS NULL))
+);
+
+CREATE INDEX idx_station_captures_station ON warehouse.station_captures (station_id, capture_id DESC);
+
+-- Measured gross weight and outside dimensions of a packed carton
+ALTER TABLE warehouse.cartons
+    ADD COLUMN weight_kg DECIMAL(10,3),
+    ADD COLUMN length_cm DECIMAL(10,2),
+    ADD COLUMN width_cm DECIMAL(10,2),
+    ADD COLUMN height_cm DECIMAL(10,2),
+    ADD COLUMN measured_at TIMESTAMPTZ;
@@ -0,0 +1,12 @@
+-- Packaging materials: stock items a box size uses up per carton packed (the carton or pallet
+-- itself, void fill, tape, stretch wrap), issued from stock as each carton is packed
+
+CREATE TABLE warehouse.box_materials (
+    box_id INTEGER NOT NULL REFERENCES warehouse.box_sizes(box_id) ON DELETE CASCADE,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    -- Units of the item used per carton, in the item's stock unit
+    quantity DECIMAL(15,4) NOT NULL CHECK (quantity > 0),
+    PRIMARY KEY (box_id, item_id)
+);
+
+CREATE INDEX idx_box_materials_item ON warehouse.box_materials (item_id);
@@ -0,0 +1,33 @@
+-- License plates (LPNs): barcoded pallets, cases and containers grouping stock, nestable
+-- (cases on a pallet), moved, received and shipped as a unit
+
+CREATE TABLE warehouse.lpns (
+    lpn_id SERIAL PRIMARY KEY,
+    -- Barcode on the plate; our own 'LPN' numbers unless a supplier label is used
+    lpn_number VARCHAR(30) UNIQUE NOT NULL,
+    lpn_type VARCHAR(10) NOT NULL CHECK (lpn_type IN ('PALLET', 'CASE', 'TOTE', 'CONTAINER')),
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    -- Nested LPNs sit wherever their outermost LPN is
+    location_id INTEGER REFERENCES warehouse.locations(location_id),
+    parent_lpn_id INTEGER REFERENCES warehouse.lpns(lpn_id),
+    status VARCHAR(10) NOT NULL DEFAULT 'ACTIVE' CHECK (status IN ('ACTIVE', 'SHIPPED')),
+    receipt_id INTEGER REFERENCES warehouse.inbound_receipts(receipt_id),
+    shipment_id INTEGER REFERENCES warehouse.shipments(shipment_id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (parent_lpn_id <> lpn_id)
+);
+
+CREATE INDEX idx_lpns_warehouse ON warehouse.lpns (warehouse_id, status);
+CREATE INDEX idx_lpns_parent ON warehouse.lpns (parent_lpn_id) WHERE parent_lpn_id IS NOT NULL;
+
+-- Loose units directly on an LPN; units on nested LPNs are held by those
+CREATE TABLE warehouse.lpn_contents (
+    lpn_id INTEGER NOT NULL REFERENCES warehouse.lpns(lpn_id) ON DELETE CASCADE,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity DECIMAL(15,4) NOT NULL CHECK (quantity > 0),
+    PRIMARY KEY (lpn_id, item_id)
+);
+
+-- The supplier's LPN an ASN line arrives on, so the whole plate can be received in one scan
+ALTER TABLE warehouse.inbound_receipt_lines ADD COLUMN lpn_number VARCHAR(30);
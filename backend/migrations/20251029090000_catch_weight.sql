@@ -0,0 +1,19 @@
+-- Catch weight: items handled in pieces but sold by actual weight carry a nominal piece
+-- weight and a tolerance, and stock and movements track the weight alongside the pieces
+
+ALTER TABLE warehouse.items
+    ADD COLUMN catch_weight_nominal_kg DECIMAL(12,4) CHECK (catch_weight_nominal_kg > 0),
+    -- Allowed deviation of the average piece weight from nominal, in percent
+    ADD COLUMN catch_weight_tolerance_pct DECIMAL(5,2)
+        CHECK (catch_weight_tolerance_pct >= 0 AND catch_weight_tolerance_pct < 100),
+    ADD CONSTRAINT items_catch_weight_complete
+        CHECK ((catch_weight_nominal_kg IS NULL) = (catch_weight_tolerance_pct IS NULL));
+
+-- Actual weight of the pieces on hand and in quarantine; NULL for items counted in pieces only
+ALTER TABLE warehouse.stock_inventory ADD COLUMN catch_weight_kg DECIMAL(15,4);
+
+-- Signed like quantity
+ALTER TABLE warehouse.stock_movements ADD COLUMN catch_weight_kg DECIMAL(15,4);
+
+ALTER TABLE warehouse.inbound_receipt_lines ADD COLUMN weight_received_kg DECIMAL(15,4);
+ALTER TABLE warehouse.shipment_lines ADD COLUMN weight_kg DECIMAL(15,4);
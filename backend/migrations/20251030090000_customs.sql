@@ -0,0 +1,11 @@
+-- Customs data on the item master for shipments leaving the warehouse's country
+
+ALTER TABLE warehouse.items
+    -- Harmonized System code, digits only: 6 internationally, up to 10 with national subheadings
+    ADD COLUMN hs_code VARCHAR(10) CHECK (hs_code ~ '^[0-9]{6,10}$'),
+    -- ISO 3166-1 alpha-2
+    ADD COLUMN country_of_origin CHAR(2) CHECK (country_of_origin ~ '^[A-Z]{2}$'),
+    ADD COLUMN customs_description VARCHAR(200),
+    ADD CONSTRAINT items_customs_complete CHECK (
+        (hs_code IS NULL) = (country_of_origin IS NULL) AND (hs_code IS NULL) = (customs_description IS NULL)
+    );
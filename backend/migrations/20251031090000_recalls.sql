@@ -0,0 +1,28 @@
+-- Lot and serial traceability through the stock ledger, and product recalls that hold
+-- affected stock and report where it went
+
+-- Lot or serial number of the units moved; NULL when not captured
+ALTER TABLE warehouse.stock_movements ADD COLUMN lot_number VARCHAR(50);
+CREATE INDEX idx_movements_item_lot ON warehouse.stock_movements (item_id, lot_number) WHERE lot_number IS NOT NULL;
+
+ALTER TABLE warehouse.shipment_lines ADD COLUMN lot_number VARCHAR(50);
+CREATE INDEX idx_shipment_lines_item_lot ON warehouse.shipment_lines (item_id, lot_number) WHERE lot_number IS NOT NULL;
+
+CREATE TABLE warehouse.recalls (
+    recall_id SERIAL PRIMARY KEY,
+    recall_number VARCHAR(30) UNIQUE NOT NULL,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    -- Either one lot, or an inclusive range of serial numbers of the same length
+    lot_number VARCHAR(50),
+    serial_from VARCHAR(50),
+    serial_to VARCHAR(50),
+    reason TEXT NOT NULL,
+    status VARCHAR(10) NOT NULL DEFAULT 'OPEN' CHECK (status IN ('OPEN', 'CLOSED')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by INTEGER,
+    closed_at TIMESTAMPTZ,
+    CHECK ((lot_number IS NULL) = (serial_from IS NOT NULL AND serial_to IS NOT NULL)),
+    CHECK ((serial_from IS NULL) = (serial_to IS NULL))
+);
+
+CREATE INDEX idx_recalls_item ON warehouse.recalls (item_id);
@@ -0,0 +1,94 @@
+-- Double-entry inventory ledger: every stock movement is posted as a balanced transaction
+-- of entries across inventory buckets and the contra accounts goods come from or go to
+
+CREATE TABLE warehouse.ledger_transactions (
+    transaction_id BIGSERIAL PRIMARY KEY,
+    -- Movement the transaction posts; NULL for ledger-only transfers such as deliveries
+    movement_id BIGINT UNIQUE REFERENCES warehouse.stock_movements(movement_id),
+    reference_type VARCHAR(50),
+    reference_number VARCHAR(100),
+    posted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE warehouse.ledger_entries (
+    entry_id BIGSERIAL PRIMARY KEY,
+    transaction_id BIGINT NOT NULL REFERENCES warehouse.ledger_transactions(transaction_id),
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    consignor_id INTEGER REFERENCES warehouse.consignors(consignor_id),
+    account VARCHAR(20) NOT NULL CHECK (account IN (
+        'ON_HAND', 'RESERVED', 'QUARANTINE', 'IN_TRANSIT', 'ON_LOAN', 'SCRAP',
+        'SUPPLIER', 'CUSTOMER', 'CONSUMED', 'TRANSFER', 'ADJUSTMENT', 'OPENING'
+    )),
+    -- Signed: positive debits the account, negative credits it
+    quantity DECIMAL(15,4) NOT NULL CHECK (quantity <> 0)
+);
+
+CREATE INDEX idx_ledger_entries_transaction ON warehouse.ledger_entries (transaction_id);
+CREATE INDEX idx_ledger_entries_balance ON warehouse.ledger_entries (item_id, warehouse_id, account);
+
+-- Post the movements already in the ledger; the contra accounts mirror `contra_account`
+WITH posted AS (
+    INSERT INTO warehouse.ledger_transactions (movement_id, reference_type, reference_number, posted_at)
+    SELECT movement_id, reference_type, reference_number, movement_date
+    FROM warehouse.stock_movements
+    ORDER BY movement_id
+    RETURNING transaction_id, movement_id
+)
+INSERT INTO warehouse.ledger_entries (transaction_id, item_id, warehouse_id, consignor_id, account, quantity)
+SELECT p.transaction_id, m.item_id, m.warehouse_id, m.consignor_id, leg.account, leg.quantity
+FROM posted p
+JOIN warehouse.stock_movements m ON m.movement_id = p.movement_id
+CROSS JOIN LATERAL (VALUES
+    (CASE WHEN m.stock_status = 'QUARANTINE' THEN 'QUARANTINE' ELSE 'ON_HAND' END, m.quantity),
+    (CASE
+        WHEN m.movement_type IN ('RECEIPT', 'RFID_IN') THEN 'SUPPLIER'
+        WHEN m.movement_type IN ('RETURN_RECEIPT', 'RFID_OUT') THEN 'CUSTOMER'
+        WHEN m.movement_type = 'SHIPMENT' THEN 'IN_TRANSIT'
+        WHEN m.movement_type = 'PACKAGING' THEN 'CONSUMED'
+        WHEN m.movement_type IN ('QC_REJECT', 'RETURN_SCRAP') THEN 'SCRAP'
+        WHEN m.movement_type IN ('QC_RELEASE', 'RETURN_RESTOCK', 'CONSIGNMENT_CONSUME', 'RECALL_HOLD') THEN 'TRANSFER'
+        ELSE 'ADJUSTMENT'
+     END, -m.quantity)
+) AS leg(account, quantity);
+
+-- Shipments the carrier has already delivered leave transit
+WITH delivered AS (
+    INSERT INTO warehouse.ledger_transactions (reference_type, reference_number, posted_at)
+    SELECT 'DELIVERY', shipment_number, delivered_at
+    FROM warehouse.shipments
+    WHERE delivered_at IS NOT NULL
+    RETURNING transaction_id, reference_number
+)
+INSERT INTO warehouse.ledger_entries (transaction_id, item_id, warehouse_id, consignor_id, account, quantity)
+SELECT d.transaction_id, m.item_id, m.warehouse_id, m.consignor_id, leg.account, leg.quantity
+FROM delivered d
+JOIN warehouse.stock_movements m
+  ON m.movement_type = 'SHIPMENT' AND m.reference_type = 'SHIPMENT' AND m.reference_number = d.reference_number
+CROSS JOIN LATERAL (VALUES ('IN_TRANSIT', m.quantity), ('CUSTOMER', -m.quantity)) AS leg(account, quantity);
+
+-- Balances that never went through the movement ledger are brought in as opening stock
+INSERT INTO warehouse.ledger_transactions (reference_type) VALUES ('OPENING');
+
+INSERT INTO warehouse.ledger_entries (transaction_id, item_id, warehouse_id, consignor_id, account, quantity)
+SELECT (SELECT transaction_id FROM warehouse.ledger_transactions WHERE reference_type = 'OPENING'),
+       s.item_id, s.warehouse_id, s.consignor_id, bucket.account, bucket.quantity
+FROM warehouse.stock_inventory s
+CROSS JOIN LATERAL (VALUES
+    ('ON_HAND', s.quantity_on_hand - s.quantity_reserved - COALESCE((
+        SELECT SUM(e.quantity) FROM warehouse.ledger_entries e
+        WHERE e.item_id = s.item_id AND e.warehouse_id = s.warehouse_id
+          AND e.consignor_id IS NOT DISTINCT FROM s.consignor_id AND e.account = 'ON_HAND'), 0)),
+    ('RESERVED', s.quantity_reserved),
+    ('QUARANTINE', s.quantity_quarantined - COALESCE((
+        SELECT SUM(e.quantity) FROM warehouse.ledger_entries e
+        WHERE e.item_id = s.item_id AND e.warehouse_id = s.warehouse_id
+          AND e.consignor_id IS NOT DISTINCT FROM s.consignor_id AND e.account = 'QUARANTINE'), 0))
+) AS bucket(account, quantity)
+WHERE bucket.quantity <> 0;
+
+INSERT INTO warehouse.ledger_entries (transaction_id, item_id, warehouse_id, consignor_id, account, quantity)
+SELECT transaction_id, item_id, warehouse_id, consignor_id, 'OPENING', -SUM(quantity)
+FROM warehouse.ledger_entries
+WHERE transaction_id = (SELECT transaction_id FROM warehouse.ledger_transactions WHERE reference_type = 'OPENING')
+GROUP BY transaction_id, item_id, warehouse_id, consignor_id;
@@ -0,0 +1,18 @@
+-- Negative stock policy: whether an issue may take on-hand stock below zero. A warehouse-wide
+-- policy (item_id NULL) applies to every item unless the item has its own; FORBID when neither
+
+CREATE TABLE warehouse.negative_stock_policies (
+    policy_id SERIAL PRIMARY KEY,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id) ON DELETE CASCADE,
+    item_id INTEGER REFERENCES warehouse.items(item_id) ON DELETE CASCADE,
+    policy VARCHAR(10) NOT NULL CHECK (policy IN ('FORBID', 'WARN', 'ALLOW')),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE NULLS NOT DISTINCT (warehouse_id, item_id)
+);
+
+-- The policy is enforced where movements are posted; reserved stock still has to be on hand
+ALTER TABLE warehouse.stock_inventory
+    DROP CONSTRAINT stock_inventory_quantity_on_hand_check,
+    DROP CONSTRAINT stock_inventory_check,
+    ADD CONSTRAINT stock_inventory_reserved_on_hand_check
+        CHECK (quantity_reserved = 0 OR quantity_reserved <= quantity_on_hand);
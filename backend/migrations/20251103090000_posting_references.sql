@@ -0,0 +1,13 @@
+-- Client-supplied reference keys that make stock postings safe to retry: the first post
+-- with a key claims it, a repeat finds it taken and gets the original result back
+
+CREATE TABLE warehouse.posting_references (
+    reference_id VARCHAR(100) PRIMARY KEY,
+    -- RECEIPT, SHIPMENT, RETURN_RECEIPT, RETURN_INSPECTION or CONSIGNMENT_CONSUME
+    operation VARCHAR(30) NOT NULL,
+    -- Receipt, order, return or consignor the post was made against
+    target_id INTEGER NOT NULL,
+    -- Shipment or movement the post created, for operations that create one
+    result_id BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
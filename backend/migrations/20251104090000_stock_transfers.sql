@@ -0,0 +1,48 @@
+-- Stock transfers between warehouses, run as sagas: each step commits on its own and is
+-- logged, so a failed step can undo the ones before it
+
+CREATE TABLE warehouse.stock_transfers (
+    transfer_id SERIAL PRIMARY KEY,
+    transfer_number VARCHAR(30) UNIQUE NOT NULL,
+    source_warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    destination_warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    status VARCHAR(20) NOT NULL DEFAULT 'PLANNED'
+        CHECK (status IN ('PLANNED', 'IN_TRANSIT', 'RECEIVED', 'COMPENSATED')),
+    carrier_code VARCHAR(20),
+    service_level VARCHAR(50),
+    tracking_number VARCHAR(100),
+    label_id VARCHAR(100),
+    notes TEXT,
+    -- Why the transfer was rolled back
+    failure_reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by INTEGER,
+    dispatched_at TIMESTAMPTZ,
+    received_at TIMESTAMPTZ,
+    compensated_at TIMESTAMPTZ,
+    CHECK (source_warehouse_id <> destination_warehouse_id)
+);
+
+CREATE INDEX idx_transfers_status ON warehouse.stock_transfers (status);
+
+CREATE TABLE warehouse.stock_transfer_lines (
+    line_id SERIAL PRIMARY KEY,
+    transfer_id INTEGER NOT NULL REFERENCES warehouse.stock_transfers(transfer_id) ON DELETE CASCADE,
+    line_number INTEGER NOT NULL,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity DECIMAL(15,4) NOT NULL CHECK (quantity > 0),
+    UNIQUE (transfer_id, line_number)
+);
+
+-- Append-only log of saga steps; a step's latest entry is its state
+CREATE TABLE warehouse.saga_steps (
+    step_id BIGSERIAL PRIMARY KEY,
+    saga_type VARCHAR(30) NOT NULL,
+    saga_id INTEGER NOT NULL,
+    step VARCHAR(30) NOT NULL,
+    status VARCHAR(20) NOT NULL CHECK (status IN ('COMPLETED', 'FAILED', 'COMPENSATED', 'COMPENSATION_FAILED')),
+    detail TEXT,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_saga_steps_saga ON warehouse.saga_steps (saga_type, saga_id, step_id);
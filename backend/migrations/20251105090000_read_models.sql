@@ -0,0 +1,93 @@
+-- Read model for stock queries: one flattened row per stock balance with the item and
+-- warehouse columns list and search endpoints need, so they read a single table. Writes
+-- to the source tables append domain events, which a projector applies to the read
+-- model shortly after they commit.
+
+CREATE TABLE warehouse.domain_events (
+    event_id BIGSERIAL PRIMARY KEY,
+    aggregate_type VARCHAR(20) NOT NULL CHECK (aggregate_type IN ('STOCK', 'ITEM', 'WAREHOUSE')),
+    item_id INTEGER,
+    warehouse_id INTEGER,
+    consignor_id INTEGER,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Set once the projector applied the event; projected events are purged after a day
+    projected_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_domain_events_pending ON warehouse.domain_events (event_id) WHERE projected_at IS NULL;
+CREATE INDEX idx_domain_events_projected ON warehouse.domain_events (projected_at) WHERE projected_at IS NOT NULL;
+
+CREATE FUNCTION warehouse.log_stock_event() RETURNS TRIGGER
+LANGUAGE plpgsql AS $$
+DECLARE
+    balance warehouse.stock_inventory := COALESCE(NEW, OLD);
+BEGIN
+    INSERT INTO warehouse.domain_events (aggregate_type, item_id, warehouse_id, consignor_id)
+    VALUES ('STOCK', balance.item_id, balance.warehouse_id, balance.consignor_id);
+    RETURN NULL;
+END
+$$;
+
+CREATE TRIGGER stock_inventory_events AFTER INSERT OR UPDATE OR DELETE ON warehouse.stock_inventory
+    FOR EACH ROW EXECUTE FUNCTION warehouse.log_stock_event();
+
+CREATE FUNCTION warehouse.log_item_event() RETURNS TRIGGER
+LANGUAGE plpgsql AS $$
+BEGIN
+    INSERT INTO warehouse.domain_events (aggregate_type, item_id) VALUES ('ITEM', COALESCE(NEW.item_id, OLD.item_id));
+    RETURN NULL;
+END
+$$;
+
+CREATE TRIGGER items_events AFTER UPDATE OR DELETE ON warehouse.items
+    FOR EACH ROW EXECUTE FUNCTION warehouse.log_item_event();
+
+CREATE FUNCTION warehouse.log_warehouse_event() RETURNS TRIGGER
+LANGUAGE plpgsql AS $$
+BEGIN
+    INSERT INTO warehouse.domain_events (aggregate_type, warehouse_id)
+    VALUES ('WAREHOUSE', COALESCE(NEW.warehouse_id, OLD.warehouse_id));
+    RETURN NULL;
+END
+$$;
+
+CREATE TRIGGER warehouses_events AFTER UPDATE OR DELETE ON warehouse.warehouses
+    FOR EACH ROW EXECUTE FUNCTION warehouse.log_warehouse_event();
+
+CREATE TABLE warehouse.stock_read_model (
+    item_id INTEGER NOT NULL,
+    warehouse_id INTEGER NOT NULL,
+    consignor_id INTEGER,
+    client_id INTEGER,
+    item_code VARCHAR(100) NOT NULL,
+    item_name VARCHAR(255) NOT NULL,
+    category VARCHAR(100),
+    unit VARCHAR(50),
+    item_status VARCHAR(20),
+    warehouse_code VARCHAR(50) NOT NULL,
+    warehouse_name VARCHAR(255) NOT NULL,
+    quantity_on_hand DECIMAL(15,4) NOT NULL,
+    quantity_reserved DECIMAL(15,4) NOT NULL,
+    quantity_available DECIMAL(15,4) NOT NULL,
+    quantity_quarantined DECIMAL(15,4) NOT NULL,
+    catch_weight_kg DECIMAL(15,4),
+    reorder_point DECIMAL(15,4),
+    last_movement_date DATE,
+    refreshed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE NULLS NOT DISTINCT (item_id, warehouse_id, consignor_id)
+);
+
+CREATE INDEX idx_stock_read_model_code ON warehouse.stock_read_model (item_code, warehouse_id);
+CREATE INDEX idx_stock_read_model_warehouse ON warehouse.stock_read_model (warehouse_id, item_code);
+
+INSERT INTO warehouse.stock_read_model (
+    item_id, warehouse_id, consignor_id, client_id, item_code, item_name, category, unit, item_status,
+    warehouse_code, warehouse_name, quantity_on_hand, quantity_reserved, quantity_available,
+    quantity_quarantined, catch_weight_kg, reorder_point, last_movement_date
+)
+SELECT s.item_id, s.warehouse_id, s.consignor_id, s.client_id, i.item_code, i.item_name, i.category, i.unit,
+       i.status, w.warehouse_code, w.warehouse_name, s.quantity_on_hand, s.quantity_reserved,
+       s.quantity_available, s.quantity_quarantined, s.catch_weight_kg, s.reorder_point, s.last_movement_date
+FROM warehouse.stock_inventory s
+JOIN warehouse.items i ON i.item_id = s.item_id
+JOIN warehouse.warehouses w ON w.warehouse_id = s.warehouse_id;
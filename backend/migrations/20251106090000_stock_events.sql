@@ -0,0 +1,90 @@
+-- Event-sourced stock: every change to a stock balance is appended to a per-balance stream
+-- of events, from which balances can be folded as of any instant. The balances projection
+-- is disposable and can be rebuilt from the events at any time.
+
+CREATE TABLE warehouse.stock_events (
+    event_id BIGSERIAL PRIMARY KEY,
+    -- Stream: one stock balance
+    item_id INTEGER NOT NULL,
+    warehouse_id INTEGER NOT NULL,
+    consignor_id INTEGER,
+    -- Position in the stream; versions are gapless and assigned under the balance's row lock,
+    -- so they commit in order
+    version INTEGER NOT NULL,
+    event_type VARCHAR(20) NOT NULL CHECK (event_type IN ('OPENED', 'CHANGED', 'CLOSED')),
+    on_hand_delta DECIMAL(15,4) NOT NULL DEFAULT 0,
+    reserved_delta DECIMAL(15,4) NOT NULL DEFAULT 0,
+    quarantined_delta DECIMAL(15,4) NOT NULL DEFAULT 0,
+    catch_weight_delta DECIMAL(15,4) NOT NULL DEFAULT 0,
+    -- 3PL client the balance belongs to after the event
+    client_id INTEGER,
+    transaction_id BIGINT NOT NULL DEFAULT txid_current(),
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE NULLS NOT DISTINCT (item_id, warehouse_id, consignor_id, version)
+);
+
+CREATE INDEX idx_stock_events_occurred ON warehouse.stock_events (occurred_at);
+
+CREATE FUNCTION warehouse.append_stock_event() RETURNS TRIGGER
+LANGUAGE plpgsql AS $$
+DECLARE
+    balance warehouse.stock_inventory := COALESCE(NEW, OLD);
+    next_version INTEGER;
+BEGIN
+    IF TG_OP = 'UPDATE'
+       AND NEW.quantity_on_hand = OLD.quantity_on_hand
+       AND NEW.quantity_reserved = OLD.quantity_reserved
+       AND NEW.quantity_quarantined = OLD.quantity_quarantined
+       AND NEW.catch_weight_kg IS NOT DISTINCT FROM OLD.catch_weight_kg
+       AND NEW.client_id IS NOT DISTINCT FROM OLD.client_id THEN
+        RETURN NULL;
+    END IF;
+
+    SELECT COALESCE(MAX(version), 0) + 1 INTO next_version
+    FROM warehouse.stock_events
+    WHERE item_id = balance.item_id AND warehouse_id = balance.warehouse_id
+      AND consignor_id IS NOT DISTINCT FROM balance.consignor_id;
+
+    INSERT INTO warehouse.stock_events (
+        item_id, warehouse_id, consignor_id, version, event_type,
+        on_hand_delta, reserved_delta, quarantined_delta, catch_weight_delta, client_id
+    )
+    VALUES (
+        balance.item_id, balance.warehouse_id, balance.consignor_id, next_version,
+        CASE TG_OP WHEN 'INSERT' THEN 'OPENED' WHEN 'UPDATE' THEN 'CHANGED' ELSE 'CLOSED' END,
+        COALESCE(NEW.quantity_on_hand, 0) - COALESCE(OLD.quantity_on_hand, 0),
+        COALESCE(NEW.quantity_reserved, 0) - COALESCE(OLD.quantity_reserved, 0),
+        COALESCE(NEW.quantity_quarantined, 0) - COALESCE(OLD.quantity_quarantined, 0),
+        COALESCE(NEW.catch_weight_kg, 0) - COALESCE(OLD.catch_weight_kg, 0),
+        NEW.client_id
+    );
+    RETURN NULL;
+END
+$$;
+
+CREATE TRIGGER stock_inventory_event_stream AFTER INSERT OR UPDATE OR DELETE ON warehouse.stock_inventory
+    FOR EACH ROW EXECUTE FUNCTION warehouse.append_stock_event();
+
+-- Existing balances open their streams
+INSERT INTO warehouse.stock_events (
+    item_id, warehouse_id, consignor_id, version, event_type,
+    on_hand_delta, reserved_delta, quarantined_delta, catch_weight_delta, client_id, occurred_at
+)
+SELECT item_id, warehouse_id, consignor_id, 1, 'OPENED', quantity_on_hand, quantity_reserved,
+       quantity_quarantined, COALESCE(catch_weight_kg, 0), client_id, COALESCE(created_at, NOW())
+FROM warehouse.stock_inventory;
+
+-- Balances folded from the events up to `version` of each stream
+CREATE TABLE warehouse.stock_event_balances (
+    item_id INTEGER NOT NULL,
+    warehouse_id INTEGER NOT NULL,
+    consignor_id INTEGER,
+    version INTEGER NOT NULL,
+    quantity_on_hand DECIMAL(15,4) NOT NULL,
+    quantity_reserved DECIMAL(15,4) NOT NULL,
+    quantity_quarantined DECIMAL(15,4) NOT NULL,
+    catch_weight_kg DECIMAL(15,4) NOT NULL,
+    client_id INTEGER,
+    projected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE NULLS NOT DISTINCT (item_id, warehouse_id, consignor_id)
+);
@@ -0,0 +1,17 @@
+-- Downstream consumers of the stock event stream. Each consumer is sent batches of events
+-- in event_id order and remembers how far it got, so it can be rewound and replayed.
+
+CREATE TABLE warehouse.event_consumers (
+    consumer_id SERIAL PRIMARY KEY,
+    consumer_name VARCHAR(50) NOT NULL UNIQUE,
+    -- Batches are POSTed here as JSON
+    endpoint_url VARCHAR(500) NOT NULL,
+    api_key VARCHAR(255),
+    -- Last event_id the consumer acknowledged
+    position BIGINT NOT NULL DEFAULT 0 CHECK (position >= 0),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    last_error TEXT,
+    last_delivered_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
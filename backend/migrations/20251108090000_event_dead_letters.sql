@@ -0,0 +1,24 @@
+-- Retries with backoff for stock event consumers, and a dead-letter queue for batches that
+-- keep failing so one bad batch does not hold up the rest of the stream
+
+ALTER TABLE warehouse.event_consumers
+    ADD COLUMN failed_attempts INTEGER NOT NULL DEFAULT 0,
+    -- Set after a failure; the consumer is not retried before then
+    ADD COLUMN next_attempt_at TIMESTAMPTZ;
+
+CREATE TABLE warehouse.event_dead_letters (
+    dead_letter_id BIGSERIAL PRIMARY KEY,
+    consumer_id INTEGER NOT NULL REFERENCES warehouse.event_consumers(consumer_id) ON DELETE CASCADE,
+    -- The batch, as the range of stock events it covered
+    first_event_id BIGINT NOT NULL,
+    last_event_id BIGINT NOT NULL,
+    event_count INTEGER NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'DEAD' CHECK (status IN ('DEAD', 'REDRIVEN')),
+    attempts INTEGER NOT NULL,
+    last_error TEXT NOT NULL,
+    dead_lettered_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    redriven_at TIMESTAMPTZ,
+    CHECK (last_event_id >= first_event_id)
+);
+
+CREATE INDEX idx_event_dead_letters_consumer ON warehouse.event_dead_letters (consumer_id, dead_letter_id);
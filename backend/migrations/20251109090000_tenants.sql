@@ -0,0 +1,15 @@
+-- Catalog of tenants provisioned into a schema of their own. The catalog itself stays in
+-- the shared schema; each tenant schema gets the full warehouse schema without sample data.
+CREATE TABLE warehouse.tenants (
+    tenant_id SERIAL PRIMARY KEY,
+    tenant_code VARCHAR(30) NOT NULL UNIQUE,
+    tenant_name VARCHAR(255) NOT NULL,
+    schema_name VARCHAR(63) NOT NULL UNIQUE,
+    status VARCHAR(20) NOT NULL DEFAULT 'PROVISIONING'
+        CHECK (status IN ('PROVISIONING', 'ACTIVE', 'FAILED')),
+    -- Version of the last migration applied to the tenant schema
+    schema_version BIGINT,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    provisioned_at TIMESTAMPTZ
+);
@@ -0,0 +1,16 @@
+-- Progress of batched backfills run during expand-contract schema changes. A backfill walks
+-- its table in key order, so `last_key` is where an interrupted run picks up again.
+CREATE TABLE warehouse.backfill_jobs (
+    job_name VARCHAR(100) PRIMARY KEY,
+    table_name VARCHAR(128) NOT NULL,
+    column_name VARCHAR(63) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'RUNNING' CHECK (status IN ('RUNNING', 'COMPLETED')),
+    last_key BIGINT,
+    rows_updated BIGINT NOT NULL DEFAULT 0,
+    -- Planner estimate taken when the job started, for progress reporting only
+    estimated_rows BIGINT NOT NULL DEFAULT 0,
+    batches INTEGER NOT NULL DEFAULT 0,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
@@ -0,0 +1,17 @@
+-- Logical backups: one archive per export in object storage, holding the warehouse data of
+-- the shared schema and every active tenant schema. The archive itself carries a manifest;
+-- this catalog is what the listing and download endpoints read.
+CREATE TABLE warehouse.backups (
+    backup_id BIGSERIAL PRIMARY KEY,
+    -- Object key under the configured store
+    archive_key VARCHAR(255) NOT NULL UNIQUE,
+    format_version INTEGER NOT NULL,
+    schema_version BIGINT,
+    schemas TEXT[] NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'RUNNING' CHECK (status IN ('RUNNING', 'COMPLETED', 'FAILED')),
+    rows_exported BIGINT NOT NULL DEFAULT 0,
+    size_bytes BIGINT,
+    error TEXT,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
@@ -0,0 +1,24 @@
+-- Scheduled checks of stock invariants. Each run keeps what it found, so an alert can link
+-- to the exact balances that broke.
+CREATE TABLE warehouse.integrity_runs (
+    run_id SERIAL PRIMARY KEY,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ,
+    findings INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE warehouse.integrity_findings (
+    finding_id BIGSERIAL PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES warehouse.integrity_runs(run_id) ON DELETE CASCADE,
+    check_name VARCHAR(30) NOT NULL
+        CHECK (check_name IN ('AVAILABLE_MISMATCH', 'MOVEMENT_MISMATCH', 'ORPHANED_STOCK')),
+    stock_id INTEGER,
+    item_id INTEGER,
+    warehouse_id INTEGER,
+    consignor_id INTEGER,
+    expected DECIMAL(15,4),
+    actual DECIMAL(15,4),
+    detail TEXT NOT NULL
+);
+
+CREATE INDEX idx_integrity_findings_run ON warehouse.integrity_findings (run_id, finding_id);
@@ -0,0 +1,29 @@
+-- Month-end reconciliation of WMS stock against an ERP stock extract: one line per item and
+-- warehouse in either system, with the variance between them
+CREATE TABLE warehouse.erp_reconciliations (
+    reconciliation_id SERIAL PRIMARY KEY,
+    -- Date the ERP extract was taken
+    as_of DATE NOT NULL,
+    source VARCHAR(100),
+    lines INTEGER NOT NULL DEFAULT 0,
+    matched INTEGER NOT NULL DEFAULT 0,
+    variances INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE warehouse.erp_reconciliation_lines (
+    line_id BIGSERIAL PRIMARY KEY,
+    reconciliation_id INTEGER NOT NULL REFERENCES warehouse.erp_reconciliations(reconciliation_id) ON DELETE CASCADE,
+    -- Unset when the ERP code matches no item or warehouse
+    item_id INTEGER,
+    item_code VARCHAR(100) NOT NULL,
+    warehouse_id INTEGER,
+    warehouse_code VARCHAR(50) NOT NULL,
+    erp_quantity DECIMAL(15,4),
+    wms_quantity DECIMAL(15,4),
+    variance DECIMAL(15,4) GENERATED ALWAYS AS (COALESCE(wms_quantity, 0) - COALESCE(erp_quantity, 0)) STORED,
+    status VARCHAR(20) NOT NULL
+        CHECK (status IN ('MATCHED', 'VARIANCE', 'MISSING_IN_WMS', 'MISSING_IN_ERP', 'UNKNOWN'))
+);
+
+CREATE INDEX idx_erp_reconciliation_lines ON warehouse.erp_reconciliation_lines (reconciliation_id, line_id);
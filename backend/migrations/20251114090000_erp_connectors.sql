@@ -0,0 +1,70 @@
+-- ERP connectors: item master, purchase orders, goods receipts and goods issues exchanged
+-- with the ERP on a schedule, with field names mapped per connector
+
+CREATE TABLE warehouse.erp_connectors (
+    connector_id SERIAL PRIMARY KEY,
+    connector_code VARCHAR(30) NOT NULL UNIQUE,
+    -- The ERP on the other end; informational, the connector type decides the protocol
+    erp_system VARCHAR(20) NOT NULL CHECK (erp_system IN ('GENERIC', 'SAP', 'NETSUITE', 'ODOO')),
+    connector_type VARCHAR(10) NOT NULL CHECK (connector_type IN ('REST')),
+    base_url VARCHAR(500) NOT NULL,
+    api_key VARCHAR(255),
+    -- Side that owns the item master: ERP items are pulled, WMS items are pushed
+    item_master VARCHAR(3) NOT NULL DEFAULT 'ERP' CHECK (item_master IN ('ERP', 'WMS')),
+    sync_interval_minutes INTEGER NOT NULL CHECK (sync_interval_minutes >= 5),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    last_synced_at TIMESTAMPTZ,
+    next_run_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_erp_connectors_due ON warehouse.erp_connectors (next_run_at) WHERE is_active;
+
+-- Our field name -> ERP field name, per entity; unmapped fields keep our names
+CREATE TABLE warehouse.erp_field_mappings (
+    connector_id INTEGER NOT NULL REFERENCES warehouse.erp_connectors(connector_id) ON DELETE CASCADE,
+    entity VARCHAR(20) NOT NULL CHECK (entity IN ('ITEM', 'PURCHASE_ORDER', 'RECEIPT', 'ISSUE')),
+    field_name VARCHAR(100) NOT NULL,
+    erp_field VARCHAR(100) NOT NULL,
+    PRIMARY KEY (connector_id, entity, field_name),
+    UNIQUE (connector_id, entity, erp_field)
+);
+
+-- How far each direction and entity has synced. Pushes walk (position_at, position_id) of
+-- our records; pulls ask the ERP for records changed since position_at.
+CREATE TABLE warehouse.erp_sync_cursors (
+    connector_id INTEGER NOT NULL REFERENCES warehouse.erp_connectors(connector_id) ON DELETE CASCADE,
+    direction VARCHAR(4) NOT NULL CHECK (direction IN ('PUSH', 'PULL')),
+    entity VARCHAR(20) NOT NULL,
+    position_at TIMESTAMPTZ NOT NULL,
+    position_id BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (connector_id, direction, entity)
+);
+
+-- ERP records imported as ours, so pulling them again does not create them twice
+CREATE TABLE warehouse.erp_links (
+    connector_id INTEGER NOT NULL REFERENCES warehouse.erp_connectors(connector_id) ON DELETE CASCADE,
+    entity VARCHAR(20) NOT NULL,
+    external_id VARCHAR(100) NOT NULL,
+    local_id INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (connector_id, entity, external_id)
+);
+
+CREATE INDEX idx_erp_links_local ON warehouse.erp_links (entity, local_id);
+
+CREATE TABLE warehouse.erp_sync_runs (
+    run_id BIGSERIAL PRIMARY KEY,
+    connector_id INTEGER NOT NULL REFERENCES warehouse.erp_connectors(connector_id) ON DELETE CASCADE,
+    direction VARCHAR(4) NOT NULL,
+    entity VARCHAR(20) NOT NULL,
+    status VARCHAR(10) NOT NULL CHECK (status IN ('SUCCEEDED', 'FAILED')),
+    records INTEGER NOT NULL,
+    error TEXT,
+    started_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_erp_sync_runs_connector ON warehouse.erp_sync_runs (connector_id, run_id DESC);
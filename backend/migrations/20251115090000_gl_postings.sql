@@ -0,0 +1,79 @@
+-- General ledger export: costed movements of our own stock are summarised per period
+-- into journal lines on configurable GL accounts. Closing a period locks its movements.
+
+CREATE TABLE warehouse.gl_accounts (
+    category VARCHAR(20) PRIMARY KEY
+        CHECK (category IN ('INVENTORY', 'GRNI', 'COGS', 'SCRAP', 'ADJUSTMENT', 'IN_TRANSIT')),
+    account_code VARCHAR(30) NOT NULL,
+    account_name VARCHAR(100) NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO warehouse.gl_accounts (category, account_code, account_name) VALUES
+('INVENTORY', '1400', 'Inventory'),
+('IN_TRANSIT', '1410', 'Inventory in transit'),
+('GRNI', '2150', 'Goods received not invoiced'),
+('COGS', '5000', 'Cost of goods sold'),
+('SCRAP', '5200', 'Inventory scrap'),
+('ADJUSTMENT', '5300', 'Inventory adjustments');
+
+-- Periods are calendar months (UTC), keyed by their first day. A month without a row is open.
+CREATE TABLE warehouse.gl_periods (
+    period DATE PRIMARY KEY CHECK (EXTRACT(DAY FROM period) = 1),
+    status VARCHAR(10) NOT NULL CHECK (status IN ('OPEN', 'CLOSED')),
+    closed_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE warehouse.gl_exports (
+    export_id SERIAL PRIMARY KEY,
+    period DATE NOT NULL,
+    destination VARCHAR(500) NOT NULL,
+    status VARCHAR(10) NOT NULL CHECK (status IN ('SENT', 'FAILED')),
+    lines INTEGER NOT NULL,
+    total_debit DECIMAL(18,4) NOT NULL,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_gl_exports_period ON warehouse.gl_exports (period, export_id DESC);
+
+CREATE FUNCTION warehouse.gl_period_closed(moved_at TIMESTAMPTZ) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT EXISTS (
+        SELECT 1 FROM warehouse.gl_periods
+        WHERE period = date_trunc('month', moved_at AT TIME ZONE 'UTC')::DATE AND status = 'CLOSED'
+    )
+$$;
+
+-- Movements of a closed period cannot be posted, changed or removed. Columns that do not
+-- affect the valuation (settlement, notes) stay editable.
+CREATE FUNCTION warehouse.gl_period_lock() RETURNS TRIGGER
+LANGUAGE plpgsql AS $$
+BEGIN
+    IF TG_OP = 'DELETE' AND warehouse.gl_period_closed(OLD.movement_date) THEN
+        RAISE EXCEPTION 'movement % is in a closed period', OLD.movement_id USING ERRCODE = 'WG001';
+    END IF;
+    IF TG_OP = 'UPDATE'
+       AND (NEW.item_id, NEW.warehouse_id, NEW.movement_type, NEW.quantity, NEW.unit_cost,
+            NEW.movement_date, NEW.consignor_id, NEW.client_id)
+           IS DISTINCT FROM
+           (OLD.item_id, OLD.warehouse_id, OLD.movement_type, OLD.quantity, OLD.unit_cost,
+            OLD.movement_date, OLD.consignor_id, OLD.client_id)
+       AND (warehouse.gl_period_closed(OLD.movement_date) OR warehouse.gl_period_closed(NEW.movement_date)) THEN
+        RAISE EXCEPTION 'movement % is in a closed period', OLD.movement_id USING ERRCODE = 'WG001';
+    END IF;
+    IF TG_OP = 'INSERT' AND warehouse.gl_period_closed(NEW.movement_date) THEN
+        RAISE EXCEPTION 'cannot post a movement into a closed period' USING ERRCODE = 'WG001';
+    END IF;
+
+    IF TG_OP = 'DELETE' THEN
+        RETURN OLD;
+    END IF;
+    RETURN NEW;
+END
+$$;
+
+CREATE TRIGGER stock_movements_gl_period_lock
+    BEFORE INSERT OR UPDATE OR DELETE ON warehouse.stock_movements
+    FOR EACH ROW EXECUTE FUNCTION warehouse.gl_period_lock();
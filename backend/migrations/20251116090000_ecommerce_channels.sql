@@ -0,0 +1,70 @@
+-- E-commerce channels: available-to-promise stock pushed to storefronts (Shopify,
+-- WooCommerce) and their orders pulled in as outbound orders, on a schedule
+
+CREATE TABLE warehouse.ecommerce_channels (
+    channel_id SERIAL PRIMARY KEY,
+    channel_code VARCHAR(30) NOT NULL UNIQUE,
+    platform VARCHAR(20) NOT NULL CHECK (platform IN ('SHOPIFY', 'WOOCOMMERCE')),
+    store_url VARCHAR(500) NOT NULL,
+    -- Shopify admin access token, or WooCommerce consumer key
+    api_key VARCHAR(255) NOT NULL,
+    -- WooCommerce consumer secret
+    api_secret VARCHAR(255),
+    -- Shopify location the stock levels are set at
+    location_id VARCHAR(50),
+    -- Stock is promised from, and orders are shipped from, this warehouse
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    -- Held back from every listed item so the storefront does not oversell
+    buffer_quantity DECIMAL(15,4) NOT NULL DEFAULT 0 CHECK (buffer_quantity >= 0),
+    sync_interval_minutes INTEGER NOT NULL CHECK (sync_interval_minutes >= 5),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Orders changed up to here have been pulled
+    orders_synced_at TIMESTAMPTZ,
+    last_synced_at TIMESTAMPTZ,
+    next_run_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (platform <> 'SHOPIFY' OR location_id IS NOT NULL),
+    CHECK (platform <> 'WOOCOMMERCE' OR api_secret IS NOT NULL)
+);
+
+CREATE INDEX idx_ecommerce_channels_due ON warehouse.ecommerce_channels (next_run_at) WHERE is_active;
+
+-- Items sold on a channel, under the storefront's id: the Shopify inventory item or the
+-- WooCommerce product. Only listed items have their stock pushed.
+CREATE TABLE warehouse.ecommerce_listings (
+    channel_id INTEGER NOT NULL REFERENCES warehouse.ecommerce_channels(channel_id) ON DELETE CASCADE,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    external_id VARCHAR(100) NOT NULL,
+    -- Overrides the channel's buffer for this item
+    buffer_quantity DECIMAL(15,4) CHECK (buffer_quantity >= 0),
+    -- Last quantity the storefront accepted; unchanged quantities are not pushed again
+    pushed_quantity DECIMAL(15,4),
+    pushed_at TIMESTAMPTZ,
+    PRIMARY KEY (channel_id, item_id),
+    UNIQUE (channel_id, external_id)
+);
+
+-- Storefront orders imported as outbound orders, so pulling them again does not create
+-- them twice
+CREATE TABLE warehouse.ecommerce_orders (
+    channel_id INTEGER NOT NULL REFERENCES warehouse.ecommerce_channels(channel_id) ON DELETE CASCADE,
+    external_order_id VARCHAR(100) NOT NULL,
+    order_id INTEGER NOT NULL REFERENCES warehouse.outbound_orders(order_id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (channel_id, external_order_id)
+);
+
+CREATE TABLE warehouse.ecommerce_sync_runs (
+    run_id BIGSERIAL PRIMARY KEY,
+    channel_id INTEGER NOT NULL REFERENCES warehouse.ecommerce_channels(channel_id) ON DELETE CASCADE,
+    -- PULL of orders or PUSH of stock
+    direction VARCHAR(4) NOT NULL CHECK (direction IN ('PUSH', 'PULL')),
+    status VARCHAR(10) NOT NULL CHECK (status IN ('SUCCEEDED', 'FAILED')),
+    records INTEGER NOT NULL,
+    error TEXT,
+    started_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_ecommerce_sync_runs_channel ON warehouse.ecommerce_sync_runs (channel_id, run_id DESC);
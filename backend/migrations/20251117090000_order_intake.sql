@@ -0,0 +1,28 @@
+-- Order intake: marketplaces and other sales channels post orders in one normalized
+-- shape; each channel ships from its fulfillment warehouse
+
+CREATE TABLE warehouse.integration_channels (
+    channel_id SERIAL PRIMARY KEY,
+    channel_code VARCHAR(30) NOT NULL UNIQUE,
+    channel_name VARCHAR(100) NOT NULL,
+    -- Orders of the channel are created in, and allocated from, this warehouse
+    fulfillment_warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Channel orders taken in, so posting the same external order again returns the order
+-- created the first time
+CREATE TABLE warehouse.integration_orders (
+    channel_id INTEGER NOT NULL REFERENCES warehouse.integration_channels(channel_id) ON DELETE CASCADE,
+    external_order_id VARCHAR(100) NOT NULL,
+    order_id INTEGER NOT NULL UNIQUE REFERENCES warehouse.outbound_orders(order_id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (channel_id, external_order_id)
+);
+
+-- Stock set aside for the line when the order was taken in; what other open orders have
+-- allocated and not yet shipped is not allocated again
+ALTER TABLE warehouse.outbound_order_lines
+    ADD COLUMN quantity_allocated DECIMAL(15,4) NOT NULL DEFAULT 0 CHECK (quantity_allocated >= 0);
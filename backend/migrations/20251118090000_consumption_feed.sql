@@ -0,0 +1,21 @@
+-- Consumption feed: point-of-sale and site-consumption events from satellite and field
+-- warehouses, each issuing its quantity from stock once
+
+CREATE TABLE warehouse.consumption_events (
+    consumption_id BIGSERIAL PRIMARY KEY,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    -- The sender's id of the event; a repeat of it is skipped
+    event_id VARCHAR(100) NOT NULL,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity DECIMAL(15,4) NOT NULL CHECK (quantity > 0),
+    -- Terminal, van or technician that sent the batch
+    source VARCHAR(50),
+    -- Receipt, ticket or work order the goods were consumed against
+    reference VARCHAR(100),
+    consumed_at TIMESTAMPTZ NOT NULL,
+    movement_id BIGINT NOT NULL REFERENCES warehouse.stock_movements(movement_id),
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (warehouse_id, event_id)
+);
+
+CREATE INDEX idx_consumption_events_warehouse ON warehouse.consumption_events (warehouse_id, consumption_id DESC);
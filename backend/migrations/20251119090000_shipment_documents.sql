@@ -0,0 +1,35 @@
+-- Shipment documents: delivery orders and proforma invoices rendered as PDF and kept
+-- with the shipment, under company branding set per tenant or per warehouse
+
+-- Branding without a warehouse applies to every warehouse of the tenant's schema; a
+-- warehouse's own branding overrides it
+CREATE TABLE warehouse.document_brandings (
+    branding_id SERIAL PRIMARY KEY,
+    warehouse_id INTEGER REFERENCES warehouse.warehouses(warehouse_id),
+    company_name VARCHAR(255) NOT NULL,
+    -- Printed as is, one line per line
+    address TEXT,
+    tax_id VARCHAR(50),
+    phone VARCHAR(50),
+    email VARCHAR(255),
+    -- Colour of the header band and table headings, as #RRGGBB
+    accent_color CHAR(7) NOT NULL DEFAULT '#1F3A5F' CHECK (accent_color ~ '^#[0-9A-Fa-f]{6}$'),
+    footer_text VARCHAR(500),
+    -- Currency proforma invoice values are stated in
+    currency CHAR(3) NOT NULL DEFAULT 'IDR',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_document_brandings_warehouse ON warehouse.document_brandings ((COALESCE(warehouse_id, 0)));
+
+CREATE TABLE warehouse.shipment_documents (
+    document_id BIGSERIAL PRIMARY KEY,
+    shipment_id INTEGER NOT NULL REFERENCES warehouse.shipments(shipment_id),
+    document_type VARCHAR(20) NOT NULL CHECK (document_type IN ('DELIVERY_ORDER', 'PROFORMA_INVOICE')),
+    document_number VARCHAR(60) NOT NULL UNIQUE,
+    content BYTEA NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    -- Generating a document again replaces its content
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (shipment_id, document_type)
+);
@@ -0,0 +1,36 @@
+-- User-defined tabular reports over the fixed catalog of entities and columns the report
+-- builder knows; definitions hold names from that catalog, never SQL
+
+CREATE TABLE warehouse.report_definitions (
+    report_id SERIAL PRIMARY KEY,
+    report_code VARCHAR(50) NOT NULL UNIQUE,
+    report_name VARCHAR(255) NOT NULL,
+    description TEXT,
+    entity VARCHAR(50) NOT NULL,
+    -- Output columns in order; with grouping each must be a grouping column
+    columns TEXT[] NOT NULL,
+    group_by TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE warehouse.report_filters (
+    report_id INTEGER NOT NULL REFERENCES warehouse.report_definitions(report_id) ON DELETE CASCADE,
+    position SMALLINT NOT NULL,
+    column_name VARCHAR(50) NOT NULL,
+    operator VARCHAR(10) NOT NULL
+        CHECK (operator IN ('EQ', 'NE', 'LT', 'LTE', 'GT', 'GTE', 'CONTAINS', 'IS_NULL', 'NOT_NULL')),
+    -- Compared as the column's type; unset for IS_NULL and NOT_NULL
+    value VARCHAR(255),
+    PRIMARY KEY (report_id, position)
+);
+
+-- Aggregates follow the columns in the output
+CREATE TABLE warehouse.report_aggregates (
+    report_id INTEGER NOT NULL REFERENCES warehouse.report_definitions(report_id) ON DELETE CASCADE,
+    position SMALLINT NOT NULL,
+    function VARCHAR(10) NOT NULL CHECK (function IN ('COUNT', 'SUM', 'AVG', 'MIN', 'MAX')),
+    -- Unset for COUNT of rows
+    column_name VARCHAR(50),
+    PRIMARY KEY (report_id, position)
+);
@@ -0,0 +1,25 @@
+-- Saved views: a user's named filter, sort and column choice for one list endpoint
+
+CREATE TABLE warehouse.saved_views (
+    view_id SERIAL PRIMARY KEY,
+    -- User the view belongs to, as sent in X-User
+    owner VARCHAR(100) NOT NULL,
+    view_name VARCHAR(100) NOT NULL,
+    -- List endpoint the view applies to, e.g. items for /api/items
+    resource VARCHAR(50) NOT NULL,
+    sort_by VARCHAR(50),
+    sort_order VARCHAR(4) CHECK (sort_order IN ('ASC', 'DESC')),
+    -- Fields the client shows, in order; empty for its default
+    columns TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (owner, resource, view_name)
+);
+
+-- Query parameters the view passes to its list endpoint
+CREATE TABLE warehouse.saved_view_filters (
+    view_id INTEGER NOT NULL REFERENCES warehouse.saved_views(view_id) ON DELETE CASCADE,
+    name VARCHAR(50) NOT NULL,
+    value VARCHAR(255) NOT NULL,
+    PRIMARY KEY (view_id, name)
+);
@@ -0,0 +1,29 @@
+-- Scheduled email delivery of custom reports, with a history of every delivery
+
+CREATE TABLE warehouse.report_schedules (
+    schedule_id SERIAL PRIMARY KEY,
+    report_id INTEGER NOT NULL REFERENCES warehouse.report_definitions(report_id) ON DELETE CASCADE,
+    -- Five-field cron expression, in UTC
+    cron_expression VARCHAR(100) NOT NULL,
+    recipients TEXT[] NOT NULL CHECK (cardinality(recipients) > 0),
+    format VARCHAR(10) NOT NULL DEFAULT 'CSV' CHECK (format IN ('CSV', 'HTML')),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMPTZ NOT NULL,
+    last_run_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_report_schedules_due ON warehouse.report_schedules (next_run_at) WHERE is_active;
+
+CREATE TABLE warehouse.report_schedule_runs (
+    run_id BIGSERIAL PRIMARY KEY,
+    schedule_id INTEGER NOT NULL REFERENCES warehouse.report_schedules(schedule_id) ON DELETE CASCADE,
+    status VARCHAR(10) NOT NULL CHECK (status IN ('SENT', 'FAILED')),
+    row_count INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    started_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_report_schedule_runs ON warehouse.report_schedule_runs (schedule_id, run_id DESC);
@@ -0,0 +1,37 @@
+-- Asynchronous work started from the API (imports, exports, reports, recalculations),
+-- tracked in one place with its progress, errors and the files it produced
+
+CREATE TABLE warehouse.jobs (
+    job_id BIGSERIAL PRIMARY KEY,
+    job_type VARCHAR(20) NOT NULL CHECK (job_type IN ('IMPORT', 'EXPORT', 'REPORT', 'RECALCULATION', 'BACKUP')),
+    description VARCHAR(255) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'QUEUED' CHECK (status IN ('QUEUED', 'RUNNING', 'SUCCEEDED', 'FAILED')),
+    -- Units of work done out of `total`, when the job knows its total
+    processed BIGINT NOT NULL DEFAULT 0,
+    total BIGINT,
+    progress_percent SMALLINT NOT NULL DEFAULT 0 CHECK (progress_percent BETWEEN 0 AND 100),
+    -- Problems met along the way; a failed job's last entry is why it failed
+    errors TEXT[] NOT NULL DEFAULT '{}',
+    -- What the job works on, such as REPORT and a report id
+    reference_type VARCHAR(50),
+    reference_id BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_jobs_status ON warehouse.jobs (status, job_id DESC);
+
+-- Files a job produced, downloaded through signed URLs
+CREATE TABLE warehouse.job_artifacts (
+    artifact_id BIGSERIAL PRIMARY KEY,
+    job_id BIGINT NOT NULL REFERENCES warehouse.jobs(job_id) ON DELETE CASCADE,
+    file_name VARCHAR(255) NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    content BYTEA NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_job_artifacts_job ON warehouse.job_artifacts (job_id);
@@ -0,0 +1,8 @@
+-- Per-user settings, keyed by the user name the authenticating proxy passes in X-User.
+-- The locale picks the language of API messages and labels ahead of Accept-Language.
+
+CREATE TABLE warehouse.user_preferences (
+    owner VARCHAR(100) PRIMARY KEY,
+    locale VARCHAR(10) NOT NULL CHECK (locale IN ('en', 'id')),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
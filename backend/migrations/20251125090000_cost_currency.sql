@@ -0,0 +1,8 @@
+-- Costs carry their currency: every cost column of an item, and of a stock line, is in the
+-- row's cost currency. Existing costs were all recorded in rupiah.
+
+ALTER TABLE warehouse.items
+    ADD COLUMN cost_currency VARCHAR(3) NOT NULL DEFAULT 'IDR' CHECK (cost_currency ~ '^[A-Z]{3}$');
+
+ALTER TABLE warehouse.stock_inventory
+    ADD COLUMN cost_currency VARCHAR(3) NOT NULL DEFAULT 'IDR' CHECK (cost_currency ~ '^[A-Z]{3}$');
@@ -0,0 +1,6 @@
+-- What a location holds at most, for utilization reporting: cubic metres of goods and
+-- positions, i.e. pallets, cases or totes standing there. NULL leaves a measure unset.
+
+ALTER TABLE warehouse.locations
+    ADD COLUMN max_volume_cbm DECIMAL(10,4) CHECK (max_volume_cbm > 0),
+    ADD COLUMN max_positions INTEGER CHECK (max_positions > 0);
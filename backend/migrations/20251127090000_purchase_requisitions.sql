@@ -0,0 +1,88 @@
+-- Purchasing: the suppliers an item is bought from, draft purchase requisitions raised from
+-- reorder alerts, and the purchase orders approved requisitions are converted into
+
+CREATE TABLE warehouse.item_suppliers (
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id) ON DELETE CASCADE,
+    supplier_code VARCHAR(30) NOT NULL,
+    supplier_name VARCHAR(255) NOT NULL,
+    -- Requisitions for the item are raised on its preferred supplier
+    is_preferred BOOLEAN NOT NULL DEFAULT FALSE,
+    unit_cost DECIMAL(15,4),
+    cost_currency VARCHAR(3) NOT NULL DEFAULT 'IDR' CHECK (cost_currency ~ '^[A-Z]{3}$'),
+    lead_time_days INTEGER NOT NULL DEFAULT 0 CHECK (lead_time_days >= 0),
+    -- EOQ inputs: cost of placing one order, and the yearly cost of holding a unit as a
+    -- percentage of its unit cost
+    order_cost DECIMAL(15,4) CHECK (order_cost > 0),
+    holding_cost_pct DECIMAL(5,2) CHECK (holding_cost_pct > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (item_id, supplier_code)
+);
+
+CREATE UNIQUE INDEX idx_item_suppliers_preferred ON warehouse.item_suppliers (item_id) WHERE is_preferred;
+
+CREATE TABLE warehouse.purchase_orders (
+    po_id SERIAL PRIMARY KEY,
+    po_number VARCHAR(30) UNIQUE NOT NULL,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    supplier_code VARCHAR(30) NOT NULL,
+    supplier_name VARCHAR(255) NOT NULL,
+    -- OPEN -> RECEIVED, or OPEN -> CANCELLED
+    status VARCHAR(10) NOT NULL DEFAULT 'OPEN' CHECK (status IN ('OPEN', 'RECEIVED', 'CANCELLED')),
+    currency VARCHAR(3) NOT NULL DEFAULT 'IDR' CHECK (currency ~ '^[A-Z]{3}$'),
+    -- Today plus the longest lead time of its lines
+    expected_date DATE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by INTEGER
+);
+
+CREATE INDEX idx_purchase_orders_warehouse ON warehouse.purchase_orders (warehouse_id, status);
+
+CREATE TABLE warehouse.purchase_order_lines (
+    line_id SERIAL PRIMARY KEY,
+    po_id INTEGER NOT NULL REFERENCES warehouse.purchase_orders(po_id) ON DELETE CASCADE,
+    line_number INTEGER NOT NULL,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity_ordered DECIMAL(15,4) NOT NULL CHECK (quantity_ordered > 0),
+    quantity_received DECIMAL(15,4) NOT NULL DEFAULT 0 CHECK (quantity_received >= 0),
+    unit_cost DECIMAL(15,4),
+    UNIQUE (po_id, line_number)
+);
+
+CREATE TABLE warehouse.purchase_requisitions (
+    requisition_id SERIAL PRIMARY KEY,
+    requisition_number VARCHAR(30) UNIQUE NOT NULL,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    supplier_code VARCHAR(30) NOT NULL,
+    supplier_name VARCHAR(255) NOT NULL,
+    -- DRAFT -> APPROVED -> CONVERTED, or DRAFT -> REJECTED
+    status VARCHAR(10) NOT NULL DEFAULT 'DRAFT'
+        CHECK (status IN ('DRAFT', 'APPROVED', 'REJECTED', 'CONVERTED')),
+    -- How quantities were suggested: EOQ or MIN_MAX
+    method VARCHAR(10) NOT NULL CHECK (method IN ('EOQ', 'MIN_MAX')),
+    currency VARCHAR(3) NOT NULL DEFAULT 'IDR' CHECK (currency ~ '^[A-Z]{3}$'),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- When it was approved or rejected
+    decided_at TIMESTAMPTZ,
+    rejection_reason TEXT,
+    po_id INTEGER REFERENCES warehouse.purchase_orders(po_id)
+);
+
+CREATE INDEX idx_purchase_requisitions_warehouse ON warehouse.purchase_requisitions (warehouse_id, status);
+
+CREATE TABLE warehouse.purchase_requisition_lines (
+    requisition_id INTEGER NOT NULL REFERENCES warehouse.purchase_requisitions(requisition_id) ON DELETE CASCADE,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    -- Stock position when the alert fired
+    quantity_available DECIMAL(15,4) NOT NULL,
+    reorder_point DECIMAL(15,4) NOT NULL,
+    -- EOQ when asked for and the supplier's costs and a year of demand allow it, MIN_MAX otherwise
+    method VARCHAR(10) NOT NULL CHECK (method IN ('EOQ', 'MIN_MAX')),
+    suggested_quantity DECIMAL(15,4) NOT NULL CHECK (suggested_quantity > 0),
+    -- What will be ordered; the suggestion until an approver changes it
+    quantity DECIMAL(15,4) NOT NULL CHECK (quantity > 0),
+    unit_cost DECIMAL(15,4),
+    PRIMARY KEY (requisition_id, item_id)
+);
+
@@ -0,0 +1,8 @@
+-- What a supplier accepts on an order of an item, in the item's units: the minimum order
+-- quantity, the step orders go up in, and the units in one supplier pack. NULL leaves a
+-- constraint unset.
+
+ALTER TABLE warehouse.item_suppliers
+    ADD COLUMN min_order_quantity DECIMAL(15,3) CHECK (min_order_quantity > 0),
+    ADD COLUMN order_multiple DECIMAL(15,3) CHECK (order_multiple > 0),
+    ADD COLUMN pack_size DECIMAL(15,3) CHECK (pack_size > 0);
@@ -0,0 +1,32 @@
+-- Projects stock is issued to, each with an optional material budget, and the issues
+-- charged against it at the cost the goods left the warehouse at
+
+CREATE TABLE warehouse.projects (
+    project_id SERIAL PRIMARY KEY,
+    project_code VARCHAR(30) NOT NULL UNIQUE,
+    project_name VARCHAR(255) NOT NULL,
+    material_budget DECIMAL(15,2) CHECK (material_budget >= 0),
+    budget_currency VARCHAR(3) NOT NULL DEFAULT 'IDR' CHECK (budget_currency ~ '^[A-Z]{3}$'),
+    -- What an issue taking the project over budget does: WARN posts it and raises a
+    -- notification, BLOCK refuses it
+    budget_control VARCHAR(10) NOT NULL DEFAULT 'WARN' CHECK (budget_control IN ('WARN', 'BLOCK')),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE warehouse.project_issues (
+    issue_id BIGSERIAL PRIMARY KEY,
+    project_id INTEGER NOT NULL REFERENCES warehouse.projects(project_id),
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity DECIMAL(15,3) NOT NULL CHECK (quantity > 0),
+    -- NULL for items without a cost, which are charged nothing
+    unit_cost DECIMAL(15,4),
+    issue_value DECIMAL(15,2) NOT NULL DEFAULT 0,
+    movement_id BIGINT NOT NULL REFERENCES warehouse.stock_movements(movement_id),
+    over_budget BOOLEAN NOT NULL DEFAULT FALSE,
+    issued_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_project_issues_project ON warehouse.project_issues(project_id, issued_at);
@@ -0,0 +1,23 @@
+-- Stock held for a project ahead of the date its field team needs it. While a reservation
+-- is active its open quantity sits in stock_inventory.quantity_reserved, out of
+-- quantity_available and away from other outbound movements.
+
+CREATE TABLE warehouse.project_reservations (
+    reservation_id SERIAL PRIMARY KEY,
+    project_id INTEGER NOT NULL REFERENCES warehouse.projects(project_id),
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity DECIMAL(15,3) NOT NULL CHECK (quantity > 0),
+    -- Drawn by issues to the project; the rest stays reserved while the reservation is active
+    quantity_issued DECIMAL(15,3) NOT NULL DEFAULT 0 CHECK (quantity_issued >= 0 AND quantity_issued <= quantity),
+    needed_by DATE NOT NULL,
+    -- Still active after this date, the reservation is stale and is expired
+    expires_on DATE NOT NULL CHECK (expires_on >= needed_by),
+    status VARCHAR(20) NOT NULL DEFAULT 'ACTIVE' CHECK (status IN ('ACTIVE', 'FULFILLED', 'RELEASED', 'EXPIRED')),
+    notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    closed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_project_reservations_project ON warehouse.project_reservations(project_id, status);
+CREATE INDEX idx_project_reservations_active ON warehouse.project_reservations(expires_on) WHERE status = 'ACTIVE';
@@ -0,0 +1,65 @@
+-- Deliveries of project materials to site: a project asks for goods by a date at its
+-- site, and the request is scheduled onto a delivery day of the warehouse's calendar and
+-- a vehicle with the capacity left for it, becoming an outbound order
+
+-- Days a warehouse sends out deliveries; without a row, Monday to Friday
+CREATE TABLE warehouse.delivery_calendars (
+    warehouse_id INTEGER PRIMARY KEY REFERENCES warehouse.warehouses(warehouse_id),
+    -- ISO weekdays, 1 = Monday
+    delivery_weekdays SMALLINT[] NOT NULL DEFAULT '{1,2,3,4,5}'
+        CHECK (delivery_weekdays <@ '{1,2,3,4,5,6,7}'::SMALLINT[]),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Delivery days taken out of the calendar: holidays, stocktakes
+CREATE TABLE warehouse.delivery_closures (
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    closed_on DATE NOT NULL,
+    reason VARCHAR(255),
+    PRIMARY KEY (warehouse_id, closed_on)
+);
+
+CREATE TABLE warehouse.delivery_vehicles (
+    vehicle_id SERIAL PRIMARY KEY,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    vehicle_code VARCHAR(20) NOT NULL,
+    description VARCHAR(255),
+    -- Load one vehicle carries on a delivery day
+    capacity_kg DECIMAL(10,2) NOT NULL CHECK (capacity_kg > 0),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (warehouse_id, vehicle_code)
+);
+
+CREATE TABLE warehouse.site_deliveries (
+    delivery_id SERIAL PRIMARY KEY,
+    delivery_number VARCHAR(20) NOT NULL UNIQUE,
+    project_id INTEGER NOT NULL REFERENCES warehouse.projects(project_id),
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    needed_by DATE NOT NULL,
+    site_name VARCHAR(255) NOT NULL,
+    site_address TEXT NOT NULL,
+    site_city VARCHAR(100),
+    site_contact VARCHAR(255),
+    -- From the items' unit weights; items without one weigh nothing
+    weight_kg DECIMAL(12,3) NOT NULL DEFAULT 0,
+    status VARCHAR(20) NOT NULL DEFAULT 'REQUESTED' CHECK (status IN ('REQUESTED', 'SCHEDULED', 'CANCELLED')),
+    scheduled_date DATE,
+    vehicle_id INTEGER REFERENCES warehouse.delivery_vehicles(vehicle_id),
+    order_id INTEGER REFERENCES warehouse.outbound_orders(order_id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (status <> 'SCHEDULED' OR (scheduled_date IS NOT NULL AND vehicle_id IS NOT NULL AND order_id IS NOT NULL))
+);
+
+CREATE TABLE warehouse.site_delivery_lines (
+    delivery_id INTEGER NOT NULL REFERENCES warehouse.site_deliveries(delivery_id),
+    line_number INTEGER NOT NULL,
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity DECIMAL(15,3) NOT NULL CHECK (quantity > 0),
+    PRIMARY KEY (delivery_id, line_number)
+);
+
+CREATE INDEX idx_site_deliveries_project ON warehouse.site_deliveries(project_id, needed_by);
+CREATE INDEX idx_site_deliveries_vehicle_day ON warehouse.site_deliveries(vehicle_id, scheduled_date)
+    WHERE status = 'SCHEDULED';
@@ -0,0 +1,66 @@
+-- Delivery runs on the warehouse's own fleet: a driver takes a vehicle out on a day with
+-- shipments as its stops, and each stop is closed by a proof of delivery, which delivers
+-- the shipment
+
+CREATE TABLE warehouse.delivery_drivers (
+    driver_id SERIAL PRIMARY KEY,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    driver_code VARCHAR(20) NOT NULL,
+    driver_name VARCHAR(255) NOT NULL,
+    phone VARCHAR(50),
+    license_number VARCHAR(50) NOT NULL,
+    -- Runs are not planned for a driver past this day
+    license_expires_on DATE,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (warehouse_id, driver_code)
+);
+
+CREATE TABLE warehouse.delivery_runs (
+    run_id SERIAL PRIMARY KEY,
+    run_number VARCHAR(20) NOT NULL UNIQUE,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    vehicle_id INTEGER NOT NULL REFERENCES warehouse.delivery_vehicles(vehicle_id),
+    driver_id INTEGER NOT NULL REFERENCES warehouse.delivery_drivers(driver_id),
+    run_date DATE NOT NULL,
+    -- Completed once every stop has its proof of delivery
+    status VARCHAR(20) NOT NULL DEFAULT 'PLANNED' CHECK (status IN ('PLANNED', 'DISPATCHED', 'COMPLETED')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    dispatched_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ
+);
+
+-- A shipment goes out on one run only
+CREATE TABLE warehouse.delivery_run_stops (
+    run_id INTEGER NOT NULL REFERENCES warehouse.delivery_runs(run_id),
+    stop_number INTEGER NOT NULL,
+    shipment_id INTEGER NOT NULL UNIQUE REFERENCES warehouse.shipments(shipment_id),
+    -- Shipment lines' catch weight, or their items' unit weight
+    weight_kg DECIMAL(12,3) NOT NULL DEFAULT 0,
+    PRIMARY KEY (run_id, stop_number)
+);
+
+CREATE TABLE warehouse.proofs_of_delivery (
+    shipment_id INTEGER PRIMARY KEY REFERENCES warehouse.shipments(shipment_id),
+    run_id INTEGER NOT NULL REFERENCES warehouse.delivery_runs(run_id),
+    received_by VARCHAR(255) NOT NULL,
+    delivered_at TIMESTAMPTZ NOT NULL,
+    notes TEXT,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- The recipient's signature and a photo of the goods left on site; uploading again
+-- replaces the file
+CREATE TABLE warehouse.proof_of_delivery_files (
+    shipment_id INTEGER NOT NULL REFERENCES warehouse.proofs_of_delivery(shipment_id),
+    file_kind VARCHAR(20) NOT NULL CHECK (file_kind IN ('SIGNATURE', 'PHOTO')),
+    content_type VARCHAR(50) NOT NULL,
+    content BYTEA NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    uploaded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (shipment_id, file_kind)
+);
+
+CREATE INDEX idx_delivery_runs_warehouse_day ON warehouse.delivery_runs(warehouse_id, run_date);
+CREATE INDEX idx_delivery_runs_vehicle_day ON warehouse.delivery_runs(vehicle_id, run_date);
+CREATE INDEX idx_delivery_runs_driver_day ON warehouse.delivery_runs(driver_id, run_date);
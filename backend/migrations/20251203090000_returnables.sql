@@ -0,0 +1,31 @@
+-- Returnable packaging: crates, gas cylinders, drums and pallets issued to customers with
+-- their deliveries and expected back. Each issue and return is a movement; a customer
+-- site's balance is what it was issued less what came back.
+
+CREATE TABLE warehouse.returnable_types (
+    type_id SERIAL PRIMARY KEY,
+    type_code VARCHAR(20) NOT NULL UNIQUE,
+    description VARCHAR(255) NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE warehouse.returnable_movements (
+    movement_id BIGSERIAL PRIMARY KEY,
+    type_id INTEGER NOT NULL REFERENCES warehouse.returnable_types(type_id),
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    customer_name VARCHAR(255) NOT NULL,
+    -- The order's ship-to name; NULL when the customer has no separate sites
+    site_name VARCHAR(255),
+    direction VARCHAR(10) NOT NULL CHECK (direction IN ('ISSUE', 'RETURN')),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    -- Delivery the units went out with
+    shipment_id INTEGER REFERENCES warehouse.shipments(shipment_id),
+    notes TEXT,
+    moved_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (direction = 'RETURN' OR shipment_id IS NOT NULL)
+);
+
+CREATE INDEX idx_returnable_movements_holder ON warehouse.returnable_movements(customer_name, site_name, type_id);
+CREATE INDEX idx_returnable_movements_shipment ON warehouse.returnable_movements(shipment_id)
+    WHERE shipment_id IS NOT NULL;
@@ -0,0 +1,20 @@
+-- Stock reservations: quantities held back from a warehouse's available stock for an
+-- order, job or customer until released. Active reservations are what make up
+-- stock_inventory.quantity_reserved alongside project reservations.
+
+CREATE TABLE warehouse.stock_reservations (
+    reservation_id SERIAL PRIMARY KEY,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity DECIMAL(15,4) NOT NULL CHECK (quantity > 0),
+    -- Order, job or customer the stock is held for
+    reference VARCHAR(100),
+    status VARCHAR(20) NOT NULL DEFAULT 'ACTIVE' CHECK (status IN ('ACTIVE', 'RELEASED')),
+    notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    released_at TIMESTAMPTZ,
+    CHECK ((status = 'RELEASED') = (released_at IS NOT NULL))
+);
+
+CREATE INDEX idx_stock_reservations_stock ON warehouse.stock_reservations(warehouse_id, item_id, status);
+CREATE INDEX idx_stock_reservations_reference ON warehouse.stock_reservations(reference) WHERE reference IS NOT NULL;
@@ -0,0 +1,22 @@
+-- Project status, site, manager and dates. Whether a project takes issues and
+-- reservations now follows its status: is_active is true while it is planned or active,
+-- and projects made inactive before are taken as completed.
+
+ALTER TABLE warehouse.projects
+    ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'ACTIVE'
+        CHECK (status IN ('PLANNED', 'ACTIVE', 'ON_HOLD', 'COMPLETED', 'CANCELLED')),
+    ADD COLUMN site_name VARCHAR(255),
+    ADD COLUMN site_address TEXT,
+    ADD COLUMN site_city VARCHAR(100),
+    ADD COLUMN manager_name VARCHAR(255),
+    ADD COLUMN start_date DATE,
+    ADD COLUMN end_date DATE,
+    ADD CONSTRAINT projects_dates_check CHECK (end_date >= start_date);
+
+UPDATE warehouse.projects SET status = 'COMPLETED' WHERE NOT is_active;
+
+ALTER TABLE warehouse.projects DROP COLUMN is_active;
+ALTER TABLE warehouse.projects
+    ADD COLUMN is_active BOOLEAN GENERATED ALWAYS AS (status IN ('PLANNED', 'ACTIVE')) STORED;
+
+CREATE INDEX idx_projects_status ON warehouse.projects(status);
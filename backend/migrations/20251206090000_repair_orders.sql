@@ -0,0 +1,30 @@
+-- Repair orders for damaged tools. The tool is identified by its item and serial number,
+-- the serial its units carry on the stock ledger; while under repair it is held in
+-- quarantine, and the repair ends with it back in available stock or scrapped.
+
+CREATE TABLE warehouse.repair_orders (
+    repair_id SERIAL PRIMARY KEY,
+    repair_number VARCHAR(30) NOT NULL UNIQUE,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    serial_number VARCHAR(50) NOT NULL,
+    -- VENDOR repairs go out to vendor_name; INTERNAL ones stay with our own workshop
+    repair_type VARCHAR(10) NOT NULL CHECK (repair_type IN ('VENDOR', 'INTERNAL')),
+    vendor_name VARCHAR(255),
+    fault_description TEXT NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'OPEN'
+        CHECK (status IN ('OPEN', 'IN_REPAIR', 'RESTOCKED', 'SCRAPPED')),
+    repair_cost DECIMAL(15,2) CHECK (repair_cost >= 0),
+    cost_currency VARCHAR(3) NOT NULL DEFAULT 'IDR' CHECK (cost_currency ~ '^[A-Z]{3}$'),
+    expected_back_on DATE,
+    resolution_notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    sent_at TIMESTAMPTZ,
+    closed_at TIMESTAMPTZ,
+    CHECK (repair_type = 'INTERNAL' OR vendor_name IS NOT NULL)
+);
+
+-- A tool is under one repair at a time
+CREATE UNIQUE INDEX idx_repair_orders_open_serial ON warehouse.repair_orders(item_id, serial_number)
+    WHERE status IN ('OPEN', 'IN_REPAIR');
+CREATE INDEX idx_repair_orders_status ON warehouse.repair_orders(status);
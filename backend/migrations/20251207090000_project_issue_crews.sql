@@ -0,0 +1,4 @@
+-- Crew that drew a project issue, so consumption can be reported per crew
+ALTER TABLE warehouse.project_issues ADD COLUMN crew_name VARCHAR(100);
+
+CREATE INDEX idx_project_issues_issued_at ON warehouse.project_issues(issued_at);
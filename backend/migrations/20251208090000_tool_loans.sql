@@ -0,0 +1,27 @@
+-- Tools lent out of a warehouse to a person, optionally for a project, and expected back
+-- by a due date. While out the units leave available stock for the ON_LOAN account; a
+-- loan still out after its due date is flagged OVERDUE by the overdue scan.
+
+CREATE TABLE warehouse.tool_loans (
+    loan_id SERIAL PRIMARY KEY,
+    loan_number VARCHAR(30) NOT NULL UNIQUE,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity DECIMAL(15,3) NOT NULL CHECK (quantity > 0),
+    -- Serial number the tool carries on the stock ledger, for serialized tools
+    serial_number VARCHAR(50),
+    borrower_name VARCHAR(255) NOT NULL,
+    project_id INTEGER REFERENCES warehouse.projects(project_id),
+    due_on DATE NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'OUT' CHECK (status IN ('OUT', 'OVERDUE', 'RETURNED')),
+    notes TEXT,
+    loaned_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    overdue_at TIMESTAMPTZ,
+    returned_at TIMESTAMPTZ,
+    CHECK ((status = 'OVERDUE') = (overdue_at IS NOT NULL) OR status = 'RETURNED'),
+    CHECK ((status = 'RETURNED') = (returned_at IS NOT NULL))
+);
+
+-- The overdue scan looks for loans still out by due date
+CREATE INDEX idx_tool_loans_out_due ON warehouse.tool_loans(due_on) WHERE status = 'OUT';
+CREATE INDEX idx_tool_loans_item ON warehouse.tool_loans(item_id, serial_number);
@@ -0,0 +1,17 @@
+-- Stock-out incidents: demand that could not be allocated any stock because the warehouse
+-- had none free. Each is the lost demand of one order line, kept for planning.
+
+CREATE TABLE warehouse.stock_out_incidents (
+    incident_id BIGSERIAL PRIMARY KEY,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    item_id INTEGER NOT NULL REFERENCES warehouse.items(item_id),
+    quantity_demanded DECIMAL(15,3) NOT NULL CHECK (quantity_demanded > 0),
+    -- Customer or person whose demand went unmet
+    requested_by VARCHAR(255) NOT NULL,
+    reference_type VARCHAR(20) NOT NULL,
+    reference_number VARCHAR(50),
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_stock_out_incidents_item ON warehouse.stock_out_incidents(item_id, occurred_at);
+CREATE INDEX idx_stock_out_incidents_warehouse ON warehouse.stock_out_incidents(warehouse_id, occurred_at);
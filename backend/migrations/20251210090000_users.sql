@@ -0,0 +1,17 @@
+-- People who use the system. Passwords are kept only as argon2 hashes (PHC strings).
+-- Users are deactivated rather than deleted, so the created_by and updated_by audit
+-- columns keep pointing at who they were.
+
+CREATE TABLE warehouse.users (
+    user_id SERIAL PRIMARY KEY,
+    username VARCHAR(50) NOT NULL UNIQUE CHECK (username ~ '^[a-z0-9._-]+$'),
+    email VARCHAR(255) UNIQUE,
+    full_name VARCHAR(255) NOT NULL,
+    password_hash TEXT NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- The audit columns written so far name user 1: the system itself, which cannot log in
+INSERT INTO warehouse.users (username, full_name, password_hash) VALUES ('system', 'System', '!');
@@ -0,0 +1,17 @@
+-- Inbound loads checked in at the dock: an ASN, a purchase order shipped without one, or
+-- a transfer from another warehouse. A load arrives once; the dock board shows it as
+-- arrived until receiving starts.
+
+CREATE TABLE warehouse.dock_arrivals (
+    arrival_id SERIAL PRIMARY KEY,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    source VARCHAR(20) NOT NULL CHECK (source IN ('ASN', 'PURCHASE_ORDER', 'TRANSFER')),
+    -- receipt_id, po_id or transfer_id, by source
+    reference_id INTEGER NOT NULL,
+    dock_door VARCHAR(20),
+    notes TEXT,
+    arrived_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (source, reference_id)
+);
+
+CREATE INDEX idx_dock_arrivals_warehouse ON warehouse.dock_arrivals(warehouse_id, arrived_at);
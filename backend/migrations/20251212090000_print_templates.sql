@@ -0,0 +1,41 @@
+-- Label (ZPL) and document (HTML) layouts kept in the database, so a layout change is an
+-- API call rather than a redeploy. Every change to a template's body is a new version;
+-- old versions are kept and can be restored.
+
+CREATE TABLE warehouse.print_templates (
+    template_id SERIAL PRIMARY KEY,
+    template_code VARCHAR(50) UNIQUE NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    -- What it prints
+    purpose VARCHAR(30) NOT NULL CHECK (purpose IN (
+        'LPN_LABEL', 'LOCATION_LABEL', 'ITEM_LABEL', 'SHIPPING_LABEL', 'DELIVERY_ORDER', 'PROFORMA_INVOICE'
+    )),
+    format VARCHAR(10) NOT NULL CHECK (format IN ('ZPL', 'HTML')),
+    current_version INTEGER NOT NULL DEFAULT 1,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE warehouse.print_template_versions (
+    template_id INTEGER NOT NULL REFERENCES warehouse.print_templates(template_id),
+    version INTEGER NOT NULL CHECK (version > 0),
+    body TEXT NOT NULL,
+    change_note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (template_id, version)
+);
+
+-- The template a warehouse prints each purpose with. A pinned version stays in use when
+-- the template changes; without one the warehouse follows the current version.
+CREATE TABLE warehouse.print_template_assignments (
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    purpose VARCHAR(30) NOT NULL,
+    template_id INTEGER NOT NULL REFERENCES warehouse.print_templates(template_id),
+    pinned_version INTEGER,
+    assigned_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (warehouse_id, purpose),
+    FOREIGN KEY (template_id, pinned_version) REFERENCES warehouse.print_template_versions(template_id, version)
+);
+
+CREATE INDEX idx_print_template_assignments_template ON warehouse.print_template_assignments(template_id);
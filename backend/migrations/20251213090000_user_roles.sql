@@ -0,0 +1,8 @@
+-- What each user may do. Roles are ranked: a manager may do everything an operator may,
+-- and so on up to admin. New users are viewers until given more.
+
+ALTER TABLE warehouse.users
+    ADD COLUMN role VARCHAR(20) NOT NULL DEFAULT 'VIEWER'
+        CHECK (role IN ('ADMIN', 'MANAGER', 'OPERATOR', 'VIEWER'));
+
+UPDATE warehouse.users SET role = 'ADMIN' WHERE user_id = 1;
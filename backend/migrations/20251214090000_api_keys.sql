@@ -0,0 +1,16 @@
+-- Keys for machine integrations, sent in `X-Api-Key`. Only a SHA-256 hash of each key is
+-- kept; the key itself is shown once, when it is issued. A key's scopes bound what it may
+-- do: READ_ONLY reads, STOCK_WRITE also changes stock, ADMIN may do everything.
+
+CREATE TABLE warehouse.api_keys (
+    key_id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    key_prefix VARCHAR(12) NOT NULL,
+    key_hash VARCHAR(64) NOT NULL UNIQUE,
+    scopes TEXT[] NOT NULL
+        CHECK (cardinality(scopes) > 0 AND scopes <@ ARRAY['READ_ONLY', 'STOCK_WRITE', 'ADMIN']),
+    created_by INTEGER REFERENCES warehouse.users(user_id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
@@ -0,0 +1,25 @@
+-- Printers labels and documents are sent to. A printer may sit at a station, such as a
+-- packing bench, so that station's jobs print next to it.
+
+CREATE TABLE warehouse.printers (
+    printer_id SERIAL PRIMARY KEY,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouse.warehouses(warehouse_id),
+    printer_code VARCHAR(30) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    -- ZPL label printers take raw ZPL over TCP; an AGENT is a print agent on a station's
+    -- PC that takes labels and documents over HTTP
+    printer_type VARCHAR(10) NOT NULL CHECK (printer_type IN ('ZPL', 'AGENT')),
+    -- host[:port] for ZPL printers, port 9100 unless given; a URL for agents
+    address VARCHAR(255) NOT NULL,
+    station_id INTEGER REFERENCES warehouse.measuring_stations(station_id),
+    -- Template purposes the printer prints when a job names no printer. Each purpose has
+    -- at most one default per station, and one for the warehouse as a whole.
+    default_for TEXT[] NOT NULL DEFAULT '{}',
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    last_printed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (warehouse_id, printer_code)
+);
+
+CREATE INDEX idx_printers_warehouse ON warehouse.printers(warehouse_id, station_id);
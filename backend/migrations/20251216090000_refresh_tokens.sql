@@ -0,0 +1,19 @@
+-- Long-lived refresh tokens, exchanged for a new access token and a new refresh token so
+-- terminals stay signed in. Only a token's hash is kept. Each exchange retires the token;
+-- a retired token presented again means it was copied, and its whole family is revoked.
+
+CREATE TABLE warehouse.refresh_tokens (
+    token_id SERIAL PRIMARY KEY,
+    -- The first token of the sign-in; tokens exchanged from it share its family
+    family_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES warehouse.users(user_id),
+    token_hash VARCHAR(64) UNIQUE NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- When it was exchanged for the next token of its family
+    used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_refresh_tokens_family ON warehouse.refresh_tokens(family_id);
+CREATE INDEX idx_refresh_tokens_user ON warehouse.refresh_tokens(user_id) WHERE revoked_at IS NULL;
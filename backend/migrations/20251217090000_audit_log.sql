@@ -0,0 +1,129 @@
+-- Who changed what: every insert, update and delete on the audited tables is recorded by
+-- trigger, so writes from the API, the CLI and background jobs are all covered. Updates
+-- keep only the columns that changed. The actor is whoever the connection was tagged
+-- for (`wms.audit_actor`, e.g. `user:7` or `api_key:3`); writes nobody was tagged for,
+-- such as scheduled jobs, have none.
+
+CREATE TABLE warehouse.audit_log (
+    audit_id BIGSERIAL PRIMARY KEY,
+    -- Singular and lowercase, e.g. warehouse or purchase_order
+    entity VARCHAR(30) NOT NULL,
+    entity_id TEXT NOT NULL,
+    action VARCHAR(10) NOT NULL CHECK (action IN ('CREATE', 'UPDATE', 'DELETE')),
+    -- The row before and after; for updates only the changed columns
+    old_values JSONB,
+    new_values JSONB,
+    actor VARCHAR(100),
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_log_entity ON warehouse.audit_log (entity, entity_id, audit_id DESC);
+CREATE INDEX idx_audit_log_actor ON warehouse.audit_log (actor, audit_id DESC) WHERE actor IS NOT NULL;
+
+-- Secrets and their hashes are recorded as changed, never by value
+CREATE FUNCTION warehouse.audit_redact(row_values JSONB) RETURNS JSONB
+LANGUAGE sql IMMUTABLE AS $$
+    SELECT jsonb_object_agg(
+        key,
+        CASE WHEN key ~ '(password|secret|token|api_key|_hash$)' THEN '"[redacted]"'::JSONB ELSE value END
+    )
+    FROM jsonb_each(row_values)
+$$;
+
+-- Arguments: the entity name and the table's key column
+CREATE FUNCTION warehouse.record_audit() RETURNS TRIGGER
+LANGUAGE plpgsql AS $$
+DECLARE
+    old_values JSONB;
+    new_values JSONB;
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        old_values := to_jsonb(OLD);
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        new_values := to_jsonb(NEW);
+    END IF;
+
+    IF TG_OP = 'UPDATE' THEN
+        -- Bookkeeping columns change as things run rather than as anyone edits them
+        SELECT jsonb_object_agg(o.key, o.value), jsonb_object_agg(o.key, new_values -> o.key)
+        INTO old_values, new_values
+        FROM jsonb_each(old_values) o
+        WHERE o.value IS DISTINCT FROM new_values -> o.key
+          AND o.key NOT IN (
+              'updated_at', 'last_used_at', 'last_printed_at', 'last_capture_at', 'last_sent_at',
+              'last_synced_at', 'last_delivered_at', 'last_error', 'next_run_at', 'next_attempt_at', 'position'
+          );
+        IF old_values IS NULL THEN
+            RETURN NULL;
+        END IF;
+    END IF;
+
+    INSERT INTO warehouse.audit_log (entity, entity_id, action, old_values, new_values, actor)
+    VALUES (
+        TG_ARGV[0],
+        COALESCE(to_jsonb(NEW), to_jsonb(OLD)) ->> TG_ARGV[1],
+        CASE TG_OP WHEN 'INSERT' THEN 'CREATE' ELSE TG_OP END,
+        warehouse.audit_redact(old_values),
+        warehouse.audit_redact(new_values),
+        NULLIF(current_setting('wms.audit_actor', true), '')
+    );
+    RETURN NULL;
+END
+$$;
+
+DO $$
+DECLARE
+    audited RECORD;
+BEGIN
+    FOR audited IN
+        SELECT * FROM (VALUES
+            ('warehouses', 'warehouse', 'warehouse_id'),
+            ('items', 'item', 'item_id'),
+            ('zones', 'zone', 'zone_id'),
+            ('locations', 'location', 'location_id'),
+            ('lpns', 'lpn', 'lpn_id'),
+            ('outbound_orders', 'order', 'order_id'),
+            ('shipments', 'shipment', 'shipment_id'),
+            ('inbound_receipts', 'receipt', 'receipt_id'),
+            ('return_authorizations', 'return', 'return_id'),
+            ('stock_transfers', 'transfer', 'transfer_id'),
+            ('stock_reservations', 'reservation', 'reservation_id'),
+            ('purchase_orders', 'purchase_order', 'po_id'),
+            ('purchase_requisitions', 'requisition', 'requisition_id'),
+            ('recalls', 'recall', 'recall_id'),
+            ('projects', 'project', 'project_id'),
+            ('repair_orders', 'repair', 'repair_id'),
+            ('tool_loans', 'loan', 'loan_id'),
+            ('negative_stock_policies', 'negative_stock_policy', 'policy_id'),
+            ('consignors', 'consignor', 'consignor_id'),
+            ('billing_clients', 'client', 'client_id'),
+            ('tenants', 'tenant', 'tenant_id'),
+            ('measuring_stations', 'station', 'station_id'),
+            ('delivery_vehicles', 'vehicle', 'vehicle_id'),
+            ('delivery_drivers', 'driver', 'driver_id'),
+            ('returnable_types', 'returnable_type', 'type_id'),
+            ('gl_accounts', 'gl_account', 'category'),
+            ('erp_connectors', 'erp_connector', 'connector_id'),
+            ('ecommerce_channels', 'ecommerce_channel', 'channel_id'),
+            ('integration_channels', 'integration_channel', 'channel_id'),
+            ('event_consumers', 'event_consumer', 'consumer_id'),
+            ('vmi_feeds', 'vmi_feed', 'feed_id'),
+            ('report_schedules', 'report_schedule', 'schedule_id'),
+            ('print_templates', 'print_template', 'template_id'),
+            ('printers', 'printer', 'printer_id'),
+            ('users', 'user', 'user_id'),
+            ('api_keys', 'api_key', 'key_id')
+        ) AS t (table_name, entity, key_column)
+    LOOP
+        EXECUTE format(
+            'CREATE TRIGGER %I AFTER INSERT OR UPDATE OR DELETE ON warehouse.%I
+                 FOR EACH ROW EXECUTE FUNCTION warehouse.record_audit(%L, %L)',
+            audited.table_name || '_audit',
+            audited.table_name,
+            audited.entity,
+            audited.key_column
+        );
+    END LOOP;
+END
+$$;
@@ -0,0 +1,43 @@
+-- Warehouses whose stock is held on a database shard (DATABASE_SHARDS), written on the
+-- primary from the shard map at startup. Only the stock endpoints that route by warehouse
+-- reach a shard; receipts, orders, transfers and the other documents stay on the primary,
+-- so the primary refuses their stock changes for a sharded warehouse rather than splitting
+-- its stock between two databases. Shards leave this table empty.
+
+CREATE TABLE warehouse.warehouse_shards (
+    warehouse_id INTEGER PRIMARY KEY REFERENCES warehouse.warehouses(warehouse_id),
+    shard_name VARCHAR(50) NOT NULL
+);
+
+CREATE FUNCTION warehouse.refuse_sharded_stock() RETURNS TRIGGER
+LANGUAGE plpgsql AS $$
+DECLARE
+    changed_warehouse INTEGER;
+    shard VARCHAR(50);
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed_warehouse := OLD.warehouse_id;
+    ELSE
+        changed_warehouse := NEW.warehouse_id;
+    END IF;
+
+    SELECT shard_name INTO shard FROM warehouse.warehouse_shards WHERE warehouse_id = changed_warehouse;
+    IF FOUND THEN
+        RAISE EXCEPTION 'stock of warehouse % is held on database shard %', changed_warehouse, shard
+            USING ERRCODE = 'WG002';
+    END IF;
+
+    IF TG_OP = 'DELETE' THEN
+        RETURN OLD;
+    END IF;
+    RETURN NEW;
+END
+$$;
+
+CREATE TRIGGER stock_inventory_refuse_sharded
+    BEFORE INSERT OR UPDATE OR DELETE ON warehouse.stock_inventory
+    FOR EACH ROW EXECUTE FUNCTION warehouse.refuse_sharded_stock();
+
+CREATE TRIGGER stock_movements_refuse_sharded
+    BEFORE INSERT OR UPDATE OR DELETE ON warehouse.stock_movements
+    FOR EACH ROW EXECUTE FUNCTION warehouse.refuse_sharded_stock();
@@ -0,0 +1,219 @@
+//! Command-line interface for the server binary
+
+use anyhow::Result;
+use clap::{Args, Parser, Subcommand};
+use tracing::info;
+
+use warehouse_core::restore::{self, RestoreOptions, RestoreTarget};
+use warehouse_core::{backup, replay, Config};
+use warehouse_db::generator::{self, GeneratorConfig};
+use warehouse_db::{Database, DatabaseManager};
+use warehouse_models::validator::Validate;
+use warehouse_models::{CreateTenant, CreateUser, ReplayRequest};
+
+#[derive(Parser)]
+#[command(name = "server", about = "Warehouse Management System API")]
+pub struct Cli {
+    /// Serve in-memory seed data instead of connecting to Postgres. Only warehouses, items
+    /// and stock movements are served; other routes answer 503.
+    #[arg(long)]
+    pub mock: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Fill the database with synthetic warehouses, items, stock and movements
+    Generate(GenerateArgs),
+    /// Replay stock events from an offset into a projection or to a registered consumer
+    Replay(ReplayArgs),
+    /// Restore a backup archive into an empty environment or a new tenant
+    Restore(RestoreArgs),
+    /// Create a user, such as the first admin, without signing in
+    CreateUser(CreateUserArgs),
+}
+
+#[derive(Args)]
+pub struct GenerateArgs {
+    #[arg(long, default_value_t = 5)]
+    pub warehouses: usize,
+    #[arg(long, default_value_t = 1_000)]
+    pub items: usize,
+    #[arg(long, default_value_t = 50_000)]
+    pub movements: usize,
+    /// Spread movements over this many days back from today
+    #[arg(long, default_value_t = 365)]
+    pub days: i64,
+    /// Same seed, same data; also used as the code prefix (GEN<seed>-...)
+    #[arg(long, default_value_t = 42)]
+    pub seed: u64,
+    #[arg(long, default_value_t = 5_000)]
+    pub batch_size: usize,
+}
+
+#[derive(Args)]
+pub struct ReplayArgs {
+    /// BALANCES, READ_MODEL or CONSUMER
+    #[arg(long)]
+    pub target: String,
+    /// Consumer to replay to, for CONSUMER
+    #[arg(long)]
+    pub consumer_id: Option<i32>,
+    /// First event to replay; 0 replays the whole stream
+    #[arg(long, default_value_t = 0)]
+    pub from: i64,
+}
+
+#[derive(Args)]
+pub struct RestoreArgs {
+    /// Archive file to restore
+    #[arg(long, conflicts_with = "backup_id", required_unless_present = "backup_id")]
+    pub archive: Option<std::path::PathBuf>,
+    /// Completed backup to fetch from the backup store
+    #[arg(long)]
+    pub backup_id: Option<i64>,
+    /// Provision this tenant and restore one schema of the archive into it
+    #[arg(long)]
+    pub tenant: Option<String>,
+    #[arg(long, requires = "tenant")]
+    pub tenant_name: Option<String>,
+    /// Schema of the archive restored into the tenant
+    #[arg(long, default_value = "warehouse")]
+    pub from_schema: String,
+    /// Empty the target tables, and every table referencing them, before restoring
+    #[arg(long)]
+    pub replace: bool,
+}
+
+#[derive(Args)]
+pub struct CreateUserArgs {
+    #[arg(long)]
+    pub username: String,
+    #[arg(long)]
+    pub full_name: String,
+    #[arg(long)]
+    pub email: Option<String>,
+    #[arg(long)]
+    pub password: String,
+    /// ADMIN, MANAGER, OPERATOR or VIEWER
+    #[arg(long, default_value = "ADMIN")]
+    pub role: String,
+}
+
+pub async fn run_generate(config: &Config, args: GenerateArgs) -> Result<()> {
+    let pool = DatabaseManager::connect(&config.database.connection_settings()).await?;
+    sqlx::migrate!("../migrations").run(&pool).await?;
+
+    let generator_config = GeneratorConfig {
+        warehouses: args.warehouses,
+        items: args.items,
+        movements: args.movements,
+        days: args.days,
+        seed: args.seed,
+        batch_size: args.batch_size.max(1),
+    };
+
+    let started = std::time::Instant::now();
+    let report = generator::generate(&pool, &generator_config).await?;
+
+    info!(
+        "Generated {} warehouses, {} items, {} stock rows, {} movements in {:.1}s",
+        report.warehouses,
+        report.items,
+        report.stock_rows,
+        report.movements,
+        started.elapsed().as_secs_f64()
+    );
+
+    Ok(())
+}
+
+pub async fn run_replay(config: &Config, args: ReplayArgs) -> Result<()> {
+    let pool = DatabaseManager::connect(&config.database.connection_settings()).await?;
+    sqlx::migrate!("../migrations").run(&pool).await?;
+    let db = Database::new(pool);
+
+    let request = ReplayRequest {
+        target: args.target.to_uppercase(),
+        consumer_id: args.consumer_id,
+        from_event_id: args.from,
+    };
+    request.validate()?;
+
+    let report = replay::replay(&db, &config.events, &request).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+    info!(
+        "Replayed {} events from event {} into {}; {} rebuilt",
+        report.events, report.from_event_id, report.target, report.rebuilt
+    );
+
+    Ok(())
+}
+
+pub async fn run_restore(config: &Config, args: RestoreArgs) -> Result<()> {
+    let pool = DatabaseManager::connect(&config.database.connection_settings()).await?;
+    sqlx::migrate!("../migrations").run(&pool).await?;
+    let db = Database::new(pool);
+
+    let archive = match (&args.archive, args.backup_id) {
+        (Some(path), _) => tokio::fs::read(path).await?,
+        (None, Some(id)) => {
+            let backup = db.backups().get_by_id(id).await?.ok_or_else(|| anyhow::anyhow!("backup {} not found", id))?;
+            backup::download(&config.backups, &backup).await.map_err(|e| anyhow::anyhow!("{}", e))?
+        }
+        (None, None) => anyhow::bail!("--archive or --backup-id is required"),
+    };
+
+    let target = match args.tenant {
+        Some(code) => RestoreTarget::Tenant {
+            source_schema: args.from_schema,
+            tenant: CreateTenant {
+                tenant_name: args.tenant_name.unwrap_or_else(|| code.clone()),
+                tenant_code: code,
+                warehouses: Vec::new(),
+            },
+        },
+        None => RestoreTarget::Environment,
+    };
+    let options = RestoreOptions { target, replace: args.replace };
+
+    let report = restore::restore(&db, archive.as_slice(), options).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+    for table in &report.tables {
+        info!(
+            "{}.{} -> {}.{}: {} of {} rows restored",
+            table.source_schema, table.table, table.schema, table.table, table.rows_restored, table.rows_expected
+        );
+        for violation in &table.violations {
+            info!("  {} rows break {} (references {})", violation.rows, violation.constraint_name, violation.references);
+        }
+    }
+    if !report.committed {
+        anyhow::bail!("backup {} not restored: rows reference data that is missing", report.backup_id);
+    }
+
+    info!("Restored backup {}: {} rows", report.backup_id, report.rows_restored());
+    Ok(())
+}
+
+pub async fn run_create_user(config: &Config, args: CreateUserArgs) -> Result<()> {
+    let pool = DatabaseManager::connect(&config.database.connection_settings()).await?;
+    sqlx::migrate!("../migrations").run(&pool).await?;
+    let db = Database::new(pool);
+
+    let user = CreateUser {
+        username: args.username,
+        email: args.email,
+        full_name: args.full_name,
+        password: args.password,
+        role: Some(args.role.to_uppercase()),
+    };
+    user.validate()?;
+    if db.users().username_exists(&user.username).await? {
+        anyhow::bail!("user {} already exists", user.username);
+    }
+
+    let created = db.users().create(user).await?;
+    info!("Created {} user {} ({})", created.role.to_lowercase(), created.username, created.user_id);
+    Ok(())
+}
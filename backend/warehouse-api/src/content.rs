@@ -0,0 +1,111 @@
+//! Response content negotiation
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+
+use crate::jsonapi;
+
+const TEXT_CSV: &str = "text/csv";
+
+/// Representation selected from the `Accept` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    Json,
+    JsonApi,
+    Csv,
+}
+
+/// Pick the response format from `Accept`; anything unrecognised gets the default JSON envelope
+pub fn negotiate(headers: &HeaderMap) -> ResponseFormat {
+    if accepts(headers, TEXT_CSV) {
+        ResponseFormat::Csv
+    } else if accepts(headers, jsonapi::MEDIA_TYPE) {
+        ResponseFormat::JsonApi
+    } else {
+        ResponseFormat::Json
+    }
+}
+
+fn accepts(headers: &HeaderMap, media_type: &str) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media| media.split(';').next().unwrap_or("").trim().eq_ignore_ascii_case(media_type))
+}
+
+/// Stream rows as a CSV attachment; the header row is taken from the first record's field names
+pub fn csv_response<T, S>(filename: &str, rows: S) -> Response
+where
+    T: Serialize,
+    S: Stream<Item = anyhow::Result<T>> + Send + 'static,
+{
+    let mut first = true;
+    let body = rows.map(move |row| -> anyhow::Result<Vec<u8>> {
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(first)
+            .from_writer(Vec::new());
+        first = false;
+        writer.serialize(row?)?;
+        Ok(writer.into_inner()?)
+    });
+
+    let disposition = format!("attachment; filename=\"{}\"", filename);
+
+    (
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static("text/csv; charset=utf-8")),
+            (
+                header::CONTENT_DISPOSITION,
+                HeaderValue::from_str(&disposition)
+                    .unwrap_or_else(|_| HeaderValue::from_static("attachment")),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response()
+}
+
+/// A PDF to display inline, saved under `filename` when downloaded
+pub fn pdf_response(filename: &str, content: Vec<u8>) -> Response {
+    let disposition = format!("inline; filename=\"{}\"", filename);
+
+    (
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static("application/pdf")),
+            (
+                header::CONTENT_DISPOSITION,
+                HeaderValue::from_str(&disposition).unwrap_or_else(|_| HeaderValue::from_static("inline")),
+            ),
+        ],
+        Body::from(content),
+    )
+        .into_response()
+}
+
+/// A file to save as `filename`
+pub fn attachment_response(filename: &str, content_type: &str, content: Vec<u8>) -> Response {
+    let disposition = format!("attachment; filename=\"{}\"", filename);
+
+    (
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_str(content_type)
+                    .unwrap_or_else(|_| HeaderValue::from_static("application/octet-stream")),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                HeaderValue::from_str(&disposition).unwrap_or_else(|_| HeaderValue::from_static("attachment")),
+            ),
+        ],
+        Body::from(content),
+    )
+        .into_response()
+}
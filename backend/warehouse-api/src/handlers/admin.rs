@@ -0,0 +1,144 @@
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Json, Response},
+};
+
+use warehouse_core::auth::{AdministerSystem, RequirePermission};
+use warehouse_core::config::RuntimeConfig;
+use warehouse_core::{backup, jobs, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+/// Reloadable settings as currently in effect
+pub async fn get_runtime_config(
+    _: RequirePermission<AdministerSystem>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<RuntimeConfig>>> {
+    Ok(Json(ApiResponse::success(state.runtime.current().as_ref().clone())))
+}
+
+/// Re-read the reloadable settings from `.env` and the environment, as on SIGHUP
+pub async fn reload_runtime_config(
+    _: RequirePermission<AdministerSystem>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<RuntimeConfig>>> {
+    let reloaded = state.runtime.reload().map_err(|e| AppError::Config(e.to_string()))?;
+    Ok(Json(ApiResponse::success_with_message(reloaded.as_ref().clone(), "Configuration reloaded".to_string())))
+}
+
+pub async fn list_tenants(
+    _: RequirePermission<AdministerSystem>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Tenant>>>> {
+    let tenants = state.db.tenants().list().await?;
+    Ok(Json(ApiResponse::success(tenants)))
+}
+
+pub async fn get_tenant(
+    _: RequirePermission<AdministerSystem>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Tenant>>> {
+    let tenant = state.db.tenants().get_by_id(id).await?.ok_or_else(|| AppError::not_found("tenant"))?;
+    Ok(Json(ApiResponse::success(tenant)))
+}
+
+/// Provision a tenant into a schema of its own: create the schema, migrate it, seed its
+/// warehouses and register it in the catalog. Guarded like `run_migrations`.
+pub async fn provision_tenant(
+    _: RequirePermission<AdministerSystem>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateTenant>,
+) -> AppResult<Json<ApiResponse<TenantProvisioned>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let provisioned =
+        state.db.tenants().provision(payload).await?.ok_or_else(|| AppError::already_exists("tenant code"))?;
+    let message = format!(
+        "Tenant {} provisioned into schema {}",
+        provisioned.tenant.tenant_code, provisioned.tenant.schema_name
+    );
+    Ok(Json(ApiResponse::success_with_message(provisioned, message)))
+}
+
+/// Embedded migrations against those the database has applied, with their checksums
+pub async fn get_migrations(
+    _: RequirePermission<AdministerSystem>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<MigrationStatus>>> {
+    let status = state.db.migrations().status().await?;
+    Ok(Json(ApiResponse::success(status)))
+}
+
+/// Apply pending migrations. Admins and admin API keys only, since it changes the schema
+/// under the running service.
+pub async fn run_migrations(
+    _: RequirePermission<AdministerSystem>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<MigrationRun>>> {
+    let run = state.db.migrations().run().await?;
+    let message = format!("Applied {} migrations", run.applied_versions.len());
+    Ok(Json(ApiResponse::success_with_message(run, message)))
+}
+
+pub async fn list_backups(
+    _: RequirePermission<AdministerSystem>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Backup>>>> {
+    let backups = state.db.backups().list().await?;
+    Ok(Json(ApiResponse::success(backups)))
+}
+
+pub async fn get_backup(
+    _: RequirePermission<AdministerSystem>,
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Backup>>> {
+    let backup = state.db.backups().get_by_id(id).await?.ok_or_else(|| AppError::not_found("backup"))?;
+    Ok(Json(ApiResponse::success(backup)))
+}
+
+/// Start a logical backup of every schema to object storage. Runs in the background;
+/// poll the backup until it is COMPLETED or FAILED. Guarded like `run_migrations`.
+pub async fn create_backup(
+    _: RequirePermission<AdministerSystem>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Backup>>> {
+    let backup = backup::request(&state.db).await?;
+    let job = NewJob {
+        job_type: JOB_BACKUP,
+        description: format!("Backup {}", backup.archive_key),
+        reference_type: Some("BACKUP".to_string()),
+        reference_id: Some(backup.backup_id),
+    };
+    let (db, config, requested) = (state.db.clone(), state.config.backups.clone(), backup.clone());
+    // Failures are recorded on the backup as well as the job
+    let job = jobs::spawn(&state.db, job, move |_| async move {
+        backup::run(&db, &config, &requested).await.map(|_| ())
+    })
+    .await?;
+
+    let message = format!("Backup {} started as job {}", backup.archive_key, job.job_id);
+    Ok(Json(ApiResponse::success_with_message(backup, message)))
+}
+
+/// The archive of a completed backup. Guarded like `run_migrations`, since it holds all data.
+pub async fn download_backup(
+    _: RequirePermission<AdministerSystem>,
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> AppResult<Response> {
+    let backup = state.db.backups().get_by_id(id).await?.ok_or_else(|| AppError::not_found("backup"))?;
+    let archive = backup::download(&state.config.backups, &backup).await?;
+    let file_name = backup.archive_key.rsplit('/').next().unwrap_or(&backup.archive_key).to_string();
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file_name)),
+        ],
+        archive,
+    )
+        .into_response())
+}
@@ -0,0 +1,44 @@
+//! API keys for machine integrations, presented in `X-Api-Key`. Admins, or admin-scoped
+//! keys, issue and revoke them.
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{ManageApiKeys, RequirePermission};
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+pub async fn list_api_keys(
+    _: RequirePermission<ManageApiKeys>,
+    Query(filter): Query<ApiKeyFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<ApiKey>>>> {
+    let keys = state.db.api_keys().list(&filter).await?;
+    Ok(Json(ApiResponse::success(keys)))
+}
+
+pub async fn issue_api_key(
+    permitted: RequirePermission<ManageApiKeys>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateApiKey>,
+) -> AppResult<Json<ApiResponse<IssuedApiKey>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let issued = state.db.api_keys().issue(payload, permitted.caller.user_id()).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        issued,
+        "API key issued; store it now, it is not shown again".to_string(),
+    )))
+}
+
+pub async fn revoke_api_key(
+    _: RequirePermission<ManageApiKeys>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ApiKey>>> {
+    let key = state.db.api_keys().revoke(id).await?.ok_or_else(|| AppError::not_found("API key"))?;
+    Ok(Json(ApiResponse::success_with_message(key, "API key revoked".to_string())))
+}
@@ -0,0 +1,42 @@
+use axum::{
+    extract::{OriginalUri, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{RequirePermission, ViewAuditLog};
+use warehouse_core::{paging, AppError, AppResult, AppState};
+use warehouse_models::*;
+
+use crate::links;
+
+/// Recorded changes, newest first, e.g. `?entity=warehouse&id=3` for one warehouse's
+pub async fn list_audit(
+    _: RequirePermission<ViewAuditLog>,
+    Query(filter): Query<AuditFilter>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<AuditEntry>>>> {
+    paging::check_keyset(&state.config.pagination, "audit", &keyset)?;
+    if let Some(entity) = filter.entity.as_deref().filter(|entity| !AUDITED_ENTITIES.contains(entity)) {
+        return Err(AppError::validation(format!(
+            "unknown audit entity {}; entity must be one of: {}",
+            entity,
+            AUDITED_ENTITIES.join(", ")
+        )));
+    }
+    if filter.id.is_some() && filter.entity.is_none() {
+        return Err(AppError::validation("id needs an entity"));
+    }
+    if let Some(action) = filter.action.as_deref().filter(|action| !AUDIT_ACTIONS.contains(action)) {
+        return Err(AppError::validation(format!(
+            "unknown audit action {}; action must be one of: {}",
+            action,
+            AUDIT_ACTIONS.join(", ")
+        )));
+    }
+
+    let page = state.db.audit().history(&filter, keyset).await?;
+    let navigation = links::keyset_links(page.next_after_id, &uri);
+    Ok(Json(ApiResponse::success(page.with_links(navigation))))
+}
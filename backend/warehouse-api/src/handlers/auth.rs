@@ -0,0 +1,77 @@
+use axum::{extract::State, response::Json};
+use chrono::Utc;
+
+use warehouse_core::auth::{self, CurrentUser};
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::ResourceLinks;
+
+/// Sign in with a username and password for a short-lived access token, and a refresh
+/// token to get the next one with
+pub async fn login(
+    State(state): State<AppState>,
+    Json(payload): Json<Login>,
+) -> AppResult<Json<ApiResponse<AccessToken>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let user =
+        state.db.sign_in().authenticate(&payload.username, &payload.password).await?.ok_or(AppError::Unauthorized)?;
+    // Mock runs keep no refresh tokens; their users sign in again once the token expires
+    let refresh = if state.db.is_mock() {
+        None
+    } else {
+        let ttl_secs = state.config.security.refresh_token_ttl_secs;
+        Some(state.db.refresh_tokens().issue(user.user_id, ttl_secs).await?)
+    };
+    Ok(Json(ApiResponse::success(access_token(&state, user, refresh))))
+}
+
+/// Exchange a refresh token for a new access token and a new refresh token; the one
+/// presented cannot be used again
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(payload): Json<RefreshAccess>,
+) -> AppResult<Json<ApiResponse<AccessToken>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let ttl_secs = state.config.security.refresh_token_ttl_secs;
+    let refresh =
+        state.db.refresh_tokens().rotate(&payload.refresh_token, ttl_secs).await?.ok_or(AppError::Unauthorized)?;
+    let user = state.db.sign_in().get(refresh.user_id).await?.ok_or(AppError::Unauthorized)?;
+    Ok(Json(ApiResponse::success(access_token(&state, user, Some(refresh)))))
+}
+
+/// Sign out: the refresh token and those exchanged from the same sign-in are revoked.
+/// Access tokens already issued run until they expire.
+pub async fn logout(
+    State(state): State<AppState>,
+    Json(payload): Json<RefreshAccess>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    state.db.refresh_tokens().revoke(&payload.refresh_token).await?;
+    Ok(Json(ApiResponse::success_with_message((), "Signed out".to_string())))
+}
+
+/// The signed-in user
+pub async fn get_me(user: CurrentUser, State(state): State<AppState>) -> AppResult<Json<ApiResponse<User>>> {
+    let user =
+        state.db.sign_in().get(user.user_id).await?.filter(|user| user.is_active).ok_or(AppError::Unauthorized)?;
+    let resource_links = user.links();
+    Ok(Json(ApiResponse::success(user).with_links(resource_links)))
+}
+
+fn access_token(state: &AppState, user: User, refresh: Option<IssuedRefreshToken>) -> AccessToken {
+    let security = &state.config.security;
+    let token = auth::issue_token(&security.jwt_secret, user, security.access_token_ttl_secs, Utc::now());
+    match refresh {
+        Some(refresh) => AccessToken {
+            refresh_token: Some(refresh.refresh_token),
+            refresh_expires_at: Some(refresh.expires_at),
+            ..token
+        },
+        None => token,
+    }
+}
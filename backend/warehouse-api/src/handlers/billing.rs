@@ -0,0 +1,177 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::{IntoResponse, Json, Response},
+};
+use futures::stream;
+
+use warehouse_core::{paging, AppError, AppResult, AppState};
+use warehouse_models::chrono::Utc;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::content;
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_clients(State(state): State<AppState>) -> AppResult<Json<ApiResponse<Vec<Linked<BillingClient>>>>> {
+    let clients = state.db.billing().list_clients().await?;
+    Ok(Json(ApiResponse::success(clients.into_iter().map(links::linked).collect())))
+}
+
+pub async fn get_client(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<BillingClient>>> {
+    let client = state.db.billing().get_client(id).await?.ok_or_else(|| AppError::not_found("client"))?;
+    let resource_links = client.links();
+    Ok(Json(ApiResponse::success(client).with_links(resource_links)))
+}
+
+pub async fn create_client(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateBillingClient>,
+) -> AppResult<Json<ApiResponse<BillingClient>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.billing().client_code_exists(&payload.client_code).await? {
+        return Err(AppError::already_exists("client code"));
+    }
+
+    let client = state.db.billing().create_client(payload).await?;
+    let resource_links = client.links();
+    let response = ApiResponse::success_with_message(client, "Client created successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn list_client_items(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<ClientItem>>>> {
+    ensure_client(&state, id).await?;
+
+    let items = state.db.billing().items(id).await?;
+    Ok(Json(ApiResponse::success(items)))
+}
+
+pub async fn assign_client_items(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<AssignClientItems>,
+) -> AppResult<Json<ApiResponse<Vec<ClientItem>>>> {
+    payload.validate().map_err(AppError::validation)?;
+    ensure_client(&state, id).await?;
+
+    let items = state.db.billing().assign_items(id, payload).await?;
+    let message = format!("Client owns {} items", items.len());
+    Ok(Json(ApiResponse::success_with_message(items, message)))
+}
+
+pub async fn get_rates(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<BillingRate>>>> {
+    ensure_client(&state, id).await?;
+
+    let rates = state.db.billing().rates(id).await?;
+    Ok(Json(ApiResponse::success(rates)))
+}
+
+pub async fn set_rates(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<SetBillingRates>,
+) -> AppResult<Json<ApiResponse<Vec<BillingRate>>>> {
+    payload.validate().map_err(AppError::validation)?;
+    ensure_client(&state, id).await?;
+
+    let rates = state.db.billing().set_rates(id, payload).await?;
+    let message = format!("{} rates saved", rates.len());
+    Ok(Json(ApiResponse::success_with_message(rates, message)))
+}
+
+/// Value-added services are recorded by hand; storage, receipts and picks are captured
+pub async fn record_service(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<RecordService>,
+) -> AppResult<Json<ApiResponse<BillableEvent>>> {
+    payload.validate().map_err(AppError::validation)?;
+    ensure_client(&state, id).await?;
+
+    let event = state.db.billing().record_service(id, payload).await?;
+    let resource_links = event.links();
+    let response = ApiResponse::success_with_message(event, "Service recorded".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn list_events(
+    Path(id): Path<i32>,
+    Query(filter): Query<BillableEventFilter>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<BillableEvent>>>>> {
+    paging::check_keyset(&state.config.pagination, "billing/clients/events", &keyset)?;
+    ensure_client(&state, id).await?;
+
+    let events = state.db.billing().events(id, filter, keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(events, &uri))))
+}
+
+/// Run a capture now instead of waiting for the scheduled one
+pub async fn capture(State(state): State<AppState>) -> AppResult<Json<ApiResponse<BillingCapture>>> {
+    let captured = state.db.billing().capture(Utc::now().date_naive()).await?;
+    Ok(Json(ApiResponse::success(captured)))
+}
+
+pub async fn list_invoices(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<BillingInvoice>>>> {
+    ensure_client(&state, id).await?;
+
+    let invoices = state.db.billing().invoices(id).await?;
+    Ok(Json(ApiResponse::success(invoices)))
+}
+
+pub async fn create_invoice(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateInvoice>,
+) -> AppResult<Json<ApiResponse<InvoiceDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+    ensure_client(&state, id).await?;
+
+    let invoice = state.db.billing().create_invoice(id, payload).await?;
+    let message = format!(
+        "Invoice {} raised for {} {}",
+        invoice.invoice.invoice_number, invoice.invoice.total_amount, invoice.invoice.currency
+    );
+    Ok(Json(ApiResponse::success_with_message(invoice, message)))
+}
+
+pub async fn get_invoice(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<InvoiceDetail>>> {
+    let invoice = state.db.billing().get_invoice(id).await?.ok_or_else(|| AppError::not_found("invoice"))?;
+    Ok(Json(ApiResponse::success(invoice)))
+}
+
+/// Invoice lines of all clients for a month as CSV, for the accounting system
+pub async fn export_invoice_lines(
+    Query(query): Query<InvoiceExportQuery>,
+    State(state): State<AppState>,
+) -> AppResult<Response> {
+    query.validate().map_err(AppError::validation)?;
+
+    let lines = state.db.billing().export(query.month).await?;
+    let filename = format!("billing-{}.csv", query.month.format("%Y-%m"));
+    Ok(content::csv_response(&filename, stream::iter(lines.into_iter().map(Ok))).into_response())
+}
+
+async fn ensure_client(state: &AppState, id: i32) -> AppResult<()> {
+    if state.db.billing().get_client(id).await?.is_none() {
+        return Err(AppError::not_found("client"));
+    }
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+
+use warehouse_core::{cartonization, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+/// Every box size of the warehouse, retired ones included
+pub async fn list_box_sizes(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<BoxSize>>>> {
+    let db = state.db.for_warehouse(warehouse_id);
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let boxes = db.cartonization().boxes(warehouse_id, false).await?;
+    Ok(Json(ApiResponse::success(boxes)))
+}
+
+pub async fn create_box_size(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateBoxSize>,
+) -> AppResult<Json<ApiResponse<BoxSize>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let db = state.db.for_warehouse(warehouse_id);
+
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if db.cartonization().box_code_exists(warehouse_id, &payload.box_code).await? {
+        return Err(AppError::already_exists("box code"));
+    }
+
+    let size = db.cartonization().create_box(warehouse_id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(size, "Box size created successfully".to_string())))
+}
+
+pub async fn update_box_size(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateBoxSize>,
+) -> AppResult<Json<ApiResponse<BoxSize>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let size =
+        state.db.cartonization().update_box(id, payload).await?.ok_or_else(|| AppError::not_found("box size"))?;
+    Ok(Json(ApiResponse::success_with_message(size, "Box size updated successfully".to_string())))
+}
+
+/// Suggested cartons for what is still to pack on the order, from the order warehouse's
+/// active box sizes
+pub async fn suggest_cartons(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<CartonPlan>>> {
+    let order = state.db.orders().get_by_id(id).await?.ok_or_else(|| AppError::not_found("order"))?;
+
+    let lines = state.db.cartonization().packing_lines(id).await?;
+    let boxes = state.db.cartonization().boxes(order.order.warehouse_id, true).await?;
+    Ok(Json(ApiResponse::success(cartonization::plan(id, &lines, &boxes))))
+}
@@ -0,0 +1,33 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use super::hazmat::item_response;
+
+pub async fn set_catch_weight(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CatchWeightProfile>,
+) -> AppResult<Json<ApiResponse<Item>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if !state.db.catch_weight().set_profile(id, Some(payload)).await? {
+        return Err(AppError::not_found("item"));
+    }
+    item_response(&state, id, "Item tracked by catch weight").await
+}
+
+pub async fn clear_catch_weight(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Item>>> {
+    if !state.db.catch_weight().set_profile(id, None).await? {
+        return Err(AppError::not_found("item"));
+    }
+    item_response(&state, id, "Catch weight tracking removed").await
+}
@@ -0,0 +1,127 @@
+//! Client access for 3PL deployments. Staff issue and revoke client API keys; a client
+//! presenting its key as `Authorization: Bearer <key>` sees only its own items, stock and
+//! movements under `/api/client`.
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, OriginalUri, Path, Query, State},
+    http::{header, request::Parts},
+    response::Json,
+};
+
+use warehouse_core::auth::{ManageApiKeys, RequirePermission};
+use warehouse_core::{paging, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links;
+
+/// The client a request's API key belongs to. Handlers taking it must scope every query
+/// to `client_id`, whatever the request parameters say.
+pub struct ClientAccess(pub BillingClient);
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientAccess {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let api_key = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(AppError::Unauthorized)?;
+
+        let client = state.db.clients().authenticate(api_key.trim()).await?.ok_or(AppError::Unauthorized)?;
+        Ok(Self(client))
+    }
+}
+
+pub async fn list_api_keys(
+    _: RequirePermission<ManageApiKeys>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<ClientApiKey>>>> {
+    ensure_client(&state, id).await?;
+
+    let keys = state.db.clients().keys(id).await?;
+    Ok(Json(ApiResponse::success(keys)))
+}
+
+pub async fn issue_api_key(
+    _: RequirePermission<ManageApiKeys>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateClientApiKey>,
+) -> AppResult<Json<ApiResponse<IssuedClientApiKey>>> {
+    payload.validate().map_err(AppError::validation)?;
+    ensure_client(&state, id).await?;
+
+    let issued = state.db.clients().issue_key(id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        issued,
+        "API key issued; store it now, it is not shown again".to_string(),
+    )))
+}
+
+pub async fn revoke_api_key(
+    _: RequirePermission<ManageApiKeys>,
+    Path((id, key_id)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ClientApiKey>>> {
+    let key = state.db.clients().revoke_key(id, key_id).await?.ok_or_else(|| AppError::not_found("API key"))?;
+    Ok(Json(ApiResponse::success_with_message(key, "API key revoked".to_string())))
+}
+
+/// Staff view of a client's stock
+pub async fn get_client_stock(
+    Path(id): Path<i32>,
+    Query(filter): Query<ClientStockFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<ClientStock>>>> {
+    ensure_client(&state, id).await?;
+
+    let stock = state.db.read_models().client_stock(id, filter).await?;
+    Ok(Json(ApiResponse::success(stock)))
+}
+
+pub async fn my_items(
+    ClientAccess(client): ClientAccess,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<ClientItem>>>> {
+    let items = state.db.billing().items(client.client_id).await?;
+    Ok(Json(ApiResponse::success(items)))
+}
+
+pub async fn my_stock(
+    ClientAccess(client): ClientAccess,
+    Query(filter): Query<ClientStockFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<ClientStock>>>> {
+    let stock = state.db.read_models().client_stock(client.client_id, filter).await?;
+    Ok(Json(ApiResponse::success(stock)))
+}
+
+/// Movement history of the client's goods. Rows carry no links: the resources they would
+/// point at are staff endpoints.
+pub async fn my_movements(
+    ClientAccess(client): ClientAccess,
+    Query(filter): Query<MovementFilter>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<StockMovement>>>> {
+    paging::check_keyset(&state.config.pagination, "client/movements", &keyset)?;
+    let filter = MovementFilter { client_id: Some(client.client_id), ..filter };
+
+    let page = state.db.movements().history(filter, keyset).await?;
+    let navigation = links::keyset_links(page.next_after_id, &uri);
+    Ok(Json(ApiResponse::success(page.with_links(navigation))))
+}
+
+async fn ensure_client(state: &AppState, id: i32) -> AppResult<()> {
+    if state.db.billing().get_client(id).await?.is_none() {
+        return Err(AppError::not_found("client"));
+    }
+    Ok(())
+}
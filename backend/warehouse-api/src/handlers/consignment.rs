@@ -0,0 +1,134 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_consignors(State(state): State<AppState>) -> AppResult<Json<ApiResponse<Vec<Linked<Consignor>>>>> {
+    let consignors = state.db.consignment().list().await?;
+    Ok(Json(ApiResponse::success(consignors.into_iter().map(links::linked).collect())))
+}
+
+pub async fn get_consignor(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Consignor>>> {
+    let consignor = state.db.consignment().get_by_id(id).await?.ok_or_else(|| AppError::not_found("consignor"))?;
+    let resource_links = consignor.links();
+    Ok(Json(ApiResponse::success(consignor).with_links(resource_links)))
+}
+
+pub async fn create_consignor(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateConsignor>,
+) -> AppResult<Json<ApiResponse<Consignor>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.consignment().code_exists(&payload.consignor_code).await? {
+        return Err(AppError::already_exists("consignor code"));
+    }
+
+    let consignor = state.db.consignment().create(payload).await?;
+    let resource_links = consignor.links();
+    let response = ApiResponse::success_with_message(consignor, "Consignor created successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn set_prices(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<SetConsignmentPrices>,
+) -> AppResult<Json<ApiResponse<Vec<ConsignmentPrice>>>> {
+    payload.validate().map_err(AppError::validation)?;
+    ensure_consignor(&state, id).await?;
+
+    let prices = state.db.consignment().set_prices(id, payload).await?;
+    let message = format!("{} prices saved", prices.len());
+    Ok(Json(ApiResponse::success_with_message(prices, message)))
+}
+
+pub async fn get_consigned_stock(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<ConsignmentStock>>>> {
+    ensure_consignor(&state, id).await?;
+
+    let stock = state.db.consignment().stock(id).await?;
+    Ok(Json(ApiResponse::success(stock)))
+}
+
+/// Explicit consumption; issues of our own stock consume consigned stock on their own
+/// when what we own runs short
+pub async fn consume(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<ConsumeConsignment>,
+) -> AppResult<Json<ApiResponse<StockMovement>>> {
+    payload.validate().map_err(AppError::validation)?;
+    ensure_consignor(&state, id).await?;
+
+    let movement = state.db.consignment().consume(id, payload).await?;
+    let resource_links = movement.links();
+    let response = ApiResponse::success_with_message(movement, "Consigned stock consumed".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn get_consumption(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Query(query): Query<ConsumptionQuery>,
+) -> AppResult<Json<ApiResponse<Vec<ConsumptionLine>>>> {
+    ensure_consignor(&state, id).await?;
+
+    let lines = state.db.consignment().consumption(id, query).await?;
+    Ok(Json(ApiResponse::success(lines)))
+}
+
+pub async fn list_settlements(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<ConsignmentSettlement>>>> {
+    ensure_consignor(&state, id).await?;
+
+    let settlements = state.db.consignment().settlements(id).await?;
+    Ok(Json(ApiResponse::success(settlements)))
+}
+
+pub async fn create_settlement(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateSettlement>,
+) -> AppResult<Json<ApiResponse<SettlementDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+    ensure_consignor(&state, id).await?;
+
+    let settlement = state.db.consignment().settle(id, payload).await?;
+    let message = format!(
+        "Settlement {} raised for {} {}",
+        settlement.settlement.settlement_number, settlement.settlement.total_amount, settlement.settlement.currency
+    );
+    Ok(Json(ApiResponse::success_with_message(settlement, message)))
+}
+
+pub async fn get_settlement(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<SettlementDetail>>> {
+    let settlement =
+        state.db.consignment().get_settlement(id).await?.ok_or_else(|| AppError::not_found("settlement"))?;
+    Ok(Json(ApiResponse::success(settlement)))
+}
+
+async fn ensure_consignor(state: &AppState, id: i32) -> AppResult<()> {
+    if state.db.consignment().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("consignor"));
+    }
+    Ok(())
+}
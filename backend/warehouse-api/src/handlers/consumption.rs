@@ -0,0 +1,53 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{paging, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links;
+
+/// Take in a batch of point-of-sale or site-consumption events; see
+/// `ConsumptionRepository::ingest`
+pub async fn ingest_consumption(
+    _: RequirePermission<AdjustStock>,
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<ConsumptionBatch>,
+) -> AppResult<Json<ApiResponse<ConsumptionResult>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let db = state.db.for_warehouse(warehouse_id);
+
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let result = db.consumption().ingest(warehouse_id, payload).await?;
+    let message = format!(
+        "{} of {} events issued, {} duplicates, {} exceptions",
+        result.movements.len(),
+        result.received,
+        result.duplicates.len(),
+        result.exceptions.len()
+    );
+    Ok(Json(ApiResponse::success_with_message(result, message)))
+}
+
+pub async fn list_consumption(
+    Path(warehouse_id): Path<i32>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<ConsumptionRecord>>>>> {
+    paging::check_keyset(&state.config.pagination, "warehouses/consumption", &keyset)?;
+    let db = state.db.for_warehouse(warehouse_id);
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let records = db.consumption().events(warehouse_id, keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(records, &uri))))
+}
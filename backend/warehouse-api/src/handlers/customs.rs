@@ -0,0 +1,43 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use super::hazmat::item_response;
+
+pub async fn set_item_customs(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CustomsData>,
+) -> AppResult<Json<ApiResponse<Item>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if !state.db.customs().set_item_customs(id, Some(payload)).await? {
+        return Err(AppError::not_found("item"));
+    }
+    item_response(&state, id, "Customs data updated").await
+}
+
+pub async fn clear_item_customs(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Item>>> {
+    if !state.db.customs().set_item_customs(id, None).await? {
+        return Err(AppError::not_found("item"));
+    }
+    item_response(&state, id, "Customs data removed").await
+}
+
+/// Customs declaration to hand the carrier or broker with an international shipment
+pub async fn get_shipment_declaration(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<CustomsDeclaration>>> {
+    let declaration = state.db.customs().declaration(id).await?.ok_or_else(|| AppError::not_found("shipment"))?;
+
+    Ok(Json(ApiResponse::success(declaration)))
+}
@@ -0,0 +1,172 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::chrono::{Days, Utc};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+/// Longest span of delivery days the schedule covers at once
+const MAX_SCHEDULE_DAYS: u64 = 92;
+
+pub async fn get_calendar(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<DeliveryCalendar>>> {
+    if state.db.warehouses().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let calendar = state.db.deliveries().calendar(id).await?;
+    Ok(Json(ApiResponse::success(calendar)))
+}
+
+pub async fn set_calendar(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<SetDeliveryCalendar>,
+) -> AppResult<Json<ApiResponse<DeliveryCalendar>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if state.db.warehouses().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let calendar = state.db.deliveries().set_calendar(id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(calendar, "Delivery calendar updated".to_string())))
+}
+
+pub async fn list_vehicles(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<DeliveryVehicle>>>> {
+    if state.db.warehouses().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let vehicles = state.db.deliveries().vehicles(id).await?;
+    Ok(Json(ApiResponse::success(vehicles)))
+}
+
+pub async fn add_vehicle(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateDeliveryVehicle>,
+) -> AppResult<Json<ApiResponse<DeliveryVehicle>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if state.db.warehouses().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if state.db.deliveries().vehicle_code_exists(id, &payload.vehicle_code).await? {
+        return Err(AppError::already_exists("vehicle code"));
+    }
+
+    let vehicle = state.db.deliveries().add_vehicle(id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(vehicle, "Vehicle added".to_string())))
+}
+
+pub async fn update_vehicle(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateDeliveryVehicle>,
+) -> AppResult<Json<ApiResponse<DeliveryVehicle>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let vehicle =
+        state.db.deliveries().update_vehicle(id, payload).await?.ok_or_else(|| AppError::not_found("vehicle"))?;
+    Ok(Json(ApiResponse::success_with_message(vehicle, "Vehicle updated".to_string())))
+}
+
+/// What each vehicle carries per delivery day, from today for four weeks unless given
+pub async fn get_schedule(
+    Path(id): Path<i32>,
+    Query(filter): Query<DeliveryScheduleFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<VehicleLoad>>>> {
+    let from = filter.from.unwrap_or_else(|| Utc::now().date_naive());
+    let to = filter.to.unwrap_or(from + Days::new(27));
+    if from > to {
+        return Err(AppError::validation("from must not be after to"));
+    }
+    if to > from + Days::new(MAX_SCHEDULE_DAYS - 1) {
+        return Err(AppError::validation(format!("the schedule covers at most {} days", MAX_SCHEDULE_DAYS)));
+    }
+    if state.db.warehouses().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let loads = state.db.deliveries().vehicle_loads(id, from, to).await?;
+    Ok(Json(ApiResponse::success(loads)))
+}
+
+pub async fn list_project_deliveries(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<SiteDelivery>>>>> {
+    let deliveries = state.db.deliveries().for_project(id).await?.ok_or_else(|| AppError::not_found("project"))?;
+    Ok(Json(ApiResponse::success(deliveries.into_iter().map(links::linked).collect())))
+}
+
+/// Ask for a project's materials at its site by a date
+pub async fn request_delivery(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<RequestSiteDelivery>,
+) -> AppResult<Json<ApiResponse<SiteDeliveryDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if payload.needed_by < Utc::now().date_naive() {
+        return Err(AppError::validation("needed_by must not be in the past"));
+    }
+    if state.db.warehouses().get_by_id(payload.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let detail = state.db.deliveries().request(id, payload).await?.ok_or_else(|| AppError::not_found("project"))?;
+    let message = format!("Delivery {} requested", detail.delivery.delivery_number);
+    Ok(with_links(detail, Some(message)))
+}
+
+pub async fn get_delivery(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<SiteDeliveryDetail>>> {
+    let detail = state.db.deliveries().get(id).await?.ok_or_else(|| AppError::not_found("delivery"))?;
+    Ok(with_links(detail, None))
+}
+
+/// Schedule a requested delivery onto a delivery day and vehicle, creating its order
+pub async fn schedule_delivery(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<ScheduleSiteDelivery>,
+) -> AppResult<Json<ApiResponse<SiteDeliveryDetail>>> {
+    let detail = state.db.deliveries().schedule(id, payload).await?.ok_or_else(|| AppError::not_found("delivery"))?;
+    let delivery = &detail.delivery;
+    let message = format!(
+        "Delivery {} scheduled for {}",
+        delivery.delivery_number,
+        delivery.scheduled_date.expect("scheduled deliveries have a date")
+    );
+    Ok(with_links(detail, Some(message)))
+}
+
+pub async fn cancel_delivery(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<SiteDeliveryDetail>>> {
+    let detail = state.db.deliveries().cancel(id).await?.ok_or_else(|| AppError::not_found("delivery"))?;
+    let message = format!("Delivery {} cancelled", detail.delivery.delivery_number);
+    Ok(with_links(detail, Some(message)))
+}
+
+fn with_links(detail: SiteDeliveryDetail, message: Option<String>) -> Json<ApiResponse<SiteDeliveryDetail>> {
+    let resource_links = detail.delivery.links();
+    let response = match message {
+        Some(message) => ApiResponse::success_with_message(detail, message),
+        None => ApiResponse::success(detail),
+    };
+    Json(response.with_links(resource_links))
+}
@@ -0,0 +1,40 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+/// The warehouse's inbound loads for a day and how far along each is, for the dock board
+pub async fn get_dock_board(
+    Path(id): Path<i32>,
+    Query(query): Query<DockBoardQuery>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<DockBoard>>> {
+    if state.db.warehouses().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let date = query.date.unwrap_or_else(|| Utc::now().date_naive());
+    let entries = state.db.dock().board(id, date).await?;
+    Ok(Json(ApiResponse::success(DockBoard::new(id, date, entries))))
+}
+
+/// Check an inbound load in at the dock
+pub async fn check_in_arrival(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CheckInArrival>,
+) -> AppResult<Json<ApiResponse<DockArrival>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let arrival = state.db.dock().check_in(id, payload).await?.ok_or_else(|| AppError::not_found("inbound load"))?;
+    let message = match &arrival.dock_door {
+        Some(door) => format!("Arrived at door {}", door),
+        None => "Arrived".to_string(),
+    };
+    Ok(Json(ApiResponse::success_with_message(arrival, message)))
+}
@@ -0,0 +1,83 @@
+use axum::{
+    extract::{Path, State},
+    response::{Json, Response},
+};
+
+use warehouse_core::{documents, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::content;
+use crate::links::{self, ResourceLinks};
+
+/// The tenant-wide branding and each warehouse's own
+pub async fn list_brandings(
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<DocumentBranding>>>>> {
+    let brandings = state.db.documents().brandings().await?;
+    Ok(Json(ApiResponse::success(brandings.into_iter().map(links::linked).collect())))
+}
+
+/// Branding for the documents of every warehouse without its own
+pub async fn set_default_branding(
+    State(state): State<AppState>,
+    Json(payload): Json<SetDocumentBranding>,
+) -> AppResult<Json<ApiResponse<DocumentBranding>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let branding = state.db.documents().set_branding(None, payload).await?;
+    let response = ApiResponse::success_with_message(branding, "Document branding updated successfully".to_string());
+    Ok(Json(response))
+}
+
+pub async fn set_warehouse_branding(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<SetDocumentBranding>,
+) -> AppResult<Json<ApiResponse<DocumentBranding>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let branding = state.db.documents().set_branding(Some(warehouse_id), payload).await?;
+    let resource_links = branding.links();
+    let response = ApiResponse::success_with_message(branding, "Document branding updated successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn list_shipment_documents(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<ShipmentDocument>>>>> {
+    if state.db.orders().get_shipment(id).await?.is_none() {
+        return Err(AppError::not_found("shipment"));
+    }
+
+    let documents = state.db.documents().documents(id).await?;
+    Ok(Json(ApiResponse::success(documents.into_iter().map(links::linked).collect())))
+}
+
+/// Render a delivery order or proforma invoice for the shipment, replacing one of the
+/// type generated before
+pub async fn generate_shipment_document(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<GenerateDocument>,
+) -> AppResult<Json<ApiResponse<ShipmentDocument>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let document = documents::generate(&state.db, id, &payload.document_type)
+        .await?
+        .ok_or_else(|| AppError::not_found("shipment"))?;
+    let resource_links = document.links();
+    let message = format!("{} generated", document.document_number);
+    Ok(Json(ApiResponse::success_with_message(document, message).with_links(resource_links)))
+}
+
+/// The document's PDF
+pub async fn get_document_pdf(Path(id): Path<i64>, State(state): State<AppState>) -> AppResult<Response> {
+    let (document, pdf) = state.db.documents().content(id).await?.ok_or_else(|| AppError::not_found("document"))?;
+    Ok(content::pdf_response(&format!("{}.pdf", document.document_number), pdf))
+}
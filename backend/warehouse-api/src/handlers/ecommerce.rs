@@ -0,0 +1,106 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::{ecommerce, paging, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_channels(
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<EcommerceChannel>>>>> {
+    let channels = state.db.ecommerce().list().await?;
+    Ok(Json(ApiResponse::success(channels.into_iter().map(links::linked).collect())))
+}
+
+pub async fn get_channel(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<EcommerceChannelDetail>>> {
+    let detail = state.db.ecommerce().get_by_id(id).await?.ok_or_else(|| AppError::not_found("e-commerce channel"))?;
+    let resource_links = detail.channel.links();
+    Ok(Json(ApiResponse::success(detail).with_links(resource_links)))
+}
+
+pub async fn create_channel(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateEcommerceChannel>,
+) -> AppResult<Json<ApiResponse<EcommerceChannelDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.warehouses().get_by_id(payload.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if state.db.ecommerce().code_exists(&payload.channel_code).await? {
+        return Err(AppError::already_exists("e-commerce channel code"));
+    }
+
+    let detail = state.db.ecommerce().create(payload).await?;
+    let resource_links = detail.channel.links();
+    let response = ApiResponse::success_with_message(detail, "E-commerce channel created successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn update_channel(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateEcommerceChannel>,
+) -> AppResult<Json<ApiResponse<EcommerceChannelDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let detail =
+        state.db.ecommerce().update(id, payload).await?.ok_or_else(|| AppError::not_found("e-commerce channel"))?;
+    let resource_links = detail.channel.links();
+    let response = ApiResponse::success_with_message(detail, "E-commerce channel updated successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+/// What the next sync would offer the storefront for each listed item
+pub async fn get_stock_levels(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<EcommerceStockLevel>>>> {
+    if state.db.ecommerce().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("e-commerce channel"));
+    }
+
+    let levels = state.db.ecommerce().stock_levels(id).await?;
+    Ok(Json(ApiResponse::success(levels)))
+}
+
+/// Sync the channel now, outside its schedule
+pub async fn sync_channel(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<EcommerceSyncRun>>>> {
+    let detail = state.db.ecommerce().get_by_id(id).await?.ok_or_else(|| AppError::not_found("e-commerce channel"))?;
+
+    let runs = ecommerce::sync(&state.db, &detail.channel).await?;
+    let records = |direction: &str| -> i32 {
+        runs.iter().filter(|run| run.direction == direction).map(|run| run.records).sum()
+    };
+    let failed = runs.iter().filter(|run| run.status == ECOMMERCE_RUN_FAILED).count();
+    let message = match failed {
+        0 => format!("Imported {} orders, pushed {} stock levels", records(ECOMMERCE_PULL), records(ECOMMERCE_PUSH)),
+        _ => format!("Sync failed for {} of {} directions", failed, runs.len()),
+    };
+    Ok(Json(ApiResponse::success_with_message(runs, message)))
+}
+
+pub async fn list_sync_runs(
+    Path(id): Path<i32>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<EcommerceSyncRun>>>>> {
+    paging::check_keyset(&state.config.pagination, "ecommerce/channels/runs", &keyset)?;
+    if state.db.ecommerce().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("e-commerce channel"));
+    }
+
+    let runs = state.db.ecommerce().runs(id, keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(runs, &uri))))
+}
@@ -0,0 +1,75 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    http::header,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+
+use warehouse_core::{edi, paging, AppError, AppResult, AppState};
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+#[derive(Debug, Deserialize)]
+pub struct InboundQuery {
+    /// Warehouse the partner's documents apply to
+    pub warehouse_id: i32,
+}
+
+/// Accept a raw X12 interchange (856 or 940); the response lists one log entry per transaction set
+pub async fn receive_inbound(
+    Query(query): Query<InboundQuery>,
+    State(state): State<AppState>,
+    body: String,
+) -> AppResult<Json<ApiResponse<Vec<Linked<EdiDocument>>>>> {
+    if state.db.warehouses().get_by_id(query.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let documents = edi::receive(&state.db, query.warehouse_id, &body).await?;
+    let failed = documents.iter().filter(|document| document.status == EDI_FAILED).count();
+    let message = format!("{} processed, {} failed", documents.len() - failed, failed);
+
+    Ok(Json(ApiResponse::success_with_message(
+        documents.into_iter().map(links::linked).collect(),
+        message,
+    )))
+}
+
+pub async fn list_documents(
+    Query(filter): Query<EdiFilter>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<EdiDocument>>>>> {
+    paging::check_keyset(&state.config.pagination, "edi/documents", &keyset)?;
+    let result = state.db.edi().list(filter, keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(result, &uri))))
+}
+
+pub async fn get_document(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<EdiDocument>>> {
+    let document = state
+        .db
+        .edi()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("EDI document"))?;
+
+    let resource_links = document.links();
+    Ok(Json(ApiResponse::success(document).with_links(resource_links)))
+}
+
+/// The document exactly as received or emitted, for partners collecting outbound 945s
+pub async fn get_document_content(Path(id): Path<i64>, State(state): State<AppState>) -> AppResult<Response> {
+    let document = state
+        .db
+        .edi()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("EDI document"))?;
+
+    Ok(([(header::CONTENT_TYPE, "application/edi-x12")], document.content).into_response())
+}
@@ -0,0 +1,124 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+use chrono::Duration;
+
+use warehouse_core::{environment, paging, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links;
+
+pub async fn get_thresholds(
+    Path(zone_id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ZoneThresholds>>> {
+    let thresholds =
+        state.db.environment().thresholds(zone_id).await?.ok_or_else(|| AppError::not_found("zone thresholds"))?;
+
+    Ok(Json(ApiResponse::success(thresholds)))
+}
+
+pub async fn set_thresholds(
+    Path(zone_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<SetThresholds>,
+) -> AppResult<Json<ApiResponse<ZoneThresholds>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if let (Some(min), Some(max)) = (payload.min_temperature, payload.max_temperature) {
+        if min > max {
+            return Err(AppError::validation("min_temperature must not exceed max_temperature"));
+        }
+    }
+    if let (Some(min), Some(max)) = (payload.min_humidity, payload.max_humidity) {
+        if min > max {
+            return Err(AppError::validation("min_humidity must not exceed max_humidity"));
+        }
+    }
+
+    if state.db.locations().get_zone(zone_id).await?.is_none() {
+        return Err(AppError::not_found("zone"));
+    }
+
+    let thresholds = state.db.environment().set_thresholds(zone_id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(thresholds, "Thresholds updated".to_string())))
+}
+
+pub async fn list_sensors(
+    Path(zone_id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Sensor>>>> {
+    if state.db.locations().get_zone(zone_id).await?.is_none() {
+        return Err(AppError::not_found("zone"));
+    }
+
+    let sensors = state.db.environment().sensors(zone_id).await?;
+    Ok(Json(ApiResponse::success(sensors)))
+}
+
+pub async fn create_sensor(
+    Path(zone_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateSensor>,
+) -> AppResult<Json<ApiResponse<Sensor>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.locations().get_zone(zone_id).await?.is_none() {
+        return Err(AppError::not_found("zone"));
+    }
+    if state.db.environment().sensor_code_exists(&payload.sensor_code).await? {
+        return Err(AppError::already_exists("sensor code"));
+    }
+
+    let sensor = state.db.environment().create_sensor(zone_id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(sensor, "Sensor created successfully".to_string())))
+}
+
+/// Bulk reading upload from sensor gateways; re-sent readings are ignored
+pub async fn ingest_readings(
+    State(state): State<AppState>,
+    Json(payload): Json<SensorReadings>,
+) -> AppResult<Json<ApiResponse<ReadingIngestResult>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let result = environment::ingest(&state.db, payload).await?;
+    let message =
+        format!("{} readings accepted, {} excursions opened", result.accepted, result.excursions_opened.len());
+    Ok(Json(ApiResponse::success_with_message(result, message)))
+}
+
+/// Temperature and humidity history of a zone with its excursions, for cold-chain audit
+pub async fn get_environment_history(
+    Path(zone_id): Path<i32>,
+    Query(query): Query<EnvironmentHistoryQuery>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<EnvironmentHistory>>> {
+    if query.from >= query.to {
+        return Err(AppError::validation("from must be before to"));
+    }
+    if query.to - query.from > Duration::days(ENVIRONMENT_HISTORY_MAX_DAYS) {
+        return Err(AppError::validation(format!(
+            "history is limited to {} days per request",
+            ENVIRONMENT_HISTORY_MAX_DAYS
+        )));
+    }
+
+    if state.db.locations().get_zone(zone_id).await?.is_none() {
+        return Err(AppError::not_found("zone"));
+    }
+
+    let history = state.db.environment().history(zone_id, query).await?;
+    Ok(Json(ApiResponse::success(history)))
+}
+
+pub async fn list_excursions(
+    Query(filter): Query<ExcursionFilter>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<Excursion>>>>> {
+    paging::check_keyset(&state.config.pagination, "excursions", &keyset)?;
+    let result = state.db.environment().excursions(filter, keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(result, &uri))))
+}
@@ -0,0 +1,84 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::{erp, paging, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_connectors(State(state): State<AppState>) -> AppResult<Json<ApiResponse<Vec<Linked<ErpConnector>>>>> {
+    let connectors = state.db.erp().list().await?;
+    Ok(Json(ApiResponse::success(connectors.into_iter().map(links::linked).collect())))
+}
+
+pub async fn get_connector(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ErpConnectorDetail>>> {
+    let detail = state.db.erp().get_by_id(id).await?.ok_or_else(|| AppError::not_found("ERP connector"))?;
+    let resource_links = detail.connector.links();
+    Ok(Json(ApiResponse::success(detail).with_links(resource_links)))
+}
+
+pub async fn create_connector(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateErpConnector>,
+) -> AppResult<Json<ApiResponse<ErpConnectorDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.erp().code_exists(&payload.connector_code).await? {
+        return Err(AppError::already_exists("ERP connector code"));
+    }
+
+    let detail = state.db.erp().create(payload).await?;
+    let resource_links = detail.connector.links();
+    let response = ApiResponse::success_with_message(detail, "ERP connector created successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn update_connector(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateErpConnector>,
+) -> AppResult<Json<ApiResponse<ErpConnectorDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let detail = state.db.erp().update(id, payload).await?.ok_or_else(|| AppError::not_found("ERP connector"))?;
+    let resource_links = detail.connector.links();
+    let response = ApiResponse::success_with_message(detail, "ERP connector updated successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+/// Sync the connector now, outside its schedule
+pub async fn sync_connector(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<ErpSyncRun>>>> {
+    let detail = state.db.erp().get_by_id(id).await?.ok_or_else(|| AppError::not_found("ERP connector"))?;
+
+    let runs = erp::sync(&state.db, &detail).await?;
+    let failed = runs.iter().filter(|run| run.status == ERP_RUN_FAILED).count();
+    let message = match failed {
+        0 => format!("Synced {} records", runs.iter().map(|run| run.records).sum::<i32>()),
+        _ => format!("Sync failed for {} of {} entities", failed, runs.len()),
+    };
+    Ok(Json(ApiResponse::success_with_message(runs, message)))
+}
+
+pub async fn list_sync_runs(
+    Path(id): Path<i32>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<ErpSyncRun>>>>> {
+    paging::check_keyset(&state.config.pagination, "erp/connectors/runs", &keyset)?;
+    if state.db.erp().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("ERP connector"));
+    }
+
+    let runs = state.db.erp().runs(id, keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(runs, &uri))))
+}
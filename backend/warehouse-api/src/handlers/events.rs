@@ -0,0 +1,89 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdministerSystem, RequirePermission};
+use warehouse_core::{paging, replay, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_consumers(State(state): State<AppState>) -> AppResult<Json<ApiResponse<Vec<Linked<EventConsumer>>>>> {
+    let consumers = state.db.event_consumers().list().await?;
+    Ok(Json(ApiResponse::success(consumers.into_iter().map(links::linked).collect())))
+}
+
+/// Register a downstream consumer; it is sent the stream from its starting position on
+pub async fn create_consumer(
+    _: RequirePermission<AdministerSystem>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateEventConsumer>,
+) -> AppResult<Json<ApiResponse<EventConsumer>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let consumer = state.db.event_consumers().create(payload).await?;
+    let resource_links = consumer.links();
+    let message = format!("Consumer {} registered", consumer.consumer_name);
+    Ok(Json(ApiResponse::success_with_message(consumer, message).with_links(resource_links)))
+}
+
+pub async fn get_consumer(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<EventConsumer>>> {
+    let consumer =
+        state.db.event_consumers().get_by_id(id).await?.ok_or_else(|| AppError::not_found("event consumer"))?;
+    let resource_links = consumer.links();
+    Ok(Json(ApiResponse::success(consumer).with_links(resource_links)))
+}
+
+/// Replay the stock event stream from an offset into a projection or to a consumer
+pub async fn replay(
+    _: RequirePermission<AdministerSystem>,
+    State(state): State<AppState>,
+    Json(payload): Json<ReplayRequest>,
+) -> AppResult<Json<ApiResponse<ReplayReport>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let report = replay::replay(&state.db, &state.config.events, &payload).await?;
+    let message = format!("Replayed {} events from event {}", report.events, report.from_event_id);
+    Ok(Json(ApiResponse::success_with_message(report, message)))
+}
+
+/// Batches consumers failed to accept after every retry, newest first
+pub async fn list_dead_letters(
+    Query(filter): Query<DeadLetterFilter>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<EventDeadLetter>>>>> {
+    paging::check_keyset(&state.config.pagination, "stock/events/dead-letters", &keyset)?;
+    let page = state.db.event_consumers().dead_letters(filter, keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(page, &uri))))
+}
+
+/// A dead letter with the events of its batch
+pub async fn get_dead_letter(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<DeadLetterDetail>>> {
+    let dead_letter =
+        state.db.event_consumers().get_dead_letter(id).await?.ok_or_else(|| AppError::not_found("dead letter"))?;
+    let events = state.db.stock_events().range(dead_letter.first_event_id, dead_letter.last_event_id).await?;
+    let resource_links = dead_letter.links();
+    Ok(Json(ApiResponse::success(DeadLetterDetail { dead_letter, events }).with_links(resource_links)))
+}
+
+/// Send a dead-lettered batch to its consumer again
+pub async fn redrive_dead_letter(
+    _: RequirePermission<AdministerSystem>,
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<EventDeadLetter>>> {
+    let dead_letter = replay::redrive(&state.db, id).await?.ok_or_else(|| AppError::not_found("dead letter"))?;
+    let resource_links = dead_letter.links();
+    let message = format!("{} events re-driven", dead_letter.event_count);
+    Ok(Json(ApiResponse::success_with_message(dead_letter, message).with_links(resource_links)))
+}
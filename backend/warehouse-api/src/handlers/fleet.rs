@@ -0,0 +1,242 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{Json, Response},
+};
+
+use warehouse_core::{routing, AppError, AppResult, AppState};
+use warehouse_models::chrono::Utc;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::content;
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_drivers(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<DeliveryDriver>>>> {
+    if state.db.warehouses().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let drivers = state.db.fleet().drivers(id).await?;
+    Ok(Json(ApiResponse::success(drivers)))
+}
+
+pub async fn add_driver(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateDeliveryDriver>,
+) -> AppResult<Json<ApiResponse<DeliveryDriver>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if state.db.warehouses().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if state.db.fleet().driver_code_exists(id, &payload.driver_code).await? {
+        return Err(AppError::already_exists("driver code"));
+    }
+
+    let driver = state.db.fleet().add_driver(id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(driver, "Driver added".to_string())))
+}
+
+pub async fn update_driver(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateDeliveryDriver>,
+) -> AppResult<Json<ApiResponse<DeliveryDriver>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let driver = state.db.fleet().update_driver(id, payload).await?.ok_or_else(|| AppError::not_found("driver"))?;
+    Ok(Json(ApiResponse::success_with_message(driver, "Driver updated".to_string())))
+}
+
+pub async fn list_runs(
+    Path(id): Path<i32>,
+    Query(filter): Query<DeliveryRunFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<DeliveryRun>>>>> {
+    let statuses = [RUN_PLANNED, RUN_DISPATCHED, RUN_COMPLETED];
+    if let Some(status) = filter.status.as_deref().filter(|status| !statuses.contains(status)) {
+        return Err(AppError::validation(format!(
+            "unknown run status {}; status must be one of: {}",
+            status,
+            statuses.join(", ")
+        )));
+    }
+    if state.db.warehouses().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let runs = state.db.fleet().runs(id, filter).await?;
+    Ok(Json(ApiResponse::success(runs.into_iter().map(links::linked).collect())))
+}
+
+/// Plan a run of a vehicle and driver for a day
+pub async fn create_run(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateDeliveryRun>,
+) -> AppResult<Json<ApiResponse<DeliveryRunDetail>>> {
+    if payload.run_date < Utc::now().date_naive() {
+        return Err(AppError::validation("run_date must not be in the past"));
+    }
+    if state.db.warehouses().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let detail = state.db.fleet().create_run(id, payload).await?;
+    let message = format!("Run {} planned for {}", detail.run.run_number, detail.run.run_date);
+    Ok(with_links(detail, Some(message)))
+}
+
+pub async fn get_run(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<DeliveryRunDetail>>> {
+    let detail = state.db.fleet().get_run(id).await?.ok_or_else(|| AppError::not_found("run"))?;
+    Ok(with_links(detail, None))
+}
+
+/// Add shipments to a planned run as its next stops
+pub async fn assign_shipments(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<AssignShipments>,
+) -> AppResult<Json<ApiResponse<DeliveryRunDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let count = payload.shipment_ids.len();
+    let detail = state.db.fleet().assign(id, payload).await?.ok_or_else(|| AppError::not_found("run"))?;
+    let message = format!("{} shipment(s) added to {}", count, detail.run.run_number);
+    Ok(with_links(detail, Some(message)))
+}
+
+/// Send a run out, putting its shipments out for delivery
+pub async fn dispatch_run(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<DeliveryRunDetail>>> {
+    let detail = state.db.fleet().dispatch(id).await?.ok_or_else(|| AppError::not_found("run"))?;
+    let message = format!("Run {} dispatched", detail.run.run_number);
+    Ok(with_links(detail, Some(message)))
+}
+
+/// Suggest routes over the vehicles free on the day for the warehouse's shipments waiting
+/// for a run, from the site coordinates given. Nothing is planned until runs are created.
+pub async fn plan_routes(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<PlanRoutes>,
+) -> AppResult<Json<ApiResponse<RoutePlan>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let run_date = payload.run_date.unwrap_or_else(|| Utc::now().date_naive());
+    if run_date < Utc::now().date_naive() {
+        return Err(AppError::validation("run_date must not be in the past"));
+    }
+    if state.db.warehouses().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let shipments = state.db.fleet().routable(id).await?;
+    for site in &payload.sites {
+        if !shipments.iter().any(|shipment| shipment.shipment_id == site.shipment_id) {
+            return Err(AppError::validation(format!(
+                "shipment {} is not a shipment of warehouse {} waiting for a run",
+                site.shipment_id, id
+            )));
+        }
+    }
+    let vehicles = state.db.fleet().free_vehicles(id, run_date).await?;
+
+    let plan = routing::plan(id, run_date, payload.depot, &shipments, &payload.sites, &vehicles);
+    Ok(Json(ApiResponse::success(plan)))
+}
+
+pub async fn get_proof(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ProofOfDelivery>>> {
+    let proof = state.db.fleet().proof(id).await?.ok_or_else(|| AppError::not_found("proof of delivery"))?;
+    let resource_links = proof.links();
+    Ok(Json(ApiResponse::success(proof).with_links(resource_links)))
+}
+
+/// Record who took a shipment at its stop, delivering it
+pub async fn record_proof(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<RecordProofOfDelivery>,
+) -> AppResult<Json<ApiResponse<ProofOfDelivery>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if payload.delivered_at.is_some_and(|delivered_at| delivered_at > Utc::now()) {
+        return Err(AppError::validation("delivered_at must not be in the future"));
+    }
+
+    let proof = state.db.fleet().record_proof(id, payload).await?.ok_or_else(|| AppError::not_found("shipment"))?;
+    let resource_links = proof.links();
+    let response = ApiResponse::success_with_message(proof, "Delivery recorded".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+/// Upload the recipient's signature or a photo of the delivered goods as the raw request
+/// body, typed by its `Content-Type`
+pub async fn upload_proof_file(
+    Path((id, kind)): Path<(i32, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<Json<ApiResponse<ProofOfDeliveryFile>>> {
+    let file_kind = file_kind(&kind)?;
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(';').next().unwrap_or("").trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    if !POD_CONTENT_TYPES.contains(&content_type.as_str()) {
+        return Err(AppError::validation(format!("Content-Type must be one of: {}", POD_CONTENT_TYPES.join(", "))));
+    }
+    if body.is_empty() {
+        return Err(AppError::validation(format!("the {} is empty", kind)));
+    }
+
+    let file = state
+        .db
+        .fleet()
+        .attach_file(id, file_kind, &content_type, &body)
+        .await?
+        .ok_or_else(|| AppError::not_found("proof of delivery"))?;
+    let message = if file_kind == POD_SIGNATURE { "Signature uploaded" } else { "Photo uploaded" };
+    Ok(Json(ApiResponse::success_with_message(file, message.to_string())))
+}
+
+pub async fn get_proof_file(
+    Path((id, kind)): Path<(i32, String)>,
+    State(state): State<AppState>,
+) -> AppResult<Response> {
+    let file_kind = file_kind(&kind)?;
+    let (file, content) =
+        state.db.fleet().file(id, file_kind).await?.ok_or_else(|| AppError::not_found("proof of delivery file"))?;
+    let extension = file.content_type.rsplit('/').next().unwrap_or("bin");
+    let filename = format!("shipment-{}-{}.{}", id, kind, extension);
+    Ok(content::attachment_response(&filename, &file.content_type, content))
+}
+
+fn file_kind(kind: &str) -> AppResult<&'static str> {
+    match kind {
+        "signature" => Ok(POD_SIGNATURE),
+        "photo" => Ok(POD_PHOTO),
+        _ => Err(AppError::validation(format!("unknown proof of delivery file {}; use signature or photo", kind))),
+    }
+}
+
+fn with_links(detail: DeliveryRunDetail, message: Option<String>) -> Json<ApiResponse<DeliveryRunDetail>> {
+    let resource_links = detail.run.links();
+    let response = match message {
+        Some(message) => ApiResponse::success_with_message(detail, message),
+        None => ApiResponse::success(detail),
+    };
+    Json(response.with_links(resource_links))
+}
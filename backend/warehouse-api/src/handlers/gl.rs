@@ -0,0 +1,118 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Json, Response},
+};
+use futures::stream;
+
+use warehouse_core::auth::{ManageAccounting, RequirePermission};
+use warehouse_core::{gl, paging, AppError, AppResult, AppState};
+use warehouse_models::chrono::NaiveDate;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::content::{self, ResponseFormat};
+use crate::links::{self, ResourceLinks};
+
+fn period(period: &str) -> AppResult<NaiveDate> {
+    parse_gl_period(period).ok_or_else(|| AppError::validation(format!("period {} is not YYYY-MM", period)))
+}
+
+pub async fn list_accounts(State(state): State<AppState>) -> AppResult<Json<ApiResponse<Vec<GlAccount>>>> {
+    let accounts = state.db.gl().accounts().await?;
+    Ok(Json(ApiResponse::success(accounts)))
+}
+
+pub async fn update_account(
+    _: RequirePermission<ManageAccounting>,
+    Path(category): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateGlAccount>,
+) -> AppResult<Json<ApiResponse<GlAccount>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let account = state
+        .db
+        .gl()
+        .update_account(&category.to_uppercase(), payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("GL account category"))?;
+    Ok(Json(ApiResponse::success_with_message(account, "GL account updated successfully".to_string())))
+}
+
+/// Periods that have been closed, and reopened ones; any other period is open
+pub async fn list_periods(State(state): State<AppState>) -> AppResult<Json<ApiResponse<Vec<Linked<GlPeriod>>>>> {
+    let periods = state.db.gl().periods().await?;
+    Ok(Json(ApiResponse::success(periods.into_iter().map(links::linked).collect())))
+}
+
+/// The period's journal, as CSV lines when asked for `text/csv`
+pub async fn get_journal(
+    Path(month): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let period = period(&month)?;
+    let journal = gl::journal(&state.db, period).await?;
+
+    Ok(match content::negotiate(&headers) {
+        ResponseFormat::Csv => {
+            let filename = format!("gl-journal-{}.csv", month);
+            content::csv_response(&filename, stream::iter(journal.lines.into_iter().map(Ok)))
+        }
+        _ => {
+            let resource_links = state.db.gl().period(period).await?.links();
+            Json(ApiResponse::success(journal).with_links(resource_links)).into_response()
+        }
+    })
+}
+
+/// Post the period's journal to the accounting API
+pub async fn export_journal(
+    _: RequirePermission<ManageAccounting>,
+    Path(month): Path<String>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<GlExport>>> {
+    let period = period(&month)?;
+
+    let export = gl::push(&state.db, &state.config.gl, period).await?;
+    let message = match &export.error {
+        None => format!("Exported {} journal lines", export.lines),
+        Some(error) => format!("Journal export failed: {}", error),
+    };
+    let resource_links = export.links();
+    Ok(Json(ApiResponse::success_with_message(export, message).with_links(resource_links)))
+}
+
+/// Lock the period's movements against changes
+pub async fn close_period(
+    _: RequirePermission<ManageAccounting>,
+    Path(month): Path<String>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<GlPeriod>>> {
+    let closed = state.db.gl().close(period(&month)?).await?;
+    let resource_links = closed.links();
+    let response = ApiResponse::success_with_message(closed, format!("Period {} closed", month));
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn reopen_period(
+    _: RequirePermission<ManageAccounting>,
+    Path(month): Path<String>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<GlPeriod>>> {
+    let reopened = state.db.gl().reopen(period(&month)?).await?;
+    let resource_links = reopened.links();
+    let response = ApiResponse::success_with_message(reopened, format!("Period {} reopened", month));
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn list_exports(
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<GlExport>>>>> {
+    paging::check_keyset(&state.config.pagination, "gl/exports", &keyset)?;
+    let exports = state.db.gl().exports(keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(exports, &uri))))
+}
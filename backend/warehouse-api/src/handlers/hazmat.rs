@@ -0,0 +1,60 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::ResourceLinks;
+
+pub async fn classify_item(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<HazmatClassification>,
+) -> AppResult<Json<ApiResponse<Item>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if !state.db.hazmat().classify_item(id, Some(payload)).await? {
+        return Err(AppError::not_found("item"));
+    }
+    item_response(&state, id, "Item classified as dangerous goods").await
+}
+
+pub async fn declassify_item(Path(id): Path<i32>, State(state): State<AppState>) -> AppResult<Json<ApiResponse<Item>>> {
+    if !state.db.hazmat().classify_item(id, None).await? {
+        return Err(AppError::not_found("item"));
+    }
+    item_response(&state, id, "Dangerous goods classification removed").await
+}
+
+pub async fn set_zone_hazmat(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<ZoneHazmat>,
+) -> AppResult<Json<ApiResponse<Zone>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let zone = state.db.hazmat().set_zone_classes(id, payload).await?.ok_or_else(|| AppError::not_found("zone"))?;
+    let resource_links = zone.links();
+    Ok(Json(
+        ApiResponse::success_with_message(zone, "Zone hazmat approval updated".to_string()).with_links(resource_links),
+    ))
+}
+
+/// Dangerous goods declaration to hand the carrier with a shipment
+pub async fn get_shipment_declaration(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<HazmatDeclaration>>> {
+    let declaration = state.db.hazmat().declaration(id).await?.ok_or_else(|| AppError::not_found("shipment"))?;
+
+    Ok(Json(ApiResponse::success(declaration)))
+}
+
+pub(super) async fn item_response(state: &AppState, id: i32, message: &str) -> AppResult<Json<ApiResponse<Item>>> {
+    let item = state.db.items().get_by_id(id).await?.ok_or_else(|| AppError::not_found("item"))?;
+    let resource_links = item.links();
+    Ok(Json(ApiResponse::success_with_message(item, message.to_string()).with_links(resource_links)))
+}
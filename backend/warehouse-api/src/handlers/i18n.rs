@@ -0,0 +1,35 @@
+use std::collections::BTreeMap;
+
+use axum::{extract::State, response::Json};
+
+use warehouse_core::auth::CurrentUser;
+use warehouse_core::{i18n, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+/// Display labels of status and type codes, by group, in the request's language
+pub async fn list_labels() -> Json<ApiResponse<BTreeMap<String, BTreeMap<String, String>>>> {
+    Json(ApiResponse::success(i18n::labels(i18n::current())))
+}
+
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<Json<ApiResponse<UserPreference>>> {
+    let owner = user.username;
+    let preference = state.db.preferences().get(&owner).await?.ok_or_else(|| AppError::not_found("preferences"))?;
+    Ok(Json(ApiResponse::success(preference)))
+}
+
+/// Save the user's language; it takes precedence over `Accept-Language` from then on
+pub async fn set_preferences(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(payload): Json<SetUserPreference>,
+) -> AppResult<Json<ApiResponse<UserPreference>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let owner = user.username;
+    let preference = state.db.preferences().set(&owner, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(preference, "Preferences saved".to_string())))
+}
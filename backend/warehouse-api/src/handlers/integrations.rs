@@ -0,0 +1,124 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_channels(
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<IntegrationChannel>>>>> {
+    let channels = state.db.integrations().list_channels().await?;
+    Ok(Json(ApiResponse::success(channels.into_iter().map(links::linked).collect())))
+}
+
+pub async fn get_channel(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<IntegrationChannel>>> {
+    let channel =
+        state.db.integrations().get_channel(id).await?.ok_or_else(|| AppError::not_found("integration channel"))?;
+    let resource_links = channel.links();
+    Ok(Json(ApiResponse::success(channel).with_links(resource_links)))
+}
+
+pub async fn create_channel(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateIntegrationChannel>,
+) -> AppResult<Json<ApiResponse<IntegrationChannel>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.warehouses().get_by_id(payload.fulfillment_warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if state.db.integrations().get_channel_by_code(&payload.channel_code).await?.is_some() {
+        return Err(AppError::already_exists("integration channel code"));
+    }
+
+    let channel = state.db.integrations().create_channel(payload).await?;
+    let resource_links = channel.links();
+    let response = ApiResponse::success_with_message(channel, "Integration channel created successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn update_channel(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateIntegrationChannel>,
+) -> AppResult<Json<ApiResponse<IntegrationChannel>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if let Some(warehouse_id) = payload.fulfillment_warehouse_id {
+        if state.db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+            return Err(AppError::not_found("warehouse"));
+        }
+    }
+
+    let channel = state
+        .db
+        .integrations()
+        .update_channel(id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("integration channel"))?;
+    let resource_links = channel.links();
+    let response = ApiResponse::success_with_message(channel, "Integration channel updated successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+/// Take in a channel's order: it is created in the channel's fulfillment warehouse with
+/// free stock allocated to its lines. Posting the same external order again returns the
+/// order created the first time.
+pub async fn intake_order(
+    State(state): State<AppState>,
+    Json(payload): Json<IntakeOrder>,
+) -> AppResult<Json<ApiResponse<IntakeResult>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let channel = state
+        .db
+        .integrations()
+        .get_channel_by_code(&payload.channel_code)
+        .await?
+        .ok_or_else(|| AppError::not_found("integration channel"))?;
+
+    let mut lines = Vec::with_capacity(payload.lines.len());
+    for line in &payload.lines {
+        let item = state
+            .db
+            .items()
+            .get_by_code(&line.sku)
+            .await?
+            .ok_or_else(|| AppError::not_found(&format!("item {}", line.sku)))?;
+        lines.push(CreateOrderLine { item_id: item.item_id, quantity_ordered: line.quantity });
+    }
+
+    let order = CreateOrder {
+        warehouse_id: channel.fulfillment_warehouse_id,
+        customer_name: payload.customer_name,
+        customer_reference: payload.customer_reference.or_else(|| Some(payload.external_order_id.clone())),
+        ship_to_name: payload.ship_to_name,
+        ship_to_address: payload.ship_to_address,
+        ship_to_city: payload.ship_to_city,
+        ship_to_state: payload.ship_to_state,
+        ship_to_postal_code: payload.ship_to_postal_code,
+        ship_to_country: payload.ship_to_country,
+        requested_ship_date: payload.requested_ship_date,
+        carrier_code: payload.carrier_code,
+        dropship_supplier_code: None,
+        lines,
+    };
+    let (detail, duplicate) = state.db.integrations().intake(&channel, &payload.external_order_id, order).await?;
+
+    let resource_links = detail.order.links();
+    let result = IntakeResult::new(channel.channel_code, payload.external_order_id, duplicate, detail);
+    let message = if duplicate {
+        format!("Order already taken in as {}", result.order.order.order_number)
+    } else {
+        format!("Order taken in as {} ({})", result.order.order.order_number, result.allocation_status)
+    };
+    Ok(Json(ApiResponse::success_with_message(result, message).with_links(resource_links)))
+}
@@ -0,0 +1,39 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::{integrity, paging, AppError, AppResult, AppState};
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+/// Integrity runs, newest first
+pub async fn list_runs(
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<IntegrityRun>>>>> {
+    paging::check_keyset(&state.config.pagination, "integrity/runs", &keyset)?;
+    let page = state.db.integrity().runs(keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(page, &uri))))
+}
+
+/// Check the stock invariants now rather than waiting for the schedule
+pub async fn run_checks(State(state): State<AppState>) -> AppResult<Json<ApiResponse<IntegrityReport>>> {
+    let report = integrity::run(&state.db).await?;
+    let resource_links = report.run.links();
+    let message = format!("Integrity run {} found {} broken invariants", report.run.run_id, report.run.findings);
+    Ok(Json(ApiResponse::success_with_message(report, message).with_links(resource_links)))
+}
+
+/// A run with its findings; `?check_name=` and `?warehouse_id=` narrow them down
+pub async fn get_run(
+    Path(id): Path<i32>,
+    Query(filter): Query<IntegrityFindingFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<IntegrityRunDetail>>> {
+    let detail = state.db.integrity().get_run(id, filter).await?.ok_or_else(|| AppError::not_found("integrity run"))?;
+    let resource_links = detail.run.links();
+    Ok(Json(ApiResponse::success(detail).with_links(resource_links)))
+}
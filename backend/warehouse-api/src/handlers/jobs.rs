@@ -0,0 +1,46 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::{Json, Response},
+};
+
+use warehouse_core::{jobs, paging, AppError, AppResult, AppState};
+use warehouse_models::chrono::Utc;
+use warehouse_models::*;
+
+use crate::content;
+use crate::links::{self, ResourceLinks};
+
+/// Imports, exports, reports and recalculations, newest first
+pub async fn list_jobs(
+    Query(filter): Query<JobFilter>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<Job>>>>> {
+    paging::check_keyset(&state.config.pagination, "jobs", &keyset)?;
+    let result = state.db.jobs().list(filter, keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(result, &uri))))
+}
+
+/// The job's progress and errors, with signed download URLs for what it produced
+pub async fn get_job(Path(id): Path<i64>, State(state): State<AppState>) -> AppResult<Json<ApiResponse<JobDetail>>> {
+    let job = state.db.jobs().get_by_id(id).await?.ok_or_else(|| AppError::not_found("job"))?;
+    let detail = jobs::detail(&state.db, &state.config.security.jwt_secret, &state.config.jobs, job).await?;
+    let resource_links = detail.job.links();
+    Ok(Json(ApiResponse::success(detail).with_links(resource_links)))
+}
+
+/// Download through a URL from `get_job`; the signature stands in for other credentials
+pub async fn download_artifact(
+    Path(id): Path<i64>,
+    Query(signed): Query<ArtifactSignature>,
+    State(state): State<AppState>,
+) -> AppResult<Response> {
+    if !jobs::verify(&state.config.security.jwt_secret, id, &signed, Utc::now()) {
+        return Err(AppError::Unauthorized);
+    }
+
+    let (artifact, content) =
+        state.db.jobs().artifact_content(id).await?.ok_or_else(|| AppError::not_found("job artifact"))?;
+    Ok(content::attachment_response(&artifact.file_name, &artifact.content_type, content))
+}
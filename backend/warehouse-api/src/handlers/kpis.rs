@@ -0,0 +1,59 @@
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::*;
+
+fn check_filter(filter: &KpiFilter) -> AppResult<()> {
+    if !KPI_PERIODS.contains(&filter.period()) {
+        return Err(AppError::validation(format!("period must be one of: {}", KPI_PERIODS.join(", "))));
+    }
+    if let (Some(from), Some(to)) = (filter.from, filter.to) {
+        if from > to {
+            return Err(AppError::validation("from must not be after to"));
+        }
+    }
+    Ok(())
+}
+
+/// Share of orders shipped in full by their requested ship date
+pub async fn otif(
+    Query(filter): Query<KpiFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<OtifKpi>>>> {
+    check_filter(&filter)?;
+    let rows = state.db.kpis().otif(filter).await?;
+    Ok(Json(ApiResponse::success(rows)))
+}
+
+/// How much of each order its first shipment carried
+pub async fn fill_rate(
+    Query(filter): Query<KpiFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<FillRateKpi>>>> {
+    check_filter(&filter)?;
+    let rows = state.db.kpis().fill_rate(filter).await?;
+    Ok(Json(ApiResponse::success(rows)))
+}
+
+/// Hours from unloading to stock being available
+pub async fn dock_to_stock(
+    Query(filter): Query<KpiFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<DockToStockKpi>>>> {
+    check_filter(&filter)?;
+    let rows = state.db.kpis().dock_to_stock(filter).await?;
+    Ok(Json(ApiResponse::success(rows)))
+}
+
+/// Counts matching the quantity on record
+pub async fn inventory_accuracy(
+    Query(filter): Query<KpiFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<InventoryAccuracyKpi>>>> {
+    check_filter(&filter)?;
+    let rows = state.db.kpis().inventory_accuracy(filter).await?;
+    Ok(Json(ApiResponse::success(rows)))
+}
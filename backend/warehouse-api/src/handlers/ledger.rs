@@ -0,0 +1,27 @@
+use axum::{
+    extract::{OriginalUri, Query, State},
+    response::Json,
+};
+
+use warehouse_core::{paging, AppResult, AppState};
+use warehouse_models::*;
+
+use crate::links;
+
+pub async fn list_ledger_entries(
+    Query(filter): Query<LedgerFilter>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<LedgerEntry>>>> {
+    paging::check_keyset(&state.config.pagination, "ledger/entries", &keyset)?;
+    let page = state.db.ledger().entries(filter, keyset).await?;
+    let navigation = links::keyset_links(page.next_after_id, &uri);
+    Ok(Json(ApiResponse::success(page.with_links(navigation))))
+}
+
+/// Prove the ledger balances and reconciles with the stock balances
+pub async fn check_ledger_integrity(State(state): State<AppState>) -> AppResult<Json<ApiResponse<LedgerIntegrity>>> {
+    let integrity = state.db.ledger().integrity().await?;
+    Ok(Json(ApiResponse::success(integrity)))
+}
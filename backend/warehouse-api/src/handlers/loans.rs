@@ -0,0 +1,76 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_loans(
+    Query(filter): Query<LoanFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<ToolLoan>>>>> {
+    if let Some(status) = filter.status.as_deref().filter(|status| !LOAN_STATUSES.contains(status)) {
+        return Err(AppError::validation(format!(
+            "unknown loan status {}; status must be one of: {}",
+            status,
+            LOAN_STATUSES.join(", ")
+        )));
+    }
+
+    let loans = state.db.loans().list(&filter).await?;
+    Ok(Json(ApiResponse::success(loans.into_iter().map(links::linked).collect())))
+}
+
+pub async fn get_loan(Path(id): Path<i32>, State(state): State<AppState>) -> AppResult<Json<ApiResponse<ToolLoan>>> {
+    let loan = state.db.loans().get(id).await?.ok_or_else(|| AppError::not_found("loan"))?;
+    let resource_links = loan.links();
+    Ok(Json(ApiResponse::success(loan).with_links(resource_links)))
+}
+
+/// Lend a loanable item out of available stock
+pub async fn create_loan(
+    _: RequirePermission<AdjustStock>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateLoan>,
+) -> AppResult<Json<ApiResponse<ToolLoan>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if state.db.warehouses().get_by_id(payload.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if state.db.items().get_by_id(payload.item_id).await?.is_none() {
+        return Err(AppError::not_found("item"));
+    }
+    if let Some(project_id) = payload.project_id {
+        if state.db.projects().get(project_id).await?.is_none() {
+            return Err(AppError::not_found("project"));
+        }
+    }
+
+    let loan = state.db.loans().lend(payload).await?;
+    let resource_links = loan.links();
+    let message = format!("{} lent to {}, due {}", loan.loan_number, loan.borrower_name, loan.due_on);
+    Ok(Json(ApiResponse::success_with_message(loan, message).with_links(resource_links)))
+}
+
+/// Take a lent tool back into available stock
+pub async fn return_loan(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ToolLoan>>> {
+    let loan = state.db.loans().return_loan(id).await?.ok_or_else(|| AppError::not_found("loan"))?;
+    let resource_links = loan.links();
+    let message = format!("{} returned", loan.loan_number);
+    Ok(Json(ApiResponse::success_with_message(loan, message).with_links(resource_links)))
+}
+
+/// Loans past their due date, longest overdue first, for supervisors to chase
+pub async fn list_overdue_loans(State(state): State<AppState>) -> AppResult<Json<ApiResponse<Vec<OverdueLoan>>>> {
+    let loans = state.db.loans().overdue().await?;
+    Ok(Json(ApiResponse::success(loans)))
+}
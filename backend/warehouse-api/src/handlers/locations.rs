@@ -0,0 +1,113 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_zones(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<Zone>>>>> {
+    let db = state.db.for_warehouse(warehouse_id);
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let zones = db.locations().zones(warehouse_id).await?;
+    Ok(Json(ApiResponse::success(zones.into_iter().map(links::linked).collect())))
+}
+
+pub async fn create_zone(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateZone>,
+) -> AppResult<Json<ApiResponse<Zone>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let db = state.db.for_warehouse(warehouse_id);
+
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if db.locations().zone_code_exists(warehouse_id, &payload.zone_code).await? {
+        return Err(AppError::already_exists("zone code"));
+    }
+
+    let zone = db.locations().create_zone(warehouse_id, payload).await?;
+    let resource_links = zone.links();
+    Ok(Json(
+        ApiResponse::success_with_message(zone, "Zone created successfully".to_string()).with_links(resource_links),
+    ))
+}
+
+/// Occupancy of every zone and location, for the warehouse heatmap
+pub async fn get_utilization(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<WarehouseUtilization>>> {
+    let db = state.db.for_warehouse(warehouse_id);
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let utilization = db.locations().utilization(warehouse_id).await?;
+    Ok(Json(ApiResponse::success(utilization)))
+}
+
+pub async fn list_locations(
+    Path(zone_id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Location>>>> {
+    if state.db.locations().get_zone(zone_id).await?.is_none() {
+        return Err(AppError::not_found("zone"));
+    }
+
+    let locations = state.db.locations().locations(zone_id).await?;
+    Ok(Json(ApiResponse::success(locations)))
+}
+
+pub async fn create_location(
+    Path(zone_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateLocation>,
+) -> AppResult<Json<ApiResponse<Location>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.locations().get_zone(zone_id).await?.is_none() {
+        return Err(AppError::not_found("zone"));
+    }
+    if let Some(item_id) = payload.item_id {
+        if state.db.items().get_by_id(item_id).await?.is_none() {
+            return Err(AppError::not_found(&format!("item {}", item_id)));
+        }
+    }
+    if state.db.locations().location_code_exists(zone_id, &payload.location_code).await? {
+        return Err(AppError::already_exists("location code"));
+    }
+
+    let location = state.db.locations().create_location(zone_id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        location,
+        "Location created successfully".to_string(),
+    )))
+}
+
+pub async fn set_location_capacity(
+    Path(location_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<SetLocationCapacity>,
+) -> AppResult<Json<ApiResponse<Location>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let location = state
+        .db
+        .locations()
+        .set_capacity(location_id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("location"))?;
+    Ok(Json(ApiResponse::success(location)))
+}
@@ -0,0 +1,85 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_lpns(
+    Path(warehouse_id): Path<i32>,
+    Query(filter): Query<LpnFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<Lpn>>>>> {
+    let db = state.db.for_warehouse(warehouse_id);
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let lpns = db.lpns().lpns(warehouse_id, filter).await?;
+    Ok(Json(ApiResponse::success(lpns.into_iter().map(links::linked).collect())))
+}
+
+pub async fn create_lpn(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateLpn>,
+) -> AppResult<Json<ApiResponse<LpnDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let db = state.db.for_warehouse(warehouse_id);
+
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if let Some(number) = &payload.lpn_number {
+        if db.lpns().lpn_number_exists(number).await? {
+            return Err(AppError::already_exists("LPN number"));
+        }
+    }
+
+    let lpn = db.lpns().create(warehouse_id, payload).await?;
+    let resource_links = lpn.lpn.links();
+    let response = ApiResponse::success_with_message(lpn, "LPN created successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn get_lpn(Path(id): Path<i32>, State(state): State<AppState>) -> AppResult<Json<ApiResponse<LpnDetail>>> {
+    let lpn = state.db.lpns().get(id).await?.ok_or_else(|| AppError::not_found("LPN"))?;
+    let resource_links = lpn.lpn.links();
+    Ok(Json(ApiResponse::success(lpn).with_links(resource_links)))
+}
+
+/// Move the LPN and everything nested in it in one scan
+pub async fn move_lpn(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<MoveLpn>,
+) -> AppResult<Json<ApiResponse<LpnDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let lpn = state.db.lpns().move_lpn(id, payload).await?.ok_or_else(|| AppError::not_found("LPN"))?;
+    let resource_links = lpn.lpn.links();
+    Ok(Json(ApiResponse::success_with_message(lpn, "LPN moved".to_string()).with_links(resource_links)))
+}
+
+pub async fn set_lpn_content(
+    _: RequirePermission<AdjustStock>,
+    Path((id, item_id)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+    Json(payload): Json<SetLpnContent>,
+) -> AppResult<Json<ApiResponse<LpnDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.items().get_by_id(item_id).await?.is_none() {
+        return Err(AppError::not_found("item"));
+    }
+
+    let lpn = state.db.lpns().set_content(id, item_id, payload).await?.ok_or_else(|| AppError::not_found("LPN"))?;
+    let resource_links = lpn.lpn.links();
+    Ok(Json(ApiResponse::success(lpn).with_links(resource_links)))
+}
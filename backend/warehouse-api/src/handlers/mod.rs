@@ -0,0 +1,64 @@
+//! HTTP handlers grouped by resource
+
+pub mod admin;
+pub mod api_keys;
+pub mod audit;
+pub mod auth;
+pub mod billing;
+pub mod cartonization;
+pub mod catch_weight;
+pub mod clients;
+pub mod consignment;
+pub mod consumption;
+pub mod customs;
+pub mod deliveries;
+pub mod dock;
+pub mod documents;
+pub mod ecommerce;
+pub mod edi;
+pub mod environment;
+pub mod erp;
+pub mod events;
+pub mod fleet;
+pub mod gl;
+pub mod hazmat;
+pub mod i18n;
+pub mod integrations;
+pub mod integrity;
+pub mod jobs;
+pub mod kpis;
+pub mod ledger;
+pub mod loans;
+pub mod locations;
+pub mod lpns;
+pub mod movements;
+pub mod negative_stock;
+pub mod notifications;
+pub mod orders;
+pub mod packaging;
+pub mod printers;
+pub mod projects;
+pub mod purchasing;
+pub mod quality;
+pub mod recalls;
+pub mod reconciliation;
+pub mod receipts;
+pub mod repairs;
+pub mod reports;
+pub mod reservations;
+pub mod returnables;
+pub mod returns;
+pub mod rfid;
+pub mod shifts;
+pub mod stations;
+pub mod stock;
+pub mod stock_outs;
+pub mod sync;
+pub mod tasks;
+pub mod templates;
+pub mod tracking;
+pub mod transfers;
+pub mod users;
+pub mod views;
+pub mod vmi;
+pub mod waves;
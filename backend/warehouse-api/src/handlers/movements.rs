@@ -0,0 +1,27 @@
+use axum::{
+    extract::{OriginalUri, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Json, Response},
+};
+
+use warehouse_core::{paging, AppResult, AppState};
+use warehouse_models::*;
+
+use crate::content::{self, ResponseFormat};
+use crate::{jsonapi, links};
+
+pub async fn list_movements(
+    Query(filter): Query<MovementFilter>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    paging::check_keyset(&state.config.pagination, "movements", &keyset)?;
+    let result = state.db.movements().history(filter, keyset).await?;
+
+    Ok(match content::negotiate(&headers) {
+        ResponseFormat::JsonApi => jsonapi::keyset_collection(&result, &uri),
+        _ => Json(ApiResponse::success(links::keyset(result, &uri))).into_response(),
+    })
+}
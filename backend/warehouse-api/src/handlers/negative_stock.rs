@@ -0,0 +1,72 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{ManageWarehouses, RequirePermission};
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_db::Database;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+pub async fn list_policies(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<NegativeStockPolicy>>>> {
+    let db = state.db.for_warehouse(warehouse_id);
+    ensure_warehouse(&db, warehouse_id).await?;
+
+    let policies = db.negative_stock().list(warehouse_id).await?;
+    Ok(Json(ApiResponse::success(policies)))
+}
+
+/// Policy for every item in the warehouse without one of its own
+pub async fn set_warehouse_policy(
+    _: RequirePermission<ManageWarehouses>,
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<SetNegativeStockPolicy>,
+) -> AppResult<Json<ApiResponse<NegativeStockPolicy>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let db = state.db.for_warehouse(warehouse_id);
+    ensure_warehouse(&db, warehouse_id).await?;
+
+    let policy = db.negative_stock().set(warehouse_id, None, &payload.policy).await?;
+    Ok(Json(ApiResponse::success_with_message(policy, "Negative stock policy updated".to_string())))
+}
+
+pub async fn set_item_policy(
+    _: RequirePermission<ManageWarehouses>,
+    Path((warehouse_id, item_id)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+    Json(payload): Json<SetNegativeStockPolicy>,
+) -> AppResult<Json<ApiResponse<NegativeStockPolicy>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let db = state.db.for_warehouse(warehouse_id);
+    ensure_warehouse(&db, warehouse_id).await?;
+    if db.items().get_by_id(item_id).await?.is_none() {
+        return Err(AppError::not_found("item"));
+    }
+
+    let policy = db.negative_stock().set(warehouse_id, Some(item_id), &payload.policy).await?;
+    Ok(Json(ApiResponse::success_with_message(policy, "Negative stock policy updated".to_string())))
+}
+
+pub async fn clear_item_policy(
+    _: RequirePermission<ManageWarehouses>,
+    Path((warehouse_id, item_id)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let db = state.db.for_warehouse(warehouse_id);
+    if !db.negative_stock().clear(warehouse_id, item_id).await? {
+        return Err(AppError::not_found("negative stock policy"));
+    }
+    Ok(Json(ApiResponse::success_with_message((), "Negative stock policy removed".to_string())))
+}
+
+async fn ensure_warehouse(db: &Database, warehouse_id: i32) -> AppResult<()> {
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    Ok(())
+}
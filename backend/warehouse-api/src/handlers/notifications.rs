@@ -0,0 +1,50 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::{paging, AppError, AppResult, AppState};
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_notifications(
+    Query(filter): Query<NotificationFilter>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<Notification>>>>> {
+    paging::check_keyset(&state.config.pagination, "notifications", &keyset)?;
+    let result = state.db.notifications().list(filter, keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(result, &uri))))
+}
+
+pub async fn get_notification(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Notification>>> {
+    let notification = state
+        .db
+        .notifications()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("notification"))?;
+
+    let resource_links = notification.links();
+    Ok(Json(ApiResponse::success(notification).with_links(resource_links)))
+}
+
+pub async fn acknowledge_notification(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Notification>>> {
+    let notification = state
+        .db
+        .notifications()
+        .acknowledge(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("notification"))?;
+
+    let resource_links = notification.links();
+    Ok(Json(ApiResponse::success(notification).with_links(resource_links)))
+}
@@ -0,0 +1,177 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{edi, paging, AppError, AppResult, AppState};
+use warehouse_db::ORDER_ORDER;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_orders(
+    Query(filter): Query<OrderFilter>,
+    Query(pagination): Query<PaginationQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<PaginatedResponse<Linked<OutboundOrder>>>>> {
+    paging::check_page(&state.config.pagination, &ORDER_ORDER, &pagination)?;
+    let result = state.db.orders().list(filter, pagination).await?;
+    Ok(Json(ApiResponse::success(links::paginated(result, &uri))))
+}
+
+pub async fn get_order(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<OrderDetail>>> {
+    let detail = state
+        .db
+        .orders()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("order"))?;
+
+    let resource_links = detail.order.links();
+    Ok(Json(ApiResponse::success(detail).with_links(resource_links)))
+}
+
+pub async fn create_order(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateOrder>,
+) -> AppResult<Json<ApiResponse<OrderDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.warehouses().get_by_id(payload.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    for line in &payload.lines {
+        if state.db.items().get_by_id(line.item_id).await?.is_none() {
+            return Err(AppError::not_found(&format!("item {}", line.item_id)));
+        }
+    }
+
+    let detail = state.db.orders().create(payload).await?;
+    let resource_links = detail.order.links();
+    Ok(Json(
+        ApiResponse::success_with_message(detail, "Order created successfully".to_string()).with_links(resource_links),
+    ))
+}
+
+/// Rate quotes for shipping what is left on the order
+pub async fn quote_order_rates(
+    Path(id): Path<i32>,
+    Query(filter): Query<RateFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<RateQuote>>>> {
+    let order = state
+        .db
+        .orders()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("order"))?;
+
+    let quotes = state.carriers.quote(&state.db, &order, filter).await?;
+    Ok(Json(ApiResponse::success(quotes)))
+}
+
+/// Ship-confirm, buying a carrier label when a service level is given; EDI orders also get a 945 shipping advice queued for the partner
+pub async fn ship_order(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<ShipOrder>,
+) -> AppResult<Json<ApiResponse<ShipmentDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let (order, shipment) = state
+        .carriers
+        .ship(&state.db, id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("order"))?;
+
+    if order.order.source == SOURCE_EDI {
+        edi::shipping_advice(&state.db, &state.config, &order, &shipment).await?;
+    }
+
+    let resource_links = shipment.shipment.links();
+    Ok(Json(
+        ApiResponse::success_with_message(shipment, "Order shipped".to_string()).with_links(resource_links),
+    ))
+}
+
+/// The supplier accepted a dropship order routed to them
+pub async fn confirm_dropship_order(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<SupplierConfirmation>,
+) -> AppResult<Json<ApiResponse<OrderDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let detail = state
+        .db
+        .orders()
+        .confirm_dropship(id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("order"))?;
+
+    let resource_links = detail.order.links();
+    Ok(Json(
+        ApiResponse::success_with_message(detail, "Supplier confirmation recorded".to_string())
+            .with_links(resource_links),
+    ))
+}
+
+/// The supplier shipped (part of) a dropship order; no stock moves
+pub async fn record_supplier_shipment(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<SupplierShipment>,
+) -> AppResult<Json<ApiResponse<ShipmentDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let (order, shipment) = state
+        .db
+        .orders()
+        .record_supplier_shipment(id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("order"))?;
+
+    if order.order.source == SOURCE_EDI {
+        edi::shipping_advice(&state.db, &state.config, &order, &shipment).await?;
+    }
+
+    let resource_links = shipment.shipment.links();
+    Ok(Json(
+        ApiResponse::success_with_message(shipment, "Supplier shipment recorded".to_string())
+            .with_links(resource_links),
+    ))
+}
+
+pub async fn list_order_shipments(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<Shipment>>>>> {
+    if state.db.orders().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("order"));
+    }
+
+    let shipments = state.db.orders().shipments_for_order(id).await?;
+    Ok(Json(ApiResponse::success(shipments.into_iter().map(links::linked).collect())))
+}
+
+pub async fn get_shipment(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ShipmentDetail>>> {
+    let shipment = state
+        .db
+        .orders()
+        .get_shipment(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("shipment"))?;
+
+    let resource_links = shipment.shipment.links();
+    Ok(Json(ApiResponse::success(shipment).with_links(resource_links)))
+}
@@ -0,0 +1,73 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+pub async fn get_box_materials(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<BoxMaterial>>>> {
+    if state.db.cartonization().get_box(id).await?.is_none() {
+        return Err(AppError::not_found("box size"));
+    }
+
+    let materials = state.db.packaging().materials(id).await?;
+    Ok(Json(ApiResponse::success(materials)))
+}
+
+/// Replace what packing a carton in this box size issues from stock
+pub async fn set_box_materials(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<SetBoxMaterials>,
+) -> AppResult<Json<ApiResponse<Vec<BoxMaterial>>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.cartonization().get_box(id).await?.is_none() {
+        return Err(AppError::not_found("box size"));
+    }
+
+    let materials = state.db.packaging().set_materials(id, payload).await?;
+    let message = format!("{} packaging materials set", materials.len());
+    Ok(Json(ApiResponse::success_with_message(materials, message)))
+}
+
+pub async fn list_reorder_alerts(
+    Path(warehouse_id): Path<i32>,
+    Query(filter): Query<ReorderAlertFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<ReorderAlert>>>> {
+    let db = state.db.for_warehouse(warehouse_id);
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let alerts = db.packaging().reorder_alerts(warehouse_id, filter).await?;
+    Ok(Json(ApiResponse::success(alerts)))
+}
+
+/// Set the reorder point and maximum; saved through the same upsert as `PUT .../stock/:item_id`
+pub async fn set_stock_levels(
+    _: RequirePermission<AdjustStock>,
+    Path((warehouse_id, item_id)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+    Json(payload): Json<SetStockLevels>,
+) -> AppResult<Json<ApiResponse<StockInventory>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let db = state.db.for_warehouse(warehouse_id);
+
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if db.items().get_by_id(item_id).await?.is_none() {
+        return Err(AppError::not_found("item"));
+    }
+
+    let stock = db.stock_rows().upsert(warehouse_id, item_id, payload.into()).await?;
+    Ok(Json(ApiResponse::success_with_message(stock, "Stock levels updated".to_string())))
+}
@@ -0,0 +1,80 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{ManageWarehouses, RequirePermission};
+use warehouse_core::{printing, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_printers(
+    Path(warehouse_id): Path<i32>,
+    Query(filter): Query<PrinterFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<Printer>>>>> {
+    if state.db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let printers = state.db.printers().list(warehouse_id, &filter).await?;
+    Ok(Json(ApiResponse::success(printers.into_iter().map(links::linked).collect())))
+}
+
+pub async fn get_printer(Path(id): Path<i32>, State(state): State<AppState>) -> AppResult<Json<ApiResponse<Printer>>> {
+    let printer = state.db.printers().get(id).await?.ok_or_else(|| AppError::not_found("printer"))?;
+    let resource_links = printer.links();
+    Ok(Json(ApiResponse::success(printer).with_links(resource_links)))
+}
+
+pub async fn create_printer(
+    _: RequirePermission<ManageWarehouses>,
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreatePrinter>,
+) -> AppResult<Json<ApiResponse<Printer>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if state.db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if state.db.printers().code_exists(warehouse_id, &payload.printer_code).await? {
+        return Err(AppError::already_exists("printer code"));
+    }
+
+    let printer = state.db.printers().create(warehouse_id, payload).await?;
+    let resource_links = printer.links();
+    let message = format!("Printer {} registered", printer.printer_code);
+    Ok(Json(ApiResponse::success_with_message(printer, message).with_links(resource_links)))
+}
+
+pub async fn update_printer(
+    _: RequirePermission<ManageWarehouses>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdatePrinter>,
+) -> AppResult<Json<ApiResponse<Printer>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let printer = state.db.printers().update(id, payload).await?.ok_or_else(|| AppError::not_found("printer"))?;
+    let resource_links = printer.links();
+    Ok(Json(ApiResponse::success_with_message(printer, "Printer updated".to_string()).with_links(resource_links)))
+}
+
+/// Print a label or document from a mobile device or station; see `printing::dispatch`
+pub async fn print(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<PrintRequest>,
+) -> AppResult<Json<ApiResponse<PrintDispatch>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if state.db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let dispatched = printing::dispatch(&state.db, warehouse_id, payload).await?;
+    let resource_links = dispatched.printer.links();
+    let message = format!("Sent to {}", dispatched.printer.printer_code);
+    Ok(Json(ApiResponse::success_with_message(dispatched, message).with_links(resource_links)))
+}
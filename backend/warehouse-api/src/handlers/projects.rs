@@ -0,0 +1,156 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::chrono::Utc;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_projects(
+    Query(filter): Query<ProjectFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<Project>>>>> {
+    if let Some(status) = filter.status.as_deref().filter(|status| !PROJECT_STATUSES.contains(status)) {
+        return Err(AppError::validation(format!(
+            "unknown project status {}; status must be one of: {}",
+            status,
+            PROJECT_STATUSES.join(", ")
+        )));
+    }
+
+    let projects = state.db.projects().list(&filter).await?;
+    Ok(Json(ApiResponse::success(projects.into_iter().map(links::linked).collect())))
+}
+
+pub async fn get_project(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Project>>> {
+    let project = state.db.projects().get(id).await?.ok_or_else(|| AppError::not_found("project"))?;
+    let resource_links = project.links();
+    Ok(Json(ApiResponse::success(project).with_links(resource_links)))
+}
+
+pub async fn create_project(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateProject>,
+) -> AppResult<Json<ApiResponse<Project>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.projects().code_exists(&payload.project_code).await? {
+        return Err(AppError::already_exists("project code"));
+    }
+
+    let project = state.db.projects().create(payload).await?;
+    let resource_links = project.links();
+    let response = ApiResponse::success_with_message(project, "Project created successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn update_project(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateProject>,
+) -> AppResult<Json<ApiResponse<Project>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let project = state.db.projects().update(id, payload).await?.ok_or_else(|| AppError::not_found("project"))?;
+    let resource_links = project.links();
+    let response = ApiResponse::success_with_message(project, "Project updated successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+/// Issue stock to a project, checked against its material budget
+pub async fn issue_to_project(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<IssueToProject>,
+) -> AppResult<Json<ApiResponse<ProjectIssueResult>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if state.db.warehouses().get_by_id(payload.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let result = state.db.projects().issue(id, payload).await?.ok_or_else(|| AppError::not_found("project"))?;
+    let message = match &result.warning {
+        Some(warning) => format!("Issued over budget: {}", warning),
+        None => format!("Issued {} {} to the project", result.issue_value.amount, result.issue_value.currency),
+    };
+    Ok(Json(ApiResponse::success_with_message(result, message)))
+}
+
+/// Budget, consumption to date and the items issued to a project
+pub async fn get_consumption(
+    Path(id): Path<i32>,
+    Query(filter): Query<ProjectConsumptionFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ProjectConsumption>>> {
+    if let (Some(from), Some(to)) = (filter.from, filter.to) {
+        if from > to {
+            return Err(AppError::validation("from must not be after to"));
+        }
+    }
+
+    let report = state.db.projects().consumption(id, filter).await?.ok_or_else(|| AppError::not_found("project"))?;
+    Ok(Json(ApiResponse::success(report)))
+}
+
+pub async fn list_reservations(
+    Path(id): Path<i32>,
+    Query(filter): Query<ProjectReservationFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<ProjectReservation>>>>> {
+    let statuses = [RESERVATION_ACTIVE, RESERVATION_FULFILLED, RESERVATION_RELEASED, RESERVATION_EXPIRED];
+    if let Some(status) = filter.status.as_deref().filter(|status| !statuses.contains(status)) {
+        return Err(AppError::validation(format!(
+            "unknown reservation status {}; status must be one of: {}",
+            status,
+            statuses.join(", ")
+        )));
+    }
+
+    let reservations =
+        state.db.projects().reservations(id, filter).await?.ok_or_else(|| AppError::not_found("project"))?;
+    Ok(Json(ApiResponse::success(reservations.into_iter().map(links::linked).collect())))
+}
+
+/// Reserve stock for the date a project needs it, out of the warehouse's available stock
+pub async fn reserve_for_project(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<ReserveForProject>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<ProjectReservation>>>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if payload.needed_by < Utc::now().date_naive() {
+        return Err(AppError::validation("needed_by must not be in the past"));
+    }
+    if payload.expires_on.is_some_and(|expires_on| expires_on < payload.needed_by) {
+        return Err(AppError::validation("expires_on must not be before needed_by"));
+    }
+    if state.db.warehouses().get_by_id(payload.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let reservations = state.db.projects().reserve(id, payload).await?.ok_or_else(|| AppError::not_found("project"))?;
+    let message = format!("Reserved {} lines for the project", reservations.len());
+    Ok(Json(ApiResponse::success_with_message(reservations.into_iter().map(links::linked).collect(), message)))
+}
+
+/// Give what an active reservation still holds back to the warehouse's available stock
+pub async fn release_reservation(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ProjectReservation>>> {
+    let reservation = state.db.projects().release(id).await?.ok_or_else(|| AppError::not_found("reservation"))?;
+    let resource_links = reservation.links();
+    let response = ApiResponse::success_with_message(reservation, "Reservation released".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
@@ -0,0 +1,158 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::{paging, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_item_suppliers(
+    Path(item_id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<ItemSupplier>>>> {
+    if state.db.items().get_by_id(item_id).await?.is_none() {
+        return Err(AppError::not_found("item"));
+    }
+
+    let suppliers = state.db.purchasing().suppliers(item_id).await?;
+    Ok(Json(ApiResponse::success(suppliers)))
+}
+
+pub async fn set_item_supplier(
+    Path((item_id, supplier_code)): Path<(i32, String)>,
+    State(state): State<AppState>,
+    Json(payload): Json<SetItemSupplier>,
+) -> AppResult<Json<ApiResponse<ItemSupplier>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if supplier_code.is_empty() || supplier_code.len() > 30 {
+        return Err(AppError::validation("supplier code must be 1 to 30 characters"));
+    }
+    if state.db.items().get_by_id(item_id).await?.is_none() {
+        return Err(AppError::not_found("item"));
+    }
+
+    let supplier = state.db.purchasing().set_supplier(item_id, &supplier_code, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(supplier, "Item supplier saved".to_string())))
+}
+
+pub async fn list_requisitions(
+    Path(warehouse_id): Path<i32>,
+    Query(filter): Query<RequisitionFilter>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<PurchaseRequisition>>>>> {
+    paging::check_keyset(&state.config.pagination, "warehouses/requisitions", &keyset)?;
+    let db = state.db.for_warehouse(warehouse_id);
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let result = db.purchasing().list(warehouse_id, filter, keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(result, &uri))))
+}
+
+/// Draft requisitions for the warehouse's current reorder alerts
+pub async fn generate_requisitions(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<GenerateRequisitions>,
+) -> AppResult<Json<ApiResponse<RequisitionRun>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let db = state.db.for_warehouse(warehouse_id);
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let run = db.purchasing().generate(warehouse_id, payload).await?;
+    let message = format!("{} requisitions drafted", run.requisitions.len());
+    Ok(Json(ApiResponse::success_with_message(run, message)))
+}
+
+pub async fn get_requisition(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<RequisitionDetail>>> {
+    let detail = state.db.purchasing().get(id).await?.ok_or_else(|| AppError::not_found("requisition"))?;
+    let resource_links = detail.requisition.links();
+    Ok(Json(ApiResponse::success(detail).with_links(resource_links)))
+}
+
+pub async fn set_requisition_line(
+    Path((id, item_id)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+    Json(payload): Json<SetRequisitionLine>,
+) -> AppResult<Json<ApiResponse<RequisitionDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let detail =
+        state.db.purchasing().set_line(id, item_id, payload).await?.ok_or_else(|| AppError::not_found("requisition"))?;
+    let resource_links = detail.requisition.links();
+    Ok(Json(ApiResponse::success(detail).with_links(resource_links)))
+}
+
+pub async fn approve_requisition(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<PurchaseRequisition>>> {
+    let requisition = state.db.purchasing().approve(id).await?.ok_or_else(|| AppError::not_found("requisition"))?;
+    let resource_links = requisition.links();
+    Ok(Json(
+        ApiResponse::success_with_message(requisition, "Requisition approved".to_string()).with_links(resource_links),
+    ))
+}
+
+pub async fn reject_requisition(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<RejectRequisition>,
+) -> AppResult<Json<ApiResponse<PurchaseRequisition>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let requisition =
+        state.db.purchasing().reject(id, payload).await?.ok_or_else(|| AppError::not_found("requisition"))?;
+    let resource_links = requisition.links();
+    Ok(Json(
+        ApiResponse::success_with_message(requisition, "Requisition rejected".to_string()).with_links(resource_links),
+    ))
+}
+
+/// Raise the purchase order for an approved requisition
+pub async fn convert_requisition(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<PurchaseOrderDetail>>> {
+    let order = state.db.purchasing().convert(id).await?.ok_or_else(|| AppError::not_found("requisition"))?;
+    let resource_links = order.order.links();
+    let message = format!("Purchase order {} raised", order.order.po_number);
+    Ok(Json(ApiResponse::success_with_message(order, message).with_links(resource_links)))
+}
+
+pub async fn list_purchase_orders(
+    Path(warehouse_id): Path<i32>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<PurchaseOrder>>>>> {
+    paging::check_keyset(&state.config.pagination, "warehouses/purchase-orders", &keyset)?;
+    let db = state.db.for_warehouse(warehouse_id);
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let result = db.purchasing().purchase_orders(warehouse_id, keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(result, &uri))))
+}
+
+pub async fn get_purchase_order(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<PurchaseOrderDetail>>> {
+    let order =
+        state.db.purchasing().get_purchase_order(id).await?.ok_or_else(|| AppError::not_found("purchase order"))?;
+    let resource_links = order.order.links();
+    Ok(Json(ApiResponse::success(order).with_links(resource_links)))
+}
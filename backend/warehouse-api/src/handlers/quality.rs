@@ -0,0 +1,85 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{paging, AppError, AppResult, AppState};
+use warehouse_db::INSPECTION_ORDER;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_inspections(
+    Query(filter): Query<InspectionFilter>,
+    Query(pagination): Query<PaginationQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<PaginatedResponse<Linked<QualityInspection>>>>> {
+    paging::check_page(&state.config.pagination, &INSPECTION_ORDER, &pagination)?;
+    let result = state.db.quality().list(filter, pagination).await?;
+    Ok(Json(ApiResponse::success(links::paginated(result, &uri))))
+}
+
+pub async fn get_inspection(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<QualityInspection>>> {
+    let inspection = state
+        .db
+        .quality()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("inspection"))?;
+
+    let resource_links = inspection.links();
+    Ok(Json(ApiResponse::success(inspection).with_links(resource_links)))
+}
+
+pub async fn create_inspection(
+    _: RequirePermission<AdjustStock>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateInspection>,
+) -> AppResult<Json<ApiResponse<QualityInspection>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.warehouses().get_by_id(payload.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if state.db.items().get_by_id(payload.item_id).await?.is_none() {
+        return Err(AppError::not_found("item"));
+    }
+
+    let inspection = state.db.quality().create(payload).await?;
+    let resource_links = inspection.links();
+    Ok(Json(
+        ApiResponse::success_with_message(inspection, "Goods received into quarantine".to_string())
+            .with_links(resource_links),
+    ))
+}
+
+pub async fn record_inspection(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<RecordInspection>,
+) -> AppResult<Json<ApiResponse<QualityInspection>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let inspection = state
+        .db
+        .quality()
+        .record(id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("inspection"))?;
+
+    let message = match inspection.status.as_str() {
+        INSPECTION_PASSED => "Inspection passed; stock released",
+        _ => "Inspection failed; stock rejected",
+    };
+    let resource_links = inspection.links();
+    Ok(Json(
+        ApiResponse::success_with_message(inspection, message.to_string()).with_links(resource_links),
+    ))
+}
@@ -0,0 +1,52 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_recalls(
+    Query(filter): Query<RecallFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<Recall>>>>> {
+    let recalls = state.db.recalls().list(filter).await?;
+    Ok(Json(ApiResponse::success(recalls.into_iter().map(links::linked).collect())))
+}
+
+/// Open a recall, holding affected stock, and return the recall report
+pub async fn create_recall(
+    _: RequirePermission<AdjustStock>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateRecall>,
+) -> AppResult<Json<ApiResponse<RecallReport>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.items().get_by_id(payload.item_id).await?.is_none() {
+        return Err(AppError::not_found("item"));
+    }
+
+    let report = state.db.recalls().create(payload).await?;
+    let resource_links = report.recall.links();
+    let message = format!("Recall {} opened", report.recall.recall_number);
+    Ok(Json(ApiResponse::success_with_message(report, message).with_links(resource_links)))
+}
+
+pub async fn get_recall(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<RecallReport>>> {
+    let report = state.db.recalls().get(id).await?.ok_or_else(|| AppError::not_found("recall"))?;
+    let resource_links = report.recall.links();
+    Ok(Json(ApiResponse::success(report).with_links(resource_links)))
+}
+
+pub async fn close_recall(Path(id): Path<i32>, State(state): State<AppState>) -> AppResult<Json<ApiResponse<Recall>>> {
+    let recall = state.db.recalls().close(id).await?.ok_or_else(|| AppError::not_found("recall"))?;
+    let resource_links = recall.links();
+    Ok(Json(ApiResponse::success_with_message(recall, "Recall closed".to_string()).with_links(resource_links)))
+}
@@ -0,0 +1,111 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{paging, AppError, AppResult, AppState};
+use warehouse_db::RECEIPT_ORDER;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_receipts(
+    Query(filter): Query<ReceiptFilter>,
+    Query(pagination): Query<PaginationQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<PaginatedResponse<Linked<InboundReceipt>>>>> {
+    paging::check_page(&state.config.pagination, &RECEIPT_ORDER, &pagination)?;
+    let result = state.db.receipts().list(filter, pagination).await?;
+    Ok(Json(ApiResponse::success(links::paginated(result, &uri))))
+}
+
+pub async fn get_receipt(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ReceiptDetail>>> {
+    let detail = state
+        .db
+        .receipts()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("receipt"))?;
+
+    let resource_links = detail.receipt.links();
+    Ok(Json(ApiResponse::success(detail).with_links(resource_links)))
+}
+
+pub async fn create_receipt(
+    _: RequirePermission<AdjustStock>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateReceipt>,
+) -> AppResult<Json<ApiResponse<ReceiptDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.warehouses().get_by_id(payload.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if state.db.receipts().asn_exists(payload.warehouse_id, &payload.asn_number).await? {
+        return Err(AppError::already_exists("receipt with this ASN number"));
+    }
+    if let Some(consignor_id) = payload.consignor_id {
+        if state.db.consignment().get_by_id(consignor_id).await?.is_none() {
+            return Err(AppError::not_found("consignor"));
+        }
+    }
+    for line in &payload.lines {
+        if state.db.items().get_by_id(line.item_id).await?.is_none() {
+            return Err(AppError::not_found(&format!("item {}", line.item_id)));
+        }
+    }
+
+    let detail = state.db.receipts().create(payload).await?;
+    let resource_links = detail.receipt.links();
+    Ok(Json(
+        ApiResponse::success_with_message(detail, "Receipt created successfully".to_string())
+            .with_links(resource_links),
+    ))
+}
+
+pub async fn receive_goods(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<ReceiveGoods>,
+) -> AppResult<Json<ApiResponse<ReceiptDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let detail = state
+        .db
+        .receipts()
+        .receive(id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("receipt"))?;
+
+    let resource_links = detail.receipt.links();
+    Ok(Json(
+        ApiResponse::success_with_message(detail, "Goods received".to_string()).with_links(resource_links),
+    ))
+}
+
+/// Receive the ASN lines labelled with one LPN in full, building the LPN as the goods go away
+pub async fn receive_lpn(
+    _: RequirePermission<AdjustStock>,
+    Path((id, lpn_number)): Path<(i32, String)>,
+    State(state): State<AppState>,
+    Json(payload): Json<ReceiveLpn>,
+) -> AppResult<Json<ApiResponse<LpnDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let (_, lpn) = state
+        .db
+        .receipts()
+        .receive_lpn(id, &lpn_number, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("receipt"))?;
+
+    let resource_links = lpn.lpn.links();
+    Ok(Json(ApiResponse::success_with_message(lpn, "LPN received".to_string()).with_links(resource_links)))
+}
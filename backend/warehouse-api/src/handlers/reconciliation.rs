@@ -0,0 +1,75 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Json, Response},
+};
+use futures::stream;
+
+use warehouse_core::{paging, reconciliation, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::content::{self, ResponseFormat};
+use crate::links::{self, ResourceLinks};
+
+/// Reconcile an ERP stock extract against WMS stock. JSON bodies are an `ErpExtract`;
+/// `text/csv` bodies take `as_of` and `source` from the query string.
+pub async fn reconcile(
+    Query(query): Query<ErpExtractQuery>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> AppResult<Json<ApiResponse<ErpReconciliationDetail>>> {
+    let is_csv = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/csv"));
+    let extract = if is_csv {
+        reconciliation::parse_csv(query, &body)?
+    } else {
+        serde_json::from_str::<ErpExtract>(&body).map_err(AppError::validation)?
+    };
+    extract.validate().map_err(AppError::validation)?;
+
+    let detail = state.db.reconciliation().reconcile(extract).await?;
+    let resource_links = detail.reconciliation.links();
+    let message = format!(
+        "{} lines reconciled, {} matched, {} with variances",
+        detail.reconciliation.lines, detail.reconciliation.matched, detail.reconciliation.variances
+    );
+    Ok(Json(ApiResponse::success_with_message(detail, message).with_links(resource_links)))
+}
+
+pub async fn list_reconciliations(
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<ErpReconciliation>>>>> {
+    paging::check_keyset(&state.config.pagination, "reconciliation/erp", &keyset)?;
+    let page = state.db.reconciliation().list(keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(page, &uri))))
+}
+
+/// The variance report; `Accept: text/csv` downloads the lines for the three-way match
+pub async fn get_reconciliation(
+    Path(id): Path<i32>,
+    Query(filter): Query<ErpReconciliationFilter>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let reconciliation =
+        state.db.reconciliation().get_by_id(id).await?.ok_or_else(|| AppError::not_found("reconciliation"))?;
+    let lines = state.db.reconciliation().lines(id, filter).await?;
+
+    Ok(match content::negotiate(&headers) {
+        ResponseFormat::Csv => {
+            let filename = format!("erp-reconciliation-{}-{}.csv", id, reconciliation.as_of);
+            content::csv_response(&filename, stream::iter(lines.into_iter().map(Ok)))
+        }
+        _ => {
+            let resource_links = reconciliation.links();
+            let detail = ErpReconciliationDetail { reconciliation, lines };
+            Json(ApiResponse::success(detail).with_links(resource_links)).into_response()
+        }
+    })
+}
@@ -0,0 +1,96 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_repairs(
+    Query(filter): Query<RepairOrderFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<RepairOrder>>>>> {
+    if let Some(status) = filter.status.as_deref().filter(|status| !REPAIR_STATUSES.contains(status)) {
+        return Err(AppError::validation(format!(
+            "unknown repair status {}; status must be one of: {}",
+            status,
+            REPAIR_STATUSES.join(", ")
+        )));
+    }
+
+    let repairs = state.db.repairs().list(&filter).await?;
+    Ok(Json(ApiResponse::success(repairs.into_iter().map(links::linked).collect())))
+}
+
+pub async fn get_repair(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<RepairOrder>>> {
+    let repair = state.db.repairs().get(id).await?.ok_or_else(|| AppError::not_found("repair order"))?;
+    let resource_links = repair.links();
+    Ok(Json(ApiResponse::success(repair).with_links(resource_links)))
+}
+
+/// Take a damaged tool out of available stock for repair
+pub async fn create_repair(
+    _: RequirePermission<AdjustStock>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateRepairOrder>,
+) -> AppResult<Json<ApiResponse<RepairOrder>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if state.db.warehouses().get_by_id(payload.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if state.db.items().get_by_id(payload.item_id).await?.is_none() {
+        return Err(AppError::not_found("item"));
+    }
+
+    let repair = state.db.repairs().create(payload).await?;
+    let resource_links = repair.links();
+    let message = format!("Repair order {} opened", repair.repair_number);
+    Ok(Json(ApiResponse::success_with_message(repair, message).with_links(resource_links)))
+}
+
+/// Hand the tool to the repair vendor or workshop
+pub async fn send_repair(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<SendForRepair>,
+) -> AppResult<Json<ApiResponse<RepairOrder>>> {
+    let repair = state.db.repairs().send(id, payload).await?.ok_or_else(|| AppError::not_found("repair order"))?;
+    let resource_links = repair.links();
+    let message = format!("{} sent for repair", repair.repair_number);
+    Ok(Json(ApiResponse::success_with_message(repair, message).with_links(resource_links)))
+}
+
+pub async fn record_repair_cost(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<RecordRepairCost>,
+) -> AppResult<Json<ApiResponse<RepairOrder>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let repair =
+        state.db.repairs().record_cost(id, payload).await?.ok_or_else(|| AppError::not_found("repair order"))?;
+    let resource_links = repair.links();
+    Ok(Json(ApiResponse::success_with_message(repair, "Repair cost recorded".to_string()).with_links(resource_links)))
+}
+
+/// Put the repaired tool back into stock or scrap it
+pub async fn close_repair(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CloseRepairOrder>,
+) -> AppResult<Json<ApiResponse<RepairOrder>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let repair = state.db.repairs().close(id, payload).await?.ok_or_else(|| AppError::not_found("repair order"))?;
+    let resource_links = repair.links();
+    let message = format!("{} closed: tool {}", repair.repair_number, repair.status.to_lowercase());
+    Ok(Json(ApiResponse::success_with_message(repair, message).with_links(resource_links)))
+}
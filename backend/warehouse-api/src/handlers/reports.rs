@@ -0,0 +1,271 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Json, Response},
+};
+use futures::stream;
+
+use warehouse_core::{i18n, paging, reports, AppError, AppResult, AppState};
+use warehouse_models::chrono::{Days, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::content::{self, ResponseFormat};
+use crate::links::{self, ResourceLinks};
+
+/// Picks and receipts per hour and task accuracy per operator and shift
+pub async fn labor_report(
+    Query(filter): Query<LaborReportFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<LaborProductivity>>>> {
+    if let (Some(from), Some(to)) = (filter.from, filter.to) {
+        if from >= to {
+            return Err(AppError::validation("from must be before to"));
+        }
+    }
+
+    let rows = state.db.labor().report(filter).await?;
+    Ok(Json(ApiResponse::success(rows)))
+}
+
+/// Consumables drawn per project, crew and period, with usage well above other projects'
+/// flagged
+pub async fn consumable_usage_report(
+    Query(filter): Query<ConsumableUsageFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<ConsumableUsage>>>> {
+    let periods = [USAGE_PERIOD_WEEK, USAGE_PERIOD_MONTH];
+    if let Some(period) = filter.period.as_deref().filter(|period| !periods.contains(period)) {
+        return Err(AppError::validation(format!(
+            "unknown period {}; period must be one of: {}",
+            period,
+            periods.join(", ")
+        )));
+    }
+    let to = filter.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = filter.from.unwrap_or(to - Days::new(90));
+    if from > to {
+        return Err(AppError::validation("from must not be after to"));
+    }
+    let threshold = filter.threshold.unwrap_or(Decimal::TWO);
+    if threshold <= Decimal::ONE {
+        return Err(AppError::validation("threshold must be greater than 1"));
+    }
+
+    let rows = state.db.projects().consumable_usage(&filter, from, to, threshold).await?;
+    Ok(Json(ApiResponse::success(rows)))
+}
+
+/// How often each item's demand went unmet and how much of it was lost
+pub async fn lost_demand_report(
+    Query(filter): Query<StockOutFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<LostDemand>>>> {
+    let to = filter.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = filter.from.unwrap_or(to - Days::new(90));
+    if from > to {
+        return Err(AppError::validation("from must not be after to"));
+    }
+
+    let rows = state.db.stock_outs().lost_demand(&filter, from, to).await?;
+    Ok(Json(ApiResponse::success(rows)))
+}
+
+/// The entities and columns custom reports can use
+pub async fn report_catalog(State(state): State<AppState>) -> AppResult<Json<ApiResponse<Vec<ReportEntity>>>> {
+    Ok(Json(ApiResponse::success(state.db.reports().catalog())))
+}
+
+pub async fn list_custom_reports(
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<ReportDefinition>>>>> {
+    let reports = state.db.reports().list().await?;
+    Ok(Json(ApiResponse::success(reports.into_iter().map(links::linked).collect())))
+}
+
+pub async fn get_custom_report(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ReportDefinitionDetail>>> {
+    let detail = state.db.reports().get_by_id(id).await?.ok_or_else(|| AppError::not_found("report"))?;
+    let resource_links = detail.definition.links();
+    Ok(Json(ApiResponse::success(detail).with_links(resource_links)))
+}
+
+pub async fn create_custom_report(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateReportDefinition>,
+) -> AppResult<Json<ApiResponse<ReportDefinitionDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.reports().code_exists(&payload.report_code).await? {
+        return Err(AppError::already_exists("report code"));
+    }
+
+    let detail = state.db.reports().create(payload).await?;
+    let resource_links = detail.definition.links();
+    let response = ApiResponse::success_with_message(detail, "Report created successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn update_custom_report(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateReportDefinition>,
+) -> AppResult<Json<ApiResponse<ReportDefinitionDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let detail = state.db.reports().update(id, payload).await?.ok_or_else(|| AppError::not_found("report"))?;
+    let resource_links = detail.definition.links();
+    let response = ApiResponse::success_with_message(detail, "Report updated successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn delete_custom_report(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    if !state.db.reports().delete(id).await? {
+        return Err(AppError::not_found("report"));
+    }
+    Ok(Json(ApiResponse::success_with_message((), "Report deleted successfully".to_string())))
+}
+
+/// Run the report; `Accept: text/csv` downloads the rows under a header of the column headings
+pub async fn run_custom_report(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let detail = state.db.reports().get_by_id(id).await?.ok_or_else(|| AppError::not_found("report"))?;
+    let result = reports::run(&state.db, &detail, i18n::current()).await?;
+
+    Ok(match content::negotiate(&headers) {
+        ResponseFormat::Csv => {
+            let filename = format!("{}.csv", detail.definition.report_code.to_lowercase());
+            let header = result.headers.into_iter().map(Some).collect();
+            let rows = std::iter::once(header).chain(result.rows).map(Ok);
+            content::csv_response(&filename, stream::iter(rows))
+        }
+        _ => {
+            let message = if result.truncated {
+                format!("Showing the first {} rows", REPORT_MAX_ROWS)
+            } else {
+                format!("{} rows", result.rows.len())
+            };
+            let resource_links = detail.definition.links();
+            Json(ApiResponse::success_with_message(result, message).with_links(resource_links)).into_response()
+        }
+    })
+}
+
+/// Run the report in the background; the job holds the CSV when it is done
+pub async fn export_custom_report(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Job>>> {
+    let report = state.db.reports().get_by_id(id).await?.ok_or_else(|| AppError::not_found("report"))?;
+
+    let job = reports::export(&state.db, report).await?;
+    let resource_links = job.links();
+    let message = format!("Export started as job {}", job.job_id);
+    Ok(Json(ApiResponse::success_with_message(job, message).with_links(resource_links)))
+}
+
+pub async fn list_report_schedules(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<ReportSchedule>>>>> {
+    if state.db.reports().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("report"));
+    }
+
+    let schedules = state.db.reports().schedules(id).await?;
+    Ok(Json(ApiResponse::success(schedules.into_iter().map(links::linked).collect())))
+}
+
+/// Email the report to its recipients whenever the cron expression comes due
+pub async fn create_report_schedule(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateReportSchedule>,
+) -> AppResult<Json<ApiResponse<ReportSchedule>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.reports().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("report"));
+    }
+
+    let schedule = state.db.reports().create_schedule(id, payload).await?;
+    let resource_links = schedule.links();
+    let message = format!("Report scheduled; first delivery at {}", schedule.next_run_at.to_rfc3339());
+    Ok(Json(ApiResponse::success_with_message(schedule, message).with_links(resource_links)))
+}
+
+pub async fn get_report_schedule(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ReportSchedule>>> {
+    let schedule = state.db.reports().get_schedule(id).await?.ok_or_else(|| AppError::not_found("report schedule"))?;
+    let resource_links = schedule.links();
+    Ok(Json(ApiResponse::success(schedule).with_links(resource_links)))
+}
+
+pub async fn update_report_schedule(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateReportSchedule>,
+) -> AppResult<Json<ApiResponse<ReportSchedule>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let schedule = state
+        .db
+        .reports()
+        .update_schedule(id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("report schedule"))?;
+    let resource_links = schedule.links();
+    let response = ApiResponse::success_with_message(schedule, "Report schedule updated successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn delete_report_schedule(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    if !state.db.reports().delete_schedule(id).await? {
+        return Err(AppError::not_found("report schedule"));
+    }
+    Ok(Json(ApiResponse::success_with_message((), "Report schedule deleted successfully".to_string())))
+}
+
+/// Deliver the schedule now, outside its cron expression
+pub async fn deliver_report_schedule(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ReportScheduleRun>>> {
+    let schedule = state.db.reports().get_schedule(id).await?.ok_or_else(|| AppError::not_found("report schedule"))?;
+
+    let run = reports::deliver(&state.db, &state.config.mail, &schedule).await?;
+    let message = match &run.error {
+        None => format!("Sent {} rows to {} recipients", run.row_count, schedule.recipients.len()),
+        Some(error) => format!("Delivery failed: {}", error),
+    };
+    Ok(Json(ApiResponse::success_with_message(run, message)))
+}
+
+pub async fn list_report_deliveries(
+    Path(id): Path<i32>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<ReportScheduleRun>>>>> {
+    paging::check_keyset(&state.config.pagination, "reports/schedules/runs", &keyset)?;
+    if state.db.reports().get_schedule(id).await?.is_none() {
+        return Err(AppError::not_found("report schedule"));
+    }
+
+    let runs = state.db.reports().deliveries(id, keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(runs, &uri))))
+}
@@ -0,0 +1,69 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+/// Stock reservations, newest first. Those of sharded warehouses are listed when the
+/// filter names the warehouse.
+pub async fn list_reservations(
+    Query(filter): Query<StockReservationFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<StockReservation>>>>> {
+    let statuses = [RESERVATION_ACTIVE, RESERVATION_RELEASED];
+    if let Some(status) = filter.status.as_deref().filter(|status| !statuses.contains(status)) {
+        return Err(AppError::validation(format!(
+            "unknown reservation status {}; status must be one of: {}",
+            status,
+            statuses.join(", ")
+        )));
+    }
+
+    let db = match filter.warehouse_id {
+        Some(warehouse_id) => state.db.for_warehouse(warehouse_id),
+        None => state.db.clone(),
+    };
+    let reservations = db.reservations().reservations(&filter).await?;
+    Ok(Json(ApiResponse::success(reservations.into_iter().map(links::linked).collect())))
+}
+
+/// Hold back stock out of what a warehouse has available
+pub async fn create_reservation(
+    _: RequirePermission<AdjustStock>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateStockReservation>,
+) -> AppResult<Json<ApiResponse<StockReservation>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let db = state.db.for_warehouse(payload.warehouse_id);
+    if db.warehouses().get_by_id(payload.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let reservation = db.reservations().reserve(payload).await?;
+    let resource_links = reservation.links();
+    let response = ApiResponse::success_with_message(reservation, "Stock reserved".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+/// Release a reservation; one of a sharded warehouse is found through `warehouse_id`
+pub async fn release_reservation(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    Query(target): Query<ReleaseReservationQuery>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<StockReservation>>> {
+    let db = match target.warehouse_id {
+        Some(warehouse_id) => state.db.for_warehouse(warehouse_id),
+        None => state.db.clone(),
+    };
+    let reservation = db.reservations().release(id).await?.ok_or_else(|| AppError::not_found("stock reservation"))?;
+    let resource_links = reservation.links();
+    let response = ApiResponse::success_with_message(reservation, "Reservation released".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
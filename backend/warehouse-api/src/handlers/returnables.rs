@@ -0,0 +1,77 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::chrono::Utc;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+pub async fn list_types(State(state): State<AppState>) -> AppResult<Json<ApiResponse<Vec<ReturnableType>>>> {
+    let types = state.db.returnables().types().await?;
+    Ok(Json(ApiResponse::success(types)))
+}
+
+pub async fn create_type(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateReturnableType>,
+) -> AppResult<Json<ApiResponse<ReturnableType>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if state.db.returnables().type_code_exists(&payload.type_code).await? {
+        return Err(AppError::already_exists("returnable type code"));
+    }
+
+    let returnable = state.db.returnables().create_type(payload).await?;
+    Ok(Json(ApiResponse::success_with_message(returnable, "Returnable type created".to_string())))
+}
+
+/// Record returnable packaging that went out with a shipment
+pub async fn issue_with_shipment(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<IssueReturnables>,
+) -> AppResult<Json<ApiResponse<Vec<ReturnableMovement>>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let movements = state.db.returnables().issue(id, payload).await?.ok_or_else(|| AppError::not_found("shipment"))?;
+    let issued: i32 = movements.iter().map(|movement| movement.quantity).sum();
+    Ok(Json(ApiResponse::success_with_message(movements, format!("{} returnable(s) issued", issued))))
+}
+
+/// Record returnable packaging a customer gave back
+pub async fn receive_returnables(
+    _: RequirePermission<AdjustStock>,
+    State(state): State<AppState>,
+    Json(payload): Json<ReceiveReturnables>,
+) -> AppResult<Json<ApiResponse<Vec<ReturnableMovement>>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if state.db.warehouses().get_by_id(payload.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let movements = state.db.returnables().receive(payload).await?;
+    let returned: i32 = movements.iter().map(|movement| movement.quantity).sum();
+    Ok(Json(ApiResponse::success_with_message(movements, format!("{} returnable(s) received", returned))))
+}
+
+/// What each customer site holds
+pub async fn get_balances(
+    Query(filter): Query<ReturnableFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<ReturnableBalance>>>> {
+    let balances = state.db.returnables().balances(&filter).await?;
+    Ok(Json(ApiResponse::success(balances)))
+}
+
+/// Outstanding returnables by how long ago they went out, oldest first
+pub async fn get_aging(
+    Query(filter): Query<ReturnableFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<ReturnableAging>>>> {
+    let as_of = filter.as_of.unwrap_or_else(|| Utc::now().date_naive());
+    let aging = state.db.returnables().aging(&filter, as_of).await?;
+    Ok(Json(ApiResponse::success(aging)))
+}
@@ -0,0 +1,117 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{paging, AppError, AppResult, AppState};
+use warehouse_db::RETURN_ORDER;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_returns(
+    Query(filter): Query<ReturnFilter>,
+    Query(pagination): Query<PaginationQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<PaginatedResponse<Linked<ReturnAuthorization>>>>> {
+    paging::check_page(&state.config.pagination, &RETURN_ORDER, &pagination)?;
+    let result = state.db.returns().list(filter, pagination).await?;
+    Ok(Json(ApiResponse::success(links::paginated(result, &uri))))
+}
+
+pub async fn get_return(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ReturnDetail>>> {
+    let detail = state
+        .db
+        .returns()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("return"))?;
+
+    Ok(with_links(detail, None))
+}
+
+pub async fn create_return(
+    _: RequirePermission<AdjustStock>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateReturn>,
+) -> AppResult<Json<ApiResponse<ReturnDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.warehouses().get_by_id(payload.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    for line in &payload.lines {
+        if state.db.items().get_by_id(line.item_id).await?.is_none() {
+            return Err(AppError::not_found(&format!("item {}", line.item_id)));
+        }
+    }
+
+    let detail = state.db.returns().create(payload).await?;
+    Ok(with_links(detail, Some("Return authorized successfully")))
+}
+
+pub async fn receive_return(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<ReceiveReturn>,
+) -> AppResult<Json<ApiResponse<ReturnDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let detail = state
+        .db
+        .returns()
+        .receive(id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("return"))?;
+
+    Ok(with_links(detail, Some("Returned goods received into quarantine")))
+}
+
+pub async fn inspect_return(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<InspectReturn>,
+) -> AppResult<Json<ApiResponse<ReturnDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let detail = state
+        .db
+        .returns()
+        .inspect(id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("return"))?;
+
+    Ok(with_links(detail, Some("Inspection recorded")))
+}
+
+pub async fn cancel_return(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ReturnDetail>>> {
+    let detail = state
+        .db
+        .returns()
+        .cancel(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("return"))?;
+
+    Ok(with_links(detail, Some("Return cancelled")))
+}
+
+fn with_links(detail: ReturnDetail, message: Option<&str>) -> Json<ApiResponse<ReturnDetail>> {
+    let resource_links = detail.authorization.links();
+    let response = match message {
+        Some(message) => ApiResponse::success_with_message(detail, message.to_string()),
+        None => ApiResponse::success(detail),
+    };
+    Json(response.with_links(resource_links))
+}
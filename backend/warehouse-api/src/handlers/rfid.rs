@@ -0,0 +1,76 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_portals(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<RfidPortal>>>>> {
+    let db = state.db.for_warehouse(warehouse_id);
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let portals = db.rfid().portals(warehouse_id).await?;
+    Ok(Json(ApiResponse::success(portals.into_iter().map(links::linked).collect())))
+}
+
+pub async fn create_portal(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreatePortal>,
+) -> AppResult<Json<ApiResponse<RfidPortal>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let db = state.db.for_warehouse(warehouse_id);
+
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if db.rfid().portal_code_exists(warehouse_id, &payload.portal_code).await? {
+        return Err(AppError::already_exists("portal code"));
+    }
+
+    let portal = db.rfid().create_portal(warehouse_id, payload).await?;
+    let resource_links = portal.links();
+    let response = ApiResponse::success_with_message(portal, "Portal created successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+/// Bulk ingest of one reader batch; see `RfidRepository::ingest`
+pub async fn ingest_reads(
+    _: RequirePermission<AdjustStock>,
+    Path(portal_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<PortalReads>,
+) -> AppResult<Json<ApiResponse<RfidIngestResult>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let result = state.db.rfid().ingest(portal_id, payload).await?.ok_or_else(|| AppError::not_found("portal"))?;
+
+    let message = format!("{} of {} reads accepted, {} crossings", result.accepted, result.received, result.crossings);
+    Ok(Json(ApiResponse::success_with_message(result, message)))
+}
+
+pub async fn register_tags(
+    State(state): State<AppState>,
+    Json(payload): Json<RegisterTags>,
+) -> AppResult<Json<ApiResponse<Vec<RfidTag>>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let tags = state.db.rfid().register_tags(payload).await?;
+    let message = format!("{} tags registered", tags.len());
+    Ok(Json(ApiResponse::success_with_message(tags, message)))
+}
+
+pub async fn get_tag(Path(epc): Path<String>, State(state): State<AppState>) -> AppResult<Json<ApiResponse<RfidTag>>> {
+    let tag = state.db.rfid().get_tag(&epc).await?.ok_or_else(|| AppError::not_found("tag"))?;
+    Ok(Json(ApiResponse::success(tag)))
+}
@@ -0,0 +1,113 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_shifts(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<Shift>>>>> {
+    let db = state.db.for_warehouse(warehouse_id);
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let shifts = db.shifts().list(warehouse_id).await?;
+    Ok(Json(ApiResponse::success(shifts.into_iter().map(links::linked).collect())))
+}
+
+pub async fn create_shift(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateShift>,
+) -> AppResult<Json<ApiResponse<ShiftDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let db = state.db.for_warehouse(warehouse_id);
+
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if db.shifts().code_exists(warehouse_id, &payload.shift_code).await? {
+        return Err(AppError::already_exists("shift code"));
+    }
+
+    let detail = db.shifts().create(warehouse_id, payload).await?;
+    Ok(with_links(detail, Some("Shift created successfully")))
+}
+
+pub async fn get_shift(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ShiftDetail>>> {
+    let detail = state
+        .db
+        .shifts()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("shift"))?;
+
+    Ok(with_links(detail, None))
+}
+
+pub async fn update_shift(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateShift>,
+) -> AppResult<Json<ApiResponse<ShiftDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let detail = state
+        .db
+        .shifts()
+        .update(id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("shift"))?;
+
+    Ok(with_links(detail, Some("Shift updated successfully")))
+}
+
+pub async fn add_shift_operators(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<RosterOperators>,
+) -> AppResult<Json<ApiResponse<ShiftDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let detail = state
+        .db
+        .shifts()
+        .add_operators(id, &payload.operator_ids)
+        .await?
+        .ok_or_else(|| AppError::not_found("shift"))?;
+
+    Ok(with_links(detail, Some("Roster updated")))
+}
+
+pub async fn remove_shift_operator(
+    Path((id, operator_id)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ShiftDetail>>> {
+    let detail = state
+        .db
+        .shifts()
+        .remove_operator(id, operator_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("shift"))?;
+
+    Ok(with_links(detail, Some("Roster updated")))
+}
+
+fn with_links(detail: ShiftDetail, message: Option<&str>) -> Json<ApiResponse<ShiftDetail>> {
+    let resource_links = detail.shift.links();
+    let response = match message {
+        Some(message) => ApiResponse::success_with_message(detail, message.to_string()),
+        None => ApiResponse::success(detail),
+    };
+    Json(response.with_links(resource_links))
+}
@@ -0,0 +1,74 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::{paging, scales, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_stations(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<MeasuringStation>>>>> {
+    let db = state.db.for_warehouse(warehouse_id);
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let stations = db.stations().stations(warehouse_id).await?;
+    Ok(Json(ApiResponse::success(stations.into_iter().map(links::linked).collect())))
+}
+
+pub async fn create_station(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateStation>,
+) -> AppResult<Json<ApiResponse<MeasuringStation>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let db = state.db.for_warehouse(warehouse_id);
+
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if db.stations().station_code_exists(warehouse_id, &payload.station_code).await? {
+        return Err(AppError::already_exists("station code"));
+    }
+
+    let station = db.stations().create_station(warehouse_id, payload).await?;
+    let resource_links = station.links();
+    let response = ApiResponse::success_with_message(station, "Station created successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+/// Device push from a station's scale and dimensioner; see `scales::normalise`
+pub async fn record_capture(
+    Path(station_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CaptureMeasurement>,
+) -> AppResult<Json<ApiResponse<StationCapture>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let capture =
+        scales::capture(&state.db, station_id, payload).await?.ok_or_else(|| AppError::not_found("station"))?;
+    let resource_links = capture.links();
+    let response = ApiResponse::success_with_message(capture, "Measurement captured".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn list_captures(
+    Path(station_id): Path<i32>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<StationCapture>>>>> {
+    paging::check_keyset(&state.config.pagination, "stations/captures", &keyset)?;
+    if state.db.stations().get_station(station_id).await?.is_none() {
+        return Err(AppError::not_found("station"));
+    }
+
+    let captures = state.db.stations().captures(station_id, keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(captures, &uri))))
+}
@@ -0,0 +1,174 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::Utc;
+use futures::stream;
+
+use warehouse_core::auth::{AdjustStock, AdministerSystem, RequirePermission};
+use warehouse_core::{atp, paging, AppError, AppResult, AppState};
+use warehouse_db::{ITEM_STOCK_ORDER, WAREHOUSE_STOCK_ORDER};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::content::{self, ResponseFormat};
+use crate::links;
+
+/// Stock on hand by item, warehouse and owner, optionally as of a past day for audits.
+/// Today's stock comes from the read model; past days are rebuilt from the ledger. The
+/// balances come as CSV lines when asked for `text/csv`.
+pub async fn get_stock(
+    Query(filter): Query<StockFilter>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let today = Utc::now().date_naive();
+    let snapshot = match filter.as_of {
+        Some(as_of) if as_of > today => return Err(AppError::validation("as_of must not be in the future")),
+        Some(as_of) if as_of < today => state.db.stock().snapshot(filter).await?,
+        // The in-memory store has no read models; it sums its movements for today as well
+        _ if state.db.is_mock() => state.db.stock().snapshot(filter).await?,
+        _ => StockSnapshot { as_of: today, balances: state.db.read_models().stock(filter).await? },
+    };
+
+    Ok(match content::negotiate(&headers) {
+        ResponseFormat::Csv => {
+            let filename = format!("stock-{}.csv", snapshot.as_of);
+            content::csv_response(&filename, stream::iter(snapshot.balances.into_iter().map(Ok)))
+        }
+        _ => Json(ApiResponse::success(snapshot)).into_response(),
+    })
+}
+
+/// How far the read models lag behind the source tables
+pub async fn read_model_status(State(state): State<AppState>) -> AppResult<Json<ApiResponse<ReadModelStatus>>> {
+    let status = state.db.read_models().status().await?;
+    Ok(Json(ApiResponse::success(status)))
+}
+
+/// Audit trail of stock balance changes, newest first
+pub async fn list_events(
+    Query(filter): Query<StockEventFilter>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<StockEvent>>>> {
+    paging::check_keyset(&state.config.pagination, "stock/events", &keyset)?;
+    let page = state.db.stock_events().history(filter, keyset).await?;
+    let navigation = links::keyset_links(page.next_after_id, &uri);
+    Ok(Json(ApiResponse::success(page.with_links(navigation))))
+}
+
+/// Refold the stock balances projection from the event streams
+pub async fn rebuild_projection(
+    _: RequirePermission<AdministerSystem>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<StockProjection>>> {
+    let rebuilt = state.db.stock_events().rebuild().await?;
+    let message = format!("Rebuilt {} stock balances from their events", rebuilt.streams);
+    Ok(Json(ApiResponse::success_with_message(rebuilt, message)))
+}
+
+/// Every owner's stock rows in a warehouse, with their planning levels and costs
+pub async fn list_warehouse_stock(
+    Path(id): Path<i32>,
+    Query(pagination): Query<PaginationQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<PaginatedResponse<Linked<StockInventory>>>>> {
+    paging::check_page(&state.config.pagination, &WAREHOUSE_STOCK_ORDER, &pagination)?;
+    let db = state.db.for_warehouse(id);
+    if db.warehouses().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let stock = db.stock_rows().by_warehouse(id, pagination).await?;
+    Ok(Json(ApiResponse::success(links::paginated(stock, &uri))))
+}
+
+/// Every owner's stock rows of an item, warehouse by warehouse
+pub async fn list_item_stock(
+    Path(id): Path<i32>,
+    Query(pagination): Query<PaginationQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<PaginatedResponse<Linked<StockInventory>>>>> {
+    paging::check_page(&state.config.pagination, &ITEM_STOCK_ORDER, &pagination)?;
+    if state.db.items().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("item"));
+    }
+
+    let stock = state.db.stock_rows().by_item(id, pagination).await?;
+    Ok(Json(ApiResponse::success(links::paginated(stock, &uri))))
+}
+
+/// How much of the item the warehouse can promise by a date, and when it can promise the
+/// quantity asked for
+pub async fn get_item_availability(
+    Path(id): Path<i32>,
+    Query(query): Query<AvailabilityQuery>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<ItemAvailability>>> {
+    let today = Utc::now().date_naive();
+    let date = query.date.unwrap_or(today);
+    if date < today {
+        return Err(AppError::validation("date must not be in the past"));
+    }
+    if query.qty.is_some_and(|qty| qty <= Decimal::ZERO) {
+        return Err(AppError::validation("qty must be positive"));
+    }
+    if state.db.items().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("item"));
+    }
+    if state.db.warehouses().get_by_id(query.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let (position, inbound) = state.db.stock_rows().supply(query.warehouse_id, id).await?;
+    let availability = atp::promise(id, query.warehouse_id, today, date, query.qty, position, inbound);
+    Ok(Json(ApiResponse::success(availability)))
+}
+
+/// Create an item's stock row in a warehouse or set its levels
+pub async fn upsert_stock(
+    _: RequirePermission<AdjustStock>,
+    Path((warehouse_id, item_id)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpsertStock>,
+) -> AppResult<Json<ApiResponse<StockInventory>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let db = state.db.for_warehouse(warehouse_id);
+
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if db.items().get_by_id(item_id).await?.is_none() {
+        return Err(AppError::not_found("item"));
+    }
+
+    let stock = db.stock_rows().upsert(warehouse_id, item_id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(stock, "Stock saved".to_string())))
+}
+
+/// Issue our own available stock of an item out of a warehouse
+pub async fn issue_stock(
+    _: RequirePermission<AdjustStock>,
+    Path((warehouse_id, item_id)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+    Json(payload): Json<IssueStock>,
+) -> AppResult<Json<ApiResponse<StockMovement>>> {
+    payload.validate().map_err(AppError::validation)?;
+    let db = state.db.for_warehouse(warehouse_id);
+
+    if db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if db.items().get_by_id(item_id).await?.is_none() {
+        return Err(AppError::not_found("item"));
+    }
+
+    let movement = db.stock_rows().issue(warehouse_id, item_id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(movement, "Stock issued".to_string())))
+}
@@ -0,0 +1,22 @@
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::*;
+
+/// Demand that found no free stock, newest first
+pub async fn list_stock_outs(
+    Query(filter): Query<StockOutFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<StockOutIncident>>>> {
+    if let (Some(from), Some(to)) = (filter.from, filter.to) {
+        if from > to {
+            return Err(AppError::validation("from must not be after to"));
+        }
+    }
+
+    let incidents = state.db.stock_outs().incidents(&filter).await?;
+    Ok(Json(ApiResponse::success(incidents)))
+}
@@ -0,0 +1,36 @@
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{sync, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+/// Delta of tasks and locations for a handheld; without `since` a full snapshot
+pub async fn get_sync_delta(
+    Query(query): Query<SyncQuery>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<SyncDelta>>> {
+    if state.db.warehouses().get_by_id(query.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let delta = state.db.sync().delta(query.warehouse_id, query.since).await?;
+    Ok(Json(ApiResponse::success(delta)))
+}
+
+/// Apply task mutations a device queued offline; each gets its own outcome
+pub async fn submit_sync_mutations(
+    _: RequirePermission<AdjustStock>,
+    State(state): State<AppState>,
+    Json(payload): Json<SyncBatch>,
+) -> AppResult<Json<ApiResponse<Vec<MutationResult>>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let results = sync::apply(&state.db, payload).await?;
+    let applied = results.iter().filter(|r| r.status == MutationStatus::Applied).count();
+    let message = format!("{} of {} mutations applied", applied, results.len());
+    Ok(Json(ApiResponse::success_with_message(results, message)))
+}
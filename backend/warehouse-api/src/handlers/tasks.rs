@@ -0,0 +1,147 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::{paging, AppError, AppResult, AppState};
+use warehouse_db::TASK_ORDER;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_tasks(
+    Query(filter): Query<TaskFilter>,
+    Query(pagination): Query<PaginationQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<PaginatedResponse<Linked<Task>>>>> {
+    paging::check_page(&state.config.pagination, &TASK_ORDER, &pagination)?;
+    let result = state.db.tasks().list(filter, pagination).await?;
+    Ok(Json(ApiResponse::success(links::paginated(result, &uri))))
+}
+
+pub async fn get_task(Path(id): Path<i32>, State(state): State<AppState>) -> AppResult<Json<ApiResponse<Task>>> {
+    let task = state
+        .db
+        .tasks()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("task"))?;
+
+    Ok(with_links(task, None))
+}
+
+pub async fn create_task(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateTask>,
+) -> AppResult<Json<ApiResponse<Task>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.warehouses().get_by_id(payload.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if let Some(item_id) = payload.item_id {
+        if state.db.items().get_by_id(item_id).await?.is_none() {
+            return Err(AppError::not_found("item"));
+        }
+    }
+
+    let task = state.db.tasks().create(payload).await?;
+    Ok(with_links(task, Some("Task queued")))
+}
+
+/// Supervisor assigns a task to an operator
+pub async fn assign_task(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<AssignTask>,
+) -> AppResult<Json<ApiResponse<Task>>> {
+    let task = state
+        .db
+        .tasks()
+        .assign(id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("task"))?;
+
+    Ok(with_links(task, Some("Task assigned")))
+}
+
+pub async fn claim_task(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<ClaimTask>,
+) -> AppResult<Json<ApiResponse<Task>>> {
+    let task = state
+        .db
+        .tasks()
+        .claim(id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("task"))?;
+
+    Ok(with_links(task, Some("Task claimed")))
+}
+
+/// Operator takes the next task from the queue; `data` is null when there is no work
+pub async fn claim_next_task(
+    State(state): State<AppState>,
+    Json(payload): Json<ClaimNextTask>,
+) -> AppResult<Json<ApiResponse<Option<Task>>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    match state.db.tasks().claim_next(payload).await? {
+        Some(task) => {
+            let resource_links = task.links();
+            Ok(Json(
+                ApiResponse::success_with_message(Some(task), "Task claimed".to_string()).with_links(resource_links),
+            ))
+        }
+        None => Ok(Json(ApiResponse::success_with_message(None, "No tasks waiting".to_string()))),
+    }
+}
+
+pub async fn complete_task(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CompleteTask>,
+) -> AppResult<Json<ApiResponse<Task>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let task = state
+        .db
+        .tasks()
+        .complete(id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("task"))?;
+
+    Ok(with_links(task, Some("Task completed")))
+}
+
+pub async fn cancel_task(Path(id): Path<i32>, State(state): State<AppState>) -> AppResult<Json<ApiResponse<Task>>> {
+    let task = state
+        .db
+        .tasks()
+        .cancel(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("task"))?;
+
+    Ok(with_links(task, Some("Task cancelled")))
+}
+
+/// Supervisor view of outstanding work per warehouse and task type
+pub async fn queue_depth(
+    Query(filter): Query<QueueDepthFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<QueueDepth>>>> {
+    let depth = state.db.tasks().queue_depth(filter).await?;
+    Ok(Json(ApiResponse::success(depth)))
+}
+
+fn with_links(task: Task, message: Option<&str>) -> Json<ApiResponse<Task>> {
+    let resource_links = task.links();
+    let response = match message {
+        Some(message) => ApiResponse::success_with_message(task, message.to_string()),
+        None => ApiResponse::success(task),
+    };
+    Json(response.with_links(resource_links))
+}
@@ -0,0 +1,176 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_templates(
+    Query(filter): Query<PrintTemplateFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<PrintTemplate>>>>> {
+    if let Some(purpose) = filter.purpose.as_deref() {
+        check_purpose(purpose)?;
+    }
+    if let Some(format) = filter.format.as_deref().filter(|format| !TEMPLATE_FORMATS.contains(format)) {
+        return Err(AppError::validation(format!(
+            "unknown template format {}; format must be one of: {}",
+            format,
+            TEMPLATE_FORMATS.join(", ")
+        )));
+    }
+
+    let templates = state.db.templates().list(&filter).await?;
+    Ok(Json(ApiResponse::success(templates.into_iter().map(links::linked).collect())))
+}
+
+/// A template with the body of its current version
+pub async fn get_template(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<PrintTemplateDetail>>> {
+    let template = state.db.templates().get(id).await?.ok_or_else(|| AppError::not_found("print template"))?;
+    let resource_links = template.template.links();
+    Ok(Json(ApiResponse::success(template).with_links(resource_links)))
+}
+
+pub async fn create_template(
+    State(state): State<AppState>,
+    Json(payload): Json<CreatePrintTemplate>,
+) -> AppResult<Json<ApiResponse<PrintTemplateDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if state.db.templates().code_exists(&payload.template_code).await? {
+        return Err(AppError::already_exists("print template code"));
+    }
+
+    let template = state.db.templates().create(payload).await?;
+    let resource_links = template.template.links();
+    let message = format!("Template {} created", template.template.template_code);
+    Ok(Json(ApiResponse::success_with_message(template, message).with_links(resource_links)))
+}
+
+/// Change a template; a new body becomes its next version
+pub async fn update_template(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdatePrintTemplate>,
+) -> AppResult<Json<ApiResponse<PrintTemplateDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let template =
+        state.db.templates().update(id, payload).await?.ok_or_else(|| AppError::not_found("print template"))?;
+    let resource_links = template.template.links();
+    let message = format!("Template updated, now at version {}", template.template.current_version);
+    Ok(Json(ApiResponse::success_with_message(template, message).with_links(resource_links)))
+}
+
+/// Deactivate a template; its versions are kept
+pub async fn delete_template(Path(id): Path<i32>, State(state): State<AppState>) -> AppResult<Json<ApiResponse<()>>> {
+    let update = UpdatePrintTemplate { is_active: Some(false), ..Default::default() };
+    if state.db.templates().update(id, update).await?.is_none() {
+        return Err(AppError::not_found("print template"));
+    }
+    Ok(Json(ApiResponse::success_with_message((), "Template deactivated".to_string())))
+}
+
+pub async fn list_versions(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<PrintTemplateVersion>>>> {
+    if state.db.templates().get(id).await?.is_none() {
+        return Err(AppError::not_found("print template"));
+    }
+
+    let versions = state.db.templates().versions(id).await?;
+    Ok(Json(ApiResponse::success(versions)))
+}
+
+pub async fn get_version(
+    Path((id, version)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<PrintTemplateVersion>>> {
+    let version = state
+        .db
+        .templates()
+        .version(id, version)
+        .await?
+        .ok_or_else(|| AppError::not_found("print template version"))?;
+    Ok(Json(ApiResponse::success(version)))
+}
+
+/// Make an earlier version current again
+pub async fn restore_version(
+    Path((id, version)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<PrintTemplateDetail>>> {
+    let template = state
+        .db
+        .templates()
+        .restore(id, version)
+        .await?
+        .ok_or_else(|| AppError::not_found("print template version"))?;
+    let resource_links = template.template.links();
+    let message = format!("Version {} restored as version {}", version, template.template.current_version);
+    Ok(Json(ApiResponse::success_with_message(template, message).with_links(resource_links)))
+}
+
+/// The templates a warehouse prints with
+pub async fn list_assignments(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<PrintTemplateAssignment>>>>> {
+    if state.db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let assignments = state.db.templates().assignments(warehouse_id).await?;
+    Ok(Json(ApiResponse::success(assignments.into_iter().map(links::linked).collect())))
+}
+
+/// Print the template's purpose in the warehouse with it
+pub async fn assign_template(
+    Path(warehouse_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<AssignPrintTemplate>,
+) -> AppResult<Json<ApiResponse<PrintTemplateAssignment>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if state.db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+
+    let assignment = state
+        .db
+        .templates()
+        .assign(warehouse_id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("print template"))?;
+    let resource_links = assignment.links();
+    let message = format!("{} assigned for {}", assignment.template_code, assignment.purpose);
+    Ok(Json(ApiResponse::success_with_message(assignment, message).with_links(resource_links)))
+}
+
+pub async fn unassign_template(
+    Path((warehouse_id, purpose)): Path<(i32, String)>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    check_purpose(&purpose)?;
+    if !state.db.templates().unassign(warehouse_id, &purpose).await? {
+        return Err(AppError::not_found("print template assignment"));
+    }
+    Ok(Json(ApiResponse::success_with_message((), "Template unassigned".to_string())))
+}
+
+fn check_purpose(purpose: &str) -> AppResult<()> {
+    if TEMPLATE_PURPOSES.contains(&purpose) {
+        return Ok(());
+    }
+    Err(AppError::validation(format!(
+        "unknown template purpose {}; purpose must be one of: {}",
+        purpose,
+        TEMPLATE_PURPOSES.join(", ")
+    )))
+}
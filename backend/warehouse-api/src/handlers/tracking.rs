@@ -0,0 +1,68 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Json,
+};
+
+use warehouse_core::carrier::tracking;
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+pub async fn get_shipment_tracking(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<TrackingTimeline>>> {
+    let timeline = state
+        .db
+        .tracking()
+        .timeline(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("shipment"))?;
+
+    Ok(Json(ApiResponse::success(timeline)))
+}
+
+/// Carrier push of tracking events. When the carrier has a webhook secret configured
+/// the request must carry it in `X-Webhook-Token`.
+pub async fn receive_tracking_webhook(
+    Path(code): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<TrackingUpdate>,
+) -> AppResult<Json<ApiResponse<TrackingTimeline>>> {
+    let carrier = state
+        .config
+        .carriers
+        .iter()
+        .find(|carrier| carrier.code.eq_ignore_ascii_case(&code))
+        .ok_or_else(|| AppError::not_found("carrier"))?;
+    if let Some(secret) = &carrier.webhook_secret {
+        let token = headers.get("x-webhook-token").and_then(|value| value.to_str().ok());
+        if token != Some(secret.as_str()) {
+            return Err(AppError::Unauthorized);
+        }
+    }
+
+    payload.validate().map_err(AppError::validation)?;
+
+    let shipment = state
+        .db
+        .tracking()
+        .find_shipment(&carrier.code, &payload.tracking_number)
+        .await?
+        .ok_or_else(|| AppError::not_found("shipment"))?;
+
+    let recorded = tracking::ingest(&state.db, &shipment, payload.events, TRACKING_SOURCE_WEBHOOK).await?;
+    let timeline = state
+        .db
+        .tracking()
+        .timeline(shipment.shipment_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("shipment"))?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        timeline,
+        format!("{} new tracking events", recorded.len()),
+    )))
+}
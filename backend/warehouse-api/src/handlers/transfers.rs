@@ -0,0 +1,91 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{transfers, AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_transfers(
+    Query(filter): Query<TransferFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<StockTransfer>>>>> {
+    let transfers = state.db.transfers().list(filter).await?;
+    Ok(Json(ApiResponse::success(transfers.into_iter().map(links::linked).collect())))
+}
+
+pub async fn create_transfer(
+    _: RequirePermission<AdjustStock>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateTransfer>,
+) -> AppResult<Json<ApiResponse<TransferDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    for warehouse_id in [payload.source_warehouse_id, payload.destination_warehouse_id] {
+        if state.db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+            return Err(AppError::not_found("warehouse"));
+        }
+    }
+    if let Some(code) = &payload.carrier_code {
+        if state.carriers.get(code).is_none() {
+            return Err(AppError::validation(format!("no carrier configured for {}", code)));
+        }
+    }
+
+    let detail = state.db.transfers().create(payload).await?;
+    let resource_links = detail.transfer.links();
+    let message = format!("Transfer {} planned", detail.transfer.transfer_number);
+    Ok(Json(ApiResponse::success_with_message(detail, message).with_links(resource_links)))
+}
+
+pub async fn get_transfer(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<TransferDetail>>> {
+    let detail = state.db.transfers().get_by_id(id).await?.ok_or_else(|| AppError::not_found("transfer"))?;
+    let resource_links = detail.transfer.links();
+    Ok(Json(ApiResponse::success(detail).with_links(resource_links)))
+}
+
+/// Issue the goods from the source into transit, buying a label when a service is set
+pub async fn dispatch_transfer(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<TransferDetail>>> {
+    let detail =
+        transfers::dispatch(&state.db, &state.carriers, id).await?.ok_or_else(|| AppError::not_found("transfer"))?;
+    let resource_links = detail.transfer.links();
+    Ok(Json(ApiResponse::success_with_message(detail, "Transfer dispatched".to_string()).with_links(resource_links)))
+}
+
+/// Book the goods in at the destination; if that fails the transfer is rolled back to the source
+pub async fn receive_transfer(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<TransferDetail>>> {
+    let detail =
+        transfers::receive(&state.db, &state.carriers, id).await?.ok_or_else(|| AppError::not_found("transfer"))?;
+    let resource_links = detail.transfer.links();
+    Ok(Json(ApiResponse::success_with_message(detail, "Transfer received".to_string()).with_links(resource_links)))
+}
+
+pub async fn compensate_transfer(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CompensateTransfer>,
+) -> AppResult<Json<ApiResponse<TransferDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let detail = transfers::compensate(&state.db, &state.carriers, id, &payload.reason)
+        .await?
+        .ok_or_else(|| AppError::not_found("transfer"))?;
+    let resource_links = detail.transfer.links();
+    Ok(Json(ApiResponse::success_with_message(detail, "Transfer rolled back".to_string()).with_links(resource_links)))
+}
@@ -0,0 +1,92 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{ManageUsers, RequirePermission};
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_db::UserStore;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_users(
+    _: RequirePermission<ManageUsers>,
+    Query(filter): Query<UserFilter>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<Vec<Linked<User>>>>> {
+    if let Some(role) = filter.role.as_deref().filter(|role| !ROLES.contains(role)) {
+        return Err(AppError::validation(format!("unknown role {}; role must be one of: {}", role, ROLES.join(", "))));
+    }
+
+    let users = state.db.users().list(&filter).await?;
+    Ok(Json(ApiResponse::success(users.into_iter().map(links::linked).collect())))
+}
+
+pub async fn get_user(
+    _: RequirePermission<ManageUsers>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<User>>> {
+    let user = state.db.users().get(id).await?.ok_or_else(|| AppError::not_found("user"))?;
+    let resource_links = user.links();
+    Ok(Json(ApiResponse::success(user).with_links(resource_links)))
+}
+
+pub async fn create_user(
+    _: RequirePermission<ManageUsers>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateUser>,
+) -> AppResult<Json<ApiResponse<User>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if state.db.users().username_exists(&payload.username).await? {
+        return Err(AppError::already_exists("user with this username"));
+    }
+    if let Some(email) = &payload.email {
+        if state.db.users().email_exists(email, None).await? {
+            return Err(AppError::already_exists("user with this email"));
+        }
+    }
+
+    let user = state.db.users().create(payload).await?;
+    let resource_links = user.links();
+    let message = format!("User {} created", user.username);
+    Ok(Json(ApiResponse::success_with_message(user, message).with_links(resource_links)))
+}
+
+pub async fn update_user(
+    _: RequirePermission<ManageUsers>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateUser>,
+) -> AppResult<Json<ApiResponse<User>>> {
+    payload.validate().map_err(AppError::validation)?;
+    if id == SYSTEM_USER_ID {
+        return Err(AppError::validation("the system user cannot be changed"));
+    }
+    if let Some(email) = &payload.email {
+        if state.db.users().email_exists(email, Some(id)).await? {
+            return Err(AppError::already_exists("user with this email"));
+        }
+    }
+
+    let user = state.db.users().update(id, payload).await?.ok_or_else(|| AppError::not_found("user"))?;
+    let resource_links = user.links();
+    Ok(Json(ApiResponse::success_with_message(user, "User updated".to_string()).with_links(resource_links)))
+}
+
+/// Deactivate a user; audit columns keep naming them
+pub async fn delete_user(
+    _: RequirePermission<ManageUsers>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    if id == SYSTEM_USER_ID {
+        return Err(AppError::validation("the system user cannot be changed"));
+    }
+    if !state.db.users().deactivate(id).await? {
+        return Err(AppError::not_found("user"));
+    }
+    Ok(Json(ApiResponse::success_with_message((), "User deactivated".to_string())))
+}
@@ -0,0 +1,86 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::CurrentUser;
+use warehouse_core::{AppError, AppResult, AppState};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+/// The signed-in user's own views, optionally of one list endpoint
+pub async fn list_views(
+    Query(query): Query<ViewQuery>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<Json<ApiResponse<Vec<Linked<SavedView>>>>> {
+    let owner = user.username;
+    let views = state.db.views().list(&owner, query.resource.as_deref()).await?;
+    Ok(Json(ApiResponse::success(views.into_iter().map(links::linked).collect())))
+}
+
+pub async fn get_view(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<Json<ApiResponse<SavedViewDetail>>> {
+    let owner = user.username;
+    let detail = state.db.views().get(&owner, id).await?.ok_or_else(|| AppError::not_found("view"))?;
+    let resource_links = detail.links();
+    Ok(Json(ApiResponse::success(detail).with_links(resource_links)))
+}
+
+pub async fn create_view(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(payload): Json<CreateSavedView>,
+) -> AppResult<Json<ApiResponse<SavedViewDetail>>> {
+    let owner = user.username;
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.views().name_exists(&owner, &payload.resource, &payload.view_name).await? {
+        return Err(AppError::already_exists("view name"));
+    }
+
+    let detail = state.db.views().create(&owner, payload).await?;
+    let resource_links = detail.links();
+    let response = ApiResponse::success_with_message(detail, "View saved successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn update_view(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(payload): Json<UpdateSavedView>,
+) -> AppResult<Json<ApiResponse<SavedViewDetail>>> {
+    let owner = user.username;
+    payload.validate().map_err(AppError::validation)?;
+
+    if let Some(view_name) = &payload.view_name {
+        let current = state.db.views().get(&owner, id).await?.ok_or_else(|| AppError::not_found("view"))?;
+        let renamed = current.view.view_name != *view_name;
+        if renamed && state.db.views().name_exists(&owner, &current.view.resource, view_name).await? {
+            return Err(AppError::already_exists("view name"));
+        }
+    }
+
+    let detail = state.db.views().update(&owner, id, payload).await?.ok_or_else(|| AppError::not_found("view"))?;
+    let resource_links = detail.links();
+    let response = ApiResponse::success_with_message(detail, "View updated successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn delete_view(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let owner = user.username;
+    if !state.db.views().delete(&owner, id).await? {
+        return Err(AppError::not_found("view"));
+    }
+    Ok(Json(ApiResponse::success_with_message((), "View deleted successfully".to_string())))
+}
@@ -0,0 +1,96 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::{paging, vmi, AppError, AppResult, AppState};
+use warehouse_models::chrono::Utc;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_feeds(State(state): State<AppState>) -> AppResult<Json<ApiResponse<Vec<Linked<VmiFeed>>>>> {
+    let feeds = state.db.vmi().list().await?;
+    Ok(Json(ApiResponse::success(feeds.into_iter().map(links::linked).collect())))
+}
+
+pub async fn get_feed(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<VmiFeedDetail>>> {
+    let detail = state.db.vmi().get_by_id(id).await?.ok_or_else(|| AppError::not_found("VMI feed"))?;
+    let resource_links = detail.feed.links();
+    Ok(Json(ApiResponse::success(detail).with_links(resource_links)))
+}
+
+pub async fn create_feed(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateVmiFeed>,
+) -> AppResult<Json<ApiResponse<VmiFeedDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if let Some(warehouse_id) = payload.warehouse_id {
+        if state.db.warehouses().get_by_id(warehouse_id).await?.is_none() {
+            return Err(AppError::not_found("warehouse"));
+        }
+    }
+    if state.db.vmi().supplier_code_exists(&payload.supplier_code).await? {
+        return Err(AppError::already_exists("VMI feed for this supplier"));
+    }
+
+    let detail = state.db.vmi().create(payload).await?;
+    let resource_links = detail.feed.links();
+    let response = ApiResponse::success_with_message(detail, "VMI feed created successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+pub async fn update_feed(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateVmiFeed>,
+) -> AppResult<Json<ApiResponse<VmiFeedDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let detail = state.db.vmi().update(id, payload).await?.ok_or_else(|| AppError::not_found("VMI feed"))?;
+    let resource_links = detail.feed.links();
+    let response = ApiResponse::success_with_message(detail, "VMI feed updated successfully".to_string());
+    Ok(Json(response.with_links(resource_links)))
+}
+
+/// What the feed would send right now, without sending it
+pub async fn preview_feed(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<VmiSnapshot>>> {
+    let detail = state.db.vmi().get_by_id(id).await?.ok_or_else(|| AppError::not_found("VMI feed"))?;
+    let snapshot = state.db.vmi().snapshot(&detail.feed, Utc::now()).await?;
+    Ok(Json(ApiResponse::success(snapshot)))
+}
+
+/// Deliver the feed now, outside its schedule
+pub async fn run_feed(Path(id): Path<i32>, State(state): State<AppState>) -> AppResult<Json<ApiResponse<VmiFeedRun>>> {
+    let detail = state.db.vmi().get_by_id(id).await?.ok_or_else(|| AppError::not_found("VMI feed"))?;
+
+    let run = vmi::run_feed(&state.db, &state.config.vmi, &detail.feed).await?;
+    let message = match &run.error {
+        None => format!("Feed delivered with {} lines", run.line_count),
+        Some(error) => format!("Feed delivery failed: {}", error),
+    };
+    Ok(Json(ApiResponse::success_with_message(run, message)))
+}
+
+pub async fn list_feed_runs(
+    Path(id): Path<i32>,
+    Query(keyset): Query<KeysetQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<KeysetPage<Linked<VmiFeedRun>>>>> {
+    paging::check_keyset(&state.config.pagination, "vmi/feeds/runs", &keyset)?;
+    if state.db.vmi().get_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("VMI feed"));
+    }
+
+    let runs = state.db.vmi().runs(id, keyset).await?;
+    Ok(Json(ApiResponse::success(links::keyset(runs, &uri))))
+}
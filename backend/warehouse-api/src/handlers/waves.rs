@@ -0,0 +1,151 @@
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    response::Json,
+};
+
+use warehouse_core::auth::{AdjustStock, RequirePermission};
+use warehouse_core::{paging, AppError, AppResult, AppState};
+use warehouse_db::WAVE_ORDER;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub async fn list_waves(
+    Query(filter): Query<WaveFilter>,
+    Query(pagination): Query<PaginationQuery>,
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+) -> AppResult<Json<ApiResponse<PaginatedResponse<Linked<Wave>>>>> {
+    paging::check_page(&state.config.pagination, &WAVE_ORDER, &pagination)?;
+    let result = state.db.waves().list(filter, pagination).await?;
+    Ok(Json(ApiResponse::success(links::paginated(result, &uri))))
+}
+
+pub async fn get_wave(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<WaveDetail>>> {
+    let detail = state
+        .db
+        .waves()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("wave"))?;
+
+    Ok(with_links(detail, None))
+}
+
+pub async fn create_wave(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateWave>,
+) -> AppResult<Json<ApiResponse<WaveDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.warehouses().get_by_id(payload.warehouse_id).await?.is_none() {
+        return Err(AppError::not_found("warehouse"));
+    }
+    if let Some(zone_id) = payload.zone_id {
+        match state.db.locations().get_zone(zone_id).await? {
+            Some(zone) if zone.warehouse_id == payload.warehouse_id => {}
+            _ => return Err(AppError::not_found("zone")),
+        }
+    }
+
+    let detail = state.db.waves().create(payload).await?;
+    let message = format!("Wave planned with {} orders", detail.order_ids.len());
+    Ok(with_links(detail, Some(&message)))
+}
+
+pub async fn release_wave(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<WaveDetail>>> {
+    let detail = state
+        .db
+        .waves()
+        .release(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("wave"))?;
+
+    let message = format!("Wave released with {} pick tasks", detail.tasks.len());
+    Ok(with_links(detail, Some(&message)))
+}
+
+pub async fn cancel_wave(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<WaveDetail>>> {
+    let detail = state
+        .db
+        .waves()
+        .cancel(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("wave"))?;
+
+    Ok(with_links(detail, Some("Wave cancelled")))
+}
+
+pub async fn confirm_pick(
+    _: RequirePermission<AdjustStock>,
+    Path((id, task_id)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+    Json(payload): Json<ConfirmPick>,
+) -> AppResult<Json<ApiResponse<WaveDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let detail = state
+        .db
+        .waves()
+        .confirm_pick(id, task_id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("pick task"))?;
+
+    Ok(with_links(detail, Some("Pick confirmed")))
+}
+
+/// Pack picked goods for one order into a carton
+pub async fn pack_carton(
+    _: RequirePermission<AdjustStock>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<PackCarton>,
+) -> AppResult<Json<ApiResponse<CartonDetail>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    let (_, carton) = state
+        .db
+        .waves()
+        .pack(id, payload)
+        .await?
+        .ok_or_else(|| AppError::not_found("wave"))?;
+
+    let resource_links = carton.carton.links();
+    Ok(Json(
+        ApiResponse::success_with_message(carton, "Carton packed".to_string()).with_links(resource_links),
+    ))
+}
+
+pub async fn get_carton(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<CartonDetail>>> {
+    let carton = state
+        .db
+        .waves()
+        .get_carton(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("carton"))?;
+
+    let resource_links = carton.carton.links();
+    Ok(Json(ApiResponse::success(carton).with_links(resource_links)))
+}
+
+fn with_links(detail: WaveDetail, message: Option<&str>) -> Json<ApiResponse<WaveDetail>> {
+    let resource_links = detail.wave.links();
+    let response = match message {
+        Some(message) => ApiResponse::success_with_message(detail, message.to_string()),
+        None => ApiResponse::success(detail),
+    };
+    Json(response.with_links(resource_links))
+}
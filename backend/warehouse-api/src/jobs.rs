@@ -0,0 +1,347 @@
+//! Background jobs run alongside the HTTP server. Where several replicas run, each job
+//! runs on the one replica leading it; see `warehouse_core::locks`.
+
+use std::time::Duration;
+
+use tracing::{info, warn};
+use warehouse_models::chrono::Utc;
+use warehouse_models::GenerateRequisitions;
+use warehouse_core::AppState;
+
+/// Poll carriers for tracking events on shipments still in flight
+pub fn spawn_tracking_poller(state: AppState) {
+    let every = state.config.tracking.poll_interval_secs;
+    if every == 0 || state.carriers.is_empty() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let every = Duration::from_secs(every);
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let work = async {
+                match state.carriers.poll_tracking(&state.db).await {
+                    Ok(0) => {}
+                    Ok(recorded) => info!("Tracking poll recorded {} new events", recorded),
+                    Err(e) => warn!("Tracking poll failed: {}", e),
+                }
+            };
+            state.locks.run("tracking-poll", every, work).await;
+        }
+    });
+}
+
+/// Deliver vendor-managed inventory feeds as they come due
+pub fn spawn_vmi_scheduler(state: AppState) {
+    let every = state.config.vmi.poll_interval_secs;
+    if every == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let every = Duration::from_secs(every);
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let work = async {
+                match warehouse_core::vmi::run_due(&state.db, &state.config.vmi).await {
+                    Ok(0) => {}
+                    Ok(delivered) => info!("Delivered {} VMI feeds", delivered),
+                    Err(e) => warn!("VMI feed run failed: {}", e),
+                }
+            };
+            state.locks.run("vmi-feeds", every, work).await;
+        }
+    });
+}
+
+/// Capture billable client activity, including the daily storage snapshot
+pub fn spawn_billing_capture(state: AppState) {
+    let every = state.config.billing.capture_interval_secs;
+    if every == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let every = Duration::from_secs(every);
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let work = async {
+                match state.db.billing().capture(Utc::now().date_naive()).await {
+                    Ok(captured) => info!(
+                        "Billing capture recorded {} receipts, {} picks, {} storage snapshots",
+                        captured.receipts, captured.picks, captured.storage
+                    ),
+                    Err(e) => warn!("Billing capture failed: {}", e),
+                }
+            };
+            state.locks.run("billing-capture", every, work).await;
+        }
+    });
+}
+
+/// Draft purchase requisitions for reorder alerts in every active warehouse
+pub fn spawn_requisition_generation(state: AppState) {
+    let every = state.config.purchasing.requisition_interval_secs;
+    if every == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let every = Duration::from_secs(every);
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let work = async {
+                let warehouse_ids = match state.db.purchasing().warehouse_ids().await {
+                    Ok(ids) => ids,
+                    Err(e) => {
+                        warn!("Requisition generation failed: {}", e);
+                        return;
+                    }
+                };
+                for warehouse_id in warehouse_ids {
+                    let request =
+                        GenerateRequisitions { method: Some(state.config.purchasing.requisition_method.clone()) };
+                    match state.db.for_warehouse(warehouse_id).purchasing().generate(warehouse_id, request).await {
+                        Ok(run) if run.requisitions.is_empty() => {}
+                        Ok(run) => {
+                            info!("Drafted {} requisitions for warehouse {}", run.requisitions.len(), warehouse_id)
+                        }
+                        Err(e) => warn!("Requisition generation failed for warehouse {}: {}", warehouse_id, e),
+                    }
+                }
+            };
+            state.locks.run("requisition-generation", every, work).await;
+        }
+    });
+}
+
+/// Expire project reservations past their expiry date, giving the stock they hold back
+pub fn spawn_reservation_expiry(state: AppState) {
+    let every = state.config.projects.reservation_expiry_interval_secs;
+    if every == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let every = Duration::from_secs(every);
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let work = async {
+                match state.db.projects().expire_reservations().await {
+                    Ok(expired) if expired.is_empty() => {}
+                    Ok(expired) => info!("Expired {} project reservations", expired.len()),
+                    Err(e) => warn!("Project reservation expiry failed: {}", e),
+                }
+            };
+            state.locks.run("reservation-expiry", every, work).await;
+        }
+    });
+}
+
+/// Flag loans still out after their due date as overdue, notifying supervisors of each
+pub fn spawn_overdue_loans(state: AppState) {
+    let every = state.config.loans.overdue_scan_interval_secs;
+    if every == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let every = Duration::from_secs(every);
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let work = async {
+                match state.db.loans().flag_overdue().await {
+                    Ok(flagged) if flagged.is_empty() => {}
+                    Ok(flagged) => info!("Flagged {} loans overdue", flagged.len()),
+                    Err(e) => warn!("Overdue loan scan failed: {}", e),
+                }
+            };
+            state.locks.run("overdue-loans", every, work).await;
+        }
+    });
+}
+
+/// Keep the read models behind list and search endpoints current with the source tables
+pub fn spawn_read_model_projector(state: AppState) {
+    let every = state.config.read_models.project_interval_secs;
+    if every == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let every = Duration::from_secs(every);
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let work = async {
+                // Drain the backlog a batch at a time so a burst of postings does not hold the lock long
+                loop {
+                    match state.db.read_models().project(500).await {
+                        Ok(500) => continue,
+                        Ok(_) => break,
+                        Err(e) => {
+                            warn!("Read model projection failed: {}", e);
+                            break;
+                        }
+                    }
+                }
+                if let Err(e) = state.db.stock_events().project().await {
+                    warn!("Stock event projection failed: {}", e);
+                }
+            };
+            state.locks.run("read-model-projection", every, work).await;
+        }
+    });
+}
+
+/// Send stock events to downstream consumers that are behind
+pub fn spawn_event_delivery(state: AppState) {
+    let every = state.config.events.delivery_interval_secs;
+    if every == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let every = Duration::from_secs(every);
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let work = async {
+                match warehouse_core::replay::deliver_pending(&state.db, &state.config.events).await {
+                    Ok(0) => {}
+                    Ok(delivered) => info!("Delivered {} stock events to consumers", delivered),
+                    Err(e) => warn!("Stock event delivery failed: {}", e),
+                }
+            };
+            state.locks.run("event-delivery", every, work).await;
+        }
+    });
+}
+
+/// Check stock invariants and alert when they break
+pub fn spawn_integrity_checks(state: AppState) {
+    let every = state.config.integrity.check_interval_secs;
+    if every == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let every = Duration::from_secs(every);
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let work = async {
+                match warehouse_core::integrity::run(&state.db).await {
+                    Ok(report) if !report.passed() => {
+                        warn!("Integrity run {} found {} broken invariants", report.run.run_id, report.run.findings)
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!("Integrity checks failed: {}", e),
+                }
+            };
+            state.locks.run("integrity-checks", every, work).await;
+        }
+    });
+}
+
+/// Copy warehouses and items to the database shards, catching rows changed outside the API
+pub fn spawn_shard_copy(state: AppState) {
+    let every = state.config.database.shard_copy_interval_secs;
+    if every == 0 || state.config.shards.is_empty() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let every = Duration::from_secs(every);
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let work = async {
+                match state.db.shards().copy_master_data().await {
+                    Ok(0) => {}
+                    Ok(copied) => info!("Copied {} warehouse and item rows to the database shards", copied),
+                    Err(e) => warn!("Copying master data to the database shards failed: {}", e),
+                }
+            };
+            state.locks.run("shard-copy", every, work).await;
+        }
+    });
+}
+
+/// Sync ERP connectors as they come due
+pub fn spawn_erp_sync(state: AppState) {
+    let every = state.config.erp.poll_interval_secs;
+    if every == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let every = Duration::from_secs(every);
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let work = async {
+                match warehouse_core::erp::run_due(&state.db).await {
+                    Ok(0) => {}
+                    Ok(synced) => info!("Synced {} ERP connectors", synced),
+                    Err(e) => warn!("ERP sync run failed: {}", e),
+                }
+            };
+            state.locks.run("erp-sync", every, work).await;
+        }
+    });
+}
+
+/// Sync e-commerce channels as they come due
+pub fn spawn_ecommerce_sync(state: AppState) {
+    let every = state.config.ecommerce.poll_interval_secs;
+    if every == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let every = Duration::from_secs(every);
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let work = async {
+                match warehouse_core::ecommerce::run_due(&state.db).await {
+                    Ok(0) => {}
+                    Ok(synced) => info!("Synced {} e-commerce channels", synced),
+                    Err(e) => warn!("E-commerce sync run failed: {}", e),
+                }
+            };
+            state.locks.run("ecommerce-sync", every, work).await;
+        }
+    });
+}
+
+/// Email scheduled reports as they come due
+pub fn spawn_report_delivery(state: AppState) {
+    let every = state.config.reports.poll_interval_secs;
+    if every == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let every = Duration::from_secs(every);
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let work = async {
+                match warehouse_core::reports::run_due(&state.db, &state.config.mail).await {
+                    Ok(0) => {}
+                    Ok(sent) => info!("Delivered {} scheduled reports", sent),
+                    Err(e) => warn!("Report delivery run failed: {}", e),
+                }
+            };
+            state.locks.run("report-delivery", every, work).await;
+        }
+    });
+}
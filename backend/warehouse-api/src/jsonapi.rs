@@ -0,0 +1,150 @@
+//! JSON:API (https://jsonapi.org) serialization, selected with `Accept: application/vnd.api+json`
+
+use axum::{
+    http::{header, HeaderValue, Uri},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use warehouse_models::*;
+
+use crate::links::{self, ResourceLinks};
+
+pub const MEDIA_TYPE: &str = "application/vnd.api+json";
+
+/// A to-one relationship expressed through a foreign key column
+pub struct Relationship {
+    pub name: &'static str,
+    pub resource_type: &'static str,
+    pub foreign_key: &'static str,
+    pub id: Option<String>,
+}
+
+impl Relationship {
+    pub fn to_one(
+        name: &'static str,
+        resource_type: &'static str,
+        foreign_key: &'static str,
+        id: Option<impl ToString>,
+    ) -> Self {
+        Self {
+            name,
+            resource_type,
+            foreign_key,
+            id: id.map(|id| id.to_string()),
+        }
+    }
+}
+
+/// A model that can be rendered as a JSON:API resource object
+pub trait Resource: Serialize + ResourceLinks {
+    const TYPE: &'static str;
+    /// Primary key field; it is moved out of `attributes` into `id`
+    const ID_FIELD: &'static str;
+
+    fn id(&self) -> String;
+
+    fn relationships(&self) -> Vec<Relationship> {
+        Vec::new()
+    }
+}
+
+impl Resource for Warehouse {
+    const TYPE: &'static str = "warehouses";
+    const ID_FIELD: &'static str = "warehouse_id";
+
+    fn id(&self) -> String {
+        self.warehouse_id.to_string()
+    }
+}
+
+impl Resource for Item {
+    const TYPE: &'static str = "items";
+    const ID_FIELD: &'static str = "item_id";
+
+    fn id(&self) -> String {
+        self.item_id.to_string()
+    }
+}
+
+impl Resource for StockMovement {
+    const TYPE: &'static str = "movements";
+    const ID_FIELD: &'static str = "movement_id";
+
+    fn id(&self) -> String {
+        self.movement_id.to_string()
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![
+            Relationship::to_one("item", Item::TYPE, "item_id", Some(self.item_id)),
+            Relationship::to_one("warehouse", Warehouse::TYPE, "warehouse_id", Some(self.warehouse_id)),
+        ]
+    }
+}
+
+/// Document with a single primary resource
+pub fn single<T: Resource>(resource: &T) -> Response {
+    document(json!({ "data": resource_object(resource) }))
+}
+
+/// Document for an offset-paginated collection, with first/prev/next/last links
+pub fn collection<T: Resource>(page: &PaginatedResponse<T>, uri: &Uri) -> Response {
+    document(json!({
+        "data": page.data.iter().map(resource_object).collect::<Vec<_>>(),
+        "links": links::page_links(&page.pagination, uri),
+        "meta": page.pagination,
+    }))
+}
+
+/// Document for a keyset-paginated collection; `next` carries the `after_id` cursor
+pub fn keyset_collection<T: Resource>(page: &KeysetPage<T>, uri: &Uri) -> Response {
+    document(json!({
+        "data": page.data.iter().map(resource_object).collect::<Vec<_>>(),
+        "links": links::keyset_links(page.next_after_id, uri),
+        "meta": { "limit": page.limit, "has_more": page.has_more },
+    }))
+}
+
+fn resource_object<T: Resource>(resource: &T) -> Value {
+    let relationships = resource.relationships();
+
+    let mut attributes = serde_json::to_value(resource).unwrap_or(Value::Null);
+    if let Value::Object(fields) = &mut attributes {
+        fields.remove(T::ID_FIELD);
+        for relationship in &relationships {
+            fields.remove(relationship.foreign_key);
+        }
+    }
+
+    let mut object = json!({
+        "type": T::TYPE,
+        "id": resource.id(),
+        "attributes": attributes,
+        "links": resource.links(),
+    });
+
+    if !relationships.is_empty() {
+        let related: Map<String, Value> = relationships
+            .into_iter()
+            .map(|relationship| {
+                let data = relationship
+                    .id
+                    .map(|id| json!({ "type": relationship.resource_type, "id": id }))
+                    .unwrap_or(Value::Null);
+                (relationship.name.to_string(), json!({ "data": data }))
+            })
+            .collect();
+        object["relationships"] = Value::Object(related);
+    }
+
+    object
+}
+
+fn document(body: Value) -> Response {
+    (
+        [(header::CONTENT_TYPE, HeaderValue::from_static(MEDIA_TYPE))],
+        Json(body),
+    )
+        .into_response()
+}
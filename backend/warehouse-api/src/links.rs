@@ -0,0 +1,842 @@
+//! Hypermedia links for resources and pages
+
+use axum::http::Uri;
+use warehouse_models::*;
+
+/// A model that knows the URLs of itself and its related resources
+pub trait ResourceLinks {
+    fn links(&self) -> Links;
+}
+
+impl ResourceLinks for Warehouse {
+    fn links(&self) -> Links {
+        let id = self.warehouse_id;
+        Links::from([
+            ("self".to_string(), format!("/api/warehouses/{}", id)),
+            ("stock".to_string(), format!("/api/warehouses/{}/stock", id)),
+            ("movements".to_string(), format!("/api/movements?warehouse_id={}", id)),
+        ])
+    }
+}
+
+impl ResourceLinks for Item {
+    fn links(&self) -> Links {
+        let id = self.item_id;
+        Links::from([
+            ("self".to_string(), format!("/api/items/{}", id)),
+            ("movements".to_string(), format!("/api/movements?item_id={}", id)),
+        ])
+    }
+}
+
+impl ResourceLinks for StockMovement {
+    fn links(&self) -> Links {
+        Links::from([
+            ("item".to_string(), format!("/api/items/{}", self.item_id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for ConsumptionRecord {
+    fn links(&self) -> Links {
+        Links::from([
+            ("item".to_string(), format!("/api/items/{}", self.item_id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for ReturnAuthorization {
+    fn links(&self) -> Links {
+        let id = self.return_id;
+        Links::from([
+            ("self".to_string(), format!("/api/returns/{}", id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+            ("receive".to_string(), format!("/api/returns/{}/receive", id)),
+            ("inspect".to_string(), format!("/api/returns/{}/inspect", id)),
+            (
+                "movements".to_string(),
+                format!("/api/movements?warehouse_id={}", self.warehouse_id),
+            ),
+        ])
+    }
+}
+
+impl ResourceLinks for QualityInspection {
+    fn links(&self) -> Links {
+        Links::from([
+            ("self".to_string(), format!("/api/inspections/{}", self.inspection_id)),
+            ("item".to_string(), format!("/api/items/{}", self.item_id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for OutboundOrder {
+    fn links(&self) -> Links {
+        let id = self.order_id;
+        let mut links = Links::from([
+            ("self".to_string(), format!("/api/orders/{}", id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+            ("shipments".to_string(), format!("/api/orders/{}/shipments", id)),
+        ]);
+        if self.order_type == ORDER_TYPE_DROPSHIP {
+            links.insert("supplier-confirmation".to_string(), format!("/api/orders/{}/supplier-confirmation", id));
+            links.insert("supplier-shipments".to_string(), format!("/api/orders/{}/supplier-shipments", id));
+        } else {
+            links.insert("ship".to_string(), format!("/api/orders/{}/ship", id));
+            links.insert("rates".to_string(), format!("/api/orders/{}/rates", id));
+            links.insert("cartonization".to_string(), format!("/api/orders/{}/cartonization", id));
+        }
+        if let Some(wave_id) = self.wave_id {
+            links.insert("wave".to_string(), format!("/api/waves/{}", wave_id));
+        }
+        links
+    }
+}
+
+impl ResourceLinks for Shipment {
+    fn links(&self) -> Links {
+        Links::from([
+            ("self".to_string(), format!("/api/shipments/{}", self.shipment_id)),
+            ("tracking".to_string(), format!("/api/shipments/{}/tracking", self.shipment_id)),
+            ("hazmat".to_string(), format!("/api/shipments/{}/hazmat", self.shipment_id)),
+            ("documents".to_string(), format!("/api/shipments/{}/documents", self.shipment_id)),
+            ("proof-of-delivery".to_string(), format!("/api/shipments/{}/proof-of-delivery", self.shipment_id)),
+            ("order".to_string(), format!("/api/orders/{}", self.order_id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for ShipmentDocument {
+    fn links(&self) -> Links {
+        Links::from([
+            ("pdf".to_string(), format!("/api/shipment-documents/{}", self.document_id)),
+            ("shipment".to_string(), format!("/api/shipments/{}", self.shipment_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for DocumentBranding {
+    fn links(&self) -> Links {
+        match self.warehouse_id {
+            Some(warehouse_id) => Links::from([("warehouse".to_string(), format!("/api/warehouses/{}", warehouse_id))]),
+            None => Links::new(),
+        }
+    }
+}
+
+impl ResourceLinks for ReportDefinition {
+    fn links(&self) -> Links {
+        Links::from([
+            ("self".to_string(), format!("/api/reports/custom/{}", self.report_id)),
+            ("run".to_string(), format!("/api/reports/custom/{}/run", self.report_id)),
+            ("export".to_string(), format!("/api/reports/custom/{}/export", self.report_id)),
+            ("schedules".to_string(), format!("/api/reports/custom/{}/schedules", self.report_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for ReportSchedule {
+    fn links(&self) -> Links {
+        let id = self.schedule_id;
+        Links::from([
+            ("self".to_string(), format!("/api/reports/schedules/{}", id)),
+            ("report".to_string(), format!("/api/reports/custom/{}", self.report_id)),
+            ("deliver".to_string(), format!("/api/reports/schedules/{}/deliver", id)),
+            ("runs".to_string(), format!("/api/reports/schedules/{}/runs", id)),
+        ])
+    }
+}
+
+impl ResourceLinks for ReportScheduleRun {
+    fn links(&self) -> Links {
+        Links::from([("schedule".to_string(), format!("/api/reports/schedules/{}", self.schedule_id))])
+    }
+}
+
+impl ResourceLinks for Job {
+    fn links(&self) -> Links {
+        let mut links = Links::from([("self".to_string(), format!("/api/jobs/{}", self.job_id))]);
+        match (self.reference_type.as_deref(), self.reference_id) {
+            (Some("REPORT"), Some(id)) => {
+                links.insert("report".to_string(), format!("/api/reports/custom/{}", id));
+            }
+            (Some("BACKUP"), Some(id)) => {
+                links.insert("backup".to_string(), format!("/api/admin/backups/{}", id));
+            }
+            _ => {}
+        }
+        links
+    }
+}
+
+impl ResourceLinks for SavedView {
+    fn links(&self) -> Links {
+        Links::from([("self".to_string(), format!("/api/views/{}", self.view_id))])
+    }
+}
+
+impl ResourceLinks for SavedViewDetail {
+    /// `results` lists the resource with the view's filters and sort applied
+    fn links(&self) -> Links {
+        let view = &self.view;
+        let mut params: Vec<String> =
+            self.filters.iter().map(|filter| format!("{}={}", filter.name, encode(&filter.value))).collect();
+        if let Some(sort_by) = &view.sort_by {
+            params.push(format!("sort_by={}", sort_by));
+        }
+        if let Some(sort_order) = &view.sort_order {
+            params.push(format!("sort_order={}", sort_order));
+        }
+
+        let mut results = format!("/api/{}", view.resource);
+        if !params.is_empty() {
+            results = format!("{}?{}", results, params.join("&"));
+        }
+        let mut links = view.links();
+        links.insert("results".to_string(), results);
+        links
+    }
+}
+
+impl ResourceLinks for InboundReceipt {
+    fn links(&self) -> Links {
+        let id = self.receipt_id;
+        let mut links = Links::from([
+            ("self".to_string(), format!("/api/receipts/{}", id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+            ("receive".to_string(), format!("/api/receipts/{}/receive", id)),
+        ]);
+        if let Some(consignor_id) = self.consignor_id {
+            links.insert("consignor".to_string(), format!("/api/consignors/{}", consignor_id));
+        }
+        links
+    }
+}
+
+impl ResourceLinks for EdiDocument {
+    fn links(&self) -> Links {
+        let id = self.document_id;
+        let mut links = Links::from([
+            ("self".to_string(), format!("/api/edi/documents/{}", id)),
+            ("content".to_string(), format!("/api/edi/documents/{}/content", id)),
+        ]);
+        match (self.reference_type.as_deref(), self.reference_id) {
+            (Some("ORDER"), Some(order_id)) => {
+                links.insert("order".to_string(), format!("/api/orders/{}", order_id));
+            }
+            (Some("RECEIPT"), Some(receipt_id)) => {
+                links.insert("receipt".to_string(), format!("/api/receipts/{}", receipt_id));
+            }
+            (Some("SHIPMENT"), Some(shipment_id)) => {
+                links.insert("shipment".to_string(), format!("/api/shipments/{}", shipment_id));
+            }
+            _ => {}
+        }
+        links
+    }
+}
+
+impl ResourceLinks for Zone {
+    fn links(&self) -> Links {
+        Links::from([
+            ("locations".to_string(), format!("/api/zones/{}/locations", self.zone_id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for Excursion {
+    fn links(&self) -> Links {
+        Links::from([
+            ("sensors".to_string(), format!("/api/zones/{}/sensors", self.zone_id)),
+            ("thresholds".to_string(), format!("/api/zones/{}/thresholds", self.zone_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for Wave {
+    fn links(&self) -> Links {
+        let id = self.wave_id;
+        Links::from([
+            ("self".to_string(), format!("/api/waves/{}", id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+            ("release".to_string(), format!("/api/waves/{}/release", id)),
+            ("pack".to_string(), format!("/api/waves/{}/pack", id)),
+        ])
+    }
+}
+
+impl ResourceLinks for Carton {
+    fn links(&self) -> Links {
+        let mut links = Links::from([
+            ("self".to_string(), format!("/api/cartons/{}", self.carton_id)),
+            ("order".to_string(), format!("/api/orders/{}", self.order_id)),
+        ]);
+        if let Some(wave_id) = self.wave_id {
+            links.insert("wave".to_string(), format!("/api/waves/{}", wave_id));
+        }
+        links
+    }
+}
+
+impl ResourceLinks for Notification {
+    fn links(&self) -> Links {
+        let id = self.notification_id;
+        let mut links = Links::from([
+            ("self".to_string(), format!("/api/notifications/{}", id)),
+            ("acknowledge".to_string(), format!("/api/notifications/{}/acknowledge", id)),
+        ]);
+        match (self.reference_type.as_deref(), self.reference_id) {
+            (Some("SHIPMENT"), Some(shipment_id)) => {
+                links.insert("shipment".to_string(), format!("/api/shipments/{}", shipment_id));
+            }
+            (Some("INTEGRITY_RUN"), Some(run_id)) => {
+                links.insert("integrity_run".to_string(), format!("/api/integrity/runs/{}", run_id));
+            }
+            (Some("ERP_CONNECTOR"), Some(connector_id)) => {
+                links.insert("erp_connector".to_string(), format!("/api/erp/connectors/{}", connector_id));
+            }
+            (Some("ECOMMERCE_CHANNEL"), Some(channel_id)) => {
+                links.insert("ecommerce_channel".to_string(), format!("/api/ecommerce/channels/{}", channel_id));
+            }
+            _ => {}
+        }
+        links
+    }
+}
+
+impl ResourceLinks for Shift {
+    fn links(&self) -> Links {
+        let id = self.shift_id;
+        Links::from([
+            ("self".to_string(), format!("/api/shifts/{}", id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+            ("operators".to_string(), format!("/api/shifts/{}/operators", id)),
+            ("labor".to_string(), format!("/api/reports/labor?shift_id={}", id)),
+        ])
+    }
+}
+
+impl ResourceLinks for Consignor {
+    fn links(&self) -> Links {
+        let id = self.consignor_id;
+        Links::from([
+            ("self".to_string(), format!("/api/consignors/{}", id)),
+            ("stock".to_string(), format!("/api/consignors/{}/stock", id)),
+            ("consumption".to_string(), format!("/api/consignors/{}/consumption", id)),
+            ("settlements".to_string(), format!("/api/consignors/{}/settlements", id)),
+        ])
+    }
+}
+
+impl ResourceLinks for StockInventory {
+    fn links(&self) -> Links {
+        let mut links = Links::from([
+            ("item".to_string(), format!("/api/items/{}", self.item_id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+        ]);
+        // Levels are set on our own stock only
+        if self.consignor_id.is_none() && self.client_id.is_none() {
+            links.insert("self".to_string(), format!("/api/warehouses/{}/stock/{}", self.warehouse_id, self.item_id));
+        }
+        links
+    }
+}
+
+impl ResourceLinks for Project {
+    fn links(&self) -> Links {
+        let id = self.project_id;
+        Links::from([
+            ("self".to_string(), format!("/api/projects/{}", id)),
+            ("issues".to_string(), format!("/api/projects/{}/issues", id)),
+            ("consumption".to_string(), format!("/api/projects/{}/consumption", id)),
+            ("reservations".to_string(), format!("/api/projects/{}/reservations", id)),
+            ("deliveries".to_string(), format!("/api/projects/{}/deliveries", id)),
+        ])
+    }
+}
+
+impl ResourceLinks for SiteDelivery {
+    fn links(&self) -> Links {
+        let id = self.delivery_id;
+        let mut links = Links::from([
+            ("self".to_string(), format!("/api/site-deliveries/{}", id)),
+            ("project".to_string(), format!("/api/projects/{}", self.project_id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+        ]);
+        if self.status == SITE_DELIVERY_REQUESTED {
+            links.insert("schedule".to_string(), format!("/api/site-deliveries/{}/schedule", id));
+        }
+        if self.status != SITE_DELIVERY_CANCELLED {
+            links.insert("cancel".to_string(), format!("/api/site-deliveries/{}/cancel", id));
+        }
+        if let Some(order_id) = self.order_id {
+            links.insert("order".to_string(), format!("/api/orders/{}", order_id));
+        }
+        links
+    }
+}
+
+impl ResourceLinks for ProjectReservation {
+    fn links(&self) -> Links {
+        let mut links = Links::from([
+            ("project".to_string(), format!("/api/projects/{}", self.project_id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+            ("item".to_string(), format!("/api/items/{}", self.item_id)),
+        ]);
+        if self.status == RESERVATION_ACTIVE {
+            links.insert("release".to_string(), format!("/api/project-reservations/{}/release", self.reservation_id));
+        }
+        links
+    }
+}
+
+impl ResourceLinks for RepairOrder {
+    fn links(&self) -> Links {
+        let id = self.repair_id;
+        let mut links = Links::from([
+            ("self".to_string(), format!("/api/repair-orders/{}", id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+            ("item".to_string(), format!("/api/items/{}", self.item_id)),
+        ]);
+        if self.status == REPAIR_OPEN {
+            links.insert("send".to_string(), format!("/api/repair-orders/{}/send", id));
+        }
+        if !self.is_closed() {
+            links.insert("cost".to_string(), format!("/api/repair-orders/{}/cost", id));
+            links.insert("close".to_string(), format!("/api/repair-orders/{}/close", id));
+        }
+        links
+    }
+}
+
+impl ResourceLinks for ToolLoan {
+    fn links(&self) -> Links {
+        let id = self.loan_id;
+        let mut links = Links::from([
+            ("self".to_string(), format!("/api/loans/{}", id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+            ("item".to_string(), format!("/api/items/{}", self.item_id)),
+        ]);
+        if let Some(project_id) = self.project_id {
+            links.insert("project".to_string(), format!("/api/projects/{}", project_id));
+        }
+        if !self.is_returned() {
+            links.insert("return".to_string(), format!("/api/loans/{}/return", id));
+        }
+        links
+    }
+}
+
+impl ResourceLinks for PrintTemplate {
+    fn links(&self) -> Links {
+        Links::from([
+            ("self".to_string(), format!("/api/print-templates/{}", self.template_id)),
+            ("versions".to_string(), format!("/api/print-templates/{}/versions", self.template_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for PrintTemplateAssignment {
+    fn links(&self) -> Links {
+        Links::from([
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+            ("template".to_string(), format!("/api/print-templates/{}", self.template_id)),
+            ("version".to_string(), format!("/api/print-templates/{}/versions/{}", self.template_id, self.version)),
+        ])
+    }
+}
+
+impl ResourceLinks for Printer {
+    fn links(&self) -> Links {
+        let mut links = Links::from([
+            ("self".to_string(), format!("/api/printers/{}", self.printer_id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+        ]);
+        if let Some(station_id) = self.station_id {
+            links.insert("station".to_string(), format!("/api/stations/{}/captures", station_id));
+        }
+        links
+    }
+}
+
+impl ResourceLinks for User {
+    fn links(&self) -> Links {
+        Links::from([("self".to_string(), format!("/api/users/{}", self.user_id))])
+    }
+}
+
+impl ResourceLinks for StockReservation {
+    fn links(&self) -> Links {
+        let mut links = Links::from([
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+            ("item".to_string(), format!("/api/items/{}", self.item_id)),
+        ]);
+        if self.status == RESERVATION_ACTIVE {
+            let release =
+                format!("/api/stock/reservations/{}/release?warehouse_id={}", self.reservation_id, self.warehouse_id);
+            links.insert("release".to_string(), release);
+        }
+        links
+    }
+}
+
+impl ResourceLinks for DeliveryRun {
+    fn links(&self) -> Links {
+        let id = self.run_id;
+        let mut links = Links::from([
+            ("self".to_string(), format!("/api/delivery-runs/{}", id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+        ]);
+        if self.status == RUN_PLANNED {
+            links.insert("shipments".to_string(), format!("/api/delivery-runs/{}/shipments", id));
+            links.insert("dispatch".to_string(), format!("/api/delivery-runs/{}/dispatch", id));
+        }
+        links
+    }
+}
+
+impl ResourceLinks for ProofOfDelivery {
+    fn links(&self) -> Links {
+        let shipment = format!("/api/shipments/{}", self.shipment_id);
+        let mut links = Links::from([
+            ("self".to_string(), format!("{}/proof-of-delivery", shipment)),
+            ("run".to_string(), format!("/api/delivery-runs/{}", self.run_id)),
+        ]);
+        for file in &self.files {
+            let kind = file.file_kind.to_lowercase();
+            links.insert(kind.clone(), format!("{}/proof-of-delivery/{}", shipment, kind));
+        }
+        links.insert("shipment".to_string(), shipment);
+        links
+    }
+}
+
+impl ResourceLinks for BillingClient {
+    fn links(&self) -> Links {
+        let id = self.client_id;
+        Links::from([
+            ("self".to_string(), format!("/api/billing/clients/{}", id)),
+            ("items".to_string(), format!("/api/billing/clients/{}/items", id)),
+            ("rates".to_string(), format!("/api/billing/clients/{}/rates", id)),
+            ("stock".to_string(), format!("/api/billing/clients/{}/stock", id)),
+            ("api-keys".to_string(), format!("/api/billing/clients/{}/api-keys", id)),
+            ("events".to_string(), format!("/api/billing/clients/{}/events", id)),
+            ("invoices".to_string(), format!("/api/billing/clients/{}/invoices", id)),
+        ])
+    }
+}
+
+impl ResourceLinks for BillableEvent {
+    fn links(&self) -> Links {
+        let mut links = Links::from([
+            ("client".to_string(), format!("/api/billing/clients/{}", self.client_id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+        ]);
+        if let Some(invoice_id) = self.invoice_id {
+            links.insert("invoice".to_string(), format!("/api/billing/invoices/{}", invoice_id));
+        }
+        links
+    }
+}
+
+impl ResourceLinks for RfidPortal {
+    fn links(&self) -> Links {
+        Links::from([
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+            ("reads".to_string(), format!("/api/rfid/portals/{}/reads", self.portal_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for Lpn {
+    fn links(&self) -> Links {
+        let mut links = Links::from([
+            ("self".to_string(), format!("/api/lpns/{}", self.lpn_id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+        ]);
+        if let Some(parent_lpn_id) = self.parent_lpn_id {
+            links.insert("parent".to_string(), format!("/api/lpns/{}", parent_lpn_id));
+        }
+        if let Some(receipt_id) = self.receipt_id {
+            links.insert("receipt".to_string(), format!("/api/receipts/{}", receipt_id));
+        }
+        if let Some(shipment_id) = self.shipment_id {
+            links.insert("shipment".to_string(), format!("/api/shipments/{}", shipment_id));
+        }
+        links
+    }
+}
+
+impl ResourceLinks for Recall {
+    fn links(&self) -> Links {
+        Links::from([
+            ("self".to_string(), format!("/api/recalls/{}", self.recall_id)),
+            ("item".to_string(), format!("/api/items/{}", self.item_id)),
+            ("movements".to_string(), format!("/api/movements?item_id={}&movement_type=RECALL_HOLD", self.item_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for StockTransfer {
+    fn links(&self) -> Links {
+        Links::from([
+            ("self".to_string(), format!("/api/transfers/{}", self.transfer_id)),
+            ("source".to_string(), format!("/api/warehouses/{}", self.source_warehouse_id)),
+            ("destination".to_string(), format!("/api/warehouses/{}", self.destination_warehouse_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for PurchaseRequisition {
+    fn links(&self) -> Links {
+        let id = self.requisition_id;
+        let mut links = Links::from([
+            ("self".to_string(), format!("/api/requisitions/{}", id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+        ]);
+        match self.status.as_str() {
+            REQUISITION_DRAFT => {
+                links.insert("approve".to_string(), format!("/api/requisitions/{}/approve", id));
+                links.insert("reject".to_string(), format!("/api/requisitions/{}/reject", id));
+            }
+            REQUISITION_APPROVED => {
+                links.insert("convert".to_string(), format!("/api/requisitions/{}/convert", id));
+            }
+            _ => {}
+        }
+        if let Some(po_id) = self.po_id {
+            links.insert("purchase_order".to_string(), format!("/api/purchase-orders/{}", po_id));
+        }
+        links
+    }
+}
+
+impl ResourceLinks for PurchaseOrder {
+    fn links(&self) -> Links {
+        Links::from([
+            ("self".to_string(), format!("/api/purchase-orders/{}", self.po_id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for EventConsumer {
+    fn links(&self) -> Links {
+        Links::from([("self".to_string(), format!("/api/stock/events/consumers/{}", self.consumer_id))])
+    }
+}
+
+impl ResourceLinks for ErpReconciliation {
+    fn links(&self) -> Links {
+        Links::from([("self".to_string(), format!("/api/reconciliation/erp/{}", self.reconciliation_id))])
+    }
+}
+
+impl ResourceLinks for IntegrityRun {
+    fn links(&self) -> Links {
+        Links::from([("self".to_string(), format!("/api/integrity/runs/{}", self.run_id))])
+    }
+}
+
+impl ResourceLinks for EventDeadLetter {
+    fn links(&self) -> Links {
+        Links::from([
+            ("self".to_string(), format!("/api/stock/events/dead-letters/{}", self.dead_letter_id)),
+            ("redrive".to_string(), format!("/api/stock/events/dead-letters/{}/redrive", self.dead_letter_id)),
+            ("consumer".to_string(), format!("/api/stock/events/consumers/{}", self.consumer_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for MeasuringStation {
+    fn links(&self) -> Links {
+        Links::from([
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+            ("captures".to_string(), format!("/api/stations/{}/captures", self.station_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for StationCapture {
+    fn links(&self) -> Links {
+        let mut links = Links::from([("station".to_string(), format!("/api/stations/{}/captures", self.station_id))]);
+        if let Some(carton_id) = self.carton_id {
+            links.insert("carton".to_string(), format!("/api/cartons/{}", carton_id));
+        }
+        if let Some(item_id) = self.item_id {
+            links.insert("item".to_string(), format!("/api/items/{}", item_id));
+        }
+        links
+    }
+}
+
+impl ResourceLinks for VmiFeed {
+    fn links(&self) -> Links {
+        let id = self.feed_id;
+        Links::from([
+            ("self".to_string(), format!("/api/vmi/feeds/{}", id)),
+            ("preview".to_string(), format!("/api/vmi/feeds/{}/preview", id)),
+            ("run".to_string(), format!("/api/vmi/feeds/{}/run", id)),
+            ("runs".to_string(), format!("/api/vmi/feeds/{}/runs", id)),
+        ])
+    }
+}
+
+impl ResourceLinks for VmiFeedRun {
+    fn links(&self) -> Links {
+        Links::from([("feed".to_string(), format!("/api/vmi/feeds/{}", self.feed_id))])
+    }
+}
+
+impl ResourceLinks for ErpConnector {
+    fn links(&self) -> Links {
+        let id = self.connector_id;
+        Links::from([
+            ("self".to_string(), format!("/api/erp/connectors/{}", id)),
+            ("sync".to_string(), format!("/api/erp/connectors/{}/sync", id)),
+            ("runs".to_string(), format!("/api/erp/connectors/{}/runs", id)),
+        ])
+    }
+}
+
+impl ResourceLinks for ErpSyncRun {
+    fn links(&self) -> Links {
+        Links::from([("connector".to_string(), format!("/api/erp/connectors/{}", self.connector_id))])
+    }
+}
+
+impl ResourceLinks for EcommerceChannel {
+    fn links(&self) -> Links {
+        let id = self.channel_id;
+        Links::from([
+            ("self".to_string(), format!("/api/ecommerce/channels/{}", id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+            ("stock".to_string(), format!("/api/ecommerce/channels/{}/stock", id)),
+            ("sync".to_string(), format!("/api/ecommerce/channels/{}/sync", id)),
+            ("runs".to_string(), format!("/api/ecommerce/channels/{}/runs", id)),
+        ])
+    }
+}
+
+impl ResourceLinks for EcommerceSyncRun {
+    fn links(&self) -> Links {
+        Links::from([("channel".to_string(), format!("/api/ecommerce/channels/{}", self.channel_id))])
+    }
+}
+
+impl ResourceLinks for IntegrationChannel {
+    fn links(&self) -> Links {
+        Links::from([
+            ("self".to_string(), format!("/api/integrations/channels/{}", self.channel_id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.fulfillment_warehouse_id)),
+        ])
+    }
+}
+
+impl ResourceLinks for GlPeriod {
+    fn links(&self) -> Links {
+        let period = self.period.format("%Y-%m");
+        Links::from([
+            ("journal".to_string(), format!("/api/gl/periods/{}/journal", period)),
+            ("export".to_string(), format!("/api/gl/periods/{}/export", period)),
+            ("close".to_string(), format!("/api/gl/periods/{}/close", period)),
+            ("reopen".to_string(), format!("/api/gl/periods/{}/reopen", period)),
+        ])
+    }
+}
+
+impl ResourceLinks for GlExport {
+    fn links(&self) -> Links {
+        Links::from([("journal".to_string(), format!("/api/gl/periods/{}/journal", self.period.format("%Y-%m")))])
+    }
+}
+
+impl ResourceLinks for Task {
+    fn links(&self) -> Links {
+        let id = self.task_id;
+        let mut links = Links::from([
+            ("self".to_string(), format!("/api/tasks/{}", id)),
+            ("warehouse".to_string(), format!("/api/warehouses/{}", self.warehouse_id)),
+            ("claim".to_string(), format!("/api/tasks/{}/claim", id)),
+            ("complete".to_string(), format!("/api/tasks/{}/complete", id)),
+        ]);
+        if let Some(item_id) = self.item_id {
+            links.insert("item".to_string(), format!("/api/items/{}", item_id));
+        }
+        links
+    }
+}
+
+pub fn linked<T: ResourceLinks>(resource: T) -> Linked<T> {
+    let links = resource.links();
+    Linked { resource, links }
+}
+
+/// Attach per-row links and self/first/prev/next/last navigation to an offset page
+pub fn paginated<T: ResourceLinks>(page: PaginatedResponse<T>, uri: &Uri) -> PaginatedResponse<Linked<T>> {
+    let navigation = page_links(&page.pagination, uri);
+    page.map(linked).with_links(navigation)
+}
+
+/// Attach per-row links and self/next navigation to a keyset page
+pub fn keyset<T: ResourceLinks>(page: KeysetPage<T>, uri: &Uri) -> KeysetPage<Linked<T>> {
+    let navigation = keyset_links(page.next_after_id, uri);
+    page.map(linked).with_links(navigation)
+}
+
+pub fn page_links(meta: &PaginationMeta, uri: &Uri) -> Links {
+    let mut links = Links::new();
+    links.insert("self".into(), uri.to_string());
+    links.insert("first".into(), with_param(uri, "page", 1));
+    if meta.has_prev {
+        links.insert("prev".into(), with_param(uri, "page", meta.page - 1));
+    }
+    if meta.has_next {
+        links.insert("next".into(), with_param(uri, "page", meta.page + 1));
+    }
+    if let Some(total_pages) = meta.total_pages {
+        links.insert("last".into(), with_param(uri, "page", total_pages.max(1)));
+    }
+    links
+}
+
+pub fn keyset_links(next_after_id: Option<i64>, uri: &Uri) -> Links {
+    let mut links = Links::new();
+    links.insert("self".into(), uri.to_string());
+    if let Some(after_id) = next_after_id {
+        links.insert("next".into(), with_param(uri, "after_id", after_id));
+    }
+    links
+}
+
+/// Rebuild the request URI with one query parameter replaced
+fn with_param(uri: &Uri, name: &str, value: impl ToString) -> String {
+    let prefix = format!("{}=", name);
+    let mut params: Vec<String> = uri
+        .query()
+        .unwrap_or("")
+        .split('&')
+        .filter(|param| !param.is_empty() && !param.starts_with(&prefix))
+        .map(str::to_string)
+        .collect();
+    params.push(format!("{}{}", prefix, value.to_string()));
+
+    format!("{}?{}", uri.path(), params.join("&"))
+}
+
+/// Percent-encode a query parameter value
+fn encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
@@ -0,0 +1,46 @@
+//! Picks the language of each API response: the locale the signed-in user saved, else
+//! the best match for `Accept-Language`, else English. Handlers run inside it, so errors
+//! and labels come out in that language; the choice is echoed in `Content-Language`.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::warn;
+
+use warehouse_core::auth::CurrentUser;
+use warehouse_core::i18n::{self, Locale};
+use warehouse_core::AppState;
+
+pub async fn negotiate(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let locale = preferred(&state, request.headers()).await;
+    let mut response = i18n::scope(locale, next.run(request)).await;
+
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_LANGUAGE, HeaderValue::from_static(locale.code()));
+    headers.append(header::VARY, HeaderValue::from_static("accept-language"));
+    response
+}
+
+async fn preferred(state: &AppState, headers: &HeaderMap) -> Locale {
+    let user = CurrentUser::from_headers(headers, &state.config.security.jwt_secret);
+    if let Some(user) = user.filter(|_| !state.db.is_mock()) {
+        match state.db.preferences().get(&user.username).await {
+            Ok(Some(preference)) => {
+                if let Some(locale) = Locale::parse(&preference.locale) {
+                    return locale;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Cannot read the preferences of {}: {}", user.username, e),
+        }
+    }
+
+    headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(Locale::from_accept_language)
+        .unwrap_or_default()
+}
@@ -1,44 +1,95 @@
 use anyhow::Result;
+use clap::Parser;
 use axum::{
-    extract::{Path, Query, State},
-    response::Json,
-    routing::get,
+    extract::{OriginalUri, Path, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Json, Response},
+    middleware,
+    routing::{delete, get, post, put},
     Router,
 };
 use dotenvy::dotenv;
-use sqlx::PgPool;
-use std::env;
 use tower::ServiceBuilder;
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
+use std::net::SocketAddr;
+use tower_http::trace::TraceLayer;
 use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use warehouse_core::{AppError, AppResult, AppState, Config};
-use warehouse_db::Database;
+use warehouse_core::auth::{self, DeleteWarehouses, ManageItems, ManageWarehouses, RequirePermission};
+use warehouse_core::{paging, AppError, AppResult, AppState, Config, JobLocks};
+use warehouse_db::{Database, DatabaseManager, MemoryStore, ITEM_ORDER, WAREHOUSE_ORDER};
 use warehouse_models::*;
+use warehouse_models::validator::Validate;
+
+mod cli;
+mod content;
+mod handlers;
+mod jobs;
+mod jsonapi;
+mod links;
+mod locale;
+mod mock;
+mod runtime;
+
+use content::ResponseFormat;
+use links::ResourceLinks;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
 
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "warehouse_api=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
-    let config = Config::from_env()?;
+    let cli = cli::Cli::parse();
+    let config = if cli.mock { Config::from_env_mock()? } else { Config::from_env()? };
+    let log_filter = runtime::init_tracing(&config.runtime.log_filter);
     config.validate()?;
 
+    match cli.command {
+        Some(cli::Command::Generate(args)) => return cli::run_generate(&config, args).await,
+        Some(cli::Command::Replay(args)) => return cli::run_replay(&config, args).await,
+        Some(cli::Command::Restore(args)) => return cli::run_restore(&config, args).await,
+        Some(cli::Command::CreateUser(args)) => return cli::run_create_user(&config, args).await,
+        None => {}
+    }
+
     info!("Starting warehouse system in {} mode", config.server.environment);
 
-    let pool = PgPool::connect(&config.database.url).await?;
-    sqlx::migrate!("../migrations").run(&pool).await?;
-    
-    let db = Database::new(pool);
-    let app_state = AppState::new(db, config.clone());
+    let db = if config.server.mock_mode {
+        info!("Mock mode: serving in-memory seed data, no database connection");
+        Database::in_memory(MemoryStore::seeded())?
+    } else {
+        let pool = DatabaseManager::connect(&config.database.connection_settings()).await?;
+        sqlx::migrate!("../migrations").run(&pool).await?;
+        let mut db = Database::new(pool).with_event_sourced_stock(config.stock.event_sourced);
+        let mut sharded = Vec::new();
+        for shard in &config.shards {
+            let shard_pool = DatabaseManager::connect(&config.database.shard_settings(shard)).await?;
+            sqlx::migrate!("../migrations").run(&shard_pool).await?;
+            info!("Database shard {} serves warehouses {:?}", shard.name, shard.warehouse_ids);
+            db = db.with_shard(&shard.warehouse_ids, shard_pool);
+            sharded.extend(shard.warehouse_ids.iter().map(|id| (*id, shard.name.clone())));
+        }
+        db.shards().assign(&sharded).await?;
+        db.shards().copy_master_data().await?;
+        db
+    };
+    let locks = JobLocks::from_config(&config.redis).await?;
+    let app_state = AppState::new(db, config.clone()).with_job_locks(locks);
+    runtime::spawn_log_filter_watcher(app_state.runtime.clone(), log_filter);
+    runtime::spawn_sighup_reloader(app_state.runtime.clone())?;
+    if !config.server.mock_mode {
+        jobs::spawn_tracking_poller(app_state.clone());
+        jobs::spawn_vmi_scheduler(app_state.clone());
+        jobs::spawn_billing_capture(app_state.clone());
+        jobs::spawn_requisition_generation(app_state.clone());
+        jobs::spawn_reservation_expiry(app_state.clone());
+        jobs::spawn_overdue_loans(app_state.clone());
+        jobs::spawn_read_model_projector(app_state.clone());
+        jobs::spawn_event_delivery(app_state.clone());
+        jobs::spawn_integrity_checks(app_state.clone());
+        jobs::spawn_erp_sync(app_state.clone());
+        jobs::spawn_shard_copy(app_state.clone());
+        jobs::spawn_ecommerce_sync(app_state.clone());
+        jobs::spawn_report_delivery(app_state.clone());
+    }
 
     let app = create_app(app_state);
 
@@ -46,7 +97,7 @@ async fn main() -> Result<()> {
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     
     info!("Server starting on {}", addr);
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     Ok(())
 }
@@ -57,16 +108,337 @@ pub fn create_app(state: AppState) -> Router {
         .route("/health", get(health))
         .route("/api/warehouses", get(list_warehouses).post(create_warehouse))
         .route("/api/warehouses/:id", get(get_warehouse).put(update_warehouse).delete(delete_warehouse))
-        .route("/api/items", get(list_items).post(create_item))
-        .route("/api/items/:id", get(get_item))
+        .route("/api/warehouses/:id/zones", get(handlers::locations::list_zones).post(handlers::locations::create_zone))
+        .route("/api/warehouses/:id/utilization", get(handlers::locations::get_utilization))
+        .route("/api/warehouses/:id/shifts", get(handlers::shifts::list_shifts).post(handlers::shifts::create_shift))
+        .route("/api/shifts/:id", get(handlers::shifts::get_shift).put(handlers::shifts::update_shift))
+        .route("/api/shifts/:id/operators", post(handlers::shifts::add_shift_operators))
+        .route("/api/shifts/:id/operators/:operator_id", delete(handlers::shifts::remove_shift_operator))
+        .route("/api/warehouses/:id/box-sizes", get(handlers::cartonization::list_box_sizes).post(handlers::cartonization::create_box_size))
+        .route("/api/box-sizes/:id", put(handlers::cartonization::update_box_size))
+        .route("/api/box-sizes/:id/materials", get(handlers::packaging::get_box_materials).put(handlers::packaging::set_box_materials))
+        .route("/api/warehouses/:id/reorder-alerts", get(handlers::packaging::list_reorder_alerts))
+        .route("/api/warehouses/:id/requisitions", get(handlers::purchasing::list_requisitions))
+        .route("/api/warehouses/:id/requisitions/generate", post(handlers::purchasing::generate_requisitions))
+        .route("/api/requisitions/:id", get(handlers::purchasing::get_requisition))
+        .route("/api/requisitions/:id/lines/:item_id", put(handlers::purchasing::set_requisition_line))
+        .route("/api/requisitions/:id/approve", post(handlers::purchasing::approve_requisition))
+        .route("/api/requisitions/:id/reject", post(handlers::purchasing::reject_requisition))
+        .route("/api/requisitions/:id/convert", post(handlers::purchasing::convert_requisition))
+        .route("/api/warehouses/:id/purchase-orders", get(handlers::purchasing::list_purchase_orders))
+        .route("/api/purchase-orders/:id", get(handlers::purchasing::get_purchase_order))
+        .route("/api/warehouses/:id/dock-board", get(handlers::dock::get_dock_board))
+        .route("/api/warehouses/:id/dock-board/arrivals", post(handlers::dock::check_in_arrival))
+        .route("/api/warehouses/:id/stock", get(handlers::stock::list_warehouse_stock))
+        .route("/api/warehouses/:id/negative-stock-policy", get(handlers::negative_stock::list_policies).put(handlers::negative_stock::set_warehouse_policy))
+        .route("/api/warehouses/:id/negative-stock-policy/:item_id", put(handlers::negative_stock::set_item_policy).delete(handlers::negative_stock::clear_item_policy))
+        .route("/api/warehouses/:id/lpns", get(handlers::lpns::list_lpns).post(handlers::lpns::create_lpn))
+        .route("/api/lpns/:id", get(handlers::lpns::get_lpn))
+        .route("/api/warehouses/:id/stations", get(handlers::stations::list_stations).post(handlers::stations::create_station))
+        .route("/api/stations/:id/captures", get(handlers::stations::list_captures).post(handlers::stations::record_capture))
+        .route("/api/warehouses/:id/rfid-portals", get(handlers::rfid::list_portals).post(handlers::rfid::create_portal))
+        .route("/api/rfid/tags", post(handlers::rfid::register_tags))
+        .route("/api/rfid/tags/:epc", get(handlers::rfid::get_tag))
+        .route("/api/zones/:id/locations", get(handlers::locations::list_locations).post(handlers::locations::create_location))
+        .route("/api/locations/:id/capacity", put(handlers::locations::set_location_capacity))
+        .route("/api/zones/:id/hazmat", put(handlers::hazmat::set_zone_hazmat))
+        .route("/api/zones/:id/thresholds", get(handlers::environment::get_thresholds).put(handlers::environment::set_thresholds))
+        .route("/api/zones/:id/sensors", get(handlers::environment::list_sensors).post(handlers::environment::create_sensor))
+        .route("/api/zones/:id/environment", get(handlers::environment::get_environment_history))
+        .route("/api/sensors/readings", post(handlers::environment::ingest_readings))
+        .route("/api/excursions", get(handlers::environment::list_excursions))
+        .route("/api/items/:id/stock", get(handlers::stock::list_item_stock))
+        .route("/api/items/:id/availability", get(handlers::stock::get_item_availability))
+        .route("/api/items/:id/hazmat", put(handlers::hazmat::classify_item).delete(handlers::hazmat::declassify_item))
+        .route("/api/items/:id/catch-weight", put(handlers::catch_weight::set_catch_weight).delete(handlers::catch_weight::clear_catch_weight))
+        .route("/api/items/:id/suppliers", get(handlers::purchasing::list_item_suppliers))
+        .route("/api/items/:id/suppliers/:supplier_code", put(handlers::purchasing::set_item_supplier))
+        .route("/api/items/:id/customs", put(handlers::customs::set_item_customs).delete(handlers::customs::clear_item_customs))
+        .route("/api/movements", get(handlers::movements::list_movements))
+        .route("/api/stock", get(handlers::stock::get_stock))
+        .route("/api/stock/read-model", get(handlers::stock::read_model_status))
+        .route("/api/stock/stock-outs", get(handlers::stock_outs::list_stock_outs))
+        .route("/api/stock/events", get(handlers::stock::list_events))
+        .route("/api/stock/events/rebuild", post(handlers::stock::rebuild_projection))
+        .route("/api/stock/events/replay", post(handlers::events::replay))
+        .route("/api/stock/events/consumers", get(handlers::events::list_consumers).post(handlers::events::create_consumer))
+        .route("/api/stock/events/consumers/:id", get(handlers::events::get_consumer))
+        .route("/api/stock/events/dead-letters", get(handlers::events::list_dead_letters))
+        .route("/api/stock/events/dead-letters/:id", get(handlers::events::get_dead_letter))
+        .route("/api/stock/events/dead-letters/:id/redrive", post(handlers::events::redrive_dead_letter))
+        .route("/api/ledger/entries", get(handlers::ledger::list_ledger_entries))
+        .route("/api/ledger/integrity", get(handlers::ledger::check_ledger_integrity))
+        .route("/api/reconciliation/erp", get(handlers::reconciliation::list_reconciliations).post(handlers::reconciliation::reconcile))
+        .route("/api/reconciliation/erp/:id", get(handlers::reconciliation::get_reconciliation))
+        .route("/api/integrity/runs", get(handlers::integrity::list_runs).post(handlers::integrity::run_checks))
+        .route("/api/integrity/runs/:id", get(handlers::integrity::get_run))
+        .route("/api/transfers/:id", get(handlers::transfers::get_transfer))
+        .route("/api/consignors", get(handlers::consignment::list_consignors).post(handlers::consignment::create_consignor))
+        .route("/api/consignors/:id", get(handlers::consignment::get_consignor))
+        .route("/api/consignors/:id/prices", put(handlers::consignment::set_prices))
+        .route("/api/consignors/:id/stock", get(handlers::consignment::get_consigned_stock))
+        .route("/api/consignors/:id/consumption", get(handlers::consignment::get_consumption))
+        .route("/api/consignors/:id/settlements", get(handlers::consignment::list_settlements).post(handlers::consignment::create_settlement))
+        .route("/api/consignment-settlements/:id", get(handlers::consignment::get_settlement))
+        .route("/api/projects", get(handlers::projects::list_projects).post(handlers::projects::create_project))
+        .route("/api/projects/:id", get(handlers::projects::get_project).put(handlers::projects::update_project))
+        .route("/api/projects/:id/consumption", get(handlers::projects::get_consumption))
+        .route(
+            "/api/projects/:id/deliveries",
+            get(handlers::deliveries::list_project_deliveries).post(handlers::deliveries::request_delivery),
+        )
+        .route("/api/site-deliveries/:id", get(handlers::deliveries::get_delivery))
+        .route("/api/site-deliveries/:id/schedule", post(handlers::deliveries::schedule_delivery))
+        .route("/api/site-deliveries/:id/cancel", post(handlers::deliveries::cancel_delivery))
+        .route(
+            "/api/warehouses/:id/delivery-calendar",
+            get(handlers::deliveries::get_calendar).put(handlers::deliveries::set_calendar),
+        )
+        .route(
+            "/api/warehouses/:id/vehicles",
+            get(handlers::deliveries::list_vehicles).post(handlers::deliveries::add_vehicle),
+        )
+        .route("/api/warehouses/:id/delivery-schedule", get(handlers::deliveries::get_schedule))
+        .route("/api/vehicles/:id", put(handlers::deliveries::update_vehicle))
+        .route("/api/warehouses/:id/drivers", get(handlers::fleet::list_drivers).post(handlers::fleet::add_driver))
+        .route("/api/drivers/:id", put(handlers::fleet::update_driver))
+        .route("/api/warehouses/:id/delivery-runs", get(handlers::fleet::list_runs).post(handlers::fleet::create_run))
+        .route("/api/warehouses/:id/route-plans", post(handlers::fleet::plan_routes))
+        .route("/api/delivery-runs/:id", get(handlers::fleet::get_run))
+        .route("/api/delivery-runs/:id/shipments", post(handlers::fleet::assign_shipments))
+        .route("/api/delivery-runs/:id/dispatch", post(handlers::fleet::dispatch_run))
+        .route(
+            "/api/shipments/:id/proof-of-delivery",
+            get(handlers::fleet::get_proof).post(handlers::fleet::record_proof),
+        )
+        .route(
+            "/api/shipments/:id/proof-of-delivery/:kind",
+            get(handlers::fleet::get_proof_file).put(handlers::fleet::upload_proof_file),
+        )
+        .route(
+            "/api/returnable-types",
+            get(handlers::returnables::list_types).post(handlers::returnables::create_type),
+        )
+        .route("/api/returnables/balances", get(handlers::returnables::get_balances))
+        .route("/api/returnables/aging", get(handlers::returnables::get_aging))
+        .route("/api/billing/clients", get(handlers::billing::list_clients).post(handlers::billing::create_client))
+        .route("/api/billing/clients/:id", get(handlers::billing::get_client))
+        .route("/api/billing/clients/:id/items", get(handlers::billing::list_client_items).post(handlers::billing::assign_client_items))
+        .route("/api/billing/clients/:id/rates", get(handlers::billing::get_rates).put(handlers::billing::set_rates))
+        .route("/api/billing/clients/:id/services", post(handlers::billing::record_service))
+        .route("/api/billing/clients/:id/events", get(handlers::billing::list_events))
+        .route("/api/billing/clients/:id/invoices", get(handlers::billing::list_invoices).post(handlers::billing::create_invoice))
+        .route("/api/billing/invoices/:id", get(handlers::billing::get_invoice))
+        .route("/api/billing/invoice-lines", get(handlers::billing::export_invoice_lines))
+        .route("/api/billing/capture", post(handlers::billing::capture))
+        .route("/api/billing/clients/:id/stock", get(handlers::clients::get_client_stock))
+        .route("/api/billing/clients/:id/api-keys", get(handlers::clients::list_api_keys).post(handlers::clients::issue_api_key))
+        .route("/api/billing/clients/:id/api-keys/:key_id", delete(handlers::clients::revoke_api_key))
+        .route("/api/client/items", get(handlers::clients::my_items))
+        .route("/api/client/stock", get(handlers::clients::my_stock))
+        .route("/api/client/movements", get(handlers::clients::my_movements))
+        .route("/api/orders", get(handlers::orders::list_orders).post(handlers::orders::create_order))
+        .route("/api/orders/:id", get(handlers::orders::get_order))
+        .route("/api/orders/:id/rates", get(handlers::orders::quote_order_rates))
+        .route("/api/orders/:id/cartonization", get(handlers::cartonization::suggest_cartons))
+        .route("/api/orders/:id/supplier-confirmation", post(handlers::orders::confirm_dropship_order))
+        .route("/api/orders/:id/supplier-shipments", post(handlers::orders::record_supplier_shipment))
+        .route("/api/orders/:id/shipments", get(handlers::orders::list_order_shipments))
+        .route("/api/shipments/:id", get(handlers::orders::get_shipment))
+        .route("/api/shipments/:id/hazmat", get(handlers::hazmat::get_shipment_declaration))
+        .route("/api/shipments/:id/customs", get(handlers::customs::get_shipment_declaration))
+        .route("/api/shipments/:id/tracking", get(handlers::tracking::get_shipment_tracking))
+        .route("/api/shipments/:id/documents", get(handlers::documents::list_shipment_documents).post(handlers::documents::generate_shipment_document))
+        .route("/api/shipment-documents/:id", get(handlers::documents::get_document_pdf))
+        .route("/api/document-brandings", get(handlers::documents::list_brandings))
+        .route("/api/document-brandings/default", put(handlers::documents::set_default_branding))
+        .route("/api/warehouses/:id/document-branding", put(handlers::documents::set_warehouse_branding))
+        .route("/api/print-templates", get(handlers::templates::list_templates).post(handlers::templates::create_template))
+        .route("/api/print-templates/:id", get(handlers::templates::get_template).put(handlers::templates::update_template).delete(handlers::templates::delete_template))
+        .route("/api/print-templates/:id/versions", get(handlers::templates::list_versions))
+        .route("/api/print-templates/:id/versions/:version", get(handlers::templates::get_version))
+        .route("/api/print-templates/:id/versions/:version/restore", post(handlers::templates::restore_version))
+        .route("/api/warehouses/:id/print-templates", get(handlers::templates::list_assignments).put(handlers::templates::assign_template))
+        .route("/api/warehouses/:id/print-templates/:purpose", delete(handlers::templates::unassign_template))
+        .route("/api/warehouses/:id/printers", get(handlers::printers::list_printers).post(handlers::printers::create_printer))
+        .route("/api/printers/:id", get(handlers::printers::get_printer).put(handlers::printers::update_printer))
+        .route("/api/warehouses/:id/print-jobs", post(handlers::printers::print))
+        .route("/api/vmi/feeds", get(handlers::vmi::list_feeds).post(handlers::vmi::create_feed))
+        .route("/api/vmi/feeds/:id", get(handlers::vmi::get_feed).put(handlers::vmi::update_feed))
+        .route("/api/vmi/feeds/:id/preview", get(handlers::vmi::preview_feed))
+        .route("/api/vmi/feeds/:id/run", post(handlers::vmi::run_feed))
+        .route("/api/vmi/feeds/:id/runs", get(handlers::vmi::list_feed_runs))
+        .route("/api/erp/connectors", get(handlers::erp::list_connectors).post(handlers::erp::create_connector))
+        .route("/api/erp/connectors/:id", get(handlers::erp::get_connector).put(handlers::erp::update_connector))
+        .route("/api/erp/connectors/:id/sync", post(handlers::erp::sync_connector))
+        .route("/api/erp/connectors/:id/runs", get(handlers::erp::list_sync_runs))
+        .route("/api/gl/accounts", get(handlers::gl::list_accounts))
+        .route("/api/gl/accounts/:category", put(handlers::gl::update_account))
+        .route("/api/gl/periods", get(handlers::gl::list_periods))
+        .route("/api/gl/periods/:period/journal", get(handlers::gl::get_journal))
+        .route("/api/gl/periods/:period/export", post(handlers::gl::export_journal))
+        .route("/api/gl/periods/:period/close", post(handlers::gl::close_period))
+        .route("/api/gl/periods/:period/reopen", post(handlers::gl::reopen_period))
+        .route("/api/gl/exports", get(handlers::gl::list_exports))
+        .route("/api/ecommerce/channels", get(handlers::ecommerce::list_channels).post(handlers::ecommerce::create_channel))
+        .route("/api/ecommerce/channels/:id", get(handlers::ecommerce::get_channel).put(handlers::ecommerce::update_channel))
+        .route("/api/ecommerce/channels/:id/stock", get(handlers::ecommerce::get_stock_levels))
+        .route("/api/ecommerce/channels/:id/sync", post(handlers::ecommerce::sync_channel))
+        .route("/api/ecommerce/channels/:id/runs", get(handlers::ecommerce::list_sync_runs))
+        .route("/api/integrations/channels", get(handlers::integrations::list_channels).post(handlers::integrations::create_channel))
+        .route("/api/integrations/channels/:id", get(handlers::integrations::get_channel).put(handlers::integrations::update_channel))
+        .route("/api/integrations/orders", post(handlers::integrations::intake_order))
+        .route("/api/notifications", get(handlers::notifications::list_notifications))
+        .route("/api/notifications/:id", get(handlers::notifications::get_notification))
+        .route("/api/notifications/:id/acknowledge", post(handlers::notifications::acknowledge_notification))
+        .route("/api/waves", get(handlers::waves::list_waves).post(handlers::waves::create_wave))
+        .route("/api/waves/:id", get(handlers::waves::get_wave))
+        .route("/api/waves/:id/release", post(handlers::waves::release_wave))
+        .route("/api/waves/:id/cancel", post(handlers::waves::cancel_wave))
+        .route("/api/cartons/:id", get(handlers::waves::get_carton))
+        .route("/api/tasks", get(handlers::tasks::list_tasks).post(handlers::tasks::create_task))
+        .route("/api/tasks/claim-next", post(handlers::tasks::claim_next_task))
+        .route("/api/tasks/queue-depth", get(handlers::tasks::queue_depth))
+        .route("/api/tasks/:id", get(handlers::tasks::get_task))
+        .route("/api/tasks/:id/assign", post(handlers::tasks::assign_task))
+        .route("/api/tasks/:id/claim", post(handlers::tasks::claim_task))
+        .route("/api/tasks/:id/complete", post(handlers::tasks::complete_task))
+        .route("/api/tasks/:id/cancel", post(handlers::tasks::cancel_task))
+        .route("/api/reports/labor", get(handlers::reports::labor_report))
+        .route("/api/reports/consumable-usage", get(handlers::reports::consumable_usage_report))
+        .route("/api/reports/lost-demand", get(handlers::reports::lost_demand_report))
+        .route("/api/kpis/otif", get(handlers::kpis::otif))
+        .route("/api/kpis/fill-rate", get(handlers::kpis::fill_rate))
+        .route("/api/kpis/dock-to-stock", get(handlers::kpis::dock_to_stock))
+        .route("/api/kpis/inventory-accuracy", get(handlers::kpis::inventory_accuracy))
+        .route("/api/reports/catalog", get(handlers::reports::report_catalog))
+        .route("/api/reports/custom", get(handlers::reports::list_custom_reports).post(handlers::reports::create_custom_report))
+        .route("/api/reports/custom/:id", get(handlers::reports::get_custom_report).put(handlers::reports::update_custom_report).delete(handlers::reports::delete_custom_report))
+        .route("/api/reports/custom/:id/run", get(handlers::reports::run_custom_report))
+        .route("/api/reports/custom/:id/export", post(handlers::reports::export_custom_report))
+        .route("/api/reports/custom/:id/schedules", get(handlers::reports::list_report_schedules).post(handlers::reports::create_report_schedule))
+        .route("/api/reports/schedules/:id", get(handlers::reports::get_report_schedule).put(handlers::reports::update_report_schedule).delete(handlers::reports::delete_report_schedule))
+        .route("/api/reports/schedules/:id/deliver", post(handlers::reports::deliver_report_schedule))
+        .route("/api/reports/schedules/:id/runs", get(handlers::reports::list_report_deliveries))
+        .route("/api/jobs", get(handlers::jobs::list_jobs))
+        .route("/api/jobs/:id", get(handlers::jobs::get_job))
+        .route("/api/job-artifacts/:id", get(handlers::jobs::download_artifact))
+        .route("/api/labels", get(handlers::i18n::list_labels))
+        .route("/api/preferences", get(handlers::i18n::get_preferences).put(handlers::i18n::set_preferences))
+        .route("/api/auth/me", get(handlers::auth::get_me))
+        .route("/api/users", get(handlers::users::list_users).post(handlers::users::create_user))
+        .route("/api/users/:id", get(handlers::users::get_user).put(handlers::users::update_user).delete(handlers::users::delete_user))
+        .route("/api/api-keys", get(handlers::api_keys::list_api_keys).post(handlers::api_keys::issue_api_key))
+        .route("/api/api-keys/:id", delete(handlers::api_keys::revoke_api_key))
+        .route("/api/audit", get(handlers::audit::list_audit))
+        .route("/api/views", get(handlers::views::list_views).post(handlers::views::create_view))
+        .route("/api/views/:id", get(handlers::views::get_view).put(handlers::views::update_view).delete(handlers::views::delete_view))
+        .route("/api/sync", get(handlers::sync::get_sync_delta))
+        .route("/api/recalls/:id", get(handlers::recalls::get_recall))
+        .route("/api/recalls/:id/close", post(handlers::recalls::close_recall))
+        .route("/api/repair-orders/:id", get(handlers::repairs::get_repair))
+        .route("/api/repair-orders/:id/send", post(handlers::repairs::send_repair))
+        .route("/api/repair-orders/:id/cost", put(handlers::repairs::record_repair_cost))
+        .route("/api/loans/overdue", get(handlers::loans::list_overdue_loans))
+        .route("/api/loans/:id", get(handlers::loans::get_loan))
+        .route("/api/receipts/:id", get(handlers::receipts::get_receipt))
+        .route("/api/edi/inbound", post(handlers::edi::receive_inbound))
+        .route("/api/edi/documents", get(handlers::edi::list_documents))
+        .route("/api/edi/documents/:id", get(handlers::edi::get_document))
+        .route("/api/edi/documents/:id/content", get(handlers::edi::get_document_content))
+        .route("/api/inspections/:id", get(handlers::quality::get_inspection))
+        .route("/api/returns/:id", get(handlers::returns::get_return))
+        .route("/api/admin/config", get(handlers::admin::get_runtime_config))
+        .route("/api/admin/config/reload", post(handlers::admin::reload_runtime_config))
+        .route("/api/admin/migrations", get(handlers::admin::get_migrations))
+        .route("/api/admin/migrations/run", post(handlers::admin::run_migrations))
+        .route("/api/admin/backups", get(handlers::admin::list_backups).post(handlers::admin::create_backup))
+        .route("/api/admin/backups/:id", get(handlers::admin::get_backup))
+        .route("/api/admin/backups/:id/download", get(handlers::admin::download_backup))
+        .route("/api/admin/tenants", get(handlers::admin::list_tenants).post(handlers::admin::provision_tenant))
+        .route("/api/admin/tenants/:id", get(handlers::admin::get_tenant))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::guard_changes))
+        .merge(stock_routes(state.clone()))
+        .merge(open_routes())
         .layer(
             ServiceBuilder::new()
                 .layer(TraceLayer::new_for_http())
-                .layer(CorsLayer::permissive())
+                .layer(runtime::cors(state.runtime.clone()))
+                .layer(middleware::from_fn_with_state(state.clone(), locale::negotiate))
+                .layer(middleware::from_fn_with_state(state.db.clone(), mock::only_mock_routes))
+                .layer(middleware::from_fn_with_state(state.clone(), auth::api_keys))
+                .layer(middleware::from_fn_with_state(
+                    (state.clone(), runtime::RateLimiter::default()),
+                    runtime::rate_limit,
+                ))
+                .layer(middleware::from_fn_with_state(state.clone(), auth::audit_actor))
         )
         .with_state(state)
 }
 
+/// Routes whose changes are stock writes, each taking a `RequirePermission` with the stock
+/// write scope; the only routes a stock-write API key may change things through
+fn stock_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/api/warehouses/:id/stock-levels/:item_id", put(handlers::packaging::set_stock_levels))
+        .route("/api/warehouses/:id/stock/:item_id", put(handlers::stock::upsert_stock))
+        .route("/api/warehouses/:id/stock/:item_id/issues", post(handlers::stock::issue_stock))
+        .route("/api/lpns/:id/move", post(handlers::lpns::move_lpn))
+        .route("/api/lpns/:id/contents/:item_id", put(handlers::lpns::set_lpn_content))
+        .route("/api/rfid/portals/:id/reads", post(handlers::rfid::ingest_reads))
+        .route("/api/warehouses/:id/consumption", get(handlers::consumption::list_consumption).post(handlers::consumption::ingest_consumption))
+        .route("/api/items", get(list_items).post(create_item))
+        .route("/api/items/:id", get(get_item).put(update_item).delete(delete_item))
+        .route("/api/items/:id/restore", post(restore_item))
+        .route(
+            "/api/stock/reservations",
+            get(handlers::reservations::list_reservations).post(handlers::reservations::create_reservation),
+        )
+        .route("/api/stock/reservations/:id/release", post(handlers::reservations::release_reservation))
+        .route("/api/transfers", get(handlers::transfers::list_transfers).post(handlers::transfers::create_transfer))
+        .route("/api/transfers/:id/dispatch", post(handlers::transfers::dispatch_transfer))
+        .route("/api/transfers/:id/receive", post(handlers::transfers::receive_transfer))
+        .route("/api/transfers/:id/compensate", post(handlers::transfers::compensate_transfer))
+        .route("/api/consignors/:id/consume", post(handlers::consignment::consume))
+        .route("/api/projects/:id/issues", post(handlers::projects::issue_to_project))
+        .route(
+            "/api/projects/:id/reservations",
+            get(handlers::projects::list_reservations).post(handlers::projects::reserve_for_project),
+        )
+        .route("/api/project-reservations/:id/release", post(handlers::projects::release_reservation))
+        .route("/api/shipments/:id/returnables", post(handlers::returnables::issue_with_shipment))
+        .route("/api/returnables/returns", post(handlers::returnables::receive_returnables))
+        .route("/api/orders/:id/ship", post(handlers::orders::ship_order))
+        .route("/api/waves/:id/tasks/:task_id/pick", post(handlers::waves::confirm_pick))
+        .route("/api/waves/:id/pack", post(handlers::waves::pack_carton))
+        .route("/api/sync/mutations", post(handlers::sync::submit_sync_mutations))
+        .route("/api/recalls", get(handlers::recalls::list_recalls).post(handlers::recalls::create_recall))
+        .route("/api/repair-orders", get(handlers::repairs::list_repairs).post(handlers::repairs::create_repair))
+        .route("/api/repair-orders/:id/close", post(handlers::repairs::close_repair))
+        .route("/api/loans", get(handlers::loans::list_loans).post(handlers::loans::create_loan))
+        .route("/api/loans/:id/return", post(handlers::loans::return_loan))
+        .route("/api/receipts", get(handlers::receipts::list_receipts).post(handlers::receipts::create_receipt))
+        .route("/api/receipts/:id/receive", post(handlers::receipts::receive_goods))
+        .route("/api/receipts/:id/lpns/:lpn_number/receive", post(handlers::receipts::receive_lpn))
+        .route("/api/inspections", get(handlers::quality::list_inspections).post(handlers::quality::create_inspection))
+        .route("/api/inspections/:id/result", post(handlers::quality::record_inspection))
+        .route("/api/returns", get(handlers::returns::list_returns).post(handlers::returns::create_return))
+        .route("/api/returns/:id/receive", post(handlers::returns::receive_return))
+        .route("/api/returns/:id/inspect", post(handlers::returns::inspect_return))
+        .route("/api/returns/:id/cancel", post(handlers::returns::cancel_return))
+        .route_layer(middleware::from_fn_with_state(state, auth::guard_stock_changes))
+}
+
+/// Routes that change things without an API key or a signed-in user: signing in, and
+/// carrier webhooks, which carry their own token
+fn open_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/auth/login", post(handlers::auth::login))
+        .route("/api/auth/refresh", post(handlers::auth::refresh))
+        .route("/api/auth/logout", post(handlers::auth::logout))
+        .route("/api/carriers/:code/tracking", post(handlers::tracking::receive_tracking_webhook))
+}
+
 async fn root() -> &'static str {
     "Warehouse Management System API v1.0"
 }
@@ -119,41 +491,129 @@ async fn health(State(state): State<AppState>) -> AppResult<Json<HealthStatus>>
 async fn list_warehouses(
     Query(pagination): Query<PaginationQuery>,
     State(state): State<AppState>,
-) -> AppResult<Json<ApiResponse<PaginatedResponse<Warehouse>>>> {
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    paging::check_page(&state.config.pagination, &WAREHOUSE_ORDER, &pagination)?;
     let result = state.db.warehouses().list(pagination).await?;
-    Ok(Json(ApiResponse::success(result)))
+
+    Ok(match content::negotiate(&headers) {
+        ResponseFormat::JsonApi => jsonapi::collection(&result, &uri),
+        _ => Json(ApiResponse::success(links::paginated(result, &uri))).into_response(),
+    })
 }
 
 async fn get_warehouse(
     Path(id): Path<i32>,
     State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let warehouse = state
+        .db
+        .warehouses()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("warehouse"))?;
+
+    Ok(match content::negotiate(&headers) {
+        ResponseFormat::JsonApi => jsonapi::single(&warehouse),
+        _ => {
+            let resource_links = warehouse.links();
+            Json(ApiResponse::success(warehouse).with_links(resource_links)).into_response()
+        }
+    })
+}
+
+async fn create_warehouse(
+    _: RequirePermission<ManageWarehouses>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateWarehouse>,
 ) -> AppResult<Json<ApiResponse<Warehouse>>> {
-    match state.db.warehouses().get_by_id(id).await? {
-        Some(warehouse) => Ok(Json(ApiResponse::success(warehouse))),
+    payload.validate().map_err(AppError::validation)?;
+
+    if state.db.warehouses().code_exists(&payload.warehouse_code, None).await? {
+        return Err(AppError::already_exists("warehouse with this code"));
+    }
+
+    let result = state.db.warehouses().create(payload).await?;
+    state.db.shards().copy_warehouse(result.warehouse_id).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        result,
+        "Warehouse created successfully".to_string()
+    )))
+}
+
+async fn update_warehouse(
+    _: RequirePermission<ManageWarehouses>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateWarehouse>,
+) -> AppResult<Json<ApiResponse<Warehouse>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    match state.db.warehouses().update(id, payload).await? {
+        Some(warehouse) => {
+            state.db.shards().copy_warehouse(id).await?;
+            Ok(Json(ApiResponse::success_with_message(
+                warehouse,
+                "Warehouse updated successfully".to_string()
+            )))
+        }
         None => Err(AppError::not_found("warehouse")),
     }
 }
 
+async fn delete_warehouse(
+    _: RequirePermission<DeleteWarehouses>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    if state.db.warehouses().delete(id).await? {
+        Ok(Json(ApiResponse::success_with_message(
+            (),
+            "Warehouse deleted successfully".to_string()
+        )))
+    } else {
+        Err(AppError::not_found("warehouse"))
+    }
+}
+
 // Items handlers
 async fn list_items(
     Query(pagination): Query<PaginationQuery>,
     State(state): State<AppState>,
-) -> AppResult<Json<ApiResponse<PaginatedResponse<Item>>>> {
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    paging::check_page(&state.config.pagination, &ITEM_ORDER, &pagination)?;
+    let format = content::negotiate(&headers);
+    if format == ResponseFormat::Csv {
+        // The export holds every page of the listing, matching the same search
+        let items = state.db.items().stream_active(pagination.search.clone());
+        return Ok(content::csv_response("items.csv", items));
+    }
+
     let result = state.db.items().list(pagination).await?;
-    Ok(Json(ApiResponse::success(result)))
+
+    Ok(match format {
+        ResponseFormat::JsonApi => jsonapi::collection(&result, &uri),
+        _ => Json(ApiResponse::success(links::paginated(result, &uri))).into_response(),
+    })
 }
 
 async fn create_item(
+    _: RequirePermission<ManageItems>,
     State(state): State<AppState>,
     Json(payload): Json<CreateItem>,
 ) -> AppResult<Json<ApiResponse<Item>>> {
-    payload.validate().map_err(|e| AppError::validation(e))?;
+    payload.validate().map_err(AppError::validation)?;
 
     if state.db.items().code_exists(&payload.item_code, None).await? {
         return Err(AppError::already_exists("item with this code"));
     }
 
     let result = state.db.items().create(payload).await?;
+    state.db.shards().copy_item(result.item_id).await?;
     Ok(Json(ApiResponse::success_with_message(
         result, 
         "Item created successfully".to_string()
@@ -163,9 +623,75 @@ async fn create_item(
 async fn get_item(
     Path(id): Path<i32>,
     State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let item = state
+        .db
+        .items()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("item"))?;
+
+    Ok(match content::negotiate(&headers) {
+        ResponseFormat::JsonApi => jsonapi::single(&item),
+        _ => {
+            let resource_links = item.links();
+            Json(ApiResponse::success(item).with_links(resource_links)).into_response()
+        }
+    })
+}
+
+async fn update_item(
+    _: RequirePermission<ManageItems>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateItem>,
+) -> AppResult<Json<ApiResponse<Item>>> {
+    payload.validate().map_err(AppError::validation)?;
+
+    if let Some(code) = &payload.item_code {
+        if state.db.items().code_exists(code, Some(id)).await? {
+            return Err(AppError::already_exists("item with this code"));
+        }
+    }
+
+    match state.db.items().update(id, payload).await? {
+        Some(item) => {
+            state.db.shards().copy_item(id).await?;
+            Ok(Json(ApiResponse::success_with_message(
+                item,
+                "Item updated successfully".to_string()
+            )))
+        }
+        None => Err(AppError::not_found("item")),
+    }
+}
+
+async fn delete_item(
+    _: RequirePermission<ManageItems>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    if state.db.items().delete(id).await? {
+        Ok(Json(ApiResponse::success_with_message(
+            (),
+            "Item deleted successfully".to_string()
+        )))
+    } else {
+        Err(AppError::not_found("item"))
+    }
+}
+
+async fn restore_item(
+    _: RequirePermission<ManageItems>,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
 ) -> AppResult<Json<ApiResponse<Item>>> {
-    match state.db.items().get_by_id(id).await? {
-        Some(item) => Ok(Json(ApiResponse::success(item))),
+    match state.db.items().restore(id).await? {
+        Some(item) => Ok(Json(ApiResponse::success_with_message(
+            item,
+            "Item restored successfully".to_string()
+        ))),
         None => Err(AppError::not_found("item")),
     }
 }
@@ -0,0 +1,43 @@
+//! `--mock` runs serve the in-memory store, which backs warehouses, items, stock movements
+//! and balances, and signing in. Every other API route answers 503 rather than failing on
+//! the database connection mock mode never makes.
+//!
+//! The store has one user per role, named after it (`admin`, `manager`, `operator`,
+//! `viewer`), each with the password `mock` (`warehouse_db::MOCK_PASSWORD`). Their access tokens reach the
+//! routes above that change things; no refresh token is issued.
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use warehouse_core::AppError;
+use warehouse_db::Database;
+
+/// Routes the in-memory store serves, as matched in the router
+pub const MOCK_ROUTES: &[&str] = &[
+    "/",
+    "/health",
+    "/api/items",
+    "/api/items/:id",
+    "/api/items/:id/restore",
+    "/api/auth/login",
+    "/api/auth/me",
+    "/api/movements",
+    "/api/stock",
+    "/api/warehouses",
+    "/api/warehouses/:id",
+];
+
+/// Refuse routes outside [`MOCK_ROUTES`] when serving the in-memory store
+pub async fn only_mock_routes(State(db): State<Database>, request: Request, next: Next) -> Result<Response, AppError> {
+    if !db.is_mock() {
+        return Ok(next.run(request).await);
+    }
+    let route = request.extensions().get::<MatchedPath>().map(MatchedPath::as_str);
+    if route.is_some_and(|route| !MOCK_ROUTES.contains(&route)) {
+        return Err(AppError::MockMode);
+    }
+    Ok(next.run(request).await)
+}
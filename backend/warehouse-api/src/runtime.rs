@@ -0,0 +1,151 @@
+//! Hooks that apply reloaded runtime settings to the running server: the log filter, CORS
+//! origins and the per-caller rate limit. Reloads come from SIGHUP or the admin endpoint.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing::{info, warn};
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry};
+
+use warehouse_core::{auth, AppError, AppState, LiveConfig};
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Most callers tracked at once. Past it, windows that have ended are dropped, and then the
+/// oldest window, so a flood of new addresses cannot grow the map without bound.
+const RATE_TRACKED_CALLERS: usize = 10_000;
+
+pub type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Install the global subscriber with a filter that can be swapped later
+pub fn init_tracing(filter: &str) -> LogFilterHandle {
+    let filter = EnvFilter::try_new(filter).unwrap_or_else(|e| {
+        eprintln!("Invalid log filter {:?} ({}), using info", filter, e);
+        EnvFilter::new("info")
+    });
+    let (filter, handle) = reload::Layer::new(filter);
+    tracing_subscriber::registry().with(filter).with(tracing_subscriber::fmt::layer()).init();
+    handle
+}
+
+/// Swap the log filter whenever a reload changes it
+pub fn spawn_log_filter_watcher(live: LiveConfig, handle: LogFilterHandle) {
+    let mut changes = live.subscribe();
+    tokio::spawn(async move {
+        let mut applied = live.current().log_filter.clone();
+        while changes.changed().await.is_ok() {
+            let wanted = changes.borrow_and_update().log_filter.clone();
+            if wanted == applied {
+                continue;
+            }
+            match EnvFilter::try_new(&wanted) {
+                Ok(filter) => match handle.reload(filter) {
+                    Ok(()) => {
+                        info!("Log filter set to {}", wanted);
+                        applied = wanted;
+                    }
+                    Err(e) => warn!("Cannot apply log filter {}: {}", wanted, e),
+                },
+                Err(e) => warn!("Ignoring invalid log filter {}: {}", wanted, e),
+            }
+        }
+    });
+}
+
+/// Reload the runtime settings on SIGHUP
+#[cfg(unix)]
+pub fn spawn_sighup_reloader(live: LiveConfig) -> std::io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            if let Err(e) = live.reload() {
+                warn!("Configuration reload failed: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn spawn_sighup_reloader(_live: LiveConfig) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// CORS that checks each request's origin against the current allow-list
+pub fn cors(live: LiveConfig) -> CorsLayer {
+    let origins = AllowOrigin::predicate(move |origin, _| {
+        origin.to_str().is_ok_and(|origin| live.current().allows_origin(origin))
+    });
+    CorsLayer::new().allow_origin(origins).allow_methods(Any).allow_headers(Any).expose_headers(Any)
+}
+
+/// Fixed one-minute windows of request counts per caller
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    windows: Arc<Mutex<HashMap<String, (Instant, u32)>>>,
+}
+
+impl RateLimiter {
+    /// Count a request from `caller`; false once it is over `limit` in the current window
+    fn admit(&self, caller: String, limit: u32) -> bool {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().expect("rate limiter poisoned");
+        if windows.len() >= RATE_TRACKED_CALLERS && !windows.contains_key(&caller) {
+            windows.retain(|_, (started, _)| now.duration_since(*started) < RATE_WINDOW);
+            if windows.len() >= RATE_TRACKED_CALLERS {
+                let oldest = windows.iter().min_by_key(|(_, (started, _))| *started).map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    windows.remove(&oldest);
+                }
+            }
+        }
+
+        let (started, count) = windows.entry(caller).or_insert((now, 0));
+        if now.duration_since(*started) >= RATE_WINDOW {
+            *started = now;
+            *count = 0;
+        }
+        *count += 1;
+        *count <= limit
+    }
+}
+
+/// Reject API requests over the current per-minute limit. Callers are told apart by who
+/// they verifiably are, an accepted API key or the user of a valid access token, and
+/// otherwise by address; credentials that do not check out count against the address.
+/// Runs inside `auth::api_keys`, which has checked any key by then.
+pub async fn rate_limit(
+    State((state, limiter)): State<(AppState, RateLimiter)>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let limit = state.runtime.current().rate_limit_per_minute;
+    if limit == 0 || !request.uri().path().starts_with("/api/") {
+        return Ok(next.run(request).await);
+    }
+
+    if !limiter.admit(caller(&state, &request), limit) {
+        return Err(AppError::RateLimited);
+    }
+    Ok(next.run(request).await)
+}
+
+fn caller(state: &AppState, request: &Request) -> String {
+    if let Some(actor) = auth::actor(request, &state.config.security.jwt_secret) {
+        return actor;
+    }
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "anonymous".to_string(),
+    }
+}
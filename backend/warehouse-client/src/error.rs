@@ -0,0 +1,22 @@
+//! Client error types
+
+use thiserror::Error;
+
+pub type ClientResult<T> = Result<T, ClientError>;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The API answered with its error envelope
+    #[error("API error {status} {code}: {message}")]
+    Api {
+        status: u16,
+        code: String,
+        message: String,
+    },
+
+    #[error("Response contained no data")]
+    EmptyResponse,
+}
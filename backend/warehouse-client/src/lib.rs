@@ -0,0 +1,301 @@
+//! Warehouse Management System - HTTP client
+//!
+//! Typed wrapper over the REST API for other services. It covers warehouses, items,
+//! movements, stock and reservations, sign-in and users; the other routes are not
+//! wrapped yet.
+//!
+//! ```no_run
+//! # async fn run() -> warehouse_client::ClientResult<()> {
+//! let client = warehouse_client::WarehouseClient::new("http://localhost:8000");
+//! let warehouses = client.list_warehouses(&Default::default()).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod error;
+
+pub use error::{ClientError, ClientResult};
+
+use reqwest::{Method, RequestBuilder, Response};
+use serde::{de::DeserializeOwned, Deserialize};
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct WarehouseClient {
+    http: reqwest::Client,
+    base_url: String,
+    bearer_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorEnvelope {
+    error: ErrorBody,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    code: String,
+    message: String,
+}
+
+impl WarehouseClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_http_client(reqwest::Client::new(), base_url)
+    }
+
+    /// Use a preconfigured reqwest client (timeouts, proxies, TLS roots)
+    pub fn with_http_client(http: reqwest::Client, base_url: impl Into<String>) -> Self {
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            bearer_token: None,
+        }
+    }
+
+    /// Send `Authorization: Bearer <token>` on every request
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    // ------------------------------------------------------------------
+    // System
+    // ------------------------------------------------------------------
+
+    pub async fn health(&self) -> ClientResult<HealthStatus> {
+        let response = self.request(Method::GET, "/health").send().await?;
+        Ok(Self::check(response).await?.json().await?)
+    }
+
+    // ------------------------------------------------------------------
+    // Warehouses
+    // ------------------------------------------------------------------
+
+    pub async fn list_warehouses(
+        &self,
+        query: &PaginationQuery,
+    ) -> ClientResult<PaginatedResponse<Linked<Warehouse>>> {
+        self.send(self.request(Method::GET, "/api/warehouses").query(query)).await
+    }
+
+    pub async fn get_warehouse(&self, id: i32) -> ClientResult<Warehouse> {
+        self.send(self.request(Method::GET, &format!("/api/warehouses/{}", id))).await
+    }
+
+    pub async fn create_warehouse(&self, warehouse: &CreateWarehouse) -> ClientResult<Warehouse> {
+        self.send(self.request(Method::POST, "/api/warehouses").json(warehouse)).await
+    }
+
+    pub async fn update_warehouse(&self, id: i32, warehouse: &UpdateWarehouse) -> ClientResult<Warehouse> {
+        self.send(self.request(Method::PUT, &format!("/api/warehouses/{}", id)).json(warehouse)).await
+    }
+
+    pub async fn delete_warehouse(&self, id: i32) -> ClientResult<()> {
+        self.send_empty(self.request(Method::DELETE, &format!("/api/warehouses/{}", id))).await
+    }
+
+    // ------------------------------------------------------------------
+    // Items
+    // ------------------------------------------------------------------
+
+    pub async fn list_items(&self, query: &PaginationQuery) -> ClientResult<PaginatedResponse<Linked<Item>>> {
+        self.send(self.request(Method::GET, "/api/items").query(query)).await
+    }
+
+    pub async fn get_item(&self, id: i32) -> ClientResult<Item> {
+        self.send(self.request(Method::GET, &format!("/api/items/{}", id))).await
+    }
+
+    pub async fn create_item(&self, item: &CreateItem) -> ClientResult<Item> {
+        self.send(self.request(Method::POST, "/api/items").json(item)).await
+    }
+
+    pub async fn update_item(&self, id: i32, item: &UpdateItem) -> ClientResult<Item> {
+        self.send(self.request(Method::PUT, &format!("/api/items/{}", id)).json(item)).await
+    }
+
+    pub async fn delete_item(&self, id: i32) -> ClientResult<()> {
+        self.send_empty(self.request(Method::DELETE, &format!("/api/items/{}", id))).await
+    }
+
+    pub async fn restore_item(&self, id: i32) -> ClientResult<Item> {
+        self.send(self.request(Method::POST, &format!("/api/items/{}/restore", id))).await
+    }
+
+    // ------------------------------------------------------------------
+    // Movements
+    // ------------------------------------------------------------------
+
+    pub async fn list_movements(
+        &self,
+        filter: &MovementFilter,
+        keyset: &KeysetQuery,
+    ) -> ClientResult<KeysetPage<Linked<StockMovement>>> {
+        self.send(
+            self.request(Method::GET, "/api/movements")
+                .query(filter)
+                .query(keyset),
+        )
+        .await
+    }
+
+    // ------------------------------------------------------------------
+    // Stock
+    // ------------------------------------------------------------------
+
+    /// Stock on hand, or as of a past day when the filter names one
+    pub async fn get_stock(&self, filter: &StockFilter) -> ClientResult<StockSnapshot> {
+        self.send(self.request(Method::GET, "/api/stock").query(filter)).await
+    }
+
+    pub async fn list_warehouse_stock(
+        &self,
+        warehouse_id: i32,
+        query: &PaginationQuery,
+    ) -> ClientResult<PaginatedResponse<Linked<StockInventory>>> {
+        self.send(self.request(Method::GET, &format!("/api/warehouses/{}/stock", warehouse_id)).query(query)).await
+    }
+
+    pub async fn list_item_stock(
+        &self,
+        item_id: i32,
+        query: &PaginationQuery,
+    ) -> ClientResult<PaginatedResponse<Linked<StockInventory>>> {
+        self.send(self.request(Method::GET, &format!("/api/items/{}/stock", item_id)).query(query)).await
+    }
+
+    pub async fn upsert_stock(
+        &self,
+        warehouse_id: i32,
+        item_id: i32,
+        stock: &UpsertStock,
+    ) -> ClientResult<StockInventory> {
+        let path = format!("/api/warehouses/{}/stock/{}", warehouse_id, item_id);
+        self.send(self.request(Method::PUT, &path).json(stock)).await
+    }
+
+    pub async fn issue_stock(
+        &self,
+        warehouse_id: i32,
+        item_id: i32,
+        issue: &IssueStock,
+    ) -> ClientResult<StockMovement> {
+        let path = format!("/api/warehouses/{}/stock/{}/issues", warehouse_id, item_id);
+        self.send(self.request(Method::POST, &path).json(issue)).await
+    }
+
+    // ------------------------------------------------------------------
+    // Reservations
+    // ------------------------------------------------------------------
+
+    pub async fn list_reservations(
+        &self,
+        filter: &StockReservationFilter,
+    ) -> ClientResult<Vec<Linked<StockReservation>>> {
+        self.send(self.request(Method::GET, "/api/stock/reservations").query(filter)).await
+    }
+
+    pub async fn create_reservation(&self, reservation: &CreateStockReservation) -> ClientResult<StockReservation> {
+        self.send(self.request(Method::POST, "/api/stock/reservations").json(reservation)).await
+    }
+
+    /// Release `reservation`, routed to the database holding its warehouse's stock
+    pub async fn release_reservation(&self, reservation: &StockReservation) -> ClientResult<StockReservation> {
+        let path = format!("/api/stock/reservations/{}/release", reservation.reservation_id);
+        let target = ReleaseReservationQuery { warehouse_id: Some(reservation.warehouse_id) };
+        self.send(self.request(Method::POST, &path).query(&target)).await
+    }
+
+    // ------------------------------------------------------------------
+    // Auth
+    // ------------------------------------------------------------------
+
+    /// Sign in; pass the access token to `with_bearer_token` to call as the user
+    pub async fn login(&self, login: &Login) -> ClientResult<AccessToken> {
+        self.send(self.request(Method::POST, "/api/auth/login").json(login)).await
+    }
+
+    /// Exchange a refresh token for a new access token and refresh token
+    pub async fn refresh(&self, refresh: &RefreshAccess) -> ClientResult<AccessToken> {
+        self.send(self.request(Method::POST, "/api/auth/refresh").json(refresh)).await
+    }
+
+    pub async fn logout(&self, refresh: &RefreshAccess) -> ClientResult<()> {
+        self.send_empty(self.request(Method::POST, "/api/auth/logout").json(refresh)).await
+    }
+
+    /// The user the bearer token belongs to
+    pub async fn get_me(&self) -> ClientResult<User> {
+        self.send(self.request(Method::GET, "/api/auth/me")).await
+    }
+
+    // ------------------------------------------------------------------
+    // Users
+    // ------------------------------------------------------------------
+
+    pub async fn list_users(&self, filter: &UserFilter) -> ClientResult<Vec<Linked<User>>> {
+        self.send(self.request(Method::GET, "/api/users").query(filter)).await
+    }
+
+    pub async fn get_user(&self, id: i32) -> ClientResult<User> {
+        self.send(self.request(Method::GET, &format!("/api/users/{}", id))).await
+    }
+
+    pub async fn create_user(&self, user: &CreateUser) -> ClientResult<User> {
+        self.send(self.request(Method::POST, "/api/users").json(user)).await
+    }
+
+    pub async fn update_user(&self, id: i32, user: &UpdateUser) -> ClientResult<User> {
+        self.send(self.request(Method::PUT, &format!("/api/users/{}", id)).json(user)).await
+    }
+
+    /// Deactivate a user
+    pub async fn delete_user(&self, id: i32) -> ClientResult<()> {
+        self.send_empty(self.request(Method::DELETE, &format!("/api/users/{}", id))).await
+    }
+
+    // ------------------------------------------------------------------
+    // Plumbing
+    // ------------------------------------------------------------------
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let builder = self.http.request(method, format!("{}{}", self.base_url, path));
+        match &self.bearer_token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        }
+    }
+
+    /// Send a request and unwrap the `data` field of the `ApiResponse` envelope
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> ClientResult<T> {
+        let response = Self::check(request.send().await?).await?;
+        let envelope: ApiResponse<T> = response.json().await?;
+        envelope.data.ok_or(ClientError::EmptyResponse)
+    }
+
+    /// Send a request whose envelope carries no data
+    async fn send_empty(&self, request: RequestBuilder) -> ClientResult<()> {
+        Self::check(request.send().await?).await?;
+        Ok(())
+    }
+
+    /// Turn non-2xx responses into `ClientError::Api` using the server's error envelope
+    async fn check(response: Response) -> ClientResult<Response> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        let (code, message) = match serde_json::from_str::<ErrorEnvelope>(&body) {
+            Ok(envelope) => (envelope.error.code, envelope.error.message),
+            Err(_) => ("UNKNOWN".to_string(), body),
+        };
+
+        Err(ClientError::Api {
+            status: status.as_u16(),
+            code,
+            message,
+        })
+    }
+}
@@ -0,0 +1,64 @@
+//! Available-to-promise for order taking. What a warehouse can promise of an item by a
+//! date is its stock on hand less what is reserved, allocated to open orders and planned
+//! out on transfers, plus what is on its way in by then:
+//!
+//! - transfers in transit to the warehouse count on any date, having already left their
+//!   source;
+//! - purchase orders count from their expected date, or from today when they are late;
+//! - supply nobody expects on a known date is listed but never promised.
+
+use warehouse_models::chrono::NaiveDate;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// The item's availability in the warehouse on `date`, and the earliest date `quantity`
+/// can be promised
+pub fn promise(
+    item_id: i32,
+    warehouse_id: i32,
+    today: NaiveDate,
+    date: NaiveDate,
+    quantity: Option<Decimal>,
+    position: StockPosition,
+    inbound: Vec<InboundSupply>,
+) -> ItemAvailability {
+    let available_now = position.on_hand - position.reserved - position.allocated - position.transfers_out;
+    let in_transit: Decimal =
+        inbound.iter().filter(|supply| supply.source == SUPPLY_TRANSFER).map(|supply| supply.quantity).sum();
+
+    // Purchase orders by the day they can be promised from, earliest first
+    let mut scheduled: Vec<(NaiveDate, Decimal)> = inbound
+        .iter()
+        .filter(|supply| supply.source == SUPPLY_PURCHASE_ORDER)
+        .filter_map(|supply| supply.expected_date.map(|expected| (expected.max(today), supply.quantity)))
+        .collect();
+    scheduled.sort_by_key(|(expected, _)| *expected);
+    let inbound_by_date: Decimal =
+        scheduled.iter().filter(|(expected, _)| *expected <= date).map(|(_, quantity)| *quantity).sum();
+
+    let promise_date = quantity.and_then(|quantity| {
+        let mut covered = available_now + in_transit;
+        if covered >= quantity {
+            return Some(today);
+        }
+        scheduled.iter().find_map(|(expected, arriving)| {
+            covered += *arriving;
+            (covered >= quantity).then_some(*expected)
+        })
+    });
+
+    ItemAvailability {
+        item_id,
+        warehouse_id,
+        date,
+        available_now,
+        in_transit,
+        inbound_by_date,
+        available_to_promise: (available_now + in_transit + inbound_by_date).max(Decimal::ZERO),
+        quantity_requested: quantity,
+        can_promise: quantity.map(|_| promise_date.is_some_and(|promised| promised <= date)),
+        promise_date,
+        position,
+        inbound,
+    }
+}
@@ -0,0 +1,427 @@
+//! Who is calling and what they may do. Users sign in for a short-lived JWT (HS256 over
+//! `security.jwt_secret`) carrying their id and role, and a refresh token to get the next
+//! one with; integrations send an API key in `X-Api-Key`, checked by the [`api_keys`]
+//! layer. Handlers opt in to checks by taking a [`CurrentUser`], [`RequireRole`] or
+//! [`RequirePermission`] argument, which reject the request before the handler runs.
+//! Changes under `/api/` need a key or a signed-in user whatever the handler takes; only
+//! signing in and carrier webhooks are open.
+//!
+//! Roles are ranked admin, manager, operator, viewer; each may do everything the ones
+//! below it may. A changed role takes effect with the user's next access token. An API
+//! key holds scopes instead: each permission names the scope that grants it, and the
+//! admin scope grants them all.
+
+use std::marker::PhantomData;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap, Method},
+    middleware::Next,
+    response::Response,
+};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use warehouse_db::audit;
+use warehouse_models::chrono::{DateTime, Duration, Utc};
+use warehouse_models::*;
+
+use crate::config::{SecurityConfig, DEFAULT_API_KEY};
+use crate::{AppError, AppResult, AppState};
+
+/// What an access token says about its holder
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Claims {
+    /// The user's id
+    pub sub: i32,
+    pub username: String,
+    pub role: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Access token for the user, valid for `ttl_secs` from `now`
+pub fn issue_token(secret: &str, user: User, ttl_secs: u64, now: DateTime<Utc>) -> AccessToken {
+    let expires_at = now + Duration::seconds(ttl_secs as i64);
+    let claims = Claims {
+        sub: user.user_id,
+        username: user.username.clone(),
+        role: user.role.clone(),
+        iat: now.timestamp(),
+        exp: expires_at.timestamp(),
+    };
+    let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","typ":"JWT"}"#);
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).expect("claims serialize"));
+    let signing_input = format!("{}.{}", header, payload);
+    let signature = URL_SAFE_NO_PAD.encode(mac(secret, &signing_input).finalize().into_bytes());
+
+    AccessToken {
+        access_token: format!("{}.{}", signing_input, signature),
+        token_type: "Bearer".to_string(),
+        expires_at,
+        refresh_token: None,
+        refresh_expires_at: None,
+        user,
+    }
+}
+
+/// The token's claims, if we signed it and it has not expired
+pub fn verify_token(secret: &str, token: &str, now: DateTime<Utc>) -> Option<Claims> {
+    let (signing_input, signature) = token.rsplit_once('.')?;
+    let (_, payload) = signing_input.split_once('.')?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    mac(secret, signing_input).verify_slice(&signature).ok()?;
+
+    let claims: Claims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    (claims.exp > now.timestamp()).then_some(claims)
+}
+
+fn mac(secret: &str, signing_input: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(signing_input.as_bytes());
+    mac
+}
+
+/// Whether `role` may do what `required` may
+pub fn has_role(role: &str, required: &str) -> bool {
+    // ROLES runs highest first
+    let rank = |role: &str| ROLES.iter().position(|known| *known == role);
+    matches!((rank(role), rank(required)), (Some(held), Some(needed)) if held <= needed)
+}
+
+/// A role a route requires, named as a type for [`RequireRole`]
+pub trait Role {
+    const ROLE: &'static str;
+}
+
+pub struct Admin;
+pub struct Manager;
+pub struct Operator;
+pub struct Viewer;
+
+impl Role for Admin {
+    const ROLE: &'static str = ROLE_ADMIN;
+}
+
+impl Role for Manager {
+    const ROLE: &'static str = ROLE_MANAGER;
+}
+
+impl Role for Operator {
+    const ROLE: &'static str = ROLE_OPERATOR;
+}
+
+impl Role for Viewer {
+    const ROLE: &'static str = ROLE_VIEWER;
+}
+
+/// Something a route may require, granted to a role and the roles above it, and to API
+/// keys with its scope
+pub trait Permission {
+    /// What it allows, as in "deleting warehouses requires the admin role"
+    const ACTION: &'static str;
+    const MINIMUM_ROLE: &'static str;
+    const SCOPE: &'static str;
+}
+
+pub struct AdjustStock;
+pub struct ManageItems;
+pub struct ManageWarehouses;
+pub struct DeleteWarehouses;
+pub struct ManageUsers;
+pub struct ManageApiKeys;
+/// GL accounts, journal exports and closing or reopening periods
+pub struct ManageAccounting;
+pub struct ViewAuditLog;
+/// Migrations, backups and other operations that change or expose everything
+pub struct AdministerSystem;
+
+impl Permission for AdjustStock {
+    const ACTION: &'static str = "adjusting stock";
+    const MINIMUM_ROLE: &'static str = ROLE_OPERATOR;
+    const SCOPE: &'static str = SCOPE_STOCK_WRITE;
+}
+
+impl Permission for ManageItems {
+    const ACTION: &'static str = "changing items";
+    const MINIMUM_ROLE: &'static str = ROLE_MANAGER;
+    const SCOPE: &'static str = SCOPE_STOCK_WRITE;
+}
+
+impl Permission for ManageWarehouses {
+    const ACTION: &'static str = "changing warehouses";
+    const MINIMUM_ROLE: &'static str = ROLE_MANAGER;
+    const SCOPE: &'static str = SCOPE_ADMIN;
+}
+
+impl Permission for DeleteWarehouses {
+    const ACTION: &'static str = "deleting warehouses";
+    const MINIMUM_ROLE: &'static str = ROLE_ADMIN;
+    const SCOPE: &'static str = SCOPE_ADMIN;
+}
+
+impl Permission for ManageUsers {
+    const ACTION: &'static str = "managing users";
+    const MINIMUM_ROLE: &'static str = ROLE_ADMIN;
+    const SCOPE: &'static str = SCOPE_ADMIN;
+}
+
+impl Permission for ManageApiKeys {
+    const ACTION: &'static str = "managing API keys";
+    const MINIMUM_ROLE: &'static str = ROLE_ADMIN;
+    const SCOPE: &'static str = SCOPE_ADMIN;
+}
+
+impl Permission for ManageAccounting {
+    const ACTION: &'static str = "managing the books";
+    const MINIMUM_ROLE: &'static str = ROLE_MANAGER;
+    const SCOPE: &'static str = SCOPE_ADMIN;
+}
+
+impl Permission for ViewAuditLog {
+    const ACTION: &'static str = "viewing the audit log";
+    const MINIMUM_ROLE: &'static str = ROLE_MANAGER;
+    const SCOPE: &'static str = SCOPE_ADMIN;
+}
+
+impl Permission for AdministerSystem {
+    const ACTION: &'static str = "administering the system";
+    const MINIMUM_ROLE: &'static str = ROLE_ADMIN;
+    const SCOPE: &'static str = SCOPE_ADMIN;
+}
+
+/// The signed-in user a request's `Authorization: Bearer` access token belongs to
+#[derive(Debug, Clone)]
+pub struct CurrentUser {
+    pub user_id: i32,
+    pub username: String,
+    pub role: String,
+}
+
+impl CurrentUser {
+    /// The user of a valid bearer token, for code outside handlers such as middleware
+    pub fn from_headers(headers: &HeaderMap, jwt_secret: &str) -> Option<Self> {
+        let claims = bearer_claims(headers, jwt_secret)?;
+        Some(Self { user_id: claims.sub, username: claims.username, role: claims.role })
+    }
+
+    pub fn require_role(&self, role: &str) -> AppResult<()> {
+        if has_role(&self.role, role) {
+            return Ok(());
+        }
+        Err(AppError::forbidden(&format!("this requires the {} role", role.to_lowercase())))
+    }
+
+    pub fn require_permission<P: Permission>(&self) -> AppResult<()> {
+        if has_role(&self.role, P::MINIMUM_ROLE) {
+            return Ok(());
+        }
+        Err(AppError::forbidden(&format!("{} requires the {} role", P::ACTION, P::MINIMUM_ROLE.to_lowercase())))
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for CurrentUser {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        Self::from_headers(&parts.headers, &state.config.security.jwt_secret).ok_or(AppError::Unauthorized)
+    }
+}
+
+/// Claims of a valid `Authorization: Bearer` access token
+fn bearer_claims(headers: &HeaderMap, secret: &str) -> Option<Claims> {
+    let token = headers.get(header::AUTHORIZATION)?.to_str().ok()?.strip_prefix("Bearer ")?;
+    verify_token(secret, token.trim(), Utc::now())
+}
+
+/// A signed-in user holding role `R` or one above it
+pub struct RequireRole<R> {
+    pub user: CurrentUser,
+    role: PhantomData<fn() -> R>,
+}
+
+#[async_trait]
+impl<R: Role> FromRequestParts<AppState> for RequireRole<R> {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let user = CurrentUser::from_request_parts(parts, state).await?;
+        user.require_role(R::ROLE)?;
+        Ok(Self { user, role: PhantomData })
+    }
+}
+
+/// Header integrations send their API key in
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// The API key a request was made with, left on the request by [`api_keys`]
+#[derive(Debug, Clone)]
+pub enum ApiKeyAccess {
+    /// `security.api_key`, which may do everything; it issues the first keys
+    Configured,
+    Issued(ApiKey),
+}
+
+impl ApiKeyAccess {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        match self {
+            Self::Configured => true,
+            Self::Issued(key) => key.has_scope(scope),
+        }
+    }
+}
+
+/// Authenticate API requests carrying `X-Api-Key`: an unknown or revoked key is refused.
+/// The key is left on the request for the extractors and for the route layers that bound
+/// what it may change, [`guard_stock_changes`] and [`guard_changes`].
+pub async fn api_keys(State(state): State<AppState>, mut request: Request, next: Next) -> Result<Response, AppError> {
+    let Some(presented) = request.headers().get(API_KEY_HEADER) else {
+        return Ok(next.run(request).await);
+    };
+    if !request.uri().path().starts_with("/api/") {
+        return Ok(next.run(request).await);
+    }
+    let presented = presented.to_str().map_err(|_| AppError::Unauthorized)?.trim();
+    let access = authenticate_key(&state, presented).await?.ok_or(AppError::Unauthorized)?;
+    request.extensions_mut().insert(access);
+    Ok(next.run(request).await)
+}
+
+/// Route layer of the routes whose changes are stock writes. A change needs an API key or
+/// a signed-in user; each takes a [`RequirePermission`] whose scope is stock write, so any
+/// key but a read-only one may make it.
+pub async fn guard_stock_changes(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    may_change(&state, &request, true)?;
+    Ok(next.run(request).await)
+}
+
+/// Route layer of the other routes that change things. A change needs an API key or a
+/// signed-in user; a route that declares no stock permission cannot have a key's scopes
+/// checked against it, so the key needs the admin scope.
+pub async fn guard_changes(State(state): State<AppState>, request: Request, next: Next) -> Result<Response, AppError> {
+    may_change(&state, &request, false)?;
+    Ok(next.run(request).await)
+}
+
+fn may_change(state: &AppState, request: &Request, stock_route: bool) -> AppResult<()> {
+    if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return Ok(());
+    }
+    match request.extensions().get::<ApiKeyAccess>() {
+        Some(access) => key_may_change(access, stock_route),
+        None => match bearer_claims(request.headers(), &state.config.security.jwt_secret) {
+            Some(_) => Ok(()),
+            None => Err(AppError::Unauthorized),
+        },
+    }
+}
+
+/// Whether the key may make a change through a stock route, or through any other route
+pub fn key_may_change(access: &ApiKeyAccess, stock_route: bool) -> AppResult<()> {
+    if access.has_scope(SCOPE_ADMIN) {
+        return Ok(());
+    }
+    if !access.has_scope(SCOPE_STOCK_WRITE) {
+        return Err(AppError::forbidden("a read-only API key cannot make changes"));
+    }
+    if !stock_route {
+        return Err(AppError::forbidden("this change needs an API key with the admin scope"));
+    }
+    Ok(())
+}
+
+async fn authenticate_key(state: &AppState, presented: &str) -> AppResult<Option<ApiKeyAccess>> {
+    if is_configured_key(&state.config.security, presented) {
+        return Ok(Some(ApiKeyAccess::Configured));
+    }
+    if state.db.is_mock() {
+        return Ok(None);
+    }
+    Ok(state.db.api_keys().authenticate(presented).await?.map(ApiKeyAccess::Issued))
+}
+
+/// Whether the key presented is `security.api_key`. The shipped default is public, so it
+/// never is, whatever the environment.
+pub fn is_configured_key(security: &SecurityConfig, presented: &str) -> bool {
+    security.api_key != DEFAULT_API_KEY && same_secret(presented, &security.api_key)
+}
+
+/// Compare secrets in time that depends only on their length, not on where they differ
+fn same_secret(presented: &str, expected: &str) -> bool {
+    presented.len() == expected.len()
+        && presented.bytes().zip(expected.bytes()).fold(0, |differs, (a, b)| differs | (a ^ b)) == 0
+}
+
+/// Record the request's changes in the audit log as made by its caller: the API key
+/// [`api_keys`] left on it, or the user its access token names. Requests from neither are
+/// recorded without an actor.
+pub async fn audit_actor(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    match actor(&request, &state.config.security.jwt_secret) {
+        Some(actor) => audit::as_actor(actor, next.run(request)).await,
+        None => next.run(request).await,
+    }
+}
+
+/// The verified caller of a request, as `api_key:3` or `user:7`: an API key `api_keys`
+/// accepted, or the user of a valid bearer token. `None` for anonymous requests and for
+/// credentials that do not check out.
+pub fn actor(request: &Request, jwt_secret: &str) -> Option<String> {
+    match request.extensions().get::<ApiKeyAccess>() {
+        Some(ApiKeyAccess::Issued(key)) => Some(format!("api_key:{}", key.key_id)),
+        Some(ApiKeyAccess::Configured) => Some("api_key:configured".to_string()),
+        None => bearer_claims(request.headers(), jwt_secret).map(|claims| format!("user:{}", claims.sub)),
+    }
+}
+
+/// Who a permitted request came from
+#[derive(Debug, Clone)]
+pub enum Caller {
+    User(CurrentUser),
+    ApiKey(ApiKeyAccess),
+}
+
+impl Caller {
+    /// The user to record as making the change, when a user made it
+    pub fn user_id(&self) -> Option<i32> {
+        match self {
+            Self::User(user) => Some(user.user_id),
+            Self::ApiKey(_) => None,
+        }
+    }
+}
+
+/// A signed-in user granted permission `P`, or an API key with its scope
+pub struct RequirePermission<P> {
+    pub caller: Caller,
+    permission: PhantomData<fn() -> P>,
+}
+
+#[async_trait]
+impl<P: Permission> FromRequestParts<AppState> for RequirePermission<P> {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if let Some(key) = parts.extensions.get::<ApiKeyAccess>() {
+            if !key.has_scope(P::SCOPE) {
+                return Err(AppError::forbidden(&format!(
+                    "{} requires an API key with the {} scope",
+                    P::ACTION,
+                    P::SCOPE.to_lowercase()
+                )));
+            }
+            return Ok(Self { caller: Caller::ApiKey(key.clone()), permission: PhantomData });
+        }
+
+        let user = CurrentUser::from_request_parts(parts, state).await?;
+        user.require_permission::<P>()?;
+        Ok(Self { caller: Caller::User(user), permission: PhantomData })
+    }
+}
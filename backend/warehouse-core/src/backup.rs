@@ -0,0 +1,190 @@
+//! Logical backups. An export writes the warehouses, items, stock balances and movements
+//! of the shared schema and every active tenant schema to one archive: a manifest line
+//! followed by one JSON line per row. Archives are named after the time they were taken and
+//! never overwritten, so the store keeps every version.
+//!
+//! `s3://` stores are reached through the AWS CLI, like VMI drops go through `sftp`; a
+//! `file://` store is a plain directory, for development and mounted volumes.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use anyhow::{bail, Context, Result};
+use futures::TryStreamExt;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::process::Command;
+use tracing::{info, warn};
+use warehouse_db::Database;
+use warehouse_models::chrono::Utc;
+use warehouse_models::*;
+
+use crate::config::BackupConfig;
+use crate::{AppError, AppResult};
+
+/// Where archives are kept
+pub enum ObjectStore {
+    Directory(PathBuf),
+    S3 { prefix: String, command: String, endpoint_url: Option<String> },
+}
+
+impl ObjectStore {
+    pub fn from_config(config: &BackupConfig) -> Result<Self> {
+        if let Some(path) = config.store_url.strip_prefix("file://") {
+            Ok(Self::Directory(PathBuf::from(path)))
+        } else if config.store_url.starts_with("s3://") {
+            Ok(Self::S3 {
+                prefix: config.store_url.trim_end_matches('/').to_string(),
+                command: config.s3_command.clone(),
+                endpoint_url: config.s3_endpoint_url.clone(),
+            })
+        } else {
+            bail!("unsupported backup store {}", config.store_url)
+        }
+    }
+
+    /// Upload a staged file under `key`. Directory stores rename into place, so a partial
+    /// archive is never visible under its final name.
+    pub async fn put(&self, local: &Path, key: &str) -> Result<()> {
+        match self {
+            Self::Directory(root) => {
+                let target = root.join(key);
+                if let Some(parent) = target.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                let partial = target.with_extension("part");
+                tokio::fs::copy(local, &partial).await.with_context(|| format!("cannot write {}", partial.display()))?;
+                tokio::fs::rename(&partial, &target).await?;
+                Ok(())
+            }
+            Self::S3 { prefix, .. } => {
+                let local = local.display().to_string();
+                self.aws(&["s3", "cp", "--only-show-errors", &local, &format!("{}/{}", prefix, key)]).await?;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        match self {
+            Self::Directory(root) => {
+                let path = root.join(key);
+                tokio::fs::read(&path).await.with_context(|| format!("cannot read {}", path.display()))
+            }
+            Self::S3 { prefix, .. } => self.aws(&["s3", "cp", "--only-show-errors", &format!("{}/{}", prefix, key), "-"]).await,
+        }
+    }
+
+    /// Run the AWS CLI, returning its output
+    async fn aws(&self, args: &[&str]) -> Result<Vec<u8>> {
+        let Self::S3 { command, endpoint_url, .. } = self else {
+            bail!("not an S3 store");
+        };
+
+        let mut aws = Command::new(command);
+        aws.args(args).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+        if let Some(endpoint_url) = endpoint_url {
+            aws.args(["--endpoint-url", endpoint_url]);
+        }
+
+        let output = aws.output().await.with_context(|| format!("cannot run {}", command))?;
+        if !output.status.success() {
+            bail!("{} {} failed: {}", command, args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(output.stdout)
+    }
+}
+
+/// Register a new backup of the shared schema and every active tenant; `run` writes it
+pub async fn request(db: &Database) -> AppResult<Backup> {
+    let schema_version = db.migrations().status().await?.current_version;
+    let schemas = db.backups().schemas().await?;
+    let archive_key = format!(
+        "backups/{}-v{}.ndjson",
+        Utc::now().format("%Y%m%dT%H%M%S%3fZ"),
+        BACKUP_FORMAT_VERSION
+    );
+
+    Ok(db.backups().create(&archive_key, schema_version, &schemas).await?)
+}
+
+/// Export the requested backup and upload it, recording the outcome on the backup
+pub async fn run(db: &Database, config: &BackupConfig, backup: &Backup) -> AppResult<Backup> {
+    match export(db, config, backup).await {
+        Ok((rows, size)) => {
+            let completed = db.backups().complete(backup.backup_id, rows, size).await?;
+            info!(backup_id = backup.backup_id, rows, size, key = %backup.archive_key, "Backup completed");
+            Ok(completed)
+        }
+        Err(e) => {
+            warn!(backup_id = backup.backup_id, "Backup failed: {:#}", e);
+            db.backups().fail(backup.backup_id, &format!("{:#}", e)).await?;
+            Err(AppError::Internal(e))
+        }
+    }
+}
+
+/// The archive of a completed backup
+pub async fn download(config: &BackupConfig, backup: &Backup) -> AppResult<Vec<u8>> {
+    if backup.status != BACKUP_COMPLETED {
+        return Err(AppError::BusinessRule(format!("backup {} is {}", backup.backup_id, backup.status)));
+    }
+    let store = ObjectStore::from_config(config)?;
+    Ok(store.get(&backup.archive_key).await?)
+}
+
+/// Stage the archive in a temporary file and upload it. Returns rows and bytes written.
+async fn export(db: &Database, config: &BackupConfig, backup: &Backup) -> Result<(i64, i64)> {
+    let store = ObjectStore::from_config(config)?;
+    let staged_rows = std::env::temp_dir().join(format!("warehouse-backup-{}.rows", backup.backup_id));
+    let staged = std::env::temp_dir().join(format!("warehouse-backup-{}.ndjson", backup.backup_id));
+
+    let result = async {
+        let tables = write_rows(db, backup, &staged_rows).await?;
+        let manifest = BackupManifest {
+            format: BACKUP_FORMAT.to_string(),
+            format_version: backup.format_version,
+            backup_id: backup.backup_id,
+            schema_version: backup.schema_version,
+            created_at: Utc::now(),
+            tables,
+        };
+
+        // The manifest leads, so a restore knows what it reads before the first row
+        let mut archive = BufWriter::new(tokio::fs::File::create(&staged).await?);
+        archive.write_all(serde_json::to_string(&manifest)?.as_bytes()).await?;
+        archive.write_all(b"\n").await?;
+        tokio::io::copy(&mut tokio::fs::File::open(&staged_rows).await?, &mut archive).await?;
+        archive.flush().await?;
+
+        store.put(&staged, &backup.archive_key).await?;
+        let rows = manifest.tables.iter().map(|table| table.rows).sum();
+        let size = tokio::fs::metadata(&staged).await?.len() as i64;
+        Ok((rows, size))
+    }
+    .await;
+
+    let _ = tokio::fs::remove_file(&staged_rows).await;
+    let _ = tokio::fs::remove_file(&staged).await;
+    result
+}
+
+async fn write_rows(db: &Database, backup: &Backup, path: &Path) -> Result<Vec<BackupTableCount>> {
+    let mut file = BufWriter::new(tokio::fs::File::create(path).await?);
+    let mut tables = Vec::new();
+
+    for schema in &backup.schemas {
+        for table in BACKUP_TABLES {
+            let mut rows = db.backups().rows(schema, table)?;
+            let mut count = 0;
+            while let Some(row) = rows.try_next().await.with_context(|| format!("exporting {}.{}", schema, table))? {
+                let line = format!("{{\"schema\":\"{}\",\"table\":\"{}\",\"row\":{}}}\n", schema, table, row);
+                file.write_all(line.as_bytes()).await?;
+                count += 1;
+            }
+            tables.push(BackupTableCount { schema: schema.clone(), table: table.to_string(), rows: count });
+        }
+    }
+
+    file.flush().await?;
+    Ok(tables)
+}
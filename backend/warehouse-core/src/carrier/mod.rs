@@ -0,0 +1,271 @@
+//! Shipping carriers: rate quotes, label purchase and tracking numbers at ship-confirm,
+//! and tracking events afterwards. Carriers are configured by code
+//! (`CARRIERS=JNE,SICEPAT`) and looked up by the `carrier_code` on a shipment.
+
+pub mod rest;
+pub mod tracking;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use warehouse_db::Database;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+use crate::config::CarrierConfig;
+use crate::{AppError, AppResult};
+
+pub use rest::RestCarrier;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Address {
+    pub name: Option<String>,
+    pub address: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Parcel {
+    pub weight_kg: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateRequest {
+    pub ship_from: Address,
+    pub ship_to: Address,
+    pub parcel: Parcel,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelRequest {
+    /// Our shipment reference, printed on the label
+    pub reference: String,
+    pub service_level: String,
+    pub ship_from: Address,
+    pub ship_to: Address,
+    pub parcel: Parcel,
+}
+
+#[async_trait]
+pub trait Carrier: Send + Sync {
+    fn code(&self) -> &str;
+    async fn rates(&self, request: &RateRequest) -> AppResult<Vec<RateQuote>>;
+    async fn purchase_label(&self, request: &LabelRequest) -> AppResult<ShipmentLabel>;
+    async fn void_label(&self, label_id: &str) -> AppResult<()>;
+    /// Full event history for a tracking number, in any order
+    async fn tracking(&self, tracking_number: &str) -> AppResult<Vec<NewTrackingEvent>>;
+}
+
+/// Configured carriers by code
+#[derive(Clone, Default)]
+pub struct Carriers {
+    by_code: HashMap<String, Arc<dyn Carrier>>,
+}
+
+impl Carriers {
+    pub fn from_config(configs: &[CarrierConfig]) -> Self {
+        let mut carriers = Self::default();
+        for config in configs {
+            carriers.register(Arc::new(RestCarrier::new(config)));
+        }
+        carriers
+    }
+
+    pub fn register(&mut self, carrier: Arc<dyn Carrier>) {
+        self.by_code.insert(carrier.code().to_uppercase(), carrier);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_code.is_empty()
+    }
+
+    pub fn get(&self, code: &str) -> Option<Arc<dyn Carrier>> {
+        self.by_code.get(&code.to_uppercase()).cloned()
+    }
+
+    /// Quote the order's unshipped quantities with one carrier, or every configured carrier.
+    /// A carrier that fails to quote is skipped when quoting all of them.
+    pub async fn quote(&self, db: &Database, order: &OrderDetail, filter: RateFilter) -> AppResult<Vec<RateQuote>> {
+        let open: Vec<(i32, Decimal)> = order
+            .lines
+            .iter()
+            .map(|line| (line.item_id, line.quantity_ordered - line.quantity_shipped))
+            .filter(|(_, quantity)| *quantity > Decimal::ZERO)
+            .collect();
+        if open.is_empty() {
+            return Err(AppError::BusinessRule(format!("{} has nothing left to ship", order.order.order_number)));
+        }
+
+        let request = RateRequest {
+            ship_from: warehouse_address(db, order.order.warehouse_id).await?,
+            ship_to: ship_to(&order.order),
+            parcel: parcel(db, &open).await?,
+        };
+
+        if let Some(code) = filter.carrier_code {
+            let carrier = self.get(&code).ok_or_else(|| unknown_carrier(&code))?;
+            return carrier.rates(&request).await;
+        }
+
+        let mut quotes = Vec::new();
+        for carrier in self.by_code.values() {
+            match carrier.rates(&request).await {
+                Ok(rates) => quotes.extend(rates),
+                Err(e) => warn!("Carrier {} did not quote: {}", carrier.code(), e),
+            }
+        }
+        quotes.sort_by_key(|quote| quote.amount);
+        Ok(quotes)
+    }
+
+    /// Ship-confirm, buying a label first when the confirmation names a service level.
+    /// The label is voided again if the shipment cannot be recorded, or if a retry with the
+    /// same reference_id turns out to replay an earlier shipment.
+    pub async fn ship(
+        &self,
+        db: &Database,
+        id: i32,
+        confirmation: ShipOrder,
+    ) -> AppResult<Option<(OrderDetail, ShipmentDetail)>> {
+        let Some(service_level) = confirmation.service_level.clone() else {
+            return Ok(db.orders().ship(id, confirmation, None).await?);
+        };
+        if let Some(reference_id) = &confirmation.reference_id {
+            if db.postings().find(reference_id).await?.is_some() {
+                return Ok(db.orders().ship(id, confirmation, None).await?);
+            }
+        }
+
+        let code = confirmation
+            .carrier_code
+            .as_deref()
+            .ok_or_else(|| AppError::validation("carrier_code is required to buy a label"))?;
+        let carrier = self.get(code).ok_or_else(|| unknown_carrier(code))?;
+        let Some(order) = db.orders().get_by_id(id).await? else {
+            return Ok(None);
+        };
+        // The supplier ships dropship orders; buying a label here would only have to be voided
+        if order.order.order_type == ORDER_TYPE_DROPSHIP {
+            return Err(AppError::BusinessRule(format!("{} is a dropship order", order.order.order_number)));
+        }
+
+        let mut shipping = Vec::with_capacity(confirmation.lines.len());
+        for shipped in &confirmation.lines {
+            let line = order
+                .lines
+                .iter()
+                .find(|line| line.line_id == shipped.line_id)
+                .ok_or_else(|| {
+                    AppError::BusinessRule(format!("line {} is not part of {}", shipped.line_id, order.order.order_number))
+                })?;
+            shipping.push((line.item_id, shipped.quantity));
+        }
+        for content in db.lpns().totals(order.order.warehouse_id, &confirmation.lpns).await? {
+            shipping.push((content.item_id, content.quantity));
+        }
+
+        let label = carrier
+            .purchase_label(&LabelRequest {
+                reference: order.order.order_number.clone(),
+                service_level,
+                ship_from: warehouse_address(db, order.order.warehouse_id).await?,
+                ship_to: ship_to(&order.order),
+                parcel: parcel(db, &shipping).await?,
+            })
+            .await?;
+        let label_id = label.label_id.clone();
+
+        match db.orders().ship(id, confirmation, Some(label)).await {
+            Ok(Some((order, shipment))) => {
+                if shipment.shipment.label_id.as_deref() != Some(label_id.as_str()) {
+                    if let Err(void_error) = carrier.void_label(&label_id).await {
+                        warn!("Could not void {} label {}: {}", carrier.code(), label_id, void_error);
+                    }
+                }
+                Ok(Some((order, shipment)))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => {
+                if let Err(void_error) = carrier.void_label(&label_id).await {
+                    warn!("Could not void {} label {}: {}", carrier.code(), label_id, void_error);
+                }
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Buy a label for a transfer from its source to its destination warehouse
+    pub async fn purchase_transfer_label(&self, db: &Database, detail: &TransferDetail) -> AppResult<ShipmentLabel> {
+        let transfer = &detail.transfer;
+        let (Some(code), Some(service_level)) = (&transfer.carrier_code, &transfer.service_level) else {
+            return Err(AppError::validation(format!("{} has no carrier service", transfer.transfer_number)));
+        };
+        let carrier = self.get(code).ok_or_else(|| unknown_carrier(code))?;
+        let contents: Vec<(i32, Decimal)> = detail.lines.iter().map(|line| (line.item_id, line.quantity)).collect();
+
+        carrier
+            .purchase_label(&LabelRequest {
+                reference: transfer.transfer_number.clone(),
+                service_level: service_level.clone(),
+                ship_from: warehouse_address(db, transfer.source_warehouse_id).await?,
+                ship_to: warehouse_address(db, transfer.destination_warehouse_id).await?,
+                parcel: parcel(db, &contents).await?,
+            })
+            .await
+    }
+
+    /// Void a label bought from the carrier with `code`
+    pub async fn void_label(&self, code: &str, label_id: &str) -> AppResult<()> {
+        let carrier = self.get(code).ok_or_else(|| unknown_carrier(code))?;
+        carrier.void_label(label_id).await
+    }
+}
+
+fn unknown_carrier(code: &str) -> AppError {
+    AppError::validation(format!("no carrier configured for {}", code))
+}
+
+fn ship_to(order: &OutboundOrder) -> Address {
+    Address {
+        name: order.ship_to_name.clone().or_else(|| Some(order.customer_name.clone())),
+        address: order.ship_to_address.clone(),
+        city: order.ship_to_city.clone(),
+        state: order.ship_to_state.clone(),
+        postal_code: order.ship_to_postal_code.clone(),
+        country: order.ship_to_country.clone(),
+    }
+}
+
+async fn warehouse_address(db: &Database, warehouse_id: i32) -> AppResult<Address> {
+    let warehouse = db
+        .warehouses()
+        .get_by_id(warehouse_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("warehouse"))?;
+
+    Ok(Address {
+        name: Some(warehouse.warehouse_name),
+        address: warehouse.address,
+        city: warehouse.city,
+        state: warehouse.state,
+        postal_code: warehouse.postal_code,
+        country: warehouse.country,
+    })
+}
+
+/// One parcel weighing the sum of the item weights; items without a weight count as zero
+async fn parcel(db: &Database, contents: &[(i32, Decimal)]) -> AppResult<Parcel> {
+    let mut weight_kg = Decimal::ZERO;
+    for (item_id, quantity) in contents {
+        if let Some(weight) = db.items().get_by_id(*item_id).await?.and_then(|item| item.weight_kg) {
+            weight_kg += weight * quantity;
+        }
+    }
+    Ok(Parcel { weight_kg })
+}
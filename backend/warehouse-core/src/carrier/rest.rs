@@ -0,0 +1,157 @@
+//! Generic REST carrier. Speaks a small JSON protocol that carrier adapters or
+//! aggregators can expose:
+//!
+//! - `POST {base_url}/rates` with a [`RateRequest`] → `{"rates": [{service_level, service_name, amount, currency, estimated_days}]}`
+//! - `POST {base_url}/labels` with a [`LabelRequest`] → `{label_id, tracking_number, label_url, label_format, amount, currency}`
+//! - `DELETE {base_url}/labels/{label_id}`
+//! - `GET {base_url}/tracking/{tracking_number}` → `{"events": [{status, description, location, occurred_at}]}`
+//!
+//! Requests carry `Authorization: Bearer <api_key>` when a key is configured.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{RequestBuilder, Response};
+use serde::Deserialize;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+use super::{Carrier, LabelRequest, RateRequest};
+use crate::config::CarrierConfig;
+use crate::{AppError, AppResult};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub struct RestCarrier {
+    code: String,
+    base_url: String,
+    api_key: Option<String>,
+    http: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct RatesResponse {
+    rates: Vec<Rate>,
+}
+
+#[derive(Deserialize)]
+struct Rate {
+    service_level: String,
+    service_name: Option<String>,
+    amount: Decimal,
+    currency: String,
+    estimated_days: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct TrackingResponse {
+    events: Vec<NewTrackingEvent>,
+}
+
+#[derive(Deserialize)]
+struct LabelResponse {
+    label_id: String,
+    tracking_number: String,
+    label_url: Option<String>,
+    label_format: Option<String>,
+    amount: Option<Decimal>,
+    currency: Option<String>,
+}
+
+impl RestCarrier {
+    pub fn new(config: &CarrierConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            code: config.code.to_uppercase(),
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            api_key: config.api_key.clone(),
+            http,
+        }
+    }
+
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    fn error(&self, message: impl ToString) -> AppError {
+        AppError::ExternalService {
+            service: format!("carrier {}", self.code),
+            message: message.to_string(),
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> AppResult<Response> {
+        let response = self.authorized(request).send().await.map_err(|e| self.error(e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(self.error(format!("{} {}", status, body)));
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl Carrier for RestCarrier {
+    fn code(&self) -> &str {
+        &self.code
+    }
+
+    async fn rates(&self, request: &RateRequest) -> AppResult<Vec<RateQuote>> {
+        let response = self
+            .send(self.http.post(format!("{}/rates", self.base_url)).json(request))
+            .await?;
+        let body: RatesResponse = response.json().await.map_err(|e| self.error(e))?;
+
+        Ok(body
+            .rates
+            .into_iter()
+            .map(|rate| RateQuote {
+                carrier_code: self.code.clone(),
+                service_level: rate.service_level,
+                service_name: rate.service_name,
+                amount: rate.amount,
+                currency: rate.currency,
+                estimated_days: rate.estimated_days,
+            })
+            .collect())
+    }
+
+    async fn purchase_label(&self, request: &LabelRequest) -> AppResult<ShipmentLabel> {
+        let response = self
+            .send(self.http.post(format!("{}/labels", self.base_url)).json(request))
+            .await?;
+        let label: LabelResponse = response.json().await.map_err(|e| self.error(e))?;
+
+        Ok(ShipmentLabel {
+            label_id: label.label_id,
+            tracking_number: label.tracking_number,
+            service_level: request.service_level.clone(),
+            label_url: label.label_url,
+            label_format: label.label_format,
+            amount: label.amount,
+            currency: label.currency,
+        })
+    }
+
+    async fn void_label(&self, label_id: &str) -> AppResult<()> {
+        self.send(self.http.delete(format!("{}/labels/{}", self.base_url, label_id)))
+            .await?;
+        Ok(())
+    }
+
+    async fn tracking(&self, tracking_number: &str) -> AppResult<Vec<NewTrackingEvent>> {
+        let response = self
+            .send(self.http.get(format!("{}/tracking/{}", self.base_url, tracking_number)))
+            .await?;
+        let body: TrackingResponse = response.json().await.map_err(|e| self.error(e))?;
+        Ok(body.events)
+    }
+}
@@ -0,0 +1,68 @@
+//! Tracking event intake, from carrier webhooks and from polling carriers for
+//! shipments that are still moving
+
+use tracing::warn;
+use warehouse_db::Database;
+use warehouse_models::*;
+
+use super::Carriers;
+use crate::{notify, AppResult};
+
+/// Shipments are polled for this long after they leave; older ones are assumed lost to follow-up
+const POLL_MAX_AGE_DAYS: i32 = 60;
+const POLL_BATCH: i64 = 200;
+
+/// Record tracking events for a shipment; new delivery exceptions raise a notification
+pub async fn ingest(
+    db: &Database,
+    shipment: &Shipment,
+    events: Vec<NewTrackingEvent>,
+    source: &str,
+) -> AppResult<Vec<TrackingEvent>> {
+    let recorded = db.tracking().record(shipment.shipment_id, events, source).await?;
+
+    for event in recorded.iter().filter(|e| e.status == TrackingStatus::Exception.as_str()) {
+        notify::raise(
+            db,
+            NewNotification {
+                kind: NOTIFY_DELIVERY_EXCEPTION,
+                severity: SEVERITY_WARNING,
+                subject: format!(
+                    "Delivery exception on {} ({} {})",
+                    shipment.shipment_number,
+                    shipment.carrier_code.as_deref().unwrap_or_default(),
+                    shipment.tracking_number.as_deref().unwrap_or_default()
+                ),
+                message: event.description.clone(),
+                reference_type: Some("SHIPMENT".to_string()),
+                reference_id: Some(shipment.shipment_id),
+            },
+        )
+        .await?;
+    }
+
+    Ok(recorded)
+}
+
+impl Carriers {
+    /// Ask carriers for news on shipments still in flight. Returns how many new events
+    /// were recorded; a carrier failing for one shipment does not stop the rest.
+    pub async fn poll_tracking(&self, db: &Database) -> AppResult<usize> {
+        let mut recorded = 0;
+        for shipment in db.tracking().pollable(POLL_MAX_AGE_DAYS, POLL_BATCH).await? {
+            let (Some(code), Some(tracking_number)) = (&shipment.carrier_code, &shipment.tracking_number) else {
+                continue;
+            };
+            let Some(carrier) = self.get(code) else {
+                continue;
+            };
+
+            match carrier.tracking(tracking_number).await {
+                Ok(events) if events.is_empty() => {}
+                Ok(events) => recorded += ingest(db, &shipment, events, TRACKING_SOURCE_POLL).await?.len(),
+                Err(e) => warn!("Tracking poll for {} failed: {}", shipment.shipment_number, e),
+            }
+        }
+        Ok(recorded)
+    }
+}
@@ -0,0 +1,171 @@
+//! Cartonization: choose boxes for what is left to pack on an order and assign its units
+//! to them. Units go largest first into the first open carton with room for them
+//! (first-fit decreasing), a new carton taking the largest box the unit fits; each carton
+//! is then shrunk to the smallest box its contents fit in.
+//!
+//! Room is judged on volume (up to `CARTON_FILL_PERCENT` of the box), weight and each
+//! unit's own dimensions against the box's; units are not stacked geometrically.
+
+use warehouse_models::rust_decimal::prelude::ToPrimitive;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+const CM3_PER_CBM: i64 = 1_000_000;
+
+/// One unit of an order line, or the fractional remainder of a line
+struct Unit {
+    line: usize,
+    quantity: Decimal,
+    /// Longest side first; None when the item has no dimensions
+    dims: Option<[Decimal; 3]>,
+    volume: Decimal,
+    weight: Decimal,
+}
+
+struct Capacity<'a> {
+    size: &'a BoxSize,
+    dims: [Decimal; 3],
+    /// Fillable share of the inside volume
+    volume: Decimal,
+}
+
+impl<'a> Capacity<'a> {
+    fn new(size: &'a BoxSize) -> Self {
+        let volume = size.volume_cm3() * Decimal::from(CARTON_FILL_PERCENT) / Decimal::from(100);
+        Self { size, dims: sorted([size.length_cm, size.width_cm, size.height_cm]), volume }
+    }
+
+    fn admits(&self, unit: &Unit) -> bool {
+        self.has_room(unit.volume, unit.weight) && self.fits_dims(unit)
+    }
+
+    fn has_room(&self, volume: Decimal, weight: Decimal) -> bool {
+        volume <= self.volume && weight <= self.size.max_weight_kg
+    }
+
+    fn fits_dims(&self, unit: &Unit) -> bool {
+        unit.dims.is_none_or(|dims| dims.iter().zip(self.dims).all(|(side, inside)| *side <= inside))
+    }
+}
+
+struct OpenCarton {
+    capacity: usize,
+    volume: Decimal,
+    weight: Decimal,
+    units: Vec<usize>,
+}
+
+/// Suggest cartons for `lines` from a warehouse's box sizes. Inactive boxes are ignored.
+pub fn plan(order_id: i32, lines: &[PackingLine], boxes: &[BoxSize]) -> CartonPlan {
+    let mut warnings = Vec::new();
+
+    let mut capacities: Vec<Capacity> = boxes.iter().filter(|size| size.is_active).map(Capacity::new).collect();
+    capacities.sort_by(|a, b| a.volume.cmp(&b.volume).then(a.size.max_weight_kg.cmp(&b.size.max_weight_kg)));
+    if capacities.is_empty() && !lines.is_empty() {
+        warnings.push("the warehouse has no box sizes configured".to_string());
+    }
+
+    let mut units: Vec<Unit> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        units.extend(units_of(index, line, &mut warnings));
+    }
+    units.sort_by(|a, b| b.volume.cmp(&a.volume).then(b.weight.cmp(&a.weight)));
+
+    let mut cartons: Vec<OpenCarton> = Vec::new();
+    let mut unboxed: Vec<usize> = Vec::new();
+    for (index, unit) in units.iter().enumerate() {
+        let open = cartons.iter_mut().find(|carton| {
+            let capacity = &capacities[carton.capacity];
+            capacity.has_room(carton.volume + unit.volume, carton.weight + unit.weight) && capacity.fits_dims(unit)
+        });
+        if let Some(carton) = open {
+            carton.volume += unit.volume;
+            carton.weight += unit.weight;
+            carton.units.push(index);
+        } else if let Some(capacity) = capacities.iter().rposition(|capacity| capacity.admits(unit)) {
+            cartons.push(OpenCarton { capacity, volume: unit.volume, weight: unit.weight, units: vec![index] });
+        } else {
+            unboxed.push(index);
+        }
+    }
+
+    let cartons = cartons
+        .into_iter()
+        .map(|carton| {
+            let smallest = capacities
+                .iter()
+                .position(|capacity| {
+                    capacity.has_room(carton.volume, carton.weight)
+                        && carton.units.iter().all(|&unit| capacity.fits_dims(&units[unit]))
+                })
+                .unwrap_or(carton.capacity);
+            let size = capacities[smallest].size;
+            SuggestedCarton {
+                box_id: size.box_id,
+                box_code: size.box_code.clone(),
+                lines: merge(&carton.units, &units, lines),
+                content_volume_cm3: carton.volume.round_dp(2),
+                content_weight_kg: carton.weight.round_dp(3),
+                gross_weight_kg: (carton.weight + size.tare_weight_kg).round_dp(3),
+                fill_percent: (carton.volume * Decimal::from(100) / size.volume_cm3()).round_dp(1),
+            }
+        })
+        .collect();
+
+    CartonPlan { order_id, cartons, unboxed: merge(&unboxed, &units, lines), warnings }
+}
+
+fn units_of(index: usize, line: &PackingLine, warnings: &mut Vec<String>) -> Vec<Unit> {
+    let weight = line.weight_kg.unwrap_or_else(|| {
+        warnings.push(format!("{} has no weight", line.item_code));
+        Decimal::ZERO
+    });
+    let (dims, volume) = match (line.length_cm, line.width_cm, line.height_cm, line.volume_cbm) {
+        (Some(length), Some(width), Some(height), _) => {
+            (Some(sorted([length, width, height])), length * width * height)
+        }
+        (_, _, _, Some(cbm)) => (None, cbm * Decimal::from(CM3_PER_CBM)),
+        _ => {
+            warnings.push(format!("{} has no dimensions", line.item_code));
+            (None, Decimal::ZERO)
+        }
+    };
+
+    let whole = line.quantity.trunc();
+    let remainder = line.quantity - whole;
+    let unit =
+        |quantity: Decimal| Unit { line: index, quantity, dims, volume: volume * quantity, weight: weight * quantity };
+
+    let mut units: Vec<Unit> = (0..whole.to_usize().unwrap_or(0)).map(|_| unit(Decimal::ONE)).collect();
+    if !remainder.is_zero() {
+        units.push(unit(remainder));
+    }
+    units
+}
+
+/// Sum the units' quantities per order line, in line order
+fn merge(indexes: &[usize], units: &[Unit], lines: &[PackingLine]) -> Vec<SuggestedCartonLine> {
+    let mut totals: Vec<(usize, Decimal)> = Vec::new();
+    for &index in indexes {
+        let unit = &units[index];
+        match totals.iter_mut().find(|(line, _)| *line == unit.line) {
+            Some((_, quantity)) => *quantity += unit.quantity,
+            None => totals.push((unit.line, unit.quantity)),
+        }
+    }
+    totals.sort_by_key(|(line, _)| *line);
+
+    totals
+        .into_iter()
+        .map(|(line, quantity)| SuggestedCartonLine {
+            line_id: lines[line].line_id,
+            item_id: lines[line].item_id,
+            quantity,
+        })
+        .collect()
+}
+
+fn sorted(mut sides: [Decimal; 3]) -> [Decimal; 3] {
+    sides.sort_by(|a, b| b.cmp(a));
+    sides
+}
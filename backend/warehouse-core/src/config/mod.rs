@@ -2,7 +2,18 @@
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
+use std::time::Duration;
+use warehouse_db::ConnectionSettings;
+use warehouse_models::{REQUISITION_METHODS, REQUISITION_METHOD_MIN_MAX};
+
+pub mod runtime;
+
+pub use runtime::{LiveConfig, RuntimeConfig};
+
+/// The API key shipped as the default; it is never accepted as a key
+pub const DEFAULT_API_KEY: &str = "default-api-key";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -11,6 +22,29 @@ pub struct Config {
     pub redis: RedisConfig,
     pub logging: LoggingConfig,
     pub security: SecurityConfig,
+    pub edi: EdiConfig,
+    pub carriers: Vec<CarrierConfig>,
+    pub shards: Vec<ShardConfig>,
+    pub tracking: TrackingConfig,
+    pub vmi: VmiConfig,
+    pub billing: BillingConfig,
+    pub purchasing: PurchasingConfig,
+    pub projects: ProjectConfig,
+    pub loans: LoanConfig,
+    pub read_models: ReadModelConfig,
+    pub stock: StockConfig,
+    pub events: EventConfig,
+    pub backups: BackupConfig,
+    pub integrity: IntegrityConfig,
+    pub erp: ErpConfig,
+    pub gl: GlConfig,
+    pub ecommerce: EcommerceConfig,
+    pub reports: ReportConfig,
+    pub mail: MailConfig,
+    pub jobs: JobConfig,
+    pub pagination: PaginationConfig,
+    /// Reloadable without a restart
+    pub runtime: RuntimeConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +55,8 @@ pub struct ServerConfig {
     pub enable_cors: bool,
     pub enable_swagger: bool,
     pub enable_request_logging: bool,
+    /// Serve seed data from memory instead of Postgres (`--mock`)
+    pub mock_mode: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,12 +65,63 @@ pub struct DatabaseConfig {
     pub max_connections: u32,
     pub min_connections: u32,
     pub acquire_timeout: u64,
+    /// Seconds, `0` keeps idle connections open
+    pub idle_timeout: u64,
+    /// Seconds, `0` keeps connections for as long as they stay healthy
+    pub max_lifetime: u64,
+    /// `DATABASE_SSL_MODE`, overriding any `sslmode` in the URL
+    pub ssl_mode: Option<String>,
+    /// `DATABASE_SSL_ROOT_CERT`, path to the CA bundle used by `verify-ca`/`verify-full`
+    pub ssl_root_cert: Option<String>,
+    pub application_name: String,
+    /// `DATABASE_SESSION_SETTINGS=statement_timeout=30s,lock_timeout=5s`, applied to every connection
+    pub session_settings: Vec<(String, String)>,
+    /// Seconds between copies of warehouses and items to the shards, `0` copies only at startup
+    /// and as they change through the API
+    pub shard_copy_interval_secs: u64,
+}
+
+impl DatabaseConfig {
+    /// Pool settings for a shard: its own URL, everything else as for the primary
+    pub fn shard_settings(&self, shard: &ShardConfig) -> ConnectionSettings {
+        ConnectionSettings { url: shard.url.clone(), ..self.connection_settings() }
+    }
+
+    pub fn connection_settings(&self) -> ConnectionSettings {
+        ConnectionSettings {
+            url: self.url.clone(),
+            max_connections: self.max_connections,
+            min_connections: self.min_connections,
+            acquire_timeout: Duration::from_secs(self.acquire_timeout),
+            idle_timeout: (self.idle_timeout > 0).then(|| Duration::from_secs(self.idle_timeout)),
+            max_lifetime: (self.max_lifetime > 0).then(|| Duration::from_secs(self.max_lifetime)),
+            ssl_mode: self.ssl_mode.clone(),
+            ssl_root_cert: self.ssl_root_cert.clone(),
+            application_name: Some(self.application_name.clone()),
+            session_settings: self.session_settings.clone(),
+        }
+    }
+}
+
+/// A Postgres cluster holding the stock of some warehouses. Each shard carries the full
+/// schema and a copy of the primary's warehouses and items; warehouses not listed in any
+/// shard stay on the primary database. Only stock, reservations and warehouse setup are
+/// served from a shard: receipts, orders, transfers and other documents for a sharded
+/// warehouse are refused, since they are kept on the primary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardConfig {
+    pub name: String,
+    pub url: String,
+    pub warehouse_ids: Vec<i32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RedisConfig {
     pub url: String,
     pub password: Option<String>,
+    /// Elect one replica to run each recurring job through leases in Redis. Off for a
+    /// single replica, which runs every job itself.
+    pub scheduler_locks: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,15 +134,183 @@ pub struct LoggingConfig {
 pub struct SecurityConfig {
     pub jwt_secret: String,
     pub api_key: String,
+    /// How long a sign-in's access token is accepted
+    pub access_token_ttl_secs: u64,
+    /// How long a refresh token may be exchanged; each exchange starts it afresh
+    pub refresh_token_ttl_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdiConfig {
+    /// Our interchange id (ISA06/GS02) on outbound documents
+    pub sender_id: String,
+}
+
+/// A carrier reached through the generic REST carrier protocol
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CarrierConfig {
+    /// Code used as `carrier_code` on shipments (e.g. JNE)
+    pub code: String,
+    pub base_url: String,
+    pub api_key: Option<String>,
+    /// Shared secret the carrier sends in `X-Webhook-Token` on tracking webhooks
+    pub webhook_secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackingConfig {
+    /// How often carriers are polled for tracking events; 0 disables polling
+    pub poll_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VmiConfig {
+    /// How often due supplier feeds are looked for; 0 disables the scheduler
+    pub poll_interval_secs: u64,
+    /// Private key used for SFTP drops; the ssh agent and defaults are used when unset
+    pub sftp_identity_file: Option<String>,
+    /// `sftp` client binary
+    pub sftp_command: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BillingConfig {
+    /// How often client billable events are captured; 0 disables capture between invoice runs
+    pub capture_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchasingConfig {
+    /// How often reorder alerts are turned into draft requisitions; 0 leaves it to the API
+    pub requisition_interval_secs: u64,
+    /// Quantity method of scheduled requisitions: `EOQ` or `MIN_MAX`
+    pub requisition_method: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectConfig {
+    /// How often project reservations past their expiry date are expired; 0 disables expiry
+    pub reservation_expiry_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoanConfig {
+    /// How often loans past their due date are flagged overdue; 0 disables the scan
+    pub overdue_scan_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadModelConfig {
+    /// How often pending domain events are projected into the read models; 0 disables projection
+    pub project_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockConfig {
+    /// Answer stock queries from the stock event streams rather than the balances table
+    pub event_sourced: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventConfig {
+    /// How often stock events are sent to consumers that are behind; 0 disables delivery
+    pub delivery_interval_secs: u64,
+    /// Attempts at a batch before it is dead-lettered
+    pub max_attempts: i32,
+    /// Delay before the first retry; doubled after every further failure
+    pub retry_base_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityConfig {
+    /// How often stock invariants are checked; 0 disables the scheduled checks
+    pub check_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErpConfig {
+    /// How often due ERP connectors are looked for; 0 disables scheduled syncs
+    pub poll_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlConfig {
+    /// Accounting API journals are posted to; exports are CSV only when unset
+    pub export_url: Option<String>,
+    /// Sent as a bearer token
+    pub export_api_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EcommerceConfig {
+    /// How often due e-commerce channels are looked for; 0 disables scheduled syncs
+    pub poll_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportConfig {
+    /// How often due report schedules are looked for; 0 disables scheduled delivery
+    pub poll_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MailConfig {
+    /// Sender of the system's email
+    pub from_address: String,
+    /// `sendmail`-compatible binary the messages are piped to
+    pub sendmail_command: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobConfig {
+    /// How long signed download URLs for job artifacts stay valid
+    pub download_ttl_secs: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PaginationConfig {
+    /// `PAGE_SIZE_LIMITS=movements=100,items=50`: largest `limit` a list accepts, by its
+    /// path under `/api` without ids (e.g. `stations/captures`). Lists not named take as
+    /// many rows as the store returns at once.
+    pub page_size_limits: BTreeMap<String, i64>,
+}
+
+impl PaginationConfig {
+    /// Largest `limit` the list accepts, at most `ceiling`
+    pub fn max_page_size(&self, resource: &str, ceiling: i64) -> i64 {
+        self.page_size_limits.get(resource).map_or(ceiling, |limit| (*limit).min(ceiling))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupConfig {
+    /// Where archives are written: `s3://bucket/prefix` or a directory as `file:///path`
+    pub store_url: String,
+    /// AWS CLI used for `s3://` stores
+    pub s3_command: String,
+    /// S3-compatible endpoint such as MinIO; AWS when unset
+    pub s3_endpoint_url: Option<String>,
 }
 
 impl Config {
     /// Load configuration from environment variables - Returns Result
     pub fn from_env() -> Result<Self> {
+        Self::load(false)
+    }
+
+    /// Load configuration for `--mock` runs, where DATABASE_URL is optional
+    pub fn from_env_mock() -> Result<Self> {
+        Self::load(true)
+    }
+
+    fn load(mock_mode: bool) -> Result<Self> {
         dotenvy::dotenv().ok();
         
-        let database_url = env::var("DATABASE_URL")
-            .map_err(|_| anyhow::anyhow!("DATABASE_URL must be set"))?;
+        let database_url = match env::var("DATABASE_URL") {
+            Ok(url) => url,
+            Err(_) if mock_mode => String::new(),
+            Err(_) => anyhow::bail!("DATABASE_URL must be set"),
+        };
         
         let config = Config {
             server: ServerConfig {
@@ -77,6 +332,7 @@ impl Config {
                     .unwrap_or_else(|_| "true".to_string())
                     .parse()
                     .unwrap_or(true),
+                mock_mode,
             },
             database: DatabaseConfig {
                 url: database_url,
@@ -92,10 +348,31 @@ impl Config {
                     .unwrap_or_else(|_| "30".to_string())
                     .parse()
                     .unwrap_or(30),
+                idle_timeout: env::var("DATABASE_IDLE_TIMEOUT")
+                    .unwrap_or_else(|_| "600".to_string())
+                    .parse()
+                    .unwrap_or(600),
+                max_lifetime: env::var("DATABASE_MAX_LIFETIME")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()
+                    .unwrap_or(3600),
+                ssl_mode: env::var("DATABASE_SSL_MODE").ok().filter(|mode| !mode.is_empty()),
+                ssl_root_cert: env::var("DATABASE_SSL_ROOT_CERT").ok().filter(|path| !path.is_empty()),
+                application_name: env::var("DATABASE_APPLICATION_NAME")
+                    .unwrap_or_else(|_| "warehouse-api".to_string()),
+                session_settings: session_settings_from_env()?,
+                shard_copy_interval_secs: env::var("DATABASE_SHARD_COPY_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "300".to_string())
+                    .parse()
+                    .unwrap_or(300),
             },
             redis: RedisConfig {
                 url: env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string()),
                 password: env::var("REDIS_PASSWORD").ok(),
+                scheduler_locks: env::var("SCHEDULER_LOCKS")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()
+                    .unwrap_or(false),
             },
             logging: LoggingConfig {
                 level: env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string()),
@@ -105,8 +382,133 @@ impl Config {
                 jwt_secret: env::var("JWT_SECRET")
                     .unwrap_or_else(|_| "default-secret-change-in-production".to_string()),
                 api_key: env::var("API_KEY")
-                    .unwrap_or_else(|_| "default-api-key".to_string()),
+                    .unwrap_or_else(|_| DEFAULT_API_KEY.to_string()),
+                access_token_ttl_secs: env::var("ACCESS_TOKEN_TTL_SECS")
+                    .unwrap_or_else(|_| "900".to_string())
+                    .parse()
+                    .unwrap_or(900),
+                refresh_token_ttl_secs: env::var("REFRESH_TOKEN_TTL_SECS")
+                    .unwrap_or_else(|_| "2592000".to_string())
+                    .parse()
+                    .unwrap_or(2592000),
+            },
+            edi: EdiConfig {
+                sender_id: env::var("EDI_SENDER_ID").unwrap_or_else(|_| "LSFWAREHOUSE".to_string()),
+            },
+            carriers: carriers_from_env()?,
+            shards: shards_from_env()?,
+            tracking: TrackingConfig {
+                poll_interval_secs: env::var("TRACKING_POLL_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "900".to_string())
+                    .parse()
+                    .unwrap_or(900),
+            },
+            vmi: VmiConfig {
+                poll_interval_secs: env::var("VMI_POLL_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .unwrap_or(60),
+                sftp_identity_file: env::var("VMI_SFTP_IDENTITY_FILE").ok(),
+                sftp_command: env::var("VMI_SFTP_COMMAND").unwrap_or_else(|_| "sftp".to_string()),
+            },
+            billing: BillingConfig {
+                capture_interval_secs: env::var("BILLING_CAPTURE_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()
+                    .unwrap_or(3600),
+            },
+            purchasing: PurchasingConfig {
+                requisition_interval_secs: env::var("REQUISITION_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                requisition_method: env::var("REQUISITION_METHOD")
+                    .map(|method| method.to_uppercase())
+                    .unwrap_or_else(|_| REQUISITION_METHOD_MIN_MAX.to_string()),
+            },
+            projects: ProjectConfig {
+                reservation_expiry_interval_secs: env::var("PROJECT_RESERVATION_EXPIRY_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()
+                    .unwrap_or(3600),
+            },
+            loans: LoanConfig {
+                overdue_scan_interval_secs: env::var("LOAN_OVERDUE_SCAN_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()
+                    .unwrap_or(3600),
+            },
+            read_models: ReadModelConfig {
+                project_interval_secs: env::var("READ_MODEL_PROJECT_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "1".to_string())
+                    .parse()
+                    .unwrap_or(1),
+            },
+            stock: StockConfig {
+                event_sourced: env::var("STOCK_EVENT_SOURCED")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()
+                    .unwrap_or(false),
+            },
+            events: EventConfig {
+                delivery_interval_secs: env::var("EVENT_DELIVERY_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()
+                    .unwrap_or(5),
+                max_attempts: env::var("EVENT_DELIVERY_MAX_ATTEMPTS")
+                    .unwrap_or_else(|_| "8".to_string())
+                    .parse()
+                    .unwrap_or(8),
+                retry_base_secs: env::var("EVENT_DELIVERY_RETRY_BASE_SECS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .unwrap_or(30),
+            },
+            backups: BackupConfig {
+                store_url: env::var("BACKUP_STORE_URL").unwrap_or_else(|_| "file://backups".to_string()),
+                s3_command: env::var("BACKUP_S3_COMMAND").unwrap_or_else(|_| "aws".to_string()),
+                s3_endpoint_url: env::var("BACKUP_S3_ENDPOINT_URL").ok().filter(|url| !url.is_empty()),
+            },
+            integrity: IntegrityConfig {
+                check_interval_secs: env::var("INTEGRITY_CHECK_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()
+                    .unwrap_or(3600),
+            },
+            erp: ErpConfig {
+                poll_interval_secs: env::var("ERP_POLL_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .unwrap_or(60),
+            },
+            gl: GlConfig {
+                export_url: env::var("GL_EXPORT_URL").ok(),
+                export_api_key: env::var("GL_EXPORT_API_KEY").ok(),
             },
+            ecommerce: EcommerceConfig {
+                poll_interval_secs: env::var("ECOMMERCE_POLL_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .unwrap_or(60),
+            },
+            reports: ReportConfig {
+                poll_interval_secs: env::var("REPORT_POLL_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .unwrap_or(60),
+            },
+            mail: MailConfig {
+                from_address: env::var("MAIL_FROM_ADDRESS").unwrap_or_else(|_| "warehouse@localhost".to_string()),
+                sendmail_command: env::var("MAIL_SENDMAIL_COMMAND").unwrap_or_else(|_| "sendmail".to_string()),
+            },
+            jobs: JobConfig {
+                download_ttl_secs: env::var("JOB_DOWNLOAD_TTL_SECS")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()
+                    .unwrap_or(3600),
+            },
+            pagination: PaginationConfig { page_size_limits: page_size_limits_from_env()? },
+            runtime: RuntimeConfig::from_env(),
         };
         
         Ok(config)
@@ -118,11 +520,155 @@ impl Config {
             && self.server.environment == "production" {
             anyhow::bail!("JWT_SECRET must be changed in production");
         }
+
+        if self.security.api_key == DEFAULT_API_KEY && self.server.environment == "production" {
+            anyhow::bail!("API_KEY must be changed in production");
+        }
         
         if self.database.max_connections < self.database.min_connections {
             anyhow::bail!("DATABASE_MAX_CONNECTIONS must be >= DATABASE_MIN_CONNECTIONS");
         }
+
+        if self.database.max_connections == 0 {
+            anyhow::bail!("DATABASE_MAX_CONNECTIONS must be at least 1");
+        }
+
+        if self.database.acquire_timeout == 0 {
+            anyhow::bail!("DATABASE_ACQUIRE_TIMEOUT must be at least 1 second");
+        }
+
+        let mut sharded = std::collections::HashSet::new();
+        for shard in &self.shards {
+            if let Some(id) = shard.warehouse_ids.iter().find(|id| !sharded.insert(**id)) {
+                anyhow::bail!("warehouse {} is assigned to more than one database shard", id);
+            }
+        }
+
+        if !self.backups.store_url.starts_with("s3://") && !self.backups.store_url.starts_with("file://") {
+            anyhow::bail!("BACKUP_STORE_URL must be an s3:// or file:// URL");
+        }
+
+        if let Some(mode) = &self.database.ssl_mode {
+            if !SSL_MODES.contains(&mode.as_str()) {
+                anyhow::bail!("DATABASE_SSL_MODE must be one of {}", SSL_MODES.join(", "));
+            }
+        }
+
+        if !REQUISITION_METHODS.contains(&self.purchasing.requisition_method.as_str()) {
+            anyhow::bail!("REQUISITION_METHOD must be one of {}", REQUISITION_METHODS.join(", "));
+        }
+
+        if let Some((resource, _)) = self.pagination.page_size_limits.iter().find(|(_, limit)| **limit < 1) {
+            anyhow::bail!("PAGE_SIZE_LIMITS for {} must be at least 1", resource);
+        }
+
+        if let Some(path) = &self.database.ssl_root_cert {
+            if !std::path::Path::new(path).is_file() {
+                anyhow::bail!("DATABASE_SSL_ROOT_CERT {} does not exist", path);
+            }
+        }
         
         Ok(())
     }
 }
+
+const SSL_MODES: [&str; 6] = ["disable", "allow", "prefer", "require", "verify-ca", "verify-full"];
+
+/// `DATABASE_SESSION_SETTINGS=statement_timeout=30s,lock_timeout=5s`
+fn session_settings_from_env() -> Result<Vec<(String, String)>> {
+    let Ok(settings) = env::var("DATABASE_SESSION_SETTINGS") else {
+        return Ok(Vec::new());
+    };
+
+    settings
+        .split(',')
+        .map(str::trim)
+        .filter(|setting| !setting.is_empty())
+        .map(|setting| {
+            let (name, value) = setting
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("DATABASE_SESSION_SETTINGS entry {} must be name=value", setting))?;
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+/// `PAGE_SIZE_LIMITS=movements=100,items=50`
+fn page_size_limits_from_env() -> Result<BTreeMap<String, i64>> {
+    let Ok(limits) = env::var("PAGE_SIZE_LIMITS") else {
+        return Ok(BTreeMap::new());
+    };
+
+    limits
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (resource, limit) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("PAGE_SIZE_LIMITS entry {} must be resource=limit", entry))?;
+            let limit = limit
+                .trim()
+                .parse()
+                .map_err(|_| anyhow::anyhow!("PAGE_SIZE_LIMITS has invalid limit {} for {}", limit, resource))?;
+            Ok((resource.trim().to_string(), limit))
+        })
+        .collect()
+}
+
+/// `DATABASE_SHARDS=EU,APAC` with `DATABASE_SHARD_<NAME>_URL` and
+/// `DATABASE_SHARD_<NAME>_WAREHOUSES=3,4` (both required)
+fn shards_from_env() -> Result<Vec<ShardConfig>> {
+    let Ok(names) = env::var("DATABASE_SHARDS") else {
+        return Ok(Vec::new());
+    };
+
+    names
+        .split(',')
+        .map(|name| name.trim().to_uppercase())
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let url = env::var(format!("DATABASE_SHARD_{}_URL", name))
+                .map_err(|_| anyhow::anyhow!("DATABASE_SHARD_{}_URL must be set", name))?;
+            let warehouses = env::var(format!("DATABASE_SHARD_{}_WAREHOUSES", name))
+                .map_err(|_| anyhow::anyhow!("DATABASE_SHARD_{}_WAREHOUSES must be set", name))?;
+            let warehouse_ids = warehouses
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(|id| {
+                    id.parse().map_err(|_| {
+                        anyhow::anyhow!("DATABASE_SHARD_{}_WAREHOUSES has invalid warehouse id {}", name, id)
+                    })
+                })
+                .collect::<Result<Vec<i32>>>()?;
+            Ok(ShardConfig { name, url, warehouse_ids })
+        })
+        .collect()
+}
+
+/// `CARRIERS=JNE,SICEPAT` with `CARRIER_<CODE>_URL` (required), `CARRIER_<CODE>_API_KEY`
+/// and `CARRIER_<CODE>_WEBHOOK_SECRET`
+fn carriers_from_env() -> Result<Vec<CarrierConfig>> {
+    let Ok(codes) = env::var("CARRIERS") else {
+        return Ok(Vec::new());
+    };
+
+    codes
+        .split(',')
+        .map(|code| code.trim().to_uppercase())
+        .filter(|code| !code.is_empty())
+        .map(|code| {
+            let base_url = env::var(format!("CARRIER_{}_URL", code))
+                .map_err(|_| anyhow::anyhow!("CARRIER_{}_URL must be set", code))?;
+            let api_key = env::var(format!("CARRIER_{}_API_KEY", code)).ok();
+            let webhook_secret = env::var(format!("CARRIER_{}_WEBHOOK_SECRET", code)).ok();
+            Ok(CarrierConfig {
+                code,
+                base_url,
+                api_key,
+                webhook_secret,
+            })
+        })
+        .collect()
+}
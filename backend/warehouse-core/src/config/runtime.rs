@@ -0,0 +1,106 @@
+//! Settings that can change while the server runs. They are read from the environment at
+//! startup like the rest of the config; a reload re-reads `.env` over the environment and
+//! publishes the new values to everything watching [`LiveConfig`].
+
+use std::collections::BTreeSet;
+use std::env;
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tracing::info;
+
+const DEFAULT_LOG_FILTER: &str = "warehouse_api=debug,tower_http=debug";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeConfig {
+    /// `tracing` filter directives, e.g. `info` or `warehouse_api=debug,sqlx=warn`
+    pub log_filter: String,
+    /// Requests each caller may make per minute; 0 disables the limit
+    pub rate_limit_per_minute: u32,
+    /// Origins allowed to call the API from a browser; empty allows any origin
+    pub cors_origins: Vec<String>,
+    /// Names of the feature flags switched on
+    pub features: BTreeSet<String>,
+}
+
+impl RuntimeConfig {
+    pub fn from_env() -> Self {
+        Self {
+            log_filter: env::var("RUST_LOG").unwrap_or_else(|_| DEFAULT_LOG_FILTER.to_string()),
+            rate_limit_per_minute: env::var("RATE_LIMIT_PER_MINUTE")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            cors_origins: list("CORS_ORIGINS"),
+            features: list("FEATURE_FLAGS").into_iter().collect(),
+        }
+    }
+
+    pub fn feature(&self, name: &str) -> bool {
+        self.features.contains(name)
+    }
+
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.cors_origins.is_empty() || self.cors_origins.iter().any(|allowed| allowed == origin)
+    }
+}
+
+/// Comma-separated values of an environment variable, trimmed, without empties
+fn list(name: &str) -> Vec<String> {
+    env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The current [`RuntimeConfig`], shared by every clone and replaced as a whole on reload
+#[derive(Clone)]
+pub struct LiveConfig {
+    sender: Arc<watch::Sender<Arc<RuntimeConfig>>>,
+}
+
+impl LiveConfig {
+    pub fn new(config: RuntimeConfig) -> Self {
+        let (sender, _) = watch::channel(Arc::new(config));
+        Self { sender: Arc::new(sender) }
+    }
+
+    pub fn current(&self) -> Arc<RuntimeConfig> {
+        self.sender.borrow().clone()
+    }
+
+    /// Receiver that is notified whenever a reload changes the config
+    pub fn subscribe(&self) -> watch::Receiver<Arc<RuntimeConfig>> {
+        self.sender.subscribe()
+    }
+
+    /// Publish `config` if it differs from the current one. Returns whether it did.
+    pub fn replace(&self, config: RuntimeConfig) -> bool {
+        self.sender.send_if_modified(|current| {
+            if **current == config {
+                return false;
+            }
+            *current = Arc::new(config);
+            true
+        })
+    }
+
+    /// Re-read `.env` and the environment and publish the result
+    pub fn reload(&self) -> Result<Arc<RuntimeConfig>> {
+        if let Err(e) = dotenvy::dotenv_override() {
+            if !e.not_found() {
+                return Err(e.into());
+            }
+        }
+
+        if self.replace(RuntimeConfig::from_env()) {
+            info!("Runtime configuration reloaded");
+        }
+        Ok(self.current())
+    }
+}
@@ -0,0 +1,298 @@
+//! Shipment documents: a delivery order listing what was handed over for the consignee to
+//! sign, and a proforma invoice stating what the goods are worth at standard cost. Both
+//! are rendered as PDF under the shipping warehouse's branding, or the tenant's, and kept
+//! with the shipment.
+
+pub mod pdf;
+
+use warehouse_db::Database;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+use crate::AppResult;
+use pdf::{Font, Page, Rgb, PAGE_HEIGHT, PAGE_WIDTH};
+
+const MARGIN: f32 = 40.0;
+const ROW_HEIGHT: f32 = 16.0;
+/// Rows stop here to leave room for the footer
+const BODY_BOTTOM: f32 = 90.0;
+/// Characters of a description that fit its column
+const DESCRIPTION_CHARS: usize = 34;
+
+/// Render the shipment's document of `document_type` and keep it with the shipment,
+/// replacing one generated before. Returns None when the shipment does not exist.
+pub async fn generate(db: &Database, shipment_id: i32, document_type: &str) -> AppResult<Option<ShipmentDocument>> {
+    let Some(source) = db.documents().source(shipment_id).await? else {
+        return Ok(None);
+    };
+    let branding = db.documents().branding_for(source.shipment.warehouse_id).await?;
+
+    let number = document_number(document_type, &source.shipment.shipment_number);
+    let content = render(document_type, &number, &source, branding.as_ref());
+    let document = db.documents().save(shipment_id, document_type, &number, &content).await?;
+    Ok(Some(document))
+}
+
+/// `DO-` or `PI-` and the shipment number
+pub fn document_number(document_type: &str, shipment_number: &str) -> String {
+    let prefix = if document_type == DOCUMENT_PROFORMA_INVOICE { "PI" } else { "DO" };
+    format!("{}-{}", prefix, shipment_number)
+}
+
+/// The PDF. Without any branding the warehouse's name heads the document.
+pub fn render(
+    document_type: &str,
+    number: &str,
+    source: &ShipmentDocumentSource,
+    branding: Option<&DocumentBranding>,
+) -> Vec<u8> {
+    let invoice = document_type == DOCUMENT_PROFORMA_INVOICE;
+    let accent = branding
+        .and_then(|branding| Rgb::from_hex(&branding.accent_color))
+        .or_else(|| Rgb::from_hex(DEFAULT_ACCENT_COLOR))
+        .unwrap_or(Rgb::BLACK);
+    let currency = branding.map(|branding| branding.currency.as_str()).unwrap_or("IDR");
+    let title = if invoice { "PROFORMA INVOICE" } else { "DELIVERY ORDER" };
+
+    let mut document = pdf::Document::new();
+    let page = document.add_page();
+    let mut y = letterhead(page, title, accent, source, branding);
+    y = parties(page, y, number, source);
+
+    let columns = if invoice { INVOICE_COLUMNS } else { DELIVERY_COLUMNS };
+    table_header(page, y, columns, accent, currency);
+    y -= ROW_HEIGHT + 4.0;
+
+    let mut total_quantity = Decimal::ZERO;
+    let mut total_value = Some(Decimal::ZERO);
+    for (index, line) in source.lines.iter().enumerate() {
+        if y < BODY_BOTTOM + ROW_HEIGHT {
+            let page = document.add_page();
+            y = PAGE_HEIGHT - MARGIN - ROW_HEIGHT;
+            table_header(page, y, columns, accent, currency);
+            y -= ROW_HEIGHT + 4.0;
+        }
+        let page = document.pages_mut().last_mut().expect("a page was added above");
+
+        let value = line.unit_value.map(|unit_value| (unit_value * line.quantity).round_dp(2));
+        total_quantity += line.quantity;
+        total_value = total_value.zip(value).map(|(total, value)| total + value);
+
+        let mut description = line.description.chars().take(DESCRIPTION_CHARS).collect::<String>();
+        if !invoice {
+            if let Some(lot) = &line.lot_number {
+                description = format!("{} (lot {})", description, lot);
+            }
+        }
+        let cells = if invoice {
+            vec![
+                (index + 1).to_string(),
+                line.item_code.clone(),
+                description,
+                quantity(line.quantity, line.unit.as_deref()),
+                line.unit_value.map(money).unwrap_or_else(|| "-".to_string()),
+                value.map(money).unwrap_or_else(|| "-".to_string()),
+            ]
+        } else {
+            vec![
+                (index + 1).to_string(),
+                line.item_code.clone(),
+                description,
+                quantity(line.quantity, line.unit.as_deref()),
+            ]
+        };
+        row(page, y, columns, &cells);
+        y -= ROW_HEIGHT;
+    }
+
+    if y < BODY_BOTTOM + 100.0 {
+        document.add_page();
+        y = PAGE_HEIGHT - MARGIN;
+    }
+    let page = document.pages_mut().last_mut().expect("a page was added above");
+    page.rule(MARGIN, y + ROW_HEIGHT - 4.0, PAGE_WIDTH - MARGIN, y + ROW_HEIGHT - 4.0, 0.75, accent);
+    if invoice {
+        let total = total_value.map(|total| format!("{} {}", currency, money(total)));
+        let total = total.unwrap_or_else(|| "not all items have a cost on file".to_string());
+        page.text_right(PAGE_WIDTH - MARGIN, y, 10.0, Font::Bold, Rgb::BLACK, &format!("Total: {}", total));
+        page.text(
+            MARGIN,
+            y - 2.0 * ROW_HEIGHT,
+            8.0,
+            Font::Regular,
+            Rgb::GREY,
+            "Proforma invoice: not a demand for payment. Values are stated at standard cost.",
+        );
+    } else {
+        let total = format!("Total quantity: {}", quantity(total_quantity, None));
+        page.text_right(PAGE_WIDTH - MARGIN, y, 10.0, Font::Bold, Rgb::BLACK, &total);
+        let sign_y = y - 4.0 * ROW_HEIGHT;
+        for (x, label) in [(MARGIN, "Delivered by"), (PAGE_WIDTH / 2.0 + 10.0, "Received by (name, signature, date)")] {
+            page.rule(x, sign_y, x + 200.0, sign_y, 0.5, Rgb::BLACK);
+            page.text(x, sign_y - 12.0, 8.0, Font::Regular, Rgb::GREY, label);
+        }
+    }
+
+    let footer = branding.and_then(|branding| branding.footer_text.as_deref());
+    let pages = document.pages_mut();
+    let count = pages.len();
+    for (index, page) in pages.iter_mut().enumerate() {
+        page.rule(MARGIN, 50.0, PAGE_WIDTH - MARGIN, 50.0, 0.5, accent);
+        if let Some(footer) = footer {
+            page.text(MARGIN, 38.0, 8.0, Font::Regular, Rgb::GREY, footer);
+        }
+        let numbering = format!("{}  -  page {} of {}", number, index + 1, count);
+        page.text_right(PAGE_WIDTH - MARGIN, 38.0, 8.0, Font::Regular, Rgb::GREY, &numbering);
+    }
+
+    document.finish()
+}
+
+/// Accent band, company block and title; returns where the next block starts
+fn letterhead(
+    page: &mut Page,
+    title: &str,
+    accent: Rgb,
+    source: &ShipmentDocumentSource,
+    branding: Option<&DocumentBranding>,
+) -> f32 {
+    page.fill_rect(0.0, PAGE_HEIGHT - 12.0, PAGE_WIDTH, 12.0, accent);
+
+    let mut y = PAGE_HEIGHT - MARGIN - 10.0;
+    let company = branding.map(|branding| branding.company_name.as_str()).unwrap_or(&source.warehouse_name);
+    page.text(MARGIN, y, 16.0, Font::Bold, accent, company);
+    page.text_right(PAGE_WIDTH - MARGIN, y, 16.0, Font::Bold, accent, title);
+
+    let mut details: Vec<String> = Vec::new();
+    if let Some(branding) = branding {
+        details.extend(branding.address.iter().flat_map(|address| address.lines()).map(str::to_string));
+        details.extend(branding.tax_id.iter().map(|tax_id| format!("Tax ID: {}", tax_id)));
+        let contact: Vec<&str> = [&branding.phone, &branding.email].into_iter().flatten().map(String::as_str).collect();
+        if !contact.is_empty() {
+            details.push(contact.join("  |  "));
+        }
+    }
+    for line in details {
+        y -= 12.0;
+        page.text(MARGIN, y, 9.0, Font::Regular, Rgb::GREY, &line);
+    }
+
+    y - 30.0
+}
+
+/// Document references on the right, ship-to and ship-from on the left
+fn parties(page: &mut Page, top: f32, number: &str, source: &ShipmentDocumentSource) -> f32 {
+    let shipment = &source.shipment;
+    let order = &source.order;
+
+    let mut references = vec![
+        ("Number", number.to_string()),
+        ("Date", shipment.shipped_at.format("%Y-%m-%d").to_string()),
+        ("Shipment", shipment.shipment_number.clone()),
+        ("Order", order.order_number.clone()),
+    ];
+    references.extend(order.customer_reference.clone().map(|reference| ("Customer ref.", reference)));
+    references.extend(shipment.carrier_code.clone().map(|carrier| ("Carrier", carrier)));
+    references.extend(shipment.tracking_number.clone().map(|tracking| ("Tracking", tracking)));
+    let mut right_y = top;
+    for (label, value) in references {
+        page.text(PAGE_WIDTH / 2.0 + 60.0, right_y, 9.0, Font::Bold, Rgb::BLACK, label);
+        page.text(PAGE_WIDTH / 2.0 + 140.0, right_y, 9.0, Font::Regular, Rgb::BLACK, &value);
+        right_y -= 13.0;
+    }
+
+    let locality = [&order.ship_to_city, &order.ship_to_state, &order.ship_to_postal_code]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    let ship_to: Vec<String> = [
+        Some(order.ship_to_name.clone().unwrap_or_else(|| order.customer_name.clone())),
+        order.ship_to_address.clone(),
+        Some(locality).filter(|locality| !locality.is_empty()),
+        order.ship_to_country.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let mut ship_from = vec![source.warehouse_name.clone()];
+    ship_from.extend(source.ship_from.iter().cloned());
+
+    let mut left_y = top;
+    for (heading, lines) in [("Ship to", ship_to), ("Ship from", ship_from)] {
+        page.text(MARGIN, left_y, 9.0, Font::Bold, Rgb::BLACK, heading);
+        for line in lines {
+            left_y -= 12.0;
+            page.text(MARGIN, left_y, 9.0, Font::Regular, Rgb::BLACK, &line);
+        }
+        left_y -= 20.0;
+    }
+
+    left_y.min(right_y) - 10.0
+}
+
+/// A column's heading, left edge, and whether its cells align right
+type Column = (&'static str, f32, bool);
+
+const DELIVERY_COLUMNS: &[Column] = &[
+    ("#", MARGIN, false),
+    ("Item", MARGIN + 25.0, false),
+    ("Description", MARGIN + 125.0, false),
+    ("Quantity", PAGE_WIDTH - MARGIN, true),
+];
+
+const INVOICE_COLUMNS: &[Column] = &[
+    ("#", MARGIN, false),
+    ("Item", MARGIN + 25.0, false),
+    ("Description", MARGIN + 125.0, false),
+    ("Quantity", MARGIN + 375.0, true),
+    ("Unit value", MARGIN + 445.0, true),
+    ("Value", PAGE_WIDTH - MARGIN, true),
+];
+
+fn table_header(page: &mut Page, y: f32, columns: &[Column], accent: Rgb, currency: &str) {
+    page.fill_rect(MARGIN - 4.0, y - 5.0, PAGE_WIDTH - 2.0 * MARGIN + 8.0, ROW_HEIGHT, accent);
+    for (heading, x, right) in columns {
+        // Value columns are in the branding's currency
+        let heading =
+            if *right && *heading != "Quantity" { format!("{} ({})", heading, currency) } else { heading.to_string() };
+        if *right {
+            page.text_right(*x, y, 9.0, Font::Bold, Rgb::WHITE, &heading);
+        } else {
+            page.text(*x, y, 9.0, Font::Bold, Rgb::WHITE, &heading);
+        }
+    }
+}
+
+fn row(page: &mut Page, y: f32, columns: &[Column], cells: &[String]) {
+    for ((_, x, right), cell) in columns.iter().zip(cells) {
+        if *right {
+            page.text_right(*x, y, 9.0, Font::Regular, Rgb::BLACK, cell);
+        } else {
+            page.text(*x, y, 9.0, Font::Regular, Rgb::BLACK, cell);
+        }
+    }
+}
+
+fn quantity(quantity: Decimal, unit: Option<&str>) -> String {
+    match unit {
+        Some(unit) => format!("{} {}", quantity.normalize(), unit),
+        None => quantity.normalize().to_string(),
+    }
+}
+
+/// Two decimals with thousands separators
+fn money(amount: Decimal) -> String {
+    let fixed = format!("{:.2}", amount.round_dp(2));
+    let (sign, fixed) = fixed.strip_prefix('-').map_or(("", fixed.as_str()), |rest| ("-", rest));
+    let (whole, fraction) = fixed.split_once('.').unwrap_or((fixed, "00"));
+    let mut grouped = String::new();
+    for (index, digit) in whole.chars().enumerate() {
+        if index > 0 && (whole.len() - index) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("{}{}.{}", sign, grouped, fraction)
+}
@@ -0,0 +1,193 @@
+//! Just enough PDF for printed business documents: A4 pages of text in the standard
+//! Helvetica faces, filled rectangles and rules. Text is WinAnsi encoded; characters
+//! outside Latin-1 print as `?`.
+
+use std::fmt::Write as _;
+
+pub const PAGE_WIDTH: f32 = 595.0;
+pub const PAGE_HEIGHT: f32 = 842.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        }
+    }
+}
+
+/// Colour components from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb(pub f32, pub f32, pub f32);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0.0, 0.0, 0.0);
+    pub const WHITE: Rgb = Rgb(1.0, 1.0, 1.0);
+    pub const GREY: Rgb = Rgb(0.45, 0.45, 0.45);
+
+    /// `#RRGGBB`
+    pub fn from_hex(hex: &str) -> Option<Rgb> {
+        let hex = hex.strip_prefix('#')?;
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |at: usize| u8::from_str_radix(hex.get(at..at + 2)?, 16).ok().map(|value| value as f32 / 255.0);
+        Some(Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+/// Width of `text` set in `font` at `size`. Exact for digits and common punctuation,
+/// which is what right-aligned columns hold; an average for letters.
+pub fn text_width(text: &str, font: Font, size: f32) -> f32 {
+    let units: u32 = text
+        .chars()
+        .map(|c| match c {
+            '0'..='9' => 556,
+            ' ' | '.' | ',' | ':' | ';' | '/' => 278,
+            '-' | '(' | ')' => 333,
+            'i' | 'j' | 'l' | 'I' | '!' | '\'' => 222,
+            'm' | 'w' | 'M' | 'W' => 833,
+            'A'..='Z' if font == Font::Bold => 722,
+            'A'..='Z' => 667,
+            _ if font == Font::Bold => 611,
+            _ => 556,
+        })
+        .sum();
+    units as f32 * size / 1000.0
+}
+
+/// Content of one page, in page coordinates: points from the bottom left corner
+#[derive(Debug, Default)]
+pub struct Page {
+    content: Vec<u8>,
+}
+
+impl Page {
+    pub fn text(&mut self, x: f32, y: f32, size: f32, font: Font, color: Rgb, text: &str) {
+        self.op(format_args!(
+            "BT {} {} {} rg /{} {} Tf {} {} Td (",
+            color.0,
+            color.1,
+            color.2,
+            font.resource(),
+            size,
+            x,
+            y
+        ));
+        for c in text.chars() {
+            match c {
+                '(' | ')' | '\\' => self.content.extend([b'\\', c as u8]),
+                c if (c as u32) < 0x20 => self.content.push(b' '),
+                c if (c as u32) <= 0xFF => self.content.push(c as u32 as u8),
+                _ => self.content.push(b'?'),
+            }
+        }
+        self.content.extend_from_slice(b") Tj ET\n");
+    }
+
+    /// Text ending at `right`
+    pub fn text_right(&mut self, right: f32, y: f32, size: f32, font: Font, color: Rgb, text: &str) {
+        self.text(right - text_width(text, font, size), y, size, font, color, text);
+    }
+
+    pub fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Rgb) {
+        self.op(format_args!("{} {} {} rg {} {} {} {} re f\n", color.0, color.1, color.2, x, y, width, height));
+    }
+
+    pub fn rule(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, width: f32, color: Rgb) {
+        self.op(format_args!(
+            "{} {} {} RG {} w {} {} m {} {} l S\n",
+            color.0, color.1, color.2, width, x1, y1, x2, y2
+        ));
+    }
+
+    fn op(&mut self, args: std::fmt::Arguments) {
+        let mut op = String::new();
+        let _ = op.write_fmt(args);
+        self.content.extend_from_slice(op.as_bytes());
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Document {
+    pages: Vec<Page>,
+}
+
+impl Document {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a page and draw on it
+    pub fn add_page(&mut self) -> &mut Page {
+        self.pages.push(Page::default());
+        self.pages.last_mut().expect("page pushed above")
+    }
+
+    pub fn pages_mut(&mut self) -> &mut [Page] {
+        &mut self.pages
+    }
+
+    /// The PDF file
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.pages.is_empty() {
+            self.add_page();
+        }
+
+        // Objects 1 and 2 are the catalog and page tree, 3 and 4 the fonts; each page
+        // is followed by its content stream
+        let page_ids: Vec<usize> = (0..self.pages.len()).map(|index| 5 + index * 2).collect();
+        let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), page_ids.len()).into_bytes(),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec(),
+        ];
+        for (page, id) in self.pages.into_iter().zip(&page_ids) {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                     /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    PAGE_WIDTH,
+                    PAGE_HEIGHT,
+                    id + 1
+                )
+                .into_bytes(),
+            );
+            let mut stream = format!("<< /Length {} >>\nstream\n", page.content.len()).into_bytes();
+            stream.extend_from_slice(&page.content);
+            stream.extend_from_slice(b"\nendstream");
+            objects.push(stream);
+        }
+
+        let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            pdf.extend_from_slice(object);
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref = pdf.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(table, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            table,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        );
+        pdf.extend_from_slice(table.as_bytes());
+        pdf
+    }
+}
@@ -0,0 +1,182 @@
+//! E-commerce channels: each sync pulls the storefront's new orders in as outbound orders
+//! of the channel's warehouse, then pushes the available-to-promise quantity of every
+//! listed item whose quantity changed. Pulling first means the new orders already count
+//! against the stock that is pushed.
+//!
+//! Order lines are matched to items by SKU, which is our item code. The platform decides
+//! the protocol; `shopify` and `woocommerce` speak their stores' admin REST APIs.
+
+pub mod shopify;
+pub mod woocommerce;
+
+use async_trait::async_trait;
+use tracing::{info, warn};
+use warehouse_db::Database;
+use warehouse_models::chrono::{DateTime, Utc};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::{notify, AppError, AppResult};
+
+pub use shopify::ShopifyStorefront;
+pub use woocommerce::WooCommerceStorefront;
+
+/// Stock levels pushed per request
+const PUSH_BATCH: usize = 100;
+
+#[async_trait]
+pub trait Storefront: Send + Sync {
+    /// Set the sellable quantity of each listing
+    async fn set_stock(&self, levels: &[EcommerceStockLevel]) -> AppResult<()>;
+    /// Orders changed since `since`, or all of them
+    async fn orders(&self, since: Option<DateTime<Utc>>) -> AppResult<Vec<StorefrontOrder>>;
+}
+
+pub fn storefront(channel: &EcommerceChannel) -> AppResult<Box<dyn Storefront>> {
+    match channel.platform.as_str() {
+        ECOMMERCE_SHOPIFY => Ok(Box::new(ShopifyStorefront::new(channel)?)),
+        ECOMMERCE_WOOCOMMERCE => Ok(Box::new(WooCommerceStorefront::new(channel)?)),
+        other => Err(AppError::validation(format!("unknown e-commerce platform {}", other))),
+    }
+}
+
+/// Sync every channel that is due. Returns how many synced without a failure.
+pub async fn run_due(db: &Database) -> AppResult<usize> {
+    let mut synced = 0;
+    for channel in db.ecommerce().claim_due(Utc::now()).await? {
+        if sync(db, &channel).await?.iter().all(|run| run.status == ECOMMERCE_RUN_SUCCEEDED) {
+            synced += 1;
+        }
+    }
+
+    Ok(synced)
+}
+
+/// Pull orders, then push stock, recording a run for each. A failing direction raises a
+/// notification and does not stop the other; orders are pulled again from where the last
+/// successful pull left off.
+pub async fn sync(db: &Database, channel: &EcommerceChannel) -> AppResult<Vec<EcommerceSyncRun>> {
+    let storefront = storefront(channel)?;
+
+    let mut runs = Vec::with_capacity(2);
+    for direction in [ECOMMERCE_PULL, ECOMMERCE_PUSH] {
+        let started_at = Utc::now();
+        let mut records = 0;
+        let outcome = match direction {
+            ECOMMERCE_PULL => pull_orders(db, channel, storefront.as_ref(), &mut records).await,
+            _ => push_stock(db, channel, storefront.as_ref(), &mut records).await,
+        };
+
+        let error = match outcome {
+            Ok(()) => None,
+            Err(AppError::Database(e)) => return Err(AppError::Database(e)),
+            Err(AppError::Internal(e)) => return Err(AppError::Internal(e)),
+            Err(e) => Some(e.to_string()),
+        };
+        runs.push(db.ecommerce().record_run(channel.channel_id, direction, started_at, records, error).await?);
+    }
+
+    let failed: Vec<String> =
+        runs.iter().filter_map(|run| Some(format!("{}: {}", run.direction, run.error.as_ref()?))).collect();
+    if failed.is_empty() {
+        let records: i32 = runs.iter().map(|run| run.records).sum();
+        info!(channel = %channel.channel_code, records, "E-commerce sync completed");
+    } else {
+        warn!(channel = %channel.channel_code, "E-commerce sync failed: {}", failed.join("; "));
+        notify::raise(
+            db,
+            NewNotification {
+                kind: NOTIFY_ECOMMERCE_SYNC_FAILED,
+                severity: SEVERITY_WARNING,
+                subject: format!("E-commerce sync with {} failed", channel.channel_code),
+                message: Some(failed.join("\n")),
+                reference_type: Some("ECOMMERCE_CHANNEL".to_string()),
+                reference_id: Some(channel.channel_id),
+            },
+        )
+        .await?;
+    }
+
+    Ok(runs)
+}
+
+/// Storefront orders become outbound orders. Orders imported before are skipped, so
+/// pulling the same changes twice is harmless.
+async fn pull_orders(
+    db: &Database,
+    channel: &EcommerceChannel,
+    storefront: &dyn Storefront,
+    records: &mut i32,
+) -> AppResult<()> {
+    let mut latest = channel.orders_synced_at;
+    for order in storefront.orders(channel.orders_synced_at).await? {
+        if import_order(db, channel, &order).await? {
+            *records += 1;
+        }
+        latest = latest.max(Some(order.updated_at));
+    }
+
+    if let Some(synced_at) = latest {
+        db.ecommerce().advance_orders(channel.channel_id, synced_at).await?;
+    }
+    Ok(())
+}
+
+/// Returns false when the order was there already or is not ready to ship
+pub async fn import_order(db: &Database, channel: &EcommerceChannel, order: &StorefrontOrder) -> AppResult<bool> {
+    if !order.ready {
+        return Ok(false);
+    }
+    let label = format!("order {}", order.order_number);
+    order.validate().map_err(|e| AppError::validation(format!("{}: {}", label, e)))?;
+
+    if db.ecommerce().imported_order(channel.channel_id, &order.external_id).await?.is_some() {
+        return Ok(false);
+    }
+
+    let mut lines = Vec::with_capacity(order.lines.len());
+    for line in &order.lines {
+        let item = db
+            .items()
+            .get_by_code(&line.sku)
+            .await?
+            .ok_or_else(|| AppError::validation(format!("{}: unknown SKU {}", label, line.sku)))?;
+        lines.push(CreateOrderLine { item_id: item.item_id, quantity_ordered: line.quantity });
+    }
+
+    let create = CreateOrder {
+        warehouse_id: channel.warehouse_id,
+        customer_name: order.customer_name.clone(),
+        customer_reference: Some(order.order_number.clone()),
+        ship_to_name: order.ship_to_name.clone(),
+        ship_to_address: order.ship_to_address.clone(),
+        ship_to_city: order.ship_to_city.clone(),
+        ship_to_state: order.ship_to_state.clone(),
+        ship_to_postal_code: order.ship_to_postal_code.clone(),
+        ship_to_country: order.ship_to_country.clone(),
+        requested_ship_date: None,
+        carrier_code: None,
+        dropship_supplier_code: None,
+        lines,
+    };
+    let created = db.orders().create_from(create, SOURCE_ECOMMERCE, None).await?;
+    db.ecommerce().link_order(channel.channel_id, &order.external_id, created.order.order_id).await?;
+    Ok(true)
+}
+
+async fn push_stock(
+    db: &Database,
+    channel: &EcommerceChannel,
+    storefront: &dyn Storefront,
+    records: &mut i32,
+) -> AppResult<()> {
+    let levels: Vec<EcommerceStockLevel> =
+        db.ecommerce().stock_levels(channel.channel_id).await?.into_iter().filter(|level| level.is_changed()).collect();
+
+    for batch in levels.chunks(PUSH_BATCH) {
+        storefront.set_stock(batch).await?;
+        db.ecommerce().mark_pushed(channel.channel_id, batch).await?;
+        *records += batch.len() as i32;
+    }
+    Ok(())
+}
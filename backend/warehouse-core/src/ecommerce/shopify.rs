@@ -0,0 +1,212 @@
+//! Shopify storefronts, through the Admin REST API:
+//!
+//! - `POST {store_url}/admin/api/{version}/inventory_levels/set.json` sets the available
+//!   quantity of an inventory item at the channel's location
+//! - `GET {store_url}/admin/api/{version}/orders.json?status=any&updated_at_min=...` lists
+//!   orders, following the `Link` header's `rel="next"` page
+//!
+//! Requests carry the channel's access token as `X-Shopify-Access-Token`. Paid orders that
+//! are not cancelled are ready; lines that need no shipping are left off.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{RequestBuilder, Response};
+use serde::Deserialize;
+use serde_json::json;
+use warehouse_models::chrono::{DateTime, SecondsFormat, Utc};
+use warehouse_models::rust_decimal::prelude::ToPrimitive;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+use super::Storefront;
+use crate::{AppError, AppResult};
+
+pub const API_VERSION: &str = "2024-01";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const PAGE_SIZE: &str = "250";
+
+pub struct ShopifyStorefront {
+    code: String,
+    admin_url: String,
+    access_token: String,
+    location_id: i64,
+    http: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct Orders {
+    orders: Vec<ShopifyOrder>,
+}
+
+#[derive(Deserialize)]
+struct ShopifyOrder {
+    id: i64,
+    name: String,
+    email: Option<String>,
+    financial_status: Option<String>,
+    cancelled_at: Option<DateTime<Utc>>,
+    updated_at: DateTime<Utc>,
+    customer: Option<Customer>,
+    shipping_address: Option<Address>,
+    line_items: Vec<LineItem>,
+}
+
+#[derive(Deserialize)]
+struct Customer {
+    first_name: Option<String>,
+    last_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Address {
+    name: Option<String>,
+    address1: Option<String>,
+    address2: Option<String>,
+    city: Option<String>,
+    province_code: Option<String>,
+    zip: Option<String>,
+    country_code: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LineItem {
+    sku: Option<String>,
+    quantity: i64,
+    #[serde(default = "ships")]
+    requires_shipping: bool,
+}
+
+fn ships() -> bool {
+    true
+}
+
+impl ShopifyStorefront {
+    pub fn new(channel: &EcommerceChannel) -> AppResult<Self> {
+        let location_id = channel.location_id.as_deref().and_then(|id| id.parse().ok()).ok_or_else(|| {
+            AppError::validation(format!("channel {} has no numeric Shopify location", channel.channel_code))
+        })?;
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Ok(Self {
+            code: channel.channel_code.clone(),
+            admin_url: format!("{}/admin/api/{}", channel.store_url.trim_end_matches('/'), API_VERSION),
+            access_token: channel.api_key.clone(),
+            location_id,
+            http,
+        })
+    }
+
+    fn error(&self, message: impl ToString) -> AppError {
+        AppError::ExternalService {
+            service: format!("Shopify {}", self.code),
+            message: message.to_string(),
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> AppResult<Response> {
+        let response =
+            request.header("X-Shopify-Access-Token", &self.access_token).send().await.map_err(|e| self.error(e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(self.error(format!("{} {}", status, body)));
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl Storefront for ShopifyStorefront {
+    /// Shopify sets one inventory item per request
+    async fn set_stock(&self, levels: &[EcommerceStockLevel]) -> AppResult<()> {
+        let url = format!("{}/inventory_levels/set.json", self.admin_url);
+        for level in levels {
+            let inventory_item_id: i64 = level
+                .external_id
+                .parse()
+                .map_err(|_| AppError::validation(format!("{} is not a Shopify inventory item", level.external_id)))?;
+            let body = json!({
+                "location_id": self.location_id,
+                "inventory_item_id": inventory_item_id,
+                "available": level.quantity.to_i64().unwrap_or_default(),
+            });
+            self.send(self.http.post(&url).json(&body)).await?;
+        }
+        Ok(())
+    }
+
+    async fn orders(&self, since: Option<DateTime<Utc>>) -> AppResult<Vec<StorefrontOrder>> {
+        let mut request = self
+            .http
+            .get(format!("{}/orders.json", self.admin_url))
+            .query(&[("status", "any"), ("limit", PAGE_SIZE)]);
+        if let Some(since) = since {
+            request = request.query(&[("updated_at_min", since.to_rfc3339_opts(SecondsFormat::Secs, true))]);
+        }
+
+        let mut orders = Vec::new();
+        loop {
+            let response = self.send(request).await?;
+            let next = next_page(&response);
+            let page: Orders = response.json().await.map_err(|e| self.error(e))?;
+            orders.extend(page.orders.into_iter().map(storefront_order));
+            match next {
+                // The next page's URL carries the filters
+                Some(url) => request = self.http.get(url),
+                None => return Ok(orders),
+            }
+        }
+    }
+}
+
+/// `<url>; rel="next"` of the `Link` header
+fn next_page(response: &Response) -> Option<String> {
+    let links = response.headers().get(reqwest::header::LINK)?.to_str().ok()?;
+    links.split(',').find_map(|link| {
+        let (url, rel) = link.split_once(';')?;
+        rel.contains("rel=\"next\"").then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+fn storefront_order(order: ShopifyOrder) -> StorefrontOrder {
+    let lines: Vec<StorefrontOrderLine> = order
+        .line_items
+        .into_iter()
+        .filter(|line| line.requires_shipping && line.quantity > 0)
+        .map(|line| StorefrontOrderLine { sku: line.sku.unwrap_or_default(), quantity: Decimal::from(line.quantity) })
+        .collect();
+    let paid = matches!(order.financial_status.as_deref(), Some("paid" | "partially_paid" | "authorized"));
+
+    let customer_name = order
+        .customer
+        .and_then(|customer| {
+            let name = [customer.first_name, customer.last_name].into_iter().flatten().collect::<Vec<_>>().join(" ");
+            (!name.trim().is_empty()).then_some(name)
+        })
+        .or(order.email)
+        .unwrap_or_else(|| order.name.clone());
+    let address = order.shipping_address;
+    let street = address.as_ref().map(|address| {
+        [address.address1.clone(), address.address2.clone()].into_iter().flatten().collect::<Vec<_>>().join(", ")
+    });
+
+    StorefrontOrder {
+        external_id: order.id.to_string(),
+        order_number: order.name,
+        customer_name,
+        ship_to_name: address.as_ref().and_then(|address| address.name.clone()),
+        ship_to_address: street.filter(|street| !street.is_empty()),
+        ship_to_city: address.as_ref().and_then(|address| address.city.clone()),
+        ship_to_state: address.as_ref().and_then(|address| address.province_code.clone()),
+        ship_to_postal_code: address.as_ref().and_then(|address| address.zip.clone()),
+        ship_to_country: address.and_then(|address| address.country_code),
+        ready: paid && order.cancelled_at.is_none() && !lines.is_empty(),
+        lines,
+        updated_at: order.updated_at,
+    }
+}
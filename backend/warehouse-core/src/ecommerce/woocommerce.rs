@@ -0,0 +1,204 @@
+//! WooCommerce storefronts, through the WooCommerce REST API (`/wp-json/wc/v3`):
+//!
+//! - `POST {store_url}/wp-json/wc/v3/products/batch` with `{"update": [...]}` sets
+//!   `stock_quantity` of the listed products, stock management turned on
+//! - `GET {store_url}/wp-json/wc/v3/orders?modified_after=...&dates_are_gmt=true` lists
+//!   orders a page at a time
+//!
+//! Requests authenticate with the consumer key and secret over HTTP basic auth. Orders in
+//! `processing` (paid, awaiting fulfilment) are ready.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{RequestBuilder, Response};
+use serde::Deserialize;
+use serde_json::json;
+use warehouse_models::chrono::{DateTime, NaiveDateTime, Utc};
+use warehouse_models::rust_decimal::prelude::ToPrimitive;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+use super::Storefront;
+use crate::{AppError, AppResult};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const PAGE_SIZE: usize = 100;
+
+pub struct WooCommerceStorefront {
+    code: String,
+    api_url: String,
+    consumer_key: String,
+    consumer_secret: String,
+    http: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct WooOrder {
+    id: i64,
+    number: String,
+    status: String,
+    /// UTC, without an offset
+    date_modified_gmt: NaiveDateTime,
+    billing: Option<Contact>,
+    shipping: Option<Contact>,
+    line_items: Vec<LineItem>,
+}
+
+#[derive(Deserialize)]
+struct Contact {
+    #[serde(default)]
+    first_name: String,
+    #[serde(default)]
+    last_name: String,
+    #[serde(default)]
+    address_1: String,
+    #[serde(default)]
+    address_2: String,
+    #[serde(default)]
+    city: String,
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    postcode: String,
+    #[serde(default)]
+    country: String,
+    email: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LineItem {
+    #[serde(default)]
+    sku: String,
+    quantity: i64,
+}
+
+impl WooCommerceStorefront {
+    pub fn new(channel: &EcommerceChannel) -> AppResult<Self> {
+        let consumer_secret = channel.api_secret.clone().ok_or_else(|| {
+            AppError::validation(format!("channel {} has no WooCommerce consumer secret", channel.channel_code))
+        })?;
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Ok(Self {
+            code: channel.channel_code.clone(),
+            api_url: format!("{}/wp-json/wc/v3", channel.store_url.trim_end_matches('/')),
+            consumer_key: channel.api_key.clone(),
+            consumer_secret,
+            http,
+        })
+    }
+
+    fn error(&self, message: impl ToString) -> AppError {
+        AppError::ExternalService {
+            service: format!("WooCommerce {}", self.code),
+            message: message.to_string(),
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> AppResult<Response> {
+        let response = request
+            .basic_auth(&self.consumer_key, Some(&self.consumer_secret))
+            .send()
+            .await
+            .map_err(|e| self.error(e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(self.error(format!("{} {}", status, body)));
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl Storefront for WooCommerceStorefront {
+    async fn set_stock(&self, levels: &[EcommerceStockLevel]) -> AppResult<()> {
+        let mut update = Vec::with_capacity(levels.len());
+        for level in levels {
+            let product_id: i64 = level
+                .external_id
+                .parse()
+                .map_err(|_| AppError::validation(format!("{} is not a WooCommerce product", level.external_id)))?;
+            update.push(json!({
+                "id": product_id,
+                "manage_stock": true,
+                "stock_quantity": level.quantity.to_i64().unwrap_or_default(),
+            }));
+        }
+
+        let url = format!("{}/products/batch", self.api_url);
+        self.send(self.http.post(url).json(&json!({ "update": update }))).await?;
+        Ok(())
+    }
+
+    async fn orders(&self, since: Option<DateTime<Utc>>) -> AppResult<Vec<StorefrontOrder>> {
+        let mut orders = Vec::new();
+        for page in 1.. {
+            let mut request = self.http.get(format!("{}/orders", self.api_url)).query(&[
+                ("orderby", "modified".to_string()),
+                ("order", "asc".to_string()),
+                ("per_page", PAGE_SIZE.to_string()),
+                ("page", page.to_string()),
+            ]);
+            if let Some(since) = since {
+                request = request.query(&[
+                    ("modified_after", since.naive_utc().format("%Y-%m-%dT%H:%M:%S").to_string()),
+                    ("dates_are_gmt", "true".to_string()),
+                ]);
+            }
+
+            let batch: Vec<WooOrder> = self.send(request).await?.json().await.map_err(|e| self.error(e))?;
+            let last = batch.len() < PAGE_SIZE;
+            orders.extend(batch.into_iter().map(storefront_order));
+            if last {
+                break;
+            }
+        }
+        Ok(orders)
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let value = value.trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+fn storefront_order(order: WooOrder) -> StorefrontOrder {
+    let lines: Vec<StorefrontOrderLine> = order
+        .line_items
+        .into_iter()
+        .filter(|line| line.quantity > 0)
+        .map(|line| StorefrontOrderLine { sku: line.sku, quantity: Decimal::from(line.quantity) })
+        .collect();
+
+    let full_name = |contact: &Contact| non_empty(format!("{} {}", contact.first_name, contact.last_name));
+    let customer_name = order
+        .billing
+        .as_ref()
+        .and_then(|billing| full_name(billing).or_else(|| billing.email.clone().and_then(non_empty)))
+        .unwrap_or_else(|| format!("#{}", order.number));
+    let shipping = order.shipping;
+
+    StorefrontOrder {
+        external_id: order.id.to_string(),
+        order_number: format!("#{}", order.number),
+        customer_name,
+        ship_to_name: shipping.as_ref().and_then(full_name),
+        ship_to_address: shipping.as_ref().and_then(|shipping| {
+            let street: Vec<&str> =
+                [shipping.address_1.trim(), shipping.address_2.trim()].into_iter().filter(|part| !part.is_empty()).collect();
+            non_empty(street.join(", "))
+        }),
+        ship_to_city: shipping.as_ref().and_then(|shipping| non_empty(shipping.city.clone())),
+        ship_to_state: shipping.as_ref().and_then(|shipping| non_empty(shipping.state.clone())),
+        ship_to_postal_code: shipping.as_ref().and_then(|shipping| non_empty(shipping.postcode.clone())),
+        ship_to_country: shipping.and_then(|shipping| non_empty(shipping.country)),
+        ready: order.status == "processing" && !lines.is_empty(),
+        lines,
+        updated_at: order.date_modified_gmt.and_utc(),
+    }
+}
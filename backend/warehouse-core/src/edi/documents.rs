@@ -0,0 +1,257 @@
+//! Mapping between X12 transaction sets and warehouse documents
+
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, Utc};
+use warehouse_models::rust_decimal::Decimal;
+
+use super::x12::{pad, Segment, Transaction, Writer};
+
+/// Item lines identify products by our item code
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdiLine {
+    pub item_code: String,
+    pub quantity: Decimal,
+}
+
+/// 856 Ship Notice/Manifest: a supplier announcing goods on their way to us
+#[derive(Debug, Clone)]
+pub struct AdvanceShipNotice {
+    pub shipment_id: String,
+    pub supplier_name: Option<String>,
+    pub purchase_order_number: Option<String>,
+    pub carrier_code: Option<String>,
+    pub expected_date: Option<NaiveDate>,
+    pub lines: Vec<EdiLine>,
+}
+
+/// 940 Warehouse Shipping Order: a depositor asking us to ship to their customer
+#[derive(Debug, Clone)]
+pub struct ShippingOrder {
+    pub depositor_order_number: String,
+    pub purchase_order_number: Option<String>,
+    pub ship_to_name: Option<String>,
+    pub ship_to_address: Option<String>,
+    pub ship_to_city: Option<String>,
+    pub ship_to_state: Option<String>,
+    pub ship_to_postal_code: Option<String>,
+    pub ship_to_country: Option<String>,
+    pub requested_ship_date: Option<NaiveDate>,
+    pub lines: Vec<EdiLine>,
+}
+
+/// Product id qualifiers we accept for our item code, in order of preference
+const ITEM_QUALIFIERS: &[&str] = &["BP", "VN", "VP", "SK", "IN"];
+
+pub fn parse_asn(transaction: &Transaction) -> Result<AdvanceShipNotice> {
+    let bsn = find(transaction, "BSN").context("856 is missing its BSN segment")?;
+    let mut asn = AdvanceShipNotice {
+        shipment_id: bsn.get(2).context("BSN02 shipment id missing")?.to_string(),
+        supplier_name: None,
+        purchase_order_number: None,
+        carrier_code: None,
+        expected_date: None,
+        lines: Vec::new(),
+    };
+
+    let mut item_code: Option<String> = None;
+    for segment in &transaction.segments {
+        match segment.id.as_str() {
+            // 017 = estimated delivery
+            "DTM" if segment.get(1) == Some("017") => asn.expected_date = segment.get(2).and_then(date),
+            "TD5" => asn.carrier_code = segment.get(3).map(str::to_string),
+            "N1" if segment.get(1) == Some("SF") => asn.supplier_name = segment.get(2).map(str::to_string),
+            "PRF" => asn.purchase_order_number = segment.get(1).map(str::to_string),
+            "LIN" => item_code = Some(product_id(segment, 2).context("LIN without a usable product id")?),
+            "SN1" => {
+                let code = item_code.take().context("SN1 without a preceding LIN")?;
+                asn.lines.push(EdiLine {
+                    item_code: code,
+                    quantity: quantity(segment.get(2))?,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    if asn.lines.is_empty() {
+        bail!("856 {} has no item lines", asn.shipment_id);
+    }
+    Ok(asn)
+}
+
+pub fn parse_shipping_order(transaction: &Transaction) -> Result<ShippingOrder> {
+    let w05 = find(transaction, "W05").context("940 is missing its W05 segment")?;
+    let mut order = ShippingOrder {
+        depositor_order_number: w05.get(2).context("W05-02 depositor order number missing")?.to_string(),
+        purchase_order_number: w05.get(3).map(str::to_string),
+        ship_to_name: None,
+        ship_to_address: None,
+        ship_to_city: None,
+        ship_to_state: None,
+        ship_to_postal_code: None,
+        ship_to_country: None,
+        requested_ship_date: None,
+        lines: Vec::new(),
+    };
+
+    // N3/N4 belong to whichever N1 loop precedes them; only ship-to is kept
+    let mut in_ship_to = false;
+    for segment in &transaction.segments {
+        match segment.id.as_str() {
+            "N1" => {
+                in_ship_to = segment.get(1) == Some("ST");
+                if in_ship_to {
+                    order.ship_to_name = segment.get(2).map(str::to_string);
+                }
+            }
+            "N3" if in_ship_to => order.ship_to_address = segment.get(1).map(str::to_string),
+            "N4" if in_ship_to => {
+                order.ship_to_city = segment.get(1).map(str::to_string);
+                order.ship_to_state = segment.get(2).map(str::to_string);
+                order.ship_to_postal_code = segment.get(3).map(str::to_string);
+                order.ship_to_country = segment.get(4).map(str::to_string);
+            }
+            // 10 = requested ship date
+            "G62" if segment.get(1) == Some("10") => order.requested_ship_date = segment.get(2).and_then(date),
+            "W01" => order.lines.push(EdiLine {
+                item_code: product_id(segment, 4).context("W01 without a usable product id")?,
+                quantity: quantity(segment.get(1))?,
+            }),
+            _ => {}
+        }
+    }
+
+    if order.lines.is_empty() {
+        bail!("940 {} has no item lines", order.depositor_order_number);
+    }
+    Ok(order)
+}
+
+/// Shipment details a 945 reports back to the depositor
+#[derive(Debug, Clone)]
+pub struct ShippingAdvice<'a> {
+    pub sender_id: &'a str,
+    pub receiver_id: &'a str,
+    pub control_number: i64,
+    pub depositor_order_number: &'a str,
+    pub purchase_order_number: Option<&'a str>,
+    pub shipment_number: &'a str,
+    pub ship_to_name: Option<&'a str>,
+    pub carrier_code: Option<&'a str>,
+    pub lines: Vec<AdviceLine>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AdviceLine {
+    pub item_code: String,
+    pub quantity_ordered: Decimal,
+    /// Shipped in this shipment
+    pub quantity_shipped: Decimal,
+    /// Still to ship after this and earlier shipments
+    pub quantity_outstanding: Decimal,
+}
+
+/// Build a 945 Warehouse Shipping Advice interchange
+pub fn build_shipping_advice(advice: &ShippingAdvice) -> String {
+    let now = Utc::now();
+    let control = format!("{:09}", advice.control_number % 1_000_000_000);
+    let group_control = (advice.control_number % 1_000_000_000).to_string();
+
+    let mut writer = Writer::new();
+    writer.segment(
+        "ISA",
+        &[
+            "00", &pad("", 10), "00", &pad("", 10),
+            "ZZ", &pad(advice.sender_id, 15),
+            "ZZ", &pad(advice.receiver_id, 15),
+            &now.format("%y%m%d").to_string(), &now.format("%H%M").to_string(),
+            "U", "00401", &control, "0", "P", ">",
+        ],
+    );
+    writer.segment(
+        "GS",
+        &[
+            "SW", advice.sender_id, advice.receiver_id,
+            &now.format("%Y%m%d").to_string(), &now.format("%H%M").to_string(),
+            &group_control, "X", "004010",
+        ],
+    );
+
+    let st_index = writer.len();
+    writer.segment("ST", &["945", "0001"]);
+    writer.segment(
+        "W06",
+        &[
+            "F",
+            advice.depositor_order_number,
+            &now.format("%Y%m%d").to_string(),
+            advice.shipment_number,
+            "",
+            advice.purchase_order_number.unwrap_or_default(),
+        ],
+    );
+    if let Some(name) = advice.ship_to_name {
+        writer.segment("N1", &["ST", name]);
+    }
+    if let Some(carrier) = advice.carrier_code {
+        writer.segment("W27", &["M", carrier]);
+    }
+
+    let mut total_shipped = Decimal::ZERO;
+    for (index, line) in advice.lines.iter().enumerate() {
+        // CC = line complete, PR = partial with more to follow
+        let status = if line.quantity_outstanding.is_zero() { "CC" } else { "PR" };
+        writer.segment("LX", &[&(index + 1).to_string()]);
+        writer.segment(
+            "W12",
+            &[
+                status,
+                &line.quantity_ordered.normalize().to_string(),
+                &line.quantity_shipped.normalize().to_string(),
+                &line.quantity_outstanding.normalize().to_string(),
+                "EA", "", "VN", &line.item_code,
+            ],
+        );
+        total_shipped += line.quantity_shipped;
+    }
+    writer.segment("W03", &[&total_shipped.normalize().to_string()]);
+
+    // SE counts ST and SE themselves
+    let segment_count = writer.len() - st_index + 1;
+    writer.segment("SE", &[&segment_count.to_string(), "0001"]);
+    writer.segment("GE", &["1", &group_control]);
+    writer.segment("IEA", &["1", &control]);
+    writer.finish()
+}
+
+fn find<'a>(transaction: &'a Transaction, id: &str) -> Option<&'a Segment> {
+    transaction.segments.iter().find(|segment| segment.id == id)
+}
+
+/// First product id whose qualifier we recognise, scanning qualifier/id pairs from `start`
+fn product_id(segment: &Segment, start: usize) -> Option<String> {
+    let pairs: Vec<(&str, &str)> = (start..=segment.elements.len())
+        .step_by(2)
+        .filter_map(|position| Some((segment.get(position)?, segment.get(position + 1)?)))
+        .collect();
+
+    ITEM_QUALIFIERS.iter().find_map(|wanted| {
+        pairs
+            .iter()
+            .find(|(qualifier, _)| qualifier == wanted)
+            .map(|(_, id)| id.to_string())
+    })
+}
+
+fn quantity(value: Option<&str>) -> Result<Decimal> {
+    let value = value.context("quantity missing")?;
+    let quantity: Decimal = value.parse().with_context(|| format!("invalid quantity '{}'", value))?;
+    if quantity <= Decimal::ZERO {
+        bail!("quantity must be positive, got {}", value);
+    }
+    Ok(quantity)
+}
+
+fn date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y%m%d").ok()
+}
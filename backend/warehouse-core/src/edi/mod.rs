@@ -0,0 +1,207 @@
+//! EDI (ANSI X12) exchange: inbound 856 ship notices and 940 shipping orders are mapped
+//! onto receipts and outbound orders; shipping an EDI order emits a 945 shipping advice.
+
+pub mod documents;
+pub mod x12;
+
+use warehouse_db::Database;
+use warehouse_models::*;
+
+use crate::{AppError, AppResult, Config};
+use documents::{AdviceLine, EdiLine, ShippingAdvice};
+
+/// Process every transaction set in an inbound interchange for one warehouse.
+/// Each set is logged with its outcome; one failing set does not stop the others.
+pub async fn receive(db: &Database, warehouse_id: i32, raw: &str) -> AppResult<Vec<EdiDocument>> {
+    let transactions = x12::parse(raw).map_err(AppError::validation)?;
+
+    let mut logged = Vec::with_capacity(transactions.len());
+    for transaction in transactions {
+        let outcome = match transaction.transaction_set.as_str() {
+            "856" => import_asn(db, warehouse_id, &transaction).await,
+            "940" => import_shipping_order(db, warehouse_id, &transaction).await,
+            other => Err(AppError::validation(format!("unsupported transaction set {}", other))),
+        };
+
+        let (status, error, reference) = match outcome {
+            Ok(reference) => (EDI_PROCESSED, None, Some(reference)),
+            Err(AppError::Database(e)) => return Err(AppError::Database(e)),
+            Err(AppError::Internal(e)) => return Err(AppError::Internal(e)),
+            Err(e) => (EDI_FAILED, Some(e.to_string()), None),
+        };
+
+        logged.push(
+            db.edi()
+                .log(NewEdiDocument {
+                    direction: EDI_INBOUND,
+                    transaction_set: transaction.transaction_set.clone(),
+                    partner_id: Some(transaction.sender_id.clone()),
+                    control_number: Some(transaction.interchange_control_number.clone()),
+                    status,
+                    error,
+                    reference_type: reference.as_ref().map(|(kind, _)| kind.to_string()),
+                    reference_id: reference.map(|(_, id)| id),
+                    content: raw.to_string(),
+                })
+                .await?,
+        );
+    }
+
+    Ok(logged)
+}
+
+/// Emit a 945 for a shipment of an EDI-sourced order and queue it for the partner.
+/// `order` must be loaded after the shipment so line totals include it.
+pub async fn shipping_advice(
+    db: &Database,
+    config: &Config,
+    order: &OrderDetail,
+    shipment: &ShipmentDetail,
+) -> AppResult<EdiDocument> {
+    // A retried ship-confirm replays the original shipment; its advice is already queued
+    let shipment_id = shipment.shipment.shipment_id;
+    if let Some(document) = db.edi().find_outbound("945", "SHIPMENT", shipment_id).await? {
+        return Ok(document);
+    }
+
+    let mut lines = Vec::with_capacity(shipment.lines.len());
+    for shipped in &shipment.lines {
+        let (ordered, outstanding) = order
+            .lines
+            .iter()
+            .find(|line| line.line_id == shipped.order_line_id)
+            .map(|line| (line.quantity_ordered, line.quantity_ordered - line.quantity_shipped))
+            .unwrap_or((shipped.quantity, Default::default()));
+        let item_code = match db.items().get_by_id(shipped.item_id).await? {
+            Some(item) => item.item_code,
+            None => shipped.item_id.to_string(),
+        };
+        lines.push(AdviceLine {
+            item_code,
+            quantity_ordered: ordered,
+            quantity_shipped: shipped.quantity,
+            quantity_outstanding: outstanding,
+        });
+    }
+
+    let partner = order.order.edi_partner_id.as_deref().unwrap_or_default();
+    let control_number = db.edi().next_control_number().await?;
+    let content = documents::build_shipping_advice(&ShippingAdvice {
+        sender_id: &config.edi.sender_id,
+        receiver_id: partner,
+        control_number,
+        depositor_order_number: order.order.customer_reference.as_deref().unwrap_or(&order.order.order_number),
+        purchase_order_number: None,
+        shipment_number: &shipment.shipment.shipment_number,
+        ship_to_name: order.order.ship_to_name.as_deref(),
+        carrier_code: shipment.shipment.carrier_code.as_deref(),
+        lines,
+    });
+
+    let document = db
+        .edi()
+        .log(NewEdiDocument {
+            direction: EDI_OUTBOUND,
+            transaction_set: "945".to_string(),
+            partner_id: Some(partner.to_string()),
+            control_number: Some(format!("{:09}", control_number)),
+            status: EDI_READY,
+            error: None,
+            reference_type: Some("SHIPMENT".to_string()),
+            reference_id: Some(shipment_id),
+            content,
+        })
+        .await?;
+
+    Ok(document)
+}
+
+async fn import_asn(db: &Database, warehouse_id: i32, transaction: &x12::Transaction) -> AppResult<(&'static str, i32)> {
+    let asn = documents::parse_asn(transaction).map_err(AppError::validation)?;
+
+    if db.receipts().asn_exists(warehouse_id, &asn.shipment_id).await? {
+        return Err(AppError::already_exists(&format!("ASN {}", asn.shipment_id)));
+    }
+
+    let mut lines = Vec::with_capacity(asn.lines.len());
+    for (item_id, line) in resolve_items(db, &asn.lines).await? {
+        lines.push(CreateReceiptLine {
+            item_id,
+            quantity_expected: line.quantity,
+            lpn_number: None,
+        });
+    }
+
+    let receipt = db
+        .receipts()
+        .create_from(
+            CreateReceipt {
+                asn_number: asn.shipment_id,
+                warehouse_id,
+                supplier_name: asn.supplier_name,
+                purchase_order_number: asn.purchase_order_number,
+                carrier_code: asn.carrier_code,
+                expected_date: asn.expected_date,
+                consignor_id: None,
+                lines,
+            },
+            SOURCE_EDI,
+        )
+        .await?;
+
+    Ok(("RECEIPT", receipt.receipt.receipt_id))
+}
+
+async fn import_shipping_order(
+    db: &Database,
+    warehouse_id: i32,
+    transaction: &x12::Transaction,
+) -> AppResult<(&'static str, i32)> {
+    let order = documents::parse_shipping_order(transaction).map_err(AppError::validation)?;
+
+    let mut lines = Vec::with_capacity(order.lines.len());
+    for (item_id, line) in resolve_items(db, &order.lines).await? {
+        lines.push(CreateOrderLine {
+            item_id,
+            quantity_ordered: line.quantity,
+        });
+    }
+
+    let created = db
+        .orders()
+        .create_from(
+            CreateOrder {
+                warehouse_id,
+                customer_name: order.ship_to_name.clone().unwrap_or_else(|| transaction.sender_id.clone()),
+                customer_reference: Some(order.depositor_order_number),
+                ship_to_name: order.ship_to_name,
+                ship_to_address: order.ship_to_address,
+                ship_to_city: order.ship_to_city,
+                ship_to_state: order.ship_to_state,
+                ship_to_postal_code: order.ship_to_postal_code,
+                ship_to_country: order.ship_to_country,
+                requested_ship_date: order.requested_ship_date,
+                carrier_code: None,
+                dropship_supplier_code: None,
+                lines,
+            },
+            SOURCE_EDI,
+            Some(transaction.sender_id.clone()),
+        )
+        .await?;
+
+    Ok(("ORDER", created.order.order_id))
+}
+
+async fn resolve_items<'a>(db: &Database, lines: &'a [EdiLine]) -> AppResult<Vec<(i32, &'a EdiLine)>> {
+    let mut resolved = Vec::with_capacity(lines.len());
+    for line in lines {
+        let item = db
+            .items()
+            .get_by_code(&line.item_code)
+            .await?
+            .ok_or_else(|| AppError::not_found(&format!("item {}", line.item_code)))?;
+        resolved.push((item.item_id, line));
+    }
+    Ok(resolved)
+}
@@ -0,0 +1,155 @@
+//! Minimal ANSI X12 envelope reader and writer
+
+use anyhow::{bail, Context, Result};
+
+/// One segment, e.g. `W01*10*EA**VN*ITM002` -> id `W01`, elements `["10", "EA", "", "VN", "ITM002"]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub id: String,
+    pub elements: Vec<String>,
+}
+
+impl Segment {
+    /// Element by its X12 position (1-based, as in `W05-02`); empty elements read as None
+    pub fn get(&self, position: usize) -> Option<&str> {
+        self.elements
+            .get(position.checked_sub(1)?)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// A transaction set (ST ... SE) with the envelope fields callers need
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    /// ISA06, the trading partner's interchange sender id
+    pub sender_id: String,
+    /// ISA13
+    pub interchange_control_number: String,
+    /// ST01: 856, 940, ...
+    pub transaction_set: String,
+    /// Segments between ST and SE, exclusive
+    pub segments: Vec<Segment>,
+}
+
+/// Split an interchange into its transaction sets. Delimiters are read from the ISA header,
+/// so partners may use any element separator and segment terminator.
+pub fn parse(raw: &str) -> Result<Vec<Transaction>> {
+    let raw = raw.trim_start();
+    if !raw.starts_with("ISA") || raw.len() < 106 {
+        bail!("document does not start with an ISA header");
+    }
+
+    let element_separator = raw[3..].chars().next().context("missing element separator")?;
+    // ISA has 16 elements; the segment terminator follows the last (component separator)
+    let isa_end = raw
+        .char_indices()
+        .filter(|(_, c)| *c == element_separator)
+        .nth(15)
+        .map(|(index, _)| index)
+        .context("truncated ISA header")?;
+    let segment_terminator = raw[isa_end + 1..]
+        .chars()
+        .nth(1)
+        .context("missing segment terminator")?;
+
+    let segments: Vec<Segment> = raw
+        .split(segment_terminator)
+        .map(|segment| segment.trim_matches(|c: char| c == '\r' || c == '\n' || c == ' '))
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            let mut parts = segment.split(element_separator);
+            Segment {
+                id: parts.next().unwrap_or_default().to_string(),
+                elements: parts.map(|part| part.trim().to_string()).collect(),
+            }
+        })
+        .collect();
+
+    let isa = &segments[0];
+    let sender_id = isa.get(6).unwrap_or_default().to_string();
+    let interchange_control_number = isa.get(13).unwrap_or_default().to_string();
+
+    let mut transactions = Vec::new();
+    let mut current: Option<Transaction> = None;
+    for segment in segments.into_iter().skip(1) {
+        match segment.id.as_str() {
+            "ST" => {
+                if current.is_some() {
+                    bail!("ST segment before the previous transaction set was closed");
+                }
+                current = Some(Transaction {
+                    sender_id: sender_id.clone(),
+                    interchange_control_number: interchange_control_number.clone(),
+                    transaction_set: segment.get(1).context("ST01 missing")?.to_string(),
+                    segments: Vec::new(),
+                });
+            }
+            "SE" => transactions.push(current.take().context("SE without matching ST")?),
+            _ => {
+                if let Some(transaction) = current.as_mut() {
+                    transaction.segments.push(segment);
+                }
+            }
+        }
+    }
+
+    if current.is_some() {
+        bail!("transaction set is missing its SE trailer");
+    }
+    if transactions.is_empty() {
+        bail!("interchange contains no transaction sets");
+    }
+    Ok(transactions)
+}
+
+/// Builds an outbound interchange with `*` elements, `>` components and `~` terminators
+pub struct Writer {
+    segments: Vec<String>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self { segments: Vec::new() }
+    }
+
+    pub fn segment(&mut self, id: &str, elements: &[&str]) -> &mut Self {
+        let mut segment = id.to_string();
+        for element in elements {
+            segment.push('*');
+            segment.push_str(element);
+        }
+        // Trailing empty elements are dropped, as X12 requires
+        while segment.ends_with('*') {
+            segment.pop();
+        }
+        self.segments.push(segment);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn finish(self) -> String {
+        self.segments
+            .into_iter()
+            .map(|segment| segment + "~\n")
+            .collect()
+    }
+}
+
+impl Default for Writer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fixed-width ISA elements are space padded
+pub fn pad(value: &str, width: usize) -> String {
+    format!("{:<width$}", value.chars().take(width).collect::<String>(), width = width)
+}
@@ -0,0 +1,47 @@
+//! Zone sensor intake and cold-chain alerting
+
+use warehouse_db::Database;
+use warehouse_models::*;
+
+use crate::{notify, AppResult};
+
+/// Store sensor readings; every excursion they open raises a notification. Temperature
+/// excursions are critical since they can spoil stock, humidity ones are warnings.
+pub async fn ingest(db: &Database, batch: SensorReadings) -> AppResult<ReadingIngestResult> {
+    let result = db.environment().ingest(batch).await?;
+
+    for excursion in &result.excursions_opened {
+        let zone = db.locations().get_zone(excursion.zone_id).await?;
+        let sensor = db.environment().get_sensor(excursion.sensor_id).await?;
+        let (label, severity) = match excursion.measure.as_str() {
+            MEASURE_TEMPERATURE => ("Temperature", SEVERITY_CRITICAL),
+            _ => ("Humidity", SEVERITY_WARNING),
+        };
+
+        notify::raise(
+            db,
+            NewNotification {
+                kind: NOTIFY_COLD_CHAIN_EXCURSION,
+                severity,
+                subject: format!(
+                    "{} excursion in zone {}: {} outside {}..{}",
+                    label,
+                    zone.map(|z| z.zone_code).unwrap_or_default(),
+                    excursion.peak_value,
+                    excursion.limit_min.map(|v| v.to_string()).unwrap_or_default(),
+                    excursion.limit_max.map(|v| v.to_string()).unwrap_or_default()
+                ),
+                message: Some(format!(
+                    "Sensor {} out of range since {}",
+                    sensor.map(|s| s.sensor_code).unwrap_or_default(),
+                    excursion.started_at
+                )),
+                reference_type: Some("EXCURSION".to_string()),
+                reference_id: Some(excursion.excursion_id),
+            },
+        )
+        .await?;
+    }
+
+    Ok(result)
+}
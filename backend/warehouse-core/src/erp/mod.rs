@@ -0,0 +1,323 @@
+//! ERP connectors: the item master and purchase orders come in from the ERP, goods
+//! receipts and goods issues go out to it. Connectors are set up through the API and
+//! synced on their own interval; each sync pulls before it pushes, so receipts of freshly
+//! imported purchase orders carry the ERP's order id.
+//!
+//! Records are exchanged in our field names, renamed per connector by its field mappings.
+//! The protocol is the connector type's; `rest` is the first.
+
+pub mod rest;
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::{info, warn};
+use warehouse_db::Database;
+use warehouse_models::chrono::{DateTime, Utc};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::{notify, AppError, AppResult};
+
+pub use rest::RestErpClient;
+
+/// Records pushed per request
+const PUSH_BATCH: i64 = 200;
+
+#[async_trait]
+pub trait ErpClient: Send + Sync {
+    /// Send records of `entity`, already in the ERP's field names
+    async fn push(&self, entity: &str, records: Vec<Value>) -> AppResult<()>;
+    /// Records of `entity` changed since `since`, or all of them
+    async fn pull(&self, entity: &str, since: Option<DateTime<Utc>>) -> AppResult<Vec<Value>>;
+}
+
+pub fn client(connector: &ErpConnector) -> AppResult<Box<dyn ErpClient>> {
+    match connector.connector_type.as_str() {
+        ERP_CONNECTOR_REST => Ok(Box::new(RestErpClient::new(connector))),
+        other => Err(AppError::validation(format!("unknown ERP connector type {}", other))),
+    }
+}
+
+/// Field names of one entity, ours to the ERP's and back
+pub struct FieldMap {
+    to_erp: HashMap<String, String>,
+    from_erp: HashMap<String, String>,
+}
+
+impl FieldMap {
+    pub fn new(mappings: &[ErpFieldMapping], entity: &str) -> Self {
+        let pairs = mappings.iter().filter(|mapping| mapping.entity == entity);
+        Self {
+            to_erp: pairs.clone().map(|m| (m.field_name.clone(), m.erp_field.clone())).collect(),
+            from_erp: pairs.map(|m| (m.erp_field.clone(), m.field_name.clone())).collect(),
+        }
+    }
+
+    pub fn to_erp<T: Serialize>(&self, record: &T) -> AppResult<Value> {
+        let value = serde_json::to_value(record).map_err(|e| AppError::Internal(e.into()))?;
+        Ok(rename(value, &self.to_erp))
+    }
+
+    /// Read a record sent by the ERP. Errors name the field that did not fit.
+    pub fn from_erp<T: DeserializeOwned>(&self, record: Value) -> AppResult<T> {
+        serde_json::from_value(rename(record, &self.from_erp))
+            .map_err(|e| AppError::validation(format!("ERP record does not map: {}", e)))
+    }
+}
+
+/// Rename object keys at every depth, so line fields map like header fields
+fn rename(value: Value, names: &HashMap<String, String>) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (names.get(&key).cloned().unwrap_or(key), rename(value, names)))
+                .collect::<Map<String, Value>>(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(|value| rename(value, names)).collect()),
+        other => other,
+    }
+}
+
+/// Sync every connector that is due. Returns how many synced without a failure.
+pub async fn run_due(db: &Database) -> AppResult<usize> {
+    let mut synced = 0;
+    for connector in db.erp().claim_due(Utc::now()).await? {
+        let Some(detail) = db.erp().get_by_id(connector.connector_id).await? else {
+            continue;
+        };
+        if sync(db, &detail).await?.iter().all(|run| run.status == ERP_RUN_SUCCEEDED) {
+            synced += 1;
+        }
+    }
+
+    Ok(synced)
+}
+
+/// Pull and push every entity the connector exchanges, recording a run for each. A
+/// failing entity raises a notification and does not stop the others; its cursor stays
+/// where the last successful batch left it.
+pub async fn sync(db: &Database, detail: &ErpConnectorDetail) -> AppResult<Vec<ErpSyncRun>> {
+    let connector = &detail.connector;
+    let client = client(connector)?;
+    let mut steps = Vec::new();
+    if connector.item_master == ERP_ITEM_MASTER_ERP {
+        steps.push((ERP_PULL, ERP_ENTITY_ITEM));
+    }
+    steps.push((ERP_PULL, ERP_ENTITY_PURCHASE_ORDER));
+    if connector.item_master == ERP_ITEM_MASTER_WMS {
+        steps.push((ERP_PUSH, ERP_ENTITY_ITEM));
+    }
+    steps.push((ERP_PUSH, ERP_ENTITY_RECEIPT));
+    steps.push((ERP_PUSH, ERP_ENTITY_ISSUE));
+
+    let mut runs = Vec::with_capacity(steps.len());
+    for (direction, entity) in steps {
+        let step = Step { db, connector, client: client.as_ref(), map: FieldMap::new(&detail.field_mappings, entity) };
+        let started_at = Utc::now();
+        let mut records = 0;
+        let outcome = match (direction, entity) {
+            (ERP_PULL, ERP_ENTITY_ITEM) => step.pull_items(&mut records).await,
+            (ERP_PULL, _) => step.pull_purchase_orders(&mut records).await,
+            (_, ERP_ENTITY_ITEM) => step.push_items(&mut records).await,
+            (_, ERP_ENTITY_RECEIPT) => step.push_receipts(&mut records).await,
+            _ => step.push_issues(&mut records).await,
+        };
+
+        let error = match outcome {
+            Ok(()) => None,
+            Err(AppError::Database(e)) => return Err(AppError::Database(e)),
+            Err(AppError::Internal(e)) => return Err(AppError::Internal(e)),
+            Err(e) => Some(e.to_string()),
+        };
+        runs.push(db.erp().record_run(connector.connector_id, direction, entity, started_at, records, error).await?);
+    }
+
+    let failed: Vec<String> = runs
+        .iter()
+        .filter_map(|run| Some(format!("{} {}: {}", run.direction, run.entity, run.error.as_ref()?)))
+        .collect();
+    if failed.is_empty() {
+        let records: i32 = runs.iter().map(|run| run.records).sum();
+        info!(connector = %connector.connector_code, records, "ERP sync completed");
+    } else {
+        warn!(connector = %connector.connector_code, "ERP sync failed: {}", failed.join("; "));
+        notify::raise(
+            db,
+            NewNotification {
+                kind: NOTIFY_ERP_SYNC_FAILED,
+                severity: SEVERITY_WARNING,
+                subject: format!("ERP sync with {} failed", connector.connector_code),
+                message: Some(failed.join("\n")),
+                reference_type: Some("ERP_CONNECTOR".to_string()),
+                reference_id: Some(connector.connector_id),
+            },
+        )
+        .await?;
+    }
+
+    Ok(runs)
+}
+
+/// One direction and entity of a sync
+struct Step<'a> {
+    db: &'a Database,
+    connector: &'a ErpConnector,
+    client: &'a dyn ErpClient,
+    map: FieldMap,
+}
+
+impl Step<'_> {
+    async fn pull_items(&self, records: &mut i32) -> AppResult<()> {
+        let cursor = self.cursor(ERP_PULL, ERP_ENTITY_ITEM).await?;
+        let mut latest = cursor.map(|cursor| cursor.position_at);
+
+        for record in self.client.pull(ERP_ENTITY_ITEM, latest).await? {
+            let item: ErpItem = self.map.from_erp(record)?;
+            item.validate().map_err(|e| AppError::validation(format!("item {}: {}", item.item_code, e)))?;
+            self.db.erp().import_item(&item).await?;
+            latest = latest.max(Some(item.updated_at));
+            *records += 1;
+        }
+
+        self.advance(ERP_PULL, ERP_ENTITY_ITEM, latest).await
+    }
+
+    /// Purchase orders become expected receipts. Orders imported before are skipped, so
+    /// pulling the same changes twice is harmless.
+    async fn pull_purchase_orders(&self, records: &mut i32) -> AppResult<()> {
+        let cursor = self.cursor(ERP_PULL, ERP_ENTITY_PURCHASE_ORDER).await?;
+        let mut latest = cursor.map(|cursor| cursor.position_at);
+
+        for record in self.client.pull(ERP_ENTITY_PURCHASE_ORDER, latest).await? {
+            let order: ErpPurchaseOrder = self.map.from_erp(record)?;
+            latest = latest.max(Some(order.updated_at));
+            if self.import_purchase_order(&order).await? {
+                *records += 1;
+            }
+        }
+
+        self.advance(ERP_PULL, ERP_ENTITY_PURCHASE_ORDER, latest).await
+    }
+
+    /// Returns false when the order was there already
+    async fn import_purchase_order(&self, order: &ErpPurchaseOrder) -> AppResult<bool> {
+        let connector_id = self.connector.connector_id;
+        let label = format!("purchase order {}", order.purchase_order_number);
+        order.validate().map_err(|e| AppError::validation(format!("{}: {}", label, e)))?;
+
+        if self.db.erp().linked(connector_id, ERP_ENTITY_PURCHASE_ORDER, &order.external_id).await?.is_some() {
+            return Ok(false);
+        }
+        let warehouse_id = self
+            .db
+            .erp()
+            .warehouse_id(&order.warehouse_code)
+            .await?
+            .ok_or_else(|| AppError::validation(format!("{}: unknown warehouse {}", label, order.warehouse_code)))?;
+        if self.db.receipts().asn_exists(warehouse_id, &order.purchase_order_number).await? {
+            warn!("{} already has a receipt in {}; not imported", label, order.warehouse_code);
+            return Ok(false);
+        }
+
+        let mut lines = Vec::with_capacity(order.lines.len());
+        for line in &order.lines {
+            let item = self
+                .db
+                .items()
+                .get_by_code(&line.item_code)
+                .await?
+                .ok_or_else(|| AppError::validation(format!("{}: unknown item {}", label, line.item_code)))?;
+            lines.push(CreateReceiptLine { item_id: item.item_id, quantity_expected: line.quantity, lpn_number: None });
+        }
+
+        let receipt = CreateReceipt {
+            asn_number: order.purchase_order_number.clone(),
+            warehouse_id,
+            supplier_name: order.supplier_name.clone(),
+            purchase_order_number: Some(order.purchase_order_number.clone()),
+            carrier_code: None,
+            expected_date: order.expected_date,
+            consignor_id: None,
+            lines,
+        };
+        let created = self.db.receipts().create_from(receipt, SOURCE_ERP).await?;
+        self.db
+            .erp()
+            .link(connector_id, ERP_ENTITY_PURCHASE_ORDER, &order.external_id, created.receipt.receipt_id)
+            .await?;
+        Ok(true)
+    }
+
+    async fn push_items(&self, records: &mut i32) -> AppResult<()> {
+        let mut cursor = self.cursor(ERP_PUSH, ERP_ENTITY_ITEM).await?;
+        loop {
+            let items = self.db.erp().changed_items(cursor, PUSH_BATCH).await?;
+            let Some(last) = items.last() else {
+                return Ok(());
+            };
+            let next = ErpSyncCursor { position_at: last.updated_at, position_id: last.item_id.unwrap_or_default().into() };
+            cursor = Some(self.push(ERP_ENTITY_ITEM, &items, next).await?);
+            *records += items.len() as i32;
+            if (items.len() as i64) < PUSH_BATCH {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn push_receipts(&self, records: &mut i32) -> AppResult<()> {
+        let mut cursor = self.cursor(ERP_PUSH, ERP_ENTITY_RECEIPT).await?;
+        loop {
+            let receipts = self.db.erp().goods_receipts(self.connector.connector_id, cursor, PUSH_BATCH).await?;
+            let Some(last) = receipts.last() else {
+                return Ok(());
+            };
+            let next = ErpSyncCursor { position_at: last.received_at, position_id: last.receipt_id.into() };
+            cursor = Some(self.push(ERP_ENTITY_RECEIPT, &receipts, next).await?);
+            *records += receipts.len() as i32;
+            if (receipts.len() as i64) < PUSH_BATCH {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn push_issues(&self, records: &mut i32) -> AppResult<()> {
+        let mut cursor = self.cursor(ERP_PUSH, ERP_ENTITY_ISSUE).await?;
+        loop {
+            let issues = self.db.erp().goods_issues(cursor, PUSH_BATCH).await?;
+            let Some(last) = issues.last() else {
+                return Ok(());
+            };
+            let next = ErpSyncCursor { position_at: last.shipped_at, position_id: last.shipment_id.into() };
+            cursor = Some(self.push(ERP_ENTITY_ISSUE, &issues, next).await?);
+            *records += issues.len() as i32;
+            if (issues.len() as i64) < PUSH_BATCH {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Send one batch and move the cursor past it
+    async fn push<T: Serialize>(&self, entity: &str, batch: &[T], next: ErpSyncCursor) -> AppResult<ErpSyncCursor> {
+        let records = batch.iter().map(|record| self.map.to_erp(record)).collect::<AppResult<Vec<_>>>()?;
+        self.client.push(entity, records).await?;
+        self.db.erp().advance(self.connector.connector_id, ERP_PUSH, entity, next).await?;
+        Ok(next)
+    }
+
+    async fn cursor(&self, direction: &str, entity: &str) -> AppResult<Option<ErpSyncCursor>> {
+        Ok(self.db.erp().cursor(self.connector.connector_id, direction, entity).await?)
+    }
+
+    async fn advance(&self, direction: &str, entity: &str, latest: Option<DateTime<Utc>>) -> AppResult<()> {
+        if let Some(position_at) = latest {
+            let cursor = ErpSyncCursor { position_at, position_id: 0 };
+            self.db.erp().advance(self.connector.connector_id, direction, entity, cursor).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+//! Generic REST ERP connector. Speaks a small JSON protocol that ERP middleware or an
+//! ERP's own integration layer can expose, one resource per entity (`items`,
+//! `purchase-orders`, `goods-receipts`, `goods-issues`):
+//!
+//! - `POST {base_url}/{resource}` with `{"records": [...]}` to push records
+//! - `GET {base_url}/{resource}?since=<RFC 3339>` → `{"records": [...]}` to pull records
+//!   changed since then; `since` is left out on the first pull
+//!
+//! Records carry the connector's field names. Requests carry `Authorization: Bearer
+//! <api_key>` when a key is configured.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use warehouse_models::chrono::{DateTime, SecondsFormat, Utc};
+use warehouse_models::*;
+
+use super::ErpClient;
+use crate::{AppError, AppResult};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub struct RestErpClient {
+    code: String,
+    base_url: String,
+    api_key: Option<String>,
+    http: reqwest::Client,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Records {
+    records: Vec<Value>,
+}
+
+impl RestErpClient {
+    pub fn new(connector: &ErpConnector) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            code: connector.connector_code.clone(),
+            base_url: connector.base_url.trim_end_matches('/').to_string(),
+            api_key: connector.api_key.clone(),
+            http,
+        }
+    }
+
+    fn url(&self, entity: &str) -> AppResult<String> {
+        let resource = match entity {
+            ERP_ENTITY_ITEM => "items",
+            ERP_ENTITY_PURCHASE_ORDER => "purchase-orders",
+            ERP_ENTITY_RECEIPT => "goods-receipts",
+            ERP_ENTITY_ISSUE => "goods-issues",
+            other => return Err(AppError::validation(format!("unknown ERP entity {}", other))),
+        };
+        Ok(format!("{}/{}", self.base_url, resource))
+    }
+
+    fn error(&self, message: impl ToString) -> AppError {
+        AppError::ExternalService {
+            service: format!("ERP {}", self.code),
+            message: message.to_string(),
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> AppResult<Response> {
+        let request = match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        };
+        let response = request.send().await.map_err(|e| self.error(e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(self.error(format!("{} {}", status, body)));
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl ErpClient for RestErpClient {
+    async fn push(&self, entity: &str, records: Vec<Value>) -> AppResult<()> {
+        self.send(self.http.post(self.url(entity)?).json(&Records { records })).await?;
+        Ok(())
+    }
+
+    async fn pull(&self, entity: &str, since: Option<DateTime<Utc>>) -> AppResult<Vec<Value>> {
+        let mut request = self.http.get(self.url(entity)?);
+        if let Some(since) = since {
+            request = request.query(&[("since", since.to_rfc3339_opts(SecondsFormat::Micros, true))]);
+        }
+
+        let body: Records = self.send(request).await?.json().await.map_err(|e| self.error(e))?;
+        Ok(body.records)
+    }
+}
@@ -7,6 +7,9 @@ use axum::{
 use serde_json::json;
 use thiserror::Error;
 use tracing::error;
+use warehouse_db::{closed_period, sharded_stock, NegativeStock, RuleViolation};
+
+use crate::i18n;
 
 /// Main application result type
 pub type AppResult<T> = Result<T, AppError>;
@@ -31,15 +34,43 @@ pub enum AppError {
     
     #[error("Forbidden: {reason}")]
     Forbidden { reason: String },
+
+    #[error("Rate limit exceeded")]
+    RateLimited,
     
     #[error("Configuration error: {0}")]
     Config(String),
     
+    #[error("Business rule violated: {0}")]
+    BusinessRule(String),
+    
+    #[error("Negative stock: {0}")]
+    NegativeStock(NegativeStock),
+    
+    #[error("Not available in mock mode")]
+    MockMode,
+
     #[error("External service error: {service} - {message}")]
     ExternalService { service: String, message: String },
     
     #[error("Internal server error: {0}")]
-    Internal(#[from] anyhow::Error),
+    Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for AppError {
+    /// Rule violations raised by the database layer are the caller's fault, not ours
+    fn from(error: anyhow::Error) -> Self {
+        if let Some(violation) = error.downcast_ref::<RuleViolation>() {
+            return Self::BusinessRule(violation.0.clone());
+        }
+        if let Some(message) = closed_period(&error).or_else(|| sharded_stock(&error)) {
+            return Self::BusinessRule(message);
+        }
+        match error.downcast::<NegativeStock>() {
+            Ok(negative) => Self::NegativeStock(negative),
+            Err(error) => Self::Internal(error),
+        }
+    }
 }
 
 impl AppError {
@@ -71,38 +102,61 @@ impl AppError {
 }
 
 impl IntoResponse for AppError {
+    /// Messages are in the locale of the request. Validation, rule and refusal messages the
+    /// catalog lists under `rule.*` are translated; others, such as those naming values and
+    /// the field errors of request body checks, are passed on in English as raised.
     fn into_response(self) -> axum::response::Response {
+        let locale = i18n::current();
+        let text = |key: &str| i18n::text(locale, key, &[]);
         let (status, message, error_code) = match &self {
             AppError::Database(_) => {
                 error!("Database error: {}", self);
-                (StatusCode::INTERNAL_SERVER_ERROR, "Database error occurred".to_string(), "DATABASE_ERROR")
+                (StatusCode::INTERNAL_SERVER_ERROR, text("error.database"), "DATABASE_ERROR")
             }
             AppError::Validation(msg) => {
-                (StatusCode::BAD_REQUEST, msg.clone(), "VALIDATION_ERROR")
+                (StatusCode::BAD_REQUEST, i18n::rule(locale, msg), "VALIDATION_ERROR")
             }
             AppError::NotFound { resource } => {
-                (StatusCode::NOT_FOUND, format!("{} not found", resource), "NOT_FOUND")
+                let resource = i18n::resource(locale, resource);
+                (StatusCode::NOT_FOUND, i18n::text(locale, "error.not_found", &[("resource", &resource)]), "NOT_FOUND")
             }
             AppError::AlreadyExists { resource } => {
-                (StatusCode::CONFLICT, format!("{} already exists", resource), "ALREADY_EXISTS")
+                let resource = i18n::resource(locale, resource);
+                let message = i18n::text(locale, "error.already_exists", &[("resource", &resource)]);
+                (StatusCode::CONFLICT, message, "ALREADY_EXISTS")
             }
             AppError::Unauthorized => {
-                (StatusCode::UNAUTHORIZED, "Unauthorized access".to_string(), "UNAUTHORIZED")
+                (StatusCode::UNAUTHORIZED, text("error.unauthorized"), "UNAUTHORIZED")
             }
             AppError::Forbidden { reason } => {
-                (StatusCode::FORBIDDEN, reason.clone(), "FORBIDDEN")
+                (StatusCode::FORBIDDEN, i18n::rule(locale, reason), "FORBIDDEN")
+            }
+            AppError::RateLimited => {
+                (StatusCode::TOO_MANY_REQUESTS, text("error.rate_limited"), "RATE_LIMITED")
             }
             AppError::Config(msg) => {
                 error!("Configuration error: {}", msg);
-                (StatusCode::INTERNAL_SERVER_ERROR, "Configuration error".to_string(), "CONFIG_ERROR")
+                (StatusCode::INTERNAL_SERVER_ERROR, text("error.config"), "CONFIG_ERROR")
+            }
+            AppError::BusinessRule(msg) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, i18n::rule(locale, msg), "BUSINESS_RULE_VIOLATION")
+            }
+            AppError::NegativeStock(negative) => {
+                let (item_id, warehouse_id) = (negative.item_id.to_string(), negative.warehouse_id.to_string());
+                let on_hand = negative.on_hand.to_string();
+                let args = [("item_id", item_id.as_str()), ("warehouse_id", &warehouse_id), ("on_hand", &on_hand)];
+                (StatusCode::UNPROCESSABLE_ENTITY, i18n::text(locale, "error.negative_stock", &args), "NEGATIVE_STOCK")
+            }
+            AppError::MockMode => {
+                (StatusCode::SERVICE_UNAVAILABLE, text("error.mock_mode"), "NOT_IN_MOCK_MODE")
             }
             AppError::ExternalService { service, message } => {
                 error!("External service {} error: {}", service, message);
-                (StatusCode::BAD_GATEWAY, "External service error".to_string(), "EXTERNAL_SERVICE_ERROR")
+                (StatusCode::BAD_GATEWAY, text("error.external_service"), "EXTERNAL_SERVICE_ERROR")
             }
             AppError::Internal(_) => {
                 error!("Internal error: {}", self);
-                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string(), "INTERNAL_ERROR")
+                (StatusCode::INTERNAL_SERVER_ERROR, text("error.internal"), "INTERNAL_ERROR")
             }
         };
 
@@ -0,0 +1,116 @@
+//! General ledger journals. Each period's costed movements of our own stock are netted per
+//! warehouse into an inventory leg and the account that balances it (goods received not
+//! invoiced, cost of goods sold, scrap, in transit or adjustments), then exported as CSV or
+//! posted to an accounting API:
+//!
+//! - `POST {export_url}` with the journal as JSON, `Authorization: Bearer <export_api_key>`
+//!   when a key is configured
+//!
+//! Closing a period locks its movements in the database, so a journal exported after the
+//! close stays the journal of record.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use tracing::{info, warn};
+use warehouse_db::Database;
+use warehouse_models::chrono::NaiveDate;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+use crate::config::GlConfig;
+use crate::{AppError, AppResult};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Journal of `period` from its movements as they stand
+pub async fn journal(db: &Database, period: NaiveDate) -> AppResult<GlJournal> {
+    let status = db.gl().period(period).await?.status;
+    let totals = db.gl().movement_totals(period).await?;
+    let accounts = db.gl().accounts().await?;
+    Ok(build_journal(period, status, &totals, &accounts))
+}
+
+/// Net each account per warehouse: the inventory leg moves by the value of the movements,
+/// the offset account by the opposite. A positive net is a debit, a negative one a credit.
+pub fn build_journal(
+    period: NaiveDate,
+    status: String,
+    totals: &[GlMovementTotal],
+    accounts: &[GlAccount],
+) -> GlJournal {
+    let mut nets: BTreeMap<(&str, &str), Decimal> = BTreeMap::new();
+    for total in totals {
+        let Some(offset) = gl_offset_category(&total.movement_type) else {
+            continue;
+        };
+        *nets.entry((GL_INVENTORY, total.warehouse_code.as_str())).or_default() += total.value;
+        *nets.entry((offset, total.warehouse_code.as_str())).or_default() -= total.value;
+    }
+
+    let mut lines: Vec<GlJournalLine> = nets
+        .into_iter()
+        .filter(|(_, net)| !net.is_zero())
+        .map(|((category, warehouse_code), net)| {
+            let account = accounts.iter().find(|account| account.category == category);
+            GlJournalLine {
+                period,
+                account_code: account.map(|account| account.account_code.clone()).unwrap_or_default(),
+                account_name: account.map(|account| account.account_name.clone()).unwrap_or_default(),
+                category: category.to_string(),
+                warehouse_code: warehouse_code.to_string(),
+                debit: net.max(Decimal::ZERO),
+                credit: (-net).max(Decimal::ZERO),
+            }
+        })
+        .collect();
+    lines.sort_by(|a, b| (&a.account_code, &a.warehouse_code).cmp(&(&b.account_code, &b.warehouse_code)));
+
+    GlJournal {
+        period,
+        status,
+        total_debit: lines.iter().map(|line| line.debit).sum(),
+        total_credit: lines.iter().map(|line| line.credit).sum(),
+        movements: totals.iter().map(|total| total.movements).sum(),
+        uncosted_movements: totals.iter().map(|total| total.uncosted_movements).sum(),
+        lines,
+    }
+}
+
+/// Post the journal of `period` to the accounting API. A failed post is recorded on the
+/// export rather than returned.
+pub async fn push(db: &Database, config: &GlConfig, period: NaiveDate) -> AppResult<GlExport> {
+    let url = config
+        .export_url
+        .as_deref()
+        .ok_or_else(|| AppError::Config("GL_EXPORT_URL is not set".to_string()))?;
+    let journal = journal(db, period).await?;
+
+    let error = post(url, config.export_api_key.as_deref(), &journal).await.err();
+    let export = db.gl().record_export(&journal, url, error).await?;
+    match &export.error {
+        None => info!(period = %period, lines = export.lines, "GL journal exported"),
+        Some(error) => warn!(period = %period, "GL journal export failed: {}", error),
+    }
+    Ok(export)
+}
+
+async fn post(url: &str, api_key: Option<&str>, journal: &GlJournal) -> Result<(), String> {
+    let http = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default();
+    let request = http.post(url).json(journal);
+    let request = match api_key {
+        Some(key) => request.bearer_auth(key),
+        None => request,
+    };
+
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("{} {}", status, body));
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+//! Message catalog: each key with its English and Bahasa Indonesia text. `{name}` marks a
+//! placeholder. Keys are grouped by prefix:
+//!
+//! - `error.*`: error responses
+//! - `resource.*`: resource names used in error messages
+//! - `rule.*`: validation, business rule and refusal messages, keyed by their English text.
+//!   Messages naming values, and the field errors of request body checks, have no entry.
+//! - `label.<group>.<CODE>`: display labels of status and type codes
+//! - `column.*` and `aggregate.*`: custom report headings
+
+/// (key, English, Bahasa Indonesia)
+pub(super) const MESSAGES: &[(&str, &str, &str)] = &[
+    ("error.database", "Database error occurred", "Terjadi kesalahan basis data"),
+    ("error.not_found", "{resource} not found", "{resource} tidak ditemukan"),
+    ("error.already_exists", "{resource} already exists", "{resource} sudah ada"),
+    ("error.unauthorized", "Unauthorized access", "Akses tidak diizinkan"),
+    ("error.rate_limited", "Too many requests, slow down", "Terlalu banyak permintaan, mohon perlambat"),
+    ("error.config", "Configuration error", "Kesalahan konfigurasi"),
+    ("error.external_service", "External service error", "Kesalahan layanan eksternal"),
+    ("error.mock_mode", "Not available in mock mode", "Tidak tersedia dalam mode tiruan"),
+    (
+        "error.negative_stock",
+        "item {item_id} in warehouse {warehouse_id} would go to {on_hand} on hand; negative stock is not allowed",
+        "stok barang {item_id} di gudang {warehouse_id} akan menjadi {on_hand}; stok negatif tidak diizinkan",
+    ),
+    ("error.internal", "Internal server error", "Kesalahan internal server"),
+    ("resource.API key", "API key", "kunci API"),
+    ("resource.backup", "backup", "cadangan"),
+    ("resource.client", "client", "klien"),
+    ("resource.delivery", "delivery", "pengantaran"),
+    ("resource.driver", "driver", "pengemudi"),
+    ("resource.driver code", "driver code", "kode pengemudi"),
+    ("resource.inbound load", "inbound load", "muatan masuk"),
+    ("resource.inspection", "inspection", "inspeksi"),
+    ("resource.item", "item", "barang"),
+    ("resource.job", "job", "pekerjaan"),
+    ("resource.job artifact", "job artifact", "berkas pekerjaan"),
+    ("resource.loan", "loan", "pinjaman"),
+    ("resource.location", "location", "lokasi"),
+    ("resource.notification", "notification", "notifikasi"),
+    ("resource.order", "order", "pesanan"),
+    ("resource.preferences", "preferences", "preferensi"),
+    ("resource.print template", "print template", "templat cetak"),
+    ("resource.print template assignment", "print template assignment", "penetapan templat cetak"),
+    ("resource.print template code", "print template code", "kode templat cetak"),
+    ("resource.print template version", "print template version", "versi templat cetak"),
+    ("resource.printer", "printer", "printer"),
+    ("resource.printer code", "printer code", "kode printer"),
+    ("resource.project", "project", "proyek"),
+    ("resource.proof of delivery", "proof of delivery", "bukti pengantaran"),
+    ("resource.proof of delivery file", "proof of delivery file", "berkas bukti pengantaran"),
+    ("resource.purchase order", "purchase order", "pesanan pembelian"),
+    ("resource.receipt", "receipt", "penerimaan"),
+    ("resource.repair order", "repair order", "order perbaikan"),
+    ("resource.report", "report", "laporan"),
+    ("resource.report schedule", "report schedule", "jadwal laporan"),
+    ("resource.reservation", "reservation", "reservasi"),
+    ("resource.requisition", "requisition", "permintaan pembelian"),
+    ("resource.return", "return", "retur"),
+    ("resource.returnable type code", "returnable type code", "kode jenis kemasan kembali"),
+    ("resource.run", "run", "perjalanan"),
+    ("resource.shift", "shift", "sif"),
+    ("resource.shipment", "shipment", "pengiriman"),
+    ("resource.stock reservation", "stock reservation", "reservasi stok"),
+    ("resource.task", "task", "tugas"),
+    ("resource.transfer", "transfer", "transfer"),
+    ("resource.user", "user", "pengguna"),
+    ("resource.user with this email", "user with this email", "pengguna dengan email ini"),
+    ("resource.user with this username", "user with this username", "pengguna dengan nama pengguna ini"),
+    ("resource.vehicle", "vehicle", "kendaraan"),
+    ("resource.vehicle code", "vehicle code", "kode kendaraan"),
+    ("resource.view", "view", "tampilan"),
+    ("resource.view name", "view name", "nama tampilan"),
+    ("resource.warehouse", "warehouse", "gudang"),
+    ("resource.wave", "wave", "gelombang"),
+    ("resource.zone", "zone", "zona"),
+    ("rule.from must not be after to", "from must not be after to", "from tidak boleh setelah to"),
+    ("rule.from must be before to", "from must be before to", "from harus sebelum to"),
+    ("rule.as_of must not be in the future", "as_of must not be in the future", "as_of tidak boleh di masa depan"),
+    (
+        "rule.as_of is required for CSV extracts",
+        "as_of is required for CSV extracts",
+        "as_of wajib diisi untuk ekstrak CSV",
+    ),
+    ("rule.date must not be in the past", "date must not be in the past", "date tidak boleh di masa lalu"),
+    (
+        "rule.delivered_at must not be in the future",
+        "delivered_at must not be in the future",
+        "delivered_at tidak boleh di masa depan",
+    ),
+    (
+        "rule.needed_by must not be in the past",
+        "needed_by must not be in the past",
+        "needed_by tidak boleh di masa lalu",
+    ),
+    ("rule.run_date must not be in the past", "run_date must not be in the past", "run_date tidak boleh di masa lalu"),
+    (
+        "rule.expires_on must not be before needed_by",
+        "expires_on must not be before needed_by",
+        "expires_on tidak boleh sebelum needed_by",
+    ),
+    ("rule.qty must be positive", "qty must be positive", "qty harus positif"),
+    (
+        "rule.the system user cannot be changed",
+        "the system user cannot be changed",
+        "pengguna sistem tidak dapat diubah",
+    ),
+    (
+        "rule.a received transfer cannot be rolled back",
+        "a received transfer cannot be rolled back",
+        "transfer yang sudah diterima tidak dapat dibatalkan",
+    ),
+    (
+        "rule.a loan cannot be due before today",
+        "a loan cannot be due before today",
+        "pinjaman tidak boleh jatuh tempo sebelum hari ini",
+    ),
+    (
+        "rule.a serialized tool is lent one unit at a time",
+        "a serialized tool is lent one unit at a time",
+        "alat berseri dipinjamkan satu unit setiap kali",
+    ),
+    (
+        "rule.no open orders match the wave criteria",
+        "no open orders match the wave criteria",
+        "tidak ada pesanan terbuka yang cocok dengan kriteria gelombang",
+    ),
+    (
+        "rule.a read-only API key cannot make changes",
+        "a read-only API key cannot make changes",
+        "kunci API hanya-baca tidak dapat melakukan perubahan",
+    ),
+    (
+        "rule.this change needs an API key with the admin scope",
+        "this change needs an API key with the admin scope",
+        "perubahan ini memerlukan kunci API dengan cakupan admin",
+    ),
+    ("label.order_status.OPEN", "Open", "Terbuka"),
+    ("label.order_status.PARTIALLY_SHIPPED", "Partially shipped", "Dikirim sebagian"),
+    ("label.order_status.SHIPPED", "Shipped", "Dikirim"),
+    ("label.order_status.CANCELLED", "Cancelled", "Dibatalkan"),
+    ("label.receipt_status.EXPECTED", "Expected", "Diharapkan"),
+    ("label.receipt_status.PARTIALLY_RECEIVED", "Partially received", "Diterima sebagian"),
+    ("label.receipt_status.RECEIVED", "Received", "Diterima"),
+    ("label.return_status.AUTHORIZED", "Authorized", "Disetujui"),
+    ("label.return_status.RECEIVED", "Received", "Diterima"),
+    ("label.return_status.COMPLETED", "Completed", "Selesai"),
+    ("label.return_status.CANCELLED", "Cancelled", "Dibatalkan"),
+    ("label.inspection_status.PENDING", "Pending", "Menunggu"),
+    ("label.inspection_status.PASSED", "Passed", "Lolos"),
+    ("label.inspection_status.FAILED", "Failed", "Gagal"),
+    ("label.stock_status.AVAILABLE", "Available", "Tersedia"),
+    ("label.stock_status.QUARANTINE", "Quarantine", "Karantina"),
+    ("label.task_type.RECEIVE", "Receive", "Terima"),
+    ("label.task_type.PUTAWAY", "Put away", "Simpan"),
+    ("label.task_type.PICK", "Pick", "Ambil"),
+    ("label.task_type.COUNT", "Count", "Hitung"),
+    ("label.task_type.MOVE", "Move", "Pindah"),
+    ("label.task_status.OPEN", "Open", "Terbuka"),
+    ("label.task_status.ASSIGNED", "Assigned", "Ditugaskan"),
+    ("label.task_status.IN_PROGRESS", "In progress", "Sedang dikerjakan"),
+    ("label.task_status.COMPLETED", "Completed", "Selesai"),
+    ("label.task_status.CANCELLED", "Cancelled", "Dibatalkan"),
+    ("label.transfer_status.PLANNED", "Planned", "Direncanakan"),
+    ("label.transfer_status.IN_TRANSIT", "In transit", "Dalam perjalanan"),
+    ("label.transfer_status.RECEIVED", "Received", "Diterima"),
+    ("label.transfer_status.COMPENSATED", "Rolled back", "Dibatalkan kembali"),
+    ("label.job_type.IMPORT", "Import", "Impor"),
+    ("label.job_type.EXPORT", "Export", "Ekspor"),
+    ("label.job_type.REPORT", "Report", "Laporan"),
+    ("label.job_type.RECALCULATION", "Recalculation", "Perhitungan ulang"),
+    ("label.job_type.BACKUP", "Backup", "Pencadangan"),
+    ("label.job_status.QUEUED", "Queued", "Dalam antrean"),
+    ("label.job_status.RUNNING", "Running", "Berjalan"),
+    ("label.job_status.SUCCEEDED", "Succeeded", "Berhasil"),
+    ("label.job_status.FAILED", "Failed", "Gagal"),
+    ("column.category", "Category", "Kategori"),
+    ("column.cost_currency", "Cost currency", "Mata uang biaya"),
+    ("column.customer_name", "Customer", "Pelanggan"),
+    ("column.is_active", "Active", "Aktif"),
+    ("column.item_code", "Item code", "Kode barang"),
+    ("column.item_name", "Item name", "Nama barang"),
+    ("column.item_type", "Item type", "Jenis barang"),
+    ("column.last_movement_date", "Last movement", "Pergerakan terakhir"),
+    ("column.lot_number", "Lot number", "Nomor lot"),
+    ("column.movement_date", "Movement date", "Tanggal pergerakan"),
+    ("column.movement_type", "Movement type", "Jenis pergerakan"),
+    ("column.order_date", "Order date", "Tanggal pesanan"),
+    ("column.order_number", "Order number", "Nomor pesanan"),
+    ("column.quantity", "Quantity", "Jumlah"),
+    ("column.quantity_available", "Available", "Tersedia"),
+    ("column.quantity_on_hand", "On hand", "Stok fisik"),
+    ("column.quantity_ordered", "Ordered", "Dipesan"),
+    ("column.quantity_reserved", "Reserved", "Dicadangkan"),
+    ("column.quantity_shipped", "Shipped", "Dikirim"),
+    ("column.reference_number", "Reference number", "Nomor referensi"),
+    ("column.reference_type", "Reference type", "Jenis referensi"),
+    ("column.source", "Source", "Sumber"),
+    ("column.standard_cost", "Standard cost", "Biaya standar"),
+    ("column.status", "Status", "Status"),
+    ("column.stock_status", "Stock status", "Status stok"),
+    ("column.total_value", "Total value", "Nilai total"),
+    ("column.unit", "Unit", "Satuan"),
+    ("column.unit_cost", "Unit cost", "Biaya satuan"),
+    ("column.warehouse_code", "Warehouse code", "Kode gudang"),
+    ("column.warehouse_name", "Warehouse", "Gudang"),
+    ("column.count", "Rows", "Jumlah baris"),
+    ("aggregate.sum", "{column} (sum)", "{column} (total)"),
+    ("aggregate.avg", "{column} (average)", "{column} (rata-rata)"),
+    ("aggregate.min", "{column} (minimum)", "{column} (minimum)"),
+    ("aggregate.max", "{column} (maximum)", "{column} (maksimum)"),
+];
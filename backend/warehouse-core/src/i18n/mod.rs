@@ -0,0 +1,148 @@
+//! User-facing text in English and Bahasa Indonesia. The API picks each request's locale
+//! from the user's saved preference or `Accept-Language` and runs the request inside
+//! `scope`, so error messages, labels and report headings built anywhere beneath it come
+//! out in that language. Text without a catalog entry stays as written, in English.
+
+mod catalog;
+
+use std::collections::BTreeMap;
+use std::future::Future;
+
+use serde::Serialize;
+
+use catalog::MESSAGES;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Id,
+}
+
+impl Locale {
+    /// ISO 639-1 code, as sent in `Content-Language`
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Id => "id",
+        }
+    }
+
+    /// A language tag such as `id`, `id-ID` or `en-GB`; `in` is the old code for Indonesian
+    pub fn parse(tag: &str) -> Option<Locale> {
+        let language = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Some(Locale::En),
+            "id" | "in" => Some(Locale::Id),
+            _ => None,
+        }
+    }
+
+    /// The supported language the `Accept-Language` header rates highest; the first of
+    /// equally rated ones
+    pub fn from_accept_language(header: &str) -> Option<Locale> {
+        let mut best: Option<(Locale, f32)> = None;
+        for range in header.split(',') {
+            let mut parts = range.split(';');
+            let Some(locale) = parts.next().and_then(Locale::parse) else {
+                continue;
+            };
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality > 0.0 && !best.is_some_and(|(_, top)| quality <= top) {
+                best = Some((locale, quality));
+            }
+        }
+        best.map(|(locale, _)| locale)
+    }
+}
+
+tokio::task_local! {
+    static LOCALE: Locale;
+}
+
+/// Run `work` with `locale` as the current locale
+pub async fn scope<F: Future>(locale: Locale, work: F) -> F::Output {
+    LOCALE.scope(locale, work).await
+}
+
+/// Locale of the request being served; English outside a request
+pub fn current() -> Locale {
+    LOCALE.try_with(|locale| *locale).unwrap_or_default()
+}
+
+fn lookup(locale: Locale, key: &str) -> Option<&'static str> {
+    MESSAGES.iter().find(|(k, _, _)| *k == key).map(|(_, en, id)| match locale {
+        Locale::En => *en,
+        Locale::Id => *id,
+    })
+}
+
+/// The catalog text for `key` with its `{name}` placeholders filled in; the key itself
+/// when the catalog has no entry
+pub fn text(locale: Locale, key: &str, args: &[(&str, &str)]) -> String {
+    let mut text = lookup(locale, key).unwrap_or(key).to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), value);
+    }
+    text
+}
+
+/// Name of a resource as error messages use it, e.g. `item`
+pub fn resource(locale: Locale, name: &str) -> String {
+    lookup(locale, &format!("resource.{}", name)).unwrap_or(name).to_string()
+}
+
+/// A validation or business rule message in the locale; messages the catalog has no
+/// entry for, such as those naming values, are passed on as raised
+pub fn rule(locale: Locale, message: &str) -> String {
+    lookup(locale, &format!("rule.{}", message)).unwrap_or(message).to_string()
+}
+
+/// Display label of a status or type code in `group`, e.g. `order_status` and `SHIPPED`.
+/// Codes without an entry are spelled out: `ON_HOLD` becomes `On hold`.
+pub fn label(locale: Locale, group: &str, code: &str) -> String {
+    lookup(locale, &format!("label.{}.{}", group, code)).map(str::to_string).unwrap_or_else(|| spell_out(code))
+}
+
+/// Every label in the catalog, by group and code
+pub fn labels(locale: Locale) -> BTreeMap<String, BTreeMap<String, String>> {
+    let mut groups: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    for (key, _, _) in MESSAGES {
+        if let Some((group, code)) = key.strip_prefix("label.").and_then(|rest| rest.split_once('.')) {
+            groups.entry(group.to_string()).or_default().insert(code.to_string(), label(locale, group, code));
+        }
+    }
+    groups
+}
+
+/// Headings for custom report columns: `item_code` becomes `Item code` or `Kode barang`,
+/// and aggregates such as `sum_quantity_on_hand` name their function after the column
+pub fn report_headers(locale: Locale, columns: &[String]) -> Vec<String> {
+    columns.iter().map(|column| report_header(locale, column)).collect()
+}
+
+fn report_header(locale: Locale, column: &str) -> String {
+    if let Some(heading) = lookup(locale, &format!("column.{}", column)) {
+        return heading.to_string();
+    }
+    if let Some((function, name)) = column.split_once('_') {
+        if let Some(template) = lookup(locale, &format!("aggregate.{}", function)) {
+            return template.replace("{column}", &report_header(locale, name));
+        }
+    }
+    spell_out(column)
+}
+
+/// `IN_TRANSIT` or `in_transit` as `In transit`
+fn spell_out(code: &str) -> String {
+    let words = code.replace('_', " ").to_lowercase();
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => words,
+    }
+}
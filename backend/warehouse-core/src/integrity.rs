@@ -0,0 +1,33 @@
+//! Scheduled integrity checks. Every run records its findings; a run that finds anything
+//! raises a critical notification pointing at the run for drill-down.
+
+use tracing::info;
+use warehouse_db::Database;
+use warehouse_models::*;
+
+use crate::{notify, AppResult};
+
+pub async fn run(db: &Database) -> AppResult<IntegrityReport> {
+    let report = db.integrity().check().await?;
+    if report.passed() {
+        info!(run_id = report.run.run_id, "Integrity checks passed");
+        return Ok(report);
+    }
+
+    let summary: Vec<String> =
+        report.failed_checks.iter().map(|check| format!("{} {}", check.findings, check.check_name)).collect();
+    notify::raise(
+        db,
+        NewNotification {
+            kind: NOTIFY_INTEGRITY_VIOLATION,
+            severity: SEVERITY_CRITICAL,
+            subject: format!("Integrity run {} found {} broken stock invariants", report.run.run_id, report.run.findings),
+            message: Some(summary.join(", ")),
+            reference_type: Some("INTEGRITY_RUN".to_string()),
+            reference_id: Some(report.run.run_id),
+        },
+    )
+    .await?;
+
+    Ok(report)
+}
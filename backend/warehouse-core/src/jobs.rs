@@ -0,0 +1,144 @@
+//! Asynchronous work tracked as jobs. `spawn` records a job and runs its work in the
+//! background, handing it a `Progress` to report how far it has got, problems it carried
+//! on past and the files it produced; the job succeeds when the work returns and fails
+//! with its error otherwise.
+//!
+//! Artifacts are downloaded through URLs signed with HMAC-SHA256 over the artifact id and
+//! expiry time, so a link works for whoever holds it, without other credentials, until it
+//! expires.
+
+use std::future::Future;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tracing::{info, warn};
+use warehouse_db::Database;
+use warehouse_models::chrono::{DateTime, Duration, Utc};
+use warehouse_models::*;
+
+use crate::config::JobConfig;
+use crate::AppResult;
+
+/// Handed to a job's work to report through
+#[derive(Clone)]
+pub struct Progress {
+    db: Database,
+    job_id: i64,
+}
+
+impl Progress {
+    pub fn job_id(&self) -> i64 {
+        self.job_id
+    }
+
+    /// `processed` units of work done, out of `total` when it is known
+    pub async fn advance(&self, processed: i64, total: Option<i64>) -> AppResult<()> {
+        self.db.jobs().progress(self.job_id, processed, total).await?;
+        Ok(())
+    }
+
+    /// A problem the work carries on past, such as a rejected import row
+    pub async fn error(&self, message: &str) -> AppResult<()> {
+        self.db.jobs().add_error(self.job_id, message).await?;
+        Ok(())
+    }
+
+    pub async fn artifact(&self, file_name: &str, content_type: &str, content: &[u8]) -> AppResult<JobArtifact> {
+        Ok(self.db.jobs().add_artifact(self.job_id, file_name, content_type, content).await?)
+    }
+}
+
+/// Record the job and run `work` in the background. Returns the job as queued.
+pub async fn spawn<F, Fut>(db: &Database, job: NewJob, work: F) -> AppResult<Job>
+where
+    F: FnOnce(Progress) -> Fut + Send + 'static,
+    Fut: Future<Output = AppResult<()>> + Send + 'static,
+{
+    let queued = db.jobs().create(job).await?;
+    let progress = Progress { db: db.clone(), job_id: queued.job_id };
+    tokio::spawn(async move {
+        let job_id = progress.job_id;
+        if let Err(e) = run(progress, work).await {
+            warn!(job_id, "Job status could not be recorded: {}", e);
+        }
+    });
+
+    Ok(queued)
+}
+
+/// Record the job and run `work` to the end. Returns the finished job.
+pub async fn track<F, Fut>(db: &Database, job: NewJob, work: F) -> AppResult<Job>
+where
+    F: FnOnce(Progress) -> Fut,
+    Fut: Future<Output = AppResult<()>>,
+{
+    let queued = db.jobs().create(job).await?;
+    run(Progress { db: db.clone(), job_id: queued.job_id }, work).await
+}
+
+async fn run<F, Fut>(progress: Progress, work: F) -> AppResult<Job>
+where
+    F: FnOnce(Progress) -> Fut,
+    Fut: Future<Output = AppResult<()>>,
+{
+    let (db, job_id) = (progress.db.clone(), progress.job_id);
+    db.jobs().start(job_id).await?;
+
+    match work(progress).await {
+        Ok(()) => {
+            let job = db.jobs().succeed(job_id).await?;
+            info!(job_id, job_type = %job.job_type, "Job succeeded");
+            Ok(job)
+        }
+        Err(e) => {
+            warn!(job_id, "Job failed: {}", e);
+            Ok(db.jobs().fail(job_id, &e.to_string()).await?)
+        }
+    }
+}
+
+/// The job with download URLs for its artifacts
+pub async fn detail(db: &Database, secret: &str, config: &JobConfig, job: Job) -> AppResult<JobDetail> {
+    let expires_at = Utc::now() + Duration::seconds(config.download_ttl_secs as i64);
+    let artifacts = db
+        .jobs()
+        .artifacts(job.job_id)
+        .await?
+        .into_iter()
+        .map(|artifact| signed(secret, artifact, expires_at))
+        .collect();
+
+    Ok(JobDetail { job, artifacts })
+}
+
+pub fn signed(secret: &str, artifact: JobArtifact, expires_at: DateTime<Utc>) -> SignedArtifact {
+    let expires = expires_at.timestamp();
+    let download_url = format!(
+        "/api/job-artifacts/{}?expires={}&signature={}",
+        artifact.artifact_id,
+        expires,
+        signature(secret, artifact.artifact_id, expires)
+    );
+    SignedArtifact { artifact, download_url, expires_at }
+}
+
+/// Whether the URL was signed by us for the artifact and has not expired
+pub fn verify(secret: &str, artifact_id: i64, signed: &ArtifactSignature, now: DateTime<Utc>) -> bool {
+    if signed.expires < now.timestamp() {
+        return false;
+    }
+    let Ok(given) = hex::decode(&signed.signature) else {
+        return false;
+    };
+    mac(secret, artifact_id, signed.expires).verify_slice(&given).is_ok()
+}
+
+fn signature(secret: &str, artifact_id: i64, expires: i64) -> String {
+    hex::encode(mac(secret, artifact_id, expires).finalize().into_bytes())
+}
+
+fn mac(secret: &str, artifact_id: i64, expires: i64) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(format!("{}:{}", artifact_id, expires).as_bytes());
+    mac
+}
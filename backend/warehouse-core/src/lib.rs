@@ -1,10 +1,40 @@
 //! Warehouse Management System - Core Business Logic
 
+pub mod atp;
+pub mod auth;
+pub mod backup;
+pub mod carrier;
+pub mod cartonization;
 pub mod config;
+pub mod documents;
+pub mod ecommerce;
+pub mod edi;
+pub mod environment;
+pub mod erp;
 pub mod error;
+pub mod gl;
+pub mod i18n;
+pub mod integrity;
+pub mod jobs;
+pub mod locks;
+pub mod notify;
+pub mod paging;
+pub mod printing;
+pub mod reconciliation;
+pub mod reports;
+pub mod replay;
+pub mod restore;
+pub mod routing;
+pub mod saga;
+pub mod scales;
+pub mod sync;
+pub mod transfers;
+pub mod vmi;
 
-pub use config::Config;
+pub use carrier::Carriers;
+pub use config::{Config, LiveConfig};
 pub use error::{AppError, AppResult};
+pub use locks::JobLocks;
 
 use warehouse_db::Database;
 
@@ -13,10 +43,22 @@ use warehouse_db::Database;
 pub struct AppState {
     pub db: Database,
     pub config: Config,
+    pub carriers: Carriers,
+    /// Current values of the reloadable settings; `config.runtime` holds the startup values
+    pub runtime: LiveConfig,
+    /// Decides which replica runs each recurring job
+    pub locks: JobLocks,
 }
 
 impl AppState {
     pub fn new(db: Database, config: Config) -> Self {
-        Self { db, config }
+        let carriers = Carriers::from_config(&config.carriers);
+        let runtime = LiveConfig::new(config.runtime.clone());
+        Self { db, config, carriers, runtime, locks: JobLocks::local() }
+    }
+
+    pub fn with_job_locks(mut self, locks: JobLocks) -> Self {
+        self.locks = locks;
+        self
     }
 }
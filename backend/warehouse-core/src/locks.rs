@@ -0,0 +1,105 @@
+//! Electing the one replica that runs each recurring job when several API replicas run
+//! side by side. A job's leader holds a lease on it in Redis and renews the lease every
+//! time it runs the job; the other replicas find the lease taken and skip their turn.
+//! A lease outlasts two of the job's intervals, so when its holder goes away another
+//! replica takes the job over within two intervals.
+//!
+//! With scheduler locks off, a single replica is assumed and runs every job itself.
+
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::Result;
+use redis::aio::ConnectionManager;
+use redis::{IntoConnectionInfo, Script};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::RedisConfig;
+
+/// The shortest lease, so that renewing one does not race its expiry on short intervals
+const MIN_LEASE: Duration = Duration::from_secs(10);
+
+/// Renews the lease when the caller already holds it, and takes it when nobody does
+const CLAIM: &str = r"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then
+    return 1
+end
+return 0
+";
+
+#[derive(Clone)]
+pub struct JobLocks {
+    redis: Option<ConnectionManager>,
+    /// Names this replica as a lease's holder
+    owner: String,
+}
+
+impl JobLocks {
+    /// Locks for a replica running alone: every job always runs
+    pub fn local() -> Self {
+        Self { redis: None, owner: Uuid::new_v4().to_string() }
+    }
+
+    /// Leases in the configured Redis, or local locks when scheduler locks are off
+    pub async fn from_config(config: &RedisConfig) -> Result<Self> {
+        if !config.scheduler_locks {
+            return Ok(Self::local());
+        }
+
+        let mut connection = config.url.as_str().into_connection_info()?;
+        if let Some(password) = &config.password {
+            connection.redis.password = Some(password.clone());
+        }
+        let redis = ConnectionManager::new(redis::Client::open(connection)?).await?;
+        let locks = Self { redis: Some(redis), ..Self::local() };
+        info!(owner = %locks.owner, "Recurring jobs run on the replica holding their lease in Redis");
+        Ok(locks)
+    }
+
+    /// Run `work` for `job`, which recurs `every` so often, when this replica leads it.
+    /// Returns None when another replica holds the lease, or Redis cannot say who does.
+    /// The lease is renewed while the work runs and kept once it is done, so this replica
+    /// goes on leading the job.
+    pub async fn run<F: Future>(&self, job: &str, every: Duration, work: F) -> Option<F::Output> {
+        let Some(redis) = &self.redis else {
+            return Some(work.await);
+        };
+        let lease = (every * 2).max(MIN_LEASE);
+        match self.claim(redis.clone(), job, lease).await {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(e) => {
+                warn!(job, "Skipping the run, its lease could not be claimed: {}", e);
+                return None;
+            }
+        }
+
+        let mut work = std::pin::pin!(work);
+        let mut renew = tokio::time::interval(lease / 3);
+        renew.tick().await;
+        loop {
+            tokio::select! {
+                output = &mut work => return Some(output),
+                _ = renew.tick() => match self.claim(redis.clone(), job, lease).await {
+                    Ok(true) => {}
+                    Ok(false) => warn!(job, "Another replica took over the job's lease mid-run"),
+                    Err(e) => warn!(job, "The job's lease could not be renewed: {}", e),
+                },
+            }
+        }
+    }
+
+    async fn claim(&self, mut redis: ConnectionManager, job: &str, lease: Duration) -> Result<bool> {
+        let claimed: i64 = Script::new(CLAIM)
+            .key(format!("warehouse:job-lease:{}", job))
+            .arg(&self.owner)
+            .arg(lease.as_millis() as u64)
+            .invoke_async(&mut redis)
+            .await?;
+        Ok(claimed == 1)
+    }
+}
@@ -0,0 +1,124 @@
+//! Raising notifications for people to act on, and sending email. Every notification is
+//! logged and kept in the notification log until someone acknowledges it.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::warn;
+use warehouse_db::Database;
+use warehouse_models::*;
+
+use crate::config::MailConfig;
+use crate::AppResult;
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub async fn raise(db: &Database, notification: NewNotification) -> AppResult<Notification> {
+    warn!(kind = notification.kind, severity = notification.severity, "{}", notification.subject);
+    Ok(db.notifications().raise(notification).await?)
+}
+
+/// An email with an optional file attached
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: Vec<String>,
+    pub subject: String,
+    /// HTML body
+    pub body: String,
+    /// File name, media type and content
+    pub attachment: Option<(String, String, Vec<u8>)>,
+}
+
+/// Hand the email to the local mail transfer agent through `sendmail -t`
+pub async fn send_email(config: &MailConfig, email: &Email) -> Result<(), String> {
+    let message = mime_message(&config.from_address, email);
+
+    let mut child = Command::new(&config.sendmail_command)
+        .args(["-t", "-i"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("cannot run {}: {}", config.sendmail_command, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&message).await.map_err(|e| e.to_string())?;
+    }
+
+    let output = tokio::time::timeout(SEND_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("{} timed out", config.sendmail_command))?
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            config.sendmail_command,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
+
+/// The email as a MIME message; the body and attachment are base64 encoded
+pub fn mime_message(from: &str, email: &Email) -> Vec<u8> {
+    let boundary = format!("=_{}", uuid::Uuid::new_v4().simple());
+    let subject = header_value(&email.subject);
+
+    let mut message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nMIME-Version: 1.0\r\n",
+        header_value(from),
+        email.to.iter().map(|to| header_value(to)).collect::<Vec<_>>().join(", "),
+        subject
+    );
+    message.push_str(&format!("Content-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n", boundary));
+    message.push_str(&format!(
+        "--{}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+        boundary,
+        base64_lines(email.body.as_bytes())
+    ));
+    if let Some((file_name, media_type, content)) = &email.attachment {
+        message.push_str(&format!(
+            "--{}\r\nContent-Type: {}; name=\"{}\"\r\nContent-Disposition: attachment; filename=\"{}\"\r\n\
+             Content-Transfer-Encoding: base64\r\n\r\n{}",
+            boundary,
+            media_type,
+            header_value(file_name),
+            header_value(file_name),
+            base64_lines(content)
+        ));
+    }
+    message.push_str(&format!("--{}--\r\n", boundary));
+    message.into_bytes()
+}
+
+/// Header text on one line, so recipients and subjects cannot add headers
+fn header_value(value: &str) -> String {
+    value.chars().map(|c| if c == '\r' || c == '\n' { ' ' } else { c }).collect()
+}
+
+/// Base64 in lines of 76 characters, each ending in CRLF
+fn base64_lines(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len() * 4 / 3 + 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let group = ((bytes[0] as u32) << 16) | ((bytes[1] as u32) << 8) | bytes[2] as u32;
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[((group >> (18 - 6 * index)) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    let mut lines = String::with_capacity(encoded.len() + encoded.len() / 38 + 2);
+    for line in encoded.as_bytes().chunks(76) {
+        lines.push_str(std::str::from_utf8(line).expect("base64 is ASCII"));
+        lines.push_str("\r\n");
+    }
+    lines
+}
@@ -0,0 +1,58 @@
+//! Strict checks of list query parameters. The store clamps what it is given, so list
+//! handlers check `page`, `limit`, `sort_by` and `sort_order` here first and answer out of
+//! range or unknown values with 400 naming what the list accepts.
+
+use warehouse_db::{ListOrder, MAX_KEYSET_PAGE_SIZE, MAX_PAGE_SIZE};
+use warehouse_models::{KeysetQuery, PaginationQuery};
+
+use crate::config::PaginationConfig;
+use crate::{AppError, AppResult};
+
+/// Parameters of a page-numbered list
+pub fn check_page(config: &PaginationConfig, list: &ListOrder, query: &PaginationQuery) -> AppResult<()> {
+    if let Some(page) = query.page.filter(|page| *page < 1) {
+        return Err(AppError::validation(format!("page must be 1 or more, not {}", page)));
+    }
+    check_limit(list.resource, query.limit, config.max_page_size(list.resource, MAX_PAGE_SIZE))?;
+
+    if let Some(sort_by) = query.sort_by.as_deref().filter(|field| !list.sort_fields.contains(field)) {
+        return Err(AppError::validation(format!(
+            "{} cannot be sorted by {}; sort_by must be one of: {}",
+            list.resource,
+            sort_by,
+            list.sort_fields.join(", ")
+        )));
+    }
+    if let Some(order) = &query.sort_order {
+        let order = order.to_ascii_uppercase();
+        if order != "ASC" && order != "DESC" {
+            return Err(AppError::validation(format!("sort_order must be ASC or DESC, not {}", order)));
+        }
+        if order != list.sort_order {
+            return Err(AppError::validation(format!(
+                "{} are listed in {} order only",
+                list.resource, list.sort_order
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Parameters of a keyset list; `resource` is its path under `/api` without ids
+pub fn check_keyset(config: &PaginationConfig, resource: &str, query: &KeysetQuery) -> AppResult<()> {
+    if let Some(after_id) = query.after_id.filter(|after_id| *after_id < 1) {
+        return Err(AppError::validation(format!("after_id must be 1 or more, not {}", after_id)));
+    }
+    check_limit(resource, query.limit, config.max_page_size(resource, MAX_KEYSET_PAGE_SIZE))
+}
+
+fn check_limit(resource: &str, limit: Option<i64>, max: i64) -> AppResult<()> {
+    match limit {
+        Some(limit) if !(1..=max).contains(&limit) => Err(AppError::validation(format!(
+            "limit must be between 1 and {} for {}, not {}",
+            max, resource, limit
+        ))),
+        _ => Ok(()),
+    }
+}
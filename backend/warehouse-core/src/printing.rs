@@ -0,0 +1,165 @@
+//! Sending labels and documents to the warehouse's printers. A job prints the template
+//! the warehouse is assigned for its purpose, with the job's `{{field}}` values filled in.
+//!
+//! ZPL printers are sent the label over a raw TCP connection, once per copy. Print agents
+//! are posted the job as JSON and print it on the station's own printer.
+
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use warehouse_db::Database;
+use warehouse_models::*;
+
+use crate::{AppError, AppResult};
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Print the request's purpose in the warehouse, on the printer it names or else the
+/// default printer at its station or in the warehouse
+pub async fn dispatch(db: &Database, warehouse_id: i32, request: PrintRequest) -> AppResult<PrintDispatch> {
+    let assignment = db
+        .templates()
+        .assignments(warehouse_id)
+        .await?
+        .into_iter()
+        .find(|assignment| assignment.purpose == request.purpose)
+        .ok_or_else(|| AppError::BusinessRule(format!("no template is assigned for {}", request.purpose)))?;
+
+    let printer = match request.printer_id {
+        Some(id) => db
+            .printers()
+            .get(id)
+            .await?
+            .filter(|printer| printer.warehouse_id == warehouse_id)
+            .ok_or_else(|| AppError::not_found("printer"))?,
+        None => db.printers().default_printer(warehouse_id, request.station_id, &request.purpose).await?.ok_or_else(
+            || AppError::BusinessRule(format!("no printer is the default for {}; name one", request.purpose)),
+        )?,
+    };
+    if !printer.is_active {
+        return Err(AppError::BusinessRule(format!("printer {} is inactive", printer.printer_code)));
+    }
+    if printer.printer_type == PRINTER_ZPL && assignment.format != TEMPLATE_ZPL {
+        return Err(AppError::BusinessRule(format!(
+            "printer {} prints ZPL only, and {} is {}",
+            printer.printer_code, assignment.template_code, assignment.format
+        )));
+    }
+
+    let template = db
+        .templates()
+        .version(assignment.template_id, assignment.version)
+        .await?
+        .ok_or_else(|| AppError::not_found("print template version"))?;
+    let body = render(&template.body, &assignment.format, &request.fields).map_err(AppError::validation)?;
+    let copies = request.copies.unwrap_or(1);
+
+    let job = AgentJob { purpose: &request.purpose, format: &assignment.format, copies, body: &body };
+    send(&printer, &job).await.map_err(|message| AppError::ExternalService {
+        service: format!("printer {}", printer.printer_code),
+        message,
+    })?;
+
+    Ok(PrintDispatch {
+        printer: db.printers().mark_printed(printer.printer_id).await?,
+        purpose: request.purpose,
+        template_id: assignment.template_id,
+        template_code: assignment.template_code,
+        version: assignment.version,
+        copies,
+    })
+}
+
+/// Fill in a template's `{{field}}` placeholders, escaping values for its format. Every
+/// placeholder needs a value, and ZPL values may not carry the `^` and `~` that start
+/// ZPL commands.
+pub fn render(body: &str, format: &str, fields: &HashMap<String, String>) -> Result<String, String> {
+    let mut rendered = String::with_capacity(body.len());
+    let mut missing = BTreeSet::new();
+    let mut rest = body;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(length) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        let name = rest[start + 2..start + 2 + length].trim();
+        match fields.get(name) {
+            Some(value) if format == TEMPLATE_ZPL => {
+                if value.contains(['^', '~']) {
+                    return Err(format!("field {} cannot contain ^ or ~ on a ZPL label", name));
+                }
+                rendered.push_str(value);
+            }
+            Some(value) => rendered.push_str(&escape_html(value)),
+            None => {
+                missing.insert(name);
+            }
+        }
+        rest = &rest[start + 2 + length + 2..];
+    }
+    rendered.push_str(rest);
+
+    if !missing.is_empty() {
+        return Err(format!("no value for {}", missing.into_iter().collect::<Vec<_>>().join(", ")));
+    }
+    Ok(rendered)
+}
+
+fn escape_html(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&#39;")
+}
+
+/// What a print agent is posted
+#[derive(Debug, Serialize)]
+struct AgentJob<'a> {
+    purpose: &'a str,
+    format: &'a str,
+    copies: i32,
+    body: &'a str,
+}
+
+async fn send(printer: &Printer, job: &AgentJob<'_>) -> Result<(), String> {
+    if printer.printer_type == PRINTER_AGENT {
+        let http = reqwest::Client::builder().timeout(SEND_TIMEOUT).build().map_err(|e| e.to_string())?;
+        let response = http
+            .post(&printer.address)
+            .json(job)
+            .send()
+            .await
+            .map_err(|e| format!("{} unreachable: {}", printer.address, e))?;
+        if !response.status().is_success() {
+            return Err(format!("{} answered {}", printer.address, response.status()));
+        }
+        return Ok(());
+    }
+
+    let (host, port) = zpl_endpoint(&printer.address);
+    let mut stream = tokio::time::timeout(SEND_TIMEOUT, TcpStream::connect((host, port)))
+        .await
+        .map_err(|_| format!("{} did not answer", printer.address))?
+        .map_err(|e| format!("{} unreachable: {}", printer.address, e))?;
+    let label = job.body.trim();
+    let write = async {
+        for _ in 0..job.copies {
+            stream.write_all(label.as_bytes()).await?;
+        }
+        stream.shutdown().await
+    };
+    tokio::time::timeout(SEND_TIMEOUT, write)
+        .await
+        .map_err(|_| format!("{} stopped taking the label", printer.address))?
+        .map_err(|e| format!("sending to {} failed: {}", printer.address, e))
+}
+
+/// Host and port of a ZPL printer's `host[:port]` address
+pub fn zpl_endpoint(address: &str) -> (&str, u16) {
+    let address = address.trim();
+    match address.rsplit_once(':').and_then(|(host, port)| Some((host, port.parse().ok()?))) {
+        Some((host, port)) => (host, port),
+        None => (address, ZPL_DEFAULT_PORT),
+    }
+}
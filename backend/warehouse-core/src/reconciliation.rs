@@ -0,0 +1,19 @@
+//! ERP stock extracts for reconciliation, posted as JSON or as CSV with an
+//! `item_code,warehouse_code,quantity` header row
+
+use warehouse_models::*;
+
+use crate::{AppError, AppResult};
+
+/// Read a CSV extract. Errors name the line, as finance teams fix extracts by hand.
+pub fn parse_csv(query: ErpExtractQuery, body: &str) -> AppResult<ErpExtract> {
+    let as_of = query.as_of.ok_or_else(|| AppError::validation("as_of is required for CSV extracts"))?;
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(body.as_bytes());
+    let lines = reader
+        .deserialize::<ErpStockLine>()
+        .enumerate()
+        .map(|(index, line)| line.map_err(|e| AppError::validation(format!("line {}: {}", index + 2, e))))
+        .collect::<AppResult<Vec<_>>>()?;
+
+    Ok(ErpExtract { as_of, source: query.source, lines })
+}
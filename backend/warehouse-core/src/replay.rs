@@ -0,0 +1,183 @@
+//! Stock event delivery and replay. Consumers are sent the stock event stream in batches,
+//! oldest first, as JSON POSTed to their endpoint with `Authorization: Bearer <api_key>`
+//! when they have a key; a consumer's position only moves once a batch is acknowledged
+//! with a 2xx, so delivery is at least once.
+//!
+//! A failed batch is retried with exponential backoff. Once it has failed
+//! `max_attempts` times it is parked in the dead-letter queue and the consumer moves on;
+//! dead letters can be inspected and re-driven once the consumer is fixed.
+//!
+//! A replay rewinds one target to an offset and brings it up to date again: the balances
+//! projection and the stock read model are rebuilt for every balance the replayed events
+//! touch, and a consumer is sent every event from the offset on.
+
+use std::time::Duration;
+
+use tracing::info;
+use warehouse_db::Database;
+use warehouse_models::chrono::{self, Utc};
+use warehouse_models::*;
+
+use crate::config::EventConfig;
+use crate::{notify, AppError, AppResult};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest wait between retries of a batch
+const MAX_RETRY_DELAY_SECS: u64 = 3600;
+
+/// Read model projection batch while draining a replay
+const PROJECT_BATCH: i64 = 500;
+
+pub async fn replay(db: &Database, config: &EventConfig, request: &ReplayRequest) -> AppResult<ReplayReport> {
+    let from_event_id = request.from_event_id;
+    let events = db.stock_events().count_from(from_event_id).await?;
+
+    let rebuilt = match request.target.as_str() {
+        REPLAY_BALANCES => db.stock_events().rebuild_from(from_event_id).await?.streams,
+        REPLAY_READ_MODEL => {
+            let queued = db.read_models().requeue_from(from_event_id).await?;
+            while db.read_models().project(PROJECT_BATCH).await? as i64 == PROJECT_BATCH {}
+            queued
+        }
+        REPLAY_CONSUMER => {
+            let id = request.consumer_id.ok_or_else(|| AppError::validation("consumer_id is required"))?;
+            let consumer = db
+                .event_consumers()
+                .rewind(id, (from_event_id - 1).max(0))
+                .await?
+                .ok_or_else(|| AppError::not_found("event consumer"))?;
+            deliver(db, config, &consumer).await? as i64
+        }
+        other => return Err(AppError::validation(format!("unknown replay target {}", other))),
+    };
+
+    info!(target = %request.target, from_event_id, events, rebuilt, "Stock events replayed");
+    Ok(ReplayReport { target: request.target.clone(), from_event_id, events, rebuilt })
+}
+
+/// Send every active consumer the events it has not acknowledged yet. Returns how many
+/// events were delivered; a consumer that fails keeps its position and the error.
+pub async fn deliver_pending(db: &Database, config: &EventConfig) -> AppResult<usize> {
+    let mut delivered = 0;
+    for consumer in db.event_consumers().behind().await? {
+        match deliver(db, config, &consumer).await {
+            Ok(sent) => delivered += sent,
+            Err(AppError::ExternalService { .. }) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(delivered)
+}
+
+/// Send one consumer everything after its position, a batch at a time. Returns how many
+/// events it acknowledged.
+pub async fn deliver(db: &Database, config: &EventConfig, consumer: &EventConsumer) -> AppResult<usize> {
+    let http = client()?;
+
+    let mut position = consumer.position;
+    let mut failed_attempts = consumer.failed_attempts;
+    let mut delivered = 0;
+    loop {
+        let events = db.stock_events().after(position, CONSUMER_BATCH_SIZE).await?;
+        let Some(last) = events.last().map(|e| e.event_id) else {
+            break;
+        };
+
+        let batch = EventBatch { consumer_name: consumer.consumer_name.clone(), events };
+        if let Err(message) = push(&http, consumer, &batch).await {
+            let attempts = failed_attempts + 1;
+            if attempts < config.max_attempts {
+                let next_attempt_at = Utc::now() + retry_delay(config, attempts);
+                db.event_consumers().record_failure(consumer.consumer_id, &message, next_attempt_at).await?;
+                return Err(AppError::ExternalService { service: consumer.consumer_name.clone(), message });
+            }
+
+            dead_letter(db, consumer, &batch.events, attempts, &message).await?;
+        } else {
+            db.event_consumers().advance(consumer.consumer_id, last).await?;
+            delivered += batch.events.len();
+        }
+        position = last;
+        failed_attempts = 0;
+    }
+
+    Ok(delivered)
+}
+
+/// Send a dead-lettered batch to its consumer again. Returns None when the dead letter
+/// does not exist.
+pub async fn redrive(db: &Database, id: i64) -> AppResult<Option<EventDeadLetter>> {
+    let Some(dead_letter) = db.event_consumers().get_dead_letter(id).await? else {
+        return Ok(None);
+    };
+    if dead_letter.status == DEAD_LETTER_REDRIVEN {
+        return Err(AppError::BusinessRule(format!("dead letter {} was already re-driven", id)));
+    }
+    let consumer = db
+        .event_consumers()
+        .get_by_id(dead_letter.consumer_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("event consumer"))?;
+
+    let events = db.stock_events().range(dead_letter.first_event_id, dead_letter.last_event_id).await?;
+    let batch = EventBatch { consumer_name: consumer.consumer_name.clone(), events };
+    match push(&client()?, &consumer, &batch).await {
+        Ok(()) => Ok(Some(db.event_consumers().record_redrive(id, None).await?)),
+        Err(message) => {
+            db.event_consumers().record_redrive(id, Some(&message)).await?;
+            Err(AppError::ExternalService { service: consumer.consumer_name, message })
+        }
+    }
+}
+
+/// Wait before retrying a batch that has failed `attempts` times
+pub fn retry_delay(config: &EventConfig, attempts: i32) -> chrono::Duration {
+    let doublings = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    let secs = config.retry_base_secs.saturating_mul(1 << doublings).min(MAX_RETRY_DELAY_SECS);
+    chrono::Duration::seconds(secs as i64)
+}
+
+async fn dead_letter(
+    db: &Database,
+    consumer: &EventConsumer,
+    events: &[StockEvent],
+    attempts: i32,
+    error: &str,
+) -> AppResult<()> {
+    let dead_letter = db.event_consumers().dead_letter(consumer, events, attempts, error).await?;
+
+    notify::raise(
+        db,
+        NewNotification {
+            kind: NOTIFY_EVENTS_DEAD_LETTERED,
+            severity: SEVERITY_WARNING,
+            subject: format!("{} stock events to {} were dead-lettered", dead_letter.event_count, consumer.consumer_name),
+            message: Some(error.to_string()),
+            reference_type: Some("EVENT_CONSUMER".to_string()),
+            reference_id: Some(consumer.consumer_id),
+        },
+    )
+    .await?;
+
+    Ok(())
+}
+
+fn client() -> AppResult<reqwest::Client> {
+    reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().map_err(|e| AppError::Internal(e.into()))
+}
+
+async fn push(http: &reqwest::Client, consumer: &EventConsumer, batch: &EventBatch) -> Result<(), String> {
+    let mut request = http.post(&consumer.endpoint_url).json(batch);
+    if let Some(key) = &consumer.api_key {
+        request = request.bearer_auth(key);
+    }
+
+    let response = request.send().await.map_err(|e| format!("{} unreachable: {}", consumer.endpoint_url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} answered {}", consumer.endpoint_url, response.status()));
+    }
+
+    Ok(())
+}
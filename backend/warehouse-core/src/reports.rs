@@ -0,0 +1,176 @@
+//! Scheduled delivery of custom reports. Each due schedule runs its report and emails the
+//! result to its recipients, as a CSV attachment or an HTML table; a failed delivery is
+//! recorded with the schedule and raises a notification. Reports can also be exported on
+//! demand as a background job, leaving the CSV on the job for download.
+
+use tracing::info;
+use warehouse_db::Database;
+use warehouse_models::chrono::Utc;
+use warehouse_models::*;
+
+use crate::config::MailConfig;
+use crate::i18n::{self, Locale};
+use crate::jobs::{self, Progress};
+use crate::notify::{self, Email};
+use crate::{AppError, AppResult};
+
+/// Deliver every schedule that is due. Returns how many were sent.
+pub async fn run_due(db: &Database, config: &MailConfig) -> AppResult<usize> {
+    let mut sent = 0;
+    for schedule in db.reports().claim_due_schedules(Utc::now()).await? {
+        if deliver(db, config, &schedule).await?.status == REPORT_DELIVERY_SENT {
+            sent += 1;
+        }
+    }
+
+    Ok(sent)
+}
+
+/// Run the schedule's report and email it now, recording the delivery
+pub async fn deliver(db: &Database, config: &MailConfig, schedule: &ReportSchedule) -> AppResult<ReportScheduleRun> {
+    let started_at = Utc::now();
+    let report = db
+        .reports()
+        .get_by_id(schedule.report_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("report"))?;
+
+    let delivered = match run(db, &report, i18n::current()).await {
+        Ok(result) => send(config, schedule, &report.definition, &result).await.map(|()| result.rows.len()),
+        Err(error) => Err(format!("report failed to run: {}", error)),
+    };
+
+    let run = db.reports().record_delivery(schedule.schedule_id, started_at, delivered).await?;
+    match &run.error {
+        None => info!(report = %report.definition.report_code, rows = run.row_count, "Report delivered"),
+        Some(error) => {
+            notify::raise(
+                db,
+                NewNotification {
+                    kind: NOTIFY_REPORT_DELIVERY_FAILED,
+                    severity: SEVERITY_WARNING,
+                    subject: format!("Delivery of report {} failed", report.definition.report_code),
+                    message: Some(error.clone()),
+                    reference_type: Some("REPORT_SCHEDULE".to_string()),
+                    reference_id: Some(schedule.schedule_id),
+                },
+            )
+            .await?;
+        }
+    }
+
+    Ok(run)
+}
+
+/// Run the report, headed in `locale`
+pub async fn run(db: &Database, report: &ReportDefinitionDetail, locale: Locale) -> anyhow::Result<ReportResult> {
+    let mut result = db.reports().run(report).await?;
+    result.headers = i18n::report_headers(locale, &result.columns);
+    Ok(result)
+}
+
+/// Run the report as a job; its rows are kept on the job as a CSV file
+pub async fn export(db: &Database, report: ReportDefinitionDetail) -> AppResult<Job> {
+    let job = NewJob {
+        job_type: JOB_REPORT,
+        description: format!("Export report {}", report.definition.report_code),
+        reference_type: Some("REPORT".to_string()),
+        reference_id: Some(report.definition.report_id as i64),
+    };
+    let (worker, locale) = (db.clone(), i18n::current());
+    jobs::spawn(db, job, move |progress| async move { export_csv(&worker, &report, locale, &progress).await }).await
+}
+
+/// The work of an export job
+pub async fn export_csv(
+    db: &Database,
+    report: &ReportDefinitionDetail,
+    locale: Locale,
+    progress: &Progress,
+) -> AppResult<()> {
+    let result = run(db, report, locale).await?;
+    let rows = result.rows.len() as i64;
+    progress.advance(rows, Some(rows)).await?;
+    if result.truncated {
+        progress.error(&format!("only the first {} rows were exported", REPORT_MAX_ROWS)).await?;
+    }
+
+    progress.artifact(&csv_file_name(&report.definition), "text/csv", &render_csv(&result)?).await?;
+    Ok(())
+}
+
+async fn send(
+    config: &MailConfig,
+    schedule: &ReportSchedule,
+    report: &ReportDefinition,
+    result: &ReportResult,
+) -> Result<(), String> {
+    let email = email(schedule, report, result).map_err(|e| e.to_string())?;
+    notify::send_email(config, &email).await
+}
+
+/// The message for one delivery: a short note with the CSV attached, or the rows as a table
+pub fn email(schedule: &ReportSchedule, report: &ReportDefinition, result: &ReportResult) -> AppResult<Email> {
+    let mut note = format!("<p>{} rows", result.rows.len());
+    if result.truncated {
+        note.push_str(&format!(", cut off at {}", REPORT_MAX_ROWS));
+    }
+    note.push_str("</p>");
+
+    let (body, attachment) = if schedule.format == REPORT_FORMAT_HTML {
+        (format!("<h2>{}</h2>{}{}", escape(&report.report_name), note, render_html(result)), None)
+    } else {
+        let attachment = (csv_file_name(report), "text/csv".to_string(), render_csv(result)?);
+        (format!("<h2>{}</h2>{}", escape(&report.report_name), note), Some(attachment))
+    };
+
+    Ok(Email { to: schedule.recipients.clone(), subject: report.report_name.clone(), body, attachment })
+}
+
+/// `<report code>-<date>.csv`
+fn csv_file_name(report: &ReportDefinition) -> String {
+    format!("{}-{}.csv", report.report_code.to_lowercase(), Utc::now().format("%Y%m%d"))
+}
+
+/// The rows under a header of the column names
+pub fn render_csv(result: &ReportResult) -> AppResult<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let csv_error = |e: csv::Error| AppError::Internal(e.into());
+
+    writer.write_record(headings(result)).map_err(csv_error)?;
+    for row in &result.rows {
+        writer.write_record(row.iter().map(|value| value.as_deref().unwrap_or(""))).map_err(csv_error)?;
+    }
+
+    writer.into_inner().map_err(|e| AppError::Internal(anyhow::anyhow!("report CSV: {}", e)))
+}
+
+pub fn render_html(result: &ReportResult) -> String {
+    let mut html = String::from("<table border=\"1\" cellpadding=\"4\" cellspacing=\"0\"><tr>");
+    for heading in headings(result) {
+        html.push_str(&format!("<th>{}</th>", escape(heading)));
+    }
+    html.push_str("</tr>");
+    for row in &result.rows {
+        html.push_str("<tr>");
+        for value in row {
+            html.push_str(&format!("<td>{}</td>", escape(value.as_deref().unwrap_or(""))));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// The display headings, or the column names when there are none
+fn headings(result: &ReportResult) -> &[String] {
+    if result.headers.is_empty() {
+        &result.columns
+    } else {
+        &result.headers
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,209 @@
+//! Restore of logical backups written by `backup`. An archive is restored into an empty
+//! environment, every exported schema into the schema of the same name, or one exported
+//! schema into a newly provisioned tenant.
+//!
+//! The whole restore is one transaction: tables load parents first, and a table whose rows
+//! reference data that is neither in the archive nor already present fails the restore with
+//! the offending foreign keys counted in the report. A tenant provisioned for a restore
+//! that fails is kept, empty.
+
+use anyhow::{anyhow, bail, Context, Result};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use tracing::{info, warn};
+use warehouse_db::{Database, RestoreSession};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::{AppError, AppResult};
+
+/// Rows staged per statement
+const STAGE_BATCH: usize = 1000;
+
+pub enum RestoreTarget {
+    /// Every schema in the archive into the schema of the same name, which must exist
+    Environment,
+    /// One schema of the archive into a tenant provisioned for the restore
+    Tenant { source_schema: String, tenant: CreateTenant },
+}
+
+pub struct RestoreOptions {
+    pub target: RestoreTarget,
+    /// Empty the target tables first instead of requiring them to be empty
+    pub replace: bool,
+}
+
+/// One row line of an archive
+#[derive(serde::Deserialize)]
+struct ArchiveRow {
+    schema: String,
+    table: String,
+    row: serde_json::Value,
+}
+
+pub async fn restore(
+    db: &Database,
+    archive: impl AsyncBufRead + Unpin,
+    options: RestoreOptions,
+) -> AppResult<RestoreReport> {
+    let mut lines = archive.lines();
+    let manifest = read_manifest(lines.next_line().await.map_err(anyhow::Error::from)?)?;
+
+    let current_version = db.migrations().status().await?.current_version;
+    if manifest.schema_version > current_version {
+        return Err(AppError::BusinessRule(format!(
+            "archive was taken at schema version {:?}, this database is at {:?}; migrate it first",
+            manifest.schema_version, current_version
+        )));
+    }
+
+    // Archived schema -> schema restored into
+    let schemas: Vec<(String, String)> = match &options.target {
+        RestoreTarget::Environment => {
+            let mut schemas: Vec<String> = manifest.tables.iter().map(|table| table.schema.clone()).collect();
+            schemas.dedup();
+            schemas.into_iter().map(|schema| (schema.clone(), schema)).collect()
+        }
+        RestoreTarget::Tenant { source_schema, tenant } => {
+            tenant.validate().map_err(AppError::validation)?;
+            if !manifest.tables.iter().any(|table| &table.schema == source_schema) {
+                return Err(AppError::validation(format!("archive has no schema {}", source_schema)));
+            }
+            let provisioned = db
+                .tenants()
+                .provision(tenant.clone())
+                .await?
+                .ok_or_else(|| AppError::already_exists("tenant code"))?;
+            vec![(source_schema.clone(), provisioned.tenant.schema_name)]
+        }
+    };
+
+    let mut session = db.backups().begin_restore().await?;
+    for (_, schema) in &schemas {
+        session.prepare(schema, options.replace).await?;
+    }
+
+    let mut tables: Vec<RestoredTable> = manifest
+        .tables
+        .iter()
+        .filter_map(|table| {
+            let (_, schema) = schemas.iter().find(|(source, _)| source == &table.schema)?;
+            Some(RestoredTable {
+                source_schema: table.schema.clone(),
+                schema: schema.clone(),
+                table: table.table.clone(),
+                rows_expected: table.rows,
+                rows_read: 0,
+                rows_restored: 0,
+                violations: Vec::new(),
+            })
+        })
+        .collect();
+
+    let loaded = load(&mut session, &mut lines, &mut tables).await;
+    let committed = match loaded {
+        Ok(true) => {
+            session.commit().await?;
+            true
+        }
+        // Dropping the session rolls everything back
+        Ok(false) => false,
+        Err(e) => return Err(e.into()),
+    };
+
+    let report =
+        RestoreReport { backup_id: manifest.backup_id, format_version: manifest.format_version, tables, committed };
+    if committed {
+        info!(backup_id = report.backup_id, rows = report.rows_restored(), "Backup restored");
+    } else {
+        warn!(backup_id = report.backup_id, "Backup not restored: rows reference missing data");
+    }
+    Ok(report)
+}
+
+fn read_manifest(line: Option<String>) -> AppResult<BackupManifest> {
+    let line = line.ok_or_else(|| AppError::validation("archive is empty"))?;
+    let manifest: BackupManifest =
+        serde_json::from_str(&line).map_err(|e| AppError::validation(format!("archive has no manifest: {}", e)))?;
+
+    if manifest.format != BACKUP_FORMAT {
+        return Err(AppError::validation(format!("{} is not a warehouse backup", manifest.format)));
+    }
+    if manifest.format_version > BACKUP_FORMAT_VERSION {
+        return Err(AppError::validation(format!(
+            "archive format version {} is newer than {}",
+            manifest.format_version, BACKUP_FORMAT_VERSION
+        )));
+    }
+    Ok(manifest)
+}
+
+/// Stage and load every table of the archive in order. `false` if a table broke its
+/// foreign keys; loading stops there.
+async fn load<R: AsyncBufRead + Unpin>(
+    session: &mut RestoreSession,
+    lines: &mut tokio::io::Lines<R>,
+    tables: &mut [RestoredTable],
+) -> Result<bool> {
+    let mut current: Option<usize> = None;
+    let mut batch = Vec::with_capacity(STAGE_BATCH);
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row: ArchiveRow = serde_json::from_str(&line).context("malformed archive row")?;
+        let Some(index) =
+            tables.iter().position(|table| table.source_schema == row.schema && table.table == row.table)
+        else {
+            // A schema not being restored
+            continue;
+        };
+
+        if current != Some(index) {
+            if current.is_some_and(|previous| previous > index) {
+                bail!("archive rows of {}.{} are out of order", row.schema, row.table);
+            }
+            if let Some(previous) = current {
+                if !finish(session, &mut tables[previous], &mut batch).await? {
+                    return Ok(false);
+                }
+            }
+            session.stage_table(&tables[index].schema, &tables[index].table).await?;
+            current = Some(index);
+        }
+
+        batch.push(row.row.to_string());
+        tables[index].rows_read += 1;
+        if batch.len() == STAGE_BATCH {
+            session.stage(&batch).await?;
+            batch.clear();
+        }
+    }
+
+    if let Some(previous) = current {
+        if !finish(session, &mut tables[previous], &mut batch).await? {
+            return Ok(false);
+        }
+    }
+
+    if let Some(short) = tables.iter().find(|table| table.rows_read != table.rows_expected) {
+        return Err(anyhow!(
+            "archive is truncated: {}.{} has {} of {} rows",
+            short.source_schema,
+            short.table,
+            short.rows_read,
+            short.rows_expected
+        ));
+    }
+    Ok(true)
+}
+
+async fn finish(session: &mut RestoreSession, table: &mut RestoredTable, batch: &mut Vec<String>) -> Result<bool> {
+    session.stage(batch).await?;
+    batch.clear();
+
+    let (restored, violations) = session.load(&table.schema, &table.table).await?;
+    table.rows_restored = restored;
+    table.violations = violations;
+    Ok(table.violations.is_empty())
+}
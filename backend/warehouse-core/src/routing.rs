@@ -0,0 +1,130 @@
+//! Route planning for multi-drop deliveries: shipments waiting for a run are shared out
+//! over the vehicles free that day, largest vehicle first. Each route starts at the depot
+//! and drives to the nearest site not yet visited whose shipment still fits in the vehicle
+//! (nearest neighbour), until nothing left fits; the next vehicle takes what remains.
+//!
+//! Distances are great-circle distances between the coordinates, not road distances.
+
+use warehouse_models::chrono::NaiveDate;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+struct Stop<'a> {
+    shipment: &'a RoutableShipment,
+    location: GeoPoint,
+}
+
+/// Straight-line distance between two points over the earth's surface (haversine)
+pub fn distance_km(from: GeoPoint, to: GeoPoint) -> f64 {
+    let (lat1, lat2) = (from.latitude.to_radians(), to.latitude.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (to.longitude - from.longitude).to_radians();
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// Suggest routes for `shipments` dropped off at `sites`. Shipments without a site, or
+/// heavier than any vehicle carries, are left unrouted, as are those no vehicle has room
+/// left for.
+pub fn plan(
+    warehouse_id: i32,
+    run_date: NaiveDate,
+    depot: GeoPoint,
+    shipments: &[RoutableShipment],
+    sites: &[RouteSite],
+    vehicles: &[DeliveryVehicle],
+) -> RoutePlan {
+    let mut vehicles: Vec<&DeliveryVehicle> = vehicles.iter().collect();
+    vehicles.sort_by(|a, b| b.capacity_kg.cmp(&a.capacity_kg).then_with(|| a.vehicle_code.cmp(&b.vehicle_code)));
+    let largest = vehicles.first().map(|vehicle| vehicle.capacity_kg);
+
+    let mut unrouted = Vec::new();
+    let mut pending = Vec::new();
+    for shipment in shipments {
+        let site = sites.iter().find(|site| site.shipment_id == shipment.shipment_id);
+        match (site, largest) {
+            (None, _) => unrouted.push(unrouted_shipment(shipment, "no site coordinates")),
+            (Some(_), None) => unrouted.push(unrouted_shipment(shipment, "no vehicle is free on the day")),
+            (Some(_), Some(capacity)) if shipment.weight_kg > capacity => {
+                unrouted.push(unrouted_shipment(shipment, "heavier than any free vehicle carries"))
+            }
+            (Some(site), Some(_)) => pending.push(Stop { shipment, location: site.location }),
+        }
+    }
+
+    let mut routes = Vec::new();
+    for vehicle in vehicles {
+        if pending.is_empty() {
+            break;
+        }
+        if let Some(route) = route(vehicle, depot, &mut pending) {
+            routes.push(route);
+        }
+    }
+    for stop in pending {
+        unrouted.push(unrouted_shipment(stop.shipment, "no free vehicle has room left"));
+    }
+
+    let distance_km = round_km(routes.iter().map(|route| route.distance_km).sum());
+    RoutePlan { warehouse_id, run_date, routes, unrouted, distance_km }
+}
+
+/// Take the nearest stops that still fit in the vehicle off `pending`, in driving order
+fn route(vehicle: &DeliveryVehicle, depot: GeoPoint, pending: &mut Vec<Stop>) -> Option<SuggestedRoute> {
+    let mut here = depot;
+    let mut load_kg = Decimal::ZERO;
+    let mut distance = 0.0;
+    let mut stops: Vec<SuggestedStop> = Vec::new();
+
+    loop {
+        let nearest = pending
+            .iter()
+            .enumerate()
+            .filter(|(_, stop)| load_kg + stop.shipment.weight_kg <= vehicle.capacity_kg)
+            .map(|(index, stop)| (index, distance_km(here, stop.location)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+        let Some((index, leg)) = nearest else {
+            break;
+        };
+
+        let stop = pending.remove(index);
+        load_kg += stop.shipment.weight_kg;
+        distance += leg;
+        here = stop.location;
+        stops.push(SuggestedStop {
+            sequence: stops.len() as i32 + 1,
+            shipment_id: stop.shipment.shipment_id,
+            shipment_number: stop.shipment.shipment_number.clone(),
+            location: stop.location,
+            weight_kg: stop.shipment.weight_kg,
+            leg_km: round_km(leg),
+        });
+    }
+    if stops.is_empty() {
+        return None;
+    }
+
+    Some(SuggestedRoute {
+        vehicle_id: vehicle.vehicle_id,
+        vehicle_code: vehicle.vehicle_code.clone(),
+        capacity_kg: vehicle.capacity_kg,
+        load_kg,
+        distance_km: round_km(distance + distance_km(here, depot)),
+        stops,
+    })
+}
+
+fn unrouted_shipment(shipment: &RoutableShipment, reason: &str) -> UnroutedShipment {
+    UnroutedShipment {
+        shipment_id: shipment.shipment_id,
+        shipment_number: shipment.shipment_number.clone(),
+        reason: reason.to_string(),
+    }
+}
+
+/// To 100 m
+fn round_km(km: f64) -> f64 {
+    (km * 10.0).round() / 10.0
+}
@@ -0,0 +1,137 @@
+//! Sagas: operations spanning several commits that cannot share one database transaction,
+//! e.g. a stock transfer touching two warehouses and a carrier. Each step commits on its
+//! own and knows how to undo itself. Steps are logged as they complete, possibly over
+//! several requests; when one fails, the steps completed so far are compensated in reverse
+//! order, so the operation is never left half done.
+
+use async_trait::async_trait;
+use tracing::warn;
+use warehouse_db::Database;
+use warehouse_models::*;
+
+use crate::{AppError, AppResult};
+
+#[async_trait]
+pub trait Step<C: Sync>: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn execute(&self, context: &C) -> AppResult<()>;
+    /// Undo a completed `execute`
+    async fn compensate(&self, context: &C) -> AppResult<()>;
+}
+
+/// How a failed step left the saga
+#[derive(Debug)]
+pub enum Failure {
+    /// Nothing had been done yet, so there was nothing to undo
+    Aborted(AppError),
+    /// The steps completed before `step` were undone
+    RolledBack { step: &'static str, error: AppError },
+    /// Undoing the completed steps failed as well; [`Saga::compensate`] retries
+    Stuck { step: &'static str, error: AppError },
+}
+
+pub struct Saga<'a, C> {
+    db: &'a Database,
+    saga_type: &'static str,
+    saga_id: i32,
+    /// Every step of the saga, in order
+    steps: Vec<Box<dyn Step<C> + 'a>>,
+}
+
+impl<'a, C: Sync> Saga<'a, C> {
+    pub fn new(db: &'a Database, saga_type: &'static str, saga_id: i32, steps: Vec<Box<dyn Step<C> + 'a>>) -> Self {
+        Self { db, saga_type, saga_id, steps }
+    }
+
+    /// Run the named steps in order. Steps the log shows completed are skipped, so a retry
+    /// picks up where the last attempt stopped.
+    pub async fn run(&self, context: &C, names: &[&str]) -> Result<(), Failure> {
+        let log = self.db.sagas().log(self.saga_type, self.saga_id).await.map_err(|e| Failure::Aborted(e.into()))?;
+        let mut anything_done = self.steps.iter().any(|step| to_compensate(&log, step.name()));
+
+        for name in names {
+            let step = self.step(name);
+            if latest_status(&log, step.name()) == Some(SAGA_STEP_COMPLETED) {
+                continue;
+            }
+
+            let error = match step.execute(context).await {
+                Ok(()) => {
+                    anything_done = true;
+                    self.record(step.name(), SAGA_STEP_COMPLETED, None)
+                        .await
+                        .map_err(|error| Failure::Stuck { step: step.name(), error })?;
+                    continue;
+                }
+                Err(error) => error,
+            };
+
+            warn!(saga = self.saga_type, id = self.saga_id, step = step.name(), "Saga step failed: {}", error);
+            if let Err(log_error) = self.record(step.name(), SAGA_STEP_FAILED, Some(error.to_string())).await {
+                warn!("Could not log the failed step: {}", log_error);
+            }
+            if !anything_done {
+                return Err(Failure::Aborted(error));
+            }
+            return match self.compensate(context).await {
+                Ok(()) => Err(Failure::RolledBack { step: step.name(), error }),
+                Err(_) => Err(Failure::Stuck { step: step.name(), error }),
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Undo every completed step, last first. A step whose compensation fails is left for
+    /// the next attempt, but the steps before it are still compensated; the first failure is
+    /// returned.
+    pub async fn compensate(&self, context: &C) -> AppResult<()> {
+        let log = self.db.sagas().log(self.saga_type, self.saga_id).await?;
+
+        let mut first_error = None;
+        for step in self.steps.iter().rev() {
+            if !to_compensate(&log, step.name()) {
+                continue;
+            }
+
+            match step.compensate(context).await {
+                Ok(()) => self.record(step.name(), SAGA_STEP_COMPENSATED, None).await?,
+                Err(error) => {
+                    warn!(
+                        saga = self.saga_type,
+                        id = self.saga_id,
+                        step = step.name(),
+                        "Compensation failed: {}",
+                        error
+                    );
+                    self.record(step.name(), SAGA_STEP_COMPENSATION_FAILED, Some(error.to_string())).await?;
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+
+        first_error.map_or(Ok(()), Err)
+    }
+
+    fn step(&self, name: &str) -> &dyn Step<C> {
+        self.steps
+            .iter()
+            .find(|step| step.name() == name)
+            .map(|step| step.as_ref())
+            .unwrap_or_else(|| panic!("{} saga has no step {}", self.saga_type, name))
+    }
+
+    async fn record(&self, step: &str, status: &str, detail: Option<String>) -> AppResult<()> {
+        self.db.sagas().record(self.saga_type, self.saga_id, step, status, detail).await?;
+        Ok(())
+    }
+}
+
+fn latest_status<'l>(log: &'l [SagaStep], step: &str) -> Option<&'l str> {
+    log.iter().rev().find(|entry| entry.step == step).map(|entry| entry.status.as_str())
+}
+
+/// Whether a step is done and not yet undone
+fn to_compensate(log: &[SagaStep], step: &str) -> bool {
+    matches!(latest_status(log, step), Some(SAGA_STEP_COMPLETED | SAGA_STEP_COMPENSATION_FAILED))
+}
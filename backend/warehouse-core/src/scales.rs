@@ -0,0 +1,117 @@
+//! Scale and dimensioner adapter: device output is normalised to kg and cm before a
+//! capture is stored against the carton or item a station measured.
+//!
+//! Scales are read in one of the frame formats most bench and floor scales can emit:
+//! - MT-SICS weight responses: `S S     12.345 kg`, where `S D` is a dynamic (unsettled)
+//!   reading and `S +` / `S -` are overload and underload
+//! - A&D style streams: `ST,GS,+0012.345  kg`, header `ST` stable, `US` unstable, `OL` overload
+//! - A bare value and unit: `12.345 kg`
+
+use warehouse_db::Database;
+use warehouse_models::chrono::Utc;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+use crate::{AppError, AppResult};
+
+/// What a scale frame says about the load on the platform
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScaleFrame {
+    /// A settled reading, in kg
+    Stable(Decimal),
+    Unstable,
+    Overload,
+    Underload,
+}
+
+/// Parse one scale output frame
+pub fn parse_frame(frame: &str) -> Result<ScaleFrame, String> {
+    let frame = frame.trim();
+    let unreadable = || format!("unrecognised scale output '{}'", frame);
+
+    let reading = if let Some(rest) = frame.strip_prefix("S ") {
+        let rest = rest.trim_start();
+        match rest.chars().next() {
+            Some('S') => &rest[1..],
+            Some('D') => return Ok(ScaleFrame::Unstable),
+            Some('+') => return Ok(ScaleFrame::Overload),
+            Some('-') => return Ok(ScaleFrame::Underload),
+            Some('I') => return Err("scale busy, command not executed".to_string()),
+            _ => return Err(unreadable()),
+        }
+    } else if frame.len() > 6 && frame.as_bytes()[2] == b',' && frame.as_bytes()[5] == b',' {
+        match (&frame[..2], &frame[3..5]) {
+            ("ST", "GS" | "NT") => &frame[6..],
+            ("US", _) => return Ok(ScaleFrame::Unstable),
+            ("OL", _) => return Ok(ScaleFrame::Overload),
+            _ => return Err(unreadable()),
+        }
+    } else {
+        frame
+    };
+
+    let reading = reading.trim();
+    let split = reading.find(|c: char| c.is_ascii_alphabetic()).ok_or_else(unreadable)?;
+    let value: Decimal = reading[..split].trim().trim_start_matches('+').parse().map_err(|_| unreadable())?;
+    let unit = reading[split..].trim().to_ascii_lowercase();
+    if !WEIGHT_UNITS.contains(&unit.as_str()) {
+        return Err(format!("unsupported weight unit '{}'", unit));
+    }
+
+    Ok(ScaleFrame::Stable(weight_kg(value, &unit)))
+}
+
+/// Convert a weight to kg, to the gram
+pub fn weight_kg(value: Decimal, unit: &str) -> Decimal {
+    let factor = match unit {
+        "g" => Decimal::new(1, 3),
+        "lb" => Decimal::new(45359237, 8),
+        "oz" => Decimal::new(28349523125, 12),
+        _ => Decimal::ONE,
+    };
+    (value * factor).round_dp(3)
+}
+
+/// Convert a length to cm, to the tenth of a millimetre
+pub fn length_cm(value: Decimal, unit: &str) -> Decimal {
+    let factor = match unit {
+        "mm" => Decimal::new(1, 1),
+        "m" => Decimal::ONE_HUNDRED,
+        "in" => Decimal::new(254, 2),
+        _ => Decimal::ONE,
+    };
+    (value * factor).round_dp(2)
+}
+
+/// Normalise a validated capture. Scales that are not settled or out of range are
+/// refused so a bad reading never overwrites a good one.
+pub fn normalise(capture: CaptureMeasurement) -> AppResult<NewCapture> {
+    let weight = match (&capture.scale_output, capture.weight) {
+        (Some(frame), _) => match parse_frame(frame).map_err(AppError::validation)? {
+            ScaleFrame::Stable(weight) if weight > Decimal::ZERO => Some(weight),
+            ScaleFrame::Stable(_) => return Err(AppError::BusinessRule("scale reads no load".to_string())),
+            ScaleFrame::Unstable => return Err(AppError::BusinessRule("scale reading is not stable".to_string())),
+            ScaleFrame::Overload => return Err(AppError::BusinessRule("scale is overloaded".to_string())),
+            ScaleFrame::Underload => return Err(AppError::BusinessRule("scale is underloaded".to_string())),
+        },
+        (None, weight) => weight.map(|weight| weight_kg(weight, capture.weight_unit.as_deref().unwrap_or("kg"))),
+    };
+    let unit = capture.dimension_unit.as_deref().unwrap_or("cm");
+
+    Ok(NewCapture {
+        carton_id: capture.carton_id,
+        item_id: capture.item_id,
+        weight_kg: weight,
+        length_cm: capture.length.map(|value| length_cm(value, unit)),
+        width_cm: capture.width.map(|value| length_cm(value, unit)),
+        height_cm: capture.height.map(|value| length_cm(value, unit)),
+        scale_output: capture.scale_output.map(|frame| frame.trim().to_string()),
+        captured_at: capture.captured_at.unwrap_or_else(Utc::now),
+    })
+}
+
+/// Record what a station's devices measured; `None` when the station does not exist
+pub async fn capture(db: &Database, station_id: i32, capture: CaptureMeasurement) -> AppResult<Option<StationCapture>> {
+    let capture = normalise(capture)?;
+    Ok(db.stations().record(station_id, capture).await?)
+}
@@ -0,0 +1,82 @@
+//! Applying mutation batches that handheld devices queued while out of coverage
+
+use std::collections::HashMap;
+
+use warehouse_db::{rejection, Database};
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+use crate::AppResult;
+
+/// Apply a device's mutations in order. Each one is checked against the task version
+/// the device last saw; a task the device itself moved earlier in the batch does not
+/// count as changed. Outcomes are recorded so a resubmitted batch replays them instead
+/// of applying anything twice.
+pub async fn apply(db: &Database, batch: SyncBatch) -> AppResult<Vec<MutationResult>> {
+    let sync = db.sync();
+    let tasks = db.tasks();
+    // Version each task was left at by this batch's own mutations
+    let mut applied_versions: HashMap<i32, i32> = HashMap::new();
+    let mut results = Vec::with_capacity(batch.mutations.len());
+
+    for mutation in batch.mutations {
+        if let Some(recorded) = sync.recorded(&batch.device_id, &mutation.mutation_id).await? {
+            results.push(MutationResult {
+                mutation_id: mutation.mutation_id,
+                status: MutationStatus::parse(&recorded.status).unwrap_or(MutationStatus::Rejected),
+                message: recorded.message,
+                replayed: true,
+                task: tasks.get_by_id(recorded.task_id).await?,
+            });
+            continue;
+        }
+
+        let (status, message, task) = apply_one(db, &mutation, batch.policy, &mut applied_versions).await?;
+        sync.record(&batch.device_id, &mutation.mutation_id, mutation.task_id, status, message.as_deref()).await?;
+        results.push(MutationResult { mutation_id: mutation.mutation_id, status, message, replayed: false, task });
+    }
+
+    Ok(results)
+}
+
+async fn apply_one(
+    db: &Database,
+    mutation: &SyncMutation,
+    default_policy: ConflictPolicy,
+    applied_versions: &mut HashMap<i32, i32>,
+) -> AppResult<(MutationStatus, Option<String>, Option<Task>)> {
+    let tasks = db.tasks();
+    let Some(current) = tasks.get_by_id(mutation.task_id).await? else {
+        return Ok((MutationStatus::Rejected, Some(format!("task {} not found", mutation.task_id)), None));
+    };
+
+    let unchanged =
+        current.version == mutation.base_version || applied_versions.get(&current.task_id) == Some(&current.version);
+    if !unchanged && mutation.policy.unwrap_or(default_policy) == ConflictPolicy::ServerWins {
+        let message =
+            format!("task {} is at version {}, device saw {}", current.task_id, current.version, mutation.base_version);
+        return Ok((MutationStatus::Conflict, Some(message), Some(current)));
+    }
+
+    let outcome = match &mutation.action {
+        TaskAction::Claim(claim) => tasks.claim(current.task_id, claim.clone()).await,
+        TaskAction::Complete(completion) => {
+            if let Err(errors) = completion.validate() {
+                return Ok((MutationStatus::Rejected, Some(errors.to_string()), Some(current)));
+            }
+            tasks.complete(current.task_id, completion.clone()).await
+        }
+    };
+
+    match outcome {
+        Ok(Some(task)) => {
+            applied_versions.insert(task.task_id, task.version);
+            Ok((MutationStatus::Applied, None, Some(task)))
+        }
+        Ok(None) => Ok((MutationStatus::Rejected, Some(format!("task {} not found", mutation.task_id)), None)),
+        Err(error) => match rejection(&error) {
+            Some(message) => Ok((MutationStatus::Rejected, Some(message), Some(current))),
+            None => Err(error.into()),
+        },
+    }
+}
@@ -0,0 +1,208 @@
+//! Stock transfers between warehouses, run as a saga: issue the goods from the source into
+//! transit, buy a carrier label if the transfer names a service, and book the goods into
+//! the destination on arrival. When a step fails after the goods left the source, the
+//! label is voided and the goods are put back at the source instead of staying stranded
+//! in transit.
+
+use async_trait::async_trait;
+use tracing::{error, warn};
+use warehouse_db::Database;
+use warehouse_models::*;
+
+use crate::saga::{Failure, Saga, Step};
+use crate::{AppError, AppResult, Carriers};
+
+struct Context<'a> {
+    db: &'a Database,
+    carriers: &'a Carriers,
+    transfer_id: i32,
+}
+
+/// Dispatch a planned transfer. Returns None when the transfer does not exist.
+pub async fn dispatch(db: &Database, carriers: &Carriers, id: i32) -> AppResult<Option<TransferDetail>> {
+    let Some(detail) = db.transfers().get_by_id(id).await? else {
+        return Ok(None);
+    };
+    ensure_status(&detail.transfer, TRANSFER_PLANNED, "dispatch")?;
+
+    run(db, carriers, &detail.transfer, &[TRANSFER_STEP_ISSUE, TRANSFER_STEP_LABEL]).await
+}
+
+/// Receive an in-transit transfer at its destination. Returns None when the transfer does
+/// not exist.
+pub async fn receive(db: &Database, carriers: &Carriers, id: i32) -> AppResult<Option<TransferDetail>> {
+    let Some(detail) = db.transfers().get_by_id(id).await? else {
+        return Ok(None);
+    };
+    ensure_status(&detail.transfer, TRANSFER_IN_TRANSIT, "receive")?;
+
+    run(db, carriers, &detail.transfer, &[TRANSFER_STEP_RECEIVE]).await
+}
+
+/// Roll back a transfer that has not arrived, or finish rolling back one whose automatic
+/// compensation failed. Returns None when the transfer does not exist.
+pub async fn compensate(
+    db: &Database,
+    carriers: &Carriers,
+    id: i32,
+    reason: &str,
+) -> AppResult<Option<TransferDetail>> {
+    let Some(detail) = db.transfers().get_by_id(id).await? else {
+        return Ok(None);
+    };
+    let transfer = &detail.transfer;
+    if transfer.status != TRANSFER_PLANNED && transfer.status != TRANSFER_IN_TRANSIT {
+        return Err(AppError::BusinessRule(format!(
+            "cannot roll back {}: it is {}",
+            transfer.transfer_number,
+            transfer.status.to_lowercase()
+        )));
+    }
+
+    let context = Context { db, carriers, transfer_id: id };
+    saga(&context).compensate(&context).await?;
+    db.transfers().mark_compensated(id, reason).await?;
+    Ok(db.transfers().get_by_id(id).await?)
+}
+
+async fn run(
+    db: &Database,
+    carriers: &Carriers,
+    transfer: &StockTransfer,
+    steps: &[&str],
+) -> AppResult<Option<TransferDetail>> {
+    let id = transfer.transfer_id;
+    let context = Context { db, carriers, transfer_id: id };
+
+    match saga(&context).run(&context, steps).await {
+        Ok(()) => {}
+        Err(Failure::Aborted(error)) => return Err(error),
+        Err(Failure::RolledBack { step, error }) => {
+            let reason = format!("{} failed: {}", step, message(&error));
+            db.transfers().mark_compensated(id, &reason).await?;
+            return Err(AppError::BusinessRule(format!("{} was rolled back; {}", transfer.transfer_number, reason)));
+        }
+        Err(Failure::Stuck { step, error }) => {
+            error!(transfer = %transfer.transfer_number, step, "Transfer could not be rolled back");
+            return Err(error);
+        }
+    }
+
+    Ok(db.transfers().get_by_id(id).await?)
+}
+
+fn saga<'a>(context: &Context<'a>) -> Saga<'a, Context<'a>> {
+    Saga::new(
+        context.db,
+        SAGA_TRANSFER,
+        context.transfer_id,
+        vec![Box::new(IssueStock), Box::new(PurchaseLabel), Box::new(ReceiveStock)],
+    )
+}
+
+fn ensure_status(transfer: &StockTransfer, status: &str, action: &str) -> AppResult<()> {
+    if transfer.status != status {
+        return Err(AppError::BusinessRule(format!(
+            "cannot {} {}: it is {}",
+            action,
+            transfer.transfer_number,
+            transfer.status.to_lowercase()
+        )));
+    }
+    Ok(())
+}
+
+/// An error's message without the kind prefix, for the transfer's failure reason
+fn message(error: &AppError) -> String {
+    match error {
+        AppError::Validation(message) | AppError::BusinessRule(message) => message.clone(),
+        AppError::NegativeStock(negative) => negative.to_string(),
+        other => other.to_string(),
+    }
+}
+
+struct IssueStock;
+
+#[async_trait]
+impl<'a> Step<Context<'a>> for IssueStock {
+    fn name(&self) -> &'static str {
+        TRANSFER_STEP_ISSUE
+    }
+
+    async fn execute(&self, context: &Context<'a>) -> AppResult<()> {
+        context.db.transfers().issue(context.transfer_id).await?;
+        Ok(())
+    }
+
+    async fn compensate(&self, context: &Context<'a>) -> AppResult<()> {
+        context.db.transfers().return_to_source(context.transfer_id).await?;
+        Ok(())
+    }
+}
+
+struct PurchaseLabel;
+
+#[async_trait]
+impl<'a> Step<Context<'a>> for PurchaseLabel {
+    fn name(&self) -> &'static str {
+        TRANSFER_STEP_LABEL
+    }
+
+    async fn execute(&self, context: &Context<'a>) -> AppResult<()> {
+        let detail = load(context).await?;
+        if detail.transfer.service_level.is_none() {
+            return Ok(());
+        }
+
+        let label = context.carriers.purchase_transfer_label(context.db, &detail).await?;
+        if let Err(e) = context.db.transfers().set_label(context.transfer_id, Some(&label)).await {
+            void(context, &detail.transfer, &label.label_id).await;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    async fn compensate(&self, context: &Context<'a>) -> AppResult<()> {
+        let detail = load(context).await?;
+        if let Some(label_id) = &detail.transfer.label_id {
+            void(context, &detail.transfer, label_id).await;
+            context.db.transfers().set_label(context.transfer_id, None).await?;
+        }
+        Ok(())
+    }
+}
+
+struct ReceiveStock;
+
+#[async_trait]
+impl<'a> Step<Context<'a>> for ReceiveStock {
+    fn name(&self) -> &'static str {
+        TRANSFER_STEP_RECEIVE
+    }
+
+    async fn execute(&self, context: &Context<'a>) -> AppResult<()> {
+        context.db.transfers().receive(context.transfer_id).await?;
+        Ok(())
+    }
+
+    /// The last step: once the goods are booked in there is nothing left that could fail,
+    /// and moving them back is a new transfer
+    async fn compensate(&self, _context: &Context<'a>) -> AppResult<()> {
+        Err(AppError::BusinessRule("a received transfer cannot be rolled back".to_string()))
+    }
+}
+
+async fn load(context: &Context<'_>) -> AppResult<TransferDetail> {
+    context.db.transfers().get_by_id(context.transfer_id).await?.ok_or_else(|| AppError::not_found("transfer"))
+}
+
+/// Void a transfer's label. A label the carrier will not void only costs money, so this
+/// never holds up the rollback of the stock.
+async fn void(context: &Context<'_>, transfer: &StockTransfer, label_id: &str) {
+    let Some(code) = &transfer.carrier_code else {
+        return;
+    };
+    if let Err(e) = context.carriers.void_label(code, label_id).await {
+        warn!("Could not void {} label {} of {}: {}", code, label_id, transfer.transfer_number, e);
+    }
+}
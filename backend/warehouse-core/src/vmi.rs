@@ -0,0 +1,176 @@
+//! Vendor-managed inventory feeds. Each due feed gets a snapshot of its items' stock and
+//! consumption, delivered either as JSON POSTed to the supplier's API or as a CSV file
+//! dropped on the supplier's SFTP server.
+//!
+//! API feeds send the [`VmiSnapshot`] as the request body, with `Authorization: Bearer
+//! <api_key>` when the feed has a key. SFTP drops are uploaded under a temporary name and
+//! renamed once complete, so suppliers never pick up half-written files.
+
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::info;
+use warehouse_db::Database;
+use warehouse_models::chrono::Utc;
+use warehouse_models::*;
+
+use crate::config::VmiConfig;
+use crate::{notify, AppError, AppResult};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Run every feed that is due. Returns how many were delivered.
+pub async fn run_due(db: &Database, config: &VmiConfig) -> AppResult<usize> {
+    let mut delivered = 0;
+    for feed in db.vmi().claim_due(Utc::now()).await? {
+        if run_feed(db, config, &feed).await?.status == VMI_RUN_SENT {
+            delivered += 1;
+        }
+    }
+
+    Ok(delivered)
+}
+
+/// Build and deliver one feed now, recording the attempt. A failed delivery raises a
+/// notification; it is not an error of the caller.
+pub async fn run_feed(db: &Database, config: &VmiConfig, feed: &VmiFeed) -> AppResult<VmiFeedRun> {
+    let snapshot = db.vmi().snapshot(feed, Utc::now()).await?;
+
+    let delivered = match feed.transport.as_str() {
+        VMI_TRANSPORT_API => push(feed, &snapshot).await.map(|()| None),
+        VMI_TRANSPORT_SFTP => drop_file(config, feed, &snapshot).await.map(Some),
+        other => Err(format!("unknown transport {}", other)),
+    };
+
+    let run = db.vmi().record_run(feed, &snapshot, delivered).await?;
+    match &run.error {
+        None => info!(supplier = %feed.supplier_code, lines = run.line_count, "VMI feed delivered"),
+        Some(error) => {
+            notify::raise(
+                db,
+                NewNotification {
+                    kind: NOTIFY_VMI_FEED_FAILED,
+                    severity: SEVERITY_WARNING,
+                    subject: format!("VMI feed to {} failed", feed.supplier_code),
+                    message: Some(error.clone()),
+                    reference_type: Some("VMI_FEED".to_string()),
+                    reference_id: Some(feed.feed_id),
+                },
+            )
+            .await?;
+        }
+    }
+
+    Ok(run)
+}
+
+/// CSV layout of an SFTP drop: one row per item and warehouse
+pub fn render_csv(snapshot: &VmiSnapshot) -> AppResult<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let csv_error = |e: csv::Error| AppError::Internal(e.into());
+
+    writer
+        .write_record([
+            "supplier_code",
+            "item_code",
+            "warehouse_code",
+            "quantity_on_hand",
+            "quantity_reserved",
+            "quantity_available",
+            "quantity_consumed",
+            "period_start",
+            "period_end",
+        ])
+        .map_err(csv_error)?;
+    for line in &snapshot.lines {
+        writer
+            .write_record([
+                snapshot.supplier_code.clone(),
+                line.item_code.clone(),
+                line.warehouse_code.clone(),
+                line.quantity_on_hand.normalize().to_string(),
+                line.quantity_reserved.normalize().to_string(),
+                line.quantity_available.normalize().to_string(),
+                line.quantity_consumed.normalize().to_string(),
+                snapshot.period_start.to_rfc3339(),
+                snapshot.period_end.to_rfc3339(),
+            ])
+            .map_err(csv_error)?;
+    }
+
+    writer.into_inner().map_err(|e| AppError::Internal(anyhow::anyhow!("VMI CSV: {}", e)))
+}
+
+async fn push(feed: &VmiFeed, snapshot: &VmiSnapshot) -> Result<(), String> {
+    let http = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().map_err(|e| e.to_string())?;
+
+    let mut request = http.post(&feed.destination).json(snapshot);
+    if let Some(key) = &feed.api_key {
+        request = request.bearer_auth(key);
+    }
+
+    let response = request.send().await.map_err(|e| format!("{} unreachable: {}", feed.destination, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} answered {}", feed.destination, response.status()));
+    }
+
+    Ok(())
+}
+
+/// Upload the CSV with the system `sftp` client in batch mode. Returns the file name.
+async fn drop_file(config: &VmiConfig, feed: &VmiFeed, snapshot: &VmiSnapshot) -> Result<String, String> {
+    let destination = SftpDestination::parse(&feed.destination)
+        .ok_or_else(|| format!("{} is not an sftp:// destination", feed.destination))?;
+
+    let file_name = format!("VMI_{}_{}.csv", feed.supplier_code, snapshot.period_end.format("%Y%m%d%H%M%S"));
+    let body = render_csv(snapshot).map_err(|e| e.to_string())?;
+    let local = std::env::temp_dir().join(format!("{}-{}", feed.feed_id, file_name));
+    tokio::fs::write(&local, body).await.map_err(|e| format!("cannot stage {}: {}", file_name, e))?;
+
+    let result = upload(config, &destination, &local, &file_name).await;
+    let _ = tokio::fs::remove_file(&local).await;
+    result.map(|()| file_name)
+}
+
+async fn upload(
+    config: &VmiConfig,
+    destination: &SftpDestination,
+    local: &Path,
+    file_name: &str,
+) -> Result<(), String> {
+    let remote = format!("{}/{}", destination.directory.trim_end_matches('/'), file_name);
+    let batch = format!("put \"{}\" \"{}.part\"\nrename \"{}.part\" \"{}\"\n", local.display(), remote, remote, remote);
+
+    let mut command = Command::new(&config.sftp_command);
+    command
+        .args(["-b", "-", "-o", "BatchMode=yes", "-P", &destination.port.to_string()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    if let Some(identity) = &config.sftp_identity_file {
+        command.args(["-i", identity]);
+    }
+    command.arg(format!("{}@{}", destination.user, destination.host));
+
+    let mut child = command.spawn().map_err(|e| format!("cannot run {}: {}", config.sftp_command, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(batch.as_bytes()).await.map_err(|e| e.to_string())?;
+    }
+
+    let output = tokio::time::timeout(REQUEST_TIMEOUT * 4, child.wait_with_output())
+        .await
+        .map_err(|_| format!("upload to {} timed out", destination.host))?
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "upload to {} failed: {}",
+            destination.host,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
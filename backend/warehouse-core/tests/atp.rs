@@ -0,0 +1,83 @@
+use warehouse_core::atp;
+use warehouse_models::chrono::NaiveDate;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn day(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 12, day).unwrap()
+}
+
+fn position() -> StockPosition {
+    StockPosition {
+        on_hand: Decimal::from(50),
+        reserved: Decimal::from(10),
+        allocated: Decimal::from(15),
+        transfers_out: Decimal::from(5),
+    }
+}
+
+fn supply(source: &str, reference: &str, expected_date: Option<NaiveDate>, quantity: i64) -> InboundSupply {
+    InboundSupply {
+        source: source.to_string(),
+        reference_number: reference.to_string(),
+        expected_date,
+        quantity: Decimal::from(quantity),
+    }
+}
+
+fn inbound() -> Vec<InboundSupply> {
+    vec![
+        supply(SUPPLY_TRANSFER, "TRF-000001", None, 5),
+        supply(SUPPLY_PURCHASE_ORDER, "PO-000002", None, 100),
+        supply(SUPPLY_PURCHASE_ORDER, "PO-000001", Some(day(10)), 30),
+        supply(SUPPLY_PURCHASE_ORDER, "PO-000003", Some(day(20)), 40),
+    ]
+}
+
+#[test]
+fn free_stock_and_transfers_in_transit_are_promised_today() {
+    let today = day(5);
+    let availability = atp::promise(1, 1, today, today, Some(Decimal::from(25)), position(), inbound());
+
+    assert_eq!(availability.available_now, Decimal::from(20));
+    assert_eq!(availability.in_transit, Decimal::from(5));
+    assert_eq!(availability.inbound_by_date, Decimal::ZERO);
+    assert_eq!(availability.available_to_promise, Decimal::from(25));
+    assert_eq!(availability.can_promise, Some(true));
+    assert_eq!(availability.promise_date, Some(today));
+}
+
+#[test]
+fn larger_quantities_wait_for_the_purchase_orders_that_cover_them() {
+    let availability = atp::promise(1, 1, day(5), day(15), Some(Decimal::from(80)), position(), inbound());
+
+    assert_eq!(availability.inbound_by_date, Decimal::from(30));
+    assert_eq!(availability.available_to_promise, Decimal::from(55));
+    assert_eq!(availability.can_promise, Some(false));
+    assert_eq!(availability.promise_date, Some(day(20)));
+
+    // A purchase order with no expected date is never promised against
+    let availability = atp::promise(1, 1, day(5), day(25), Some(Decimal::from(100)), position(), inbound());
+    assert_eq!(availability.available_to_promise, Decimal::from(95));
+    assert_eq!(availability.promise_date, None);
+}
+
+#[test]
+fn late_purchase_orders_count_from_today() {
+    let late = vec![supply(SUPPLY_PURCHASE_ORDER, "PO-000001", Some(day(1)), 30)];
+    let availability = atp::promise(1, 1, day(5), day(5), Some(Decimal::from(40)), position(), late);
+
+    assert_eq!(availability.inbound_by_date, Decimal::from(30));
+    assert_eq!(availability.promise_date, Some(day(5)));
+    assert_eq!(availability.can_promise, Some(true));
+}
+
+#[test]
+fn overcommitted_stock_promises_nothing() {
+    let overcommitted = StockPosition { allocated: Decimal::from(60), ..position() };
+    let availability = atp::promise(1, 1, day(5), day(5), None, overcommitted, Vec::new());
+
+    assert_eq!(availability.available_now, Decimal::from(-25));
+    assert_eq!(availability.available_to_promise, Decimal::ZERO);
+    assert_eq!((availability.can_promise, availability.promise_date), (None, None));
+}
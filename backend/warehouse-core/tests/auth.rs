@@ -0,0 +1,159 @@
+use axum::extract::FromRequestParts;
+use axum::http::{header, Request, StatusCode};
+use axum::response::IntoResponse;
+use warehouse_core::auth::{
+    self, AdjustStock, ApiKeyAccess, CurrentUser, DeleteWarehouses, ManageItems, ManageWarehouses, Permission,
+    RequirePermission,
+};
+use warehouse_core::config::DEFAULT_API_KEY;
+use warehouse_core::{AppState, Config};
+use warehouse_db::{Database, MemoryStore, MOCK_PASSWORD};
+use warehouse_models::chrono::{Duration, Utc};
+use warehouse_models::*;
+
+const SECRET: &str = "test-signing-secret";
+
+fn user(role: &str) -> User {
+    let now = Utc::now();
+    User {
+        user_id: 7,
+        username: "siti".to_string(),
+        email: None,
+        full_name: "Siti Rahayu".to_string(),
+        role: role.to_string(),
+        is_active: true,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+fn signed_in(role: &str) -> CurrentUser {
+    CurrentUser { user_id: 7, username: "siti".to_string(), role: role.to_string() }
+}
+
+#[test]
+fn access_tokens_carry_the_user_until_they_expire() {
+    let now = Utc::now();
+    let token = auth::issue_token(SECRET, user(ROLE_OPERATOR), 900, now);
+    assert_eq!(token.token_type, "Bearer");
+    assert_eq!(token.access_token.split('.').count(), 3);
+
+    let claims = auth::verify_token(SECRET, &token.access_token, now).unwrap();
+    assert_eq!((claims.sub, claims.username.as_str(), claims.role.as_str()), (7, "siti", ROLE_OPERATOR));
+    assert_eq!(claims.exp, token.expires_at.timestamp());
+    assert!(auth::verify_token(SECRET, &token.access_token, now + Duration::seconds(899)).is_some());
+    assert!(auth::verify_token(SECRET, &token.access_token, now + Duration::seconds(900)).is_none());
+}
+
+#[test]
+fn tokens_we_did_not_sign_are_refused() {
+    let now = Utc::now();
+    let token = auth::issue_token(SECRET, user(ROLE_VIEWER), 900, now).access_token;
+    assert!(auth::verify_token("another-secret", &token, now).is_none());
+
+    // A viewer promoting themselves in the payload breaks the signature
+    let admin = auth::issue_token(SECRET, user(ROLE_ADMIN), 900, now).access_token;
+    let parts: Vec<&str> = token.split('.').collect();
+    let admin_payload = admin.split('.').nth(1).unwrap();
+    let forged = format!("{}.{}.{}", parts[0], admin_payload, parts[2]);
+    assert!(auth::verify_token(SECRET, &forged, now).is_none());
+    assert!(auth::verify_token(SECRET, "not-a-token", now).is_none());
+}
+
+#[test]
+fn higher_roles_may_do_what_lower_ones_may() {
+    assert!(auth::has_role(ROLE_ADMIN, ROLE_OPERATOR));
+    assert!(auth::has_role(ROLE_OPERATOR, ROLE_OPERATOR));
+    assert!(!auth::has_role(ROLE_VIEWER, ROLE_OPERATOR));
+    assert!(!auth::has_role("AUDITOR", ROLE_VIEWER));
+
+    assert!(signed_in(ROLE_OPERATOR).require_permission::<AdjustStock>().is_ok());
+    assert!(signed_in(ROLE_VIEWER).require_permission::<AdjustStock>().is_err());
+    assert!(signed_in(ROLE_MANAGER).require_permission::<ManageWarehouses>().is_ok());
+    let refused = signed_in(ROLE_MANAGER).require_permission::<DeleteWarehouses>().unwrap_err();
+    assert_eq!(refused.to_string(), "Forbidden: deleting warehouses requires the admin role");
+    assert_eq!(DeleteWarehouses::MINIMUM_ROLE, ROLE_ADMIN);
+    assert!(signed_in(ROLE_ADMIN).require_role(ROLE_MANAGER).is_ok());
+    assert!(signed_in(ROLE_OPERATOR).require_role(ROLE_MANAGER).is_err());
+}
+
+/// What issuing stock with the given access token would answer, as far as permissions go
+async fn issuing_stock_as(state: &AppState, token: Option<&str>) -> StatusCode {
+    let mut request = Request::post("/api/warehouses/1/stock/1/issues");
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let (mut parts, ()) = request.body(()).unwrap().into_parts();
+    match RequirePermission::<AdjustStock>::from_request_parts(&mut parts, state).await {
+        Ok(_) => StatusCode::OK,
+        Err(refused) => refused.into_response().status(),
+    }
+}
+
+#[tokio::test]
+async fn viewers_may_not_move_stock() {
+    let state = AppState::new(Database::in_memory(MemoryStore::seeded()).unwrap(), Config::from_env_mock().unwrap());
+    let secret = &state.config.security.jwt_secret;
+    let viewer = auth::issue_token(secret, user(ROLE_VIEWER), 900, Utc::now()).access_token;
+    let operator = auth::issue_token(secret, user(ROLE_OPERATOR), 900, Utc::now()).access_token;
+
+    assert_eq!(issuing_stock_as(&state, Some(&viewer)).await, StatusCode::FORBIDDEN);
+    assert_eq!(issuing_stock_as(&state, None).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(issuing_stock_as(&state, Some(&operator)).await, StatusCode::OK);
+
+    // Items are master data, changed by managers
+    assert!(signed_in(ROLE_OPERATOR).require_permission::<ManageItems>().is_err());
+    assert!(signed_in(ROLE_MANAGER).require_permission::<ManageItems>().is_ok());
+}
+
+#[tokio::test]
+async fn mock_users_sign_in_and_may_change_items() {
+    let state = AppState::new(Database::in_memory(MemoryStore::seeded()).unwrap(), Config::from_env_mock().unwrap());
+    let sign_in = state.db.sign_in();
+    assert!(sign_in.authenticate("manager", "gudang-utama-1").await.unwrap().is_none());
+    let manager = sign_in.authenticate("manager", MOCK_PASSWORD).await.unwrap().unwrap();
+    assert_eq!(manager.role, ROLE_MANAGER);
+    assert_eq!(sign_in.get(manager.user_id).await.unwrap().unwrap().username, "manager");
+
+    let token = auth::issue_token(&state.config.security.jwt_secret, manager, 900, Utc::now()).access_token;
+    let request = Request::post("/api/items").header(header::AUTHORIZATION, format!("Bearer {}", token));
+    let (mut parts, ()) = request.body(()).unwrap().into_parts();
+    assert!(RequirePermission::<ManageItems>::from_request_parts(&mut parts, &state).await.is_ok());
+}
+
+fn key(scope: &str) -> ApiKeyAccess {
+    ApiKeyAccess::Issued(ApiKey {
+        key_id: 3,
+        name: "ERP sync".to_string(),
+        key_prefix: "wmk_abcd".to_string(),
+        scopes: vec![scope.to_string()],
+        created_by: None,
+        created_at: Utc::now(),
+        last_used_at: None,
+        revoked_at: None,
+    })
+}
+
+#[test]
+fn stock_write_keys_only_change_things_through_stock_routes() {
+    let stock_write = key(SCOPE_STOCK_WRITE);
+    assert!(auth::key_may_change(&stock_write, true).is_ok());
+    let refused = auth::key_may_change(&stock_write, false).unwrap_err();
+    assert_eq!(refused.to_string(), "Forbidden: this change needs an API key with the admin scope");
+
+    assert!(auth::key_may_change(&key(SCOPE_READ_ONLY), true).is_err());
+    assert!(auth::key_may_change(&key(SCOPE_ADMIN), false).is_ok());
+    assert!(auth::key_may_change(&ApiKeyAccess::Configured, false).is_ok());
+}
+
+#[test]
+fn the_configured_key_is_matched_unless_it_is_the_shipped_default() {
+    let mut security = Config::from_env_mock().unwrap().security;
+    security.api_key = DEFAULT_API_KEY.to_string();
+    assert!(!auth::is_configured_key(&security, DEFAULT_API_KEY));
+
+    security.api_key = "wms-ops-7f3a9c".to_string();
+    assert!(auth::is_configured_key(&security, "wms-ops-7f3a9c"));
+    assert!(!auth::is_configured_key(&security, "wms-ops-7f3a9d"));
+    assert!(!auth::is_configured_key(&security, "wms-ops"));
+}
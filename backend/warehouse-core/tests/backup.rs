@@ -0,0 +1,66 @@
+use sqlx::PgPool;
+use warehouse_core::backup;
+use warehouse_core::config::BackupConfig;
+use warehouse_db::Database;
+use warehouse_models::*;
+
+fn store(name: &str) -> (BackupConfig, std::path::PathBuf) {
+    let dir = std::env::temp_dir().join(format!("warehouse-backups-{}-{}", name, std::process::id()));
+    let config = BackupConfig {
+        store_url: format!("file://{}", dir.display()),
+        s3_command: "aws".to_string(),
+        s3_endpoint_url: None,
+    };
+    (config, dir)
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn exports_every_table_behind_a_manifest(pool: PgPool) {
+    let db = Database::new(pool.clone());
+    let (config, dir) = store("export");
+
+    let requested = backup::request(&db).await.unwrap();
+    assert_eq!(requested.status, BACKUP_RUNNING);
+    assert_eq!(requested.schemas, vec!["warehouse".to_string()]);
+
+    let completed = backup::run(&db, &config, &requested).await.unwrap();
+    assert_eq!(completed.status, BACKUP_COMPLETED);
+    assert!(std::fs::metadata(dir.join(&completed.archive_key)).unwrap().is_file());
+
+    let archive = backup::download(&config, &completed).await.unwrap();
+    assert_eq!(archive.len() as i64, completed.size_bytes.unwrap());
+    let text = String::from_utf8(archive).unwrap();
+    let mut lines = text.lines();
+
+    let manifest: BackupManifest = serde_json::from_str(lines.next().unwrap()).unwrap();
+    assert_eq!(manifest.format, BACKUP_FORMAT);
+    assert_eq!(manifest.format_version, BACKUP_FORMAT_VERSION);
+    assert_eq!(manifest.tables.len(), BACKUP_TABLES.len());
+    let warehouses: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM warehouse.warehouses").fetch_one(&pool).await.unwrap();
+    assert_eq!(manifest.tables[0].rows, warehouses);
+
+    let rows: Vec<serde_json::Value> = lines.map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(rows.len() as i64, completed.rows_exported);
+    assert_eq!(rows[0]["table"], "warehouses");
+    assert!(rows[0]["row"]["warehouse_code"].is_string());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn a_failed_upload_is_recorded_and_cannot_be_downloaded(pool: PgPool) {
+    let db = Database::new(pool);
+    let config = BackupConfig {
+        store_url: "s3://dr-drills/warehouse".to_string(),
+        s3_command: "/nonexistent/aws".to_string(),
+        s3_endpoint_url: None,
+    };
+
+    let requested = backup::request(&db).await.unwrap();
+    assert!(backup::run(&db, &config, &requested).await.is_err());
+
+    let failed = db.backups().get_by_id(requested.backup_id).await.unwrap().unwrap();
+    assert_eq!(failed.status, BACKUP_FAILED);
+    assert!(failed.error.unwrap().contains("/nonexistent/aws"));
+    assert!(backup::download(&config, &failed).await.is_err());
+}
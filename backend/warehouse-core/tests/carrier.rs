@@ -0,0 +1,157 @@
+use axum::{
+    extract::Path,
+    http::{HeaderMap, StatusCode},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde_json::{json, Value};
+use warehouse_core::carrier::{Address, Carrier, LabelRequest, Parcel, RateRequest, RestCarrier};
+use warehouse_core::config::CarrierConfig;
+use warehouse_core::AppError;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::TrackingStatus;
+
+/// Fake carrier API; rejects requests without the expected bearer token
+async fn spawn_carrier() -> String {
+    fn authorized(headers: &HeaderMap) -> bool {
+        headers.get("authorization").and_then(|v| v.to_str().ok()) == Some("Bearer secret")
+    }
+
+    let app = Router::new()
+        .route(
+            "/rates",
+            post(|headers: HeaderMap, Json(body): Json<Value>| async move {
+                if !authorized(&headers) {
+                    return Err(StatusCode::UNAUTHORIZED);
+                }
+                let weight: f64 = body["parcel"]["weight_kg"].as_str().unwrap().parse().unwrap();
+                Ok(Json(json!({
+                    "rates": [
+                        { "service_level": "REG", "service_name": "Reguler", "amount": weight * 10000.0, "currency": "IDR", "estimated_days": 3 },
+                        { "service_level": "YES", "amount": "45000", "currency": "IDR" }
+                    ]
+                })))
+            }),
+        )
+        .route(
+            "/labels",
+            post(|Json(body): Json<Value>| async move {
+                if body["service_level"] == "NONE" {
+                    return Err((StatusCode::UNPROCESSABLE_ENTITY, "unknown service"));
+                }
+                Ok(Json(json!({
+                    "label_id": "lbl_1",
+                    "tracking_number": format!("TRK-{}", body["reference"].as_str().unwrap()),
+                    "label_url": "https://labels.example/lbl_1.pdf",
+                    "label_format": "PDF",
+                    "amount": 18000,
+                    "currency": "IDR"
+                })))
+            }),
+        )
+        .route("/labels/:id", delete(|Path(_id): Path<String>| async { StatusCode::NO_CONTENT }))
+        .route(
+            "/tracking/:number",
+            get(|Path(number): Path<String>| async move {
+                Json(json!({
+                    "events": [
+                        { "status": "IN_TRANSIT", "location": "Jakarta Hub", "occurred_at": "2025-10-07T08:00:00Z" },
+                        { "status": "EXCEPTION", "description": format!("{} address not found", number), "occurred_at": "2025-10-08T10:30:00Z" }
+                    ]
+                }))
+            }),
+        );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}/", addr)
+}
+
+fn carrier(base_url: String, api_key: Option<&str>) -> RestCarrier {
+    RestCarrier::new(&CarrierConfig {
+        code: "jne".to_string(),
+        base_url,
+        api_key: api_key.map(str::to_string),
+        webhook_secret: None,
+    })
+}
+
+fn rate_request() -> RateRequest {
+    RateRequest {
+        ship_from: Address::default(),
+        ship_to: Address {
+            city: Some("Karawang".to_string()),
+            ..Default::default()
+        },
+        parcel: Parcel {
+            weight_kg: Decimal::new(25, 1),
+        },
+    }
+}
+
+#[tokio::test]
+async fn quotes_rates_tagged_with_carrier_code() {
+    let jne = carrier(spawn_carrier().await, Some("secret"));
+
+    let quotes = jne.rates(&rate_request()).await.unwrap();
+
+    assert_eq!(jne.code(), "JNE");
+    assert_eq!(quotes.len(), 2);
+    assert!(quotes.iter().all(|q| q.carrier_code == "JNE"));
+    assert_eq!(quotes[0].amount, Decimal::from(25000));
+    assert_eq!(quotes[0].estimated_days, Some(3));
+    assert_eq!(quotes[1].amount, Decimal::from(45000));
+}
+
+#[tokio::test]
+async fn purchases_and_voids_label() {
+    let jne = carrier(spawn_carrier().await, Some("secret"));
+
+    let label = jne
+        .purchase_label(&LabelRequest {
+            reference: "SO-000007".to_string(),
+            service_level: "REG".to_string(),
+            ship_from: Address::default(),
+            ship_to: Address::default(),
+            parcel: Parcel { weight_kg: Decimal::ONE },
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(label.tracking_number, "TRK-SO-000007");
+    assert_eq!(label.service_level, "REG");
+    assert_eq!(label.amount, Some(Decimal::from(18000)));
+    jne.void_label(&label.label_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn carrier_failures_are_external_service_errors() {
+    let base_url = spawn_carrier().await;
+
+    let unauthorized = carrier(base_url.clone(), None).rates(&rate_request()).await.unwrap_err();
+    assert!(matches!(unauthorized, AppError::ExternalService { ref message, .. } if message.starts_with("401")));
+
+    let rejected = carrier(base_url, Some("secret"))
+        .purchase_label(&LabelRequest {
+            reference: "SO-000007".to_string(),
+            service_level: "NONE".to_string(),
+            ship_from: Address::default(),
+            ship_to: Address::default(),
+            parcel: Parcel { weight_kg: Decimal::ONE },
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(rejected, AppError::ExternalService { ref message, .. } if message.contains("unknown service")));
+}
+
+#[tokio::test]
+async fn fetches_tracking_history() {
+    let jne = carrier(spawn_carrier().await, None);
+
+    let events = jne.tracking("JNE0042").await.unwrap();
+
+    let statuses: Vec<_> = events.iter().map(|e| e.status).collect();
+    assert_eq!(statuses, [TrackingStatus::InTransit, TrackingStatus::Exception]);
+    assert_eq!(events[1].description.as_deref(), Some("JNE0042 address not found"));
+}
@@ -0,0 +1,100 @@
+use warehouse_core::cartonization;
+use warehouse_models::chrono::Utc;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn size(box_id: i32, code: &str, sides: [i64; 3], max_weight: i64) -> BoxSize {
+    BoxSize {
+        box_id,
+        warehouse_id: 1,
+        box_code: code.to_string(),
+        length_cm: Decimal::from(sides[0]),
+        width_cm: Decimal::from(sides[1]),
+        height_cm: Decimal::from(sides[2]),
+        max_weight_kg: Decimal::from(max_weight),
+        tare_weight_kg: "0.2".parse().unwrap(),
+        is_active: true,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+/// S holds 2,550 cm3 / 5 kg, M 20,400 cm3 / 15 kg, L 81,600 cm3 / 25 kg
+fn boxes() -> Vec<BoxSize> {
+    vec![size(3, "L", [60, 40, 40], 25), size(1, "S", [20, 15, 10], 5), size(2, "M", [40, 30, 20], 15)]
+}
+
+fn line(line_id: i32, quantity: &str, sides: Option<[i64; 3]>, weight: Option<&str>) -> PackingLine {
+    PackingLine {
+        line_id,
+        item_id: line_id * 10,
+        item_code: format!("ITM{}", line_id),
+        quantity: quantity.parse().unwrap(),
+        weight_kg: weight.map(|weight| weight.parse().unwrap()),
+        length_cm: sides.map(|s| Decimal::from(s[0])),
+        width_cm: sides.map(|s| Decimal::from(s[1])),
+        height_cm: sides.map(|s| Decimal::from(s[2])),
+        volume_cbm: None,
+    }
+}
+
+fn summary(plan: &CartonPlan) -> Vec<(&str, Vec<(i32, Decimal)>)> {
+    plan.cartons
+        .iter()
+        .map(|carton| {
+            (carton.box_code.as_str(), carton.lines.iter().map(|line| (line.line_id, line.quantity)).collect())
+        })
+        .collect()
+}
+
+#[test]
+fn small_orders_get_the_smallest_box() {
+    let plan = cartonization::plan(7, &[line(1, "2", Some([10, 10, 10]), Some("0.5"))], &boxes());
+
+    assert_eq!(summary(&plan), [("S", vec![(1, Decimal::from(2))])]);
+    let carton = &plan.cartons[0];
+    assert_eq!(carton.content_volume_cm3, Decimal::from(2000));
+    assert_eq!(carton.gross_weight_kg, "1.2".parse::<Decimal>().unwrap());
+    assert_eq!(carton.fill_percent, "66.7".parse::<Decimal>().unwrap());
+    assert!(plan.unboxed.is_empty() && plan.warnings.is_empty());
+}
+
+#[test]
+fn weight_splits_cartons_and_the_last_is_downsized() {
+    // 60 units of 1,000 cm3 and 0.5 kg: an L carton takes 50 by weight, the other 10 fit an M
+    let plan = cartonization::plan(7, &[line(1, "60", Some([10, 10, 10]), Some("0.5"))], &boxes());
+
+    assert_eq!(summary(&plan), [("L", vec![(1, Decimal::from(50))]), ("M", vec![(1, Decimal::from(10))])]);
+    assert_eq!(plan.cartons[0].content_weight_kg, Decimal::from(25));
+}
+
+#[test]
+fn mixed_lines_share_cartons_largest_first() {
+    let lines = [
+        line(1, "1", Some([35, 25, 15]), Some("4")),
+        line(2, "3", Some([10, 10, 5]), Some("0.3")),
+        line(3, "2.5", Some([5, 5, 5]), Some("1")),
+    ];
+    let plan = cartonization::plan(7, &lines, &boxes());
+
+    let fraction: Decimal = "2.5".parse().unwrap();
+    assert_eq!(summary(&plan), [("M", vec![(1, Decimal::ONE), (2, Decimal::from(3)), (3, fraction)])]);
+    assert_eq!(plan.cartons[0].content_weight_kg, "7.4".parse::<Decimal>().unwrap());
+}
+
+#[test]
+fn oversized_and_unmeasured_items_are_reported() {
+    let lines = [line(1, "1", Some([70, 10, 10]), Some("2")), line(2, "1", None, None)];
+    let plan = cartonization::plan(7, &lines, &boxes());
+
+    assert_eq!(plan.unboxed, [SuggestedCartonLine { line_id: 1, item_id: 10, quantity: Decimal::ONE }]);
+    assert_eq!(summary(&plan), [("S", vec![(2, Decimal::ONE)])]);
+    assert_eq!(plan.warnings, ["ITM2 has no weight", "ITM2 has no dimensions"]);
+
+    let mut retired = boxes();
+    retired.iter_mut().for_each(|size| size.is_active = false);
+    let plan = cartonization::plan(7, &lines[..1], &retired);
+    assert!(plan.cartons.is_empty());
+    assert_eq!(plan.unboxed.len(), 1);
+    assert_eq!(plan.warnings, ["the warehouse has no box sizes configured"]);
+}
@@ -0,0 +1,105 @@
+use sqlx::PgPool;
+use warehouse_core::documents;
+use warehouse_db::Database;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// Ship 4 helmets from Jakarta and return the shipment
+async fn shipped(db: &Database) -> i32 {
+    let order = db
+        .orders()
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "PT Karya Beton".to_string(),
+            customer_reference: Some("PO-77".to_string()),
+            ship_to_name: None,
+            ship_to_address: Some("Jl. Gatot Subroto 12".to_string()),
+            ship_to_city: Some("Bandung".to_string()),
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: Some("ID".to_string()),
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![CreateOrderLine { item_id: 3, quantity_ordered: Decimal::from(4) }],
+        })
+        .await
+        .unwrap();
+    let confirmation = ShipOrder {
+        carrier_code: Some("JNE".to_string()),
+        tracking_number: None,
+        service_level: None,
+        lines: vec![ShipOrderLine {
+            line_id: order.lines[0].line_id,
+            quantity: Decimal::from(4),
+            weight_kg: None,
+            lot_number: None,
+        }],
+        lpns: Vec::new(),
+        reference_id: None,
+    };
+    let (_, shipment) = db.orders().ship(order.order.order_id, confirmation, None).await.unwrap().unwrap();
+    shipment.shipment.shipment_id
+}
+
+fn branding(company_name: &str) -> SetDocumentBranding {
+    SetDocumentBranding {
+        company_name: company_name.to_string(),
+        address: Some("Jl. Industri 5\nCikarang".to_string()),
+        tax_id: Some("01.234.567.8-901.000".to_string()),
+        phone: None,
+        email: None,
+        accent_color: Some("#aa3300".to_string()),
+        footer_text: None,
+        currency: None,
+    }
+}
+
+fn contains(pdf: &[u8], text: &str) -> bool {
+    pdf.windows(text.len()).any(|window| window == text.as_bytes())
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn delivery_order_falls_back_to_the_warehouse_name(pool: PgPool) {
+    let db = Database::new(pool);
+    let shipment_id = shipped(&db).await;
+
+    let document = documents::generate(&db, shipment_id, DOCUMENT_DELIVERY_ORDER).await.unwrap().unwrap();
+    assert!(document.document_number.starts_with("DO-SHP-"));
+
+    let (_, pdf) = db.documents().content(document.document_id).await.unwrap().unwrap();
+    assert!(pdf.starts_with(b"%PDF-"));
+    assert_eq!(pdf.len() as i32, document.size_bytes);
+    assert!(contains(&pdf, "(DELIVERY ORDER)"));
+    assert!(contains(&pdf, "(Jakarta Warehouse)"));
+    assert!(contains(&pdf, "(ITM003)"));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn warehouse_branding_wins_and_regenerating_replaces(pool: PgPool) {
+    let db = Database::new(pool);
+    let shipment_id = shipped(&db).await;
+    db.documents().set_branding(None, branding("PT Gudang Nusantara")).await.unwrap();
+
+    let first = documents::generate(&db, shipment_id, DOCUMENT_PROFORMA_INVOICE).await.unwrap().unwrap();
+    let (_, pdf) = db.documents().content(first.document_id).await.unwrap().unwrap();
+    assert!(contains(&pdf, "(PROFORMA INVOICE)"));
+    assert!(contains(&pdf, "(PT Gudang Nusantara)"));
+
+    let saved = db.documents().set_branding(Some(1), branding("PT Gudang Jakarta")).await.unwrap();
+    assert_eq!(saved.accent_color, "#AA3300");
+    let second = documents::generate(&db, shipment_id, DOCUMENT_PROFORMA_INVOICE).await.unwrap().unwrap();
+    let (_, pdf) = db.documents().content(second.document_id).await.unwrap().unwrap();
+    assert!(contains(&pdf, "(PT Gudang Jakarta)"));
+    assert!(!contains(&pdf, "(PT Gudang Nusantara)"));
+
+    let kept = db.documents().documents(shipment_id).await.unwrap();
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].document_number, second.document_number);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn generate_returns_none_for_a_missing_shipment(pool: PgPool) {
+    let db = Database::new(pool);
+    assert!(documents::generate(&db, 999, DOCUMENT_DELIVERY_ORDER).await.unwrap().is_none());
+}
@@ -0,0 +1,111 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{extract::State, routing::get, Json, Router};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use warehouse_core::ecommerce;
+use warehouse_db::Database;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+type Pushed = Arc<Mutex<Vec<Value>>>;
+
+/// Fake Shopify store with a paid order and a cancelled one; remembers the stock levels set
+async fn spawn_shopify() -> (String, Pushed) {
+    let pushed: Pushed = Arc::default();
+    let app = Router::new()
+        .route(
+            "/admin/api/2024-01/orders.json",
+            get(|| async {
+                Json(json!({ "orders": [
+                    {
+                        "id": 450789469,
+                        "name": "#1001",
+                        "email": "bob@example.com",
+                        "financial_status": "paid",
+                        "cancelled_at": null,
+                        "updated_at": "2025-11-01T08:00:00Z",
+                        "customer": { "first_name": "Bob", "last_name": "Norman" },
+                        "shipping_address": {
+                            "name": "Bob Norman", "address1": "Jl. Sudirman 1", "city": "Jakarta",
+                            "province_code": "JK", "zip": "10210", "country_code": "ID"
+                        },
+                        "line_items": [
+                            { "sku": "ITM003", "quantity": 2, "requires_shipping": true },
+                            { "sku": "GIFT-CARD", "quantity": 1, "requires_shipping": false }
+                        ]
+                    },
+                    {
+                        "id": 450789470,
+                        "name": "#1002",
+                        "financial_status": "voided",
+                        "cancelled_at": "2025-11-01T09:00:00Z",
+                        "updated_at": "2025-11-01T09:00:00Z",
+                        "line_items": [{ "sku": "ITM003", "quantity": 1 }]
+                    }
+                ]}))
+            }),
+        )
+        .route(
+            "/admin/api/2024-01/inventory_levels/set.json",
+            axum::routing::post(|State(pushed): State<Pushed>, Json(body): Json<Value>| async move {
+                pushed.lock().unwrap().push(body.clone());
+                Json(json!({ "inventory_level": body }))
+            }),
+        )
+        .with_state(pushed.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{}", addr), pushed)
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn sync_imports_paid_orders_once_and_pushes_changed_stock(pool: PgPool) {
+    let db = Database::new(pool);
+    let (store_url, pushed) = spawn_shopify().await;
+    let detail = db
+        .ecommerce()
+        .create(CreateEcommerceChannel {
+            channel_code: "SHOP".to_string(),
+            platform: ECOMMERCE_SHOPIFY.to_string(),
+            store_url,
+            api_key: "shpat_test".to_string(),
+            api_secret: None,
+            location_id: Some("655441491".to_string()),
+            warehouse_id: 1,
+            buffer_quantity: Some(Decimal::from(5)),
+            sync_interval_minutes: 15,
+            listings: vec![SetEcommerceListing { item_id: 3, external_id: "808950810".to_string(), buffer_quantity: None }],
+        })
+        .await
+        .unwrap();
+
+    let runs = ecommerce::sync(&db, &detail.channel).await.unwrap();
+    assert!(runs.iter().all(|run| run.status == ECOMMERCE_RUN_SUCCEEDED), "{:?}", runs);
+    let pulled = runs.iter().find(|run| run.direction == ECOMMERCE_PULL).unwrap();
+    assert_eq!(pulled.records, 1);
+
+    let order_id = db.ecommerce().imported_order(detail.channel.channel_id, "450789469").await.unwrap().unwrap();
+    let order = db.orders().get_by_id(order_id).await.unwrap().unwrap();
+    assert_eq!(order.order.source, SOURCE_ECOMMERCE);
+    assert_eq!(order.order.customer_reference.as_deref(), Some("#1001"));
+    assert_eq!(order.lines.len(), 1);
+    assert!(db.ecommerce().imported_order(detail.channel.channel_id, "450789470").await.unwrap().is_none());
+
+    // The imported order already counts against what is pushed
+    let level = db.ecommerce().stock_levels(detail.channel.channel_id).await.unwrap().remove(0);
+    assert_eq!(level.pushed_quantity, Some(level.quantity));
+    let sent = pushed.lock().unwrap().clone();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0]["inventory_item_id"], 808950810);
+    assert_eq!(sent[0]["location_id"], 655441491);
+    assert_eq!(Decimal::from(sent[0]["available"].as_i64().unwrap()), level.quantity);
+
+    // Nothing changed since: no orders are created and no stock is pushed again
+    let channel = db.ecommerce().get_by_id(detail.channel.channel_id).await.unwrap().unwrap().channel;
+    let again = ecommerce::sync(&db, &channel).await.unwrap();
+    assert!(again.iter().all(|run| run.records == 0), "{:?}", again);
+    assert_eq!(pushed.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,107 @@
+use warehouse_core::edi::documents::{self, AdviceLine, ShippingAdvice};
+use warehouse_core::edi::x12;
+use warehouse_models::chrono::NaiveDate;
+use warehouse_models::rust_decimal::Decimal;
+
+const ASN: &str = include_str!("fixtures/asn_856.x12");
+const SHIPPING_ORDERS: &str = include_str!("fixtures/shipping_orders_940.x12");
+
+#[test]
+fn parses_envelope_and_transaction_sets() {
+    let transactions = x12::parse(SHIPPING_ORDERS).unwrap();
+
+    assert_eq!(transactions.len(), 2);
+    assert!(transactions.iter().all(|t| t.transaction_set == "940"));
+    assert_eq!(transactions[0].sender_id, "DEPOSITOR7");
+    assert_eq!(transactions[0].interchange_control_number, "000000456");
+    assert_eq!(transactions[1].segments.len(), 2);
+}
+
+#[test]
+fn reads_delimiters_from_isa_header() {
+    let raw = ASN.replace('*', "|").replace('~', "\n");
+
+    let transactions = x12::parse(&raw).unwrap();
+
+    assert_eq!(transactions[0].transaction_set, "856");
+    assert_eq!(documents::parse_asn(&transactions[0]).unwrap().shipment_id, "ASN-7781");
+}
+
+#[test]
+fn rejects_documents_without_envelope() {
+    assert!(x12::parse("ST*856*0001~BSN*00*1~SE*2*0001~").is_err());
+    assert!(x12::parse(&ASN.replace("SE*14*0001~", "")).is_err());
+}
+
+#[test]
+fn maps_856_to_expected_receipt() {
+    let transactions = x12::parse(ASN).unwrap();
+
+    let asn = documents::parse_asn(&transactions[0]).unwrap();
+
+    assert_eq!(asn.supplier_name.as_deref(), Some("PT Baja Steel"));
+    assert_eq!(asn.purchase_order_number.as_deref(), Some("PO-5521"));
+    assert_eq!(asn.carrier_code.as_deref(), Some("JNE"));
+    assert_eq!(asn.expected_date, NaiveDate::from_ymd_opt(2025, 10, 5));
+    let lines: Vec<_> = asn.lines.iter().map(|l| (l.item_code.as_str(), l.quantity)).collect();
+    // BP (buyer part number) wins over the supplier's own VP code
+    assert_eq!(lines, [("ITM002", Decimal::from(200)), ("ITM003", Decimal::from(30))]);
+}
+
+#[test]
+fn maps_940_to_outbound_order() {
+    let transactions = x12::parse(SHIPPING_ORDERS).unwrap();
+
+    let order = documents::parse_shipping_order(&transactions[0]).unwrap();
+
+    assert_eq!(order.depositor_order_number, "DO-3321");
+    assert_eq!(order.purchase_order_number.as_deref(), Some("PO-88"));
+    assert_eq!(order.ship_to_name.as_deref(), Some("Proyek Tol Cikampek"));
+    assert_eq!(order.ship_to_city.as_deref(), Some("Karawang"));
+    assert_eq!(order.ship_to_postal_code.as_deref(), Some("41361"));
+    assert_eq!(order.requested_ship_date, NaiveDate::from_ymd_opt(2025, 10, 3));
+    assert_eq!(order.lines.len(), 2);
+    assert_eq!(order.lines[1].item_code, "ITM003");
+}
+
+#[test]
+fn built_945_parses_back() {
+    let raw = documents::build_shipping_advice(&ShippingAdvice {
+        sender_id: "LSFWAREHOUSE",
+        receiver_id: "DEPOSITOR7",
+        control_number: 42,
+        depositor_order_number: "DO-3321",
+        purchase_order_number: None,
+        shipment_number: "SHP-000001",
+        ship_to_name: Some("Proyek Tol Cikampek"),
+        carrier_code: Some("JNE"),
+        lines: vec![
+            AdviceLine {
+                item_code: "ITM002".to_string(),
+                quantity_ordered: Decimal::from(40),
+                quantity_shipped: Decimal::from(40),
+                quantity_outstanding: Decimal::ZERO,
+            },
+            AdviceLine {
+                item_code: "ITM003".to_string(),
+                quantity_ordered: Decimal::from(5),
+                quantity_shipped: Decimal::from(3),
+                quantity_outstanding: Decimal::from(2),
+            },
+        ],
+    });
+
+    let transactions = x12::parse(&raw).unwrap();
+    assert_eq!(transactions.len(), 1);
+    let advice = &transactions[0];
+    assert_eq!(advice.transaction_set, "945");
+    assert_eq!(advice.sender_id, "LSFWAREHOUSE");
+    assert_eq!(advice.interchange_control_number, "000000042");
+
+    let w12: Vec<_> = advice.segments.iter().filter(|s| s.id == "W12").collect();
+    assert_eq!(w12[0].get(1), Some("CC"));
+    assert_eq!(w12[1].get(1), Some("PR"));
+    assert_eq!(w12[1].get(4), Some("2"));
+    assert!(raw.contains("SE*10*0001~"));
+    assert!(raw.contains("W03*43~"));
+}
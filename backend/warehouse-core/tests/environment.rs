@@ -0,0 +1,44 @@
+use sqlx::PgPool;
+use warehouse_core::environment;
+use warehouse_db::Database;
+use warehouse_models::chrono::{Duration, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn excursion_raises_cold_chain_notification(pool: PgPool) {
+    let db = Database::new(pool);
+    let zone = db
+        .locations()
+        .create_zone(1, CreateZone { zone_code: "FRZ".to_string(), zone_name: "Freezer".to_string(), pick_sequence: 1 })
+        .await
+        .unwrap();
+    db.environment()
+        .set_thresholds(zone.zone_id, SetThresholds { max_temperature: Some(Decimal::from(-18)), ..Default::default() })
+        .await
+        .unwrap();
+    db.environment().create_sensor(zone.zone_id, CreateSensor { sensor_code: "F-1".to_string() }).await.unwrap();
+
+    let reading = |minutes: i64, temperature: i64| NewSensorReading {
+        sensor_code: "F-1".to_string(),
+        recorded_at: Utc::now() - Duration::minutes(minutes),
+        temperature: Some(Decimal::from(temperature)),
+        humidity: Some(Decimal::from(40)),
+    };
+    let batch = SensorReadings { readings: vec![reading(3, -12), reading(2, -10), reading(1, -20)] };
+    let result = environment::ingest(&db, batch).await.unwrap();
+    assert_eq!(result.excursions_opened.len(), 1);
+    assert_eq!(result.excursions_closed.len(), 1);
+
+    let raised = db
+        .notifications()
+        .list(
+            NotificationFilter { kind: Some(NOTIFY_COLD_CHAIN_EXCURSION.to_string()), acknowledged: None },
+            KeysetQuery::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(raised.data.len(), 1);
+    assert_eq!(raised.data[0].severity, SEVERITY_CRITICAL);
+    assert!(raised.data[0].subject.contains("FRZ"));
+}
@@ -0,0 +1,119 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{extract::State, routing::get, Json, Router};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use warehouse_core::erp::{self, FieldMap};
+use warehouse_db::Database;
+use warehouse_models::*;
+
+type Pushed = Arc<Mutex<Vec<(String, Value)>>>;
+
+/// Fake ERP speaking SAP field names; remembers what was pushed to it
+async fn spawn_erp() -> (String, Pushed) {
+    let pushed: Pushed = Arc::default();
+    let record = |resource: &'static str| {
+        move |State(pushed): State<Pushed>, Json(body): Json<Value>| async move {
+            for record in body["records"].as_array().unwrap() {
+                pushed.lock().unwrap().push((resource.to_string(), record.clone()));
+            }
+            Json(json!({}))
+        }
+    };
+
+    let app = Router::new()
+        .route(
+            "/items",
+            get(|| async {
+                Json(json!({ "records": [
+                    { "MATNR": "ERP-100", "MAKTX": "Pallet wrap", "unit": "ROLL", "updated_at": "2025-11-01T08:00:00Z" }
+                ]}))
+            }),
+        )
+        .route(
+            "/purchase-orders",
+            get(|| async {
+                Json(json!({ "records": [{
+                    "external_id": "4500000017",
+                    "EBELN": "PO-4500000017",
+                    "warehouse_code": "WH001",
+                    "supplier_name": "Wrap Co",
+                    "lines": [{ "MATNR": "ERP-100", "quantity": "40" }],
+                    "updated_at": "2025-11-01T09:00:00Z"
+                }]}))
+            }),
+        )
+        .route("/goods-receipts", axum::routing::post(record("goods-receipts")))
+        .route("/goods-issues", axum::routing::post(record("goods-issues")))
+        .with_state(pushed.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{}", addr), pushed)
+}
+
+fn mapping(entity: &str, field_name: &str, erp_field: &str) -> ErpFieldMapping {
+    ErpFieldMapping { entity: entity.to_string(), field_name: field_name.to_string(), erp_field: erp_field.to_string() }
+}
+
+#[test]
+fn field_maps_rename_nested_lines_both_ways() {
+    let map = FieldMap::new(
+        &[mapping(ERP_ENTITY_ISSUE, "item_code", "MATNR"), mapping(ERP_ENTITY_ITEM, "item_code", "ItemCode")],
+        ERP_ENTITY_ISSUE,
+    );
+    let record = json!({ "shipment_number": "SHP-1", "lines": [{ "item_code": "ITM001", "quantity": "2" }] });
+
+    let sent = map.to_erp(&record).unwrap();
+    assert_eq!(sent["lines"][0]["MATNR"], "ITM001");
+    assert_eq!(sent["shipment_number"], "SHP-1");
+
+    let back: Value = map.from_erp(sent).unwrap();
+    assert_eq!(back, record);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn sync_imports_items_and_purchase_orders_once(pool: PgPool) {
+    let db = Database::new(pool);
+    let (base_url, pushed) = spawn_erp().await;
+    let detail = db
+        .erp()
+        .create(CreateErpConnector {
+            connector_code: "SAP".to_string(),
+            erp_system: "SAP".to_string(),
+            connector_type: ERP_CONNECTOR_REST.to_string(),
+            base_url,
+            api_key: None,
+            item_master: None,
+            sync_interval_minutes: 15,
+            field_mappings: vec![
+                mapping(ERP_ENTITY_ITEM, "item_code", "MATNR"),
+                mapping(ERP_ENTITY_ITEM, "item_name", "MAKTX"),
+                mapping(ERP_ENTITY_PURCHASE_ORDER, "purchase_order_number", "EBELN"),
+                mapping(ERP_ENTITY_PURCHASE_ORDER, "item_code", "MATNR"),
+            ],
+        })
+        .await
+        .unwrap();
+
+    let runs = erp::sync(&db, &detail).await.unwrap();
+    assert!(runs.iter().all(|run| run.status == ERP_RUN_SUCCEEDED), "{:?}", runs);
+    let item = db.items().get_by_code("ERP-100").await.unwrap().unwrap();
+    assert_eq!(item.item_name, "Pallet wrap");
+
+    let receipt_id = db.erp().linked(detail.connector.connector_id, ERP_ENTITY_PURCHASE_ORDER, "4500000017").await.unwrap();
+    let receipt = db.receipts().get_by_id(receipt_id.unwrap()).await.unwrap().unwrap();
+    assert_eq!(receipt.receipt.source, SOURCE_ERP);
+    assert_eq!(receipt.lines[0].item_id, item.item_id);
+
+    let issues = runs.iter().find(|run| run.entity == ERP_ENTITY_ISSUE).unwrap();
+    let pushed_issues = pushed.lock().unwrap().iter().filter(|(resource, _)| resource == "goods-issues").count();
+    assert_eq!(pushed_issues, issues.records as usize);
+
+    // The same changes pulled again create nothing, and nothing new is pushed
+    let again = erp::sync(&db, &detail).await.unwrap();
+    let orders = again.iter().find(|run| run.entity == ERP_ENTITY_PURCHASE_ORDER).unwrap();
+    assert_eq!(orders.records, 0);
+    assert!(again.iter().filter(|run| run.direction == ERP_PUSH).all(|run| run.records == 0));
+}
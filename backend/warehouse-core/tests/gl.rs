@@ -0,0 +1,63 @@
+use warehouse_core::gl;
+use warehouse_models::chrono::{NaiveDate, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn period() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 10, 1).unwrap()
+}
+
+fn total(warehouse_code: &str, movement_type: &str, value: i64) -> GlMovementTotal {
+    GlMovementTotal {
+        warehouse_code: warehouse_code.to_string(),
+        movement_type: movement_type.to_string(),
+        value: Decimal::from(value),
+        movements: 1,
+        uncosted_movements: 0,
+    }
+}
+
+fn accounts() -> Vec<GlAccount> {
+    [(GL_INVENTORY, "1400"), (GL_IN_TRANSIT, "1410"), (GL_GRNI, "2150"), (GL_COGS, "5000"), (GL_SCRAP, "5200")]
+        .into_iter()
+        .map(|(category, code)| GlAccount {
+            category: category.to_string(),
+            account_code: code.to_string(),
+            account_name: category.to_string(),
+            updated_at: Utc::now(),
+        })
+        .collect()
+}
+
+fn line<'a>(journal: &'a GlJournal, category: &str, warehouse_code: &str) -> &'a GlJournalLine {
+    journal.lines.iter().find(|line| line.category == category && line.warehouse_code == warehouse_code).unwrap()
+}
+
+#[test]
+fn nets_each_account_per_warehouse_and_balances() {
+    let totals = [
+        total("WH001", "RECEIPT", 1000),
+        total("WH001", "SHIPMENT", -400),
+        total("WH001", "SCRAP", -50),
+        total("WH001", "TRANSFER_OUT", -100),
+        total("WH002", "TRANSFER_IN", 100),
+    ];
+    let journal = gl::build_journal(period(), GL_PERIOD_OPEN.to_string(), &totals, &accounts());
+
+    assert_eq!(line(&journal, GL_INVENTORY, "WH001").debit, Decimal::from(450));
+    assert_eq!(line(&journal, GL_GRNI, "WH001").credit, Decimal::from(1000));
+    assert_eq!(line(&journal, GL_COGS, "WH001").debit, Decimal::from(400));
+    assert_eq!(line(&journal, GL_SCRAP, "WH001").account_code, "5200");
+    assert_eq!(line(&journal, GL_IN_TRANSIT, "WH002").credit, Decimal::from(100));
+    assert_eq!(journal.total_debit, journal.total_credit);
+    assert_eq!(journal.movements, 5);
+}
+
+#[test]
+fn moves_within_our_inventory_post_nothing() {
+    let totals = [total("WH001", "QC_RELEASE", 300), total("WH001", "RETURN_RESTOCK", 20)];
+    let journal = gl::build_journal(period(), GL_PERIOD_OPEN.to_string(), &totals, &accounts());
+
+    assert!(journal.lines.is_empty());
+    assert_eq!(journal.total_debit, Decimal::ZERO);
+}
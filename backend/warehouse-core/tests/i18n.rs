@@ -0,0 +1,88 @@
+use axum::response::IntoResponse;
+use serde_json::Value;
+use sqlx::PgPool;
+use warehouse_core::i18n::{self, Locale};
+use warehouse_core::AppError;
+use warehouse_db::{Database, NegativeStock};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn error_message(error: AppError) -> String {
+    let body = axum::body::to_bytes(error.into_response().into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    json["error"]["message"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn accept_language_picks_the_best_rated_supported_language() {
+    assert_eq!(Locale::from_accept_language("id-ID,id;q=0.9,en-US;q=0.8,en;q=0.7"), Some(Locale::Id));
+    assert_eq!(Locale::from_accept_language("fr-FR, en;q=0.5, id;q=0.4"), Some(Locale::En));
+    assert_eq!(Locale::from_accept_language("en;q=0.2, in;q=0.8"), Some(Locale::Id));
+    assert_eq!(Locale::from_accept_language("en, id"), Some(Locale::En));
+    assert_eq!(Locale::from_accept_language("id;q=0, en;q=0.1"), Some(Locale::En));
+    assert_eq!(Locale::from_accept_language("fr, de;q=0.9"), None);
+    assert_eq!(Locale::from_accept_language(""), None);
+}
+
+#[tokio::test]
+async fn errors_are_worded_in_the_current_locale() {
+    assert_eq!(error_message(AppError::not_found("item")).await, "item not found");
+
+    let message = i18n::scope(Locale::Id, error_message(AppError::not_found("item"))).await;
+    assert_eq!(message, "barang tidak ditemukan");
+    let message = i18n::scope(Locale::Id, error_message(AppError::already_exists("report code"))).await;
+    assert_eq!(message, "report code sudah ada");
+    let message = i18n::scope(Locale::Id, error_message(AppError::Unauthorized)).await;
+    assert_eq!(message, "Akses tidak diizinkan");
+    let message = i18n::scope(Locale::Id, error_message(AppError::MockMode)).await;
+    assert_eq!(message, "Tidak tersedia dalam mode tiruan");
+
+    let message = i18n::scope(Locale::Id, error_message(AppError::validation("from must not be after to"))).await;
+    assert_eq!(message, "from tidak boleh setelah to");
+    // Rule messages the catalog does not know are passed on as raised
+    let message = i18n::scope(Locale::Id, error_message(AppError::BusinessRule("period is closed".into()))).await;
+    assert_eq!(message, "period is closed");
+
+    let negative = NegativeStock { item_id: 2, warehouse_id: 1, on_hand: Decimal::from(-3) };
+    let message = i18n::scope(Locale::Id, error_message(AppError::NegativeStock(negative))).await;
+    assert_eq!(message, "stok barang 2 di gudang 1 akan menjadi -3; stok negatif tidak diizinkan");
+}
+
+#[test]
+fn labels_fall_back_to_the_spelled_out_code() {
+    assert_eq!(i18n::label(Locale::En, "order_status", ORDER_PARTIALLY_SHIPPED), "Partially shipped");
+    assert_eq!(i18n::label(Locale::Id, "order_status", ORDER_PARTIALLY_SHIPPED), "Dikirim sebagian");
+    assert_eq!(i18n::label(Locale::Id, "order_status", "ON_HOLD"), "On hold");
+
+    let labels = i18n::labels(Locale::Id);
+    assert_eq!(labels["job_status"][JOB_SUCCEEDED], "Berhasil");
+    assert_eq!(labels["task_type"].len(), TASK_TYPES.len());
+}
+
+#[test]
+fn report_headers_name_columns_and_aggregates() {
+    let columns: Vec<String> = ["warehouse_code", "sum_quantity_on_hand", "count", "max_expiry_date"]
+        .iter()
+        .map(|column| column.to_string())
+        .collect();
+
+    assert_eq!(
+        i18n::report_headers(Locale::En, &columns),
+        ["Warehouse code", "On hand (sum)", "Rows", "Expiry date (maximum)"]
+    );
+    assert_eq!(
+        i18n::report_headers(Locale::Id, &columns),
+        ["Kode gudang", "Stok fisik (total)", "Jumlah baris", "Expiry date (maksimum)"]
+    );
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn saved_locale_replaces_the_earlier_one(pool: PgPool) {
+    let db = Database::new(pool);
+    assert!(db.preferences().get("dewi").await.unwrap().is_none());
+
+    db.preferences().set("dewi", SetUserPreference { locale: "en".to_string() }).await.unwrap();
+    let saved = db.preferences().set("dewi", SetUserPreference { locale: "id".to_string() }).await.unwrap();
+    assert_eq!(saved.locale, "id");
+    assert_eq!(db.preferences().get("dewi").await.unwrap().unwrap().locale, "id");
+}
@@ -0,0 +1,26 @@
+use sqlx::PgPool;
+use warehouse_core::integrity;
+use warehouse_db::Database;
+use warehouse_models::*;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn a_run_with_findings_raises_a_critical_notification(pool: PgPool) {
+    let db = Database::new(pool.clone());
+    sqlx::query(
+        "UPDATE warehouse.stock_inventory SET quantity_on_hand = quantity_on_hand + 1
+         WHERE stock_id = (SELECT MIN(stock_id) FROM warehouse.stock_inventory)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let report = integrity::run(&db).await.unwrap();
+    assert!(!report.passed());
+
+    let filter = NotificationFilter { kind: Some(NOTIFY_INTEGRITY_VIOLATION.to_string()), acknowledged: None };
+    let raised = db.notifications().list(filter, KeysetQuery::default()).await.unwrap();
+    assert_eq!(raised.data.len(), 1);
+    assert_eq!(raised.data[0].severity, SEVERITY_CRITICAL);
+    assert_eq!(raised.data[0].reference_id, Some(report.run.run_id));
+    assert!(raised.data[0].message.as_deref().unwrap().contains(CHECK_MOVEMENT_MISMATCH));
+}
@@ -0,0 +1,152 @@
+use sqlx::PgPool;
+use warehouse_core::config::JobConfig;
+use warehouse_core::{jobs, reports, AppError};
+use warehouse_db::Database;
+use warehouse_models::chrono::{Duration, Utc};
+use warehouse_models::*;
+
+const SECRET: &str = "test-signing-secret";
+
+fn import_job() -> NewJob {
+    NewJob { job_type: JOB_IMPORT, description: "Import items".to_string(), reference_type: None, reference_id: None }
+}
+
+/// The query string of a download URL
+fn signature_of(url: &str) -> ArtifactSignature {
+    let query = url.split_once('?').unwrap().1;
+    let mut signed = ArtifactSignature { expires: 0, signature: String::new() };
+    for (name, value) in query.split('&').map(|pair| pair.split_once('=').unwrap()) {
+        match name {
+            "expires" => signed.expires = value.parse().unwrap(),
+            "signature" => signed.signature = value.to_string(),
+            _ => panic!("unexpected parameter {}", name),
+        }
+    }
+    signed
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn finished_jobs_keep_progress_errors_and_artifacts(pool: PgPool) {
+    let db = Database::new(pool);
+
+    let reader = db.clone();
+    let job = jobs::track(&db, import_job(), |progress| async move {
+        progress.advance(0, Some(4)).await?;
+        progress.advance(2, None).await?;
+        let running = reader.jobs().get_by_id(progress.job_id()).await?.unwrap();
+        assert_eq!(running.status, JOB_RUNNING);
+        assert_eq!(running.progress_percent, 50);
+
+        progress.error("row 3: unknown unit BOX").await?;
+        progress.advance(4, None).await?;
+        progress.artifact("rejected.csv", "text/csv", b"row,error\n3,unknown unit BOX\n").await?;
+        Ok(())
+    })
+    .await
+    .unwrap();
+    assert_eq!(job.status, JOB_SUCCEEDED);
+    assert_eq!(job.progress_percent, 100);
+    assert_eq!((job.processed, job.total), (4, Some(4)));
+    assert_eq!(job.errors, vec!["row 3: unknown unit BOX".to_string()]);
+    assert!(job.is_finished());
+
+    let detail = jobs::detail(&db, SECRET, &JobConfig { download_ttl_secs: 600 }, job).await.unwrap();
+    assert_eq!(detail.artifacts.len(), 1);
+    let artifact = &detail.artifacts[0];
+    assert_eq!(artifact.artifact.size_bytes, 29);
+    assert!(artifact.download_url.starts_with(&format!("/api/job-artifacts/{}?", artifact.artifact.artifact_id)));
+
+    let (_, content) = db.jobs().artifact_content(artifact.artifact.artifact_id).await.unwrap().unwrap();
+    assert_eq!(content, b"row,error\n3,unknown unit BOX\n");
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn failing_work_fails_the_job_with_its_error(pool: PgPool) {
+    let db = Database::new(pool);
+
+    let job = jobs::track(&db, import_job(), |progress| async move {
+        progress.advance(1, Some(10)).await?;
+        Err(AppError::validation("column item_code is missing"))
+    })
+    .await
+    .unwrap();
+    assert_eq!(job.status, JOB_FAILED);
+    assert_eq!(job.progress_percent, 10);
+    assert!(job.errors.last().unwrap().contains("column item_code is missing"));
+    assert!(job.completed_at.is_some());
+
+    let failed = db
+        .jobs()
+        .list(JobFilter { status: Some(JOB_FAILED.to_string()), ..Default::default() }, KeysetQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(failed.data.len(), 1);
+    assert_eq!(failed.data[0].job_id, job.job_id);
+}
+
+#[test]
+fn download_urls_only_work_as_signed_and_until_they_expire() {
+    let artifact = JobArtifact {
+        artifact_id: 7,
+        job_id: 1,
+        file_name: "items.csv".to_string(),
+        content_type: "text/csv".to_string(),
+        size_bytes: 10,
+        created_at: Utc::now(),
+    };
+    let expires_at = Utc::now() + Duration::minutes(10);
+    let signed = signature_of(&jobs::signed(SECRET, artifact, expires_at).download_url);
+
+    assert!(jobs::verify(SECRET, 7, &signed, Utc::now()));
+    assert!(!jobs::verify(SECRET, 8, &signed, Utc::now()));
+    assert!(!jobs::verify("another-secret", 7, &signed, Utc::now()));
+    assert!(!jobs::verify(SECRET, 7, &signed, expires_at + Duration::seconds(1)));
+
+    let extended = ArtifactSignature { expires: signed.expires + 3600, signature: signed.signature.clone() };
+    assert!(!jobs::verify(SECRET, 7, &extended, Utc::now()));
+    let garbled = ArtifactSignature { expires: signed.expires, signature: "not-hex".to_string() };
+    assert!(!jobs::verify(SECRET, 7, &garbled, Utc::now()));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn report_exports_leave_the_csv_on_the_job(pool: PgPool) {
+    let db = Database::new(pool);
+    let report = db
+        .reports()
+        .create(CreateReportDefinition {
+            report_code: "ITEMS".to_string(),
+            report_name: "Items".to_string(),
+            description: None,
+            entity: "ITEMS".to_string(),
+            columns: vec!["item_code".to_string()],
+            filters: vec![],
+            group_by: vec![],
+            aggregates: vec![],
+        })
+        .await
+        .unwrap();
+
+    let queued = reports::export(&db, report).await.unwrap();
+    assert_eq!(queued.job_type, JOB_REPORT);
+    assert_eq!(queued.reference_type.as_deref(), Some("REPORT"));
+
+    let mut job = queued;
+    for _ in 0..100 {
+        if job.is_finished() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        job = db.jobs().get_by_id(job.job_id).await.unwrap().unwrap();
+    }
+    assert_eq!(job.status, JOB_SUCCEEDED, "{:?}", job.errors);
+    assert_eq!(job.processed, 4);
+
+    let artifacts = db.jobs().artifacts(job.job_id).await.unwrap();
+    assert!(artifacts[0].file_name.starts_with("items-"));
+    let (_, content) = db.jobs().artifact_content(artifacts[0].artifact_id).await.unwrap().unwrap();
+    let csv = String::from_utf8(content).unwrap();
+    let mut lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.remove(0), "Item code");
+    lines.sort();
+    assert_eq!(lines, ["ITM001", "ITM002", "ITM003", "ITM004"]);
+}
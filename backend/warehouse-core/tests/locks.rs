@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use warehouse_core::config::RedisConfig;
+use warehouse_core::JobLocks;
+
+const EVERY: Duration = Duration::from_secs(60);
+
+/// Scheduler locks in the Redis at REDIS_URL; the Redis tests are skipped without one
+fn redis() -> Option<RedisConfig> {
+    let url = std::env::var("REDIS_URL").ok()?;
+    Some(RedisConfig { url, password: std::env::var("REDIS_PASSWORD").ok(), scheduler_locks: true })
+}
+
+#[tokio::test]
+async fn a_replica_running_alone_runs_every_job() {
+    let config = RedisConfig { url: "redis://unreachable:6379".to_string(), password: None, scheduler_locks: false };
+    let locks = JobLocks::from_config(&config).await.unwrap();
+    assert_eq!(locks.run("billing-capture", EVERY, async { 1 }).await, Some(1));
+    assert_eq!(locks.run("billing-capture", EVERY, async { 2 }).await, Some(2));
+}
+
+#[tokio::test]
+async fn only_the_leading_replica_runs_a_job() {
+    let Some(config) = redis() else {
+        return;
+    };
+    let job = format!("test-{}", uuid::Uuid::new_v4());
+    let leader = JobLocks::from_config(&config).await.unwrap();
+    let follower = JobLocks::from_config(&config).await.unwrap();
+
+    assert_eq!(leader.run(&job, EVERY, async { "ran" }).await, Some("ran"));
+    // The leader keeps the lease between runs, and its next turn renews it
+    assert_eq!(follower.run(&job, EVERY, async { "ran" }).await, None);
+    assert_eq!(leader.run(&job, EVERY, async { "ran again" }).await, Some("ran again"));
+    assert_eq!(follower.run(&job, EVERY, async { "ran" }).await, None);
+
+    // Other jobs are led separately
+    let other = format!("test-{}", uuid::Uuid::new_v4());
+    assert_eq!(follower.run(&other, EVERY, async { "ran" }).await, Some("ran"));
+}
@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+
+use warehouse_core::config::PaginationConfig;
+use warehouse_core::{paging, AppError};
+use warehouse_db::{ORDER_ORDER, WAREHOUSE_ORDER};
+use warehouse_models::*;
+
+fn page(page: i64, limit: i64) -> PaginationQuery {
+    PaginationQuery { page: Some(page), limit: Some(limit), sort_order: None, ..Default::default() }
+}
+
+fn rejection(result: Result<(), AppError>) -> String {
+    match result {
+        Err(AppError::Validation(message)) => message,
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn out_of_range_pages_and_limits_are_rejected() {
+    let config = PaginationConfig::default();
+    assert!(paging::check_page(&config, &ORDER_ORDER, &page(3, 100)).is_ok());
+
+    assert_eq!(rejection(paging::check_page(&config, &ORDER_ORDER, &page(0, 20))), "page must be 1 or more, not 0");
+    assert_eq!(
+        rejection(paging::check_page(&config, &ORDER_ORDER, &page(1, 101))),
+        "limit must be between 1 and 100 for orders, not 101"
+    );
+
+    let keyset = |after_id, limit| KeysetQuery { after_id, limit };
+    assert!(paging::check_keyset(&config, "movements", &keyset(Some(40), Some(200))).is_ok());
+    assert!(paging::check_keyset(&config, "movements", &keyset(None, Some(0))).is_err());
+    assert!(paging::check_keyset(&config, "movements", &keyset(Some(-1), None)).is_err());
+}
+
+#[test]
+fn limits_can_be_lowered_per_resource() {
+    let config = PaginationConfig {
+        page_size_limits: BTreeMap::from([("movements".to_string(), 50), ("orders".to_string(), 500)]),
+    };
+
+    let keyset = KeysetQuery { after_id: None, limit: Some(51) };
+    assert_eq!(
+        rejection(paging::check_keyset(&config, "movements", &keyset)),
+        "limit must be between 1 and 50 for movements, not 51"
+    );
+    assert!(paging::check_keyset(&config, "notifications", &keyset).is_ok());
+
+    // The store's own ceiling still holds
+    assert!(paging::check_page(&config, &ORDER_ORDER, &page(1, 101)).is_err());
+}
+
+#[test]
+fn unknown_sort_fields_name_the_allowed_ones() {
+    let config = PaginationConfig::default();
+    let sorted = |sort_by: &str, sort_order: &str| PaginationQuery {
+        sort_by: Some(sort_by.to_string()),
+        sort_order: Some(sort_order.to_string()),
+        ..page(1, 20)
+    };
+
+    assert!(paging::check_page(&config, &WAREHOUSE_ORDER, &sorted("warehouse_name", "asc")).is_ok());
+    assert_eq!(
+        rejection(paging::check_page(&config, &WAREHOUSE_ORDER, &sorted("city", "ASC"))),
+        "warehouses cannot be sorted by city; sort_by must be one of: warehouse_name"
+    );
+    assert_eq!(
+        rejection(paging::check_page(&config, &WAREHOUSE_ORDER, &sorted("warehouse_name", "up"))),
+        "sort_order must be ASC or DESC, not UP"
+    );
+    assert_eq!(
+        rejection(paging::check_page(&config, &ORDER_ORDER, &sorted("order_id", "ASC"))),
+        "orders are listed in DESC order only"
+    );
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+use tokio::io::AsyncReadExt;
+use warehouse_core::{printing, AppError};
+use warehouse_db::Database;
+use warehouse_models::*;
+
+const LPN_LABEL: &str = "^XA^FO50,50^BCN,100^FD{{lpn_number}}^FS^FO50,200^FD{{ item_code }}^FS^XZ";
+
+fn fields(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+}
+
+#[test]
+fn placeholders_are_filled_and_escaped_for_the_format() {
+    let values = fields(&[("lpn_number", "LPN0001"), ("item_code", "SEM-50")]);
+    assert_eq!(
+        printing::render(LPN_LABEL, TEMPLATE_ZPL, &values).unwrap(),
+        "^XA^FO50,50^BCN,100^FDLPN0001^FS^FO50,200^FDSEM-50^FS^XZ"
+    );
+
+    let error = printing::render(LPN_LABEL, TEMPLATE_ZPL, &fields(&[("lpn_number", "^XZ")])).unwrap_err();
+    assert!(error.contains("cannot contain ^ or ~"), "{}", error);
+    let error = printing::render(LPN_LABEL, TEMPLATE_ZPL, &HashMap::new()).unwrap_err();
+    assert_eq!(error, "no value for item_code, lpn_number");
+
+    let html = printing::render("<p>{{name}}</p>", TEMPLATE_HTML, &fields(&[("name", "Toko <Maju> & Co")])).unwrap();
+    assert_eq!(html, "<p>Toko &lt;Maju&gt; &amp; Co</p>");
+    // An unclosed brace is left as written
+    assert_eq!(printing::render("{{name", TEMPLATE_HTML, &HashMap::new()).unwrap(), "{{name");
+}
+
+#[test]
+fn zpl_printers_default_to_port_9100() {
+    assert_eq!(printing::zpl_endpoint("10.0.4.21"), ("10.0.4.21", 9100));
+    assert_eq!(printing::zpl_endpoint(" zebra-pack-2:6101 "), ("zebra-pack-2", 6101));
+}
+
+/// Fake ZPL printer; returns its address and what it was sent over one connection
+async fn spawn_printer() -> (String, tokio::task::JoinHandle<String>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let received = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut label = String::new();
+        socket.read_to_string(&mut label).await.unwrap();
+        label
+    });
+    (addr.to_string(), received)
+}
+
+fn printer(code: &str, address: String, station_id: Option<i32>, default_for: &[&str]) -> CreatePrinter {
+    CreatePrinter {
+        printer_code: code.to_string(),
+        name: format!("Zebra {}", code),
+        printer_type: PRINTER_ZPL.to_string(),
+        address,
+        station_id,
+        default_for: default_for.iter().map(|purpose| purpose.to_string()).collect(),
+    }
+}
+
+fn request(station_id: Option<i32>) -> PrintRequest {
+    PrintRequest {
+        purpose: TEMPLATE_LPN_LABEL.to_string(),
+        printer_id: None,
+        station_id,
+        copies: Some(2),
+        fields: fields(&[("lpn_number", "LPN0001"), ("item_code", "SEM-50")]),
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn jobs_print_on_the_station_printer_before_the_warehouse_one(pool: PgPool) {
+    let db = Database::new(pool);
+    let error = printing::dispatch(&db, 1, request(None)).await.unwrap_err();
+    assert!(matches!(&error, AppError::BusinessRule(message) if message.contains("no template")), "{:?}", error);
+
+    let template = db
+        .templates()
+        .create(CreatePrintTemplate {
+            template_code: "LPN-4X6".to_string(),
+            name: "LPN label 4x6".to_string(),
+            purpose: TEMPLATE_LPN_LABEL.to_string(),
+            format: TEMPLATE_ZPL.to_string(),
+            body: LPN_LABEL.to_string(),
+            change_note: None,
+        })
+        .await
+        .unwrap();
+    let assignment = AssignPrintTemplate { template_id: template.template.template_id, pinned_version: None };
+    db.templates().assign(1, assignment).await.unwrap().unwrap();
+    let error = printing::dispatch(&db, 1, request(None)).await.unwrap_err();
+    assert!(matches!(&error, AppError::BusinessRule(message) if message.contains("no printer")), "{:?}", error);
+
+    let station = db
+        .stations()
+        .create_station(
+            1,
+            CreateStation {
+                station_code: "PACK-2".to_string(),
+                station_name: "Packing bench 2".to_string(),
+                station_type: STATION_PACKING.to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    let (floor_address, floor) = spawn_printer().await;
+    let (bench_address, bench) = spawn_printer().await;
+    db.printers().create(1, printer("FLOOR", floor_address, None, &[TEMPLATE_LPN_LABEL])).await.unwrap();
+    let at_bench = printer("PACK-2", bench_address, Some(station.station_id), &[TEMPLATE_LPN_LABEL]);
+    db.printers().create(1, at_bench).await.unwrap();
+
+    let sent = printing::dispatch(&db, 1, request(Some(station.station_id))).await.unwrap();
+    assert_eq!((sent.printer.printer_code.as_str(), sent.version, sent.copies), ("PACK-2", 1, 2));
+    assert!(sent.printer.last_printed_at.is_some());
+    let label = "^XA^FO50,50^BCN,100^FDLPN0001^FS^FO50,200^FDSEM-50^FS^XZ";
+    assert_eq!(bench.await.unwrap(), label.repeat(2));
+
+    let sent = printing::dispatch(&db, 1, request(None)).await.unwrap();
+    assert_eq!(sent.printer.printer_code, "FLOOR");
+    assert_eq!(floor.await.unwrap(), label.repeat(2));
+}
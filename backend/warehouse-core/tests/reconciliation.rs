@@ -0,0 +1,32 @@
+use warehouse_core::reconciliation;
+use warehouse_models::chrono::NaiveDate;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn query() -> ErpExtractQuery {
+    ErpExtractQuery { as_of: NaiveDate::from_ymd_opt(2025, 10, 31), source: Some("SAP".to_string()) }
+}
+
+#[test]
+fn parses_a_csv_extract() {
+    let body = "item_code,warehouse_code,quantity\nITM-1, WH-JKT ,12.5\nITM-2,WH-JKT,0\n";
+    let extract = reconciliation::parse_csv(query(), body).unwrap();
+
+    assert_eq!(extract.source.as_deref(), Some("SAP"));
+    assert_eq!(extract.lines.len(), 2);
+    assert_eq!(extract.lines[0].warehouse_code, "WH-JKT");
+    assert_eq!(extract.lines[0].quantity, Decimal::new(125, 1));
+}
+
+#[test]
+fn names_the_line_that_does_not_parse() {
+    let body = "item_code,warehouse_code,quantity\nITM-1,WH-JKT,12\nITM-2,WH-JKT,lots\n";
+    let error = reconciliation::parse_csv(query(), body).unwrap_err();
+    assert!(error.to_string().contains("line 3"));
+}
+
+#[test]
+fn requires_the_extract_date() {
+    let body = "item_code,warehouse_code,quantity\nITM-1,WH-JKT,12\n";
+    assert!(reconciliation::parse_csv(ErpExtractQuery::default(), body).is_err());
+}
@@ -0,0 +1,171 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{http::HeaderMap, http::StatusCode, routing::post, Json, Router};
+use serde_json::Value;
+use sqlx::PgPool;
+use warehouse_core::config::EventConfig;
+use warehouse_core::replay;
+use warehouse_db::Database;
+use warehouse_models::chrono::{Duration, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// Fake consumer endpoint; keeps the event ids of authorized batches
+async fn spawn_consumer(received: Arc<Mutex<Vec<i64>>>) -> String {
+    let app = Router::new().route(
+        "/events",
+        post(move |headers: HeaderMap, Json(body): Json<Value>| async move {
+            if headers.get("authorization").and_then(|v| v.to_str().ok()) != Some("Bearer secret") {
+                return StatusCode::UNAUTHORIZED;
+            }
+            let events = body["events"].as_array().unwrap();
+            received.lock().unwrap().extend(events.iter().map(|e| e["event_id"].as_i64().unwrap()));
+            StatusCode::NO_CONTENT
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}/events", addr)
+}
+
+fn config(max_attempts: i32, retry_base_secs: u64) -> EventConfig {
+    EventConfig { delivery_interval_secs: 0, max_attempts, retry_base_secs }
+}
+
+fn consumer(name: &str, endpoint_url: String, api_key: &str) -> CreateEventConsumer {
+    CreateEventConsumer {
+        consumer_name: name.to_string(),
+        endpoint_url,
+        api_key: Some(api_key.to_string()),
+        position: None,
+    }
+}
+
+async fn bump_stock(pool: &PgPool, item_id: i32) {
+    sqlx::query("UPDATE warehouse.stock_inventory SET quantity_on_hand = quantity_on_hand + 1 WHERE item_id = $1")
+        .bind(item_id)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+async fn event_ids(pool: &PgPool) -> Vec<i64> {
+    sqlx::query_scalar("SELECT event_id FROM warehouse.stock_events ORDER BY event_id").fetch_all(pool).await.unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn consumers_are_sent_the_stream_and_can_be_rewound(pool: PgPool) {
+    let db = Database::new(pool.clone());
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let url = spawn_consumer(received.clone()).await;
+    let analytics = db.event_consumers().create(consumer("analytics", url.clone(), "secret")).await.unwrap();
+    let rejected = db.event_consumers().create(consumer("audit", url.clone(), "wrong")).await.unwrap();
+    assert!(db.event_consumers().create(consumer("analytics", url, "secret")).await.is_err());
+
+    let seeded = event_ids(&pool).await;
+    assert_eq!(replay::deliver_pending(&db, &config(8, 30)).await.unwrap(), seeded.len());
+    bump_stock(&pool, 3).await;
+    assert_eq!(replay::deliver_pending(&db, &config(8, 30)).await.unwrap(), 1);
+    let all = event_ids(&pool).await;
+    assert_eq!(*received.lock().unwrap(), all);
+
+    let audit = db.event_consumers().get_by_id(rejected.consumer_id).await.unwrap().unwrap();
+    assert_eq!(audit.position, 0);
+    assert!(audit.last_error.as_deref().unwrap().contains("401"));
+
+    received.lock().unwrap().clear();
+    let request = ReplayRequest {
+        target: REPLAY_CONSUMER.to_string(),
+        consumer_id: Some(analytics.consumer_id),
+        from_event_id: all[2],
+    };
+    let report = replay::replay(&db, &config(8, 30), &request).await.unwrap();
+    assert_eq!(report.events, all.len() as i64 - 2);
+    assert_eq!(report.rebuilt, report.events);
+    assert_eq!(*received.lock().unwrap(), all[2..]);
+
+    let analytics = db.event_consumers().get_by_id(analytics.consumer_id).await.unwrap().unwrap();
+    assert_eq!(analytics.position, *all.last().unwrap());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn corrupted_projections_are_rebuilt_by_replay(pool: PgPool) {
+    let db = Database::new(pool.clone()).with_event_sourced_stock(true);
+    bump_stock(&pool, 2).await;
+    db.stock_events().project().await.unwrap();
+    db.read_models().project(500).await.unwrap();
+    let expected = db.stock().snapshot(StockFilter::default()).await.unwrap().balances;
+    let on_hand = |balances: &[StockBalance]| balances.iter().map(|b| b.quantity_on_hand).collect::<Vec<_>>();
+
+    sqlx::query("UPDATE warehouse.stock_event_balances SET quantity_on_hand = 0").execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM warehouse.stock_read_model WHERE item_id = 2").execute(&pool).await.unwrap();
+
+    let from = |target: &str| ReplayRequest { target: target.to_string(), consumer_id: None, from_event_id: 0 };
+    let balances = replay::replay(&db, &config(8, 30), &from(REPLAY_BALANCES)).await.unwrap();
+    assert_eq!(balances.rebuilt, 4);
+    let restored = db.stock().snapshot(StockFilter::default()).await.unwrap().balances;
+    assert_eq!(on_hand(&restored), on_hand(&expected));
+
+    replay::replay(&db, &config(8, 30), &from(REPLAY_READ_MODEL)).await.unwrap();
+    let rebar = db.read_models().stock(StockFilter { item_id: Some(2), ..Default::default() }).await.unwrap();
+    assert_eq!(rebar[0].quantity_on_hand, Decimal::from(1001));
+    assert_eq!(db.read_models().status().await.unwrap().pending_events, 0);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn failing_batches_back_off_then_dead_letter_and_can_be_redriven(pool: PgPool) {
+    let db = Database::new(pool.clone());
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let url = spawn_consumer(received.clone()).await;
+    let audit = db.event_consumers().create(consumer("audit", url, "wrong")).await.unwrap();
+    let all = event_ids(&pool).await;
+
+    // A failure holds the consumer back for the retry delay
+    assert_eq!(replay::deliver_pending(&db, &config(3, 60)).await.unwrap(), 0);
+    let waiting = db.event_consumers().get_by_id(audit.consumer_id).await.unwrap().unwrap();
+    assert_eq!(waiting.failed_attempts, 1);
+    assert!(waiting.next_attempt_at.unwrap() > Utc::now() + Duration::seconds(50));
+    assert!(db.event_consumers().behind().await.unwrap().is_empty());
+
+    // With no delay the retries run out and the batch is parked
+    let retries = config(3, 0);
+    sqlx::query("UPDATE warehouse.event_consumers SET next_attempt_at = NULL").execute(&pool).await.unwrap();
+    replay::deliver_pending(&db, &retries).await.unwrap();
+    replay::deliver_pending(&db, &retries).await.unwrap();
+    let moved_on = db.event_consumers().get_by_id(audit.consumer_id).await.unwrap().unwrap();
+    assert_eq!(moved_on.position, *all.last().unwrap());
+    assert_eq!(moved_on.failed_attempts, 0);
+
+    let parked = db.event_consumers().dead_letters(DeadLetterFilter::default(), KeysetQuery::default()).await.unwrap();
+    assert_eq!(parked.data.len(), 1);
+    let dead_letter = &parked.data[0];
+    assert_eq!((dead_letter.first_event_id, dead_letter.last_event_id), (all[0], *all.last().unwrap()));
+    assert_eq!(dead_letter.attempts, 3);
+    assert!(dead_letter.last_error.contains("401"));
+    let alerts = db
+        .notifications()
+        .list(
+            NotificationFilter { kind: Some(NOTIFY_EVENTS_DEAD_LETTERED.to_string()), ..Default::default() },
+            KeysetQuery::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(alerts.data[0].reference_id, Some(audit.consumer_id));
+
+    assert!(replay::redrive(&db, dead_letter.dead_letter_id).await.is_err());
+    sqlx::query("UPDATE warehouse.event_consumers SET api_key = 'secret'").execute(&pool).await.unwrap();
+    let redriven = replay::redrive(&db, dead_letter.dead_letter_id).await.unwrap().unwrap();
+    assert_eq!(redriven.status, DEAD_LETTER_REDRIVEN);
+    assert_eq!(redriven.attempts, 5);
+    assert_eq!(*received.lock().unwrap(), all);
+    assert!(replay::redrive(&db, dead_letter.dead_letter_id).await.is_err());
+}
+
+#[test]
+fn retry_delay_doubles_up_to_an_hour() {
+    let config = config(8, 30);
+    let delays: Vec<_> = [1, 2, 3, 20].into_iter().map(|n| replay::retry_delay(&config, n).num_seconds()).collect();
+    assert_eq!(delays, [30, 60, 120, 3600]);
+}
@@ -0,0 +1,171 @@
+use sqlx::PgPool;
+use warehouse_core::config::MailConfig;
+use warehouse_core::{notify, reports};
+use warehouse_db::Database;
+use warehouse_models::chrono::{DateTime, Duration, Utc};
+use warehouse_models::*;
+
+fn config(sendmail_command: &str) -> MailConfig {
+    MailConfig { from_address: "reports@warehouse.example".to_string(), sendmail_command: sendmail_command.to_string() }
+}
+
+fn at(timestamp: &str) -> DateTime<Utc> {
+    timestamp.parse().unwrap()
+}
+
+async fn scheduled_report(db: &Database, format: &str) -> ReportSchedule {
+    let report = db
+        .reports()
+        .create(CreateReportDefinition {
+            report_code: "ITEMS".to_string(),
+            report_name: "Items <weekly>".to_string(),
+            description: None,
+            entity: "ITEMS".to_string(),
+            columns: vec!["item_code".to_string(), "item_name".to_string()],
+            filters: vec![],
+            group_by: vec![],
+            aggregates: vec![],
+        })
+        .await
+        .unwrap();
+
+    db.reports()
+        .create_schedule(
+            report.definition.report_id,
+            CreateReportSchedule {
+                cron_expression: "0 7 * * 1".to_string(),
+                recipients: vec!["ops@warehouse.example".to_string(), "finance@warehouse.example".to_string()],
+                format: Some(format.to_string()),
+            },
+        )
+        .await
+        .unwrap()
+}
+
+#[test]
+fn cron_finds_the_next_matching_minute() {
+    let weekdays = CronSchedule::parse("30 7 * * 1-5").unwrap();
+    // Friday evening rolls over to Monday morning
+    assert_eq!(weekdays.next_after(at("2025-11-21T18:00:00Z")), Some(at("2025-11-24T07:30:00Z")));
+    assert_eq!(weekdays.next_after(at("2025-11-24T07:29:59Z")), Some(at("2025-11-24T07:30:00Z")));
+    assert_eq!(weekdays.next_after(at("2025-11-24T07:30:00Z")), Some(at("2025-11-25T07:30:00Z")));
+
+    let quarter_hours = CronSchedule::parse("*/15 * * * *").unwrap();
+    assert_eq!(quarter_hours.next_after(at("2025-11-24T23:50:00Z")), Some(at("2025-11-25T00:00:00Z")));
+
+    // Either day field matches when both are restricted
+    let first_or_sunday = CronSchedule::parse("0 0 1 * 0").unwrap();
+    assert_eq!(first_or_sunday.next_after(at("2025-11-24T00:00:00Z")), Some(at("2025-11-30T00:00:00Z")));
+    assert_eq!(first_or_sunday.next_after(at("2025-11-30T00:00:00Z")), Some(at("2025-12-01T00:00:00Z")));
+
+    assert_eq!(CronSchedule::parse("0 0 31 2 *").unwrap().next_after(at("2025-11-24T00:00:00Z")), None);
+    assert!(CronSchedule::parse("0 7 * *").is_err());
+    assert!(CronSchedule::parse("60 7 * * *").is_err());
+    assert!(CronSchedule::parse("0 7 * * */0").is_err());
+}
+
+#[test]
+fn mime_message_keeps_headers_on_one_line() {
+    let email = notify::Email {
+        to: vec!["ops@warehouse.example".to_string()],
+        subject: "Items\r\nBcc: everyone@example.com".to_string(),
+        body: "<p>hello</p>".to_string(),
+        attachment: Some(("items.csv".to_string(), "text/csv".to_string(), b"item_code\nITM001\n".to_vec())),
+    };
+
+    let message = String::from_utf8(notify::mime_message("reports@warehouse.example", &email)).unwrap();
+    assert!(message.contains("Subject: Items  Bcc: everyone@example.com\r\n"));
+    assert!(!message.contains("\r\nBcc:"));
+    assert!(message.contains("Content-Disposition: attachment; filename=\"items.csv\""));
+    // "<p>hello</p>" and the CSV, base64 encoded
+    assert!(message.contains("PHA+aGVsbG88L3A+\r\n"));
+    assert!(message.contains("aXRlbV9jb2RlCklUTTAwMQo=\r\n"));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn schedules_start_at_their_next_run_and_are_claimed_once(pool: PgPool) {
+    let db = Database::new(pool);
+    let schedule = scheduled_report(&db, REPORT_FORMAT_CSV).await;
+    assert!(schedule.next_run_at > Utc::now());
+    assert!(db.reports().claim_due_schedules(Utc::now()).await.unwrap().is_empty());
+
+    let due_at = schedule.next_run_at + Duration::minutes(1);
+    let claimed = db.reports().claim_due_schedules(due_at).await.unwrap();
+    assert_eq!(claimed.len(), 1);
+    assert_eq!(claimed[0].next_run_at, schedule.next_run_at + Duration::days(7));
+    assert!(db.reports().claim_due_schedules(due_at).await.unwrap().is_empty());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn delivery_mails_the_report_as_csv(pool: PgPool) {
+    let db = Database::new(pool);
+    let schedule = scheduled_report(&db, REPORT_FORMAT_CSV).await;
+
+    // Stand-in for sendmail: keeps the message and its arguments
+    let dir = std::env::temp_dir().join(format!("report-sendmail-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("sendmail");
+    std::fs::write(
+        &script,
+        format!("#!/bin/sh\ncat > {dir}/message\necho \"$@\" > {dir}/args\n", dir = dir.display()),
+    )
+    .unwrap();
+    std::process::Command::new("chmod").arg("+x").arg(&script).status().unwrap();
+
+    let run = reports::deliver(&db, &config(script.to_str().unwrap()), &schedule).await.unwrap();
+    assert_eq!(run.status, REPORT_DELIVERY_SENT, "{:?}", run.error);
+    assert_eq!(run.row_count, 4);
+
+    let args = std::fs::read_to_string(dir.join("args")).unwrap();
+    assert_eq!(args.trim_end(), "-t -i");
+    let message = std::fs::read_to_string(dir.join("message")).unwrap();
+    assert!(message.starts_with("From: reports@warehouse.example\r\n"));
+    assert!(message.contains("To: ops@warehouse.example, finance@warehouse.example\r\n"));
+    assert!(message.contains("Subject: Items <weekly>\r\n"));
+    assert!(message.contains("filename=\"items-"));
+
+    let schedule = db.reports().get_schedule(schedule.schedule_id).await.unwrap().unwrap();
+    assert_eq!(schedule.last_run_at, Some(run.started_at));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn html_reports_are_rendered_into_the_body(pool: PgPool) {
+    let db = Database::new(pool);
+    let schedule = scheduled_report(&db, REPORT_FORMAT_HTML).await;
+    let report = db.reports().get_by_id(schedule.report_id).await.unwrap().unwrap();
+    let result = db.reports().run(&report).await.unwrap();
+
+    let email = reports::email(&schedule, &report.definition, &result).unwrap();
+    assert!(email.attachment.is_none());
+    assert!(email.body.starts_with("<h2>Items &lt;weekly&gt;</h2><p>4 rows</p><table"));
+    assert!(email.body.contains("<tr><th>item_code</th><th>item_name</th></tr>"));
+    assert!(email.body.contains("<td>Safety Helmet</td>"));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn failed_delivery_is_recorded_and_raises_an_alert(pool: PgPool) {
+    let db = Database::new(pool);
+    let schedule = scheduled_report(&db, REPORT_FORMAT_CSV).await;
+
+    let run = reports::deliver(&db, &config("/nonexistent/sendmail"), &schedule).await.unwrap();
+    assert_eq!(run.status, REPORT_DELIVERY_FAILED);
+    assert_eq!(run.row_count, 0);
+    assert!(run.error.is_some());
+
+    let runs = db.reports().deliveries(schedule.schedule_id, KeysetQuery::default()).await.unwrap();
+    assert_eq!(runs.data.len(), 1);
+    assert_eq!(runs.data[0].run_id, run.run_id);
+
+    let alerts = db
+        .notifications()
+        .list(
+            NotificationFilter { kind: Some(NOTIFY_REPORT_DELIVERY_FAILED.to_string()), ..Default::default() },
+            KeysetQuery::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(alerts.data.len(), 1);
+    assert_eq!(alerts.data[0].reference_id, Some(schedule.schedule_id));
+}
@@ -0,0 +1,112 @@
+use sqlx::PgPool;
+use warehouse_core::backup;
+use warehouse_core::config::BackupConfig;
+use warehouse_core::restore::{self, RestoreOptions, RestoreTarget};
+use warehouse_db::Database;
+use warehouse_models::chrono::Utc;
+use warehouse_models::*;
+
+async fn export(db: &Database, name: &str) -> Vec<u8> {
+    let dir = std::env::temp_dir().join(format!("warehouse-restore-{}-{}", name, std::process::id()));
+    let config = BackupConfig {
+        store_url: format!("file://{}", dir.display()),
+        s3_command: "aws".to_string(),
+        s3_endpoint_url: None,
+    };
+    let requested = backup::request(db).await.unwrap();
+    let completed = backup::run(db, &config, &requested).await.unwrap();
+    let archive = backup::download(&config, &completed).await.unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+    archive
+}
+
+fn into_tenant(code: &str) -> RestoreOptions {
+    RestoreOptions {
+        target: RestoreTarget::Tenant {
+            source_schema: "warehouse".to_string(),
+            tenant: CreateTenant { tenant_code: code.to_string(), tenant_name: code.to_string(), warehouses: Vec::new() },
+        },
+        replace: false,
+    }
+}
+
+async fn count(pool: &PgPool, sql: &str) -> i64 {
+    sqlx::query_scalar(sql).fetch_one(pool).await.unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn restores_an_export_into_a_new_tenant(pool: PgPool) {
+    let db = Database::new(pool.clone());
+    let archive = export(&db, "tenant").await;
+
+    let report = restore::restore(&db, archive.as_slice(), into_tenant("drill")).await.unwrap();
+    assert!(report.committed);
+    assert_eq!(report.tables.len(), BACKUP_TABLES.len());
+    assert!(report.tables.iter().all(|table| table.rows_restored == table.rows_expected));
+    assert!(report.tables.iter().all(|table| table.schema == "tenant_drill"));
+
+    for table in BACKUP_TABLES {
+        let shared = count(&pool, &format!("SELECT COUNT(*) FROM warehouse.{}", table)).await;
+        assert_eq!(count(&pool, &format!("SELECT COUNT(*) FROM tenant_drill.{}", table)).await, shared, "{}", table);
+    }
+
+    // Sequences continue after the restored keys
+    sqlx::query(
+        "INSERT INTO tenant_drill.warehouses (warehouse_code, warehouse_name) VALUES ('DRILL-NEW', 'After restore')",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn refuses_an_environment_that_already_has_data(pool: PgPool) {
+    let db = Database::new(pool);
+    let archive = export(&db, "populated").await;
+
+    let options = RestoreOptions { target: RestoreTarget::Environment, replace: false };
+    assert!(restore::restore(&db, archive.as_slice(), options).await.is_err());
+
+    let options = RestoreOptions { target: RestoreTarget::Environment, replace: true };
+    assert!(restore::restore(&db, archive.as_slice(), options).await.unwrap().committed);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn reports_rows_referencing_missing_data_and_restores_nothing(pool: PgPool) {
+    let db = Database::new(pool.clone());
+    let manifest = BackupManifest {
+        format: BACKUP_FORMAT.to_string(),
+        format_version: BACKUP_FORMAT_VERSION,
+        backup_id: 7,
+        schema_version: None,
+        created_at: Utc::now(),
+        tables: vec![
+            BackupTableCount { schema: "warehouse".to_string(), table: "warehouses".to_string(), rows: 1 },
+            BackupTableCount { schema: "warehouse".to_string(), table: "stock_inventory".to_string(), rows: 1 },
+        ],
+    };
+    let archive = format!(
+        "{}\n{}\n{}\n",
+        serde_json::to_string(&manifest).unwrap(),
+        r#"{"schema":"warehouse","table":"warehouses","row":{"warehouse_id":1,"warehouse_code":"W1","warehouse_name":"One"}}"#,
+        r#"{"schema":"warehouse","table":"stock_inventory","row":{"stock_id":1,"item_id":999,"warehouse_id":1,"quantity_on_hand":5,"quantity_reserved":0,"quantity_quarantined":0}}"#,
+    );
+
+    let report = restore::restore(&db, archive.as_bytes(), into_tenant("broken")).await.unwrap();
+    assert!(!report.committed);
+    let stock = &report.tables[1];
+    assert_eq!(stock.rows_restored, 0);
+    assert_eq!(stock.violations.len(), 1);
+    assert_eq!(stock.violations[0].references, "tenant_broken.items");
+    assert_eq!(stock.violations[0].rows, 1);
+    assert_eq!(count(&pool, "SELECT COUNT(*) FROM tenant_broken.warehouses").await, 0);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn rejects_archives_it_cannot_read(pool: PgPool) {
+    let db = Database::new(pool);
+    let options = || RestoreOptions { target: RestoreTarget::Environment, replace: true };
+
+    assert!(restore::restore(&db, &b""[..], options()).await.is_err());
+    assert!(restore::restore(&db, &b"{\"format\":\"other\"}\n"[..], options()).await.is_err());
+}
@@ -0,0 +1,82 @@
+use warehouse_core::routing;
+use warehouse_models::chrono::{NaiveDate, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+const DEPOT: GeoPoint = GeoPoint { latitude: -6.2, longitude: 106.8 };
+
+fn vehicle(vehicle_id: i32, code: &str, capacity_kg: i64) -> DeliveryVehicle {
+    DeliveryVehicle {
+        vehicle_id,
+        warehouse_id: 1,
+        vehicle_code: code.to_string(),
+        description: None,
+        capacity_kg: Decimal::from(capacity_kg),
+        is_active: true,
+        created_at: Utc::now(),
+    }
+}
+
+fn shipment(shipment_id: i32, weight_kg: i64) -> RoutableShipment {
+    RoutableShipment {
+        shipment_id,
+        shipment_number: format!("SHP-{:06}", shipment_id),
+        weight_kg: Decimal::from(weight_kg),
+    }
+}
+
+/// A site `north` and `east` degrees from the depot
+fn site(shipment_id: i32, north: f64, east: f64) -> RouteSite {
+    RouteSite {
+        shipment_id,
+        location: GeoPoint { latitude: DEPOT.latitude + north, longitude: DEPOT.longitude + east },
+    }
+}
+
+fn run_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 12, 2).unwrap()
+}
+
+#[test]
+fn distances_follow_the_earths_surface() {
+    let north = GeoPoint { latitude: DEPOT.latitude + 1.0, longitude: DEPOT.longitude };
+    assert!((routing::distance_km(DEPOT, north) - 111.2).abs() < 0.1);
+    assert_eq!(routing::distance_km(DEPOT, DEPOT), 0.0);
+}
+
+#[test]
+fn nearest_sites_go_first_and_the_rest_to_the_next_vehicle() {
+    let shipments = [shipment(1, 50), shipment(2, 150), shipment(3, 60), shipment(4, 400), shipment(5, 10)];
+    let sites = [site(1, 0.0, 0.01), site(2, 0.0, 0.05), site(3, 0.02, 0.0), site(4, 0.01, 0.01)];
+    let vehicles = [vehicle(1, "VAN-1", 200), vehicle(2, "TRUCK-1", 250)];
+
+    let plan = routing::plan(1, run_date(), DEPOT, &shipments, &sites, &vehicles);
+
+    let routes: Vec<(&str, Vec<i32>)> = plan
+        .routes
+        .iter()
+        .map(|route| (route.vehicle_code.as_str(), route.stops.iter().map(|stop| stop.shipment_id).collect()))
+        .collect();
+    assert_eq!(routes, [("TRUCK-1", vec![1, 3]), ("VAN-1", vec![2])]);
+
+    let truck = &plan.routes[0];
+    assert_eq!(truck.load_kg, Decimal::from(110));
+    assert_eq!(truck.stops[0].leg_km, 1.1);
+    assert_eq!(truck.stops.iter().map(|stop| stop.sequence).collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(truck.distance_km, 5.8);
+    assert_eq!(plan.routes[1].distance_km, 11.1);
+    assert_eq!(plan.distance_km, 16.9);
+
+    let unrouted: Vec<(i32, &str)> =
+        plan.unrouted.iter().map(|shipment| (shipment.shipment_id, shipment.reason.as_str())).collect();
+    assert_eq!(unrouted, [(4, "heavier than any free vehicle carries"), (5, "no site coordinates")]);
+}
+
+#[test]
+fn shipments_wait_when_no_vehicle_is_free() {
+    let plan = routing::plan(1, run_date(), DEPOT, &[shipment(1, 50)], &[site(1, 0.0, 0.01)], &[]);
+
+    assert!(plan.routes.is_empty());
+    assert_eq!(plan.unrouted[0].reason, "no vehicle is free on the day");
+    assert_eq!(plan.distance_km, 0.0);
+}
@@ -0,0 +1,37 @@
+use std::env;
+
+use warehouse_core::config::{LiveConfig, RuntimeConfig};
+
+#[tokio::test]
+async fn reloads_publish_changed_settings_to_watchers() {
+    env::set_var("RATE_LIMIT_PER_MINUTE", "120");
+    env::set_var("CORS_ORIGINS", "https://portal.example.com, https://ops.example.com");
+    env::remove_var("FEATURE_FLAGS");
+    let live = LiveConfig::new(RuntimeConfig::from_env());
+    // Take in whatever `.env` sets, as a running server would have at startup
+    live.reload().unwrap();
+    let mut changes = live.subscribe();
+
+    let current = live.current();
+    assert_eq!(current.rate_limit_per_minute, 120);
+    assert!(current.allows_origin("https://ops.example.com"));
+    assert!(!current.allows_origin("https://evil.example.com"));
+
+    // Nothing changed, nobody is woken
+    live.reload().unwrap();
+    assert!(!changes.has_changed().unwrap());
+
+    env::set_var("RATE_LIMIT_PER_MINUTE", "30");
+    env::set_var("CORS_ORIGINS", "");
+    env::set_var("FEATURE_FLAGS", "wave_autorelease,beta_portal");
+    live.reload().unwrap();
+    assert!(changes.has_changed().unwrap());
+
+    let reloaded = changes.borrow_and_update().clone();
+    assert_eq!(reloaded.rate_limit_per_minute, 30);
+    assert!(reloaded.allows_origin("https://evil.example.com"));
+    assert!(reloaded.feature("beta_portal"));
+    assert!(!reloaded.feature("dark_mode"));
+    // The handle everyone shares sees the new values too
+    assert_eq!(live.clone().current().as_ref(), reloaded.as_ref());
+}
@@ -0,0 +1,59 @@
+use warehouse_core::scales::{self, ScaleFrame};
+use warehouse_core::AppError;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn kg(value: &str) -> ScaleFrame {
+    ScaleFrame::Stable(value.parse().unwrap())
+}
+
+#[test]
+fn frames_from_common_scale_protocols() {
+    assert_eq!(scales::parse_frame("S S      12.345 kg\r\n"), Ok(kg("12.345")));
+    assert_eq!(scales::parse_frame("S D      12.300 kg"), Ok(ScaleFrame::Unstable));
+    assert_eq!(scales::parse_frame("S +"), Ok(ScaleFrame::Overload));
+    assert_eq!(scales::parse_frame("S -"), Ok(ScaleFrame::Underload));
+
+    assert_eq!(scales::parse_frame("ST,GS,+0001250.  g"), Ok(kg("1.25")));
+    assert_eq!(scales::parse_frame("ST,NT,+0004.40 lb"), Ok(kg("1.996")));
+    assert_eq!(scales::parse_frame("US,GS,+0004.40 lb"), Ok(ScaleFrame::Unstable));
+    assert_eq!(scales::parse_frame("OL,GS,+9999.99 kg"), Ok(ScaleFrame::Overload));
+
+    assert_eq!(scales::parse_frame("0.75kg"), Ok(kg("0.75")));
+    assert!(scales::parse_frame("12.5").is_err());
+    assert!(scales::parse_frame("12.5 st").is_err());
+    assert!(scales::parse_frame("S I").is_err());
+}
+
+#[test]
+fn captures_are_normalised_to_kg_and_cm() {
+    let capture = scales::normalise(CaptureMeasurement {
+        item_id: Some(3),
+        weight: Some(Decimal::from(16)),
+        weight_unit: Some("oz".to_string()),
+        length: Some(Decimal::from(12)),
+        width: Some(Decimal::from(8)),
+        height: Some("2.5".parse().unwrap()),
+        dimension_unit: Some("in".to_string()),
+        ..Default::default()
+    })
+    .unwrap();
+
+    assert_eq!(capture.weight_kg, Some("0.454".parse().unwrap()));
+    assert_eq!(capture.length_cm, Some("30.48".parse().unwrap()));
+    assert_eq!(capture.width_cm, Some("20.32".parse().unwrap()));
+    assert_eq!(capture.height_cm, Some("6.35".parse().unwrap()));
+    assert_eq!(capture.scale_output, None);
+
+    let frame = |frame: &str| CaptureMeasurement {
+        carton_id: Some(1),
+        scale_output: Some(frame.to_string()),
+        ..Default::default()
+    };
+    let scaled = scales::normalise(frame(" S S   3.120 kg ")).unwrap();
+    assert_eq!(scaled.weight_kg, Some("3.12".parse().unwrap()));
+    assert_eq!(scaled.scale_output.as_deref(), Some("S S   3.120 kg"));
+    assert!(matches!(scales::normalise(frame("S D   3.100 kg")), Err(AppError::BusinessRule(_))));
+    assert!(matches!(scales::normalise(frame("S S   0.000 kg")), Err(AppError::BusinessRule(_))));
+    assert!(matches!(scales::normalise(frame("garbage")), Err(AppError::Validation(_))));
+}
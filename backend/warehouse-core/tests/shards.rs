@@ -0,0 +1,31 @@
+use std::env;
+
+use warehouse_core::Config;
+
+#[test]
+fn shard_map_is_read_from_the_environment() {
+    env::set_var("DATABASE_SHARDS", "eu, apac");
+    env::set_var("DATABASE_SHARD_EU_URL", "postgres://eu.example.com/warehouse");
+    env::set_var("DATABASE_SHARD_EU_WAREHOUSES", "3, 4");
+    env::set_var("DATABASE_SHARD_APAC_URL", "postgres://apac.example.com/warehouse");
+    env::set_var("DATABASE_SHARD_APAC_WAREHOUSES", "7");
+
+    let config = Config::from_env_mock().unwrap();
+    config.validate().unwrap();
+    assert_eq!(config.shards.len(), 2);
+    assert_eq!(config.shards[0].name, "EU");
+    assert_eq!(config.shards[0].warehouse_ids, vec![3, 4]);
+    assert_eq!(config.database.shard_settings(&config.shards[1]).url, "postgres://apac.example.com/warehouse");
+
+    // A warehouse can only live in one place
+    env::set_var("DATABASE_SHARD_APAC_WAREHOUSES", "4,7");
+    let overlapping = Config::from_env_mock().unwrap();
+    assert!(overlapping.validate().is_err());
+
+    env::set_var("DATABASE_SHARD_APAC_WAREHOUSES", "seven");
+    assert!(Config::from_env_mock().is_err());
+
+    env::remove_var("DATABASE_SHARD_APAC_URL");
+    env::set_var("DATABASE_SHARD_APAC_WAREHOUSES", "7");
+    assert!(Config::from_env_mock().is_err());
+}
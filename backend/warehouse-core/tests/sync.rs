@@ -0,0 +1,78 @@
+use serde_json::json;
+use sqlx::PgPool;
+use warehouse_core::sync;
+use warehouse_db::Database;
+use warehouse_models::*;
+
+async fn queued(db: &Database) -> Task {
+    db.tasks()
+        .create(CreateTask {
+            warehouse_id: 1,
+            task_type: TASK_PUTAWAY.to_string(),
+            priority: None,
+            item_id: None,
+            quantity: None,
+            from_location_id: None,
+            to_location_id: None,
+            notes: None,
+            due_at: None,
+        })
+        .await
+        .unwrap()
+}
+
+fn batch(mutations: serde_json::Value) -> SyncBatch {
+    serde_json::from_value(json!({ "device_id": "HH-01", "mutations": mutations })).unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn claim_and_complete_offline_in_one_batch(pool: PgPool) {
+    let db = Database::new(pool);
+    let task = queued(&db).await;
+
+    let results = sync::apply(
+        &db,
+        batch(json!([
+            { "mutation_id": "m-1", "task_id": task.task_id, "base_version": task.version, "action": "CLAIM", "operator_id": 7 },
+            { "mutation_id": "m-2", "task_id": task.task_id, "base_version": task.version, "action": "COMPLETE", "operator_id": 7 }
+        ])),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(results.iter().map(|r| r.status).collect::<Vec<_>>(), [MutationStatus::Applied; 2]);
+    assert_eq!(results[1].task.as_ref().unwrap().status, TASK_COMPLETED);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn stale_version_conflicts_unless_client_wins(pool: PgPool) {
+    let db = Database::new(pool);
+    let task = queued(&db).await;
+    db.tasks().assign(task.task_id, AssignTask { operator_id: 7 }).await.unwrap();
+
+    let claim = |id: &str, policy: &str| json!({ "mutation_id": id, "task_id": task.task_id, "base_version": task.version, "policy": policy, "action": "CLAIM", "operator_id": 7 });
+    let results =
+        sync::apply(&db, batch(json!([claim("m-1", "SERVER_WINS"), claim("m-2", "CLIENT_WINS")]))).await.unwrap();
+
+    assert_eq!(results[0].status, MutationStatus::Conflict);
+    assert_eq!(results[0].task.as_ref().unwrap().status, TASK_ASSIGNED);
+    assert_eq!(results[1].status, MutationStatus::Applied);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn resubmitted_batch_replays_outcomes(pool: PgPool) {
+    let db = Database::new(pool);
+    let task = queued(&db).await;
+    let mutations = json!([
+        { "mutation_id": "m-1", "task_id": task.task_id, "base_version": task.version, "action": "CLAIM", "operator_id": 7 },
+        { "mutation_id": "m-2", "task_id": task.task_id + 1000, "base_version": 1, "action": "CLAIM", "operator_id": 7 }
+    ]);
+
+    sync::apply(&db, batch(mutations.clone())).await.unwrap();
+    let replay = sync::apply(&db, batch(mutations)).await.unwrap();
+
+    assert!(replay.iter().all(|r| r.replayed));
+    assert_eq!(replay[0].status, MutationStatus::Applied);
+    assert_eq!(replay[1].status, MutationStatus::Rejected);
+    assert_eq!(replay[0].task.as_ref().unwrap().version, task.version + 1);
+}
@@ -0,0 +1,170 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+use warehouse_core::carrier::{Carrier, LabelRequest, RateRequest};
+use warehouse_core::{transfers, AppError, AppResult, Carriers};
+use warehouse_db::Database;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// Carrier that sells labels for any service but NONE and remembers what was voided
+#[derive(Default)]
+struct FakeCarrier {
+    voided: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl Carrier for FakeCarrier {
+    fn code(&self) -> &str {
+        "FAKE"
+    }
+
+    async fn rates(&self, _request: &RateRequest) -> AppResult<Vec<RateQuote>> {
+        Ok(Vec::new())
+    }
+
+    async fn purchase_label(&self, request: &LabelRequest) -> AppResult<ShipmentLabel> {
+        if request.service_level == "NONE" {
+            return Err(AppError::ExternalService {
+                service: "FAKE".to_string(),
+                message: "unknown service".to_string(),
+            });
+        }
+        Ok(ShipmentLabel {
+            label_id: format!("lbl-{}", request.reference),
+            tracking_number: format!("TRK-{}", request.reference),
+            service_level: request.service_level.clone(),
+            label_url: None,
+            label_format: None,
+            amount: None,
+            currency: None,
+        })
+    }
+
+    async fn void_label(&self, label_id: &str) -> AppResult<()> {
+        self.voided.lock().unwrap().push(label_id.to_string());
+        Ok(())
+    }
+
+    async fn tracking(&self, _tracking_number: &str) -> AppResult<Vec<NewTrackingEvent>> {
+        Ok(Vec::new())
+    }
+}
+
+fn transfer(service_level: Option<&str>) -> CreateTransfer {
+    CreateTransfer {
+        source_warehouse_id: 1,
+        destination_warehouse_id: 2,
+        carrier_code: service_level.map(|_| "FAKE".to_string()),
+        service_level: service_level.map(str::to_string),
+        notes: None,
+        lines: vec![CreateTransferLine { item_id: 2, quantity: Decimal::from(100) }],
+    }
+}
+
+async fn on_hand(pool: &PgPool, warehouse_id: i32) -> Decimal {
+    sqlx::query_scalar(
+        "SELECT COALESCE(SUM(quantity_on_hand), 0) FROM warehouse.stock_inventory WHERE item_id = 2 AND warehouse_id = $1",
+    )
+    .bind(warehouse_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+fn log(detail: &TransferDetail) -> Vec<(&str, &str)> {
+    detail.steps.iter().map(|s| (s.step.as_str(), s.status.as_str())).collect()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn transfer_moves_stock_through_transit(pool: PgPool) {
+    let db = Database::new(pool.clone());
+    let carriers = Carriers::default();
+    let id = db.transfers().create(transfer(None)).await.unwrap().transfer.transfer_id;
+
+    let dispatched = transfers::dispatch(&db, &carriers, id).await.unwrap().unwrap();
+    assert_eq!(dispatched.transfer.status, TRANSFER_IN_TRANSIT);
+    assert_eq!(on_hand(&pool, 1).await, Decimal::from(900));
+    assert_eq!(on_hand(&pool, 2).await, Decimal::ZERO);
+
+    let received = transfers::receive(&db, &carriers, id).await.unwrap().unwrap();
+    assert_eq!(received.transfer.status, TRANSFER_RECEIVED);
+    assert_eq!(
+        log(&received),
+        [
+            (TRANSFER_STEP_ISSUE, SAGA_STEP_COMPLETED),
+            (TRANSFER_STEP_LABEL, SAGA_STEP_COMPLETED),
+            (TRANSFER_STEP_RECEIVE, SAGA_STEP_COMPLETED)
+        ]
+    );
+    assert_eq!(on_hand(&pool, 2).await, Decimal::from(100));
+
+    // Nothing is left in transit, in either warehouse
+    let integrity = db.ledger().integrity().await.unwrap();
+    assert!(integrity.reconciled);
+    let open: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM (
+             SELECT e.warehouse_id FROM warehouse.ledger_entries e
+             JOIN warehouse.ledger_transactions t USING (transaction_id)
+             WHERE t.reference_type = 'TRANSFER' AND e.account = 'IN_TRANSIT'
+             GROUP BY e.warehouse_id
+             HAVING SUM(e.quantity) <> 0
+         ) open",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(open, 0);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn failed_receive_returns_stock_and_voids_label(pool: PgPool) {
+    let db = Database::new(pool.clone());
+    let fake = Arc::new(FakeCarrier::default());
+    let mut carriers = Carriers::default();
+    carriers.register(fake.clone());
+    let id = db.transfers().create(transfer(Some("REG"))).await.unwrap().transfer.transfer_id;
+
+    let dispatched = transfers::dispatch(&db, &carriers, id).await.unwrap().unwrap();
+    let label_id = dispatched.transfer.label_id.clone().unwrap();
+
+    sqlx::query("UPDATE warehouse.warehouses SET is_active = FALSE WHERE warehouse_id = 2")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let err = transfers::receive(&db, &carriers, id).await.unwrap_err();
+    assert!(matches!(err, AppError::BusinessRule(_)));
+
+    let rolled_back = db.transfers().get_by_id(id).await.unwrap().unwrap();
+    assert_eq!(rolled_back.transfer.status, TRANSFER_COMPENSATED);
+    assert!(rolled_back.transfer.failure_reason.as_deref().unwrap().contains("not active"));
+    assert_eq!(rolled_back.transfer.label_id, None);
+    assert_eq!(
+        log(&rolled_back)[2..],
+        [
+            (TRANSFER_STEP_RECEIVE, SAGA_STEP_FAILED),
+            (TRANSFER_STEP_LABEL, SAGA_STEP_COMPENSATED),
+            (TRANSFER_STEP_ISSUE, SAGA_STEP_COMPENSATED)
+        ]
+    );
+    assert_eq!(*fake.voided.lock().unwrap(), [label_id]);
+    assert_eq!(on_hand(&pool, 1).await, Decimal::from(1000));
+    assert!(db.ledger().integrity().await.unwrap().reconciled);
+
+    assert!(transfers::receive(&db, &carriers, id).await.is_err());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn failed_label_purchase_undoes_the_issue(pool: PgPool) {
+    let db = Database::new(pool.clone());
+    let mut carriers = Carriers::default();
+    carriers.register(Arc::new(FakeCarrier::default()));
+    let id = db.transfers().create(transfer(Some("NONE"))).await.unwrap().transfer.transfer_id;
+
+    assert!(transfers::dispatch(&db, &carriers, id).await.is_err());
+
+    let rolled_back = db.transfers().get_by_id(id).await.unwrap().unwrap();
+    assert_eq!(rolled_back.transfer.status, TRANSFER_COMPENSATED);
+    assert_eq!(on_hand(&pool, 1).await, Decimal::from(1000));
+}
@@ -0,0 +1,120 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{http::HeaderMap, http::StatusCode, routing::post, Json, Router};
+use serde_json::Value;
+use sqlx::PgPool;
+use warehouse_core::config::VmiConfig;
+use warehouse_core::vmi;
+use warehouse_db::Database;
+use warehouse_models::*;
+
+/// Fake supplier endpoint; keeps the bodies of authorized pushes
+async fn spawn_supplier(received: Arc<Mutex<Vec<Value>>>) -> String {
+    let app = Router::new().route(
+        "/vmi",
+        post(move |headers: HeaderMap, Json(body): Json<Value>| async move {
+            if headers.get("authorization").and_then(|v| v.to_str().ok()) != Some("Bearer secret") {
+                return StatusCode::UNAUTHORIZED;
+            }
+            received.lock().unwrap().push(body);
+            StatusCode::ACCEPTED
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}/vmi", addr)
+}
+
+fn config(sftp_command: &str) -> VmiConfig {
+    VmiConfig { poll_interval_secs: 0, sftp_identity_file: None, sftp_command: sftp_command.to_string() }
+}
+
+fn feed(code: &str, transport: &str, destination: String, api_key: Option<&str>) -> CreateVmiFeed {
+    CreateVmiFeed {
+        supplier_code: code.to_string(),
+        supplier_name: format!("{} Supplies", code),
+        transport: transport.to_string(),
+        destination,
+        api_key: api_key.map(str::to_string),
+        frequency_minutes: 60,
+        warehouse_id: Some(1),
+        item_ids: vec![2, 3],
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn api_feeds_are_pushed_when_due(pool: PgPool) {
+    let db = Database::new(pool);
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let url = spawn_supplier(received.clone()).await;
+    db.vmi().create(feed("ACME", VMI_TRANSPORT_API, url.clone(), Some("secret"))).await.unwrap();
+    let rejected = db.vmi().create(feed("BETA", VMI_TRANSPORT_API, url, Some("wrong"))).await.unwrap();
+
+    assert_eq!(vmi::run_due(&db, &config("sftp")).await.unwrap(), 1);
+    assert_eq!(vmi::run_due(&db, &config("sftp")).await.unwrap(), 0);
+
+    let bodies = received.lock().unwrap().clone();
+    assert_eq!(bodies.len(), 1);
+    assert_eq!(bodies[0]["supplier_code"], "ACME");
+    assert_eq!(bodies[0]["lines"].as_array().unwrap().len(), 2);
+
+    let runs = db.vmi().runs(rejected.feed.feed_id, KeysetQuery::default()).await.unwrap();
+    assert_eq!(runs.data[0].status, VMI_RUN_FAILED);
+    assert!(runs.data[0].error.as_deref().unwrap().contains("401"));
+
+    let alerts = db
+        .notifications()
+        .list(
+            NotificationFilter { kind: Some(NOTIFY_VMI_FEED_FAILED.to_string()), ..Default::default() },
+            KeysetQuery::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(alerts.data.len(), 1);
+    assert_eq!(alerts.data[0].reference_id, Some(rejected.feed.feed_id));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn sftp_feeds_drop_a_csv_file(pool: PgPool) {
+    let db = Database::new(pool);
+
+    // Stand-in for the sftp client: keeps the batch script and the uploaded file
+    let dir = std::env::temp_dir().join(format!("vmi-sftp-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("sftp");
+    std::fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\ncat > {dir}/batch\nsrc=$(sed -n 's/^put \"\\([^\"]*\\)\".*/\\1/p' {dir}/batch)\ncp \"$src\" {dir}/upload.csv\necho \"$@\" > {dir}/args\n",
+            dir = dir.display()
+        ),
+    )
+    .unwrap();
+    std::process::Command::new("chmod").arg("+x").arg(&script).status().unwrap();
+
+    let detail = db
+        .vmi()
+        .create(feed("ACME", VMI_TRANSPORT_SFTP, "sftp://vmi@supplier.example:2222/inbound".to_string(), None))
+        .await
+        .unwrap();
+
+    let run = vmi::run_feed(&db, &config(script.to_str().unwrap()), &detail.feed).await.unwrap();
+    assert_eq!(run.status, VMI_RUN_SENT, "{:?}", run.error);
+    let file_name = run.file_name.unwrap();
+    assert!(file_name.starts_with("VMI_ACME_") && file_name.ends_with(".csv"));
+
+    let batch = std::fs::read_to_string(dir.join("batch")).unwrap();
+    assert!(batch.contains(&format!("rename \"/inbound/{}.part\" \"/inbound/{}\"", file_name, file_name)));
+    let args = std::fs::read_to_string(dir.join("args")).unwrap();
+    assert!(args.contains("-P 2222") && args.trim_end().ends_with("vmi@supplier.example"));
+
+    let csv = std::fs::read_to_string(dir.join("upload.csv")).unwrap();
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(rows.len(), 3);
+    assert!(rows[0].starts_with("supplier_code,item_code,warehouse_code"));
+    assert!(rows[1].starts_with("ACME,"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
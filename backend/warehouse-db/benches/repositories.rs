@@ -0,0 +1,119 @@
+//! Repository hot-path benchmarks
+//!
+//! Runs against `BENCH_DATABASE_URL` (falling back to `DATABASE_URL`). The first run
+//! fills the database with generator seed 7; later runs reuse that data.
+//!
+//!     cargo bench -p warehouse-db
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use sqlx::PgPool;
+use tokio::runtime::Runtime;
+use warehouse_db::generator::{self, GeneratorConfig};
+use warehouse_db::*;
+use warehouse_models::*;
+
+const SEED: u64 = 7;
+
+fn setup(runtime: &Runtime) -> Database {
+    dotenvy::dotenv().ok();
+    let url = std::env::var("BENCH_DATABASE_URL")
+        .or_else(|_| std::env::var("DATABASE_URL"))
+        .expect("BENCH_DATABASE_URL or DATABASE_URL must be set");
+
+    runtime.block_on(async {
+        let pool = PgPool::connect(&url).await.expect("connect to benchmark database");
+
+        let seeded: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.items WHERE item_code LIKE $1)",
+        )
+        .bind(format!("GEN{}-%", SEED))
+        .fetch_one(&pool)
+        .await
+        .expect("check benchmark seed");
+
+        if !seeded {
+            let config = GeneratorConfig {
+                warehouses: 5,
+                items: 5_000,
+                movements: 200_000,
+                seed: SEED,
+                ..Default::default()
+            };
+            generator::generate(&pool, &config).await.expect("generate benchmark data");
+            sqlx::query("ANALYZE").execute(&pool).await.expect("analyze");
+        }
+
+        Database::new(pool)
+    })
+}
+
+fn page(page: i64, count: CountMode) -> PaginationQuery {
+    PaginationQuery {
+        page: Some(page),
+        limit: Some(50),
+        count: Some(count),
+        ..Default::default()
+    }
+}
+
+fn list_pagination(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let db = setup(&runtime);
+    let mut group = c.benchmark_group("items_list");
+
+    for (name, mode) in [
+        ("exact", CountMode::Exact),
+        ("estimate", CountMode::Estimate),
+        ("skip", CountMode::Skip),
+    ] {
+        for page_number in [1, 50] {
+            group.bench_with_input(
+                BenchmarkId::new(name, format!("page_{}", page_number)),
+                &page_number,
+                |b, &page_number| {
+                    b.to_async(&runtime)
+                        .iter(|| async { db.items().list(page(page_number, mode)).await.unwrap() })
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+fn movement_history(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let db = setup(&runtime);
+
+    // A cursor near the oldest end of the ledger, where offset pagination would be slowest
+    let deep_cursor = runtime.block_on(async {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT movement_id FROM warehouse.stock_movements ORDER BY movement_id ASC OFFSET 100 LIMIT 1",
+        )
+        .fetch_one(&db.pool)
+        .await
+        .unwrap()
+    });
+
+    let mut group = c.benchmark_group("movement_history");
+    for (name, after_id) in [("first_page", None), ("deep_page", Some(deep_cursor))] {
+        group.bench_function(name, |b| {
+            b.to_async(&runtime).iter(|| async {
+                db.movements()
+                    .history(
+                        MovementFilter::default(),
+                        KeysetQuery {
+                            after_id,
+                            limit: Some(50),
+                        },
+                    )
+                    .await
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, list_pagination, movement_history);
+criterion_main!(benches);
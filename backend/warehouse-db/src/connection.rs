@@ -1,67 +1,105 @@
 //! Database connection management
 
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use anyhow::{Context, Result};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
+use sqlx::{Executor, PgPool};
+use std::str::FromStr;
 use std::time::Duration;
-use warehouse_models::*;
+
+/// How to reach Postgres. Anything set here overrides the same option in the URL, so
+/// managed instances can be configured without encoding TLS settings into it.
+#[derive(Debug, Clone)]
+pub struct ConnectionSettings {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout: Duration,
+    /// Close connections idle for longer than this, down to `min_connections`
+    pub idle_timeout: Option<Duration>,
+    /// Recycle connections after this long so failovers and credential rotation are picked up
+    pub max_lifetime: Option<Duration>,
+    /// `disable`, `allow`, `prefer`, `require`, `verify-ca` or `verify-full`
+    pub ssl_mode: Option<String>,
+    /// PEM file with the CA that signed the server certificate, e.g. the RDS bundle
+    pub ssl_root_cert: Option<String>,
+    /// Shown in `pg_stat_activity` and server logs
+    pub application_name: Option<String>,
+    /// Run-time parameters set on every new connection, e.g. `statement_timeout`
+    pub session_settings: Vec<(String, String)>,
+}
 
 pub struct DatabaseManager;
 
 impl DatabaseManager {
-    /// Create a new database connection pool with optimized settings
-    pub async fn connect(database_url: &str) -> Result<PgPool> {
-        let pool = PgPoolOptions::new()
-            .max_connections(20)
-            .min_connections(5)
-            .acquire_timeout(Duration::from_secs(30))
-            .idle_timeout(Duration::from_secs(600))
-            .max_lifetime(Duration::from_secs(3600))
-            .test_before_acquire(true)
-            .connect(database_url)
-            .await
-            .map_err(|e| {
-                tracing::error!("Failed to connect to database: {}", e);
-                WarehouseError::Database(e)
-            })?;
+    /// Create a connection pool sized and timed by `settings`
+    pub async fn connect(settings: &ConnectionSettings) -> Result<PgPool> {
+        let options = Self::connect_options(settings)?;
+
+        let pool = Self::pool_options(settings).connect_with(options).await.map_err(|e| {
+            tracing::error!("Failed to connect to database: {}", e);
+            e
+        })?;
 
         tracing::info!("Database connection pool created successfully");
         Ok(pool)
     }
 
-    /// Run database migrations
-    pub async fn run_migrations(pool: &PgPool) -> Result<()> {
-        sqlx::migrate!("../migrations")
-            .run(pool)
-            .await
-            .map_err(|e| {
-                tracing::error!("Failed to run migrations: {}", e);
-                WarehouseError::Database(e)
-            })?;
-
-        tracing::info!("Database migrations completed successfully");
-        Ok(())
+    /// Pool sizing and timing from `settings`, with connections tagged with the audit actor
+    /// of the request taking them, whether they were idle or opened for it
+    pub fn pool_options(settings: &ConnectionSettings) -> PgPoolOptions {
+        let session_settings = settings.session_settings.clone();
+        PgPoolOptions::new()
+            .max_connections(settings.max_connections)
+            .min_connections(settings.min_connections)
+            .acquire_timeout(settings.acquire_timeout)
+            .idle_timeout(settings.idle_timeout)
+            .max_lifetime(settings.max_lifetime)
+            // Tagging a connection with the audit actor is a round trip that fails on a dead
+            // connection, so it stands in for the ping that would otherwise test it
+            .test_before_acquire(false)
+            .before_acquire(|conn, _| {
+                Box::pin(async move { crate::repositories::audit::tag_connection(conn).await.map(|()| true) })
+            })
+            .after_connect(move |conn, _| {
+                let session_settings = session_settings.clone();
+                Box::pin(async move {
+                    // set_config rather than SET so values are bound, not spliced into SQL
+                    for (name, value) in &session_settings {
+                        conn.execute(sqlx::query("SELECT set_config($1, $2, false)").bind(name).bind(value)).await?;
+                    }
+                    // before_acquire only sees idle connections; one opened for the
+                    // request is connected on its task and tagged here
+                    crate::repositories::audit::tag_connection(conn).await
+                })
+            })
     }
 
-    /// Verify database schema and connectivity
-    pub async fn verify_database(pool: &PgPool) -> Result<()> {
-        // Check if main tables exist
-        let tables = vec!["warehouses", "items", "projects"];
-        
-        for table in tables {
-            let exists = sqlx::query_scalar!(
-                "SELECT EXISTS (SELECT 1 FROM information_schema.tables WHERE table_name = $1)",
-                table
-            )
-            .fetch_one(pool)
-            .await?;
+    /// Connection options from the URL with the explicit settings applied over it
+    pub fn connect_options(settings: &ConnectionSettings) -> Result<PgConnectOptions> {
+        let mut options = PgConnectOptions::from_str(&settings.url).context("invalid database URL")?;
 
-            if !exists.unwrap_or(false) {
-                return Err(WarehouseError::Internal(
-                    format!("Required table '{}' does not exist", table)
-                ));
-            }
+        if let Some(mode) = &settings.ssl_mode {
+            let mode = PgSslMode::from_str(mode).with_context(|| format!("invalid sslmode {}", mode))?;
+            options = options.ssl_mode(mode);
         }
+        if let Some(cert) = &settings.ssl_root_cert {
+            options = options.ssl_root_cert(cert.as_str());
+        }
+        if let Some(name) = &settings.application_name {
+            options = options.application_name(name);
+        }
+
+        Ok(options)
+    }
 
-        tracing::info!("Database schema verification completed");
+    /// Run database migrations
+    pub async fn run_migrations(pool: &PgPool) -> Result<()> {
+        sqlx::migrate!("../migrations").run(pool).await.map_err(|e| {
+            tracing::error!("Failed to run migrations: {}", e);
+            e
+        })?;
+
+        tracing::info!("Database migrations completed successfully");
         Ok(())
     }
 }
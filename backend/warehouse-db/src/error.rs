@@ -0,0 +1,58 @@
+//! Errors the database layer reports to callers as something other than a failure
+
+use warehouse_models::rust_decimal::Decimal;
+
+/// A well-formed request that breaks a business rule, such as receiving more than an RMA
+/// authorized or issuing stock that is not on hand. Returned inside `anyhow::Error`;
+/// callers recover it with `downcast_ref`.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct RuleViolation(pub String);
+
+pub fn violation(message: impl Into<String>) -> anyhow::Error {
+    RuleViolation(message.into()).into()
+}
+
+/// An issue that would take on-hand stock below zero where the negative stock policy
+/// forbids it. Returned inside `anyhow::Error` like `RuleViolation`.
+#[derive(Debug, thiserror::Error)]
+#[error("item {item_id} in warehouse {warehouse_id} would go to {on_hand} on hand; negative stock is not allowed")]
+pub struct NegativeStock {
+    pub item_id: i32,
+    pub warehouse_id: i32,
+    pub on_hand: Decimal,
+}
+
+/// SQLSTATE the database raises for a stock movement written into a closed GL period
+pub const CLOSED_PERIOD: &str = "WG001";
+
+/// Message of a write the database refused because its movement is in a closed GL period
+pub fn closed_period(error: &anyhow::Error) -> Option<String> {
+    match error.downcast_ref::<sqlx::Error>() {
+        Some(sqlx::Error::Database(e)) if e.code().as_deref() == Some(CLOSED_PERIOD) => Some(e.message().to_string()),
+        _ => None,
+    }
+}
+
+/// SQLSTATE the primary raises for a stock change of a warehouse held on a database shard
+pub const SHARDED_STOCK: &str = "WG002";
+
+/// Message of a write the primary refused because the warehouse's stock is on a shard
+pub fn sharded_stock(error: &anyhow::Error) -> Option<String> {
+    match error.downcast_ref::<sqlx::Error>() {
+        Some(sqlx::Error::Database(e)) if e.code().as_deref() == Some(SHARDED_STOCK) => Some(e.message().to_string()),
+        _ => None,
+    }
+}
+
+/// Message of an error that rejects the request on business grounds, if it is one
+pub fn rejection(error: &anyhow::Error) -> Option<String> {
+    match error.downcast_ref::<RuleViolation>() {
+        Some(RuleViolation(message)) => Some(message.clone()),
+        None => error
+            .downcast_ref::<NegativeStock>()
+            .map(NegativeStock::to_string)
+            .or_else(|| closed_period(error))
+            .or_else(|| sharded_stock(error)),
+    }
+}
@@ -0,0 +1,268 @@
+//! Helpers for expand-contract schema changes on large tables
+//!
+//! A column is replaced in steps that each keep the API running: a migration adds the new
+//! column (expand), `install_dual_write` keeps it in step with the old ones on every write,
+//! `Backfill` fills existing rows in small keyed batches, `verify` confirms no row differs,
+//! and once readers use the new column a later migration drops the old one and
+//! `remove_dual_write` the trigger (contract).
+//!
+//! Table, key and column names are checked to be plain identifiers. The expression is SQL
+//! written by the developer over the table's columns and is used as given.
+
+use std::time::Duration;
+
+use anyhow::{ensure, Result};
+use sqlx::{Executor, PgPool};
+use warehouse_models::*;
+
+/// Lock wait allowed to a backfill batch before it gives way to API traffic
+const BATCH_LOCK_TIMEOUT: &str = "2s";
+
+/// A column derived from other columns of the same row
+#[derive(Debug, Clone)]
+pub struct ColumnChange {
+    /// Schema-qualified, e.g. `warehouse.items`
+    table: String,
+    /// Integer primary key the backfill walks
+    key: String,
+    column: String,
+    expression: String,
+}
+
+impl ColumnChange {
+    pub fn new(table: &str, key: &str, column: &str, expression: &str) -> Result<Self> {
+        let (schema, name) = table.split_once('.').unwrap_or(("warehouse", table));
+        for identifier in [schema, name, key, column] {
+            ensure!(is_identifier(identifier), "{} is not a plain identifier", identifier);
+        }
+        ensure!(!expression.trim().is_empty(), "expression for {} is empty", column);
+
+        Ok(Self {
+            table: format!("{}.{}", schema, name),
+            key: key.to_string(),
+            column: column.to_string(),
+            expression: expression.to_string(),
+        })
+    }
+
+    /// Name shared by the trigger and its function
+    fn trigger(&self) -> String {
+        format!("{}_{}_dual_write", self.table.replace('.', "_"), self.column)
+    }
+
+    fn trigger_function(&self) -> String {
+        let schema = self.table.split_once('.').map(|(schema, _)| schema).unwrap_or("warehouse");
+        format!("{}.{}", schema, self.trigger())
+    }
+}
+
+/// Keep `change.column` computed from the row on every insert and update, so rows written
+/// while the backfill runs never need it
+pub async fn install_dual_write(pool: &PgPool, change: &ColumnChange) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    tx.execute(
+        format!(
+            "CREATE OR REPLACE FUNCTION {function}() RETURNS TRIGGER
+             LANGUAGE plpgsql AS $$
+             BEGIN
+                 SELECT {expression} INTO NEW.{column} FROM (SELECT NEW.*) AS src;
+                 RETURN NEW;
+             END
+             $$",
+            function = change.trigger_function(),
+            expression = change.expression,
+            column = change.column,
+        )
+        .as_str(),
+    )
+    .await?;
+    tx.execute(format!("DROP TRIGGER IF EXISTS {} ON {}", change.trigger(), change.table).as_str()).await?;
+    tx.execute(
+        format!(
+            "CREATE TRIGGER {} BEFORE INSERT OR UPDATE ON {} FOR EACH ROW EXECUTE FUNCTION {}()",
+            change.trigger(),
+            change.table,
+            change.trigger_function()
+        )
+        .as_str(),
+    )
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!("Dual write to {}.{} installed", change.table, change.column);
+    Ok(())
+}
+
+/// Stop maintaining the column, once the old columns are gone or no longer written
+pub async fn remove_dual_write(pool: &PgPool, change: &ColumnChange) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    tx.execute(format!("DROP TRIGGER IF EXISTS {} ON {}", change.trigger(), change.table).as_str()).await?;
+    tx.execute(format!("DROP FUNCTION IF EXISTS {}()", change.trigger_function()).as_str()).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Rows whose column differs from the expression. Scans the whole table; run it off-peak.
+pub async fn verify(pool: &PgPool, change: &ColumnChange) -> Result<BackfillVerification> {
+    let (rows, mismatched): (i64, i64) = sqlx::query_as(&format!(
+        "SELECT COUNT(*), COUNT(*) FILTER (WHERE {column} IS DISTINCT FROM ({expression})) FROM {table}",
+        column = change.column,
+        expression = change.expression,
+        table = change.table,
+    ))
+    .fetch_one(pool)
+    .await?;
+
+    Ok(BackfillVerification { rows, mismatched })
+}
+
+/// Fills `change.column` for existing rows in key order, one short transaction per batch,
+/// recording progress in `warehouse.backfill_jobs` so an interrupted run resumes where it
+/// stopped
+pub struct Backfill {
+    pool: PgPool,
+    change: ColumnChange,
+    job_name: String,
+    batch_size: i64,
+    pause: Duration,
+}
+
+impl Backfill {
+    pub fn new(pool: PgPool, job_name: &str, change: ColumnChange) -> Self {
+        Self { pool, change, job_name: job_name.to_string(), batch_size: 1000, pause: Duration::from_millis(50) }
+    }
+
+    /// Rows updated per transaction
+    pub fn with_batch_size(mut self, batch_size: i64) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Wait between batches, leaving room for API writes
+    pub fn with_pause(mut self, pause: Duration) -> Self {
+        self.pause = pause;
+        self
+    }
+
+    /// Run batches until the table is done. A completed job is returned as is.
+    pub async fn run(&self) -> Result<BackfillJob> {
+        loop {
+            let job = self.step().await?;
+            if job.status == BACKFILL_COMPLETED {
+                tracing::info!("Backfill {} completed: {} rows in {} batches", job.job_name, job.rows_updated, job.batches);
+                return Ok(job);
+            }
+            tracing::debug!("Backfill {} at key {:?}, {:.1}% done", job.job_name, job.last_key, job.percent_done());
+            tokio::time::sleep(self.pause).await;
+        }
+    }
+
+    /// Backfill the next batch after the recorded position
+    pub async fn step(&self) -> Result<BackfillJob> {
+        let job = self.start().await?;
+        if job.status == BACKFILL_COMPLETED {
+            return Ok(job);
+        }
+
+        let mut tx = self.pool.begin().await?;
+        tx.execute(format!("SET LOCAL lock_timeout = '{}'", BATCH_LOCK_TIMEOUT).as_str()).await?;
+        let (updated, last_key): (i64, Option<i64>) = sqlx::query_as(&format!(
+            "WITH batch AS (
+                 SELECT {key} AS batch_key FROM {table}
+                 WHERE $1::BIGINT IS NULL OR {key} > $1
+                 ORDER BY {key}
+                 LIMIT $2
+             ),
+             updated AS (
+                 UPDATE {table} SET {column} = ({expression})
+                 FROM batch
+                 WHERE {table}.{key} = batch.batch_key
+                 RETURNING batch.batch_key
+             )
+             SELECT COUNT(*), MAX(batch_key)::BIGINT FROM updated",
+            key = self.change.key,
+            table = self.change.table,
+            column = self.change.column,
+            expression = self.change.expression,
+        ))
+        .bind(job.last_key)
+        .bind(self.batch_size)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let job = sqlx::query_as!(
+            BackfillJob,
+            "UPDATE warehouse.backfill_jobs
+             SET last_key = COALESCE($2, last_key),
+                 rows_updated = rows_updated + $3,
+                 batches = batches + 1,
+                 status = CASE WHEN $3 < $4 THEN 'COMPLETED' ELSE status END,
+                 completed_at = CASE WHEN $3 < $4 THEN NOW() END,
+                 updated_at = NOW()
+             WHERE job_name = $1
+             RETURNING *",
+            self.job_name,
+            last_key,
+            updated,
+            self.batch_size
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(job)
+    }
+
+    /// The job's record, created on first use
+    async fn start(&self) -> Result<BackfillJob> {
+        let (schema, table) = self.change.table.split_once('.').unwrap_or(("warehouse", &self.change.table));
+        let estimated_rows: i64 = sqlx::query_scalar(
+            "SELECT GREATEST(c.reltuples, 0)::BIGINT FROM pg_class c
+             JOIN pg_namespace n ON n.oid = c.relnamespace
+             WHERE n.nspname = $1 AND c.relname = $2",
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_optional(&self.pool)
+        .await?
+        .unwrap_or(0);
+
+        sqlx::query!(
+            "INSERT INTO warehouse.backfill_jobs (job_name, table_name, column_name, estimated_rows)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (job_name) DO NOTHING",
+            self.job_name,
+            self.change.table,
+            self.change.column,
+            estimated_rows
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let job = sqlx::query_as!(BackfillJob, "SELECT * FROM warehouse.backfill_jobs WHERE job_name = $1", self.job_name)
+            .fetch_one(&self.pool)
+            .await?;
+        ensure!(
+            job.table_name == self.change.table && job.column_name == self.change.column,
+            "backfill {} was started for {}.{}",
+            job.job_name,
+            job.table_name,
+            job.column_name
+        );
+        Ok(job)
+    }
+}
+
+pub async fn backfill_jobs(pool: &PgPool) -> Result<Vec<BackfillJob>> {
+    let jobs = sqlx::query_as!(BackfillJob, "SELECT * FROM warehouse.backfill_jobs ORDER BY started_at DESC")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(jobs)
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.len() <= 63
+        && name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
@@ -0,0 +1,284 @@
+//! Synthetic data generator for load and scale testing
+//!
+//! Popularity follows a Zipf-like curve (a few items carry most movements) and
+//! quantities are log-normal, which is close to what real warehouses see.
+//! Stock rows are derived from the generated movements so on-hand always equals
+//! the movement sum.
+
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use rand_distr::LogNormal;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use warehouse_models::rust_decimal::Decimal;
+
+const CITIES: &[(&str, &str)] = &[
+    ("Jakarta", "DKI Jakarta"),
+    ("Surabaya", "East Java"),
+    ("Bandung", "West Java"),
+    ("Medan", "North Sumatra"),
+    ("Semarang", "Central Java"),
+    ("Makassar", "South Sulawesi"),
+    ("Balikpapan", "East Kalimantan"),
+    ("Denpasar", "Bali"),
+];
+
+const CATEGORIES: &[(&str, &str, &str)] = &[
+    ("Construction Materials", "STOCK", "CONSUMABLE"),
+    ("Safety Equipment", "STOCK", "CONSUMABLE"),
+    ("Electrical", "STOCK", "CONSUMABLE"),
+    ("Plumbing", "STOCK", "CONSUMABLE"),
+    ("Hand Tools", "ASSET", "TOOL"),
+    ("Power Tools", "ASSET", "TOOL"),
+    ("Construction Equipment", "ASSET", "TOOL"),
+    ("Electronics", "ASSET", "ASSET"),
+];
+
+#[derive(Debug, Clone)]
+pub struct GeneratorConfig {
+    pub warehouses: usize,
+    pub items: usize,
+    pub movements: usize,
+    /// Movements are spread over this many days back from now
+    pub days: i64,
+    pub seed: u64,
+    pub batch_size: usize,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            warehouses: 5,
+            items: 1_000,
+            movements: 50_000,
+            days: 365,
+            seed: 42,
+            batch_size: 5_000,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct GeneratorReport {
+    pub warehouses: usize,
+    pub items: usize,
+    pub stock_rows: usize,
+    pub movements: usize,
+}
+
+struct PendingMovement {
+    item_id: i32,
+    warehouse_id: i32,
+    quantity: Decimal,
+    unit_cost: Decimal,
+    days_ago: f64,
+}
+
+pub async fn generate(pool: &PgPool, config: &GeneratorConfig) -> Result<GeneratorReport> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let prefix = format!("GEN{}", config.seed);
+    let mut report = GeneratorReport::default();
+
+    let warehouse_ids = insert_warehouses(pool, &mut rng, &prefix, config.warehouses).await?;
+    report.warehouses = warehouse_ids.len();
+
+    let items = insert_items(pool, &mut rng, &prefix, config.items).await?;
+    report.items = items.len();
+
+    if warehouse_ids.is_empty() || items.is_empty() {
+        return Ok(report);
+    }
+
+    // Zipf-like popularity: the item at rank r gets weight 1 / r^1.1
+    let popularity = WeightedIndex::new((1..=items.len()).map(|rank| 1.0 / (rank as f64).powf(1.1)))?;
+    let quantity: LogNormal<f64> = LogNormal::new(2.0, 1.0)?;
+
+    // Each item is stocked in one to three warehouses
+    let mut homes: HashMap<i32, Vec<i32>> = HashMap::new();
+    for (item_id, _) in &items {
+        let count = rng.gen_range(1..=3.min(warehouse_ids.len()));
+        homes.insert(*item_id, warehouse_ids.choose_multiple(&mut rng, count).copied().collect());
+    }
+
+    // Oldest first so movement_id order matches movement_date order
+    let mut offsets: Vec<f64> = (0..config.movements)
+        .map(|_| rng.gen_range(0.0..config.days as f64))
+        .collect();
+    offsets.sort_by(|a, b| b.total_cmp(a));
+
+    let mut balances: HashMap<(i32, i32), (Decimal, Decimal)> = HashMap::new();
+    let mut pending = Vec::with_capacity(config.batch_size);
+
+    for days_ago in offsets {
+        let (item_id, unit_cost) = items[popularity.sample(&mut rng)];
+        let warehouse_id = *homes[&item_id].choose(&mut rng).unwrap();
+        let entry = balances.entry((item_id, warehouse_id)).or_insert((Decimal::ZERO, unit_cost));
+
+        let amount = Decimal::from(quantity.sample(&mut rng).ceil().max(1.0) as i64);
+        // Issue only what is on hand; otherwise replenish
+        let signed = if entry.0 >= amount && rng.gen_bool(0.6) { -amount } else { amount * Decimal::from(3) };
+        entry.0 += signed;
+
+        pending.push(PendingMovement {
+            item_id,
+            warehouse_id,
+            quantity: signed,
+            unit_cost,
+            days_ago,
+        });
+
+        if pending.len() >= config.batch_size {
+            report.movements += insert_movements(pool, &pending).await?;
+            pending.clear();
+        }
+    }
+    report.movements += insert_movements(pool, &pending).await?;
+
+    report.stock_rows = upsert_stock(pool, &balances).await?;
+
+    Ok(report)
+}
+
+async fn insert_warehouses(pool: &PgPool, rng: &mut StdRng, prefix: &str, count: usize) -> Result<Vec<i32>> {
+    let mut codes = Vec::with_capacity(count);
+    let mut names = Vec::with_capacity(count);
+    let mut cities = Vec::with_capacity(count);
+    let mut states = Vec::with_capacity(count);
+
+    for n in 1..=count {
+        let (city, state) = CITIES.choose(rng).unwrap();
+        codes.push(format!("{}-WH{:04}", prefix, n));
+        names.push(format!("{} Warehouse {}", city, n));
+        cities.push(city.to_string());
+        states.push(state.to_string());
+    }
+
+    let ids = sqlx::query_scalar!(
+        "INSERT INTO warehouse.warehouses (warehouse_code, warehouse_name, city, state)
+         SELECT * FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::VARCHAR[], $4::VARCHAR[])
+         RETURNING warehouse_id",
+        &codes,
+        &names,
+        &cities,
+        &states
+    )
+    .fetch_all(pool)
+    .await
+    .context("inserting generated warehouses (was this seed already generated?)")?;
+
+    Ok(ids)
+}
+
+/// Returns (item_id, unit cost) pairs in popularity rank order
+async fn insert_items(pool: &PgPool, rng: &mut StdRng, prefix: &str, count: usize) -> Result<Vec<(i32, Decimal)>> {
+    let cost: LogNormal<f64> = LogNormal::new(11.0, 1.5)?;
+    let mut costs = Vec::with_capacity(count);
+    let mut ids = Vec::with_capacity(count);
+
+    for chunk_start in (0..count).step_by(5_000) {
+        let chunk_end = (chunk_start + 5_000).min(count);
+        let mut codes = Vec::new();
+        let mut names = Vec::new();
+        let mut types = Vec::new();
+        let mut usage_types = Vec::new();
+        let mut categories = Vec::new();
+        let mut unit_costs = Vec::new();
+
+        for n in chunk_start + 1..=chunk_end {
+            let (category, item_type, usage_type) = CATEGORIES.choose(rng).unwrap();
+            let unit_cost = Decimal::from(cost.sample(rng).round() as i64).max(Decimal::ONE);
+            codes.push(format!("{}-ITM{:06}", prefix, n));
+            names.push(format!("{} {}", category, n));
+            types.push(item_type.to_string());
+            usage_types.push(usage_type.to_string());
+            categories.push(category.to_string());
+            unit_costs.push(unit_cost);
+        }
+
+        let chunk_ids = sqlx::query_scalar!(
+            "INSERT INTO warehouse.items (item_code, item_name, item_type, item_usage_type, category, standard_cost, status)
+             SELECT code, name, item_type, usage_type, category, cost, 'ACTIVE'
+             FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::VARCHAR[], $4::VARCHAR[], $5::VARCHAR[], $6::DECIMAL[])
+                  AS t(code, name, item_type, usage_type, category, cost)
+             RETURNING item_id",
+            &codes,
+            &names,
+            &types,
+            &usage_types,
+            &categories,
+            &unit_costs
+        )
+        .fetch_all(pool)
+        .await
+        .context("inserting generated items (was this seed already generated?)")?;
+
+        ids.extend(chunk_ids);
+        costs.extend(unit_costs);
+    }
+
+    Ok(ids.into_iter().zip(costs).collect())
+}
+
+async fn insert_movements(pool: &PgPool, batch: &[PendingMovement]) -> Result<usize> {
+    if batch.is_empty() {
+        return Ok(0);
+    }
+
+    let now = Utc::now();
+    let item_ids: Vec<i32> = batch.iter().map(|m| m.item_id).collect();
+    let warehouse_ids: Vec<i32> = batch.iter().map(|m| m.warehouse_id).collect();
+    let types: Vec<String> = batch
+        .iter()
+        .map(|m| if m.quantity.is_sign_negative() { "ISSUE" } else { "RECEIPT" }.to_string())
+        .collect();
+    let quantities: Vec<Decimal> = batch.iter().map(|m| m.quantity).collect();
+    let costs: Vec<Decimal> = batch.iter().map(|m| m.unit_cost).collect();
+    let dates: Vec<_> = batch
+        .iter()
+        .map(|m| now - Duration::seconds((m.days_ago * 86_400.0) as i64))
+        .collect();
+
+    let result = sqlx::query!(
+        "INSERT INTO warehouse.stock_movements
+             (item_id, warehouse_id, movement_type, quantity, unit_cost, reference_type, movement_date, created_at)
+         SELECT item_id, warehouse_id, movement_type, quantity, unit_cost, 'GENERATED', movement_date, movement_date
+         FROM UNNEST($1::INT[], $2::INT[], $3::VARCHAR[], $4::DECIMAL[], $5::DECIMAL[], $6::TIMESTAMPTZ[])
+              AS t(item_id, warehouse_id, movement_type, quantity, unit_cost, movement_date)",
+        &item_ids,
+        &warehouse_ids,
+        &types,
+        &quantities,
+        &costs,
+        &dates
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() as usize)
+}
+
+async fn upsert_stock(pool: &PgPool, balances: &HashMap<(i32, i32), (Decimal, Decimal)>) -> Result<usize> {
+    let item_ids: Vec<i32> = balances.keys().map(|(item_id, _)| *item_id).collect();
+    let warehouse_ids: Vec<i32> = balances.keys().map(|(_, warehouse_id)| *warehouse_id).collect();
+    let quantities: Vec<Decimal> = balances.values().map(|(quantity, _)| *quantity).collect();
+    let costs: Vec<Decimal> = balances.values().map(|(_, cost)| *cost).collect();
+
+    let result = sqlx::query!(
+        "INSERT INTO warehouse.stock_inventory
+             (item_id, warehouse_id, quantity_on_hand, unit_cost, average_cost, reorder_point, last_movement_date)
+         SELECT item_id, warehouse_id, quantity, cost, cost, GREATEST(quantity / 5, 1), CURRENT_DATE
+         FROM UNNEST($1::INT[], $2::INT[], $3::DECIMAL[], $4::DECIMAL[]) AS t(item_id, warehouse_id, quantity, cost)
+         ON CONFLICT (item_id, warehouse_id, consignor_id) DO UPDATE
+         SET quantity_on_hand = EXCLUDED.quantity_on_hand, updated_at = NOW()",
+        &item_ids,
+        &warehouse_ids,
+        &quantities,
+        &costs
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() as usize)
+}
@@ -2,32 +2,492 @@
 
 use anyhow::Result;
 use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
 
+pub mod connection;
+pub mod error;
+pub mod expand_contract;
+pub mod generator;
+pub mod memory;
 pub mod repositories;
+pub mod store;
 pub mod utils;
 
+pub use connection::{ConnectionSettings, DatabaseManager};
+pub use error::{closed_period, rejection, sharded_stock, violation, NegativeStock, RuleViolation};
+pub use memory::{MemoryStore, MOCK_PASSWORD};
 pub use repositories::*;
+pub use store::*;
 pub use utils::*;
 
 /// Main database connection wrapper
 #[derive(Clone)]
 pub struct Database {
     pub pool: PgPool,
+    /// Set in mock mode; repositories with an in-memory implementation use it instead of `pool`
+    memory: Option<Arc<MemoryStore>>,
+    /// Answer stock queries by folding stock events instead of from the balances table
+    event_sourced_stock: bool,
+    /// Pools for warehouses that live on another cluster; everything else uses `pool`
+    shards: Arc<HashMap<i32, PgPool>>,
+    /// Each shard's pool once, for work that visits every shard
+    shard_pools: Arc<Vec<PgPool>>,
 }
 
 impl Database {
     /// Create new database instance
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self { pool, memory: None, event_sourced_stock: false, shards: Arc::default(), shard_pools: Arc::default() }
+    }
+
+    /// Database backed by an in-memory store. The pool is lazy and never connects,
+    /// so repositories without an in-memory implementation fail with a database error; the
+    /// API answers their routes with 503 instead.
+    pub fn in_memory(store: MemoryStore) -> Result<Self> {
+        let pool = PgPool::connect_lazy("postgres://mock@localhost/mock")?;
+        Ok(Self {
+            pool,
+            memory: Some(Arc::new(store)),
+            event_sourced_stock: false,
+            shards: Arc::default(),
+            shard_pools: Arc::default(),
+        })
+    }
+
+    /// Serve stock state from the event-sourced store
+    pub fn with_event_sourced_stock(mut self, enabled: bool) -> Self {
+        self.event_sourced_stock = enabled;
+        self
+    }
+
+    /// Route `warehouse_ids` to the pool of the cluster holding their stock
+    pub fn with_shard(mut self, warehouse_ids: &[i32], pool: PgPool) -> Self {
+        let shards = Arc::make_mut(&mut self.shards);
+        shards.extend(warehouse_ids.iter().map(|id| (*id, pool.clone())));
+        Arc::make_mut(&mut self.shard_pools).push(pool);
+        self
+    }
+
+    /// The database holding `warehouse_id`'s stock. Repositories taken from it run
+    /// warehouse-scoped queries against that warehouse's shard. Only the stock, reservation
+    /// and warehouse setup handlers route this way; documents such as receipts, orders and
+    /// transfers stay on the primary, which refuses their stock changes for a sharded
+    /// warehouse. Shards get their warehouses and items from the primary, see `shards()`.
+    pub fn for_warehouse(&self, warehouse_id: i32) -> Self {
+        match self.shards.get(&warehouse_id) {
+            Some(pool) => Self { pool: pool.clone(), ..self.clone() },
+            None => self.clone(),
+        }
+    }
+
+    /// Warehouses served by a shard rather than the primary pool
+    pub fn sharded_warehouses(&self) -> Vec<i32> {
+        let mut ids: Vec<i32> = self.shards.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    pub fn is_mock(&self) -> bool {
+        self.memory.is_some()
     }
 
     /// Get warehouse repository
-    pub fn warehouses(&self) -> WarehouseRepository {
-        WarehouseRepository::new(self.pool.clone())
+    pub fn warehouses(&self) -> Arc<dyn WarehouseStore> {
+        match &self.memory {
+            Some(store) => store.clone(),
+            None => Arc::new(WarehouseRepository::new(self.pool.clone())),
+        }
+    }
+
+    /// Get item repository
+    pub fn items(&self) -> Arc<dyn ItemStore> {
+        match &self.memory {
+            Some(store) => store.clone(),
+            None => Arc::new(ItemRepository::new(self.pool.clone())),
+        }
+    }
+
+    /// Get stock movement repository
+    pub fn movements(&self) -> Arc<dyn MovementStore> {
+        match &self.memory {
+            Some(store) => store.clone(),
+            None => Arc::new(MovementRepository::new(self.pool.clone())),
+        }
+    }
+
+    /// Get customer return (RMA) repository
+    pub fn returns(&self) -> ReturnRepository {
+        ReturnRepository::new(self.pool.clone())
+    }
+
+    /// Get zone sensor and cold-chain repository
+    pub fn environment(&self) -> EnvironmentRepository {
+        EnvironmentRepository::new(self.pool.clone())
+    }
+
+    /// Get dangerous goods classification repository
+    pub fn hazmat(&self) -> HazmatRepository {
+        HazmatRepository::new(self.pool.clone())
+    }
+
+    /// Get consignment stock and self-billing repository
+    pub fn consignment(&self) -> ConsignmentRepository {
+        ConsignmentRepository::new(self.pool.clone())
+    }
+
+    /// Get vendor-managed inventory feed repository
+    pub fn vmi(&self) -> VmiRepository {
+        VmiRepository::new(self.pool.clone())
+    }
+
+    /// Get 3PL client billing repository
+    pub fn billing(&self) -> BillingRepository {
+        BillingRepository::new(self.pool.clone())
+    }
+
+    /// Get 3PL client access repository
+    pub fn clients(&self) -> ClientRepository {
+        ClientRepository::new(self.pool.clone())
+    }
+
+    /// Get box size and cartonization repository
+    pub fn cartonization(&self) -> CartonizationRepository {
+        CartonizationRepository::new(self.pool.clone())
+    }
+
+    /// Get customs data and declaration repository
+    pub fn customs(&self) -> CustomsRepository {
+        CustomsRepository::new(self.pool.clone())
+    }
+
+    /// Get inbound dock board and arrival repository
+    pub fn dock(&self) -> DockRepository {
+        DockRepository::new(self.pool.clone())
+    }
+
+    /// Get shipment document and branding repository
+    pub fn documents(&self) -> DocumentRepository {
+        DocumentRepository::new(self.pool.clone())
+    }
+
+    /// Get label and document template repository
+    pub fn templates(&self) -> TemplateRepository {
+        TemplateRepository::new(self.pool.clone())
+    }
+
+    /// Get printer repository
+    pub fn printers(&self) -> PrinterRepository {
+        PrinterRepository::new(self.pool.clone())
+    }
+
+    /// Get lot and serial recall repository
+    pub fn recalls(&self) -> RecallRepository {
+        RecallRepository::new(self.pool.clone())
+    }
+
+    /// Get catch-weight item repository
+    pub fn catch_weight(&self) -> CatchWeightRepository {
+        CatchWeightRepository::new(self.pool.clone())
+    }
+
+    /// Get packaging material and reorder alert repository
+    pub fn packaging(&self) -> PackagingRepository {
+        PackagingRepository::new(self.pool.clone())
+    }
+
+    /// Get item supplier, purchase requisition and purchase order repository
+    pub fn purchasing(&self) -> PurchasingRepository {
+        PurchasingRepository::new(self.pool.clone())
+    }
+
+    /// Get license plate (LPN) repository
+    pub fn lpns(&self) -> LpnRepository {
+        LpnRepository::new(self.pool.clone())
+    }
+
+    /// Get inventory ledger repository
+    pub fn ledger(&self) -> LedgerRepository {
+        LedgerRepository::new(self.pool.clone())
+    }
+
+    /// Get negative stock policy repository
+    pub fn negative_stock(&self) -> NegativeStockRepository {
+        NegativeStockRepository::new(self.pool.clone())
+    }
+
+    /// Get posting reference repository
+    pub fn postings(&self) -> PostingRepository {
+        PostingRepository::new(self.pool.clone())
+    }
+
+    /// Get saga log repository
+    pub fn sagas(&self) -> SagaRepository {
+        SagaRepository::new(self.pool.clone())
+    }
+
+    /// Get stock transfer repository
+    pub fn transfers(&self) -> TransferRepository {
+        TransferRepository::new(self.pool.clone())
+    }
+
+    /// Get read model repository
+    pub fn read_models(&self) -> ReadModelRepository {
+        ReadModelRepository::new(self.pool.clone())
+    }
+
+    /// Get event stream consumer repository
+    pub fn event_consumers(&self) -> EventConsumerRepository {
+        EventConsumerRepository::new(self.pool.clone())
+    }
+
+    /// Get ERP stock reconciliation repository
+    pub fn reconciliation(&self) -> ReconciliationRepository {
+        ReconciliationRepository::new(self.pool.clone())
+    }
+
+    /// Get ERP connector repository
+    pub fn erp(&self) -> ErpRepository {
+        ErpRepository::new(self.pool.clone())
+    }
+
+    /// Get general ledger export repository
+    pub fn gl(&self) -> GlRepository {
+        GlRepository::new(self.pool.clone())
+    }
+
+    /// Get e-commerce channel repository
+    pub fn ecommerce(&self) -> EcommerceRepository {
+        EcommerceRepository::new(self.pool.clone())
+    }
+
+    /// Get order intake repository
+    pub fn integrations(&self) -> IntegrationRepository {
+        IntegrationRepository::new(self.pool.clone())
+    }
+
+    /// Get stock integrity check repository
+    pub fn integrity(&self) -> IntegrityRepository {
+        IntegrityRepository::new(self.pool.clone())
+    }
+
+    /// Get custom report definition repository
+    pub fn reports(&self) -> ReportRepository {
+        ReportRepository::new(self.pool.clone())
+    }
+
+    /// Get user repository
+    pub fn users(&self) -> UserRepository {
+        UserRepository::new(self.pool.clone())
+    }
+
+    /// Get the users people sign in as: the user repository, or the in-memory store's
+    /// one user per role
+    pub fn sign_in(&self) -> Arc<dyn UserStore> {
+        match &self.memory {
+            Some(store) => store.clone(),
+            None => Arc::new(UserRepository::new(self.pool.clone())),
+        }
+    }
+
+    /// Get integration API key repository
+    pub fn api_keys(&self) -> ApiKeyRepository {
+        ApiKeyRepository::new(self.pool.clone())
+    }
+
+    /// Get sign-in refresh token repository
+    pub fn refresh_tokens(&self) -> RefreshTokenRepository {
+        RefreshTokenRepository::new(self.pool.clone())
+    }
+
+    /// Get audit log repository
+    pub fn audit(&self) -> AuditRepository {
+        AuditRepository::new(self.pool.clone())
+    }
+
+    /// Get saved list view repository
+    pub fn views(&self) -> ViewRepository {
+        ViewRepository::new(self.pool.clone())
+    }
+
+    /// Get asynchronous job repository
+    pub fn jobs(&self) -> JobRepository {
+        JobRepository::new(self.pool.clone())
+    }
+
+    /// Get user preference repository
+    pub fn preferences(&self) -> PreferenceRepository {
+        PreferenceRepository::new(self.pool.clone())
+    }
+
+    /// Get project and material budget repository
+    pub fn projects(&self) -> ProjectRepository {
+        ProjectRepository::new(self.pool.clone())
+    }
+
+    /// Get project site delivery, delivery calendar and vehicle repository
+    pub fn deliveries(&self) -> DeliveryRepository {
+        DeliveryRepository::new(self.pool.clone())
+    }
+
+    /// Get delivery driver, run and proof of delivery repository
+    pub fn fleet(&self) -> FleetRepository {
+        FleetRepository::new(self.pool.clone())
+    }
+
+    /// Get tool loan repository
+    pub fn loans(&self) -> LoanRepository {
+        LoanRepository::new(self.pool.clone())
+    }
+
+    /// Get tool repair order repository
+    pub fn repairs(&self) -> RepairRepository {
+        RepairRepository::new(self.pool.clone())
+    }
+
+    /// Get stock reservation repository
+    pub fn reservations(&self) -> ReservationRepository {
+        ReservationRepository::new(self.pool.clone())
+    }
+
+    /// Get returnable packaging repository
+    pub fn returnables(&self) -> ReturnableRepository {
+        ReturnableRepository::new(self.pool.clone())
+    }
+
+    /// Get logical backup catalog repository
+    pub fn backups(&self) -> BackupRepository {
+        BackupRepository::new(self.pool.clone())
+    }
+
+    /// Get schema migration repository
+    pub fn migrations(&self) -> MigrationRepository {
+        MigrationRepository::new(self.pool.clone())
+    }
+
+    /// Get the repository recording the shard map and copying master data to the shards
+    pub fn shards(&self) -> ShardRepository {
+        ShardRepository::new(self.pool.clone(), self.shard_pools.to_vec())
+    }
+
+    /// Get schema-per-tenant catalog repository
+    pub fn tenants(&self) -> TenantRepository {
+        TenantRepository::new(self.pool.clone())
+    }
+
+    /// Get stock balance repository
+    pub fn stock(&self) -> Arc<dyn StockStore> {
+        if let Some(store) = &self.memory {
+            store.clone()
+        } else if self.event_sourced_stock {
+            Arc::new(StockEventRepository::new(self.pool.clone()))
+        } else {
+            Arc::new(StockRepository::new(self.pool.clone()))
+        }
+    }
+
+    /// Get stock row repository: the balances table with its planning levels and costs,
+    /// whichever store answers stock queries
+    pub fn stock_rows(&self) -> StockRepository {
+        StockRepository::new(self.pool.clone())
+    }
+
+    /// Get stock event stream repository
+    pub fn stock_events(&self) -> StockEventRepository {
+        StockEventRepository::new(self.pool.clone())
+    }
+
+    /// Get stock-out incident repository
+    pub fn stock_outs(&self) -> StockOutRepository {
+        StockOutRepository::new(self.pool.clone())
+    }
+
+    /// Get measuring station repository
+    pub fn stations(&self) -> StationRepository {
+        StationRepository::new(self.pool.clone())
+    }
+
+    /// Get RFID portal and tag repository
+    pub fn rfid(&self) -> RfidRepository {
+        RfidRepository::new(self.pool.clone())
+    }
+
+    /// Get point-of-sale and site consumption repository
+    pub fn consumption(&self) -> ConsumptionRepository {
+        ConsumptionRepository::new(self.pool.clone())
+    }
+
+    /// Get labor productivity reporting repository
+    pub fn labor(&self) -> LaborRepository {
+        LaborRepository::new(self.pool.clone())
+    }
+
+    /// Get warehouse KPI repository
+    pub fn kpis(&self) -> KpiRepository {
+        KpiRepository::new(self.pool.clone())
+    }
+
+    /// Get shift and roster repository
+    pub fn shifts(&self) -> ShiftRepository {
+        ShiftRepository::new(self.pool.clone())
+    }
+
+    /// Get handheld device sync repository
+    pub fn sync(&self) -> SyncRepository {
+        SyncRepository::new(self.pool.clone())
+    }
+
+    /// Get operator task queue repository
+    pub fn tasks(&self) -> TaskRepository {
+        TaskRepository::new(self.pool.clone())
+    }
+
+    /// Get quality inspection repository
+    pub fn quality(&self) -> QualityRepository {
+        QualityRepository::new(self.pool.clone())
+    }
+
+    /// Get outbound order and shipment repository
+    pub fn orders(&self) -> OrderRepository {
+        OrderRepository::new(self.pool.clone())
+    }
+
+    /// Get inbound receipt (ASN) repository
+    pub fn receipts(&self) -> ReceiptRepository {
+        ReceiptRepository::new(self.pool.clone())
+    }
+
+    /// Get EDI document log repository
+    pub fn edi(&self) -> EdiRepository {
+        EdiRepository::new(self.pool.clone())
+    }
+
+    /// Get shipment tracking event repository
+    pub fn tracking(&self) -> TrackingRepository {
+        TrackingRepository::new(self.pool.clone())
+    }
+
+    /// Get zone and location repository
+    pub fn locations(&self) -> LocationRepository {
+        LocationRepository::new(self.pool.clone())
+    }
+
+    /// Get wave picking repository
+    pub fn waves(&self) -> WaveRepository {
+        WaveRepository::new(self.pool.clone())
+    }
+
+    /// Get notification log repository
+    pub fn notifications(&self) -> NotificationRepository {
+        NotificationRepository::new(self.pool.clone())
     }
 
     /// Health check - test database connectivity
     pub async fn health_check(&self) -> Result<bool> {
+        if self.is_mock() {
+            return Ok(true);
+        }
+
         let row: (i32,) = sqlx::query_as("SELECT 1")
             .fetch_one(&self.pool)
             .await?;
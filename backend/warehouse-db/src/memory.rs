@@ -0,0 +1,529 @@
+//! In-memory store with deterministic seed data, used by the API's `--mock` mode
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use futures::stream::{self, BoxStream};
+use std::sync::RwLock;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+use crate::repositories::audit::acting_user;
+use crate::store::*;
+use crate::utils::*;
+
+/// Password of every seeded user
+pub const MOCK_PASSWORD: &str = "mock";
+
+#[derive(Default)]
+pub struct MemoryStore {
+    warehouses: RwLock<Vec<Warehouse>>,
+    items: RwLock<Vec<Item>>,
+    movements: RwLock<Vec<StockMovement>>,
+    users: Vec<User>,
+}
+
+/// Fixed clock for seed rows so every mock run returns identical payloads
+fn seed_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+}
+
+/// Page an already filtered and ordered slice the same way the SQL repositories do
+fn paginate<T: Clone>(rows: &[T], pagination: &PaginationQuery) -> PaginatedResponse<T> {
+    let (page, limit) = validate_pagination(pagination);
+    let offset = calculate_offset(page, limit) as usize;
+    let mode = pagination.count.unwrap_or_default();
+    let total = match mode {
+        CountMode::Skip => None,
+        _ => Some(rows.len() as i64),
+    };
+
+    let window = rows
+        .iter()
+        .skip(offset)
+        .take(limit as usize + 1)
+        .cloned()
+        .collect();
+
+    PaginatedResponse::from_probe(window, total, mode, page, limit)
+}
+
+impl MemoryStore {
+    /// Store pre-filled with the same reference data as the SQL migrations
+    pub fn seeded() -> Self {
+        // One user per role, named after it; user 1 is the system user of the SQL seed
+        let users = ROLES
+            .iter()
+            .zip(2..)
+            .map(|(role, user_id)| User {
+                user_id,
+                username: role.to_lowercase(),
+                email: None,
+                full_name: format!("Mock {}", role.to_lowercase()),
+                role: role.to_string(),
+                is_active: true,
+                created_at: seed_time(),
+                updated_at: seed_time(),
+            })
+            .collect();
+        let store = Self { users, ..Self::default() };
+
+        for (code, name, city, state) in [
+            ("WH001", "Jakarta Warehouse", "Jakarta", "DKI Jakarta"),
+            ("WH002", "Surabaya Warehouse", "Surabaya", "East Java"),
+        ] {
+            store.insert_warehouse(CreateWarehouse {
+                warehouse_code: code.to_string(),
+                warehouse_name: name.to_string(),
+                warehouse_type: None,
+                address: None,
+                city: Some(city.to_string()),
+                state: Some(state.to_string()),
+                postal_code: None,
+                country: None,
+                email: None,
+                phone: None,
+                manager_user_id: None,
+                timezone: None,
+            });
+        }
+
+        for (code, name, description, item_type, category, loanable) in [
+            ("ITM001", "Laptop Dell Inspiron 15", "Dell Inspiron 15 3000 Series", "ASSET", "Electronics", true),
+            ("ITM002", "Steel Rebar 12mm", "Steel reinforcement bar 12mm diameter", "STOCK", "Construction Materials", false),
+            ("ITM003", "Safety Helmet", "Construction safety helmet", "STOCK", "Safety Equipment", false),
+            ("ITM004", "Concrete Mixer 500L", "Heavy duty concrete mixer", "ASSET", "Construction Equipment", true),
+        ] {
+            store.insert_item(CreateItem {
+                item_code: code.to_string(),
+                item_name: name.to_string(),
+                item_description: Some(description.to_string()),
+                item_type: item_type.to_string(),
+                item_usage_type: None,
+                category: Some(category.to_string()),
+                subcategory: None,
+                brand: None,
+                model: None,
+                unit: None,
+                is_loanable: Some(loanable),
+                maintenance_required: None,
+                calibration_required: None,
+                replacement_cost: None,
+                hazmat: None,
+                customs: None,
+            });
+        }
+
+        for (item_id, warehouse_id, quantity, unit_cost) in [
+            (1, 1, 5, 15_000_000),
+            (2, 1, 1000, 25_000),
+            (3, 1, 50, 150_000),
+            (4, 2, 2, 75_000_000),
+        ] {
+            let mut movements = store.movements.write().unwrap();
+            let movement_id = movements.len() as i64 + 1;
+            movements.push(StockMovement {
+                movement_id,
+                item_id,
+                warehouse_id,
+                movement_type: "RECEIPT".to_string(),
+                quantity: Decimal::from(quantity),
+                catch_weight_kg: None,
+                lot_number: None,
+                unit_cost: Some(Decimal::from(unit_cost)),
+                stock_status: STOCK_AVAILABLE.to_string(),
+                consignor_id: None,
+                client_id: None,
+                reference_type: Some("OPENING_BALANCE".to_string()),
+                reference_number: None,
+                notes: None,
+                movement_date: seed_time(),
+                created_at: Some(seed_time()),
+                created_by: Some(1),
+            });
+        }
+
+        store
+    }
+
+    fn insert_warehouse(&self, warehouse: CreateWarehouse) -> Warehouse {
+        let mut warehouses = self.warehouses.write().unwrap();
+        let created = Warehouse {
+            warehouse_id: warehouses.len() as i32 + 1,
+            warehouse_code: warehouse.warehouse_code,
+            warehouse_name: warehouse.warehouse_name,
+            warehouse_type: warehouse.warehouse_type,
+            address: warehouse.address,
+            city: warehouse.city,
+            state: warehouse.state,
+            postal_code: warehouse.postal_code,
+            country: Some(warehouse.country.unwrap_or_else(|| "Indonesia".to_string())),
+            phone: warehouse.phone.map(|phone| normalize_phone(&phone).unwrap_or(phone)),
+            email: warehouse.email,
+            manager_user_id: warehouse.manager_user_id,
+            timezone: warehouse.timezone,
+            is_active: true,
+            created_at: Some(seed_time()),
+            updated_at: Some(seed_time()),
+            created_by: None,
+            updated_by: None,
+        };
+        warehouses.push(created.clone());
+        created
+    }
+
+    fn insert_item(&self, item: CreateItem) -> Item {
+        let mut items = self.items.write().unwrap();
+        let created = Item {
+            item_id: items.len() as i32 + 1,
+            item_code: item.item_code,
+            item_name: item.item_name,
+            item_description: item.item_description,
+            item_type: item.item_type,
+            item_usage_type: Some(item.item_usage_type.unwrap_or_else(|| "CONSUMABLE".to_string())),
+            category: item.category,
+            subcategory: item.subcategory,
+            brand: item.brand,
+            model: item.model,
+            unit: Some(item.unit.unwrap_or_else(|| "PCS".to_string())),
+            weight_kg: None,
+            length_cm: None,
+            width_cm: None,
+            height_cm: None,
+            volume_cbm: None,
+            is_loanable: item.is_loanable.unwrap_or(false),
+            requires_return: false,
+            max_loan_duration_days: Some(30),
+            replacement_cost: item.replacement_cost,
+            maintenance_required: item.maintenance_required.unwrap_or(false),
+            calibration_required: item.calibration_required.unwrap_or(false),
+            standard_cost: None,
+            last_cost: None,
+            average_cost: None,
+            un_number: item.hazmat.as_ref().map(|h| h.un_number.clone()),
+            hazard_class: item.hazmat.as_ref().map(|h| h.hazard_class.clone()),
+            packing_group: item.hazmat.as_ref().and_then(|h| h.packing_group.clone()),
+            proper_shipping_name: item.hazmat.map(|h| h.proper_shipping_name),
+            catch_weight_nominal_kg: None,
+            catch_weight_tolerance_pct: None,
+            hs_code: item.customs.as_ref().map(|c| c.hs_code.clone()),
+            country_of_origin: item.customs.as_ref().map(|c| c.country_of_origin.clone()),
+            customs_description: item.customs.map(|c| c.customs_description),
+            status: "ACTIVE".to_string(),
+            created_at: Some(seed_time()),
+            updated_at: Some(seed_time()),
+            created_by: acting_user(),
+            updated_by: acting_user(),
+        };
+        items.push(created.clone());
+        created
+    }
+
+    /// Active items whose code or name contains `search`, ignoring case
+    fn active_items(&self, search: Option<&str>) -> Vec<Item> {
+        let search = search.map(|search| search.trim().to_lowercase()).filter(|search| !search.is_empty());
+        let matches = |item: &Item| match &search {
+            Some(search) => {
+                item.item_code.to_lowercase().contains(search) || item.item_name.to_lowercase().contains(search)
+            }
+            None => true,
+        };
+        let mut items: Vec<Item> = self
+            .items
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|item| item.status == "ACTIVE" && matches(item))
+            .cloned()
+            .collect();
+        items.sort_by(|a, b| a.item_name.cmp(&b.item_name));
+        items
+    }
+}
+
+#[async_trait]
+impl WarehouseStore for MemoryStore {
+    async fn list(&self, pagination: PaginationQuery) -> Result<PaginatedResponse<Warehouse>> {
+        let mut active: Vec<Warehouse> = self
+            .warehouses
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|warehouse| warehouse.is_active)
+            .cloned()
+            .collect();
+        active.sort_by(|a, b| a.warehouse_name.cmp(&b.warehouse_name));
+
+        Ok(paginate(&active, &pagination))
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Option<Warehouse>> {
+        Ok(self
+            .warehouses
+            .read()
+            .unwrap()
+            .iter()
+            .find(|warehouse| warehouse.warehouse_id == id && warehouse.is_active)
+            .cloned())
+    }
+
+    async fn create(&self, warehouse: CreateWarehouse) -> Result<Warehouse> {
+        Ok(self.insert_warehouse(warehouse))
+    }
+
+    async fn update(&self, id: i32, changes: UpdateWarehouse) -> Result<Option<Warehouse>> {
+        let mut warehouses = self.warehouses.write().unwrap();
+        let Some(warehouse) = warehouses
+            .iter_mut()
+            .find(|warehouse| warehouse.warehouse_id == id && warehouse.is_active)
+        else {
+            return Ok(None);
+        };
+
+        if let Some(name) = changes.warehouse_name {
+            warehouse.warehouse_name = name;
+        }
+        if changes.city.is_some() {
+            warehouse.city = changes.city;
+        }
+        if changes.state.is_some() {
+            warehouse.state = changes.state;
+        }
+        if changes.country.is_some() {
+            warehouse.country = changes.country;
+        }
+        warehouse.updated_at = Some(Utc::now());
+
+        Ok(Some(warehouse.clone()))
+    }
+
+    async fn delete(&self, id: i32) -> Result<bool> {
+        let mut warehouses = self.warehouses.write().unwrap();
+        match warehouses
+            .iter_mut()
+            .find(|warehouse| warehouse.warehouse_id == id && warehouse.is_active)
+        {
+            Some(warehouse) => {
+                warehouse.is_active = false;
+                warehouse.updated_at = Some(Utc::now());
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn code_exists(&self, code: &str, exclude_id: Option<i32>) -> Result<bool> {
+        Ok(self.warehouses.read().unwrap().iter().any(|warehouse| {
+            warehouse.warehouse_code == code
+                && warehouse.is_active
+                && Some(warehouse.warehouse_id) != exclude_id
+        }))
+    }
+}
+
+#[async_trait]
+impl ItemStore for MemoryStore {
+    async fn list(&self, pagination: PaginationQuery) -> Result<PaginatedResponse<Item>> {
+        Ok(paginate(&self.active_items(pagination.search.as_deref()), &pagination))
+    }
+
+    fn stream_active(&self, search: Option<String>) -> BoxStream<'static, Result<Item>> {
+        Box::pin(stream::iter(self.active_items(search.as_deref()).into_iter().map(Ok)))
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Option<Item>> {
+        Ok(self
+            .items
+            .read()
+            .unwrap()
+            .iter()
+            .find(|item| item.item_id == id && item.status == "ACTIVE")
+            .cloned())
+    }
+
+    async fn get_by_code(&self, code: &str) -> Result<Option<Item>> {
+        Ok(self
+            .items
+            .read()
+            .unwrap()
+            .iter()
+            .find(|item| item.item_code == code && item.status == "ACTIVE")
+            .cloned())
+    }
+
+    async fn create(&self, item: CreateItem) -> Result<Item> {
+        Ok(self.insert_item(item))
+    }
+
+    async fn update(&self, id: i32, changes: UpdateItem) -> Result<Option<Item>> {
+        let mut items = self.items.write().unwrap();
+        let Some(item) = items.iter_mut().find(|item| item.item_id == id && item.status == "ACTIVE") else {
+            return Ok(None);
+        };
+
+        if let Some(code) = changes.item_code {
+            item.item_code = code;
+        }
+        if let Some(name) = changes.item_name {
+            item.item_name = name;
+        }
+        if let Some(item_type) = changes.item_type {
+            item.item_type = item_type;
+        }
+        if changes.item_description.is_some() {
+            item.item_description = changes.item_description;
+        }
+        if changes.category.is_some() {
+            item.category = changes.category;
+        }
+        if changes.subcategory.is_some() {
+            item.subcategory = changes.subcategory;
+        }
+        if changes.brand.is_some() {
+            item.brand = changes.brand;
+        }
+        if changes.model.is_some() {
+            item.model = changes.model;
+        }
+        if changes.unit.is_some() {
+            item.unit = changes.unit;
+        }
+        if changes.replacement_cost.is_some() {
+            item.replacement_cost = changes.replacement_cost;
+        }
+        item.updated_at = Some(Utc::now());
+
+        Ok(Some(item.clone()))
+    }
+
+    async fn delete(&self, id: i32) -> Result<bool> {
+        let mut items = self.items.write().unwrap();
+        match items.iter_mut().find(|item| item.item_id == id && item.status == "ACTIVE") {
+            Some(item) => {
+                item.status = "INACTIVE".to_string();
+                item.updated_at = Some(Utc::now());
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn restore(&self, id: i32) -> Result<Option<Item>> {
+        let mut items = self.items.write().unwrap();
+        let Some(item) = items.iter_mut().find(|item| item.item_id == id && item.status == "INACTIVE") else {
+            return Ok(None);
+        };
+        item.status = "ACTIVE".to_string();
+        item.updated_at = Some(Utc::now());
+        Ok(Some(item.clone()))
+    }
+
+    async fn code_exists(&self, code: &str, exclude_id: Option<i32>) -> Result<bool> {
+        Ok(self.items.read().unwrap().iter().any(|item| {
+            item.item_code == code && item.status == "ACTIVE" && Some(item.item_id) != exclude_id
+        }))
+    }
+}
+
+#[async_trait]
+impl MovementStore for MemoryStore {
+    async fn history(&self, filter: MovementFilter, keyset: KeysetQuery) -> Result<KeysetPage<StockMovement>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = self
+            .movements
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|m| after_id.is_none_or(|after| m.movement_id < after))
+            .filter(|m| filter.item_id.is_none_or(|id| m.item_id == id))
+            .filter(|m| filter.warehouse_id.is_none_or(|id| m.warehouse_id == id))
+            .filter(|m| filter.movement_type.as_ref().is_none_or(|t| &m.movement_type == t))
+            .filter(|m| filter.client_id.is_none_or(|id| m.client_id == Some(id)))
+            .take(limit as usize + 1)
+            .cloned()
+            .collect();
+
+        Ok(KeysetPage::from_probe(rows, limit, |m| m.movement_id))
+    }
+}
+
+#[async_trait]
+impl UserStore for MemoryStore {
+    async fn authenticate(&self, username: &str, password: &str) -> Result<Option<User>> {
+        if password != MOCK_PASSWORD {
+            return Ok(None);
+        }
+        Ok(self.users.iter().find(|user| user.username == username && user.is_active).cloned())
+    }
+
+    async fn get(&self, id: i32) -> Result<Option<User>> {
+        Ok(self.users.iter().find(|user| user.user_id == id).cloned())
+    }
+}
+
+#[async_trait]
+impl StockStore for MemoryStore {
+    /// Stock on hand at the end of `filter.as_of`, summed from the movements up to then
+    async fn snapshot(&self, filter: StockFilter) -> Result<StockSnapshot> {
+        let as_of = filter.as_of.unwrap_or_else(|| Utc::now().date_naive());
+        let search = filter.q.map(|search| search.to_lowercase());
+        let items = self.items.read().unwrap();
+
+        let mut balances: Vec<StockBalance> = Vec::new();
+        for movement in self.movements.read().unwrap().iter() {
+            if movement.movement_date.date_naive() > as_of
+                || filter.warehouse_id.is_some_and(|id| movement.warehouse_id != id)
+                || filter.item_id.is_some_and(|id| movement.item_id != id)
+            {
+                continue;
+            }
+            let Some(item) = items.iter().find(|item| item.item_id == movement.item_id) else {
+                continue;
+            };
+            if search.as_ref().is_some_and(|search| {
+                !item.item_code.to_lowercase().contains(search) && !item.item_name.to_lowercase().contains(search)
+            }) {
+                continue;
+            }
+
+            let position = balances.iter().position(|balance| {
+                balance.item_id == movement.item_id
+                    && balance.warehouse_id == movement.warehouse_id
+                    && balance.consignor_id == movement.consignor_id
+            });
+            let balance = match position {
+                Some(position) => &mut balances[position],
+                None => {
+                    balances.push(StockBalance {
+                        item_id: item.item_id,
+                        item_code: item.item_code.clone(),
+                        item_name: item.item_name.clone(),
+                        warehouse_id: movement.warehouse_id,
+                        consignor_id: movement.consignor_id,
+                        client_id: movement.client_id,
+                        quantity_on_hand: Decimal::ZERO,
+                        quantity_quarantined: Decimal::ZERO,
+                        catch_weight_kg: None,
+                    });
+                    balances.last_mut().unwrap()
+                }
+            };
+            if movement.stock_status == STOCK_QUARANTINE {
+                balance.quantity_quarantined += movement.quantity;
+            } else {
+                balance.quantity_on_hand += movement.quantity;
+            }
+            if let Some(weight) = movement.catch_weight_kg {
+                balance.catch_weight_kg = Some(balance.catch_weight_kg.unwrap_or_default() + weight);
+            }
+        }
+
+        balances.retain(|balance| !balance.quantity_on_hand.is_zero() || !balance.quantity_quarantined.is_zero());
+        balances.sort_by(|a, b| {
+            (&a.item_code, a.warehouse_id, a.consignor_id).cmp(&(&b.item_code, b.warehouse_id, b.consignor_id))
+        });
+        Ok(StockSnapshot { as_of, balances })
+    }
+}
@@ -1,21 +0,0 @@
-//! Project repository implementation
-
-use sqlx::PgPool;
-use warehouse_models::*;
-
-#[derive(Clone)]
-pub struct ProjectRepository {
-    pool: PgPool,
-}
-
-impl ProjectRepository {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
-    }
-
-    // TODO: Implement project CRUD operations
-    // This will be implemented in Phase 3
-    pub async fn placeholder(&self) -> Result<()> {
-        Ok(())
-    }
-}
@@ -0,0 +1,85 @@
+use anyhow::Result;
+use rand::distributions::{Alphanumeric, DistString};
+use sqlx::PgPool;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct ApiKeyRepository {
+    pool: PgPool,
+}
+
+impl ApiKeyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Keys newest first
+    pub async fn list(&self, filter: &ApiKeyFilter) -> Result<Vec<ApiKey>> {
+        let keys = sqlx::query_as!(
+            ApiKey,
+            "SELECT key_id, name, key_prefix, scopes, created_by, created_at, last_used_at, revoked_at
+             FROM warehouse.api_keys
+             WHERE $1 OR revoked_at IS NULL
+             ORDER BY key_id DESC",
+            filter.include_revoked
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(keys)
+    }
+
+    pub async fn issue(&self, request: CreateApiKey, created_by: Option<i32>) -> Result<IssuedApiKey> {
+        let api_key = format!("{}{}", API_KEY_PREFIX, Alphanumeric.sample_string(&mut rand::thread_rng(), 40));
+        let mut scopes = request.scopes;
+        scopes.sort();
+        scopes.dedup();
+
+        let key = sqlx::query_as!(
+            ApiKey,
+            "INSERT INTO warehouse.api_keys (name, key_prefix, key_hash, scopes, created_by)
+             VALUES ($1, LEFT($2, 12), ENCODE(SHA256(CONVERT_TO($2, 'UTF8')), 'hex'), $3, $4)
+             RETURNING key_id, name, key_prefix, scopes, created_by, created_at, last_used_at, revoked_at",
+            request.name,
+            api_key,
+            &scopes,
+            created_by
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(IssuedApiKey { key, api_key })
+    }
+
+    /// Revoke a key; requests made with it are refused from now on. Returns None when
+    /// there is no such key.
+    pub async fn revoke(&self, id: i32) -> Result<Option<ApiKey>> {
+        let key = sqlx::query_as!(
+            ApiKey,
+            "UPDATE warehouse.api_keys SET revoked_at = COALESCE(revoked_at, NOW())
+             WHERE key_id = $1
+             RETURNING key_id, name, key_prefix, scopes, created_by, created_at, last_used_at, revoked_at",
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(key)
+    }
+
+    /// The unrevoked key presented, if it is one of ours, marked as used now
+    pub async fn authenticate(&self, api_key: &str) -> Result<Option<ApiKey>> {
+        if !api_key.starts_with(API_KEY_PREFIX) {
+            return Ok(None);
+        }
+
+        let key = sqlx::query_as!(
+            ApiKey,
+            "UPDATE warehouse.api_keys SET last_used_at = NOW()
+             WHERE key_hash = ENCODE(SHA256(CONVERT_TO($1, 'UTF8')), 'hex') AND revoked_at IS NULL
+             RETURNING key_id, name, key_prefix, scopes, created_by, created_at, last_used_at, revoked_at",
+            api_key
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(key)
+    }
+}
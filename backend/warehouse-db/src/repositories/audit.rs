@@ -0,0 +1,75 @@
+//! Naming who the audit log records as making a change. The API runs each request's
+//! handler inside [`as_actor`]; every connection the request then takes from the pool is
+//! tagged with the actor for the audit triggers to read. Work outside a request, such as
+//! scheduled jobs, runs untagged and is recorded without an actor.
+
+use std::future::Future;
+
+use crate::utils::*;
+use anyhow::Result;
+use sqlx::{Executor, PgConnection, PgPool};
+use warehouse_models::*;
+
+tokio::task_local! {
+    static ACTOR: String;
+}
+
+/// Run `work` with its database changes recorded as made by `actor`, e.g. `user:7`
+pub async fn as_actor<F: Future>(actor: String, work: F) -> F::Output {
+    ACTOR.scope(actor, work).await
+}
+
+/// The actor the current task's changes are recorded as made by
+pub fn current_actor() -> Option<String> {
+    ACTOR.try_with(Clone::clone).ok()
+}
+
+/// The user the current task acts for, recorded as `created_by` on the rows it writes;
+/// `None` for API keys and work outside a request
+pub fn acting_user() -> Option<i32> {
+    current_actor()?.strip_prefix("user:")?.parse().ok()
+}
+
+/// Tag a connection taken from the pool with the current actor, clearing the tag it
+/// was returned with
+pub async fn tag_connection(conn: &mut PgConnection) -> sqlx::Result<()> {
+    let actor = current_actor().unwrap_or_default();
+    conn.execute(sqlx::query("SELECT set_config('wms.audit_actor', $1, false)").bind(actor)).await?;
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct AuditRepository {
+    pool: PgPool,
+}
+
+impl AuditRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Changes matching the filter, newest first
+    pub async fn history(&self, filter: &AuditFilter, keyset: KeysetQuery) -> Result<KeysetPage<AuditEntry>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            AuditEntry,
+            "SELECT * FROM warehouse.audit_log
+             WHERE ($1::VARCHAR IS NULL OR entity = $1) AND ($2::TEXT IS NULL OR entity_id = $2)
+               AND ($3::VARCHAR IS NULL OR action = $3) AND ($4::VARCHAR IS NULL OR actor = $4)
+               AND ($5::BIGINT IS NULL OR audit_id < $5)
+             ORDER BY audit_id DESC
+             LIMIT $6",
+            filter.entity,
+            filter.id,
+            filter.action,
+            filter.actor,
+            after_id,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |entry| entry.audit_id))
+    }
+}
@@ -0,0 +1,287 @@
+use anyhow::{bail, ensure, Result};
+use futures::stream::BoxStream;
+use futures::TryStreamExt;
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use warehouse_models::*;
+
+/// Temporary table rows of the table being restored are staged in
+const STAGE: &str = "restore_stage";
+
+#[derive(Clone)]
+pub struct BackupRepository {
+    pool: PgPool,
+}
+
+impl BackupRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self) -> Result<Vec<Backup>> {
+        let backups = sqlx::query_as!(Backup, "SELECT * FROM warehouse.backups ORDER BY backup_id DESC")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(backups)
+    }
+
+    pub async fn get_by_id(&self, id: i64) -> Result<Option<Backup>> {
+        let backup = sqlx::query_as!(Backup, "SELECT * FROM warehouse.backups WHERE backup_id = $1", id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(backup)
+    }
+
+    /// The shared schema followed by every active tenant schema
+    pub async fn schemas(&self) -> Result<Vec<String>> {
+        let tenants = sqlx::query_scalar!(
+            "SELECT schema_name FROM warehouse.tenants WHERE status = 'ACTIVE' ORDER BY tenant_code"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(std::iter::once("warehouse".to_string()).chain(tenants).collect())
+    }
+
+    pub async fn create(&self, archive_key: &str, schema_version: Option<i64>, schemas: &[String]) -> Result<Backup> {
+        let backup = sqlx::query_as!(
+            Backup,
+            "INSERT INTO warehouse.backups (archive_key, format_version, schema_version, schemas)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *",
+            archive_key,
+            BACKUP_FORMAT_VERSION,
+            schema_version,
+            schemas
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(backup)
+    }
+
+    pub async fn complete(&self, id: i64, rows_exported: i64, size_bytes: i64) -> Result<Backup> {
+        let backup = sqlx::query_as!(
+            Backup,
+            "UPDATE warehouse.backups
+             SET status = 'COMPLETED', rows_exported = $2, size_bytes = $3, completed_at = NOW()
+             WHERE backup_id = $1
+             RETURNING *",
+            id,
+            rows_exported,
+            size_bytes
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(backup)
+    }
+
+    pub async fn fail(&self, id: i64, error: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE warehouse.backups SET status = 'FAILED', error = $2, completed_at = NOW() WHERE backup_id = $1",
+            id,
+            error
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Start restoring into this database; everything loaded through the session commits
+    /// or rolls back together
+    pub async fn begin_restore(&self) -> Result<RestoreSession> {
+        Ok(RestoreSession { tx: self.pool.begin().await? })
+    }
+
+    /// Every row of `schema.table` as a JSON object, streamed so large tables are not held
+    /// in memory
+    pub fn rows(&self, schema: &str, table: &str) -> Result<BoxStream<'static, Result<String>>> {
+        ensure!(BACKUP_TABLES.contains(&table), "{} is not a backed up table", table);
+        ensure_schema_name(schema)?;
+
+        let pool = self.pool.clone();
+        let sql = format!("SELECT to_jsonb(t)::TEXT FROM \"{}\".{} t", schema, table);
+        Ok(Box::pin(async_stream::try_stream! {
+            let mut rows = sqlx::query_scalar::<_, String>(&sql).fetch(&pool);
+            while let Some(row) = rows.try_next().await? {
+                yield row;
+            }
+        }))
+    }
+}
+
+/// Loads archived rows table by table: each table's rows are staged, copied into the target
+/// table, and checked against its foreign keys when Postgres rejects them
+pub struct RestoreSession {
+    tx: Transaction<'static, Postgres>,
+}
+
+impl RestoreSession {
+    /// Make sure the backed up tables of `schema` hold no rows, or empty them when
+    /// `replace` is set. Emptying cascades to every table that references them.
+    pub async fn prepare(&mut self, schema: &str, replace: bool) -> Result<()> {
+        ensure_schema_name(schema)?;
+        if replace {
+            let tables: Vec<String> = BACKUP_TABLES.iter().map(|table| format!("\"{}\".{}", schema, table)).collect();
+            self.tx.execute(format!("TRUNCATE {} RESTART IDENTITY CASCADE", tables.join(", ")).as_str()).await?;
+            return Ok(());
+        }
+
+        for table in BACKUP_TABLES {
+            let (populated,): (bool,) = sqlx::query_as(&format!("SELECT EXISTS (SELECT 1 FROM \"{}\".{})", schema, table))
+                .fetch_one(&mut *self.tx)
+                .await?;
+            if populated {
+                bail!("{}.{} is not empty; restore into an empty environment or replace its data", schema, table);
+            }
+        }
+        Ok(())
+    }
+
+    /// Start staging rows for `schema.table`, dropping whatever was staged before
+    pub async fn stage_table(&mut self, schema: &str, table: &str) -> Result<()> {
+        ensure_schema_name(schema)?;
+        ensure!(BACKUP_TABLES.contains(&table), "{} is not a backed up table", table);
+
+        self.tx.execute(format!("DROP TABLE IF EXISTS pg_temp.{}", STAGE).as_str()).await?;
+        // LIKE copies generated columns as plain ones, so they take the archived values
+        self.tx
+            .execute(format!("CREATE TEMP TABLE {} (LIKE \"{}\".{}) ON COMMIT DROP", STAGE, schema, table).as_str())
+            .await?;
+        Ok(())
+    }
+
+    /// Stage rows, each a JSON object as exported
+    pub async fn stage(&mut self, rows: &[String]) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        sqlx::query(&format!(
+            "INSERT INTO {stage} SELECT * FROM jsonb_populate_recordset(NULL::{stage}, $1::TEXT::JSONB)",
+            stage = STAGE
+        ))
+        .bind(format!("[{}]", rows.join(",")))
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Copy the staged rows into `schema.table`. Rows breaking a foreign key leave the table
+    /// empty and are counted per constraint in the returned violations.
+    pub async fn load(&mut self, schema: &str, table: &str) -> Result<(i64, Vec<ReferenceViolation>)> {
+        let columns: Vec<String> = sqlx::query_scalar(
+            "SELECT quote_ident(attname) FROM pg_attribute
+             WHERE attrelid = $1::regclass AND attnum > 0 AND NOT attisdropped AND attgenerated = ''
+             ORDER BY attnum",
+        )
+        .bind(format!("\"{}\".{}", schema, table))
+        .fetch_all(&mut *self.tx)
+        .await?;
+        let columns = columns.join(", ");
+
+        self.tx.execute("SAVEPOINT restore_table").await?;
+        let inserted = sqlx::query(&format!(
+            "INSERT INTO \"{schema}\".{table} ({columns}) OVERRIDING SYSTEM VALUE SELECT {columns} FROM {stage}",
+            schema = schema,
+            table = table,
+            columns = columns,
+            stage = STAGE
+        ))
+        .execute(&mut *self.tx)
+        .await;
+
+        match inserted {
+            Ok(result) => {
+                self.tx.execute("RELEASE SAVEPOINT restore_table").await?;
+                self.reset_sequences(schema, table).await?;
+                Ok((result.rows_affected() as i64, Vec::new()))
+            }
+            Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23503") => {
+                self.tx.execute("ROLLBACK TO SAVEPOINT restore_table").await?;
+                let violations = self.violations(schema, table).await?;
+                Ok((0, violations))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn commit(self) -> Result<()> {
+        self.tx.commit().await?;
+        Ok(())
+    }
+
+    /// Staged rows without a match for each foreign key of `schema.table`
+    async fn violations(&mut self, schema: &str, table: &str) -> Result<Vec<ReferenceViolation>> {
+        let constraints: Vec<(String, String, Vec<String>, Vec<String>)> = sqlx::query_as(
+            "SELECT c.conname::TEXT, c.confrelid::regclass::TEXT,
+                    ARRAY(SELECT quote_ident(attname) FROM unnest(c.conkey) WITH ORDINALITY k(attnum, n)
+                          JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum ORDER BY k.n),
+                    ARRAY(SELECT quote_ident(attname) FROM unnest(c.confkey) WITH ORDINALITY k(attnum, n)
+                          JOIN pg_attribute a ON a.attrelid = c.confrelid AND a.attnum = k.attnum ORDER BY k.n)
+             FROM pg_constraint c
+             WHERE c.contype = 'f' AND c.conrelid = $1::regclass
+             ORDER BY c.conname",
+        )
+        .bind(format!("\"{}\".{}", schema, table))
+        .fetch_all(&mut *self.tx)
+        .await?;
+
+        let mut violations = Vec::new();
+        for (constraint_name, references, columns, referenced) in constraints {
+            let present = columns.iter().map(|column| format!("s.{} IS NOT NULL", column)).collect::<Vec<_>>();
+            let matched = columns
+                .iter()
+                .zip(&referenced)
+                .map(|(column, target)| format!("r.{} = s.{}", target, column))
+                .collect::<Vec<_>>();
+            let (rows,): (i64,) = sqlx::query_as(&format!(
+                "SELECT COUNT(*) FROM {stage} s
+                 WHERE {present} AND NOT EXISTS (SELECT 1 FROM {references} r WHERE {matched})",
+                stage = STAGE,
+                present = present.join(" AND "),
+                references = references,
+                matched = matched.join(" AND ")
+            ))
+            .fetch_one(&mut *self.tx)
+            .await?;
+            if rows > 0 {
+                violations.push(ReferenceViolation { constraint_name, references, rows });
+            }
+        }
+        Ok(violations)
+    }
+
+    /// Move serial sequences past the restored keys so new rows do not collide with them
+    async fn reset_sequences(&mut self, schema: &str, table: &str) -> Result<()> {
+        let qualified = format!("\"{}\".{}", schema, table);
+        let serials: Vec<(String, String)> = sqlx::query_as(
+            "SELECT quote_ident(attname), pg_get_serial_sequence($1, attname) FROM pg_attribute
+             WHERE attrelid = $1::regclass AND attnum > 0 AND NOT attisdropped
+               AND pg_get_serial_sequence($1, attname) IS NOT NULL",
+        )
+        .bind(&qualified)
+        .fetch_all(&mut *self.tx)
+        .await?;
+
+        for (column, sequence) in serials {
+            sqlx::query(&format!("SELECT setval($1, COALESCE(MAX({}), 0) + 1, false) FROM {}", column, qualified))
+                .bind(sequence)
+                .execute(&mut *self.tx)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+fn ensure_schema_name(schema: &str) -> Result<()> {
+    ensure!(
+        !schema.is_empty() && schema.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'),
+        "{} is not a schema name",
+        schema
+    );
+    Ok(())
+}
@@ -0,0 +1,523 @@
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::utils::*;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::chrono::{Days, Months, NaiveDate, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct BillingRepository {
+    pool: PgPool,
+}
+
+impl BillingRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Clients by code
+    pub async fn list_clients(&self) -> Result<Vec<BillingClient>> {
+        let clients = sqlx::query_as!(BillingClient, "SELECT * FROM warehouse.billing_clients ORDER BY client_code")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(clients)
+    }
+
+    pub async fn get_client(&self, id: i32) -> Result<Option<BillingClient>> {
+        let client = sqlx::query_as!(BillingClient, "SELECT * FROM warehouse.billing_clients WHERE client_id = $1", id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(client)
+    }
+
+    pub async fn client_code_exists(&self, code: &str) -> Result<bool> {
+        let exists =
+            sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM warehouse.billing_clients WHERE client_code = $1)", code)
+                .fetch_one(&self.pool)
+                .await?
+                .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    pub async fn create_client(&self, client: CreateBillingClient) -> Result<BillingClient> {
+        let created = sqlx::query_as!(
+            BillingClient,
+            "INSERT INTO warehouse.billing_clients (client_code, client_name, currency)
+             VALUES ($1, $2, COALESCE($3, 'IDR'))
+             RETURNING *",
+            client.client_code,
+            client.client_name,
+            client.currency.map(|currency| currency.to_uppercase())
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(created)
+    }
+
+    /// Items the client owns
+    pub async fn items(&self, client_id: i32) -> Result<Vec<ClientItem>> {
+        let items = sqlx::query_as!(
+            ClientItem,
+            "SELECT c.item_id, i.item_code, c.assigned_at
+             FROM warehouse.billing_client_items c
+             JOIN warehouse.items i ON i.item_id = c.item_id
+             WHERE c.client_id = $1
+             ORDER BY i.item_code",
+            client_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(items)
+    }
+
+    /// Put items in the client's ownership. Items already owned by the client keep their
+    /// assignment date; items owned by another client are rejected.
+    pub async fn assign_items(&self, client_id: i32, assignment: AssignClientItems) -> Result<Vec<ClientItem>> {
+        let mut tx = self.pool.begin().await?;
+
+        let unknown_items = sqlx::query_scalar!(
+            "SELECT id AS \"id!\" FROM UNNEST($1::INT[]) id
+             EXCEPT SELECT item_id FROM warehouse.items",
+            &assignment.item_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if let Some(item_id) = unknown_items.first() {
+            return Err(violation(format!("item {} does not exist", item_id)));
+        }
+
+        let taken = sqlx::query!(
+            "SELECT i.item_code, b.client_code
+             FROM warehouse.billing_client_items c
+             JOIN warehouse.items i ON i.item_id = c.item_id
+             JOIN warehouse.billing_clients b ON b.client_id = c.client_id
+             WHERE c.item_id = ANY($1) AND c.client_id <> $2
+             ORDER BY i.item_code
+             LIMIT 1",
+            &assignment.item_ids,
+            client_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(taken) = taken {
+            return Err(violation(format!("{} already belongs to client {}", taken.item_code, taken.client_code)));
+        }
+
+        sqlx::query!(
+            "INSERT INTO warehouse.billing_client_items (item_id, client_id)
+             SELECT id, $1 FROM UNNEST($2::INT[]) id
+             ON CONFLICT (item_id) DO NOTHING",
+            client_id,
+            &assignment.item_ids
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        self.items(client_id).await
+    }
+
+    /// The client's rate card by charge code
+    pub async fn rates(&self, client_id: i32) -> Result<Vec<BillingRate>> {
+        let rates = sqlx::query_as!(
+            BillingRate,
+            "SELECT * FROM warehouse.billing_rates WHERE client_id = $1 ORDER BY charge_code",
+            client_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rates)
+    }
+
+    /// Add or replace rate card entries. Invoices already raised keep the rates they were
+    /// priced at.
+    pub async fn set_rates(&self, client_id: i32, rates: SetBillingRates) -> Result<Vec<BillingRate>> {
+        let codes: Vec<String> = rates.rates.iter().map(|rate| rate.charge_code.to_uppercase()).collect();
+        let descriptions: Vec<Option<String>> = rates.rates.iter().map(|rate| rate.description.clone()).collect();
+        let unit_rates: Vec<Decimal> = rates.rates.iter().map(|rate| rate.unit_rate).collect();
+
+        let saved = sqlx::query_as!(
+            BillingRate,
+            "INSERT INTO warehouse.billing_rates (client_id, charge_code, description, unit_rate)
+             SELECT DISTINCT ON (code) $1, code, description, unit_rate
+             FROM UNNEST($2::VARCHAR[], $3::VARCHAR[], $4::DECIMAL[]) WITH ORDINALITY
+                 AS r(code, description, unit_rate, n)
+             ORDER BY code, n DESC
+             ON CONFLICT (client_id, charge_code) DO UPDATE SET
+                 description = EXCLUDED.description,
+                 unit_rate = EXCLUDED.unit_rate,
+                 updated_at = NOW()
+             RETURNING *",
+            client_id,
+            &codes,
+            &descriptions as &[Option<String>],
+            &unit_rates
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(saved)
+    }
+
+    /// Record a value-added service against a service charge code on the client's rate card
+    pub async fn record_service(&self, client_id: i32, service: RecordService) -> Result<BillableEvent> {
+        let charge_code = service.charge_code.to_uppercase();
+        if BILLING_ACTIVITY_CODES.contains(&charge_code.as_str()) {
+            return Err(violation(format!("{} is captured from warehouse activity", charge_code)));
+        }
+        let event_date = service.event_date.unwrap_or_else(|| Utc::now().date_naive());
+
+        let mut tx = self.pool.begin().await?;
+
+        let rated = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.billing_rates WHERE client_id = $1 AND charge_code = $2)",
+            client_id,
+            charge_code
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .unwrap_or(false);
+        if !rated {
+            return Err(violation(format!("{} is not on the client's rate card", charge_code)));
+        }
+
+        let warehouse_exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.warehouses WHERE warehouse_id = $1)",
+            service.warehouse_id
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .unwrap_or(false);
+        if !warehouse_exists {
+            return Err(violation(format!("warehouse {} does not exist", service.warehouse_id)));
+        }
+
+        if let Some(item_id) = service.item_id {
+            let owned = sqlx::query_scalar!(
+                "SELECT EXISTS(SELECT 1 FROM warehouse.billing_client_items WHERE client_id = $1 AND item_id = $2)",
+                client_id,
+                item_id
+            )
+            .fetch_one(&mut *tx)
+            .await?
+            .unwrap_or(false);
+            if !owned {
+                return Err(violation(format!("item {} does not belong to the client", item_id)));
+            }
+        }
+
+        if let Some(invoice_number) = invoice_covering(&mut tx, client_id, event_date).await? {
+            return Err(violation(format!("{} is already invoiced on {}", event_date, invoice_number)));
+        }
+
+        let event = sqlx::query_as!(
+            BillableEvent,
+            "INSERT INTO warehouse.billable_events (
+                 client_id, event_type, charge_code, warehouse_id, item_id, quantity, event_date, notes, created_by
+             )
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             RETURNING *",
+            client_id,
+            BILLING_SERVICE,
+            charge_code,
+            service.warehouse_id,
+            service.item_id,
+            service.quantity,
+            event_date,
+            service.notes,
+            acting_user() // created_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(event)
+    }
+
+    /// Capture billable activity: receipts and picks from stock movements not captured yet,
+    /// and a storage snapshot of client stock on hand for `storage_date` unless one was
+    /// already taken that day. Safe to run repeatedly.
+    pub async fn capture(&self, storage_date: NaiveDate) -> Result<BillingCapture> {
+        let mut tx = self.pool.begin().await?;
+
+        let (receipts, picks) = capture_movements(&mut tx).await?;
+
+        let storage = sqlx::query!(
+            "INSERT INTO warehouse.billable_events (
+                 client_id, event_type, charge_code, warehouse_id, item_id, quantity, event_date
+             )
+             SELECT c.client_id, $1, $1, s.warehouse_id, s.item_id, SUM(s.quantity_on_hand), $2
+             FROM warehouse.stock_inventory s
+             JOIN warehouse.billing_client_items c ON c.item_id = s.item_id
+             WHERE c.assigned_at::DATE <= $2
+             GROUP BY c.client_id, s.warehouse_id, s.item_id
+             HAVING SUM(s.quantity_on_hand) > 0
+             ON CONFLICT (client_id, item_id, warehouse_id, event_date) WHERE event_type = 'STORAGE' DO NOTHING",
+            BILLING_STORAGE,
+            storage_date
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+        Ok(BillingCapture { receipts, picks, storage })
+    }
+
+    /// Captured events, newest first
+    pub async fn events(
+        &self,
+        client_id: i32,
+        filter: BillableEventFilter,
+        keyset: KeysetQuery,
+    ) -> Result<KeysetPage<BillableEvent>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            BillableEvent,
+            "SELECT * FROM warehouse.billable_events
+             WHERE client_id = $1
+               AND ($2::DATE IS NULL OR event_date >= $2)
+               AND ($3::DATE IS NULL OR event_date <= $3)
+               AND ($4::VARCHAR IS NULL OR event_type = $4)
+               AND (NOT COALESCE($5, FALSE) OR invoice_id IS NULL)
+               AND ($6::BIGINT IS NULL OR event_id < $6)
+             ORDER BY event_id DESC
+             LIMIT $7",
+            client_id,
+            filter.from,
+            filter.to,
+            filter.event_type.map(|event_type| event_type.to_uppercase()),
+            filter.unbilled,
+            after_id,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |event| event.event_id))
+    }
+
+    /// Invoice a finished calendar month: all of the client's unbilled events in the month
+    /// are priced against the rate card, one line per charge code
+    pub async fn create_invoice(&self, client_id: i32, request: CreateInvoice) -> Result<InvoiceDetail> {
+        let period_start = request.month;
+        let period_end = period_start + Months::new(1) - Days::new(1);
+        if period_end >= Utc::now().date_naive() {
+            return Err(violation(format!("{} has not ended yet", period_start.format("%B %Y"))));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        // The row lock keeps two invoices from billing the same events
+        let client = sqlx::query_as!(
+            BillingClient,
+            "SELECT * FROM warehouse.billing_clients WHERE client_id = $1 FOR UPDATE",
+            client_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| violation(format!("client {} does not exist", client_id)))?;
+
+        if let Some(invoice_number) = invoice_covering(&mut tx, client_id, period_start).await? {
+            return Err(violation(format!(
+                "{} is already invoiced for {} on {}",
+                client.client_code,
+                period_start.format("%B %Y"),
+                invoice_number
+            )));
+        }
+
+        // Movements posted since the last capture still belong to the month
+        capture_movements(&mut tx).await?;
+
+        let billable = sqlx::query!(
+            r#"SELECT e.charge_code, r.description, r.unit_rate AS "unit_rate?", SUM(e.quantity) AS "quantity!"
+               FROM warehouse.billable_events e
+               LEFT JOIN warehouse.billing_rates r ON r.client_id = e.client_id AND r.charge_code = e.charge_code
+               WHERE e.client_id = $1 AND e.invoice_id IS NULL AND e.event_date BETWEEN $2 AND $3
+               GROUP BY e.charge_code, r.description, r.unit_rate
+               ORDER BY e.charge_code"#,
+            client_id,
+            period_start,
+            period_end
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if billable.is_empty() {
+            return Err(violation(format!(
+                "{} has no billable activity in {}",
+                client.client_code,
+                period_start.format("%B %Y")
+            )));
+        }
+        if let Some(unrated) = billable.iter().find(|line| line.unit_rate.is_none()) {
+            return Err(violation(format!(
+                "{} has no rate for {} on its rate card",
+                client.client_code, unrated.charge_code
+            )));
+        }
+
+        let total_amount: Decimal =
+            billable.iter().map(|line| line.quantity * line.unit_rate.unwrap_or_default()).sum();
+        let invoice_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.billing_invoices (
+                 invoice_id, invoice_number, client_id, period_start, period_end, currency, total_amount, created_by
+             )
+             SELECT next_id, 'BIL-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4, $5, $6
+             FROM (SELECT nextval('warehouse.billing_invoices_invoice_id_seq')::INT AS next_id) seq
+             RETURNING invoice_id",
+            client_id,
+            period_start,
+            period_end,
+            client.currency,
+            total_amount,
+            acting_user() // created_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for line in &billable {
+            let unit_rate = line.unit_rate.unwrap_or_default();
+            sqlx::query!(
+                "INSERT INTO warehouse.billing_invoice_lines (
+                     invoice_id, charge_code, description, quantity, unit_rate, amount
+                 )
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+                invoice_id,
+                line.charge_code,
+                line.description,
+                line.quantity,
+                unit_rate,
+                line.quantity * unit_rate
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.billable_events SET invoice_id = $4
+             WHERE client_id = $1 AND invoice_id IS NULL AND event_date BETWEEN $2 AND $3",
+            client_id,
+            period_start,
+            period_end,
+            invoice_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let detail = load_invoice(&mut tx, invoice_id).await?.expect("invoice inserted above");
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Invoices raised for a client, newest first
+    pub async fn invoices(&self, client_id: i32) -> Result<Vec<BillingInvoice>> {
+        let invoices = sqlx::query_as!(
+            BillingInvoice,
+            "SELECT * FROM warehouse.billing_invoices WHERE client_id = $1 ORDER BY period_start DESC",
+            client_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(invoices)
+    }
+
+    pub async fn get_invoice(&self, id: i32) -> Result<Option<InvoiceDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_invoice(&mut conn, id).await
+    }
+
+    /// Invoice lines of every client for a month, for the accounting export
+    pub async fn export(&self, month: NaiveDate) -> Result<Vec<InvoiceExportLine>> {
+        let lines = sqlx::query_as!(
+            InvoiceExportLine,
+            "SELECT i.invoice_number, c.client_code, i.period_start, i.period_end,
+                    l.charge_code, l.description, l.quantity, l.unit_rate, l.amount, i.currency
+             FROM warehouse.billing_invoices i
+             JOIN warehouse.billing_clients c ON c.client_id = i.client_id
+             JOIN warehouse.billing_invoice_lines l ON l.invoice_id = i.invoice_id
+             WHERE i.period_start = $1
+             ORDER BY i.invoice_number, l.line_id",
+            month
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(lines)
+    }
+}
+
+/// Receipts and shipments of client-owned goods, once per movement
+async fn capture_movements(conn: &mut PgConnection) -> Result<(u64, u64)> {
+    let captured = sqlx::query!(
+        r#"WITH captured AS (
+               INSERT INTO warehouse.billable_events (
+                   client_id, event_type, charge_code, warehouse_id, item_id, quantity, event_date, movement_id
+               )
+               SELECT m.client_id, kind.event_type, kind.event_type, m.warehouse_id, m.item_id, ABS(m.quantity),
+                      m.movement_date::DATE, m.movement_id
+               FROM warehouse.stock_movements m
+               CROSS JOIN LATERAL (
+                   SELECT CASE WHEN m.movement_type = 'RECEIPT' THEN $1 ELSE $2 END AS event_type
+               ) kind
+               WHERE m.client_id IS NOT NULL
+                 AND ((m.movement_type = 'RECEIPT' AND m.quantity > 0)
+                      OR (m.movement_type = 'SHIPMENT' AND m.quantity < 0))
+               ON CONFLICT (movement_id) DO NOTHING
+               RETURNING event_type
+           )
+           SELECT COUNT(*) FILTER (WHERE event_type = $1) AS "receipts!",
+                  COUNT(*) FILTER (WHERE event_type = $2) AS "picks!"
+           FROM captured"#,
+        BILLING_RECEIPT,
+        BILLING_PICK
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok((captured.receipts as u64, captured.picks as u64))
+}
+
+/// Number of the client's invoice covering `date`, if that month is already invoiced
+async fn invoice_covering(conn: &mut PgConnection, client_id: i32, date: NaiveDate) -> Result<Option<String>> {
+    let number = sqlx::query_scalar!(
+        "SELECT invoice_number FROM warehouse.billing_invoices
+         WHERE client_id = $1 AND $2 BETWEEN period_start AND period_end",
+        client_id,
+        date
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(number)
+}
+
+async fn load_invoice(conn: &mut PgConnection, id: i32) -> Result<Option<InvoiceDetail>> {
+    let Some(invoice) =
+        sqlx::query_as!(BillingInvoice, "SELECT * FROM warehouse.billing_invoices WHERE invoice_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    else {
+        return Ok(None);
+    };
+
+    let lines = sqlx::query_as!(
+        InvoiceLine,
+        "SELECT * FROM warehouse.billing_invoice_lines WHERE invoice_id = $1 ORDER BY line_id",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(InvoiceDetail { invoice, lines }))
+}
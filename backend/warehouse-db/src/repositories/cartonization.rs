@@ -0,0 +1,114 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct CartonizationRepository {
+    pool: PgPool,
+}
+
+impl CartonizationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// A warehouse's box sizes, smallest first
+    pub async fn boxes(&self, warehouse_id: i32, active_only: bool) -> Result<Vec<BoxSize>> {
+        let boxes = sqlx::query_as!(
+            BoxSize,
+            "SELECT * FROM warehouse.box_sizes
+             WHERE warehouse_id = $1 AND (is_active OR NOT $2)
+             ORDER BY length_cm * width_cm * height_cm, max_weight_kg, box_code",
+            warehouse_id,
+            active_only
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(boxes)
+    }
+
+    pub async fn get_box(&self, id: i32) -> Result<Option<BoxSize>> {
+        let size = sqlx::query_as!(BoxSize, "SELECT * FROM warehouse.box_sizes WHERE box_id = $1", id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(size)
+    }
+
+    pub async fn box_code_exists(&self, warehouse_id: i32, code: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.box_sizes WHERE warehouse_id = $1 AND box_code = $2)",
+            warehouse_id,
+            code
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    pub async fn create_box(&self, warehouse_id: i32, size: CreateBoxSize) -> Result<BoxSize> {
+        let created = sqlx::query_as!(
+            BoxSize,
+            "INSERT INTO warehouse.box_sizes (
+                 warehouse_id, box_code, length_cm, width_cm, height_cm, max_weight_kg, tare_weight_kg
+             )
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING *",
+            warehouse_id,
+            size.box_code,
+            size.length_cm,
+            size.width_cm,
+            size.height_cm,
+            size.max_weight_kg,
+            size.tare_weight_kg
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(created)
+    }
+
+    /// Dimensions are fixed once a box is in use; retire it and add a new size instead
+    pub async fn update_box(&self, id: i32, update: UpdateBoxSize) -> Result<Option<BoxSize>> {
+        let updated = sqlx::query_as!(
+            BoxSize,
+            "UPDATE warehouse.box_sizes SET
+                 max_weight_kg = COALESCE($2, max_weight_kg),
+                 tare_weight_kg = COALESCE($3, tare_weight_kg),
+                 is_active = COALESCE($4, is_active),
+                 updated_at = NOW()
+             WHERE box_id = $1
+             RETURNING *",
+            id,
+            update.max_weight_kg,
+            update.tare_weight_kg,
+            update.is_active
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(updated)
+    }
+
+    /// Quantities of an order still to pack, with the item measurements cartonization needs
+    pub async fn packing_lines(&self, order_id: i32) -> Result<Vec<PackingLine>> {
+        let lines = sqlx::query_as!(
+            PackingLine,
+            r#"SELECT l.line_id, l.item_id, i.item_code,
+                      l.quantity_ordered - l.quantity_shipped - l.quantity_packed AS "quantity!",
+                      i.weight_kg, i.length_cm, i.width_cm, i.height_cm, i.volume_cbm
+               FROM warehouse.outbound_order_lines l
+               JOIN warehouse.items i ON i.item_id = l.item_id
+               WHERE l.order_id = $1 AND l.quantity_ordered - l.quantity_shipped - l.quantity_packed > 0
+               ORDER BY l.line_number"#,
+            order_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(lines)
+    }
+}
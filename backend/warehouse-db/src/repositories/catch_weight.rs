@@ -0,0 +1,96 @@
+use crate::error::violation;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct CatchWeightRepository {
+    pool: PgPool,
+}
+
+impl CatchWeightRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Make an item catch-weight, or count it in pieces only again with None. Stock already
+    /// held starts out at its nominal weight. Returns false when the item does not exist.
+    pub async fn set_profile(&self, item_id: i32, profile: Option<CatchWeightProfile>) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query!(
+            "UPDATE warehouse.items SET catch_weight_nominal_kg = $2, catch_weight_tolerance_pct = $3, updated_at = NOW()
+             WHERE item_id = $1 AND status = 'ACTIVE'",
+            item_id,
+            profile.as_ref().map(|p| p.nominal_weight_kg),
+            profile.as_ref().map(|p| p.tolerance_pct)
+        )
+        .execute(&mut *tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.stock_inventory SET
+                 catch_weight_kg = CASE WHEN $2::DECIMAL IS NULL THEN NULL
+                                        ELSE COALESCE(catch_weight_kg, (quantity_on_hand + quantity_quarantined) * $2) END
+             WHERE item_id = $1",
+            item_id,
+            profile.as_ref().map(|p| p.nominal_weight_kg)
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+}
+
+pub(crate) async fn profile(conn: &mut PgConnection, item_id: i32) -> Result<Option<CatchWeightProfile>> {
+    let row = sqlx::query!(
+        "SELECT catch_weight_nominal_kg, catch_weight_tolerance_pct FROM warehouse.items WHERE item_id = $1",
+        item_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(row.and_then(|row| match (row.catch_weight_nominal_kg, row.catch_weight_tolerance_pct) {
+        (Some(nominal_weight_kg), Some(tolerance_pct)) => Some(CatchWeightProfile { nominal_weight_kg, tolerance_pct }),
+        _ => None,
+    }))
+}
+
+/// Check the weight captured for a receipt or shipment line against the item's tolerance.
+/// Returns the weight to record, None for items counted in pieces only.
+pub(crate) async fn check_weight(
+    conn: &mut PgConnection,
+    item_id: i32,
+    quantity: Decimal,
+    weight_kg: Option<Decimal>,
+) -> Result<Option<Decimal>> {
+    let Some(profile) = profile(conn, item_id).await? else {
+        if weight_kg.is_some() {
+            return Err(violation(format!("item {} is not tracked by catch weight", item_id)));
+        }
+        return Ok(None);
+    };
+    let Some(weight_kg) = weight_kg else {
+        return Err(violation(format!("item {} is sold by catch weight; record the actual weight", item_id)));
+    };
+
+    let (min, max) = profile.bounds(quantity);
+    if weight_kg < min || weight_kg > max {
+        return Err(violation(format!(
+            "{} kg for {} of item {} is outside the {}% tolerance ({} to {} kg)",
+            weight_kg.normalize(),
+            quantity.normalize(),
+            item_id,
+            profile.tolerance_pct.normalize(),
+            min.round_dp(3).normalize(),
+            max.round_dp(3).normalize()
+        )));
+    }
+    Ok(Some(weight_kg))
+}
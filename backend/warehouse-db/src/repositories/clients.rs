@@ -0,0 +1,115 @@
+use anyhow::Result;
+use rand::distributions::{Alphanumeric, DistString};
+use sqlx::PgPool;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct ClientRepository {
+    pool: PgPool,
+}
+
+impl ClientRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Keys issued to a client, newest first, revoked ones included
+    pub async fn keys(&self, client_id: i32) -> Result<Vec<ClientApiKey>> {
+        let keys = sqlx::query_as!(
+            ClientApiKey,
+            "SELECT key_id, client_id, key_prefix, label, created_at, last_used_at, revoked_at
+             FROM warehouse.client_api_keys
+             WHERE client_id = $1
+             ORDER BY key_id DESC",
+            client_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(keys)
+    }
+
+    pub async fn issue_key(&self, client_id: i32, request: CreateClientApiKey) -> Result<IssuedClientApiKey> {
+        let api_key = format!("{}{}", CLIENT_KEY_PREFIX, Alphanumeric.sample_string(&mut rand::thread_rng(), 40));
+
+        let key = sqlx::query_as!(
+            ClientApiKey,
+            "INSERT INTO warehouse.client_api_keys (client_id, key_prefix, key_hash, label)
+             VALUES ($1, LEFT($2, 12), ENCODE(SHA256(CONVERT_TO($2, 'UTF8')), 'hex'), $3)
+             RETURNING key_id, client_id, key_prefix, label, created_at, last_used_at, revoked_at",
+            client_id,
+            api_key,
+            request.label
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(IssuedClientApiKey { key, api_key })
+    }
+
+    /// Revoke a key; requests made with it are refused from now on. Returns None when the
+    /// client has no such key.
+    pub async fn revoke_key(&self, client_id: i32, key_id: i32) -> Result<Option<ClientApiKey>> {
+        let key = sqlx::query_as!(
+            ClientApiKey,
+            "UPDATE warehouse.client_api_keys SET revoked_at = COALESCE(revoked_at, NOW())
+             WHERE client_id = $1 AND key_id = $2
+             RETURNING key_id, client_id, key_prefix, label, created_at, last_used_at, revoked_at",
+            client_id,
+            key_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(key)
+    }
+
+    /// The active client a presented key belongs to, if the key is valid
+    pub async fn authenticate(&self, api_key: &str) -> Result<Option<BillingClient>> {
+        if !api_key.starts_with(CLIENT_KEY_PREFIX) {
+            return Ok(None);
+        }
+
+        let client = sqlx::query_as!(
+            BillingClient,
+            "WITH used AS (
+                 UPDATE warehouse.client_api_keys SET last_used_at = NOW()
+                 WHERE key_hash = ENCODE(SHA256(CONVERT_TO($1, 'UTF8')), 'hex') AND revoked_at IS NULL
+                 RETURNING client_id
+             )
+             SELECT c.* FROM warehouse.billing_clients c JOIN used ON used.client_id = c.client_id
+             WHERE c.is_active",
+            api_key
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(client)
+    }
+
+    /// Stock the client owns, by item and warehouse
+    pub async fn stock(&self, client_id: i32, filter: ClientStockFilter) -> Result<Vec<ClientStock>> {
+        let stock = sqlx::query_as!(
+            ClientStock,
+            r#"SELECT s.item_id, i.item_code, i.item_name, s.warehouse_id,
+                      SUM(s.quantity_on_hand) AS "quantity_on_hand!",
+                      SUM(s.quantity_reserved) AS "quantity_reserved!",
+                      SUM(s.quantity_available) AS "quantity_available!",
+                      SUM(s.quantity_quarantined) AS "quantity_quarantined!"
+               FROM warehouse.stock_inventory s
+               JOIN warehouse.items i ON i.item_id = s.item_id
+               WHERE s.client_id = $1
+                 AND ($2::INT IS NULL OR s.warehouse_id = $2)
+                 AND ($3::INT IS NULL OR s.item_id = $3)
+               GROUP BY s.item_id, i.item_code, i.item_name, s.warehouse_id
+               ORDER BY i.item_code, s.warehouse_id"#,
+            client_id,
+            filter.warehouse_id,
+            filter.item_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(stock)
+    }
+}
@@ -0,0 +1,299 @@
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::movements::{consume_consignment, load_movement};
+use crate::repositories::postings::{claim, record_result, Claim};
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::chrono::Utc;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct ConsignmentRepository {
+    pool: PgPool,
+}
+
+impl ConsignmentRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Consignors by code
+    pub async fn list(&self) -> Result<Vec<Consignor>> {
+        let consignors = sqlx::query_as!(Consignor, "SELECT * FROM warehouse.consignors ORDER BY consignor_code")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(consignors)
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<Consignor>> {
+        let consignor = sqlx::query_as!(Consignor, "SELECT * FROM warehouse.consignors WHERE consignor_id = $1", id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(consignor)
+    }
+
+    pub async fn code_exists(&self, code: &str) -> Result<bool> {
+        let exists =
+            sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM warehouse.consignors WHERE consignor_code = $1)", code)
+                .fetch_one(&self.pool)
+                .await?
+                .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    pub async fn create(&self, consignor: CreateConsignor) -> Result<Consignor> {
+        let created = sqlx::query_as!(
+            Consignor,
+            "INSERT INTO warehouse.consignors (consignor_code, consignor_name, currency)
+             VALUES ($1, $2, COALESCE($3, 'IDR'))
+             RETURNING *",
+            consignor.consignor_code,
+            consignor.consignor_name,
+            consignor.currency.map(|currency| currency.to_uppercase())
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(created)
+    }
+
+    /// Set agreed prices. Consumption already recorded keeps the price it was taken at.
+    pub async fn set_prices(&self, consignor_id: i32, prices: SetConsignmentPrices) -> Result<Vec<ConsignmentPrice>> {
+        let item_ids: Vec<i32> = prices.prices.iter().map(|price| price.item_id).collect();
+        let unit_prices: Vec<Decimal> = prices.prices.iter().map(|price| price.unit_price).collect();
+
+        let mut tx = self.pool.begin().await?;
+
+        let unknown_items = sqlx::query_scalar!(
+            "SELECT id AS \"id!\" FROM UNNEST($1::INT[]) id
+             EXCEPT SELECT item_id FROM warehouse.items",
+            &item_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if let Some(item_id) = unknown_items.first() {
+            return Err(violation(format!("item {} does not exist", item_id)));
+        }
+
+        let saved = sqlx::query_as!(
+            ConsignmentPrice,
+            "INSERT INTO warehouse.consignment_prices (consignor_id, item_id, unit_price)
+             SELECT DISTINCT ON (item_id) $1, item_id, unit_price
+             FROM UNNEST($2::INT[], $3::DECIMAL[]) WITH ORDINALITY AS p(item_id, unit_price, n)
+             ORDER BY item_id, n DESC
+             ON CONFLICT (consignor_id, item_id) DO UPDATE SET
+                 unit_price = EXCLUDED.unit_price,
+                 updated_at = NOW()
+             RETURNING *",
+            consignor_id,
+            &item_ids,
+            &unit_prices
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(saved)
+    }
+
+    /// Stock a consignor owns in our warehouses
+    pub async fn stock(&self, consignor_id: i32) -> Result<Vec<ConsignmentStock>> {
+        let stock = sqlx::query_as!(
+            ConsignmentStock,
+            r#"SELECT s.item_id, i.item_code, s.warehouse_id,
+                      s.quantity_on_hand AS "quantity_on_hand!", p.unit_price AS "unit_price?"
+               FROM warehouse.stock_inventory s
+               JOIN warehouse.items i ON i.item_id = s.item_id
+               LEFT JOIN warehouse.consignment_prices p ON p.consignor_id = s.consignor_id AND p.item_id = s.item_id
+               WHERE s.consignor_id = $1 AND s.quantity_on_hand <> 0
+               ORDER BY s.warehouse_id, i.item_code"#,
+            consignor_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(stock)
+    }
+
+    /// Take consigned stock into our ownership at the agreed price
+    pub async fn consume(&self, consignor_id: i32, consumption: ConsumeConsignment) -> Result<StockMovement> {
+        let mut tx = self.pool.begin().await?;
+
+        let reference_id = consumption.reference_id.as_deref();
+        let claimed = claim(&mut tx, reference_id, POSTING_CONSIGNMENT_CONSUME, consignor_id).await?;
+        if let Claim::Replay(movement_id) = claimed {
+            let movement_id = movement_id.expect("movement recorded with its key");
+            return load_movement(&mut tx, movement_id).await;
+        }
+
+        let consumed = consume_consignment(
+            &mut tx,
+            consignor_id,
+            consumption.item_id,
+            consumption.warehouse_id,
+            consumption.quantity,
+            None,
+            consumption.notes,
+        )
+        .await?;
+        record_result(&mut tx, reference_id, consumed.movement_id).await?;
+
+        tx.commit().await?;
+        Ok(consumed)
+    }
+
+    /// Consumption over `[from, to)` by item, warehouse and price
+    pub async fn consumption(&self, consignor_id: i32, query: ConsumptionQuery) -> Result<Vec<ConsumptionLine>> {
+        let lines = sqlx::query_as!(
+            ConsumptionLine,
+            r#"SELECT m.item_id, i.item_code, m.warehouse_id,
+                      COALESCE(m.unit_cost, 0) AS "unit_price!",
+                      -SUM(m.quantity) AS "quantity!",
+                      -SUM(m.quantity * COALESCE(m.unit_cost, 0)) AS "amount!",
+                      -COALESCE(SUM(m.quantity) FILTER (WHERE m.settlement_id IS NULL), 0) AS "unsettled_quantity!"
+               FROM warehouse.stock_movements m
+               JOIN warehouse.items i ON i.item_id = m.item_id
+               WHERE m.consignor_id = $1 AND m.movement_type = $2
+                 AND ($3::TIMESTAMPTZ IS NULL OR m.movement_date >= $3)
+                 AND ($4::TIMESTAMPTZ IS NULL OR m.movement_date < $4)
+                 AND ($5::INT IS NULL OR m.warehouse_id = $5)
+               GROUP BY m.item_id, i.item_code, m.warehouse_id, m.unit_cost
+               ORDER BY i.item_code, m.warehouse_id, m.unit_cost"#,
+            consignor_id,
+            MOVEMENT_CONSIGNMENT_CONSUME,
+            query.from,
+            query.to,
+            query.warehouse_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(lines)
+    }
+
+    /// Raise a self-billing settlement for all consumption up to the period end not yet
+    /// billed, one line per item and price
+    pub async fn settle(&self, consignor_id: i32, request: CreateSettlement) -> Result<SettlementDetail> {
+        let period_end = request.period_end.unwrap_or_else(Utc::now);
+
+        let mut tx = self.pool.begin().await?;
+
+        // The row lock keeps two settlements from billing the same consumption
+        let consignor = sqlx::query_as!(
+            Consignor,
+            "SELECT * FROM warehouse.consignors WHERE consignor_id = $1 FOR UPDATE",
+            consignor_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| violation(format!("consignor {} does not exist", consignor_id)))?;
+
+        let billable = sqlx::query!(
+            r#"SELECT item_id, COALESCE(unit_cost, 0) AS "unit_price!", -SUM(quantity) AS "quantity!"
+               FROM warehouse.stock_movements
+               WHERE consignor_id = $1 AND movement_type = $2 AND settlement_id IS NULL AND movement_date <= $3
+               GROUP BY item_id, unit_cost
+               HAVING SUM(quantity) <> 0
+               ORDER BY item_id, unit_cost"#,
+            consignor_id,
+            MOVEMENT_CONSIGNMENT_CONSUME,
+            period_end
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if billable.is_empty() {
+            return Err(violation(format!("{} has no unbilled consumption", consignor.consignor_code)));
+        }
+
+        let total_amount: Decimal = billable.iter().map(|line| line.quantity * line.unit_price).sum();
+        let settlement_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.consignment_settlements (
+                 settlement_id, settlement_number, consignor_id, period_end, currency, total_amount, created_by
+             )
+             SELECT next_id, 'CSB-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4, $5
+             FROM (SELECT nextval('warehouse.consignment_settlements_settlement_id_seq')::INT AS next_id) seq
+             RETURNING settlement_id",
+            consignor_id,
+            period_end,
+            consignor.currency,
+            total_amount,
+            acting_user() // created_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for line in &billable {
+            sqlx::query!(
+                "INSERT INTO warehouse.consignment_settlement_lines (settlement_id, item_id, quantity, unit_price, amount)
+                 VALUES ($1, $2, $3, $4, $5)",
+                settlement_id,
+                line.item_id,
+                line.quantity,
+                line.unit_price,
+                line.quantity * line.unit_price
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.stock_movements SET settlement_id = $4
+             WHERE consignor_id = $1 AND movement_type = $2 AND settlement_id IS NULL AND movement_date <= $3",
+            consignor_id,
+            MOVEMENT_CONSIGNMENT_CONSUME,
+            period_end,
+            settlement_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let detail = load_settlement(&mut tx, settlement_id).await?.expect("settlement inserted above");
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Settlements raised for a consignor, newest first
+    pub async fn settlements(&self, consignor_id: i32) -> Result<Vec<ConsignmentSettlement>> {
+        let settlements = sqlx::query_as!(
+            ConsignmentSettlement,
+            "SELECT * FROM warehouse.consignment_settlements WHERE consignor_id = $1 ORDER BY settlement_id DESC",
+            consignor_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(settlements)
+    }
+
+    pub async fn get_settlement(&self, id: i32) -> Result<Option<SettlementDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_settlement(&mut conn, id).await
+    }
+}
+
+async fn load_settlement(conn: &mut PgConnection, id: i32) -> Result<Option<SettlementDetail>> {
+    let Some(settlement) = sqlx::query_as!(
+        ConsignmentSettlement,
+        "SELECT * FROM warehouse.consignment_settlements WHERE settlement_id = $1",
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let lines = sqlx::query_as!(
+        SettlementLine,
+        "SELECT * FROM warehouse.consignment_settlement_lines WHERE settlement_id = $1 ORDER BY line_id",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(SettlementDetail { settlement, lines }))
+}
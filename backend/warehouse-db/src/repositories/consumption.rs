@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use crate::error::rejection;
+use crate::repositories::audit::acting_user;
+use crate::repositories::movements::{lock_stock, post_movement};
+use crate::utils::*;
+use anyhow::Result;
+use sqlx::{Connection, PgPool};
+use warehouse_models::chrono::Utc;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct ConsumptionRepository {
+    pool: PgPool,
+}
+
+impl ConsumptionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Issue each event's quantity from the warehouse's available stock as a
+    /// `CONSUMPTION` movement. Events already taken in are skipped, so a device can send
+    /// a batch again after a lost response. An event that cannot be issued is reported
+    /// and left out without failing the rest of the batch.
+    pub async fn ingest(&self, warehouse_id: i32, batch: ConsumptionBatch) -> Result<ConsumptionResult> {
+        let received = batch.events.len();
+        let mut tx = self.pool.begin().await?;
+
+        let codes: Vec<String> = batch.events.iter().map(|event| event.item_code.clone()).collect();
+        let items: HashMap<String, i32> = sqlx::query!(
+            "SELECT item_id, item_code FROM warehouse.items WHERE item_code = ANY($1)",
+            &codes
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|item| (item.item_code, item.item_id))
+        .collect();
+
+        let mut item_ids: Vec<i32> = items.values().copied().collect();
+        item_ids.sort_unstable();
+        lock_stock(&mut tx, warehouse_id, &item_ids).await?;
+
+        let mut result = ConsumptionResult { received, ..Default::default() };
+        for event in batch.events {
+            let Some(&item_id) = items.get(&event.item_code) else {
+                result.exceptions.push(format!("{}: unknown item {}", event.event_id, event.item_code));
+                continue;
+            };
+
+            // A savepoint per event: its record and movement stand or fall together
+            let mut savepoint = tx.begin().await?;
+            let taken = sqlx::query_scalar!(
+                "SELECT EXISTS(SELECT 1 FROM warehouse.consumption_events WHERE warehouse_id = $1 AND event_id = $2)",
+                warehouse_id,
+                event.event_id
+            )
+            .fetch_one(&mut *savepoint)
+            .await?
+            .unwrap_or(false);
+            if taken {
+                savepoint.rollback().await?;
+                result.duplicates.push(event.event_id);
+                continue;
+            }
+
+            let posted = post_movement(
+                &mut savepoint,
+                NewMovement {
+                    item_id,
+                    warehouse_id,
+                    movement_type: MOVEMENT_CONSUMPTION.to_string(),
+                    quantity: -event.quantity,
+                    catch_weight_kg: None,
+                    lot_number: event.lot_number.clone(),
+                    unit_cost: None,
+                    stock_status: STOCK_AVAILABLE,
+                    consignor_id: None,
+                    reference_type: Some("CONSUMPTION".to_string()),
+                    reference_number: Some(event.event_id.clone()),
+                    notes: event.reference.clone(),
+                    created_by: acting_user(),
+                },
+            )
+            .await;
+            let movement = match posted {
+                Ok(movement) => movement,
+                Err(error) => match rejection(&error) {
+                    Some(message) => {
+                        savepoint.rollback().await?;
+                        result.exceptions.push(format!("{}: {}", event.event_id, message));
+                        continue;
+                    }
+                    None => return Err(error),
+                },
+            };
+
+            // A concurrent batch carrying the same event waits here for the first to commit
+            let recorded = sqlx::query_scalar!(
+                "INSERT INTO warehouse.consumption_events (
+                     warehouse_id, event_id, item_id, quantity, source, reference, consumed_at, movement_id
+                 ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                 ON CONFLICT (warehouse_id, event_id) DO NOTHING
+                 RETURNING consumption_id",
+                warehouse_id,
+                event.event_id,
+                item_id,
+                event.quantity,
+                batch.source,
+                event.reference,
+                event.consumed_at.unwrap_or_else(Utc::now),
+                movement.movement_id
+            )
+            .fetch_optional(&mut *savepoint)
+            .await?;
+            if recorded.is_none() {
+                savepoint.rollback().await?;
+                result.duplicates.push(event.event_id);
+                continue;
+            }
+
+            savepoint.commit().await?;
+            result.movements.push(movement);
+        }
+
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    /// Events taken in at the warehouse, newest first
+    pub async fn events(&self, warehouse_id: i32, keyset: KeysetQuery) -> Result<KeysetPage<ConsumptionRecord>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            ConsumptionRecord,
+            "SELECT * FROM warehouse.consumption_events
+             WHERE warehouse_id = $1 AND ($2::BIGINT IS NULL OR consumption_id < $2)
+             ORDER BY consumption_id DESC
+             LIMIT $3",
+            warehouse_id,
+            after_id,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |record| record.consumption_id))
+    }
+}
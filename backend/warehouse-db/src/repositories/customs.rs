@@ -0,0 +1,115 @@
+use crate::error::violation;
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct CustomsRepository {
+    pool: PgPool,
+}
+
+impl CustomsRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Set or, with None, clear an item's customs data. Returns false when the item does
+    /// not exist.
+    pub async fn set_item_customs(&self, item_id: i32, customs: Option<CustomsData>) -> Result<bool> {
+        let updated = sqlx::query!(
+            "UPDATE warehouse.items SET hs_code = $2, country_of_origin = $3, customs_description = $4, updated_at = NOW()
+             WHERE item_id = $1 AND status = 'ACTIVE'",
+            item_id,
+            customs.as_ref().map(|c| c.hs_code.clone()),
+            customs.as_ref().map(|c| c.country_of_origin.clone()),
+            customs.as_ref().map(|c| c.customs_description.clone())
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(updated.rows_affected() > 0)
+    }
+
+    /// Customs declaration for what a shipment carried. A shipment is international when
+    /// its order ships to a country other than the warehouse's; every item on it then needs
+    /// customs data. Returns None when the shipment does not exist.
+    pub async fn declaration(&self, shipment_id: i32) -> Result<Option<CustomsDeclaration>> {
+        let Some(header) = sqlx::query!(
+            r#"SELECT s.shipment_id, s.shipment_number, s.carrier_code, s.tracking_number,
+                      o.order_number, o.ship_to_country, w.country AS origin_country,
+                      COALESCE(UPPER(TRIM(o.ship_to_country)) <> UPPER(TRIM(w.country)), FALSE) AS "international!"
+               FROM warehouse.shipments s
+               JOIN warehouse.outbound_orders o ON o.order_id = s.order_id
+               JOIN warehouse.warehouses w ON w.warehouse_id = s.warehouse_id
+               WHERE s.shipment_id = $1"#,
+            shipment_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let rows = sqlx::query!(
+            r#"SELECT i.item_id, i.item_code, i.item_name, i.hs_code, i.country_of_origin, i.customs_description,
+                      i.unit, i.standard_cost, i.weight_kg,
+                      SUM(sl.quantity) AS "quantity!",
+                      SUM(COALESCE(sl.weight_kg, sl.quantity * i.weight_kg)) AS weight_shipped_kg,
+                      BOOL_AND(sl.weight_kg IS NOT NULL OR i.weight_kg IS NOT NULL) AS "weighed!"
+               FROM warehouse.shipment_lines sl
+               JOIN warehouse.items i ON i.item_id = sl.item_id
+               WHERE sl.shipment_id = $1
+               GROUP BY i.item_id
+               ORDER BY i.hs_code, i.item_code"#,
+            shipment_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        if header.international {
+            let missing: Vec<&str> =
+                rows.iter().filter(|row| row.hs_code.is_none()).map(|row| row.item_code.as_str()).collect();
+            if !missing.is_empty() {
+                return Err(violation(format!(
+                    "{} ships to {} but {} have no customs data",
+                    header.shipment_number,
+                    header.ship_to_country.as_deref().unwrap_or_default(),
+                    missing.join(", ")
+                )));
+            }
+        }
+
+        let lines: Vec<CustomsLine> = rows
+            .into_iter()
+            .map(|row| CustomsLine {
+                item_id: row.item_id,
+                item_code: row.item_code,
+                description: row.customs_description.unwrap_or(row.item_name),
+                hs_code: row.hs_code,
+                country_of_origin: row.country_of_origin,
+                quantity: row.quantity,
+                unit: row.unit,
+                unit_value: row.standard_cost,
+                value: row.standard_cost.map(|cost| cost * row.quantity),
+                weight_kg: if row.weighed { row.weight_shipped_kg } else { None },
+            })
+            .collect();
+        let total_value = lines.iter().map(|line| line.value).sum::<Option<Decimal>>();
+        let total_weight_kg = lines.iter().map(|line| line.weight_kg).sum::<Option<Decimal>>();
+
+        Ok(Some(CustomsDeclaration {
+            shipment_id: header.shipment_id,
+            shipment_number: header.shipment_number,
+            order_number: header.order_number,
+            carrier_code: header.carrier_code,
+            tracking_number: header.tracking_number,
+            origin_country: header.origin_country,
+            destination_country: header.ship_to_country,
+            international: header.international,
+            lines,
+            total_value,
+            total_weight_kg,
+        }))
+    }
+}
@@ -0,0 +1,483 @@
+use crate::error::violation;
+use crate::repositories::orders::insert_order;
+use crate::repositories::projects::load_project;
+use anyhow::Result;
+use chrono::{Datelike, Days, NaiveDate, Utc};
+use sqlx::{PgConnection, PgPool};
+use std::collections::{HashMap, HashSet};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct DeliveryRepository {
+    pool: PgPool,
+}
+
+impl DeliveryRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn calendar(&self, warehouse_id: i32) -> Result<DeliveryCalendar> {
+        let mut conn = self.pool.acquire().await?;
+        load_calendar(&mut conn, warehouse_id).await
+    }
+
+    /// Replace the delivery weekdays and the closures from today on; past closures stay
+    /// as they were
+    pub async fn set_calendar(&self, warehouse_id: i32, calendar: SetDeliveryCalendar) -> Result<DeliveryCalendar> {
+        let mut weekdays = calendar.delivery_weekdays;
+        weekdays.sort_unstable();
+        weekdays.dedup();
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "INSERT INTO warehouse.delivery_calendars (warehouse_id, delivery_weekdays) VALUES ($1, $2)
+             ON CONFLICT (warehouse_id) DO UPDATE SET
+                 delivery_weekdays = EXCLUDED.delivery_weekdays,
+                 updated_at = NOW()",
+            warehouse_id,
+            &weekdays
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "DELETE FROM warehouse.delivery_closures WHERE warehouse_id = $1 AND closed_on >= CURRENT_DATE",
+            warehouse_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "INSERT INTO warehouse.delivery_closures (warehouse_id, closed_on, reason)
+             SELECT $1, * FROM UNNEST($2::DATE[], $3::VARCHAR[])
+             WHERE closed_on >= CURRENT_DATE
+             ON CONFLICT (warehouse_id, closed_on) DO UPDATE SET reason = EXCLUDED.reason",
+            warehouse_id,
+            &calendar.closures.iter().map(|c| c.closed_on).collect::<Vec<_>>(),
+            &calendar.closures.iter().map(|c| c.reason.clone()).collect::<Vec<_>>() as &[Option<String>]
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let calendar = load_calendar(&mut tx, warehouse_id).await?;
+        tx.commit().await?;
+        Ok(calendar)
+    }
+
+    /// A warehouse's vehicles, active ones first
+    pub async fn vehicles(&self, warehouse_id: i32) -> Result<Vec<DeliveryVehicle>> {
+        let vehicles = sqlx::query_as!(
+            DeliveryVehicle,
+            "SELECT * FROM warehouse.delivery_vehicles WHERE warehouse_id = $1
+             ORDER BY is_active DESC, vehicle_code",
+            warehouse_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(vehicles)
+    }
+
+    pub async fn vehicle_code_exists(&self, warehouse_id: i32, code: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.delivery_vehicles WHERE warehouse_id = $1 AND vehicle_code = $2)",
+            warehouse_id,
+            code
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(exists.unwrap_or(false))
+    }
+
+    pub async fn add_vehicle(&self, warehouse_id: i32, vehicle: CreateDeliveryVehicle) -> Result<DeliveryVehicle> {
+        let vehicle = sqlx::query_as!(
+            DeliveryVehicle,
+            "INSERT INTO warehouse.delivery_vehicles (warehouse_id, vehicle_code, description, capacity_kg)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *",
+            warehouse_id,
+            vehicle.vehicle_code,
+            vehicle.description,
+            vehicle.capacity_kg
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(vehicle)
+    }
+
+    pub async fn update_vehicle(&self, id: i32, vehicle: UpdateDeliveryVehicle) -> Result<Option<DeliveryVehicle>> {
+        let vehicle = sqlx::query_as!(
+            DeliveryVehicle,
+            "UPDATE warehouse.delivery_vehicles SET
+                 description = COALESCE($2, description),
+                 capacity_kg = COALESCE($3, capacity_kg),
+                 is_active = COALESCE($4, is_active)
+             WHERE vehicle_id = $1
+             RETURNING *",
+            id,
+            vehicle.description,
+            vehicle.capacity_kg,
+            vehicle.is_active
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(vehicle)
+    }
+
+    /// Record a delivery request of an active project, weighed from its items' unit
+    /// weights. Returns None when there is no such active project.
+    pub async fn request(&self, project_id: i32, request: RequestSiteDelivery) -> Result<Option<SiteDeliveryDetail>> {
+        let mut tx = self.pool.begin().await?;
+        if load_project(&mut tx, project_id, false).await?.filter(|project| project.is_active).is_none() {
+            return Ok(None);
+        }
+
+        let item_ids: Vec<i32> = request.lines.iter().map(|line| line.item_id).collect();
+        let weights: HashMap<i32, Option<Decimal>> = sqlx::query!(
+            "SELECT item_id, weight_kg FROM warehouse.items WHERE item_id = ANY($1) AND status = 'ACTIVE'",
+            &item_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| (row.item_id, row.weight_kg))
+        .collect();
+        let mut weight_kg = Decimal::ZERO;
+        for line in &request.lines {
+            let weight =
+                weights.get(&line.item_id).ok_or_else(|| violation(format!("unknown item {}", line.item_id)))?;
+            weight_kg += weight.unwrap_or_default() * line.quantity;
+        }
+
+        let delivery_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.site_deliveries (
+                 delivery_id, delivery_number, project_id, warehouse_id, needed_by, site_name, site_address,
+                 site_city, site_contact, weight_kg
+             )
+             SELECT next_id, 'DLV-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4, $5, $6, $7, $8
+             FROM (SELECT nextval('warehouse.site_deliveries_delivery_id_seq')::INT AS next_id) seq
+             RETURNING delivery_id",
+            project_id,
+            request.warehouse_id,
+            request.needed_by,
+            request.site_name,
+            request.site_address,
+            request.site_city,
+            request.site_contact,
+            weight_kg.round_dp(3)
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for (index, line) in request.lines.into_iter().enumerate() {
+            sqlx::query!(
+                "INSERT INTO warehouse.site_delivery_lines (delivery_id, line_number, item_id, quantity)
+                 VALUES ($1, $2, $3, $4)",
+                delivery_id,
+                index as i32 + 1,
+                line.item_id,
+                line.quantity
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let detail = load_delivery(&mut tx, delivery_id, false).await?.expect("delivery inserted above");
+        tx.commit().await?;
+        Ok(Some(detail))
+    }
+
+    /// A project's delivery requests, soonest needed first. Returns None when there is no
+    /// such project.
+    pub async fn for_project(&self, project_id: i32) -> Result<Option<Vec<SiteDelivery>>> {
+        let mut conn = self.pool.acquire().await?;
+        if load_project(&mut conn, project_id, false).await?.is_none() {
+            return Ok(None);
+        }
+
+        let deliveries = sqlx::query_as!(
+            SiteDelivery,
+            "SELECT * FROM warehouse.site_deliveries WHERE project_id = $1 ORDER BY needed_by, delivery_id",
+            project_id
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok(Some(deliveries))
+    }
+
+    pub async fn get(&self, id: i32) -> Result<Option<SiteDeliveryDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_delivery(&mut conn, id, false).await
+    }
+
+    /// Put a requested delivery on an open delivery day no later than it is needed, on a
+    /// vehicle with room left that day, and create its outbound order
+    pub async fn schedule(&self, id: i32, schedule: ScheduleSiteDelivery) -> Result<Option<SiteDeliveryDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_delivery(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let delivery = &detail.delivery;
+        if delivery.status != SITE_DELIVERY_REQUESTED {
+            return Err(violation(format!(
+                "cannot schedule {}: it is {}",
+                delivery.delivery_number,
+                delivery.status.to_lowercase()
+            )));
+        }
+
+        let today = Utc::now().date_naive();
+        let (first, last) = match schedule.delivery_date {
+            Some(date) if date < today || date > delivery.needed_by => {
+                return Err(violation(format!(
+                    "{} must be delivered between {} and {}, not on {}",
+                    delivery.delivery_number, today, delivery.needed_by, date
+                )));
+            }
+            Some(date) => (date, date),
+            None => (today, delivery.needed_by),
+        };
+
+        // Locking the warehouse's vehicles queues concurrent scheduling, so each sees the
+        // loads the one before it left
+        let vehicles = sqlx::query_as!(
+            DeliveryVehicle,
+            "SELECT * FROM warehouse.delivery_vehicles
+             WHERE warehouse_id = $1 AND is_active
+             ORDER BY capacity_kg, vehicle_code
+             FOR UPDATE",
+            delivery.warehouse_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let vehicles: Vec<_> = match schedule.vehicle_id {
+            Some(vehicle_id) => {
+                let vehicle = vehicles.into_iter().find(|vehicle| vehicle.vehicle_id == vehicle_id);
+                let vehicle = vehicle.ok_or_else(|| {
+                    violation(format!(
+                        "vehicle {} is not an active vehicle of warehouse {}",
+                        vehicle_id, delivery.warehouse_id
+                    ))
+                })?;
+                vec![vehicle]
+            }
+            None => vehicles,
+        };
+
+        let loads: HashMap<(i32, NaiveDate), Decimal> = sqlx::query!(
+            r#"SELECT vehicle_id AS "vehicle_id!", scheduled_date AS "scheduled_date!", SUM(weight_kg) AS "load_kg!"
+               FROM warehouse.site_deliveries
+               WHERE status = 'SCHEDULED' AND vehicle_id = ANY($1) AND scheduled_date BETWEEN $2 AND $3
+               GROUP BY vehicle_id, scheduled_date"#,
+            &vehicles.iter().map(|vehicle| vehicle.vehicle_id).collect::<Vec<_>>(),
+            first,
+            last
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| ((row.vehicle_id, row.scheduled_date), row.load_kg))
+        .collect();
+
+        let days = delivery_days(&mut tx, delivery.warehouse_id, first, last).await?;
+        let slot = days.iter().rev().find_map(|day| {
+            vehicles
+                .iter()
+                .find(|vehicle| {
+                    let load = loads.get(&(vehicle.vehicle_id, *day)).copied().unwrap_or_default();
+                    load + delivery.weight_kg <= vehicle.capacity_kg
+                })
+                .map(|vehicle| (*day, vehicle.vehicle_id))
+        });
+        let Some((day, vehicle_id)) = slot else {
+            return Err(violation(format!(
+                "no delivery day from {} to {} has a vehicle with {} kg free for {}",
+                first,
+                last,
+                delivery.weight_kg.normalize(),
+                delivery.delivery_number
+            )));
+        };
+
+        let project = load_project(&mut tx, delivery.project_id, false).await?.expect("delivery's project");
+        let order = CreateOrder {
+            warehouse_id: delivery.warehouse_id,
+            customer_name: project.project_name,
+            customer_reference: Some(delivery.delivery_number.clone()),
+            ship_to_name: Some(delivery.site_name.clone()),
+            ship_to_address: Some(delivery.site_address.clone()),
+            ship_to_city: delivery.site_city.clone(),
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: Some(day),
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: detail
+                .lines
+                .iter()
+                .map(|line| CreateOrderLine { item_id: line.item_id, quantity_ordered: line.quantity })
+                .collect(),
+        };
+        let order_id = insert_order(&mut tx, order, SOURCE_PROJECT, None).await?;
+
+        sqlx::query!(
+            "UPDATE warehouse.site_deliveries
+             SET status = $2, scheduled_date = $3, vehicle_id = $4, order_id = $5, updated_at = NOW()
+             WHERE delivery_id = $1",
+            id,
+            SITE_DELIVERY_SCHEDULED,
+            day,
+            vehicle_id,
+            order_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let detail = load_delivery(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Cancel a delivery, and its order when scheduled, which frees its room on the vehicle.
+    /// A scheduled delivery whose order is being picked or has shipped cannot be cancelled.
+    pub async fn cancel(&self, id: i32) -> Result<Option<SiteDeliveryDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_delivery(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let delivery = &detail.delivery;
+        if delivery.status == SITE_DELIVERY_CANCELLED {
+            return Err(violation(format!("cannot cancel {}: it is cancelled", delivery.delivery_number)));
+        }
+
+        if let Some(order_id) = delivery.order_id {
+            let order = sqlx::query!(
+                "SELECT order_number, status, wave_id FROM warehouse.outbound_orders WHERE order_id = $1 FOR UPDATE",
+                order_id
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            if order.status != ORDER_OPEN || order.wave_id.is_some() {
+                let state = match order.wave_id {
+                    Some(wave_id) if order.status == ORDER_OPEN => format!("in wave {}", wave_id),
+                    _ => order.status.to_lowercase(),
+                };
+                return Err(violation(format!(
+                    "cannot cancel {}: its order {} is {}",
+                    delivery.delivery_number, order.order_number, state
+                )));
+            }
+            sqlx::query!(
+                "UPDATE warehouse.outbound_orders SET status = $2, updated_at = NOW() WHERE order_id = $1",
+                order_id,
+                ORDER_CANCELLED
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.site_deliveries SET status = $2, updated_at = NOW() WHERE delivery_id = $1",
+            id,
+            SITE_DELIVERY_CANCELLED
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let detail = load_delivery(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Load on each vehicle of a warehouse per delivery day between `from` and `to`, both
+    /// inclusive, for the days something is scheduled
+    pub async fn vehicle_loads(&self, warehouse_id: i32, from: NaiveDate, to: NaiveDate) -> Result<Vec<VehicleLoad>> {
+        let loads = sqlx::query_as!(
+            VehicleLoad,
+            r#"SELECT d.scheduled_date AS "scheduled_date!", v.vehicle_id, v.vehicle_code, v.capacity_kg,
+                      SUM(d.weight_kg) AS "load_kg!", COUNT(*) AS "deliveries!"
+               FROM warehouse.site_deliveries d
+               JOIN warehouse.delivery_vehicles v ON v.vehicle_id = d.vehicle_id
+               WHERE d.warehouse_id = $1 AND d.status = 'SCHEDULED' AND d.scheduled_date BETWEEN $2 AND $3
+               GROUP BY d.scheduled_date, v.vehicle_id
+               ORDER BY d.scheduled_date, v.vehicle_code"#,
+            warehouse_id,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(loads)
+    }
+}
+
+async fn load_calendar(conn: &mut PgConnection, warehouse_id: i32) -> Result<DeliveryCalendar> {
+    let weekdays = sqlx::query_scalar!(
+        "SELECT delivery_weekdays FROM warehouse.delivery_calendars WHERE warehouse_id = $1",
+        warehouse_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    let closures = sqlx::query_as!(
+        DeliveryClosure,
+        "SELECT closed_on, reason FROM warehouse.delivery_closures
+         WHERE warehouse_id = $1 AND closed_on >= CURRENT_DATE
+         ORDER BY closed_on",
+        warehouse_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(DeliveryCalendar {
+        warehouse_id,
+        delivery_weekdays: weekdays.unwrap_or_else(|| DEFAULT_DELIVERY_WEEKDAYS.to_vec()),
+        closures,
+    })
+}
+
+/// The warehouse's open delivery days from `first` to `last`, in date order
+async fn delivery_days(
+    conn: &mut PgConnection,
+    warehouse_id: i32,
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Result<Vec<NaiveDate>> {
+    let calendar = load_calendar(conn, warehouse_id).await?;
+    let closed: HashSet<NaiveDate> = calendar.closures.iter().map(|closure| closure.closed_on).collect();
+
+    let mut days = Vec::new();
+    let mut day = first;
+    while day <= last {
+        let weekday = day.weekday().number_from_monday() as i16;
+        if calendar.delivery_weekdays.contains(&weekday) && !closed.contains(&day) {
+            days.push(day);
+        }
+        day = day + Days::new(1);
+    }
+    Ok(days)
+}
+
+async fn load_delivery(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<SiteDeliveryDetail>> {
+    let delivery = if lock {
+        sqlx::query_as!(SiteDelivery, "SELECT * FROM warehouse.site_deliveries WHERE delivery_id = $1 FOR UPDATE", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    } else {
+        sqlx::query_as!(SiteDelivery, "SELECT * FROM warehouse.site_deliveries WHERE delivery_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    };
+    let Some(delivery) = delivery else {
+        return Ok(None);
+    };
+
+    let lines = sqlx::query_as!(
+        SiteDeliveryLine,
+        "SELECT * FROM warehouse.site_delivery_lines WHERE delivery_id = $1 ORDER BY line_number",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(Some(SiteDeliveryDetail { delivery, lines }))
+}
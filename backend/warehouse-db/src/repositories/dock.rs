@@ -0,0 +1,142 @@
+use crate::error::violation;
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::chrono::NaiveDate;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct DockRepository {
+    pool: PgPool,
+}
+
+impl DockRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// The warehouse's inbound loads for `date`: those expected that day or late, those
+    /// that arrived or completed that day, and transfers in transit to it. Purchase orders
+    /// with an ASN show as the ASN.
+    pub async fn board(&self, warehouse_id: i32, date: NaiveDate) -> Result<Vec<DockBoardEntry>> {
+        let entries = sqlx::query_as!(
+            DockBoardEntry,
+            r#"WITH arrivals AS (
+                   SELECT * FROM warehouse.dock_arrivals WHERE warehouse_id = $1
+               ),
+               entries AS (
+                   SELECT 'ASN' AS source, r.receipt_id AS reference_id, r.asn_number AS reference_number,
+                          r.supplier_name AS shipper, r.carrier_code, r.expected_date,
+                          CASE WHEN r.status = 'RECEIVED' THEN 'COMPLETED'
+                               WHEN r.status = 'PARTIALLY_RECEIVED' THEN 'RECEIVING'
+                               WHEN a.arrival_id IS NOT NULL THEN 'ARRIVED'
+                               ELSE 'EXPECTED' END AS status,
+                          a.dock_door, a.arrived_at,
+                          CASE WHEN r.status = 'RECEIVED' THEN r.received_at END AS completed_at
+                   FROM warehouse.inbound_receipts r
+                   LEFT JOIN arrivals a ON a.source = 'ASN' AND a.reference_id = r.receipt_id
+                   WHERE r.warehouse_id = $1
+                     AND (r.expected_date = $2 OR (r.status <> 'RECEIVED' AND r.expected_date < $2)
+                          OR a.arrived_at::DATE = $2 OR r.received_at::DATE = $2)
+                   UNION ALL
+                   SELECT 'PURCHASE_ORDER', po.po_id, po.po_number, po.supplier_name, NULL, po.expected_date,
+                          CASE WHEN po.status = 'RECEIVED' THEN 'COMPLETED'
+                               WHEN a.arrival_id IS NOT NULL THEN 'ARRIVED'
+                               ELSE 'EXPECTED' END,
+                          a.dock_door, a.arrived_at, NULL
+                   FROM warehouse.purchase_orders po
+                   LEFT JOIN arrivals a ON a.source = 'PURCHASE_ORDER' AND a.reference_id = po.po_id
+                   WHERE po.warehouse_id = $1 AND po.status <> 'CANCELLED'
+                     AND NOT EXISTS (
+                         SELECT 1 FROM warehouse.inbound_receipts r
+                         WHERE r.warehouse_id = po.warehouse_id AND r.purchase_order_number = po.po_number
+                     )
+                     AND (po.expected_date = $2 OR (po.status = 'OPEN' AND po.expected_date < $2)
+                          OR a.arrived_at::DATE = $2)
+                   UNION ALL
+                   SELECT 'TRANSFER', t.transfer_id, t.transfer_number, w.warehouse_code, t.carrier_code, NULL,
+                          CASE WHEN t.status = 'RECEIVED' THEN 'COMPLETED'
+                               WHEN a.arrival_id IS NOT NULL THEN 'ARRIVED'
+                               ELSE 'EXPECTED' END,
+                          a.dock_door, a.arrived_at, t.received_at
+                   FROM warehouse.stock_transfers t
+                   JOIN warehouse.warehouses w ON w.warehouse_id = t.source_warehouse_id
+                   LEFT JOIN arrivals a ON a.source = 'TRANSFER' AND a.reference_id = t.transfer_id
+                   WHERE t.destination_warehouse_id = $1
+                     AND (t.status = 'IN_TRANSIT' OR t.received_at::DATE = $2)
+               )
+               SELECT source AS "source!", reference_id AS "reference_id!", reference_number AS "reference_number!",
+                      shipper, carrier_code, expected_date, status AS "status!",
+                      (status <> 'COMPLETED' AND expected_date IS NOT NULL AND expected_date < $2) AS "is_late!",
+                      dock_door, arrived_at, completed_at
+               FROM entries
+               ORDER BY expected_date NULLS LAST, arrived_at NULLS LAST, source, reference_number"#,
+            warehouse_id,
+            date
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+
+    /// Check a load in at the dock. It must be still open in the warehouse: an ASN not
+    /// fully received, an open purchase order, or a transfer in transit to it. Returns None
+    /// when there is no such load for the warehouse.
+    pub async fn check_in(&self, warehouse_id: i32, arrival: CheckInArrival) -> Result<Option<DockArrival>> {
+        let load = match arrival.source.as_str() {
+            DOCK_SOURCE_ASN => sqlx::query!(
+                "SELECT asn_number, status FROM warehouse.inbound_receipts WHERE receipt_id = $1 AND warehouse_id = $2",
+                arrival.reference_id,
+                warehouse_id
+            )
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|row| (row.asn_number, row.status.clone(), row.status != RECEIPT_RECEIVED)),
+            DOCK_SOURCE_PURCHASE_ORDER => sqlx::query!(
+                "SELECT po_number, status FROM warehouse.purchase_orders WHERE po_id = $1 AND warehouse_id = $2",
+                arrival.reference_id,
+                warehouse_id
+            )
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|row| (row.po_number, row.status.clone(), row.status == PO_OPEN)),
+            _ => sqlx::query!(
+                "SELECT transfer_number, status FROM warehouse.stock_transfers
+                 WHERE transfer_id = $1 AND destination_warehouse_id = $2",
+                arrival.reference_id,
+                warehouse_id
+            )
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|row| (row.transfer_number, row.status.clone(), row.status == TRANSFER_IN_TRANSIT)),
+        };
+        let Some((number, status, open)) = load else {
+            return Ok(None);
+        };
+        if !open {
+            return Err(violation(format!(
+                "cannot check in {}: it is {}",
+                number,
+                status.to_lowercase().replace('_', " ")
+            )));
+        }
+
+        let checked_in = sqlx::query_as!(
+            DockArrival,
+            "INSERT INTO warehouse.dock_arrivals (warehouse_id, source, reference_id, dock_door, notes)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (source, reference_id) DO NOTHING
+             RETURNING *",
+            warehouse_id,
+            arrival.source,
+            arrival.reference_id,
+            arrival.dock_door,
+            arrival.notes
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        match checked_in {
+            Some(checked_in) => Ok(Some(checked_in)),
+            None => Err(violation(format!("{} has already arrived", number))),
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct DocumentRepository {
+    pool: PgPool,
+}
+
+impl DocumentRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// The tenant-wide branding first, then each warehouse's
+    pub async fn brandings(&self) -> Result<Vec<DocumentBranding>> {
+        let brandings = sqlx::query_as!(
+            DocumentBranding,
+            "SELECT * FROM warehouse.document_brandings ORDER BY warehouse_id NULLS FIRST"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(brandings)
+    }
+
+    /// Branding the warehouse's documents are printed under: its own, else the
+    /// tenant-wide one
+    pub async fn branding_for(&self, warehouse_id: i32) -> Result<Option<DocumentBranding>> {
+        let branding = sqlx::query_as!(
+            DocumentBranding,
+            "SELECT * FROM warehouse.document_brandings
+             WHERE warehouse_id = $1 OR warehouse_id IS NULL
+             ORDER BY warehouse_id NULLS LAST
+             LIMIT 1",
+            warehouse_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(branding)
+    }
+
+    /// Set the branding of a warehouse, or the tenant-wide one for None
+    pub async fn set_branding(
+        &self,
+        warehouse_id: Option<i32>,
+        branding: SetDocumentBranding,
+    ) -> Result<DocumentBranding> {
+        let saved = sqlx::query_as!(
+            DocumentBranding,
+            "INSERT INTO warehouse.document_brandings (
+                 warehouse_id, company_name, address, tax_id, phone, email, accent_color, footer_text, currency
+             ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             ON CONFLICT ((COALESCE(warehouse_id, 0))) DO UPDATE SET
+                 company_name = EXCLUDED.company_name,
+                 address = EXCLUDED.address,
+                 tax_id = EXCLUDED.tax_id,
+                 phone = EXCLUDED.phone,
+                 email = EXCLUDED.email,
+                 accent_color = EXCLUDED.accent_color,
+                 footer_text = EXCLUDED.footer_text,
+                 currency = EXCLUDED.currency,
+                 updated_at = NOW()
+             RETURNING *",
+            warehouse_id,
+            branding.company_name,
+            branding.address,
+            branding.tax_id,
+            branding.phone.map(|phone| normalize_phone(&phone).unwrap_or(phone)),
+            branding.email,
+            branding.accent_color.unwrap_or_else(|| DEFAULT_ACCENT_COLOR.to_string()).to_uppercase(),
+            branding.footer_text,
+            branding.currency.unwrap_or_else(|| "IDR".to_string()).to_uppercase()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(saved)
+    }
+
+    /// What the shipment's documents print, its lines in order line sequence. Returns
+    /// None when the shipment does not exist.
+    pub async fn source(&self, shipment_id: i32) -> Result<Option<ShipmentDocumentSource>> {
+        let Some(shipment) =
+            sqlx::query_as!(Shipment, "SELECT * FROM warehouse.shipments WHERE shipment_id = $1", shipment_id)
+                .fetch_optional(&self.pool)
+                .await?
+        else {
+            return Ok(None);
+        };
+
+        let order = sqlx::query_as!(
+            OutboundOrder,
+            "SELECT * FROM warehouse.outbound_orders WHERE order_id = $1",
+            shipment.order_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let warehouse = sqlx::query!(
+            "SELECT warehouse_name, city, state, country FROM warehouse.warehouses WHERE warehouse_id = $1",
+            shipment.warehouse_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        let ship_from = [warehouse.city, warehouse.state, warehouse.country]
+            .into_iter()
+            .flatten()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+
+        let lines = sqlx::query_as!(
+            DocumentLine,
+            r#"SELECT i.item_code, i.item_name AS description, sl.lot_number, SUM(sl.quantity) AS "quantity!",
+                      i.unit, i.standard_cost AS unit_value
+               FROM warehouse.shipment_lines sl
+               JOIN warehouse.outbound_order_lines ol ON ol.line_id = sl.order_line_id
+               JOIN warehouse.items i ON i.item_id = sl.item_id
+               WHERE sl.shipment_id = $1
+               GROUP BY ol.line_number, i.item_id, sl.lot_number
+               ORDER BY ol.line_number, sl.lot_number NULLS FIRST"#,
+            shipment_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(Some(ShipmentDocumentSource {
+            shipment,
+            order,
+            warehouse_name: warehouse.warehouse_name,
+            ship_from,
+            lines,
+        }))
+    }
+
+    /// Keep a rendered document with its shipment, replacing an earlier one of the type
+    pub async fn save(
+        &self,
+        shipment_id: i32,
+        document_type: &str,
+        document_number: &str,
+        content: &[u8],
+    ) -> Result<ShipmentDocument> {
+        let document = sqlx::query_as!(
+            ShipmentDocument,
+            "INSERT INTO warehouse.shipment_documents (shipment_id, document_type, document_number, content, size_bytes)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (shipment_id, document_type) DO UPDATE SET
+                 document_number = EXCLUDED.document_number,
+                 content = EXCLUDED.content,
+                 size_bytes = EXCLUDED.size_bytes,
+                 created_at = NOW()
+             RETURNING document_id, shipment_id, document_type, document_number, size_bytes, created_at",
+            shipment_id,
+            document_type,
+            document_number,
+            content,
+            content.len() as i32
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(document)
+    }
+
+    pub async fn documents(&self, shipment_id: i32) -> Result<Vec<ShipmentDocument>> {
+        let documents = sqlx::query_as!(
+            ShipmentDocument,
+            "SELECT document_id, shipment_id, document_type, document_number, size_bytes, created_at
+             FROM warehouse.shipment_documents
+             WHERE shipment_id = $1
+             ORDER BY document_type",
+            shipment_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(documents)
+    }
+
+    /// A document and its PDF
+    pub async fn content(&self, document_id: i64) -> Result<Option<(ShipmentDocument, Vec<u8>)>> {
+        let row = sqlx::query!(
+            "SELECT document_id, shipment_id, document_type, document_number, size_bytes, created_at, content
+             FROM warehouse.shipment_documents
+             WHERE document_id = $1",
+            document_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let document = ShipmentDocument {
+                document_id: row.document_id,
+                shipment_id: row.shipment_id,
+                document_type: row.document_type,
+                document_number: row.document_number,
+                size_bytes: row.size_bytes,
+                created_at: row.created_at,
+            };
+            (document, row.content)
+        }))
+    }
+}
@@ -0,0 +1,364 @@
+use crate::error::violation;
+use crate::utils::*;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::chrono::{DateTime, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct EcommerceRepository {
+    pool: PgPool,
+}
+
+impl EcommerceRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Channels by code
+    pub async fn list(&self) -> Result<Vec<EcommerceChannel>> {
+        let channels =
+            sqlx::query_as!(EcommerceChannel, "SELECT * FROM warehouse.ecommerce_channels ORDER BY channel_code")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(channels)
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<EcommerceChannelDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_channel(&mut conn, id).await
+    }
+
+    pub async fn code_exists(&self, code: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.ecommerce_channels WHERE channel_code = $1)",
+            code
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    /// Set up a channel; the first sync is due right away
+    pub async fn create(&self, channel: CreateEcommerceChannel) -> Result<EcommerceChannelDetail> {
+        let mut tx = self.pool.begin().await?;
+
+        let channel_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.ecommerce_channels (
+                 channel_code, platform, store_url, api_key, api_secret, location_id, warehouse_id,
+                 buffer_quantity, sync_interval_minutes
+             ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             RETURNING channel_id",
+            channel.channel_code,
+            channel.platform,
+            channel.store_url,
+            channel.api_key,
+            channel.api_secret,
+            channel.location_id,
+            channel.warehouse_id,
+            channel.buffer_quantity.unwrap_or(Decimal::ZERO),
+            channel.sync_interval_minutes
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        set_listings(&mut tx, channel_id, &channel.listings).await?;
+
+        let detail = load_channel(&mut tx, channel_id).await?.expect("channel inserted above");
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Returns None when the channel does not exist
+    pub async fn update(&self, id: i32, changes: UpdateEcommerceChannel) -> Result<Option<EcommerceChannelDetail>> {
+        let mut tx = self.pool.begin().await?;
+
+        // A new interval takes effect from the last sync rather than waiting out the old one
+        let updated = sqlx::query!(
+            "UPDATE warehouse.ecommerce_channels SET
+                 store_url = COALESCE($2, store_url),
+                 api_key = COALESCE($3, api_key),
+                 api_secret = COALESCE($4, api_secret),
+                 location_id = COALESCE($5, location_id),
+                 buffer_quantity = COALESCE($6, buffer_quantity),
+                 sync_interval_minutes = COALESCE($7, sync_interval_minutes),
+                 is_active = COALESCE($8, is_active),
+                 next_run_at = CASE WHEN $7::INT IS NULL THEN next_run_at
+                                    ELSE COALESCE(last_synced_at + make_interval(mins => $7), NOW()) END,
+                 updated_at = NOW()
+             WHERE channel_id = $1",
+            id,
+            changes.store_url,
+            changes.api_key,
+            changes.api_secret,
+            changes.location_id,
+            changes.buffer_quantity,
+            changes.sync_interval_minutes,
+            changes.is_active
+        )
+        .execute(&mut *tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        if let Some(listings) = &changes.listings {
+            sqlx::query!("DELETE FROM warehouse.ecommerce_listings WHERE channel_id = $1", id)
+                .execute(&mut *tx)
+                .await?;
+            set_listings(&mut tx, id, listings).await?;
+        }
+
+        let detail = load_channel(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Take the active channels due at `now` and push their next run out by their
+    /// interval, so a channel is synced by one scheduler even with several servers running
+    pub async fn claim_due(&self, now: DateTime<Utc>) -> Result<Vec<EcommerceChannel>> {
+        let channels = sqlx::query_as!(
+            EcommerceChannel,
+            "UPDATE warehouse.ecommerce_channels SET next_run_at = $1 + make_interval(mins => sync_interval_minutes)
+             WHERE channel_id IN (
+                 SELECT channel_id FROM warehouse.ecommerce_channels
+                 WHERE is_active AND next_run_at <= $1
+                 ORDER BY next_run_at
+                 FOR UPDATE SKIP LOCKED
+             )
+             RETURNING *",
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(channels)
+    }
+
+    /// What the storefront may sell of each listed item. Open orders of the channel's
+    /// warehouse count against the stock until they ship, whichever channel they came from.
+    pub async fn stock_levels(&self, channel_id: i32) -> Result<Vec<EcommerceStockLevel>> {
+        let levels = sqlx::query_as!(
+            EcommerceStockLevel,
+            r#"SELECT l.item_id, i.item_code, l.external_id,
+                      COALESCE(s.available, 0) AS "available!",
+                      COALESCE(o.committed, 0) AS "committed!",
+                      COALESCE(l.buffer_quantity, c.buffer_quantity) AS "buffer_quantity!",
+                      GREATEST(FLOOR(COALESCE(s.available, 0) - COALESCE(o.committed, 0)
+                                     - COALESCE(l.buffer_quantity, c.buffer_quantity)), 0) AS "quantity!",
+                      l.pushed_quantity
+               FROM warehouse.ecommerce_listings l
+               JOIN warehouse.ecommerce_channels c ON c.channel_id = l.channel_id
+               JOIN warehouse.items i ON i.item_id = l.item_id
+               LEFT JOIN LATERAL (
+                   SELECT SUM(quantity_available) AS available FROM warehouse.stock_inventory
+                   WHERE item_id = l.item_id AND warehouse_id = c.warehouse_id
+               ) s ON TRUE
+               LEFT JOIN LATERAL (
+                   SELECT SUM(ol.quantity_ordered - ol.quantity_shipped) AS committed
+                   FROM warehouse.outbound_order_lines ol
+                   JOIN warehouse.outbound_orders oo ON oo.order_id = ol.order_id
+                   WHERE ol.item_id = l.item_id AND oo.warehouse_id = c.warehouse_id
+                     AND oo.status IN ($2, $3) AND oo.order_type = $4
+               ) o ON TRUE
+               WHERE l.channel_id = $1
+               ORDER BY i.item_code"#,
+            channel_id,
+            ORDER_OPEN,
+            ORDER_PARTIALLY_SHIPPED,
+            ORDER_TYPE_STANDARD
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(levels)
+    }
+
+    /// Remember the quantities the storefront accepted
+    pub async fn mark_pushed(&self, channel_id: i32, levels: &[EcommerceStockLevel]) -> Result<()> {
+        let item_ids: Vec<i32> = levels.iter().map(|level| level.item_id).collect();
+        let quantities: Vec<Decimal> = levels.iter().map(|level| level.quantity).collect();
+        sqlx::query!(
+            "UPDATE warehouse.ecommerce_listings l SET pushed_quantity = p.quantity, pushed_at = NOW()
+             FROM UNNEST($2::INT[], $3::DECIMAL[]) AS p(item_id, quantity)
+             WHERE l.channel_id = $1 AND l.item_id = p.item_id",
+            channel_id,
+            &item_ids,
+            &quantities
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Our order for a storefront order imported earlier
+    pub async fn imported_order(&self, channel_id: i32, external_order_id: &str) -> Result<Option<i32>> {
+        let order_id = sqlx::query_scalar!(
+            "SELECT order_id FROM warehouse.ecommerce_orders WHERE channel_id = $1 AND external_order_id = $2",
+            channel_id,
+            external_order_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(order_id)
+    }
+
+    pub async fn link_order(&self, channel_id: i32, external_order_id: &str, order_id: i32) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO warehouse.ecommerce_orders (channel_id, external_order_id, order_id) VALUES ($1, $2, $3)
+             ON CONFLICT (channel_id, external_order_id) DO NOTHING",
+            channel_id,
+            external_order_id,
+            order_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Orders changed up to `synced_at` have been pulled
+    pub async fn advance_orders(&self, channel_id: i32, synced_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query!(
+            "UPDATE warehouse.ecommerce_channels SET orders_synced_at = GREATEST(orders_synced_at, $2)
+             WHERE channel_id = $1",
+            channel_id,
+            synced_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record one direction of a sync. A failure brings the channel's next run forward so
+    /// it is retried soon.
+    pub async fn record_run(
+        &self,
+        channel_id: i32,
+        direction: &str,
+        started_at: DateTime<Utc>,
+        records: i32,
+        error: Option<String>,
+    ) -> Result<EcommerceSyncRun> {
+        let mut tx = self.pool.begin().await?;
+        let status = if error.is_none() { ECOMMERCE_RUN_SUCCEEDED } else { ECOMMERCE_RUN_FAILED };
+
+        let run = sqlx::query_as!(
+            EcommerceSyncRun,
+            "INSERT INTO warehouse.ecommerce_sync_runs (channel_id, direction, status, records, error, started_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING *",
+            channel_id,
+            direction,
+            status,
+            records,
+            error,
+            started_at
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if status == ECOMMERCE_RUN_SUCCEEDED {
+            sqlx::query!(
+                "UPDATE warehouse.ecommerce_channels SET last_synced_at = GREATEST(last_synced_at, $2)
+                 WHERE channel_id = $1",
+                channel_id,
+                started_at
+            )
+            .execute(&mut *tx)
+            .await?;
+        } else {
+            sqlx::query!(
+                "UPDATE warehouse.ecommerce_channels SET
+                     next_run_at = LEAST(next_run_at, NOW() + make_interval(mins => LEAST(sync_interval_minutes, $2)))
+                 WHERE channel_id = $1",
+                channel_id,
+                ECOMMERCE_RETRY_MINUTES
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(run)
+    }
+
+    pub async fn runs(&self, channel_id: i32, keyset: KeysetQuery) -> Result<KeysetPage<EcommerceSyncRun>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            EcommerceSyncRun,
+            "SELECT * FROM warehouse.ecommerce_sync_runs
+             WHERE channel_id = $1 AND ($2::BIGINT IS NULL OR run_id < $2)
+             ORDER BY run_id DESC
+             LIMIT $3",
+            channel_id,
+            after_id,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |run| run.run_id))
+    }
+}
+
+async fn set_listings(conn: &mut PgConnection, channel_id: i32, listings: &[SetEcommerceListing]) -> Result<()> {
+    for (index, listing) in listings.iter().enumerate() {
+        for other in &listings[..index] {
+            if other.item_id == listing.item_id {
+                return Err(violation(format!("item {} is listed more than once", listing.item_id)));
+            }
+            if other.external_id == listing.external_id {
+                return Err(violation(format!("{} is listed for more than one item", listing.external_id)));
+            }
+        }
+    }
+
+    let item_ids: Vec<i32> = listings.iter().map(|listing| listing.item_id).collect();
+    let external_ids: Vec<String> = listings.iter().map(|listing| listing.external_id.clone()).collect();
+    let buffers: Vec<Option<Decimal>> = listings.iter().map(|listing| listing.buffer_quantity).collect();
+    sqlx::query!(
+        "INSERT INTO warehouse.ecommerce_listings (channel_id, item_id, external_id, buffer_quantity)
+         SELECT $1, * FROM UNNEST($2::INT[], $3::VARCHAR[], $4::DECIMAL[])",
+        channel_id,
+        &item_ids,
+        &external_ids,
+        &buffers as &[Option<Decimal>]
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn load_channel(conn: &mut PgConnection, id: i32) -> Result<Option<EcommerceChannelDetail>> {
+    let Some(channel) =
+        sqlx::query_as!(EcommerceChannel, "SELECT * FROM warehouse.ecommerce_channels WHERE channel_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    else {
+        return Ok(None);
+    };
+
+    let listings = sqlx::query_as!(
+        EcommerceListing,
+        "SELECT l.item_id, i.item_code, l.external_id, l.buffer_quantity, l.pushed_quantity, l.pushed_at
+         FROM warehouse.ecommerce_listings l
+         JOIN warehouse.items i ON i.item_id = l.item_id
+         WHERE l.channel_id = $1
+         ORDER BY i.item_code",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(EcommerceChannelDetail { channel, listings }))
+}
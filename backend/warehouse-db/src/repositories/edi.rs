@@ -0,0 +1,111 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::*;
+use crate::utils::*;
+
+#[derive(Clone)]
+pub struct EdiRepository {
+    pool: PgPool,
+}
+
+impl EdiRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Append a document to the EDI log
+    pub async fn log(&self, document: NewEdiDocument) -> Result<EdiDocument> {
+        let logged = sqlx::query_as!(
+            EdiDocument,
+            "INSERT INTO warehouse.edi_documents (
+                 direction, transaction_set, partner_id, control_number, status, error,
+                 reference_type, reference_id, content
+             ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             RETURNING *",
+            document.direction,
+            document.transaction_set,
+            document.partner_id,
+            document.control_number,
+            document.status,
+            document.error,
+            document.reference_type,
+            document.reference_id,
+            document.content
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(logged)
+    }
+
+    /// Document log newest first, paged by `document_id`
+    pub async fn list(&self, filter: EdiFilter, keyset: KeysetQuery) -> Result<KeysetPage<EdiDocument>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            EdiDocument,
+            "SELECT * FROM warehouse.edi_documents
+             WHERE ($1::BIGINT IS NULL OR document_id < $1)
+               AND ($2::VARCHAR IS NULL OR direction = $2)
+               AND ($3::VARCHAR IS NULL OR transaction_set = $3)
+               AND ($4::VARCHAR IS NULL OR status = $4)
+             ORDER BY document_id DESC
+             LIMIT $5",
+            after_id,
+            filter.direction,
+            filter.transaction_set,
+            filter.status,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |d| d.document_id))
+    }
+
+    pub async fn get_by_id(&self, id: i64) -> Result<Option<EdiDocument>> {
+        let document = sqlx::query_as!(
+            EdiDocument,
+            "SELECT * FROM warehouse.edi_documents WHERE document_id = $1",
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(document)
+    }
+
+    /// Outbound document of a transaction set already generated for a record
+    pub async fn find_outbound(
+        &self,
+        transaction_set: &str,
+        reference_type: &str,
+        reference_id: i32,
+    ) -> Result<Option<EdiDocument>> {
+        let document = sqlx::query_as!(
+            EdiDocument,
+            "SELECT * FROM warehouse.edi_documents
+             WHERE direction = $1 AND transaction_set = $2 AND reference_type = $3 AND reference_id = $4
+             ORDER BY document_id
+             LIMIT 1",
+            EDI_OUTBOUND,
+            transaction_set,
+            reference_type,
+            reference_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(document)
+    }
+
+    /// Next interchange control number for outbound documents (ISA13)
+    pub async fn next_control_number(&self) -> Result<i64> {
+        let next = sqlx::query_scalar!("SELECT nextval('warehouse.edi_control_number_seq')")
+            .fetch_one(&self.pool)
+            .await?
+            .unwrap_or(1);
+
+        Ok(next)
+    }
+}
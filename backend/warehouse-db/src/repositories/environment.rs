@@ -0,0 +1,339 @@
+use std::collections::BTreeMap;
+
+use crate::utils::*;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct EnvironmentRepository {
+    pool: PgPool,
+}
+
+impl EnvironmentRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn thresholds(&self, zone_id: i32) -> Result<Option<ZoneThresholds>> {
+        let thresholds =
+            sqlx::query_as!(ZoneThresholds, "SELECT * FROM warehouse.zone_thresholds WHERE zone_id = $1", zone_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(thresholds)
+    }
+
+    /// Replace a zone's thresholds. Excursions already open keep the limits they opened with.
+    pub async fn set_thresholds(&self, zone_id: i32, thresholds: SetThresholds) -> Result<ZoneThresholds> {
+        let saved = sqlx::query_as!(
+            ZoneThresholds,
+            "INSERT INTO warehouse.zone_thresholds (zone_id, min_temperature, max_temperature, min_humidity, max_humidity)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (zone_id) DO UPDATE SET
+                 min_temperature = EXCLUDED.min_temperature,
+                 max_temperature = EXCLUDED.max_temperature,
+                 min_humidity = EXCLUDED.min_humidity,
+                 max_humidity = EXCLUDED.max_humidity,
+                 updated_at = NOW()
+             RETURNING *",
+            zone_id,
+            thresholds.min_temperature,
+            thresholds.max_temperature,
+            thresholds.min_humidity,
+            thresholds.max_humidity
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(saved)
+    }
+
+    /// Sensors placed in a zone by code
+    pub async fn sensors(&self, zone_id: i32) -> Result<Vec<Sensor>> {
+        let sensors =
+            sqlx::query_as!(Sensor, "SELECT * FROM warehouse.sensors WHERE zone_id = $1 ORDER BY sensor_code", zone_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(sensors)
+    }
+
+    pub async fn get_sensor(&self, id: i32) -> Result<Option<Sensor>> {
+        let sensor = sqlx::query_as!(Sensor, "SELECT * FROM warehouse.sensors WHERE sensor_id = $1", id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(sensor)
+    }
+
+    pub async fn sensor_code_exists(&self, code: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM warehouse.sensors WHERE sensor_code = $1)", code)
+            .fetch_one(&self.pool)
+            .await?
+            .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    pub async fn create_sensor(&self, zone_id: i32, sensor: CreateSensor) -> Result<Sensor> {
+        let created = sqlx::query_as!(
+            Sensor,
+            "INSERT INTO warehouse.sensors (sensor_code, zone_id) VALUES ($1, $2) RETURNING *",
+            sensor.sensor_code,
+            zone_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(created)
+    }
+
+    /// Store a batch of readings and track excursions. A reading outside its zone's
+    /// thresholds opens an excursion for the sensor and measure, further out-of-range
+    /// readings extend it and the first reading back in range closes it. Readings older
+    /// than the newest one already seen from a sensor are stored for history only.
+    pub async fn ingest(&self, batch: SensorReadings) -> Result<ReadingIngestResult> {
+        let mut tx = self.pool.begin().await?;
+
+        let mut codes: Vec<String> = batch.readings.iter().map(|r| r.sensor_code.clone()).collect();
+        codes.sort();
+        codes.dedup();
+
+        // Locking the sensors runs batches from the same sensor one after another
+        let sensors: BTreeMap<String, Sensor> = sqlx::query_as!(
+            Sensor,
+            "SELECT * FROM warehouse.sensors WHERE sensor_code = ANY($1) ORDER BY sensor_id FOR UPDATE",
+            &codes
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|sensor| (sensor.sensor_code.clone(), sensor))
+        .collect();
+        let unknown_sensors: Vec<String> = codes.into_iter().filter(|code| !sensors.contains_key(code)).collect();
+
+        let known: Vec<(&Sensor, &NewSensorReading)> = batch
+            .readings
+            .iter()
+            .filter_map(|reading| sensors.get(&reading.sensor_code).map(|sensor| (sensor, reading)))
+            .collect();
+
+        let sensor_ids: Vec<i32> = known.iter().map(|(sensor, _)| sensor.sensor_id).collect();
+        let zone_ids: Vec<i32> = known.iter().map(|(sensor, _)| sensor.zone_id).collect();
+        let recorded_at: Vec<_> = known.iter().map(|(_, reading)| reading.recorded_at).collect();
+        let temperatures: Vec<Option<Decimal>> = known.iter().map(|(_, reading)| reading.temperature).collect();
+        let humidities: Vec<Option<Decimal>> = known.iter().map(|(_, reading)| reading.humidity).collect();
+
+        let mut stored = sqlx::query_as!(
+            SensorReading,
+            "INSERT INTO warehouse.sensor_readings (sensor_id, zone_id, recorded_at, temperature, humidity)
+             SELECT * FROM UNNEST($1::INT[], $2::INT[], $3::TIMESTAMPTZ[], $4::DECIMAL[], $5::DECIMAL[])
+             ON CONFLICT (sensor_id, recorded_at) DO NOTHING
+             RETURNING *",
+            &sensor_ids,
+            &zone_ids,
+            &recorded_at,
+            &temperatures as &[Option<Decimal>],
+            &humidities as &[Option<Decimal>]
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        stored.sort_by_key(|reading| (reading.sensor_id, reading.recorded_at));
+
+        let mut result = ReadingIngestResult {
+            accepted: stored.len(),
+            duplicates: known.len() - stored.len(),
+            unknown_sensors,
+            ..Default::default()
+        };
+
+        for sensor in sensors.values() {
+            let readings: Vec<&SensorReading> = stored
+                .iter()
+                .filter(|reading| reading.sensor_id == sensor.sensor_id)
+                .filter(|reading| sensor.last_reading_at.is_none_or(|last| reading.recorded_at > last))
+                .collect();
+            let Some(newest) = readings.last().map(|reading| reading.recorded_at) else {
+                continue;
+            };
+
+            let thresholds = sqlx::query_as!(
+                ZoneThresholds,
+                "SELECT * FROM warehouse.zone_thresholds WHERE zone_id = $1",
+                sensor.zone_id
+            )
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            if let Some(thresholds) = thresholds {
+                for reading in readings {
+                    let measures = [
+                        (
+                            MEASURE_TEMPERATURE,
+                            reading.temperature,
+                            thresholds.min_temperature,
+                            thresholds.max_temperature,
+                        ),
+                        (MEASURE_HUMIDITY, reading.humidity, thresholds.min_humidity, thresholds.max_humidity),
+                    ];
+                    for (measure, value, min, max) in measures {
+                        let Some(value) = value else {
+                            continue;
+                        };
+                        track_excursion(&mut tx, &mut result, reading, measure, value, min, max).await?;
+                    }
+                }
+            }
+
+            sqlx::query!(
+                "UPDATE warehouse.sensors SET last_reading_at = $2 WHERE sensor_id = $1",
+                sensor.sensor_id,
+                newest
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    /// Excursions newest first, paged by `excursion_id`
+    pub async fn excursions(&self, filter: ExcursionFilter, keyset: KeysetQuery) -> Result<KeysetPage<Excursion>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            Excursion,
+            "SELECT * FROM warehouse.excursions
+             WHERE ($1::BIGINT IS NULL OR excursion_id < $1)
+               AND ($2::INT IS NULL OR zone_id = $2)
+               AND ($3::BOOLEAN IS NULL OR (ended_at IS NULL) = $3)
+             ORDER BY excursion_id DESC
+             LIMIT $4",
+            after_id,
+            filter.zone_id,
+            filter.open,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |e| e.excursion_id as i64))
+    }
+
+    /// Readings of a zone in `[from, to)` and the excursions overlapping it
+    pub async fn history(&self, zone_id: i32, query: EnvironmentHistoryQuery) -> Result<EnvironmentHistory> {
+        let readings = sqlx::query_as!(
+            SensorReading,
+            "SELECT * FROM warehouse.sensor_readings
+             WHERE zone_id = $1 AND recorded_at >= $2 AND recorded_at < $3
+               AND ($4::INT IS NULL OR sensor_id = $4)
+             ORDER BY recorded_at, sensor_id",
+            zone_id,
+            query.from,
+            query.to,
+            query.sensor_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let excursions = sqlx::query_as!(
+            Excursion,
+            "SELECT * FROM warehouse.excursions
+             WHERE zone_id = $1 AND started_at < $3 AND (ended_at IS NULL OR ended_at >= $2)
+               AND ($4::INT IS NULL OR sensor_id = $4)
+             ORDER BY started_at, excursion_id",
+            zone_id,
+            query.from,
+            query.to,
+            query.sensor_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(EnvironmentHistory {
+            zone_id,
+            from: query.from,
+            to: query.to,
+            thresholds: self.thresholds(zone_id).await?,
+            readings,
+            excursions,
+        })
+    }
+}
+
+/// Open, extend or close the sensor's excursion for one measure given a new reading
+async fn track_excursion(
+    conn: &mut PgConnection,
+    result: &mut ReadingIngestResult,
+    reading: &SensorReading,
+    measure: &str,
+    value: Decimal,
+    min: Option<Decimal>,
+    max: Option<Decimal>,
+) -> Result<()> {
+    let out_of_range = min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max);
+
+    let open = sqlx::query_as!(
+        Excursion,
+        "SELECT * FROM warehouse.excursions WHERE sensor_id = $1 AND measure = $2 AND ended_at IS NULL",
+        reading.sensor_id,
+        measure
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    match (open, out_of_range) {
+        (None, true) => {
+            let opened = sqlx::query_as!(
+                Excursion,
+                "INSERT INTO warehouse.excursions (sensor_id, zone_id, measure, limit_min, limit_max, peak_value, started_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 RETURNING *",
+                reading.sensor_id,
+                reading.zone_id,
+                measure,
+                min,
+                max,
+                value,
+                reading.recorded_at
+            )
+            .fetch_one(&mut *conn)
+            .await?;
+            result.excursions_opened.push(opened);
+        }
+        (Some(excursion), true) => {
+            // The peak is the reading furthest outside the range the excursion opened with
+            let beyond = |v: Decimal| match (excursion.limit_min, excursion.limit_max) {
+                (_, Some(max)) if v > max => v - max,
+                (Some(min), _) if v < min => min - v,
+                _ => Decimal::ZERO,
+            };
+            if beyond(value) > beyond(excursion.peak_value) {
+                sqlx::query!(
+                    "UPDATE warehouse.excursions SET peak_value = $2 WHERE excursion_id = $1",
+                    excursion.excursion_id,
+                    value
+                )
+                .execute(&mut *conn)
+                .await?;
+            }
+        }
+        (Some(excursion), false) => {
+            let closed = sqlx::query_as!(
+                Excursion,
+                "UPDATE warehouse.excursions SET ended_at = $2 WHERE excursion_id = $1 RETURNING *",
+                excursion.excursion_id,
+                reading.recorded_at
+            )
+            .fetch_one(&mut *conn)
+            .await?;
+            result.excursions_closed.push(closed);
+        }
+        (None, false) => {}
+    }
+
+    Ok(())
+}
@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+
+use crate::error::violation;
+use crate::utils::*;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::chrono::{DateTime, Utc};
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct ErpRepository {
+    pool: PgPool,
+}
+
+/// Line of a receipt or shipment, before grouping under its document
+struct DocumentLine {
+    document_id: i32,
+    item_code: String,
+    quantity: warehouse_models::rust_decimal::Decimal,
+}
+
+impl ErpRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Connectors by code
+    pub async fn list(&self) -> Result<Vec<ErpConnector>> {
+        let connectors =
+            sqlx::query_as!(ErpConnector, "SELECT * FROM warehouse.erp_connectors ORDER BY connector_code")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(connectors)
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<ErpConnectorDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_connector(&mut conn, id).await
+    }
+
+    pub async fn code_exists(&self, code: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.erp_connectors WHERE connector_code = $1)",
+            code
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    /// Set up a connector; the first sync is due right away
+    pub async fn create(&self, connector: CreateErpConnector) -> Result<ErpConnectorDetail> {
+        let mut tx = self.pool.begin().await?;
+
+        let connector_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.erp_connectors (
+                 connector_code, erp_system, connector_type, base_url, api_key, item_master, sync_interval_minutes
+             ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING connector_id",
+            connector.connector_code,
+            connector.erp_system,
+            connector.connector_type,
+            connector.base_url,
+            connector.api_key,
+            connector.item_master.as_deref().unwrap_or(ERP_ITEM_MASTER_ERP),
+            connector.sync_interval_minutes
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        set_field_mappings(&mut tx, connector_id, &connector.field_mappings).await?;
+
+        let detail = load_connector(&mut tx, connector_id).await?.expect("connector inserted above");
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Returns None when the connector does not exist
+    pub async fn update(&self, id: i32, changes: UpdateErpConnector) -> Result<Option<ErpConnectorDetail>> {
+        let mut tx = self.pool.begin().await?;
+
+        // A new interval takes effect from the last sync rather than waiting out the old one
+        let updated = sqlx::query!(
+            "UPDATE warehouse.erp_connectors SET
+                 base_url = COALESCE($2, base_url),
+                 api_key = COALESCE($3, api_key),
+                 item_master = COALESCE($4, item_master),
+                 sync_interval_minutes = COALESCE($5, sync_interval_minutes),
+                 is_active = COALESCE($6, is_active),
+                 next_run_at = CASE WHEN $5::INT IS NULL THEN next_run_at
+                                    ELSE COALESCE(last_synced_at + make_interval(mins => $5), NOW()) END,
+                 updated_at = NOW()
+             WHERE connector_id = $1",
+            id,
+            changes.base_url,
+            changes.api_key,
+            changes.item_master,
+            changes.sync_interval_minutes,
+            changes.is_active
+        )
+        .execute(&mut *tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        if let Some(field_mappings) = &changes.field_mappings {
+            sqlx::query!("DELETE FROM warehouse.erp_field_mappings WHERE connector_id = $1", id)
+                .execute(&mut *tx)
+                .await?;
+            set_field_mappings(&mut tx, id, field_mappings).await?;
+        }
+
+        let detail = load_connector(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Take the active connectors due at `now` and push their next run out by their
+    /// interval, so a connector is synced by one scheduler even with several servers running
+    pub async fn claim_due(&self, now: DateTime<Utc>) -> Result<Vec<ErpConnector>> {
+        let connectors = sqlx::query_as!(
+            ErpConnector,
+            "UPDATE warehouse.erp_connectors SET next_run_at = $1 + make_interval(mins => sync_interval_minutes)
+             WHERE connector_id IN (
+                 SELECT connector_id FROM warehouse.erp_connectors
+                 WHERE is_active AND next_run_at <= $1
+                 ORDER BY next_run_at
+                 FOR UPDATE SKIP LOCKED
+             )
+             RETURNING *",
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(connectors)
+    }
+
+    /// How far `direction` of `entity` has synced; None before its first sync
+    pub async fn cursor(&self, connector_id: i32, direction: &str, entity: &str) -> Result<Option<ErpSyncCursor>> {
+        let cursor = sqlx::query_as!(
+            ErpSyncCursor,
+            "SELECT position_at, position_id FROM warehouse.erp_sync_cursors
+             WHERE connector_id = $1 AND direction = $2 AND entity = $3",
+            connector_id,
+            direction,
+            entity
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(cursor)
+    }
+
+    pub async fn advance(&self, connector_id: i32, direction: &str, entity: &str, cursor: ErpSyncCursor) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO warehouse.erp_sync_cursors (connector_id, direction, entity, position_at, position_id)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (connector_id, direction, entity) DO UPDATE
+             SET position_at = EXCLUDED.position_at, position_id = EXCLUDED.position_id, updated_at = NOW()",
+            connector_id,
+            direction,
+            entity,
+            cursor.position_at,
+            cursor.position_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record one direction and entity of a sync. A failure brings the connector's next
+    /// run forward so it is retried soon.
+    pub async fn record_run(
+        &self,
+        connector_id: i32,
+        direction: &str,
+        entity: &str,
+        started_at: DateTime<Utc>,
+        records: i32,
+        error: Option<String>,
+    ) -> Result<ErpSyncRun> {
+        let mut tx = self.pool.begin().await?;
+        let status = if error.is_none() { ERP_RUN_SUCCEEDED } else { ERP_RUN_FAILED };
+
+        let run = sqlx::query_as!(
+            ErpSyncRun,
+            "INSERT INTO warehouse.erp_sync_runs (connector_id, direction, entity, status, records, error, started_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING *",
+            connector_id,
+            direction,
+            entity,
+            status,
+            records,
+            error,
+            started_at
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if status == ERP_RUN_SUCCEEDED {
+            sqlx::query!(
+                "UPDATE warehouse.erp_connectors SET last_synced_at = GREATEST(last_synced_at, $2) WHERE connector_id = $1",
+                connector_id,
+                started_at
+            )
+            .execute(&mut *tx)
+            .await?;
+        } else {
+            sqlx::query!(
+                "UPDATE warehouse.erp_connectors SET
+                     next_run_at = LEAST(next_run_at, NOW() + make_interval(mins => LEAST(sync_interval_minutes, $2)))
+                 WHERE connector_id = $1",
+                connector_id,
+                ERP_RETRY_MINUTES
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(run)
+    }
+
+    /// Sync runs of a connector, newest first, paged by `run_id`
+    pub async fn runs(&self, connector_id: i32, keyset: KeysetQuery) -> Result<KeysetPage<ErpSyncRun>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            ErpSyncRun,
+            "SELECT * FROM warehouse.erp_sync_runs
+             WHERE connector_id = $1 AND ($2::BIGINT IS NULL OR run_id < $2)
+             ORDER BY run_id DESC
+             LIMIT $3",
+            connector_id,
+            after_id,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |run| run.run_id))
+    }
+
+    /// Our local id for an ERP record imported earlier
+    pub async fn linked(&self, connector_id: i32, entity: &str, external_id: &str) -> Result<Option<i32>> {
+        let local_id = sqlx::query_scalar!(
+            "SELECT local_id FROM warehouse.erp_links WHERE connector_id = $1 AND entity = $2 AND external_id = $3",
+            connector_id,
+            entity,
+            external_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(local_id)
+    }
+
+    pub async fn link(&self, connector_id: i32, entity: &str, external_id: &str, local_id: i32) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO warehouse.erp_links (connector_id, entity, external_id, local_id)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (connector_id, entity, external_id) DO UPDATE SET local_id = EXCLUDED.local_id",
+            connector_id,
+            entity,
+            external_id,
+            local_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Warehouses are named by code in ERP documents
+    pub async fn warehouse_id(&self, warehouse_code: &str) -> Result<Option<i32>> {
+        let warehouse_id = sqlx::query_scalar!(
+            "SELECT warehouse_id FROM warehouse.warehouses WHERE warehouse_code = $1 AND is_active",
+            warehouse_code
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(warehouse_id)
+    }
+
+    /// Items changed after `after`, oldest change first
+    pub async fn changed_items(&self, after: Option<ErpSyncCursor>, limit: i64) -> Result<Vec<ErpItem>> {
+        let (after_at, after_id) = split_cursor(after);
+
+        let items = sqlx::query_as!(
+            ErpItem,
+            r#"SELECT item_id AS "item_id?", item_code, item_name, item_description, unit, category,
+                      COALESCE(updated_at, created_at, 'epoch') AS "updated_at!"
+               FROM warehouse.items
+               WHERE (COALESCE(updated_at, created_at, 'epoch'), item_id) > ($1, $2)
+               ORDER BY COALESCE(updated_at, created_at, 'epoch'), item_id
+               LIMIT $3"#,
+            after_at,
+            after_id as i32,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(items)
+    }
+
+    /// Create or update an item pulled from the ERP, keyed by its code. Returns its id.
+    pub async fn import_item(&self, item: &ErpItem) -> Result<i32> {
+        let item_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.items (item_code, item_name, item_description, unit, category, created_by)
+             VALUES ($1, $2, $3, COALESCE($4, 'PCS'), $5, 1)
+             ON CONFLICT (item_code) DO UPDATE SET
+                 item_name = EXCLUDED.item_name,
+                 item_description = EXCLUDED.item_description,
+                 unit = COALESCE($4, warehouse.items.unit),
+                 category = EXCLUDED.category,
+                 updated_at = NOW()
+             RETURNING item_id",
+            item.item_code,
+            item.item_name,
+            item.item_description,
+            item.unit,
+            item.category
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(item_id)
+    }
+
+    /// Receipts fully received after `after`, oldest first
+    pub async fn goods_receipts(
+        &self,
+        connector_id: i32,
+        after: Option<ErpSyncCursor>,
+        limit: i64,
+    ) -> Result<Vec<ErpGoodsReceipt>> {
+        let (after_at, after_id) = split_cursor(after);
+
+        let headers = sqlx::query!(
+            r#"SELECT r.receipt_id, r.asn_number, r.purchase_order_number, l.external_id AS "erp_purchase_order_id?",
+                      w.warehouse_code, r.supplier_name, r.received_at AS "received_at!"
+               FROM warehouse.inbound_receipts r
+               JOIN warehouse.warehouses w ON w.warehouse_id = r.warehouse_id
+               LEFT JOIN warehouse.erp_links l
+                      ON l.connector_id = $1 AND l.entity = $2 AND l.local_id = r.receipt_id
+               WHERE r.status = $3 AND r.received_at IS NOT NULL
+                 AND (r.received_at, r.receipt_id) > ($4, $5)
+               ORDER BY r.received_at, r.receipt_id
+               LIMIT $6"#,
+            connector_id,
+            ERP_ENTITY_PURCHASE_ORDER,
+            RECEIPT_RECEIVED,
+            after_at,
+            after_id as i32,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let receipt_ids: Vec<i32> = headers.iter().map(|header| header.receipt_id).collect();
+        let mut lines = group_lines(
+            sqlx::query_as!(
+                DocumentLine,
+                r#"SELECT l.receipt_id AS document_id, i.item_code, SUM(l.quantity_received) AS "quantity!"
+                   FROM warehouse.inbound_receipt_lines l
+                   JOIN warehouse.items i ON i.item_id = l.item_id
+                   WHERE l.receipt_id = ANY($1) AND l.quantity_received > 0
+                   GROUP BY l.receipt_id, i.item_code
+                   ORDER BY l.receipt_id, i.item_code"#,
+                &receipt_ids
+            )
+            .fetch_all(&self.pool)
+            .await?,
+        );
+
+        Ok(headers
+            .into_iter()
+            .map(|header| ErpGoodsReceipt {
+                lines: lines.remove(&header.receipt_id).unwrap_or_default(),
+                receipt_id: header.receipt_id,
+                asn_number: header.asn_number,
+                purchase_order_number: header.purchase_order_number,
+                erp_purchase_order_id: header.erp_purchase_order_id,
+                warehouse_code: header.warehouse_code,
+                supplier_name: header.supplier_name,
+                received_at: header.received_at,
+            })
+            .collect())
+    }
+
+    /// Shipments made after `after`, oldest first
+    pub async fn goods_issues(&self, after: Option<ErpSyncCursor>, limit: i64) -> Result<Vec<ErpGoodsIssue>> {
+        let (after_at, after_id) = split_cursor(after);
+
+        let headers = sqlx::query!(
+            "SELECT s.shipment_id, s.shipment_number, o.order_number, o.customer_reference, w.warehouse_code, s.shipped_at
+             FROM warehouse.shipments s
+             JOIN warehouse.outbound_orders o ON o.order_id = s.order_id
+             JOIN warehouse.warehouses w ON w.warehouse_id = s.warehouse_id
+             WHERE (s.shipped_at, s.shipment_id) > ($1, $2)
+             ORDER BY s.shipped_at, s.shipment_id
+             LIMIT $3",
+            after_at,
+            after_id as i32,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let shipment_ids: Vec<i32> = headers.iter().map(|header| header.shipment_id).collect();
+        let mut lines = group_lines(
+            sqlx::query_as!(
+                DocumentLine,
+                r#"SELECT l.shipment_id AS document_id, i.item_code, SUM(l.quantity) AS "quantity!"
+                   FROM warehouse.shipment_lines l
+                   JOIN warehouse.items i ON i.item_id = l.item_id
+                   WHERE l.shipment_id = ANY($1)
+                   GROUP BY l.shipment_id, i.item_code
+                   ORDER BY l.shipment_id, i.item_code"#,
+                &shipment_ids
+            )
+            .fetch_all(&self.pool)
+            .await?,
+        );
+
+        Ok(headers
+            .into_iter()
+            .map(|header| ErpGoodsIssue {
+                lines: lines.remove(&header.shipment_id).unwrap_or_default(),
+                shipment_id: header.shipment_id,
+                shipment_number: header.shipment_number,
+                order_number: header.order_number,
+                customer_reference: header.customer_reference,
+                warehouse_code: header.warehouse_code,
+                shipped_at: header.shipped_at,
+            })
+            .collect())
+    }
+}
+
+/// Cursor bounds that sort before every record when nothing has synced yet
+fn split_cursor(cursor: Option<ErpSyncCursor>) -> (DateTime<Utc>, i64) {
+    match cursor {
+        Some(cursor) => (cursor.position_at, cursor.position_id),
+        None => (DateTime::<Utc>::MIN_UTC, 0),
+    }
+}
+
+fn group_lines(rows: Vec<DocumentLine>) -> HashMap<i32, Vec<ErpLine>> {
+    let mut grouped: HashMap<i32, Vec<ErpLine>> = HashMap::new();
+    for row in rows {
+        grouped
+            .entry(row.document_id)
+            .or_default()
+            .push(ErpLine { item_code: row.item_code, quantity: row.quantity });
+    }
+    grouped
+}
+
+async fn set_field_mappings(conn: &mut PgConnection, connector_id: i32, mappings: &[ErpFieldMapping]) -> Result<()> {
+    for (index, mapping) in mappings.iter().enumerate() {
+        let same_entity = mappings[..index].iter().filter(|other| other.entity == mapping.entity);
+        for other in same_entity {
+            if other.field_name == mapping.field_name || other.erp_field == mapping.erp_field {
+                return Err(violation(format!("{} {} is mapped more than once", mapping.entity, mapping.field_name)));
+            }
+        }
+    }
+
+    let entities: Vec<String> = mappings.iter().map(|mapping| mapping.entity.clone()).collect();
+    let field_names: Vec<String> = mappings.iter().map(|mapping| mapping.field_name.clone()).collect();
+    let erp_fields: Vec<String> = mappings.iter().map(|mapping| mapping.erp_field.clone()).collect();
+    sqlx::query!(
+        "INSERT INTO warehouse.erp_field_mappings (connector_id, entity, field_name, erp_field)
+         SELECT $1, * FROM UNNEST($2::VARCHAR[], $3::VARCHAR[], $4::VARCHAR[])",
+        connector_id,
+        &entities,
+        &field_names,
+        &erp_fields
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn load_connector(conn: &mut PgConnection, id: i32) -> Result<Option<ErpConnectorDetail>> {
+    let Some(connector) =
+        sqlx::query_as!(ErpConnector, "SELECT * FROM warehouse.erp_connectors WHERE connector_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    else {
+        return Ok(None);
+    };
+
+    let field_mappings = sqlx::query_as!(
+        ErpFieldMapping,
+        "SELECT entity, field_name, erp_field FROM warehouse.erp_field_mappings
+         WHERE connector_id = $1
+         ORDER BY entity, field_name",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(ErpConnectorDetail { connector, field_mappings }))
+}
@@ -0,0 +1,223 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use warehouse_models::*;
+
+use crate::utils::*;
+use crate::violation;
+
+#[derive(Clone)]
+pub struct EventConsumerRepository {
+    pool: PgPool,
+}
+
+impl EventConsumerRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self) -> Result<Vec<EventConsumer>> {
+        let consumers = sqlx::query_as!(EventConsumer, "SELECT * FROM warehouse.event_consumers ORDER BY consumer_name")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(consumers)
+    }
+
+    /// Active consumers that have not yet been sent the latest event and are not waiting
+    /// out a retry delay
+    pub async fn behind(&self) -> Result<Vec<EventConsumer>> {
+        let consumers = sqlx::query_as!(
+            EventConsumer,
+            "SELECT * FROM warehouse.event_consumers c
+             WHERE is_active
+               AND (next_attempt_at IS NULL OR next_attempt_at <= NOW())
+               AND EXISTS (SELECT 1 FROM warehouse.stock_events e WHERE e.event_id > c.position)
+             ORDER BY consumer_id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(consumers)
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<EventConsumer>> {
+        let consumer =
+            sqlx::query_as!(EventConsumer, "SELECT * FROM warehouse.event_consumers WHERE consumer_id = $1", id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(consumer)
+    }
+
+    pub async fn create(&self, consumer: CreateEventConsumer) -> Result<EventConsumer> {
+        let created = sqlx::query_as!(
+            EventConsumer,
+            "INSERT INTO warehouse.event_consumers (consumer_name, endpoint_url, api_key, position)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (consumer_name) DO NOTHING
+             RETURNING *",
+            consumer.consumer_name,
+            consumer.endpoint_url,
+            consumer.api_key,
+            consumer.position.unwrap_or(0)
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        created.ok_or_else(|| violation(format!("consumer {} is already registered", consumer.consumer_name)))
+    }
+
+    /// Move the consumer back (or forward) so its next delivery starts after `position`
+    pub async fn rewind(&self, id: i32, position: i64) -> Result<Option<EventConsumer>> {
+        let consumer = sqlx::query_as!(
+            EventConsumer,
+            "UPDATE warehouse.event_consumers
+             SET position = $2, last_error = NULL, failed_attempts = 0, next_attempt_at = NULL, updated_at = NOW()
+             WHERE consumer_id = $1
+             RETURNING *",
+            id,
+            position
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(consumer)
+    }
+
+    /// Record that the consumer acknowledged every event up to `position`
+    pub async fn advance(&self, id: i32, position: i64) -> Result<()> {
+        sqlx::query!(
+            "UPDATE warehouse.event_consumers
+             SET position = GREATEST(position, $2), last_error = NULL, failed_attempts = 0, next_attempt_at = NULL,
+                 last_delivered_at = NOW(), updated_at = NOW()
+             WHERE consumer_id = $1",
+            id,
+            position
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Count a failed attempt at the batch after the consumer's position and hold further
+    /// attempts until `next_attempt_at`
+    pub async fn record_failure(&self, id: i32, error: &str, next_attempt_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query!(
+            "UPDATE warehouse.event_consumers
+             SET failed_attempts = failed_attempts + 1, last_error = $2, next_attempt_at = $3, updated_at = NOW()
+             WHERE consumer_id = $1",
+            id,
+            error,
+            next_attempt_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Give up on the batch `events`: park it in the dead-letter queue and move the consumer
+    /// past it
+    pub async fn dead_letter(
+        &self,
+        consumer: &EventConsumer,
+        events: &[StockEvent],
+        attempts: i32,
+        error: &str,
+    ) -> Result<EventDeadLetter> {
+        let (Some(first), Some(last)) = (events.first(), events.last()) else {
+            return Err(violation("cannot dead-letter an empty batch"));
+        };
+        let mut tx = self.pool.begin().await?;
+
+        let dead_letter = sqlx::query_as!(
+            EventDeadLetter,
+            "INSERT INTO warehouse.event_dead_letters (
+                 consumer_id, first_event_id, last_event_id, event_count, attempts, last_error
+             ) VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING *",
+            consumer.consumer_id,
+            first.event_id,
+            last.event_id,
+            events.len() as i32,
+            attempts,
+            error
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE warehouse.event_consumers
+             SET position = GREATEST(position, $2), failed_attempts = 0, next_attempt_at = NULL, updated_at = NOW()
+             WHERE consumer_id = $1",
+            consumer.consumer_id,
+            last.event_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(dead_letter)
+    }
+
+    /// Dead letters newest first, paged by `dead_letter_id`
+    pub async fn dead_letters(
+        &self,
+        filter: DeadLetterFilter,
+        keyset: KeysetQuery,
+    ) -> Result<KeysetPage<EventDeadLetter>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            EventDeadLetter,
+            "SELECT * FROM warehouse.event_dead_letters
+             WHERE ($1::BIGINT IS NULL OR dead_letter_id < $1)
+               AND ($2::INT IS NULL OR consumer_id = $2)
+               AND ($3::VARCHAR IS NULL OR status = $3)
+             ORDER BY dead_letter_id DESC
+             LIMIT $4",
+            after_id,
+            filter.consumer_id,
+            filter.status,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |d| d.dead_letter_id))
+    }
+
+    pub async fn get_dead_letter(&self, id: i64) -> Result<Option<EventDeadLetter>> {
+        let dead_letter = sqlx::query_as!(
+            EventDeadLetter,
+            "SELECT * FROM warehouse.event_dead_letters WHERE dead_letter_id = $1",
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(dead_letter)
+    }
+
+    /// Record the outcome of re-driving a dead letter: delivered, or one more failed attempt
+    pub async fn record_redrive(&self, id: i64, error: Option<&str>) -> Result<EventDeadLetter> {
+        let dead_letter = sqlx::query_as!(
+            EventDeadLetter,
+            "UPDATE warehouse.event_dead_letters
+             SET status = CASE WHEN $2::TEXT IS NULL THEN 'REDRIVEN' ELSE status END,
+                 redriven_at = CASE WHEN $2::TEXT IS NULL THEN NOW() ELSE redriven_at END,
+                 attempts = attempts + 1,
+                 last_error = COALESCE($2, last_error)
+             WHERE dead_letter_id = $1
+             RETURNING *",
+            id,
+            error
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(dead_letter)
+    }
+}
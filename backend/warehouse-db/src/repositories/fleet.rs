@@ -0,0 +1,589 @@
+use crate::error::violation;
+use crate::repositories::tracking::record_events;
+use anyhow::Result;
+use chrono::{NaiveDate, Utc};
+use sqlx::{PgConnection, PgPool};
+use std::collections::{HashMap, HashSet};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct FleetRepository {
+    pool: PgPool,
+}
+
+impl FleetRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// A warehouse's drivers, active ones first
+    pub async fn drivers(&self, warehouse_id: i32) -> Result<Vec<DeliveryDriver>> {
+        let drivers = sqlx::query_as!(
+            DeliveryDriver,
+            "SELECT * FROM warehouse.delivery_drivers WHERE warehouse_id = $1
+             ORDER BY is_active DESC, driver_code",
+            warehouse_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(drivers)
+    }
+
+    pub async fn driver_code_exists(&self, warehouse_id: i32, code: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.delivery_drivers WHERE warehouse_id = $1 AND driver_code = $2)",
+            warehouse_id,
+            code
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(exists.unwrap_or(false))
+    }
+
+    pub async fn add_driver(&self, warehouse_id: i32, driver: CreateDeliveryDriver) -> Result<DeliveryDriver> {
+        let driver = sqlx::query_as!(
+            DeliveryDriver,
+            "INSERT INTO warehouse.delivery_drivers (
+                 warehouse_id, driver_code, driver_name, phone, license_number, license_expires_on
+             ) VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING *",
+            warehouse_id,
+            driver.driver_code,
+            driver.driver_name,
+            driver.phone,
+            driver.license_number,
+            driver.license_expires_on
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(driver)
+    }
+
+    pub async fn update_driver(&self, id: i32, driver: UpdateDeliveryDriver) -> Result<Option<DeliveryDriver>> {
+        let driver = sqlx::query_as!(
+            DeliveryDriver,
+            "UPDATE warehouse.delivery_drivers SET
+                 driver_name = COALESCE($2, driver_name),
+                 phone = COALESCE($3, phone),
+                 license_number = COALESCE($4, license_number),
+                 license_expires_on = COALESCE($5, license_expires_on),
+                 is_active = COALESCE($6, is_active)
+             WHERE driver_id = $1
+             RETURNING *",
+            id,
+            driver.driver_name,
+            driver.phone,
+            driver.license_number,
+            driver.license_expires_on,
+            driver.is_active
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(driver)
+    }
+
+    /// Plan a run of an active vehicle and driver of the warehouse. Neither may be on
+    /// another run that day still under way, and the driver's licence must be valid on it.
+    pub async fn create_run(&self, warehouse_id: i32, run: CreateDeliveryRun) -> Result<DeliveryRunDetail> {
+        let mut tx = self.pool.begin().await?;
+
+        // Locking the vehicle and driver queues concurrent planning of runs for them
+        let vehicle = sqlx::query_as!(
+            DeliveryVehicle,
+            "SELECT * FROM warehouse.delivery_vehicles WHERE vehicle_id = $1 AND warehouse_id = $2 AND is_active
+             FOR UPDATE",
+            run.vehicle_id,
+            warehouse_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| {
+            violation(format!("vehicle {} is not an active vehicle of warehouse {}", run.vehicle_id, warehouse_id))
+        })?;
+        let driver = sqlx::query_as!(
+            DeliveryDriver,
+            "SELECT * FROM warehouse.delivery_drivers WHERE driver_id = $1 AND warehouse_id = $2 AND is_active
+             FOR UPDATE",
+            run.driver_id,
+            warehouse_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| {
+            violation(format!("driver {} is not an active driver of warehouse {}", run.driver_id, warehouse_id))
+        })?;
+        if let Some(expires_on) = driver.license_expires_on.filter(|expires_on| *expires_on < run.run_date) {
+            return Err(violation(format!(
+                "{}'s licence expires on {}, before the run on {}",
+                driver.driver_code, expires_on, run.run_date
+            )));
+        }
+
+        let busy = sqlx::query!(
+            "SELECT run_number, vehicle_id FROM warehouse.delivery_runs
+             WHERE run_date = $3 AND status <> 'COMPLETED' AND (vehicle_id = $1 OR driver_id = $2)
+             LIMIT 1",
+            vehicle.vehicle_id,
+            driver.driver_id,
+            run.run_date
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(busy) = busy {
+            let code = if busy.vehicle_id == vehicle.vehicle_id { &vehicle.vehicle_code } else { &driver.driver_code };
+            return Err(violation(format!("{} is already out on {} on {}", code, busy.run_number, run.run_date)));
+        }
+
+        let run_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.delivery_runs (run_id, run_number, warehouse_id, vehicle_id, driver_id, run_date)
+             SELECT next_id, 'RUN-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4
+             FROM (SELECT nextval('warehouse.delivery_runs_run_id_seq')::INT AS next_id) seq
+             RETURNING run_id",
+            warehouse_id,
+            vehicle.vehicle_id,
+            driver.driver_id,
+            run.run_date
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let detail = load_run(&mut tx, run_id, false).await?.expect("run inserted above");
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// A warehouse's runs, latest day first
+    pub async fn runs(&self, warehouse_id: i32, filter: DeliveryRunFilter) -> Result<Vec<DeliveryRun>> {
+        let runs = sqlx::query_as!(
+            DeliveryRun,
+            "SELECT * FROM warehouse.delivery_runs
+             WHERE warehouse_id = $1
+               AND ($2::DATE IS NULL OR run_date = $2)
+               AND ($3::VARCHAR IS NULL OR status = $3)
+             ORDER BY run_date DESC, run_id DESC",
+            warehouse_id,
+            filter.run_date,
+            filter.status
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(runs)
+    }
+
+    pub async fn get_run(&self, id: i32) -> Result<Option<DeliveryRunDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_run(&mut conn, id, false).await
+    }
+
+    /// Add shipments of the run's warehouse as its next stops. Shipments handed to a
+    /// carrier, already delivered or on a run are refused, as is more than the vehicle
+    /// carries.
+    pub async fn assign(&self, run_id: i32, assignment: AssignShipments) -> Result<Option<DeliveryRunDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_run(&mut tx, run_id, true).await? else {
+            return Ok(None);
+        };
+        let run = &detail.run;
+        if run.status != RUN_PLANNED {
+            return Err(violation(format!(
+                "cannot add stops to {}: it is {}",
+                run.run_number,
+                run.status.to_lowercase()
+            )));
+        }
+        let mut seen = HashSet::new();
+        if let Some(duplicate) = assignment.shipment_ids.iter().find(|id| !seen.insert(**id)) {
+            return Err(violation(format!("shipment {} is listed more than once", duplicate)));
+        }
+
+        let shipments = sqlx::query!(
+            r#"SELECT s.shipment_id, s.shipment_number, s.warehouse_id, s.carrier_code, s.status, s.delivered_at,
+                      st.run_id AS "run_id?"
+               FROM warehouse.shipments s
+               LEFT JOIN warehouse.delivery_run_stops st ON st.shipment_id = s.shipment_id
+               WHERE s.shipment_id = ANY($1)
+               ORDER BY s.shipment_id
+               FOR UPDATE OF s"#,
+            &assignment.shipment_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for id in &assignment.shipment_ids {
+            let shipment = shipments
+                .iter()
+                .find(|shipment| shipment.shipment_id == *id)
+                .ok_or_else(|| violation(format!("unknown shipment {}", id)))?;
+            let number = &shipment.shipment_number;
+            if shipment.warehouse_id != run.warehouse_id {
+                return Err(violation(format!(
+                    "{} ships from warehouse {}, not {}",
+                    number, shipment.warehouse_id, run.warehouse_id
+                )));
+            }
+            if let Some(carrier_code) = &shipment.carrier_code {
+                return Err(violation(format!("{} is carried by {}", number, carrier_code)));
+            }
+            if shipment.delivered_at.is_some() || shipment.status == TrackingStatus::ReturnedToSender.as_str() {
+                return Err(violation(format!("{} is {}", number, shipment.status.to_lowercase().replace('_', " "))));
+            }
+            if shipment.run_id.is_some() {
+                return Err(violation(format!("{} is already on a delivery run", number)));
+            }
+        }
+
+        let weights = shipment_weights(&mut tx, &assignment.shipment_ids).await?;
+        let weight_of = |id: i32| weights.get(&id).copied().unwrap_or_default();
+        let load_kg = detail.load_kg + assignment.shipment_ids.iter().map(|id| weight_of(*id)).sum::<Decimal>();
+        if load_kg > detail.capacity_kg {
+            return Err(violation(format!(
+                "{} would carry {} kg on {}, which takes {} kg",
+                run.run_number,
+                load_kg.normalize(),
+                detail.vehicle_code,
+                detail.capacity_kg.normalize()
+            )));
+        }
+
+        let first_stop = detail.stops.last().map_or(1, |stop| stop.stop_number + 1);
+        for (index, shipment_id) in assignment.shipment_ids.iter().enumerate() {
+            sqlx::query!(
+                "INSERT INTO warehouse.delivery_run_stops (run_id, stop_number, shipment_id, weight_kg)
+                 VALUES ($1, $2, $3, $4)",
+                run_id,
+                first_stop + index as i32,
+                shipment_id,
+                weight_of(*shipment_id)
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let detail = load_run(&mut tx, run_id, false).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Send a planned run out on its day or later, putting its shipments out for delivery
+    pub async fn dispatch(&self, run_id: i32) -> Result<Option<DeliveryRunDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_run(&mut tx, run_id, true).await? else {
+            return Ok(None);
+        };
+        let run = &detail.run;
+        if run.status != RUN_PLANNED {
+            return Err(violation(format!("cannot dispatch {}: it is {}", run.run_number, run.status.to_lowercase())));
+        }
+        if detail.stops.is_empty() {
+            return Err(violation(format!("cannot dispatch {}: it has no stops", run.run_number)));
+        }
+        let now = Utc::now();
+        if run.run_date > now.date_naive() {
+            return Err(violation(format!("cannot dispatch {} before {}", run.run_number, run.run_date)));
+        }
+
+        for stop in &detail.stops {
+            sqlx::query!(
+                "SELECT shipment_id FROM warehouse.shipments WHERE shipment_id = $1 FOR UPDATE",
+                stop.shipment_id
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            let event = NewTrackingEvent {
+                status: TrackingStatus::OutForDelivery,
+                description: Some(format!("Out for delivery on {} with {}", run.run_number, detail.vehicle_code)),
+                location: None,
+                occurred_at: now,
+            };
+            record_events(&mut tx, stop.shipment_id, vec![event], TRACKING_SOURCE_FLEET).await?;
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.delivery_runs SET status = $2, dispatched_at = $3 WHERE run_id = $1",
+            run_id,
+            RUN_DISPATCHED,
+            now
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let detail = load_run(&mut tx, run_id, false).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Shipments of the warehouse's own fleet still to go out on a run, oldest first
+    pub async fn routable(&self, warehouse_id: i32) -> Result<Vec<RoutableShipment>> {
+        let mut conn = self.pool.acquire().await?;
+        let shipments = sqlx::query!(
+            "SELECT s.shipment_id, s.shipment_number FROM warehouse.shipments s
+             WHERE s.warehouse_id = $1 AND s.carrier_code IS NULL AND s.delivered_at IS NULL AND s.status <> $2
+               AND NOT EXISTS (SELECT 1 FROM warehouse.delivery_run_stops st WHERE st.shipment_id = s.shipment_id)
+             ORDER BY s.shipped_at, s.shipment_id",
+            warehouse_id,
+            TrackingStatus::ReturnedToSender.as_str()
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let ids: Vec<i32> = shipments.iter().map(|shipment| shipment.shipment_id).collect();
+        let weights = shipment_weights(&mut conn, &ids).await?;
+        Ok(shipments
+            .into_iter()
+            .map(|shipment| RoutableShipment {
+                weight_kg: weights.get(&shipment.shipment_id).copied().unwrap_or_default(),
+                shipment_id: shipment.shipment_id,
+                shipment_number: shipment.shipment_number,
+            })
+            .collect())
+    }
+
+    /// Active vehicles of the warehouse not out on a run on `day`
+    pub async fn free_vehicles(&self, warehouse_id: i32, day: NaiveDate) -> Result<Vec<DeliveryVehicle>> {
+        let vehicles = sqlx::query_as!(
+            DeliveryVehicle,
+            "SELECT * FROM warehouse.delivery_vehicles v
+             WHERE v.warehouse_id = $1 AND v.is_active
+               AND NOT EXISTS (
+                   SELECT 1 FROM warehouse.delivery_runs r
+                   WHERE r.vehicle_id = v.vehicle_id AND r.run_date = $2 AND r.status <> 'COMPLETED'
+               )
+             ORDER BY v.vehicle_code",
+            warehouse_id,
+            day
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(vehicles)
+    }
+
+    /// Record who took a shipment at its stop, which delivers the shipment, and complete
+    /// the run once every stop has its proof. Returns None when there is no such shipment.
+    pub async fn record_proof(
+        &self,
+        shipment_id: i32,
+        proof: RecordProofOfDelivery,
+    ) -> Result<Option<ProofOfDelivery>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(shipment_number) =
+            sqlx::query_scalar!("SELECT shipment_number FROM warehouse.shipments WHERE shipment_id = $1", shipment_id)
+                .fetch_optional(&mut *tx)
+                .await?
+        else {
+            return Ok(None);
+        };
+        let run_id =
+            sqlx::query_scalar!("SELECT run_id FROM warehouse.delivery_run_stops WHERE shipment_id = $1", shipment_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or_else(|| violation(format!("{} is not on a delivery run", shipment_number)))?;
+
+        // The run before the shipment, as dispatching locks them
+        let run =
+            sqlx::query_as!(DeliveryRun, "SELECT * FROM warehouse.delivery_runs WHERE run_id = $1 FOR UPDATE", run_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        sqlx::query!("SELECT shipment_id FROM warehouse.shipments WHERE shipment_id = $1 FOR UPDATE", shipment_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if run.status != RUN_DISPATCHED {
+            return Err(violation(format!(
+                "cannot record delivery of {}: its run {} is {}",
+                shipment_number,
+                run.run_number,
+                run.status.to_lowercase()
+            )));
+        }
+        if load_proof(&mut tx, shipment_id).await?.is_some() {
+            return Err(violation(format!("{} already has a proof of delivery", shipment_number)));
+        }
+
+        let delivered_at = proof.delivered_at.unwrap_or_else(Utc::now);
+        sqlx::query!(
+            "INSERT INTO warehouse.proofs_of_delivery (shipment_id, run_id, received_by, delivered_at, notes)
+             VALUES ($1, $2, $3, $4, $5)",
+            shipment_id,
+            run_id,
+            proof.received_by,
+            delivered_at,
+            proof.notes
+        )
+        .execute(&mut *tx)
+        .await?;
+        let event = NewTrackingEvent {
+            status: TrackingStatus::Delivered,
+            description: Some(format!("Received by {}", proof.received_by)),
+            location: None,
+            occurred_at: delivered_at,
+        };
+        record_events(&mut tx, shipment_id, vec![event], TRACKING_SOURCE_FLEET).await?;
+
+        sqlx::query!(
+            "UPDATE warehouse.delivery_runs SET status = $2, completed_at = NOW()
+             WHERE run_id = $1 AND NOT EXISTS (
+                 SELECT 1 FROM warehouse.delivery_run_stops st
+                 WHERE st.run_id = $1
+                   AND NOT EXISTS (SELECT 1 FROM warehouse.proofs_of_delivery p WHERE p.shipment_id = st.shipment_id)
+             )",
+            run_id,
+            RUN_COMPLETED
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let proof = load_proof(&mut tx, shipment_id).await?;
+        tx.commit().await?;
+        Ok(proof)
+    }
+
+    pub async fn proof(&self, shipment_id: i32) -> Result<Option<ProofOfDelivery>> {
+        let mut conn = self.pool.acquire().await?;
+        load_proof(&mut conn, shipment_id).await
+    }
+
+    /// Keep a signature or photo with a shipment's proof of delivery, replacing one of the
+    /// kind uploaded before. Returns None when the shipment has no proof of delivery.
+    pub async fn attach_file(
+        &self,
+        shipment_id: i32,
+        file_kind: &str,
+        content_type: &str,
+        content: &[u8],
+    ) -> Result<Option<ProofOfDeliveryFile>> {
+        let file = sqlx::query_as!(
+            ProofOfDeliveryFile,
+            "INSERT INTO warehouse.proof_of_delivery_files (shipment_id, file_kind, content_type, content, size_bytes)
+             SELECT shipment_id, $2, $3, $4, $5 FROM warehouse.proofs_of_delivery WHERE shipment_id = $1
+             ON CONFLICT (shipment_id, file_kind) DO UPDATE SET
+                 content_type = EXCLUDED.content_type,
+                 content = EXCLUDED.content,
+                 size_bytes = EXCLUDED.size_bytes,
+                 uploaded_at = NOW()
+             RETURNING shipment_id, file_kind, content_type, size_bytes, uploaded_at",
+            shipment_id,
+            file_kind,
+            content_type,
+            content,
+            content.len() as i32
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(file)
+    }
+
+    /// A proof of delivery's signature or photo and its content
+    pub async fn file(&self, shipment_id: i32, file_kind: &str) -> Result<Option<(ProofOfDeliveryFile, Vec<u8>)>> {
+        let row = sqlx::query!(
+            "SELECT shipment_id, file_kind, content_type, size_bytes, uploaded_at, content
+             FROM warehouse.proof_of_delivery_files
+             WHERE shipment_id = $1 AND file_kind = $2",
+            shipment_id,
+            file_kind
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let file = ProofOfDeliveryFile {
+                shipment_id: row.shipment_id,
+                file_kind: row.file_kind,
+                content_type: row.content_type,
+                size_bytes: row.size_bytes,
+                uploaded_at: row.uploaded_at,
+            };
+            (file, row.content)
+        }))
+    }
+}
+
+async fn load_run(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<DeliveryRunDetail>> {
+    let run = if lock {
+        sqlx::query_as!(DeliveryRun, "SELECT * FROM warehouse.delivery_runs WHERE run_id = $1 FOR UPDATE", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    } else {
+        sqlx::query_as!(DeliveryRun, "SELECT * FROM warehouse.delivery_runs WHERE run_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    };
+    let Some(run) = run else {
+        return Ok(None);
+    };
+
+    let crew = sqlx::query!(
+        "SELECT v.vehicle_code, v.capacity_kg, d.driver_name
+         FROM warehouse.delivery_vehicles v, warehouse.delivery_drivers d
+         WHERE v.vehicle_id = $1 AND d.driver_id = $2",
+        run.vehicle_id,
+        run.driver_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let stops = sqlx::query_as!(
+        DeliveryRunStop,
+        "SELECT st.stop_number, st.shipment_id, s.shipment_number, s.order_id, o.ship_to_name, o.ship_to_address,
+                o.ship_to_city, st.weight_kg, s.status, s.delivered_at
+         FROM warehouse.delivery_run_stops st
+         JOIN warehouse.shipments s ON s.shipment_id = st.shipment_id
+         JOIN warehouse.outbound_orders o ON o.order_id = s.order_id
+         WHERE st.run_id = $1
+         ORDER BY st.stop_number",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(DeliveryRunDetail {
+        run,
+        vehicle_code: crew.vehicle_code,
+        capacity_kg: crew.capacity_kg,
+        driver_name: crew.driver_name,
+        load_kg: stops.iter().map(|stop| stop.weight_kg).sum(),
+        stops,
+    }))
+}
+
+/// What each shipment weighs: its lines' catch weight, or their items' unit weight
+async fn shipment_weights(conn: &mut PgConnection, shipment_ids: &[i32]) -> Result<HashMap<i32, Decimal>> {
+    let weights = sqlx::query!(
+        r#"SELECT sl.shipment_id, SUM(COALESCE(sl.weight_kg, sl.quantity * i.weight_kg, 0)) AS "weight_kg!"
+           FROM warehouse.shipment_lines sl
+           JOIN warehouse.items i ON i.item_id = sl.item_id
+           WHERE sl.shipment_id = ANY($1)
+           GROUP BY sl.shipment_id"#,
+        shipment_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(weights.into_iter().map(|row| (row.shipment_id, row.weight_kg.round_dp(3))).collect())
+}
+
+async fn load_proof(conn: &mut PgConnection, shipment_id: i32) -> Result<Option<ProofOfDelivery>> {
+    let Some(proof) = sqlx::query!("SELECT * FROM warehouse.proofs_of_delivery WHERE shipment_id = $1", shipment_id)
+        .fetch_optional(&mut *conn)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let files = sqlx::query_as!(
+        ProofOfDeliveryFile,
+        "SELECT shipment_id, file_kind, content_type, size_bytes, uploaded_at
+         FROM warehouse.proof_of_delivery_files
+         WHERE shipment_id = $1
+         ORDER BY file_kind",
+        shipment_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(ProofOfDelivery {
+        shipment_id,
+        run_id: proof.run_id,
+        received_by: proof.received_by,
+        delivered_at: proof.delivered_at,
+        notes: proof.notes,
+        recorded_at: proof.recorded_at,
+        files,
+    }))
+}
@@ -0,0 +1,174 @@
+use crate::error::violation;
+use crate::utils::*;
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::chrono::{NaiveDate, Utc};
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct GlRepository {
+    pool: PgPool,
+}
+
+impl GlRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn accounts(&self) -> Result<Vec<GlAccount>> {
+        let accounts = sqlx::query_as!(GlAccount, "SELECT * FROM warehouse.gl_accounts ORDER BY account_code")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(accounts)
+    }
+
+    /// Returns None for an unknown category
+    pub async fn update_account(&self, category: &str, account: UpdateGlAccount) -> Result<Option<GlAccount>> {
+        let updated = sqlx::query_as!(
+            GlAccount,
+            "UPDATE warehouse.gl_accounts SET account_code = $2, account_name = $3, updated_at = NOW()
+             WHERE category = $1
+             RETURNING *",
+            category,
+            account.account_code,
+            account.account_name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(updated)
+    }
+
+    /// Periods that have been closed at some point, newest first
+    pub async fn periods(&self) -> Result<Vec<GlPeriod>> {
+        let periods = sqlx::query_as!(GlPeriod, "SELECT * FROM warehouse.gl_periods ORDER BY period DESC")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(periods)
+    }
+
+    /// The period starting at `period`; open unless it was closed
+    pub async fn period(&self, period: NaiveDate) -> Result<GlPeriod> {
+        let found = sqlx::query_as!(GlPeriod, "SELECT * FROM warehouse.gl_periods WHERE period = $1", period)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(found.unwrap_or_else(|| GlPeriod {
+            period,
+            status: GL_PERIOD_OPEN.to_string(),
+            closed_at: None,
+            updated_at: Utc::now(),
+        }))
+    }
+
+    /// Value moved per warehouse and movement type in the period, for our own stock only:
+    /// consigned and client-owned goods are not on our books. Movements are valued at their
+    /// unit cost, or the item's standard cost when they have none.
+    pub async fn movement_totals(&self, period: NaiveDate) -> Result<Vec<GlMovementTotal>> {
+        let totals = sqlx::query_as!(
+            GlMovementTotal,
+            r#"SELECT w.warehouse_code, m.movement_type,
+                      COALESCE(SUM(m.quantity * COALESCE(m.unit_cost, i.standard_cost)), 0) AS "value!",
+                      COUNT(*) AS "movements!",
+                      COUNT(*) FILTER (WHERE COALESCE(m.unit_cost, i.standard_cost) IS NULL) AS "uncosted_movements!"
+               FROM warehouse.stock_movements m
+               JOIN warehouse.items i ON i.item_id = m.item_id
+               JOIN warehouse.warehouses w ON w.warehouse_id = m.warehouse_id
+               WHERE m.consignor_id IS NULL AND m.client_id IS NULL
+                 AND m.movement_date >= $1::TIMESTAMP AT TIME ZONE 'UTC'
+                 AND m.movement_date < $2::TIMESTAMP AT TIME ZONE 'UTC'
+               GROUP BY w.warehouse_code, m.movement_type
+               ORDER BY w.warehouse_code, m.movement_type"#,
+            period,
+            gl_period_end(period)
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(totals)
+    }
+
+    /// Lock the period's movements. Only a period that has ended can be closed.
+    pub async fn close(&self, period: NaiveDate) -> Result<GlPeriod> {
+        if gl_period_end(period) > Utc::now().date_naive() {
+            return Err(violation(format!("period {} has not ended", period.format("%Y-%m"))));
+        }
+
+        let closed = sqlx::query_as!(
+            GlPeriod,
+            "INSERT INTO warehouse.gl_periods (period, status, closed_at) VALUES ($1, $2, NOW())
+             ON CONFLICT (period) DO UPDATE
+             SET status = EXCLUDED.status, closed_at = EXCLUDED.closed_at, updated_at = NOW()
+             RETURNING *",
+            period,
+            GL_PERIOD_CLOSED
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(closed)
+    }
+
+    /// Unlock a closed period so its movements can be corrected
+    pub async fn reopen(&self, period: NaiveDate) -> Result<GlPeriod> {
+        let reopened = sqlx::query_as!(
+            GlPeriod,
+            "INSERT INTO warehouse.gl_periods (period, status) VALUES ($1, $2)
+             ON CONFLICT (period) DO UPDATE SET status = EXCLUDED.status, closed_at = NULL, updated_at = NOW()
+             RETURNING *",
+            period,
+            GL_PERIOD_OPEN
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(reopened)
+    }
+
+    pub async fn record_export(
+        &self,
+        journal: &GlJournal,
+        destination: &str,
+        error: Option<String>,
+    ) -> Result<GlExport> {
+        let status = if error.is_none() { GL_EXPORT_SENT } else { GL_EXPORT_FAILED };
+
+        let export = sqlx::query_as!(
+            GlExport,
+            "INSERT INTO warehouse.gl_exports (period, destination, status, lines, total_debit, error)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING *",
+            journal.period,
+            destination,
+            status,
+            journal.lines.len() as i32,
+            journal.total_debit,
+            error
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(export)
+    }
+
+    /// Exports newest first, paged by `export_id`
+    pub async fn exports(&self, keyset: KeysetQuery) -> Result<KeysetPage<GlExport>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            GlExport,
+            "SELECT * FROM warehouse.gl_exports
+             WHERE ($1::BIGINT IS NULL OR export_id < $1)
+             ORDER BY export_id DESC
+             LIMIT $2",
+            after_id,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |export| export.export_id as i64))
+    }
+}
@@ -0,0 +1,234 @@
+use crate::error::violation;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct HazmatRepository {
+    pool: PgPool,
+}
+
+impl HazmatRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Classify an item as a dangerous good, or clear its classification with None. The
+    /// new class must still be allowed in every zone the item is stored in. Returns false
+    /// when the item does not exist.
+    pub async fn classify_item(&self, item_id: i32, classification: Option<HazmatClassification>) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let exists = sqlx::query_scalar!(
+            "SELECT item_id FROM warehouse.items WHERE item_id = $1 AND status = 'ACTIVE' FOR UPDATE",
+            item_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .is_some();
+        if !exists {
+            return Ok(false);
+        }
+
+        if let Some(classification) = &classification {
+            let zone_ids = sqlx::query_scalar!(
+                "SELECT l.zone_id FROM warehouse.locations l WHERE l.item_id = $1 AND l.is_active
+                 UNION
+                 SELECT l.zone_id FROM warehouse.tasks t
+                 JOIN warehouse.locations l ON l.location_id = t.to_location_id
+                 WHERE t.item_id = $1 AND t.task_type = $2 AND t.status NOT IN ($3, $4)",
+                item_id,
+                TASK_PUTAWAY,
+                TASK_COMPLETED,
+                TASK_CANCELLED
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+
+            for zone_id in zone_ids.into_iter().flatten() {
+                check_zone(&mut tx, zone_id, item_id, &classification.hazard_class).await?;
+            }
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.items SET
+                 un_number = $2, hazard_class = $3, packing_group = $4, proper_shipping_name = $5,
+                 updated_at = NOW()
+             WHERE item_id = $1",
+            item_id,
+            classification.as_ref().map(|c| c.un_number.clone()),
+            classification.as_ref().map(|c| c.hazard_class.clone()),
+            classification.as_ref().and_then(|c| c.packing_group.clone()),
+            classification.as_ref().map(|c| c.proper_shipping_name.clone())
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Replace the hazard classes a zone is approved for. Dangerous goods already stored
+    /// there must stay covered. Returns None when the zone does not exist.
+    pub async fn set_zone_classes(&self, zone_id: i32, hazmat: ZoneHazmat) -> Result<Option<Zone>> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(zone) = lock_zone(&mut tx, zone_id).await? else {
+            return Ok(None);
+        };
+
+        for (item_code, class) in zone_contents(&mut tx, zone_id, None).await? {
+            if !hazmat.hazard_classes.iter().any(|approved| hazard_class_matches(approved, &class)) {
+                return Err(violation(format!(
+                    "zone {} holds {} (class {}), which the new approval would not cover",
+                    zone.zone_code, item_code, class
+                )));
+            }
+        }
+
+        let zone = sqlx::query_as!(
+            Zone,
+            "UPDATE warehouse.zones SET hazard_classes = $2 WHERE zone_id = $1 RETURNING *",
+            zone_id,
+            &hazmat.hazard_classes
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(zone))
+    }
+
+    /// Dangerous goods declaration for what a shipment carried. Returns None when the
+    /// shipment does not exist.
+    pub async fn declaration(&self, shipment_id: i32) -> Result<Option<HazmatDeclaration>> {
+        let Some(shipment) =
+            sqlx::query_as!(Shipment, "SELECT * FROM warehouse.shipments WHERE shipment_id = $1", shipment_id)
+                .fetch_optional(&self.pool)
+                .await?
+        else {
+            return Ok(None);
+        };
+
+        let lines = sqlx::query!(
+            r#"SELECT i.item_id, i.item_code, i.un_number AS "un_number!", i.hazard_class AS "hazard_class!",
+                      i.packing_group, i.proper_shipping_name, i.unit, i.weight_kg,
+                      SUM(sl.quantity) AS "quantity!"
+               FROM warehouse.shipment_lines sl
+               JOIN warehouse.items i ON i.item_id = sl.item_id
+               WHERE sl.shipment_id = $1 AND i.un_number IS NOT NULL
+               GROUP BY i.item_id
+               ORDER BY i.hazard_class, i.un_number"#,
+            shipment_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let lines: Vec<HazmatLine> = lines
+            .into_iter()
+            .map(|row| HazmatLine {
+                item_id: row.item_id,
+                item_code: row.item_code,
+                un_number: row.un_number,
+                proper_shipping_name: row.proper_shipping_name.unwrap_or_default(),
+                hazard_class: row.hazard_class,
+                packing_group: row.packing_group,
+                quantity: row.quantity,
+                unit: row.unit,
+                weight_kg: row.weight_kg.map(|weight| weight * row.quantity),
+            })
+            .collect();
+        let total_weight_kg = lines.iter().map(|line| line.weight_kg).sum::<Option<Decimal>>();
+
+        Ok(Some(HazmatDeclaration {
+            shipment_id: shipment.shipment_id,
+            shipment_number: shipment.shipment_number,
+            carrier_code: shipment.carrier_code,
+            tracking_number: shipment.tracking_number,
+            lines,
+            total_weight_kg,
+        }))
+    }
+}
+
+/// Reject storing an item in a zone that is not approved for its hazard class or that
+/// holds dangerous goods it must be segregated from. Items without a classification can
+/// go anywhere. Takes a lock on the zone so concurrent putaways are checked one at a time.
+pub(crate) async fn ensure_storable(conn: &mut PgConnection, item_id: i32, zone_id: i32) -> Result<()> {
+    let class = sqlx::query_scalar!("SELECT hazard_class FROM warehouse.items WHERE item_id = $1", item_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .flatten();
+
+    match class {
+        Some(class) => check_zone(conn, zone_id, item_id, &class).await,
+        None => Ok(()),
+    }
+}
+
+async fn check_zone(conn: &mut PgConnection, zone_id: i32, item_id: i32, class: &str) -> Result<()> {
+    let Some(zone) = lock_zone(conn, zone_id).await? else {
+        return Err(violation(format!("zone {} does not exist", zone_id)));
+    };
+
+    if !zone.hazard_classes.iter().any(|approved| hazard_class_matches(approved, class)) {
+        return Err(violation(format!("zone {} is not approved for hazard class {}", zone.zone_code, class)));
+    }
+
+    for (item_code, stored) in zone_contents(conn, zone_id, Some(item_id)).await? {
+        if hazard_classes_segregated(class, &stored) {
+            return Err(violation(format!(
+                "hazard class {} must be segregated from {} (class {}) in zone {}",
+                class, item_code, stored, zone.zone_code
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+async fn lock_zone(conn: &mut PgConnection, zone_id: i32) -> Result<Option<Zone>> {
+    let zone = sqlx::query_as!(Zone, "SELECT * FROM warehouse.zones WHERE zone_id = $1 FOR UPDATE", zone_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    Ok(zone)
+}
+
+/// Dangerous goods in a zone as (item code, hazard class): items whose pick face is there
+/// and items on putaway tasks still heading there
+async fn zone_contents(
+    conn: &mut PgConnection,
+    zone_id: i32,
+    except_item_id: Option<i32>,
+) -> Result<Vec<(String, String)>> {
+    let rows = sqlx::query!(
+        r#"SELECT i.item_code, i.hazard_class AS "hazard_class!"
+           FROM warehouse.items i
+           WHERE i.hazard_class IS NOT NULL
+             AND ($2::INT IS NULL OR i.item_id <> $2)
+             AND (
+                 EXISTS (
+                     SELECT 1 FROM warehouse.locations l
+                     WHERE l.zone_id = $1 AND l.is_active AND l.item_id = i.item_id
+                 )
+                 OR EXISTS (
+                     SELECT 1 FROM warehouse.tasks t
+                     JOIN warehouse.locations l ON l.location_id = t.to_location_id
+                     WHERE l.zone_id = $1 AND t.item_id = i.item_id
+                       AND t.task_type = $3 AND t.status NOT IN ($4, $5)
+                 )
+             )
+           ORDER BY i.item_code"#,
+        zone_id,
+        except_item_id,
+        TASK_PUTAWAY,
+        TASK_COMPLETED,
+        TASK_CANCELLED
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| (row.item_code, row.hazard_class)).collect())
+}
@@ -0,0 +1,226 @@
+use crate::error::violation;
+use crate::repositories::movements::lock_stock;
+use crate::repositories::orders::{insert_order, load_order};
+use crate::repositories::stock_outs::record_stock_out;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct IntegrationRepository {
+    pool: PgPool,
+}
+
+impl IntegrationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Channels by code
+    pub async fn list_channels(&self) -> Result<Vec<IntegrationChannel>> {
+        let channels =
+            sqlx::query_as!(IntegrationChannel, "SELECT * FROM warehouse.integration_channels ORDER BY channel_code")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(channels)
+    }
+
+    pub async fn get_channel(&self, id: i32) -> Result<Option<IntegrationChannel>> {
+        let channel =
+            sqlx::query_as!(IntegrationChannel, "SELECT * FROM warehouse.integration_channels WHERE channel_id = $1", id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(channel)
+    }
+
+    pub async fn get_channel_by_code(&self, code: &str) -> Result<Option<IntegrationChannel>> {
+        let channel = sqlx::query_as!(
+            IntegrationChannel,
+            "SELECT * FROM warehouse.integration_channels WHERE channel_code = $1",
+            code
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(channel)
+    }
+
+    pub async fn create_channel(&self, channel: CreateIntegrationChannel) -> Result<IntegrationChannel> {
+        let created = sqlx::query_as!(
+            IntegrationChannel,
+            "INSERT INTO warehouse.integration_channels (channel_code, channel_name, fulfillment_warehouse_id)
+             VALUES ($1, $2, $3)
+             RETURNING *",
+            channel.channel_code,
+            channel.channel_name,
+            channel.fulfillment_warehouse_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(created)
+    }
+
+    /// Returns None when the channel does not exist
+    pub async fn update_channel(
+        &self,
+        id: i32,
+        changes: UpdateIntegrationChannel,
+    ) -> Result<Option<IntegrationChannel>> {
+        let updated = sqlx::query_as!(
+            IntegrationChannel,
+            "UPDATE warehouse.integration_channels SET
+                 channel_name = COALESCE($2, channel_name),
+                 fulfillment_warehouse_id = COALESCE($3, fulfillment_warehouse_id),
+                 is_active = COALESCE($4, is_active),
+                 updated_at = NOW()
+             WHERE channel_id = $1
+             RETURNING *",
+            id,
+            changes.channel_name,
+            changes.fulfillment_warehouse_id,
+            changes.is_active
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(updated)
+    }
+
+    /// Create the channel's order in its fulfillment warehouse and allocate what stock is
+    /// free to its lines. An external order taken in before, by this post or a concurrent
+    /// one, is returned as it is with `true`; an inactive channel takes in no new orders.
+    pub async fn intake(
+        &self,
+        channel: &IntegrationChannel,
+        external_order_id: &str,
+        order: CreateOrder,
+    ) -> Result<(OrderDetail, bool)> {
+        if let Some(detail) = self.taken_in(channel.channel_id, external_order_id).await? {
+            return Ok((detail, true));
+        }
+        if !channel.is_active {
+            return Err(violation(format!("channel {} is not active", channel.channel_code)));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let order_id = insert_order(&mut tx, order, SOURCE_CHANNEL, None).await?;
+
+        // A concurrent post of the same order waits here for the first to commit
+        let linked = sqlx::query_scalar!(
+            "INSERT INTO warehouse.integration_orders (channel_id, external_order_id, order_id) VALUES ($1, $2, $3)
+             ON CONFLICT (channel_id, external_order_id) DO NOTHING
+             RETURNING order_id",
+            channel.channel_id,
+            external_order_id,
+            order_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if linked.is_none() {
+            tx.rollback().await?;
+            let detail = self
+                .taken_in(channel.channel_id, external_order_id)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("order {} of {} vanished", external_order_id, channel.channel_code))?;
+            return Ok((detail, true));
+        }
+
+        allocate(&mut tx, channel.fulfillment_warehouse_id, order_id).await?;
+
+        let detail = load_order(&mut tx, order_id, false)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("order {} vanished after insert", order_id))?;
+        tx.commit().await?;
+        Ok((detail, false))
+    }
+
+    /// Our order for a channel order taken in earlier
+    async fn taken_in(&self, channel_id: i32, external_order_id: &str) -> Result<Option<OrderDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        let order_id = sqlx::query_scalar!(
+            "SELECT order_id FROM warehouse.integration_orders WHERE channel_id = $1 AND external_order_id = $2",
+            channel_id,
+            external_order_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        match order_id {
+            Some(order_id) => load_order(&mut conn, order_id, false).await,
+            None => Ok(None),
+        }
+    }
+}
+
+/// Allocate each line of the order what the warehouse has available and other open
+/// orders have not allocated and shipped yet. The stock rows stay locked until commit so
+/// concurrent intakes cannot allocate the same units. A line that finds nothing free is
+/// recorded as a stock-out incident.
+async fn allocate(conn: &mut PgConnection, warehouse_id: i32, order_id: i32) -> Result<()> {
+    let lines = sqlx::query!(
+        "SELECT l.line_id, l.item_id, l.quantity_ordered, o.order_number, o.customer_name
+         FROM warehouse.outbound_order_lines l
+         JOIN warehouse.outbound_orders o ON o.order_id = l.order_id
+         WHERE l.order_id = $1
+         ORDER BY l.line_number",
+        order_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut item_ids: Vec<i32> = lines.iter().map(|line| line.item_id).collect();
+    item_ids.sort_unstable();
+    item_ids.dedup();
+    lock_stock(conn, warehouse_id, &item_ids).await?;
+
+    for line in lines {
+        let free = sqlx::query_scalar!(
+            r#"SELECT GREATEST(
+                   COALESCE((SELECT SUM(quantity_available) FROM warehouse.stock_inventory
+                             WHERE warehouse_id = $1 AND item_id = $2), 0)
+                   - COALESCE((SELECT SUM(GREATEST(l.quantity_allocated - l.quantity_shipped, 0))
+                               FROM warehouse.outbound_order_lines l
+                               JOIN warehouse.outbound_orders o ON o.order_id = l.order_id
+                               WHERE l.item_id = $2 AND o.warehouse_id = $1 AND o.status IN ($3, $4)
+                                 AND l.line_id <> $5), 0),
+                   0) AS "free!""#,
+            warehouse_id,
+            line.item_id,
+            ORDER_OPEN,
+            ORDER_PARTIALLY_SHIPPED,
+            line.line_id
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        if free <= Decimal::ZERO {
+            record_stock_out(
+                &mut *conn,
+                NewStockOut {
+                    warehouse_id,
+                    item_id: line.item_id,
+                    quantity_demanded: line.quantity_ordered,
+                    requested_by: line.customer_name,
+                    reference_type: STOCK_OUT_ORDER,
+                    reference_number: Some(line.order_number),
+                },
+            )
+            .await?;
+            continue;
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.outbound_order_lines SET quantity_allocated = $2 WHERE line_id = $1",
+            line.line_id,
+            free.min(line.quantity_ordered)
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
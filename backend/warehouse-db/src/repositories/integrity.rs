@@ -0,0 +1,158 @@
+use anyhow::Result;
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use warehouse_models::*;
+
+use crate::utils::validate_keyset;
+
+#[derive(Clone)]
+pub struct IntegrityRepository {
+    pool: PgPool,
+}
+
+impl IntegrityRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Check every stock balance and record what breaks. The checks read one snapshot, so
+    /// stock moving while they run cannot show up as a mismatch.
+    pub async fn check(&self) -> Result<IntegrityReport> {
+        let mut tx = self.pool.begin().await?;
+        tx.execute("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ").await?;
+
+        let run_id = sqlx::query_scalar!("INSERT INTO warehouse.integrity_runs DEFAULT VALUES RETURNING run_id")
+            .fetch_one(&mut *tx)
+            .await?;
+
+        sqlx::query!(
+            "INSERT INTO warehouse.integrity_findings
+                 (run_id, check_name, stock_id, item_id, warehouse_id, consignor_id, expected, actual, detail)
+             SELECT $1, $2, stock_id, item_id, warehouse_id, consignor_id,
+                    quantity_on_hand - quantity_reserved, quantity_available,
+                    'quantity_available is ' || quantity_available || ', on hand ' || quantity_on_hand
+                        || ' less reserved ' || quantity_reserved || ' is ' || (quantity_on_hand - quantity_reserved)
+             FROM warehouse.stock_inventory
+             WHERE quantity_available IS DISTINCT FROM quantity_on_hand - quantity_reserved",
+            run_id,
+            CHECK_AVAILABLE_MISMATCH
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "WITH movements AS (
+                 SELECT item_id, warehouse_id, consignor_id, SUM(quantity) AS quantity, COUNT(*) AS movements
+                 FROM warehouse.stock_movements
+                 GROUP BY item_id, warehouse_id, consignor_id
+             )
+             INSERT INTO warehouse.integrity_findings
+                 (run_id, check_name, stock_id, item_id, warehouse_id, consignor_id, expected, actual, detail)
+             SELECT $1, $2, s.stock_id, COALESCE(s.item_id, m.item_id), COALESCE(s.warehouse_id, m.warehouse_id),
+                    COALESCE(s.consignor_id, m.consignor_id), COALESCE(m.quantity, 0), COALESCE(s.quantity_on_hand, 0),
+                    CASE WHEN s.stock_id IS NULL
+                         THEN COALESCE(m.movements, 0) || ' movements net ' || m.quantity || ' but there is no stock row'
+                         ELSE 'on hand ' || s.quantity_on_hand || ' but ' || COALESCE(m.movements, 0)
+                              || ' movements net ' || COALESCE(m.quantity, 0)
+                    END
+             FROM warehouse.stock_inventory s
+             FULL JOIN movements m ON m.item_id = s.item_id AND m.warehouse_id = s.warehouse_id
+                                  AND m.consignor_id IS NOT DISTINCT FROM s.consignor_id
+             WHERE COALESCE(m.quantity, 0) <> COALESCE(s.quantity_on_hand, 0)",
+            run_id,
+            CHECK_MOVEMENT_MISMATCH
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "INSERT INTO warehouse.integrity_findings
+                 (run_id, check_name, stock_id, item_id, warehouse_id, consignor_id, actual, detail)
+             SELECT $1, $2, s.stock_id, s.item_id, s.warehouse_id, s.consignor_id, s.quantity_on_hand,
+                    CASE WHEN i.item_id IS NULL THEN 'item ' || s.item_id ELSE 'warehouse ' || s.warehouse_id END
+                        || ' does not exist'
+             FROM warehouse.stock_inventory s
+             LEFT JOIN warehouse.items i ON i.item_id = s.item_id
+             LEFT JOIN warehouse.warehouses w ON w.warehouse_id = s.warehouse_id
+             WHERE i.item_id IS NULL OR w.warehouse_id IS NULL",
+            run_id,
+            CHECK_ORPHANED_STOCK
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let run = sqlx::query_as!(
+            IntegrityRun,
+            "UPDATE warehouse.integrity_runs
+             SET finished_at = NOW(),
+                 findings = (SELECT COUNT(*) FROM warehouse.integrity_findings WHERE run_id = $1)
+             WHERE run_id = $1
+             RETURNING *",
+            run_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let failed_checks = counts(&mut tx, run_id).await?;
+        tx.commit().await?;
+
+        Ok(IntegrityReport { run, failed_checks })
+    }
+
+    pub async fn runs(&self, keyset: KeysetQuery) -> Result<KeysetPage<IntegrityRun>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+        let runs = sqlx::query_as!(
+            IntegrityRun,
+            "SELECT * FROM warehouse.integrity_runs
+             WHERE ($1::BIGINT IS NULL OR run_id < $1)
+             ORDER BY run_id DESC
+             LIMIT $2",
+            after_id,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(runs, limit, |run| run.run_id as i64))
+    }
+
+    /// A run with its findings, optionally narrowed to one check or warehouse
+    pub async fn get_run(&self, id: i32, filter: IntegrityFindingFilter) -> Result<Option<IntegrityRunDetail>> {
+        let Some(run) = sqlx::query_as!(IntegrityRun, "SELECT * FROM warehouse.integrity_runs WHERE run_id = $1", id)
+            .fetch_optional(&self.pool)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let findings = sqlx::query_as!(
+            IntegrityFinding,
+            "SELECT * FROM warehouse.integrity_findings
+             WHERE run_id = $1
+               AND ($2::VARCHAR IS NULL OR check_name = $2)
+               AND ($3::INTEGER IS NULL OR warehouse_id = $3)
+             ORDER BY finding_id",
+            id,
+            filter.check_name,
+            filter.warehouse_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(Some(IntegrityRunDetail { run, findings }))
+    }
+}
+
+async fn counts(tx: &mut Transaction<'_, Postgres>, run_id: i32) -> Result<Vec<IntegrityCheckCount>> {
+    let counts = sqlx::query_as!(
+        IntegrityCheckCount,
+        r#"SELECT check_name, COUNT(*) AS "findings!"
+           FROM warehouse.integrity_findings
+           WHERE run_id = $1
+           GROUP BY check_name
+           ORDER BY check_name"#,
+        run_id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    Ok(counts)
+}
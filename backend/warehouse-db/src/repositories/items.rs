@@ -1,6 +1,14 @@
 use anyhow::Result;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use futures::TryStreamExt;
 use sqlx::PgPool;
+use warehouse_models::chrono::{DateTime, Utc};
+use warehouse_models::rust_decimal::Decimal;
 use warehouse_models::*;
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::store::*;
 use crate::utils::*;
 
 #[derive(Clone)]
@@ -12,22 +20,130 @@ impl ItemRepository {
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
     }
+}
+
+/// An item as stored, its costs in separate amount and currency columns
+struct ItemRow {
+    item_id: i32,
+    item_code: String,
+    item_name: String,
+    item_description: Option<String>,
+    item_type: String,
+    item_usage_type: Option<String>,
+    category: Option<String>,
+    subcategory: Option<String>,
+    brand: Option<String>,
+    model: Option<String>,
+    unit: Option<String>,
+    weight_kg: Option<Decimal>,
+    length_cm: Option<Decimal>,
+    width_cm: Option<Decimal>,
+    height_cm: Option<Decimal>,
+    volume_cbm: Option<Decimal>,
+    is_loanable: bool,
+    requires_return: bool,
+    max_loan_duration_days: Option<i32>,
+    replacement_cost: Option<Decimal>,
+    maintenance_required: bool,
+    calibration_required: bool,
+    standard_cost: Option<Decimal>,
+    last_cost: Option<Decimal>,
+    average_cost: Option<Decimal>,
+    cost_currency: String,
+    un_number: Option<String>,
+    hazard_class: Option<String>,
+    packing_group: Option<String>,
+    proper_shipping_name: Option<String>,
+    catch_weight_nominal_kg: Option<Decimal>,
+    catch_weight_tolerance_pct: Option<Decimal>,
+    hs_code: Option<String>,
+    country_of_origin: Option<String>,
+    customs_description: Option<String>,
+    status: String,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+    created_by: Option<i32>,
+    updated_by: Option<i32>,
+}
 
-    pub async fn list(&self, pagination: PaginationQuery) -> Result<PaginatedResponse<Item>> {
+impl From<ItemRow> for Item {
+    fn from(row: ItemRow) -> Self {
+        Item {
+            item_id: row.item_id,
+            item_code: row.item_code,
+            item_name: row.item_name,
+            item_description: row.item_description,
+            item_type: row.item_type,
+            item_usage_type: row.item_usage_type,
+            category: row.category,
+            subcategory: row.subcategory,
+            brand: row.brand,
+            model: row.model,
+            unit: row.unit,
+            weight_kg: row.weight_kg,
+            length_cm: row.length_cm,
+            width_cm: row.width_cm,
+            height_cm: row.height_cm,
+            volume_cbm: row.volume_cbm,
+            is_loanable: row.is_loanable,
+            requires_return: row.requires_return,
+            max_loan_duration_days: row.max_loan_duration_days,
+            replacement_cost: Money::from_column(row.replacement_cost, &row.cost_currency),
+            maintenance_required: row.maintenance_required,
+            calibration_required: row.calibration_required,
+            standard_cost: Money::from_column(row.standard_cost, &row.cost_currency),
+            last_cost: Money::from_column(row.last_cost, &row.cost_currency),
+            average_cost: Money::from_column(row.average_cost, &row.cost_currency),
+            un_number: row.un_number,
+            hazard_class: row.hazard_class,
+            packing_group: row.packing_group,
+            proper_shipping_name: row.proper_shipping_name,
+            catch_weight_nominal_kg: row.catch_weight_nominal_kg,
+            catch_weight_tolerance_pct: row.catch_weight_tolerance_pct,
+            hs_code: row.hs_code,
+            country_of_origin: row.country_of_origin,
+            customs_description: row.customs_description,
+            status: row.status,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            created_by: row.created_by,
+            updated_by: row.updated_by,
+        }
+    }
+}
+
+#[async_trait]
+impl ItemStore for ItemRepository {
+    async fn list(&self, pagination: PaginationQuery) -> Result<PaginatedResponse<Item>> {
         let (page, limit) = validate_pagination(&pagination);
         let offset = calculate_offset(page, limit);
 
-        let total = sqlx::query_scalar!(
-            "SELECT COUNT(*) FROM warehouse.items WHERE status = 'ACTIVE'"
-        )
-        .fetch_one(&self.pool)
-        .await?
-        .unwrap_or(0);
+        let count_mode = pagination.count.unwrap_or_default();
+        let search = pagination.search.as_deref().map(str::trim).filter(|search| !search.is_empty());
 
+        let total = match count_mode {
+            CountMode::Exact => Some(
+                sqlx::query_scalar!(
+                    "SELECT COUNT(*) FROM warehouse.items
+                     WHERE status = 'ACTIVE'
+                       AND ($1::TEXT IS NULL OR item_code ILIKE '%' || $1 || '%' OR item_name ILIKE '%' || $1 || '%')",
+                    search
+                )
+                .fetch_one(&self.pool)
+                .await?
+                .unwrap_or(0),
+            ),
+            CountMode::Estimate => Some(estimate_row_count(&self.pool, "warehouse.items").await?),
+            CountMode::Skip => None,
+        };
+
+        // Fetch one extra row so has_next is known without counting
         let rows = sqlx::query!(
-            "SELECT * FROM warehouse.items WHERE status = 'ACTIVE' 
+            "SELECT * FROM warehouse.items
+             WHERE status = 'ACTIVE'
+               AND ($3::TEXT IS NULL OR item_code ILIKE '%' || $3 || '%' OR item_name ILIKE '%' || $3 || '%')
              ORDER BY item_name LIMIT $1 OFFSET $2",
-            limit, offset
+            limit + 1, offset, search
         )
         .fetch_all(&self.pool)
         .await?;
@@ -54,13 +170,22 @@ impl ItemRepository {
                 is_loanable: row.is_loanable.unwrap_or(false),
                 requires_return: row.requires_return.unwrap_or(false),
                 max_loan_duration_days: row.max_loan_duration_days,
-                replacement_cost: row.replacement_cost,
+                replacement_cost: Money::from_column(row.replacement_cost, &row.cost_currency),
                 maintenance_required: row.maintenance_required.unwrap_or(false),
                 calibration_required: row.calibration_required.unwrap_or(false),
-                standard_cost: row.standard_cost,
-                last_cost: row.last_cost,
-                average_cost: row.average_cost,
-                status: row.status,
+                standard_cost: Money::from_column(row.standard_cost, &row.cost_currency),
+                last_cost: Money::from_column(row.last_cost, &row.cost_currency),
+                average_cost: Money::from_column(row.average_cost, &row.cost_currency),
+                un_number: row.un_number,
+                hazard_class: row.hazard_class,
+                packing_group: row.packing_group,
+                proper_shipping_name: row.proper_shipping_name,
+                catch_weight_nominal_kg: row.catch_weight_nominal_kg,
+                catch_weight_tolerance_pct: row.catch_weight_tolerance_pct,
+                hs_code: row.hs_code,
+                country_of_origin: row.country_of_origin,
+                customs_description: row.customs_description,
+                status: row.status.unwrap_or_else(|| "ACTIVE".to_string()),
                 created_at: row.created_at,
                 updated_at: row.updated_at,
                 created_by: row.created_by,
@@ -69,10 +194,71 @@ impl ItemRepository {
             items.push(item);
         }
 
-        Ok(PaginatedResponse::new(items, total, page, limit))
+        Ok(PaginatedResponse::from_probe(items, total, count_mode, page, limit))
+    }
+
+    fn stream_active(&self, search: Option<String>) -> BoxStream<'static, Result<Item>> {
+        let pool = self.pool.clone();
+        let search = search.map(|search| search.trim().to_string()).filter(|search| !search.is_empty());
+
+        Box::pin(async_stream::try_stream! {
+            let mut rows = sqlx::query_as!(
+                ItemRow,
+                r#"SELECT item_id, item_code, item_name, item_description, item_type, item_usage_type,
+                          category, subcategory, brand, model, unit,
+                          weight_kg, length_cm, width_cm, height_cm, volume_cbm,
+                          COALESCE(is_loanable, FALSE) AS "is_loanable!",
+                          COALESCE(requires_return, FALSE) AS "requires_return!",
+                          max_loan_duration_days, replacement_cost,
+                          COALESCE(maintenance_required, FALSE) AS "maintenance_required!",
+                          COALESCE(calibration_required, FALSE) AS "calibration_required!",
+                          standard_cost, last_cost, average_cost, cost_currency, cost_currency,
+                          un_number, hazard_class, packing_group, proper_shipping_name,
+                          catch_weight_nominal_kg, catch_weight_tolerance_pct,
+                          hs_code, country_of_origin, customs_description,
+                          COALESCE(status, 'ACTIVE') AS "status!",
+                          created_at, updated_at, created_by, updated_by
+                   FROM warehouse.items
+                   WHERE status = 'ACTIVE'
+                     AND ($1::TEXT IS NULL OR item_code ILIKE '%' || $1 || '%' OR item_name ILIKE '%' || $1 || '%')
+                   ORDER BY item_name"#,
+                search
+            )
+            .fetch(&pool);
+
+            while let Some(row) = rows.try_next().await? {
+                yield Item::from(row);
+            }
+        })
+    }
+
+    async fn get_by_code(&self, code: &str) -> Result<Option<Item>> {
+        let row = sqlx::query_as!(
+            ItemRow,
+            r#"SELECT item_id, item_code, item_name, item_description, item_type, item_usage_type,
+                      category, subcategory, brand, model, unit,
+                      weight_kg, length_cm, width_cm, height_cm, volume_cbm,
+                      COALESCE(is_loanable, FALSE) AS "is_loanable!",
+                      COALESCE(requires_return, FALSE) AS "requires_return!",
+                      max_loan_duration_days, replacement_cost,
+                      COALESCE(maintenance_required, FALSE) AS "maintenance_required!",
+                      COALESCE(calibration_required, FALSE) AS "calibration_required!",
+                      standard_cost, last_cost, average_cost, cost_currency,
+                      un_number, hazard_class, packing_group, proper_shipping_name,
+                      catch_weight_nominal_kg, catch_weight_tolerance_pct,
+                      hs_code, country_of_origin, customs_description,
+                      COALESCE(status, 'ACTIVE') AS "status!",
+                      created_at, updated_at, created_by, updated_by
+               FROM warehouse.items WHERE item_code = $1 AND status = 'ACTIVE'"#,
+            code
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(Item::from))
     }
 
-    pub async fn get_by_id(&self, id: i32) -> Result<Option<Item>> {
+    async fn get_by_id(&self, id: i32) -> Result<Option<Item>> {
         let result = sqlx::query!(
             "SELECT * FROM warehouse.items WHERE item_id = $1 AND status = 'ACTIVE'",
             id
@@ -101,13 +287,22 @@ impl ItemRepository {
                 is_loanable: row.is_loanable.unwrap_or(false),
                 requires_return: row.requires_return.unwrap_or(false),
                 max_loan_duration_days: row.max_loan_duration_days,
-                replacement_cost: row.replacement_cost,
+                replacement_cost: Money::from_column(row.replacement_cost, &row.cost_currency),
                 maintenance_required: row.maintenance_required.unwrap_or(false),
                 calibration_required: row.calibration_required.unwrap_or(false),
-                standard_cost: row.standard_cost,
-                last_cost: row.last_cost,
-                average_cost: row.average_cost,
-                status: row.status,
+                standard_cost: Money::from_column(row.standard_cost, &row.cost_currency),
+                last_cost: Money::from_column(row.last_cost, &row.cost_currency),
+                average_cost: Money::from_column(row.average_cost, &row.cost_currency),
+                un_number: row.un_number,
+                hazard_class: row.hazard_class,
+                packing_group: row.packing_group,
+                proper_shipping_name: row.proper_shipping_name,
+                catch_weight_nominal_kg: row.catch_weight_nominal_kg,
+                catch_weight_tolerance_pct: row.catch_weight_tolerance_pct,
+                hs_code: row.hs_code,
+                country_of_origin: row.country_of_origin,
+                customs_description: row.customs_description,
+                status: row.status.unwrap_or_else(|| "ACTIVE".to_string()),
                 created_at: row.created_at,
                 updated_at: row.updated_at,
                 created_by: row.created_by,
@@ -117,14 +312,16 @@ impl ItemRepository {
         }
     }
 
-    pub async fn create(&self, item: CreateItem) -> Result<Item> {
+    async fn create(&self, item: CreateItem) -> Result<Item> {
         let result = sqlx::query!(
             r#"
             INSERT INTO warehouse.items (
                 item_code, item_name, item_description, item_type, item_usage_type,
                 category, subcategory, brand, model, unit, is_loanable,
-                maintenance_required, calibration_required, replacement_cost, created_by, updated_by
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+                maintenance_required, calibration_required, replacement_cost, created_by, updated_by,
+                un_number, hazard_class, packing_group, proper_shipping_name,
+                hs_code, country_of_origin, customs_description, cost_currency
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, COALESCE($10, 'PCS'), $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24)
             RETURNING *
             "#,
             item.item_code,
@@ -140,9 +337,17 @@ impl ItemRepository {
             item.is_loanable.unwrap_or(false),
             item.maintenance_required.unwrap_or(false),
             item.calibration_required.unwrap_or(false),
-            item.replacement_cost,
-            1i32, // created_by
-            1i32  // updated_by
+            item.replacement_cost.as_ref().map(|cost| cost.amount),
+            acting_user(), // created_by
+            acting_user(), // updated_by
+            item.hazmat.as_ref().map(|h| h.un_number.clone()),
+            item.hazmat.as_ref().map(|h| h.hazard_class.clone()),
+            item.hazmat.as_ref().and_then(|h| h.packing_group.clone()),
+            item.hazmat.as_ref().map(|h| h.proper_shipping_name.clone()),
+            item.customs.as_ref().map(|c| c.hs_code.clone()),
+            item.customs.as_ref().map(|c| c.country_of_origin.clone()),
+            item.customs.as_ref().map(|c| c.customs_description.clone()),
+            item.replacement_cost.as_ref().map_or(DEFAULT_CURRENCY, |cost| cost.currency.as_str())
         )
         .fetch_one(&self.pool)
         .await?;
@@ -167,13 +372,22 @@ impl ItemRepository {
             is_loanable: result.is_loanable.unwrap_or(false),
             requires_return: result.requires_return.unwrap_or(false),
             max_loan_duration_days: result.max_loan_duration_days,
-            replacement_cost: result.replacement_cost,
+            replacement_cost: Money::from_column(result.replacement_cost, &result.cost_currency),
             maintenance_required: result.maintenance_required.unwrap_or(false),
             calibration_required: result.calibration_required.unwrap_or(false),
-            standard_cost: result.standard_cost,
-            last_cost: result.last_cost,
-            average_cost: result.average_cost,
-            status: result.status,
+            standard_cost: Money::from_column(result.standard_cost, &result.cost_currency),
+            last_cost: Money::from_column(result.last_cost, &result.cost_currency),
+            average_cost: Money::from_column(result.average_cost, &result.cost_currency),
+            un_number: result.un_number,
+            hazard_class: result.hazard_class,
+            packing_group: result.packing_group,
+            proper_shipping_name: result.proper_shipping_name,
+            catch_weight_nominal_kg: result.catch_weight_nominal_kg,
+            catch_weight_tolerance_pct: result.catch_weight_tolerance_pct,
+            hs_code: result.hs_code,
+            country_of_origin: result.country_of_origin,
+            customs_description: result.customs_description,
+            status: result.status.unwrap_or_else(|| "ACTIVE".to_string()),
             created_at: result.created_at,
             updated_at: result.updated_at,
             created_by: result.created_by,
@@ -181,7 +395,109 @@ impl ItemRepository {
         })
     }
 
-    pub async fn code_exists(&self, code: &str, exclude_id: Option<i32>) -> Result<bool> {
+    /// Costs are all kept in the item's cost currency, so a replacement cost may only switch
+    /// currency while the item has no other cost
+    async fn update(&self, id: i32, item: UpdateItem) -> Result<Option<Item>> {
+        let Some(current) = self.get_by_id(id).await? else {
+            return Ok(None);
+        };
+        if let Some(cost) = &item.replacement_cost {
+            let costed = [&current.standard_cost, &current.last_cost, &current.average_cost]
+                .into_iter()
+                .flatten()
+                .find(|existing| existing.currency != cost.currency);
+            if let Some(existing) = costed {
+                return Err(violation(format!(
+                    "{} is costed in {}; its replacement cost must be too",
+                    current.item_code, existing.currency
+                )));
+            }
+        }
+
+        let updated = sqlx::query_scalar!(
+            "UPDATE warehouse.items
+             SET item_code = COALESCE($2, item_code),
+                 item_name = COALESCE($3, item_name),
+                 item_description = COALESCE($4, item_description),
+                 item_type = COALESCE($5, item_type),
+                 category = COALESCE($6, category),
+                 subcategory = COALESCE($7, subcategory),
+                 brand = COALESCE($8, brand),
+                 model = COALESCE($9, model),
+                 unit = COALESCE($10, unit),
+                 replacement_cost = COALESCE($11, replacement_cost),
+                 cost_currency = COALESCE($12, cost_currency),
+                 updated_at = NOW(),
+                 updated_by = $13
+             WHERE item_id = $1 AND status = 'ACTIVE'
+             RETURNING item_id",
+            id,
+            item.item_code,
+            item.item_name,
+            item.item_description,
+            item.item_type,
+            item.category,
+            item.subcategory,
+            item.brand,
+            item.model,
+            item.unit,
+            item.replacement_cost.as_ref().map(|cost| cost.amount),
+            item.replacement_cost.as_ref().map(|cost| cost.currency.as_str()),
+            acting_user() // updated_by
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match updated {
+            Some(id) => self.get_by_id(id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Refused while any of the item is still on hand
+    async fn delete(&self, id: i32) -> Result<bool> {
+        let Some(item) = self.get_by_id(id).await? else {
+            return Ok(false);
+        };
+        let on_hand = sqlx::query_scalar!(
+            "SELECT COALESCE(SUM(quantity_on_hand), 0) FROM warehouse.stock_inventory WHERE item_id = $1",
+            id
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or_default();
+        if on_hand > Decimal::ZERO {
+            return Err(violation(format!("cannot delete {}: {} still on hand", item.item_code, on_hand)));
+        }
+
+        let result = sqlx::query!(
+            "UPDATE warehouse.items SET status = 'INACTIVE', updated_at = NOW()
+             WHERE item_id = $1 AND status = 'ACTIVE'",
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn restore(&self, id: i32) -> Result<Option<Item>> {
+        let restored = sqlx::query_scalar!(
+            "UPDATE warehouse.items SET status = 'ACTIVE', updated_at = NOW()
+             WHERE item_id = $1 AND status = 'INACTIVE'
+             RETURNING item_id",
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match restored {
+            Some(id) => self.get_by_id(id).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn code_exists(&self, code: &str, exclude_id: Option<i32>) -> Result<bool> {
         let exists = match exclude_id {
             Some(id) => {
                 sqlx::query_scalar!(
@@ -0,0 +1,221 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::*;
+use crate::utils::*;
+
+#[derive(Clone)]
+pub struct JobRepository {
+    pool: PgPool,
+}
+
+impl JobRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, job: NewJob) -> Result<Job> {
+        let created = sqlx::query_as!(
+            Job,
+            "INSERT INTO warehouse.jobs (job_type, description, reference_type, reference_id)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *",
+            job.job_type,
+            job.description,
+            job.reference_type,
+            job.reference_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(created)
+    }
+
+    pub async fn get_by_id(&self, id: i64) -> Result<Option<Job>> {
+        let job = sqlx::query_as!(Job, "SELECT * FROM warehouse.jobs WHERE job_id = $1", id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(job)
+    }
+
+    /// Newest first
+    pub async fn list(&self, filter: JobFilter, keyset: KeysetQuery) -> Result<KeysetPage<Job>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            Job,
+            "SELECT * FROM warehouse.jobs
+             WHERE ($1::BIGINT IS NULL OR job_id < $1)
+               AND ($2::VARCHAR IS NULL OR job_type = $2)
+               AND ($3::VARCHAR IS NULL OR status = $3)
+             ORDER BY job_id DESC
+             LIMIT $4",
+            after_id,
+            filter.job_type,
+            filter.status,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |job| job.job_id))
+    }
+
+    pub async fn start(&self, id: i64) -> Result<Job> {
+        let job = sqlx::query_as!(
+            Job,
+            "UPDATE warehouse.jobs SET status = $2, started_at = NOW(), updated_at = NOW()
+             WHERE job_id = $1
+             RETURNING *",
+            id,
+            JOB_RUNNING
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    /// Record how far the job has got. Without a total the percentage stays where it is.
+    pub async fn progress(&self, id: i64, processed: i64, total: Option<i64>) -> Result<Job> {
+        let job = sqlx::query_as!(
+            Job,
+            "UPDATE warehouse.jobs SET
+                 processed = $2,
+                 total = COALESCE($3, total),
+                 progress_percent = CASE
+                     WHEN COALESCE($3, total) > 0 THEN LEAST(99, $2 * 100 / COALESCE($3, total))::SMALLINT
+                     ELSE progress_percent
+                 END,
+                 updated_at = NOW()
+             WHERE job_id = $1
+             RETURNING *",
+            id,
+            processed,
+            total
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    /// Note a problem the job carries on past, up to `JOB_MAX_ERRORS` of them
+    pub async fn add_error(&self, id: i64, error: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE warehouse.jobs SET errors = array_append(errors, $2), updated_at = NOW()
+             WHERE job_id = $1 AND cardinality(errors) < $3",
+            id,
+            error,
+            JOB_MAX_ERRORS as i32
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn add_artifact(
+        &self,
+        id: i64,
+        file_name: &str,
+        content_type: &str,
+        content: &[u8],
+    ) -> Result<JobArtifact> {
+        let artifact = sqlx::query_as!(
+            JobArtifact,
+            "INSERT INTO warehouse.job_artifacts (job_id, file_name, content_type, content, size_bytes)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING artifact_id, job_id, file_name, content_type, size_bytes, created_at",
+            id,
+            file_name,
+            content_type,
+            content,
+            content.len() as i64
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(artifact)
+    }
+
+    pub async fn succeed(&self, id: i64) -> Result<Job> {
+        let job = sqlx::query_as!(
+            Job,
+            "UPDATE warehouse.jobs SET
+                 status = $2,
+                 processed = GREATEST(processed, COALESCE(total, 0)),
+                 progress_percent = 100,
+                 completed_at = NOW(),
+                 updated_at = NOW()
+             WHERE job_id = $1
+             RETURNING *",
+            id,
+            JOB_SUCCEEDED
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    /// End the job with `error` as its last error, past the error limit if need be
+    pub async fn fail(&self, id: i64, error: &str) -> Result<Job> {
+        let job = sqlx::query_as!(
+            Job,
+            "UPDATE warehouse.jobs SET
+                 status = $2,
+                 errors = array_append(errors, $3),
+                 completed_at = NOW(),
+                 updated_at = NOW()
+             WHERE job_id = $1
+             RETURNING *",
+            id,
+            JOB_FAILED,
+            error
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    pub async fn artifacts(&self, job_id: i64) -> Result<Vec<JobArtifact>> {
+        let artifacts = sqlx::query_as!(
+            JobArtifact,
+            "SELECT artifact_id, job_id, file_name, content_type, size_bytes, created_at
+             FROM warehouse.job_artifacts
+             WHERE job_id = $1
+             ORDER BY artifact_id",
+            job_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(artifacts)
+    }
+
+    /// An artifact and its file
+    pub async fn artifact_content(&self, artifact_id: i64) -> Result<Option<(JobArtifact, Vec<u8>)>> {
+        let row = sqlx::query!(
+            "SELECT artifact_id, job_id, file_name, content_type, size_bytes, created_at, content
+             FROM warehouse.job_artifacts
+             WHERE artifact_id = $1",
+            artifact_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let artifact = JobArtifact {
+                artifact_id: row.artifact_id,
+                job_id: row.job_id,
+                file_name: row.file_name,
+                content_type: row.content_type,
+                size_bytes: row.size_bytes,
+                created_at: row.created_at,
+            };
+            (artifact, row.content)
+        }))
+    }
+}
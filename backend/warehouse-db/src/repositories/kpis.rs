@@ -0,0 +1,220 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct KpiRepository {
+    pool: PgPool,
+}
+
+impl KpiRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// On-time-in-full per warehouse and period over standard orders with a requested ship
+    /// date; dropship orders leave from the supplier and are not counted
+    pub async fn otif(&self, filter: KpiFilter) -> Result<Vec<OtifKpi>> {
+        let rows = sqlx::query_as!(
+            OtifKpi,
+            r#"WITH due AS (
+                   SELECT o.order_id, o.warehouse_id, o.status,
+                          o.requested_ship_date AS due_date,
+                          date_trunc($4, o.requested_ship_date::TIMESTAMP)::DATE AS period_start,
+                          (NOW() AT TIME ZONE COALESCE(w.timezone, 'UTC'))::DATE AS today,
+                          MIN(s.shipped_at AT TIME ZONE COALESCE(w.timezone, 'UTC'))::DATE AS first_shipped_on,
+                          MAX(s.shipped_at AT TIME ZONE COALESCE(w.timezone, 'UTC'))::DATE AS last_shipped_on
+                   FROM warehouse.outbound_orders o
+                   JOIN warehouse.warehouses w ON w.warehouse_id = o.warehouse_id
+                   LEFT JOIN warehouse.shipments s ON s.order_id = o.order_id
+                   WHERE o.order_type = $5
+                     AND o.status <> $6
+                     AND o.requested_ship_date IS NOT NULL
+                     AND ($1::INT IS NULL OR o.warehouse_id = $1)
+                   GROUP BY o.order_id, w.timezone
+               )
+               SELECT warehouse_id AS "warehouse_id!",
+                      period_start AS "period_start!",
+                      COUNT(*) AS "orders_due!",
+                      COUNT(*) FILTER (WHERE first_shipped_on <= due_date) AS "on_time!",
+                      COUNT(*) FILTER (WHERE status = $7) AS "in_full!",
+                      COUNT(*) FILTER (WHERE status = $7 AND last_shipped_on <= due_date) AS "on_time_in_full!",
+                      ROUND(100.0 * COUNT(*) FILTER (WHERE status = $7 AND last_shipped_on <= due_date) / COUNT(*), 2)
+                          AS otif_pct
+               FROM due
+               WHERE due_date >= COALESCE($2, today - 30)
+                 AND due_date <= COALESCE($3, today)
+                 AND (status = $7 OR due_date < today)
+               GROUP BY warehouse_id, period_start
+               ORDER BY period_start, warehouse_id"#,
+            filter.warehouse_id,
+            filter.from,
+            filter.to,
+            filter.period(),
+            ORDER_TYPE_STANDARD,
+            ORDER_CANCELLED,
+            ORDER_SHIPPED
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Order, line and unit fill rate of first shipments per warehouse and period. A line
+    /// is filled when the first shipment carried all of it.
+    pub async fn fill_rate(&self, filter: KpiFilter) -> Result<Vec<FillRateKpi>> {
+        let rows = sqlx::query_as!(
+            FillRateKpi,
+            r#"WITH first_shipments AS (
+                   SELECT DISTINCT ON (s.order_id)
+                          s.order_id, s.shipment_id, s.warehouse_id,
+                          (s.shipped_at AT TIME ZONE COALESCE(w.timezone, 'UTC'))::DATE AS shipped_on,
+                          (NOW() AT TIME ZONE COALESCE(w.timezone, 'UTC'))::DATE AS today
+                   FROM warehouse.shipments s
+                   JOIN warehouse.outbound_orders o ON o.order_id = s.order_id
+                   JOIN warehouse.warehouses w ON w.warehouse_id = s.warehouse_id
+                   WHERE o.order_type = $5
+                     AND ($1::INT IS NULL OR s.warehouse_id = $1)
+                   ORDER BY s.order_id, s.shipped_at, s.shipment_id
+               ),
+               lines AS (
+                   SELECT f.order_id, f.warehouse_id,
+                          date_trunc($4, f.shipped_on::TIMESTAMP)::DATE AS period_start,
+                          l.quantity_ordered,
+                          COALESCE(SUM(sl.quantity), 0) AS quantity_shipped
+                   FROM first_shipments f
+                   JOIN warehouse.outbound_order_lines l ON l.order_id = f.order_id
+                   LEFT JOIN warehouse.shipment_lines sl
+                          ON sl.shipment_id = f.shipment_id AND sl.order_line_id = l.line_id
+                   WHERE f.shipped_on >= COALESCE($2, f.today - 30)
+                     AND f.shipped_on <= COALESCE($3, f.today)
+                   GROUP BY f.order_id, f.warehouse_id, f.shipped_on, l.line_id
+               ),
+               orders AS (
+                   SELECT order_id, warehouse_id, period_start,
+                          BOOL_AND(quantity_shipped >= quantity_ordered) AS filled,
+                          COUNT(*) AS lines_ordered,
+                          COUNT(*) FILTER (WHERE quantity_shipped >= quantity_ordered) AS lines_filled,
+                          SUM(quantity_ordered) AS units_ordered,
+                          SUM(quantity_shipped) AS units_shipped
+                   FROM lines
+                   GROUP BY order_id, warehouse_id, period_start
+               )
+               SELECT warehouse_id AS "warehouse_id!",
+                      period_start AS "period_start!",
+                      COUNT(*) AS "orders_shipped!",
+                      COUNT(*) FILTER (WHERE filled) AS "orders_filled!",
+                      ROUND(100.0 * COUNT(*) FILTER (WHERE filled) / COUNT(*), 2) AS order_fill_pct,
+                      SUM(lines_ordered)::BIGINT AS "lines_ordered!",
+                      SUM(lines_filled)::BIGINT AS "lines_filled!",
+                      ROUND(100.0 * SUM(lines_filled) / NULLIF(SUM(lines_ordered), 0), 2) AS line_fill_pct,
+                      SUM(units_ordered) AS "units_ordered!",
+                      SUM(units_shipped) AS "units_shipped!",
+                      ROUND(100 * SUM(units_shipped) / NULLIF(SUM(units_ordered), 0), 2) AS unit_fill_pct
+               FROM orders
+               GROUP BY warehouse_id, period_start
+               ORDER BY period_start, warehouse_id"#,
+            filter.warehouse_id,
+            filter.from,
+            filter.to,
+            filter.period(),
+            ORDER_TYPE_STANDARD
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Dock-to-stock hours of fully received receipts per warehouse and period. Unloading
+    /// starts when the receiving task is claimed, or with the first posted line when
+    /// nobody claimed it; the goods are in stock when the receiving task closes.
+    pub async fn dock_to_stock(&self, filter: KpiFilter) -> Result<Vec<DockToStockKpi>> {
+        let rows = sqlx::query_as!(
+            DockToStockKpi,
+            r#"WITH received AS (
+                   SELECT r.warehouse_id,
+                          (t.completed_at AT TIME ZONE COALESCE(w.timezone, 'UTC'))::DATE AS received_on,
+                          date_trunc($4, t.completed_at AT TIME ZONE COALESCE(w.timezone, 'UTC'))::DATE AS period_start,
+                          (NOW() AT TIME ZONE COALESCE(w.timezone, 'UTC'))::DATE AS today,
+                          EXTRACT(EPOCH FROM t.completed_at - LEAST(t.started_at, r.received_at))::NUMERIC / 3600
+                              AS hours
+                   FROM warehouse.inbound_receipts r
+                   JOIN warehouse.warehouses w ON w.warehouse_id = r.warehouse_id
+                   JOIN warehouse.tasks t ON t.reference_type = $5 AND t.reference_id = r.receipt_id
+                   WHERE r.status = $6
+                     AND t.status = $7
+                     AND t.completed_at IS NOT NULL
+                     AND ($1::INT IS NULL OR r.warehouse_id = $1)
+               )
+               SELECT warehouse_id AS "warehouse_id!",
+                      period_start AS "period_start!",
+                      COUNT(*) AS "receipts!",
+                      ROUND(AVG(hours), 2) AS "avg_hours!",
+                      ROUND((PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY hours))::NUMERIC, 2) AS "median_hours!",
+                      ROUND(MAX(hours), 2) AS "max_hours!"
+               FROM received
+               WHERE received_on >= COALESCE($2, today - 30)
+                 AND received_on <= COALESCE($3, today)
+               GROUP BY warehouse_id, period_start
+               ORDER BY period_start, warehouse_id"#,
+            filter.warehouse_id,
+            filter.from,
+            filter.to,
+            filter.period(),
+            TASK_REF_RECEIPT,
+            RECEIPT_RECEIVED,
+            TASK_COMPLETED
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Inventory record accuracy from completed count tasks per warehouse and period; the
+    /// task quantity is the quantity on record, the quantity done the one counted
+    pub async fn inventory_accuracy(&self, filter: KpiFilter) -> Result<Vec<InventoryAccuracyKpi>> {
+        let rows = sqlx::query_as!(
+            InventoryAccuracyKpi,
+            r#"WITH counted AS (
+                   SELECT t.warehouse_id, t.quantity AS expected, t.quantity_done AS counted,
+                          (t.completed_at AT TIME ZONE COALESCE(w.timezone, 'UTC'))::DATE AS counted_on,
+                          date_trunc($4, t.completed_at AT TIME ZONE COALESCE(w.timezone, 'UTC'))::DATE AS period_start,
+                          (NOW() AT TIME ZONE COALESCE(w.timezone, 'UTC'))::DATE AS today
+                   FROM warehouse.tasks t
+                   JOIN warehouse.warehouses w ON w.warehouse_id = t.warehouse_id
+                   WHERE t.task_type = $5
+                     AND t.status = $6
+                     AND t.quantity IS NOT NULL
+                     AND t.quantity_done IS NOT NULL
+                     AND t.completed_at IS NOT NULL
+                     AND ($1::INT IS NULL OR t.warehouse_id = $1)
+               )
+               SELECT warehouse_id AS "warehouse_id!",
+                      period_start AS "period_start!",
+                      COUNT(*) AS "counts!",
+                      COUNT(*) FILTER (WHERE counted = expected) AS "accurate_counts!",
+                      ROUND(100.0 * COUNT(*) FILTER (WHERE counted = expected) / COUNT(*), 2) AS "accuracy_pct!",
+                      SUM(expected) AS "units_expected!",
+                      SUM(ABS(counted - expected)) AS "units_variance!",
+                      ROUND(100 * SUM(ABS(counted - expected)) / NULLIF(SUM(expected), 0), 2) AS variance_pct
+               FROM counted
+               WHERE counted_on >= COALESCE($2, today - 30)
+                 AND counted_on <= COALESCE($3, today)
+               GROUP BY warehouse_id, period_start
+               ORDER BY period_start, warehouse_id"#,
+            filter.warehouse_id,
+            filter.from,
+            filter.to,
+            filter.period(),
+            TASK_COUNT,
+            TASK_COMPLETED
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
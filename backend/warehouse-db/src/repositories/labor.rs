@@ -0,0 +1,86 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct LaborRepository {
+    pool: PgPool,
+}
+
+impl LaborRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Productivity per operator and shift from completed tasks. Tasks are placed in the
+    /// warehouse shift during which they were claimed, in the warehouse's local time;
+    /// hours are the claim-to-completion time of the operator's tasks.
+    pub async fn report(&self, filter: LaborReportFilter) -> Result<Vec<LaborProductivity>> {
+        let rows = sqlx::query_as!(
+            LaborProductivity,
+            r#"WITH done AS (
+                   SELECT t.*,
+                          (t.started_at AT TIME ZONE COALESCE(w.timezone, 'UTC')) AS local_start,
+                          EXTRACT(EPOCH FROM t.completed_at - t.started_at) / 3600 AS hours
+                   FROM warehouse.tasks t
+                   JOIN warehouse.warehouses w ON w.warehouse_id = t.warehouse_id
+                   WHERE t.status = $1
+                     AND t.completed_by IS NOT NULL
+                     AND t.started_at IS NOT NULL
+                     AND t.completed_at >= COALESCE($2, NOW() - INTERVAL '7 days')
+                     AND t.completed_at < COALESCE($3, NOW())
+                     AND ($4::INT IS NULL OR t.warehouse_id = $4)
+                     AND ($5::INT IS NULL OR t.completed_by = $5)
+               ),
+               placed AS (
+                   SELECT d.*,
+                          s.shift_id,
+                          COALESCE(s.shift_code, $7) AS shift_code,
+                          CASE WHEN s.starts_at >= s.ends_at AND d.local_start::TIME < s.ends_at
+                               THEN d.local_start::DATE - 1
+                               ELSE d.local_start::DATE
+                          END AS shift_date
+                   FROM done d
+                   LEFT JOIN LATERAL (
+                       SELECT * FROM warehouse.shifts s
+                       WHERE s.warehouse_id = d.warehouse_id
+                         AND s.is_active
+                         AND warehouse.in_shift(d.local_start::TIME, s.starts_at, s.ends_at)
+                       ORDER BY s.starts_at
+                       LIMIT 1
+                   ) s ON TRUE
+               )
+               SELECT completed_by AS "operator_id!",
+                      warehouse_id AS "warehouse_id!",
+                      shift_code AS "shift_code!",
+                      shift_date AS "shift_date!",
+                      COUNT(*) AS "tasks_completed!",
+                      COUNT(*) FILTER (WHERE task_type = $8) AS "picks!",
+                      COUNT(*) FILTER (WHERE task_type = $9) AS "receipts!",
+                      ROUND(SUM(hours), 2) AS "hours_worked!",
+                      ROUND(COUNT(*) FILTER (WHERE task_type = $8) / NULLIF(SUM(hours) FILTER (WHERE task_type = $8), 0), 2)
+                          AS picks_per_hour,
+                      ROUND(COUNT(*) FILTER (WHERE task_type = $9) / NULLIF(SUM(hours) FILTER (WHERE task_type = $9), 0), 2)
+                          AS receipts_per_hour,
+                      ROUND(100.0 * COUNT(*) FILTER (WHERE quantity IS NULL OR quantity_done >= quantity) / COUNT(*), 2)
+                          AS "accuracy_pct!"
+               FROM placed
+               WHERE ($6::INT IS NULL OR shift_id = $6)
+               GROUP BY completed_by, warehouse_id, shift_code, shift_date
+               ORDER BY shift_date, warehouse_id, shift_code, completed_by"#,
+            TASK_COMPLETED,
+            filter.from,
+            filter.to,
+            filter.warehouse_id,
+            filter.operator_id,
+            filter.shift_id,
+            SHIFT_UNSCHEDULED,
+            TASK_PICK,
+            TASK_RECEIVE
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
@@ -0,0 +1,217 @@
+use crate::utils::validate_keyset;
+use anyhow::{ensure, Result};
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct LedgerRepository {
+    pool: PgPool,
+}
+
+impl LedgerRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Ledger entries, newest first
+    pub async fn entries(&self, filter: LedgerFilter, keyset: KeysetQuery) -> Result<KeysetPage<LedgerEntry>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            LedgerEntry,
+            "SELECT entry_id, transaction_id, item_id, warehouse_id, consignor_id, account, quantity
+             FROM warehouse.ledger_entries
+             WHERE ($1::BIGINT IS NULL OR entry_id < $1)
+               AND ($2::INT IS NULL OR item_id = $2)
+               AND ($3::INT IS NULL OR warehouse_id = $3)
+               AND ($4::VARCHAR IS NULL OR account = $4)
+             ORDER BY entry_id DESC
+             LIMIT $5",
+            after_id,
+            filter.item_id,
+            filter.warehouse_id,
+            filter.account,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |e| e.entry_id))
+    }
+
+    /// Check that every transaction balances, that the ON_HAND, RESERVED and QUARANTINE
+    /// buckets match the stock balances of each item, warehouse and owner, and that the
+    /// transfer clearing account nets to zero per item and warehouse
+    pub async fn integrity(&self) -> Result<LedgerIntegrity> {
+        let mut conn = self.pool.acquire().await?;
+
+        let trial_balance = sqlx::query_as!(
+            AccountBalance,
+            r#"SELECT account, SUM(quantity) AS "quantity!"
+               FROM warehouse.ledger_entries
+               GROUP BY account
+               ORDER BY account"#
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let unbalanced_transactions = sqlx::query_as!(
+            UnbalancedTransaction,
+            r#"SELECT t.transaction_id, t.movement_id, t.reference_type, t.reference_number, t.posted_at,
+                      SUM(e.quantity) AS "imbalance!"
+               FROM warehouse.ledger_transactions t
+               JOIN warehouse.ledger_entries e ON e.transaction_id = t.transaction_id
+               GROUP BY t.transaction_id
+               HAVING SUM(e.quantity) <> 0
+               ORDER BY t.transaction_id"#
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let bucket_mismatches = sqlx::query_as!(
+            BucketMismatch,
+            r#"WITH ledger AS (
+                   SELECT item_id, warehouse_id, consignor_id,
+                          SUM(quantity) FILTER (WHERE account = 'ON_HAND') AS on_hand,
+                          SUM(quantity) FILTER (WHERE account = 'RESERVED') AS reserved,
+                          SUM(quantity) FILTER (WHERE account = 'QUARANTINE') AS quarantined
+                   FROM warehouse.ledger_entries
+                   WHERE account IN ('ON_HAND', 'RESERVED', 'QUARANTINE')
+                   GROUP BY item_id, warehouse_id, consignor_id
+               )
+               SELECT COALESCE(s.item_id, l.item_id) AS "item_id!",
+                      COALESCE(s.warehouse_id, l.warehouse_id) AS "warehouse_id!",
+                      COALESCE(s.consignor_id, l.consignor_id) AS consignor_id,
+                      bucket.account AS "account!",
+                      bucket.ledger_quantity AS "ledger_quantity!",
+                      bucket.stock_quantity AS "stock_quantity!"
+               FROM warehouse.stock_inventory s
+               FULL JOIN ledger l ON l.item_id = s.item_id AND l.warehouse_id = s.warehouse_id
+                                 AND l.consignor_id IS NOT DISTINCT FROM s.consignor_id
+               CROSS JOIN LATERAL (VALUES
+                   ('ON_HAND', COALESCE(l.on_hand, 0), COALESCE(s.quantity_on_hand - s.quantity_reserved, 0)),
+                   ('RESERVED', COALESCE(l.reserved, 0), COALESCE(s.quantity_reserved, 0)),
+                   ('QUARANTINE', COALESCE(l.quarantined, 0), COALESCE(s.quantity_quarantined, 0))
+               ) AS bucket(account, ledger_quantity, stock_quantity)
+               WHERE bucket.ledger_quantity <> bucket.stock_quantity
+               ORDER BY 1, 2, 3 NULLS FIRST, 4"#
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let open_transfers = sqlx::query_as!(
+            OpenTransfer,
+            r#"SELECT item_id, warehouse_id, SUM(quantity) AS "quantity!"
+               FROM warehouse.ledger_entries
+               WHERE account = $1
+               GROUP BY item_id, warehouse_id
+               HAVING SUM(quantity) <> 0
+               ORDER BY item_id, warehouse_id"#,
+            ACCOUNT_TRANSFER
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let reconciled = unbalanced_transactions.is_empty()
+            && bucket_mismatches.is_empty()
+            && open_transfers.is_empty()
+            && trial_balance.iter().map(|b| b.quantity).sum::<Decimal>().is_zero();
+
+        Ok(LedgerIntegrity { reconciled, trial_balance, unbalanced_transactions, bucket_mismatches, open_transfers })
+    }
+}
+
+/// Post a balanced transaction of `entries`. Runs on the caller's connection so it joins
+/// the caller's transaction.
+pub(crate) async fn post_transaction(
+    conn: &mut PgConnection,
+    movement_id: Option<i64>,
+    reference_type: Option<&str>,
+    reference_number: Option<&str>,
+    entries: Vec<NewLedgerEntry>,
+) -> Result<i64> {
+    ensure!(
+        entries.iter().map(|e| e.quantity).sum::<Decimal>().is_zero(),
+        "ledger transaction for {:?} {:?} does not balance",
+        reference_type,
+        reference_number
+    );
+
+    let transaction_id = sqlx::query_scalar!(
+        "INSERT INTO warehouse.ledger_transactions (movement_id, reference_type, reference_number)
+         VALUES ($1, $2, $3)
+         RETURNING transaction_id",
+        movement_id,
+        reference_type,
+        reference_number
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let entries: Vec<_> = entries.into_iter().filter(|e| !e.quantity.is_zero()).collect();
+    sqlx::query!(
+        "INSERT INTO warehouse.ledger_entries (transaction_id, item_id, warehouse_id, consignor_id, account, quantity)
+         SELECT $1, * FROM UNNEST($2::INT[], $3::INT[], $4::INT[], $5::VARCHAR[], $6::DECIMAL[])",
+        transaction_id,
+        &entries.iter().map(|e| e.item_id).collect::<Vec<_>>(),
+        &entries.iter().map(|e| e.warehouse_id).collect::<Vec<_>>(),
+        &entries.iter().map(|e| e.consignor_id).collect::<Vec<_>>() as &[Option<i32>],
+        &entries.iter().map(|e| e.account.to_string()).collect::<Vec<_>>(),
+        &entries.iter().map(|e| e.quantity).collect::<Vec<_>>()
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(transaction_id)
+}
+
+/// Take a delivered shipment's goods out of transit and over to the customer. Does nothing
+/// until the shipment is delivered, or once its delivery has been posted.
+pub(crate) async fn post_delivery(conn: &mut PgConnection, shipment_id: i32) -> Result<()> {
+    let shipment_number = sqlx::query_scalar!(
+        "SELECT s.shipment_number FROM warehouse.shipments s
+         WHERE s.shipment_id = $1 AND s.delivered_at IS NOT NULL
+           AND NOT EXISTS (
+               SELECT 1 FROM warehouse.ledger_transactions t
+               WHERE t.reference_type = $2 AND t.reference_number = s.shipment_number
+           )",
+        shipment_id,
+        REFERENCE_DELIVERY
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(shipment_number) = shipment_number else {
+        return Ok(());
+    };
+
+    let shipped = sqlx::query!(
+        "SELECT item_id, warehouse_id, consignor_id, quantity
+         FROM warehouse.stock_movements
+         WHERE movement_type = 'SHIPMENT' AND reference_type = 'SHIPMENT' AND reference_number = $1",
+        shipment_number
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    if shipped.is_empty() {
+        // Drop-shipped: the goods never passed through our stock
+        return Ok(());
+    }
+
+    let entries = shipped
+        .iter()
+        .flat_map(|m| {
+            [(ACCOUNT_IN_TRANSIT, m.quantity), (ACCOUNT_CUSTOMER, -m.quantity)].map(|(account, quantity)| {
+                NewLedgerEntry {
+                    item_id: m.item_id,
+                    warehouse_id: m.warehouse_id,
+                    consignor_id: m.consignor_id,
+                    account,
+                    quantity,
+                }
+            })
+        })
+        .collect();
+    post_transaction(conn, None, Some(REFERENCE_DELIVERY), Some(&shipment_number), entries).await?;
+    Ok(())
+}
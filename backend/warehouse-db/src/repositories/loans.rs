@@ -0,0 +1,276 @@
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::movements::{lock_stock, post_movement};
+use crate::repositories::notifications::insert_notification;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::chrono::{Days, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct LoanRepository {
+    pool: PgPool,
+}
+
+impl LoanRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Loans matching the filter, newest first
+    pub async fn list(&self, filter: &LoanFilter) -> Result<Vec<ToolLoan>> {
+        let loans = sqlx::query_as!(
+            ToolLoan,
+            "SELECT * FROM warehouse.tool_loans
+             WHERE ($1::VARCHAR IS NULL OR status = $1) AND ($2::INT IS NULL OR item_id = $2)
+               AND ($3::VARCHAR IS NULL OR borrower_name = $3)
+             ORDER BY loan_id DESC",
+            filter.status,
+            filter.item_id,
+            filter.borrower_name
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(loans)
+    }
+
+    pub async fn get(&self, id: i32) -> Result<Option<ToolLoan>> {
+        let mut conn = self.pool.acquire().await?;
+        load_loan(&mut conn, id, false).await
+    }
+
+    /// Lend a loanable item, moving the quantity out of the warehouse's available stock
+    /// until it comes back. The loan is due by the item's maximum loan duration unless an
+    /// earlier due date is given. A serialized tool must be in available stock and not
+    /// already out on loan.
+    pub async fn lend(&self, loan: CreateLoan) -> Result<ToolLoan> {
+        let mut tx = self.pool.begin().await?;
+        let Some(item) = sqlx::query!(
+            "SELECT is_loanable, max_loan_duration_days FROM warehouse.items WHERE item_id = $1 AND status = 'ACTIVE'",
+            loan.item_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Err(violation(format!("unknown item {}", loan.item_id)));
+        };
+        if item.is_loanable != Some(true) {
+            return Err(violation(format!("item {} is not loanable", loan.item_id)));
+        }
+
+        let today = Utc::now().date_naive();
+        let max_days = item.max_loan_duration_days.unwrap_or(30).max(1);
+        let latest_due = today + Days::new(max_days as u64);
+        let due_on = loan.due_on.unwrap_or(latest_due);
+        if due_on < today {
+            return Err(violation("a loan cannot be due before today"));
+        }
+        if due_on > latest_due {
+            return Err(violation(format!("item {} may be lent for at most {} days", loan.item_id, max_days)));
+        }
+
+        lock_stock(&mut tx, loan.warehouse_id, &[loan.item_id]).await?;
+        if let Some(serial_number) = &loan.serial_number {
+            if loan.quantity != Decimal::ONE {
+                return Err(violation("a serialized tool is lent one unit at a time"));
+            }
+            if let Some(open) = sqlx::query_scalar!(
+                "SELECT loan_number FROM warehouse.tool_loans
+                 WHERE item_id = $1 AND serial_number = $2 AND status <> 'RETURNED'",
+                loan.item_id,
+                serial_number
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            {
+                return Err(violation(format!("serial {} is already out on {}", serial_number, open)));
+            }
+
+            let in_stock = sqlx::query_scalar!(
+                r#"SELECT COALESCE(SUM(quantity), 0) AS "quantity!"
+                   FROM warehouse.stock_movements
+                   WHERE item_id = $1 AND warehouse_id = $2 AND lot_number = $3 AND stock_status = $4
+                     AND consignor_id IS NULL"#,
+                loan.item_id,
+                loan.warehouse_id,
+                serial_number,
+                STOCK_AVAILABLE
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            if in_stock < Decimal::ONE {
+                return Err(violation(format!(
+                    "serial {} of item {} is not in available stock in warehouse {}",
+                    serial_number, loan.item_id, loan.warehouse_id
+                )));
+            }
+        }
+
+        let available = sqlx::query_scalar!(
+            "SELECT quantity_available FROM warehouse.stock_inventory
+             WHERE item_id = $1 AND warehouse_id = $2 AND consignor_id IS NULL",
+            loan.item_id,
+            loan.warehouse_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .flatten()
+        .unwrap_or_default();
+        if loan.quantity > available {
+            return Err(violation(format!(
+                "cannot lend {} of item {} in warehouse {}: only {} available",
+                loan.quantity,
+                loan.item_id,
+                loan.warehouse_id,
+                available.max(Decimal::ZERO).normalize()
+            )));
+        }
+
+        let created = sqlx::query_as!(
+            ToolLoan,
+            "INSERT INTO warehouse.tool_loans (
+                 loan_id, loan_number, warehouse_id, item_id, quantity, serial_number, borrower_name, project_id,
+                 due_on, notes
+             )
+             SELECT next_id, 'LN-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4, $5, $6, $7, $8
+             FROM (SELECT nextval('warehouse.tool_loans_loan_id_seq')::INT AS next_id) seq
+             RETURNING *",
+            loan.warehouse_id,
+            loan.item_id,
+            loan.quantity,
+            loan.serial_number,
+            loan.borrower_name,
+            loan.project_id,
+            due_on,
+            loan.notes
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        move_loaned(&mut tx, &created, MOVEMENT_LOAN_OUT, -created.quantity).await?;
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    /// Take a lent tool back into available stock, whether or not it is overdue. Returns
+    /// None when there is no such loan.
+    pub async fn return_loan(&self, id: i32) -> Result<Option<ToolLoan>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(loan) = load_loan(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        if loan.is_returned() {
+            return Err(violation(format!("cannot return {}: it is {}", loan.loan_number, loan.status.to_lowercase())));
+        }
+
+        lock_stock(&mut tx, loan.warehouse_id, &[loan.item_id]).await?;
+        move_loaned(&mut tx, &loan, MOVEMENT_LOAN_RETURN, loan.quantity).await?;
+        sqlx::query!(
+            "UPDATE warehouse.tool_loans SET status = $2, returned_at = NOW() WHERE loan_id = $1",
+            id,
+            LOAN_RETURNED
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let returned = load_loan(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(returned)
+    }
+
+    /// Flag every loan still out after its due date as overdue, raising a notification for
+    /// each. A loan is flagged once; returns the loans flagged by this scan.
+    pub async fn flag_overdue(&self) -> Result<Vec<ToolLoan>> {
+        let mut tx = self.pool.begin().await?;
+        let flagged = sqlx::query_as!(
+            ToolLoan,
+            "UPDATE warehouse.tool_loans SET status = $1, overdue_at = NOW()
+             WHERE status = $2 AND due_on < CURRENT_DATE
+             RETURNING *",
+            LOAN_OVERDUE,
+            LOAN_OUT
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for loan in &flagged {
+            insert_notification(
+                &mut tx,
+                NewNotification {
+                    kind: NOTIFY_LOAN_OVERDUE,
+                    severity: SEVERITY_WARNING,
+                    subject: format!(
+                        "{} was due back from {} on {}",
+                        loan.loan_number, loan.borrower_name, loan.due_on
+                    ),
+                    message: Some(format!(
+                        "{} of item {} lent from warehouse {}",
+                        loan.quantity, loan.item_id, loan.warehouse_id
+                    )),
+                    reference_type: Some(REFERENCE_LOAN.to_string()),
+                    reference_id: Some(loan.loan_id),
+                },
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(flagged)
+    }
+
+    /// Loans still out after their due date, longest overdue first, including any the
+    /// overdue scan has not reached yet
+    pub async fn overdue(&self) -> Result<Vec<OverdueLoan>> {
+        let loans = sqlx::query_as!(
+            OverdueLoan,
+            r#"SELECT l.loan_id, l.loan_number, l.warehouse_id, l.item_id, i.item_code, l.quantity, l.serial_number,
+                      l.borrower_name, l.project_id, l.due_on, (CURRENT_DATE - l.due_on) AS "days_overdue!",
+                      l.overdue_at
+               FROM warehouse.tool_loans l
+               JOIN warehouse.items i ON i.item_id = l.item_id
+               WHERE l.status IN ('OUT', 'OVERDUE') AND l.due_on < CURRENT_DATE
+               ORDER BY l.due_on, l.loan_id"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(loans)
+    }
+}
+
+async fn load_loan(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<ToolLoan>> {
+    let loan = if lock {
+        sqlx::query_as!(ToolLoan, "SELECT * FROM warehouse.tool_loans WHERE loan_id = $1 FOR UPDATE", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    } else {
+        sqlx::query_as!(ToolLoan, "SELECT * FROM warehouse.tool_loans WHERE loan_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    };
+    Ok(loan)
+}
+
+/// Post the loan's movement of available stock, under its serial number if it has one
+async fn move_loaned(conn: &mut PgConnection, loan: &ToolLoan, movement_type: &str, quantity: Decimal) -> Result<()> {
+    post_movement(
+        &mut *conn,
+        NewMovement {
+            item_id: loan.item_id,
+            warehouse_id: loan.warehouse_id,
+            movement_type: movement_type.to_string(),
+            quantity,
+            catch_weight_kg: None,
+            lot_number: loan.serial_number.clone(),
+            unit_cost: None,
+            stock_status: STOCK_AVAILABLE,
+            consignor_id: None,
+            reference_type: Some(REFERENCE_LOAN.to_string()),
+            reference_number: Some(loan.loan_number.clone()),
+            notes: None,
+            created_by: acting_user(),
+        },
+    )
+    .await?;
+    Ok(())
+}
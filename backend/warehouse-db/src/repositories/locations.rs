@@ -0,0 +1,222 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::*;
+use crate::repositories::hazmat::ensure_storable;
+
+#[derive(Clone)]
+pub struct LocationRepository {
+    pool: PgPool,
+}
+
+impl LocationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Zones of a warehouse in pick-path order
+    pub async fn zones(&self, warehouse_id: i32) -> Result<Vec<Zone>> {
+        let zones = sqlx::query_as!(
+            Zone,
+            "SELECT * FROM warehouse.zones WHERE warehouse_id = $1 ORDER BY pick_sequence, zone_code",
+            warehouse_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(zones)
+    }
+
+    pub async fn get_zone(&self, id: i32) -> Result<Option<Zone>> {
+        let zone = sqlx::query_as!(Zone, "SELECT * FROM warehouse.zones WHERE zone_id = $1", id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(zone)
+    }
+
+    pub async fn zone_code_exists(&self, warehouse_id: i32, code: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.zones WHERE warehouse_id = $1 AND zone_code = $2)",
+            warehouse_id,
+            code
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    pub async fn create_zone(&self, warehouse_id: i32, zone: CreateZone) -> Result<Zone> {
+        let created = sqlx::query_as!(
+            Zone,
+            "INSERT INTO warehouse.zones (warehouse_id, zone_code, zone_name, pick_sequence)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *",
+            warehouse_id,
+            zone.zone_code,
+            zone.zone_name,
+            zone.pick_sequence
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(created)
+    }
+
+    /// Locations of a zone in pick-path order
+    pub async fn locations(&self, zone_id: i32) -> Result<Vec<Location>> {
+        let locations = sqlx::query_as!(
+            Location,
+            "SELECT * FROM warehouse.locations WHERE zone_id = $1 ORDER BY pick_sequence, location_code",
+            zone_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(locations)
+    }
+
+    pub async fn location_code_exists(&self, zone_id: i32, code: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.locations WHERE zone_id = $1 AND location_code = $2)",
+            zone_id,
+            code
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    /// Add a location; making it the pick face of a dangerous good is checked against
+    /// the zone's hazmat rules
+    pub async fn create_location(&self, zone_id: i32, location: CreateLocation) -> Result<Location> {
+        let mut tx = self.pool.begin().await?;
+
+        if let Some(item_id) = location.item_id {
+            ensure_storable(&mut tx, item_id, zone_id).await?;
+        }
+
+        let created = sqlx::query_as!(
+            Location,
+            "INSERT INTO warehouse.locations (zone_id, location_code, pick_sequence, item_id)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *",
+            zone_id,
+            location.location_code,
+            location.pick_sequence,
+            location.item_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    pub async fn set_capacity(&self, location_id: i32, capacity: SetLocationCapacity) -> Result<Option<Location>> {
+        let updated = sqlx::query_as!(
+            Location,
+            "UPDATE warehouse.locations SET max_volume_cbm = $2, max_positions = $3
+             WHERE location_id = $1
+             RETURNING *",
+            location_id,
+            capacity.max_volume_cbm,
+            capacity.max_positions
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(updated)
+    }
+
+    /// How full each active location of the warehouse is, grouped by zone in pick-path
+    /// order. Goods are counted where their outermost active LPN stands; an item's volume
+    /// is its `volume_cbm`, else its length x width x height.
+    pub async fn utilization(&self, warehouse_id: i32) -> Result<WarehouseUtilization> {
+        let rows = sqlx::query!(
+            r#"WITH RECURSIVE placed AS (
+                   SELECT lpn_id, location_id, TRUE AS outermost
+                   FROM warehouse.lpns
+                   WHERE warehouse_id = $1 AND status = 'ACTIVE' AND parent_lpn_id IS NULL
+                     AND location_id IS NOT NULL
+                   UNION ALL
+                   SELECT child.lpn_id, placed.location_id, FALSE
+                   FROM warehouse.lpns child
+                   JOIN placed ON child.parent_lpn_id = placed.lpn_id
+                   WHERE child.status = 'ACTIVE'
+               ),
+               held AS (
+                   SELECT placed.location_id,
+                          SUM(c.quantity * COALESCE(i.volume_cbm, i.length_cm * i.width_cm * i.height_cm / 1000000))
+                              AS used_volume,
+                          SUM(c.quantity) FILTER (
+                              WHERE i.volume_cbm IS NULL
+                                AND (i.length_cm IS NULL OR i.width_cm IS NULL OR i.height_cm IS NULL)
+                          ) AS unmeasured
+                   FROM placed
+                   JOIN warehouse.lpn_contents c ON c.lpn_id = placed.lpn_id
+                   JOIN warehouse.items i ON i.item_id = c.item_id
+                   GROUP BY placed.location_id
+               ),
+               standing AS (
+                   SELECT location_id, COUNT(*) AS positions
+                   FROM placed
+                   WHERE outermost
+                   GROUP BY location_id
+               )
+               SELECT z.zone_id, z.zone_code, z.zone_name, l.location_id, l.location_code,
+                      l.max_volume_cbm, l.max_positions,
+                      COALESCE(held.used_volume, 0) AS "used_volume!",
+                      COALESCE(held.unmeasured, 0) AS "unmeasured!",
+                      COALESCE(standing.positions, 0) AS "positions!"
+               FROM warehouse.zones z
+               JOIN warehouse.locations l ON l.zone_id = z.zone_id AND l.is_active
+               LEFT JOIN held ON held.location_id = l.location_id
+               LEFT JOIN standing ON standing.location_id = l.location_id
+               WHERE z.warehouse_id = $1
+               ORDER BY z.pick_sequence, z.zone_code, l.pick_sequence, l.location_code"#,
+            warehouse_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut zones: Vec<ZoneUtilization> = Vec::new();
+        for row in rows {
+            let location = LocationUtilization {
+                location_id: row.location_id,
+                location_code: row.location_code,
+                occupancy: Occupancy::new(
+                    row.used_volume,
+                    row.max_volume_cbm,
+                    row.positions,
+                    row.max_positions.map(i64::from),
+                ),
+                unmeasured_units: row.unmeasured,
+            };
+            match zones.last_mut() {
+                Some(zone) if zone.zone_id == row.zone_id => zone.locations.push(location),
+                _ => zones.push(ZoneUtilization {
+                    zone_id: row.zone_id,
+                    zone_code: row.zone_code,
+                    zone_name: row.zone_name,
+                    occupancy: Occupancy::combine([]),
+                    locations: vec![location],
+                }),
+            }
+        }
+        for zone in &mut zones {
+            zone.occupancy = Occupancy::combine(zone.locations.iter().map(|location| &location.occupancy));
+        }
+
+        Ok(WarehouseUtilization {
+            warehouse_id,
+            occupancy: Occupancy::combine(
+                zones.iter().flat_map(|zone| zone.locations.iter().map(|location| &location.occupancy)),
+            ),
+            zones,
+        })
+    }
+}
@@ -0,0 +1,410 @@
+use crate::error::violation;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct LpnRepository {
+    pool: PgPool,
+}
+
+impl LpnRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn lpns(&self, warehouse_id: i32, filter: LpnFilter) -> Result<Vec<Lpn>> {
+        let lpns = sqlx::query_as!(
+            Lpn,
+            "SELECT * FROM warehouse.lpns
+             WHERE warehouse_id = $1
+               AND ($2::VARCHAR IS NULL OR status = $2)
+               AND ($3::INT IS NULL OR location_id = $3)
+               AND ($4::VARCHAR IS NULL OR lpn_number = $4)
+             ORDER BY lpn_number",
+            warehouse_id,
+            filter.status.map(|status| status.to_uppercase()),
+            filter.location_id,
+            filter.lpn_number
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(lpns)
+    }
+
+    pub async fn get(&self, id: i32) -> Result<Option<LpnDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_lpn(&mut conn, id).await
+    }
+
+    pub async fn lpn_number_exists(&self, number: &str) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        number_exists(&mut conn, number).await
+    }
+
+    pub async fn create(&self, warehouse_id: i32, lpn: CreateLpn) -> Result<LpnDetail> {
+        let mut tx = self.pool.begin().await?;
+
+        let location_id = match lpn.parent_lpn_id {
+            Some(parent_id) => {
+                let parent = lock_active(&mut tx, parent_id).await?;
+                if parent.warehouse_id != warehouse_id {
+                    return Err(violation(format!("{} is in another warehouse", parent.lpn_number)));
+                }
+                parent.location_id
+            }
+            None => lpn.location_id,
+        };
+        let id = insert_lpn(
+            &mut tx,
+            warehouse_id,
+            lpn.lpn_number.as_deref(),
+            &lpn.lpn_type,
+            location_id,
+            lpn.parent_lpn_id,
+            None,
+        )
+        .await?;
+
+        let detail = load_lpn(&mut tx, id).await?.expect("lpn inserted above");
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Set how much of an item sits directly on an LPN. Units put on LPNs cannot add up to
+    /// more than the warehouse holds. Returns None when the LPN does not exist.
+    pub async fn set_content(&self, id: i32, item_id: i32, content: SetLpnContent) -> Result<Option<LpnDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(lpn) = lock(&mut tx, id).await? else {
+            return Ok(None);
+        };
+        ensure_active(&lpn)?;
+
+        if content.quantity.is_zero() {
+            sqlx::query!("DELETE FROM warehouse.lpn_contents WHERE lpn_id = $1 AND item_id = $2", id, item_id)
+                .execute(&mut *tx)
+                .await?;
+        } else {
+            let stock = sqlx::query!(
+                r#"SELECT
+                       (SELECT COALESCE(SUM(quantity_on_hand), 0) FROM warehouse.stock_inventory
+                        WHERE item_id = $1 AND warehouse_id = $2) AS "on_hand!",
+                       (SELECT COALESCE(SUM(c.quantity), 0) FROM warehouse.lpn_contents c
+                        JOIN warehouse.lpns l ON l.lpn_id = c.lpn_id
+                        WHERE c.item_id = $1 AND l.warehouse_id = $2 AND l.status = $3 AND l.lpn_id <> $4) AS "on_lpns!""#,
+                item_id,
+                lpn.warehouse_id,
+                LPN_ACTIVE,
+                id
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            let loose = stock.on_hand - stock.on_lpns;
+            if content.quantity > loose {
+                return Err(violation(format!(
+                    "only {} of item {} in warehouse {} is not on another LPN",
+                    loose.max(Decimal::ZERO),
+                    item_id,
+                    lpn.warehouse_id
+                )));
+            }
+
+            sqlx::query!(
+                "INSERT INTO warehouse.lpn_contents (lpn_id, item_id, quantity) VALUES ($1, $2, $3)
+                 ON CONFLICT (lpn_id, item_id) DO UPDATE SET quantity = EXCLUDED.quantity",
+                id,
+                item_id,
+                content.quantity
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query!("UPDATE warehouse.lpns SET updated_at = NOW() WHERE lpn_id = $1", id).execute(&mut *tx).await?;
+
+        let detail = load_lpn(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Move an LPN and everything nested in it to a location, or nest it onto another LPN
+    /// where it takes that LPN's location. Returns None when the LPN does not exist.
+    pub async fn move_lpn(&self, id: i32, target: MoveLpn) -> Result<Option<LpnDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(lpn) = lock(&mut tx, id).await? else {
+            return Ok(None);
+        };
+        ensure_active(&lpn)?;
+
+        let location_id = match target.parent_lpn_id {
+            Some(parent_id) => {
+                let parent = lock_active(&mut tx, parent_id).await?;
+                if parent.warehouse_id != lpn.warehouse_id {
+                    return Err(violation(format!("{} is in another warehouse", parent.lpn_number)));
+                }
+                if subtree(&mut tx, &[id]).await?.contains(&parent_id) {
+                    return Err(violation(format!("{} is nested inside {}", parent.lpn_number, lpn.lpn_number)));
+                }
+                parent.location_id
+            }
+            None => {
+                ensure_location(&mut tx, lpn.warehouse_id, target.location_id).await?;
+                target.location_id
+            }
+        };
+
+        sqlx::query!(
+            "UPDATE warehouse.lpns SET parent_lpn_id = $2, updated_at = NOW() WHERE lpn_id = $1",
+            id,
+            target.parent_lpn_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        let moved = subtree(&mut tx, &[id]).await?;
+        sqlx::query!(
+            "UPDATE warehouse.lpns SET location_id = $2, updated_at = NOW() WHERE lpn_id = ANY($1)",
+            &moved,
+            location_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let detail = load_lpn(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Combined contents of active LPNs by number, for sizing a parcel before shipping them
+    pub async fn totals(&self, warehouse_id: i32, numbers: &[String]) -> Result<Vec<LpnContent>> {
+        let mut conn = self.pool.acquire().await?;
+        let ids = sqlx::query_scalar!(
+            "SELECT lpn_id FROM warehouse.lpns WHERE lpn_number = ANY($1) AND warehouse_id = $2 AND status = $3",
+            numbers,
+            warehouse_id,
+            LPN_ACTIVE
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        let tree = subtree(&mut conn, &ids).await?;
+        totals(&mut conn, &tree).await
+    }
+}
+
+/// Insert an LPN, numbering it from its id unless a barcode is given
+pub(crate) async fn insert_lpn(
+    conn: &mut PgConnection,
+    warehouse_id: i32,
+    lpn_number: Option<&str>,
+    lpn_type: &str,
+    location_id: Option<i32>,
+    parent_lpn_id: Option<i32>,
+    receipt_id: Option<i32>,
+) -> Result<i32> {
+    if let Some(number) = lpn_number {
+        if number_exists(conn, number).await? {
+            return Err(violation(format!("LPN {} is already in use", number)));
+        }
+    }
+    ensure_location(conn, warehouse_id, location_id).await?;
+
+    let id = sqlx::query_scalar!(
+        "INSERT INTO warehouse.lpns (lpn_id, lpn_number, lpn_type, warehouse_id, location_id, parent_lpn_id, receipt_id)
+         SELECT next_id, COALESCE($1, 'LPN' || LPAD(next_id::TEXT, 8, '0')), $2, $3, $4, $5, $6
+         FROM (SELECT nextval('warehouse.lpns_lpn_id_seq')::INT AS next_id) seq
+         RETURNING lpn_id",
+        lpn_number,
+        lpn_type,
+        warehouse_id,
+        location_id,
+        parent_lpn_id,
+        receipt_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(id)
+}
+
+/// Ship LPNs whole with their nested LPNs. Their contents are allocated to the order lines
+/// for the same items, after what `lines` already ships; the returned lines are to be
+/// shipped alongside those.
+pub(crate) async fn ship_lpns(
+    conn: &mut PgConnection,
+    detail: &OrderDetail,
+    shipment_id: i32,
+    numbers: &[String],
+    lines: &[ShipOrderLine],
+) -> Result<Vec<ShipOrderLine>> {
+    let order = &detail.order;
+    let lpns = sqlx::query_as!(Lpn, "SELECT * FROM warehouse.lpns WHERE lpn_number = ANY($1) FOR UPDATE", numbers)
+        .fetch_all(&mut *conn)
+        .await?;
+    if let Some(unknown) = numbers.iter().find(|number| !lpns.iter().any(|lpn| &lpn.lpn_number == *number)) {
+        return Err(violation(format!("LPN {} does not exist", unknown)));
+    }
+    for lpn in &lpns {
+        ensure_active(lpn)?;
+        if lpn.warehouse_id != order.warehouse_id {
+            return Err(violation(format!(
+                "{} is not in the warehouse {} ships from",
+                lpn.lpn_number, order.order_number
+            )));
+        }
+    }
+
+    let roots: Vec<i32> = lpns.iter().map(|lpn| lpn.lpn_id).collect();
+    let tree = subtree(conn, &roots).await?;
+
+    let mut outstanding: Vec<(i32, i32, Decimal)> = detail
+        .lines
+        .iter()
+        .map(|line| {
+            let shipping: Decimal = lines.iter().filter(|l| l.line_id == line.line_id).map(|l| l.quantity).sum();
+            (line.line_id, line.item_id, line.quantity_ordered - line.quantity_shipped - shipping)
+        })
+        .collect();
+    let mut allocated = Vec::new();
+    for content in totals(conn, &tree).await? {
+        let mut remaining = content.quantity;
+        for (line_id, item_id, open) in outstanding.iter_mut() {
+            if *item_id != content.item_id || *open <= Decimal::ZERO || remaining.is_zero() {
+                continue;
+            }
+            let quantity = remaining.min(*open);
+            allocated.push(ShipOrderLine { line_id: *line_id, quantity, weight_kg: None, lot_number: None });
+            *open -= quantity;
+            remaining -= quantity;
+        }
+        if remaining > Decimal::ZERO {
+            return Err(violation(format!(
+                "the LPNs hold {} of {} beyond what {} still needs",
+                remaining, content.item_code, order.order_number
+            )));
+        }
+    }
+
+    // Plates shipped off a pallet that stays behind come off that pallet
+    sqlx::query!(
+        "UPDATE warehouse.lpns SET parent_lpn_id = NULL WHERE lpn_id = ANY($1) AND parent_lpn_id <> ALL($2)",
+        &roots,
+        &tree
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "UPDATE warehouse.lpns SET status = $2, shipment_id = $3, location_id = NULL, updated_at = NOW()
+         WHERE lpn_id = ANY($1)",
+        &tree,
+        LPN_SHIPPED,
+        shipment_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(allocated)
+}
+
+async fn number_exists(conn: &mut PgConnection, number: &str) -> Result<bool> {
+    let exists = sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM warehouse.lpns WHERE lpn_number = $1)", number)
+        .fetch_one(&mut *conn)
+        .await?
+        .unwrap_or(false);
+
+    Ok(exists)
+}
+
+async fn ensure_location(conn: &mut PgConnection, warehouse_id: i32, location_id: Option<i32>) -> Result<()> {
+    let Some(location_id) = location_id else {
+        return Ok(());
+    };
+    let in_warehouse = sqlx::query_scalar!(
+        "SELECT EXISTS(SELECT 1 FROM warehouse.locations l JOIN warehouse.zones z ON z.zone_id = l.zone_id
+                       WHERE l.location_id = $1 AND z.warehouse_id = $2 AND l.is_active)",
+        location_id,
+        warehouse_id
+    )
+    .fetch_one(&mut *conn)
+    .await?
+    .unwrap_or(false);
+    if !in_warehouse {
+        return Err(violation(format!(
+            "location {} is not an active location in warehouse {}",
+            location_id, warehouse_id
+        )));
+    }
+    Ok(())
+}
+
+fn ensure_active(lpn: &Lpn) -> Result<()> {
+    if lpn.status != LPN_ACTIVE {
+        return Err(violation(format!("{} has been {}", lpn.lpn_number, lpn.status.to_lowercase())));
+    }
+    Ok(())
+}
+
+async fn lock(conn: &mut PgConnection, id: i32) -> Result<Option<Lpn>> {
+    let lpn = sqlx::query_as!(Lpn, "SELECT * FROM warehouse.lpns WHERE lpn_id = $1 FOR UPDATE", id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    Ok(lpn)
+}
+
+async fn lock_active(conn: &mut PgConnection, id: i32) -> Result<Lpn> {
+    let lpn = lock(conn, id).await?.ok_or_else(|| violation(format!("LPN {} does not exist", id)))?;
+    ensure_active(&lpn)?;
+    Ok(lpn)
+}
+
+/// The given LPNs and every LPN nested in them, at any depth
+async fn subtree(conn: &mut PgConnection, ids: &[i32]) -> Result<Vec<i32>> {
+    let tree = sqlx::query_scalar!(
+        r#"WITH RECURSIVE tree AS (
+               SELECT lpn_id FROM warehouse.lpns WHERE lpn_id = ANY($1)
+               UNION
+               SELECT l.lpn_id FROM warehouse.lpns l JOIN tree t ON l.parent_lpn_id = t.lpn_id
+           )
+           SELECT lpn_id AS "lpn_id!" FROM tree"#,
+        ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(tree)
+}
+
+async fn totals(conn: &mut PgConnection, ids: &[i32]) -> Result<Vec<LpnContent>> {
+    let totals = sqlx::query_as!(
+        LpnContent,
+        r#"SELECT c.item_id, i.item_code, SUM(c.quantity) AS "quantity!"
+           FROM warehouse.lpn_contents c
+           JOIN warehouse.items i ON i.item_id = c.item_id
+           WHERE c.lpn_id = ANY($1)
+           GROUP BY c.item_id, i.item_code
+           ORDER BY i.item_code"#,
+        ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(totals)
+}
+
+pub(crate) async fn load_lpn(conn: &mut PgConnection, id: i32) -> Result<Option<LpnDetail>> {
+    let Some(lpn) =
+        sqlx::query_as!(Lpn, "SELECT * FROM warehouse.lpns WHERE lpn_id = $1", id).fetch_optional(&mut *conn).await?
+    else {
+        return Ok(None);
+    };
+
+    let contents = totals(conn, &[id]).await?;
+    let children =
+        sqlx::query_as!(Lpn, "SELECT * FROM warehouse.lpns WHERE parent_lpn_id = $1 ORDER BY lpn_number", id)
+            .fetch_all(&mut *conn)
+            .await?;
+    let tree = subtree(conn, &[id]).await?;
+    let totals = totals(conn, &tree).await?;
+
+    Ok(Some(LpnDetail { lpn, contents, children, totals }))
+}
@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::migrate::Migrator;
+use sqlx::PgPool;
+use warehouse_models::*;
+
+/// The migrations shipped with this binary
+pub(crate) static MIGRATOR: Migrator = sqlx::migrate!("../migrations");
+
+/// A row of sqlx's `_sqlx_migrations` bookkeeping table
+type AppliedRow = (i64, String, DateTime<Utc>, bool, Vec<u8>, i64);
+
+#[derive(Clone)]
+pub struct MigrationRepository {
+    pool: PgPool,
+}
+
+impl MigrationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Embedded migrations next to what the database has recorded as applied
+    pub async fn status(&self) -> Result<MigrationStatus> {
+        let recorded: Option<String> = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations')::TEXT")
+            .fetch_one(&self.pool)
+            .await?;
+        let applied: Vec<AppliedRow> = match recorded {
+            Some(_) => {
+                sqlx::query_as(
+                    "SELECT version, description, installed_on, success, checksum, execution_time
+                     FROM _sqlx_migrations ORDER BY version",
+                )
+                .fetch_all(&self.pool)
+                .await?
+            }
+            None => Vec::new(),
+        };
+
+        let mut entries: BTreeMap<i64, MigrationEntry> = MIGRATOR
+            .iter()
+            .map(|migration| {
+                let entry = MigrationEntry {
+                    version: migration.version,
+                    description: migration.description.to_string(),
+                    checksum: hex(&migration.checksum),
+                    applied: false,
+                    applied_at: None,
+                    success: None,
+                    execution_time_ms: None,
+                    checksum_matches: true,
+                };
+                (migration.version, entry)
+            })
+            .collect();
+        for (version, description, installed_on, success, checksum, execution_time) in applied {
+            let embedded = entries.get(&version).map(|entry| entry.checksum.clone());
+            let checksum = hex(&checksum);
+            entries.insert(
+                version,
+                MigrationEntry {
+                    version,
+                    description,
+                    checksum_matches: embedded.as_deref() == Some(checksum.as_str()),
+                    checksum,
+                    applied: true,
+                    applied_at: Some(installed_on),
+                    success: Some(success),
+                    // Recorded in nanoseconds
+                    execution_time_ms: Some(execution_time / 1_000_000),
+                },
+            );
+        }
+
+        let migrations: Vec<MigrationEntry> = entries.into_values().collect();
+        Ok(MigrationStatus {
+            current_version: migrations
+                .iter()
+                .filter(|migration| migration.success == Some(true))
+                .map(|migration| migration.version)
+                .max(),
+            applied: migrations.iter().filter(|migration| migration.applied).count(),
+            pending: migrations.iter().filter(|migration| !migration.applied).count(),
+            mismatched: migrations.iter().filter(|migration| migration.applied && !migration.checksum_matches).count(),
+            migrations,
+        })
+    }
+
+    /// Apply the pending migrations. Fails without applying anything further if an applied
+    /// migration was changed or is missing from the binary.
+    pub async fn run(&self) -> Result<MigrationRun> {
+        let pending = self.status().await?.pending_versions();
+        MIGRATOR.run(&self.pool).await?;
+        let status = self.status().await?;
+
+        let still_pending = status.pending_versions();
+        let applied_versions = pending.into_iter().filter(|version| !still_pending.contains(version)).collect();
+        Ok(MigrationRun { applied_versions, status })
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -1,12 +1,147 @@
 //! Repository modules for database access
 
+pub mod api_keys;
+pub mod audit;
+pub mod backups;
+pub mod billing;
+pub mod cartonization;
+pub mod catch_weight;
+pub mod clients;
+pub mod consignment;
+pub mod consumption;
+pub mod customs;
+pub mod deliveries;
+pub mod dock;
+pub mod documents;
+pub mod ecommerce;
+pub mod edi;
+pub mod erp;
+pub mod event_consumers;
+pub mod environment;
+pub mod fleet;
+pub mod gl;
+pub mod hazmat;
+pub mod integrations;
+pub mod integrity;
+pub mod items;
+pub mod jobs;
+pub mod kpis;
+pub mod labor;
+pub mod ledger;
+pub mod loans;
+pub mod locations;
+pub mod lpns;
+pub mod migrations;
+pub mod movements;
+pub mod negative_stock;
+pub mod notifications;
+pub mod orders;
+pub mod packaging;
+pub mod postings;
+pub mod preferences;
+pub mod printers;
+pub mod projects;
+pub mod purchasing;
+pub mod quality;
+pub mod recalls;
+pub mod reconciliation;
+pub mod refresh_tokens;
+pub mod read_models;
+pub mod receipts;
+pub mod repairs;
+pub mod reports;
+pub mod reservations;
+pub mod returnables;
+pub mod returns;
+pub mod rfid;
+pub mod sagas;
+pub mod shards;
+pub mod shifts;
+pub mod stations;
+pub mod stock;
+pub mod stock_events;
+pub mod stock_outs;
+pub mod sync;
+pub mod tasks;
+pub mod templates;
+pub mod tenants;
+pub mod tracking;
+pub mod transfers;
+pub mod users;
+pub mod views;
+pub mod vmi;
 pub mod warehouses;
-// Comment out repositories that are not implemented yet
-// pub mod items;
-// pub mod projects;
-// pub mod stock;
+pub mod waves;
 
+pub use api_keys::ApiKeyRepository;
+pub use audit::AuditRepository;
+pub use backups::{BackupRepository, RestoreSession};
+pub use billing::BillingRepository;
+pub use cartonization::CartonizationRepository;
+pub use catch_weight::CatchWeightRepository;
+pub use clients::ClientRepository;
+pub use consignment::ConsignmentRepository;
+pub use consumption::ConsumptionRepository;
+pub use customs::CustomsRepository;
+pub use deliveries::DeliveryRepository;
+pub use dock::DockRepository;
+pub use documents::DocumentRepository;
+pub use ecommerce::EcommerceRepository;
+pub use edi::EdiRepository;
+pub use erp::ErpRepository;
+pub use event_consumers::EventConsumerRepository;
+pub use environment::EnvironmentRepository;
+pub use fleet::FleetRepository;
+pub use gl::GlRepository;
+pub use hazmat::HazmatRepository;
+pub use integrations::IntegrationRepository;
+pub use integrity::IntegrityRepository;
+pub use items::ItemRepository;
+pub use jobs::JobRepository;
+pub use kpis::KpiRepository;
+pub use labor::LaborRepository;
+pub use ledger::LedgerRepository;
+pub use loans::LoanRepository;
+pub use locations::LocationRepository;
+pub use lpns::LpnRepository;
+pub use migrations::MigrationRepository;
+pub use movements::{post_movement, MovementRepository};
+pub use negative_stock::NegativeStockRepository;
+pub use notifications::NotificationRepository;
+pub use orders::OrderRepository;
+pub use packaging::PackagingRepository;
+pub use postings::PostingRepository;
+pub use preferences::PreferenceRepository;
+pub use printers::PrinterRepository;
+pub use projects::ProjectRepository;
+pub use purchasing::PurchasingRepository;
+pub use quality::QualityRepository;
+pub use recalls::RecallRepository;
+pub use reconciliation::ReconciliationRepository;
+pub use refresh_tokens::RefreshTokenRepository;
+pub use read_models::ReadModelRepository;
+pub use receipts::ReceiptRepository;
+pub use repairs::RepairRepository;
+pub use reports::ReportRepository;
+pub use reservations::ReservationRepository;
+pub use returnables::ReturnableRepository;
+pub use returns::ReturnRepository;
+pub use rfid::RfidRepository;
+pub use sagas::SagaRepository;
+pub use shards::ShardRepository;
+pub use shifts::ShiftRepository;
+pub use stations::StationRepository;
+pub use stock::StockRepository;
+pub use stock_events::StockEventRepository;
+pub use stock_outs::StockOutRepository;
+pub use sync::SyncRepository;
+pub use tasks::TaskRepository;
+pub use templates::TemplateRepository;
+pub use tenants::TenantRepository;
+pub use tracking::TrackingRepository;
+pub use transfers::TransferRepository;
+pub use users::UserRepository;
+pub use views::ViewRepository;
+pub use vmi::VmiRepository;
 pub use warehouses::WarehouseRepository;
-// pub use items::ItemRepository;
-// pub use projects::ProjectRepository;  
-// pub use stock::StockRepository;
+pub use waves::WaveRepository;
@@ -0,0 +1,407 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::error::ErrorKind;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::catch_weight::profile;
+use crate::repositories::ledger::post_transaction;
+use crate::repositories::negative_stock::enforce_policy;
+use crate::store::*;
+use crate::utils::*;
+
+#[derive(Clone)]
+pub struct MovementRepository {
+    pool: PgPool,
+}
+
+impl MovementRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl MovementStore for MovementRepository {
+    async fn history(
+        &self,
+        filter: MovementFilter,
+        keyset: KeysetQuery,
+    ) -> Result<KeysetPage<StockMovement>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            StockMovement,
+            "SELECT movement_id, item_id, warehouse_id, movement_type, quantity, catch_weight_kg, lot_number, unit_cost,
+                    stock_status, consignor_id, client_id, reference_type, reference_number, notes, movement_date,
+                    created_at, created_by
+             FROM warehouse.stock_movements
+             WHERE ($1::BIGINT IS NULL OR movement_id < $1)
+               AND ($2::INT IS NULL OR item_id = $2)
+               AND ($3::INT IS NULL OR warehouse_id = $3)
+               AND ($4::VARCHAR IS NULL OR movement_type = $4)
+               AND ($5::INT IS NULL OR client_id = $5)
+             ORDER BY movement_id DESC
+             LIMIT $6",
+            after_id,
+            filter.item_id,
+            filter.warehouse_id,
+            filter.movement_type,
+            filter.client_id,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |m| m.movement_id))
+    }
+}
+
+/// Append a movement to the ledger and apply it to the item's stock balance in the
+/// movement's bucket. Runs on the caller's connection so it joins the caller's transaction.
+///
+/// When an issue of our own available stock exceeds what we own, the shortfall is first
+/// taken into our ownership from consignment stock of the same item in the warehouse,
+/// lowest consignor first, at the agreed price.
+pub async fn post_movement(conn: &mut PgConnection, movement: NewMovement) -> Result<StockMovement> {
+    if movement.quantity < Decimal::ZERO
+        && movement.stock_status == STOCK_AVAILABLE
+        && movement.consignor_id.is_none()
+    {
+        consume_shortfall(conn, &movement).await?;
+    }
+
+    apply_movement(conn, movement, None).await
+}
+
+/// Post goods arriving out of transit from another warehouse. The in-transit leg is
+/// taken off the sending warehouse, where the dispatch put it.
+pub(crate) async fn post_arrival(
+    conn: &mut PgConnection,
+    movement: NewMovement,
+    from_warehouse_id: i32,
+) -> Result<StockMovement> {
+    apply_movement(conn, movement, Some(from_warehouse_id)).await
+}
+
+/// Move consigned stock into our ownership: out of the consignor's balance and into ours
+/// as a pair of `CONSIGNMENT_CONSUME` movements. Returns the consignor-side movement.
+pub(crate) async fn consume_consignment(
+    conn: &mut PgConnection,
+    consignor_id: i32,
+    item_id: i32,
+    warehouse_id: i32,
+    quantity: Decimal,
+    reference: Option<(String, String)>,
+    notes: Option<String>,
+) -> Result<StockMovement> {
+    let unit_price = sqlx::query_scalar!(
+        "SELECT unit_price FROM warehouse.consignment_prices WHERE consignor_id = $1 AND item_id = $2",
+        consignor_id,
+        item_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| violation(format!("consignor {} has no agreed price for item {}", consignor_id, item_id)))?;
+
+    let (reference_type, reference_number) = reference.unzip();
+    let consumed = apply_movement(
+        conn,
+        NewMovement {
+            item_id,
+            warehouse_id,
+            movement_type: MOVEMENT_CONSIGNMENT_CONSUME.to_string(),
+            quantity: -quantity,
+            catch_weight_kg: None,
+            lot_number: None,
+            unit_cost: Some(unit_price),
+            stock_status: STOCK_AVAILABLE,
+            consignor_id: Some(consignor_id),
+            reference_type: reference_type.clone(),
+            reference_number: reference_number.clone(),
+            notes: notes.clone(),
+            created_by: acting_user(),
+        },
+        None,
+    )
+    .await?;
+
+    apply_movement(
+        conn,
+        NewMovement {
+            item_id,
+            warehouse_id,
+            movement_type: MOVEMENT_CONSIGNMENT_CONSUME.to_string(),
+            quantity,
+            catch_weight_kg: None,
+            lot_number: None,
+            unit_cost: Some(unit_price),
+            stock_status: STOCK_AVAILABLE,
+            consignor_id: None,
+            reference_type,
+            reference_number,
+            notes,
+            created_by: acting_user(),
+        },
+        None,
+    )
+    .await?;
+
+    Ok(consumed)
+}
+
+async fn consume_shortfall(conn: &mut PgConnection, movement: &NewMovement) -> Result<()> {
+    let owned = sqlx::query_scalar!(
+        "SELECT quantity_available FROM warehouse.stock_inventory
+         WHERE item_id = $1 AND warehouse_id = $2 AND consignor_id IS NULL
+         FOR UPDATE",
+        movement.item_id,
+        movement.warehouse_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .flatten()
+    .unwrap_or_default();
+
+    let mut shortfall = -movement.quantity - owned.max(Decimal::ZERO);
+    if shortfall <= Decimal::ZERO {
+        return Ok(());
+    }
+
+    let consigned = sqlx::query!(
+        r#"SELECT consignor_id AS "consignor_id!", quantity_available AS "quantity_available!"
+           FROM warehouse.stock_inventory
+           WHERE item_id = $1 AND warehouse_id = $2 AND consignor_id IS NOT NULL AND quantity_available > 0
+           ORDER BY consignor_id
+           FOR UPDATE"#,
+        movement.item_id,
+        movement.warehouse_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let reference = movement.reference_type.clone().zip(movement.reference_number.clone());
+    for row in consigned {
+        if shortfall <= Decimal::ZERO {
+            break;
+        }
+        let take = shortfall.min(row.quantity_available);
+        consume_consignment(
+            conn,
+            row.consignor_id,
+            movement.item_id,
+            movement.warehouse_id,
+            take,
+            reference.clone(),
+            Some(format!("consumed by {}", movement.movement_type)),
+        )
+        .await?;
+        shortfall -= take;
+    }
+
+    Ok(())
+}
+
+/// Apply a movement to its balance and post it. The contra leg goes to `contra_warehouse_id`,
+/// the movement's own warehouse when None.
+async fn apply_movement(
+    conn: &mut PgConnection,
+    movement: NewMovement,
+    contra_warehouse_id: Option<i32>,
+) -> Result<StockMovement> {
+    let quarantined = movement.stock_status == STOCK_QUARANTINE;
+    let catch_weight = match movement.catch_weight_kg {
+        Some(weight) => Some(weight),
+        None => profile(conn, movement.item_id).await?.map(|p| p.nominal_weight_kg * movement.quantity),
+    };
+
+    // The UPDATE takes the balance row's lock, so concurrent postings to one balance queue
+    // up and each sees what the previous one left: the CHECK constraints and the negative
+    // stock policy judge that balance, never a stale read
+    let mut balance = update_balance(conn, &movement, quarantined, catch_weight).await;
+    if let Ok(None) = balance {
+        // First movement of this balance. Another one may be creating the row at the same
+        // time, so add an empty row unless there is one and update whichever won.
+        sqlx::query!(
+            "INSERT INTO warehouse.stock_inventory (item_id, warehouse_id, consignor_id) VALUES ($1, $2, $3)
+             ON CONFLICT ON CONSTRAINT stock_inventory_item_warehouse_owner_key DO NOTHING",
+            movement.item_id,
+            movement.warehouse_id,
+            movement.consignor_id
+        )
+        .execute(&mut *conn)
+        .await?;
+        balance = update_balance(conn, &movement, quarantined, catch_weight).await;
+    }
+
+    let insufficient = || {
+        violation(format!(
+            "insufficient {} stock of item {} in warehouse {}",
+            movement.stock_status.to_lowercase(),
+            movement.item_id,
+            movement.warehouse_id
+        ))
+    };
+    let on_hand = match balance {
+        Err(sqlx::Error::Database(e)) if e.kind() == ErrorKind::CheckViolation => return Err(insufficient()),
+        other => other?.expect("balance row exists"),
+    };
+    // Whether our own stock may go negative is up to the policy; consigned stock never may
+    if !quarantined && movement.quantity < Decimal::ZERO && on_hand < Decimal::ZERO {
+        if movement.consignor_id.is_some() {
+            return Err(insufficient());
+        }
+        enforce_policy(conn, movement.item_id, movement.warehouse_id, on_hand).await?;
+    }
+
+    let posted = sqlx::query_as!(
+        StockMovement,
+        "INSERT INTO warehouse.stock_movements (
+             item_id, warehouse_id, movement_type, quantity, unit_cost, stock_status, consignor_id,
+             reference_type, reference_number, notes, created_by, catch_weight_kg, lot_number
+         ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+         RETURNING movement_id, item_id, warehouse_id, movement_type, quantity, catch_weight_kg, lot_number, unit_cost,
+                   stock_status, consignor_id, client_id, reference_type, reference_number, notes, movement_date,
+                   created_at, created_by",
+        movement.item_id,
+        movement.warehouse_id,
+        movement.movement_type,
+        movement.quantity,
+        movement.unit_cost,
+        movement.stock_status,
+        movement.consignor_id,
+        movement.reference_type,
+        movement.reference_number,
+        movement.notes,
+        movement.created_by,
+        catch_weight,
+        movement.lot_number
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let legs = [
+        (bucket_account(&posted.stock_status), posted.warehouse_id, posted.quantity),
+        (
+            contra_account(&posted.movement_type),
+            contra_warehouse_id.unwrap_or(posted.warehouse_id),
+            -posted.quantity,
+        ),
+    ];
+    let entries = legs
+        .map(|(account, warehouse_id, quantity)| NewLedgerEntry {
+            item_id: posted.item_id,
+            warehouse_id,
+            consignor_id: posted.consignor_id,
+            account,
+            quantity,
+        })
+        .to_vec();
+    post_transaction(
+        conn,
+        Some(posted.movement_id),
+        posted.reference_type.as_deref(),
+        posted.reference_number.as_deref(),
+        entries,
+    )
+    .await?;
+
+    Ok(posted)
+}
+
+/// Move a balance row by a movement's quantity and weight. Returns the new on-hand
+/// quantity, or None when the row does not exist yet.
+async fn update_balance(
+    conn: &mut PgConnection,
+    movement: &NewMovement,
+    quarantined: bool,
+    catch_weight: Option<Decimal>,
+) -> sqlx::Result<Option<Decimal>> {
+    sqlx::query_scalar!(
+        "UPDATE warehouse.stock_inventory SET
+             quantity_on_hand = quantity_on_hand + CASE WHEN $4 THEN 0 ELSE $3::DECIMAL END,
+             quantity_quarantined = quantity_quarantined + CASE WHEN $4 THEN $3::DECIMAL ELSE 0 END,
+             catch_weight_kg = CASE WHEN $6::DECIMAL IS NULL THEN catch_weight_kg ELSE COALESCE(catch_weight_kg, 0) + $6 END,
+             last_movement_date = CURRENT_DATE,
+             updated_at = NOW()
+         WHERE item_id = $1 AND warehouse_id = $2 AND consignor_id IS NOT DISTINCT FROM $5
+         RETURNING quantity_on_hand",
+        movement.item_id,
+        movement.warehouse_id,
+        movement.quantity,
+        quarantined,
+        movement.consignor_id,
+        catch_weight
+    )
+    .fetch_optional(&mut *conn)
+    .await
+}
+
+/// Lock the balances of `item_ids` in a warehouse, every owner's, in item order. Callers
+/// about to post movements for several items take these locks first, so two transactions
+/// touching the same items in a different order wait for each other instead of deadlocking.
+pub(crate) async fn lock_stock(conn: &mut PgConnection, warehouse_id: i32, item_ids: &[i32]) -> Result<()> {
+    sqlx::query!(
+        "SELECT stock_id FROM warehouse.stock_inventory
+         WHERE warehouse_id = $1 AND item_id = ANY($2)
+         ORDER BY item_id, consignor_id NULLS FIRST
+         FOR UPDATE",
+        warehouse_id,
+        item_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Move `quantity` of our own stock of an item from the ON_HAND bucket to the RESERVED
+/// one, or back when negative, posting the move under the given ledger reference. The
+/// caller holds the stock lock and has checked availability; the balance CHECK keeps
+/// reserved stock on hand regardless.
+pub(crate) async fn move_reserved(
+    conn: &mut PgConnection,
+    warehouse_id: i32,
+    item_id: i32,
+    quantity: Decimal,
+    reference_type: &str,
+    reference_number: Option<&str>,
+) -> Result<()> {
+    if quantity.is_zero() {
+        return Ok(());
+    }
+    sqlx::query!(
+        "UPDATE warehouse.stock_inventory
+         SET quantity_reserved = quantity_reserved + $3, updated_at = NOW()
+         WHERE item_id = $1 AND warehouse_id = $2 AND consignor_id IS NULL",
+        item_id,
+        warehouse_id,
+        quantity
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let entries = [(ACCOUNT_ON_HAND, -quantity), (ACCOUNT_RESERVED, quantity)]
+        .map(|(account, quantity)| NewLedgerEntry { item_id, warehouse_id, consignor_id: None, account, quantity })
+        .to_vec();
+    post_transaction(conn, None, Some(reference_type), reference_number, entries).await?;
+    Ok(())
+}
+
+pub(crate) async fn load_movement(conn: &mut PgConnection, movement_id: i64) -> Result<StockMovement> {
+    let movement = sqlx::query_as!(
+        StockMovement,
+        "SELECT movement_id, item_id, warehouse_id, movement_type, quantity, catch_weight_kg, lot_number, unit_cost,
+                stock_status, consignor_id, client_id, reference_type, reference_number, notes, movement_date,
+                created_at, created_by
+         FROM warehouse.stock_movements
+         WHERE movement_id = $1",
+        movement_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(movement)
+}
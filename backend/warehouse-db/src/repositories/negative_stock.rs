@@ -0,0 +1,105 @@
+use crate::error::NegativeStock;
+use crate::repositories::notifications::insert_notification;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct NegativeStockRepository {
+    pool: PgPool,
+}
+
+impl NegativeStockRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// The warehouse-wide policy first, then item policies by item
+    pub async fn list(&self, warehouse_id: i32) -> Result<Vec<NegativeStockPolicy>> {
+        let policies = sqlx::query_as!(
+            NegativeStockPolicy,
+            "SELECT policy_id, warehouse_id, item_id, policy, updated_at
+             FROM warehouse.negative_stock_policies
+             WHERE warehouse_id = $1
+             ORDER BY item_id NULLS FIRST",
+            warehouse_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(policies)
+    }
+
+    /// Set the policy of a warehouse, or of one item in it
+    pub async fn set(&self, warehouse_id: i32, item_id: Option<i32>, policy: &str) -> Result<NegativeStockPolicy> {
+        let set = sqlx::query_as!(
+            NegativeStockPolicy,
+            "INSERT INTO warehouse.negative_stock_policies (warehouse_id, item_id, policy)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (warehouse_id, item_id) DO UPDATE SET policy = EXCLUDED.policy, updated_at = NOW()
+             RETURNING policy_id, warehouse_id, item_id, policy, updated_at",
+            warehouse_id,
+            item_id,
+            policy
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(set)
+    }
+
+    /// Drop an item's own policy so the warehouse-wide one applies again
+    pub async fn clear(&self, warehouse_id: i32, item_id: i32) -> Result<bool> {
+        let deleted = sqlx::query!(
+            "DELETE FROM warehouse.negative_stock_policies WHERE warehouse_id = $1 AND item_id = $2",
+            warehouse_id,
+            item_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(deleted.rows_affected() > 0)
+    }
+}
+
+/// Apply the negative stock policy to a balance a movement has just taken below zero.
+/// Runs on the caller's connection, which holds the stock row locked, so the check and
+/// the posting stand or fall together.
+pub(crate) async fn enforce_policy(
+    conn: &mut PgConnection,
+    item_id: i32,
+    warehouse_id: i32,
+    on_hand: Decimal,
+) -> Result<()> {
+    let policy = sqlx::query_scalar!(
+        "SELECT policy FROM warehouse.negative_stock_policies
+         WHERE warehouse_id = $1 AND (item_id = $2 OR item_id IS NULL)
+         ORDER BY item_id NULLS LAST
+         LIMIT 1",
+        warehouse_id,
+        item_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    match policy.as_deref().unwrap_or(NEGATIVE_STOCK_FORBID) {
+        NEGATIVE_STOCK_ALLOW => Ok(()),
+        NEGATIVE_STOCK_WARN => {
+            insert_notification(
+                conn,
+                NewNotification {
+                    kind: NOTIFY_NEGATIVE_STOCK,
+                    severity: SEVERITY_WARNING,
+                    subject: format!("Item {} in warehouse {} is at {} on hand", item_id, warehouse_id, on_hand),
+                    message: None,
+                    reference_type: Some("ITEM".to_string()),
+                    reference_id: Some(item_id),
+                },
+            )
+            .await?;
+            Ok(())
+        }
+        _ => Err(NegativeStock { item_id, warehouse_id, on_hand }.into()),
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::*;
+use crate::utils::*;
+
+#[derive(Clone)]
+pub struct NotificationRepository {
+    pool: PgPool,
+}
+
+impl NotificationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn raise(&self, notification: NewNotification) -> Result<Notification> {
+        let mut conn = self.pool.acquire().await?;
+        insert_notification(&mut conn, notification).await
+    }
+
+    /// Notifications newest first, paged by `notification_id`
+    pub async fn list(&self, filter: NotificationFilter, keyset: KeysetQuery) -> Result<KeysetPage<Notification>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            Notification,
+            "SELECT * FROM warehouse.notifications
+             WHERE ($1::BIGINT IS NULL OR notification_id < $1)
+               AND ($2::VARCHAR IS NULL OR kind = $2)
+               AND ($3::BOOLEAN IS NULL OR (acknowledged_at IS NOT NULL) = $3)
+             ORDER BY notification_id DESC
+             LIMIT $4",
+            after_id,
+            filter.kind,
+            filter.acknowledged,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |n| n.notification_id))
+    }
+
+    pub async fn get_by_id(&self, id: i64) -> Result<Option<Notification>> {
+        let notification = sqlx::query_as!(
+            Notification,
+            "SELECT * FROM warehouse.notifications WHERE notification_id = $1",
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(notification)
+    }
+
+    /// Mark a notification as handled; acknowledging twice keeps the first time
+    pub async fn acknowledge(&self, id: i64) -> Result<Option<Notification>> {
+        let notification = sqlx::query_as!(
+            Notification,
+            "UPDATE warehouse.notifications
+             SET acknowledged_at = COALESCE(acknowledged_at, NOW())
+             WHERE notification_id = $1
+             RETURNING *",
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(notification)
+    }
+}
+
+/// Raise a notification on the caller's connection, so it only stands if the caller's
+/// transaction commits
+pub(crate) async fn insert_notification(conn: &mut PgConnection, notification: NewNotification) -> Result<Notification> {
+    let raised = sqlx::query_as!(
+        Notification,
+        "INSERT INTO warehouse.notifications (kind, severity, subject, message, reference_type, reference_id)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING *",
+        notification.kind,
+        notification.severity,
+        notification.subject,
+        notification.message,
+        notification.reference_type,
+        notification.reference_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(raised)
+}
@@ -0,0 +1,477 @@
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::catch_weight::check_weight;
+use crate::repositories::lpns::ship_lpns;
+use crate::repositories::movements::{lock_stock, post_movement};
+use crate::repositories::postings::{claim, record_result, Claim};
+use crate::utils::*;
+
+#[derive(Clone)]
+pub struct OrderRepository {
+    pool: PgPool,
+}
+
+impl OrderRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// List outbound orders, newest first
+    pub async fn list(&self, filter: OrderFilter, pagination: PaginationQuery) -> Result<PaginatedResponse<OutboundOrder>> {
+        let (page, limit) = validate_pagination(&pagination);
+        let offset = calculate_offset(page, limit);
+        let mode = pagination.count.unwrap_or_default();
+
+        let total = match mode {
+            CountMode::Exact => sqlx::query_scalar!(
+                "SELECT COUNT(*) FROM warehouse.outbound_orders
+                 WHERE ($1::VARCHAR IS NULL OR status = $1)
+                   AND ($2::INT IS NULL OR warehouse_id = $2)
+                   AND ($3::VARCHAR IS NULL OR order_type = $3)",
+                filter.status,
+                filter.warehouse_id,
+                filter.order_type
+            )
+            .fetch_one(&self.pool)
+            .await?,
+            CountMode::Estimate => Some(estimate_row_count(&self.pool, "warehouse.outbound_orders").await?),
+            CountMode::Skip => None,
+        };
+
+        let rows = sqlx::query_as!(
+            OutboundOrder,
+            "SELECT * FROM warehouse.outbound_orders
+             WHERE ($1::VARCHAR IS NULL OR status = $1)
+               AND ($2::INT IS NULL OR warehouse_id = $2)
+               AND ($3::VARCHAR IS NULL OR order_type = $3)
+             ORDER BY order_id DESC
+             LIMIT $4 OFFSET $5",
+            filter.status,
+            filter.warehouse_id,
+            filter.order_type,
+            limit + 1,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PaginatedResponse::from_probe(rows, total, mode, page, limit))
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<OrderDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_order(&mut conn, id, false).await
+    }
+
+    pub async fn create(&self, order: CreateOrder) -> Result<OrderDetail> {
+        self.create_from(order, SOURCE_API, None).await
+    }
+
+    /// Create an order received through a channel other than the JSON API
+    pub async fn create_from(
+        &self,
+        order: CreateOrder,
+        source: &str,
+        edi_partner_id: Option<String>,
+    ) -> Result<OrderDetail> {
+        let mut tx = self.pool.begin().await?;
+
+        let order_id = insert_order(&mut tx, order, source, edi_partner_id).await?;
+        let detail = load_order(&mut tx, order_id, false)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("order {} vanished after insert", order_id))?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Ship-confirm: issue the shipped quantities from available stock and record a
+    /// shipment, with the carrier label if one was bought. LPNs shipped whole add their
+    /// contents to the shipped lines. Returns None when the order does not exist.
+    pub async fn ship(
+        &self,
+        id: i32,
+        confirmation: ShipOrder,
+        label: Option<ShipmentLabel>,
+    ) -> Result<Option<(OrderDetail, ShipmentDetail)>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_order(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let reference_id = confirmation.reference_id.as_deref();
+        if let Claim::Replay(shipment_id) = claim(&mut tx, reference_id, POSTING_SHIPMENT, id).await? {
+            let shipment_id = shipment_id.expect("shipment recorded with its key") as i32;
+            let shipment = load_shipment(&mut tx, shipment_id).await?.expect("shipment recorded with its key");
+            return Ok(Some((detail, shipment)));
+        }
+        let order = &detail.order;
+
+        if order.status != ORDER_OPEN && order.status != ORDER_PARTIALLY_SHIPPED {
+            return Err(violation(format!("cannot ship a {} order", order.status.to_lowercase())));
+        }
+        if order.order_type == ORDER_TYPE_DROPSHIP {
+            return Err(violation(format!(
+                "{} is a dropship order; record the supplier's shipment instead",
+                order.order_number
+            )));
+        }
+
+        let shipment_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.shipments (
+                 shipment_id, shipment_number, order_id, warehouse_id, carrier_code, tracking_number, created_by,
+                 service_level, shipping_cost, currency, label_id, label_url, label_format
+             )
+             SELECT next_id, 'SHP-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11
+             FROM (SELECT nextval('warehouse.shipments_shipment_id_seq')::INT AS next_id) seq
+             RETURNING shipment_id",
+            order.order_id,
+            order.warehouse_id,
+            confirmation.carrier_code,
+            label.as_ref().map(|l| l.tracking_number.clone()).or(confirmation.tracking_number),
+            acting_user(), // created_by
+            label.as_ref().map(|l| l.service_level.clone()),
+            label.as_ref().and_then(|l| l.amount),
+            label.as_ref().and_then(|l| l.currency.clone()),
+            label.as_ref().map(|l| l.label_id.clone()),
+            label.as_ref().and_then(|l| l.label_url.clone()),
+            label.as_ref().and_then(|l| l.label_format.clone())
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let shipment_number = format!("SHP-{:06}", shipment_id);
+
+        let mut lines = confirmation.lines;
+        if !confirmation.lpns.is_empty() {
+            let lpn_lines = ship_lpns(&mut tx, &detail, shipment_id, &confirmation.lpns, &lines).await?;
+            lines.extend(lpn_lines);
+        }
+        ship_lines(&mut tx, &detail, shipment_id, &shipment_number, lines, true).await?;
+        record_result(&mut tx, reference_id, shipment_id.into()).await?;
+
+        let detail = load_order(&mut tx, id, false).await?.expect("order locked above");
+        let shipment = load_shipment(&mut tx, shipment_id).await?.expect("shipment inserted above");
+        tx.commit().await?;
+        Ok(Some((detail, shipment)))
+    }
+
+    /// Record the supplier's acceptance of a dropship order. Returns None when the order
+    /// does not exist.
+    pub async fn confirm_dropship(&self, id: i32, confirmation: SupplierConfirmation) -> Result<Option<OrderDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_order(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let order = &detail.order;
+
+        ensure_dropship(order)?;
+        if order.status != ORDER_OPEN {
+            return Err(violation(format!("cannot confirm a {} order", order.status.to_lowercase())));
+        }
+        if let Some(number) = &order.supplier_order_number {
+            if *number != confirmation.supplier_order_number {
+                return Err(violation(format!(
+                    "{} is already confirmed as supplier order {}",
+                    order.order_number, number
+                )));
+            }
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.outbound_orders SET
+                 supplier_order_number = $2,
+                 supplier_confirmed_at = COALESCE(supplier_confirmed_at, NOW()),
+                 updated_at = NOW()
+             WHERE order_id = $1",
+            id,
+            confirmation.supplier_order_number
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let detail = load_order(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Record a shipment the supplier made for a confirmed dropship order. The order's
+    /// shipped quantities move on as for a shipment from stock, but no stock is issued.
+    /// Returns None when the order does not exist.
+    pub async fn record_supplier_shipment(
+        &self,
+        id: i32,
+        shipment: SupplierShipment,
+    ) -> Result<Option<(OrderDetail, ShipmentDetail)>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_order(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let order = &detail.order;
+
+        ensure_dropship(order)?;
+        if order.status != ORDER_OPEN && order.status != ORDER_PARTIALLY_SHIPPED {
+            return Err(violation(format!("cannot ship a {} order", order.status.to_lowercase())));
+        }
+        if order.supplier_confirmed_at.is_none() {
+            return Err(violation(format!(
+                "{} has not been confirmed by {}",
+                order.order_number,
+                order.supplier_code.as_deref().unwrap_or_default()
+            )));
+        }
+
+        let shipment_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.shipments (
+                 shipment_id, shipment_number, order_id, warehouse_id, carrier_code, tracking_number, created_by,
+                 shipped_by_supplier
+             )
+             SELECT next_id, 'SHP-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4, $5, TRUE
+             FROM (SELECT nextval('warehouse.shipments_shipment_id_seq')::INT AS next_id) seq
+             RETURNING shipment_id",
+            order.order_id,
+            order.warehouse_id,
+            shipment.carrier_code.to_uppercase(),
+            shipment.tracking_number,
+            acting_user() // created_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let shipment_number = format!("SHP-{:06}", shipment_id);
+
+        ship_lines(&mut tx, &detail, shipment_id, &shipment_number, shipment.lines, false).await?;
+
+        let detail = load_order(&mut tx, id, false).await?.expect("order locked above");
+        let shipment = load_shipment(&mut tx, shipment_id).await?.expect("shipment inserted above");
+        tx.commit().await?;
+        Ok(Some((detail, shipment)))
+    }
+
+    pub async fn get_shipment(&self, id: i32) -> Result<Option<ShipmentDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_shipment(&mut conn, id).await
+    }
+
+    /// Shipments made against an order, oldest first
+    pub async fn shipments_for_order(&self, order_id: i32) -> Result<Vec<Shipment>> {
+        let shipments = sqlx::query_as!(
+            Shipment,
+            "SELECT * FROM warehouse.shipments WHERE order_id = $1 ORDER BY shipment_id",
+            order_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(shipments)
+    }
+}
+
+fn ensure_dropship(order: &OutboundOrder) -> Result<()> {
+    if order.order_type != ORDER_TYPE_DROPSHIP {
+        return Err(violation(format!("{} is not a dropship order", order.order_number)));
+    }
+    Ok(())
+}
+
+/// Add shipped quantities to a shipment and its order, issuing them from available stock
+/// unless a dropship supplier shipped them, and move the order to (partially) shipped
+async fn ship_lines(
+    conn: &mut PgConnection,
+    detail: &OrderDetail,
+    shipment_id: i32,
+    shipment_number: &str,
+    lines: Vec<ShipOrderLine>,
+    issue_stock: bool,
+) -> Result<()> {
+    let order = &detail.order;
+    let mut shipped_so_far: Vec<(i32, Decimal)> =
+        detail.lines.iter().map(|line| (line.line_id, line.quantity_shipped)).collect();
+    if issue_stock {
+        let item_ids: Vec<i32> = detail.lines.iter().map(|line| line.item_id).collect();
+        lock_stock(conn, order.warehouse_id, &item_ids).await?;
+    }
+
+    for shipped in lines {
+        let line = detail
+            .lines
+            .iter()
+            .find(|line| line.line_id == shipped.line_id)
+            .ok_or_else(|| violation(format!("line {} is not part of {}", shipped.line_id, order.order_number)))?;
+        let total = shipped_so_far
+            .iter_mut()
+            .find(|(line_id, _)| *line_id == line.line_id)
+            .map(|(_, quantity)| quantity)
+            .expect("shipped_so_far covers every line");
+        if *total + shipped.quantity > line.quantity_ordered {
+            return Err(violation(format!(
+                "line {} would ship {} of {} ordered",
+                line.line_number,
+                *total + shipped.quantity,
+                line.quantity_ordered
+            )));
+        }
+        *total += shipped.quantity;
+        let weight_kg = check_weight(&mut *conn, line.item_id, shipped.quantity, shipped.weight_kg).await?;
+
+        if issue_stock {
+            post_movement(
+                &mut *conn,
+                NewMovement {
+                    item_id: line.item_id,
+                    warehouse_id: order.warehouse_id,
+                    movement_type: "SHIPMENT".to_string(),
+                    quantity: -shipped.quantity,
+                    catch_weight_kg: weight_kg.map(|weight| -weight),
+                    lot_number: shipped.lot_number.clone(),
+                    unit_cost: None,
+                    stock_status: STOCK_AVAILABLE,
+                    consignor_id: None,
+                    reference_type: Some("SHIPMENT".to_string()),
+                    reference_number: Some(shipment_number.to_string()),
+                    notes: Some(order.order_number.clone()),
+                    created_by: order.created_by,
+                },
+            )
+            .await?;
+        }
+
+        sqlx::query!(
+            "INSERT INTO warehouse.shipment_lines (shipment_id, order_line_id, item_id, quantity, weight_kg, lot_number)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            shipment_id,
+            line.line_id,
+            line.item_id,
+            shipped.quantity,
+            weight_kg,
+            shipped.lot_number
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE warehouse.outbound_order_lines SET quantity_shipped = quantity_shipped + $2 WHERE line_id = $1",
+            line.line_id,
+            shipped.quantity
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    let fully_shipped = shipped_so_far
+        .iter()
+        .zip(&detail.lines)
+        .all(|((_, shipped), line)| *shipped >= line.quantity_ordered);
+    sqlx::query!(
+        "UPDATE warehouse.outbound_orders SET status = $2, updated_at = NOW() WHERE order_id = $1",
+        order.order_id,
+        if fully_shipped { ORDER_SHIPPED } else { ORDER_PARTIALLY_SHIPPED }
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Insert an order and its lines; returns the new order's id
+pub(crate) async fn insert_order(
+    conn: &mut PgConnection,
+    order: CreateOrder,
+    source: &str,
+    edi_partner_id: Option<String>,
+) -> Result<i32> {
+    let order_id = sqlx::query_scalar!(
+        "INSERT INTO warehouse.outbound_orders (
+             order_id, order_number, warehouse_id, customer_name, customer_reference,
+             ship_to_name, ship_to_address, ship_to_city, ship_to_state, ship_to_postal_code,
+             ship_to_country, requested_ship_date, source, edi_partner_id, created_by, carrier_code,
+             order_type, supplier_code
+         )
+         SELECT next_id, 'SO-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14,
+                $15, $16
+         FROM (SELECT nextval('warehouse.outbound_orders_order_id_seq')::INT AS next_id) seq
+         RETURNING order_id",
+        order.warehouse_id,
+        order.customer_name,
+        order.customer_reference,
+        order.ship_to_name,
+        order.ship_to_address,
+        order.ship_to_city,
+        order.ship_to_state,
+        order.ship_to_postal_code,
+        order.ship_to_country,
+        order.requested_ship_date,
+        source,
+        edi_partner_id,
+        acting_user(), // created_by
+        order.carrier_code,
+        if order.dropship_supplier_code.is_some() { ORDER_TYPE_DROPSHIP } else { ORDER_TYPE_STANDARD },
+        order.dropship_supplier_code.map(|code| code.to_uppercase())
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    for (index, line) in order.lines.into_iter().enumerate() {
+        sqlx::query!(
+            "INSERT INTO warehouse.outbound_order_lines (order_id, line_number, item_id, quantity_ordered)
+             VALUES ($1, $2, $3, $4)",
+            order_id,
+            index as i32 + 1,
+            line.item_id,
+            line.quantity_ordered
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(order_id)
+}
+
+pub(crate) async fn load_order(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<OrderDetail>> {
+    let order = if lock {
+        sqlx::query_as!(
+            OutboundOrder,
+            "SELECT * FROM warehouse.outbound_orders WHERE order_id = $1 FOR UPDATE",
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+    } else {
+        sqlx::query_as!(OutboundOrder, "SELECT * FROM warehouse.outbound_orders WHERE order_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    };
+
+    let Some(order) = order else {
+        return Ok(None);
+    };
+
+    let lines = sqlx::query_as!(
+        OrderLine,
+        "SELECT * FROM warehouse.outbound_order_lines WHERE order_id = $1 ORDER BY line_number",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(OrderDetail { order, lines }))
+}
+
+async fn load_shipment(conn: &mut PgConnection, id: i32) -> Result<Option<ShipmentDetail>> {
+    let Some(shipment) = sqlx::query_as!(Shipment, "SELECT * FROM warehouse.shipments WHERE shipment_id = $1", id)
+        .fetch_optional(&mut *conn)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let lines = sqlx::query_as!(
+        ShipmentLine,
+        "SELECT * FROM warehouse.shipment_lines WHERE shipment_id = $1 ORDER BY shipment_line_id",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(ShipmentDetail { shipment, lines }))
+}
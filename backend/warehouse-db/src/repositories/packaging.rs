@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::movements::{lock_stock, post_movement};
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct PackagingRepository {
+    pool: PgPool,
+}
+
+impl PackagingRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn materials(&self, box_id: i32) -> Result<Vec<BoxMaterial>> {
+        let mut conn = self.pool.acquire().await?;
+        load_materials(&mut conn, box_id).await
+    }
+
+    pub async fn set_materials(&self, box_id: i32, materials: SetBoxMaterials) -> Result<Vec<BoxMaterial>> {
+        let mut seen = HashSet::new();
+        if let Some(repeated) = materials.materials.iter().find(|material| !seen.insert(material.item_id)) {
+            return Err(violation(format!("item {} is listed more than once", repeated.item_id)));
+        }
+        let (item_ids, quantities): (Vec<i32>, Vec<_>) =
+            materials.materials.iter().map(|material| (material.item_id, material.quantity)).unzip();
+
+        let mut tx = self.pool.begin().await?;
+
+        let unknown_items = sqlx::query_scalar!(
+            "SELECT id AS \"id!\" FROM UNNEST($1::INT[]) id
+             EXCEPT SELECT item_id FROM warehouse.items",
+            &item_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if let Some(item_id) = unknown_items.first() {
+            return Err(violation(format!("item {} does not exist", item_id)));
+        }
+
+        sqlx::query!("DELETE FROM warehouse.box_materials WHERE box_id = $1", box_id).execute(&mut *tx).await?;
+        sqlx::query!(
+            "INSERT INTO warehouse.box_materials (box_id, item_id, quantity)
+             SELECT $1, item_id, quantity FROM UNNEST($2::INT[], $3::DECIMAL[]) AS m(item_id, quantity)",
+            box_id,
+            &item_ids,
+            &quantities
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let stored = load_materials(&mut tx, box_id).await?;
+        tx.commit().await?;
+        Ok(stored)
+    }
+
+    /// Own stock at or below its reorder point, lowest cover first. Client and consigned
+    /// stock is replenished by its owner and never alerts.
+    pub async fn reorder_alerts(&self, warehouse_id: i32, filter: ReorderAlertFilter) -> Result<Vec<ReorderAlert>> {
+        let alerts = sqlx::query_as!(
+            ReorderAlert,
+            r#"SELECT s.item_id, i.item_code, i.item_name, s.warehouse_id, s.quantity_on_hand,
+                      s.quantity_available AS "quantity_available!", s.reorder_point AS "reorder_point!",
+                      NULLIF(s.max_stock_level, 0) AS max_stock_level,
+                      CASE WHEN s.max_stock_level > s.quantity_available
+                           THEN s.max_stock_level - s.quantity_available END AS suggested_quantity,
+                      EXISTS(SELECT 1 FROM warehouse.box_materials m
+                             JOIN warehouse.box_sizes b ON b.box_id = m.box_id
+                             WHERE m.item_id = s.item_id AND b.warehouse_id = s.warehouse_id) AS "is_packaging!"
+               FROM warehouse.stock_inventory s
+               JOIN warehouse.items i ON i.item_id = s.item_id
+               WHERE s.warehouse_id = $1 AND s.consignor_id IS NULL AND s.client_id IS NULL
+                 AND s.reorder_point > 0 AND s.quantity_available <= s.reorder_point
+               ORDER BY s.quantity_available / s.reorder_point, i.item_code"#,
+            warehouse_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(match filter.packaging {
+            Some(packaging) => alerts.into_iter().filter(|alert| alert.is_packaging == packaging).collect(),
+            None => alerts,
+        })
+    }
+}
+
+async fn load_materials(conn: &mut PgConnection, box_id: i32) -> Result<Vec<BoxMaterial>> {
+    let materials = sqlx::query_as!(
+        BoxMaterial,
+        "SELECT m.box_id, m.item_id, i.item_code, i.item_name, m.quantity
+         FROM warehouse.box_materials m
+         JOIN warehouse.items i ON i.item_id = m.item_id
+         WHERE m.box_id = $1
+         ORDER BY i.item_code",
+        box_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(materials)
+}
+
+/// Issue a box size's materials from stock for one packed carton
+pub(crate) async fn issue_packaging(
+    conn: &mut PgConnection,
+    box_id: i32,
+    warehouse_id: i32,
+    carton_number: &str,
+) -> Result<Vec<StockMovement>> {
+    let materials = load_materials(conn, box_id).await?;
+    let item_ids: Vec<i32> = materials.iter().map(|material| material.item_id).collect();
+    lock_stock(conn, warehouse_id, &item_ids).await?;
+
+    let mut movements = Vec::with_capacity(materials.len());
+    for material in materials {
+        movements.push(
+            post_movement(
+                &mut *conn,
+                NewMovement {
+                    item_id: material.item_id,
+                    warehouse_id,
+                    movement_type: MOVEMENT_PACKAGING.to_string(),
+                    quantity: -material.quantity,
+                    catch_weight_kg: None,
+                    lot_number: None,
+                    unit_cost: None,
+                    stock_status: STOCK_AVAILABLE,
+                    consignor_id: None,
+                    reference_type: Some("CARTON".to_string()),
+                    reference_number: Some(carton_number.to_string()),
+                    notes: None,
+                    created_by: acting_user(),
+                },
+            )
+            .await?,
+        );
+    }
+
+    Ok(movements)
+}
@@ -0,0 +1,90 @@
+use crate::error::violation;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct PostingRepository {
+    pool: PgPool,
+}
+
+impl PostingRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find(&self, reference_id: &str) -> Result<Option<PostingReference>> {
+        let reference = sqlx::query_as!(
+            PostingReference,
+            "SELECT * FROM warehouse.posting_references WHERE reference_id = $1",
+            reference_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(reference)
+    }
+}
+
+/// Outcome of claiming a client reference key for a post
+pub(crate) enum Claim {
+    /// First post with the key, or no key given: go ahead and post
+    New,
+    /// The key was claimed by an earlier post of the same operation against the same
+    /// target; carries what that post created
+    Replay(Option<i64>),
+}
+
+/// Claim a client reference key on the caller's connection, so the claim only stands if
+/// the post commits. A concurrent post with the same key waits here until the first one
+/// commits or rolls back.
+pub(crate) async fn claim(
+    conn: &mut PgConnection,
+    reference_id: Option<&str>,
+    operation: &str,
+    target_id: i32,
+) -> Result<Claim> {
+    let Some(reference_id) = reference_id else {
+        return Ok(Claim::New);
+    };
+
+    let claimed = sqlx::query_scalar!(
+        "INSERT INTO warehouse.posting_references (reference_id, operation, target_id) VALUES ($1, $2, $3)
+         ON CONFLICT (reference_id) DO NOTHING
+         RETURNING reference_id",
+        reference_id,
+        operation,
+        target_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    if claimed.is_some() {
+        return Ok(Claim::New);
+    }
+
+    let earlier = sqlx::query_as!(
+        PostingReference,
+        "SELECT * FROM warehouse.posting_references WHERE reference_id = $1",
+        reference_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if earlier.operation != operation || earlier.target_id != target_id {
+        return Err(violation(format!("reference_id {} was already used for another posting", reference_id)));
+    }
+    Ok(Claim::Replay(earlier.result_id))
+}
+
+/// Remember what a post with a claimed key created, for replays
+pub(crate) async fn record_result(conn: &mut PgConnection, reference_id: Option<&str>, result_id: i64) -> Result<()> {
+    if let Some(reference_id) = reference_id {
+        sqlx::query!(
+            "UPDATE warehouse.posting_references SET result_id = $2 WHERE reference_id = $1",
+            reference_id,
+            result_id
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
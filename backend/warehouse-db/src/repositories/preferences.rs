@@ -0,0 +1,39 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct PreferenceRepository {
+    pool: PgPool,
+}
+
+impl PreferenceRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get(&self, owner: &str) -> Result<Option<UserPreference>> {
+        let preference =
+            sqlx::query_as!(UserPreference, "SELECT * FROM warehouse.user_preferences WHERE owner = $1", owner)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(preference)
+    }
+
+    pub async fn set(&self, owner: &str, preference: SetUserPreference) -> Result<UserPreference> {
+        let saved = sqlx::query_as!(
+            UserPreference,
+            "INSERT INTO warehouse.user_preferences (owner, locale)
+             VALUES ($1, $2)
+             ON CONFLICT (owner) DO UPDATE SET locale = EXCLUDED.locale, updated_at = NOW()
+             RETURNING *",
+            owner,
+            preference.locale
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(saved)
+    }
+}
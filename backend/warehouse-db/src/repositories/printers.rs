@@ -0,0 +1,224 @@
+use crate::error::violation;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct PrinterRepository {
+    pool: PgPool,
+}
+
+impl PrinterRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self, warehouse_id: i32, filter: &PrinterFilter) -> Result<Vec<Printer>> {
+        let printers = sqlx::query_as!(
+            Printer,
+            "SELECT * FROM warehouse.printers
+             WHERE warehouse_id = $1 AND ($2::INTEGER IS NULL OR station_id = $2)
+               AND ($3::BOOLEAN IS NULL OR is_active = $3)
+             ORDER BY printer_code",
+            warehouse_id,
+            filter.station_id,
+            filter.is_active
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(printers)
+    }
+
+    pub async fn get(&self, id: i32) -> Result<Option<Printer>> {
+        let mut conn = self.pool.acquire().await?;
+        load_printer(&mut conn, id, false).await
+    }
+
+    pub async fn code_exists(&self, warehouse_id: i32, code: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.printers WHERE warehouse_id = $1 AND printer_code = $2)",
+            warehouse_id,
+            code
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(exists.unwrap_or(false))
+    }
+
+    /// Register a printer. Where it is made the default for a purpose, it takes over from
+    /// the printer that was the default at its station, or in the warehouse.
+    pub async fn create(&self, warehouse_id: i32, printer: CreatePrinter) -> Result<Printer> {
+        check_address(&printer.printer_type, &printer.address)?;
+        let mut tx = self.pool.begin().await?;
+        if let Some(station_id) = printer.station_id {
+            check_station(&mut tx, warehouse_id, station_id).await?;
+        }
+
+        let created = sqlx::query_as!(
+            Printer,
+            "INSERT INTO warehouse.printers (warehouse_id, printer_code, name, printer_type, address, station_id, default_for)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING *",
+            warehouse_id,
+            printer.printer_code,
+            printer.name,
+            printer.printer_type,
+            printer.address.trim(),
+            printer.station_id,
+            &purposes(printer.default_for)
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        take_over_defaults(&mut tx, &created).await?;
+
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    /// Returns None when there is no such printer
+    pub async fn update(&self, id: i32, update: UpdatePrinter) -> Result<Option<Printer>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(current) = load_printer(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+
+        if let Some(address) = update.address.as_deref() {
+            check_address(&current.printer_type, address)?;
+        }
+        let station_id = if update.clear_station { None } else { update.station_id.or(current.station_id) };
+        if let Some(station_id) = station_id.filter(|station_id| Some(*station_id) != current.station_id) {
+            check_station(&mut tx, current.warehouse_id, station_id).await?;
+        }
+
+        let updated = sqlx::query_as!(
+            Printer,
+            "UPDATE warehouse.printers SET
+                 name = COALESCE($2, name),
+                 address = COALESCE($3, address),
+                 station_id = $4,
+                 default_for = COALESCE($5, default_for),
+                 is_active = COALESCE($6, is_active),
+                 updated_at = NOW()
+             WHERE printer_id = $1
+             RETURNING *",
+            id,
+            update.name,
+            update.address.as_deref().map(str::trim),
+            station_id,
+            update.default_for.map(purposes).as_deref(),
+            update.is_active
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        take_over_defaults(&mut tx, &updated).await?;
+
+        tx.commit().await?;
+        Ok(Some(updated))
+    }
+
+    /// The active printer a job for `purpose` goes to when it names none: the station's
+    /// default for the purpose, or else the warehouse's
+    pub async fn default_printer(
+        &self,
+        warehouse_id: i32,
+        station_id: Option<i32>,
+        purpose: &str,
+    ) -> Result<Option<Printer>> {
+        let printer = sqlx::query_as!(
+            Printer,
+            "SELECT * FROM warehouse.printers
+             WHERE warehouse_id = $1 AND is_active AND $3 = ANY(default_for)
+               AND (station_id IS NULL OR station_id = $2)
+             ORDER BY station_id NULLS LAST
+             LIMIT 1",
+            warehouse_id,
+            station_id,
+            purpose
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(printer)
+    }
+
+    pub async fn mark_printed(&self, id: i32) -> Result<Printer> {
+        let printer = sqlx::query_as!(
+            Printer,
+            "UPDATE warehouse.printers SET last_printed_at = NOW() WHERE printer_id = $1 RETURNING *",
+            id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(printer)
+    }
+}
+
+/// ZPL printers are reached at host[:port], agents at an HTTP URL
+fn check_address(printer_type: &str, address: &str) -> Result<()> {
+    let address = address.trim();
+    let is_url = address.starts_with("http://") || address.starts_with("https://");
+    if printer_type == PRINTER_AGENT && !is_url {
+        return Err(violation("a print agent's address must be an http:// or https:// URL"));
+    }
+    if printer_type == PRINTER_ZPL {
+        let port_ok = address.rsplit_once(':').map_or(true, |(_, port)| port.parse::<u16>().is_ok());
+        if is_url || address.contains('/') || !port_ok {
+            return Err(violation("a ZPL printer's address must be host or host:port"));
+        }
+    }
+    Ok(())
+}
+
+async fn check_station(conn: &mut PgConnection, warehouse_id: i32, station_id: i32) -> Result<()> {
+    let in_warehouse = sqlx::query_scalar!(
+        "SELECT EXISTS(SELECT 1 FROM warehouse.measuring_stations WHERE station_id = $1 AND warehouse_id = $2)",
+        station_id,
+        warehouse_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if !in_warehouse.unwrap_or(false) {
+        return Err(violation(format!("station {} is not in warehouse {}", station_id, warehouse_id)));
+    }
+    Ok(())
+}
+
+/// Drop the printer's default purposes from the other printers at its station, or of
+/// the warehouse as a whole when it is at none
+async fn take_over_defaults(conn: &mut PgConnection, printer: &Printer) -> Result<()> {
+    if printer.default_for.is_empty() {
+        return Ok(());
+    }
+    sqlx::query!(
+        "UPDATE warehouse.printers SET
+             default_for = ARRAY(SELECT purpose FROM UNNEST(default_for) AS purpose WHERE purpose <> ALL($4)),
+             updated_at = NOW()
+         WHERE warehouse_id = $1 AND station_id IS NOT DISTINCT FROM $2 AND printer_id <> $3
+           AND default_for && $4",
+        printer.warehouse_id,
+        printer.station_id,
+        printer.printer_id,
+        &printer.default_for
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+fn purposes(mut purposes: Vec<String>) -> Vec<String> {
+    purposes.sort();
+    purposes.dedup();
+    purposes
+}
+
+async fn load_printer(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<Printer>> {
+    let printer = if lock {
+        sqlx::query_as!(Printer, "SELECT * FROM warehouse.printers WHERE printer_id = $1 FOR UPDATE", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    } else {
+        sqlx::query_as!(Printer, "SELECT * FROM warehouse.printers WHERE printer_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    };
+    Ok(printer)
+}
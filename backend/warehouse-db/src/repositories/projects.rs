@@ -0,0 +1,732 @@
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::movements::{lock_stock, move_reserved, post_movement};
+use crate::repositories::notifications::insert_notification;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use std::collections::{BTreeMap, HashMap};
+use warehouse_models::chrono::{DateTime, NaiveDate, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// A project as stored, its budget in separate amount and currency columns
+struct ProjectRow {
+    project_id: i32,
+    project_code: String,
+    project_name: String,
+    material_budget: Option<Decimal>,
+    budget_currency: String,
+    budget_control: String,
+    status: String,
+    is_active: Option<bool>,
+    site_name: Option<String>,
+    site_address: Option<String>,
+    site_city: Option<String>,
+    manager_name: Option<String>,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<ProjectRow> for Project {
+    fn from(row: ProjectRow) -> Self {
+        Project {
+            project_id: row.project_id,
+            project_code: row.project_code,
+            project_name: row.project_name,
+            material_budget: Money::from_column(row.material_budget, &row.budget_currency),
+            budget_control: row.budget_control,
+            status: row.status,
+            is_active: row.is_active.unwrap_or(false),
+            site_name: row.site_name,
+            site_address: row.site_address,
+            site_city: row.site_city,
+            manager_name: row.manager_name,
+            start_date: row.start_date,
+            end_date: row.end_date,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ProjectRepository {
+    pool: PgPool,
+}
+
+impl ProjectRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Projects in the filter's status, or the planned and active ones, by code
+    pub async fn list(&self, filter: &ProjectFilter) -> Result<Vec<Project>> {
+        let rows = sqlx::query_as!(
+            ProjectRow,
+            "SELECT * FROM warehouse.projects
+             WHERE CASE WHEN $1::VARCHAR IS NULL THEN is_active ELSE status = $1 END
+             ORDER BY project_code",
+            filter.status
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(Project::from).collect())
+    }
+
+    pub async fn get(&self, id: i32) -> Result<Option<Project>> {
+        let mut conn = self.pool.acquire().await?;
+        load_project(&mut conn, id, false).await
+    }
+
+    pub async fn code_exists(&self, code: &str) -> Result<bool> {
+        let exists =
+            sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM warehouse.projects WHERE project_code = $1)", code)
+                .fetch_one(&self.pool)
+                .await?;
+        Ok(exists.unwrap_or(false))
+    }
+
+    pub async fn create(&self, project: CreateProject) -> Result<Project> {
+        let id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.projects (
+                 project_code, project_name, material_budget, budget_currency, budget_control, status,
+                 site_name, site_address, site_city, manager_name, start_date, end_date
+             ) VALUES ($1, $2, $3, COALESCE($4, 'IDR'), COALESCE($5, 'WARN'), COALESCE($6, 'ACTIVE'),
+                       $7, $8, $9, $10, $11, $12)
+             RETURNING project_id",
+            project.project_code,
+            project.project_name,
+            project.material_budget.as_ref().map(|budget| budget.amount),
+            project.material_budget.as_ref().map(|budget| budget.currency.as_str()),
+            project.budget_control,
+            project.status,
+            project.site_name,
+            project.site_address,
+            project.site_city,
+            project.manager_name,
+            project.start_date,
+            project.end_date
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(self.get(id).await?.expect("project inserted above"))
+    }
+
+    /// A new budget may be in another currency only while nothing costed has been issued,
+    /// and the project may not end before it starts
+    pub async fn update(&self, id: i32, project: UpdateProject) -> Result<Option<Project>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(current) = load_project(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        if let Some(budget) = &project.material_budget {
+            let consumed = consumed(&mut tx, id).await?;
+            let currency = current.material_budget.as_ref().map_or(DEFAULT_CURRENCY, |b| b.currency.as_str());
+            if budget.currency != currency && !consumed.is_zero() {
+                return Err(violation(format!(
+                    "{} has {} {} issued; its budget must stay in {}",
+                    current.project_code, consumed, currency, currency
+                )));
+            }
+        }
+        if let (Some(start), Some(end)) =
+            (project.start_date.or(current.start_date), project.end_date.or(current.end_date))
+        {
+            if end < start {
+                return Err(violation(format!(
+                    "{} cannot end on {} before it starts on {}",
+                    current.project_code, end, start
+                )));
+            }
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.projects
+             SET project_name = COALESCE($2, project_name),
+                 material_budget = COALESCE($3, material_budget),
+                 budget_currency = COALESCE($4, budget_currency),
+                 budget_control = COALESCE($5, budget_control),
+                 status = COALESCE($6, status),
+                 site_name = COALESCE($7, site_name),
+                 site_address = COALESCE($8, site_address),
+                 site_city = COALESCE($9, site_city),
+                 manager_name = COALESCE($10, manager_name),
+                 start_date = COALESCE($11, start_date),
+                 end_date = COALESCE($12, end_date),
+                 updated_at = NOW()
+             WHERE project_id = $1",
+            id,
+            project.project_name,
+            project.material_budget.as_ref().map(|budget| budget.amount),
+            project.material_budget.as_ref().map(|budget| budget.currency.as_str()),
+            project.budget_control,
+            project.status,
+            project.site_name,
+            project.site_address,
+            project.site_city,
+            project.manager_name,
+            project.start_date,
+            project.end_date
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let updated = load_project(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    /// Issue stock to an active project as `PROJECT_ISSUE` movements, valued at the
+    /// warehouse's average cost or else the item's cost. When the issue takes the project
+    /// past its material budget, a BLOCK project refuses it and a WARN project posts it
+    /// and raises a notification. Each line draws first on the project's active
+    /// reservations of the item, soonest needed first. Returns None when there is no such
+    /// active project.
+    pub async fn issue(&self, id: i32, issue: IssueToProject) -> Result<Option<ProjectIssueResult>> {
+        let mut tx = self.pool.begin().await?;
+        // The project row lock queues concurrent issues, so each checks what the last one charged
+        let Some(project) = load_project(&mut tx, id, true).await?.filter(|project| project.is_active) else {
+            return Ok(None);
+        };
+        let currency = project.material_budget.as_ref().map_or(DEFAULT_CURRENCY, |b| b.currency.clone());
+
+        let mut item_ids: Vec<i32> = issue.lines.iter().map(|line| line.item_id).collect();
+        item_ids.sort_unstable();
+        item_ids.dedup();
+        lock_stock(&mut tx, issue.warehouse_id, &item_ids).await?;
+
+        let costs: HashMap<i32, _> = sqlx::query!(
+            "SELECT i.item_id, i.item_code, i.cost_currency,
+                    COALESCE(s.average_cost, i.average_cost, i.standard_cost) AS unit_cost
+             FROM warehouse.items i
+             LEFT JOIN warehouse.stock_inventory s ON s.item_id = i.item_id AND s.warehouse_id = $1
+                  AND s.consignor_id IS NULL AND s.client_id IS NULL
+             WHERE i.item_id = ANY($2) AND i.status = 'ACTIVE'",
+            issue.warehouse_id,
+            &item_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| (row.item_id, row))
+        .collect();
+
+        let mut line_values = Vec::with_capacity(issue.lines.len());
+        for line in &issue.lines {
+            let cost = costs.get(&line.item_id).ok_or_else(|| violation(format!("unknown item {}", line.item_id)))?;
+            let value = match cost.unit_cost {
+                Some(_) if cost.cost_currency != currency => {
+                    return Err(violation(format!(
+                        "{} is costed in {} and cannot be charged to {}, budgeted in {}",
+                        cost.item_code, cost.cost_currency, project.project_code, currency
+                    )));
+                }
+                Some(unit_cost) => (unit_cost * line.quantity).round_dp(2),
+                None => Decimal::ZERO,
+            };
+            line_values.push(value);
+        }
+        let issue_value: Decimal = line_values.iter().sum();
+
+        let consumed = consumed(&mut tx, id).await? + issue_value;
+        let budget = project.material_budget.as_ref().map(|budget| budget.amount);
+        let over_budget = budget.is_some_and(|budget| consumed > budget);
+        let warning = over_budget.then(|| {
+            format!(
+                "{} has {} {} issued against a material budget of {}",
+                project.project_code,
+                consumed,
+                currency,
+                budget.unwrap_or_default()
+            )
+        });
+        if let Some(warning) = &warning {
+            if project.budget_control == PROJECT_BUDGET_BLOCK {
+                return Err(violation(format!("issue of {} {} refused: {}", issue_value, currency, warning)));
+            }
+        }
+
+        let mut movements = Vec::with_capacity(issue.lines.len());
+        let mut reservations = BTreeMap::new();
+        for (line, line_value) in issue.lines.into_iter().zip(line_values) {
+            let unit_cost = costs[&line.item_id].unit_cost;
+            // Give the reserved stock back to ON_HAND first, so the movement takes it from there
+            let drawn = draw_reservations(&mut tx, &project, issue.warehouse_id, line.item_id, line.quantity).await?;
+            reservations.extend(drawn.into_iter().map(|reservation| (reservation.reservation_id, reservation)));
+            let movement = post_movement(
+                &mut tx,
+                NewMovement {
+                    item_id: line.item_id,
+                    warehouse_id: issue.warehouse_id,
+                    movement_type: MOVEMENT_PROJECT_ISSUE.to_string(),
+                    quantity: -line.quantity,
+                    catch_weight_kg: None,
+                    lot_number: line.lot_number,
+                    unit_cost,
+                    stock_status: STOCK_AVAILABLE,
+                    consignor_id: None,
+                    reference_type: Some("PROJECT".to_string()),
+                    reference_number: Some(project.project_code.clone()),
+                    notes: issue.reference.clone(),
+                    created_by: acting_user(),
+                },
+            )
+            .await?;
+
+            sqlx::query!(
+                "INSERT INTO warehouse.project_issues (
+                     project_id, warehouse_id, item_id, quantity, unit_cost, issue_value, movement_id, over_budget,
+                     crew_name
+                 ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+                id,
+                issue.warehouse_id,
+                line.item_id,
+                line.quantity,
+                unit_cost,
+                line_value,
+                movement.movement_id,
+                over_budget,
+                issue.crew
+            )
+            .execute(&mut *tx)
+            .await?;
+            movements.push(movement);
+        }
+
+        if let Some(warning) = &warning {
+            insert_notification(
+                &mut tx,
+                NewNotification {
+                    kind: NOTIFY_PROJECT_OVER_BUDGET,
+                    severity: SEVERITY_WARNING,
+                    subject: warning.clone(),
+                    message: issue.reference.clone(),
+                    reference_type: Some("PROJECT".to_string()),
+                    reference_id: Some(id),
+                },
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(Some(ProjectIssueResult {
+            movements,
+            issue_value: Money::new(issue_value, &currency),
+            consumed: Money::new(consumed, &currency),
+            material_budget: project.material_budget,
+            over_budget,
+            warning,
+            reservations: reservations.into_values().collect(),
+        }))
+    }
+
+    /// Reserve stock for an active project: each line's quantity moves out of the
+    /// warehouse's available stock into its reserved stock until the project draws on it
+    /// or the reservation is released or expires. Refused when the warehouse has less
+    /// available than asked for. Returns None when there is no such active project.
+    pub async fn reserve(&self, id: i32, reserve: ReserveForProject) -> Result<Option<Vec<ProjectReservation>>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(project) = load_project(&mut tx, id, true).await?.filter(|project| project.is_active) else {
+            return Ok(None);
+        };
+
+        let mut requested: BTreeMap<i32, Decimal> = BTreeMap::new();
+        for line in &reserve.lines {
+            *requested.entry(line.item_id).or_default() += line.quantity;
+        }
+        let item_ids: Vec<i32> = requested.keys().copied().collect();
+        lock_stock(&mut tx, reserve.warehouse_id, &item_ids).await?;
+
+        let available: HashMap<i32, _> = sqlx::query!(
+            r#"SELECT i.item_id, i.item_code, COALESCE(s.quantity_available, 0) AS "available!"
+               FROM warehouse.items i
+               LEFT JOIN warehouse.stock_inventory s ON s.item_id = i.item_id AND s.warehouse_id = $1
+                    AND s.consignor_id IS NULL
+               WHERE i.item_id = ANY($2) AND i.status = 'ACTIVE'"#,
+            reserve.warehouse_id,
+            &item_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| (row.item_id, row))
+        .collect();
+        for (item_id, quantity) in &requested {
+            let stock = available.get(item_id).ok_or_else(|| violation(format!("unknown item {}", item_id)))?;
+            if *quantity > stock.available {
+                return Err(violation(format!(
+                    "cannot reserve {} of {} in warehouse {}: only {} available",
+                    quantity,
+                    stock.item_code,
+                    reserve.warehouse_id,
+                    stock.available.max(Decimal::ZERO)
+                )));
+            }
+        }
+
+        let expires_on = reserve.expires_on.unwrap_or(reserve.needed_by);
+        let mut reservations = Vec::with_capacity(reserve.lines.len());
+        for line in reserve.lines {
+            move_reserved(
+                &mut tx,
+                reserve.warehouse_id,
+                line.item_id,
+                line.quantity,
+                REFERENCE_PROJECT_RESERVATION,
+                Some(&project.project_code),
+            )
+            .await?;
+            let reservation = sqlx::query_as!(
+                ProjectReservation,
+                "INSERT INTO warehouse.project_reservations (
+                     project_id, warehouse_id, item_id, quantity, needed_by, expires_on, notes
+                 ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 RETURNING *",
+                id,
+                reserve.warehouse_id,
+                line.item_id,
+                line.quantity,
+                reserve.needed_by,
+                expires_on,
+                reserve.notes
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            reservations.push(reservation);
+        }
+
+        tx.commit().await?;
+        Ok(Some(reservations))
+    }
+
+    /// A project's reservations, soonest needed first. Returns None when there is no such
+    /// project.
+    pub async fn reservations(
+        &self,
+        id: i32,
+        filter: ProjectReservationFilter,
+    ) -> Result<Option<Vec<ProjectReservation>>> {
+        let mut conn = self.pool.acquire().await?;
+        if load_project(&mut conn, id, false).await?.is_none() {
+            return Ok(None);
+        }
+
+        let reservations = sqlx::query_as!(
+            ProjectReservation,
+            "SELECT * FROM warehouse.project_reservations
+             WHERE project_id = $1 AND ($2::VARCHAR IS NULL OR status = $2)
+             ORDER BY needed_by, reservation_id",
+            id,
+            filter.status
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok(Some(reservations))
+    }
+
+    /// Give an active reservation's open quantity back to the warehouse's available stock
+    pub async fn release(&self, reservation_id: i32) -> Result<Option<ProjectReservation>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(project) = reservation_project(&mut tx, reservation_id).await? else {
+            return Ok(None);
+        };
+        let reservation = load_reservation(&mut tx, reservation_id).await?;
+        if reservation.status != RESERVATION_ACTIVE {
+            return Err(violation(format!(
+                "cannot release reservation {}: it is {}",
+                reservation_id,
+                reservation.status.to_lowercase()
+            )));
+        }
+
+        let released = close_reservation(&mut tx, &project, reservation, RESERVATION_RELEASED).await?;
+        tx.commit().await?;
+        Ok(Some(released))
+    }
+
+    /// Consumables issued to projects by period, project, crew and item between `from` and
+    /// `to`. Each row is measured against the average of the other projects' rows for the
+    /// same item and period, and flagged abnormal at `threshold` times that average or more.
+    pub async fn consumable_usage(
+        &self,
+        filter: &ConsumableUsageFilter,
+        from: NaiveDate,
+        to: NaiveDate,
+        threshold: Decimal,
+    ) -> Result<Vec<ConsumableUsage>> {
+        let period = filter.period.as_deref().unwrap_or(USAGE_PERIOD_MONTH).to_lowercase();
+        // Peers are counted over every project before the project and crew filters apply
+        let rows = sqlx::query!(
+            r#"WITH usage AS (
+                   SELECT DATE_TRUNC($3, pi.issued_at)::DATE AS period_start, pi.project_id, pi.crew_name,
+                          pi.item_id, SUM(pi.quantity) AS quantity, COUNT(*) AS issues
+                   FROM warehouse.project_issues pi
+                   JOIN warehouse.items i ON i.item_id = pi.item_id
+                   WHERE pi.issued_at >= $1::DATE AND pi.issued_at < $2::DATE + 1
+                     AND i.item_usage_type = $4
+                   GROUP BY 1, pi.project_id, pi.crew_name, pi.item_id
+               ),
+               peers AS (
+                   SELECT u.*,
+                          SUM(quantity) OVER item_period - SUM(quantity) OVER project_item AS peer_quantity,
+                          COUNT(*) OVER item_period - COUNT(*) OVER project_item AS peer_rows
+                   FROM usage u
+                   WINDOW item_period AS (PARTITION BY period_start, item_id),
+                          project_item AS (PARTITION BY period_start, item_id, project_id)
+               )
+               SELECT u.period_start AS "period_start!", u.project_id, p.project_code, u.crew_name,
+                      u.item_id, i.item_code, i.item_name, u.quantity AS "quantity!", u.issues AS "issues!",
+                      CASE WHEN u.peer_rows > 0 THEN u.peer_quantity / u.peer_rows END AS peer_average
+               FROM peers u
+               JOIN warehouse.projects p ON p.project_id = u.project_id
+               JOIN warehouse.items i ON i.item_id = u.item_id
+               WHERE ($5::INT IS NULL OR u.project_id = $5) AND ($6::VARCHAR IS NULL OR u.crew_name = $6)
+               ORDER BY u.period_start, p.project_code, u.crew_name NULLS FIRST, i.item_code"#,
+            from,
+            to,
+            period,
+            ITEM_USAGE_CONSUMABLE,
+            filter.project_id,
+            filter.crew
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let peer_average = row.peer_average.map(|average| average.round_dp(3));
+                let peer_ratio = row
+                    .peer_average
+                    .filter(|average| !average.is_zero())
+                    .map(|average| (row.quantity / average).round_dp(2));
+                ConsumableUsage {
+                    period_start: row.period_start,
+                    project_id: row.project_id,
+                    project_code: row.project_code,
+                    crew: row.crew_name,
+                    item_id: row.item_id,
+                    item_code: row.item_code,
+                    item_name: row.item_name,
+                    quantity: row.quantity,
+                    issues: row.issues,
+                    peer_average,
+                    abnormal: peer_ratio.is_some_and(|ratio| ratio >= threshold),
+                    peer_ratio,
+                }
+            })
+            .collect())
+    }
+
+    /// Expire the active reservations past their expiry date, giving what they still hold
+    /// back to the available stock. Each is expired in a transaction of its own.
+    pub async fn expire_reservations(&self) -> Result<Vec<ProjectReservation>> {
+        let stale = sqlx::query_scalar!(
+            "SELECT reservation_id FROM warehouse.project_reservations
+             WHERE status = 'ACTIVE' AND expires_on < CURRENT_DATE
+             ORDER BY reservation_id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut expired = Vec::with_capacity(stale.len());
+        for reservation_id in stale {
+            let mut tx = self.pool.begin().await?;
+            let project = reservation_project(&mut tx, reservation_id).await?.expect("reservation listed above");
+            let reservation = load_reservation(&mut tx, reservation_id).await?;
+            // Drawn in full or released since it was listed
+            if reservation.status != RESERVATION_ACTIVE {
+                continue;
+            }
+            expired.push(close_reservation(&mut tx, &project, reservation, RESERVATION_EXPIRED).await?);
+            tx.commit().await?;
+        }
+        Ok(expired)
+    }
+
+    /// What has been issued to the project against its budget, with the items issued
+    /// between `from` and `to`, both inclusive
+    pub async fn consumption(&self, id: i32, filter: ProjectConsumptionFilter) -> Result<Option<ProjectConsumption>> {
+        let mut conn = self.pool.acquire().await?;
+        let Some(project) = load_project(&mut conn, id, false).await? else {
+            return Ok(None);
+        };
+        let currency = project.material_budget.as_ref().map_or(DEFAULT_CURRENCY, |b| b.currency.clone());
+        let consumed = consumed(&mut conn, id).await?;
+
+        let rows = sqlx::query!(
+            r#"SELECT p.item_id, i.item_code, i.item_name,
+                      SUM(p.quantity) AS "quantity!", SUM(p.issue_value) AS "issue_value!",
+                      COUNT(*) AS "issues!", MAX(p.issued_at) AS "last_issued_at!"
+               FROM warehouse.project_issues p
+               JOIN warehouse.items i ON i.item_id = p.item_id
+               WHERE p.project_id = $1
+                 AND ($2::DATE IS NULL OR p.issued_at >= $2::DATE)
+                 AND ($3::DATE IS NULL OR p.issued_at < $3::DATE + 1)
+               GROUP BY p.item_id, i.item_code, i.item_name
+               ORDER BY SUM(p.issue_value) DESC, i.item_code"#,
+            id,
+            filter.from,
+            filter.to
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let budget = project.material_budget.as_ref().map(|budget| budget.amount);
+        Ok(Some(ProjectConsumption {
+            project_id: project.project_id,
+            project_code: project.project_code,
+            consumed: Money::new(consumed, &currency),
+            remaining: budget.map(|budget| Money::new(budget - consumed, &currency)),
+            consumed_pct: budget
+                .filter(|budget| !budget.is_zero())
+                .map(|budget| (consumed * Decimal::from(100) / budget).round_dp(1)),
+            material_budget: project.material_budget,
+            items: rows
+                .into_iter()
+                .map(|row| ProjectItemConsumption {
+                    item_id: row.item_id,
+                    item_code: row.item_code,
+                    item_name: row.item_name,
+                    quantity: row.quantity,
+                    issue_value: Money::new(row.issue_value, &currency),
+                    issues: row.issues,
+                    last_issued_at: row.last_issued_at,
+                })
+                .collect(),
+        }))
+    }
+}
+
+/// Draw up to `quantity` from the project's active reservations of an item, soonest
+/// needed first, giving what is drawn back to ON_HAND. Returns the reservations drawn on.
+async fn draw_reservations(
+    conn: &mut PgConnection,
+    project: &Project,
+    warehouse_id: i32,
+    item_id: i32,
+    quantity: Decimal,
+) -> Result<Vec<ProjectReservation>> {
+    let active = sqlx::query_as!(
+        ProjectReservation,
+        "SELECT * FROM warehouse.project_reservations
+         WHERE project_id = $1 AND warehouse_id = $2 AND item_id = $3 AND status = 'ACTIVE'
+         ORDER BY needed_by, reservation_id",
+        project.project_id,
+        warehouse_id,
+        item_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut left = quantity;
+    let mut drawn = Vec::new();
+    for reservation in active {
+        if left.is_zero() {
+            break;
+        }
+        let take = left.min(reservation.quantity_open());
+        move_reserved(conn, warehouse_id, item_id, -take, REFERENCE_PROJECT_RESERVATION, Some(&project.project_code))
+            .await?;
+        let reservation = sqlx::query_as!(
+            ProjectReservation,
+            "UPDATE warehouse.project_reservations
+             SET quantity_issued = quantity_issued + $2,
+                 status = CASE WHEN quantity_issued + $2 = quantity THEN 'FULFILLED' ELSE status END,
+                 closed_at = CASE WHEN quantity_issued + $2 = quantity THEN NOW() END
+             WHERE reservation_id = $1
+             RETURNING *",
+            reservation.reservation_id,
+            take
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        left -= take;
+        drawn.push(reservation);
+    }
+    Ok(drawn)
+}
+
+/// Close an active reservation as `status`, giving its open quantity back to ON_HAND
+async fn close_reservation(
+    conn: &mut PgConnection,
+    project: &Project,
+    reservation: ProjectReservation,
+    status: &str,
+) -> Result<ProjectReservation> {
+    lock_stock(conn, reservation.warehouse_id, &[reservation.item_id]).await?;
+    let open = reservation.quantity_open();
+    move_reserved(
+        conn,
+        reservation.warehouse_id,
+        reservation.item_id,
+        -open,
+        REFERENCE_PROJECT_RESERVATION,
+        Some(&project.project_code),
+    )
+    .await?;
+
+    let closed = sqlx::query_as!(
+        ProjectReservation,
+        "UPDATE warehouse.project_reservations SET status = $2, closed_at = NOW()
+         WHERE reservation_id = $1
+         RETURNING *",
+        reservation.reservation_id,
+        status
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(closed)
+}
+
+/// Lock the project a reservation belongs to. Reservations change only under their
+/// project's lock, so what the caller reads of them afterwards holds until it commits.
+async fn reservation_project(conn: &mut PgConnection, reservation_id: i32) -> Result<Option<Project>> {
+    let project_id = sqlx::query_scalar!(
+        "SELECT project_id FROM warehouse.project_reservations WHERE reservation_id = $1",
+        reservation_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    match project_id {
+        Some(project_id) => load_project(conn, project_id, true).await,
+        None => Ok(None),
+    }
+}
+
+async fn load_reservation(conn: &mut PgConnection, reservation_id: i32) -> Result<ProjectReservation> {
+    let reservation = sqlx::query_as!(
+        ProjectReservation,
+        "SELECT * FROM warehouse.project_reservations WHERE reservation_id = $1",
+        reservation_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(reservation)
+}
+
+async fn consumed(conn: &mut PgConnection, project_id: i32) -> Result<Decimal> {
+    let consumed = sqlx::query_scalar!(
+        "SELECT COALESCE(SUM(issue_value), 0) FROM warehouse.project_issues WHERE project_id = $1",
+        project_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(consumed.unwrap_or_default())
+}
+
+pub(crate) async fn load_project(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<Project>> {
+    let row = if lock {
+        sqlx::query_as!(ProjectRow, "SELECT * FROM warehouse.projects WHERE project_id = $1 FOR UPDATE", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    } else {
+        sqlx::query_as!(ProjectRow, "SELECT * FROM warehouse.projects WHERE project_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    };
+    Ok(row.map(Project::from))
+}
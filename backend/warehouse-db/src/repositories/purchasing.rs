@@ -0,0 +1,588 @@
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::packaging::PackagingRepository;
+use crate::utils::validate_keyset;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use std::collections::BTreeMap;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// First key of the advisory lock taken while drafting a warehouse's requisitions; the
+/// warehouse id is the second
+const REQUISITION_LOCK: i32 = 0x7072_7100;
+
+#[derive(Clone)]
+pub struct PurchasingRepository {
+    pool: PgPool,
+}
+
+impl PurchasingRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// An item's suppliers, preferred first
+    pub async fn suppliers(&self, item_id: i32) -> Result<Vec<ItemSupplier>> {
+        let rows = sqlx::query!(
+            "SELECT * FROM warehouse.item_suppliers WHERE item_id = $1 ORDER BY is_preferred DESC, supplier_code",
+            item_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ItemSupplier {
+                item_id: row.item_id,
+                supplier_code: row.supplier_code,
+                supplier_name: row.supplier_name,
+                is_preferred: row.is_preferred,
+                unit_cost: Money::from_column(row.unit_cost, &row.cost_currency),
+                lead_time_days: row.lead_time_days,
+                order_cost: Money::from_column(row.order_cost, &row.cost_currency),
+                holding_cost_pct: row.holding_cost_pct,
+                constraints: OrderConstraints {
+                    min_order_quantity: row.min_order_quantity,
+                    order_multiple: row.order_multiple,
+                    pack_size: row.pack_size,
+                },
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            })
+            .collect())
+    }
+
+    pub async fn set_supplier(
+        &self,
+        item_id: i32,
+        supplier_code: &str,
+        supplier: SetItemSupplier,
+    ) -> Result<ItemSupplier> {
+        let mut tx = self.pool.begin().await?;
+
+        if supplier.is_preferred {
+            sqlx::query!(
+                "UPDATE warehouse.item_suppliers SET is_preferred = FALSE, updated_at = NOW()
+                 WHERE item_id = $1 AND supplier_code <> $2 AND is_preferred",
+                item_id,
+                supplier_code
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query!(
+            "INSERT INTO warehouse.item_suppliers (
+                 item_id, supplier_code, supplier_name, is_preferred, unit_cost, cost_currency, lead_time_days,
+                 order_cost, holding_cost_pct, min_order_quantity, order_multiple, pack_size
+             ) VALUES ($1, $2, $3, $4, $5, COALESCE($6, 'IDR'), COALESCE($7, 0), $8, $9, $10, $11, $12)
+             ON CONFLICT (item_id, supplier_code) DO UPDATE SET
+                 supplier_name = EXCLUDED.supplier_name,
+                 is_preferred = EXCLUDED.is_preferred,
+                 unit_cost = EXCLUDED.unit_cost,
+                 cost_currency = EXCLUDED.cost_currency,
+                 lead_time_days = EXCLUDED.lead_time_days,
+                 order_cost = EXCLUDED.order_cost,
+                 holding_cost_pct = EXCLUDED.holding_cost_pct,
+                 min_order_quantity = EXCLUDED.min_order_quantity,
+                 order_multiple = EXCLUDED.order_multiple,
+                 pack_size = EXCLUDED.pack_size,
+                 updated_at = NOW()",
+            item_id,
+            supplier_code,
+            supplier.supplier_name,
+            supplier.is_preferred,
+            supplier.unit_cost.as_ref().map(|cost| cost.amount),
+            supplier.currency(),
+            supplier.lead_time_days,
+            supplier.order_cost.as_ref().map(|cost| cost.amount),
+            supplier.holding_cost_pct,
+            supplier.min_order_quantity,
+            supplier.order_multiple,
+            supplier.pack_size
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        let saved = self.suppliers(item_id).await?.into_iter().find(|s| s.supplier_code == supplier_code);
+        Ok(saved.expect("supplier saved above"))
+    }
+
+    /// Active warehouses, for scheduled requisition runs
+    pub async fn warehouse_ids(&self) -> Result<Vec<i32>> {
+        let ids = sqlx::query_scalar!(
+            "SELECT warehouse_id FROM warehouse.warehouses WHERE is_active = true ORDER BY warehouse_id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(ids)
+    }
+
+    /// Draft requisitions for the warehouse's reorder alerts, one per preferred supplier and
+    /// currency. Suggested quantities are rounded up to what the supplier accepts. Items
+    /// already on an open requisition or purchase order are left alone, so a repeated pass
+    /// only picks up new alerts.
+    pub async fn generate(&self, warehouse_id: i32, request: GenerateRequisitions) -> Result<RequisitionRun> {
+        let alerts = PackagingRepository::new(self.pool.clone())
+            .reorder_alerts(warehouse_id, ReorderAlertFilter::default())
+            .await?;
+        let item_ids: Vec<i32> = alerts.iter().map(|alert| alert.item_id).collect();
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!("SELECT pg_advisory_xact_lock($1, $2)", REQUISITION_LOCK, warehouse_id).execute(&mut *tx).await?;
+
+        // Yearly demand is what left the warehouse over the last 365 days
+        let sources = sqlx::query!(
+            r#"WITH demand AS (
+                   SELECT item_id, -SUM(quantity) AS yearly
+                   FROM warehouse.stock_movements
+                   WHERE warehouse_id = $1 AND quantity < 0 AND movement_date >= NOW() - INTERVAL '365 days'
+                   GROUP BY item_id
+               )
+               SELECT a.item_id AS "item_id!",
+                      sup.supplier_code AS "supplier_code?",
+                      sup.supplier_name AS "supplier_name?",
+                      sup.cost_currency AS "cost_currency?",
+                      sup.unit_cost, sup.min_order_quantity, sup.order_multiple, sup.pack_size,
+                      CEIL(SQRT(2 * d.yearly * sup.order_cost
+                                / NULLIF(sup.unit_cost * sup.holding_cost_pct / 100, 0))) AS eoq,
+                      EXISTS(SELECT 1 FROM warehouse.purchase_requisition_lines l
+                             JOIN warehouse.purchase_requisitions r ON r.requisition_id = l.requisition_id
+                             WHERE r.warehouse_id = $1 AND r.status IN ($3, $4) AND l.item_id = a.item_id)
+                      OR EXISTS(SELECT 1 FROM warehouse.purchase_order_lines l
+                                JOIN warehouse.purchase_orders o ON o.po_id = l.po_id
+                                WHERE o.warehouse_id = $1 AND o.status = $5 AND l.item_id = a.item_id
+                                  AND l.quantity_received < l.quantity_ordered) AS "on_order!"
+               FROM UNNEST($2::INT[]) AS a(item_id)
+               LEFT JOIN warehouse.item_suppliers sup ON sup.item_id = a.item_id AND sup.is_preferred
+               LEFT JOIN demand d ON d.item_id = a.item_id"#,
+            warehouse_id,
+            &item_ids,
+            REQUISITION_DRAFT,
+            REQUISITION_APPROVED,
+            PO_OPEN
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let sources: BTreeMap<i32, _> = sources.into_iter().map(|source| (source.item_id, source)).collect();
+
+        let mut without_supplier = Vec::new();
+        let mut groups: BTreeMap<(String, String), (String, Vec<NewRequisitionLine>)> = BTreeMap::new();
+        for alert in alerts {
+            let Some(source) = sources.get(&alert.item_id).filter(|source| !source.on_order) else {
+                continue;
+            };
+            let (Some(code), Some(name), Some(currency)) =
+                (&source.supplier_code, &source.supplier_name, &source.cost_currency)
+            else {
+                without_supplier.push(alert);
+                continue;
+            };
+
+            let shortfall = alert.reorder_point - alert.quantity_available;
+            let (method, suggested) = match source.eoq {
+                Some(eoq) if request.method() == REQUISITION_METHOD_EOQ => (REQUISITION_METHOD_EOQ, eoq.max(shortfall)),
+                _ => (REQUISITION_METHOD_MIN_MAX, alert.suggested_quantity.unwrap_or(shortfall)),
+            };
+            if suggested <= Decimal::ZERO {
+                continue;
+            }
+            let constraints = OrderConstraints {
+                min_order_quantity: source.min_order_quantity,
+                order_multiple: source.order_multiple,
+                pack_size: source.pack_size,
+            };
+            let suggested = constraints.round_up(suggested);
+
+            let (_, lines) =
+                groups.entry((code.clone(), currency.clone())).or_insert_with(|| (name.clone(), Vec::new()));
+            lines.push(NewRequisitionLine { alert, method, suggested, unit_cost: source.unit_cost });
+        }
+
+        let mut requisition_ids = Vec::new();
+        for ((supplier_code, currency), (supplier_name, lines)) in groups {
+            let requisition_id = sqlx::query_scalar!(
+                "INSERT INTO warehouse.purchase_requisitions (
+                     requisition_id, requisition_number, warehouse_id, supplier_code, supplier_name, method, currency
+                 )
+                 SELECT next_id, 'PRQ-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4, $5
+                 FROM (SELECT nextval('warehouse.purchase_requisitions_requisition_id_seq')::INT AS next_id) seq
+                 RETURNING requisition_id",
+                warehouse_id,
+                supplier_code,
+                supplier_name,
+                request.method(),
+                currency
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+
+            for line in lines {
+                sqlx::query!(
+                    "INSERT INTO warehouse.purchase_requisition_lines (
+                         requisition_id, item_id, quantity_available, reorder_point, method, suggested_quantity,
+                         quantity, unit_cost
+                     ) VALUES ($1, $2, $3, $4, $5, $6, $6, $7)",
+                    requisition_id,
+                    line.alert.item_id,
+                    line.alert.quantity_available,
+                    line.alert.reorder_point,
+                    line.method,
+                    line.suggested,
+                    line.unit_cost
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+            requisition_ids.push(requisition_id);
+        }
+
+        let mut requisitions = Vec::with_capacity(requisition_ids.len());
+        for id in requisition_ids {
+            requisitions.push(load_requisition(&mut tx, id, false).await?.expect("requisition inserted above"));
+        }
+        tx.commit().await?;
+        Ok(RequisitionRun { requisitions, without_supplier })
+    }
+
+    /// A warehouse's requisitions, newest first
+    pub async fn list(
+        &self,
+        warehouse_id: i32,
+        filter: RequisitionFilter,
+        keyset: KeysetQuery,
+    ) -> Result<KeysetPage<PurchaseRequisition>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            PurchaseRequisition,
+            "SELECT * FROM warehouse.purchase_requisitions
+             WHERE warehouse_id = $1
+               AND ($2::BIGINT IS NULL OR requisition_id < $2)
+               AND ($3::VARCHAR IS NULL OR status = $3)
+             ORDER BY requisition_id DESC
+             LIMIT $4",
+            warehouse_id,
+            after_id,
+            filter.status,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |requisition| requisition.requisition_id as i64))
+    }
+
+    pub async fn get(&self, id: i32) -> Result<Option<RequisitionDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_requisition(&mut conn, id, false).await
+    }
+
+    /// Change what a draft orders of one item. Returns None when the requisition does not exist.
+    pub async fn set_line(&self, id: i32, item_id: i32, line: SetRequisitionLine) -> Result<Option<RequisitionDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_requisition(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        ensure_status(&detail.requisition, REQUISITION_DRAFT, "change")?;
+        if let Some(current) = detail.lines.iter().find(|current| current.item_id == item_id) {
+            let constraints = order_constraints(&mut tx, &detail.requisition.supplier_code, &[item_id]).await?;
+            check_quantity(&detail.requisition, current, line.quantity, &constraints)?;
+        }
+
+        let updated = sqlx::query!(
+            "UPDATE warehouse.purchase_requisition_lines SET quantity = $3 WHERE requisition_id = $1 AND item_id = $2",
+            id,
+            item_id,
+            line.quantity
+        )
+        .execute(&mut *tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(violation(format!("item {} is not on {}", item_id, detail.requisition.requisition_number)));
+        }
+        sqlx::query!("UPDATE warehouse.purchase_requisitions SET updated_at = NOW() WHERE requisition_id = $1", id)
+            .execute(&mut *tx)
+            .await?;
+
+        let detail = load_requisition(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Returns None when the requisition does not exist
+    pub async fn approve(&self, id: i32) -> Result<Option<PurchaseRequisition>> {
+        self.decide(id, REQUISITION_APPROVED, None).await
+    }
+
+    /// Returns None when the requisition does not exist
+    pub async fn reject(&self, id: i32, rejection: RejectRequisition) -> Result<Option<PurchaseRequisition>> {
+        self.decide(id, REQUISITION_REJECTED, Some(rejection.reason)).await
+    }
+
+    async fn decide(&self, id: i32, status: &str, reason: Option<String>) -> Result<Option<PurchaseRequisition>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_requisition(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let action = if status == REQUISITION_APPROVED { "approve" } else { "reject" };
+        ensure_status(&detail.requisition, REQUISITION_DRAFT, action)?;
+
+        let decided = sqlx::query_as!(
+            PurchaseRequisition,
+            "UPDATE warehouse.purchase_requisitions
+             SET status = $2, rejection_reason = $3, decided_at = NOW(), updated_at = NOW()
+             WHERE requisition_id = $1
+             RETURNING *",
+            id,
+            status,
+            reason
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(decided))
+    }
+
+    /// Raise a purchase order for an approved requisition, expected after the supplier's
+    /// longest lead time among its items. Returns None when the requisition does not exist.
+    pub async fn convert(&self, id: i32) -> Result<Option<PurchaseOrderDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_requisition(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let requisition = &detail.requisition;
+        ensure_status(requisition, REQUISITION_APPROVED, "convert")?;
+
+        // The supplier's terms may have changed since the requisition was drafted
+        let item_ids: Vec<i32> = detail.lines.iter().map(|line| line.item_id).collect();
+        let constraints = order_constraints(&mut tx, &requisition.supplier_code, &item_ids).await?;
+        for line in &detail.lines {
+            check_quantity(requisition, line, line.quantity, &constraints)?;
+        }
+
+        let po_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.purchase_orders (
+                 po_id, po_number, warehouse_id, supplier_code, supplier_name, currency, expected_date, created_by
+             )
+             SELECT next_id, 'PO-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4,
+                    CURRENT_DATE + COALESCE((
+                        SELECT MAX(s.lead_time_days) FROM warehouse.item_suppliers s
+                        JOIN warehouse.purchase_requisition_lines l ON l.item_id = s.item_id
+                        WHERE l.requisition_id = $5 AND s.supplier_code = $2
+                    ), 0),
+                    $6
+             FROM (SELECT nextval('warehouse.purchase_orders_po_id_seq')::INT AS next_id) seq
+             RETURNING po_id",
+            requisition.warehouse_id,
+            requisition.supplier_code,
+            requisition.supplier_name,
+            requisition.currency,
+            id,
+            acting_user() // created_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for (line_number, line) in (1..).zip(&detail.lines) {
+            sqlx::query!(
+                "INSERT INTO warehouse.purchase_order_lines (po_id, line_number, item_id, quantity_ordered, unit_cost)
+                 VALUES ($1, $2, $3, $4, $5)",
+                po_id,
+                line_number,
+                line.item_id,
+                line.quantity,
+                line.unit_cost.as_ref().map(|cost| cost.amount)
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.purchase_requisitions SET status = $2, po_id = $3, updated_at = NOW()
+             WHERE requisition_id = $1",
+            id,
+            REQUISITION_CONVERTED,
+            po_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let order = load_purchase_order(&mut tx, po_id).await?.expect("purchase order inserted above");
+        tx.commit().await?;
+        Ok(Some(order))
+    }
+
+    /// A warehouse's purchase orders, newest first
+    pub async fn purchase_orders(&self, warehouse_id: i32, keyset: KeysetQuery) -> Result<KeysetPage<PurchaseOrder>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            PurchaseOrder,
+            "SELECT * FROM warehouse.purchase_orders
+             WHERE warehouse_id = $1 AND ($2::BIGINT IS NULL OR po_id < $2)
+             ORDER BY po_id DESC
+             LIMIT $3",
+            warehouse_id,
+            after_id,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |order| order.po_id as i64))
+    }
+
+    pub async fn get_purchase_order(&self, id: i32) -> Result<Option<PurchaseOrderDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_purchase_order(&mut conn, id).await
+    }
+}
+
+struct NewRequisitionLine {
+    alert: ReorderAlert,
+    method: &'static str,
+    suggested: Decimal,
+    unit_cost: Option<Decimal>,
+}
+
+fn ensure_status(requisition: &PurchaseRequisition, status: &str, action: &str) -> Result<()> {
+    if requisition.status != status {
+        return Err(violation(format!(
+            "cannot {} {}: it is {}",
+            action,
+            requisition.requisition_number,
+            requisition.status.to_lowercase()
+        )));
+    }
+    Ok(())
+}
+
+/// Order constraints of a supplier's items, by item
+async fn order_constraints(
+    conn: &mut PgConnection,
+    supplier_code: &str,
+    item_ids: &[i32],
+) -> Result<BTreeMap<i32, OrderConstraints>> {
+    let rows = sqlx::query!(
+        "SELECT item_id, min_order_quantity, order_multiple, pack_size FROM warehouse.item_suppliers
+         WHERE supplier_code = $1 AND item_id = ANY($2)",
+        supplier_code,
+        item_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let constraints = OrderConstraints {
+                min_order_quantity: row.min_order_quantity,
+                order_multiple: row.order_multiple,
+                pack_size: row.pack_size,
+            };
+            (row.item_id, constraints)
+        })
+        .collect())
+}
+
+fn check_quantity(
+    requisition: &PurchaseRequisition,
+    line: &PurchaseRequisitionLine,
+    quantity: Decimal,
+    constraints: &BTreeMap<i32, OrderConstraints>,
+) -> Result<()> {
+    let violated = constraints.get(&line.item_id).and_then(|constraints| constraints.violation(quantity));
+    match violated {
+        Some(reason) => Err(violation(format!(
+            "cannot order {} of {} from {}: {}",
+            quantity, line.item_code, requisition.supplier_code, reason
+        ))),
+        None => Ok(()),
+    }
+}
+
+async fn load_requisition(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<RequisitionDetail>> {
+    let requisition = if lock {
+        sqlx::query_as!(
+            PurchaseRequisition,
+            "SELECT * FROM warehouse.purchase_requisitions WHERE requisition_id = $1 FOR UPDATE",
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+    } else {
+        sqlx::query_as!(
+            PurchaseRequisition,
+            "SELECT * FROM warehouse.purchase_requisitions WHERE requisition_id = $1",
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+    };
+
+    let Some(requisition) = requisition else {
+        return Ok(None);
+    };
+
+    let rows = sqlx::query!(
+        "SELECT l.*, i.item_code, i.item_name
+         FROM warehouse.purchase_requisition_lines l
+         JOIN warehouse.items i ON i.item_id = l.item_id
+         WHERE l.requisition_id = $1
+         ORDER BY i.item_code",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let lines = rows
+        .into_iter()
+        .map(|row| PurchaseRequisitionLine {
+            requisition_id: row.requisition_id,
+            item_id: row.item_id,
+            item_code: row.item_code,
+            item_name: row.item_name,
+            quantity_available: row.quantity_available,
+            reorder_point: row.reorder_point,
+            method: row.method,
+            suggested_quantity: row.suggested_quantity,
+            quantity: row.quantity,
+            unit_cost: Money::from_column(row.unit_cost, &requisition.currency),
+        })
+        .collect();
+
+    Ok(Some(RequisitionDetail { requisition, lines }))
+}
+
+async fn load_purchase_order(conn: &mut PgConnection, id: i32) -> Result<Option<PurchaseOrderDetail>> {
+    let order = sqlx::query_as!(PurchaseOrder, "SELECT * FROM warehouse.purchase_orders WHERE po_id = $1", id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    let Some(order) = order else {
+        return Ok(None);
+    };
+
+    let rows = sqlx::query!("SELECT * FROM warehouse.purchase_order_lines WHERE po_id = $1 ORDER BY line_number", id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let lines = rows
+        .into_iter()
+        .map(|row| PurchaseOrderLine {
+            line_id: row.line_id,
+            po_id: row.po_id,
+            line_number: row.line_number,
+            item_id: row.item_id,
+            quantity_ordered: row.quantity_ordered,
+            quantity_received: row.quantity_received,
+            unit_cost: Money::from_column(row.unit_cost, &order.currency),
+        })
+        .collect();
+
+    Ok(Some(PurchaseOrderDetail { order, lines }))
+}
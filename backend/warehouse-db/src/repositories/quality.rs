@@ -0,0 +1,203 @@
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::movements::post_movement;
+use crate::utils::*;
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct QualityRepository {
+    pool: PgPool,
+}
+
+impl QualityRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// List inspections, oldest pending work first
+    pub async fn list(
+        &self,
+        filter: InspectionFilter,
+        pagination: PaginationQuery,
+    ) -> Result<PaginatedResponse<QualityInspection>> {
+        let (page, limit) = validate_pagination(&pagination);
+        let offset = calculate_offset(page, limit);
+        let mode = pagination.count.unwrap_or_default();
+
+        let total = match mode {
+            CountMode::Exact => {
+                sqlx::query_scalar!(
+                    "SELECT COUNT(*) FROM warehouse.quality_inspections
+                 WHERE ($1::VARCHAR IS NULL OR status = $1)
+                   AND ($2::INT IS NULL OR warehouse_id = $2)
+                   AND ($3::INT IS NULL OR item_id = $3)",
+                    filter.status,
+                    filter.warehouse_id,
+                    filter.item_id
+                )
+                .fetch_one(&self.pool)
+                .await?
+            }
+            CountMode::Estimate => {
+                Some(estimate_row_count(&self.pool, "warehouse.quality_inspections").await?)
+            }
+            CountMode::Skip => None,
+        };
+
+        let rows = sqlx::query_as!(
+            QualityInspection,
+            "SELECT * FROM warehouse.quality_inspections
+             WHERE ($1::VARCHAR IS NULL OR status = $1)
+               AND ($2::INT IS NULL OR warehouse_id = $2)
+               AND ($3::INT IS NULL OR item_id = $3)
+             ORDER BY inspection_id
+             LIMIT $4 OFFSET $5",
+            filter.status,
+            filter.warehouse_id,
+            filter.item_id,
+            limit + 1,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PaginatedResponse::from_probe(rows, total, mode, page, limit))
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<QualityInspection>> {
+        let inspection = sqlx::query_as!(
+            QualityInspection,
+            "SELECT * FROM warehouse.quality_inspections WHERE inspection_id = $1",
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(inspection)
+    }
+
+    /// Receive a lot into quarantine and open a pending inspection for it
+    pub async fn create(&self, lot: CreateInspection) -> Result<QualityInspection> {
+        let mut tx = self.pool.begin().await?;
+
+        let inspection = sqlx::query_as!(
+            QualityInspection,
+            "INSERT INTO warehouse.quality_inspections (
+                 item_id, warehouse_id, quantity, reference_type, reference_number, created_by
+             ) VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING *",
+            lot.item_id,
+            lot.warehouse_id,
+            lot.quantity,
+            lot.reference_type,
+            lot.reference_number,
+            acting_user() // created_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        post_movement(
+            &mut tx,
+            inspection_movement(&inspection, "RECEIPT", lot.quantity, STOCK_QUARANTINE),
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(inspection)
+    }
+
+    /// Record the inspection result: a pass releases the whole lot to available stock,
+    /// a fail rejects it out of stock. Returns None when the inspection does not exist.
+    pub async fn record(&self, id: i32, outcome: RecordInspection) -> Result<Option<QualityInspection>> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(pending) = sqlx::query_as!(
+            QualityInspection,
+            "SELECT * FROM warehouse.quality_inspections WHERE inspection_id = $1 FOR UPDATE",
+            id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        if pending.status != INSPECTION_PENDING {
+            return Err(violation(format!(
+                "inspection {} was already {}",
+                id,
+                pending.status.to_lowercase()
+            )));
+        }
+
+        let status = match outcome.result {
+            InspectionResult::Pass => {
+                post_movement(
+                    &mut tx,
+                    inspection_movement(&pending, "QC_RELEASE", -pending.quantity, STOCK_QUARANTINE),
+                )
+                .await?;
+                post_movement(
+                    &mut tx,
+                    inspection_movement(&pending, "QC_RELEASE", pending.quantity, STOCK_AVAILABLE),
+                )
+                .await?;
+                INSPECTION_PASSED
+            }
+            InspectionResult::Fail => {
+                post_movement(
+                    &mut tx,
+                    inspection_movement(&pending, "QC_REJECT", -pending.quantity, STOCK_QUARANTINE),
+                )
+                .await?;
+                INSPECTION_FAILED
+            }
+        };
+
+        let inspection = sqlx::query_as!(
+            QualityInspection,
+            "UPDATE warehouse.quality_inspections SET
+                 status = $2, sample_size = $3, defects_found = $4, notes = $5,
+                 inspected_at = NOW(), inspected_by = $6
+             WHERE inspection_id = $1
+             RETURNING *",
+            id,
+            status,
+            outcome.sample_size,
+            outcome.defects_found,
+            outcome.notes,
+            acting_user() // inspected_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(inspection))
+    }
+}
+
+fn inspection_movement(
+    inspection: &QualityInspection,
+    movement_type: &str,
+    quantity: Decimal,
+    stock_status: &'static str,
+) -> NewMovement {
+    NewMovement {
+        item_id: inspection.item_id,
+        warehouse_id: inspection.warehouse_id,
+        movement_type: movement_type.to_string(),
+        quantity,
+        catch_weight_kg: None,
+        lot_number: None,
+        unit_cost: None,
+        stock_status,
+        consignor_id: None,
+        reference_type: Some("INSPECTION".to_string()),
+        reference_number: Some(inspection.inspection_id.to_string()),
+        notes: None,
+        created_by: inspection.created_by,
+    }
+}
@@ -0,0 +1,168 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::*;
+
+/// Serializes projector runs across server instances
+const PROJECTOR_LOCK: i64 = 0x7265_6164_6d6f;
+
+#[derive(Clone)]
+pub struct ReadModelRepository {
+    pool: PgPool,
+}
+
+impl ReadModelRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Apply up to `batch` pending domain events to the stock read model, rebuilding the rows
+    /// each event touches from the source tables. Returns the number of events projected.
+    pub async fn project(&self, batch: i64) -> Result<usize> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!("SELECT pg_advisory_xact_lock($1)", PROJECTOR_LOCK).execute(&mut *tx).await?;
+
+        let event_ids = sqlx::query_scalar!(
+            "SELECT event_id FROM warehouse.domain_events
+             WHERE projected_at IS NULL
+             ORDER BY event_id
+             LIMIT $1",
+            batch
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if event_ids.is_empty() {
+            return Ok(0);
+        }
+
+        // Rows are rebuilt from current state, so the order events committed in does not matter
+        sqlx::query!(
+            "DELETE FROM warehouse.stock_read_model r
+             WHERE EXISTS (
+                 SELECT 1 FROM warehouse.domain_events e
+                 WHERE e.event_id = ANY($1)
+                   AND ((e.aggregate_type = 'STOCK' AND e.item_id = r.item_id AND e.warehouse_id = r.warehouse_id
+                         AND e.consignor_id IS NOT DISTINCT FROM r.consignor_id)
+                        OR (e.aggregate_type = 'ITEM' AND e.item_id = r.item_id)
+                        OR (e.aggregate_type = 'WAREHOUSE' AND e.warehouse_id = r.warehouse_id)))",
+            &event_ids
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "INSERT INTO warehouse.stock_read_model (
+                 item_id, warehouse_id, consignor_id, client_id, item_code, item_name, category, unit, item_status,
+                 warehouse_code, warehouse_name, quantity_on_hand, quantity_reserved, quantity_available,
+                 quantity_quarantined, catch_weight_kg, reorder_point, last_movement_date
+             )
+             SELECT s.item_id, s.warehouse_id, s.consignor_id, s.client_id, i.item_code, i.item_name, i.category,
+                    i.unit, i.status, w.warehouse_code, w.warehouse_name, s.quantity_on_hand, s.quantity_reserved,
+                    s.quantity_available, s.quantity_quarantined, s.catch_weight_kg, s.reorder_point,
+                    s.last_movement_date
+             FROM warehouse.stock_inventory s
+             JOIN warehouse.items i ON i.item_id = s.item_id
+             JOIN warehouse.warehouses w ON w.warehouse_id = s.warehouse_id
+             WHERE EXISTS (
+                 SELECT 1 FROM warehouse.domain_events e
+                 WHERE e.event_id = ANY($1)
+                   AND ((e.aggregate_type = 'STOCK' AND e.item_id = s.item_id AND e.warehouse_id = s.warehouse_id
+                         AND e.consignor_id IS NOT DISTINCT FROM s.consignor_id)
+                        OR (e.aggregate_type = 'ITEM' AND e.item_id = s.item_id)
+                        OR (e.aggregate_type = 'WAREHOUSE' AND e.warehouse_id = s.warehouse_id)))",
+            &event_ids
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!("UPDATE warehouse.domain_events SET projected_at = NOW() WHERE event_id = ANY($1)", &event_ids)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query!("DELETE FROM warehouse.domain_events WHERE projected_at < NOW() - INTERVAL '1 day'")
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(event_ids.len())
+    }
+
+    /// Queue the stock read model rows of every balance with a stock event at or after
+    /// `from_event_id` to be rebuilt by the next projections. Returns how many were queued.
+    pub async fn requeue_from(&self, from_event_id: i64) -> Result<i64> {
+        let queued = sqlx::query!(
+            "INSERT INTO warehouse.domain_events (aggregate_type, item_id, warehouse_id, consignor_id)
+             SELECT DISTINCT 'STOCK', item_id, warehouse_id, consignor_id
+             FROM warehouse.stock_events
+             WHERE event_id >= $1",
+            from_event_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(queued as i64)
+    }
+
+    /// Current stock on hand by item, warehouse and owner, as of the last projection
+    pub async fn stock(&self, filter: StockFilter) -> Result<Vec<StockBalance>> {
+        let balances = sqlx::query_as!(
+            StockBalance,
+            r#"SELECT item_id, item_code, item_name, warehouse_id, consignor_id, client_id,
+                      quantity_on_hand, quantity_quarantined, catch_weight_kg
+               FROM warehouse.stock_read_model
+               WHERE (quantity_on_hand <> 0 OR quantity_quarantined <> 0)
+                 AND ($1::INT IS NULL OR warehouse_id = $1)
+                 AND ($2::INT IS NULL OR item_id = $2)
+                 AND ($3::TEXT IS NULL OR item_code ILIKE '%' || $3 || '%' OR item_name ILIKE '%' || $3 || '%')
+               ORDER BY item_code, warehouse_id, consignor_id NULLS FIRST"#,
+            filter.warehouse_id,
+            filter.item_id,
+            filter.q
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(balances)
+    }
+
+    /// Stock a 3PL client owns, by item and warehouse, as of the last projection
+    pub async fn client_stock(&self, client_id: i32, filter: ClientStockFilter) -> Result<Vec<ClientStock>> {
+        let stock = sqlx::query_as!(
+            ClientStock,
+            r#"SELECT item_id, item_code, item_name, warehouse_id,
+                      SUM(quantity_on_hand) AS "quantity_on_hand!",
+                      SUM(quantity_reserved) AS "quantity_reserved!",
+                      SUM(quantity_available) AS "quantity_available!",
+                      SUM(quantity_quarantined) AS "quantity_quarantined!"
+               FROM warehouse.stock_read_model
+               WHERE client_id = $1
+                 AND ($2::INT IS NULL OR warehouse_id = $2)
+                 AND ($3::INT IS NULL OR item_id = $3)
+               GROUP BY item_id, item_code, item_name, warehouse_id
+               ORDER BY item_code, warehouse_id"#,
+            client_id,
+            filter.warehouse_id,
+            filter.item_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(stock)
+    }
+
+    /// Backlog of events not yet projected
+    pub async fn status(&self) -> Result<ReadModelStatus> {
+        let status = sqlx::query_as!(
+            ReadModelStatus,
+            r#"SELECT COUNT(*) FILTER (WHERE projected_at IS NULL) AS "pending_events!",
+                      MIN(occurred_at) FILTER (WHERE projected_at IS NULL) AS oldest_pending_at,
+                      MAX(projected_at) AS last_projected_at
+               FROM warehouse.domain_events"#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(status)
+    }
+}
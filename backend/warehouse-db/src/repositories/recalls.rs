@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::movements::post_movement;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct RecallRepository {
+    pool: PgPool,
+}
+
+impl RecallRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self, filter: RecallFilter) -> Result<Vec<Recall>> {
+        let recalls = sqlx::query_as!(
+            Recall,
+            "SELECT * FROM warehouse.recalls WHERE ($1::VARCHAR IS NULL OR status = $1) ORDER BY recall_id DESC",
+            filter.status
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(recalls)
+    }
+
+    pub async fn get(&self, id: i32) -> Result<Option<RecallReport>> {
+        let mut conn = self.pool.acquire().await?;
+        let Some(recall) = sqlx::query_as!(Recall, "SELECT * FROM warehouse.recalls WHERE recall_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(report(&mut conn, recall).await?))
+    }
+
+    /// Open a recall and put the affected stock on hold: our own available stock received
+    /// under the lot or serials moves to quarantine, as far as it is not reserved.
+    pub async fn create(&self, recall: CreateRecall) -> Result<RecallReport> {
+        let mut tx = self.pool.begin().await?;
+
+        let created = sqlx::query_as!(
+            Recall,
+            "INSERT INTO warehouse.recalls (
+                 recall_id, recall_number, item_id, lot_number, serial_from, serial_to, reason, created_by
+             )
+             SELECT next_id, 'RCL-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4, $5, $6
+             FROM (SELECT nextval('warehouse.recalls_recall_id_seq')::INT AS next_id) seq
+             RETURNING *",
+            recall.item_id,
+            recall.lot_number,
+            recall.serial_from,
+            recall.serial_to,
+            recall.reason,
+            acting_user() // created_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let affected = sqlx::query!(
+            r#"SELECT warehouse_id, lot_number AS "lot_number!", SUM(quantity) AS "quantity!"
+               FROM warehouse.stock_movements
+               WHERE item_id = $1 AND stock_status = $5 AND consignor_id IS NULL
+                 AND (lot_number = $2
+                      OR (LENGTH(lot_number) = LENGTH($3) AND lot_number COLLATE "C" BETWEEN $3 AND $4))
+               GROUP BY warehouse_id, lot_number
+               HAVING SUM(quantity) > 0
+               ORDER BY warehouse_id, lot_number"#,
+            created.item_id,
+            created.lot_number,
+            created.serial_from,
+            created.serial_to,
+            STOCK_AVAILABLE
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for lot in affected {
+            let free = sqlx::query_scalar!(
+                "SELECT quantity_available FROM warehouse.stock_inventory
+                 WHERE item_id = $1 AND warehouse_id = $2 AND consignor_id IS NULL
+                 FOR UPDATE",
+                created.item_id,
+                lot.warehouse_id
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            .flatten()
+            .unwrap_or_default();
+
+            let quantity = lot.quantity.min(free);
+            if quantity <= Decimal::ZERO {
+                continue;
+            }
+            hold(&mut tx, &created, lot.warehouse_id, &lot.lot_number, quantity).await?;
+        }
+
+        let report = report(&mut tx, created).await?;
+        tx.commit().await?;
+        Ok(report)
+    }
+
+    /// Close a recall once it has been dealt with. Held stock stays in quarantine until it
+    /// is released or written off. Returns None when the recall does not exist.
+    pub async fn close(&self, id: i32) -> Result<Option<Recall>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(recall) =
+            sqlx::query_as!(Recall, "SELECT * FROM warehouse.recalls WHERE recall_id = $1 FOR UPDATE", id)
+                .fetch_optional(&mut *tx)
+                .await?
+        else {
+            return Ok(None);
+        };
+        if recall.status != RECALL_OPEN {
+            return Err(violation(format!("{} is already closed", recall.recall_number)));
+        }
+
+        let closed = sqlx::query_as!(
+            Recall,
+            "UPDATE warehouse.recalls SET status = $2, closed_at = NOW() WHERE recall_id = $1 RETURNING *",
+            id,
+            RECALL_CLOSED
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(closed))
+    }
+}
+
+async fn hold(
+    conn: &mut PgConnection,
+    recall: &Recall,
+    warehouse_id: i32,
+    lot_number: &str,
+    quantity: Decimal,
+) -> Result<()> {
+    for (quantity, stock_status) in [(-quantity, STOCK_AVAILABLE), (quantity, STOCK_QUARANTINE)] {
+        post_movement(
+            &mut *conn,
+            NewMovement {
+                item_id: recall.item_id,
+                warehouse_id,
+                movement_type: MOVEMENT_RECALL_HOLD.to_string(),
+                quantity,
+                catch_weight_kg: None,
+                lot_number: Some(lot_number.to_string()),
+                unit_cost: None,
+                stock_status,
+                consignor_id: None,
+                reference_type: Some("RECALL".to_string()),
+                reference_number: Some(recall.recall_number.clone()),
+                notes: Some(recall.reason.clone()),
+                created_by: acting_user(),
+            },
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+async fn report(conn: &mut PgConnection, recall: Recall) -> Result<RecallReport> {
+    let stock = sqlx::query_as!(
+        RecallStock,
+        r#"SELECT m.warehouse_id, w.warehouse_code, m.lot_number AS "lot_number!",
+                  COALESCE(SUM(m.quantity) FILTER (WHERE m.stock_status = $5), 0) AS "quantity_available!",
+                  COALESCE(SUM(m.quantity) FILTER (WHERE m.stock_status = $6), 0) AS "quantity_on_hold!"
+           FROM warehouse.stock_movements m
+           JOIN warehouse.warehouses w ON w.warehouse_id = m.warehouse_id
+           WHERE m.item_id = $1 AND m.consignor_id IS NULL
+             AND (m.lot_number = $2
+                  OR (LENGTH(m.lot_number) = LENGTH($3) AND m.lot_number COLLATE "C" BETWEEN $3 AND $4))
+           GROUP BY m.warehouse_id, w.warehouse_code, m.lot_number
+           HAVING SUM(m.quantity) > 0
+           ORDER BY w.warehouse_code, m.lot_number"#,
+        recall.item_id,
+        recall.lot_number,
+        recall.serial_from,
+        recall.serial_to,
+        STOCK_AVAILABLE,
+        STOCK_QUARANTINE
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let shipments = sqlx::query_as!(
+        RecallShipment,
+        r#"SELECT s.shipment_id, s.shipment_number, o.order_number, o.customer_name, o.customer_reference,
+                  s.shipped_at, sl.lot_number AS "lot_number!", SUM(sl.quantity) AS "quantity!"
+           FROM warehouse.shipment_lines sl
+           JOIN warehouse.shipments s ON s.shipment_id = sl.shipment_id
+           JOIN warehouse.outbound_orders o ON o.order_id = s.order_id
+           WHERE sl.item_id = $1
+             AND (sl.lot_number = $2
+                  OR (LENGTH(sl.lot_number) = LENGTH($3) AND sl.lot_number COLLATE "C" BETWEEN $3 AND $4))
+           GROUP BY s.shipment_id, o.order_id, sl.lot_number
+           ORDER BY s.shipped_at, s.shipment_id, sl.lot_number"#,
+        recall.item_id,
+        recall.lot_number,
+        recall.serial_from,
+        recall.serial_to
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut exposure: BTreeMap<(String, Option<String>), (Vec<i32>, Decimal)> = BTreeMap::new();
+    for shipment in &shipments {
+        let (shipment_ids, quantity) =
+            exposure.entry((shipment.customer_name.clone(), shipment.customer_reference.clone())).or_default();
+        if !shipment_ids.contains(&shipment.shipment_id) {
+            shipment_ids.push(shipment.shipment_id);
+        }
+        *quantity += shipment.quantity;
+    }
+    let exposure = exposure
+        .into_iter()
+        .map(|((customer_name, customer_reference), (shipment_ids, quantity))| RecallExposure {
+            customer_name,
+            customer_reference,
+            shipments: shipment_ids.len(),
+            quantity,
+        })
+        .collect();
+
+    Ok(RecallReport { recall, stock, shipments, exposure })
+}
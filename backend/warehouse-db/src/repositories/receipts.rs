@@ -0,0 +1,356 @@
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::catch_weight::check_weight;
+use crate::repositories::lpns::{insert_lpn, load_lpn};
+use crate::repositories::movements::{lock_stock, post_movement};
+use crate::repositories::postings::{claim, Claim};
+use crate::utils::*;
+
+#[derive(Clone)]
+pub struct ReceiptRepository {
+    pool: PgPool,
+}
+
+impl ReceiptRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// List inbound receipts, newest first
+    pub async fn list(
+        &self,
+        filter: ReceiptFilter,
+        pagination: PaginationQuery,
+    ) -> Result<PaginatedResponse<InboundReceipt>> {
+        let (page, limit) = validate_pagination(&pagination);
+        let offset = calculate_offset(page, limit);
+        let mode = pagination.count.unwrap_or_default();
+
+        let total = match mode {
+            CountMode::Exact => sqlx::query_scalar!(
+                "SELECT COUNT(*) FROM warehouse.inbound_receipts
+                 WHERE ($1::VARCHAR IS NULL OR status = $1)
+                   AND ($2::INT IS NULL OR warehouse_id = $2)",
+                filter.status,
+                filter.warehouse_id
+            )
+            .fetch_one(&self.pool)
+            .await?,
+            CountMode::Estimate => Some(estimate_row_count(&self.pool, "warehouse.inbound_receipts").await?),
+            CountMode::Skip => None,
+        };
+
+        let rows = sqlx::query_as!(
+            InboundReceipt,
+            "SELECT * FROM warehouse.inbound_receipts
+             WHERE ($1::VARCHAR IS NULL OR status = $1)
+               AND ($2::INT IS NULL OR warehouse_id = $2)
+             ORDER BY receipt_id DESC
+             LIMIT $3 OFFSET $4",
+            filter.status,
+            filter.warehouse_id,
+            limit + 1,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PaginatedResponse::from_probe(rows, total, mode, page, limit))
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<ReceiptDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_receipt(&mut conn, id, false).await
+    }
+
+    pub async fn asn_exists(&self, warehouse_id: i32, asn_number: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.inbound_receipts WHERE warehouse_id = $1 AND asn_number = $2)",
+            warehouse_id,
+            asn_number
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    pub async fn create(&self, receipt: CreateReceipt) -> Result<ReceiptDetail> {
+        self.create_from(receipt, SOURCE_API).await
+    }
+
+    /// Create an expected receipt announced through a channel other than the JSON API
+    pub async fn create_from(&self, receipt: CreateReceipt, source: &str) -> Result<ReceiptDetail> {
+        let mut tx = self.pool.begin().await?;
+
+        // Consumption of consigned goods is billed at the agreed price, so there must be one
+        if let Some(consignor_id) = receipt.consignor_id {
+            let item_ids: Vec<i32> = receipt.lines.iter().map(|line| line.item_id).collect();
+            let unpriced = sqlx::query_scalar!(
+                "SELECT id AS \"id!\" FROM UNNEST($2::INT[]) id
+                 EXCEPT SELECT item_id FROM warehouse.consignment_prices WHERE consignor_id = $1",
+                consignor_id,
+                &item_ids
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+            if let Some(item_id) = unpriced.first() {
+                return Err(violation(format!("consignor {} has no agreed price for item {}", consignor_id, item_id)));
+            }
+        }
+
+        let receipt_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.inbound_receipts (
+                 asn_number, warehouse_id, supplier_name, purchase_order_number, carrier_code,
+                 expected_date, source, created_by, consignor_id
+             ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             RETURNING receipt_id",
+            receipt.asn_number,
+            receipt.warehouse_id,
+            receipt.supplier_name,
+            receipt.purchase_order_number,
+            receipt.carrier_code,
+            receipt.expected_date,
+            source,
+            acting_user(), // created_by
+            receipt.consignor_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for line in receipt.lines {
+            sqlx::query!(
+                "INSERT INTO warehouse.inbound_receipt_lines (receipt_id, item_id, quantity_expected, lpn_number)
+                 VALUES ($1, $2, $3, $4)",
+                receipt_id,
+                line.item_id,
+                line.quantity_expected,
+                line.lpn_number
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query!(
+            "INSERT INTO warehouse.tasks (warehouse_id, task_type, quantity, reference_type, reference_id, due_at, created_by)
+             SELECT r.warehouse_id, $2, SUM(l.quantity_expected), $3, r.receipt_id, r.expected_date::TIMESTAMPTZ, r.created_by
+             FROM warehouse.inbound_receipts r
+             JOIN warehouse.inbound_receipt_lines l ON l.receipt_id = r.receipt_id
+             WHERE r.receipt_id = $1
+             GROUP BY r.receipt_id",
+            receipt_id,
+            TASK_RECEIVE,
+            TASK_REF_RECEIPT
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let detail = load_receipt(&mut tx, receipt_id, false)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("receipt {} vanished after insert", receipt_id))?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Post counted goods into available stock. Returns None when the receipt does not exist.
+    pub async fn receive(&self, id: i32, goods: ReceiveGoods) -> Result<Option<ReceiptDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_receipt(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let reference_id = goods.reference_id.as_deref();
+        if let Claim::Replay(_) = claim(&mut tx, reference_id, POSTING_RECEIPT, id).await? {
+            return Ok(Some(detail));
+        }
+        ensure_open(&detail.receipt)?;
+
+        receive_lines(&mut tx, &detail, goods.lines).await?;
+
+        let detail = load_receipt(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Receive every line labelled with a supplier LPN in full and put the goods on an LPN
+    /// with that barcode. Catch-weight lines need their actual weight and are received line
+    /// by line instead. Returns None when the receipt does not exist.
+    pub async fn receive_lpn(
+        &self,
+        id: i32,
+        lpn_number: &str,
+        receiving: ReceiveLpn,
+    ) -> Result<Option<(ReceiptDetail, LpnDetail)>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_receipt(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let receipt = &detail.receipt;
+        ensure_open(receipt)?;
+
+        let on_lpn: Vec<&ReceiptLine> =
+            detail.lines.iter().filter(|line| line.lpn_number.as_deref() == Some(lpn_number)).collect();
+        if on_lpn.is_empty() {
+            return Err(violation(format!("ASN {} has no lines on LPN {}", receipt.asn_number, lpn_number)));
+        }
+        if on_lpn.iter().any(|line| line.quantity_received > Decimal::ZERO) {
+            return Err(violation(format!("LPN {} has already been received", lpn_number)));
+        }
+        let counted =
+            on_lpn.iter().map(|line| ReceiveGoodsLine { line_id: line.line_id, quantity: line.quantity_expected, weight_kg: None, lot_number: None }).collect();
+
+        let lpn_id = insert_lpn(
+            &mut tx,
+            receipt.warehouse_id,
+            Some(lpn_number),
+            receiving.lpn_type.as_deref().unwrap_or(LPN_PALLET),
+            receiving.location_id,
+            None,
+            Some(id),
+        )
+        .await?;
+        sqlx::query!(
+            "INSERT INTO warehouse.lpn_contents (lpn_id, item_id, quantity)
+             SELECT $1, item_id, SUM(quantity_expected) FROM warehouse.inbound_receipt_lines
+             WHERE receipt_id = $2 AND lpn_number = $3
+             GROUP BY item_id",
+            lpn_id,
+            id,
+            lpn_number
+        )
+        .execute(&mut *tx)
+        .await?;
+        receive_lines(&mut tx, &detail, counted).await?;
+
+        let detail = load_receipt(&mut tx, id, false).await?.expect("receipt locked above");
+        let lpn = load_lpn(&mut tx, lpn_id).await?.expect("lpn inserted above");
+        tx.commit().await?;
+        Ok(Some((detail, lpn)))
+    }
+}
+
+fn ensure_open(receipt: &InboundReceipt) -> Result<()> {
+    if receipt.status == RECEIPT_RECEIVED {
+        return Err(violation(format!("ASN {} is already fully received", receipt.asn_number)));
+    }
+    Ok(())
+}
+
+/// Post counted lines into stock, then update the receipt status and close its receiving
+/// task once everything is in
+async fn receive_lines(conn: &mut PgConnection, detail: &ReceiptDetail, lines: Vec<ReceiveGoodsLine>) -> Result<()> {
+    let receipt = &detail.receipt;
+    let id = receipt.receipt_id;
+    let item_ids: Vec<i32> = detail.lines.iter().map(|line| line.item_id).collect();
+    lock_stock(conn, receipt.warehouse_id, &item_ids).await?;
+
+    for counted in lines {
+        let line = detail
+            .lines
+            .iter()
+            .find(|line| line.line_id == counted.line_id)
+            .ok_or_else(|| violation(format!("line {} is not part of ASN {}", counted.line_id, receipt.asn_number)))?;
+        let weight_kg = check_weight(&mut *conn, line.item_id, counted.quantity, counted.weight_kg).await?;
+
+        post_movement(
+            &mut *conn,
+            NewMovement {
+                item_id: line.item_id,
+                warehouse_id: receipt.warehouse_id,
+                movement_type: "RECEIPT".to_string(),
+                quantity: counted.quantity,
+                catch_weight_kg: weight_kg,
+                lot_number: counted.lot_number.clone(),
+                unit_cost: None,
+                stock_status: STOCK_AVAILABLE,
+                consignor_id: receipt.consignor_id,
+                reference_type: Some("ASN".to_string()),
+                reference_number: Some(receipt.asn_number.clone()),
+                notes: receipt.purchase_order_number.clone(),
+                created_by: receipt.created_by,
+            },
+        )
+        .await?;
+
+        sqlx::query!(
+            "UPDATE warehouse.inbound_receipt_lines SET
+                 quantity_received = quantity_received + $2,
+                 weight_received_kg = COALESCE(weight_received_kg + $3, $3, weight_received_kg)
+             WHERE line_id = $1",
+            line.line_id,
+            counted.quantity,
+            weight_kg
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    sqlx::query!(
+        "UPDATE warehouse.inbound_receipts SET
+             status = CASE WHEN EXISTS (
+                 SELECT 1 FROM warehouse.inbound_receipt_lines
+                 WHERE receipt_id = $1 AND quantity_received < quantity_expected
+             ) THEN $2 ELSE $3 END,
+             received_at = COALESCE(received_at, NOW()),
+             updated_at = NOW()
+         WHERE receipt_id = $1",
+        id,
+        RECEIPT_PARTIALLY_RECEIVED,
+        RECEIPT_RECEIVED
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Close the receiving task once the last line is in, crediting whoever claimed it
+    sqlx::query!(
+        "UPDATE warehouse.tasks SET
+             status = $3, completed_at = NOW(), completed_by = assigned_to,
+             started_at = COALESCE(started_at, NOW()),
+             quantity_done = (SELECT SUM(quantity_received) FROM warehouse.inbound_receipt_lines WHERE receipt_id = $2)
+         WHERE reference_type = $1 AND reference_id = $2 AND status NOT IN ($3, $4)
+           AND EXISTS (SELECT 1 FROM warehouse.inbound_receipts WHERE receipt_id = $2 AND status = $5)",
+        TASK_REF_RECEIPT,
+        id,
+        TASK_COMPLETED,
+        TASK_CANCELLED,
+        RECEIPT_RECEIVED
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn load_receipt(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<ReceiptDetail>> {
+    let receipt = if lock {
+        sqlx::query_as!(
+            InboundReceipt,
+            "SELECT * FROM warehouse.inbound_receipts WHERE receipt_id = $1 FOR UPDATE",
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+    } else {
+        sqlx::query_as!(InboundReceipt, "SELECT * FROM warehouse.inbound_receipts WHERE receipt_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    };
+
+    let Some(receipt) = receipt else {
+        return Ok(None);
+    };
+
+    let lines = sqlx::query_as!(
+        ReceiptLine,
+        "SELECT * FROM warehouse.inbound_receipt_lines WHERE receipt_id = $1 ORDER BY line_id",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(ReceiptDetail { receipt, lines }))
+}
@@ -0,0 +1,143 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+use crate::utils::validate_keyset;
+
+#[derive(Clone)]
+pub struct ReconciliationRepository {
+    pool: PgPool,
+}
+
+impl ReconciliationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Compare the extract with current on-hand stock, summed over owners, line by line for
+    /// every item and warehouse in either. WMS balances are only reported as missing from the
+    /// ERP in warehouses the extract covers.
+    pub async fn reconcile(&self, extract: ErpExtract) -> Result<ErpReconciliationDetail> {
+        let mut tx = self.pool.begin().await?;
+
+        let reconciliation_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.erp_reconciliations (as_of, source) VALUES ($1, $2) RETURNING reconciliation_id",
+            extract.as_of,
+            extract.source
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let item_codes: Vec<String> = extract.lines.iter().map(|line| line.item_code.clone()).collect();
+        let warehouse_codes: Vec<String> = extract.lines.iter().map(|line| line.warehouse_code.clone()).collect();
+        let quantities: Vec<Decimal> = extract.lines.iter().map(|line| line.quantity).collect();
+        sqlx::query!(
+            "WITH erp AS (
+                 SELECT e.item_code, e.warehouse_code, SUM(e.quantity) AS quantity, i.item_id, w.warehouse_id
+                 FROM UNNEST($2::VARCHAR[], $3::VARCHAR[], $4::NUMERIC[]) AS e(item_code, warehouse_code, quantity)
+                 LEFT JOIN warehouse.items i ON i.item_code = e.item_code
+                 LEFT JOIN warehouse.warehouses w ON w.warehouse_code = e.warehouse_code
+                 GROUP BY e.item_code, e.warehouse_code, i.item_id, w.warehouse_id
+             ),
+             wms AS (
+                 SELECT s.item_id, s.warehouse_id, SUM(s.quantity_on_hand) AS quantity
+                 FROM warehouse.stock_inventory s
+                 WHERE s.warehouse_id IN (SELECT warehouse_id FROM erp)
+                 GROUP BY s.item_id, s.warehouse_id
+             )
+             INSERT INTO warehouse.erp_reconciliation_lines
+                 (reconciliation_id, item_id, item_code, warehouse_id, warehouse_code, erp_quantity, wms_quantity, status)
+             SELECT $1, COALESCE(e.item_id, wms.item_id), COALESCE(e.item_code, i.item_code),
+                    COALESCE(e.warehouse_id, wms.warehouse_id), COALESCE(e.warehouse_code, w.warehouse_code),
+                    e.quantity, wms.quantity,
+                    CASE WHEN e.item_code IS NULL THEN 'MISSING_IN_ERP'
+                         WHEN e.item_id IS NULL OR e.warehouse_id IS NULL THEN 'UNKNOWN'
+                         WHEN e.quantity = COALESCE(wms.quantity, 0) THEN 'MATCHED'
+                         WHEN wms.item_id IS NULL THEN 'MISSING_IN_WMS'
+                         ELSE 'VARIANCE'
+                    END
+             FROM erp e
+             FULL JOIN wms ON wms.item_id = e.item_id AND wms.warehouse_id = e.warehouse_id
+             LEFT JOIN warehouse.items i ON i.item_id = wms.item_id
+             LEFT JOIN warehouse.warehouses w ON w.warehouse_id = wms.warehouse_id
+             WHERE e.item_code IS NOT NULL OR wms.quantity <> 0
+             ORDER BY 5, 3",
+            reconciliation_id,
+            &item_codes,
+            &warehouse_codes,
+            &quantities
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let reconciliation = sqlx::query_as!(
+            ErpReconciliation,
+            "UPDATE warehouse.erp_reconciliations r
+             SET lines = counts.lines, matched = counts.matched, variances = counts.lines - counts.matched
+             FROM (
+                 SELECT COUNT(*)::INTEGER AS lines, (COUNT(*) FILTER (WHERE status = 'MATCHED'))::INTEGER AS matched
+                 FROM warehouse.erp_reconciliation_lines WHERE reconciliation_id = $1
+             ) counts
+             WHERE r.reconciliation_id = $1
+             RETURNING r.*",
+            reconciliation_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        let lines = self.lines(reconciliation_id, ErpReconciliationFilter::default()).await?;
+        Ok(ErpReconciliationDetail { reconciliation, lines })
+    }
+
+    /// Reconciliations newest first, paged by `reconciliation_id`
+    pub async fn list(&self, keyset: KeysetQuery) -> Result<KeysetPage<ErpReconciliation>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            ErpReconciliation,
+            "SELECT * FROM warehouse.erp_reconciliations
+             WHERE ($1::BIGINT IS NULL OR reconciliation_id < $1)
+             ORDER BY reconciliation_id DESC
+             LIMIT $2",
+            after_id,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |r| r.reconciliation_id as i64))
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<ErpReconciliation>> {
+        let reconciliation = sqlx::query_as!(
+            ErpReconciliation,
+            "SELECT * FROM warehouse.erp_reconciliations WHERE reconciliation_id = $1",
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(reconciliation)
+    }
+
+    /// Lines of a reconciliation by warehouse and item
+    pub async fn lines(&self, id: i32, filter: ErpReconciliationFilter) -> Result<Vec<ErpReconciliationLine>> {
+        let lines = sqlx::query_as!(
+            ErpReconciliationLine,
+            "SELECT * FROM warehouse.erp_reconciliation_lines
+             WHERE reconciliation_id = $1
+               AND ($2::VARCHAR IS NULL OR status = $2)
+               AND ($3::VARCHAR IS NULL OR warehouse_code = $3)
+             ORDER BY line_id",
+            id,
+            filter.status,
+            filter.warehouse_code
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(lines)
+    }
+}
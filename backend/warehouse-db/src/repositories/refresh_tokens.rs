@@ -0,0 +1,122 @@
+use anyhow::Result;
+use rand::distributions::{Alphanumeric, DistString};
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::chrono::{Duration, Utc};
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct RefreshTokenRepository {
+    pool: PgPool,
+}
+
+impl RefreshTokenRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// A refresh token for a new sign-in, valid for `ttl_secs`
+    pub async fn issue(&self, user_id: i32, ttl_secs: u64) -> Result<IssuedRefreshToken> {
+        let mut conn = self.pool.acquire().await?;
+        insert_token(&mut conn, user_id, None, ttl_secs).await
+    }
+
+    /// Exchange a refresh token for the next one of its family. Returns None when the
+    /// token is unknown, expired or revoked, or its user is no longer active. A token
+    /// that was already exchanged revokes its whole family, since someone else holds it.
+    pub async fn rotate(&self, presented: &str, ttl_secs: u64) -> Result<Option<IssuedRefreshToken>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(token) = sqlx::query!(
+            "SELECT t.token_id, t.family_id, t.user_id, t.expires_at, t.used_at, t.revoked_at, u.is_active
+             FROM warehouse.refresh_tokens t
+             JOIN warehouse.users u ON u.user_id = t.user_id
+             WHERE t.token_hash = ENCODE(SHA256(CONVERT_TO($1, 'UTF8')), 'hex')
+             FOR UPDATE OF t",
+            presented
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        if token.used_at.is_some() && token.revoked_at.is_none() {
+            revoke_family(&mut tx, token.family_id).await?;
+            tx.commit().await?;
+            return Ok(None);
+        }
+        if token.revoked_at.is_some() || token.expires_at <= Utc::now() || !token.is_active {
+            return Ok(None);
+        }
+
+        sqlx::query!("UPDATE warehouse.refresh_tokens SET used_at = NOW() WHERE token_id = $1", token.token_id)
+            .execute(&mut *tx)
+            .await?;
+        let next = insert_token(&mut tx, token.user_id, Some(token.family_id), ttl_secs).await?;
+        tx.commit().await?;
+        Ok(Some(next))
+    }
+
+    /// Sign out the session the token belongs to. Returns false when it is not a live
+    /// token.
+    pub async fn revoke(&self, presented: &str) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        let family_id = sqlx::query_scalar!(
+            "SELECT family_id FROM warehouse.refresh_tokens
+             WHERE token_hash = ENCODE(SHA256(CONVERT_TO($1, 'UTF8')), 'hex') AND revoked_at IS NULL",
+            presented
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+        match family_id {
+            Some(family_id) => Ok(revoke_family(&mut conn, family_id).await? > 0),
+            None => Ok(false),
+        }
+    }
+
+    /// Sign the user out everywhere. Returns how many live tokens were revoked.
+    pub async fn revoke_for_user(&self, user_id: i32) -> Result<u64> {
+        let result = sqlx::query!(
+            "UPDATE warehouse.refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL",
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+async fn insert_token(
+    conn: &mut PgConnection,
+    user_id: i32,
+    family_id: Option<i32>,
+    ttl_secs: u64,
+) -> Result<IssuedRefreshToken> {
+    let refresh_token = Alphanumeric.sample_string(&mut rand::thread_rng(), 48);
+    let expires_at = Utc::now() + Duration::seconds(ttl_secs as i64);
+
+    // A new sign-in starts its own family, named after its first token
+    sqlx::query!(
+        "WITH next AS (SELECT NEXTVAL(PG_GET_SERIAL_SEQUENCE('warehouse.refresh_tokens', 'token_id'))::INTEGER AS token_id)
+         INSERT INTO warehouse.refresh_tokens (token_id, family_id, user_id, token_hash, expires_at)
+         SELECT token_id, COALESCE($2, token_id), $1, ENCODE(SHA256(CONVERT_TO($3, 'UTF8')), 'hex'), $4
+         FROM next",
+        user_id,
+        family_id,
+        refresh_token,
+        expires_at
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(IssuedRefreshToken { user_id, refresh_token, expires_at })
+}
+
+async fn revoke_family(conn: &mut PgConnection, family_id: i32) -> Result<u64> {
+    let result = sqlx::query!(
+        "UPDATE warehouse.refresh_tokens SET revoked_at = NOW() WHERE family_id = $1 AND revoked_at IS NULL",
+        family_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,299 @@
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::movements::{lock_stock, post_movement};
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::chrono::{DateTime, NaiveDate, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// A repair order as stored, its cost in separate amount and currency columns
+struct RepairRow {
+    repair_id: i32,
+    repair_number: String,
+    warehouse_id: i32,
+    item_id: i32,
+    serial_number: String,
+    repair_type: String,
+    vendor_name: Option<String>,
+    fault_description: String,
+    status: String,
+    repair_cost: Option<Decimal>,
+    cost_currency: String,
+    expected_back_on: Option<NaiveDate>,
+    resolution_notes: Option<String>,
+    created_at: DateTime<Utc>,
+    sent_at: Option<DateTime<Utc>>,
+    closed_at: Option<DateTime<Utc>>,
+}
+
+impl From<RepairRow> for RepairOrder {
+    fn from(row: RepairRow) -> Self {
+        RepairOrder {
+            repair_id: row.repair_id,
+            repair_number: row.repair_number,
+            warehouse_id: row.warehouse_id,
+            item_id: row.item_id,
+            serial_number: row.serial_number,
+            repair_type: row.repair_type,
+            vendor_name: row.vendor_name,
+            fault_description: row.fault_description,
+            status: row.status,
+            repair_cost: Money::from_column(row.repair_cost, &row.cost_currency),
+            expected_back_on: row.expected_back_on,
+            resolution_notes: row.resolution_notes,
+            created_at: row.created_at,
+            sent_at: row.sent_at,
+            closed_at: row.closed_at,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RepairRepository {
+    pool: PgPool,
+}
+
+impl RepairRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Repair orders matching the filter, newest first
+    pub async fn list(&self, filter: &RepairOrderFilter) -> Result<Vec<RepairOrder>> {
+        let rows = sqlx::query_as!(
+            RepairRow,
+            "SELECT * FROM warehouse.repair_orders
+             WHERE ($1::VARCHAR IS NULL OR status = $1) AND ($2::INT IS NULL OR item_id = $2)
+               AND ($3::VARCHAR IS NULL OR serial_number = $3)
+             ORDER BY repair_id DESC",
+            filter.status,
+            filter.item_id,
+            filter.serial_number
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(RepairOrder::from).collect())
+    }
+
+    pub async fn get(&self, id: i32) -> Result<Option<RepairOrder>> {
+        let mut conn = self.pool.acquire().await?;
+        load_repair(&mut conn, id, false).await
+    }
+
+    /// Open a repair order for a serialized tool, moving it from available stock into
+    /// quarantine. The serial must be in our own available stock in the warehouse and not
+    /// already under repair.
+    pub async fn create(&self, repair: CreateRepairOrder) -> Result<RepairOrder> {
+        let mut tx = self.pool.begin().await?;
+        lock_stock(&mut tx, repair.warehouse_id, &[repair.item_id]).await?;
+
+        if let Some(open) = sqlx::query_scalar!(
+            "SELECT repair_number FROM warehouse.repair_orders
+             WHERE item_id = $1 AND serial_number = $2 AND status IN ('OPEN', 'IN_REPAIR')",
+            repair.item_id,
+            repair.serial_number
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        {
+            return Err(violation(format!("serial {} is already under repair on {}", repair.serial_number, open)));
+        }
+
+        let in_stock = sqlx::query_scalar!(
+            r#"SELECT COALESCE(SUM(quantity), 0) AS "quantity!"
+               FROM warehouse.stock_movements
+               WHERE item_id = $1 AND warehouse_id = $2 AND lot_number = $3 AND stock_status = $4
+                 AND consignor_id IS NULL"#,
+            repair.item_id,
+            repair.warehouse_id,
+            repair.serial_number,
+            STOCK_AVAILABLE
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let free = sqlx::query_scalar!(
+            "SELECT quantity_available FROM warehouse.stock_inventory
+             WHERE item_id = $1 AND warehouse_id = $2 AND consignor_id IS NULL",
+            repair.item_id,
+            repair.warehouse_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .flatten()
+        .unwrap_or_default();
+        if in_stock < Decimal::ONE || free < Decimal::ONE {
+            return Err(violation(format!(
+                "serial {} of item {} is not in available stock in warehouse {}",
+                repair.serial_number, repair.item_id, repair.warehouse_id
+            )));
+        }
+
+        let created = sqlx::query_as!(
+            RepairRow,
+            "INSERT INTO warehouse.repair_orders (
+                 repair_id, repair_number, warehouse_id, item_id, serial_number, repair_type, vendor_name,
+                 fault_description
+             )
+             SELECT next_id, 'RPR-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4, $5, $6
+             FROM (SELECT nextval('warehouse.repair_orders_repair_id_seq')::INT AS next_id) seq
+             RETURNING *",
+            repair.warehouse_id,
+            repair.item_id,
+            repair.serial_number,
+            repair.repair_type,
+            repair.vendor_name,
+            repair.fault_description
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .into();
+
+        move_tool(&mut tx, &created, MOVEMENT_REPAIR_HOLD, &[(-1, STOCK_AVAILABLE), (1, STOCK_QUARANTINE)]).await?;
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    /// Hand an open repair order's tool to the vendor or workshop. Returns None when there
+    /// is no such repair order.
+    pub async fn send(&self, id: i32, send: SendForRepair) -> Result<Option<RepairOrder>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(repair) = load_repair(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        if repair.status != REPAIR_OPEN {
+            return Err(violation(format!(
+                "cannot send {}: it is {}",
+                repair.repair_number,
+                repair.status.to_lowercase().replace('_', " ")
+            )));
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.repair_orders SET status = $2, sent_at = NOW(), expected_back_on = $3
+             WHERE repair_id = $1",
+            id,
+            REPAIR_IN_REPAIR,
+            send.expected_back_on
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let sent = load_repair(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(sent)
+    }
+
+    /// Record the quoted or invoiced cost of a repair still under way. Returns None when
+    /// there is no such repair order.
+    pub async fn record_cost(&self, id: i32, cost: RecordRepairCost) -> Result<Option<RepairOrder>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(repair) = load_repair(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        if repair.is_closed() {
+            return Err(violation(format!(
+                "cannot cost {}: it is {}",
+                repair.repair_number,
+                repair.status.to_lowercase()
+            )));
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.repair_orders SET repair_cost = $2, cost_currency = $3 WHERE repair_id = $1",
+            id,
+            cost.cost.amount,
+            cost.cost.currency
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let costed = load_repair(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(costed)
+    }
+
+    /// Close a repair order: RESTOCK moves the tool from quarantine back into available
+    /// stock, SCRAP writes it off. Returns None when there is no such repair order.
+    pub async fn close(&self, id: i32, close: CloseRepairOrder) -> Result<Option<RepairOrder>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(repair) = load_repair(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        if repair.is_closed() {
+            return Err(violation(format!(
+                "cannot close {}: it is {}",
+                repair.repair_number,
+                repair.status.to_lowercase()
+            )));
+        }
+
+        lock_stock(&mut tx, repair.warehouse_id, &[repair.item_id]).await?;
+        let status = if close.decision == REPAIR_DECISION_RESTOCK {
+            move_tool(&mut tx, &repair, MOVEMENT_REPAIR_RESTOCK, &[(-1, STOCK_QUARANTINE), (1, STOCK_AVAILABLE)])
+                .await?;
+            REPAIR_RESTOCKED
+        } else {
+            move_tool(&mut tx, &repair, MOVEMENT_REPAIR_SCRAP, &[(-1, STOCK_QUARANTINE)]).await?;
+            REPAIR_SCRAPPED
+        };
+
+        sqlx::query!(
+            "UPDATE warehouse.repair_orders SET status = $2, resolution_notes = $3, closed_at = NOW()
+             WHERE repair_id = $1",
+            id,
+            status,
+            close.notes
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let closed = load_repair(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(closed)
+    }
+}
+
+async fn load_repair(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<RepairOrder>> {
+    let row = if lock {
+        sqlx::query_as!(RepairRow, "SELECT * FROM warehouse.repair_orders WHERE repair_id = $1 FOR UPDATE", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    } else {
+        sqlx::query_as!(RepairRow, "SELECT * FROM warehouse.repair_orders WHERE repair_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    };
+    Ok(row.map(RepairOrder::from))
+}
+
+/// Post the tool's movements, one per (quantity, bucket) leg, under its serial number
+async fn move_tool(
+    conn: &mut PgConnection,
+    repair: &RepairOrder,
+    movement_type: &str,
+    legs: &[(i64, &'static str)],
+) -> Result<()> {
+    for &(quantity, stock_status) in legs {
+        post_movement(
+            &mut *conn,
+            NewMovement {
+                item_id: repair.item_id,
+                warehouse_id: repair.warehouse_id,
+                movement_type: movement_type.to_string(),
+                quantity: Decimal::from(quantity),
+                catch_weight_kg: None,
+                lot_number: Some(repair.serial_number.clone()),
+                unit_cost: None,
+                stock_status,
+                consignor_id: None,
+                reference_type: Some("REPAIR".to_string()),
+                reference_number: Some(repair.repair_number.clone()),
+                notes: None,
+                created_by: acting_user(),
+            },
+        )
+        .await?;
+    }
+    Ok(())
+}
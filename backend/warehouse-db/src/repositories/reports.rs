@@ -0,0 +1,674 @@
+use std::str::FromStr;
+
+use crate::error::violation;
+use crate::utils::*;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool, Postgres, QueryBuilder, Row};
+use warehouse_models::chrono::{DateTime, NaiveDate, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct ReportRepository {
+    pool: PgPool,
+}
+
+/// A column reports may use and the SQL it stands for. Only these expressions, and the
+/// entities' `FROM` clauses, ever reach a report's query; values are bound.
+struct CatalogColumn {
+    name: &'static str,
+    sql: &'static str,
+    kind: &'static str,
+}
+
+struct CatalogEntity {
+    entity: &'static str,
+    from: &'static str,
+    columns: &'static [CatalogColumn],
+}
+
+impl CatalogEntity {
+    fn column(&self, name: &str) -> Result<&'static CatalogColumn> {
+        self.columns
+            .iter()
+            .find(|column| column.name == name)
+            .ok_or_else(|| violation(format!("{} has no column {}", self.entity, name)))
+    }
+}
+
+const fn column(name: &'static str, sql: &'static str, kind: &'static str) -> CatalogColumn {
+    CatalogColumn { name, sql, kind }
+}
+
+const CATALOG: &[CatalogEntity] = &[
+    CatalogEntity {
+        entity: "ITEMS",
+        from: "warehouse.items i",
+        columns: &[
+            column("item_code", "i.item_code", REPORT_TEXT),
+            column("item_name", "i.item_name", REPORT_TEXT),
+            column("item_type", "i.item_type", REPORT_TEXT),
+            column("category", "i.category", REPORT_TEXT),
+            column("unit", "i.unit", REPORT_TEXT),
+            column("standard_cost", "i.standard_cost", REPORT_NUMBER),
+            column("cost_currency", "i.cost_currency", REPORT_TEXT),
+            column("is_active", "i.is_active", REPORT_BOOLEAN),
+        ],
+    },
+    CatalogEntity {
+        entity: "STOCK",
+        from: "warehouse.stock_inventory s
+               JOIN warehouse.items i ON i.item_id = s.item_id
+               JOIN warehouse.warehouses w ON w.warehouse_id = s.warehouse_id",
+        columns: &[
+            column("warehouse_code", "w.warehouse_code", REPORT_TEXT),
+            column("warehouse_name", "w.warehouse_name", REPORT_TEXT),
+            column("item_code", "i.item_code", REPORT_TEXT),
+            column("item_name", "i.item_name", REPORT_TEXT),
+            column("category", "i.category", REPORT_TEXT),
+            column("quantity_on_hand", "s.quantity_on_hand", REPORT_NUMBER),
+            column("quantity_reserved", "s.quantity_reserved", REPORT_NUMBER),
+            column("quantity_available", "s.quantity_available", REPORT_NUMBER),
+            column("total_value", "s.total_value", REPORT_NUMBER),
+            column("cost_currency", "s.cost_currency", REPORT_TEXT),
+            column("last_movement_date", "s.last_movement_date", REPORT_DATE),
+        ],
+    },
+    CatalogEntity {
+        entity: "MOVEMENTS",
+        from: "warehouse.stock_movements m
+               JOIN warehouse.items i ON i.item_id = m.item_id
+               JOIN warehouse.warehouses w ON w.warehouse_id = m.warehouse_id",
+        columns: &[
+            column("movement_date", "m.movement_date::DATE", REPORT_DATE),
+            column("movement_type", "m.movement_type", REPORT_TEXT),
+            column("warehouse_code", "w.warehouse_code", REPORT_TEXT),
+            column("item_code", "i.item_code", REPORT_TEXT),
+            column("item_name", "i.item_name", REPORT_TEXT),
+            column("category", "i.category", REPORT_TEXT),
+            column("lot_number", "m.lot_number", REPORT_TEXT),
+            column("stock_status", "m.stock_status", REPORT_TEXT),
+            column("reference_type", "m.reference_type", REPORT_TEXT),
+            column("reference_number", "m.reference_number", REPORT_TEXT),
+            column("quantity", "m.quantity", REPORT_NUMBER),
+            column("unit_cost", "m.unit_cost", REPORT_NUMBER),
+        ],
+    },
+    CatalogEntity {
+        entity: "ORDER_LINES",
+        from: "warehouse.outbound_order_lines l
+               JOIN warehouse.outbound_orders o ON o.order_id = l.order_id
+               JOIN warehouse.items i ON i.item_id = l.item_id
+               JOIN warehouse.warehouses w ON w.warehouse_id = o.warehouse_id",
+        columns: &[
+            column("order_number", "o.order_number", REPORT_TEXT),
+            column("order_date", "o.created_at::DATE", REPORT_DATE),
+            column("status", "o.status", REPORT_TEXT),
+            column("source", "o.source", REPORT_TEXT),
+            column("customer_name", "o.customer_name", REPORT_TEXT),
+            column("warehouse_code", "w.warehouse_code", REPORT_TEXT),
+            column("item_code", "i.item_code", REPORT_TEXT),
+            column("item_name", "i.item_name", REPORT_TEXT),
+            column("quantity_ordered", "l.quantity_ordered", REPORT_NUMBER),
+            column("quantity_shipped", "l.quantity_shipped", REPORT_NUMBER),
+        ],
+    },
+];
+
+impl ReportRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// The entities and columns reports can be defined over
+    pub fn catalog(&self) -> Vec<ReportEntity> {
+        CATALOG
+            .iter()
+            .map(|entity| ReportEntity {
+                entity: entity.entity.to_string(),
+                columns: entity
+                    .columns
+                    .iter()
+                    .map(|column| ReportColumn { name: column.name.to_string(), kind: column.kind.to_string() })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Reports by code
+    pub async fn list(&self) -> Result<Vec<ReportDefinition>> {
+        let reports =
+            sqlx::query_as!(ReportDefinition, "SELECT * FROM warehouse.report_definitions ORDER BY report_code")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(reports)
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<ReportDefinitionDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_report(&mut conn, id).await
+    }
+
+    pub async fn code_exists(&self, code: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.report_definitions WHERE report_code = $1)",
+            code
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    /// Define a report; its columns, filters, grouping and aggregates are checked against
+    /// the catalog
+    pub async fn create(&self, report: CreateReportDefinition) -> Result<ReportDefinitionDetail> {
+        check(&report.entity, &report.columns, &report.filters, &report.group_by, &report.aggregates)?;
+        let mut tx = self.pool.begin().await?;
+
+        let report_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.report_definitions (report_code, report_name, description, entity, columns, group_by)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING report_id",
+            report.report_code,
+            report.report_name,
+            report.description,
+            report.entity,
+            &report.columns,
+            &report.group_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        set_clauses(&mut tx, report_id, &report.filters, &report.aggregates).await?;
+
+        let detail = load_report(&mut tx, report_id).await?.expect("report inserted above");
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Returns None when the report does not exist
+    pub async fn update(&self, id: i32, changes: UpdateReportDefinition) -> Result<Option<ReportDefinitionDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(current) = load_report(&mut tx, id).await? else {
+            return Ok(None);
+        };
+
+        let columns = changes.columns.unwrap_or(current.definition.columns);
+        let filters = changes.filters.unwrap_or(current.filters);
+        let group_by = changes.group_by.unwrap_or(current.definition.group_by);
+        let aggregates = changes.aggregates.unwrap_or(current.aggregates);
+        check(&current.definition.entity, &columns, &filters, &group_by, &aggregates)?;
+
+        sqlx::query!(
+            "UPDATE warehouse.report_definitions SET
+                 report_name = COALESCE($2, report_name),
+                 description = COALESCE($3, description),
+                 columns = $4,
+                 group_by = $5,
+                 updated_at = NOW()
+             WHERE report_id = $1",
+            id,
+            changes.report_name,
+            changes.description,
+            &columns,
+            &group_by
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!("DELETE FROM warehouse.report_filters WHERE report_id = $1", id).execute(&mut *tx).await?;
+        sqlx::query!("DELETE FROM warehouse.report_aggregates WHERE report_id = $1", id).execute(&mut *tx).await?;
+        set_clauses(&mut tx, id, &filters, &aggregates).await?;
+
+        let detail = load_report(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    pub async fn delete(&self, id: i32) -> Result<bool> {
+        let deleted = sqlx::query!("DELETE FROM warehouse.report_definitions WHERE report_id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(deleted.rows_affected() > 0)
+    }
+
+    /// Run the report, up to `REPORT_MAX_ROWS` rows sorted by its columns
+    pub async fn run(&self, report: &ReportDefinitionDetail) -> Result<ReportResult> {
+        let definition = &report.definition;
+        let entity = check(
+            &definition.entity,
+            &definition.columns,
+            &report.filters,
+            &definition.group_by,
+            &report.aggregates,
+        )?;
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT ");
+        let mut names = Vec::with_capacity(definition.columns.len() + report.aggregates.len());
+        let mut select = query.separated(", ");
+        for name in &definition.columns {
+            select.push(format!("({})::TEXT", entity.column(name)?.sql));
+            names.push(name.clone());
+        }
+        for aggregate in &report.aggregates {
+            match &aggregate.column_name {
+                Some(name) => {
+                    select.push(format!("({}({}))::TEXT", aggregate.function, entity.column(name)?.sql));
+                    names.push(format!("{}_{}", aggregate.function.to_lowercase(), name));
+                }
+                None => {
+                    select.push("COUNT(*)::TEXT");
+                    names.push("count".to_string());
+                }
+            }
+        }
+
+        query.push(" FROM ").push(entity.from).push(" WHERE TRUE");
+        for filter in &report.filters {
+            let column = entity.column(&filter.column_name)?;
+            query.push(" AND ").push(column.sql);
+            let value = filter.value.clone().unwrap_or_default();
+            match filter.operator.as_str() {
+                REPORT_IS_NULL => {
+                    query.push(" IS NULL");
+                }
+                REPORT_NOT_NULL => {
+                    query.push(" IS NOT NULL");
+                }
+                REPORT_CONTAINS => {
+                    query.push(" ILIKE '%' || ").push_bind(value).push(" || '%'");
+                }
+                operator => {
+                    query.push(comparison(operator)).push_bind(value).push(format!("::{}", sql_type(column.kind)));
+                }
+            }
+        }
+
+        if !definition.group_by.is_empty() {
+            query.push(" GROUP BY ");
+            let mut group = query.separated(", ");
+            for name in &definition.group_by {
+                group.push(entity.column(name)?.sql);
+            }
+        }
+        if !definition.columns.is_empty() {
+            query.push(" ORDER BY ");
+            let mut order = query.separated(", ");
+            for name in &definition.columns {
+                order.push(entity.column(name)?.sql);
+            }
+        }
+        query.push(" LIMIT ").push_bind(REPORT_MAX_ROWS + 1);
+
+        let mut rows = Vec::new();
+        for row in query.build().fetch_all(&self.pool).await? {
+            let values = (0..names.len()).map(|index| row.try_get::<Option<String>, _>(index));
+            rows.push(values.collect::<Result<Vec<_>, _>>()?);
+        }
+        let truncated = rows.len() as i64 > REPORT_MAX_ROWS;
+        rows.truncate(REPORT_MAX_ROWS as usize);
+
+        Ok(ReportResult { columns: names, headers: Vec::new(), rows, truncated })
+    }
+
+    /// The report's delivery schedules
+    pub async fn schedules(&self, report_id: i32) -> Result<Vec<ReportSchedule>> {
+        let schedules = sqlx::query_as!(
+            ReportSchedule,
+            "SELECT * FROM warehouse.report_schedules WHERE report_id = $1 ORDER BY schedule_id",
+            report_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(schedules)
+    }
+
+    pub async fn get_schedule(&self, id: i32) -> Result<Option<ReportSchedule>> {
+        let schedule =
+            sqlx::query_as!(ReportSchedule, "SELECT * FROM warehouse.report_schedules WHERE schedule_id = $1", id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(schedule)
+    }
+
+    /// Schedule the report; the first delivery is at the expression's next run
+    pub async fn create_schedule(&self, report_id: i32, schedule: CreateReportSchedule) -> Result<ReportSchedule> {
+        let next_run_at = next_run(&schedule.cron_expression, Utc::now())?;
+        let created = sqlx::query_as!(
+            ReportSchedule,
+            "INSERT INTO warehouse.report_schedules (report_id, cron_expression, recipients, format, next_run_at)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING *",
+            report_id,
+            schedule.cron_expression,
+            &schedule.recipients,
+            schedule.format.as_deref().unwrap_or(REPORT_FORMAT_CSV),
+            next_run_at
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(created)
+    }
+
+    /// Returns None when the schedule does not exist. A new expression, or activating
+    /// the schedule again, moves its next delivery to the expression's next run.
+    pub async fn update_schedule(&self, id: i32, changes: UpdateReportSchedule) -> Result<Option<ReportSchedule>> {
+        let Some(current) = self.get_schedule(id).await? else {
+            return Ok(None);
+        };
+
+        let reactivated = changes.is_active == Some(true) && !current.is_active;
+        let next_run_at = match &changes.cron_expression {
+            Some(expression) => Some(next_run(expression, Utc::now())?),
+            None if reactivated => Some(next_run(&current.cron_expression, Utc::now())?),
+            None => None,
+        };
+
+        let updated = sqlx::query_as!(
+            ReportSchedule,
+            "UPDATE warehouse.report_schedules SET
+                 cron_expression = COALESCE($2, cron_expression),
+                 recipients = COALESCE($3, recipients),
+                 format = COALESCE($4, format),
+                 is_active = COALESCE($5, is_active),
+                 next_run_at = COALESCE($6, next_run_at),
+                 updated_at = NOW()
+             WHERE schedule_id = $1
+             RETURNING *",
+            id,
+            changes.cron_expression,
+            changes.recipients.as_deref(),
+            changes.format,
+            changes.is_active,
+            next_run_at
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(updated)
+    }
+
+    pub async fn delete_schedule(&self, id: i32) -> Result<bool> {
+        let deleted = sqlx::query!("DELETE FROM warehouse.report_schedules WHERE schedule_id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(deleted.rows_affected() > 0)
+    }
+
+    /// Take the active schedules due at `now` and move each to its expression's next run,
+    /// so a schedule is delivered by one server even with several running. A schedule
+    /// whose expression no longer runs is deactivated.
+    pub async fn claim_due_schedules(&self, now: DateTime<Utc>) -> Result<Vec<ReportSchedule>> {
+        let mut tx = self.pool.begin().await?;
+        let due = sqlx::query_as!(
+            ReportSchedule,
+            "SELECT * FROM warehouse.report_schedules
+             WHERE is_active AND next_run_at <= $1
+             ORDER BY next_run_at
+             FOR UPDATE SKIP LOCKED",
+            now
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut claimed = Vec::with_capacity(due.len());
+        for schedule in due {
+            let next = CronSchedule::parse(&schedule.cron_expression).ok().and_then(|cron| cron.next_after(now));
+            let updated = sqlx::query_as!(
+                ReportSchedule,
+                "UPDATE warehouse.report_schedules SET
+                     next_run_at = COALESCE($2, next_run_at),
+                     is_active = $2::TIMESTAMPTZ IS NOT NULL
+                 WHERE schedule_id = $1
+                 RETURNING *",
+                schedule.schedule_id,
+                next
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            claimed.push(updated);
+        }
+
+        tx.commit().await?;
+        Ok(claimed)
+    }
+
+    /// Record a delivery: the rows sent, or why it failed
+    pub async fn record_delivery(
+        &self,
+        schedule_id: i32,
+        started_at: DateTime<Utc>,
+        delivered: std::result::Result<usize, String>,
+    ) -> Result<ReportScheduleRun> {
+        let mut tx = self.pool.begin().await?;
+
+        let (status, row_count, error) = match delivered {
+            Ok(rows) => (REPORT_DELIVERY_SENT, rows as i32, None),
+            Err(error) => (REPORT_DELIVERY_FAILED, 0, Some(error)),
+        };
+        let run = sqlx::query_as!(
+            ReportScheduleRun,
+            "INSERT INTO warehouse.report_schedule_runs (schedule_id, status, row_count, error, started_at)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING *",
+            schedule_id,
+            status,
+            row_count,
+            error,
+            started_at
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE warehouse.report_schedules SET last_run_at = $2 WHERE schedule_id = $1",
+            schedule_id,
+            started_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(run)
+    }
+
+    /// A schedule's deliveries, newest first
+    pub async fn deliveries(&self, schedule_id: i32, keyset: KeysetQuery) -> Result<KeysetPage<ReportScheduleRun>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            ReportScheduleRun,
+            "SELECT * FROM warehouse.report_schedule_runs
+             WHERE schedule_id = $1 AND ($2::BIGINT IS NULL OR run_id < $2)
+             ORDER BY run_id DESC
+             LIMIT $3",
+            schedule_id,
+            after_id,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |run| run.run_id))
+    }
+}
+
+/// When `expression` next runs after `now`
+fn next_run(expression: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    CronSchedule::parse(expression)
+        .map_err(violation)?
+        .next_after(now)
+        .ok_or_else(|| violation(format!("{} never runs", expression)))
+}
+
+/// The entity of a report whose every part names a catalog column and fits its type
+fn check(
+    entity: &str,
+    columns: &[String],
+    filters: &[ReportFilter],
+    group_by: &[String],
+    aggregates: &[ReportAggregate],
+) -> Result<&'static CatalogEntity> {
+    let entity = CATALOG
+        .iter()
+        .find(|candidate| candidate.entity == entity)
+        .ok_or_else(|| violation(format!("unknown report entity {}", entity)))?;
+
+    if columns.is_empty() && aggregates.is_empty() {
+        return Err(violation("a report needs a column or an aggregate"));
+    }
+    for (index, name) in columns.iter().enumerate() {
+        entity.column(name)?;
+        if columns[..index].contains(name) {
+            return Err(violation(format!("column {} is listed more than once", name)));
+        }
+    }
+    for name in group_by {
+        entity.column(name)?;
+    }
+    if !group_by.is_empty() || !aggregates.is_empty() {
+        if let Some(name) = columns.iter().find(|name| !group_by.contains(name)) {
+            return Err(violation(format!("column {} must be grouped by to be shown with aggregates", name)));
+        }
+    }
+
+    for filter in filters {
+        let column = entity.column(&filter.column_name)?;
+        match (filter.operator.as_str(), &filter.value) {
+            (REPORT_IS_NULL | REPORT_NOT_NULL, None) => {}
+            (REPORT_IS_NULL | REPORT_NOT_NULL, Some(_)) => {
+                return Err(violation(format!("{} {} takes no value", filter.column_name, filter.operator)));
+            }
+            (_, None) => return Err(violation(format!("{} {} needs a value", filter.column_name, filter.operator))),
+            (REPORT_CONTAINS, Some(_)) if column.kind != REPORT_TEXT => {
+                return Err(violation(format!("{} is not text", filter.column_name)));
+            }
+            (REPORT_CONTAINS, Some(_)) => {}
+            (operator, Some(_)) if column.kind == REPORT_BOOLEAN && ![REPORT_EQ, REPORT_NE].contains(&operator) => {
+                return Err(violation(format!("{} can only be compared for equality", filter.column_name)));
+            }
+            (_, Some(value)) => {
+                let parses = match column.kind {
+                    REPORT_NUMBER => Decimal::from_str(value).is_ok(),
+                    REPORT_DATE => NaiveDate::from_str(value).is_ok(),
+                    REPORT_BOOLEAN => bool::from_str(value).is_ok(),
+                    _ => true,
+                };
+                if !parses {
+                    return Err(violation(format!("{} is not a valid {} value", value, filter.column_name)));
+                }
+            }
+        }
+    }
+
+    for aggregate in aggregates {
+        match &aggregate.column_name {
+            None if aggregate.function == REPORT_COUNT => {}
+            None => return Err(violation(format!("{} needs a column", aggregate.function))),
+            Some(name) => {
+                let column = entity.column(name)?;
+                let numeric = [REPORT_SUM, REPORT_AVG].contains(&aggregate.function.as_str());
+                if numeric && column.kind != REPORT_NUMBER {
+                    return Err(violation(format!("{} of {} needs a number column", aggregate.function, name)));
+                }
+            }
+        }
+    }
+
+    Ok(entity)
+}
+
+fn comparison(operator: &str) -> &'static str {
+    match operator {
+        REPORT_NE => " <> ",
+        REPORT_LT => " < ",
+        REPORT_LTE => " <= ",
+        REPORT_GT => " > ",
+        REPORT_GTE => " >= ",
+        _ => " = ",
+    }
+}
+
+fn sql_type(kind: &str) -> &'static str {
+    match kind {
+        REPORT_NUMBER => "NUMERIC",
+        REPORT_DATE => "DATE",
+        REPORT_BOOLEAN => "BOOLEAN",
+        _ => "TEXT",
+    }
+}
+
+async fn set_clauses(
+    conn: &mut PgConnection,
+    report_id: i32,
+    filters: &[ReportFilter],
+    aggregates: &[ReportAggregate],
+) -> Result<()> {
+    let positions: Vec<i16> = (1..=filters.len() as i16).collect();
+    let column_names: Vec<String> = filters.iter().map(|filter| filter.column_name.clone()).collect();
+    let operators: Vec<String> = filters.iter().map(|filter| filter.operator.clone()).collect();
+    let values: Vec<Option<String>> = filters.iter().map(|filter| filter.value.clone()).collect();
+    sqlx::query!(
+        "INSERT INTO warehouse.report_filters (report_id, position, column_name, operator, value)
+         SELECT $1, * FROM UNNEST($2::SMALLINT[], $3::VARCHAR[], $4::VARCHAR[], $5::VARCHAR[])",
+        report_id,
+        &positions,
+        &column_names,
+        &operators,
+        &values as &[Option<String>]
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let positions: Vec<i16> = (1..=aggregates.len() as i16).collect();
+    let functions: Vec<String> = aggregates.iter().map(|aggregate| aggregate.function.clone()).collect();
+    let column_names: Vec<Option<String>> = aggregates.iter().map(|aggregate| aggregate.column_name.clone()).collect();
+    sqlx::query!(
+        "INSERT INTO warehouse.report_aggregates (report_id, position, function, column_name)
+         SELECT $1, * FROM UNNEST($2::SMALLINT[], $3::VARCHAR[], $4::VARCHAR[])",
+        report_id,
+        &positions,
+        &functions,
+        &column_names as &[Option<String>]
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn load_report(conn: &mut PgConnection, id: i32) -> Result<Option<ReportDefinitionDetail>> {
+    let Some(definition) =
+        sqlx::query_as!(ReportDefinition, "SELECT * FROM warehouse.report_definitions WHERE report_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    else {
+        return Ok(None);
+    };
+
+    let filters = sqlx::query_as!(
+        ReportFilter,
+        "SELECT column_name, operator, value FROM warehouse.report_filters WHERE report_id = $1 ORDER BY position",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let aggregates = sqlx::query_as!(
+        ReportAggregate,
+        "SELECT function, column_name FROM warehouse.report_aggregates WHERE report_id = $1 ORDER BY position",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(ReportDefinitionDetail { definition, filters, aggregates }))
+}
@@ -0,0 +1,139 @@
+use crate::error::violation;
+use crate::repositories::movements::{lock_stock, move_reserved};
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct ReservationRepository {
+    pool: PgPool,
+}
+
+impl ReservationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Hold back our own stock of an item, moving it from ON_HAND to RESERVED. Refuses
+    /// to reserve more than the warehouse has available.
+    pub async fn reserve(&self, reservation: CreateStockReservation) -> Result<StockReservation> {
+        let mut tx = self.pool.begin().await?;
+        lock_stock(&mut tx, reservation.warehouse_id, &[reservation.item_id]).await?;
+
+        let stock = sqlx::query!(
+            r#"SELECT i.item_code, COALESCE(s.quantity_available, 0) AS "available!"
+               FROM warehouse.items i
+               LEFT JOIN warehouse.stock_inventory s ON s.item_id = i.item_id AND s.warehouse_id = $1
+                    AND s.consignor_id IS NULL
+               WHERE i.item_id = $2 AND i.status = 'ACTIVE'"#,
+            reservation.warehouse_id,
+            reservation.item_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| violation(format!("unknown item {}", reservation.item_id)))?;
+        if reservation.quantity > stock.available {
+            return Err(violation(format!(
+                "cannot reserve {} of {} in warehouse {}: only {} available",
+                reservation.quantity,
+                stock.item_code,
+                reservation.warehouse_id,
+                stock.available.max(Decimal::ZERO).normalize()
+            )));
+        }
+
+        let created = sqlx::query_as!(
+            StockReservation,
+            "INSERT INTO warehouse.stock_reservations (warehouse_id, item_id, quantity, reference, notes)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING *",
+            reservation.warehouse_id,
+            reservation.item_id,
+            reservation.quantity,
+            reservation.reference,
+            reservation.notes
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let number = format!("RSV-{}", created.reservation_id);
+        move_reserved(
+            &mut tx,
+            created.warehouse_id,
+            created.item_id,
+            created.quantity,
+            REFERENCE_STOCK_RESERVATION,
+            Some(&number),
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    /// Give an active reservation's stock back to the available stock. Returns None when
+    /// there is no such reservation.
+    pub async fn release(&self, reservation_id: i32) -> Result<Option<StockReservation>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(reservation) = sqlx::query_as!(
+            StockReservation,
+            "SELECT * FROM warehouse.stock_reservations WHERE reservation_id = $1 FOR UPDATE",
+            reservation_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+        if reservation.status != RESERVATION_ACTIVE {
+            return Err(violation(format!(
+                "cannot release reservation {}: it is {}",
+                reservation_id,
+                reservation.status.to_lowercase()
+            )));
+        }
+
+        lock_stock(&mut tx, reservation.warehouse_id, &[reservation.item_id]).await?;
+        let number = format!("RSV-{}", reservation_id);
+        move_reserved(
+            &mut tx,
+            reservation.warehouse_id,
+            reservation.item_id,
+            -reservation.quantity,
+            REFERENCE_STOCK_RESERVATION,
+            Some(&number),
+        )
+        .await?;
+        let released = sqlx::query_as!(
+            StockReservation,
+            "UPDATE warehouse.stock_reservations SET status = $2, released_at = NOW()
+             WHERE reservation_id = $1
+             RETURNING *",
+            reservation_id,
+            RESERVATION_RELEASED
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(released))
+    }
+
+    /// Reservations matching the filter, newest first
+    pub async fn reservations(&self, filter: &StockReservationFilter) -> Result<Vec<StockReservation>> {
+        let reservations = sqlx::query_as!(
+            StockReservation,
+            "SELECT * FROM warehouse.stock_reservations
+             WHERE ($1::INT IS NULL OR warehouse_id = $1) AND ($2::INT IS NULL OR item_id = $2)
+               AND ($3::VARCHAR IS NULL OR reference = $3) AND ($4::VARCHAR IS NULL OR status = $4)
+             ORDER BY created_at DESC, reservation_id DESC",
+            filter.warehouse_id,
+            filter.item_id,
+            filter.reference,
+            filter.status
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(reservations)
+    }
+}
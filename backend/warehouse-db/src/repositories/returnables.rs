@@ -0,0 +1,257 @@
+use crate::error::violation;
+use anyhow::Result;
+use chrono::NaiveDate;
+use sqlx::{PgConnection, PgPool};
+use std::collections::{HashMap, HashSet};
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct ReturnableRepository {
+    pool: PgPool,
+}
+
+impl ReturnableRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn types(&self) -> Result<Vec<ReturnableType>> {
+        let types = sqlx::query_as!(
+            ReturnableType,
+            "SELECT * FROM warehouse.returnable_types ORDER BY is_active DESC, type_code"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(types)
+    }
+
+    pub async fn type_code_exists(&self, code: &str) -> Result<bool> {
+        let exists =
+            sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM warehouse.returnable_types WHERE type_code = $1)", code)
+                .fetch_one(&self.pool)
+                .await?;
+        Ok(exists.unwrap_or(false))
+    }
+
+    pub async fn create_type(&self, returnable: CreateReturnableType) -> Result<ReturnableType> {
+        let returnable = sqlx::query_as!(
+            ReturnableType,
+            "INSERT INTO warehouse.returnable_types (type_code, description) VALUES ($1, $2) RETURNING *",
+            returnable.type_code,
+            returnable.description
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(returnable)
+    }
+
+    /// Record units that went out with a shipment, held from then on by its order's
+    /// customer at the order's ship-to site. Returns None when there is no such shipment.
+    pub async fn issue(&self, shipment_id: i32, issue: IssueReturnables) -> Result<Option<Vec<ReturnableMovement>>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(shipment) = sqlx::query!(
+            "SELECT s.warehouse_id, o.customer_name, o.ship_to_name
+             FROM warehouse.shipments s
+             JOIN warehouse.outbound_orders o ON o.order_id = s.order_id
+             WHERE s.shipment_id = $1",
+            shipment_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+        lock_types(&mut tx, &issue.lines).await?;
+
+        let mut movements = Vec::new();
+        for line in &issue.lines {
+            let movement = sqlx::query_as!(
+                ReturnableMovement,
+                "INSERT INTO warehouse.returnable_movements (
+                     type_id, warehouse_id, customer_name, site_name, direction, quantity, shipment_id, notes
+                 ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                 RETURNING *",
+                line.type_id,
+                shipment.warehouse_id,
+                shipment.customer_name,
+                shipment.ship_to_name,
+                RETURNABLE_ISSUE,
+                line.quantity,
+                shipment_id,
+                issue.notes
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            movements.push(movement);
+        }
+
+        tx.commit().await?;
+        Ok(Some(movements))
+    }
+
+    /// Record units a customer site gave back. A site cannot return more of a type than
+    /// it holds.
+    pub async fn receive(&self, receipt: ReceiveReturnables) -> Result<Vec<ReturnableMovement>> {
+        let mut tx = self.pool.begin().await?;
+        // Locking the types queues concurrent returns, so each sees the balance the one
+        // before it left
+        let codes = lock_types(&mut tx, &receipt.lines).await?;
+
+        let type_ids: Vec<i32> = receipt.lines.iter().map(|line| line.type_id).collect();
+        let held: HashMap<i32, i64> = sqlx::query!(
+            r#"SELECT type_id,
+                      SUM(CASE WHEN direction = 'ISSUE' THEN quantity ELSE -quantity END) AS "outstanding!"
+               FROM warehouse.returnable_movements
+               WHERE type_id = ANY($1) AND customer_name = $2 AND site_name IS NOT DISTINCT FROM $3
+               GROUP BY type_id"#,
+            &type_ids,
+            receipt.customer_name,
+            receipt.site_name
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| (row.type_id, row.outstanding))
+        .collect();
+
+        let holder = match &receipt.site_name {
+            Some(site_name) => format!("{} at {}", receipt.customer_name, site_name),
+            None => receipt.customer_name.clone(),
+        };
+        for line in &receipt.lines {
+            let outstanding = held.get(&line.type_id).copied().unwrap_or(0);
+            if i64::from(line.quantity) > outstanding {
+                return Err(violation(format!(
+                    "{} holds {} {}, fewer than the {} returned",
+                    holder, outstanding, codes[&line.type_id], line.quantity
+                )));
+            }
+        }
+
+        let mut movements = Vec::new();
+        for line in &receipt.lines {
+            let movement = sqlx::query_as!(
+                ReturnableMovement,
+                "INSERT INTO warehouse.returnable_movements (
+                     type_id, warehouse_id, customer_name, site_name, direction, quantity, notes
+                 ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 RETURNING *",
+                line.type_id,
+                receipt.warehouse_id,
+                receipt.customer_name,
+                receipt.site_name,
+                RETURNABLE_RETURN,
+                line.quantity,
+                receipt.notes
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            movements.push(movement);
+        }
+
+        tx.commit().await?;
+        Ok(movements)
+    }
+
+    /// What each customer site still holds, by customer and site
+    pub async fn balances(&self, filter: &ReturnableFilter) -> Result<Vec<ReturnableBalance>> {
+        let balances = sqlx::query_as!(
+            ReturnableBalance,
+            r#"SELECT m.type_id, t.type_code, m.customer_name, m.site_name,
+                      COALESCE(SUM(m.quantity) FILTER (WHERE m.direction = 'ISSUE'), 0) AS "issued!",
+                      COALESCE(SUM(m.quantity) FILTER (WHERE m.direction = 'RETURN'), 0) AS "returned!",
+                      SUM(CASE WHEN m.direction = 'ISSUE' THEN m.quantity ELSE -m.quantity END) AS "outstanding!",
+                      MAX(m.moved_at) FILTER (WHERE m.direction = 'ISSUE') AS last_issued_at
+               FROM warehouse.returnable_movements m
+               JOIN warehouse.returnable_types t ON t.type_id = m.type_id
+               WHERE ($1::VARCHAR IS NULL OR m.customer_name = $1) AND ($2::INT IS NULL OR m.type_id = $2)
+               GROUP BY m.type_id, t.type_code, m.customer_name, m.site_name
+               HAVING SUM(CASE WHEN m.direction = 'ISSUE' THEN m.quantity ELSE -m.quantity END) > 0
+               ORDER BY m.customer_name, m.site_name NULLS FIRST, t.type_code"#,
+            filter.customer_name,
+            filter.type_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(balances)
+    }
+
+    /// Outstanding units by age on `as_of`, returns set against the oldest issues first.
+    /// Movements after `as_of` are left out.
+    pub async fn aging(&self, filter: &ReturnableFilter, as_of: NaiveDate) -> Result<Vec<ReturnableAging>> {
+        let aging = sqlx::query_as!(
+            ReturnableAging,
+            r#"WITH movements AS (
+                   SELECT * FROM warehouse.returnable_movements
+                   WHERE moved_at::DATE <= $3
+                     AND ($1::VARCHAR IS NULL OR customer_name = $1) AND ($2::INT IS NULL OR type_id = $2)
+               ),
+               returned AS (
+                   SELECT type_id, customer_name, site_name, SUM(quantity) AS quantity
+                   FROM movements WHERE direction = 'RETURN'
+                   GROUP BY type_id, customer_name, site_name
+               ),
+               issues AS (
+                   SELECT type_id, customer_name, site_name, moved_at::DATE AS issued_on, quantity,
+                          SUM(quantity) OVER (
+                              PARTITION BY type_id, customer_name, site_name ORDER BY moved_at, movement_id
+                          ) AS issued_through
+                   FROM movements WHERE direction = 'ISSUE'
+               ),
+               open_issues AS (
+                   SELECT i.type_id, i.customer_name, i.site_name, i.issued_on, $3 - i.issued_on AS age_days,
+                          GREATEST(0, LEAST(i.quantity, i.issued_through - COALESCE(r.quantity, 0))) AS open_quantity
+                   FROM issues i
+                   LEFT JOIN returned r ON r.type_id = i.type_id AND r.customer_name = i.customer_name
+                       AND r.site_name IS NOT DISTINCT FROM i.site_name
+               )
+               SELECT o.type_id, t.type_code, o.customer_name, o.site_name,
+                      COALESCE(SUM(o.open_quantity) FILTER (WHERE o.age_days <= 30), 0)::BIGINT AS "days_0_30!",
+                      COALESCE(SUM(o.open_quantity) FILTER (WHERE o.age_days BETWEEN 31 AND 60), 0)::BIGINT
+                          AS "days_31_60!",
+                      COALESCE(SUM(o.open_quantity) FILTER (WHERE o.age_days BETWEEN 61 AND 90), 0)::BIGINT
+                          AS "days_61_90!",
+                      COALESCE(SUM(o.open_quantity) FILTER (WHERE o.age_days > 90), 0)::BIGINT AS "days_over_90!",
+                      SUM(o.open_quantity)::BIGINT AS "outstanding!",
+                      MIN(o.issued_on) FILTER (WHERE o.open_quantity > 0) AS "oldest_issued_on!"
+               FROM open_issues o
+               JOIN warehouse.returnable_types t ON t.type_id = o.type_id
+               GROUP BY o.type_id, t.type_code, o.customer_name, o.site_name
+               HAVING SUM(o.open_quantity) > 0
+               ORDER BY MIN(o.issued_on) FILTER (WHERE o.open_quantity > 0), o.customer_name, t.type_code"#,
+            filter.customer_name,
+            filter.type_id,
+            as_of
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(aging)
+    }
+}
+
+/// Lock the lines' returnable types, which must be active and listed once each. Returns
+/// their codes by id.
+async fn lock_types(conn: &mut PgConnection, lines: &[ReturnableLine]) -> Result<HashMap<i32, String>> {
+    let mut seen = HashSet::new();
+    if let Some(line) = lines.iter().find(|line| !seen.insert(line.type_id)) {
+        return Err(violation(format!("returnable type {} is listed more than once", line.type_id)));
+    }
+
+    let type_ids: Vec<i32> = lines.iter().map(|line| line.type_id).collect();
+    let codes: HashMap<i32, String> = sqlx::query!(
+        "SELECT type_id, type_code FROM warehouse.returnable_types
+         WHERE type_id = ANY($1) AND is_active
+         ORDER BY type_id
+         FOR UPDATE",
+        &type_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|row| (row.type_id, row.type_code))
+    .collect();
+    if let Some(line) = lines.iter().find(|line| !codes.contains_key(&line.type_id)) {
+        return Err(violation(format!("unknown returnable type {}", line.type_id)));
+    }
+    Ok(codes)
+}
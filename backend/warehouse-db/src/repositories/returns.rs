@@ -0,0 +1,380 @@
+use anyhow::Result;
+use warehouse_models::rust_decimal::Decimal;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::*;
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::movements::{lock_stock, post_movement};
+use crate::repositories::postings::{claim, Claim};
+use crate::utils::*;
+
+#[derive(Clone)]
+pub struct ReturnRepository {
+    pool: PgPool,
+}
+
+impl ReturnRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// List RMAs, newest first
+    pub async fn list(
+        &self,
+        filter: ReturnFilter,
+        pagination: PaginationQuery,
+    ) -> Result<PaginatedResponse<ReturnAuthorization>> {
+        let (page, limit) = validate_pagination(&pagination);
+        let offset = calculate_offset(page, limit);
+        let mode = pagination.count.unwrap_or_default();
+
+        let total = match mode {
+            CountMode::Exact => sqlx::query_scalar!(
+                "SELECT COUNT(*) FROM warehouse.return_authorizations
+                 WHERE ($1::VARCHAR IS NULL OR status = $1)
+                   AND ($2::INT IS NULL OR warehouse_id = $2)",
+                filter.status,
+                filter.warehouse_id
+            )
+            .fetch_one(&self.pool)
+            .await?,
+            CountMode::Estimate => Some(estimate_row_count(&self.pool, "warehouse.return_authorizations").await?),
+            CountMode::Skip => None,
+        };
+
+        let rows = sqlx::query_as!(
+            ReturnAuthorization,
+            "SELECT * FROM warehouse.return_authorizations
+             WHERE ($1::VARCHAR IS NULL OR status = $1)
+               AND ($2::INT IS NULL OR warehouse_id = $2)
+             ORDER BY return_id DESC
+             LIMIT $3 OFFSET $4",
+            filter.status,
+            filter.warehouse_id,
+            limit + 1,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PaginatedResponse::from_probe(rows, total, mode, page, limit))
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<ReturnDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_detail(&mut conn, id, false).await
+    }
+
+    /// Authorize a return; the RMA number is derived from the new id
+    pub async fn create(&self, rma: CreateReturn) -> Result<ReturnDetail> {
+        let mut tx = self.pool.begin().await?;
+
+        let return_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.return_authorizations (
+                 return_id, rma_number, warehouse_id, customer_name, customer_reference, reason, notes, created_by
+             )
+             SELECT next_id, 'RMA-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4, $5, $6
+             FROM (SELECT nextval('warehouse.return_authorizations_return_id_seq')::INT AS next_id) seq
+             RETURNING return_id",
+            rma.warehouse_id,
+            rma.customer_name,
+            rma.customer_reference,
+            rma.reason,
+            rma.notes,
+            acting_user() // created_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for line in rma.lines {
+            sqlx::query!(
+                "INSERT INTO warehouse.return_lines (return_id, item_id, reason, quantity_expected)
+                 VALUES ($1, $2, $3, $4)",
+                return_id,
+                line.item_id,
+                line.reason,
+                line.quantity_expected
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let detail = load_detail(&mut tx, return_id, false)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("return {} vanished after insert", return_id))?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Book returned goods into quarantine. Returns None when the RMA does not exist.
+    pub async fn receive(&self, id: i32, receipt: ReceiveReturn) -> Result<Option<ReturnDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_detail(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let reference_id = receipt.reference_id.as_deref();
+        if let Claim::Replay(_) = claim(&mut tx, reference_id, POSTING_RETURN_RECEIPT, id).await? {
+            return Ok(Some(detail));
+        }
+        let rma = &detail.authorization;
+
+        if rma.status != RETURN_AUTHORIZED && rma.status != RETURN_RECEIVED {
+            return Err(violation(format!("cannot receive against a {} return", rma.status.to_lowercase())));
+        }
+
+        let mut received_so_far: Vec<(i32, Decimal)> = detail
+            .lines
+            .iter()
+            .map(|line| (line.line_id, line.quantity_received))
+            .collect();
+
+        for received in receipt.lines {
+            let line = find_line(&detail, received.line_id)?;
+            let total = received_so_far
+                .iter_mut()
+                .find(|(line_id, _)| *line_id == line.line_id)
+                .map(|(_, quantity)| quantity)
+                .expect("received_so_far covers every line");
+            if *total + received.quantity > line.quantity_expected {
+                return Err(violation(format!(
+                    "line {} would receive {} of {} authorized",
+                    line.line_id,
+                    *total + received.quantity,
+                    line.quantity_expected
+                )));
+            }
+            *total += received.quantity;
+
+            post_movement(
+                &mut tx,
+                return_movement(rma, line, "RETURN_RECEIPT", received.quantity, STOCK_QUARANTINE),
+            )
+            .await?;
+
+            sqlx::query!(
+                "UPDATE warehouse.return_lines SET quantity_received = quantity_received + $2 WHERE line_id = $1",
+                line.line_id,
+                received.quantity
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.return_authorizations
+             SET status = $2, received_at = COALESCE(received_at, NOW()), updated_at = NOW()
+             WHERE return_id = $1",
+            id,
+            RETURN_RECEIVED
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let detail = load_detail(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Record inspection outcomes for quarantined goods. Restocked quantities move to
+    /// available stock, scrapped quantities leave stock, repairs stay in quarantine.
+    /// The RMA completes once everything authorized has been received and inspected.
+    pub async fn inspect(&self, id: i32, inspection: InspectReturn) -> Result<Option<ReturnDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_detail(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let reference_id = inspection.reference_id.as_deref();
+        if let Claim::Replay(_) = claim(&mut tx, reference_id, POSTING_RETURN_INSPECTION, id).await? {
+            return Ok(Some(detail));
+        }
+        let rma = &detail.authorization;
+
+        if rma.status != RETURN_RECEIVED {
+            return Err(violation(format!("cannot inspect a {} return", rma.status.to_lowercase())));
+        }
+
+        // Lines can appear more than once in a request; track what is left as we go
+        let mut pending: Vec<(i32, Decimal)> = detail
+            .lines
+            .iter()
+            .map(|line| (line.line_id, line.quantity_uninspected()))
+            .collect();
+        let item_ids: Vec<i32> = detail.lines.iter().map(|line| line.item_id).collect();
+        lock_stock(&mut tx, rma.warehouse_id, &item_ids).await?;
+
+        for inspected in inspection.lines {
+            let line = find_line(&detail, inspected.line_id)?;
+            let remaining = pending
+                .iter_mut()
+                .find(|(line_id, _)| *line_id == line.line_id)
+                .map(|(_, quantity)| quantity)
+                .expect("pending covers every line");
+            if inspected.quantity > *remaining {
+                return Err(violation(format!(
+                    "line {} has only {} awaiting inspection",
+                    line.line_id, remaining
+                )));
+            }
+            *remaining -= inspected.quantity;
+
+            match inspected.outcome {
+                InspectionOutcome::Restock => {
+                    post_movement(
+                        &mut tx,
+                        return_movement(rma, line, "RETURN_RESTOCK", -inspected.quantity, STOCK_QUARANTINE),
+                    )
+                    .await?;
+                    post_movement(
+                        &mut tx,
+                        return_movement(rma, line, "RETURN_RESTOCK", inspected.quantity, STOCK_AVAILABLE),
+                    )
+                    .await?;
+                }
+                InspectionOutcome::Scrap => {
+                    post_movement(
+                        &mut tx,
+                        return_movement(rma, line, "RETURN_SCRAP", -inspected.quantity, STOCK_QUARANTINE),
+                    )
+                    .await?;
+                }
+                InspectionOutcome::Repair => {}
+            }
+
+            let (restocked, scrapped, repair) = match inspected.outcome {
+                InspectionOutcome::Restock => (inspected.quantity, Decimal::ZERO, Decimal::ZERO),
+                InspectionOutcome::Scrap => (Decimal::ZERO, inspected.quantity, Decimal::ZERO),
+                InspectionOutcome::Repair => (Decimal::ZERO, Decimal::ZERO, inspected.quantity),
+            };
+            sqlx::query!(
+                "UPDATE warehouse.return_lines SET
+                     quantity_restocked = quantity_restocked + $2,
+                     quantity_scrapped = quantity_scrapped + $3,
+                     quantity_repair = quantity_repair + $4,
+                     inspection_notes = COALESCE($5, inspection_notes)
+                 WHERE line_id = $1",
+                line.line_id,
+                restocked,
+                scrapped,
+                repair,
+                inspected.notes
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.return_authorizations
+             SET status = $2, completed_at = NOW(), updated_at = NOW()
+             WHERE return_id = $1
+               AND NOT EXISTS (
+                   SELECT 1 FROM warehouse.return_lines
+                   WHERE return_id = $1
+                     AND (quantity_received < quantity_expected
+                          OR quantity_restocked + quantity_scrapped + quantity_repair < quantity_received)
+               )",
+            id,
+            RETURN_COMPLETED
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let detail = load_detail(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Cancel an RMA before any goods arrive
+    pub async fn cancel(&self, id: i32) -> Result<Option<ReturnDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_detail(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+
+        if detail.authorization.status != RETURN_AUTHORIZED {
+            return Err(violation(format!(
+                "cannot cancel a {} return",
+                detail.authorization.status.to_lowercase()
+            )));
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.return_authorizations SET status = $2, updated_at = NOW() WHERE return_id = $1",
+            id,
+            RETURN_CANCELLED
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let detail = load_detail(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+}
+
+/// Load an RMA with its lines; `lock` takes a row lock on the header so concurrent
+/// receipts and inspections of the same RMA run one after another
+async fn load_detail(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<ReturnDetail>> {
+    let authorization = if lock {
+        sqlx::query_as!(
+            ReturnAuthorization,
+            "SELECT * FROM warehouse.return_authorizations WHERE return_id = $1 FOR UPDATE",
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+    } else {
+        sqlx::query_as!(
+            ReturnAuthorization,
+            "SELECT * FROM warehouse.return_authorizations WHERE return_id = $1",
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+    };
+
+    let Some(authorization) = authorization else {
+        return Ok(None);
+    };
+
+    let lines = sqlx::query_as!(
+        ReturnLine,
+        "SELECT * FROM warehouse.return_lines WHERE return_id = $1 ORDER BY line_id",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(ReturnDetail { authorization, lines }))
+}
+
+fn find_line(detail: &ReturnDetail, line_id: i32) -> Result<&ReturnLine> {
+    detail
+        .lines
+        .iter()
+        .find(|line| line.line_id == line_id)
+        .ok_or_else(|| violation(format!("line {} is not part of {}", line_id, detail.authorization.rma_number)))
+}
+
+fn return_movement(
+    rma: &ReturnAuthorization,
+    line: &ReturnLine,
+    movement_type: &str,
+    quantity: Decimal,
+    stock_status: &'static str,
+) -> NewMovement {
+    NewMovement {
+        item_id: line.item_id,
+        warehouse_id: rma.warehouse_id,
+        movement_type: movement_type.to_string(),
+        quantity,
+        catch_weight_kg: None,
+        lot_number: None,
+        unit_cost: None,
+        stock_status,
+        consignor_id: None,
+        reference_type: Some("RMA".to_string()),
+        reference_number: Some(rma.rma_number.clone()),
+        notes: None,
+        created_by: rma.created_by,
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::error::{rejection, violation};
+use crate::repositories::audit::acting_user;
+use crate::repositories::movements::{lock_stock, post_movement};
+use anyhow::Result;
+use sqlx::{Connection, PgPool};
+use warehouse_models::chrono::Utc;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct RfidRepository {
+    pool: PgPool,
+}
+
+impl RfidRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Portals of a warehouse by code
+    pub async fn portals(&self, warehouse_id: i32) -> Result<Vec<RfidPortal>> {
+        let portals = sqlx::query_as!(
+            RfidPortal,
+            "SELECT * FROM warehouse.rfid_portals WHERE warehouse_id = $1 ORDER BY portal_code",
+            warehouse_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(portals)
+    }
+
+    pub async fn portal_code_exists(&self, warehouse_id: i32, code: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.rfid_portals WHERE warehouse_id = $1 AND portal_code = $2)",
+            warehouse_id,
+            code
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    pub async fn create_portal(&self, warehouse_id: i32, portal: CreatePortal) -> Result<RfidPortal> {
+        let created = sqlx::query_as!(
+            RfidPortal,
+            "INSERT INTO warehouse.rfid_portals (warehouse_id, portal_code, portal_name, direction)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *",
+            warehouse_id,
+            portal.portal_code,
+            portal.portal_name,
+            portal.direction
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(created)
+    }
+
+    pub async fn get_tag(&self, epc: &str) -> Result<Option<RfidTag>> {
+        let tag = sqlx::query_as!(RfidTag, "SELECT * FROM warehouse.rfid_tags WHERE epc = $1", epc.to_uppercase())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(tag)
+    }
+
+    /// Register tags against items, replacing the mapping of EPCs seen before. Registering
+    /// a tag into a warehouse does not post stock: the tag labels stock already on hand.
+    pub async fn register_tags(&self, registration: RegisterTags) -> Result<Vec<RfidTag>> {
+        let mut seen = HashSet::new();
+        if let Some(repeated) = registration.tags.iter().find(|tag| !seen.insert(tag.epc.to_uppercase())) {
+            return Err(violation(format!("EPC {} appears more than once", repeated.epc)));
+        }
+
+        let epcs: Vec<String> = registration.tags.iter().map(|tag| tag.epc.to_uppercase()).collect();
+        let item_ids: Vec<i32> = registration.tags.iter().map(|tag| tag.item_id).collect();
+        let warehouse_ids: Vec<Option<i32>> = registration.tags.iter().map(|tag| tag.warehouse_id).collect();
+
+        let mut tx = self.pool.begin().await?;
+
+        let unknown_items = sqlx::query_scalar!(
+            "SELECT id AS \"id!\" FROM UNNEST($1::INT[]) id
+             EXCEPT SELECT item_id FROM warehouse.items",
+            &item_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if let Some(item_id) = unknown_items.first() {
+            return Err(violation(format!("item {} does not exist", item_id)));
+        }
+
+        let unknown_warehouses = sqlx::query_scalar!(
+            "SELECT id AS \"id!\" FROM UNNEST($1::INT[]) id WHERE id IS NOT NULL
+             EXCEPT SELECT warehouse_id FROM warehouse.warehouses",
+            &warehouse_ids as &[Option<i32>]
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if let Some(warehouse_id) = unknown_warehouses.first() {
+            return Err(violation(format!("warehouse {} does not exist", warehouse_id)));
+        }
+
+        let mut tags = Vec::with_capacity(registration.tags.len());
+        for (tag, epc) in registration.tags.into_iter().zip(epcs) {
+            let registered = sqlx::query_as!(
+                RfidTag,
+                "INSERT INTO warehouse.rfid_tags (epc, item_id, serial_number, lpn, quantity, warehouse_id)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (epc) DO UPDATE SET
+                     item_id = EXCLUDED.item_id,
+                     serial_number = EXCLUDED.serial_number,
+                     lpn = EXCLUDED.lpn,
+                     quantity = EXCLUDED.quantity,
+                     warehouse_id = EXCLUDED.warehouse_id
+                 RETURNING *",
+                epc,
+                tag.item_id,
+                tag.serial_number,
+                tag.lpn,
+                tag.quantity.unwrap_or(Decimal::ONE),
+                tag.warehouse_id
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            tags.push(registered);
+        }
+
+        tx.commit().await?;
+        Ok(tags)
+    }
+
+    /// Ingest a batch of portal reads. Repeats within the batch and reads of a tag the
+    /// portal already saw inside the de-duplication window are dropped. Known tags whose
+    /// stock crosses the portal - into the warehouse at an IN portal, out of it at an OUT
+    /// portal - are moved, with one stock movement per item and warehouse. Returns None
+    /// when the portal does not exist.
+    pub async fn ingest(&self, portal_id: i32, reads: PortalReads) -> Result<Option<RfidIngestResult>> {
+        let read_at = reads.read_at.unwrap_or_else(Utc::now);
+        let received = reads.epcs.len();
+
+        let mut seen = HashSet::new();
+        let epcs: Vec<String> =
+            reads.epcs.into_iter().map(|epc| epc.to_uppercase()).filter(|epc| seen.insert(epc.clone())).collect();
+
+        let mut tx = self.pool.begin().await?;
+
+        // The row lock runs batches from one portal one after another, so the window check holds
+        let Some(portal) = sqlx::query_as!(
+            RfidPortal,
+            "SELECT * FROM warehouse.rfid_portals WHERE portal_id = $1 FOR UPDATE",
+            portal_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        if !portal.is_active {
+            return Err(violation(format!("portal {} is not active", portal.portal_code)));
+        }
+
+        let accepted = sqlx::query_scalar!(
+            "INSERT INTO warehouse.rfid_reads (portal_id, epc, read_at)
+             SELECT $1, e, $3 FROM UNNEST($2::VARCHAR[]) e
+             WHERE NOT EXISTS (
+                 SELECT 1 FROM warehouse.rfid_reads r
+                 WHERE r.portal_id = $1 AND r.epc = e
+                   AND r.read_at BETWEEN $3::TIMESTAMPTZ - make_interval(secs => $4)
+                                     AND $3::TIMESTAMPTZ + make_interval(secs => $4)
+             )
+             RETURNING epc",
+            portal_id,
+            &epcs,
+            read_at,
+            RFID_DEDUP_SECONDS as f64
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let tags = sqlx::query_as!(
+            RfidTag,
+            "SELECT * FROM warehouse.rfid_tags WHERE epc = ANY($1) ORDER BY epc FOR UPDATE",
+            &accepted
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let known: HashSet<&str> = tags.iter().map(|tag| tag.epc.as_str()).collect();
+        let unknown_epcs: Vec<String> = accepted.iter().filter(|epc| !known.contains(epc.as_str())).cloned().collect();
+
+        // Signed quantity per (warehouse, item); stock leaving another warehouse is
+        // booked out there before it is booked in here
+        let mut moves: BTreeMap<(i32, i32, bool), (Decimal, usize)> = BTreeMap::new();
+        let mut crossed = Vec::new();
+        for tag in &tags {
+            let inbound = portal.direction == PORTAL_IN;
+            if inbound && tag.warehouse_id != Some(portal.warehouse_id) {
+                if let Some(previous) = tag.warehouse_id {
+                    let entry = moves.entry((previous, tag.item_id, false)).or_default();
+                    entry.0 -= tag.quantity;
+                    entry.1 += 1;
+                }
+                let entry = moves.entry((portal.warehouse_id, tag.item_id, true)).or_default();
+                entry.0 += tag.quantity;
+                entry.1 += 1;
+                crossed.push(tag.epc.clone());
+            } else if !inbound && tag.warehouse_id == Some(portal.warehouse_id) {
+                let entry = moves.entry((portal.warehouse_id, tag.item_id, false)).or_default();
+                entry.0 -= tag.quantity;
+                entry.1 += 1;
+                crossed.push(tag.epc.clone());
+            }
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.rfid_tags SET
+                 last_portal_id = $1, last_seen_at = $2,
+                 warehouse_id = CASE WHEN epc = ANY($4) THEN $5 ELSE warehouse_id END
+             WHERE epc = ANY($3)",
+            portal_id,
+            read_at,
+            &accepted,
+            &crossed,
+            (portal.direction == PORTAL_IN).then_some(portal.warehouse_id)
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE warehouse.rfid_reads SET crossed = TRUE WHERE portal_id = $1 AND read_at = $2 AND epc = ANY($3)",
+            portal_id,
+            read_at,
+            &crossed
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let mut movements = Vec::new();
+        let mut exceptions = Vec::new();
+        let warehouse_ids: HashSet<i32> = moves.keys().map(|(warehouse_id, _, _)| *warehouse_id).collect();
+        for warehouse_id in warehouse_ids {
+            let item_ids: Vec<i32> =
+                moves.keys().filter(|(w, _, _)| *w == warehouse_id).map(|(_, item_id, _)| *item_id).collect();
+            lock_stock(&mut tx, warehouse_id, &item_ids).await?;
+        }
+        let mut outbound_first: Vec<_> = moves.into_iter().collect();
+        outbound_first.sort_by_key(|((_, _, inbound), _)| *inbound);
+        for ((warehouse_id, item_id, inbound), (quantity, tag_count)) in outbound_first {
+            // A savepoint per movement: stock already issued by other means must not
+            // fail the whole batch, the tag has physically crossed either way
+            let mut savepoint = tx.begin().await?;
+            let posted = post_movement(
+                &mut savepoint,
+                NewMovement {
+                    item_id,
+                    warehouse_id,
+                    movement_type: if inbound { "RFID_IN" } else { "RFID_OUT" }.to_string(),
+                    quantity,
+                    catch_weight_kg: None,
+                    lot_number: None,
+                    unit_cost: None,
+                    stock_status: STOCK_AVAILABLE,
+                    consignor_id: None,
+                    reference_type: Some("RFID_PORTAL".to_string()),
+                    reference_number: Some(portal.portal_code.clone()),
+                    notes: Some(format!("{} tag(s)", tag_count)),
+                    created_by: acting_user(),
+                },
+            )
+            .await;
+
+            match posted {
+                Ok(movement) => {
+                    savepoint.commit().await?;
+                    movements.push(movement);
+                }
+                Err(error) => match rejection(&error) {
+                    Some(message) => {
+                        savepoint.rollback().await?;
+                        exceptions.push(message);
+                    }
+                    None => return Err(error),
+                },
+            }
+        }
+
+        tx.commit().await?;
+        Ok(Some(RfidIngestResult {
+            received,
+            accepted: accepted.len(),
+            unknown_epcs,
+            crossings: crossed.len(),
+            movements,
+            exceptions,
+        }))
+    }
+}
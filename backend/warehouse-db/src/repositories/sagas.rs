@@ -0,0 +1,59 @@
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct SagaRepository {
+    pool: PgPool,
+}
+
+impl SagaRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Append an entry to a saga's log
+    pub async fn record(
+        &self,
+        saga_type: &str,
+        saga_id: i32,
+        step: &str,
+        status: &str,
+        detail: Option<String>,
+    ) -> Result<SagaStep> {
+        let entry = sqlx::query_as!(
+            SagaStep,
+            "INSERT INTO warehouse.saga_steps (saga_type, saga_id, step, status, detail)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING *",
+            saga_type,
+            saga_id,
+            step,
+            status,
+            detail
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(entry)
+    }
+
+    pub async fn log(&self, saga_type: &str, saga_id: i32) -> Result<Vec<SagaStep>> {
+        let mut conn = self.pool.acquire().await?;
+        load_log(&mut conn, saga_type, saga_id).await
+    }
+}
+
+/// A saga's log, oldest first
+pub(crate) async fn load_log(conn: &mut PgConnection, saga_type: &str, saga_id: i32) -> Result<Vec<SagaStep>> {
+    let steps = sqlx::query_as!(
+        SagaStep,
+        "SELECT * FROM warehouse.saga_steps WHERE saga_type = $1 AND saga_id = $2 ORDER BY step_id",
+        saga_type,
+        saga_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(steps)
+}
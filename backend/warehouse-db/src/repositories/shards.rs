@@ -0,0 +1,93 @@
+use anyhow::Result;
+use sqlx::PgPool;
+
+/// Master data every shard needs a copy of, with each table's key column
+const MASTER_DATA: [(&str, &str); 2] = [("warehouses", "warehouse_id"), ("items", "item_id")];
+
+/// The primary's side of the shard map: which warehouses it no longer holds stock for, and
+/// the master data it keeps the shards supplied with
+#[derive(Clone)]
+pub struct ShardRepository {
+    pool: PgPool,
+    shards: Vec<PgPool>,
+}
+
+impl ShardRepository {
+    pub fn new(pool: PgPool, shards: Vec<PgPool>) -> Self {
+        Self { pool, shards }
+    }
+
+    /// Replace the recorded shard map with `warehouses`, pairs of warehouse id and shard name
+    pub async fn assign(&self, warehouses: &[(i32, String)]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!("DELETE FROM warehouse.warehouse_shards").execute(&mut *tx).await?;
+        for (warehouse_id, shard_name) in warehouses {
+            sqlx::query!(
+                "INSERT INTO warehouse.warehouse_shards (warehouse_id, shard_name) VALUES ($1, $2)",
+                warehouse_id,
+                shard_name
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Copy every warehouse and item to every shard; rows already up to date are left alone.
+    /// Rows deleted on the primary are kept on the shards. Returns the rows written.
+    pub async fn copy_master_data(&self) -> Result<u64> {
+        let mut copied = 0;
+        for (table, key) in MASTER_DATA {
+            copied += self.copy(table, key, None).await?;
+        }
+        Ok(copied)
+    }
+
+    /// Copy one warehouse to every shard, e.g. right after it was created or changed
+    pub async fn copy_warehouse(&self, warehouse_id: i32) -> Result<u64> {
+        self.copy("warehouses", "warehouse_id", Some(warehouse_id)).await
+    }
+
+    /// Copy one item to every shard, e.g. right after it was created or changed
+    pub async fn copy_item(&self, item_id: i32) -> Result<u64> {
+        self.copy("items", "item_id", Some(item_id)).await
+    }
+
+    async fn copy(&self, table: &str, key: &str, id: Option<i32>) -> Result<u64> {
+        if self.shards.is_empty() {
+            return Ok(0);
+        }
+
+        let columns: Vec<String> = sqlx::query_scalar(
+            "SELECT quote_ident(column_name::TEXT) FROM information_schema.columns
+             WHERE table_schema = 'warehouse' AND table_name = $1 AND is_generated = 'NEVER'
+             ORDER BY ordinal_position",
+        )
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await?;
+        // As JSON text, read back on the shard into the same row type
+        let rows: String = sqlx::query_scalar(&format!(
+            "SELECT COALESCE(jsonb_agg(t), '[]')::TEXT FROM warehouse.{table} t WHERE $1::INTEGER IS NULL OR {key} = $1"
+        ))
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let assignments: Vec<String> = columns.iter().map(|column| format!("{column} = EXCLUDED.{column}")).collect();
+        let upsert = format!(
+            "INSERT INTO warehouse.{table} ({columns})
+             SELECT {columns} FROM jsonb_populate_recordset(NULL::warehouse.{table}, $1::JSONB)
+             ON CONFLICT ({key}) DO UPDATE SET {assignments}
+             WHERE ({table}.*) IS DISTINCT FROM (EXCLUDED.*)",
+            columns = columns.join(", "),
+            assignments = assignments.join(", ")
+        );
+        let mut copied = 0;
+        for shard in &self.shards {
+            copied += sqlx::query(&upsert).bind(&rows).execute(shard).await?.rows_affected();
+        }
+        Ok(copied)
+    }
+}
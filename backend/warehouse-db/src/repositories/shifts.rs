@@ -0,0 +1,211 @@
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::*;
+use crate::error::violation;
+
+#[derive(Clone)]
+pub struct ShiftRepository {
+    pool: PgPool,
+}
+
+impl ShiftRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Shifts of a warehouse in the order they start
+    pub async fn list(&self, warehouse_id: i32) -> Result<Vec<Shift>> {
+        let shifts = sqlx::query_as!(
+            Shift,
+            "SELECT * FROM warehouse.shifts WHERE warehouse_id = $1 ORDER BY starts_at, shift_code",
+            warehouse_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(shifts)
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<ShiftDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_shift(&mut conn, id).await
+    }
+
+    pub async fn code_exists(&self, warehouse_id: i32, code: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.shifts WHERE warehouse_id = $1 AND shift_code = $2)",
+            warehouse_id,
+            code
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    pub async fn create(&self, warehouse_id: i32, shift: CreateShift) -> Result<ShiftDetail> {
+        let mut tx = self.pool.begin().await?;
+
+        let shift_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.shifts (warehouse_id, shift_code, shift_name, starts_at, ends_at)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING shift_id",
+            warehouse_id,
+            shift.shift_code,
+            shift.shift_name,
+            shift.starts_at,
+            shift.ends_at
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        add_to_roster(&mut tx, shift_id, &shift.operator_ids).await?;
+
+        let detail = load_shift(&mut tx, shift_id).await?.expect("shift inserted above");
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    pub async fn update(&self, id: i32, shift: UpdateShift) -> Result<Option<ShiftDetail>> {
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query_scalar!(
+            "UPDATE warehouse.shifts SET
+                 shift_name = COALESCE($2, shift_name),
+                 starts_at = COALESCE($3, starts_at),
+                 ends_at = COALESCE($4, ends_at),
+                 is_active = COALESCE($5, is_active)
+             WHERE shift_id = $1
+             RETURNING shift_id",
+            id,
+            shift.shift_name,
+            shift.starts_at,
+            shift.ends_at,
+            shift.is_active
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if updated.is_none() {
+            return Ok(None);
+        }
+
+        let detail = load_shift(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Put operators on the shift's roster; operators already on it are left alone
+    pub async fn add_operators(&self, id: i32, operator_ids: &[i32]) -> Result<Option<ShiftDetail>> {
+        let mut tx = self.pool.begin().await?;
+        if load_shift(&mut tx, id).await?.is_none() {
+            return Ok(None);
+        }
+
+        add_to_roster(&mut tx, id, operator_ids).await?;
+
+        let detail = load_shift(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Take an operator off the roster. Returns None when the shift does not exist.
+    pub async fn remove_operator(&self, id: i32, operator_id: i32) -> Result<Option<ShiftDetail>> {
+        let mut tx = self.pool.begin().await?;
+        if load_shift(&mut tx, id).await?.is_none() {
+            return Ok(None);
+        }
+
+        let removed = sqlx::query!(
+            "DELETE FROM warehouse.shift_operators WHERE shift_id = $1 AND operator_id = $2",
+            id,
+            operator_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        if removed.rows_affected() == 0 {
+            return Err(violation(format!("operator {} is not rostered on shift {}", operator_id, id)));
+        }
+
+        let detail = load_shift(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+}
+
+/// Operators may take work in a warehouse that runs shifts only while rostered on one
+/// of its active shifts; with `on_duty` that shift must also be running right now.
+/// Warehouses without shifts are open to every operator.
+pub(crate) async fn ensure_rostered(
+    conn: &mut PgConnection,
+    warehouse_id: i32,
+    operator_id: i32,
+    on_duty: bool,
+) -> Result<()> {
+    let allowed = sqlx::query_scalar!(
+        "SELECT NOT EXISTS (SELECT 1 FROM warehouse.shifts WHERE warehouse_id = $1 AND is_active)
+             OR EXISTS (
+                 SELECT 1
+                 FROM warehouse.shifts s
+                 JOIN warehouse.shift_operators r ON r.shift_id = s.shift_id
+                 JOIN warehouse.warehouses w ON w.warehouse_id = s.warehouse_id
+                 WHERE s.warehouse_id = $1
+                   AND s.is_active
+                   AND r.operator_id = $2
+                   AND (NOT $3 OR warehouse.in_shift(
+                       (NOW() AT TIME ZONE COALESCE(w.timezone, 'UTC'))::TIME, s.starts_at, s.ends_at
+                   ))
+             )",
+        warehouse_id,
+        operator_id,
+        on_duty
+    )
+    .fetch_one(&mut *conn)
+    .await?
+    .unwrap_or(false);
+
+    if allowed {
+        Ok(())
+    } else if on_duty {
+        Err(violation(format!(
+            "operator {} is not rostered on a shift on duty at warehouse {}",
+            operator_id, warehouse_id
+        )))
+    } else {
+        Err(violation(format!(
+            "operator {} is not rostered on any shift at warehouse {}",
+            operator_id, warehouse_id
+        )))
+    }
+}
+
+async fn add_to_roster(conn: &mut PgConnection, shift_id: i32, operator_ids: &[i32]) -> Result<()> {
+    sqlx::query!(
+        "INSERT INTO warehouse.shift_operators (shift_id, operator_id)
+         SELECT $1, UNNEST($2::INT[])
+         ON CONFLICT DO NOTHING",
+        shift_id,
+        operator_ids
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn load_shift(conn: &mut PgConnection, id: i32) -> Result<Option<ShiftDetail>> {
+    let Some(shift) = sqlx::query_as!(Shift, "SELECT * FROM warehouse.shifts WHERE shift_id = $1", id)
+        .fetch_optional(&mut *conn)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let operator_ids = sqlx::query_scalar!(
+        "SELECT operator_id FROM warehouse.shift_operators WHERE shift_id = $1 ORDER BY operator_id",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(ShiftDetail { shift, operator_ids }))
+}
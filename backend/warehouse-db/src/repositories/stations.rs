@@ -0,0 +1,195 @@
+use crate::error::violation;
+use crate::utils::*;
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct StationRepository {
+    pool: PgPool,
+}
+
+impl StationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn stations(&self, warehouse_id: i32) -> Result<Vec<MeasuringStation>> {
+        let stations = sqlx::query_as!(
+            MeasuringStation,
+            "SELECT * FROM warehouse.measuring_stations WHERE warehouse_id = $1 ORDER BY station_code",
+            warehouse_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(stations)
+    }
+
+    pub async fn get_station(&self, id: i32) -> Result<Option<MeasuringStation>> {
+        let station =
+            sqlx::query_as!(MeasuringStation, "SELECT * FROM warehouse.measuring_stations WHERE station_id = $1", id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(station)
+    }
+
+    pub async fn station_code_exists(&self, warehouse_id: i32, code: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.measuring_stations WHERE warehouse_id = $1 AND station_code = $2)",
+            warehouse_id,
+            code
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    pub async fn create_station(&self, warehouse_id: i32, station: CreateStation) -> Result<MeasuringStation> {
+        let created = sqlx::query_as!(
+            MeasuringStation,
+            "INSERT INTO warehouse.measuring_stations (warehouse_id, station_code, station_name, station_type)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *",
+            warehouse_id,
+            station.station_code,
+            station.station_name,
+            station.station_type
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(created)
+    }
+
+    /// Store a capture and copy it onto the carton or item it measured. Packing stations
+    /// measure cartons of their own warehouse, receiving stations measure items. Only the
+    /// measured attributes are overwritten; an item's volume follows its new dimensions.
+    pub async fn record(&self, station_id: i32, capture: NewCapture) -> Result<Option<StationCapture>> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(station) = sqlx::query_as!(
+            MeasuringStation,
+            "SELECT * FROM warehouse.measuring_stations WHERE station_id = $1 FOR UPDATE",
+            station_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+        if !station.is_active {
+            return Err(violation(format!("station {} is not active", station.station_code)));
+        }
+
+        match (station.station_type.as_str(), capture.carton_id, capture.item_id) {
+            (STATION_PACKING, Some(carton_id), _) => {
+                let updated = sqlx::query_scalar!(
+                    "UPDATE warehouse.cartons c SET
+                         weight_kg = COALESCE($3, c.weight_kg),
+                         length_cm = COALESCE($4, c.length_cm),
+                         width_cm = COALESCE($5, c.width_cm),
+                         height_cm = COALESCE($6, c.height_cm),
+                         measured_at = $7
+                     FROM warehouse.outbound_orders o
+                     WHERE c.carton_id = $1 AND o.order_id = c.order_id AND o.warehouse_id = $2
+                     RETURNING c.carton_id",
+                    carton_id,
+                    station.warehouse_id,
+                    capture.weight_kg,
+                    capture.length_cm,
+                    capture.width_cm,
+                    capture.height_cm,
+                    capture.captured_at
+                )
+                .fetch_optional(&mut *tx)
+                .await?;
+                if updated.is_none() {
+                    return Err(violation(format!(
+                        "carton {} was not packed in warehouse {}",
+                        carton_id, station.warehouse_id
+                    )));
+                }
+            }
+            (STATION_RECEIVING, _, Some(item_id)) => {
+                let updated = sqlx::query_scalar!(
+                    "UPDATE warehouse.items SET
+                         weight_kg = COALESCE($2, weight_kg),
+                         length_cm = COALESCE($3, length_cm),
+                         width_cm = COALESCE($4, width_cm),
+                         height_cm = COALESCE($5, height_cm),
+                         volume_cbm = COALESCE($3 * $4 * $5 / 1000000, volume_cbm),
+                         updated_at = NOW()
+                     WHERE item_id = $1
+                     RETURNING item_id",
+                    item_id,
+                    capture.weight_kg,
+                    capture.length_cm,
+                    capture.width_cm,
+                    capture.height_cm
+                )
+                .fetch_optional(&mut *tx)
+                .await?;
+                if updated.is_none() {
+                    return Err(violation(format!("item {} does not exist", item_id)));
+                }
+            }
+            (STATION_PACKING, ..) => return Err(violation("packing stations measure cartons")),
+            _ => return Err(violation("receiving stations measure items")),
+        }
+
+        let stored = sqlx::query_as!(
+            StationCapture,
+            "INSERT INTO warehouse.station_captures (
+                 station_id, carton_id, item_id, weight_kg, length_cm, width_cm, height_cm, scale_output, captured_at
+             )
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             RETURNING *",
+            station_id,
+            capture.carton_id,
+            capture.item_id,
+            capture.weight_kg,
+            capture.length_cm,
+            capture.width_cm,
+            capture.height_cm,
+            capture.scale_output,
+            capture.captured_at
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE warehouse.measuring_stations SET last_capture_at = GREATEST(last_capture_at, $2)
+             WHERE station_id = $1",
+            station_id,
+            stored.captured_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(stored))
+    }
+
+    pub async fn captures(&self, station_id: i32, keyset: KeysetQuery) -> Result<KeysetPage<StationCapture>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            StationCapture,
+            "SELECT * FROM warehouse.station_captures
+             WHERE station_id = $1 AND ($2::BIGINT IS NULL OR capture_id < $2)
+             ORDER BY capture_id DESC
+             LIMIT $3",
+            station_id,
+            after_id,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |capture| capture.capture_id))
+    }
+}
@@ -0,0 +1,328 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use sqlx::PgPool;
+use warehouse_models::*;
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::movements::post_movement;
+use crate::store::*;
+use crate::utils::*;
+
+/// A stock row as stored, its costs in separate amount and currency columns
+pub(crate) struct StockRow {
+    pub stock_id: i32,
+    pub item_id: i32,
+    pub warehouse_id: i32,
+    pub consignor_id: Option<i32>,
+    pub client_id: Option<i32>,
+    pub quantity_on_hand: Decimal,
+    pub quantity_reserved: Decimal,
+    pub quantity_available: Option<Decimal>,
+    pub min_stock_level: Option<Decimal>,
+    pub max_stock_level: Option<Decimal>,
+    pub reorder_point: Option<Decimal>,
+    pub unit_cost: Option<Decimal>,
+    pub average_cost: Option<Decimal>,
+    pub total_value: Option<Decimal>,
+    pub cost_currency: String,
+    pub catch_weight_kg: Option<Decimal>,
+    pub last_movement_date: Option<NaiveDate>,
+    pub last_receipt_date: Option<NaiveDate>,
+    pub last_issue_date: Option<NaiveDate>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<StockRow> for StockInventory {
+    fn from(row: StockRow) -> Self {
+        StockInventory {
+            stock_id: row.stock_id,
+            item_id: row.item_id,
+            warehouse_id: row.warehouse_id,
+            consignor_id: row.consignor_id,
+            client_id: row.client_id,
+            quantity_on_hand: row.quantity_on_hand,
+            quantity_reserved: row.quantity_reserved,
+            quantity_available: row.quantity_available,
+            min_stock_level: row.min_stock_level,
+            max_stock_level: row.max_stock_level,
+            reorder_point: row.reorder_point,
+            unit_cost: Money::from_column(row.unit_cost, &row.cost_currency),
+            average_cost: Money::from_column(row.average_cost, &row.cost_currency),
+            total_value: Money::from_column(row.total_value, &row.cost_currency),
+            catch_weight_kg: row.catch_weight_kg,
+            last_movement_date: row.last_movement_date,
+            last_receipt_date: row.last_receipt_date,
+            last_issue_date: row.last_issue_date,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct StockRepository {
+    pool: PgPool,
+}
+
+impl StockRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Every owner's stock rows in a warehouse, by item
+    pub async fn by_warehouse(
+        &self,
+        warehouse_id: i32,
+        pagination: PaginationQuery,
+    ) -> Result<PaginatedResponse<StockInventory>> {
+        self.page(Some(warehouse_id), None, pagination).await
+    }
+
+    /// Every owner's stock rows of an item, by warehouse
+    pub async fn by_item(
+        &self,
+        item_id: i32,
+        pagination: PaginationQuery,
+    ) -> Result<PaginatedResponse<StockInventory>> {
+        self.page(None, Some(item_id), pagination).await
+    }
+
+    /// Create the item's own stock row in the warehouse if there is none, and set the
+    /// levels given
+    pub async fn upsert(&self, warehouse_id: i32, item_id: i32, stock: UpsertStock) -> Result<StockInventory> {
+        let row = sqlx::query_as!(
+            StockRow,
+            "INSERT INTO warehouse.stock_inventory (item_id, warehouse_id, min_stock_level, max_stock_level, reorder_point)
+             VALUES ($1, $2, COALESCE($3::DECIMAL, 0), COALESCE($4::DECIMAL, 0), COALESCE($5::DECIMAL, 0))
+             ON CONFLICT ON CONSTRAINT stock_inventory_item_warehouse_owner_key DO UPDATE SET
+                 min_stock_level = COALESCE($3, stock_inventory.min_stock_level),
+                 max_stock_level = COALESCE($4, stock_inventory.max_stock_level),
+                 reorder_point = COALESCE($5, stock_inventory.reorder_point),
+                 updated_at = NOW()
+             RETURNING stock_id, item_id, warehouse_id, consignor_id, client_id, quantity_on_hand, quantity_reserved,
+                       quantity_available, min_stock_level, max_stock_level, reorder_point, unit_cost, average_cost,
+                       total_value, cost_currency, catch_weight_kg, last_movement_date, last_receipt_date, last_issue_date,
+                       created_at, updated_at",
+            item_id,
+            warehouse_id,
+            stock.min_stock_level,
+            stock.max_stock_level,
+            stock.reorder_point
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.into())
+    }
+
+    /// Issue our own stock of an item out of a warehouse. The balance row stays locked from
+    /// the availability check to the posting, so concurrent issues queue up and none can
+    /// take units another has already taken: an issue larger than what is left is refused.
+    pub async fn issue(&self, warehouse_id: i32, item_id: i32, issue: IssueStock) -> Result<StockMovement> {
+        let mut tx = self.pool.begin().await?;
+        let available = sqlx::query_scalar!(
+            "SELECT quantity_available FROM warehouse.stock_inventory
+             WHERE item_id = $1 AND warehouse_id = $2 AND consignor_id IS NULL
+             FOR UPDATE",
+            item_id,
+            warehouse_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .flatten()
+        .unwrap_or_default();
+        if issue.quantity > available {
+            return Err(violation(format!(
+                "cannot issue {} of item {} in warehouse {}: only {} available",
+                issue.quantity,
+                item_id,
+                warehouse_id,
+                available.max(Decimal::ZERO).normalize()
+            )));
+        }
+
+        let movement = post_movement(
+            &mut tx,
+            NewMovement {
+                item_id,
+                warehouse_id,
+                movement_type: MOVEMENT_ISSUE.to_string(),
+                quantity: -issue.quantity,
+                catch_weight_kg: None,
+                lot_number: None,
+                unit_cost: None,
+                stock_status: STOCK_AVAILABLE,
+                consignor_id: None,
+                reference_type: Some("ISSUE".to_string()),
+                reference_number: issue.reference,
+                notes: issue.notes,
+                created_by: acting_user(),
+            },
+        )
+        .await?;
+        sqlx::query!(
+            "UPDATE warehouse.stock_inventory SET last_issue_date = CURRENT_DATE
+             WHERE item_id = $1 AND warehouse_id = $2 AND consignor_id IS NULL",
+            item_id,
+            warehouse_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(movement)
+    }
+
+    /// The item's stock position in the warehouse and the supply on its way in: what is
+    /// still to come on open purchase orders and on transfers in transit
+    pub async fn supply(&self, warehouse_id: i32, item_id: i32) -> Result<(StockPosition, Vec<InboundSupply>)> {
+        let position = sqlx::query_as!(
+            StockPosition,
+            r#"SELECT COALESCE(SUM(s.quantity_on_hand), 0) AS "on_hand!",
+                      COALESCE(SUM(s.quantity_reserved), 0) AS "reserved!",
+                      COALESCE((SELECT SUM(GREATEST(l.quantity_allocated - l.quantity_shipped, 0))
+                                FROM warehouse.outbound_order_lines l
+                                JOIN warehouse.outbound_orders o ON o.order_id = l.order_id
+                                WHERE l.item_id = $2 AND o.warehouse_id = $1
+                                  AND o.status IN ('OPEN', 'PARTIALLY_SHIPPED')), 0) AS "allocated!",
+                      COALESCE((SELECT SUM(l.quantity)
+                                FROM warehouse.stock_transfer_lines l
+                                JOIN warehouse.stock_transfers t ON t.transfer_id = l.transfer_id
+                                WHERE l.item_id = $2 AND t.source_warehouse_id = $1 AND t.status = 'PLANNED'), 0)
+                          AS "transfers_out!"
+               FROM warehouse.stock_inventory s
+               WHERE s.warehouse_id = $1 AND s.item_id = $2"#,
+            warehouse_id,
+            item_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let inbound = sqlx::query_as!(
+            InboundSupply,
+            r#"SELECT 'PURCHASE_ORDER' AS "source!", po.po_number AS "reference_number!",
+                      po.expected_date AS "expected_date?",
+                      SUM(l.quantity_ordered - l.quantity_received) AS "quantity!"
+               FROM warehouse.purchase_orders po
+               JOIN warehouse.purchase_order_lines l ON l.po_id = po.po_id
+               WHERE po.warehouse_id = $1 AND po.status = 'OPEN' AND l.item_id = $2
+               GROUP BY po.po_id
+               HAVING SUM(l.quantity_ordered - l.quantity_received) > 0
+               UNION ALL
+               SELECT 'TRANSFER', t.transfer_number, NULL::DATE, SUM(l.quantity)
+               FROM warehouse.stock_transfers t
+               JOIN warehouse.stock_transfer_lines l ON l.transfer_id = t.transfer_id
+               WHERE t.destination_warehouse_id = $1 AND t.status = 'IN_TRANSIT' AND l.item_id = $2
+               GROUP BY t.transfer_id
+               ORDER BY 3 NULLS FIRST, 2"#,
+            warehouse_id,
+            item_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((position, inbound))
+    }
+
+    async fn page(
+        &self,
+        warehouse_id: Option<i32>,
+        item_id: Option<i32>,
+        pagination: PaginationQuery,
+    ) -> Result<PaginatedResponse<StockInventory>> {
+        let (page, limit) = validate_pagination(&pagination);
+        let offset = calculate_offset(page, limit);
+        let mode = pagination.count.unwrap_or_default();
+
+        let total = match mode {
+            CountMode::Exact => sqlx::query_scalar!(
+                "SELECT COUNT(*) FROM warehouse.stock_inventory
+                 WHERE ($1::INT IS NULL OR warehouse_id = $1)
+                   AND ($2::INT IS NULL OR item_id = $2)",
+                warehouse_id,
+                item_id
+            )
+            .fetch_one(&self.pool)
+            .await?,
+            CountMode::Estimate => Some(estimate_row_count(&self.pool, "warehouse.stock_inventory").await?),
+            CountMode::Skip => None,
+        };
+
+        let rows = sqlx::query_as!(
+            StockRow,
+            "SELECT stock_id, item_id, warehouse_id, consignor_id, client_id, quantity_on_hand, quantity_reserved,
+                    quantity_available, min_stock_level, max_stock_level, reorder_point, unit_cost, average_cost,
+                    total_value, cost_currency, catch_weight_kg, last_movement_date, last_receipt_date, last_issue_date,
+                    created_at, updated_at
+             FROM warehouse.stock_inventory
+             WHERE ($1::INT IS NULL OR warehouse_id = $1)
+               AND ($2::INT IS NULL OR item_id = $2)
+             ORDER BY item_id, warehouse_id, consignor_id NULLS FIRST
+             LIMIT $3 OFFSET $4",
+            warehouse_id,
+            item_id,
+            limit + 1,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let stock = rows.into_iter().map(StockInventory::from).collect();
+        Ok(PaginatedResponse::from_probe(stock, total, mode, page, limit))
+    }
+}
+
+#[async_trait]
+impl StockStore for StockRepository {
+    /// Stock on hand at the end of `filter.as_of`, reconstructed by rolling the current
+    /// balances back through the movements posted after that day. Balances that never went
+    /// through the ledger (opening stock) are taken to have been there all along.
+    async fn snapshot(&self, filter: StockFilter) -> Result<StockSnapshot> {
+        let as_of = filter.as_of.unwrap_or_else(|| Utc::now().date_naive());
+
+        let balances = sqlx::query_as!(
+            StockBalance,
+            r#"WITH later AS (
+                   SELECT item_id, warehouse_id, consignor_id,
+                          SUM(CASE WHEN stock_status = 'QUARANTINE' THEN 0 ELSE quantity END) AS on_hand,
+                          SUM(CASE WHEN stock_status = 'QUARANTINE' THEN quantity ELSE 0 END) AS quarantined,
+                          SUM(catch_weight_kg) AS weight
+                   FROM warehouse.stock_movements
+                   WHERE movement_date >= ($1::DATE + 1)::TIMESTAMP AT TIME ZONE 'UTC'
+                     AND ($2::INT IS NULL OR warehouse_id = $2)
+                     AND ($3::INT IS NULL OR item_id = $3)
+                   GROUP BY item_id, warehouse_id, consignor_id
+               ), balances AS (
+                   SELECT s.item_id, s.warehouse_id, s.consignor_id, s.client_id,
+                          s.quantity_on_hand - COALESCE(l.on_hand, 0) AS quantity_on_hand,
+                          s.quantity_quarantined - COALESCE(l.quarantined, 0) AS quantity_quarantined,
+                          s.catch_weight_kg - COALESCE(l.weight, 0) AS catch_weight_kg
+                   FROM warehouse.stock_inventory s
+                   LEFT JOIN later l ON l.item_id = s.item_id AND l.warehouse_id = s.warehouse_id
+                                    AND l.consignor_id IS NOT DISTINCT FROM s.consignor_id
+                   WHERE ($2::INT IS NULL OR s.warehouse_id = $2)
+                     AND ($3::INT IS NULL OR s.item_id = $3)
+               )
+               SELECT b.item_id, i.item_code, i.item_name, b.warehouse_id, b.consignor_id, b.client_id,
+                      b.quantity_on_hand AS "quantity_on_hand!",
+                      b.quantity_quarantined AS "quantity_quarantined!",
+                      b.catch_weight_kg
+               FROM balances b
+               JOIN warehouse.items i ON i.item_id = b.item_id
+               WHERE (b.quantity_on_hand <> 0 OR b.quantity_quarantined <> 0)
+                 AND ($4::TEXT IS NULL OR i.item_code ILIKE '%' || $4 || '%' OR i.item_name ILIKE '%' || $4 || '%')
+               ORDER BY i.item_code, b.warehouse_id, b.consignor_id NULLS FIRST"#,
+            as_of,
+            filter.warehouse_id,
+            filter.item_id,
+            filter.q
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(StockSnapshot { as_of, balances })
+    }
+}
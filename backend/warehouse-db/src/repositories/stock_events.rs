@@ -0,0 +1,225 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Duration, NaiveTime, Utc};
+use sqlx::PgPool;
+use warehouse_models::*;
+use crate::store::*;
+use crate::utils::*;
+
+/// Serializes writers of the balances projection
+const PROJECTION_LOCK: i64 = 0x7374_6f63_6b65;
+
+/// Event-sourced stock: balances are folded from the stock event streams rather than read
+/// from `stock_inventory`, so they can be reproduced exactly as of any instant
+#[derive(Clone)]
+pub struct StockEventRepository {
+    pool: PgPool,
+}
+
+impl StockEventRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Events newest first, paged by `event_id`
+    pub async fn history(&self, filter: StockEventFilter, keyset: KeysetQuery) -> Result<KeysetPage<StockEvent>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            StockEvent,
+            "SELECT * FROM warehouse.stock_events
+             WHERE ($1::BIGINT IS NULL OR event_id < $1)
+               AND ($2::INT IS NULL OR item_id = $2)
+               AND ($3::INT IS NULL OR warehouse_id = $3)
+               AND ($4::INT IS NULL OR consignor_id = $4)
+             ORDER BY event_id DESC
+             LIMIT $5",
+            after_id,
+            filter.item_id,
+            filter.warehouse_id,
+            filter.consignor_id,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |e| e.event_id))
+    }
+
+    /// Fold the events appended since the last run into the balances projection
+    pub async fn project(&self) -> Result<StockProjection> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!("SELECT pg_advisory_xact_lock($1)", PROJECTION_LOCK).execute(&mut *tx).await?;
+
+        // A stream's versions commit in order, so everything up to its projected version is in
+        let streams = sqlx::query!(
+            "INSERT INTO warehouse.stock_event_balances AS b (
+                 item_id, warehouse_id, consignor_id, version, quantity_on_hand, quantity_reserved,
+                 quantity_quarantined, catch_weight_kg, client_id
+             )
+             SELECT e.item_id, e.warehouse_id, e.consignor_id, MAX(e.version), SUM(e.on_hand_delta),
+                    SUM(e.reserved_delta), SUM(e.quarantined_delta), SUM(e.catch_weight_delta),
+                    (ARRAY_AGG(e.client_id ORDER BY e.version DESC))[1]
+             FROM warehouse.stock_events e
+             LEFT JOIN warehouse.stock_event_balances p
+                    ON p.item_id = e.item_id AND p.warehouse_id = e.warehouse_id
+                   AND p.consignor_id IS NOT DISTINCT FROM e.consignor_id
+             WHERE e.version > COALESCE(p.version, 0)
+             GROUP BY e.item_id, e.warehouse_id, e.consignor_id
+             ON CONFLICT (item_id, warehouse_id, consignor_id) DO UPDATE
+             SET version = EXCLUDED.version,
+                 quantity_on_hand = b.quantity_on_hand + EXCLUDED.quantity_on_hand,
+                 quantity_reserved = b.quantity_reserved + EXCLUDED.quantity_reserved,
+                 quantity_quarantined = b.quantity_quarantined + EXCLUDED.quantity_quarantined,
+                 catch_weight_kg = b.catch_weight_kg + EXCLUDED.catch_weight_kg,
+                 client_id = EXCLUDED.client_id,
+                 projected_at = NOW()"
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+        Ok(StockProjection { streams: streams as i64 })
+    }
+
+    /// Throw the balances projection away and fold it again from the first event
+    pub async fn rebuild(&self) -> Result<StockProjection> {
+        self.rebuild_from(0).await
+    }
+
+    /// Refold, from their first event, the balances of every stream with an event at or after
+    /// `from_event_id`
+    pub async fn rebuild_from(&self, from_event_id: i64) -> Result<StockProjection> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!("SELECT pg_advisory_xact_lock($1)", PROJECTION_LOCK).execute(&mut *tx).await?;
+
+        sqlx::query!(
+            "DELETE FROM warehouse.stock_event_balances b
+             WHERE EXISTS (
+                 SELECT 1 FROM warehouse.stock_events e
+                 WHERE e.event_id >= $1 AND e.item_id = b.item_id AND e.warehouse_id = b.warehouse_id
+                   AND e.consignor_id IS NOT DISTINCT FROM b.consignor_id
+             )",
+            from_event_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let streams = sqlx::query!(
+            "INSERT INTO warehouse.stock_event_balances (
+                 item_id, warehouse_id, consignor_id, version, quantity_on_hand, quantity_reserved,
+                 quantity_quarantined, catch_weight_kg, client_id
+             )
+             SELECT item_id, warehouse_id, consignor_id, MAX(version), SUM(on_hand_delta), SUM(reserved_delta),
+                    SUM(quarantined_delta), SUM(catch_weight_delta), (ARRAY_AGG(client_id ORDER BY version DESC))[1]
+             FROM warehouse.stock_events e
+             WHERE EXISTS (
+                 SELECT 1 FROM warehouse.stock_events later
+                 WHERE later.event_id >= $1 AND later.item_id = e.item_id AND later.warehouse_id = e.warehouse_id
+                   AND later.consignor_id IS NOT DISTINCT FROM e.consignor_id
+             )
+             GROUP BY item_id, warehouse_id, consignor_id",
+            from_event_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+        Ok(StockProjection { streams: streams as i64 })
+    }
+
+    /// Up to `limit` events after `after_event_id`, oldest first
+    pub async fn after(&self, after_event_id: i64, limit: i64) -> Result<Vec<StockEvent>> {
+        let events = sqlx::query_as!(
+            StockEvent,
+            "SELECT * FROM warehouse.stock_events WHERE event_id > $1 ORDER BY event_id LIMIT $2",
+            after_event_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Events from `first_event_id` through `last_event_id`, oldest first
+    pub async fn range(&self, first_event_id: i64, last_event_id: i64) -> Result<Vec<StockEvent>> {
+        let events = sqlx::query_as!(
+            StockEvent,
+            "SELECT * FROM warehouse.stock_events WHERE event_id BETWEEN $1 AND $2 ORDER BY event_id",
+            first_event_id,
+            last_event_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Number of events at or after `from_event_id`
+    pub async fn count_from(&self, from_event_id: i64) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM warehouse.stock_events WHERE event_id >= $1"#,
+            from_event_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+}
+
+#[async_trait]
+impl StockStore for StockEventRepository {
+    /// Stock on hand at the end of `filter.as_of`. Today's balances are the projection plus
+    /// the events not yet folded into it; past days fold every event up to the day's end.
+    async fn snapshot(&self, filter: StockFilter) -> Result<StockSnapshot> {
+        let today = Utc::now().date_naive();
+        let as_of = filter.as_of.unwrap_or(today);
+        let current = as_of >= today;
+        let until = (as_of + Duration::days(1)).and_time(NaiveTime::MIN).and_utc();
+
+        let balances = sqlx::query_as!(
+            StockBalance,
+            r#"WITH folded AS (
+                   SELECT item_id, warehouse_id, consignor_id, version, quantity_on_hand AS on_hand,
+                          quantity_quarantined AS quarantined, catch_weight_kg AS weight, client_id
+                   FROM warehouse.stock_event_balances
+                   WHERE $1
+                   UNION ALL
+                   SELECT e.item_id, e.warehouse_id, e.consignor_id, e.version, e.on_hand_delta,
+                          e.quarantined_delta, e.catch_weight_delta, e.client_id
+                   FROM warehouse.stock_events e
+                   LEFT JOIN warehouse.stock_event_balances p
+                          ON $1 AND p.item_id = e.item_id AND p.warehouse_id = e.warehouse_id
+                         AND p.consignor_id IS NOT DISTINCT FROM e.consignor_id
+                   WHERE CASE WHEN $1 THEN e.version > COALESCE(p.version, 0) ELSE e.occurred_at < $2 END
+               )
+               SELECT f.item_id AS "item_id!", i.item_code, i.item_name, f.warehouse_id AS "warehouse_id!",
+                      f.consignor_id,
+                      (ARRAY_AGG(f.client_id ORDER BY f.version DESC))[1] AS client_id,
+                      SUM(f.on_hand) AS "quantity_on_hand!",
+                      SUM(f.quarantined) AS "quantity_quarantined!",
+                      NULLIF(SUM(f.weight), 0) AS catch_weight_kg
+               FROM folded f
+               JOIN warehouse.items i ON i.item_id = f.item_id
+               WHERE ($3::INT IS NULL OR f.warehouse_id = $3)
+                 AND ($4::INT IS NULL OR f.item_id = $4)
+                 AND ($5::TEXT IS NULL OR i.item_code ILIKE '%' || $5 || '%' OR i.item_name ILIKE '%' || $5 || '%')
+               GROUP BY f.item_id, i.item_code, i.item_name, f.warehouse_id, f.consignor_id
+               HAVING SUM(f.on_hand) <> 0 OR SUM(f.quarantined) <> 0
+               ORDER BY i.item_code, f.warehouse_id, f.consignor_id NULLS FIRST"#,
+            current,
+            until,
+            filter.warehouse_id,
+            filter.item_id,
+            filter.q
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(StockSnapshot { as_of, balances })
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::chrono::NaiveDate;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct StockOutRepository {
+    pool: PgPool,
+}
+
+impl StockOutRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Incidents matching the filter, newest first
+    pub async fn incidents(&self, filter: &StockOutFilter) -> Result<Vec<StockOutIncident>> {
+        let incidents = sqlx::query_as!(
+            StockOutIncident,
+            "SELECT * FROM warehouse.stock_out_incidents
+             WHERE ($1::INT IS NULL OR warehouse_id = $1) AND ($2::INT IS NULL OR item_id = $2)
+               AND ($3::DATE IS NULL OR occurred_at::DATE >= $3) AND ($4::DATE IS NULL OR occurred_at::DATE <= $4)
+             ORDER BY incident_id DESC",
+            filter.warehouse_id,
+            filter.item_id,
+            filter.from,
+            filter.to
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(incidents)
+    }
+
+    /// Lost demand per warehouse and item from `from` through `to`, the items stocked out
+    /// on the most days first
+    pub async fn lost_demand(
+        &self,
+        filter: &StockOutFilter,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<LostDemand>> {
+        let rows = sqlx::query_as!(
+            LostDemand,
+            r#"SELECT s.warehouse_id, s.item_id, i.item_code,
+                      COUNT(*) AS "incidents!",
+                      COUNT(DISTINCT s.occurred_at::DATE) AS "stock_out_days!",
+                      ROUND(COUNT(DISTINCT s.occurred_at::DATE) * 100.0 / ($4::DATE - $3::DATE + 1), 2)
+                          AS "stock_out_day_pct!",
+                      SUM(s.quantity_demanded) AS "quantity_lost!",
+                      COUNT(DISTINCT s.requested_by) AS "requesters!",
+                      MIN(s.occurred_at) AS "first_occurred_at!",
+                      MAX(s.occurred_at) AS "last_occurred_at!"
+               FROM warehouse.stock_out_incidents s
+               JOIN warehouse.items i ON i.item_id = s.item_id
+               WHERE ($1::INT IS NULL OR s.warehouse_id = $1) AND ($2::INT IS NULL OR s.item_id = $2)
+                 AND s.occurred_at::DATE BETWEEN $3 AND $4
+               GROUP BY s.warehouse_id, s.item_id, i.item_code
+               ORDER BY 5 DESC, 4 DESC, i.item_code, s.warehouse_id"#,
+            filter.warehouse_id,
+            filter.item_id,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+}
+
+/// Record demand that found no free stock
+pub(crate) async fn record_stock_out(conn: &mut PgConnection, stock_out: NewStockOut) -> Result<()> {
+    sqlx::query!(
+        "INSERT INTO warehouse.stock_out_incidents (
+             warehouse_id, item_id, quantity_demanded, requested_by, reference_type, reference_number
+         ) VALUES ($1, $2, $3, $4, $5, $6)",
+        stock_out.warehouse_id,
+        stock_out.item_id,
+        stock_out.quantity_demanded,
+        stock_out.requested_by,
+        stock_out.reference_type,
+        stock_out.reference_number
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct SyncRepository {
+    pool: PgPool,
+}
+
+impl SyncRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Tasks and locations of a warehouse changed since `since`, or the open work and
+    /// active locations when there is no cursor. The new cursor is the oldest transaction
+    /// still running when the delta was read, so changes committed after this read are
+    /// always picked up by the next one, at the cost of sometimes sending them twice.
+    pub async fn delta(&self, warehouse_id: i32, since: Option<i64>) -> Result<SyncDelta> {
+        let cursor =
+            sqlx::query_scalar!(r#"SELECT pg_snapshot_xmin(pg_current_snapshot())::TEXT::BIGINT AS "cursor!""#)
+                .fetch_one(&self.pool)
+                .await?;
+
+        let (tasks, locations) = match since {
+            Some(since) => {
+                let tasks = sqlx::query_as!(
+                    Task,
+                    "SELECT * FROM warehouse.tasks
+                     WHERE task_id IN (
+                         SELECT entity_id FROM warehouse.sync_changes
+                         WHERE warehouse_id = $1 AND entity_type = $2 AND txid >= $3
+                     )
+                     ORDER BY task_id",
+                    warehouse_id,
+                    SYNC_ENTITY_TASK,
+                    since
+                )
+                .fetch_all(&self.pool)
+                .await?;
+                let locations = sqlx::query_as!(
+                    Location,
+                    "SELECT * FROM warehouse.locations
+                     WHERE location_id IN (
+                         SELECT entity_id FROM warehouse.sync_changes
+                         WHERE warehouse_id = $1 AND entity_type = $2 AND txid >= $3
+                     )
+                     ORDER BY location_id",
+                    warehouse_id,
+                    SYNC_ENTITY_LOCATION,
+                    since
+                )
+                .fetch_all(&self.pool)
+                .await?;
+                (tasks, locations)
+            }
+            None => {
+                let tasks = sqlx::query_as!(
+                    Task,
+                    "SELECT * FROM warehouse.tasks
+                     WHERE warehouse_id = $1 AND status IN ($2, $3, $4)
+                     ORDER BY task_id",
+                    warehouse_id,
+                    TASK_OPEN,
+                    TASK_ASSIGNED,
+                    TASK_IN_PROGRESS
+                )
+                .fetch_all(&self.pool)
+                .await?;
+                let locations = sqlx::query_as!(
+                    Location,
+                    "SELECT l.* FROM warehouse.locations l
+                     JOIN warehouse.zones z ON z.zone_id = l.zone_id
+                     WHERE z.warehouse_id = $1 AND l.is_active
+                     ORDER BY l.location_id",
+                    warehouse_id
+                )
+                .fetch_all(&self.pool)
+                .await?;
+                (tasks, locations)
+            }
+        };
+
+        Ok(SyncDelta { cursor, full: since.is_none(), tasks, locations })
+    }
+
+    /// Outcome of a mutation the device submitted before, if any
+    pub async fn recorded(&self, device_id: &str, mutation_id: &str) -> Result<Option<RecordedMutation>> {
+        let recorded = sqlx::query_as!(
+            RecordedMutation,
+            "SELECT task_id, status, message FROM warehouse.sync_mutations
+             WHERE device_id = $1 AND mutation_id = $2",
+            device_id,
+            mutation_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(recorded)
+    }
+
+    pub async fn record(
+        &self,
+        device_id: &str,
+        mutation_id: &str,
+        task_id: i32,
+        status: MutationStatus,
+        message: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO warehouse.sync_mutations (device_id, mutation_id, task_id, status, message)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT DO NOTHING",
+            device_id,
+            mutation_id,
+            task_id,
+            status.as_str(),
+            message
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,348 @@
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::*;
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::hazmat::ensure_storable;
+use crate::repositories::shifts::ensure_rostered;
+use crate::repositories::waves::confirm_pick;
+use crate::utils::*;
+
+#[derive(Clone)]
+pub struct TaskRepository {
+    pool: PgPool,
+}
+
+impl TaskRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// List tasks in queue order: most urgent first, then oldest
+    pub async fn list(&self, filter: TaskFilter, pagination: PaginationQuery) -> Result<PaginatedResponse<Task>> {
+        let (page, limit) = validate_pagination(&pagination);
+        let offset = calculate_offset(page, limit);
+        let mode = pagination.count.unwrap_or_default();
+
+        let total = match mode {
+            CountMode::Exact => sqlx::query_scalar!(
+                "SELECT COUNT(*) FROM warehouse.tasks
+                 WHERE ($1::INT IS NULL OR warehouse_id = $1)
+                   AND ($2::VARCHAR IS NULL OR task_type = $2)
+                   AND ($3::VARCHAR IS NULL OR status = $3)
+                   AND ($4::INT IS NULL OR assigned_to = $4)",
+                filter.warehouse_id,
+                filter.task_type,
+                filter.status,
+                filter.assigned_to
+            )
+            .fetch_one(&self.pool)
+            .await?,
+            CountMode::Estimate => Some(estimate_row_count(&self.pool, "warehouse.tasks").await?),
+            CountMode::Skip => None,
+        };
+
+        let rows = sqlx::query_as!(
+            Task,
+            "SELECT * FROM warehouse.tasks
+             WHERE ($1::INT IS NULL OR warehouse_id = $1)
+               AND ($2::VARCHAR IS NULL OR task_type = $2)
+               AND ($3::VARCHAR IS NULL OR status = $3)
+               AND ($4::INT IS NULL OR assigned_to = $4)
+             ORDER BY priority DESC, task_id
+             LIMIT $5 OFFSET $6",
+            filter.warehouse_id,
+            filter.task_type,
+            filter.status,
+            filter.assigned_to,
+            limit + 1,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PaginatedResponse::from_probe(rows, total, mode, page, limit))
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<Task>> {
+        let mut conn = self.pool.acquire().await?;
+        load_task(&mut conn, id, false).await
+    }
+
+    /// Queue a task. A putaway is checked against the hazmat rules of the zone it heads to.
+    pub async fn create(&self, task: CreateTask) -> Result<Task> {
+        let mut tx = self.pool.begin().await?;
+
+        if let (TASK_PUTAWAY, Some(item_id), Some(location_id)) =
+            (task.task_type.as_str(), task.item_id, task.to_location_id)
+        {
+            let zone_id =
+                sqlx::query_scalar!("SELECT zone_id FROM warehouse.locations WHERE location_id = $1", location_id)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .ok_or_else(|| violation(format!("location {} does not exist", location_id)))?;
+            ensure_storable(&mut tx, item_id, zone_id).await?;
+        }
+
+        let created = sqlx::query_as!(
+            Task,
+            "INSERT INTO warehouse.tasks (
+                 warehouse_id, task_type, priority, item_id, quantity, from_location_id, to_location_id,
+                 notes, due_at, created_by
+             ) VALUES ($1, $2, COALESCE($3, 50), $4, $5, $6, $7, $8, $9, $10)
+             RETURNING *",
+            task.warehouse_id,
+            task.task_type,
+            task.priority,
+            task.item_id,
+            task.quantity,
+            task.from_location_id,
+            task.to_location_id,
+            task.notes,
+            task.due_at,
+            acting_user() // created_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    /// Hand an open or assigned task to an operator rostered at the task's warehouse.
+    /// Returns None when the task does not exist.
+    pub async fn assign(&self, id: i32, assignment: AssignTask) -> Result<Option<Task>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(task) = load_task(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+
+        if task.status != TASK_OPEN && task.status != TASK_ASSIGNED {
+            return Err(violation(format!("cannot assign a {} task", describe(&task.status))));
+        }
+        ensure_rostered(&mut tx, task.warehouse_id, assignment.operator_id, false).await?;
+
+        let task = sqlx::query_as!(
+            Task,
+            "UPDATE warehouse.tasks SET status = $2, assigned_to = $3, assigned_at = NOW()
+             WHERE task_id = $1
+             RETURNING *",
+            id,
+            TASK_ASSIGNED,
+            assignment.operator_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(task))
+    }
+
+    /// Start work on a task. Open tasks can be claimed by any operator on duty, assigned
+    /// tasks only by their operator. Returns None when the task does not exist.
+    pub async fn claim(&self, id: i32, claim: ClaimTask) -> Result<Option<Task>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(task) = load_task(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+
+        let claimable = task.status == TASK_OPEN
+            || (task.status == TASK_ASSIGNED && task.assigned_to == Some(claim.operator_id));
+        if !claimable {
+            return Err(match task.assigned_to {
+                Some(operator) if task.status == TASK_ASSIGNED || task.status == TASK_IN_PROGRESS => {
+                    violation(format!("task {} belongs to operator {}", id, operator))
+                }
+                _ => violation(format!("cannot claim a {} task", describe(&task.status))),
+            });
+        }
+
+        ensure_rostered(&mut tx, task.warehouse_id, claim.operator_id, true).await?;
+
+        let task = start_task(&mut tx, id, claim.operator_id).await?;
+        tx.commit().await?;
+        Ok(Some(task))
+    }
+
+    /// Start the operator's next task: anything already assigned to them first, then the
+    /// most urgent open task. Tasks locked by a concurrent claim are skipped, so operators
+    /// asking at the same moment get different tasks. Returns None when the queue is empty.
+    pub async fn claim_next(&self, request: ClaimNextTask) -> Result<Option<Task>> {
+        let mut tx = self.pool.begin().await?;
+        ensure_rostered(&mut tx, request.warehouse_id, request.operator_id, true).await?;
+
+        let next = sqlx::query_scalar!(
+            "SELECT task_id FROM warehouse.tasks
+             WHERE warehouse_id = $1
+               AND (status = $3 OR (status = $4 AND assigned_to = $2))
+               AND ($5::VARCHAR[] IS NULL OR task_type = ANY($5))
+             ORDER BY (assigned_to IS NOT DISTINCT FROM $2) DESC, priority DESC, due_at NULLS LAST, task_id
+             LIMIT 1
+             FOR UPDATE SKIP LOCKED",
+            request.warehouse_id,
+            request.operator_id,
+            TASK_OPEN,
+            TASK_ASSIGNED,
+            request.task_types.as_deref()
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(task_id) = next else {
+            return Ok(None);
+        };
+
+        let task = start_task(&mut tx, task_id, request.operator_id).await?;
+        tx.commit().await?;
+        Ok(Some(task))
+    }
+
+    /// Finish a task in progress. Queue tasks for wave picks also confirm the pick.
+    /// Returns None when the task does not exist.
+    pub async fn complete(&self, id: i32, completion: CompleteTask) -> Result<Option<Task>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(task) = load_task(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+
+        if task.status != TASK_IN_PROGRESS {
+            return Err(violation(format!("cannot complete a {} task", describe(&task.status))));
+        }
+        if task.assigned_to != Some(completion.operator_id) {
+            return Err(violation(format!(
+                "task {} is being worked by operator {}",
+                id,
+                task.assigned_to.unwrap_or_default()
+            )));
+        }
+
+        let quantity_done = completion.quantity_done.or(task.quantity);
+        if let (Some(done), Some(asked)) = (quantity_done, task.quantity) {
+            if done > asked {
+                return Err(violation(format!("task {} asks for {}, cannot complete {}", id, asked, done)));
+            }
+        }
+
+        let task = sqlx::query_as!(
+            Task,
+            "UPDATE warehouse.tasks SET
+                 status = $2, quantity_done = $3, completed_at = NOW(), completed_by = $4,
+                 notes = COALESCE($5, notes)
+             WHERE task_id = $1
+             RETURNING *",
+            id,
+            TASK_COMPLETED,
+            quantity_done,
+            completion.operator_id,
+            completion.notes
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if task.reference_type.as_deref() == Some(TASK_REF_PICK) {
+            let pick_task_id = task.reference_id.expect("pick queue tasks carry the pick task id");
+            let wave_id = sqlx::query_scalar!("SELECT wave_id FROM warehouse.pick_tasks WHERE task_id = $1", pick_task_id)
+                .fetch_one(&mut *tx)
+                .await?;
+            confirm_pick(
+                &mut tx,
+                wave_id,
+                pick_task_id,
+                quantity_done.unwrap_or_default(),
+                Some(completion.operator_id),
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(Some(task))
+    }
+
+    /// Withdraw a task that has not been completed. Wave pick tasks are confirmed through
+    /// the wave and cannot be cancelled here. Returns None when the task does not exist.
+    pub async fn cancel(&self, id: i32) -> Result<Option<Task>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(task) = load_task(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+
+        if task.status == TASK_COMPLETED || task.status == TASK_CANCELLED {
+            return Err(violation(format!("cannot cancel a {} task", describe(&task.status))));
+        }
+        if task.reference_type.as_deref() == Some(TASK_REF_PICK) {
+            return Err(violation(format!("task {} picks for a wave and cannot be cancelled", id)));
+        }
+
+        let task = sqlx::query_as!(
+            Task,
+            "UPDATE warehouse.tasks SET status = $2 WHERE task_id = $1 RETURNING *",
+            id,
+            TASK_CANCELLED
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(task))
+    }
+
+    /// Outstanding work per warehouse and task type, for supervisors balancing labour
+    pub async fn queue_depth(&self, filter: QueueDepthFilter) -> Result<Vec<QueueDepth>> {
+        let depth = sqlx::query_as!(
+            QueueDepth,
+            r#"SELECT warehouse_id, task_type,
+                      COUNT(*) FILTER (WHERE status = $2) AS "open!",
+                      COUNT(*) FILTER (WHERE status = $3) AS "assigned!",
+                      COUNT(*) FILTER (WHERE status = $4) AS "in_progress!",
+                      MIN(created_at) FILTER (WHERE status IN ($2, $3)) AS oldest_waiting_at
+               FROM warehouse.tasks
+               WHERE ($1::INT IS NULL OR warehouse_id = $1)
+                 AND status IN ($2, $3, $4)
+               GROUP BY warehouse_id, task_type
+               ORDER BY warehouse_id, task_type"#,
+            filter.warehouse_id,
+            TASK_OPEN,
+            TASK_ASSIGNED,
+            TASK_IN_PROGRESS
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(depth)
+    }
+}
+
+async fn load_task(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<Task>> {
+    let task = if lock {
+        sqlx::query_as!(Task, "SELECT * FROM warehouse.tasks WHERE task_id = $1 FOR UPDATE", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    } else {
+        sqlx::query_as!(Task, "SELECT * FROM warehouse.tasks WHERE task_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    };
+
+    Ok(task)
+}
+
+async fn start_task(conn: &mut PgConnection, id: i32, operator_id: i32) -> Result<Task> {
+    let task = sqlx::query_as!(
+        Task,
+        "UPDATE warehouse.tasks SET
+             status = $2, assigned_to = $3, assigned_at = COALESCE(assigned_at, NOW()), started_at = NOW()
+         WHERE task_id = $1
+         RETURNING *",
+        id,
+        TASK_IN_PROGRESS,
+        operator_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(task)
+}
+
+fn describe(status: &str) -> String {
+    status.to_lowercase().replace('_', " ")
+}
@@ -0,0 +1,294 @@
+use crate::error::violation;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct TemplateRepository {
+    pool: PgPool,
+}
+
+impl TemplateRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self, filter: &PrintTemplateFilter) -> Result<Vec<PrintTemplate>> {
+        let templates = sqlx::query_as!(
+            PrintTemplate,
+            "SELECT * FROM warehouse.print_templates
+             WHERE ($1::VARCHAR IS NULL OR purpose = $1) AND ($2::VARCHAR IS NULL OR format = $2)
+               AND ($3::BOOLEAN IS NULL OR is_active = $3)
+             ORDER BY purpose, template_code",
+            filter.purpose,
+            filter.format,
+            filter.is_active
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(templates)
+    }
+
+    pub async fn get(&self, id: i32) -> Result<Option<PrintTemplateDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_detail(&mut conn, id, false).await
+    }
+
+    pub async fn code_exists(&self, code: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.print_templates WHERE template_code = $1)",
+            code
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(exists.unwrap_or(false))
+    }
+
+    /// Create a template with its body as version 1
+    pub async fn create(&self, template: CreatePrintTemplate) -> Result<PrintTemplateDetail> {
+        check_body(&template.format, &template.body)?;
+
+        let mut tx = self.pool.begin().await?;
+        let created = sqlx::query_as!(
+            PrintTemplate,
+            "INSERT INTO warehouse.print_templates (template_code, name, purpose, format)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *",
+            template.template_code,
+            template.name,
+            template.purpose,
+            template.format
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        insert_version(&mut tx, created.template_id, 1, &template.body, template.change_note.as_deref()).await?;
+
+        tx.commit().await?;
+        Ok(PrintTemplateDetail { template: created, body: template.body })
+    }
+
+    /// Rename, activate or deactivate a template, saving a changed body as its next
+    /// version. A template still assigned to a warehouse cannot be deactivated. Returns
+    /// None when there is no such template.
+    pub async fn update(&self, id: i32, update: UpdatePrintTemplate) -> Result<Option<PrintTemplateDetail>> {
+        let mut tx = self.pool.begin().await?;
+        // Locking the template numbers concurrent edits' versions one after the other
+        let Some(current) = load_detail(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let template = &current.template;
+
+        if update.is_active == Some(false) && template.is_active {
+            let warehouses = sqlx::query_scalar!(
+                "SELECT warehouse_id FROM warehouse.print_template_assignments
+                 WHERE template_id = $1
+                 ORDER BY warehouse_id",
+                id
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+            if !warehouses.is_empty() {
+                let warehouses: Vec<String> = warehouses.iter().map(i32::to_string).collect();
+                return Err(violation(format!(
+                    "cannot deactivate {}: it is assigned to warehouse {}",
+                    template.template_code,
+                    warehouses.join(", ")
+                )));
+            }
+        }
+
+        let mut version = template.current_version;
+        if let Some(body) = update.body.as_deref().filter(|body| *body != current.body) {
+            check_body(&template.format, body)?;
+            version += 1;
+            insert_version(&mut tx, id, version, body, update.change_note.as_deref()).await?;
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.print_templates SET
+                 name = COALESCE($2, name),
+                 is_active = COALESCE($3, is_active),
+                 current_version = $4,
+                 updated_at = NOW()
+             WHERE template_id = $1",
+            id,
+            update.name,
+            update.is_active,
+            version
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let updated = load_detail(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    /// Every version of a template, newest first
+    pub async fn versions(&self, id: i32) -> Result<Vec<PrintTemplateVersion>> {
+        let versions = sqlx::query_as!(
+            PrintTemplateVersion,
+            "SELECT * FROM warehouse.print_template_versions WHERE template_id = $1 ORDER BY version DESC",
+            id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(versions)
+    }
+
+    pub async fn version(&self, id: i32, version: i32) -> Result<Option<PrintTemplateVersion>> {
+        let version = sqlx::query_as!(
+            PrintTemplateVersion,
+            "SELECT * FROM warehouse.print_template_versions WHERE template_id = $1 AND version = $2",
+            id,
+            version
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(version)
+    }
+
+    /// Make an earlier version's body current again, as a new version so the history
+    /// stays linear. Returns None when the template has no such version.
+    pub async fn restore(&self, id: i32, version: i32) -> Result<Option<PrintTemplateDetail>> {
+        let Some(restored) = self.version(id, version).await? else {
+            return Ok(None);
+        };
+        let update = UpdatePrintTemplate {
+            body: Some(restored.body),
+            change_note: Some(format!("Restored version {}", version)),
+            ..Default::default()
+        };
+        self.update(id, update).await
+    }
+
+    /// The templates the warehouse prints with, by purpose
+    pub async fn assignments(&self, warehouse_id: i32) -> Result<Vec<PrintTemplateAssignment>> {
+        let assignments = sqlx::query_as!(
+            PrintTemplateAssignment,
+            r#"SELECT a.warehouse_id, a.purpose, a.template_id, t.template_code, t.format, a.pinned_version,
+                      COALESCE(a.pinned_version, t.current_version) AS "version!", a.assigned_at
+               FROM warehouse.print_template_assignments a
+               JOIN warehouse.print_templates t ON t.template_id = a.template_id
+               WHERE a.warehouse_id = $1
+               ORDER BY a.purpose"#,
+            warehouse_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(assignments)
+    }
+
+    /// Print the template's purpose in the warehouse with it, replacing the template
+    /// assigned to that purpose before. Returns None when there is no such template.
+    pub async fn assign(
+        &self,
+        warehouse_id: i32,
+        assignment: AssignPrintTemplate,
+    ) -> Result<Option<PrintTemplateAssignment>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(template) = load_template(&mut tx, assignment.template_id, true).await? else {
+            return Ok(None);
+        };
+        if !template.is_active {
+            return Err(violation(format!("cannot assign {}: it is inactive", template.template_code)));
+        }
+        if let Some(version) = assignment.pinned_version {
+            if version > template.current_version {
+                return Err(violation(format!("{} has no version {}", template.template_code, version)));
+            }
+        }
+
+        sqlx::query!(
+            "INSERT INTO warehouse.print_template_assignments (warehouse_id, purpose, template_id, pinned_version)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (warehouse_id, purpose) DO UPDATE SET
+                 template_id = EXCLUDED.template_id,
+                 pinned_version = EXCLUDED.pinned_version,
+                 assigned_at = NOW()",
+            warehouse_id,
+            template.purpose,
+            template.template_id,
+            assignment.pinned_version
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        let assigned = self.assignments(warehouse_id).await?.into_iter().find(|a| a.purpose == template.purpose);
+        Ok(assigned)
+    }
+
+    /// Returns false when the warehouse has no template assigned to the purpose
+    pub async fn unassign(&self, warehouse_id: i32, purpose: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM warehouse.print_template_assignments WHERE warehouse_id = $1 AND purpose = $2",
+            warehouse_id,
+            purpose
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// A ZPL label must be a whole label format, from `^XA` to `^XZ`
+fn check_body(format: &str, body: &str) -> Result<()> {
+    let body = body.trim();
+    if format == TEMPLATE_ZPL && !(body.starts_with("^XA") && body.ends_with("^XZ")) {
+        return Err(violation("a ZPL template must start with ^XA and end with ^XZ"));
+    }
+    Ok(())
+}
+
+async fn insert_version(
+    conn: &mut PgConnection,
+    template_id: i32,
+    version: i32,
+    body: &str,
+    change_note: Option<&str>,
+) -> Result<()> {
+    sqlx::query!(
+        "INSERT INTO warehouse.print_template_versions (template_id, version, body, change_note)
+         VALUES ($1, $2, $3, $4)",
+        template_id,
+        version,
+        body,
+        change_note
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+async fn load_template(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<PrintTemplate>> {
+    let template = if lock {
+        sqlx::query_as!(PrintTemplate, "SELECT * FROM warehouse.print_templates WHERE template_id = $1 FOR UPDATE", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    } else {
+        sqlx::query_as!(PrintTemplate, "SELECT * FROM warehouse.print_templates WHERE template_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    };
+    Ok(template)
+}
+
+async fn load_detail(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<PrintTemplateDetail>> {
+    let Some(template) = load_template(conn, id, lock).await? else {
+        return Ok(None);
+    };
+    let body = current_body(conn, &template).await?;
+    Ok(Some(PrintTemplateDetail { template, body }))
+}
+
+async fn current_body(conn: &mut PgConnection, template: &PrintTemplate) -> Result<String> {
+    let body = sqlx::query_scalar!(
+        "SELECT body FROM warehouse.print_template_versions WHERE template_id = $1 AND version = $2",
+        template.template_id,
+        template.current_version
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(body)
+}
@@ -0,0 +1,164 @@
+use anyhow::{Context, Result};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use warehouse_models::*;
+
+// Every tenant schema is built from the same migrations the shared schema runs
+use super::migrations::MIGRATOR;
+
+#[derive(Clone)]
+pub struct TenantRepository {
+    pool: PgPool,
+}
+
+impl TenantRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self) -> Result<Vec<Tenant>> {
+        let tenants = sqlx::query_as!(Tenant, "SELECT * FROM warehouse.tenants ORDER BY tenant_code")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(tenants)
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<Tenant>> {
+        let tenant = sqlx::query_as!(Tenant, "SELECT * FROM warehouse.tenants WHERE tenant_id = $1", id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(tenant)
+    }
+
+    /// Register the tenant and build its schema. A tenant whose provisioning failed earlier is
+    /// provisioned again; `None` if the code is taken by one that is active or in progress.
+    pub async fn provision(&self, tenant: CreateTenant) -> Result<Option<TenantProvisioned>> {
+        let registered = sqlx::query_as!(
+            Tenant,
+            "INSERT INTO warehouse.tenants (tenant_code, tenant_name, schema_name)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (tenant_code) DO UPDATE
+                SET tenant_name = EXCLUDED.tenant_name, status = 'PROVISIONING', last_error = NULL
+                WHERE tenants.status = 'FAILED'
+             RETURNING *",
+            tenant.tenant_code,
+            tenant.tenant_name,
+            tenant.schema_name()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(registered) = registered else {
+            return Ok(None);
+        };
+
+        let built = self.build_schema(&registered.schema_name, &tenant.warehouses).await;
+        let (migrations_applied, warehouses_seeded) = match built {
+            Ok(counts) => counts,
+            Err(e) => {
+                sqlx::query!(
+                    "UPDATE warehouse.tenants SET status = 'FAILED', last_error = $2 WHERE tenant_id = $1",
+                    registered.tenant_id,
+                    format!("{:#}", e)
+                )
+                .execute(&self.pool)
+                .await?;
+                return Err(e);
+            }
+        };
+
+        let schema_version = MIGRATOR.iter().map(|migration| migration.version).max();
+        let tenant = sqlx::query_as!(
+            Tenant,
+            "UPDATE warehouse.tenants
+             SET status = 'ACTIVE', schema_version = $2, provisioned_at = NOW()
+             WHERE tenant_id = $1
+             RETURNING *",
+            registered.tenant_id,
+            schema_version
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Some(TenantProvisioned {
+            search_path: tenant.schema_name.clone(),
+            connection_options: format!("-c search_path={}", tenant.schema_name),
+            tenant,
+            migrations_applied,
+            warehouses_seeded,
+        }))
+    }
+
+    /// Create the schema, run every migration into it, drop the sample rows the migrations
+    /// insert and seed the tenant's own warehouses. All or nothing: a failure leaves no schema.
+    async fn build_schema(&self, schema: &str, warehouses: &[CreateWarehouse]) -> Result<(usize, usize)> {
+        let mut tx = self.pool.begin().await?;
+        tx.execute(format!("CREATE SCHEMA \"{}\"", schema).as_str()).await?;
+
+        let mut applied = 0;
+        for migration in MIGRATOR.iter() {
+            tx.execute(into_schema(&migration.sql, schema).as_str())
+                .await
+                .with_context(|| format!("migration {} {}", migration.version, migration.description))?;
+            applied += 1;
+        }
+
+        clear_sample_data(&mut tx, schema).await?;
+
+        for warehouse in warehouses {
+            sqlx::query(&format!(
+                "INSERT INTO \"{}\".warehouses (warehouse_code, warehouse_name, city, state, country, timezone)
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+                schema
+            ))
+            .bind(&warehouse.warehouse_code)
+            .bind(&warehouse.warehouse_name)
+            .bind(&warehouse.city)
+            .bind(&warehouse.state)
+            .bind(warehouse.country.as_deref().unwrap_or("Indonesia"))
+            .bind(warehouse.timezone.as_deref().unwrap_or("Asia/Jakarta"))
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("seeding warehouse {}", warehouse.warehouse_code))?;
+        }
+
+        tx.commit().await?;
+        Ok((applied, warehouses.len()))
+    }
+}
+
+async fn clear_sample_data(tx: &mut Transaction<'_, Postgres>, schema: &str) -> Result<()> {
+    let tables: Vec<String> = sqlx::query_scalar("SELECT tablename::TEXT FROM pg_tables WHERE schemaname = $1")
+        .bind(schema)
+        .fetch_all(&mut **tx)
+        .await?;
+    if tables.is_empty() {
+        return Ok(());
+    }
+
+    let tables: Vec<String> = tables.iter().map(|table| format!("\"{}\".\"{}\"", schema, table)).collect();
+    tx.execute(format!("TRUNCATE {} RESTART IDENTITY CASCADE", tables.join(", ")).as_str()).await?;
+    Ok(())
+}
+
+/// Migration SQL rewritten to build `schema` instead of the shared `warehouse` schema.
+/// Only schema-qualified names are touched, not columns or literals that merely end in
+/// "warehouse". Settings the migrations read, like the audit actor, are named outside the
+/// `warehouse.` prefix so they are left alone.
+fn into_schema(sql: &str, schema: &str) -> String {
+    let sql = sql.replace("CREATE SCHEMA IF NOT EXISTS warehouse;", "");
+    let mut rewritten = String::with_capacity(sql.len());
+    let mut rest = sql.as_str();
+    while let Some(at) = rest.find("warehouse.") {
+        let qualified = !rest[..at].ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '"');
+        rewritten.push_str(&rest[..at]);
+        if qualified {
+            rewritten.push_str(&format!("\"{}\".", schema));
+        } else {
+            rewritten.push_str("warehouse.");
+        }
+        rest = &rest[at + "warehouse.".len()..];
+    }
+    rewritten.push_str(rest);
+    rewritten
+}
@@ -0,0 +1,165 @@
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::*;
+use crate::repositories::ledger::post_delivery;
+
+#[derive(Clone)]
+pub struct TrackingRepository {
+    pool: PgPool,
+}
+
+impl TrackingRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Find the shipment a carrier's tracking number belongs to
+    pub async fn find_shipment(&self, carrier_code: &str, tracking_number: &str) -> Result<Option<Shipment>> {
+        let shipment = sqlx::query_as!(
+            Shipment,
+            "SELECT * FROM warehouse.shipments
+             WHERE UPPER(carrier_code) = UPPER($1) AND tracking_number = $2
+             ORDER BY shipment_id DESC
+             LIMIT 1",
+            carrier_code,
+            tracking_number
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(shipment)
+    }
+
+    /// Store tracking events, skipping ones already recorded, and move the shipment to the
+    /// status of its latest event. Returns only the events that were new.
+    pub async fn record(
+        &self,
+        shipment_id: i32,
+        events: Vec<NewTrackingEvent>,
+        source: &str,
+    ) -> Result<Vec<TrackingEvent>> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            "SELECT shipment_id FROM warehouse.shipments WHERE shipment_id = $1 FOR UPDATE",
+            shipment_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let recorded = record_events(&mut tx, shipment_id, events, source).await?;
+
+        tx.commit().await?;
+        Ok(recorded)
+    }
+
+    pub async fn timeline(&self, shipment_id: i32) -> Result<Option<TrackingTimeline>> {
+        let Some(shipment) = sqlx::query_as!(
+            Shipment,
+            "SELECT * FROM warehouse.shipments WHERE shipment_id = $1",
+            shipment_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let events = sqlx::query_as!(
+            TrackingEvent,
+            "SELECT * FROM warehouse.shipment_tracking_events
+             WHERE shipment_id = $1
+             ORDER BY occurred_at, event_id",
+            shipment_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(Some(TrackingTimeline {
+            shipment_id,
+            carrier_code: shipment.carrier_code,
+            tracking_number: shipment.tracking_number,
+            status: shipment.status,
+            delivered_at: shipment.delivered_at,
+            events,
+        }))
+    }
+
+    /// Shipments still moving that a carrier can be asked about, least recently shipped first.
+    /// Shipments older than `max_age_days` are left alone.
+    pub async fn pollable(&self, max_age_days: i32, limit: i64) -> Result<Vec<Shipment>> {
+        let shipments = sqlx::query_as!(
+            Shipment,
+            "SELECT * FROM warehouse.shipments
+             WHERE carrier_code IS NOT NULL
+               AND tracking_number IS NOT NULL
+               AND status <> ALL($1)
+               AND shipped_at > NOW() - make_interval(days => $2)
+             ORDER BY shipped_at
+             LIMIT $3",
+            &[
+                TrackingStatus::Delivered.as_str().to_string(),
+                TrackingStatus::ReturnedToSender.as_str().to_string(),
+            ],
+            max_age_days,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(shipments)
+    }
+}
+
+/// [`TrackingRepository::record`] within the caller's transaction, which holds the
+/// shipment locked
+pub(crate) async fn record_events(
+    conn: &mut PgConnection,
+    shipment_id: i32,
+    events: Vec<NewTrackingEvent>,
+    source: &str,
+) -> Result<Vec<TrackingEvent>> {
+    let mut recorded = Vec::new();
+    for event in events {
+        let inserted = sqlx::query_as!(
+            TrackingEvent,
+            "INSERT INTO warehouse.shipment_tracking_events (
+                 shipment_id, status, description, location, occurred_at, source
+             ) VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (shipment_id, status, occurred_at) DO NOTHING
+             RETURNING *",
+            shipment_id,
+            event.status.as_str(),
+            event.description,
+            event.location,
+            event.occurred_at,
+            source
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+        recorded.extend(inserted);
+    }
+
+    if !recorded.is_empty() {
+        sqlx::query!(
+            "UPDATE warehouse.shipments s SET
+                 status = latest.status,
+                 delivered_at = CASE WHEN latest.status = $2 THEN latest.occurred_at END
+             FROM (
+                 SELECT status, occurred_at FROM warehouse.shipment_tracking_events
+                 WHERE shipment_id = $1
+                 ORDER BY occurred_at DESC, event_id DESC
+                 LIMIT 1
+             ) latest
+             WHERE s.shipment_id = $1",
+            shipment_id,
+            TrackingStatus::Delivered.as_str()
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        post_delivery(conn, shipment_id).await?;
+    }
+
+    Ok(recorded)
+}
@@ -0,0 +1,288 @@
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::movements::{lock_stock, post_arrival, post_movement};
+use crate::repositories::sagas::load_log;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct TransferRepository {
+    pool: PgPool,
+}
+
+impl TransferRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self, filter: TransferFilter) -> Result<Vec<StockTransfer>> {
+        let transfers = sqlx::query_as!(
+            StockTransfer,
+            "SELECT * FROM warehouse.stock_transfers
+             WHERE ($1::VARCHAR IS NULL OR status = $1)
+               AND ($2::INT IS NULL OR source_warehouse_id = $2 OR destination_warehouse_id = $2)
+             ORDER BY transfer_id DESC",
+            filter.status,
+            filter.warehouse_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(transfers)
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<TransferDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_transfer(&mut conn, id, false).await
+    }
+
+    pub async fn create(&self, transfer: CreateTransfer) -> Result<TransferDetail> {
+        let mut tx = self.pool.begin().await?;
+
+        let transfer_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.stock_transfers (
+                 transfer_id, transfer_number, source_warehouse_id, destination_warehouse_id, carrier_code,
+                 service_level, notes, created_by
+             )
+             SELECT next_id, 'TRF-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4, $5, $6
+             FROM (SELECT nextval('warehouse.stock_transfers_transfer_id_seq')::INT AS next_id) seq
+             RETURNING transfer_id",
+            transfer.source_warehouse_id,
+            transfer.destination_warehouse_id,
+            transfer.carrier_code,
+            transfer.service_level,
+            transfer.notes,
+            acting_user() // created_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for (line_number, line) in (1..).zip(transfer.lines) {
+            sqlx::query!(
+                "INSERT INTO warehouse.stock_transfer_lines (transfer_id, line_number, item_id, quantity)
+                 VALUES ($1, $2, $3, $4)",
+                transfer_id,
+                line_number,
+                line.item_id,
+                line.quantity
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let detail = load_transfer(&mut tx, transfer_id, false).await?.expect("transfer inserted above");
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Issue a planned transfer's goods from the source warehouse into transit. Returns
+    /// None when the transfer does not exist.
+    pub async fn issue(&self, id: i32) -> Result<Option<StockTransfer>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_transfer(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let transfer = &detail.transfer;
+        ensure_status(transfer, TRANSFER_PLANNED, "dispatch")?;
+
+        let item_ids: Vec<i32> = detail.lines.iter().map(|line| line.item_id).collect();
+        lock_stock(&mut tx, transfer.source_warehouse_id, &item_ids).await?;
+        for line in &detail.lines {
+            post_movement(
+                &mut tx,
+                transfer_movement(transfer, line, MOVEMENT_TRANSFER_OUT, transfer.source_warehouse_id, -line.quantity),
+            )
+            .await?;
+        }
+
+        let issued = sqlx::query_as!(
+            StockTransfer,
+            "UPDATE warehouse.stock_transfers SET status = $2, dispatched_at = NOW() WHERE transfer_id = $1
+             RETURNING *",
+            id,
+            TRANSFER_IN_TRANSIT
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(issued))
+    }
+
+    /// Record the carrier label bought for a transfer, or clear a voided one
+    pub async fn set_label(&self, id: i32, label: Option<&ShipmentLabel>) -> Result<()> {
+        sqlx::query!(
+            "UPDATE warehouse.stock_transfers SET label_id = $2, tracking_number = $3 WHERE transfer_id = $1",
+            id,
+            label.map(|l| l.label_id.clone()),
+            label.map(|l| l.tracking_number.clone())
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Book an in-transit transfer's goods into the destination warehouse. Returns None
+    /// when the transfer does not exist.
+    pub async fn receive(&self, id: i32) -> Result<Option<StockTransfer>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_transfer(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let transfer = &detail.transfer;
+        ensure_status(transfer, TRANSFER_IN_TRANSIT, "receive")?;
+
+        let active = sqlx::query_scalar!(
+            "SELECT is_active FROM warehouse.warehouses WHERE warehouse_id = $1",
+            transfer.destination_warehouse_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if active == Some(false) {
+            return Err(violation(format!("warehouse {} is not active", transfer.destination_warehouse_id)));
+        }
+
+        let item_ids: Vec<i32> = detail.lines.iter().map(|line| line.item_id).collect();
+        lock_stock(&mut tx, transfer.destination_warehouse_id, &item_ids).await?;
+        for line in &detail.lines {
+            post_arrival(
+                &mut tx,
+                transfer_movement(
+                    transfer,
+                    line,
+                    MOVEMENT_TRANSFER_IN,
+                    transfer.destination_warehouse_id,
+                    line.quantity,
+                ),
+                transfer.source_warehouse_id,
+            )
+            .await?;
+        }
+
+        let received = sqlx::query_as!(
+            StockTransfer,
+            "UPDATE warehouse.stock_transfers SET status = $2, received_at = NOW() WHERE transfer_id = $1
+             RETURNING *",
+            id,
+            TRANSFER_RECEIVED
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(received))
+    }
+
+    /// Put an in-transit transfer's goods back into the source warehouse. The transfer
+    /// stays in transit until the rest of its saga is rolled back too.
+    pub async fn return_to_source(&self, id: i32) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let detail = load_transfer(&mut tx, id, true)
+            .await?
+            .ok_or_else(|| violation(format!("transfer {} does not exist", id)))?;
+        let transfer = &detail.transfer;
+        ensure_status(transfer, TRANSFER_IN_TRANSIT, "return")?;
+
+        let item_ids: Vec<i32> = detail.lines.iter().map(|line| line.item_id).collect();
+        lock_stock(&mut tx, transfer.source_warehouse_id, &item_ids).await?;
+        for line in &detail.lines {
+            post_movement(
+                &mut tx,
+                transfer_movement(
+                    transfer,
+                    line,
+                    MOVEMENT_TRANSFER_RETURN,
+                    transfer.source_warehouse_id,
+                    line.quantity,
+                ),
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Close a transfer whose saga was rolled back
+    pub async fn mark_compensated(&self, id: i32, reason: &str) -> Result<StockTransfer> {
+        let transfer = sqlx::query_as!(
+            StockTransfer,
+            "UPDATE warehouse.stock_transfers SET status = $2, failure_reason = $3, compensated_at = NOW()
+             WHERE transfer_id = $1
+             RETURNING *",
+            id,
+            TRANSFER_COMPENSATED,
+            reason
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(transfer)
+    }
+}
+
+fn ensure_status(transfer: &StockTransfer, status: &str, action: &str) -> Result<()> {
+    if transfer.status != status {
+        return Err(violation(format!(
+            "cannot {} {}: it is {}",
+            action,
+            transfer.transfer_number,
+            transfer.status.to_lowercase()
+        )));
+    }
+    Ok(())
+}
+
+fn transfer_movement(
+    transfer: &StockTransfer,
+    line: &StockTransferLine,
+    movement_type: &str,
+    warehouse_id: i32,
+    quantity: Decimal,
+) -> NewMovement {
+    NewMovement {
+        item_id: line.item_id,
+        warehouse_id,
+        movement_type: movement_type.to_string(),
+        quantity,
+        catch_weight_kg: None,
+        lot_number: None,
+        unit_cost: None,
+        stock_status: STOCK_AVAILABLE,
+        consignor_id: None,
+        reference_type: Some(SAGA_TRANSFER.to_string()),
+        reference_number: Some(transfer.transfer_number.clone()),
+        notes: None,
+        created_by: acting_user(),
+    }
+}
+
+async fn load_transfer(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<TransferDetail>> {
+    let transfer = if lock {
+        sqlx::query_as!(StockTransfer, "SELECT * FROM warehouse.stock_transfers WHERE transfer_id = $1 FOR UPDATE", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    } else {
+        sqlx::query_as!(StockTransfer, "SELECT * FROM warehouse.stock_transfers WHERE transfer_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    };
+
+    let Some(transfer) = transfer else {
+        return Ok(None);
+    };
+
+    let lines = sqlx::query_as!(
+        StockTransferLine,
+        "SELECT * FROM warehouse.stock_transfer_lines WHERE transfer_id = $1 ORDER BY line_number",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let steps = load_log(conn, SAGA_TRANSFER, id).await?;
+
+    Ok(Some(TransferDetail { transfer, lines, steps }))
+}
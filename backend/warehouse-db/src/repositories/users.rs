@@ -0,0 +1,185 @@
+use crate::repositories::refresh_tokens::RefreshTokenRepository;
+use crate::store::UserStore;
+use anyhow::Result;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use async_trait::async_trait;
+use sqlx::PgPool;
+use std::sync::LazyLock;
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct UserRepository {
+    pool: PgPool,
+}
+
+impl UserRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self, filter: &UserFilter) -> Result<Vec<User>> {
+        let users = sqlx::query_as!(
+            User,
+            "SELECT user_id, username, email, full_name, role, is_active, created_at, updated_at
+             FROM warehouse.users
+             WHERE ($1::BOOLEAN IS NULL OR is_active = $1) AND ($2::VARCHAR IS NULL OR role = $2)
+             ORDER BY username",
+            filter.is_active,
+            filter.role
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(users)
+    }
+
+    pub async fn username_exists(&self, username: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM warehouse.users WHERE username = $1)", username)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(exists.unwrap_or(false))
+    }
+
+    pub async fn email_exists(&self, email: &str, exclude_id: Option<i32>) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.users WHERE email = $1 AND user_id IS DISTINCT FROM $2)",
+            email,
+            exclude_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(exists.unwrap_or(false))
+    }
+
+    pub async fn create(&self, user: CreateUser) -> Result<User> {
+        let password_hash = hash_password(user.password.clone()).await?;
+        let created = sqlx::query_as!(
+            User,
+            "INSERT INTO warehouse.users (username, email, full_name, password_hash, role) VALUES ($1, $2, $3, $4, $5)
+             RETURNING user_id, username, email, full_name, role, is_active, created_at, updated_at",
+            user.username,
+            user.email,
+            user.full_name,
+            password_hash,
+            user.role.as_deref().unwrap_or(ROLE_VIEWER)
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(created)
+    }
+
+    /// Change what is given; a new password is hashed afresh. A new password or
+    /// deactivation signs the user out everywhere. Returns None when there is no such user.
+    pub async fn update(&self, id: i32, changes: UpdateUser) -> Result<Option<User>> {
+        let password_hash = match changes.password.clone() {
+            Some(password) => Some(hash_password(password).await?),
+            None => None,
+        };
+        let signs_out = password_hash.is_some() || changes.is_active == Some(false);
+        let updated = sqlx::query_as!(
+            User,
+            "UPDATE warehouse.users SET
+                 email = COALESCE($2, email),
+                 full_name = COALESCE($3, full_name),
+                 password_hash = COALESCE($4, password_hash),
+                 is_active = COALESCE($5, is_active),
+                 role = COALESCE($6, role),
+                 updated_at = NOW()
+             WHERE user_id = $1
+             RETURNING user_id, username, email, full_name, role, is_active, created_at, updated_at",
+            id,
+            changes.email,
+            changes.full_name,
+            password_hash,
+            changes.is_active,
+            changes.role
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if updated.is_some() && signs_out {
+            RefreshTokenRepository::new(self.pool.clone()).revoke_for_user(id).await?;
+        }
+        Ok(updated)
+    }
+
+    /// Deactivate the user and sign them out everywhere; their rows stay so audit columns
+    /// still name them. Returns false when there is no such active user.
+    pub async fn deactivate(&self, id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE warehouse.users SET is_active = FALSE, updated_at = NOW() WHERE user_id = $1 AND is_active",
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        RefreshTokenRepository::new(self.pool.clone()).revoke_for_user(id).await?;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl UserStore for UserRepository {
+    /// The active user with this username and password, or None. An unknown username is
+    /// checked against a stand-in hash, so it takes as long to refuse as a wrong password.
+    async fn authenticate(&self, username: &str, password: &str) -> Result<Option<User>> {
+        let row = sqlx::query!(
+            "SELECT user_id, password_hash FROM warehouse.users WHERE username = $1 AND is_active",
+            username
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(row) = row else {
+            verify_password(password.to_string(), UNKNOWN_USER_HASH.clone()).await?;
+            return Ok(None);
+        };
+        if !verify_password(password.to_string(), row.password_hash).await? {
+            return Ok(None);
+        }
+        self.get(row.user_id).await
+    }
+
+    async fn get(&self, id: i32) -> Result<Option<User>> {
+        let user = sqlx::query_as!(
+            User,
+            "SELECT user_id, username, email, full_name, role, is_active, created_at, updated_at
+             FROM warehouse.users WHERE user_id = $1",
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(user)
+    }
+}
+
+/// What passwords given for unknown usernames are verified against
+static UNKNOWN_USER_HASH: LazyLock<String> =
+    LazyLock::new(|| argon2_hash("not the password of any user").expect("hashing a constant password"));
+
+/// Argon2id hash of the password with a fresh salt, as a PHC string. Hashing takes tens of
+/// milliseconds of CPU, so it runs on the blocking pool rather than a runtime worker.
+async fn hash_password(password: String) -> Result<String> {
+    tokio::task::spawn_blocking(move || argon2_hash(&password)).await?
+}
+
+/// Whether the password matches the stored hash; a hash that does not parse, like the
+/// system user's, matches nothing. Runs on the blocking pool, like hashing.
+async fn verify_password(password: String, password_hash: String) -> Result<bool> {
+    let matches = tokio::task::spawn_blocking(move || {
+        PasswordHash::new(&password_hash)
+            .is_ok_and(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+    })
+    .await?;
+    Ok(matches)
+}
+
+fn argon2_hash(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow::anyhow!("could not hash password: {}", e))?;
+    Ok(hash.to_string())
+}
@@ -0,0 +1,164 @@
+use crate::error::violation;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct ViewRepository {
+    pool: PgPool,
+}
+
+impl ViewRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// The user's views by resource and name
+    pub async fn list(&self, owner: &str, resource: Option<&str>) -> Result<Vec<SavedView>> {
+        let views = sqlx::query_as!(
+            SavedView,
+            "SELECT * FROM warehouse.saved_views
+             WHERE owner = $1 AND ($2::VARCHAR IS NULL OR resource = $2)
+             ORDER BY resource, view_name",
+            owner,
+            resource
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(views)
+    }
+
+    /// Returns None when the view does not exist or is another user's
+    pub async fn get(&self, owner: &str, id: i32) -> Result<Option<SavedViewDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_view(&mut conn, owner, id).await
+    }
+
+    pub async fn name_exists(&self, owner: &str, resource: &str, view_name: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM warehouse.saved_views WHERE owner = $1 AND resource = $2 AND view_name = $3)",
+            owner,
+            resource,
+            view_name
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    pub async fn create(&self, owner: &str, view: CreateSavedView) -> Result<SavedViewDetail> {
+        let mut tx = self.pool.begin().await?;
+
+        let view_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.saved_views (owner, view_name, resource, sort_by, sort_order, columns)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING view_id",
+            owner,
+            view.view_name,
+            view.resource,
+            view.sort_by,
+            view.sort_order,
+            &view.columns
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        set_filters(&mut tx, view_id, &view.filters).await?;
+
+        let detail = load_view(&mut tx, owner, view_id).await?.expect("view inserted above");
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Returns None when the view does not exist or is another user's
+    pub async fn update(&self, owner: &str, id: i32, changes: UpdateSavedView) -> Result<Option<SavedViewDetail>> {
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query!(
+            "UPDATE warehouse.saved_views SET
+                 view_name = COALESCE($3, view_name),
+                 sort_by = COALESCE($4, sort_by),
+                 sort_order = COALESCE($5, sort_order),
+                 columns = COALESCE($6, columns),
+                 updated_at = NOW()
+             WHERE view_id = $1 AND owner = $2",
+            id,
+            owner,
+            changes.view_name,
+            changes.sort_by,
+            changes.sort_order,
+            changes.columns.as_deref()
+        )
+        .execute(&mut *tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        if let Some(filters) = &changes.filters {
+            sqlx::query!("DELETE FROM warehouse.saved_view_filters WHERE view_id = $1", id).execute(&mut *tx).await?;
+            set_filters(&mut tx, id, filters).await?;
+        }
+
+        let detail = load_view(&mut tx, owner, id).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    pub async fn delete(&self, owner: &str, id: i32) -> Result<bool> {
+        let deleted = sqlx::query!("DELETE FROM warehouse.saved_views WHERE view_id = $1 AND owner = $2", id, owner)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(deleted.rows_affected() > 0)
+    }
+}
+
+async fn set_filters(conn: &mut PgConnection, view_id: i32, filters: &[ViewFilter]) -> Result<()> {
+    for (index, filter) in filters.iter().enumerate() {
+        if filters[..index].iter().any(|other| other.name == filter.name) {
+            return Err(violation(format!("filter {} is given more than once", filter.name)));
+        }
+    }
+
+    let names: Vec<String> = filters.iter().map(|filter| filter.name.clone()).collect();
+    let values: Vec<String> = filters.iter().map(|filter| filter.value.clone()).collect();
+    sqlx::query!(
+        "INSERT INTO warehouse.saved_view_filters (view_id, name, value)
+         SELECT $1, * FROM UNNEST($2::VARCHAR[], $3::VARCHAR[])",
+        view_id,
+        &names,
+        &values
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn load_view(conn: &mut PgConnection, owner: &str, id: i32) -> Result<Option<SavedViewDetail>> {
+    let Some(view) = sqlx::query_as!(
+        SavedView,
+        "SELECT * FROM warehouse.saved_views WHERE view_id = $1 AND owner = $2",
+        id,
+        owner
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let filters = sqlx::query_as!(
+        ViewFilter,
+        "SELECT name, value FROM warehouse.saved_view_filters WHERE view_id = $1 ORDER BY name",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(SavedViewDetail { view, filters }))
+}
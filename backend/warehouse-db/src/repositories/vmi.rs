@@ -0,0 +1,303 @@
+use crate::error::violation;
+use crate::utils::*;
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::chrono::{DateTime, Utc};
+use warehouse_models::*;
+
+#[derive(Clone)]
+pub struct VmiRepository {
+    pool: PgPool,
+}
+
+impl VmiRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Feeds by supplier code
+    pub async fn list(&self) -> Result<Vec<VmiFeed>> {
+        let feeds = sqlx::query_as!(VmiFeed, "SELECT * FROM warehouse.vmi_feeds ORDER BY supplier_code")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(feeds)
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<VmiFeedDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_feed(&mut conn, id).await
+    }
+
+    pub async fn supplier_code_exists(&self, code: &str) -> Result<bool> {
+        let exists =
+            sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM warehouse.vmi_feeds WHERE supplier_code = $1)", code)
+                .fetch_one(&self.pool)
+                .await?
+                .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    /// Set up a feed; the first delivery is due right away
+    pub async fn create(&self, feed: CreateVmiFeed) -> Result<VmiFeedDetail> {
+        let mut tx = self.pool.begin().await?;
+
+        let feed_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.vmi_feeds (
+                 supplier_code, supplier_name, transport, destination, api_key, frequency_minutes, warehouse_id
+             ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING feed_id",
+            feed.supplier_code,
+            feed.supplier_name,
+            feed.transport,
+            feed.destination,
+            feed.api_key,
+            feed.frequency_minutes,
+            feed.warehouse_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        set_items(&mut tx, feed_id, &feed.item_ids).await?;
+
+        let detail = load_feed(&mut tx, feed_id).await?.expect("feed inserted above");
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Returns None when the feed does not exist
+    pub async fn update(&self, id: i32, changes: UpdateVmiFeed) -> Result<Option<VmiFeedDetail>> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(feed) =
+            sqlx::query_as!(VmiFeed, "SELECT * FROM warehouse.vmi_feeds WHERE feed_id = $1 FOR UPDATE", id)
+                .fetch_optional(&mut *tx)
+                .await?
+        else {
+            return Ok(None);
+        };
+
+        if let Some(destination) = &changes.destination {
+            if !vmi_destination_valid(&feed.transport, destination) {
+                return Err(violation(format!("{} is not a valid {} destination", destination, feed.transport)));
+            }
+        }
+
+        // A new frequency takes effect from the last delivery rather than waiting out the old one
+        sqlx::query!(
+            "UPDATE warehouse.vmi_feeds SET
+                 supplier_name = COALESCE($2, supplier_name),
+                 destination = COALESCE($3, destination),
+                 api_key = COALESCE($4, api_key),
+                 frequency_minutes = COALESCE($5, frequency_minutes),
+                 is_active = COALESCE($6, is_active),
+                 next_run_at = CASE WHEN $5::INT IS NULL THEN next_run_at
+                                    ELSE COALESCE(last_sent_at + make_interval(mins => $5), NOW()) END,
+                 updated_at = NOW()
+             WHERE feed_id = $1",
+            id,
+            changes.supplier_name,
+            changes.destination,
+            changes.api_key,
+            changes.frequency_minutes,
+            changes.is_active
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if let Some(item_ids) = &changes.item_ids {
+            sqlx::query!("DELETE FROM warehouse.vmi_feed_items WHERE feed_id = $1", id).execute(&mut *tx).await?;
+            set_items(&mut tx, id, item_ids).await?;
+        }
+
+        let detail = load_feed(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Take the active feeds due at `now` and push their next run out by their frequency,
+    /// so a feed is picked up by one scheduler even with several servers running
+    pub async fn claim_due(&self, now: DateTime<Utc>) -> Result<Vec<VmiFeed>> {
+        let feeds = sqlx::query_as!(
+            VmiFeed,
+            "UPDATE warehouse.vmi_feeds SET next_run_at = $1 + make_interval(mins => frequency_minutes)
+             WHERE feed_id IN (
+                 SELECT feed_id FROM warehouse.vmi_feeds
+                 WHERE is_active AND next_run_at <= $1
+                 ORDER BY next_run_at
+                 FOR UPDATE SKIP LOCKED
+             )
+             RETURNING *",
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(feeds)
+    }
+
+    /// Stock of the feed's items in its warehouses, with consumption since the last
+    /// delivered feed. Consumption counts issues of available stock; transfers of
+    /// ownership between consignors and us are not consumption.
+    pub async fn snapshot(&self, feed: &VmiFeed, period_end: DateTime<Utc>) -> Result<VmiSnapshot> {
+        let period_start = feed.last_sent_at.unwrap_or(feed.created_at);
+
+        let lines = sqlx::query_as!(
+            VmiFeedLine,
+            r#"WITH stock AS (
+                   SELECT item_id, warehouse_id,
+                          SUM(quantity_on_hand) AS on_hand,
+                          SUM(quantity_reserved) AS reserved,
+                          SUM(quantity_available) AS available
+                   FROM warehouse.stock_inventory
+                   GROUP BY item_id, warehouse_id
+               ), consumed AS (
+                   SELECT item_id, warehouse_id, -SUM(quantity) AS quantity
+                   FROM warehouse.stock_movements
+                   WHERE quantity < 0 AND stock_status = $5 AND movement_type <> $6
+                     AND movement_date > $3 AND movement_date <= $4
+                   GROUP BY item_id, warehouse_id
+               )
+               SELECT i.item_id, i.item_code, w.warehouse_id, w.warehouse_code,
+                      COALESCE(s.on_hand, 0) AS "quantity_on_hand!",
+                      COALESCE(s.reserved, 0) AS "quantity_reserved!",
+                      COALESCE(s.available, 0) AS "quantity_available!",
+                      COALESCE(c.quantity, 0) AS "quantity_consumed!"
+               FROM warehouse.vmi_feed_items fi
+               JOIN warehouse.items i ON i.item_id = fi.item_id
+               CROSS JOIN warehouse.warehouses w
+               LEFT JOIN stock s ON s.item_id = i.item_id AND s.warehouse_id = w.warehouse_id
+               LEFT JOIN consumed c ON c.item_id = i.item_id AND c.warehouse_id = w.warehouse_id
+               WHERE fi.feed_id = $1
+                 AND (w.warehouse_id = $2 OR ($2::INT IS NULL AND (s.item_id IS NOT NULL OR c.item_id IS NOT NULL)))
+               ORDER BY i.item_code, w.warehouse_code"#,
+            feed.feed_id,
+            feed.warehouse_id,
+            period_start,
+            period_end,
+            STOCK_AVAILABLE,
+            MOVEMENT_CONSIGNMENT_CONSUME
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(VmiSnapshot { supplier_code: feed.supplier_code.clone(), period_start, period_end, lines })
+    }
+
+    /// Record a delivery attempt. A delivered feed moves the consumption period on; a
+    /// failed one is retried soon and its period is covered by the next delivery.
+    pub async fn record_run(
+        &self,
+        feed: &VmiFeed,
+        snapshot: &VmiSnapshot,
+        delivered: std::result::Result<Option<String>, String>,
+    ) -> Result<VmiFeedRun> {
+        let mut tx = self.pool.begin().await?;
+
+        let (status, file_name, error) = match delivered {
+            Ok(file_name) => (VMI_RUN_SENT, file_name, None),
+            Err(error) => (VMI_RUN_FAILED, None, Some(error)),
+        };
+
+        let run = sqlx::query_as!(
+            VmiFeedRun,
+            "INSERT INTO warehouse.vmi_feed_runs (feed_id, period_start, period_end, status, line_count, file_name, error)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING *",
+            feed.feed_id,
+            snapshot.period_start,
+            snapshot.period_end,
+            status,
+            snapshot.lines.len() as i32,
+            file_name,
+            error
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if status == VMI_RUN_SENT {
+            sqlx::query!(
+                "UPDATE warehouse.vmi_feeds SET last_sent_at = GREATEST(last_sent_at, $2) WHERE feed_id = $1",
+                feed.feed_id,
+                snapshot.period_end
+            )
+            .execute(&mut *tx)
+            .await?;
+        } else {
+            sqlx::query!(
+                "UPDATE warehouse.vmi_feeds SET
+                     next_run_at = LEAST(next_run_at, NOW() + make_interval(mins => LEAST(frequency_minutes, $2)))
+                 WHERE feed_id = $1",
+                feed.feed_id,
+                VMI_RETRY_MINUTES
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(run)
+    }
+
+    /// Delivery attempts of a feed, newest first, paged by `run_id`
+    pub async fn runs(&self, feed_id: i32, keyset: KeysetQuery) -> Result<KeysetPage<VmiFeedRun>> {
+        let (after_id, limit) = validate_keyset(&keyset);
+
+        let rows = sqlx::query_as!(
+            VmiFeedRun,
+            "SELECT * FROM warehouse.vmi_feed_runs
+             WHERE feed_id = $1 AND ($2::BIGINT IS NULL OR run_id < $2)
+             ORDER BY run_id DESC
+             LIMIT $3",
+            feed_id,
+            after_id,
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(KeysetPage::from_probe(rows, limit, |run| run.run_id))
+    }
+}
+
+async fn set_items(conn: &mut PgConnection, feed_id: i32, item_ids: &[i32]) -> Result<()> {
+    let unknown_items = sqlx::query_scalar!(
+        "SELECT id AS \"id!\" FROM UNNEST($1::INT[]) id
+         EXCEPT SELECT item_id FROM warehouse.items",
+        item_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    if let Some(item_id) = unknown_items.first() {
+        return Err(violation(format!("item {} does not exist", item_id)));
+    }
+
+    sqlx::query!(
+        "INSERT INTO warehouse.vmi_feed_items (feed_id, item_id)
+         SELECT $1, id FROM UNNEST($2::INT[]) id
+         ON CONFLICT DO NOTHING",
+        feed_id,
+        item_ids
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn load_feed(conn: &mut PgConnection, id: i32) -> Result<Option<VmiFeedDetail>> {
+    let Some(feed) = sqlx::query_as!(VmiFeed, "SELECT * FROM warehouse.vmi_feeds WHERE feed_id = $1", id)
+        .fetch_optional(&mut *conn)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let item_ids =
+        sqlx::query_scalar!("SELECT item_id FROM warehouse.vmi_feed_items WHERE feed_id = $1 ORDER BY item_id", id)
+            .fetch_all(&mut *conn)
+            .await?;
+
+    Ok(Some(VmiFeedDetail { feed, item_ids }))
+}
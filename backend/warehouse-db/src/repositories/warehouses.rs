@@ -1,6 +1,9 @@
 use anyhow::Result;
+use async_trait::async_trait;
 use sqlx::PgPool;
 use warehouse_models::*;
+use crate::error::violation;
+use crate::store::*;
 use crate::utils::*;
 
 #[derive(Clone)]
@@ -12,24 +15,36 @@ impl WarehouseRepository {
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
     }
+}
 
-    pub async fn list(&self, pagination: PaginationQuery) -> Result<PaginatedResponse<Warehouse>> {
+#[async_trait]
+impl WarehouseStore for WarehouseRepository {
+    async fn list(&self, pagination: PaginationQuery) -> Result<PaginatedResponse<Warehouse>> {
         let (page, limit) = validate_pagination(&pagination);
         let offset = calculate_offset(page, limit);
 
-        let total = sqlx::query_scalar!(
-            "SELECT COUNT(*) FROM warehouse.warehouses WHERE is_active = true"
-        )
-        .fetch_one(&self.pool)
-        .await?
-        .unwrap_or(0);
+        let count_mode = pagination.count.unwrap_or_default();
+
+        let total = match count_mode {
+            CountMode::Exact => Some(
+                sqlx::query_scalar!(
+                    "SELECT COUNT(*) FROM warehouse.warehouses WHERE is_active = true"
+                )
+                .fetch_one(&self.pool)
+                .await?
+                .unwrap_or(0),
+            ),
+            CountMode::Estimate => Some(estimate_row_count(&self.pool, "warehouse.warehouses").await?),
+            CountMode::Skip => None,
+        };
 
+        // Fetch one extra row so has_next is known without counting
         let rows = sqlx::query!(
             "SELECT warehouse_id, warehouse_code, warehouse_name, 
-                    city, state, country, is_active, created_at, updated_at
+                    city, state, country, timezone, is_active, created_at, updated_at
              FROM warehouse.warehouses WHERE is_active = true 
              ORDER BY warehouse_name LIMIT $1 OFFSET $2",
-            limit, offset
+            limit + 1, offset
         )
         .fetch_all(&self.pool)
         .await?;
@@ -49,7 +64,7 @@ impl WarehouseRepository {
                 phone: None,
                 email: None,
                 manager_user_id: None,
-                timezone: None,
+                timezone: row.timezone,
                 is_active: row.is_active.unwrap_or(true),
                 created_at: row.created_at,
                 updated_at: row.updated_at,
@@ -59,13 +74,13 @@ impl WarehouseRepository {
             warehouses.push(warehouse);
         }
 
-        Ok(PaginatedResponse::new(warehouses, total, page, limit))
+        Ok(PaginatedResponse::from_probe(warehouses, total, count_mode, page, limit))
     }
 
-    pub async fn get_by_id(&self, id: i32) -> Result<Option<Warehouse>> {
+    async fn get_by_id(&self, id: i32) -> Result<Option<Warehouse>> {
         let result = sqlx::query!(
             "SELECT warehouse_id, warehouse_code, warehouse_name, 
-                    city, state, country, is_active, created_at, updated_at
+                    city, state, country, timezone, is_active, created_at, updated_at
              FROM warehouse.warehouses WHERE warehouse_id = $1 AND is_active = true",
             id
         )
@@ -86,7 +101,7 @@ impl WarehouseRepository {
                 phone: None,
                 email: None,
                 manager_user_id: None,
-                timezone: None,
+                timezone: row.timezone,
                 is_active: row.is_active.unwrap_or(true),
                 created_at: row.created_at,
                 updated_at: row.updated_at,
@@ -97,17 +112,20 @@ impl WarehouseRepository {
         }
     }
 
-    pub async fn create(&self, warehouse: CreateWarehouse) -> Result<Warehouse> {
+    async fn create(&self, warehouse: CreateWarehouse) -> Result<Warehouse> {
+        ensure_timezone(&self.pool, warehouse.timezone.as_deref()).await?;
+
         let result = sqlx::query!(
-            "INSERT INTO warehouse.warehouses (warehouse_code, warehouse_name, city, state, country)
-             VALUES ($1, $2, $3, $4, $5)
-             RETURNING warehouse_id, warehouse_code, warehouse_name, city, state, country, 
+            "INSERT INTO warehouse.warehouses (warehouse_code, warehouse_name, city, state, country, timezone)
+             VALUES ($1, $2, $3, $4, $5, COALESCE($6, 'Asia/Jakarta'))
+             RETURNING warehouse_id, warehouse_code, warehouse_name, city, state, country, timezone,
                       is_active, created_at, updated_at",
             warehouse.warehouse_code,
             warehouse.warehouse_name,
             warehouse.city,
             warehouse.state,
-            warehouse.country.unwrap_or_else(|| "Indonesia".to_string())
+            warehouse.country.unwrap_or_else(|| "Indonesia".to_string()),
+            warehouse.timezone
         )
         .fetch_one(&self.pool)
         .await?;
@@ -125,7 +143,7 @@ impl WarehouseRepository {
             phone: None,
             email: None,
             manager_user_id: None,
-            timezone: None,
+            timezone: result.timezone,
             is_active: result.is_active.unwrap_or(true),
             created_at: result.created_at,
             updated_at: result.updated_at,
@@ -134,22 +152,26 @@ impl WarehouseRepository {
         })
     }
 
-    pub async fn update(&self, id: i32, warehouse: UpdateWarehouse) -> Result<Option<Warehouse>> {
+    async fn update(&self, id: i32, warehouse: UpdateWarehouse) -> Result<Option<Warehouse>> {
+        ensure_timezone(&self.pool, warehouse.timezone.as_deref()).await?;
+
         let result = sqlx::query!(
             "UPDATE warehouse.warehouses 
              SET warehouse_name = COALESCE($2, warehouse_name),
                  city = COALESCE($3, city),
                  state = COALESCE($4, state),
                  country = COALESCE($5, country),
+                 timezone = COALESCE($6, timezone),
                  updated_at = NOW()
              WHERE warehouse_id = $1 AND is_active = true
-             RETURNING warehouse_id, warehouse_code, warehouse_name, city, state, country,
+             RETURNING warehouse_id, warehouse_code, warehouse_name, city, state, country, timezone,
                       is_active, created_at, updated_at",
             id,
             warehouse.warehouse_name,
             warehouse.city,
             warehouse.state,
-            warehouse.country
+            warehouse.country,
+            warehouse.timezone
         )
         .fetch_optional(&self.pool)
         .await?;
@@ -168,7 +190,7 @@ impl WarehouseRepository {
                 phone: None,
                 email: None,
                 manager_user_id: None,
-                timezone: None,
+                timezone: row.timezone,
                 is_active: row.is_active.unwrap_or(true),
                 created_at: row.created_at,
                 updated_at: row.updated_at,
@@ -179,7 +201,7 @@ impl WarehouseRepository {
         }
     }
 
-    pub async fn delete(&self, id: i32) -> Result<bool> {
+    async fn delete(&self, id: i32) -> Result<bool> {
         let result = sqlx::query!(
             "UPDATE warehouse.warehouses 
              SET is_active = false, updated_at = NOW()
@@ -192,7 +214,7 @@ impl WarehouseRepository {
         Ok(result.rows_affected() > 0)
     }
 
-    pub async fn code_exists(&self, code: &str, exclude_id: Option<i32>) -> Result<bool> {
+    async fn code_exists(&self, code: &str, exclude_id: Option<i32>) -> Result<bool> {
         let exists = match exclude_id {
             Some(id) => {
                 sqlx::query_scalar!(
@@ -216,4 +238,21 @@ impl WarehouseRepository {
 
         Ok(exists.unwrap_or(false))
     }
-}
\ No newline at end of file
+}
+/// Shift times are read in the warehouse timezone, so it must be one Postgres knows
+async fn ensure_timezone(pool: &PgPool, timezone: Option<&str>) -> Result<()> {
+    let Some(timezone) = timezone else {
+        return Ok(());
+    };
+
+    let known = sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM pg_timezone_names WHERE name = $1)", timezone)
+        .fetch_one(pool)
+        .await?
+        .unwrap_or(false);
+
+    if known {
+        Ok(())
+    } else {
+        Err(violation(format!("unknown timezone {}", timezone)))
+    }
+}
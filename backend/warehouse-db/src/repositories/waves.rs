@@ -0,0 +1,571 @@
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+use crate::error::violation;
+use crate::repositories::audit::acting_user;
+use crate::repositories::packaging::issue_packaging;
+use crate::utils::*;
+
+#[derive(Clone)]
+pub struct WaveRepository {
+    pool: PgPool,
+}
+
+impl WaveRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// List waves, newest first
+    pub async fn list(&self, filter: WaveFilter, pagination: PaginationQuery) -> Result<PaginatedResponse<Wave>> {
+        let (page, limit) = validate_pagination(&pagination);
+        let offset = calculate_offset(page, limit);
+        let mode = pagination.count.unwrap_or_default();
+
+        let total = match mode {
+            CountMode::Exact => sqlx::query_scalar!(
+                "SELECT COUNT(*) FROM warehouse.waves
+                 WHERE ($1::VARCHAR IS NULL OR status = $1)
+                   AND ($2::INT IS NULL OR warehouse_id = $2)",
+                filter.status,
+                filter.warehouse_id
+            )
+            .fetch_one(&self.pool)
+            .await?,
+            CountMode::Estimate => Some(estimate_row_count(&self.pool, "warehouse.waves").await?),
+            CountMode::Skip => None,
+        };
+
+        let rows = sqlx::query_as!(
+            Wave,
+            "SELECT * FROM warehouse.waves
+             WHERE ($1::VARCHAR IS NULL OR status = $1)
+               AND ($2::INT IS NULL OR warehouse_id = $2)
+             ORDER BY wave_id DESC
+             LIMIT $3 OFFSET $4",
+            filter.status,
+            filter.warehouse_id,
+            limit + 1,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PaginatedResponse::from_probe(rows, total, mode, page, limit))
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<WaveDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_wave(&mut conn, id, false).await
+    }
+
+    /// Plan a wave from the open orders matching the criteria, earliest requested first.
+    /// Orders already in another wave are skipped.
+    pub async fn create(&self, criteria: CreateWave) -> Result<WaveDetail> {
+        let mut tx = self.pool.begin().await?;
+
+        let order_ids = sqlx::query_scalar!(
+            "SELECT o.order_id FROM warehouse.outbound_orders o
+             WHERE o.warehouse_id = $1
+               AND o.status = ANY($2)
+               AND o.wave_id IS NULL
+               AND o.order_type = $8
+               AND ($3::VARCHAR IS NULL OR UPPER(o.carrier_code) = UPPER($3))
+               AND ($4::DATE IS NULL OR o.requested_ship_date <= $4)
+               AND ($5::INT[] IS NULL OR o.order_id = ANY($5))
+               AND ($6::INT IS NULL OR NOT EXISTS (
+                   SELECT 1 FROM warehouse.outbound_order_lines l
+                   WHERE l.order_id = o.order_id
+                     AND l.quantity_shipped < l.quantity_ordered
+                     AND NOT EXISTS (
+                         SELECT 1 FROM warehouse.locations loc
+                         WHERE loc.zone_id = $6 AND loc.item_id = l.item_id AND loc.is_active
+                     )
+               ))
+             ORDER BY o.requested_ship_date NULLS LAST, o.order_id
+             LIMIT $7
+             FOR UPDATE SKIP LOCKED",
+            criteria.warehouse_id,
+            &[ORDER_OPEN.to_string(), ORDER_PARTIALLY_SHIPPED.to_string()],
+            criteria.carrier_code,
+            criteria.cutoff_date,
+            criteria.order_ids.as_deref(),
+            criteria.zone_id,
+            criteria.max_orders.unwrap_or(100),
+            ORDER_TYPE_STANDARD
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if order_ids.is_empty() {
+            return Err(violation("no open orders match the wave criteria"));
+        }
+
+        let wave_id = sqlx::query_scalar!(
+            "INSERT INTO warehouse.waves (
+                 wave_id, wave_number, warehouse_id, zone_id, carrier_code, cutoff_date, created_by
+             )
+             SELECT next_id, 'WAVE-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4, $5
+             FROM (SELECT nextval('warehouse.waves_wave_id_seq')::INT AS next_id) seq
+             RETURNING wave_id",
+            criteria.warehouse_id,
+            criteria.zone_id,
+            criteria.carrier_code,
+            criteria.cutoff_date,
+            acting_user() // created_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE warehouse.outbound_orders SET wave_id = $1, updated_at = NOW() WHERE order_id = ANY($2)",
+            wave_id,
+            &order_ids
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let detail = load_wave(&mut tx, wave_id, false).await?.expect("wave inserted above");
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Release a planned wave to the floor: open quantities of all its orders are
+    /// consolidated into one pick task per item, sequenced along the pick path
+    pub async fn release(&self, id: i32) -> Result<Option<WaveDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_wave(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let wave = &detail.wave;
+
+        if wave.status != WAVE_PLANNED {
+            return Err(violation(format!("cannot release a {} wave", wave.status.to_lowercase())));
+        }
+
+        let tasks = sqlx::query!(
+            "INSERT INTO warehouse.pick_tasks (wave_id, sequence, item_id, location_id, quantity)
+             SELECT $1,
+                    ROW_NUMBER() OVER (
+                        ORDER BY face.zone_sequence NULLS LAST, face.pick_sequence NULLS LAST,
+                                 face.location_code NULLS LAST, demand.item_id
+                    )::INT,
+                    demand.item_id, face.location_id, demand.quantity
+             FROM (
+                 SELECT l.item_id, SUM(l.quantity_ordered - l.quantity_shipped) AS quantity
+                 FROM warehouse.outbound_order_lines l
+                 JOIN warehouse.outbound_orders o ON o.order_id = l.order_id
+                 WHERE o.wave_id = $1
+                 GROUP BY l.item_id
+                 HAVING SUM(l.quantity_ordered - l.quantity_shipped) > 0
+             ) demand
+             LEFT JOIN LATERAL (
+                 SELECT loc.location_id, loc.location_code, loc.pick_sequence, z.pick_sequence AS zone_sequence
+                 FROM warehouse.locations loc
+                 JOIN warehouse.zones z ON z.zone_id = loc.zone_id
+                 WHERE loc.item_id = demand.item_id
+                   AND loc.is_active
+                   AND z.warehouse_id = $2
+                   AND ($3::INT IS NULL OR z.zone_id = $3)
+                 ORDER BY z.pick_sequence, loc.pick_sequence, loc.location_code
+                 LIMIT 1
+             ) face ON TRUE",
+            id,
+            wave.warehouse_id,
+            wave.zone_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if tasks.rows_affected() == 0 {
+            return Err(violation(format!("{} has nothing left to pick", wave.wave_number)));
+        }
+
+        sqlx::query!(
+            "INSERT INTO warehouse.tasks (
+                 warehouse_id, task_type, item_id, quantity, from_location_id, reference_type, reference_id, created_by
+             )
+             SELECT $2, $3, item_id, quantity, location_id, $4, task_id, $5
+             FROM warehouse.pick_tasks WHERE wave_id = $1
+             ORDER BY sequence",
+            id,
+            wave.warehouse_id,
+            TASK_PICK,
+            TASK_REF_PICK,
+            acting_user() // created_by
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE warehouse.waves SET status = $2, released_at = NOW() WHERE wave_id = $1",
+            id,
+            WAVE_RELEASED
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let detail = load_wave(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Cancel a wave that has not been released; its orders become available to other waves
+    pub async fn cancel(&self, id: i32) -> Result<Option<WaveDetail>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_wave(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+
+        if detail.wave.status != WAVE_PLANNED {
+            return Err(violation(format!("cannot cancel a {} wave", detail.wave.status.to_lowercase())));
+        }
+
+        sqlx::query!(
+            "UPDATE warehouse.outbound_orders SET wave_id = NULL, updated_at = NOW() WHERE wave_id = $1",
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("UPDATE warehouse.waves SET status = $2 WHERE wave_id = $1", id, WAVE_CANCELLED)
+            .execute(&mut *tx)
+            .await?;
+
+        let detail = load_wave(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Confirm a pick task. Returns None when the wave or task does not exist.
+    pub async fn confirm_pick(&self, id: i32, task_id: i32, pick: ConfirmPick) -> Result<Option<WaveDetail>> {
+        let mut tx = self.pool.begin().await?;
+        if !confirm_pick(&mut tx, id, task_id, pick.quantity_picked, None).await? {
+            return Ok(None);
+        }
+
+        let detail = load_wave(&mut tx, id, false).await?;
+        tx.commit().await?;
+        Ok(detail)
+    }
+
+    /// Pack picked goods for one of the wave's orders into a new carton. Nothing can be
+    /// packed beyond what was picked for the item across the wave, or beyond what the
+    /// order line still needs. Packing into a configured box size issues its packaging
+    /// materials from stock. Returns None when the wave does not exist.
+    pub async fn pack(&self, id: i32, carton: PackCarton) -> Result<Option<(WaveDetail, CartonDetail)>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(detail) = load_wave(&mut tx, id, true).await? else {
+            return Ok(None);
+        };
+        let wave = &detail.wave;
+
+        if wave.status != WAVE_RELEASED && wave.status != WAVE_PICKED {
+            return Err(violation(format!("cannot pack a {} wave", wave.status.to_lowercase())));
+        }
+        if !detail.order_ids.contains(&carton.order_id) {
+            return Err(violation(format!("order {} is not part of {}", carton.order_id, wave.wave_number)));
+        }
+
+        let lines = sqlx::query_as!(
+            OrderLine,
+            "SELECT * FROM warehouse.outbound_order_lines WHERE order_id = $1 ORDER BY line_number",
+            carton.order_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        // Picked but not yet packed, per item, across all orders of the wave
+        let mut unpacked: Vec<(i32, Decimal)> = sqlx::query!(
+            r#"SELECT t.item_id, t.picked - COALESCE(p.packed, 0) AS "unpacked!"
+               FROM (SELECT item_id, SUM(quantity_picked) AS picked FROM warehouse.pick_tasks
+                     WHERE wave_id = $1 GROUP BY item_id) t
+               LEFT JOIN (SELECT cl.item_id, SUM(cl.quantity) AS packed
+                          FROM warehouse.carton_lines cl
+                          JOIN warehouse.cartons c ON c.carton_id = cl.carton_id
+                          WHERE c.wave_id = $1 GROUP BY cl.item_id) p ON p.item_id = t.item_id"#,
+            id
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| (row.item_id, row.unpacked))
+        .collect();
+        let mut packed_so_far: Vec<(i32, Decimal)> =
+            lines.iter().map(|line| (line.line_id, line.quantity_packed)).collect();
+
+        for packed in &carton.lines {
+            let line = lines
+                .iter()
+                .find(|line| line.line_id == packed.line_id)
+                .ok_or_else(|| violation(format!("line {} is not part of order {}", packed.line_id, carton.order_id)))?;
+
+            let total = packed_so_far
+                .iter_mut()
+                .find(|(line_id, _)| *line_id == line.line_id)
+                .map(|(_, quantity)| quantity)
+                .expect("packed_so_far covers every line");
+            let still_needed = line.quantity_ordered - line.quantity_shipped;
+            if *total + packed.quantity > still_needed {
+                return Err(violation(format!(
+                    "line {} would pack {} of {} still to ship",
+                    line.line_number,
+                    *total + packed.quantity,
+                    still_needed
+                )));
+            }
+            *total += packed.quantity;
+
+            let available = unpacked
+                .iter_mut()
+                .find(|(item_id, _)| *item_id == line.item_id)
+                .map(|(_, quantity)| quantity)
+                .ok_or_else(|| violation(format!("item {} was not picked in {}", line.item_id, wave.wave_number)))?;
+            if packed.quantity > *available {
+                return Err(violation(format!(
+                    "only {} of item {} picked and not yet packed",
+                    available, line.item_id
+                )));
+            }
+            *available -= packed.quantity;
+        }
+
+        if let Some(box_id) = carton.box_id {
+            let usable = sqlx::query_scalar!(
+                "SELECT is_active FROM warehouse.box_sizes WHERE box_id = $1 AND warehouse_id = $2",
+                box_id,
+                wave.warehouse_id
+            )
+            .fetch_optional(&mut *tx)
+            .await?;
+            match usable {
+                Some(true) => {}
+                Some(false) => return Err(violation(format!("box size {} is no longer in use", box_id))),
+                None => {
+                    return Err(violation(format!("box size {} does not exist in warehouse {}", box_id, wave.warehouse_id)))
+                }
+            }
+        }
+
+        let inserted = sqlx::query!(
+            "INSERT INTO warehouse.cartons (carton_id, carton_number, wave_id, order_id, created_by, box_id)
+             SELECT next_id, 'CTN-' || LPAD(next_id::TEXT, 6, '0'), $1, $2, $3, $4
+             FROM (SELECT nextval('warehouse.cartons_carton_id_seq')::INT AS next_id) seq
+             RETURNING carton_id, carton_number",
+            id,
+            carton.order_id,
+            acting_user(), // created_by
+            carton.box_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let carton_id = inserted.carton_id;
+
+        for packed in carton.lines {
+            let item_id = lines
+                .iter()
+                .find(|line| line.line_id == packed.line_id)
+                .map(|line| line.item_id)
+                .expect("checked above");
+            sqlx::query!(
+                "INSERT INTO warehouse.carton_lines (carton_id, order_line_id, item_id, quantity)
+                 VALUES ($1, $2, $3, $4)",
+                carton_id,
+                packed.line_id,
+                item_id,
+                packed.quantity
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query!(
+                "UPDATE warehouse.outbound_order_lines SET quantity_packed = quantity_packed + $2 WHERE line_id = $1",
+                packed.line_id,
+                packed.quantity
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        if let Some(box_id) = carton.box_id {
+            issue_packaging(&mut tx, box_id, wave.warehouse_id, &inserted.carton_number).await?;
+        }
+        complete_if_packed(&mut tx, id).await?;
+
+        let detail = load_wave(&mut tx, id, false).await?.expect("wave locked above");
+        let carton = load_carton(&mut tx, carton_id).await?.expect("carton inserted above");
+        tx.commit().await?;
+        Ok(Some((detail, carton)))
+    }
+
+    pub async fn get_carton(&self, id: i32) -> Result<Option<CartonDetail>> {
+        let mut conn = self.pool.acquire().await?;
+        load_carton(&mut conn, id).await
+    }
+}
+
+/// A picked wave completes once everything picked has been packed. Orders left with
+/// unpacked quantities (short picks) leave the wave so a later wave can pick them.
+/// Record the picked quantity on a wave pick task and close its queue task if the
+/// operator confirmed through the wave. Returns false when the wave or task does not exist.
+pub(crate) async fn confirm_pick(
+    conn: &mut PgConnection,
+    id: i32,
+    task_id: i32,
+    quantity_picked: Decimal,
+    completed_by: Option<i32>,
+) -> Result<bool> {
+    let Some(detail) = load_wave(&mut *conn, id, true).await? else {
+        return Ok(false);
+    };
+    let Some(task) = detail.tasks.iter().find(|task| task.task_id == task_id) else {
+        return Ok(false);
+    };
+
+    if detail.wave.status != WAVE_RELEASED {
+        return Err(violation(format!("cannot pick in a {} wave", detail.wave.status.to_lowercase())));
+    }
+    if task.status != PICK_OPEN {
+        return Err(violation(format!("task {} was already confirmed", task_id)));
+    }
+    if quantity_picked > task.quantity {
+        return Err(violation(format!(
+            "task {} asks for {}, cannot pick {}",
+            task_id, task.quantity, quantity_picked
+        )));
+    }
+
+    sqlx::query!(
+        "UPDATE warehouse.pick_tasks SET quantity_picked = $2, status = $3, picked_at = NOW() WHERE task_id = $1",
+        task_id,
+        quantity_picked,
+        if quantity_picked == task.quantity { PICK_PICKED } else { PICK_SHORT }
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE warehouse.tasks SET
+             status = $3, quantity_done = $4, completed_at = NOW(), completed_by = COALESCE($5, assigned_to),
+             started_at = COALESCE(started_at, NOW())
+         WHERE reference_type = $1 AND reference_id = $2 AND status NOT IN ($3, $6)",
+        TASK_REF_PICK,
+        task_id,
+        TASK_COMPLETED,
+        quantity_picked,
+        completed_by,
+        TASK_CANCELLED
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE warehouse.waves SET status = $2, picked_at = NOW()
+         WHERE wave_id = $1
+           AND NOT EXISTS (SELECT 1 FROM warehouse.pick_tasks WHERE wave_id = $1 AND status = $3)",
+        id,
+        WAVE_PICKED,
+        PICK_OPEN
+    )
+    .execute(&mut *conn)
+    .await?;
+    complete_if_packed(conn, id).await?;
+
+    Ok(true)
+}
+
+async fn complete_if_packed(conn: &mut PgConnection, id: i32) -> Result<()> {
+    let completed = sqlx::query!(
+        "UPDATE warehouse.waves SET status = $2, completed_at = NOW()
+         WHERE wave_id = $1
+           AND status = $3
+           AND (SELECT COALESCE(SUM(quantity_picked), 0) FROM warehouse.pick_tasks WHERE wave_id = $1)
+             = (SELECT COALESCE(SUM(cl.quantity), 0) FROM warehouse.carton_lines cl
+                JOIN warehouse.cartons c ON c.carton_id = cl.carton_id WHERE c.wave_id = $1)",
+        id,
+        WAVE_COMPLETED,
+        WAVE_PICKED
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if completed.rows_affected() > 0 {
+        sqlx::query!(
+            "UPDATE warehouse.outbound_orders o SET wave_id = NULL, updated_at = NOW()
+             WHERE o.wave_id = $1
+               AND EXISTS (
+                   SELECT 1 FROM warehouse.outbound_order_lines l
+                   WHERE l.order_id = o.order_id AND l.quantity_packed < l.quantity_ordered - l.quantity_shipped
+               )",
+            id
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn load_wave(conn: &mut PgConnection, id: i32, lock: bool) -> Result<Option<WaveDetail>> {
+    let wave = if lock {
+        sqlx::query_as!(Wave, "SELECT * FROM warehouse.waves WHERE wave_id = $1 FOR UPDATE", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    } else {
+        sqlx::query_as!(Wave, "SELECT * FROM warehouse.waves WHERE wave_id = $1", id)
+            .fetch_optional(&mut *conn)
+            .await?
+    };
+
+    let Some(wave) = wave else {
+        return Ok(None);
+    };
+
+    let order_ids = sqlx::query_scalar!(
+        "SELECT DISTINCT order_id FROM (
+             SELECT order_id FROM warehouse.outbound_orders WHERE wave_id = $1
+             UNION SELECT order_id FROM warehouse.cartons WHERE wave_id = $1
+         ) o
+         ORDER BY order_id",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let tasks = sqlx::query_as!(
+        PickTask,
+        "SELECT * FROM warehouse.pick_tasks WHERE wave_id = $1 ORDER BY sequence",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let cartons = sqlx::query_as!(
+        Carton,
+        "SELECT * FROM warehouse.cartons WHERE wave_id = $1 ORDER BY carton_id",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(WaveDetail { wave, order_ids, tasks, cartons }))
+}
+
+async fn load_carton(conn: &mut PgConnection, id: i32) -> Result<Option<CartonDetail>> {
+    let Some(carton) = sqlx::query_as!(Carton, "SELECT * FROM warehouse.cartons WHERE carton_id = $1", id)
+        .fetch_optional(&mut *conn)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let lines = sqlx::query_as!(
+        CartonLine,
+        "SELECT * FROM warehouse.carton_lines WHERE carton_id = $1 ORDER BY carton_line_id",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(CartonDetail { carton, lines }))
+}
@@ -0,0 +1,54 @@
+//! Storage traits implemented by the Postgres repositories and the in-memory mock store
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use warehouse_models::*;
+
+#[async_trait]
+pub trait WarehouseStore: Send + Sync {
+    async fn list(&self, pagination: PaginationQuery) -> Result<PaginatedResponse<Warehouse>>;
+    async fn get_by_id(&self, id: i32) -> Result<Option<Warehouse>>;
+    async fn create(&self, warehouse: CreateWarehouse) -> Result<Warehouse>;
+    async fn update(&self, id: i32, warehouse: UpdateWarehouse) -> Result<Option<Warehouse>>;
+    /// Soft delete; returns false when no active warehouse matched
+    async fn delete(&self, id: i32) -> Result<bool>;
+    async fn code_exists(&self, code: &str, exclude_id: Option<i32>) -> Result<bool>;
+}
+
+#[async_trait]
+pub trait ItemStore: Send + Sync {
+    async fn list(&self, pagination: PaginationQuery) -> Result<PaginatedResponse<Item>>;
+    /// Stream every active item matching `search` in list order, for exports that must not
+    /// buffer the whole table
+    fn stream_active(&self, search: Option<String>) -> BoxStream<'static, Result<Item>>;
+    async fn get_by_id(&self, id: i32) -> Result<Option<Item>>;
+    /// Look up an active item by its code, for documents that identify items by code (EDI, imports)
+    async fn get_by_code(&self, code: &str) -> Result<Option<Item>>;
+    async fn create(&self, item: CreateItem) -> Result<Item>;
+    async fn update(&self, id: i32, item: UpdateItem) -> Result<Option<Item>>;
+    /// Soft delete; returns false when no active item matched
+    async fn delete(&self, id: i32) -> Result<bool>;
+    /// Undo a soft delete; None when no deleted item matched
+    async fn restore(&self, id: i32) -> Result<Option<Item>>;
+    async fn code_exists(&self, code: &str, exclude_id: Option<i32>) -> Result<bool>;
+}
+
+#[async_trait]
+pub trait MovementStore: Send + Sync {
+    /// Movement history newest first, paged by `movement_id` so deep pages cost the same as the first
+    async fn history(&self, filter: MovementFilter, keyset: KeysetQuery) -> Result<KeysetPage<StockMovement>>;
+}
+
+#[async_trait]
+pub trait UserStore: Send + Sync {
+    /// The active user with this username and password, or None
+    async fn authenticate(&self, username: &str, password: &str) -> Result<Option<User>>;
+    async fn get(&self, id: i32) -> Result<Option<User>>;
+}
+
+#[async_trait]
+pub trait StockStore: Send + Sync {
+    /// Stock on hand at the end of `filter.as_of`, today when omitted
+    async fn snapshot(&self, filter: StockFilter) -> Result<StockSnapshot>;
+}
@@ -1,6 +1,8 @@
 //! Database utility functions
 
-use warehouse_models::PaginationQuery;
+use anyhow::Result;
+use sqlx::PgPool;
+use warehouse_models::{KeysetQuery, PaginationQuery};
 
 /// Build dynamic sort clause for queries
 pub fn build_sort_clause(
@@ -61,9 +63,62 @@ pub fn calculate_total_pages(total: i64, limit: i64) -> i64 {
     }
 }
 
+/// Most rows a page-numbered list returns at once
+pub const MAX_PAGE_SIZE: i64 = 100;
+/// Most rows a keyset list returns at once
+pub const MAX_KEYSET_PAGE_SIZE: i64 = 200;
+
 /// Validate pagination parameters
 pub fn validate_pagination(query: &PaginationQuery) -> (i64, i64) {
     let page = query.page.unwrap_or(1).max(1);
-    let limit = query.limit.unwrap_or(20).max(1).min(100); // Max 100 items per page
+    let limit = query.limit.unwrap_or(20).clamp(1, MAX_PAGE_SIZE);
     (page, limit)
 }
+
+/// Validate keyset parameters; returns the cursor and the clamped page size
+pub fn validate_keyset(query: &KeysetQuery) -> (Option<i64>, i64) {
+    let limit = query.limit.unwrap_or(50).clamp(1, MAX_KEYSET_PAGE_SIZE);
+    (query.after_id, limit)
+}
+
+/// The order a page-numbered list comes back in, as its query sorts it
+#[derive(Debug, Clone, Copy)]
+pub struct ListOrder {
+    /// The list's path under `/api`
+    pub resource: &'static str,
+    /// Values `sort_by` may name
+    pub sort_fields: &'static [&'static str],
+    /// `ASC` or `DESC`
+    pub sort_order: &'static str,
+}
+
+pub const ITEM_ORDER: ListOrder = ListOrder { resource: "items", sort_fields: &["item_name"], sort_order: "ASC" };
+pub const WAREHOUSE_ORDER: ListOrder =
+    ListOrder { resource: "warehouses", sort_fields: &["warehouse_name"], sort_order: "ASC" };
+pub const ORDER_ORDER: ListOrder = ListOrder { resource: "orders", sort_fields: &["order_id"], sort_order: "DESC" };
+pub const RECEIPT_ORDER: ListOrder = ListOrder { resource: "receipts", sort_fields: &["receipt_id"], sort_order: "DESC" };
+pub const RETURN_ORDER: ListOrder = ListOrder { resource: "returns", sort_fields: &["return_id"], sort_order: "DESC" };
+pub const INSPECTION_ORDER: ListOrder =
+    ListOrder { resource: "inspections", sort_fields: &["inspection_id"], sort_order: "ASC" };
+pub const TASK_ORDER: ListOrder = ListOrder { resource: "tasks", sort_fields: &["priority"], sort_order: "DESC" };
+pub const WAREHOUSE_STOCK_ORDER: ListOrder =
+    ListOrder { resource: "warehouses/stock", sort_fields: &["item_id"], sort_order: "ASC" };
+pub const ITEM_STOCK_ORDER: ListOrder =
+    ListOrder { resource: "items/stock", sort_fields: &["warehouse_id"], sort_order: "ASC" };
+pub const WAVE_ORDER: ListOrder = ListOrder { resource: "waves", sort_fields: &["wave_id"], sort_order: "DESC" };
+
+/// Estimate a table's row count from planner statistics instead of scanning it.
+/// `table` is a schema-qualified name such as `warehouse.items`.
+pub async fn estimate_row_count(pool: &PgPool, table: &str) -> Result<i64> {
+    let estimate = sqlx::query_scalar!(
+        "SELECT reltuples::BIGINT FROM pg_class WHERE oid = to_regclass($1)",
+        table
+    )
+    .fetch_optional(pool)
+    .await?
+    .flatten()
+    .unwrap_or(0);
+
+    // reltuples is -1 for tables that have never been analyzed
+    Ok(estimate.max(0))
+}
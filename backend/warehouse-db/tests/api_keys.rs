@@ -0,0 +1,57 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::*;
+
+fn request(name: &str, scopes: &[&str]) -> CreateApiKey {
+    CreateApiKey { name: name.to_string(), scopes: scopes.iter().map(|scope| scope.to_string()).collect() }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn keys_are_stored_hashed_and_stop_working_once_revoked(pool: PgPool) {
+    let repo = ApiKeyRepository::new(pool.clone());
+    let issued = repo.issue(request("ERP sync", &[SCOPE_STOCK_WRITE, SCOPE_STOCK_WRITE]), Some(1)).await.unwrap();
+    assert!(issued.api_key.starts_with(API_KEY_PREFIX));
+    assert!(issued.api_key.starts_with(&issued.key.key_prefix));
+    assert_eq!(issued.key.scopes, [SCOPE_STOCK_WRITE]);
+    assert_eq!(issued.key.created_by, Some(1));
+    assert!(issued.key.last_used_at.is_none());
+
+    let stored: String = sqlx::query_scalar("SELECT key_hash FROM warehouse.api_keys WHERE key_id = $1")
+        .bind(issued.key.key_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(stored.len(), 64);
+    assert!(!stored.contains(&issued.api_key));
+
+    let used = repo.authenticate(&issued.api_key).await.unwrap().unwrap();
+    assert_eq!(used.key_id, issued.key.key_id);
+    assert!(used.last_used_at.is_some());
+    assert!(used.has_scope(SCOPE_STOCK_WRITE));
+    assert!(!used.has_scope(SCOPE_ADMIN));
+    assert!(repo.authenticate("wmk_not-a-key").await.unwrap().is_none());
+    assert!(repo.authenticate(&issued.api_key[API_KEY_PREFIX.len()..]).await.unwrap().is_none());
+
+    let reader = repo.issue(request("Dashboard", &[SCOPE_READ_ONLY]), None).await.unwrap();
+    let revoked = repo.revoke(issued.key.key_id).await.unwrap().unwrap();
+    assert!(revoked.revoked_at.is_some());
+    assert!(repo.revoke(0).await.unwrap().is_none());
+    assert!(repo.authenticate(&issued.api_key).await.unwrap().is_none());
+
+    let active = repo.list(&ApiKeyFilter::default()).await.unwrap();
+    assert_eq!(active.iter().map(|key| key.key_id).collect::<Vec<_>>(), [reader.key.key_id]);
+    let all = repo.list(&ApiKeyFilter { include_revoked: true }).await.unwrap();
+    assert_eq!(all.len(), 2);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn an_admin_key_holds_every_scope(pool: PgPool) {
+    let repo = ApiKeyRepository::new(pool);
+    let admin = repo.issue(request("Provisioning", &[SCOPE_ADMIN]), None).await.unwrap();
+    for scope in API_KEY_SCOPES {
+        assert!(admin.key.has_scope(scope), "{}", scope);
+    }
+
+    let reader = repo.issue(request("Dashboard", &[SCOPE_READ_ONLY]), None).await.unwrap();
+    assert!(!reader.key.has_scope(SCOPE_STOCK_WRITE));
+}
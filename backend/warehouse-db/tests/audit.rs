@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::PgPool;
+use warehouse_db::audit;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn printer(code: &str) -> CreatePrinter {
+    CreatePrinter {
+        printer_code: code.to_string(),
+        name: "Zebra on the floor".to_string(),
+        printer_type: PRINTER_ZPL.to_string(),
+        address: "10.0.4.21".to_string(),
+        station_id: None,
+        default_for: vec![],
+    }
+}
+
+fn for_entity(entity: &str, id: impl ToString) -> AuditFilter {
+    AuditFilter { entity: Some(entity.to_string()), id: Some(id.to_string()), ..Default::default() }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn creates_updates_and_deletes_are_recorded_with_what_changed(pool: PgPool) {
+    let repo = AuditRepository::new(pool.clone());
+    let printers = PrinterRepository::new(pool.clone());
+    let created = printers.create(1, printer("FLOOR")).await.unwrap();
+    let update = UpdatePrinter { name: Some("Zebra by dock 3".to_string()), ..Default::default() };
+    printers.update(created.printer_id, update).await.unwrap().unwrap();
+    // Printing only moves a bookkeeping timestamp
+    printers.mark_printed(created.printer_id).await.unwrap();
+    sqlx::query("DELETE FROM warehouse.printers WHERE printer_id = $1")
+        .bind(created.printer_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let history = repo.history(&for_entity("printer", created.printer_id), KeysetQuery::default()).await.unwrap();
+    let actions: Vec<&str> = history.data.iter().map(|entry| entry.action.as_str()).collect();
+    assert_eq!(actions, [AUDIT_DELETE, AUDIT_UPDATE, AUDIT_CREATE]);
+
+    let (deleted, updated, inserted) = (&history.data[0], &history.data[1], &history.data[2]);
+    assert_eq!(inserted.old_values, None);
+    assert_eq!(inserted.new_values.as_ref().unwrap()["printer_code"], "FLOOR");
+    let changed = updated.new_values.as_ref().unwrap().as_object().unwrap();
+    assert_eq!(changed.keys().collect::<Vec<_>>(), ["name"]);
+    assert_eq!(updated.old_values.as_ref().unwrap()["name"], "Zebra on the floor");
+    assert_eq!(deleted.old_values.as_ref().unwrap()["name"], "Zebra by dock 3");
+    assert_eq!(deleted.new_values, None);
+    assert!(history.data.iter().all(|entry| entry.actor.is_none()));
+
+    let other = printers.create(1, printer("DOCK")).await.unwrap();
+    let all = repo
+        .history(&AuditFilter { entity: Some("printer".to_string()), ..Default::default() }, KeysetQuery::default());
+    assert_eq!(all.await.unwrap().data[0].entity_id, other.printer_id.to_string());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn changes_name_the_actor_and_never_record_secrets(pool: PgPool) {
+    let repo = AuditRepository::new(pool.clone());
+    let mut conn = pool.acquire().await.unwrap();
+    audit::as_actor("user:7".to_string(), async {
+        assert_eq!(audit::current_actor().as_deref(), Some("user:7"));
+        audit::tag_connection(&mut conn).await.unwrap();
+        sqlx::query("UPDATE warehouse.users SET password_hash = 'new-hash', full_name = 'System' WHERE user_id = 1")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+    })
+    .await;
+    assert_eq!(audit::current_actor(), None);
+
+    let history = repo.history(&for_entity("user", 1), KeysetQuery::default()).await.unwrap();
+    assert_eq!(history.data.len(), 1);
+    let entry = &history.data[0];
+    assert_eq!(entry.actor.as_deref(), Some("user:7"));
+    // The password changed, but neither hash is kept
+    assert_eq!(entry.new_values.as_ref().unwrap()["password_hash"], "[redacted]");
+    assert_eq!(entry.old_values.as_ref().unwrap()["password_hash"], "[redacted]");
+
+    // A connection taken outside any request is untagged again
+    audit::tag_connection(&mut conn).await.unwrap();
+    sqlx::query("UPDATE warehouse.warehouses SET warehouse_name = 'Jakarta DC' WHERE warehouse_id = 1")
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    let by_actor = AuditFilter { actor: Some("user:7".to_string()), ..Default::default() };
+    assert_eq!(repo.history(&by_actor, KeysetQuery::default()).await.unwrap().data.len(), 1);
+    let history = repo.history(&for_entity("warehouse", 1), KeysetQuery::default()).await.unwrap();
+    assert_eq!(history.data[0].actor, None);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn connections_the_pool_opens_for_a_request_are_tagged(_: PgPoolOptions, options: PgConnectOptions) {
+    let settings = ConnectionSettings {
+        url: String::new(),
+        max_connections: 1,
+        min_connections: 0,
+        acquire_timeout: Duration::from_secs(5),
+        idle_timeout: None,
+        max_lifetime: None,
+        ssl_mode: None,
+        ssl_root_cert: None,
+        application_name: None,
+        session_settings: vec![],
+    };
+    // Lazily, so the request's connection is opened for it rather than taken idle
+    let pool = DatabaseManager::pool_options(&settings).connect_lazy_with(options);
+    audit::as_actor("user:7".to_string(), async {
+        sqlx::query("UPDATE warehouse.warehouses SET warehouse_name = 'Jakarta DC' WHERE warehouse_id = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+    })
+    .await;
+    // The same connection, now idle, is untagged for work outside a request
+    sqlx::query("UPDATE warehouse.warehouses SET warehouse_name = 'Jakarta' WHERE warehouse_id = 1")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let history = AuditRepository::new(pool.clone()).history(&for_entity("warehouse", 1), KeysetQuery::default());
+    let actors: Vec<Option<String>> = history.await.unwrap().data.into_iter().map(|entry| entry.actor).collect();
+    assert_eq!(actors, [None, Some("user:7".to_string())]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn rows_written_for_a_user_name_them_as_creator(pool: PgPool) {
+    let stock = StockRepository::new(pool);
+    let issue = || IssueStock { quantity: Decimal::ONE, reference: None, notes: None };
+
+    let by_user = audit::as_actor("user:7".to_string(), stock.issue(1, 2, issue())).await.unwrap();
+    assert_eq!(by_user.created_by, Some(7));
+    // API keys and scheduled jobs are no user
+    let by_key = audit::as_actor("api_key:3".to_string(), stock.issue(1, 2, issue())).await.unwrap();
+    assert_eq!(by_key.created_by, None);
+    assert_eq!(stock.issue(1, 2, issue()).await.unwrap().created_by, None);
+}
@@ -0,0 +1,172 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::{Datelike, Days, Months, NaiveDate, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn client(repo: &BillingRepository, code: &str, item_ids: Vec<i32>) -> BillingClient {
+    let client = repo
+        .create_client(CreateBillingClient {
+            client_code: code.to_string(),
+            client_name: format!("{} Retail", code),
+            currency: None,
+        })
+        .await
+        .unwrap();
+    repo.assign_items(client.client_id, AssignClientItems { item_ids }).await.unwrap();
+    client
+}
+
+fn rate(charge_code: &str, unit_rate: &str) -> SetBillingRate {
+    SetBillingRate { charge_code: charge_code.to_string(), description: None, unit_rate: unit_rate.parse().unwrap() }
+}
+
+async fn move_stock(pool: &PgPool, item_id: i32, movement_type: &str, quantity: i64) {
+    let mut tx = pool.begin().await.unwrap();
+    post_movement(
+        &mut tx,
+        NewMovement {
+            item_id,
+            warehouse_id: 1,
+            movement_type: movement_type.to_string(),
+            quantity: Decimal::from(quantity),
+            catch_weight_kg: None,
+            lot_number: None,
+            unit_cost: None,
+            stock_status: STOCK_AVAILABLE,
+            consignor_id: None,
+            reference_type: None,
+            reference_number: None,
+            notes: None,
+            created_by: Some(1),
+        },
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+}
+
+fn last_month() -> NaiveDate {
+    let today = Utc::now().date_naive();
+    today.with_day(1).unwrap() - Months::new(1)
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn capture_records_client_activity_once(pool: PgPool) {
+    let repo = BillingRepository::new(pool.clone());
+    let acme = client(&repo, "ACME", vec![2, 3]).await;
+
+    move_stock(&pool, 2, "RECEIPT", 20).await;
+    move_stock(&pool, 2, "SHIPMENT", -5).await;
+    // Item 1 is ours, not the client's
+    move_stock(&pool, 1, "SHIPMENT", -3).await;
+
+    let today = Utc::now().date_naive();
+    let captured = repo.capture(today).await.unwrap();
+    assert_eq!((captured.receipts, captured.picks, captured.storage), (1, 1, 2));
+
+    let again = repo.capture(today).await.unwrap();
+    assert_eq!((again.receipts, again.picks, again.storage), (0, 0, 0));
+
+    let events = repo.events(acme.client_id, BillableEventFilter::default(), KeysetQuery::default()).await.unwrap();
+    let storage: Decimal =
+        events.data.iter().filter(|event| event.event_type == BILLING_STORAGE).map(|event| event.quantity).sum();
+    assert_eq!(storage, Decimal::from(1015 + 50));
+    let pick = events.data.iter().find(|event| event.event_type == BILLING_PICK).unwrap();
+    assert_eq!(pick.quantity, Decimal::from(5));
+
+    // Items belong to one client at a time
+    let other = repo
+        .create_client(CreateBillingClient {
+            client_code: "OTHER".to_string(),
+            client_name: "Other".to_string(),
+            currency: None,
+        })
+        .await
+        .unwrap();
+    let error = repo.assign_items(other.client_id, AssignClientItems { item_ids: vec![3] }).await.unwrap_err();
+    assert!(error.to_string().contains("already belongs to client ACME"), "{}", error);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn invoice_prices_a_finished_month_against_the_rate_card(pool: PgPool) {
+    let repo = BillingRepository::new(pool.clone());
+    let acme = client(&repo, "ACME", vec![2, 3]).await;
+    let id = acme.client_id;
+    let month = last_month();
+    let activity_day = month + Days::new(9);
+
+    sqlx::query("UPDATE warehouse.billing_client_items SET assigned_at = $1::DATE")
+        .bind(month)
+        .execute(&pool)
+        .await
+        .unwrap();
+    move_stock(&pool, 2, "RECEIPT", 20).await;
+    move_stock(&pool, 2, "SHIPMENT", -5).await;
+    sqlx::query("UPDATE warehouse.stock_movements SET movement_date = $1::DATE + TIME '10:00'")
+        .bind(activity_day)
+        .execute(&pool)
+        .await
+        .unwrap();
+    repo.capture(activity_day).await.unwrap();
+
+    repo.set_rates(id, SetBillingRates { rates: vec![rate("storage", "0.01"), rate("RECEIPT", "100")] }).await.unwrap();
+    let kitting = RecordService {
+        warehouse_id: 1,
+        charge_code: "KITTING".to_string(),
+        quantity: Decimal::from(4),
+        item_id: Some(2),
+        event_date: Some(activity_day),
+        notes: None,
+    };
+    let error = repo.record_service(id, kitting.clone()).await.unwrap_err();
+    assert!(error.to_string().contains("not on the client's rate card"), "{}", error);
+    repo.set_rates(id, SetBillingRates { rates: vec![rate("KITTING", "1500")] }).await.unwrap();
+    repo.record_service(id, kitting.clone()).await.unwrap();
+
+    let error = repo.create_invoice(id, CreateInvoice { month }).await.unwrap_err();
+    assert!(error.to_string().contains("no rate for PICK"), "{}", error);
+
+    repo.set_rates(id, SetBillingRates { rates: vec![rate("PICK", "250")] }).await.unwrap();
+    let invoice = repo.create_invoice(id, CreateInvoice { month }).await.unwrap();
+    assert_eq!(invoice.invoice.period_end, month + Months::new(1) - Days::new(1));
+    let lines: Vec<_> =
+        invoice.lines.iter().map(|line| (line.charge_code.as_str(), line.quantity, line.amount)).collect();
+    assert_eq!(
+        lines,
+        [
+            ("KITTING", Decimal::from(4), Decimal::from(6000)),
+            ("PICK", Decimal::from(5), Decimal::from(1250)),
+            ("RECEIPT", Decimal::from(20), Decimal::from(2000)),
+            ("STORAGE", Decimal::from(1065), "10.65".parse().unwrap()),
+        ]
+    );
+    assert_eq!(invoice.invoice.total_amount, "9260.65".parse::<Decimal>().unwrap());
+
+    let unbilled = BillableEventFilter { unbilled: Some(true), ..Default::default() };
+    assert!(repo.events(id, unbilled, KeysetQuery::default()).await.unwrap().data.is_empty());
+
+    let error = repo.create_invoice(id, CreateInvoice { month }).await.unwrap_err();
+    assert!(error.to_string().contains("already invoiced"), "{}", error);
+    let error = repo.record_service(id, kitting).await.unwrap_err();
+    assert!(error.to_string().contains("already invoiced"), "{}", error);
+
+    let exported = repo.export(month).await.unwrap();
+    assert_eq!(exported.len(), 4);
+    assert!(exported
+        .iter()
+        .all(|line| line.client_code == "ACME" && line.invoice_number == invoice.invoice.invoice_number));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn months_are_invoiced_once_they_end(pool: PgPool) {
+    let repo = BillingRepository::new(pool.clone());
+    let acme = client(&repo, "ACME", vec![2]).await;
+
+    let this_month = Utc::now().date_naive().with_day(1).unwrap();
+    let error = repo.create_invoice(acme.client_id, CreateInvoice { month: this_month }).await.unwrap_err();
+    assert!(error.to_string().contains("has not ended yet"), "{}", error);
+
+    let error = repo.create_invoice(acme.client_id, CreateInvoice { month: last_month() }).await.unwrap_err();
+    assert!(error.to_string().contains("no billable activity"), "{}", error);
+}
@@ -0,0 +1,110 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn size(code: &str, sides: [i64; 3], max_weight: i64) -> CreateBoxSize {
+    CreateBoxSize {
+        box_code: code.to_string(),
+        length_cm: Decimal::from(sides[0]),
+        width_cm: Decimal::from(sides[1]),
+        height_cm: Decimal::from(sides[2]),
+        max_weight_kg: Decimal::from(max_weight),
+        tare_weight_kg: "0.2".parse().unwrap(),
+    }
+}
+
+async fn order(pool: &PgPool, lines: &[(i32, i64)]) -> OrderDetail {
+    OrderRepository::new(pool.clone())
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "PT Karya Beton".to_string(),
+            customer_reference: None,
+            ship_to_name: None,
+            ship_to_address: None,
+            ship_to_city: None,
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: lines
+                .iter()
+                .map(|&(item_id, quantity)| CreateOrderLine { item_id, quantity_ordered: Decimal::from(quantity) })
+                .collect(),
+        })
+        .await
+        .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn boxes_list_smallest_first_and_retire(pool: PgPool) {
+    let repo = CartonizationRepository::new(pool);
+    let large = repo.create_box(1, size("L", [60, 40, 40], 25)).await.unwrap();
+    let small = repo.create_box(1, size("S", [20, 15, 10], 5)).await.unwrap();
+    repo.create_box(2, size("S", [20, 15, 10], 5)).await.unwrap();
+    assert!(repo.box_code_exists(1, "S").await.unwrap());
+    assert!(!repo.box_code_exists(1, "M").await.unwrap());
+
+    let codes = |boxes: Vec<BoxSize>| boxes.into_iter().map(|size| size.box_id).collect::<Vec<_>>();
+    assert_eq!(codes(repo.boxes(1, true).await.unwrap()), [small.box_id, large.box_id]);
+
+    let retired = repo
+        .update_box(large.box_id, UpdateBoxSize { is_active: Some(false), ..Default::default() })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(retired.max_weight_kg, Decimal::from(25));
+    assert_eq!(codes(repo.boxes(1, true).await.unwrap()), [small.box_id]);
+    assert_eq!(codes(repo.boxes(1, false).await.unwrap()), [small.box_id, large.box_id]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn packed_cartons_record_their_box(pool: PgPool) {
+    let boxes = CartonizationRepository::new(pool.clone());
+    let waves = WaveRepository::new(pool.clone());
+    let medium = boxes.create_box(1, size("M", [40, 30, 20], 15)).await.unwrap();
+    let retired = boxes.create_box(1, size("X", [40, 30, 30], 15)).await.unwrap();
+    boxes.update_box(retired.box_id, UpdateBoxSize { is_active: Some(false), ..Default::default() }).await.unwrap();
+    let elsewhere = boxes.create_box(2, size("M", [40, 30, 20], 15)).await.unwrap();
+
+    let detail = order(&pool, &[(2, 10), (3, 4)]).await;
+    let lines = boxes.packing_lines(detail.order.order_id).await.unwrap();
+    assert_eq!(lines.iter().map(|line| line.quantity).collect::<Vec<_>>(), [Decimal::from(10), Decimal::from(4)]);
+
+    let wave_id = waves
+        .create(CreateWave {
+            warehouse_id: 1,
+            zone_id: None,
+            carrier_code: None,
+            cutoff_date: None,
+            order_ids: None,
+            max_orders: None,
+        })
+        .await
+        .unwrap()
+        .wave
+        .wave_id;
+    let released = waves.release(wave_id).await.unwrap().unwrap();
+    for task in &released.tasks {
+        waves.confirm_pick(wave_id, task.task_id, ConfirmPick { quantity_picked: task.quantity }).await.unwrap();
+    }
+
+    let pack = |box_id| PackCarton {
+        order_id: detail.order.order_id,
+        box_id: Some(box_id),
+        lines: vec![PackCartonLine { line_id: detail.lines[0].line_id, quantity: Decimal::from(10) }],
+    };
+    for rejected in [retired.box_id, elsewhere.box_id] {
+        let err = waves.pack(wave_id, pack(rejected)).await.unwrap_err();
+        assert!(err.downcast_ref::<RuleViolation>().is_some());
+    }
+    let (_, carton) = waves.pack(wave_id, pack(medium.box_id)).await.unwrap().unwrap();
+    assert_eq!(carton.carton.box_id, Some(medium.box_id));
+
+    // Only the unpacked line is left to cartonize
+    let lines = boxes.packing_lines(detail.order.order_id).await.unwrap();
+    assert_eq!(lines.len(), 1);
+    assert_eq!((lines[0].item_id, lines[0].quantity), (3, Decimal::from(4)));
+}
@@ -0,0 +1,139 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// Helmets (item 3, 50 on hand in warehouse 1) at 2.5 kg a piece, give or take 4%
+async fn catch_weight_helmets(pool: &PgPool) {
+    let profile = CatchWeightProfile { nominal_weight_kg: Decimal::new(25, 1), tolerance_pct: Decimal::from(4) };
+    assert!(CatchWeightRepository::new(pool.clone()).set_profile(3, Some(profile)).await.unwrap());
+}
+
+async fn stock_weight(pool: &PgPool, item_id: i32) -> Option<Decimal> {
+    sqlx::query_scalar("SELECT catch_weight_kg FROM warehouse.stock_inventory WHERE item_id = $1 AND warehouse_id = 1")
+        .bind(item_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+fn counted(line_id: i32, quantity: i64, weight_kg: Option<Decimal>) -> ReceiveGoods {
+    ReceiveGoods {
+        lines: vec![ReceiveGoodsLine { line_id, quantity: Decimal::from(quantity), weight_kg, lot_number: None }],
+        reference_id: None,
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn receipt_records_weight_within_tolerance(pool: PgPool) {
+    catch_weight_helmets(&pool).await;
+    assert_eq!(stock_weight(&pool, 3).await, Some(Decimal::from(125)));
+
+    let receipts = ReceiptRepository::new(pool.clone());
+    let created = receipts
+        .create(CreateReceipt {
+            asn_number: "ASN-CW".to_string(),
+            warehouse_id: 1,
+            supplier_name: None,
+            purchase_order_number: None,
+            carrier_code: None,
+            expected_date: None,
+            consignor_id: None,
+            lines: vec![CreateReceiptLine { item_id: 3, quantity_expected: Decimal::from(10), lpn_number: None }],
+        })
+        .await
+        .unwrap();
+    let (id, line_id) = (created.receipt.receipt_id, created.lines[0].line_id);
+
+    let missing = receipts.receive(id, counted(line_id, 10, None)).await.unwrap_err();
+    assert!(missing.to_string().contains("record the actual weight"), "{}", missing);
+    // 10 pieces should weigh 24 to 26 kg
+    let heavy = receipts.receive(id, counted(line_id, 10, Some(Decimal::from(30)))).await.unwrap_err();
+    assert!(heavy.to_string().contains("outside the 4% tolerance"), "{}", heavy);
+
+    let received = receipts.receive(id, counted(line_id, 10, Some(Decimal::new(255, 1)))).await.unwrap().unwrap();
+    assert_eq!(received.lines[0].weight_received_kg, Some(Decimal::new(255, 1)));
+    assert_eq!(stock_weight(&pool, 3).await, Some(Decimal::new(1505, 1)));
+    assert_eq!(stock_weight(&pool, 2).await, None);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn shipment_and_other_movements_carry_weight(pool: PgPool) {
+    catch_weight_helmets(&pool).await;
+
+    let orders = OrderRepository::new(pool.clone());
+    let created = orders
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "PT Karya Beton".to_string(),
+            customer_reference: None,
+            ship_to_name: None,
+            ship_to_address: None,
+            ship_to_city: None,
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![
+                CreateOrderLine { item_id: 3, quantity_ordered: Decimal::from(4) },
+                CreateOrderLine { item_id: 2, quantity_ordered: Decimal::from(1) },
+            ],
+        })
+        .await
+        .unwrap();
+    let ship = |lines: Vec<ShipOrderLine>| ShipOrder {
+        carrier_code: None,
+        tracking_number: None,
+        service_level: None,
+        lines,
+        lpns: Vec::new(),
+        reference_id: None,
+    };
+    let (helmets, rebar) = (created.lines[0].line_id, created.lines[1].line_id);
+    let line = |line_id, quantity, weight_kg| ShipOrderLine { line_id, quantity: Decimal::from(quantity), weight_kg, lot_number: None };
+
+    let not_catch_weight =
+        orders.ship(created.order.order_id, ship(vec![line(rebar, 1, Some(Decimal::ONE))]), None).await.unwrap_err();
+    assert!(not_catch_weight.downcast_ref::<RuleViolation>().is_some());
+
+    let (_, shipment) = orders
+        .ship(
+            created.order.order_id,
+            ship(vec![line(helmets, 4, Some(Decimal::new(102, 1))), line(rebar, 1, None)]),
+            None,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(shipment.lines[0].weight_kg, Some(Decimal::new(102, 1)));
+    assert_eq!(shipment.lines[1].weight_kg, None);
+    assert_eq!(stock_weight(&pool, 3).await, Some(Decimal::new(1148, 1)));
+
+    // Movements without a captured weight move the nominal weight
+    let mut tx = pool.begin().await.unwrap();
+    let adjusted = post_movement(
+        &mut tx,
+        NewMovement {
+            item_id: 3,
+            warehouse_id: 1,
+            movement_type: "ADJUSTMENT".to_string(),
+            quantity: Decimal::from(-2),
+            catch_weight_kg: None,
+            lot_number: None,
+            unit_cost: None,
+            stock_status: STOCK_AVAILABLE,
+            consignor_id: None,
+            reference_type: None,
+            reference_number: None,
+            notes: None,
+            created_by: Some(1),
+        },
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+    assert_eq!(adjusted.catch_weight_kg, Some(Decimal::from(-5)));
+    assert_eq!(stock_weight(&pool, 3).await, Some(Decimal::new(1098, 1)));
+}
@@ -0,0 +1,101 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn client(pool: &PgPool, code: &str, item_ids: Vec<i32>) -> BillingClient {
+    let repo = BillingRepository::new(pool.clone());
+    let client = repo
+        .create_client(CreateBillingClient {
+            client_code: code.to_string(),
+            client_name: format!("{} Retail", code),
+            currency: None,
+        })
+        .await
+        .unwrap();
+    repo.assign_items(client.client_id, AssignClientItems { item_ids }).await.unwrap();
+    client
+}
+
+async fn receive(pool: &PgPool, item_id: i32, quantity: i64) -> StockMovement {
+    let mut tx = pool.begin().await.unwrap();
+    let movement = post_movement(
+        &mut tx,
+        NewMovement {
+            item_id,
+            warehouse_id: 1,
+            movement_type: "RECEIPT".to_string(),
+            quantity: Decimal::from(quantity),
+            catch_weight_kg: None,
+            lot_number: None,
+            unit_cost: None,
+            stock_status: STOCK_AVAILABLE,
+            consignor_id: None,
+            reference_type: None,
+            reference_number: None,
+            notes: None,
+            created_by: Some(1),
+        },
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+    movement
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn stock_and_movements_are_segregated_by_owner(pool: PgPool) {
+    let acme = client(&pool, "ACME", vec![2]).await;
+    let beta = client(&pool, "BETA", vec![3]).await;
+
+    let owned = receive(&pool, 2, 10).await;
+    assert_eq!(owned.client_id, Some(acme.client_id));
+    receive(&pool, 3, 5).await;
+    let ours = receive(&pool, 1, 1).await;
+    assert_eq!(ours.client_id, None);
+
+    let clients = ClientRepository::new(pool.clone());
+    let stock = clients.stock(acme.client_id, ClientStockFilter::default()).await.unwrap();
+    let balances: Vec<_> = stock.iter().map(|s| (s.item_id, s.quantity_on_hand)).collect();
+    // Stock on hand before the assignment passed to the client with the item
+    assert_eq!(balances, [(2, Decimal::from(1010))]);
+    let filtered = ClientStockFilter { item_id: Some(3), ..Default::default() };
+    assert!(clients.stock(acme.client_id, filtered).await.unwrap().is_empty());
+
+    let movements = MovementRepository::new(pool.clone());
+    let history = movements
+        .history(MovementFilter { client_id: Some(beta.client_id), ..Default::default() }, KeysetQuery::default())
+        .await
+        .unwrap();
+    let items: Vec<_> = history.data.iter().map(|m| m.item_id).collect();
+    assert_eq!(items, [3]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn api_keys_resolve_to_their_active_client(pool: PgPool) {
+    let acme = client(&pool, "ACME", vec![2]).await;
+    let clients = ClientRepository::new(pool.clone());
+
+    let issued =
+        clients.issue_key(acme.client_id, CreateClientApiKey { label: Some("portal".to_string()) }).await.unwrap();
+    assert!(issued.api_key.starts_with(CLIENT_KEY_PREFIX));
+    assert_eq!(issued.key.key_prefix, issued.api_key[..12]);
+
+    let resolved = clients.authenticate(&issued.api_key).await.unwrap().unwrap();
+    assert_eq!(resolved.client_id, acme.client_id);
+    let keys = clients.keys(acme.client_id).await.unwrap();
+    assert!(keys[0].last_used_at.is_some());
+
+    assert!(clients.authenticate("wck_not-a-key").await.unwrap().is_none());
+    assert!(clients.authenticate(&issued.api_key[..20]).await.unwrap().is_none());
+
+    // A deactivated client loses access without its keys being revoked
+    sqlx::query("UPDATE warehouse.billing_clients SET is_active = FALSE").execute(&pool).await.unwrap();
+    assert!(clients.authenticate(&issued.api_key).await.unwrap().is_none());
+    sqlx::query("UPDATE warehouse.billing_clients SET is_active = TRUE").execute(&pool).await.unwrap();
+
+    let revoked = clients.revoke_key(acme.client_id, issued.key.key_id).await.unwrap().unwrap();
+    assert!(revoked.revoked_at.is_some());
+    assert!(clients.authenticate(&issued.api_key).await.unwrap().is_none());
+    assert!(clients.revoke_key(acme.client_id + 1, issued.key.key_id).await.unwrap().is_none());
+}
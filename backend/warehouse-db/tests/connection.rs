@@ -0,0 +1,104 @@
+use std::env;
+use std::time::Duration;
+
+use warehouse_db::{ConnectionSettings, DatabaseManager};
+
+fn settings() -> ConnectionSettings {
+    dotenvy::dotenv().ok();
+    ConnectionSettings {
+        url: env::var("DATABASE_URL").expect("DATABASE_URL must be set"),
+        max_connections: 2,
+        min_connections: 0,
+        acquire_timeout: Duration::from_secs(5),
+        idle_timeout: None,
+        max_lifetime: None,
+        ssl_mode: None,
+        ssl_root_cert: None,
+        application_name: None,
+        session_settings: Vec::new(),
+    }
+}
+
+#[tokio::test]
+async fn connections_carry_application_name_and_session_settings() {
+    let pool = DatabaseManager::connect(&ConnectionSettings {
+        ssl_mode: Some("prefer".to_string()),
+        application_name: Some("warehouse-test".to_string()),
+        session_settings: vec![
+            ("statement_timeout".to_string(), "15s".to_string()),
+            ("lock_timeout".to_string(), "2s".to_string()),
+        ],
+        ..settings()
+    })
+    .await
+    .unwrap();
+
+    // Every pooled connection is configured, not only the first
+    for _ in 0..2 {
+        let mut conn = pool.acquire().await.unwrap();
+        let name: String = sqlx::query_scalar("SHOW application_name").fetch_one(&mut *conn).await.unwrap();
+        let statement_timeout: String =
+            sqlx::query_scalar("SHOW statement_timeout").fetch_one(&mut *conn).await.unwrap();
+        let lock_timeout: String = sqlx::query_scalar("SHOW lock_timeout").fetch_one(&mut *conn).await.unwrap();
+        assert_eq!(name, "warehouse-test");
+        assert_eq!(statement_timeout, "15s");
+        assert_eq!(lock_timeout, "2s");
+        conn.detach();
+    }
+}
+
+#[tokio::test]
+async fn explicit_options_override_the_url() {
+    let base = settings();
+    let url = format!("{}?sslmode=require&application_name=from-url", base.url);
+    let options = DatabaseManager::connect_options(&ConnectionSettings {
+        url,
+        ssl_mode: Some("disable".to_string()),
+        application_name: Some("from-settings".to_string()),
+        ..base
+    })
+    .unwrap();
+
+    // sslmode=require against a server without TLS would fail to connect
+    let pool = sqlx::postgres::PgPoolOptions::new().max_connections(1).connect_with(options).await.unwrap();
+    let name: String = sqlx::query_scalar("SHOW application_name").fetch_one(&pool).await.unwrap();
+    assert_eq!(name, "from-settings");
+}
+
+#[tokio::test]
+async fn rejects_unknown_ssl_modes_and_unknown_settings() {
+    let bad_mode = ConnectionSettings { ssl_mode: Some("sometimes".to_string()), ..settings() };
+    assert!(DatabaseManager::connect_options(&bad_mode).is_err());
+
+    let bad_setting = ConnectionSettings {
+        session_settings: vec![("statement_timout".to_string(), "15s".to_string())],
+        acquire_timeout: Duration::from_secs(1),
+        ..settings()
+    };
+    assert!(DatabaseManager::connect(&bad_setting).await.is_err());
+}
+
+#[tokio::test]
+async fn pool_is_sized_from_the_settings() {
+    let pool = DatabaseManager::connect(&ConnectionSettings {
+        max_connections: 3,
+        min_connections: 1,
+        idle_timeout: Some(Duration::from_secs(30)),
+        max_lifetime: Some(Duration::from_secs(60)),
+        ..settings()
+    })
+    .await
+    .unwrap();
+
+    let options = pool.options();
+    assert_eq!(options.get_max_connections(), 3);
+    assert_eq!(options.get_min_connections(), 1);
+    assert_eq!(options.get_acquire_timeout(), Duration::from_secs(5));
+    assert_eq!(options.get_idle_timeout(), Some(Duration::from_secs(30)));
+    assert_eq!(options.get_max_lifetime(), Some(Duration::from_secs(60)));
+
+    // The fourth checkout waits out the acquire timeout instead of opening another connection
+    let _held: Vec<_> = futures::future::try_join_all((0..3).map(|_| pool.acquire())).await.unwrap();
+    let blocked = tokio::time::timeout(Duration::from_millis(200), pool.acquire()).await;
+    assert!(blocked.is_err());
+}
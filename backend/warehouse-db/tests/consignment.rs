@@ -0,0 +1,180 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// Consignor with an agreed price of 12.50 for item 1, which we own 5 of (1 reserved) in warehouse 1
+async fn consignor(repo: &ConsignmentRepository) -> Consignor {
+    let consignor = repo
+        .create(CreateConsignor {
+            consignor_code: "ACME".to_string(),
+            consignor_name: "Acme Supplies".to_string(),
+            currency: None,
+        })
+        .await
+        .unwrap();
+    repo.set_prices(
+        consignor.consignor_id,
+        SetConsignmentPrices { prices: vec![SetConsignmentPrice { item_id: 1, unit_price: Decimal::new(1250, 2) }] },
+    )
+    .await
+    .unwrap();
+    consignor
+}
+
+fn consigned_asn(number: &str, consignor_id: i32, item_id: i32) -> CreateReceipt {
+    CreateReceipt {
+        asn_number: number.to_string(),
+        warehouse_id: 1,
+        supplier_name: Some("Acme Supplies".to_string()),
+        purchase_order_number: None,
+        carrier_code: None,
+        expected_date: None,
+        consignor_id: Some(consignor_id),
+        lines: vec![CreateReceiptLine { item_id, quantity_expected: Decimal::from(10), lpn_number: None }],
+    }
+}
+
+async fn on_hand(pool: &PgPool, item_id: i32, consignor_id: Option<i32>) -> Decimal {
+    sqlx::query_scalar(
+        "SELECT quantity_on_hand FROM warehouse.stock_inventory
+         WHERE item_id = $1 AND warehouse_id = 1 AND consignor_id IS NOT DISTINCT FROM $2",
+    )
+    .bind(item_id)
+    .bind(consignor_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+async fn issue(pool: &PgPool, item_id: i32, quantity: i64) -> anyhow::Result<StockMovement> {
+    let mut tx = pool.begin().await.unwrap();
+    let movement = post_movement(
+        &mut tx,
+        NewMovement {
+            item_id,
+            warehouse_id: 1,
+            movement_type: "SHIPMENT".to_string(),
+            quantity: Decimal::from(-quantity),
+            catch_weight_kg: None,
+            lot_number: None,
+            unit_cost: None,
+            stock_status: STOCK_AVAILABLE,
+            consignor_id: None,
+            reference_type: Some("SHIPMENT".to_string()),
+            reference_number: Some("SHP-TEST".to_string()),
+            notes: None,
+            created_by: Some(1),
+        },
+    )
+    .await?;
+    tx.commit().await.unwrap();
+    Ok(movement)
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn consigned_receipts_stay_owned_by_the_consignor(pool: PgPool) {
+    let repo = ConsignmentRepository::new(pool.clone());
+    let consignor = consignor(&repo).await;
+    let receipts = ReceiptRepository::new(pool.clone());
+
+    let unpriced = receipts.create(consigned_asn("ASN-C2", consignor.consignor_id, 2)).await.unwrap_err();
+    assert!(unpriced.to_string().contains("no agreed price"), "{}", unpriced);
+
+    let detail = receipts.create(consigned_asn("ASN-C1", consignor.consignor_id, 1)).await.unwrap();
+    let line_id = detail.lines[0].line_id;
+    receipts
+        .receive(
+            detail.receipt.receipt_id,
+            ReceiveGoods {
+                lines: vec![ReceiveGoodsLine { line_id, quantity: Decimal::from(10), weight_kg: None, lot_number: None }],
+                reference_id: None,
+            },
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(on_hand(&pool, 1, None).await, Decimal::from(5));
+    assert_eq!(on_hand(&pool, 1, Some(consignor.consignor_id)).await, Decimal::from(10));
+
+    let stock = repo.stock(consignor.consignor_id).await.unwrap();
+    assert_eq!(stock.len(), 1);
+    assert_eq!(stock[0].unit_price, Some(Decimal::new(1250, 2)));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn issues_beyond_owned_stock_consume_consignment(pool: PgPool) {
+    let repo = ConsignmentRepository::new(pool.clone());
+    let consignor = consignor(&repo).await;
+    let receipts = ReceiptRepository::new(pool.clone());
+    let detail = receipts.create(consigned_asn("ASN-C1", consignor.consignor_id, 1)).await.unwrap();
+    let line_id = detail.lines[0].line_id;
+    receipts
+        .receive(
+            detail.receipt.receipt_id,
+            ReceiveGoods {
+                lines: vec![ReceiveGoodsLine { line_id, quantity: Decimal::from(10), weight_kg: None, lot_number: None }],
+                reference_id: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    // 4 unreserved owned and 4 consumed from the consignor; the reserved unit stays
+    issue(&pool, 1, 8).await.unwrap();
+    assert_eq!(on_hand(&pool, 1, None).await, Decimal::ONE);
+    assert_eq!(on_hand(&pool, 1, Some(consignor.consignor_id)).await, Decimal::from(6));
+
+    let consumption = repo.consumption(consignor.consignor_id, ConsumptionQuery::default()).await.unwrap();
+    assert_eq!(consumption.len(), 1);
+    assert_eq!(consumption[0].quantity, Decimal::from(4));
+    assert_eq!(consumption[0].amount, Decimal::from(50));
+    assert_eq!(consumption[0].unsettled_quantity, Decimal::from(4));
+
+    // More than owned and consigned together still fails as a whole
+    let error = issue(&pool, 1, 8).await.unwrap_err();
+    assert!(error.downcast_ref::<RuleViolation>().is_some(), "{}", error);
+    assert_eq!(on_hand(&pool, 1, Some(consignor.consignor_id)).await, Decimal::from(6));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn settlement_bills_unsettled_consumption_once(pool: PgPool) {
+    let repo = ConsignmentRepository::new(pool.clone());
+    let consignor = consignor(&repo).await;
+    let receipts = ReceiptRepository::new(pool.clone());
+    let detail = receipts.create(consigned_asn("ASN-C1", consignor.consignor_id, 1)).await.unwrap();
+    let line_id = detail.lines[0].line_id;
+    receipts
+        .receive(
+            detail.receipt.receipt_id,
+            ReceiveGoods {
+                lines: vec![ReceiveGoodsLine { line_id, quantity: Decimal::from(10), weight_kg: None, lot_number: None }],
+                reference_id: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    repo.consume(
+        consignor.consignor_id,
+        ConsumeConsignment { warehouse_id: 1, item_id: 1, quantity: Decimal::from(4), notes: None, reference_id: None },
+    )
+    .await
+    .unwrap();
+    assert_eq!(on_hand(&pool, 1, None).await, Decimal::from(9));
+
+    let settlement = repo.settle(consignor.consignor_id, CreateSettlement::default()).await.unwrap();
+    assert!(settlement.settlement.settlement_number.starts_with("CSB-"));
+    assert_eq!(settlement.settlement.currency, "IDR");
+    assert_eq!(settlement.settlement.total_amount, Decimal::from(50));
+    assert_eq!(settlement.lines.len(), 1);
+    assert_eq!(settlement.lines[0].quantity, Decimal::from(4));
+
+    let consumption = repo.consumption(consignor.consignor_id, ConsumptionQuery::default()).await.unwrap();
+    assert_eq!(consumption[0].unsettled_quantity, Decimal::ZERO);
+
+    let error = repo.settle(consignor.consignor_id, CreateSettlement::default()).await.unwrap_err();
+    assert!(error.to_string().contains("no unbilled consumption"), "{}", error);
+    assert_eq!(repo.settlements(consignor.consignor_id).await.unwrap().len(), 1);
+}
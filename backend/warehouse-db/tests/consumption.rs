@@ -0,0 +1,72 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn event(event_id: &str, item_code: &str, quantity: i64) -> ConsumptionEvent {
+    ConsumptionEvent {
+        event_id: event_id.to_string(),
+        item_code: item_code.to_string(),
+        quantity: Decimal::from(quantity),
+        lot_number: None,
+        reference: Some("WO-7731".to_string()),
+        consumed_at: None,
+    }
+}
+
+fn batch(events: Vec<ConsumptionEvent>) -> ConsumptionBatch {
+    ConsumptionBatch { source: Some("VAN-12".to_string()), events }
+}
+
+async fn on_hand(pool: &PgPool, item_id: i32) -> Decimal {
+    sqlx::query_scalar(
+        "SELECT COALESCE(SUM(quantity_on_hand), 0) FROM warehouse.stock_inventory
+         WHERE item_id = $1 AND warehouse_id = 1",
+    )
+    .bind(item_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn events_are_issued_once(pool: PgPool) {
+    let repo = ConsumptionRepository::new(pool.clone());
+    let before = on_hand(&pool, 3).await;
+
+    let events = vec![event("POS-1", "ITM003", 2), event("POS-2", "ITM003", 3), event("POS-1", "ITM003", 2)];
+    let result = repo.ingest(1, batch(events.clone())).await.unwrap();
+    assert_eq!(result.received, 3);
+    assert_eq!(result.movements.len(), 2);
+    assert_eq!(result.duplicates, vec!["POS-1".to_string()]);
+    assert!(result.movements.iter().all(|movement| movement.movement_type == MOVEMENT_CONSUMPTION));
+    assert_eq!(on_hand(&pool, 3).await, before - Decimal::from(5));
+
+    // The device sends the batch again after losing the response
+    let again = repo.ingest(1, batch(events)).await.unwrap();
+    assert!(again.movements.is_empty());
+    assert_eq!(again.duplicates.len(), 3);
+    assert_eq!(on_hand(&pool, 3).await, before - Decimal::from(5));
+
+    let records = repo.events(1, KeysetQuery::default()).await.unwrap();
+    assert_eq!(records.data.len(), 2);
+    assert_eq!(records.data[0].source.as_deref(), Some("VAN-12"));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn events_that_cannot_be_issued_are_left_for_a_retry(pool: PgPool) {
+    let repo = ConsumptionRepository::new(pool.clone());
+    let before = on_hand(&pool, 3).await;
+
+    let events = vec![event("POS-1", "NOPE", 1), event("POS-2", "ITM003", 100_000), event("POS-3", "ITM003", 1)];
+    let result = repo.ingest(1, batch(events)).await.unwrap();
+    assert_eq!(result.movements.len(), 1);
+    assert_eq!(result.exceptions.len(), 2, "{:?}", result.exceptions);
+    assert!(result.exceptions[0].starts_with("POS-1"));
+    assert!(result.exceptions[1].starts_with("POS-2"));
+    assert_eq!(on_hand(&pool, 3).await, before - Decimal::ONE);
+
+    let retry = repo.ingest(1, batch(vec![event("POS-2", "ITM003", 1)])).await.unwrap();
+    assert_eq!(retry.movements.len(), 1);
+    assert!(retry.duplicates.is_empty());
+}
@@ -0,0 +1,173 @@
+use futures::future::join_all;
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn order(lines: &[(i32, i64)]) -> CreateOrder {
+    CreateOrder {
+        warehouse_id: 1,
+        customer_name: "PT Karya Beton".to_string(),
+        customer_reference: None,
+        ship_to_name: None,
+        ship_to_address: None,
+        ship_to_city: None,
+        ship_to_state: None,
+        ship_to_postal_code: None,
+        ship_to_country: None,
+        requested_ship_date: None,
+        carrier_code: None,
+        dropship_supplier_code: None,
+        lines: lines
+            .iter()
+            .map(|&(item_id, quantity)| CreateOrderLine { item_id, quantity_ordered: Decimal::from(quantity) })
+            .collect(),
+    }
+}
+
+/// Ship every line of an order in full
+fn ship_all(detail: &OrderDetail) -> ShipOrder {
+    ShipOrder {
+        carrier_code: None,
+        tracking_number: None,
+        service_level: None,
+        lines: detail
+            .lines
+            .iter()
+            .map(|line| ShipOrderLine {
+                line_id: line.line_id,
+                quantity: line.quantity_ordered,
+                weight_kg: None,
+                lot_number: None,
+            })
+            .collect(),
+        lpns: Vec::new(),
+        reference_id: None,
+    }
+}
+
+async fn on_hand(pool: &PgPool, item_id: i32) -> Decimal {
+    sqlx::query_scalar("SELECT quantity_on_hand FROM warehouse.stock_inventory WHERE item_id = $1 AND warehouse_id = 1")
+        .bind(item_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+/// Open ASNs for `count` deliveries of `quantity` of an item into warehouse 1
+async fn asns(pool: &PgPool, item_id: i32, quantity: i64, count: usize) -> Vec<ReceiptDetail> {
+    let receipts = ReceiptRepository::new(pool.clone());
+    let mut created = Vec::with_capacity(count);
+    for n in 0..count {
+        let asn = CreateReceipt {
+            asn_number: format!("ASN-RACE-{}", n),
+            warehouse_id: 1,
+            supplier_name: None,
+            purchase_order_number: None,
+            carrier_code: None,
+            expected_date: None,
+            consignor_id: None,
+            lines: vec![CreateReceiptLine { item_id, quantity_expected: Decimal::from(quantity), lpn_number: None }],
+        };
+        created.push(receipts.create(asn).await.unwrap());
+    }
+    created
+}
+
+fn receive_all(detail: &ReceiptDetail) -> ReceiveGoods {
+    let lines = detail
+        .lines
+        .iter()
+        .map(|line| ReceiveGoodsLine {
+            line_id: line.line_id,
+            quantity: line.quantity_expected,
+            weight_kg: None,
+            lot_number: None,
+        })
+        .collect();
+    ReceiveGoods { lines, reference_id: None }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn concurrent_shipments_cannot_take_the_same_units(pool: PgPool) {
+    let receipts = ReceiptRepository::new(pool.clone());
+    let delivery = asns(&pool, 4, 3, 1).await.remove(0);
+    receipts.receive(delivery.receipt.receipt_id, receive_all(&delivery)).await.unwrap().unwrap();
+
+    let orders = OrderRepository::new(pool.clone());
+    let mut pending = Vec::new();
+    for _ in 0..8 {
+        pending.push(orders.create(order(&[(4, 1)])).await.unwrap());
+    }
+
+    let results =
+        join_all(pending.iter().map(|detail| orders.ship(detail.order.order_id, ship_all(detail), None))).await;
+
+    let shipped = results.iter().filter(|result| result.is_ok()).count();
+    assert_eq!(shipped, 3);
+    for error in results.iter().filter_map(|result| result.as_ref().err()) {
+        assert!(error.downcast_ref::<NegativeStock>().is_some(), "{:?}", error);
+    }
+    assert_eq!(on_hand(&pool, 4).await, Decimal::ZERO);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn concurrent_first_receipts_share_one_balance(pool: PgPool) {
+    let receipts = ReceiptRepository::new(pool.clone());
+    let deliveries = asns(&pool, 4, 5, 8).await;
+
+    let results =
+        join_all(deliveries.iter().map(|detail| receipts.receive(detail.receipt.receipt_id, receive_all(detail))))
+            .await;
+
+    for result in results {
+        result.unwrap().unwrap();
+    }
+    assert_eq!(on_hand(&pool, 4).await, Decimal::from(40));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn shipments_of_items_in_opposite_order_do_not_deadlock(pool: PgPool) {
+    let orders = OrderRepository::new(pool.clone());
+    let mut pending = Vec::new();
+    for n in 0..10 {
+        let lines = if n % 2 == 0 { [(2, 1), (3, 1)] } else { [(3, 1), (2, 1)] };
+        pending.push(orders.create(order(&lines)).await.unwrap());
+    }
+
+    let results =
+        join_all(pending.iter().map(|detail| orders.ship(detail.order.order_id, ship_all(detail), None))).await;
+
+    for result in results {
+        result.unwrap().unwrap();
+    }
+    assert_eq!(on_hand(&pool, 2).await, Decimal::from(990));
+    assert_eq!(on_hand(&pool, 3).await, Decimal::from(40));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn parallel_issues_never_take_stock_below_zero(pool: PgPool) {
+    let receipts = ReceiptRepository::new(pool.clone());
+    let delivery = asns(&pool, 4, 10, 1).await.remove(0);
+    receipts.receive(delivery.receipt.receipt_id, receive_all(&delivery)).await.unwrap().unwrap();
+
+    let tasks: Vec<_> = (0..16)
+        .map(|_| {
+            let stock = StockRepository::new(pool.clone());
+            tokio::spawn(async move {
+                let issue = IssueStock { quantity: Decimal::from(3), reference: None, notes: None };
+                stock.issue(1, 4, issue).await
+            })
+        })
+        .collect();
+    let results: Vec<_> = join_all(tasks).await.into_iter().map(|task| task.unwrap()).collect();
+
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 3);
+    for error in results.iter().filter_map(|result| result.as_ref().err()) {
+        assert!(rejection(error).is_some_and(|message| message.contains("only 1 available")), "{:?}", error);
+    }
+    assert_eq!(on_hand(&pool, 4).await, Decimal::ONE);
+
+    let ledger = LedgerRepository::new(pool.clone()).integrity().await.unwrap();
+    assert!(ledger.reconciled, "{:?}", ledger);
+}
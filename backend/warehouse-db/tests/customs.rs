@@ -0,0 +1,95 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// Ship 10 rebar and 2 helmets from warehouse 1 (Indonesia) to `country`
+async fn shipment(pool: &PgPool, country: &str) -> i32 {
+    let orders = OrderRepository::new(pool.clone());
+    let created = orders
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "Lion Build Pte Ltd".to_string(),
+            customer_reference: None,
+            ship_to_name: None,
+            ship_to_address: None,
+            ship_to_city: None,
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: Some(country.to_string()),
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![
+                CreateOrderLine { item_id: 2, quantity_ordered: Decimal::from(10) },
+                CreateOrderLine { item_id: 3, quantity_ordered: Decimal::from(2) },
+            ],
+        })
+        .await
+        .unwrap();
+    let lines = created
+        .lines
+        .iter()
+        .map(|line| ShipOrderLine { line_id: line.line_id, quantity: line.quantity_ordered, weight_kg: None, lot_number: None })
+        .collect();
+    let confirmation = ShipOrder {
+        carrier_code: None,
+        tracking_number: None,
+        service_level: None,
+        lines,
+        lpns: Vec::new(),
+        reference_id: None,
+    };
+    let (_, shipment) = orders.ship(created.order.order_id, confirmation, None).await.unwrap().unwrap();
+    shipment.shipment.shipment_id
+}
+
+fn customs(hs_code: &str, description: &str) -> CustomsData {
+    CustomsData {
+        hs_code: hs_code.to_string(),
+        country_of_origin: "ID".to_string(),
+        customs_description: description.to_string(),
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn domestic_shipments_need_no_customs_data(pool: PgPool) {
+    let repo = CustomsRepository::new(pool.clone());
+    let shipment_id = shipment(&pool, "indonesia").await;
+
+    let declaration = repo.declaration(shipment_id).await.unwrap().unwrap();
+    assert!(!declaration.international);
+    assert_eq!(declaration.lines.len(), 2);
+    assert_eq!(declaration.lines[0].description, "Steel Rebar 12mm");
+    assert_eq!(declaration.total_value, None);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn international_declaration_lists_customs_data(pool: PgPool) {
+    let repo = CustomsRepository::new(pool.clone());
+    let shipment_id = shipment(&pool, "Singapore").await;
+    assert!(repo.set_item_customs(2, Some(customs("7214200000", "Steel reinforcing bars"))).await.unwrap());
+
+    let error = repo.declaration(shipment_id).await.unwrap_err();
+    assert!(error.to_string().contains("ITM003 have no customs data"), "{}", error);
+
+    assert!(repo.set_item_customs(3, Some(customs("650610", "Plastic safety helmets"))).await.unwrap());
+    sqlx::query("UPDATE warehouse.items SET standard_cost = 85000, weight_kg = 0.9 WHERE item_id = 2")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let declaration = repo.declaration(shipment_id).await.unwrap().unwrap();
+    assert!(declaration.international);
+    assert_eq!(declaration.origin_country.as_deref(), Some("Indonesia"));
+    let codes: Vec<_> = declaration.lines.iter().map(|l| l.hs_code.as_deref().unwrap()).collect();
+    assert_eq!(codes, ["650610", "7214200000"]);
+    let rebar = &declaration.lines[1];
+    assert_eq!(rebar.description, "Steel reinforcing bars");
+    assert_eq!(rebar.value, Some(Decimal::from(850_000)));
+    assert_eq!(rebar.weight_kg, Some(Decimal::from(9)));
+    // Helmets have neither a cost nor a weight on file
+    assert_eq!(declaration.total_value, None);
+    assert_eq!(declaration.total_weight_kg, None);
+
+    assert!(!repo.set_item_customs(999, None).await.unwrap());
+}
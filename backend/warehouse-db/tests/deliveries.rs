@@ -0,0 +1,116 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::{Days, NaiveDate, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// A project, item 2 weighing 10 kg, and warehouse 1 delivering every day with a 200 kg
+/// van and a 500 kg truck
+async fn delivering_project(pool: &PgPool) -> (Database, Project) {
+    sqlx::query("UPDATE warehouse.items SET weight_kg = 10 WHERE item_id = 2").execute(pool).await.unwrap();
+    let db = Database::new(pool.clone());
+    let calendar = SetDeliveryCalendar { delivery_weekdays: vec![1, 2, 3, 4, 5, 6, 7], closures: vec![] };
+    db.deliveries().set_calendar(1, calendar).await.unwrap();
+    for (code, capacity) in [("VAN-1", 200), ("TRUCK-1", 500)] {
+        let vehicle = CreateDeliveryVehicle {
+            vehicle_code: code.to_string(),
+            description: None,
+            capacity_kg: Decimal::from(capacity),
+        };
+        db.deliveries().add_vehicle(1, vehicle).await.unwrap();
+    }
+
+    let project = db
+        .projects()
+        .create(CreateProject {
+            project_code: "PRJ-TOWER".to_string(),
+            project_name: "Tower block".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    (db, project)
+}
+
+fn request(quantity: i64, needed_by: NaiveDate) -> RequestSiteDelivery {
+    RequestSiteDelivery {
+        warehouse_id: 1,
+        needed_by,
+        site_name: "Tower block site".to_string(),
+        site_address: "Jl. Sudirman 12".to_string(),
+        site_city: Some("Jakarta".to_string()),
+        site_contact: None,
+        lines: vec![SiteDeliveryLineRequest { item_id: 2, quantity: Decimal::from(quantity) }],
+    }
+}
+
+/// Request a delivery and schedule it wherever it fits
+async fn scheduled(db: &Database, project_id: i32, quantity: i64, needed_by: NaiveDate) -> SiteDelivery {
+    let requested = db.deliveries().request(project_id, request(quantity, needed_by)).await.unwrap().unwrap();
+    let id = requested.delivery.delivery_id;
+    db.deliveries().schedule(id, ScheduleSiteDelivery::default()).await.unwrap().unwrap().delivery
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn deliveries_take_the_latest_day_with_room_on_a_vehicle(pool: PgPool) {
+    let (db, project) = delivering_project(&pool).await;
+    let vehicles = db.deliveries().vehicles(1).await.unwrap();
+    let (truck, van) = (vehicles[0].vehicle_id, vehicles[1].vehicle_id);
+    let today = Utc::now().date_naive();
+    let needed_by = today + Days::new(3);
+
+    let requested = db.deliveries().request(project.project_id, request(15, needed_by)).await.unwrap().unwrap();
+    assert_eq!(requested.delivery.status, SITE_DELIVERY_REQUESTED);
+    assert_eq!(requested.delivery.weight_kg, Decimal::from(150));
+
+    let first = db.deliveries().schedule(requested.delivery.delivery_id, ScheduleSiteDelivery::default());
+    let first = first.await.unwrap().unwrap().delivery;
+    assert_eq!((first.scheduled_date, first.vehicle_id), (Some(needed_by), Some(van)));
+    let order = db.orders().get_by_id(first.order_id.unwrap()).await.unwrap().unwrap().order;
+    assert_eq!(order.source, SOURCE_PROJECT);
+    assert_eq!(order.requested_ship_date, Some(needed_by));
+    assert_eq!(order.customer_reference, Some(first.delivery_number.clone()));
+    assert!(db.deliveries().schedule(first.delivery_id, ScheduleSiteDelivery::default()).await.is_err());
+
+    // The van has 50 kg left, so the next goes on the truck
+    let second = scheduled(&db, project.project_id, 10, needed_by).await;
+    assert_eq!((second.scheduled_date, second.vehicle_id), (Some(needed_by), Some(truck)));
+
+    // Neither has 450 kg left that day, and the day before is closed
+    let closure = DeliveryClosure { closed_on: today + Days::new(2), reason: Some("Stocktake".to_string()) };
+    let calendar = SetDeliveryCalendar { delivery_weekdays: vec![1, 2, 3, 4, 5, 6, 7], closures: vec![closure] };
+    db.deliveries().set_calendar(1, calendar).await.unwrap();
+    let third = scheduled(&db, project.project_id, 45, needed_by).await;
+    assert_eq!((third.scheduled_date, third.vehicle_id), (Some(today + Days::new(1)), Some(truck)));
+
+    let loads = db.deliveries().vehicle_loads(1, today, needed_by).await.unwrap();
+    let on = |day, vehicle| loads.iter().find(|l| l.scheduled_date == day && l.vehicle_id == vehicle).unwrap().load_kg;
+    assert_eq!(on(needed_by, van), Decimal::from(150));
+    assert_eq!(on(needed_by, truck), Decimal::from(100));
+    assert_eq!(on(today + Days::new(1), truck), Decimal::from(450));
+
+    // Nothing carries 600 kg
+    let heavy = db.deliveries().request(project.project_id, request(60, needed_by)).await.unwrap().unwrap();
+    assert!(db.deliveries().schedule(heavy.delivery.delivery_id, ScheduleSiteDelivery::default()).await.is_err());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn cancelling_a_delivery_cancels_its_order_and_frees_the_vehicle(pool: PgPool) {
+    let (db, project) = delivering_project(&pool).await;
+    let needed_by = Utc::now().date_naive() + Days::new(1);
+
+    let first = scheduled(&db, project.project_id, 40, needed_by).await;
+    let cancelled = db.deliveries().cancel(first.delivery_id).await.unwrap().unwrap().delivery;
+    assert_eq!(cancelled.status, SITE_DELIVERY_CANCELLED);
+    let order = db.orders().get_by_id(first.order_id.unwrap()).await.unwrap().unwrap().order;
+    assert_eq!(order.status, ORDER_CANCELLED);
+    assert!(db.deliveries().cancel(first.delivery_id).await.is_err());
+
+    // The truck's room is back for the day it is needed
+    let again = scheduled(&db, project.project_id, 40, needed_by).await;
+    assert_eq!(again.scheduled_date, Some(needed_by));
+
+    let deliveries = db.deliveries().for_project(project.project_id).await.unwrap().unwrap();
+    assert_eq!(deliveries.len(), 2);
+    assert!(db.deliveries().for_project(999).await.unwrap().is_none());
+}
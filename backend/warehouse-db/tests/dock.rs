@@ -0,0 +1,95 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::{Days, NaiveDate, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn expect_asn(pool: &PgPool, asn_number: &str, expected_date: NaiveDate) -> ReceiptDetail {
+    ReceiptRepository::new(pool.clone())
+        .create(CreateReceipt {
+            asn_number: asn_number.to_string(),
+            warehouse_id: 1,
+            supplier_name: Some("PT Baja Prima".to_string()),
+            purchase_order_number: None,
+            carrier_code: None,
+            expected_date: Some(expected_date),
+            consignor_id: None,
+            lines: vec![CreateReceiptLine { item_id: 2, quantity_expected: Decimal::from(10), lpn_number: None }],
+        })
+        .await
+        .unwrap()
+}
+
+fn statuses(board: &[DockBoardEntry]) -> Vec<(&str, &str, bool)> {
+    board.iter().map(|entry| (entry.reference_number.as_str(), entry.status.as_str(), entry.is_late)).collect()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn the_board_follows_loads_from_expected_to_completed(pool: PgPool) {
+    let today = Utc::now().date_naive();
+    let asn = expect_asn(&pool, "ASN-TODAY", today).await;
+    expect_asn(&pool, "ASN-LATE", today - Days::new(2)).await;
+    expect_asn(&pool, "ASN-LATER", today + Days::new(3)).await;
+    sqlx::query(
+        "INSERT INTO warehouse.purchase_orders (po_number, warehouse_id, supplier_code, supplier_name, expected_date)
+         VALUES ('PO-900001', 1, 'SUP-1', 'PT Baja Prima', CURRENT_DATE)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let transfer_id: i32 = sqlx::query_scalar(
+        "INSERT INTO warehouse.stock_transfers (transfer_number, source_warehouse_id, destination_warehouse_id, status)
+         VALUES ('TRF-900001', 2, 1, 'IN_TRANSIT') RETURNING transfer_id",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let repo = DockRepository::new(pool.clone());
+    let board = repo.board(1, today).await.unwrap();
+    assert_eq!(
+        statuses(&board),
+        [
+            ("ASN-LATE", DOCK_EXPECTED, true),
+            ("ASN-TODAY", DOCK_EXPECTED, false),
+            ("PO-900001", DOCK_EXPECTED, false),
+            ("TRF-900001", DOCK_EXPECTED, false),
+        ]
+    );
+    assert_eq!(board[3].shipper.as_deref(), Some("WH002"));
+
+    let receipt_id = asn.receipt.receipt_id;
+    let check_in = |source: &str, reference_id| CheckInArrival {
+        source: source.to_string(),
+        reference_id,
+        dock_door: Some("D3".to_string()),
+        notes: None,
+    };
+    repo.check_in(1, check_in(DOCK_SOURCE_ASN, receipt_id)).await.unwrap().unwrap();
+    let twice = repo.check_in(1, check_in(DOCK_SOURCE_ASN, receipt_id)).await.unwrap_err();
+    assert!(twice.to_string().contains("already arrived"), "{}", twice);
+    repo.check_in(1, check_in(DOCK_SOURCE_TRANSFER, transfer_id)).await.unwrap().unwrap();
+    // Not a load for this warehouse
+    assert!(repo.check_in(2, check_in(DOCK_SOURCE_TRANSFER, transfer_id)).await.unwrap().is_none());
+
+    let arrived = repo.board(1, today).await.unwrap();
+    assert_eq!(arrived[1].status, DOCK_ARRIVED);
+    assert_eq!(arrived[1].dock_door.as_deref(), Some("D3"));
+    assert_eq!(arrived[3].status, DOCK_ARRIVED);
+
+    let receipts = ReceiptRepository::new(pool.clone());
+    let line = |quantity: i64| ReceiveGoodsLine {
+        line_id: asn.lines[0].line_id,
+        quantity: Decimal::from(quantity),
+        weight_kg: None,
+        lot_number: None,
+    };
+    receipts.receive(receipt_id, ReceiveGoods { lines: vec![line(4)], reference_id: None }).await.unwrap().unwrap();
+    assert_eq!(repo.board(1, today).await.unwrap()[1].status, DOCK_RECEIVING);
+    receipts.receive(receipt_id, ReceiveGoods { lines: vec![line(6)], reference_id: None }).await.unwrap().unwrap();
+    let completed = repo.board(1, today).await.unwrap();
+    assert_eq!(completed[1].status, DOCK_COMPLETED);
+    assert!(completed[1].completed_at.is_some());
+    let error = repo.check_in(1, check_in(DOCK_SOURCE_ASN, receipt_id)).await.unwrap_err();
+    assert!(error.to_string().contains("it is received"), "{}", error);
+}
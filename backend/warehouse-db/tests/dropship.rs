@@ -0,0 +1,136 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn order(supplier_code: Option<&str>, lines: &[(i32, i64)]) -> CreateOrder {
+    CreateOrder {
+        warehouse_id: 1,
+        customer_name: "Toko Sinar Jaya".to_string(),
+        customer_reference: None,
+        ship_to_name: None,
+        ship_to_address: None,
+        ship_to_city: None,
+        ship_to_state: None,
+        ship_to_postal_code: None,
+        ship_to_country: None,
+        requested_ship_date: None,
+        carrier_code: None,
+        dropship_supplier_code: supplier_code.map(str::to_string),
+        lines: lines
+            .iter()
+            .map(|&(item_id, quantity)| CreateOrderLine { item_id, quantity_ordered: Decimal::from(quantity) })
+            .collect(),
+    }
+}
+
+fn supplier_shipment(tracking_number: &str, lines: &[(i32, i64)]) -> SupplierShipment {
+    SupplierShipment {
+        carrier_code: "jne".to_string(),
+        tracking_number: tracking_number.to_string(),
+        lines: lines
+            .iter()
+            .map(|&(line_id, quantity)| ShipOrderLine { line_id, quantity: Decimal::from(quantity), weight_kg: None, lot_number: None })
+            .collect(),
+    }
+}
+
+fn confirmation(number: &str) -> SupplierConfirmation {
+    SupplierConfirmation { supplier_order_number: number.to_string() }
+}
+
+async fn stock_snapshot(pool: &PgPool) -> (Decimal, i64) {
+    let on_hand = sqlx::query_scalar("SELECT SUM(quantity_on_hand) FROM warehouse.stock_inventory")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    let movements = sqlx::query_scalar("SELECT COUNT(*) FROM warehouse.stock_movements").fetch_one(pool).await.unwrap();
+    (on_hand, movements)
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn dropship_orders_do_not_ship_from_stock(pool: PgPool) {
+    let repo = OrderRepository::new(pool.clone());
+
+    let created = repo.create(order(Some("sup-acme"), &[(2, 10)])).await.unwrap();
+    assert_eq!(created.order.order_type, ORDER_TYPE_DROPSHIP);
+    assert_eq!(created.order.supplier_code.as_deref(), Some("SUP-ACME"));
+
+    let line_id = created.lines[0].line_id;
+    let ship = ShipOrder {
+        carrier_code: Some("JNE".to_string()),
+        tracking_number: None,
+        service_level: None,
+        lines: vec![ShipOrderLine { line_id, quantity: Decimal::from(10), weight_kg: None, lot_number: None }],
+        lpns: Vec::new(),
+        reference_id: None,
+    };
+    let error = repo.ship(created.order.order_id, ship, None).await.unwrap_err();
+    assert!(error.to_string().contains("dropship"), "{}", error);
+
+    let error = repo
+        .record_supplier_shipment(created.order.order_id, supplier_shipment("TRK-1", &[(line_id, 10)]))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("has not been confirmed"), "{}", error);
+
+    // Standard orders cannot be confirmed by a supplier
+    let standard = repo.create(order(None, &[(2, 1)])).await.unwrap();
+    assert_eq!(standard.order.order_type, ORDER_TYPE_STANDARD);
+    let error = repo.confirm_dropship(standard.order.order_id, confirmation("X-1")).await.unwrap_err();
+    assert!(error.to_string().contains("not a dropship order"), "{}", error);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn supplier_shipments_track_progress_without_moving_stock(pool: PgPool) {
+    let repo = OrderRepository::new(pool.clone());
+    let created = repo.create(order(Some("SUP-ACME"), &[(2, 10), (3, 4)])).await.unwrap();
+    let id = created.order.order_id;
+    let (first, second) = (created.lines[0].line_id, created.lines[1].line_id);
+    let before = stock_snapshot(&pool).await;
+
+    let confirmed = repo.confirm_dropship(id, confirmation("ACME-5501")).await.unwrap().unwrap();
+    assert_eq!(confirmed.order.supplier_order_number.as_deref(), Some("ACME-5501"));
+    assert!(confirmed.order.supplier_confirmed_at.is_some());
+    // Confirming again with the same number is harmless; a different one is not
+    repo.confirm_dropship(id, confirmation("ACME-5501")).await.unwrap().unwrap();
+    let error = repo.confirm_dropship(id, confirmation("ACME-9999")).await.unwrap_err();
+    assert!(error.to_string().contains("already confirmed"), "{}", error);
+
+    let (detail, shipment) =
+        repo.record_supplier_shipment(id, supplier_shipment("TRK-1", &[(first, 10)])).await.unwrap().unwrap();
+    assert_eq!(detail.order.status, ORDER_PARTIALLY_SHIPPED);
+    assert!(shipment.shipment.shipped_by_supplier);
+    assert_eq!(shipment.shipment.carrier_code.as_deref(), Some("JNE"));
+    assert_eq!(shipment.shipment.tracking_number.as_deref(), Some("TRK-1"));
+
+    let (detail, _) =
+        repo.record_supplier_shipment(id, supplier_shipment("TRK-2", &[(second, 4)])).await.unwrap().unwrap();
+    assert_eq!(detail.order.status, ORDER_SHIPPED);
+    assert_eq!(repo.shipments_for_order(id).await.unwrap().len(), 2);
+
+    assert_eq!(stock_snapshot(&pool).await, before);
+
+    assert!(repo.confirm_dropship(999_999, confirmation("X")).await.unwrap().is_none());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn waves_leave_dropship_orders_out(pool: PgPool) {
+    let orders = OrderRepository::new(pool.clone());
+    let dropship = orders.create(order(Some("SUP-ACME"), &[(2, 5)])).await.unwrap();
+    let standard = orders.create(order(None, &[(2, 5)])).await.unwrap();
+
+    let wave = WaveRepository::new(pool.clone())
+        .create(CreateWave {
+            warehouse_id: 1,
+            zone_id: None,
+            carrier_code: None,
+            cutoff_date: None,
+            order_ids: Some(vec![dropship.order.order_id, standard.order.order_id]),
+            max_orders: None,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(wave.order_ids, [standard.order.order_id]);
+}
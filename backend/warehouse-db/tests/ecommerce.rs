@@ -0,0 +1,112 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn listing(item_id: i32, external_id: &str, buffer_quantity: Option<i64>) -> SetEcommerceListing {
+    SetEcommerceListing {
+        item_id,
+        external_id: external_id.to_string(),
+        buffer_quantity: buffer_quantity.map(Decimal::from),
+    }
+}
+
+fn channel(listings: Vec<SetEcommerceListing>) -> CreateEcommerceChannel {
+    CreateEcommerceChannel {
+        channel_code: "SHOP".to_string(),
+        platform: ECOMMERCE_SHOPIFY.to_string(),
+        store_url: "https://example.myshopify.com".to_string(),
+        api_key: "shpat_test".to_string(),
+        api_secret: None,
+        location_id: Some("655441491".to_string()),
+        warehouse_id: 1,
+        buffer_quantity: Some(Decimal::from(10)),
+        sync_interval_minutes: 15,
+        listings,
+    }
+}
+
+async fn place_order(pool: &PgPool, item_id: i32, quantity: i64) -> OrderDetail {
+    OrderRepository::new(pool.clone())
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "Shopper".to_string(),
+            customer_reference: None,
+            ship_to_name: None,
+            ship_to_address: None,
+            ship_to_city: None,
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![CreateOrderLine { item_id, quantity_ordered: Decimal::from(quantity) }],
+        })
+        .await
+        .unwrap()
+}
+
+/// Available stock of the seed item, less what open orders need
+async fn unpromised(pool: &PgPool, item_id: i32) -> Decimal {
+    sqlx::query_scalar(
+        "SELECT (SELECT COALESCE(SUM(quantity_available), 0) FROM warehouse.stock_inventory
+                 WHERE item_id = $1 AND warehouse_id = 1)
+              - (SELECT COALESCE(SUM(l.quantity_ordered - l.quantity_shipped), 0)
+                 FROM warehouse.outbound_order_lines l JOIN warehouse.outbound_orders o USING (order_id)
+                 WHERE l.item_id = $1 AND o.warehouse_id = 1 AND o.status IN ('OPEN', 'PARTIALLY_SHIPPED')
+                   AND o.order_type = 'STANDARD')",
+    )
+    .bind(item_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn stock_levels_hold_back_open_orders_and_buffers(pool: PgPool) {
+    let repo = EcommerceRepository::new(pool.clone());
+    let detail = repo.create(channel(vec![listing(2, "1001", None), listing(3, "1002", Some(5))])).await.unwrap();
+    let channel_id = detail.channel.channel_id;
+    let before = unpromised(&pool, 2).await;
+
+    let levels = repo.stock_levels(channel_id).await.unwrap();
+    let rebar = levels.iter().find(|level| level.item_id == 2).unwrap();
+    assert_eq!(rebar.buffer_quantity, Decimal::from(10));
+    assert_eq!(rebar.quantity, (before - Decimal::from(10)).max(Decimal::ZERO).floor());
+    assert!(rebar.is_changed());
+    let helmet = levels.iter().find(|level| level.item_id == 3).unwrap();
+    assert_eq!(helmet.buffer_quantity, Decimal::from(5));
+
+    // A new order counts against the stock until it ships
+    place_order(&pool, 2, 25).await;
+    repo.mark_pushed(channel_id, &levels).await.unwrap();
+
+    let after = repo.stock_levels(channel_id).await.unwrap();
+    let rebar_after = after.iter().find(|level| level.item_id == 2).unwrap();
+    assert_eq!(rebar_after.committed, rebar.committed + Decimal::from(25));
+    assert_eq!(rebar_after.pushed_quantity, Some(rebar.quantity));
+    assert!(rebar_after.is_changed());
+    assert!(!after.iter().find(|level| level.item_id == 3).unwrap().is_changed());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn an_item_is_listed_once_per_channel(pool: PgPool) {
+    let repo = EcommerceRepository::new(pool);
+
+    let error = repo.create(channel(vec![listing(2, "1001", None), listing(2, "1002", None)])).await.unwrap_err();
+    assert!(rejection(&error).is_some());
+    assert!(!repo.code_exists("SHOP").await.unwrap());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn storefront_orders_are_linked_once(pool: PgPool) {
+    let repo = EcommerceRepository::new(pool.clone());
+    let channel_id = repo.create(channel(Vec::new())).await.unwrap().channel.channel_id;
+    let order_id = place_order(&pool, 3, 1).await.order.order_id;
+
+    assert_eq!(repo.imported_order(channel_id, "450789469").await.unwrap(), None);
+    repo.link_order(channel_id, "450789469", order_id).await.unwrap();
+    repo.link_order(channel_id, "450789469", order_id).await.unwrap();
+    assert_eq!(repo.imported_order(channel_id, "450789469").await.unwrap(), Some(order_id));
+}
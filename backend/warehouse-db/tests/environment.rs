@@ -0,0 +1,110 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::{DateTime, Duration, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// A chiller zone held at 2..8 degrees with one sensor in it
+async fn chiller(pool: &PgPool) -> (Zone, EnvironmentRepository) {
+    let zone = LocationRepository::new(pool.clone())
+        .create_zone(
+            1,
+            CreateZone { zone_code: "CHILL".to_string(), zone_name: "Chiller".to_string(), pick_sequence: 1 },
+        )
+        .await
+        .unwrap();
+    let repo = EnvironmentRepository::new(pool.clone());
+    let thresholds = SetThresholds {
+        min_temperature: Some(Decimal::from(2)),
+        max_temperature: Some(Decimal::from(8)),
+        ..Default::default()
+    };
+    repo.set_thresholds(zone.zone_id, thresholds).await.unwrap();
+    repo.create_sensor(zone.zone_id, CreateSensor { sensor_code: "T-1".to_string() }).await.unwrap();
+    (zone, repo)
+}
+
+/// Whole seconds, so timestamps compare equal after a round trip through the database
+fn an_hour_ago() -> DateTime<Utc> {
+    DateTime::from_timestamp(Utc::now().timestamp() - 3600, 0).unwrap()
+}
+
+fn readings(start: DateTime<Utc>, temperatures: &[i64]) -> SensorReadings {
+    let readings = temperatures
+        .iter()
+        .enumerate()
+        .map(|(minute, temperature)| NewSensorReading {
+            sensor_code: "T-1".to_string(),
+            recorded_at: start + Duration::minutes(minute as i64),
+            temperature: Some(Decimal::from(*temperature)),
+            humidity: None,
+        })
+        .collect();
+    SensorReadings { readings }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn excursion_opens_peaks_and_closes(pool: PgPool) {
+    let (zone, repo) = chiller(&pool).await;
+    let start = an_hour_ago();
+
+    let first = repo.ingest(readings(start, &[5, 9, 12])).await.unwrap();
+    assert_eq!(first.accepted, 3);
+    assert_eq!(first.excursions_opened.len(), 1);
+    let opened = &first.excursions_opened[0];
+    assert_eq!(opened.measure, MEASURE_TEMPERATURE);
+    assert_eq!(opened.started_at, start + Duration::minutes(1));
+
+    let second = repo.ingest(readings(start + Duration::minutes(3), &[10, 6])).await.unwrap();
+    assert!(second.excursions_opened.is_empty());
+    assert_eq!(second.excursions_closed.len(), 1);
+    let closed = &second.excursions_closed[0];
+    assert_eq!(closed.excursion_id, opened.excursion_id);
+    assert_eq!(closed.peak_value, Decimal::from(12));
+    assert_eq!(closed.ended_at, Some(start + Duration::minutes(4)));
+
+    let open = repo
+        .excursions(ExcursionFilter { zone_id: Some(zone.zone_id), open: Some(true) }, KeysetQuery::default())
+        .await
+        .unwrap();
+    assert!(open.data.is_empty());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn resent_and_late_readings_do_not_drive_excursions(pool: PgPool) {
+    let (_, repo) = chiller(&pool).await;
+    let start = an_hour_ago();
+    repo.ingest(readings(start + Duration::minutes(10), &[5])).await.unwrap();
+
+    let resent = repo.ingest(readings(start + Duration::minutes(10), &[5])).await.unwrap();
+    assert_eq!(resent.accepted, 0);
+    assert_eq!(resent.duplicates, 1);
+
+    // A logger catching up on readings older than the newest one only fills in history
+    let late = repo.ingest(readings(start, &[15, 16])).await.unwrap();
+    assert_eq!(late.accepted, 2);
+    assert!(late.excursions_opened.is_empty());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn history_covers_window_and_overlapping_excursions(pool: PgPool) {
+    let (zone, repo) = chiller(&pool).await;
+    let start = an_hour_ago();
+    repo.ingest(readings(start, &[5, 1, 0, 4, 5])).await.unwrap();
+
+    let history = repo
+        .history(
+            zone.zone_id,
+            EnvironmentHistoryQuery {
+                from: start + Duration::minutes(2),
+                to: start + Duration::minutes(4),
+                sensor_id: None,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(history.readings.len(), 2);
+    assert_eq!(history.excursions.len(), 1);
+    assert_eq!(history.excursions[0].peak_value, Decimal::ZERO);
+    assert_eq!(history.thresholds.unwrap().max_temperature, Some(Decimal::from(8)));
+}
@@ -0,0 +1,163 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::{Duration, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn mapping(entity: &str, field_name: &str, erp_field: &str) -> ErpFieldMapping {
+    ErpFieldMapping { entity: entity.to_string(), field_name: field_name.to_string(), erp_field: erp_field.to_string() }
+}
+
+fn connector(code: &str, field_mappings: Vec<ErpFieldMapping>) -> CreateErpConnector {
+    CreateErpConnector {
+        connector_code: code.to_string(),
+        erp_system: "SAP".to_string(),
+        connector_type: ERP_CONNECTOR_REST.to_string(),
+        base_url: "https://erp.example/api".to_string(),
+        api_key: None,
+        item_master: None,
+        sync_interval_minutes: 30,
+        field_mappings,
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn connectors_keep_their_field_mappings(pool: PgPool) {
+    let repo = ErpRepository::new(pool);
+    let detail = repo
+        .create(connector("SAP-PROD", vec![mapping(ERP_ENTITY_ITEM, "item_code", "MATNR"), mapping(ERP_ENTITY_ITEM, "item_name", "MAKTX")]))
+        .await
+        .unwrap();
+    assert_eq!(detail.connector.item_master, ERP_ITEM_MASTER_ERP);
+    assert_eq!(detail.field_mappings.len(), 2);
+    assert!(repo.code_exists("SAP-PROD").await.unwrap());
+
+    let duplicate = vec![mapping(ERP_ENTITY_ITEM, "item_code", "MATNR"), mapping(ERP_ENTITY_ITEM, "item_name", "MATNR")];
+    let changes = UpdateErpConnector { field_mappings: Some(duplicate), ..Default::default() };
+    assert!(repo.update(detail.connector.connector_id, changes).await.is_err());
+
+    let changes = UpdateErpConnector {
+        field_mappings: Some(vec![mapping(ERP_ENTITY_ISSUE, "shipment_number", "VBELN")]),
+        sync_interval_minutes: Some(60),
+        ..Default::default()
+    };
+    let updated = repo.update(detail.connector.connector_id, changes).await.unwrap().unwrap();
+    assert_eq!(updated.field_mappings, vec![mapping(ERP_ENTITY_ISSUE, "shipment_number", "VBELN")]);
+    assert_eq!(updated.connector.sync_interval_minutes, 60);
+    assert!(repo.update(9999, UpdateErpConnector::default()).await.unwrap().is_none());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn due_connectors_are_claimed_once_and_retried_after_failure(pool: PgPool) {
+    let repo = ErpRepository::new(pool);
+    let detail = repo.create(connector("ODOO", Vec::new())).await.unwrap();
+    let id = detail.connector.connector_id;
+
+    let now = Utc::now();
+    assert_eq!(repo.claim_due(now).await.unwrap().len(), 1);
+    assert!(repo.claim_due(now).await.unwrap().is_empty());
+
+    let run = repo
+        .record_run(id, ERP_PULL, ERP_ENTITY_ITEM, now, 0, Some("ERP unreachable".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(run.status, ERP_RUN_FAILED);
+    let connector = repo.get_by_id(id).await.unwrap().unwrap().connector;
+    assert!(connector.next_run_at <= Utc::now() + Duration::minutes(ERP_RETRY_MINUTES as i64));
+    assert!(connector.last_synced_at.is_none());
+
+    repo.record_run(id, ERP_PUSH, ERP_ENTITY_ISSUE, now, 3, None).await.unwrap();
+    assert!(repo.get_by_id(id).await.unwrap().unwrap().connector.last_synced_at.is_some());
+    assert_eq!(repo.runs(id, KeysetQuery::default()).await.unwrap().data.len(), 2);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn changed_items_page_through_the_cursor(pool: PgPool) {
+    let repo = ErpRepository::new(pool);
+    let total = repo.changed_items(None, 1000).await.unwrap().len();
+    assert!(total >= 2);
+
+    let first = repo.changed_items(None, 1).await.unwrap();
+    let cursor =
+        ErpSyncCursor { position_at: first[0].updated_at, position_id: first[0].item_id.unwrap().into() };
+    let rest = repo.changed_items(Some(cursor), 1000).await.unwrap();
+    assert_eq!(rest.len(), total - 1);
+    assert!(rest.iter().all(|item| item.item_id != first[0].item_id));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn imported_items_are_updated_by_code(pool: PgPool) {
+    let repo = ErpRepository::new(pool.clone());
+    let mut item = ErpItem {
+        item_id: None,
+        item_code: "ERP-0001".to_string(),
+        item_name: "Hex bolt M8".to_string(),
+        item_description: None,
+        unit: None,
+        category: Some("FASTENERS".to_string()),
+        updated_at: Utc::now(),
+    };
+    let item_id = repo.import_item(&item).await.unwrap();
+
+    item.item_name = "Hex bolt M8 x 40".to_string();
+    item.unit = Some("BOX".to_string());
+    assert_eq!(repo.import_item(&item).await.unwrap(), item_id);
+
+    let stored = ItemRepository::new(pool).get_by_code("ERP-0001").await.unwrap().unwrap();
+    assert_eq!(stored.item_name, "Hex bolt M8 x 40");
+    assert_eq!(stored.unit.as_deref(), Some("BOX"));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn goods_receipts_carry_the_erp_purchase_order(pool: PgPool) {
+    let repo = ErpRepository::new(pool.clone());
+    let connector_id = repo.create(connector("NETSUITE", Vec::new())).await.unwrap().connector.connector_id;
+    assert_eq!(repo.warehouse_id("NOT-A-WAREHOUSE").await.unwrap(), None);
+
+    let receipt = ReceiptRepository::new(pool.clone())
+        .create_from(
+            CreateReceipt {
+                asn_number: "PO-7001".to_string(),
+                warehouse_id: 1,
+                supplier_name: Some("Acme".to_string()),
+                purchase_order_number: Some("PO-7001".to_string()),
+                carrier_code: None,
+                expected_date: None,
+                consignor_id: None,
+                lines: vec![CreateReceiptLine { item_id: 2, quantity_expected: Decimal::from(12), lpn_number: None }],
+            },
+            SOURCE_ERP,
+        )
+        .await
+        .unwrap();
+    let receipt_id = receipt.receipt.receipt_id;
+    repo.link(connector_id, ERP_ENTITY_PURCHASE_ORDER, "ns-7001", receipt_id).await.unwrap();
+    assert_eq!(repo.linked(connector_id, ERP_ENTITY_PURCHASE_ORDER, "ns-7001").await.unwrap(), Some(receipt_id));
+
+    // Expected receipts are not goods receipts yet
+    assert!(repo.goods_receipts(connector_id, None, 100).await.unwrap().is_empty());
+
+    sqlx::query("UPDATE warehouse.inbound_receipt_lines SET quantity_received = quantity_expected WHERE receipt_id = $1")
+        .bind(receipt_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE warehouse.inbound_receipts SET status = $2, received_at = NOW() WHERE receipt_id = $1")
+        .bind(receipt_id)
+        .bind(RECEIPT_RECEIVED)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let receipts = repo.goods_receipts(connector_id, None, 100).await.unwrap();
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].erp_purchase_order_id.as_deref(), Some("ns-7001"));
+    assert_eq!(receipts[0].lines.len(), 1);
+    assert_eq!(receipts[0].lines[0].quantity, Decimal::from(12));
+
+    let cursor = ErpSyncCursor { position_at: receipts[0].received_at, position_id: receipt_id.into() };
+    repo.advance(connector_id, ERP_PUSH, ERP_ENTITY_RECEIPT, cursor).await.unwrap();
+    let saved = repo.cursor(connector_id, ERP_PUSH, ERP_ENTITY_RECEIPT).await.unwrap();
+    assert_eq!(saved, Some(cursor));
+    assert!(repo.goods_receipts(connector_id, saved, 100).await.unwrap().is_empty());
+}
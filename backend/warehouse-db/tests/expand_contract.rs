@@ -0,0 +1,76 @@
+use sqlx::PgPool;
+use warehouse_db::expand_contract::{self, Backfill, ColumnChange};
+use warehouse_models::*;
+
+async fn expand(pool: &PgPool) -> ColumnChange {
+    sqlx::query("ALTER TABLE warehouse.items ADD COLUMN item_code_key VARCHAR(100)").execute(pool).await.unwrap();
+    ColumnChange::new("warehouse.items", "item_id", "item_code_key", "lower(item_code)").unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn backfills_in_batches_while_new_writes_are_dual_written(pool: PgPool) {
+    let change = expand(&pool).await;
+    expand_contract::install_dual_write(&pool, &change).await.unwrap();
+
+    let before = expand_contract::verify(&pool, &change).await.unwrap();
+    assert!(before.rows > 2);
+    assert_eq!(before.mismatched, before.rows);
+
+    // Written during the backfill: the trigger fills the column
+    sqlx::query(
+        "INSERT INTO warehouse.items (item_code, item_name, item_type, unit, status)
+         VALUES ('NEW-ITEM', 'Stretch film', 'STOCK', 'ROLL', 'ACTIVE')",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let key: String = sqlx::query_scalar("SELECT item_code_key FROM warehouse.items WHERE item_code = 'NEW-ITEM'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(key, "new-item");
+
+    let backfill = Backfill::new(pool.clone(), "items-code-key", change.clone()).with_batch_size(2);
+    let first = backfill.step().await.unwrap();
+    assert_eq!(first.status, BACKFILL_RUNNING);
+    assert_eq!(first.rows_updated, 2);
+
+    let done = backfill.with_pause(std::time::Duration::ZERO).run().await.unwrap();
+    assert_eq!(done.status, BACKFILL_COMPLETED);
+    assert_eq!(done.rows_updated, before.rows + 1);
+    assert!(done.completed_at.is_some());
+    assert!(expand_contract::verify(&pool, &change).await.unwrap().is_consistent());
+
+    // Contract: writes no longer maintain the column
+    expand_contract::remove_dual_write(&pool, &change).await.unwrap();
+    sqlx::query("UPDATE warehouse.items SET item_code = 'RENAMED' WHERE item_code = 'NEW-ITEM'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(expand_contract::verify(&pool, &change).await.unwrap().mismatched, 1);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn an_interrupted_backfill_resumes_from_its_last_key(pool: PgPool) {
+    let change = expand(&pool).await;
+    let backfill = || Backfill::new(pool.clone(), "items-code-key", change.clone()).with_batch_size(1);
+
+    let first = backfill().step().await.unwrap();
+    let resumed = backfill().step().await.unwrap();
+    assert!(resumed.last_key > first.last_key);
+    assert_eq!(resumed.rows_updated, 2);
+    assert_eq!(resumed.batches, 2);
+    assert_eq!(expand_contract::backfill_jobs(&pool).await.unwrap().len(), 1);
+
+    // The same job name cannot be reused for another column
+    let other = ColumnChange::new("warehouse.items", "item_id", "item_name", "upper(item_name)").unwrap();
+    assert!(Backfill::new(pool.clone(), "items-code-key", other).step().await.is_err());
+}
+
+#[test]
+fn rejects_names_that_are_not_plain_identifiers() {
+    assert!(ColumnChange::new("warehouse.items; DROP TABLE x", "item_id", "c", "1").is_err());
+    assert!(ColumnChange::new("warehouse.items", "item_id", "Code", "1").is_err());
+    assert!(ColumnChange::new("items", "item_id", "code", " ").is_err());
+    assert!(ColumnChange::new("items", "item_id", "code", "1").is_ok());
+}
@@ -0,0 +1,6 @@
+-- Soft-deleted rows that list/get/code_exists must ignore
+INSERT INTO warehouse.warehouses (warehouse_id, warehouse_code, warehouse_name, city, is_active) VALUES
+(900, 'WH900', 'Closed Warehouse', 'Medan', FALSE);
+
+INSERT INTO warehouse.items (item_id, item_code, item_name, item_type, status) VALUES
+(900, 'ITM900', 'Discontinued Drill', 'ASSET', 'INACTIVE');
@@ -0,0 +1,10 @@
+-- Ledger of six movements across two items and both seeded warehouses
+INSERT INTO warehouse.stock_movements (movement_id, item_id, warehouse_id, movement_type, quantity) VALUES
+(1, 1, 1, 'RECEIPT', 10),
+(2, 2, 1, 'RECEIPT', 500),
+(3, 1, 1, 'ISSUE', -2),
+(4, 1, 2, 'RECEIPT', 4),
+(5, 2, 1, 'ISSUE', -100),
+(6, 1, 1, 'ISSUE', -1);
+
+SELECT setval('warehouse.stock_movements_movement_id_seq', 6);
@@ -0,0 +1,157 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::{Days, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// A 100 kg van and a driver at warehouse 1, with item 2 weighing 10 kg
+async fn fleet(pool: &PgPool) -> (Database, DeliveryVehicle, DeliveryDriver) {
+    sqlx::query("UPDATE warehouse.items SET weight_kg = 10 WHERE item_id = 2").execute(pool).await.unwrap();
+    let db = Database::new(pool.clone());
+    let vehicle =
+        CreateDeliveryVehicle { vehicle_code: "VAN-1".to_string(), description: None, capacity_kg: Decimal::from(100) };
+    let vehicle = db.deliveries().add_vehicle(1, vehicle).await.unwrap();
+    let driver = CreateDeliveryDriver {
+        driver_code: "DRV-1".to_string(),
+        driver_name: "Budi Santoso".to_string(),
+        phone: None,
+        license_number: "SIM-B1-0001".to_string(),
+        license_expires_on: Some(Utc::now().date_naive() + Days::new(30)),
+    };
+    let driver = db.fleet().add_driver(1, driver).await.unwrap();
+    (db, vehicle, driver)
+}
+
+/// Ship `quantity` of item 2 from warehouse 1
+async fn shipment(db: &Database, quantity: i64, carrier_code: Option<&str>) -> Shipment {
+    let created = db
+        .orders()
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "PT Karya Beton".to_string(),
+            customer_reference: None,
+            ship_to_name: Some("Site office".to_string()),
+            ship_to_address: Some("Jl. Sudirman 12".to_string()),
+            ship_to_city: Some("Jakarta".to_string()),
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![CreateOrderLine { item_id: 2, quantity_ordered: Decimal::from(quantity) }],
+        })
+        .await
+        .unwrap();
+    let confirmation = ShipOrder {
+        carrier_code: carrier_code.map(str::to_string),
+        tracking_number: carrier_code.map(|_| "JNE0001".to_string()),
+        service_level: None,
+        lines: vec![ShipOrderLine {
+            line_id: created.lines[0].line_id,
+            quantity: Decimal::from(quantity),
+            weight_kg: None,
+            lot_number: None,
+        }],
+        lpns: Vec::new(),
+        reference_id: None,
+    };
+    let (_, shipment) = db.orders().ship(created.order.order_id, confirmation, None).await.unwrap().unwrap();
+    shipment.shipment
+}
+
+fn assign(shipments: &[&Shipment]) -> AssignShipments {
+    AssignShipments { shipment_ids: shipments.iter().map(|shipment| shipment.shipment_id).collect() }
+}
+
+fn proof() -> RecordProofOfDelivery {
+    RecordProofOfDelivery { received_by: "Pak Joko".to_string(), delivered_at: None, notes: None }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn runs_carry_shipments_until_each_stop_proves_delivery(pool: PgPool) {
+    let (db, vehicle, driver) = fleet(&pool).await;
+    let today = Utc::now().date_naive();
+    let run = CreateDeliveryRun { vehicle_id: vehicle.vehicle_id, driver_id: driver.driver_id, run_date: today };
+    let run = db.fleet().create_run(1, run).await.unwrap().run;
+    assert!(run.run_number.starts_with("RUN-"));
+    assert_eq!(run.status, RUN_PLANNED);
+
+    let small = shipment(&db, 5, None).await;
+    let large = shipment(&db, 6, None).await;
+    let carried = shipment(&db, 1, Some("JNE")).await;
+    assert!(db.fleet().assign(run.run_id, assign(&[&small, &large])).await.is_err());
+    assert!(db.fleet().assign(run.run_id, assign(&[&carried])).await.is_err());
+    assert!(db.fleet().record_proof(small.shipment_id, proof()).await.is_err());
+
+    let detail = db.fleet().assign(run.run_id, assign(&[&small])).await.unwrap().unwrap();
+    assert_eq!(detail.load_kg, Decimal::from(50));
+    assert_eq!(detail.stops[0].ship_to_name.as_deref(), Some("Site office"));
+    assert!(db.fleet().assign(run.run_id, assign(&[&small])).await.is_err());
+
+    // Proof can only be recorded once the run is out
+    assert!(db.fleet().record_proof(small.shipment_id, proof()).await.is_err());
+    let dispatched = db.fleet().dispatch(run.run_id).await.unwrap().unwrap();
+    assert_eq!(dispatched.run.status, RUN_DISPATCHED);
+    assert_eq!(dispatched.stops[0].status, TrackingStatus::OutForDelivery.as_str());
+    assert!(db.fleet().assign(run.run_id, assign(&[&large])).await.is_err());
+
+    assert!(db.fleet().attach_file(small.shipment_id, POD_SIGNATURE, "image/png", b"png").await.unwrap().is_none());
+    let recorded = db.fleet().record_proof(small.shipment_id, proof()).await.unwrap().unwrap();
+    assert_eq!(recorded.received_by, "Pak Joko");
+    assert!(db.fleet().record_proof(small.shipment_id, proof()).await.is_err());
+
+    let signature = db.fleet().attach_file(small.shipment_id, POD_SIGNATURE, "image/png", b"png").await.unwrap();
+    assert_eq!(signature.unwrap().size_bytes, 3);
+    let (file, content) = db.fleet().file(small.shipment_id, POD_SIGNATURE).await.unwrap().unwrap();
+    assert_eq!((file.content_type.as_str(), content.as_slice()), ("image/png", b"png".as_slice()));
+    assert_eq!(db.fleet().proof(small.shipment_id).await.unwrap().unwrap().files.len(), 1);
+
+    let completed = db.fleet().get_run(run.run_id).await.unwrap().unwrap();
+    assert_eq!(completed.run.status, RUN_COMPLETED);
+    assert_eq!(completed.stops[0].status, TrackingStatus::Delivered.as_str());
+    assert_eq!(completed.stops[0].delivered_at, Some(recorded.delivered_at));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn runs_need_a_free_vehicle_and_a_licensed_driver(pool: PgPool) {
+    let (db, vehicle, driver) = fleet(&pool).await;
+    let today = Utc::now().date_naive();
+    let run = |run_date| CreateDeliveryRun { vehicle_id: vehicle.vehicle_id, driver_id: driver.driver_id, run_date };
+
+    db.fleet().create_run(1, run(today)).await.unwrap();
+    assert!(db.fleet().create_run(1, run(today)).await.is_err());
+    assert!(db.fleet().create_run(1, run(today + Days::new(60))).await.is_err());
+
+    let renewed = UpdateDeliveryDriver { license_expires_on: Some(today + Days::new(365)), ..Default::default() };
+    db.fleet().update_driver(driver.driver_id, renewed).await.unwrap().unwrap();
+    db.fleet().create_run(1, run(today + Days::new(60))).await.unwrap();
+
+    let retired = UpdateDeliveryVehicle { is_active: Some(false), ..Default::default() };
+    db.deliveries().update_vehicle(vehicle.vehicle_id, retired).await.unwrap().unwrap();
+    assert!(db.fleet().create_run(1, run(today + Days::new(1))).await.is_err());
+    assert!(db.fleet().create_run(2, run(today + Days::new(2))).await.is_err());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn routing_takes_own_fleet_shipments_off_a_run_and_free_vehicles(pool: PgPool) {
+    let (db, vehicle, driver) = fleet(&pool).await;
+    let today = Utc::now().date_naive();
+    let waiting = shipment(&db, 2, None).await;
+    let assigned = shipment(&db, 3, None).await;
+    shipment(&db, 1, Some("JNE")).await;
+
+    let routable = db.fleet().routable(1).await.unwrap();
+    assert_eq!(routable.len(), 2);
+    assert_eq!(routable[0].weight_kg, Decimal::from(20));
+    assert_eq!(db.fleet().free_vehicles(1, today).await.unwrap().len(), 1);
+
+    let run = CreateDeliveryRun { vehicle_id: vehicle.vehicle_id, driver_id: driver.driver_id, run_date: today };
+    let run = db.fleet().create_run(1, run).await.unwrap().run;
+    db.fleet().assign(run.run_id, assign(&[&assigned])).await.unwrap().unwrap();
+
+    let routable = db.fleet().routable(1).await.unwrap();
+    assert_eq!(routable.iter().map(|shipment| shipment.shipment_id).collect::<Vec<_>>(), [waiting.shipment_id]);
+    assert!(db.fleet().free_vehicles(1, today).await.unwrap().is_empty());
+    assert_eq!(db.fleet().free_vehicles(1, today + Days::new(1)).await.unwrap().len(), 1);
+}
@@ -0,0 +1,112 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::NaiveDate;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// March 2024, long over and without seed movements
+fn march() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()
+}
+
+/// An item no 3PL client owns
+async fn own_item(pool: &PgPool) -> i32 {
+    sqlx::query_scalar(
+        "SELECT item_id FROM warehouse.items
+         WHERE item_id NOT IN (SELECT item_id FROM warehouse.billing_client_items)
+         ORDER BY item_id LIMIT 1",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+async fn move_in_march(pool: &PgPool, item_id: i32, movement_type: &str, quantity: i32, unit_cost: i32) -> i64 {
+    sqlx::query_scalar(
+        "INSERT INTO warehouse.stock_movements (item_id, warehouse_id, movement_type, quantity, unit_cost, movement_date)
+         VALUES ($1, 1, $2, $3, $4, '2024-03-15T10:00:00Z') RETURNING movement_id",
+    )
+    .bind(item_id)
+    .bind(movement_type)
+    .bind(Decimal::from(quantity))
+    .bind(Decimal::from(unit_cost))
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn totals_value_own_movements_in_the_period(pool: PgPool) {
+    let repo = GlRepository::new(pool.clone());
+    let item_id = own_item(&pool).await;
+    move_in_march(&pool, item_id, "RECEIPT", 10, 5).await;
+    move_in_march(&pool, item_id, "SHIPMENT", -4, 5).await;
+    move_in_march(&pool, item_id, "SCRAP", -1, 5).await;
+
+    let totals = repo.movement_totals(march()).await.unwrap();
+    let value_of = |movement_type: &str| totals.iter().find(|t| t.movement_type == movement_type).unwrap().value;
+    assert_eq!(value_of("RECEIPT"), Decimal::from(50));
+    assert_eq!(value_of("SHIPMENT"), Decimal::from(-20));
+    assert_eq!(value_of("SCRAP"), Decimal::from(-5));
+    assert!(totals.iter().all(|t| t.uncosted_movements == 0));
+
+    assert!(repo.movement_totals(gl_period_end(march())).await.unwrap().iter().all(|t| t.movement_type != "SCRAP"));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn closed_period_locks_its_movements(pool: PgPool) {
+    let repo = GlRepository::new(pool.clone());
+    let item_id = own_item(&pool).await;
+    let movement_id = move_in_march(&pool, item_id, "RECEIPT", 10, 5).await;
+
+    let closed = repo.close(march()).await.unwrap();
+    assert_eq!(closed.status, GL_PERIOD_CLOSED);
+
+    let changed = sqlx::query("UPDATE warehouse.stock_movements SET quantity = 12 WHERE movement_id = $1")
+        .bind(movement_id)
+        .execute(&pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .unwrap_err();
+    assert!(closed_period(&changed).is_some(), "{}", changed);
+
+    let deleted = sqlx::query("DELETE FROM warehouse.stock_movements WHERE movement_id = $1")
+        .bind(movement_id)
+        .execute(&pool)
+        .await;
+    assert!(deleted.is_err());
+
+    let posted = sqlx::query(
+        "INSERT INTO warehouse.stock_movements (item_id, warehouse_id, movement_type, quantity, movement_date)
+         VALUES ($1, 1, 'ADJUSTMENT', 1, '2024-03-20T00:00:00Z')",
+    )
+    .bind(item_id)
+    .execute(&pool)
+    .await;
+    assert!(posted.is_err());
+
+    // Columns that do not affect the valuation stay editable
+    sqlx::query("UPDATE warehouse.stock_movements SET notes = 'checked' WHERE movement_id = $1")
+        .bind(movement_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let reopened = repo.reopen(march()).await.unwrap();
+    assert_eq!(reopened.status, GL_PERIOD_OPEN);
+    sqlx::query("UPDATE warehouse.stock_movements SET quantity = 12 WHERE movement_id = $1")
+        .bind(movement_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn current_period_cannot_be_closed(pool: PgPool) {
+    let repo = GlRepository::new(pool);
+    let this_month = parse_gl_period(&warehouse_models::chrono::Utc::now().format("%Y-%m").to_string()).unwrap();
+
+    let error = repo.close(this_month).await.unwrap_err();
+    assert!(rejection(&error).is_some());
+    assert_eq!(repo.period(this_month).await.unwrap().status, GL_PERIOD_OPEN);
+}
@@ -0,0 +1,151 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn classification(un_number: &str, class: &str, name: &str) -> HazmatClassification {
+    HazmatClassification {
+        un_number: un_number.to_string(),
+        hazard_class: class.to_string(),
+        packing_group: Some("II".to_string()),
+        proper_shipping_name: name.to_string(),
+    }
+}
+
+/// Item 2 becomes a flammable liquid and item 3 an oxidizer
+async fn classify(repo: &HazmatRepository) {
+    repo.classify_item(2, Some(classification("UN1263", "3", "Paint"))).await.unwrap();
+    repo.classify_item(3, Some(classification("UN1479", "5.1", "Oxidizing solid, n.o.s."))).await.unwrap();
+}
+
+async fn zone_with_location(pool: &PgPool, code: &str, classes: &[&str]) -> (Zone, Location) {
+    let locations = LocationRepository::new(pool.clone());
+    let zone = locations
+        .create_zone(1, CreateZone { zone_code: code.to_string(), zone_name: code.to_string(), pick_sequence: 1 })
+        .await
+        .unwrap();
+    let hazard_classes = classes.iter().map(|class| class.to_string()).collect();
+    let zone = HazmatRepository::new(pool.clone())
+        .set_zone_classes(zone.zone_id, ZoneHazmat { hazard_classes })
+        .await
+        .unwrap()
+        .unwrap();
+    let location = locations
+        .create_location(
+            zone.zone_id,
+            CreateLocation { location_code: format!("{}-01", code), pick_sequence: 1, item_id: None },
+        )
+        .await
+        .unwrap();
+    (zone, location)
+}
+
+fn putaway(item_id: i32, location: &Location) -> CreateTask {
+    CreateTask {
+        warehouse_id: 1,
+        task_type: TASK_PUTAWAY.to_string(),
+        priority: None,
+        item_id: Some(item_id),
+        quantity: Some(Decimal::from(5)),
+        from_location_id: None,
+        to_location_id: Some(location.location_id),
+        notes: None,
+        due_at: None,
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn putaway_follows_zone_approval_and_segregation(pool: PgPool) {
+    let hazmat = HazmatRepository::new(pool.clone());
+    classify(&hazmat).await;
+    let tasks = TaskRepository::new(pool.clone());
+    let (_, hazmat_bay) = zone_with_location(&pool, "HZ", &["3", "5.1"]).await;
+    let (_, general) = zone_with_location(&pool, "GEN", &[]).await;
+
+    tasks.create(putaway(2, &hazmat_bay)).await.unwrap();
+
+    let error = tasks.create(putaway(3, &hazmat_bay)).await.unwrap_err();
+    assert!(error.to_string().contains("must be segregated"), "{}", error);
+
+    let error = tasks.create(putaway(2, &general)).await.unwrap_err();
+    assert!(error.to_string().contains("not approved"), "{}", error);
+
+    // Ordinary stock goes anywhere
+    tasks.create(putaway(1, &general)).await.unwrap();
+    tasks.create(putaway(1, &hazmat_bay)).await.unwrap();
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn stored_goods_keep_their_zone_compliant(pool: PgPool) {
+    let hazmat = HazmatRepository::new(pool.clone());
+    classify(&hazmat).await;
+    let (zone, _) = zone_with_location(&pool, "HZ", &["3"]).await;
+    LocationRepository::new(pool.clone())
+        .create_location(
+            zone.zone_id,
+            CreateLocation { location_code: "HZ-02".to_string(), pick_sequence: 2, item_id: Some(2) },
+        )
+        .await
+        .unwrap();
+
+    let error = hazmat.set_zone_classes(zone.zone_id, ZoneHazmat { hazard_classes: vec![] }).await.unwrap_err();
+    assert!(error.to_string().contains("would not cover"), "{}", error);
+
+    let error = hazmat.classify_item(2, Some(classification("UN1479", "5.1", "Oxidizer"))).await.unwrap_err();
+    assert!(error.to_string().contains("not approved"), "{}", error);
+
+    assert!(hazmat.classify_item(2, None).await.unwrap());
+    hazmat.set_zone_classes(zone.zone_id, ZoneHazmat { hazard_classes: vec![] }).await.unwrap();
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn declaration_lists_dangerous_goods_shipped(pool: PgPool) {
+    let hazmat = HazmatRepository::new(pool.clone());
+    classify(&hazmat).await;
+    sqlx::query("UPDATE warehouse.items SET weight_kg = 1.5 WHERE item_id = 2").execute(&pool).await.unwrap();
+
+    let orders = OrderRepository::new(pool.clone());
+    let line = |item_id: i32| CreateOrderLine { item_id, quantity_ordered: Decimal::from(4) };
+    let order = orders
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "PT Cat Warna".to_string(),
+            customer_reference: None,
+            ship_to_name: None,
+            ship_to_address: None,
+            ship_to_city: None,
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![line(1), line(2)],
+        })
+        .await
+        .unwrap();
+    let shipped =
+        order.lines.iter().map(|l| ShipOrderLine { line_id: l.line_id, quantity: Decimal::from(4), weight_kg: None, lot_number: None }).collect();
+    let (_, shipment) = orders
+        .ship(
+            order.order.order_id,
+            ShipOrder {
+                carrier_code: Some("JNE".to_string()),
+                tracking_number: None,
+                service_level: None,
+                lines: shipped,
+                lpns: Vec::new(),
+                reference_id: None,
+            },
+            None,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    let declaration = hazmat.declaration(shipment.shipment.shipment_id).await.unwrap().unwrap();
+    assert_eq!(declaration.lines.len(), 1);
+    assert_eq!(declaration.lines[0].un_number, "UN1263");
+    assert_eq!(declaration.lines[0].quantity, Decimal::from(4));
+    assert_eq!(declaration.total_weight_kg, Some(Decimal::from(6)));
+}
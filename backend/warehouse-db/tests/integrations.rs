@@ -0,0 +1,127 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::{Days, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn channel(pool: &PgPool) -> IntegrationChannel {
+    IntegrationRepository::new(pool.clone())
+        .create_channel(CreateIntegrationChannel {
+            channel_code: "MARKET".to_string(),
+            channel_name: "Marketplace".to_string(),
+            fulfillment_warehouse_id: 1,
+        })
+        .await
+        .unwrap()
+}
+
+fn order(item_id: i32, quantity: Decimal) -> CreateOrder {
+    CreateOrder {
+        warehouse_id: 1,
+        customer_name: "Marketplace buyer".to_string(),
+        customer_reference: None,
+        ship_to_name: None,
+        ship_to_address: None,
+        ship_to_city: None,
+        ship_to_state: None,
+        ship_to_postal_code: None,
+        ship_to_country: None,
+        requested_ship_date: None,
+        carrier_code: None,
+        dropship_supplier_code: None,
+        lines: vec![CreateOrderLine { item_id, quantity_ordered: quantity }],
+    }
+}
+
+async fn available(pool: &PgPool, item_id: i32) -> Decimal {
+    sqlx::query_scalar(
+        "SELECT COALESCE(SUM(quantity_available), 0) FROM warehouse.stock_inventory
+         WHERE item_id = $1 AND warehouse_id = 1",
+    )
+    .bind(item_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn an_external_order_is_taken_in_once(pool: PgPool) {
+    let repo = IntegrationRepository::new(pool.clone());
+    let channel = channel(&pool).await;
+
+    let (first, duplicate) = repo.intake(&channel, "MK-1001", order(3, Decimal::from(2))).await.unwrap();
+    assert!(!duplicate);
+    assert_eq!(first.order.source, SOURCE_CHANNEL);
+    assert_eq!(first.lines[0].quantity_allocated, Decimal::from(2));
+
+    let (again, duplicate) = repo.intake(&channel, "MK-1001", order(3, Decimal::from(5))).await.unwrap();
+    assert!(duplicate);
+    assert_eq!(again.order.order_id, first.order.order_id);
+    assert_eq!(again.lines[0].quantity_ordered, Decimal::from(2));
+
+    let orders: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM warehouse.outbound_orders WHERE source = 'CHANNEL'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(orders, 1);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn later_orders_are_allocated_what_is_left(pool: PgPool) {
+    let repo = IntegrationRepository::new(pool.clone());
+    let channel = channel(&pool).await;
+    let stock = available(&pool, 3).await;
+    assert!(stock > Decimal::from(10));
+
+    let (first, _) = repo.intake(&channel, "MK-1", order(3, stock - Decimal::from(10))).await.unwrap();
+    let first = IntakeResult::new(channel.channel_code.clone(), "MK-1".to_string(), false, first);
+    assert_eq!(first.allocation_status, ALLOCATION_ALLOCATED);
+
+    let (second, _) = repo.intake(&channel, "MK-2", order(3, Decimal::from(30))).await.unwrap();
+    assert_eq!(second.lines[0].quantity_allocated, Decimal::from(10));
+    let second = IntakeResult::new(channel.channel_code.clone(), "MK-2".to_string(), false, second);
+    assert_eq!(second.allocation_status, ALLOCATION_PARTIAL);
+
+    let (third, _) = repo.intake(&channel, "MK-3", order(3, Decimal::from(1))).await.unwrap();
+    assert_eq!(third.lines[0].quantity_allocated, Decimal::ZERO);
+    let third = IntakeResult::new(channel.channel_code.clone(), "MK-3".to_string(), false, third);
+    assert_eq!(third.allocation_status, ALLOCATION_UNALLOCATED);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn an_inactive_channel_takes_in_no_new_orders(pool: PgPool) {
+    let repo = IntegrationRepository::new(pool.clone());
+    let channel = channel(&pool).await;
+    repo.intake(&channel, "MK-1", order(3, Decimal::from(1))).await.unwrap();
+    let changes = UpdateIntegrationChannel { is_active: Some(false), ..Default::default() };
+    let channel = repo.update_channel(channel.channel_id, changes).await.unwrap().unwrap();
+
+    let error = repo.intake(&channel, "MK-2", order(3, Decimal::from(1))).await.unwrap_err();
+    assert!(error.downcast_ref::<RuleViolation>().is_some(), "{}", error);
+    // Orders taken in before are still found
+    assert!(repo.intake(&channel, "MK-1", order(3, Decimal::from(1))).await.unwrap().1);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn lines_allocated_nothing_are_recorded_as_lost_demand(pool: PgPool) {
+    let repo = IntegrationRepository::new(pool.clone());
+    let channel = channel(&pool).await;
+    let stock = available(&pool, 3).await;
+
+    repo.intake(&channel, "MK-1", order(3, stock)).await.unwrap();
+    let (short, _) = repo.intake(&channel, "MK-2", order(3, Decimal::from(4))).await.unwrap();
+    repo.intake(&channel, "MK-3", order(3, Decimal::from(6))).await.unwrap();
+
+    let stock_outs = StockOutRepository::new(pool.clone());
+    let incidents = stock_outs.incidents(&StockOutFilter { item_id: Some(3), ..Default::default() }).await.unwrap();
+    assert_eq!(incidents.len(), 2);
+    assert_eq!(incidents[1].reference_number.as_deref(), Some(short.order.order_number.as_str()));
+    assert_eq!(incidents[1].requested_by, "Marketplace buyer");
+
+    let today = Utc::now().date_naive();
+    let lost = stock_outs.lost_demand(&StockOutFilter::default(), today - Days::new(9), today).await.unwrap();
+    assert_eq!(lost.len(), 1);
+    assert_eq!((lost[0].item_code.as_str(), lost[0].incidents, lost[0].stock_out_days), ("ITM003", 2, 1));
+    assert_eq!(lost[0].quantity_lost, Decimal::from(10));
+    assert_eq!(lost[0].stock_out_day_pct, Decimal::from(10));
+}
@@ -0,0 +1,91 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn stock_row(pool: &PgPool) -> (i32, i32, i32) {
+    sqlx::query_as("SELECT stock_id, item_id, warehouse_id FROM warehouse.stock_inventory ORDER BY stock_id LIMIT 1")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+fn findings_for(detail: &IntegrityRunDetail, check: &str, stock_id: i32) -> Vec<IntegrityFinding> {
+    detail
+        .findings
+        .iter()
+        .filter(|finding| finding.check_name == check && finding.stock_id == Some(stock_id))
+        .cloned()
+        .collect()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn records_stock_that_drifted_from_its_movements(pool: PgPool) {
+    let repo = IntegrityRepository::new(pool.clone());
+    let (stock_id, item_id, warehouse_id) = stock_row(&pool).await;
+
+    // Bring the balance in line with its movements, then break it behind their back
+    sqlx::query(
+        "UPDATE warehouse.stock_inventory s
+         SET quantity_reserved = 0,
+             quantity_on_hand = COALESCE((SELECT SUM(m.quantity) FROM warehouse.stock_movements m
+                                          WHERE m.item_id = s.item_id AND m.warehouse_id = s.warehouse_id
+                                            AND m.consignor_id IS NOT DISTINCT FROM s.consignor_id), 0)
+         WHERE stock_id = $1",
+    )
+    .bind(stock_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let clean = repo.check().await.unwrap();
+    let detail = repo.get_run(clean.run.run_id, IntegrityFindingFilter::default()).await.unwrap().unwrap();
+    assert!(findings_for(&detail, CHECK_MOVEMENT_MISMATCH, stock_id).is_empty());
+
+    sqlx::query("UPDATE warehouse.stock_inventory SET quantity_on_hand = quantity_on_hand + 7 WHERE stock_id = $1")
+        .bind(stock_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let broken = repo.check().await.unwrap();
+    assert!(!broken.passed());
+    assert_eq!(broken.run.findings, clean.run.findings + 1);
+    assert!(broken.failed_checks.iter().any(|check| check.check_name == CHECK_MOVEMENT_MISMATCH));
+
+    let filter = IntegrityFindingFilter { check_name: Some(CHECK_MOVEMENT_MISMATCH.to_string()), warehouse_id: Some(warehouse_id) };
+    let detail = repo.get_run(broken.run.run_id, filter).await.unwrap().unwrap();
+    let finding = &findings_for(&detail, CHECK_MOVEMENT_MISMATCH, stock_id)[0];
+    assert_eq!(finding.item_id, Some(item_id));
+    assert_eq!(finding.actual.unwrap() - finding.expected.unwrap(), Decimal::from(7));
+    assert!(detail.findings.iter().all(|finding| finding.warehouse_id == Some(warehouse_id)));
+
+    let runs = repo.runs(KeysetQuery::default()).await.unwrap();
+    assert_eq!(runs.data[0].run_id, broken.run.run_id);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn movements_without_a_stock_row_are_a_mismatch(pool: PgPool) {
+    let repo = IntegrityRepository::new(pool.clone());
+    let (_, _, warehouse_id) = stock_row(&pool).await;
+    let item_id: i32 = sqlx::query_scalar(
+        "INSERT INTO warehouse.items (item_code, item_name, item_type, unit, status)
+         VALUES ('DRIFT-1', 'Unbooked item', 'STOCK', 'PCS', 'ACTIVE')
+         RETURNING item_id",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO warehouse.stock_movements (item_id, warehouse_id, movement_type, quantity) VALUES ($1, $2, 'RECEIPT', 4)")
+        .bind(item_id)
+        .bind(warehouse_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let report = repo.check().await.unwrap();
+    let detail = repo.get_run(report.run.run_id, IntegrityFindingFilter::default()).await.unwrap().unwrap();
+    let unbooked: Vec<_> = detail.findings.iter().filter(|finding| finding.item_id == Some(item_id)).collect();
+    assert_eq!(unbooked.len(), 1);
+    assert!(unbooked[0].stock_id.is_none());
+    assert_eq!(unbooked[0].expected, Some(Decimal::from(4)));
+    assert!(unbooked[0].detail.contains("no stock row"));
+}
@@ -0,0 +1,233 @@
+use futures::TryStreamExt;
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn new_item(code: &str) -> CreateItem {
+    CreateItem {
+        item_code: code.to_string(),
+        item_name: format!("Item {}", code),
+        item_description: None,
+        item_type: "STOCK".to_string(),
+        item_usage_type: None,
+        category: Some("Test".to_string()),
+        subcategory: None,
+        brand: None,
+        model: None,
+        unit: None,
+        is_loanable: None,
+        maintenance_required: Some(true),
+        calibration_required: None,
+        replacement_cost: None,
+        hazmat: None,
+        customs: None,
+    }
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("inactive_records"))]
+async fn list_orders_by_name_and_skips_inactive(pool: PgPool) {
+    let repo = ItemRepository::new(pool);
+
+    let result = repo.list(PaginationQuery::default()).await.unwrap();
+
+    let names: Vec<_> = result.data.iter().map(|i| i.item_name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["Concrete Mixer 500L", "Laptop Dell Inspiron 15", "Safety Helmet", "Steel Rebar 12mm"]
+    );
+    assert_eq!(result.pagination.total, Some(4));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn list_pages_through_all_rows(pool: PgPool) {
+    let repo = ItemRepository::new(pool);
+    let mut seen = Vec::new();
+
+    for page in 1..=3 {
+        let result = repo
+            .list(PaginationQuery {
+                page: Some(page),
+                limit: Some(3),
+                count: Some(CountMode::Skip),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        seen.extend(result.data.into_iter().map(|i| i.item_id));
+        assert_eq!(result.pagination.has_next, page == 1);
+    }
+
+    seen.sort();
+    assert_eq!(seen, [1, 2, 3, 4]);
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("inactive_records"))]
+async fn stream_active_matches_list(pool: PgPool) {
+    let repo = ItemRepository::new(pool);
+
+    let streamed: Vec<i32> = repo
+        .stream_active(None)
+        .map_ok(|item| item.item_id)
+        .try_collect()
+        .await
+        .unwrap();
+    let listed: Vec<i32> = repo
+        .list(PaginationQuery::default())
+        .await
+        .unwrap()
+        .data
+        .into_iter()
+        .map(|item| item.item_id)
+        .collect();
+
+    assert_eq!(streamed, listed);
+
+    // An export of a search holds what the search lists
+    let search = PaginationQuery { search: Some("steel".to_string()), limit: Some(100), ..Default::default() };
+    let listed: Vec<i32> = repo.list(search).await.unwrap().data.into_iter().map(|item| item.item_id).collect();
+    let streamed: Vec<i32> =
+        repo.stream_active(Some("steel".to_string())).map_ok(|item| item.item_id).try_collect().await.unwrap();
+    assert_eq!(listed, [2]);
+    assert_eq!(streamed, listed);
+    let nothing: Vec<Item> = repo.stream_active(Some("no such item".to_string())).try_collect().await.unwrap();
+    assert!(nothing.is_empty());
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("inactive_records"))]
+async fn get_by_id_ignores_inactive(pool: PgPool) {
+    let repo = ItemRepository::new(pool);
+
+    assert_eq!(repo.get_by_id(2).await.unwrap().unwrap().item_code, "ITM002");
+    assert!(repo.get_by_id(900).await.unwrap().is_none());
+    assert!(repo.get_by_id(999_999).await.unwrap().is_none());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn create_applies_column_defaults(pool: PgPool) {
+    let repo = ItemRepository::new(pool);
+
+    let created = repo.create(new_item("ITM100")).await.unwrap();
+
+    assert_eq!(created.status, "ACTIVE");
+    assert_eq!(created.unit.as_deref(), Some("PCS"));
+    assert!(created.maintenance_required);
+    assert!(!created.is_loanable);
+    assert_eq!(repo.get_by_id(created.item_id).await.unwrap().unwrap().item_name, "Item ITM100");
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn costs_come_back_in_the_item_cost_currency(pool: PgPool) {
+    let repo = ItemRepository::new(pool.clone());
+    let mut item = new_item("ITM200");
+    item.replacement_cost = Some(Money::new(Decimal::from(1200), "USD"));
+
+    let created = repo.create(item).await.unwrap();
+    assert_eq!(created.replacement_cost, Some(Money::new(Decimal::from(1200), "USD")));
+
+    sqlx::query("UPDATE warehouse.items SET standard_cost = 950 WHERE item_id = $1")
+        .bind(created.item_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let item = repo.get_by_code("ITM200").await.unwrap().unwrap();
+    assert_eq!(item.standard_cost, Some(Money::new(Decimal::from(950), "USD")));
+    assert_eq!(item.last_cost, None);
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("inactive_records"))]
+async fn code_exists_respects_exclusion_and_status(pool: PgPool) {
+    let repo = ItemRepository::new(pool);
+
+    assert!(repo.code_exists("ITM001", None).await.unwrap());
+    assert!(!repo.code_exists("ITM001", Some(1)).await.unwrap());
+    assert!(!repo.code_exists("ITM900", None).await.unwrap());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn concurrent_creates_with_same_code_admit_one(pool: PgPool) {
+    let repo = ItemRepository::new(pool);
+
+    let (a, b) = tokio::join!(repo.create(new_item("ITM777")), repo.create(new_item("ITM777")));
+
+    assert_eq!([a.is_ok(), b.is_ok()].iter().filter(|ok| **ok).count(), 1);
+}
+
+fn no_changes() -> UpdateItem {
+    UpdateItem {
+        item_code: None,
+        item_name: None,
+        item_description: None,
+        item_type: None,
+        category: None,
+        subcategory: None,
+        brand: None,
+        model: None,
+        unit: None,
+        replacement_cost: None,
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn update_changes_only_the_given_fields(pool: PgPool) {
+    let repo = ItemRepository::new(pool);
+    let created = repo.create(new_item("ITM300")).await.unwrap();
+
+    let changes = UpdateItem {
+        item_code: Some("ITM301".to_string()),
+        brand: Some("Acme".to_string()),
+        replacement_cost: Some(Money::new(Decimal::from(40), "USD")),
+        ..no_changes()
+    };
+    let updated = repo.update(created.item_id, changes).await.unwrap().unwrap();
+    assert_eq!(updated.item_code, "ITM301");
+    assert_eq!(updated.item_name, "Item ITM300");
+    assert_eq!(updated.brand.as_deref(), Some("Acme"));
+    assert_eq!(updated.category.as_deref(), Some("Test"));
+    assert_eq!(updated.replacement_cost, Some(Money::new(Decimal::from(40), "USD")));
+    assert!(repo.update(999_999, no_changes()).await.unwrap().is_none());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn update_keeps_costs_in_one_currency(pool: PgPool) {
+    let repo = ItemRepository::new(pool.clone());
+    sqlx::query("UPDATE warehouse.items SET standard_cost = 950 WHERE item_id = 2")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let changes = UpdateItem { replacement_cost: Some(Money::new(Decimal::from(1200), "USD")), ..no_changes() };
+    assert!(repo.update(2, changes).await.is_err());
+
+    let changes = UpdateItem { replacement_cost: Some(Money::new(Decimal::from(1200), "IDR")), ..no_changes() };
+    let updated = repo.update(2, changes).await.unwrap().unwrap();
+    assert_eq!(updated.replacement_cost, Some(Money::new(Decimal::from(1200), "IDR")));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn delete_soft_deletes_items_without_stock(pool: PgPool) {
+    let repo = ItemRepository::new(pool);
+    let created = repo.create(new_item("ITM400")).await.unwrap();
+
+    // Item 1 still has stock on hand
+    assert!(repo.delete(1).await.is_err());
+    assert!(repo.get_by_id(1).await.unwrap().is_some());
+
+    assert!(repo.delete(created.item_id).await.unwrap());
+    assert!(repo.get_by_id(created.item_id).await.unwrap().is_none());
+    assert!(!repo.code_exists("ITM400", None).await.unwrap());
+    assert!(!repo.delete(created.item_id).await.unwrap());
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("inactive_records"))]
+async fn restore_brings_back_deleted_items_only(pool: PgPool) {
+    let repo = ItemRepository::new(pool);
+
+    let restored = repo.restore(900).await.unwrap().unwrap();
+    assert_eq!(restored.item_code, "ITM900");
+    assert_eq!(restored.status, "ACTIVE");
+    assert!(repo.code_exists("ITM900", None).await.unwrap());
+
+    assert!(repo.restore(900).await.unwrap().is_none());
+    assert!(repo.restore(999_999).await.unwrap().is_none());
+}
@@ -0,0 +1,177 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::NaiveDate;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn october() -> KpiFilter {
+    KpiFilter {
+        warehouse_id: Some(1),
+        from: NaiveDate::from_ymd_opt(2025, 10, 1),
+        to: NaiveDate::from_ymd_opt(2025, 10, 31),
+        period: None,
+    }
+}
+
+/// An order at warehouse 1 due on `due` for `ordered` units of each line, shipped in
+/// `shipments` of (UTC time, units per line)
+async fn order(pool: &PgPool, number: &str, due: &str, status: &str, ordered: &[i64], shipments: &[(&str, &[i64])]) {
+    let order_id: i32 = sqlx::query_scalar(
+        "INSERT INTO warehouse.outbound_orders (order_number, warehouse_id, customer_name, requested_ship_date, status)
+         VALUES ($1, 1, 'PT Maju', $2::DATE, $3) RETURNING order_id",
+    )
+    .bind(number)
+    .bind(due)
+    .bind(status)
+    .fetch_one(pool)
+    .await
+    .unwrap();
+
+    let mut line_ids = Vec::new();
+    for (i, quantity) in ordered.iter().enumerate() {
+        let line_id: i32 = sqlx::query_scalar(
+            "INSERT INTO warehouse.outbound_order_lines (order_id, line_number, item_id, quantity_ordered)
+             VALUES ($1, $2, 2, $3) RETURNING line_id",
+        )
+        .bind(order_id)
+        .bind(i as i32 + 1)
+        .bind(Decimal::from(*quantity))
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        line_ids.push(line_id);
+    }
+
+    for (n, (shipped_at, quantities)) in shipments.iter().enumerate() {
+        let shipment_id: i32 = sqlx::query_scalar(
+            "INSERT INTO warehouse.shipments (shipment_number, order_id, warehouse_id, shipped_at)
+             VALUES ($1, $2, 1, $3::TIMESTAMPTZ) RETURNING shipment_id",
+        )
+        .bind(format!("{}-{}", number, n + 1))
+        .bind(order_id)
+        .bind(shipped_at)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        for (line_id, quantity) in line_ids.iter().zip(quantities.iter()).filter(|(_, q)| **q > 0) {
+            sqlx::query(
+                "INSERT INTO warehouse.shipment_lines (shipment_id, order_line_id, item_id, quantity)
+                 VALUES ($1, $2, 2, $3)",
+            )
+            .bind(shipment_id)
+            .bind(line_id)
+            .bind(Decimal::from(*quantity))
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn otif_and_fill_rate_per_period(pool: PgPool) {
+    // On time and in full; 2025-10-06 01:00 in Jakarta is still the requested day
+    order(&pool, "SO-1", "2025-10-06", ORDER_SHIPPED, &[10, 5], &[("2025-10-05 18:00:00+00", &[10, 5])]).await;
+    // Short-shipped first, completed a day late
+    order(
+        &pool,
+        "SO-2",
+        "2025-10-07",
+        ORDER_SHIPPED,
+        &[10, 5],
+        &[("2025-10-07 03:00:00+00", &[10, 0]), ("2025-10-08 03:00:00+00", &[0, 5])],
+    )
+    .await;
+    // Past due and never shipped
+    order(&pool, "SO-3", "2025-10-08", ORDER_OPEN, &[4], &[]).await;
+    // Cancelled orders are not measured
+    order(&pool, "SO-4", "2025-10-08", ORDER_CANCELLED, &[4], &[]).await;
+
+    let kpis = KpiRepository::new(pool);
+    let otif = kpis.otif(october()).await.unwrap();
+    assert_eq!(otif.len(), 1);
+    let october_otif = &otif[0];
+    assert_eq!(october_otif.period_start, NaiveDate::from_ymd_opt(2025, 10, 1).unwrap());
+    assert_eq!(
+        (october_otif.orders_due, october_otif.on_time, october_otif.in_full, october_otif.on_time_in_full),
+        (3, 2, 2, 1)
+    );
+    assert_eq!(october_otif.otif_pct, Some(Decimal::new(3333, 2)));
+
+    let fill = kpis.fill_rate(october()).await.unwrap();
+    assert_eq!(fill.len(), 1);
+    let october_fill = &fill[0];
+    assert_eq!((october_fill.orders_shipped, october_fill.orders_filled), (2, 1));
+    assert_eq!((october_fill.lines_ordered, october_fill.lines_filled), (4, 3));
+    assert_eq!(october_fill.units_ordered, Decimal::from(30));
+    assert_eq!(october_fill.units_shipped, Decimal::from(25));
+    assert_eq!(october_fill.unit_fill_pct, Some(Decimal::new(8333, 2)));
+
+    let daily = kpis.fill_rate(KpiFilter { period: Some(KPI_PERIOD_DAY.to_string()), ..october() }).await.unwrap();
+    let days: Vec<_> = daily.iter().map(|row| (row.period_start.to_string(), row.orders_filled)).collect();
+    assert_eq!(days, [("2025-10-06".to_string(), 1), ("2025-10-07".to_string(), 0)]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn dock_to_stock_and_count_accuracy(pool: PgPool) {
+    let receipts = ReceiptRepository::new(pool.clone());
+    let receipt = receipts
+        .create(CreateReceipt {
+            asn_number: "ASN-KPI".to_string(),
+            warehouse_id: 1,
+            supplier_name: None,
+            purchase_order_number: None,
+            carrier_code: None,
+            expected_date: None,
+            consignor_id: None,
+            lines: vec![CreateReceiptLine { item_id: 3, quantity_expected: Decimal::from(20), lpn_number: None }],
+        })
+        .await
+        .unwrap();
+    let id = receipt.receipt.receipt_id;
+    sqlx::query(
+        "UPDATE warehouse.inbound_receipts SET status = 'RECEIVED', received_at = '2025-10-10 03:00:00+00'
+         WHERE receipt_id = $1",
+    )
+    .bind(id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "UPDATE warehouse.tasks SET status = 'COMPLETED', started_at = '2025-10-10 02:00:00+00',
+             completed_at = '2025-10-10 06:30:00+00'
+         WHERE reference_type = 'RECEIPT' AND reference_id = $1",
+    )
+    .bind(id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    for (expected, counted) in [(50, 50), (100, 96), (8, 8), (20, 21)] {
+        sqlx::query(
+            "INSERT INTO warehouse.tasks (warehouse_id, task_type, status, item_id, quantity, quantity_done, completed_at)
+             VALUES (1, 'COUNT', 'COMPLETED', 3, $1, $2, '2025-10-15 04:00:00+00')",
+        )
+        .bind(Decimal::from(expected))
+        .bind(Decimal::from(counted))
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let kpis = KpiRepository::new(pool);
+    let dock = kpis.dock_to_stock(october()).await.unwrap();
+    assert_eq!(dock.len(), 1);
+    assert_eq!(dock[0].receipts, 1);
+    assert_eq!(dock[0].avg_hours, Decimal::new(45, 1));
+    assert_eq!(dock[0].median_hours, Decimal::new(45, 1));
+
+    let accuracy = kpis.inventory_accuracy(october()).await.unwrap();
+    assert_eq!(accuracy.len(), 1);
+    let counts = &accuracy[0];
+    assert_eq!((counts.counts, counts.accurate_counts), (4, 2));
+    assert_eq!(counts.accuracy_pct, Decimal::from(50));
+    assert_eq!(counts.units_expected, Decimal::from(178));
+    assert_eq!(counts.units_variance, Decimal::from(5));
+    assert_eq!(counts.variance_pct, Some(Decimal::new(281, 2)));
+}
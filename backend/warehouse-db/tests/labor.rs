@@ -0,0 +1,135 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::{NaiveDate, NaiveTime};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// Day and night shifts at warehouse 1 (Asia/Jakarta); returns the day shift
+async fn shifts(pool: &PgPool) -> Shift {
+    let repo = ShiftRepository::new(pool.clone());
+    let shift = |code: &str, starts: u32, ends: u32| CreateShift {
+        shift_code: code.to_string(),
+        shift_name: code.to_string(),
+        starts_at: NaiveTime::from_hms_opt(starts, 0, 0).unwrap(),
+        ends_at: NaiveTime::from_hms_opt(ends, 0, 0).unwrap(),
+        operator_ids: vec![7, 8],
+    };
+    repo.create(1, shift("NIGHT", 18, 6)).await.unwrap();
+    repo.create(1, shift("DAY", 6, 18)).await.unwrap().shift
+}
+
+/// A completed task claimed at `started` (UTC) taking `minutes`
+async fn worked(pool: &PgPool, operator_id: i32, task_type: &str, started: &str, minutes: i32, quantity: (i64, i64)) {
+    sqlx::query(
+        "INSERT INTO warehouse.tasks (
+             warehouse_id, task_type, status, quantity, quantity_done, assigned_to, completed_by,
+             started_at, completed_at
+         ) VALUES (1, $1, 'COMPLETED', $2, $3, $4, $4, $5::TIMESTAMPTZ, $5::TIMESTAMPTZ + make_interval(mins => $6))",
+    )
+    .bind(task_type)
+    .bind(Decimal::from(quantity.0))
+    .bind(Decimal::from(quantity.1))
+    .bind(operator_id)
+    .bind(started)
+    .bind(minutes)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+fn all_time() -> LaborReportFilter {
+    LaborReportFilter {
+        from: Some("2025-01-01T00:00:00Z".parse().unwrap()),
+        ..Default::default()
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn rates_and_accuracy_per_operator_and_shift(pool: PgPool) {
+    shifts(&pool).await;
+    worked(&pool, 7, TASK_PICK, "2025-10-13 08:00:00+00", 15, (4, 4)).await;
+    worked(&pool, 7, TASK_PICK, "2025-10-13 08:15:00+00", 15, (4, 3)).await;
+    worked(&pool, 7, TASK_RECEIVE, "2025-10-13 09:00:00+00", 60, (20, 20)).await;
+    worked(&pool, 8, TASK_PICK, "2025-10-13 09:00:00+00", 30, (1, 1)).await;
+
+    let rows = LaborRepository::new(pool).report(all_time()).await.unwrap();
+
+    assert_eq!(rows.len(), 2);
+    let seven = &rows[0];
+    assert_eq!((seven.operator_id, seven.shift_code.as_str()), (7, "DAY"));
+    assert_eq!((seven.tasks_completed, seven.picks, seven.receipts), (3, 2, 1));
+    assert_eq!(seven.hours_worked, Decimal::new(150, 2));
+    assert_eq!(seven.picks_per_hour, Some(Decimal::new(400, 2)));
+    assert_eq!(seven.receipts_per_hour, Some(Decimal::new(100, 2)));
+    assert_eq!(seven.accuracy_pct, Decimal::new(6667, 2));
+    assert_eq!(rows[1].receipts_per_hour, None);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn overnight_shift_belongs_to_the_day_it_started(pool: PgPool) {
+    let day = shifts(&pool).await;
+    // 01:00 in Jakarta on the 14th is the night shift of the 13th
+    worked(&pool, 7, TASK_PICK, "2025-10-13 18:00:00+00", 10, (1, 1)).await;
+    worked(&pool, 7, TASK_PICK, "2025-10-14 02:00:00+00", 10, (1, 1)).await;
+
+    let repo = LaborRepository::new(pool);
+    let rows = repo.report(all_time()).await.unwrap();
+
+    assert_eq!(rows[0].shift_code, "NIGHT");
+    assert_eq!(rows[0].shift_date, NaiveDate::from_ymd_opt(2025, 10, 13).unwrap());
+    let day_only = repo.report(LaborReportFilter { shift_id: Some(day.shift_id), ..all_time() }).await.unwrap();
+    assert_eq!(day_only.len(), 1);
+    assert_eq!(day_only[0].shift_date, NaiveDate::from_ymd_opt(2025, 10, 14).unwrap());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn warehouse_without_shifts_reports_unscheduled(pool: PgPool) {
+    worked(&pool, 7, TASK_COUNT, "2025-10-13 08:00:00+00", 10, (1, 1)).await;
+
+    let rows = LaborRepository::new(pool).report(all_time()).await.unwrap();
+
+    assert_eq!(rows[0].shift_code, SHIFT_UNSCHEDULED);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn receiving_task_follows_the_receipt(pool: PgPool) {
+    let receipts = ReceiptRepository::new(pool.clone());
+    let receipt = receipts
+        .create(CreateReceipt {
+            asn_number: "ASN-LAB-1".to_string(),
+            warehouse_id: 1,
+            supplier_name: None,
+            purchase_order_number: None,
+            carrier_code: None,
+            expected_date: None,
+            consignor_id: None,
+            lines: vec![CreateReceiptLine { item_id: 2, quantity_expected: Decimal::from(10), lpn_number: None }],
+        })
+        .await
+        .unwrap();
+    let tasks = TaskRepository::new(pool.clone());
+    let task = tasks
+        .claim_next(ClaimNextTask { warehouse_id: 1, operator_id: 7, task_types: Some(vec![TASK_RECEIVE.to_string()]) })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.reference_id, Some(receipt.receipt.receipt_id));
+
+    let line_id = receipt.lines[0].line_id;
+    receipts
+        .receive(
+            receipt.receipt.receipt_id,
+            ReceiveGoods {
+                lines: vec![ReceiveGoodsLine { line_id, quantity: Decimal::from(10), weight_kg: None, lot_number: None }],
+                reference_id: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    let done = tasks.get_by_id(task.task_id).await.unwrap().unwrap();
+    assert_eq!(done.status, TASK_COMPLETED);
+    assert_eq!(done.completed_by, Some(7));
+    let rows = LaborRepository::new(pool).report(all_time()).await.unwrap();
+    assert_eq!(rows[0].receipts, 1);
+}
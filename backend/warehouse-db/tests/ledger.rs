@@ -0,0 +1,142 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::Utc;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn receive(pool: &PgPool, item_id: i32, quantity: i64) {
+    let receipts = ReceiptRepository::new(pool.clone());
+    let created = receipts
+        .create(CreateReceipt {
+            asn_number: "ASN-LEDGER-1".to_string(),
+            warehouse_id: 1,
+            supplier_name: None,
+            purchase_order_number: None,
+            carrier_code: None,
+            expected_date: None,
+            consignor_id: None,
+            lines: vec![CreateReceiptLine { item_id, quantity_expected: Decimal::from(quantity), lpn_number: None }],
+        })
+        .await
+        .unwrap();
+    let lines = vec![ReceiveGoodsLine {
+        line_id: created.lines[0].line_id,
+        quantity: Decimal::from(quantity),
+        weight_kg: None,
+        lot_number: None,
+    }];
+    receipts.receive(created.receipt.receipt_id, ReceiveGoods { lines, reference_id: None }).await.unwrap().unwrap();
+}
+
+async fn ship(pool: &PgPool, item_id: i32, quantity: i64) -> ShipmentDetail {
+    let orders = OrderRepository::new(pool.clone());
+    let created = orders
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "PT Karya Beton".to_string(),
+            customer_reference: None,
+            ship_to_name: None,
+            ship_to_address: None,
+            ship_to_city: None,
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![CreateOrderLine { item_id, quantity_ordered: Decimal::from(quantity) }],
+        })
+        .await
+        .unwrap();
+    let confirmation = ShipOrder {
+        carrier_code: Some("JNE".to_string()),
+        tracking_number: Some("JNE-LEDGER-1".to_string()),
+        service_level: None,
+        lines: vec![ShipOrderLine {
+            line_id: created.lines[0].line_id,
+            quantity: Decimal::from(quantity),
+            weight_kg: None,
+            lot_number: None,
+        }],
+        lpns: Vec::new(),
+        reference_id: None,
+    };
+    orders.ship(created.order.order_id, confirmation, None).await.unwrap().unwrap().1
+}
+
+fn balance(integrity: &LedgerIntegrity, account: &str) -> Decimal {
+    integrity.trial_balance.iter().find(|b| b.account == account).map_or(Decimal::ZERO, |b| b.quantity)
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn movements_post_balanced_entries_through_delivery(pool: PgPool) {
+    let ledger = LedgerRepository::new(pool.clone());
+    let opening = ledger.integrity().await.unwrap();
+    assert!(opening.reconciled, "{:?}", opening);
+
+    receive(&pool, 4, 20).await;
+    let shipped = ship(&pool, 4, 5).await;
+
+    let in_transit = ledger.integrity().await.unwrap();
+    assert!(in_transit.reconciled, "{:?}", in_transit);
+    assert_eq!(balance(&in_transit, ACCOUNT_SUPPLIER), Decimal::from(-20));
+    assert_eq!(balance(&in_transit, ACCOUNT_IN_TRANSIT), Decimal::from(5));
+
+    let delivered = NewTrackingEvent {
+        status: TrackingStatus::Delivered,
+        description: None,
+        location: None,
+        occurred_at: Utc::now(),
+    };
+    let tracking = TrackingRepository::new(pool.clone());
+    tracking.record(shipped.shipment.shipment_id, vec![delivered.clone()], TRACKING_SOURCE_POLL).await.unwrap();
+    // A repeated delivery scan does not take the goods out of transit twice
+    let later = NewTrackingEvent { occurred_at: Utc::now(), ..delivered };
+    tracking.record(shipped.shipment.shipment_id, vec![later], TRACKING_SOURCE_POLL).await.unwrap();
+
+    let closed = ledger.integrity().await.unwrap();
+    assert!(closed.reconciled, "{:?}", closed);
+    assert_eq!(balance(&closed, ACCOUNT_IN_TRANSIT), Decimal::ZERO);
+    assert_eq!(balance(&closed, ACCOUNT_CUSTOMER), Decimal::from(5));
+
+    let entries = ledger
+        .entries(
+            LedgerFilter { item_id: Some(4), warehouse_id: Some(1), account: Some(ACCOUNT_ON_HAND.to_string()) },
+            KeysetQuery { after_id: None, limit: None },
+        )
+        .await
+        .unwrap();
+    let on_hand: Vec<_> = entries.data.iter().map(|e| e.quantity).collect();
+    assert_eq!(on_hand, [Decimal::from(-5), Decimal::from(20)]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn integrity_check_reports_drift_and_unbalanced_postings(pool: PgPool) {
+    receive(&pool, 4, 20).await;
+    sqlx::query(
+        "UPDATE warehouse.stock_inventory SET quantity_on_hand = quantity_on_hand + 1
+         WHERE item_id = 4 AND warehouse_id = 1",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO warehouse.ledger_entries (transaction_id, item_id, warehouse_id, account, quantity)
+         SELECT MAX(transaction_id), 3, 1, 'SCRAP', 2 FROM warehouse.ledger_transactions",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let integrity = LedgerRepository::new(pool).integrity().await.unwrap();
+
+    assert!(!integrity.reconciled);
+    let drift: Vec<_> = integrity
+        .bucket_mismatches
+        .iter()
+        .map(|m| (m.item_id, m.account.as_str(), m.ledger_quantity, m.stock_quantity))
+        .collect();
+    assert_eq!(drift, [(4, ACCOUNT_ON_HAND, Decimal::from(20), Decimal::from(21))]);
+    assert_eq!(integrity.unbalanced_transactions.len(), 1);
+    assert_eq!(integrity.unbalanced_transactions[0].imbalance, Decimal::from(2));
+}
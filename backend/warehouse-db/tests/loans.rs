@@ -0,0 +1,89 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn loan(item_id: i32, quantity: i64) -> CreateLoan {
+    CreateLoan {
+        warehouse_id: 1,
+        item_id,
+        quantity: Decimal::from(quantity),
+        borrower_name: "Budi Santoso".to_string(),
+        ..Default::default()
+    }
+}
+
+/// Item 1's available quantity in warehouse 1
+async fn item_1_available(pool: &PgPool) -> Decimal {
+    sqlx::query_scalar(
+        "SELECT quantity_available FROM warehouse.stock_inventory
+         WHERE item_id = 1 AND warehouse_id = 1 AND consignor_id IS NULL",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn lent_tools_leave_stock_until_they_come_back(pool: PgPool) {
+    let repo = LoanRepository::new(pool.clone());
+
+    let lent = repo.lend(loan(1, 3)).await.unwrap();
+    assert_eq!(lent.status, LOAN_OUT);
+    assert!(lent.loan_number.starts_with("LN-"));
+    assert_eq!(item_1_available(&pool).await, Decimal::ONE);
+
+    let short = repo.lend(loan(1, 2)).await.unwrap_err();
+    assert!(short.to_string().contains("only 1 available"), "{}", short);
+    let not_loanable = repo.lend(loan(2, 1)).await.unwrap_err();
+    assert!(not_loanable.to_string().contains("not loanable"), "{}", not_loanable);
+    let too_long = CreateLoan { due_on: Some(lent.due_on.succ_opt().unwrap()), ..loan(1, 1) };
+    assert!(repo.lend(too_long).await.is_err());
+
+    let returned = repo.return_loan(lent.loan_id).await.unwrap().unwrap();
+    assert_eq!(returned.status, LOAN_RETURNED);
+    assert!(returned.returned_at.is_some());
+    assert_eq!(item_1_available(&pool).await, Decimal::from(4));
+    assert!(repo.return_loan(lent.loan_id).await.is_err());
+
+    let ledger = LedgerRepository::new(pool.clone()).integrity().await.unwrap();
+    assert!(ledger.reconciled, "{:?}", ledger);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn loans_past_due_are_flagged_and_notified_once(pool: PgPool) {
+    let repo = LoanRepository::new(pool.clone());
+    let late = repo.lend(loan(1, 1)).await.unwrap();
+    let on_time = repo.lend(loan(1, 1)).await.unwrap();
+    sqlx::query("UPDATE warehouse.tool_loans SET due_on = CURRENT_DATE - 3 WHERE loan_id = $1")
+        .bind(late.loan_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Listed as overdue before the scan reaches it
+    let overdue = repo.overdue().await.unwrap();
+    assert_eq!(overdue.len(), 1);
+    assert_eq!((overdue[0].loan_id, overdue[0].days_overdue), (late.loan_id, 3));
+    assert_eq!(overdue[0].item_code, "ITM001");
+
+    let flagged = repo.flag_overdue().await.unwrap();
+    assert_eq!(flagged.iter().map(|loan| loan.loan_id).collect::<Vec<_>>(), [late.loan_id]);
+    assert_eq!(flagged[0].status, LOAN_OVERDUE);
+    assert!(repo.flag_overdue().await.unwrap().is_empty());
+    assert_eq!(repo.get(on_time.loan_id).await.unwrap().unwrap().status, LOAN_OUT);
+
+    let notified: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM warehouse.notifications WHERE kind = 'LOAN_OVERDUE' AND reference_id = $1",
+    )
+    .bind(late.loan_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(notified, 1);
+
+    // Returning an overdue loan closes it
+    let returned = repo.return_loan(late.loan_id).await.unwrap().unwrap();
+    assert_eq!(returned.status, LOAN_RETURNED);
+    assert!(repo.overdue().await.unwrap().is_empty());
+}
@@ -0,0 +1,136 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn lpn(lpn_type: &str, parent_lpn_id: Option<i32>) -> CreateLpn {
+    CreateLpn { lpn_number: None, lpn_type: lpn_type.to_string(), location_id: None, parent_lpn_id }
+}
+
+fn quantity(quantity: i64) -> SetLpnContent {
+    SetLpnContent { quantity: Decimal::from(quantity) }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn receive_lpn_posts_its_lines_onto_the_plate(pool: PgPool) {
+    let receipts = ReceiptRepository::new(pool.clone());
+    let line = |item_id, expected, lpn: &str| CreateReceiptLine {
+        item_id,
+        quantity_expected: Decimal::from(expected),
+        lpn_number: Some(lpn.to_string()),
+    };
+    let created = receipts
+        .create(CreateReceipt {
+            asn_number: "ASN-LPN".to_string(),
+            warehouse_id: 1,
+            supplier_name: None,
+            purchase_order_number: None,
+            carrier_code: None,
+            expected_date: None,
+            consignor_id: None,
+            lines: vec![line(2, 200, "SSCC-001"), line(3, 20, "SSCC-001"), line(2, 100, "SSCC-002")],
+        })
+        .await
+        .unwrap();
+    let id = created.receipt.receipt_id;
+
+    let (detail, received) = receipts.receive_lpn(id, "SSCC-001", ReceiveLpn::default()).await.unwrap().unwrap();
+    assert_eq!(detail.receipt.status, RECEIPT_PARTIALLY_RECEIVED);
+    assert_eq!(received.lpn.lpn_type, LPN_PALLET);
+    assert_eq!(received.lpn.receipt_id, Some(id));
+    let contents: Vec<_> = received.contents.iter().map(|c| (c.item_id, c.quantity)).collect();
+    assert_eq!(contents, [(2, Decimal::from(200)), (3, Decimal::from(20))]);
+
+    let again = receipts.receive_lpn(id, "SSCC-001", ReceiveLpn::default()).await.unwrap_err();
+    assert!(again.downcast_ref::<RuleViolation>().is_some());
+    let unknown = receipts.receive_lpn(id, "SSCC-404", ReceiveLpn::default()).await.unwrap_err();
+    assert!(unknown.downcast_ref::<RuleViolation>().is_some());
+
+    let (detail, _) = receipts.receive_lpn(id, "SSCC-002", ReceiveLpn::default()).await.unwrap().unwrap();
+    assert_eq!(detail.receipt.status, RECEIPT_RECEIVED);
+    let on_hand: Decimal = sqlx::query_scalar(
+        "SELECT quantity_on_hand FROM warehouse.stock_inventory WHERE item_id = 2 AND warehouse_id = 1",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(on_hand, Decimal::from(1300));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn nesting_rolls_up_and_rejects_cycles(pool: PgPool) {
+    let repo = LpnRepository::new(pool);
+    let pallet = repo.create(1, lpn(LPN_PALLET, None)).await.unwrap();
+    let pallet_id = pallet.lpn.lpn_id;
+    assert_eq!(pallet.lpn.lpn_number, format!("LPN{:08}", pallet_id));
+    let case = repo.create(1, lpn(LPN_CASE, Some(pallet_id))).await.unwrap();
+    let case_id = case.lpn.lpn_id;
+
+    repo.set_content(case_id, 2, quantity(600)).await.unwrap().unwrap();
+    repo.set_content(pallet_id, 2, quantity(100)).await.unwrap().unwrap();
+
+    // Only 1000 rebar on hand, 700 of it already on plates
+    let over = repo.set_content(pallet_id, 2, quantity(401)).await.unwrap_err();
+    assert!(over.downcast_ref::<RuleViolation>().is_some());
+
+    let detail = repo.get(pallet_id).await.unwrap().unwrap();
+    assert_eq!(detail.children.len(), 1);
+    let totals: Vec<_> = detail.totals.iter().map(|c| (c.item_id, c.quantity)).collect();
+    assert_eq!(totals, [(2, Decimal::from(700))]);
+
+    let cycle =
+        repo.move_lpn(pallet_id, MoveLpn { location_id: None, parent_lpn_id: Some(case_id) }).await.unwrap_err();
+    assert!(cycle.downcast_ref::<RuleViolation>().is_some());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn ship_by_lpn_ships_the_whole_plate(pool: PgPool) {
+    let lpns = LpnRepository::new(pool.clone());
+    let pallet = lpns.create(1, lpn(LPN_PALLET, None)).await.unwrap().lpn;
+    let case = lpns.create(1, lpn(LPN_CASE, Some(pallet.lpn_id))).await.unwrap().lpn;
+    lpns.set_content(case.lpn_id, 2, quantity(8)).await.unwrap().unwrap();
+    lpns.set_content(pallet.lpn_id, 3, quantity(2)).await.unwrap().unwrap();
+
+    let orders = OrderRepository::new(pool.clone());
+    let order = |lines: Vec<CreateOrderLine>| CreateOrder {
+        warehouse_id: 1,
+        customer_name: "PT Karya Beton".to_string(),
+        customer_reference: None,
+        ship_to_name: None,
+        ship_to_address: None,
+        ship_to_city: None,
+        ship_to_state: None,
+        ship_to_postal_code: None,
+        ship_to_country: None,
+        requested_ship_date: None,
+        carrier_code: None,
+        dropship_supplier_code: None,
+        lines,
+    };
+    let line = |item_id, ordered| CreateOrderLine { item_id, quantity_ordered: Decimal::from(ordered) };
+    let by_lpn = |numbers: &[&str]| ShipOrder {
+        carrier_code: None,
+        tracking_number: None,
+        service_level: None,
+        lines: Vec::new(),
+        lpns: numbers.iter().map(|n| n.to_string()).collect(),
+        reference_id: None,
+    };
+
+    // The pallet holds more rebar than this order wants
+    let short = orders.create(order(vec![line(2, 5), line(3, 2)])).await.unwrap();
+    let over = orders.ship(short.order.order_id, by_lpn(&[&pallet.lpn_number]), None).await.unwrap_err();
+    assert!(over.downcast_ref::<RuleViolation>().is_some());
+
+    let created = orders.create(order(vec![line(2, 10), line(3, 2)])).await.unwrap();
+    let (shipped, shipment) =
+        orders.ship(created.order.order_id, by_lpn(&[&pallet.lpn_number]), None).await.unwrap().unwrap();
+    assert_eq!(shipped.order.status, ORDER_PARTIALLY_SHIPPED);
+    assert_eq!(shipment.lines.len(), 2);
+
+    let case = lpns.get(case.lpn_id).await.unwrap().unwrap().lpn;
+    assert_eq!(case.status, LPN_SHIPPED);
+    assert_eq!(case.shipment_id, Some(shipment.shipment.shipment_id));
+    let again = orders.ship(created.order.order_id, by_lpn(&[&pallet.lpn_number]), None).await.unwrap_err();
+    assert!(again.downcast_ref::<RuleViolation>().is_some());
+}
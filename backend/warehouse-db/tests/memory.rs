@@ -0,0 +1,34 @@
+use warehouse_db::*;
+use warehouse_models::chrono::NaiveDate;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+#[tokio::test]
+async fn mock_stock_sums_the_seed_movements() {
+    let db = Database::in_memory(MemoryStore::seeded()).unwrap();
+
+    let snapshot = db.stock().snapshot(StockFilter::default()).await.unwrap();
+    let on_hand: Vec<(&str, i32, Decimal)> = snapshot
+        .balances
+        .iter()
+        .map(|balance| (balance.item_code.as_str(), balance.warehouse_id, balance.quantity_on_hand))
+        .collect();
+    assert_eq!(
+        on_hand,
+        [
+            ("ITM001", 1, Decimal::from(5)),
+            ("ITM002", 1, Decimal::from(1000)),
+            ("ITM003", 1, Decimal::from(50)),
+            ("ITM004", 2, Decimal::from(2)),
+        ]
+    );
+
+    let helmets = StockFilter { q: Some("helmet".to_string()), ..Default::default() };
+    assert_eq!(db.stock().snapshot(helmets).await.unwrap().balances.len(), 1);
+    let surabaya = StockFilter { warehouse_id: Some(2), ..Default::default() };
+    assert_eq!(db.stock().snapshot(surabaya).await.unwrap().balances[0].item_code, "ITM004");
+
+    // The seed movements are dated 2025-01-01
+    let before = StockFilter { as_of: NaiveDate::from_ymd_opt(2024, 12, 31), ..Default::default() };
+    assert!(db.stock().snapshot(before).await.unwrap().balances.is_empty());
+}
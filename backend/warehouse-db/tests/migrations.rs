@@ -0,0 +1,41 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn reports_every_migration_applied_with_matching_checksums(pool: PgPool) {
+    let status = MigrationRepository::new(pool).status().await.unwrap();
+
+    assert_eq!(status.pending, 0);
+    assert_eq!(status.mismatched, 0);
+    assert_eq!(status.applied, status.migrations.len());
+    assert_eq!(status.current_version, status.migrations.last().map(|migration| migration.version));
+    assert!(status.migrations.iter().all(|migration| migration.checksum.len() == 96));
+}
+
+#[sqlx::test(migrations = false)]
+async fn runs_pending_migrations_into_an_empty_database(pool: PgPool) {
+    let repo = MigrationRepository::new(pool.clone());
+    let before = repo.status().await.unwrap();
+    assert_eq!(before.applied, 0);
+    assert!(before.current_version.is_none());
+
+    let run = repo.run().await.unwrap();
+    assert_eq!(run.applied_versions.len(), before.pending);
+    assert_eq!(run.status.pending, 0);
+
+    // Nothing left to do
+    assert!(repo.run().await.unwrap().applied_versions.is_empty());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn flags_an_applied_migration_that_was_edited(pool: PgPool) {
+    sqlx::query("UPDATE _sqlx_migrations SET checksum = '\\x00' WHERE version = (SELECT MIN(version) FROM _sqlx_migrations)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let status = MigrationRepository::new(pool).status().await.unwrap();
+    assert_eq!(status.mismatched, 1);
+    assert!(!status.migrations[0].checksum_matches);
+    assert_eq!(status.migrations[0].checksum, "00");
+}
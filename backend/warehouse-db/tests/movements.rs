@@ -0,0 +1,79 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::*;
+
+fn keyset(after_id: Option<i64>, limit: i64) -> KeysetQuery {
+    KeysetQuery {
+        after_id,
+        limit: Some(limit),
+    }
+}
+
+fn ids(page: &KeysetPage<StockMovement>) -> Vec<i64> {
+    page.data.iter().map(|m| m.movement_id).collect()
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("movements"))]
+async fn history_walks_newest_first_by_cursor(pool: PgPool) {
+    let repo = MovementRepository::new(pool);
+
+    let first = repo.history(MovementFilter::default(), keyset(None, 4)).await.unwrap();
+    assert_eq!(ids(&first), [6, 5, 4, 3]);
+    assert!(first.has_more);
+    assert_eq!(first.next_after_id, Some(3));
+
+    let second = repo
+        .history(MovementFilter::default(), keyset(first.next_after_id, 4))
+        .await
+        .unwrap();
+    assert_eq!(ids(&second), [2, 1]);
+    assert!(!second.has_more);
+    assert_eq!(second.next_after_id, None);
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("movements"))]
+async fn history_applies_filters(pool: PgPool) {
+    let repo = MovementRepository::new(pool);
+
+    let by_item_and_warehouse = repo
+        .history(
+            MovementFilter {
+                item_id: Some(1),
+                warehouse_id: Some(1),
+                movement_type: None,
+                client_id: None,
+            },
+            keyset(None, 50),
+        )
+        .await
+        .unwrap();
+    assert_eq!(ids(&by_item_and_warehouse), [6, 3, 1]);
+
+    let issues = repo
+        .history(
+            MovementFilter {
+                movement_type: Some("ISSUE".to_string()),
+                ..Default::default()
+            },
+            keyset(Some(6), 50),
+        )
+        .await
+        .unwrap();
+    assert_eq!(ids(&issues), [5, 3]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn history_of_empty_ledger(pool: PgPool) {
+    let repo = MovementRepository::new(pool);
+
+    let page = repo.history(MovementFilter::default(), keyset(None, 10)).await.unwrap();
+
+    assert!(page.data.is_empty());
+    assert!(!page.has_more);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn estimate_row_count_handles_unknown_tables(pool: PgPool) {
+    assert!(estimate_row_count(&pool, "warehouse.items").await.unwrap() >= 0);
+    assert_eq!(estimate_row_count(&pool, "warehouse.no_such_table").await.unwrap(), 0);
+}
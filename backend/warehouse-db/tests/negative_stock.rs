@@ -0,0 +1,91 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn ship(pool: &PgPool, item_id: i32, quantity: i64) -> anyhow::Result<()> {
+    let orders = OrderRepository::new(pool.clone());
+    let created = orders
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "PT Karya Beton".to_string(),
+            customer_reference: None,
+            ship_to_name: None,
+            ship_to_address: None,
+            ship_to_city: None,
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![CreateOrderLine { item_id, quantity_ordered: Decimal::from(quantity) }],
+        })
+        .await?;
+    let confirmation = ShipOrder {
+        carrier_code: None,
+        tracking_number: None,
+        service_level: None,
+        lines: vec![ShipOrderLine {
+            line_id: created.lines[0].line_id,
+            quantity: Decimal::from(quantity),
+            weight_kg: None,
+            lot_number: None,
+        }],
+        lpns: Vec::new(),
+        reference_id: None,
+    };
+    orders.ship(created.order.order_id, confirmation, None).await?;
+    Ok(())
+}
+
+async fn on_hand(pool: &PgPool, item_id: i32) -> Decimal {
+    sqlx::query_scalar("SELECT quantity_on_hand FROM warehouse.stock_inventory WHERE item_id = $1 AND warehouse_id = 1")
+        .bind(item_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+async fn warnings(pool: &PgPool) -> Vec<Notification> {
+    let filter = NotificationFilter { kind: Some(NOTIFY_NEGATIVE_STOCK.to_string()), acknowledged: None };
+    NotificationRepository::new(pool.clone())
+        .list(filter, KeysetQuery { after_id: None, limit: None })
+        .await
+        .unwrap()
+        .data
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn issues_below_zero_are_forbidden_by_default(pool: PgPool) {
+    let error = ship(&pool, 3, 60).await.unwrap_err();
+
+    let negative = error.downcast_ref::<NegativeStock>().expect("negative stock error");
+    assert_eq!((negative.item_id, negative.warehouse_id, negative.on_hand), (3, 1, Decimal::from(-10)));
+    assert_eq!(on_hand(&pool, 3).await, Decimal::from(50));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn warehouse_policy_warns_and_item_policy_overrides(pool: PgPool) {
+    let policies = NegativeStockRepository::new(pool.clone());
+    policies.set(1, None, NEGATIVE_STOCK_WARN).await.unwrap();
+
+    ship(&pool, 3, 60).await.unwrap();
+    assert_eq!(on_hand(&pool, 3).await, Decimal::from(-10));
+    let raised = warnings(&pool).await;
+    assert_eq!(raised.len(), 1);
+    assert_eq!(raised[0].reference_id, Some(3));
+
+    policies.set(1, Some(3), NEGATIVE_STOCK_FORBID).await.unwrap();
+    let error = ship(&pool, 3, 1).await.unwrap_err();
+    assert!(error.downcast_ref::<NegativeStock>().is_some());
+
+    policies.set(1, Some(3), NEGATIVE_STOCK_ALLOW).await.unwrap();
+    ship(&pool, 3, 1).await.unwrap();
+    assert_eq!(on_hand(&pool, 3).await, Decimal::from(-11));
+    assert_eq!(warnings(&pool).await.len(), 1);
+
+    assert!(policies.clear(1, 3).await.unwrap());
+    let listed: Vec<_> = policies.list(1).await.unwrap().into_iter().map(|p| (p.item_id, p.policy)).collect();
+    assert_eq!(listed, [(None, NEGATIVE_STOCK_WARN.to_string())]);
+}
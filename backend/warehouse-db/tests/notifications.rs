@@ -0,0 +1,39 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::*;
+
+fn exception(subject: &str) -> NewNotification {
+    NewNotification {
+        kind: NOTIFY_DELIVERY_EXCEPTION,
+        severity: SEVERITY_WARNING,
+        subject: subject.to_string(),
+        message: None,
+        reference_type: Some("SHIPMENT".to_string()),
+        reference_id: Some(1),
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn acknowledged_notifications_drop_out_of_open_list(pool: PgPool) {
+    let repo = NotificationRepository::new(pool);
+    let first = repo.raise(exception("first")).await.unwrap();
+    repo.raise(exception("second")).await.unwrap();
+
+    let acknowledged = repo.acknowledge(first.notification_id).await.unwrap().unwrap();
+    let again = repo.acknowledge(first.notification_id).await.unwrap().unwrap();
+    assert_eq!(again.acknowledged_at, acknowledged.acknowledged_at);
+
+    let open = repo
+        .list(
+            NotificationFilter {
+                acknowledged: Some(false),
+                ..Default::default()
+            },
+            KeysetQuery::default(),
+        )
+        .await
+        .unwrap();
+    let subjects: Vec<_> = open.data.iter().map(|n| n.subject.as_str()).collect();
+    assert_eq!(subjects, ["second"]);
+    assert!(repo.acknowledge(999).await.unwrap().is_none());
+}
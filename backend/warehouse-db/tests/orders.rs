@@ -0,0 +1,129 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn order(lines: &[(i32, i64)]) -> CreateOrder {
+    CreateOrder {
+        warehouse_id: 1,
+        customer_name: "PT Karya Beton".to_string(),
+        customer_reference: Some("PO-77".to_string()),
+        ship_to_name: None,
+        ship_to_address: None,
+        ship_to_city: None,
+        ship_to_state: None,
+        ship_to_postal_code: None,
+        ship_to_country: None,
+        requested_ship_date: None,
+        carrier_code: None,
+        dropship_supplier_code: None,
+        lines: lines
+            .iter()
+            .map(|&(item_id, quantity)| CreateOrderLine {
+                item_id,
+                quantity_ordered: Decimal::from(quantity),
+            })
+            .collect(),
+    }
+}
+
+fn ship(lines: &[(i32, i64)]) -> ShipOrder {
+    ShipOrder {
+        carrier_code: Some("JNE".to_string()),
+        tracking_number: None,
+        service_level: None,
+        lines: lines
+            .iter()
+            .map(|&(line_id, quantity)| ShipOrderLine {
+                line_id,
+                quantity: Decimal::from(quantity),
+                weight_kg: None,
+                lot_number: None,
+            })
+            .collect(),
+        lpns: Vec::new(),
+        reference_id: None,
+    }
+}
+
+async fn on_hand(pool: &PgPool, item_id: i32) -> Decimal {
+    sqlx::query_scalar("SELECT quantity_on_hand FROM warehouse.stock_inventory WHERE item_id = $1 AND warehouse_id = 1")
+        .bind(item_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn create_numbers_lines_in_order(pool: PgPool) {
+    let repo = OrderRepository::new(pool);
+
+    let created = repo.create(order(&[(2, 10), (3, 4)])).await.unwrap();
+
+    assert_eq!(created.order.order_number, format!("SO-{:06}", created.order.order_id));
+    assert_eq!(created.order.status, ORDER_OPEN);
+    assert_eq!(created.order.source, SOURCE_API);
+    let numbers: Vec<_> = created.lines.iter().map(|l| l.line_number).collect();
+    assert_eq!(numbers, [1, 2]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn ship_issues_stock_and_tracks_progress(pool: PgPool) {
+    let repo = OrderRepository::new(pool.clone());
+    let created = repo.create(order(&[(2, 10), (3, 4)])).await.unwrap();
+    let id = created.order.order_id;
+    let (rebar, helmet) = (created.lines[0].line_id, created.lines[1].line_id);
+
+    let (partial, shipment) = repo.ship(id, ship(&[(rebar, 10), (helmet, 1)]), None).await.unwrap().unwrap();
+    assert_eq!(partial.order.status, ORDER_PARTIALLY_SHIPPED);
+    assert_eq!(shipment.lines.len(), 2);
+    assert_eq!(on_hand(&pool, 2).await, Decimal::from(990));
+
+    let (shipped, _) = repo.ship(id, ship(&[(helmet, 3)]), None).await.unwrap().unwrap();
+    assert_eq!(shipped.order.status, ORDER_SHIPPED);
+    assert_eq!(on_hand(&pool, 3).await, Decimal::from(46));
+    assert_eq!(repo.shipments_for_order(id).await.unwrap().len(), 2);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn ship_rejects_overshipment_and_missing_stock(pool: PgPool) {
+    let repo = OrderRepository::new(pool.clone());
+    let created = repo.create(order(&[(3, 60)])).await.unwrap();
+    let id = created.order.order_id;
+    let line_id = created.lines[0].line_id;
+
+    let over = repo.ship(id, ship(&[(line_id, 61)]), None).await.unwrap_err();
+    assert!(over.downcast_ref::<RuleViolation>().is_some());
+
+    // Only 50 helmets on hand
+    let short = repo.ship(id, ship(&[(line_id, 60)]), None).await.unwrap_err();
+    assert!(short.downcast_ref::<NegativeStock>().is_some());
+    assert_eq!(on_hand(&pool, 3).await, Decimal::from(50));
+    assert!(repo.shipments_for_order(id).await.unwrap().is_empty());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn ship_stores_purchased_label(pool: PgPool) {
+    let repo = OrderRepository::new(pool);
+    let created = repo.create(order(&[(2, 5)])).await.unwrap();
+    let mut confirmation = ship(&[(created.lines[0].line_id, 5)]);
+    confirmation.tracking_number = Some("typed-by-hand".to_string());
+    let label = ShipmentLabel {
+        label_id: "lbl_81".to_string(),
+        tracking_number: "JNE0042".to_string(),
+        service_level: "REG".to_string(),
+        label_url: Some("https://labels.example/lbl_81.pdf".to_string()),
+        label_format: Some("PDF".to_string()),
+        amount: Some(Decimal::new(4500000, 2)),
+        currency: Some("IDR".to_string()),
+    };
+
+    let (_, shipment) = repo.ship(created.order.order_id, confirmation, Some(label)).await.unwrap().unwrap();
+
+    let shipment = shipment.shipment;
+    assert_eq!(shipment.tracking_number.as_deref(), Some("JNE0042"));
+    assert_eq!(shipment.service_level.as_deref(), Some("REG"));
+    assert_eq!(shipment.label_id.as_deref(), Some("lbl_81"));
+    assert_eq!(shipment.shipping_cost, Some(Decimal::new(4500000, 2)));
+    assert_eq!(shipment.currency.as_deref(), Some("IDR"));
+}
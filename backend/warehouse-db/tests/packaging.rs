@@ -0,0 +1,145 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn materials(materials: &[(i32, i64)]) -> SetBoxMaterials {
+    SetBoxMaterials {
+        materials: materials
+            .iter()
+            .map(|&(item_id, quantity)| SetBoxMaterial { item_id, quantity: Decimal::from(quantity) })
+            .collect(),
+    }
+}
+
+/// A medium box in warehouse 1 and a picked wave with one order of 4 units of item 2
+async fn packing(pool: &PgPool) -> (BoxSize, i32, PackCarton) {
+    let size = CartonizationRepository::new(pool.clone())
+        .create_box(
+            1,
+            CreateBoxSize {
+                box_code: "M".to_string(),
+                length_cm: Decimal::from(40),
+                width_cm: Decimal::from(30),
+                height_cm: Decimal::from(20),
+                max_weight_kg: Decimal::from(15),
+                tare_weight_kg: Decimal::ZERO,
+            },
+        )
+        .await
+        .unwrap();
+    let order = OrderRepository::new(pool.clone())
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "PT Karya Beton".to_string(),
+            customer_reference: None,
+            ship_to_name: None,
+            ship_to_address: None,
+            ship_to_city: None,
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![CreateOrderLine { item_id: 2, quantity_ordered: Decimal::from(4) }],
+        })
+        .await
+        .unwrap();
+    let waves = WaveRepository::new(pool.clone());
+    let wave_id = waves
+        .create(CreateWave {
+            warehouse_id: 1,
+            zone_id: None,
+            carrier_code: None,
+            cutoff_date: None,
+            order_ids: None,
+            max_orders: None,
+        })
+        .await
+        .unwrap()
+        .wave
+        .wave_id;
+    let task = waves.release(wave_id).await.unwrap().unwrap().tasks.remove(0);
+    waves.confirm_pick(wave_id, task.task_id, ConfirmPick { quantity_picked: task.quantity }).await.unwrap();
+
+    let carton = PackCarton {
+        order_id: order.order.order_id,
+        box_id: Some(size.box_id),
+        lines: vec![PackCartonLine { line_id: order.lines[0].line_id, quantity: Decimal::from(2) }],
+    };
+    (size, wave_id, carton)
+}
+
+async fn on_hand(pool: &PgPool, item_id: i32) -> Decimal {
+    sqlx::query_scalar!(
+        "SELECT quantity_on_hand FROM warehouse.stock_inventory WHERE item_id = $1 AND warehouse_id = 1",
+        item_id
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn packing_issues_box_materials_from_stock(pool: PgPool) {
+    let repo = PackagingRepository::new(pool.clone());
+    let waves = WaveRepository::new(pool.clone());
+    let (size, wave_id, carton) = packing(&pool).await;
+
+    let repeated = repo.set_materials(size.box_id, materials(&[(1, 1), (1, 2)])).await.unwrap_err();
+    assert!(repeated.downcast_ref::<RuleViolation>().is_some());
+    let unknown = repo.set_materials(size.box_id, materials(&[(9999, 1)])).await.unwrap_err();
+    assert!(unknown.downcast_ref::<RuleViolation>().is_some());
+
+    // Item 1 stands in for the carton itself, item 3 for void fill
+    let set = repo.set_materials(size.box_id, materials(&[(3, 2), (1, 1)])).await.unwrap();
+    assert_eq!(set.iter().map(|material| material.item_id).collect::<Vec<_>>(), [1, 3]);
+
+    let (_, packed) = waves.pack(wave_id, carton.clone()).await.unwrap().unwrap();
+    assert_eq!(on_hand(&pool, 1).await, Decimal::from(4));
+    assert_eq!(on_hand(&pool, 3).await, Decimal::from(48));
+
+    let issued = MovementRepository::new(pool.clone())
+        .history(
+            MovementFilter { movement_type: Some(MOVEMENT_PACKAGING.to_string()), ..Default::default() },
+            KeysetQuery::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(issued.data.len(), 2);
+    assert!(issued
+        .data
+        .iter()
+        .all(|movement| movement.reference_number.as_deref() == Some(packed.carton.carton_number.as_str())));
+
+    // Packing stops when the packaging is not in stock
+    repo.set_materials(size.box_id, materials(&[(1, 10)])).await.unwrap();
+    let short = waves.pack(wave_id, carton).await.unwrap_err();
+    assert!(short.downcast_ref::<RuleViolation>().is_some());
+    assert_eq!(on_hand(&pool, 1).await, Decimal::from(4));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn reorder_alerts_include_packaging(pool: PgPool) {
+    let repo = PackagingRepository::new(pool.clone());
+    let (size, wave_id, carton) = packing(&pool).await;
+    repo.set_materials(size.box_id, materials(&[(1, 2)])).await.unwrap();
+    WaveRepository::new(pool.clone()).pack(wave_id, carton).await.unwrap().unwrap();
+
+    let levels = SetStockLevels { reorder_point: Decimal::from(60), max_stock_level: Some(Decimal::from(100)) };
+    let stock = StockRepository::new(pool.clone()).upsert(1, 3, levels.into()).await.unwrap();
+    assert_eq!(stock.reorder_point, Some(Decimal::from(60)));
+    assert_eq!(stock.total_value, Some(Money::new(Decimal::from(7_500_000), "IDR")));
+
+    // Item 3 has 50 of 60 and comes first; item 1 is down to 2 available, at its reorder point
+    let alerts = repo.reorder_alerts(1, ReorderAlertFilter::default()).await.unwrap();
+    let summary: Vec<_> = alerts
+        .iter()
+        .map(|alert| (alert.item_id, alert.quantity_available, alert.suggested_quantity, alert.is_packaging))
+        .collect();
+    assert_eq!(summary, [(3, Decimal::from(50), Some(Decimal::from(50)), false), (1, Decimal::from(2), None, true)]);
+
+    let packaging = repo.reorder_alerts(1, ReorderAlertFilter { packaging: Some(true) }).await.unwrap();
+    assert_eq!(packaging.iter().map(|alert| alert.item_id).collect::<Vec<_>>(), [1]);
+}
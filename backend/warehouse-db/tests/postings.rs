@@ -0,0 +1,116 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn asn(number: &str) -> CreateReceipt {
+    CreateReceipt {
+        asn_number: number.to_string(),
+        warehouse_id: 1,
+        supplier_name: None,
+        purchase_order_number: None,
+        carrier_code: None,
+        expected_date: None,
+        consignor_id: None,
+        lines: vec![CreateReceiptLine { item_id: 2, quantity_expected: Decimal::from(200), lpn_number: None }],
+    }
+}
+
+fn goods(line_id: i32, reference_id: &str) -> ReceiveGoods {
+    ReceiveGoods {
+        lines: vec![ReceiveGoodsLine { line_id, quantity: Decimal::from(50), weight_kg: None, lot_number: None }],
+        reference_id: Some(reference_id.to_string()),
+    }
+}
+
+fn order() -> CreateOrder {
+    CreateOrder {
+        warehouse_id: 1,
+        customer_name: "PT Karya Beton".to_string(),
+        customer_reference: None,
+        ship_to_name: None,
+        ship_to_address: None,
+        ship_to_city: None,
+        ship_to_state: None,
+        ship_to_postal_code: None,
+        ship_to_country: None,
+        requested_ship_date: None,
+        carrier_code: None,
+        dropship_supplier_code: None,
+        lines: vec![CreateOrderLine { item_id: 2, quantity_ordered: Decimal::from(10) }],
+    }
+}
+
+async fn on_hand(pool: &PgPool) -> Decimal {
+    sqlx::query_scalar("SELECT quantity_on_hand FROM warehouse.stock_inventory WHERE item_id = 2 AND warehouse_id = 1")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn retried_receipt_posts_once(pool: PgPool) {
+    let repo = ReceiptRepository::new(pool.clone());
+    let created = repo.create(asn("ASN-1")).await.unwrap();
+    let id = created.receipt.receipt_id;
+    let line_id = created.lines[0].line_id;
+
+    let first = repo.receive(id, goods(line_id, "scan-0001")).await.unwrap().unwrap();
+    let retried = repo.receive(id, goods(line_id, "scan-0001")).await.unwrap().unwrap();
+    assert_eq!(first.lines[0].quantity_received, Decimal::from(50));
+    assert_eq!(retried.lines[0].quantity_received, Decimal::from(50));
+    assert_eq!(on_hand(&pool).await, Decimal::from(1050));
+
+    // A new key is a new receipt
+    repo.receive(id, goods(line_id, "scan-0002")).await.unwrap().unwrap();
+    assert_eq!(on_hand(&pool).await, Decimal::from(1100));
+
+    let reference = PostingRepository::new(pool).find("scan-0001").await.unwrap().unwrap();
+    assert_eq!(reference.operation, POSTING_RECEIPT);
+    assert_eq!(reference.target_id, id);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn retried_shipment_returns_the_original(pool: PgPool) {
+    let repo = OrderRepository::new(pool.clone());
+    let created = repo.create(order()).await.unwrap();
+    let id = created.order.order_id;
+    let confirmation = ShipOrder {
+        carrier_code: Some("JNE".to_string()),
+        tracking_number: None,
+        service_level: None,
+        lines: vec![ShipOrderLine {
+            line_id: created.lines[0].line_id,
+            quantity: Decimal::from(4),
+            weight_kg: None,
+            lot_number: None,
+        }],
+        lpns: Vec::new(),
+        reference_id: Some("ship-77".to_string()),
+    };
+
+    let (_, first) = repo.ship(id, confirmation.clone(), None).await.unwrap().unwrap();
+    let (order, retried) = repo.ship(id, confirmation, None).await.unwrap().unwrap();
+    assert_eq!(retried.shipment.shipment_id, first.shipment.shipment_id);
+    assert_eq!(order.lines[0].quantity_shipped, Decimal::from(4));
+    assert_eq!(on_hand(&pool).await, Decimal::from(996));
+
+    let shipments: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM warehouse.shipments WHERE order_id = $1")
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(shipments, 1);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn reference_is_bound_to_its_posting(pool: PgPool) {
+    let repo = ReceiptRepository::new(pool.clone());
+    let first = repo.create(asn("ASN-1")).await.unwrap();
+    let second = repo.create(asn("ASN-2")).await.unwrap();
+
+    repo.receive(first.receipt.receipt_id, goods(first.lines[0].line_id, "scan-0001")).await.unwrap();
+    let err = repo.receive(second.receipt.receipt_id, goods(second.lines[0].line_id, "scan-0001")).await.unwrap_err();
+    assert!(err.downcast_ref::<RuleViolation>().is_some());
+    assert_eq!(on_hand(&pool).await, Decimal::from(1050));
+}
@@ -0,0 +1,89 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::*;
+
+async fn station(pool: &PgPool, warehouse_id: i32, code: &str) -> MeasuringStation {
+    StationRepository::new(pool.clone())
+        .create_station(
+            warehouse_id,
+            CreateStation {
+                station_code: code.to_string(),
+                station_name: format!("Packing bench {}", code),
+                station_type: STATION_PACKING.to_string(),
+            },
+        )
+        .await
+        .unwrap()
+}
+
+fn printer(code: &str, printer_type: &str, address: &str, default_for: &[&str]) -> CreatePrinter {
+    CreatePrinter {
+        printer_code: code.to_string(),
+        name: format!("Printer {}", code),
+        printer_type: printer_type.to_string(),
+        address: address.to_string(),
+        station_id: None,
+        default_for: default_for.iter().map(|purpose| purpose.to_string()).collect(),
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn a_new_default_takes_over_from_the_old_one_in_its_scope(pool: PgPool) {
+    let repo = PrinterRepository::new(pool.clone());
+    let labels = [TEMPLATE_LPN_LABEL, TEMPLATE_SHIPPING_LABEL];
+    let floor = repo.create(1, printer("FLOOR", PRINTER_ZPL, "10.0.4.21", &labels)).await.unwrap();
+    assert_eq!(floor.default_for, [TEMPLATE_LPN_LABEL, TEMPLATE_SHIPPING_LABEL]);
+    assert!(repo.code_exists(1, "FLOOR").await.unwrap());
+    assert!(!repo.code_exists(2, "FLOOR").await.unwrap());
+
+    // A printer at a station is that station's default, leaving the warehouse's alone
+    let bench = station(&pool, 1, "PACK-1").await;
+    let mut at_bench = printer("PACK-1", PRINTER_AGENT, "http://10.0.4.60:8631/print", &[TEMPLATE_LPN_LABEL]);
+    at_bench.station_id = Some(bench.station_id);
+    let at_bench = repo.create(1, at_bench).await.unwrap();
+    assert_eq!(repo.get(floor.printer_id).await.unwrap().unwrap().default_for.len(), 2);
+
+    let found = |station_id| repo.default_printer(1, station_id, TEMPLATE_LPN_LABEL);
+    assert_eq!(found(Some(bench.station_id)).await.unwrap().unwrap().printer_id, at_bench.printer_id);
+    assert_eq!(found(None).await.unwrap().unwrap().printer_id, floor.printer_id);
+    assert!(repo.default_printer(2, None, TEMPLATE_LPN_LABEL).await.unwrap().is_none());
+
+    let dock =
+        repo.create(1, printer("DOCK", PRINTER_ZPL, "10.0.4.22:6101", &[TEMPLATE_SHIPPING_LABEL])).await.unwrap();
+    assert_eq!(repo.get(floor.printer_id).await.unwrap().unwrap().default_for, [TEMPLATE_LPN_LABEL]);
+    let found = repo.default_printer(1, Some(bench.station_id), TEMPLATE_SHIPPING_LABEL).await.unwrap();
+    assert_eq!(found.unwrap().printer_id, dock.printer_id);
+
+    // Inactive printers are passed over
+    let update = UpdatePrinter { is_active: Some(false), ..Default::default() };
+    repo.update(at_bench.printer_id, update).await.unwrap().unwrap();
+    let found = repo.default_printer(1, Some(bench.station_id), TEMPLATE_LPN_LABEL).await.unwrap();
+    assert_eq!(found.unwrap().printer_id, floor.printer_id);
+
+    let active = repo.list(1, &PrinterFilter { station_id: None, is_active: Some(true) }).await.unwrap();
+    assert_eq!(active.iter().map(|printer| printer.printer_code.as_str()).collect::<Vec<_>>(), ["DOCK", "FLOOR"]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn addresses_and_stations_are_checked(pool: PgPool) {
+    let repo = PrinterRepository::new(pool.clone());
+    let error = repo.create(1, printer("ZPL-URL", PRINTER_ZPL, "http://10.0.4.21", &[])).await.unwrap_err();
+    assert!(error.to_string().contains("host or host:port"), "{}", error);
+    let error = repo.create(1, printer("ZPL-PORT", PRINTER_ZPL, "10.0.4.21:zebra", &[])).await.unwrap_err();
+    assert!(error.to_string().contains("host or host:port"), "{}", error);
+    let error = repo.create(1, printer("AGENT", PRINTER_AGENT, "10.0.4.60", &[])).await.unwrap_err();
+    assert!(error.to_string().contains("http:// or https://"), "{}", error);
+
+    let elsewhere = station(&pool, 2, "PACK-9").await;
+    let mut misplaced = printer("PACK-9", PRINTER_ZPL, "10.0.4.23", &[]);
+    misplaced.station_id = Some(elsewhere.station_id);
+    let error = repo.create(1, misplaced).await.unwrap_err();
+    assert!(error.to_string().contains("is not in warehouse 1"), "{}", error);
+
+    let created = repo.create(1, printer("FLOOR", PRINTER_ZPL, "10.0.4.21", &[])).await.unwrap();
+    let update = UpdatePrinter { station_id: Some(elsewhere.station_id), ..Default::default() };
+    assert!(repo.update(created.printer_id, update).await.is_err());
+    let update = UpdatePrinter { address: Some("zebra-floor:9100".to_string()), ..Default::default() };
+    assert_eq!(repo.update(created.printer_id, update).await.unwrap().unwrap().address, "zebra-floor:9100");
+    assert!(repo.update(0, UpdatePrinter::default()).await.unwrap().is_none());
+}
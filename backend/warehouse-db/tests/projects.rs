@@ -0,0 +1,261 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::{Days, NaiveDate, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// A project with a material budget of 1000 IDR, item 2 costing 100 in warehouse 1
+async fn budgeted_project(pool: &PgPool, control: &str) -> Project {
+    sqlx::query("UPDATE warehouse.stock_inventory SET average_cost = 100 WHERE item_id = 2 AND warehouse_id = 1")
+        .execute(pool)
+        .await
+        .unwrap();
+
+    ProjectRepository::new(pool.clone())
+        .create(CreateProject {
+            project_code: "PRJ-BRIDGE".to_string(),
+            project_name: "Bridge repair".to_string(),
+            material_budget: Some(Money::new(Decimal::from(1000), "IDR")),
+            budget_control: Some(control.to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+}
+
+fn issue(quantity: i64) -> IssueToProject {
+    IssueToProject {
+        warehouse_id: 1,
+        lines: vec![ProjectIssueLine { item_id: 2, quantity: Decimal::from(quantity), lot_number: None }],
+        reference: Some("WO-17".to_string()),
+        crew: None,
+    }
+}
+
+fn reserve(quantity: i64, needed_by: NaiveDate) -> ReserveForProject {
+    ReserveForProject {
+        warehouse_id: 1,
+        lines: vec![ProjectReservationLine { item_id: 2, quantity: Decimal::from(quantity) }],
+        needed_by,
+        expires_on: None,
+        notes: None,
+    }
+}
+
+/// Item 2's on-hand and available quantities in warehouse 1
+async fn item_2_stock(pool: &PgPool) -> (Decimal, Decimal) {
+    sqlx::query_as(
+        "SELECT quantity_on_hand, quantity_available FROM warehouse.stock_inventory
+         WHERE item_id = 2 AND warehouse_id = 1",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn warn_projects_post_over_budget_issues_and_notify(pool: PgPool) {
+    let project = budgeted_project(&pool, PROJECT_BUDGET_WARN).await;
+    let repo = ProjectRepository::new(pool.clone());
+
+    let within = repo.issue(project.project_id, issue(6)).await.unwrap().unwrap();
+    assert_eq!(within.issue_value, Money::new(Decimal::from(600), "IDR"));
+    assert!(!within.over_budget);
+    assert_eq!(within.movements[0].movement_type, MOVEMENT_PROJECT_ISSUE);
+    assert_eq!(within.movements[0].quantity, Decimal::from(-6));
+
+    let over = repo.issue(project.project_id, issue(5)).await.unwrap().unwrap();
+    assert!(over.over_budget);
+    assert_eq!(over.consumed, Money::new(Decimal::from(1100), "IDR"));
+    assert!(over.warning.is_some());
+
+    let notified: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM warehouse.notifications WHERE kind = 'PROJECT_OVER_BUDGET'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(notified, 1);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn block_projects_refuse_over_budget_issues(pool: PgPool) {
+    let project = budgeted_project(&pool, PROJECT_BUDGET_BLOCK).await;
+    let repo = ProjectRepository::new(pool.clone());
+
+    repo.issue(project.project_id, issue(10)).await.unwrap().unwrap();
+    assert!(repo.issue(project.project_id, issue(1)).await.is_err());
+
+    let issued: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM warehouse.stock_movements WHERE movement_type = $1")
+        .bind(MOVEMENT_PROJECT_ISSUE)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(issued, 1);
+
+    // Raising the budget lets the issue through
+    let raised = UpdateProject { material_budget: Some(Money::new(Decimal::from(2000), "IDR")), ..Default::default() };
+    repo.update(project.project_id, raised).await.unwrap().unwrap();
+    assert!(!repo.issue(project.project_id, issue(1)).await.unwrap().unwrap().over_budget);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn consumption_reports_issues_against_the_budget(pool: PgPool) {
+    let project = budgeted_project(&pool, PROJECT_BUDGET_WARN).await;
+    let repo = ProjectRepository::new(pool.clone());
+    repo.issue(project.project_id, issue(3)).await.unwrap().unwrap();
+    repo.issue(project.project_id, issue(2)).await.unwrap().unwrap();
+
+    let report = repo.consumption(project.project_id, ProjectConsumptionFilter::default()).await.unwrap().unwrap();
+    assert_eq!(report.consumed, Money::new(Decimal::from(500), "IDR"));
+    assert_eq!(report.remaining, Some(Money::new(Decimal::from(500), "IDR")));
+    assert_eq!(report.consumed_pct, Some(Decimal::from(50)));
+    assert_eq!(report.items.len(), 1);
+    assert_eq!(report.items[0].quantity, Decimal::from(5));
+    assert_eq!(report.items[0].issues, 2);
+
+    let yesterday = Utc::now().date_naive().pred_opt();
+    let filter = ProjectConsumptionFilter { from: None, to: yesterday };
+    let report = repo.consumption(project.project_id, filter).await.unwrap().unwrap();
+    assert!(report.items.is_empty());
+    assert_eq!(report.consumed, Money::new(Decimal::from(500), "IDR"));
+    assert!(repo.consumption(999, ProjectConsumptionFilter::default()).await.unwrap().is_none());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn reservations_hold_stock_until_the_project_draws_on_it(pool: PgPool) {
+    let project = budgeted_project(&pool, PROJECT_BUDGET_WARN).await;
+    let repo = ProjectRepository::new(pool.clone());
+    let next_week = Utc::now().date_naive() + Days::new(7);
+
+    let reserved = repo.reserve(project.project_id, reserve(600, next_week)).await.unwrap().unwrap();
+    assert_eq!(reserved[0].status, RESERVATION_ACTIVE);
+    assert_eq!(reserved[0].expires_on, next_week);
+    assert_eq!(item_2_stock(&pool).await, (Decimal::from(1000), Decimal::from(400)));
+    assert!(repo.reserve(project.project_id, reserve(500, next_week)).await.is_err());
+
+    // Issues draw on the reservation rather than on what is still available
+    let issued = repo.issue(project.project_id, issue(4)).await.unwrap().unwrap();
+    assert_eq!(issued.reservations.len(), 1);
+    assert_eq!(issued.reservations[0].quantity_issued, Decimal::from(4));
+    assert_eq!(issued.reservations[0].quantity_open(), Decimal::from(596));
+    assert_eq!(item_2_stock(&pool).await, (Decimal::from(996), Decimal::from(400)));
+
+    let ledger = LedgerRepository::new(pool.clone()).integrity().await.unwrap();
+    assert!(ledger.reconciled, "{:?}", ledger);
+
+    let released = repo.release(reserved[0].reservation_id).await.unwrap().unwrap();
+    assert_eq!(released.status, RESERVATION_RELEASED);
+    assert_eq!(item_2_stock(&pool).await, (Decimal::from(996), Decimal::from(996)));
+    assert!(repo.release(reserved[0].reservation_id).await.is_err());
+    assert!(repo.release(999).await.unwrap().is_none());
+
+    let ledger = LedgerRepository::new(pool.clone()).integrity().await.unwrap();
+    assert!(ledger.reconciled, "{:?}", ledger);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn stale_reservations_expire(pool: PgPool) {
+    let project = budgeted_project(&pool, PROJECT_BUDGET_WARN).await;
+    let repo = ProjectRepository::new(pool.clone());
+    let today = Utc::now().date_naive();
+
+    let stale = repo.reserve(project.project_id, reserve(100, today - Days::new(2))).await.unwrap().unwrap();
+    let current = repo.reserve(project.project_id, reserve(50, today)).await.unwrap().unwrap();
+    assert_eq!(item_2_stock(&pool).await.1, Decimal::from(850));
+
+    let expired = repo.expire_reservations().await.unwrap();
+    assert_eq!(expired.iter().map(|r| r.reservation_id).collect::<Vec<_>>(), [stale[0].reservation_id]);
+    assert_eq!(expired[0].status, RESERVATION_EXPIRED);
+    assert_eq!(item_2_stock(&pool).await.1, Decimal::from(950));
+    assert!(repo.expire_reservations().await.unwrap().is_empty());
+
+    let filter = ProjectReservationFilter { status: Some(RESERVATION_ACTIVE.to_string()) };
+    let active = repo.reservations(project.project_id, filter).await.unwrap().unwrap();
+    assert_eq!(active.iter().map(|r| r.reservation_id).collect::<Vec<_>>(), [current[0].reservation_id]);
+
+    // Drawn in full, a reservation is fulfilled and holds nothing more
+    let issued = repo.issue(project.project_id, issue(50)).await.unwrap().unwrap();
+    assert_eq!(issued.reservations[0].status, RESERVATION_FULFILLED);
+    assert_eq!(item_2_stock(&pool).await, (Decimal::from(950), Decimal::from(950)));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn project_status_decides_whether_it_takes_issues(pool: PgPool) {
+    let project = budgeted_project(&pool, PROJECT_BUDGET_WARN).await;
+    let repo = ProjectRepository::new(pool.clone());
+    let start = NaiveDate::from_ymd_opt(2025, 11, 1).unwrap();
+
+    let planned = repo
+        .create(CreateProject {
+            project_code: "PRJ-DEPOT".to_string(),
+            project_name: "Depot extension".to_string(),
+            status: Some(PROJECT_PLANNED.to_string()),
+            site_name: Some("Cikarang depot".to_string()),
+            manager_name: Some("Dewi Lestari".to_string()),
+            start_date: Some(start),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(planned.is_active);
+    assert_eq!(planned.site_name.as_deref(), Some("Cikarang depot"));
+
+    let held = UpdateProject { status: Some(PROJECT_ON_HOLD.to_string()), ..Default::default() };
+    let held = repo.update(project.project_id, held).await.unwrap().unwrap();
+    assert!(!held.is_active);
+    assert!(repo.issue(project.project_id, issue(1)).await.unwrap().is_none());
+
+    let open = repo.list(&ProjectFilter::default()).await.unwrap();
+    assert_eq!(open.iter().map(|p| p.project_code.as_str()).collect::<Vec<_>>(), ["PRJ-DEPOT"]);
+    let on_hold = ProjectFilter { status: Some(PROJECT_ON_HOLD.to_string()) };
+    assert_eq!(repo.list(&on_hold).await.unwrap()[0].project_id, project.project_id);
+
+    let early = UpdateProject { end_date: Some(start - Days::new(1)), ..Default::default() };
+    let refused = repo.update(planned.project_id, early).await.unwrap_err();
+    assert!(refused.to_string().contains("before it starts"), "{}", refused);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn consumable_usage_flags_crews_far_above_other_projects(pool: PgPool) {
+    let bridge = budgeted_project(&pool, PROJECT_BUDGET_WARN).await;
+    let repo = ProjectRepository::new(pool.clone());
+    let mut peers = Vec::new();
+    for code in ["PRJ-PIER", "PRJ-RAMP"] {
+        let project =
+            CreateProject { project_code: code.to_string(), project_name: code.to_string(), ..Default::default() };
+        peers.push(repo.create(project).await.unwrap());
+    }
+
+    let crew = |name: &str, quantity| IssueToProject { crew: Some(name.to_string()), ..issue(quantity) };
+    repo.issue(bridge.project_id, crew("Crew A", 100)).await.unwrap().unwrap();
+    repo.issue(bridge.project_id, crew("Crew B", 6)).await.unwrap().unwrap();
+    repo.issue(bridge.project_id, crew("Crew B", 4)).await.unwrap().unwrap();
+    for peer in &peers {
+        repo.issue(peer.project_id, issue(10)).await.unwrap().unwrap();
+    }
+
+    let today = Utc::now().date_naive();
+    let filter = ConsumableUsageFilter::default();
+    let usage = repo.consumable_usage(&filter, today, today, Decimal::TWO).await.unwrap();
+    let rows: Vec<(&str, Option<&str>, Decimal, Option<Decimal>, bool)> = usage
+        .iter()
+        .map(|row| (row.project_code.as_str(), row.crew.as_deref(), row.quantity, row.peer_ratio, row.abnormal))
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ("PRJ-BRIDGE", Some("Crew A"), Decimal::from(100), Some(Decimal::from(10)), true),
+            ("PRJ-BRIDGE", Some("Crew B"), Decimal::from(10), Some(Decimal::ONE), false),
+            ("PRJ-PIER", None, Decimal::from(10), Some(Decimal::new(25, 2)), false),
+            ("PRJ-RAMP", None, Decimal::from(10), Some(Decimal::new(25, 2)), false),
+        ]
+    );
+    assert_eq!(usage[1].issues, 2);
+    assert_eq!(usage[2].peer_average, Some(Decimal::from(40)));
+
+    // Filtering by crew keeps the other projects as peers
+    let crew_b = ConsumableUsageFilter { crew: Some("Crew B".to_string()), ..Default::default() };
+    let usage = repo.consumable_usage(&crew_b, today, today, Decimal::TWO).await.unwrap();
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0].peer_average, Some(Decimal::from(10)));
+}
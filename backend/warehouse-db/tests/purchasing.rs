@@ -0,0 +1,140 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn supplier(name: &str, is_preferred: bool) -> SetItemSupplier {
+    SetItemSupplier {
+        supplier_name: name.to_string(),
+        is_preferred,
+        unit_cost: Some(Money::new(Decimal::from(1000), "IDR")),
+        lead_time_days: Some(7),
+        order_cost: None,
+        holding_cost_pct: None,
+        min_order_quantity: None,
+        order_multiple: None,
+        pack_size: None,
+    }
+}
+
+/// Items 1 and 3 below their reorder points with ACME preferred, item 2 below its point
+/// without a supplier
+async fn alerting_items(db: &Database) {
+    let levels = [(1, 10, Some(40)), (2, 2000, None), (3, 60, Some(100))];
+    for (item_id, reorder_point, max_stock_level) in levels {
+        let levels = SetStockLevels {
+            reorder_point: Decimal::from(reorder_point),
+            max_stock_level: max_stock_level.map(Decimal::from),
+        };
+        db.stock_rows().upsert(1, item_id, levels.into()).await.unwrap();
+    }
+
+    db.purchasing().set_supplier(1, "ACME", supplier("Acme Supplies", true)).await.unwrap();
+    db.purchasing().set_supplier(3, "BETA", supplier("Beta Trading", true)).await.unwrap();
+    db.purchasing().set_supplier(3, "ACME", supplier("Acme Supplies", true)).await.unwrap();
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn alerts_are_drafted_once_per_preferred_supplier(pool: PgPool) {
+    let db = Database::new(pool);
+    alerting_items(&db).await;
+
+    let suppliers = db.purchasing().suppliers(3).await.unwrap();
+    assert_eq!(suppliers.len(), 2);
+    assert_eq!((suppliers[0].supplier_code.as_str(), suppliers[0].is_preferred), ("ACME", true));
+    assert!(!suppliers[1].is_preferred);
+
+    let alerts = db.packaging().reorder_alerts(1, ReorderAlertFilter::default()).await.unwrap();
+    let run = db.purchasing().generate(1, GenerateRequisitions::default()).await.unwrap();
+    assert_eq!(run.requisitions.len(), 1);
+    let drafted = &run.requisitions[0];
+    assert_eq!(drafted.requisition.supplier_code, "ACME");
+    assert_eq!(drafted.requisition.status, REQUISITION_DRAFT);
+    assert_eq!(drafted.lines.len(), 2);
+    for line in &drafted.lines {
+        let alert = alerts.iter().find(|alert| alert.item_id == line.item_id).unwrap();
+        assert_eq!(line.method, REQUISITION_METHOD_MIN_MAX);
+        assert_eq!(Some(line.suggested_quantity), alert.suggested_quantity);
+        assert_eq!(line.unit_cost, Some(Money::new(Decimal::from(1000), "IDR")));
+    }
+    assert_eq!(run.without_supplier.iter().map(|alert| alert.item_id).collect::<Vec<_>>(), [2]);
+
+    // Without demand history there is no EOQ, and items already drafted are not drafted again
+    let request = GenerateRequisitions { method: Some(REQUISITION_METHOD_EOQ.to_string()) };
+    let rerun = db.purchasing().generate(1, request).await.unwrap();
+    assert!(rerun.requisitions.is_empty());
+    assert_eq!(rerun.without_supplier.len(), 1);
+
+    let page = db.purchasing().list(1, RequisitionFilter::default(), KeysetQuery::default()).await.unwrap();
+    assert_eq!(page.data.len(), 1);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn approved_requisitions_convert_into_purchase_orders(pool: PgPool) {
+    let db = Database::new(pool);
+    alerting_items(&db).await;
+    let run = db.purchasing().generate(1, GenerateRequisitions::default()).await.unwrap();
+    let id = run.requisitions[0].requisition.requisition_id;
+
+    let quantity = SetRequisitionLine { quantity: Decimal::from(50) };
+    let changed = db.purchasing().set_line(id, 1, quantity.clone()).await.unwrap().unwrap();
+    let line = changed.lines.iter().find(|line| line.item_id == 1).unwrap();
+    assert_eq!(line.quantity, Decimal::from(50));
+    assert!(db.purchasing().convert(id).await.is_err());
+
+    let approved = db.purchasing().approve(id).await.unwrap().unwrap();
+    assert_eq!(approved.status, REQUISITION_APPROVED);
+    assert!(db.purchasing().set_line(id, 1, quantity).await.is_err());
+
+    let order = db.purchasing().convert(id).await.unwrap().unwrap();
+    assert!(order.order.po_number.starts_with("PO-"));
+    assert_eq!(order.order.status, PO_OPEN);
+    assert!(order.order.expected_date.is_some());
+    assert_eq!(order.lines.len(), 2);
+    let line = order.lines.iter().find(|line| line.item_id == 1).unwrap();
+    assert_eq!(line.quantity_ordered, Decimal::from(50));
+
+    let converted = db.purchasing().get(id).await.unwrap().unwrap().requisition;
+    assert_eq!(converted.status, REQUISITION_CONVERTED);
+    assert_eq!(converted.po_id, Some(order.order.po_id));
+    assert!(db.purchasing().approve(id).await.is_err());
+
+    // Items on an open purchase order are not drafted again
+    let rerun = db.purchasing().generate(1, GenerateRequisitions::default()).await.unwrap();
+    assert!(rerun.requisitions.is_empty());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn order_quantities_keep_to_the_suppliers_terms(pool: PgPool) {
+    let db = Database::new(pool);
+    alerting_items(&db).await;
+    let terms = SetItemSupplier {
+        min_order_quantity: Some(Decimal::from(100)),
+        order_multiple: Some(Decimal::from(24)),
+        pack_size: Some(Decimal::from(6)),
+        ..supplier("Acme Supplies", true)
+    };
+    let saved = db.purchasing().set_supplier(1, "ACME", terms).await.unwrap();
+    assert_eq!(saved.constraints.round_up(Decimal::from(30)), Decimal::from(120));
+    assert_eq!(saved.constraints.round_up(Decimal::from(121)), Decimal::from(144));
+
+    let run = db.purchasing().generate(1, GenerateRequisitions::default()).await.unwrap();
+    let requisition = &run.requisitions[0];
+    let line = requisition.lines.iter().find(|line| line.item_id == 1).unwrap();
+    assert_eq!(line.suggested_quantity, Decimal::from(120));
+    assert_eq!(line.quantity, Decimal::from(120));
+
+    let id = requisition.requisition.requisition_id;
+    for quantity in [96, 126, 150] {
+        let change = SetRequisitionLine { quantity: Decimal::from(quantity) };
+        assert!(db.purchasing().set_line(id, 1, change).await.is_err(), "{} accepted", quantity);
+    }
+    let change = SetRequisitionLine { quantity: Decimal::from(144) };
+    db.purchasing().set_line(id, 1, change).await.unwrap().unwrap();
+
+    // Terms tightened after approval hold the conversion back
+    db.purchasing().approve(id).await.unwrap().unwrap();
+    let terms = SetItemSupplier { min_order_quantity: Some(Decimal::from(200)), ..supplier("Acme Supplies", true) };
+    db.purchasing().set_supplier(1, "ACME", terms).await.unwrap();
+    assert!(db.purchasing().convert(id).await.is_err());
+}
@@ -0,0 +1,77 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn lot(item_id: i32, quantity: i64) -> CreateInspection {
+    CreateInspection {
+        item_id,
+        warehouse_id: 1,
+        quantity: Decimal::from(quantity),
+        reference_type: Some("PO".to_string()),
+        reference_number: Some("PO-0001".to_string()),
+    }
+}
+
+fn result(result: InspectionResult) -> RecordInspection {
+    RecordInspection {
+        result,
+        sample_size: 5,
+        defects_found: Some(0),
+        notes: None,
+    }
+}
+
+async fn balance(pool: &PgPool, item_id: i32) -> (Decimal, Decimal) {
+    sqlx::query_as(
+        "SELECT quantity_available, quantity_quarantined FROM warehouse.stock_inventory
+         WHERE item_id = $1 AND warehouse_id = 1",
+    )
+    .bind(item_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn held_lot_is_unavailable_until_passed(pool: PgPool) {
+    let repo = QualityRepository::new(pool.clone());
+
+    let inspection = repo.create(lot(3, 20)).await.unwrap();
+    assert_eq!(inspection.status, INSPECTION_PENDING);
+    assert_eq!(balance(&pool, 3).await, (Decimal::from(50), Decimal::from(20)));
+
+    let passed = repo
+        .record(inspection.inspection_id, result(InspectionResult::Pass))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(passed.status, INSPECTION_PASSED);
+    assert_eq!(passed.sample_size, Some(5));
+    assert_eq!(balance(&pool, 3).await, (Decimal::from(70), Decimal::ZERO));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn failed_lot_is_rejected_out_of_stock(pool: PgPool) {
+    let repo = QualityRepository::new(pool.clone());
+    let inspection = repo.create(lot(3, 20)).await.unwrap();
+
+    repo.record(inspection.inspection_id, result(InspectionResult::Fail)).await.unwrap();
+
+    assert_eq!(balance(&pool, 3).await, (Decimal::from(50), Decimal::ZERO));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn result_is_recorded_once(pool: PgPool) {
+    let repo = QualityRepository::new(pool);
+    let inspection = repo.create(lot(2, 1)).await.unwrap();
+
+    repo.record(inspection.inspection_id, result(InspectionResult::Pass)).await.unwrap();
+    let err = repo
+        .record(inspection.inspection_id, result(InspectionResult::Fail))
+        .await
+        .unwrap_err();
+
+    assert!(err.downcast_ref::<RuleViolation>().is_some());
+    assert!(repo.record(9999, result(InspectionResult::Pass)).await.unwrap().is_none());
+}
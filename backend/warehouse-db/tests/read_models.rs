@@ -0,0 +1,117 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn receive(pool: &PgPool, item_id: i32, quantity: i64) {
+    let mut tx = pool.begin().await.unwrap();
+    post_movement(
+        &mut tx,
+        NewMovement {
+            item_id,
+            warehouse_id: 1,
+            movement_type: "RECEIPT".to_string(),
+            quantity: Decimal::from(quantity),
+            catch_weight_kg: None,
+            lot_number: None,
+            unit_cost: None,
+            stock_status: STOCK_AVAILABLE,
+            consignor_id: None,
+            reference_type: None,
+            reference_number: None,
+            notes: None,
+            created_by: Some(1),
+        },
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+}
+
+fn on_hand(balances: &[StockBalance], item_id: i32, warehouse_id: i32) -> Option<Decimal> {
+    balances.iter().find(|b| b.item_id == item_id && b.warehouse_id == warehouse_id).map(|b| b.quantity_on_hand)
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn postings_reach_the_read_model_once_projected(pool: PgPool) {
+    let read_models = ReadModelRepository::new(pool.clone());
+    let stock = read_models.stock(StockFilter::default()).await.unwrap();
+    // Seeded balances are copied in by the migration
+    assert_eq!(on_hand(&stock, 2, 1), Some(Decimal::from(1000)));
+    assert_eq!(on_hand(&stock, 4, 2), Some(Decimal::from(2)));
+
+    receive(&pool, 4, 10).await;
+    receive(&pool, 2, 5).await;
+    let stock = read_models.stock(StockFilter::default()).await.unwrap();
+    assert_eq!(on_hand(&stock, 4, 1), None);
+    let pending = read_models.status().await.unwrap().pending_events;
+    assert!(pending >= 2);
+
+    assert_eq!(read_models.project(500).await.unwrap() as i64, pending);
+    let projected = read_models.stock(StockFilter::default()).await.unwrap();
+    assert_eq!(on_hand(&projected, 4, 1), Some(Decimal::from(10)));
+    assert_eq!(on_hand(&projected, 2, 1), Some(Decimal::from(1005)));
+
+    // Matches what the ledger-backed snapshot computes from the source tables
+    let live = StockRepository::new(pool.clone()).snapshot(StockFilter::default()).await.unwrap();
+    let key = |b: &StockBalance| (b.item_id, b.warehouse_id, b.consignor_id, b.quantity_on_hand);
+    assert_eq!(projected.iter().map(key).collect::<Vec<_>>(), live.balances.iter().map(key).collect::<Vec<_>>());
+
+    let status = read_models.status().await.unwrap();
+    assert_eq!(status.pending_events, 0);
+    assert!(status.last_projected_at.is_some());
+    assert_eq!(read_models.project(500).await.unwrap(), 0);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn item_and_warehouse_changes_are_projected_onto_their_rows(pool: PgPool) {
+    sqlx::query("UPDATE warehouse.items SET item_name = 'Galvanized Anchor Bolt' WHERE item_id = 3")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE warehouse.warehouses SET warehouse_name = 'Jakarta North DC' WHERE warehouse_id = 1")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let read_models = ReadModelRepository::new(pool.clone());
+    let search = || StockFilter { q: Some("anchor".to_string()), ..Default::default() };
+    assert!(read_models.stock(search()).await.unwrap().is_empty());
+
+    read_models.project(500).await.unwrap();
+    let found = read_models.stock(search()).await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].item_id, 3);
+    assert_eq!(found[0].item_name, "Galvanized Anchor Bolt");
+
+    let warehouse_name: String =
+        sqlx::query_scalar("SELECT DISTINCT warehouse_name FROM warehouse.stock_read_model WHERE warehouse_id = 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(warehouse_name, "Jakarta North DC");
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn client_stock_follows_item_assignment(pool: PgPool) {
+    let billing = BillingRepository::new(pool.clone());
+    let client = billing
+        .create_client(CreateBillingClient {
+            client_code: "ACME".to_string(),
+            client_name: "ACME Retail".to_string(),
+            currency: None,
+        })
+        .await
+        .unwrap();
+    billing.assign_items(client.client_id, AssignClientItems { item_ids: vec![2] }).await.unwrap();
+    receive(&pool, 2, 10).await;
+
+    let read_models = ReadModelRepository::new(pool.clone());
+    read_models.project(500).await.unwrap();
+    let projected = read_models.client_stock(client.client_id, ClientStockFilter::default()).await.unwrap();
+    let live = ClientRepository::new(pool).stock(client.client_id, ClientStockFilter::default()).await.unwrap();
+
+    let key = |s: &ClientStock| (s.item_id, s.warehouse_id, s.quantity_on_hand, s.quantity_available);
+    assert_eq!(projected.iter().map(key).collect::<Vec<_>>(), live.iter().map(key).collect::<Vec<_>>());
+    assert_eq!(projected[0].quantity_on_hand, Decimal::from(1010));
+}
@@ -0,0 +1,140 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// Receive `lots` of an item into warehouse 1 on one ASN line
+async fn receive_lots(pool: &PgPool, item_id: i32, lots: &[(&str, i64)]) {
+    let receipts = ReceiptRepository::new(pool.clone());
+    let total: i64 = lots.iter().map(|(_, quantity)| quantity).sum();
+    let created = receipts
+        .create(CreateReceipt {
+            asn_number: format!("ASN-{}-{}", item_id, lots[0].0),
+            warehouse_id: 1,
+            supplier_name: None,
+            purchase_order_number: None,
+            carrier_code: None,
+            expected_date: None,
+            consignor_id: None,
+            lines: vec![CreateReceiptLine { item_id, quantity_expected: Decimal::from(total), lpn_number: None }],
+        })
+        .await
+        .unwrap();
+    let line_id = created.lines[0].line_id;
+    let lines = lots
+        .iter()
+        .map(|&(lot, quantity)| ReceiveGoodsLine {
+            line_id,
+            quantity: Decimal::from(quantity),
+            weight_kg: None,
+            lot_number: Some(lot.to_string()),
+        })
+        .collect();
+    receipts.receive(created.receipt.receipt_id, ReceiveGoods { lines, reference_id: None }).await.unwrap().unwrap();
+}
+
+/// Order and ship `quantity` of an item from `lot` to a customer
+async fn ship_lot(pool: &PgPool, customer: &str, reference: &str, item_id: i32, lot: &str, quantity: i64) {
+    let orders = OrderRepository::new(pool.clone());
+    let created = orders
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: customer.to_string(),
+            customer_reference: Some(reference.to_string()),
+            ship_to_name: None,
+            ship_to_address: None,
+            ship_to_city: None,
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![CreateOrderLine { item_id, quantity_ordered: Decimal::from(quantity) }],
+        })
+        .await
+        .unwrap();
+    let confirmation = ShipOrder {
+        carrier_code: None,
+        tracking_number: None,
+        service_level: None,
+        lines: vec![ShipOrderLine {
+            line_id: created.lines[0].line_id,
+            quantity: Decimal::from(quantity),
+            weight_kg: None,
+            lot_number: Some(lot.to_string()),
+        }],
+        lpns: Vec::new(),
+        reference_id: None,
+    };
+    orders.ship(created.order.order_id, confirmation, None).await.unwrap().unwrap();
+}
+
+fn recall(item_id: i32, lot_number: Option<&str>, serials: Option<(&str, &str)>) -> CreateRecall {
+    CreateRecall {
+        item_id,
+        lot_number: lot_number.map(str::to_string),
+        serial_from: serials.map(|(from, _)| from.to_string()),
+        serial_to: serials.map(|(_, to)| to.to_string()),
+        reason: "Supplier reported weld defects".to_string(),
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn lot_recall_holds_stock_and_traces_shipments(pool: PgPool) {
+    receive_lots(&pool, 2, &[("L-2401", 100), ("L-2402", 50)]).await;
+    ship_lot(&pool, "PT Karya Beton", "PRJ-TOWER-A", 2, "L-2401", 30).await;
+    ship_lot(&pool, "PT Karya Beton", "PRJ-TOWER-A", 2, "L-2401", 5).await;
+    ship_lot(&pool, "CV Sinar Jaya", "PO-881", 2, "L-2401", 10).await;
+    ship_lot(&pool, "CV Sinar Jaya", "PO-882", 2, "L-2402", 10).await;
+
+    let repo = RecallRepository::new(pool.clone());
+    let report = repo.create(recall(2, Some("L-2401"), None)).await.unwrap();
+
+    assert_eq!(report.recall.recall_number, format!("RCL-{:06}", report.recall.recall_id));
+    assert_eq!(report.stock.len(), 1);
+    let held = &report.stock[0];
+    assert_eq!(
+        (held.lot_number.as_str(), held.quantity_available, held.quantity_on_hold),
+        ("L-2401", Decimal::ZERO, Decimal::from(55))
+    );
+    assert_eq!(report.shipments.len(), 3);
+    let exposure: Vec<_> = report
+        .exposure
+        .iter()
+        .map(|e| (e.customer_name.as_str(), e.customer_reference.as_deref(), e.shipments, e.quantity))
+        .collect();
+    assert_eq!(
+        exposure,
+        [
+            ("CV Sinar Jaya", Some("PO-881"), 1, Decimal::from(10)),
+            ("PT Karya Beton", Some("PRJ-TOWER-A"), 2, Decimal::from(35)),
+        ]
+    );
+
+    let quarantined: Decimal = sqlx::query_scalar(
+        "SELECT quantity_quarantined FROM warehouse.stock_inventory WHERE item_id = 2 AND warehouse_id = 1",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(quarantined, Decimal::from(55));
+
+    let closed = repo.close(report.recall.recall_id).await.unwrap().unwrap();
+    assert_eq!(closed.status, RECALL_CLOSED);
+    let again = repo.close(report.recall.recall_id).await.unwrap_err();
+    assert!(again.downcast_ref::<RuleViolation>().is_some());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn serial_range_recall_matches_same_length_serials(pool: PgPool) {
+    receive_lots(&pool, 3, &[("SN0001", 1), ("SN0002", 1), ("SN0003", 1), ("SN0010", 1), ("SN001", 1)]).await;
+    ship_lot(&pool, "PT Karya Beton", "PRJ-TOWER-A", 3, "SN0002", 1).await;
+
+    let report = RecallRepository::new(pool).create(recall(3, None, Some(("SN0001", "SN0005")))).await.unwrap();
+
+    let held: Vec<_> = report.stock.iter().map(|s| (s.lot_number.as_str(), s.quantity_on_hold)).collect();
+    assert_eq!(held, [("SN0001", Decimal::ONE), ("SN0003", Decimal::ONE)]);
+    let shipped: Vec<_> = report.shipments.iter().map(|s| s.lot_number.as_str()).collect();
+    assert_eq!(shipped, ["SN0002"]);
+}
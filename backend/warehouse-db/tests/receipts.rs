@@ -0,0 +1,71 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn asn(number: &str) -> CreateReceipt {
+    CreateReceipt {
+        asn_number: number.to_string(),
+        warehouse_id: 1,
+        supplier_name: Some("PT Baja Steel".to_string()),
+        purchase_order_number: Some("PO-5521".to_string()),
+        carrier_code: None,
+        expected_date: None,
+        consignor_id: None,
+        lines: vec![CreateReceiptLine {
+            item_id: 2,
+            quantity_expected: Decimal::from(200),
+            lpn_number: None,
+        }],
+    }
+}
+
+fn goods(line_id: i32, quantity: i64) -> ReceiveGoods {
+    ReceiveGoods {
+        lines: vec![ReceiveGoodsLine {
+            line_id,
+            quantity: Decimal::from(quantity),
+            weight_kg: None,
+            lot_number: None,
+        }],
+        reference_id: None,
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn receive_posts_stock_until_complete(pool: PgPool) {
+    let repo = ReceiptRepository::new(pool.clone());
+    let created = repo.create(asn("ASN-1")).await.unwrap();
+    let id = created.receipt.receipt_id;
+    let line_id = created.lines[0].line_id;
+
+    let partial = repo.receive(id, goods(line_id, 150)).await.unwrap().unwrap();
+    assert_eq!(partial.receipt.status, RECEIPT_PARTIALLY_RECEIVED);
+
+    let complete = repo.receive(id, goods(line_id, 50)).await.unwrap().unwrap();
+    assert_eq!(complete.receipt.status, RECEIPT_RECEIVED);
+    assert!(complete.receipt.received_at.is_some());
+
+    let on_hand: Decimal = sqlx::query_scalar(
+        "SELECT quantity_on_hand FROM warehouse.stock_inventory WHERE item_id = 2 AND warehouse_id = 1",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(on_hand, Decimal::from(1200));
+
+    let err = repo.receive(id, goods(line_id, 1)).await.unwrap_err();
+    assert!(err.downcast_ref::<RuleViolation>().is_some());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn asn_numbers_are_unique_per_warehouse(pool: PgPool) {
+    let repo = ReceiptRepository::new(pool);
+
+    assert!(!repo.asn_exists(1, "ASN-1").await.unwrap());
+    repo.create(asn("ASN-1")).await.unwrap();
+
+    assert!(repo.asn_exists(1, "ASN-1").await.unwrap());
+    assert!(!repo.asn_exists(2, "ASN-1").await.unwrap());
+    assert!(repo.create(asn("ASN-1")).await.is_err());
+}
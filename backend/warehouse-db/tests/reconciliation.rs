@@ -0,0 +1,101 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::NaiveDate;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// Item code, warehouse code and on hand of every balance in the warehouse, summed over owners
+async fn wms_stock(pool: &PgPool, warehouse_code: &str) -> Vec<(String, Decimal)> {
+    sqlx::query_as(
+        "SELECT i.item_code, SUM(s.quantity_on_hand) FROM warehouse.stock_inventory s
+         JOIN warehouse.items i ON i.item_id = s.item_id
+         JOIN warehouse.warehouses w ON w.warehouse_id = s.warehouse_id
+         WHERE w.warehouse_code = $1
+         GROUP BY i.item_code HAVING SUM(s.quantity_on_hand) <> 0
+         ORDER BY i.item_code",
+    )
+    .bind(warehouse_code)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+fn line(item_code: &str, warehouse_code: &str, quantity: Decimal) -> ErpStockLine {
+    ErpStockLine { item_code: item_code.to_string(), warehouse_code: warehouse_code.to_string(), quantity }
+}
+
+fn status_of<'a>(detail: &'a ErpReconciliationDetail, item_code: &str) -> &'a ErpReconciliationLine {
+    detail.lines.iter().find(|line| line.item_code == item_code).unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn reports_variances_line_by_line(pool: PgPool) {
+    let repo = ReconciliationRepository::new(pool.clone());
+    let warehouse_code: String =
+        sqlx::query_scalar("SELECT w.warehouse_code FROM warehouse.stock_inventory s JOIN warehouse.warehouses w USING (warehouse_id) WHERE s.quantity_on_hand > 0 LIMIT 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    let stock = wms_stock(&pool, &warehouse_code).await;
+    assert!(stock.len() >= 2, "seed data has two balances in {}", warehouse_code);
+    let (matched, matched_qty) = &stock[0];
+    let (short, short_qty) = &stock[1];
+
+    let extract = ErpExtract {
+        as_of: NaiveDate::from_ymd_opt(2025, 10, 31).unwrap(),
+        source: Some("SAP".to_string()),
+        lines: vec![
+            // Split over two lines; they are added up
+            line(matched, &warehouse_code, *matched_qty - Decimal::ONE),
+            line(matched, &warehouse_code, Decimal::ONE),
+            line(short, &warehouse_code, *short_qty + Decimal::from(3)),
+            line("NOT-AN-ITEM", &warehouse_code, Decimal::from(5)),
+        ],
+    };
+    let detail = repo.reconcile(extract).await.unwrap();
+
+    assert_eq!(status_of(&detail, matched).status, ERP_MATCHED);
+    let variance = status_of(&detail, short);
+    assert_eq!(variance.status, ERP_VARIANCE);
+    assert_eq!(variance.variance, Some(Decimal::from(-3)));
+    assert_eq!(status_of(&detail, "NOT-AN-ITEM").status, ERP_UNKNOWN);
+    assert!(status_of(&detail, "NOT-AN-ITEM").item_id.is_none());
+
+    // Everything else the WMS holds there is missing from the extract
+    let missing = detail.lines.iter().filter(|line| line.status == ERP_MISSING_IN_ERP).count();
+    assert_eq!(missing, stock.len() - 2);
+    assert_eq!(detail.reconciliation.lines as usize, detail.lines.len());
+    assert_eq!(detail.reconciliation.matched, 1);
+    assert_eq!(detail.reconciliation.variances, detail.reconciliation.lines - 1);
+
+    let filter = ErpReconciliationFilter { status: Some(ERP_VARIANCE.to_string()), warehouse_code: None };
+    let variances = repo.lines(detail.reconciliation.reconciliation_id, filter).await.unwrap();
+    assert_eq!(variances.len(), 1);
+    assert_eq!(repo.list(KeysetQuery::default()).await.unwrap().data.len(), 1);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn erp_stock_without_a_wms_balance_is_missing_in_wms(pool: PgPool) {
+    let repo = ReconciliationRepository::new(pool.clone());
+    let (item_code, warehouse_code): (String, String) = sqlx::query_as(
+        "SELECT i.item_code, w.warehouse_code FROM warehouse.items i CROSS JOIN warehouse.warehouses w
+         WHERE NOT EXISTS (SELECT 1 FROM warehouse.stock_inventory s
+                           WHERE s.item_id = i.item_id AND s.warehouse_id = w.warehouse_id)
+         LIMIT 1",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let extract = ErpExtract {
+        as_of: NaiveDate::from_ymd_opt(2025, 10, 31).unwrap(),
+        source: None,
+        lines: vec![line(&item_code, &warehouse_code, Decimal::from(12))],
+    };
+    let detail = repo.reconcile(extract).await.unwrap();
+
+    let line = status_of(&detail, &item_code);
+    assert_eq!(line.status, ERP_MISSING_IN_WMS);
+    assert!(line.wms_quantity.is_none());
+    assert_eq!(line.variance, Some(Decimal::from(-12)));
+}
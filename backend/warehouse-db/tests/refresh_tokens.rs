@@ -0,0 +1,73 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::*;
+
+const TTL: u64 = 3600;
+
+async fn user(pool: &PgPool, username: &str) -> User {
+    UserRepository::new(pool.clone())
+        .create(CreateUser {
+            username: username.to_string(),
+            email: None,
+            full_name: "Budi Santoso".to_string(),
+            password: "gudang-utama-1".to_string(),
+            role: Some(ROLE_OPERATOR.to_string()),
+        })
+        .await
+        .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn each_exchange_retires_the_token_and_reuse_revokes_the_family(pool: PgPool) {
+    let repo = RefreshTokenRepository::new(pool.clone());
+    let budi = user(&pool, "budi").await;
+    let first = repo.issue(budi.user_id, TTL).await.unwrap();
+    assert_eq!(first.user_id, budi.user_id);
+
+    let stored: String = sqlx::query_scalar("SELECT token_hash FROM warehouse.refresh_tokens WHERE user_id = $1")
+        .bind(budi.user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_ne!(stored, first.refresh_token);
+
+    let second = repo.rotate(&first.refresh_token, TTL).await.unwrap().unwrap();
+    assert_eq!(second.user_id, budi.user_id);
+    assert_ne!(second.refresh_token, first.refresh_token);
+    let third = repo.rotate(&second.refresh_token, TTL).await.unwrap().unwrap();
+    assert!(repo.rotate("not-a-token", TTL).await.unwrap().is_none());
+
+    // Another sign-in is its own family and survives the first one being copied
+    let other = repo.issue(budi.user_id, TTL).await.unwrap();
+    assert!(repo.rotate(&first.refresh_token, TTL).await.unwrap().is_none());
+    assert!(repo.rotate(&third.refresh_token, TTL).await.unwrap().is_none());
+    let other = repo.rotate(&other.refresh_token, TTL).await.unwrap().unwrap();
+
+    assert!(repo.revoke(&other.refresh_token).await.unwrap());
+    assert!(!repo.revoke(&other.refresh_token).await.unwrap());
+    assert!(repo.rotate(&other.refresh_token, TTL).await.unwrap().is_none());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn expired_tokens_and_signed_out_users_cannot_refresh(pool: PgPool) {
+    let repo = RefreshTokenRepository::new(pool.clone());
+    let users = UserRepository::new(pool.clone());
+    let siti = user(&pool, "siti").await;
+
+    let expired = repo.issue(siti.user_id, 0).await.unwrap();
+    assert!(repo.rotate(&expired.refresh_token, TTL).await.unwrap().is_none());
+
+    let before = repo.issue(siti.user_id, TTL).await.unwrap();
+    let changes = UpdateUser { password: Some("gudang-baru-22".to_string()), ..Default::default() };
+    users.update(siti.user_id, changes).await.unwrap().unwrap();
+    assert!(repo.rotate(&before.refresh_token, TTL).await.unwrap().is_none());
+
+    let after = repo.issue(siti.user_id, TTL).await.unwrap();
+    let renamed = UpdateUser { full_name: Some("Siti Rahayu".to_string()), ..Default::default() };
+    users.update(siti.user_id, renamed).await.unwrap().unwrap();
+    let after = repo.rotate(&after.refresh_token, TTL).await.unwrap().unwrap();
+
+    assert!(users.deactivate(siti.user_id).await.unwrap());
+    assert!(repo.rotate(&after.refresh_token, TTL).await.unwrap().is_none());
+    assert_eq!(repo.revoke_for_user(siti.user_id).await.unwrap(), 0);
+}
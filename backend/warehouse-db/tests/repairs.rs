@@ -0,0 +1,96 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// Receive one unit of item 4 into warehouse 1 under each serial number
+async fn receive_tools(pool: &PgPool, serials: &[&str]) {
+    let receipts = ReceiptRepository::new(pool.clone());
+    for serial in serials {
+        let created = receipts
+            .create(CreateReceipt {
+                asn_number: format!("ASN-{}", serial),
+                warehouse_id: 1,
+                supplier_name: None,
+                purchase_order_number: None,
+                carrier_code: None,
+                expected_date: None,
+                consignor_id: None,
+                lines: vec![CreateReceiptLine { item_id: 4, quantity_expected: Decimal::ONE, lpn_number: None }],
+            })
+            .await
+            .unwrap();
+        let lines = vec![ReceiveGoodsLine {
+            line_id: created.lines[0].line_id,
+            quantity: Decimal::ONE,
+            weight_kg: None,
+            lot_number: Some(serial.to_string()),
+        }];
+        receipts
+            .receive(created.receipt.receipt_id, ReceiveGoods { lines, reference_id: None })
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
+
+fn repair(serial: &str) -> CreateRepairOrder {
+    CreateRepairOrder {
+        warehouse_id: 1,
+        item_id: 4,
+        serial_number: serial.to_string(),
+        repair_type: REPAIR_VENDOR.to_string(),
+        vendor_name: Some("CV Teknik Jaya".to_string()),
+        fault_description: "Motor burnt out".to_string(),
+    }
+}
+
+/// Item 4's available and quarantined quantities in warehouse 1
+async fn item_4_stock(pool: &PgPool) -> (Decimal, Decimal) {
+    sqlx::query_as(
+        "SELECT quantity_available, quantity_quarantined FROM warehouse.stock_inventory
+         WHERE item_id = 4 AND warehouse_id = 1 AND consignor_id IS NULL",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn repaired_tools_return_to_stock_and_the_rest_are_scrapped(pool: PgPool) {
+    receive_tools(&pool, &["DRL-001", "DRL-002"]).await;
+    let repo = RepairRepository::new(pool.clone());
+
+    let fixed = repo.create(repair("DRL-001")).await.unwrap();
+    assert_eq!(fixed.status, REPAIR_OPEN);
+    assert_eq!(item_4_stock(&pool).await, (Decimal::ONE, Decimal::ONE));
+    let twice = repo.create(repair("DRL-001")).await.unwrap_err();
+    assert!(twice.to_string().contains("already under repair"), "{}", twice);
+    assert!(repo.create(repair("DRL-999")).await.is_err());
+
+    let sent = repo.send(fixed.repair_id, SendForRepair::default()).await.unwrap().unwrap();
+    assert_eq!(sent.status, REPAIR_IN_REPAIR);
+    assert!(repo.send(fixed.repair_id, SendForRepair::default()).await.is_err());
+    let cost = RecordRepairCost { cost: Money::new(Decimal::from(350_000), "IDR") };
+    let costed = repo.record_cost(fixed.repair_id, cost).await.unwrap().unwrap();
+    assert_eq!(costed.repair_cost, Some(Money::new(Decimal::from(350_000), "IDR")));
+
+    let restock = CloseRepairOrder { decision: REPAIR_DECISION_RESTOCK.to_string(), notes: None };
+    let restocked = repo.close(fixed.repair_id, restock).await.unwrap().unwrap();
+    assert_eq!(restocked.status, REPAIR_RESTOCKED);
+    assert_eq!(item_4_stock(&pool).await, (Decimal::from(2), Decimal::ZERO));
+
+    // Not worth repairing: the tool is written off from quarantine
+    let broken = repo.create(repair("DRL-002")).await.unwrap();
+    let scrap = CloseRepairOrder { decision: REPAIR_DECISION_SCRAP.to_string(), notes: Some("Housing cracked".into()) };
+    let scrapped = repo.close(broken.repair_id, scrap.clone()).await.unwrap().unwrap();
+    assert_eq!(scrapped.status, REPAIR_SCRAPPED);
+    assert_eq!(item_4_stock(&pool).await, (Decimal::ONE, Decimal::ZERO));
+    assert!(repo.close(broken.repair_id, scrap).await.is_err());
+
+    let history = RepairOrderFilter { serial_number: Some("DRL-001".to_string()), ..Default::default() };
+    assert_eq!(repo.list(&history).await.unwrap().len(), 1);
+
+    let ledger = LedgerRepository::new(pool.clone()).integrity().await.unwrap();
+    assert!(ledger.reconciled, "{:?}", ledger);
+}
@@ -0,0 +1,102 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+fn filter(column_name: &str, operator: &str, value: Option<&str>) -> ReportFilter {
+    ReportFilter {
+        column_name: column_name.to_string(),
+        operator: operator.to_string(),
+        value: value.map(String::from),
+    }
+}
+
+fn aggregate(function: &str, column_name: Option<&str>) -> ReportAggregate {
+    ReportAggregate { function: function.to_string(), column_name: column_name.map(String::from) }
+}
+
+fn stock_by_warehouse() -> CreateReportDefinition {
+    CreateReportDefinition {
+        report_code: "STOCK-BY-WH".to_string(),
+        report_name: "Stock by warehouse".to_string(),
+        description: None,
+        entity: "STOCK".to_string(),
+        columns: names(&["warehouse_code"]),
+        filters: vec![filter("quantity_on_hand", REPORT_GTE, Some("5"))],
+        group_by: names(&["warehouse_code"]),
+        aggregates: vec![aggregate(REPORT_SUM, Some("quantity_on_hand")), aggregate(REPORT_COUNT, None)],
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn grouped_report_sums_filtered_rows(pool: PgPool) {
+    let repo = ReportRepository::new(pool);
+    let report = repo.create(stock_by_warehouse()).await.unwrap();
+    assert_eq!(report.filters.len(), 1);
+    assert_eq!(report.aggregates.len(), 2);
+
+    let result = repo.run(&report).await.unwrap();
+    assert_eq!(result.columns, names(&["warehouse_code", "sum_quantity_on_hand", "count"]));
+    assert!(!result.truncated);
+    // WH002 only holds 2 mixers
+    assert_eq!(result.rows.len(), 1);
+    let row = &result.rows[0];
+    assert_eq!(row[0].as_deref(), Some("WH001"));
+    assert_eq!(row[1].as_deref().unwrap().parse::<Decimal>().unwrap(), Decimal::from(1055));
+    assert_eq!(row[2].as_deref(), Some("3"));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn update_replaces_filters_and_columns(pool: PgPool) {
+    let repo = ReportRepository::new(pool);
+    let report = repo
+        .create(CreateReportDefinition {
+            report_code: "ITEMS".to_string(),
+            report_name: "Items".to_string(),
+            description: None,
+            entity: "ITEMS".to_string(),
+            columns: names(&["item_code", "item_name"]),
+            filters: Vec::new(),
+            group_by: Vec::new(),
+            aggregates: Vec::new(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(repo.run(&report).await.unwrap().rows.len(), 4);
+
+    let changes = UpdateReportDefinition {
+        columns: Some(names(&["item_code"])),
+        filters: Some(vec![filter("item_name", REPORT_CONTAINS, Some("helmet"))]),
+        ..Default::default()
+    };
+    let updated = repo.update(report.definition.report_id, changes).await.unwrap().unwrap();
+    assert_eq!(updated.definition.report_name, "Items");
+
+    let result = repo.run(&updated).await.unwrap();
+    assert_eq!(result.rows, vec![vec![Some("ITM003".to_string())]]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn definitions_outside_the_catalog_are_rejected(pool: PgPool) {
+    let repo = ReportRepository::new(pool);
+    let cases = [
+        CreateReportDefinition { entity: "USERS".to_string(), ..stock_by_warehouse() },
+        CreateReportDefinition { columns: names(&["warehouse_code; DROP TABLE x"]), ..stock_by_warehouse() },
+        CreateReportDefinition { columns: names(&["warehouse_code", "item_code"]), ..stock_by_warehouse() },
+        CreateReportDefinition { aggregates: vec![aggregate(REPORT_SUM, Some("item_code"))], ..stock_by_warehouse() },
+        CreateReportDefinition {
+            filters: vec![filter("quantity_on_hand", REPORT_GT, Some("1 OR 1=1"))],
+            ..stock_by_warehouse()
+        },
+    ];
+
+    for case in cases {
+        let err = repo.create(case).await.unwrap_err();
+        assert!(err.downcast_ref::<RuleViolation>().is_some(), "{}", err);
+    }
+    assert!(repo.list().await.unwrap().is_empty());
+}
@@ -0,0 +1,62 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn reserve(quantity: i64, reference: &str) -> CreateStockReservation {
+    CreateStockReservation {
+        warehouse_id: 1,
+        item_id: 2,
+        quantity: Decimal::from(quantity),
+        reference: Some(reference.to_string()),
+        notes: None,
+    }
+}
+
+/// Item 2's reserved and available quantities in warehouse 1
+async fn item_2_stock(pool: &PgPool) -> (Decimal, Decimal) {
+    sqlx::query_as(
+        "SELECT quantity_reserved, quantity_available FROM warehouse.stock_inventory
+         WHERE item_id = 2 AND warehouse_id = 1",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn reservations_hold_available_stock_until_released(pool: PgPool) {
+    let repo = ReservationRepository::new(pool.clone());
+
+    let first = repo.reserve(reserve(700, "SO-1001")).await.unwrap();
+    assert_eq!(first.status, RESERVATION_ACTIVE);
+    repo.reserve(reserve(300, "SO-1002")).await.unwrap();
+    assert_eq!(item_2_stock(&pool).await, (Decimal::from(1000), Decimal::ZERO));
+
+    let refused = repo.reserve(reserve(1, "SO-1003")).await.unwrap_err();
+    assert!(refused.to_string().contains("only 0 available"), "{}", refused);
+
+    let ledger = LedgerRepository::new(pool.clone()).integrity().await.unwrap();
+    assert!(ledger.reconciled, "{:?}", ledger);
+
+    let released = repo.release(first.reservation_id).await.unwrap().unwrap();
+    assert_eq!(released.status, RESERVATION_RELEASED);
+    assert!(released.released_at.is_some());
+    assert_eq!(item_2_stock(&pool).await, (Decimal::from(300), Decimal::from(700)));
+    assert!(repo.release(first.reservation_id).await.is_err());
+    assert!(repo.release(999).await.unwrap().is_none());
+
+    let active = StockReservationFilter { status: Some(RESERVATION_ACTIVE.to_string()), ..Default::default() };
+    let active = repo.reservations(&active).await.unwrap();
+    assert_eq!(active.iter().map(|r| r.reference.as_deref()).collect::<Vec<_>>(), [Some("SO-1002")]);
+
+    let ledger = LedgerRepository::new(pool.clone()).integrity().await.unwrap();
+    assert!(ledger.reconciled, "{:?}", ledger);
+
+    // Reservations keep the fourth decimal stock quantities carry
+    let precise = CreateStockReservation { quantity: Decimal::new(6999995, 4), ..reserve(0, "SO-1004") };
+    assert_eq!(repo.reserve(precise).await.unwrap().quantity, Decimal::new(6999995, 4));
+    assert_eq!(item_2_stock(&pool).await.1, Decimal::new(5, 4));
+    let refused = repo.reserve(reserve(1, "SO-1005")).await.unwrap_err();
+    assert!(refused.to_string().contains("only 0.0005 available"), "{}", refused);
+}
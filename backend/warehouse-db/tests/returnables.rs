@@ -0,0 +1,95 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::{Days, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+/// Ship one of item 2 from warehouse 1 to the tower site of PT Karya Beton
+async fn shipment(db: &Database) -> Shipment {
+    let created = db
+        .orders()
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "PT Karya Beton".to_string(),
+            customer_reference: None,
+            ship_to_name: Some("Tower site".to_string()),
+            ship_to_address: Some("Jl. Sudirman 12".to_string()),
+            ship_to_city: Some("Jakarta".to_string()),
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![CreateOrderLine { item_id: 2, quantity_ordered: Decimal::ONE }],
+        })
+        .await
+        .unwrap();
+    let confirmation = ShipOrder {
+        carrier_code: None,
+        tracking_number: None,
+        service_level: None,
+        lines: vec![ShipOrderLine {
+            line_id: created.lines[0].line_id,
+            quantity: Decimal::ONE,
+            weight_kg: None,
+            lot_number: None,
+        }],
+        lpns: Vec::new(),
+        reference_id: None,
+    };
+    let (_, shipment) = db.orders().ship(created.order.order_id, confirmation, None).await.unwrap().unwrap();
+    shipment.shipment
+}
+
+fn crates(type_id: i32, quantity: i32) -> Vec<ReturnableLine> {
+    vec![ReturnableLine { type_id, quantity }]
+}
+
+fn returned(type_id: i32, quantity: i32, site_name: &str) -> ReceiveReturnables {
+    ReceiveReturnables {
+        warehouse_id: 1,
+        customer_name: "PT Karya Beton".to_string(),
+        site_name: Some(site_name.to_string()),
+        lines: crates(type_id, quantity),
+        notes: None,
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn returns_settle_the_oldest_issues_first(pool: PgPool) {
+    let db = Database::new(pool.clone());
+    let create = CreateReturnableType { type_code: "CRATE".to_string(), description: "Plastic crate".to_string() };
+    let crate_type = db.returnables().create_type(create).await.unwrap();
+    let today = Utc::now().date_naive();
+
+    let first = shipment(&db).await;
+    let issued = IssueReturnables { lines: crates(crate_type.type_id, 10), notes: None };
+    let issued = db.returnables().issue(first.shipment_id, issued).await.unwrap().unwrap();
+    assert_eq!(issued[0].site_name.as_deref(), Some("Tower site"));
+    sqlx::query("UPDATE warehouse.returnable_movements SET moved_at = NOW() - INTERVAL '45 days'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let second = shipment(&db).await;
+    let issued = IssueReturnables { lines: crates(crate_type.type_id, 5), notes: None };
+    db.returnables().issue(second.shipment_id, issued).await.unwrap().unwrap();
+
+    db.returnables().receive(returned(crate_type.type_id, 8, "Tower site")).await.unwrap();
+    assert!(db.returnables().receive(returned(crate_type.type_id, 8, "Tower site")).await.is_err());
+    assert!(db.returnables().receive(returned(crate_type.type_id, 1, "Bridge site")).await.is_err());
+
+    let balances = db.returnables().balances(&ReturnableFilter::default()).await.unwrap();
+    assert_eq!(balances.len(), 1);
+    assert_eq!((balances[0].issued, balances[0].returned, balances[0].outstanding), (15, 8, 7));
+
+    let aging = db.returnables().aging(&ReturnableFilter::default(), today).await.unwrap();
+    let aged = &aging[0];
+    assert_eq!((aged.days_0_30, aged.days_31_60, aged.days_61_90, aged.days_over_90), (5, 2, 0, 0));
+    assert_eq!(aged.outstanding, 7);
+    assert_eq!(aged.oldest_issued_on, today - Days::new(45));
+
+    // Ten weeks on, the older crates are past 90 days
+    let aging = db.returnables().aging(&ReturnableFilter::default(), today + Days::new(70)).await.unwrap();
+    assert_eq!((aging[0].days_61_90, aging[0].days_over_90), (5, 2));
+}
@@ -0,0 +1,133 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn rma(lines: &[(i32, i64)]) -> CreateReturn {
+    CreateReturn {
+        warehouse_id: 1,
+        customer_name: "PT Maju Jaya".to_string(),
+        customer_reference: Some("SO-1001".to_string()),
+        reason: "DAMAGED".to_string(),
+        notes: None,
+        lines: lines
+            .iter()
+            .map(|&(item_id, quantity)| CreateReturnLine {
+                item_id,
+                reason: None,
+                quantity_expected: Decimal::from(quantity),
+            })
+            .collect(),
+    }
+}
+
+async fn balance(pool: &PgPool, item_id: i32) -> (Decimal, Decimal) {
+    sqlx::query_as(
+        "SELECT quantity_on_hand, quantity_quarantined FROM warehouse.stock_inventory
+         WHERE item_id = $1 AND warehouse_id = 1",
+    )
+    .bind(item_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn create_numbers_rma_from_id(pool: PgPool) {
+    let repo = ReturnRepository::new(pool);
+
+    let created = repo.create(rma(&[(3, 5)])).await.unwrap();
+
+    let id = created.authorization.return_id;
+    assert_eq!(created.authorization.rma_number, format!("RMA-{:06}", id));
+    assert_eq!(created.authorization.status, RETURN_AUTHORIZED);
+    assert_eq!(created.lines.len(), 1);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn receive_then_inspect_moves_stock_through_quarantine(pool: PgPool) {
+    let repo = ReturnRepository::new(pool.clone());
+    let created = repo.create(rma(&[(3, 5)])).await.unwrap();
+    let id = created.authorization.return_id;
+    let line_id = created.lines[0].line_id;
+
+    let received = repo
+        .receive(
+            id,
+            ReceiveReturn {
+                lines: vec![ReceiveReturnLine { line_id, quantity: Decimal::from(5) }],
+                reference_id: None,
+            },
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(received.authorization.status, RETURN_RECEIVED);
+    assert_eq!(balance(&pool, 3).await, (Decimal::from(50), Decimal::from(5)));
+
+    let inspect = |outcome, quantity| InspectReturnLine {
+        line_id,
+        outcome,
+        quantity: Decimal::from(quantity),
+        notes: None,
+    };
+    let inspected = repo
+        .inspect(
+            id,
+            InspectReturn {
+                lines: vec![
+                    inspect(InspectionOutcome::Restock, 2),
+                    inspect(InspectionOutcome::Scrap, 2),
+                    inspect(InspectionOutcome::Repair, 1),
+                ],
+                reference_id: None,
+            },
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(inspected.authorization.status, RETURN_COMPLETED);
+    assert_eq!(balance(&pool, 3).await, (Decimal::from(52), Decimal::from(1)));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn receive_rejects_more_than_authorized(pool: PgPool) {
+    let repo = ReturnRepository::new(pool);
+    let created = repo.create(rma(&[(3, 5)])).await.unwrap();
+    let line_id = created.lines[0].line_id;
+
+    let err = repo
+        .receive(
+            created.authorization.return_id,
+            ReceiveReturn {
+                lines: vec![
+                    ReceiveReturnLine { line_id, quantity: Decimal::from(3) },
+                    ReceiveReturnLine { line_id, quantity: Decimal::from(3) },
+                ],
+                reference_id: None,
+            },
+        )
+        .await
+        .unwrap_err();
+
+    assert!(err.downcast_ref::<RuleViolation>().is_some());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn cancel_only_before_receipt(pool: PgPool) {
+    let repo = ReturnRepository::new(pool);
+    let first = repo.create(rma(&[(3, 5)])).await.unwrap();
+    let second = repo.create(rma(&[(2, 1)])).await.unwrap();
+
+    let cancelled = repo.cancel(first.authorization.return_id).await.unwrap().unwrap();
+    assert_eq!(cancelled.authorization.status, RETURN_CANCELLED);
+
+    let id = second.authorization.return_id;
+    let line_id = second.lines[0].line_id;
+    let receipt =
+        ReceiveReturn { lines: vec![ReceiveReturnLine { line_id, quantity: Decimal::ONE }], reference_id: None };
+    repo.receive(id, receipt).await.unwrap();
+    assert!(repo.cancel(id).await.is_err());
+    assert!(repo.cancel(9999).await.unwrap().is_none());
+}
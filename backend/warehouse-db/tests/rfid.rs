@@ -0,0 +1,108 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::{Duration, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn portal(repo: &RfidRepository, warehouse_id: i32, direction: &str) -> RfidPortal {
+    let portal = CreatePortal {
+        portal_code: format!("DOCK-{}", direction),
+        portal_name: "Dock door".to_string(),
+        direction: direction.to_string(),
+    };
+    repo.create_portal(warehouse_id, portal).await.unwrap()
+}
+
+async fn register(repo: &RfidRepository, epc: &str, item_id: i32, quantity: i64, warehouse_id: Option<i32>) {
+    let tag = RegisterTag {
+        epc: epc.to_string(),
+        item_id,
+        serial_number: None,
+        lpn: Some(format!("LPN-{}", epc)),
+        quantity: Some(Decimal::from(quantity)),
+        warehouse_id,
+    };
+    repo.register_tags(RegisterTags { tags: vec![tag] }).await.unwrap();
+}
+
+fn reads(epcs: &[&str], read_at: warehouse_models::chrono::DateTime<Utc>) -> PortalReads {
+    PortalReads { read_at: Some(read_at), epcs: epcs.iter().map(|epc| epc.to_string()).collect() }
+}
+
+async fn on_hand(pool: &PgPool, item_id: i32, warehouse_id: i32) -> Decimal {
+    sqlx::query_scalar(
+        "SELECT quantity_on_hand FROM warehouse.stock_inventory WHERE item_id = $1 AND warehouse_id = $2",
+    )
+    .bind(item_id)
+    .bind(warehouse_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap()
+    .unwrap_or_default()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn in_portal_receives_tagged_stock_once(pool: PgPool) {
+    let repo = RfidRepository::new(pool.clone());
+    let dock = portal(&repo, 1, PORTAL_IN).await;
+    register(&repo, "A1", 3, 1, None).await;
+    register(&repo, "A2", 3, 1, None).await;
+
+    let now = Utc::now();
+    let result = repo.ingest(dock.portal_id, reads(&["a1", "A1", "A2", "FF"], now)).await.unwrap().unwrap();
+    assert_eq!(result.received, 4);
+    assert_eq!(result.accepted, 3);
+    assert_eq!(result.unknown_epcs, vec!["FF".to_string()]);
+    assert_eq!(result.crossings, 2);
+    assert_eq!(result.movements.len(), 1);
+    assert_eq!(result.movements[0].movement_type, "RFID_IN");
+    assert_eq!(result.movements[0].quantity, Decimal::from(2));
+    assert_eq!(on_hand(&pool, 3, 1).await, Decimal::from(52));
+
+    // The reader keeps seeing the tags while they sit in the portal
+    let repeat = repo.ingest(dock.portal_id, reads(&["A1", "A2"], now + Duration::seconds(10))).await.unwrap().unwrap();
+    assert_eq!(repeat.accepted, 0);
+
+    // A later pass is a new read, but the stock is already in
+    let later = repo.ingest(dock.portal_id, reads(&["A1", "A2"], now + Duration::minutes(5))).await.unwrap().unwrap();
+    assert_eq!(later.accepted, 2);
+    assert_eq!(later.crossings, 0);
+    assert_eq!(on_hand(&pool, 3, 1).await, Decimal::from(52));
+
+    let tag = repo.get_tag("a1").await.unwrap().unwrap();
+    assert_eq!(tag.warehouse_id, Some(1));
+    assert_eq!(tag.last_portal_id, Some(dock.portal_id));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn tagged_pallet_moves_between_warehouses(pool: PgPool) {
+    let repo = RfidRepository::new(pool.clone());
+    let outbound = portal(&repo, 1, PORTAL_OUT).await;
+    let inbound = portal(&repo, 2, PORTAL_IN).await;
+    register(&repo, "B1", 3, 10, Some(1)).await;
+
+    let left = repo.ingest(outbound.portal_id, reads(&["B1"], Utc::now())).await.unwrap().unwrap();
+    assert_eq!(left.crossings, 1);
+    assert_eq!(on_hand(&pool, 3, 1).await, Decimal::from(40));
+    assert_eq!(repo.get_tag("B1").await.unwrap().unwrap().warehouse_id, None);
+
+    let arrived = repo.ingest(inbound.portal_id, reads(&["B1"], Utc::now())).await.unwrap().unwrap();
+    assert_eq!(arrived.crossings, 1);
+    assert_eq!(on_hand(&pool, 3, 2).await, Decimal::from(10));
+    assert_eq!(repo.get_tag("B1").await.unwrap().unwrap().warehouse_id, Some(2));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn crossing_without_stock_is_reported_not_failed(pool: PgPool) {
+    let repo = RfidRepository::new(pool.clone());
+    let outbound = portal(&repo, 1, PORTAL_OUT).await;
+    register(&repo, "C1", 4, 1, Some(1)).await;
+    register(&repo, "C2", 3, 5, Some(1)).await;
+
+    let result = repo.ingest(outbound.portal_id, reads(&["C1", "C2"], Utc::now())).await.unwrap().unwrap();
+    assert_eq!(result.crossings, 2);
+    assert_eq!(result.movements.len(), 1);
+    assert_eq!(result.exceptions.len(), 1);
+    assert_eq!(on_hand(&pool, 3, 1).await, Decimal::from(45));
+    assert_eq!(repo.get_tag("C1").await.unwrap().unwrap().warehouse_id, None);
+}
@@ -0,0 +1,97 @@
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use warehouse_db::*;
+
+async fn application_name(db: &Database) -> String {
+    sqlx::query_scalar("SHOW application_name").fetch_one(&db.pool).await.unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn warehouses_on_a_shard_use_its_pool(pool: PgPool) {
+    // Same database, told apart by application name
+    let options = (*pool.connect_options()).clone().application_name("shard-eu");
+    let shard = PgPoolOptions::new().max_connections(1).connect_with(options).await.unwrap();
+    let db = Database::new(pool).with_shard(&[2], shard);
+
+    assert_eq!(db.sharded_warehouses(), vec![2]);
+    assert_eq!(application_name(&db.for_warehouse(2)).await, "shard-eu");
+    assert_ne!(application_name(&db.for_warehouse(1)).await, "shard-eu");
+
+    // Repositories taken from the routed database query the shard
+    let zones = db.for_warehouse(2).locations().zones(2).await.unwrap();
+    assert!(zones.iter().all(|zone| zone.warehouse_id == 2));
+}
+
+async fn stock_item(pool: &PgPool, code: &str) -> i32 {
+    sqlx::query_scalar(
+        "INSERT INTO warehouse.items (item_code, item_name) VALUES ($1, 'Shrink wrap') RETURNING item_id",
+    )
+    .bind(code)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+async fn put_away(pool: &PgPool, item_id: i32, warehouse_id: i32) -> anyhow::Result<()> {
+    sqlx::query("INSERT INTO warehouse.stock_inventory (item_id, warehouse_id, quantity_on_hand) VALUES ($1, $2, 5)")
+        .bind(item_id)
+        .bind(warehouse_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn the_primary_refuses_stock_changes_of_a_sharded_warehouse(pool: PgPool) {
+    let db = Database::new(pool.clone());
+    db.shards().assign(&[(2, "EU".to_string())]).await.unwrap();
+    let item_id = stock_item(&pool, "SHARD-1").await;
+
+    let error = put_away(&pool, item_id, 2).await.unwrap_err();
+    assert_eq!(rejection(&error).as_deref(), Some("stock of warehouse 2 is held on database shard EU"));
+
+    // Other warehouses are not affected, and dropping the shard map lifts the refusal
+    put_away(&pool, item_id, 1).await.unwrap();
+    db.shards().assign(&[]).await.unwrap();
+    put_away(&pool, item_id, 2).await.unwrap();
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn shards_get_the_primarys_warehouses_and_items(pool: PgPool) {
+    // A second database on the same server stands in for the shard
+    let shard_name = format!("{}_shard", pool.connect_options().get_database().unwrap());
+    sqlx::query(&format!("CREATE DATABASE \"{}\"", shard_name)).execute(&pool).await.unwrap();
+    let options = (*pool.connect_options()).clone().database(&shard_name);
+    let shard = PgPoolOptions::new().max_connections(1).connect_with(options).await.unwrap();
+    sqlx::migrate!("../migrations").run(&shard).await.unwrap();
+
+    let item_id = stock_item(&pool, "SHARD-1").await;
+    sqlx::query("UPDATE warehouse.warehouses SET warehouse_name = 'Surabaya DC' WHERE warehouse_id = 2")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let db = Database::new(pool.clone()).with_shard(&[2], shard.clone());
+    assert!(db.shards().copy_master_data().await.unwrap() >= 2);
+    // Nothing changed since, nothing is written
+    assert_eq!(db.shards().copy_master_data().await.unwrap(), 0);
+
+    let name: String = sqlx::query_scalar("SELECT item_name FROM warehouse.items WHERE item_id = $1")
+        .bind(item_id)
+        .fetch_one(&shard)
+        .await
+        .unwrap();
+    assert_eq!(name, "Shrink wrap");
+    let warehouse: String =
+        sqlx::query_scalar("SELECT warehouse_name FROM warehouse.warehouses WHERE warehouse_id = 2")
+            .fetch_one(&shard)
+            .await
+            .unwrap();
+    assert_eq!(warehouse, "Surabaya DC");
+
+    // Stock of the new item can now be held on the shard
+    put_away(&shard, item_id, 2).await.unwrap();
+
+    shard.close().await;
+    sqlx::query(&format!("DROP DATABASE \"{}\"", shard_name)).execute(&pool).await.unwrap();
+}
@@ -0,0 +1,94 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::{Duration, NaiveTime, Utc};
+use warehouse_models::*;
+
+fn shift(code: &str, starts_at: NaiveTime, ends_at: NaiveTime, operator_ids: Vec<i32>) -> CreateShift {
+    CreateShift {
+        shift_code: code.to_string(),
+        shift_name: format!("{} shift", code),
+        starts_at,
+        ends_at,
+        operator_ids,
+    }
+}
+
+fn midnight() -> NaiveTime {
+    NaiveTime::from_hms_opt(0, 0, 0).unwrap()
+}
+
+fn count_task() -> CreateTask {
+    CreateTask {
+        warehouse_id: 1,
+        task_type: TASK_COUNT.to_string(),
+        priority: None,
+        item_id: None,
+        quantity: None,
+        from_location_id: None,
+        to_location_id: None,
+        notes: None,
+        due_at: None,
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn roster_is_managed_per_shift(pool: PgPool) {
+    let repo = ShiftRepository::new(pool);
+    let created = repo
+        .create(1, shift("DAY", NaiveTime::from_hms_opt(6, 0, 0).unwrap(), midnight(), vec![7]))
+        .await
+        .unwrap();
+    let id = created.shift.shift_id;
+
+    let added = repo.add_operators(id, &[7, 9, 8]).await.unwrap().unwrap();
+    assert_eq!(added.operator_ids, [7, 8, 9]);
+
+    let removed = repo.remove_operator(id, 8).await.unwrap().unwrap();
+    assert_eq!(removed.operator_ids, [7, 9]);
+    assert!(repo.remove_operator(id, 8).await.unwrap_err().downcast_ref::<RuleViolation>().is_some());
+    assert!(repo.code_exists(1, "DAY").await.unwrap());
+    assert!(!repo.code_exists(2, "DAY").await.unwrap());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn only_operators_on_duty_claim_work(pool: PgPool) {
+    let shifts = ShiftRepository::new(pool.clone());
+    let tasks = TaskRepository::new(pool);
+    tasks.create(count_task()).await.unwrap();
+
+    // Before the warehouse runs shifts anyone may claim
+    let anyone = ClaimNextTask { warehouse_id: 1, operator_id: 42, task_types: None };
+    assert!(tasks.claim_next(anyone.clone()).await.unwrap().is_some());
+
+    // A shift starting at midnight and ending at midnight covers the whole day
+    shifts.create(1, shift("ALL", midnight(), midnight(), vec![7])).await.unwrap();
+    let jakarta_now = (Utc::now() + Duration::hours(7)).time();
+    let later = shifts
+        .create(1, shift("LATER", jakarta_now + Duration::hours(6), jakarta_now + Duration::hours(7), vec![8]))
+        .await
+        .unwrap();
+
+    let queued = tasks.create(count_task()).await.unwrap();
+    let off_roster = tasks.claim_next(anyone).await.unwrap_err();
+    assert!(off_roster.downcast_ref::<RuleViolation>().is_some());
+    let off_duty = tasks.claim(queued.task_id, ClaimTask { operator_id: 8 }).await.unwrap_err();
+    assert!(off_duty.to_string().contains("on duty"));
+
+    // Rostered operators can be assigned ahead of their shift
+    tasks.assign(queued.task_id, AssignTask { operator_id: 8 }).await.unwrap().unwrap();
+    assert!(tasks.assign(queued.task_id, AssignTask { operator_id: 42 }).await.is_err());
+
+    let all_day = UpdateShift {
+        shift_name: None,
+        starts_at: Some(midnight()),
+        ends_at: Some(midnight()),
+        is_active: None,
+    };
+    shifts
+        .update(later.shift.shift_id, all_day)
+        .await
+        .unwrap()
+        .unwrap();
+    let claimed = tasks.claim(queued.task_id, ClaimTask { operator_id: 8 }).await.unwrap().unwrap();
+    assert_eq!(claimed.status, TASK_IN_PROGRESS);
+}
@@ -0,0 +1,124 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::Utc;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn station(pool: &PgPool, warehouse_id: i32, code: &str, station_type: &str) -> MeasuringStation {
+    StationRepository::new(pool.clone())
+        .create_station(
+            warehouse_id,
+            CreateStation {
+                station_code: code.to_string(),
+                station_name: format!("Station {}", code),
+                station_type: station_type.to_string(),
+            },
+        )
+        .await
+        .unwrap()
+}
+
+fn measured(weight: &str, sides: Option<[i64; 3]>) -> NewCapture {
+    NewCapture {
+        weight_kg: Some(weight.parse().unwrap()),
+        length_cm: sides.map(|s| Decimal::from(s[0])),
+        width_cm: sides.map(|s| Decimal::from(s[1])),
+        height_cm: sides.map(|s| Decimal::from(s[2])),
+        captured_at: Utc::now(),
+        ..Default::default()
+    }
+}
+
+/// A packed carton of order lines for item 2 in warehouse 1
+async fn carton(pool: &PgPool) -> Carton {
+    let order = OrderRepository::new(pool.clone())
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "PT Karya Beton".to_string(),
+            customer_reference: None,
+            ship_to_name: None,
+            ship_to_address: None,
+            ship_to_city: None,
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![CreateOrderLine { item_id: 2, quantity_ordered: Decimal::from(4) }],
+        })
+        .await
+        .unwrap();
+    let waves = WaveRepository::new(pool.clone());
+    let wave_id = waves
+        .create(CreateWave {
+            warehouse_id: 1,
+            zone_id: None,
+            carrier_code: None,
+            cutoff_date: None,
+            order_ids: None,
+            max_orders: None,
+        })
+        .await
+        .unwrap()
+        .wave
+        .wave_id;
+    let task = waves.release(wave_id).await.unwrap().unwrap().tasks.remove(0);
+    waves.confirm_pick(wave_id, task.task_id, ConfirmPick { quantity_picked: task.quantity }).await.unwrap();
+    let packed = PackCarton {
+        order_id: order.order.order_id,
+        box_id: None,
+        lines: vec![PackCartonLine { line_id: order.lines[0].line_id, quantity: Decimal::from(4) }],
+    };
+    waves.pack(wave_id, packed).await.unwrap().unwrap().1.carton
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn packing_station_measures_cartons_of_its_warehouse(pool: PgPool) {
+    let repo = StationRepository::new(pool.clone());
+    let packing = station(&pool, 1, "PACK-1", STATION_PACKING).await;
+    let elsewhere = station(&pool, 2, "PACK-1", STATION_PACKING).await;
+    let carton = carton(&pool).await;
+
+    let capture = NewCapture { carton_id: Some(carton.carton_id), ..measured("3.125", Some([40, 30, 20])) };
+    let stored = repo.record(packing.station_id, capture.clone()).await.unwrap().unwrap();
+    assert_eq!(stored.weight_kg, Some("3.125".parse().unwrap()));
+
+    // A later weight-only capture keeps the measured dimensions
+    let reweighed = NewCapture { carton_id: Some(carton.carton_id), ..measured("3.2", None) };
+    repo.record(packing.station_id, reweighed).await.unwrap().unwrap();
+    let detail = WaveRepository::new(pool.clone()).get_carton(carton.carton_id).await.unwrap().unwrap();
+    assert_eq!(detail.carton.weight_kg, Some("3.2".parse().unwrap()));
+    assert_eq!(detail.carton.length_cm, Some(Decimal::from(40)));
+    assert!(detail.carton.measured_at.is_some());
+
+    let err = repo.record(elsewhere.station_id, capture).await.unwrap_err();
+    assert!(err.downcast_ref::<RuleViolation>().is_some());
+    let item = NewCapture { item_id: Some(2), ..measured("1", None) };
+    let err = repo.record(packing.station_id, item).await.unwrap_err();
+    assert!(err.downcast_ref::<RuleViolation>().is_some());
+
+    let page = repo.captures(packing.station_id, KeysetQuery::default()).await.unwrap();
+    assert_eq!(page.data.len(), 2);
+    assert!(page.data[0].capture_id > page.data[1].capture_id);
+    let station = repo.get_station(packing.station_id).await.unwrap().unwrap();
+    assert_eq!(station.last_capture_at, Some(page.data[0].captured_at));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn receiving_station_updates_item_attributes(pool: PgPool) {
+    let repo = StationRepository::new(pool.clone());
+    let receiving = station(&pool, 1, "RCV-1", STATION_RECEIVING).await;
+
+    let capture = NewCapture { item_id: Some(3), ..measured("2.5", Some([50, 20, 10])) };
+    repo.record(receiving.station_id, capture).await.unwrap().unwrap();
+
+    let item = ItemRepository::new(pool.clone()).get_by_id(3).await.unwrap().unwrap();
+    assert_eq!(item.weight_kg, Some("2.5".parse().unwrap()));
+    assert_eq!(item.length_cm, Some(Decimal::from(50)));
+    assert_eq!(item.volume_cbm, Some("0.01".parse().unwrap()));
+
+    let missing = NewCapture { item_id: Some(9999), ..measured("1", None) };
+    assert!(repo.record(receiving.station_id, missing).await.unwrap_err().downcast_ref::<RuleViolation>().is_some());
+    assert!(repo.record(9999, measured("1", None)).await.unwrap().is_none());
+}
@@ -0,0 +1,184 @@
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn receive(pool: &PgPool, item_id: i32, quantity: i64) {
+    let receipts = ReceiptRepository::new(pool.clone());
+    let created = receipts
+        .create(CreateReceipt {
+            asn_number: "ASN-AUDIT-1".to_string(),
+            warehouse_id: 1,
+            supplier_name: None,
+            purchase_order_number: None,
+            carrier_code: None,
+            expected_date: None,
+            consignor_id: None,
+            lines: vec![CreateReceiptLine { item_id, quantity_expected: Decimal::from(quantity), lpn_number: None }],
+        })
+        .await
+        .unwrap();
+    let lines = vec![ReceiveGoodsLine {
+        line_id: created.lines[0].line_id,
+        quantity: Decimal::from(quantity),
+        weight_kg: None,
+        lot_number: None,
+    }];
+    receipts.receive(created.receipt.receipt_id, ReceiveGoods { lines, reference_id: None }).await.unwrap().unwrap();
+}
+
+async fn ship(pool: &PgPool, item_id: i32, quantity: i64) {
+    let orders = OrderRepository::new(pool.clone());
+    let created = orders
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "PT Karya Beton".to_string(),
+            customer_reference: None,
+            ship_to_name: None,
+            ship_to_address: None,
+            ship_to_city: None,
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![CreateOrderLine { item_id, quantity_ordered: Decimal::from(quantity) }],
+        })
+        .await
+        .unwrap();
+    let confirmation = ShipOrder {
+        carrier_code: None,
+        tracking_number: None,
+        service_level: None,
+        lines: vec![ShipOrderLine {
+            line_id: created.lines[0].line_id,
+            quantity: Decimal::from(quantity),
+            weight_kg: None,
+            lot_number: None,
+        }],
+        lpns: Vec::new(),
+        reference_id: None,
+    };
+    orders.ship(created.order.order_id, confirmation, None).await.unwrap().unwrap();
+}
+
+/// Move every movement of `movement_type` posted so far to `date`
+async fn backdate(pool: &PgPool, movement_type: &str, date: &str) {
+    sqlx::query(
+        "UPDATE warehouse.stock_movements SET movement_date = $2::DATE + TIME '10:00' WHERE movement_type = $1",
+    )
+    .bind(movement_type)
+    .bind(date)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+fn on_hand(snapshot: &StockSnapshot, item_id: i32) -> Option<Decimal> {
+    snapshot.balances.iter().find(|b| b.item_id == item_id && b.warehouse_id == 1).map(|b| b.quantity_on_hand)
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn snapshot_rolls_back_later_movements(pool: PgPool) {
+    receive(&pool, 4, 40).await;
+    backdate(&pool, "RECEIPT", "2024-12-10").await;
+    ship(&pool, 4, 15).await;
+    backdate(&pool, "SHIPMENT", "2025-01-05").await;
+
+    let repo = StockRepository::new(pool);
+    let at = |as_of| StockFilter {
+        warehouse_id: Some(1),
+        as_of: NaiveDate::from_ymd_opt(2024, 12, as_of),
+        ..Default::default()
+    };
+
+    let before = repo.snapshot(at(9)).await.unwrap();
+    assert_eq!(on_hand(&before, 4), None);
+    // Stock that never went through the ledger counts as opening balance
+    assert_eq!(on_hand(&before, 2), Some(Decimal::from(1000)));
+
+    let year_end = repo.snapshot(at(31)).await.unwrap();
+    assert_eq!(year_end.as_of, NaiveDate::from_ymd_opt(2024, 12, 31).unwrap());
+    assert_eq!(on_hand(&year_end, 4), Some(Decimal::from(40)));
+
+    let today = StockFilter { warehouse_id: Some(1), item_id: Some(4), ..Default::default() };
+    let today = repo.snapshot(today).await.unwrap();
+    assert_eq!(today.balances.len(), 1);
+    assert_eq!(on_hand(&today, 4), Some(Decimal::from(25)));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn stock_rows_page_by_warehouse_and_item(pool: PgPool) {
+    let repo = StockRepository::new(pool);
+    let page = |page| PaginationQuery { page: Some(page), limit: Some(2), ..Default::default() };
+
+    let warehouse = repo.by_warehouse(1, page(1)).await.unwrap();
+    assert_eq!(warehouse.data.iter().map(|s| s.item_id).collect::<Vec<_>>(), [1, 2]);
+    assert!(warehouse.pagination.has_next);
+    let rest = repo.by_warehouse(1, page(2)).await.unwrap();
+    assert_eq!(rest.data.iter().map(|s| s.item_id).collect::<Vec<_>>(), [3]);
+
+    // Upserting creates an empty row, then changes only the levels given
+    let levels = UpsertStock {
+        min_stock_level: Some(Decimal::from(5)),
+        max_stock_level: Some(Decimal::from(50)),
+        reorder_point: Some(Decimal::from(10)),
+    };
+    let created = repo.upsert(1, 4, levels).await.unwrap();
+    assert_eq!(created.quantity_on_hand, Decimal::ZERO);
+    assert_eq!(created.reorder_point, Some(Decimal::from(10)));
+    let raised = UpsertStock { min_stock_level: None, max_stock_level: Some(Decimal::from(80)), reorder_point: None };
+    let updated = repo.upsert(1, 4, raised).await.unwrap();
+    assert_eq!(updated.stock_id, created.stock_id);
+    assert_eq!(updated.min_stock_level, Some(Decimal::from(5)));
+    assert_eq!(updated.max_stock_level, Some(Decimal::from(80)));
+
+    let item = repo.by_item(4, PaginationQuery::default()).await.unwrap();
+    assert_eq!(item.data.iter().map(|s| s.warehouse_id).collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(item.pagination.total, Some(2));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn supply_counts_open_purchase_orders_and_transfers_on_the_way(pool: PgPool) {
+    sqlx::query(
+        "WITH po AS (
+             INSERT INTO warehouse.purchase_orders (po_number, warehouse_id, supplier_code, supplier_name, expected_date)
+             VALUES ('PO-900001', 1, 'SUP-1', 'PT Baja Prima', '2025-12-20') RETURNING po_id
+         )
+         INSERT INTO warehouse.purchase_order_lines (po_id, line_number, item_id, quantity_ordered, quantity_received)
+         SELECT po_id, 1, 2, 300, 100 FROM po",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "WITH transfers AS (
+             INSERT INTO warehouse.stock_transfers (transfer_number, source_warehouse_id, destination_warehouse_id, status)
+             VALUES ('TRF-900001', 2, 1, 'IN_TRANSIT'), ('TRF-900002', 1, 2, 'PLANNED')
+             RETURNING transfer_id, transfer_number
+         )
+         INSERT INTO warehouse.stock_transfer_lines (transfer_id, line_number, item_id, quantity)
+         SELECT transfer_id, 1, 2, CASE WHEN transfer_number = 'TRF-900001' THEN 40 ELSE 25 END FROM transfers",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let (position, inbound) = StockRepository::new(pool).supply(1, 2).await.unwrap();
+    assert_eq!((position.on_hand, position.reserved), (Decimal::from(1000), Decimal::from(100)));
+    assert_eq!((position.allocated, position.transfers_out), (Decimal::ZERO, Decimal::from(25)));
+
+    let inbound: Vec<(&str, Option<NaiveDate>, Decimal)> = inbound
+        .iter()
+        .map(|supply| (supply.reference_number.as_str(), supply.expected_date, supply.quantity))
+        .collect();
+    assert_eq!(
+        inbound,
+        [
+            ("TRF-900001", None, Decimal::from(40)),
+            ("PO-900001", NaiveDate::from_ymd_opt(2025, 12, 20), Decimal::from(200)),
+        ]
+    );
+}
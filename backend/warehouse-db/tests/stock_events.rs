@@ -0,0 +1,112 @@
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn post(pool: &PgPool, item_id: i32, movement_type: &str, quantity: i64) {
+    let mut tx = pool.begin().await.unwrap();
+    post_movement(
+        &mut tx,
+        NewMovement {
+            item_id,
+            warehouse_id: 1,
+            movement_type: movement_type.to_string(),
+            quantity: Decimal::from(quantity),
+            catch_weight_kg: None,
+            lot_number: None,
+            unit_cost: None,
+            stock_status: STOCK_AVAILABLE,
+            consignor_id: None,
+            reference_type: None,
+            reference_number: None,
+            notes: None,
+            created_by: Some(1),
+        },
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+}
+
+type Key = (i32, i32, Option<i32>, Decimal, Decimal);
+
+async fn balances(store: &dyn StockStore, filter: StockFilter) -> Vec<Key> {
+    let snapshot = store.snapshot(filter).await.unwrap();
+    snapshot
+        .balances
+        .iter()
+        .map(|b| (b.item_id, b.warehouse_id, b.consignor_id, b.quantity_on_hand, b.quantity_quarantined))
+        .collect()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn folded_events_agree_with_the_balances_table(pool: PgPool) {
+    post(&pool, 4, "RECEIPT", 30).await;
+    post(&pool, 4, "SHIPMENT", -12).await;
+    post(&pool, 2, "ADJUSTMENT", -7).await;
+
+    let state = StockRepository::new(pool.clone());
+    let events = StockEventRepository::new(pool.clone());
+    let expected = balances(&state, StockFilter::default()).await;
+
+    // Before any projection every balance is folded from its events
+    assert_eq!(balances(&events, StockFilter::default()).await, expected);
+    events.project().await.unwrap();
+    assert_eq!(balances(&events, StockFilter::default()).await, expected);
+
+    // Events appended after the projection are added on top of it
+    post(&pool, 4, "SHIPMENT", -3).await;
+    let filter = || StockFilter { item_id: Some(4), warehouse_id: Some(1), ..Default::default() };
+    assert_eq!(balances(&events, filter()).await, [(4, 1, None, Decimal::from(15), Decimal::ZERO)]);
+
+    let stream = events
+        .history(
+            StockEventFilter { item_id: Some(4), warehouse_id: Some(1), ..Default::default() },
+            KeysetQuery::default(),
+        )
+        .await
+        .unwrap();
+    let versions: Vec<_> = stream.data.iter().map(|e| e.version).collect();
+    assert_eq!(versions, (1..=versions.len() as i32).rev().collect::<Vec<_>>());
+    assert_eq!(stream.data.last().unwrap().event_type, STOCK_EVENT_OPENED);
+    let total: Decimal = stream.data.iter().map(|e| e.on_hand_delta).sum();
+    assert_eq!(total, Decimal::from(15));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn past_days_fold_only_the_events_before_them(pool: PgPool) {
+    post(&pool, 3, "RECEIPT", 20).await;
+    sqlx::query("UPDATE warehouse.stock_events SET occurred_at = NOW() - INTERVAL '3 days'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    post(&pool, 3, "SHIPMENT", -5).await;
+
+    let events = StockEventRepository::new(pool.clone());
+    let as_of = |days| StockFilter {
+        item_id: Some(3),
+        as_of: Some(Utc::now().date_naive() - Duration::days(days)),
+        ..Default::default()
+    };
+    assert_eq!(balances(&events, as_of(1)).await, [(3, 1, None, Decimal::from(70), Decimal::ZERO)]);
+    assert!(balances(&events, as_of(4)).await.is_empty());
+    assert_eq!(balances(&events, as_of(0)).await, [(3, 1, None, Decimal::from(65), Decimal::ZERO)]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn the_projection_rebuilds_from_the_events(pool: PgPool) {
+    post(&pool, 1, "RECEIPT", 4).await;
+    let events = StockEventRepository::new(pool.clone());
+    let projected = events.project().await.unwrap();
+    assert!(projected.streams >= 4);
+    let expected = balances(&events, StockFilter::default()).await;
+
+    sqlx::query("UPDATE warehouse.stock_event_balances SET quantity_on_hand = 0").execute(&pool).await.unwrap();
+    assert_ne!(balances(&events, StockFilter::default()).await, expected);
+
+    let rebuilt = events.rebuild().await.unwrap();
+    assert_eq!(rebuilt.streams, projected.streams);
+    assert_eq!(balances(&events, StockFilter::default()).await, expected);
+    assert_eq!(events.project().await.unwrap().streams, 0);
+}
@@ -0,0 +1,75 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::*;
+
+fn move_task() -> CreateTask {
+    CreateTask {
+        warehouse_id: 1,
+        task_type: TASK_MOVE.to_string(),
+        priority: None,
+        item_id: None,
+        quantity: None,
+        from_location_id: None,
+        to_location_id: None,
+        notes: None,
+        due_at: None,
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn full_snapshot_then_changes_since_cursor(pool: PgPool) {
+    let sync = SyncRepository::new(pool.clone());
+    let tasks = TaskRepository::new(pool.clone());
+    let open = tasks.create(move_task()).await.unwrap();
+    let done = tasks.create(move_task()).await.unwrap();
+    tasks.cancel(done.task_id).await.unwrap();
+    let other_warehouse = tasks.create(CreateTask { warehouse_id: 2, ..move_task() }).await.unwrap();
+
+    let snapshot = sync.delta(1, None).await.unwrap();
+    assert!(snapshot.full);
+    assert_eq!(snapshot.tasks.iter().map(|t| t.task_id).collect::<Vec<_>>(), [open.task_id]);
+
+    let claimed = tasks.claim(open.task_id, ClaimTask { operator_id: 7 }).await.unwrap().unwrap();
+    assert_eq!(claimed.version, open.version + 1);
+    tasks.claim(other_warehouse.task_id, ClaimTask { operator_id: 7 }).await.unwrap();
+
+    let delta = sync.delta(1, Some(snapshot.cursor)).await.unwrap();
+    assert!(!delta.full);
+    assert_eq!(delta.tasks.len(), 1);
+    assert_eq!(delta.tasks[0].status, TASK_IN_PROGRESS);
+
+    let quiet = sync.delta(1, Some(delta.cursor)).await.unwrap();
+    assert!(quiet.tasks.is_empty());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn location_changes_are_scoped_by_zone_warehouse(pool: PgPool) {
+    let locations = LocationRepository::new(pool.clone());
+    let sync = SyncRepository::new(pool);
+    let cursor = sync.delta(1, None).await.unwrap().cursor;
+    let zone = locations
+        .create_zone(1, CreateZone { zone_code: "A".to_string(), zone_name: "Zone A".to_string(), pick_sequence: 1 })
+        .await
+        .unwrap();
+    locations
+        .create_location(
+            zone.zone_id,
+            CreateLocation { location_code: "A-01".to_string(), pick_sequence: 1, item_id: None },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(sync.delta(1, Some(cursor)).await.unwrap().locations.len(), 1);
+    assert!(sync.delta(2, Some(cursor)).await.unwrap().locations.is_empty());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn mutation_outcomes_are_recorded_once(pool: PgPool) {
+    let sync = SyncRepository::new(pool);
+    sync.record("HH-01", "m-1", 5, MutationStatus::Conflict, Some("stale")).await.unwrap();
+    sync.record("HH-01", "m-1", 5, MutationStatus::Applied, None).await.unwrap();
+
+    let recorded = sync.recorded("HH-01", "m-1").await.unwrap().unwrap();
+    assert_eq!(recorded.status, "CONFLICT");
+    assert!(sync.recorded("HH-02", "m-1").await.unwrap().is_none());
+}
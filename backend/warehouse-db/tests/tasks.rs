@@ -0,0 +1,133 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn task(task_type: &str, priority: i32) -> CreateTask {
+    CreateTask {
+        warehouse_id: 1,
+        task_type: task_type.to_string(),
+        priority: Some(priority),
+        item_id: Some(2),
+        quantity: Some(Decimal::from(4)),
+        from_location_id: None,
+        to_location_id: None,
+        notes: None,
+        due_at: None,
+    }
+}
+
+fn next(operator_id: i32) -> ClaimNextTask {
+    ClaimNextTask { warehouse_id: 1, operator_id, task_types: None }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn claim_next_prefers_own_assignment_then_priority(pool: PgPool) {
+    let repo = TaskRepository::new(pool);
+    let routine = repo.create(task(TASK_PUTAWAY, 20)).await.unwrap();
+    let urgent = repo.create(task(TASK_MOVE, 90)).await.unwrap();
+    let count = repo.create(task(TASK_COUNT, 50)).await.unwrap();
+    repo.assign(routine.task_id, AssignTask { operator_id: 7 }).await.unwrap();
+
+    let mine = repo.claim_next(next(7)).await.unwrap().unwrap();
+    assert_eq!(mine.task_id, routine.task_id);
+    assert_eq!(mine.status, TASK_IN_PROGRESS);
+
+    // Someone else's assignment is never handed out
+    let other = repo.claim_next(next(8)).await.unwrap().unwrap();
+    assert_eq!(other.task_id, urgent.task_id);
+
+    let counts_only = ClaimNextTask { task_types: Some(vec![TASK_COUNT.to_string()]), ..next(9) };
+    assert_eq!(repo.claim_next(counts_only.clone()).await.unwrap().unwrap().task_id, count.task_id);
+    assert!(repo.claim_next(counts_only).await.unwrap().is_none());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn only_the_claiming_operator_completes(pool: PgPool) {
+    let repo = TaskRepository::new(pool);
+    let queued = repo.create(task(TASK_PUTAWAY, 50)).await.unwrap();
+    repo.assign(queued.task_id, AssignTask { operator_id: 7 }).await.unwrap();
+
+    let stolen = repo.claim(queued.task_id, ClaimTask { operator_id: 8 }).await.unwrap_err();
+    assert!(stolen.downcast_ref::<RuleViolation>().is_some());
+    repo.claim(queued.task_id, ClaimTask { operator_id: 7 }).await.unwrap().unwrap();
+
+    let done = |operator_id| CompleteTask { operator_id, quantity_done: None, notes: None };
+    assert!(repo.complete(queued.task_id, done(8)).await.unwrap_err().downcast_ref::<RuleViolation>().is_some());
+    let completed = repo.complete(queued.task_id, done(7)).await.unwrap().unwrap();
+
+    assert_eq!(completed.status, TASK_COMPLETED);
+    assert_eq!(completed.completed_by, Some(7));
+    assert_eq!(completed.quantity_done, Some(Decimal::from(4)));
+    assert!(repo.cancel(queued.task_id).await.unwrap_err().downcast_ref::<RuleViolation>().is_some());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn wave_release_queues_picks_and_completion_confirms_them(pool: PgPool) {
+    let order = OrderRepository::new(pool.clone())
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "PT Karya Beton".to_string(),
+            customer_reference: None,
+            ship_to_name: None,
+            ship_to_address: None,
+            ship_to_city: None,
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![CreateOrderLine { item_id: 2, quantity_ordered: Decimal::from(6) }],
+        })
+        .await
+        .unwrap();
+    let waves = WaveRepository::new(pool.clone());
+    let wave = waves
+        .create(CreateWave {
+            warehouse_id: 1,
+            zone_id: None,
+            carrier_code: None,
+            cutoff_date: None,
+            order_ids: Some(vec![order.order.order_id]),
+            max_orders: None,
+        })
+        .await
+        .unwrap();
+    waves.release(wave.wave.wave_id).await.unwrap().unwrap();
+
+    let repo = TaskRepository::new(pool);
+    let pick = repo.claim_next(next(7)).await.unwrap().unwrap();
+    assert_eq!(pick.task_type, TASK_PICK);
+    assert_eq!(pick.reference_type.as_deref(), Some(TASK_REF_PICK));
+    assert!(repo.cancel(pick.task_id).await.is_err());
+
+    repo.complete(
+        pick.task_id,
+        CompleteTask { operator_id: 7, quantity_done: Some(Decimal::from(5)), notes: None },
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    let picked = waves.get_by_id(wave.wave.wave_id).await.unwrap().unwrap();
+    assert_eq!(picked.wave.status, WAVE_PICKED);
+    assert_eq!(picked.tasks[0].status, PICK_SHORT);
+    assert_eq!(picked.tasks[0].quantity_picked, Decimal::from(5));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn queue_depth_counts_outstanding_work(pool: PgPool) {
+    let repo = TaskRepository::new(pool);
+    repo.create(task(TASK_PUTAWAY, 50)).await.unwrap();
+    let assigned = repo.create(task(TASK_PUTAWAY, 50)).await.unwrap();
+    let cancelled = repo.create(task(TASK_COUNT, 50)).await.unwrap();
+    repo.assign(assigned.task_id, AssignTask { operator_id: 7 }).await.unwrap();
+    repo.cancel(cancelled.task_id).await.unwrap();
+
+    let depth = repo.queue_depth(QueueDepthFilter { warehouse_id: Some(1) }).await.unwrap();
+
+    assert_eq!(depth.len(), 1);
+    assert_eq!((depth[0].task_type.as_str(), depth[0].open, depth[0].assigned), (TASK_PUTAWAY, 1, 1));
+    assert!(depth[0].oldest_waiting_at.is_some());
+}
@@ -0,0 +1,73 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::*;
+
+const LPN_V1: &str = "^XA^FO50,50^BCN,100^FD{{lpn_number}}^FS^XZ";
+const LPN_V2: &str = "^XA^FO50,50^BCN,120^FD{{lpn_number}}^FS^FO50,200^FD{{item_code}}^FS^XZ";
+
+fn lpn_label(body: &str) -> CreatePrintTemplate {
+    CreatePrintTemplate {
+        template_code: "LPN-4X6".to_string(),
+        name: "LPN label 4x6".to_string(),
+        purpose: TEMPLATE_LPN_LABEL.to_string(),
+        format: TEMPLATE_ZPL.to_string(),
+        body: body.to_string(),
+        change_note: None,
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn body_changes_are_versioned_and_can_be_restored(pool: PgPool) {
+    let repo = TemplateRepository::new(pool.clone());
+    let error = repo.create(lpn_label("^FO50,50^FDno format^FS")).await.unwrap_err();
+    assert!(error.to_string().contains("must start with ^XA"), "{}", error);
+
+    let created = repo.create(lpn_label(LPN_V1)).await.unwrap();
+    let id = created.template.template_id;
+    assert_eq!(created.template.current_version, 1);
+
+    let edit = |body: &str| UpdatePrintTemplate {
+        body: Some(body.to_string()),
+        change_note: Some("Add the item code".to_string()),
+        ..Default::default()
+    };
+    let updated = repo.update(id, edit(LPN_V2)).await.unwrap().unwrap();
+    assert_eq!((updated.template.current_version, updated.body.as_str()), (2, LPN_V2));
+    // Saving the same body again is not a new version
+    assert_eq!(repo.update(id, edit(LPN_V2)).await.unwrap().unwrap().template.current_version, 2);
+
+    let restored = repo.restore(id, 1).await.unwrap().unwrap();
+    assert_eq!((restored.template.current_version, restored.body.as_str()), (3, LPN_V1));
+    let versions = repo.versions(id).await.unwrap();
+    assert_eq!(versions.iter().map(|v| v.version).collect::<Vec<_>>(), [3, 2, 1]);
+    assert_eq!(versions[0].change_note.as_deref(), Some("Restored version 1"));
+    assert!(repo.restore(id, 9).await.unwrap().is_none());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn warehouses_print_with_their_assigned_version(pool: PgPool) {
+    let repo = TemplateRepository::new(pool.clone());
+    let id = repo.create(lpn_label(LPN_V1)).await.unwrap().template.template_id;
+    let update = UpdatePrintTemplate { body: Some(LPN_V2.to_string()), ..Default::default() };
+    repo.update(id, update).await.unwrap().unwrap();
+
+    let pinned =
+        repo.assign(1, AssignPrintTemplate { template_id: id, pinned_version: Some(1) }).await.unwrap().unwrap();
+    assert_eq!((pinned.purpose.as_str(), pinned.version), (TEMPLATE_LPN_LABEL, 1));
+    let current = repo.assign(2, AssignPrintTemplate { template_id: id, pinned_version: None }).await.unwrap().unwrap();
+    assert_eq!(current.version, 2);
+    let error = repo.assign(2, AssignPrintTemplate { template_id: id, pinned_version: Some(5) }).await.unwrap_err();
+    assert!(error.to_string().contains("has no version 5"), "{}", error);
+
+    let deactivate = || UpdatePrintTemplate { is_active: Some(false), ..Default::default() };
+    let error = repo.update(id, deactivate()).await.unwrap_err();
+    assert!(error.to_string().contains("assigned to warehouse 1, 2"), "{}", error);
+
+    assert!(repo.unassign(1, TEMPLATE_LPN_LABEL).await.unwrap());
+    assert!(repo.unassign(2, TEMPLATE_LPN_LABEL).await.unwrap());
+    assert!(!repo.unassign(2, TEMPLATE_LPN_LABEL).await.unwrap());
+    assert!(repo.assignments(1).await.unwrap().is_empty());
+    assert!(!repo.update(id, deactivate()).await.unwrap().unwrap().template.is_active);
+    let error = repo.assign(1, AssignPrintTemplate { template_id: id, pinned_version: None }).await.unwrap_err();
+    assert!(error.to_string().contains("it is inactive"), "{}", error);
+}
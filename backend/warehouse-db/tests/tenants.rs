@@ -0,0 +1,109 @@
+use sqlx::PgPool;
+use warehouse_db::audit;
+use warehouse_db::*;
+use warehouse_models::*;
+
+fn warehouse(code: &str) -> CreateWarehouse {
+    CreateWarehouse {
+        warehouse_code: code.to_string(),
+        warehouse_name: format!("{} Warehouse", code),
+        warehouse_type: None,
+        address: None,
+        city: Some("Bandung".to_string()),
+        state: None,
+        postal_code: None,
+        country: None,
+        email: None,
+        phone: None,
+        manager_user_id: None,
+        timezone: None,
+    }
+}
+
+fn tenant(code: &str, warehouses: Vec<CreateWarehouse>) -> CreateTenant {
+    CreateTenant { tenant_code: code.to_string(), tenant_name: format!("{} Logistics", code), warehouses }
+}
+
+async fn count(pool: &PgPool, sql: &str) -> i64 {
+    sqlx::query_scalar(sql).fetch_one(pool).await.unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn provisions_a_clean_schema_with_the_tenants_warehouses(pool: PgPool) {
+    let repo = TenantRepository::new(pool.clone());
+    let provisioned = repo.provision(tenant("acme", vec![warehouse("ACME-BDG")])).await.unwrap().unwrap();
+
+    assert_eq!(provisioned.tenant.status, TENANT_ACTIVE);
+    assert_eq!(provisioned.tenant.schema_name, "tenant_acme");
+    assert_eq!(provisioned.connection_options, "-c search_path=tenant_acme");
+    assert!(provisioned.tenant.schema_version.is_some());
+    assert!(provisioned.migrations_applied > 0);
+    assert_eq!(provisioned.warehouses_seeded, 1);
+
+    // Only the seeded warehouse; none of the sample data, and the shared schema is untouched
+    assert_eq!(count(&pool, "SELECT COUNT(*) FROM tenant_acme.warehouses").await, 1);
+    assert_eq!(count(&pool, "SELECT COUNT(*) FROM tenant_acme.items").await, 0);
+    assert_eq!(count(&pool, "SELECT COUNT(*) FROM warehouse.warehouses").await, 2);
+
+    // Triggers write to the tenant's own tables
+    sqlx::query(
+        "INSERT INTO tenant_acme.items (item_code, item_name, item_type, unit, status)
+         VALUES ('ACME-1', 'Pallet wrap', 'STOCK', 'ROLL', 'ACTIVE')",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO tenant_acme.stock_inventory (item_id, warehouse_id, quantity_on_hand) VALUES (1, 1, 10)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count(&pool, "SELECT COUNT(*) FROM tenant_acme.stock_events").await, 1);
+    let shared_events = count(&pool, "SELECT COUNT(*) FROM warehouse.stock_events").await;
+    assert_eq!(count(&pool, "SELECT COUNT(*) FROM warehouse.stock_events").await, shared_events);
+
+    // The code is taken
+    assert!(repo.provision(tenant("acme", Vec::new())).await.unwrap().is_none());
+    assert_eq!(repo.list().await.unwrap().len(), 1);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn failed_provisioning_leaves_no_schema_and_can_be_retried(pool: PgPool) {
+    let repo = TenantRepository::new(pool.clone());
+    let duplicate = vec![warehouse("GLOBEX-1"), warehouse("GLOBEX-1")];
+    assert!(repo.provision(tenant("globex", duplicate)).await.is_err());
+
+    let failed = &repo.list().await.unwrap()[0];
+    assert_eq!(failed.status, TENANT_FAILED);
+    assert!(failed.last_error.as_deref().unwrap().contains("GLOBEX-1"));
+    let schemas = count(&pool, "SELECT COUNT(*) FROM pg_namespace WHERE nspname = 'tenant_globex'").await;
+    assert_eq!(schemas, 0);
+
+    let retried = repo.provision(tenant("globex", vec![warehouse("GLOBEX-1")])).await.unwrap().unwrap();
+    assert_eq!(retried.tenant.tenant_id, failed.tenant_id);
+    assert_eq!(retried.tenant.status, TENANT_ACTIVE);
+    assert!(retried.tenant.last_error.is_none());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn tenant_changes_are_audited_with_their_actor(pool: PgPool) {
+    let repo = TenantRepository::new(pool.clone());
+    repo.provision(tenant("initech", vec![warehouse("INITECH-1")])).await.unwrap().unwrap();
+
+    let mut conn = pool.acquire().await.unwrap();
+    audit::as_actor("user:7".to_string(), async {
+        audit::tag_connection(&mut conn).await.unwrap();
+        sqlx::query("UPDATE tenant_initech.warehouses SET warehouse_name = 'Initech Cimahi'")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+    })
+    .await;
+
+    let actor: Option<String> = sqlx::query_scalar(
+        "SELECT actor FROM tenant_initech.audit_log WHERE entity = 'warehouse' AND action = 'UPDATE'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(actor.as_deref(), Some("user:7"));
+}
@@ -0,0 +1,110 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::{DateTime, Duration, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn shipment(pool: &PgPool, tracking_number: &str) -> Shipment {
+    let orders = OrderRepository::new(pool.clone());
+    let created = orders
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "PT Karya Beton".to_string(),
+            customer_reference: None,
+            ship_to_name: None,
+            ship_to_address: None,
+            ship_to_city: None,
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: None,
+            carrier_code: None,
+            dropship_supplier_code: None,
+            lines: vec![CreateOrderLine {
+                item_id: 2,
+                quantity_ordered: Decimal::ONE,
+            }],
+        })
+        .await
+        .unwrap();
+    let confirmation = ShipOrder {
+        carrier_code: Some("JNE".to_string()),
+        tracking_number: Some(tracking_number.to_string()),
+        service_level: None,
+        lines: vec![ShipOrderLine {
+            line_id: created.lines[0].line_id,
+            quantity: Decimal::ONE,
+            weight_kg: None,
+            lot_number: None,
+        }],
+        lpns: Vec::new(),
+        reference_id: None,
+    };
+    let (_, shipment) = orders.ship(created.order.order_id, confirmation, None).await.unwrap().unwrap();
+    shipment.shipment
+}
+
+fn event(status: TrackingStatus, occurred_at: DateTime<Utc>) -> NewTrackingEvent {
+    NewTrackingEvent {
+        status,
+        description: None,
+        location: Some("Jakarta Hub".to_string()),
+        occurred_at,
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn finds_shipment_by_carrier_tracking_number(pool: PgPool) {
+    let repo = TrackingRepository::new(pool.clone());
+    let shipped = shipment(&pool, "JNE0001").await;
+
+    let found = repo.find_shipment("jne", "JNE0001").await.unwrap().unwrap();
+
+    assert_eq!(found.shipment_id, shipped.shipment_id);
+    assert!(repo.find_shipment("SICEPAT", "JNE0001").await.unwrap().is_none());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn record_skips_known_events_and_follows_latest(pool: PgPool) {
+    let repo = TrackingRepository::new(pool.clone());
+    let shipped = shipment(&pool, "JNE0001").await;
+    let picked_up = Utc::now() - Duration::hours(5);
+    let delivered = Utc::now() - Duration::hours(1);
+
+    let first = repo
+        .record(shipped.shipment_id, vec![event(TrackingStatus::InTransit, picked_up)], TRACKING_SOURCE_WEBHOOK)
+        .await
+        .unwrap();
+    assert_eq!(first.len(), 1);
+
+    // Polls resend the whole history, out of order
+    let second = repo
+        .record(
+            shipped.shipment_id,
+            vec![event(TrackingStatus::Delivered, delivered), event(TrackingStatus::InTransit, picked_up)],
+            TRACKING_SOURCE_POLL,
+        )
+        .await
+        .unwrap();
+    assert_eq!(second.len(), 1);
+
+    let timeline = repo.timeline(shipped.shipment_id).await.unwrap().unwrap();
+    let statuses: Vec<_> = timeline.events.iter().map(|e| e.status.as_str()).collect();
+    assert_eq!(statuses, ["IN_TRANSIT", "DELIVERED"]);
+    assert_eq!(timeline.status, "DELIVERED");
+    assert_eq!(timeline.delivered_at.map(|t| t.timestamp()), Some(delivered.timestamp()));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn pollable_skips_finished_shipments(pool: PgPool) {
+    let repo = TrackingRepository::new(pool.clone());
+    let moving = shipment(&pool, "JNE0001").await;
+    let delivered = shipment(&pool, "JNE0002").await;
+    repo.record(delivered.shipment_id, vec![event(TrackingStatus::Delivered, Utc::now())], TRACKING_SOURCE_POLL)
+        .await
+        .unwrap();
+
+    let pollable: Vec<_> = repo.pollable(60, 100).await.unwrap().iter().map(|s| s.shipment_id).collect();
+
+    assert_eq!(pollable, [moving.shipment_id]);
+}
@@ -0,0 +1,69 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::*;
+
+fn user(username: &str) -> CreateUser {
+    CreateUser {
+        username: username.to_string(),
+        email: Some(format!("{}@warehouse.example", username)),
+        full_name: "Siti Rahayu".to_string(),
+        password: "gudang-utama-1".to_string(),
+        role: None,
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn passwords_are_stored_hashed_and_checked_against_the_hash(pool: PgPool) {
+    let repo = UserRepository::new(pool.clone());
+    let created = repo.create(user("siti")).await.unwrap();
+    assert!(created.is_active);
+    assert_eq!(created.role, ROLE_VIEWER);
+    assert!(repo.username_exists("siti").await.unwrap());
+    assert!(repo.email_exists("siti@warehouse.example", None).await.unwrap());
+    assert!(!repo.email_exists("siti@warehouse.example", Some(created.user_id)).await.unwrap());
+
+    let stored: String = sqlx::query_scalar("SELECT password_hash FROM warehouse.users WHERE user_id = $1")
+        .bind(created.user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(stored.starts_with("$argon2id$"), "{}", stored);
+    // Each hash has its own salt
+    let other = repo.create(user("budi")).await.unwrap();
+    let other_stored: String = sqlx::query_scalar("SELECT password_hash FROM warehouse.users WHERE user_id = $1")
+        .bind(other.user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_ne!(stored, other_stored);
+
+    let signed_in = repo.authenticate("siti", "gudang-utama-1").await.unwrap().unwrap();
+    assert_eq!(signed_in.user_id, created.user_id);
+    assert!(repo.authenticate("siti", "wrong-password").await.unwrap().is_none());
+    assert!(repo.authenticate("nobody", "gudang-utama-1").await.unwrap().is_none());
+    assert!(repo.authenticate("system", "!").await.unwrap().is_none());
+
+    let changes = UpdateUser { password: Some("gudang-baru-22".to_string()), ..Default::default() };
+    repo.update(created.user_id, changes).await.unwrap().unwrap();
+    assert!(repo.authenticate("siti", "gudang-utama-1").await.unwrap().is_none());
+    assert!(repo.authenticate("siti", "gudang-baru-22").await.unwrap().is_some());
+
+    assert!(repo.deactivate(created.user_id).await.unwrap());
+    assert!(!repo.deactivate(created.user_id).await.unwrap());
+    assert!(repo.authenticate("siti", "gudang-baru-22").await.unwrap().is_none());
+    let active = repo.list(&UserFilter { is_active: Some(true), role: None }).await.unwrap();
+    assert_eq!(active.iter().map(|user| user.username.as_str()).collect::<Vec<_>>(), ["budi", "system"]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn users_are_given_roles(pool: PgPool) {
+    let repo = UserRepository::new(pool.clone());
+    let manager = repo.create(CreateUser { role: Some(ROLE_MANAGER.to_string()), ..user("dewi") }).await.unwrap();
+    assert_eq!(manager.role, ROLE_MANAGER);
+    repo.create(user("budi")).await.unwrap();
+
+    let promoted = UpdateUser { role: Some(ROLE_ADMIN.to_string()), ..Default::default() };
+    assert_eq!(repo.update(manager.user_id, promoted).await.unwrap().unwrap().role, ROLE_ADMIN);
+    let admins = repo.list(&UserFilter { is_active: None, role: Some(ROLE_ADMIN.to_string()) }).await.unwrap();
+    assert_eq!(admins.iter().map(|user| user.username.as_str()).collect::<Vec<_>>(), ["dewi", "system"]);
+}
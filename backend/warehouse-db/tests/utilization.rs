@@ -0,0 +1,77 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn zone(code: &str, pick_sequence: i32) -> CreateZone {
+    CreateZone { zone_code: code.to_string(), zone_name: format!("Zone {}", code), pick_sequence }
+}
+
+fn location(code: &str) -> CreateLocation {
+    CreateLocation { location_code: code.to_string(), pick_sequence: 0, item_id: None }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn occupancy_counts_goods_where_their_outermost_lpn_stands(pool: PgPool) {
+    let locations = LocationRepository::new(pool.clone());
+    let lpns = LpnRepository::new(pool.clone());
+
+    // Rebar 0.002 m3 a bar; helmets 30 x 25 x 20 cm
+    sqlx::query("UPDATE warehouse.items SET volume_cbm = 0.002 WHERE item_id = 2").execute(&pool).await.unwrap();
+    sqlx::query("UPDATE warehouse.items SET length_cm = 30, width_cm = 25, height_cm = 20 WHERE item_id = 3")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let bulk = locations.create_zone(1, zone("BULK", 2)).await.unwrap();
+    let pick = locations.create_zone(1, zone("PICK", 1)).await.unwrap();
+    let rack = locations.create_location(bulk.zone_id, location("B-01")).await.unwrap();
+    let shelf = locations.create_location(pick.zone_id, location("P-01")).await.unwrap();
+    let capacity = SetLocationCapacity { max_volume_cbm: Some(Decimal::from(2)), max_positions: Some(4) };
+    locations.set_capacity(rack.location_id, capacity).await.unwrap().unwrap();
+
+    let on_rack = |parent_lpn_id| CreateLpn {
+        lpn_number: None,
+        lpn_type: LPN_PALLET.to_string(),
+        location_id: parent_lpn_id.is_none().then_some(rack.location_id),
+        parent_lpn_id,
+    };
+    let pallet = lpns.create(1, on_rack(None)).await.unwrap().lpn.lpn_id;
+    let case = lpns.create(1, CreateLpn { lpn_type: LPN_CASE.to_string(), ..on_rack(Some(pallet)) }).await.unwrap();
+    lpns.set_content(pallet, 2, SetLpnContent { quantity: Decimal::from(500) }).await.unwrap().unwrap();
+    lpns.set_content(case.lpn.lpn_id, 3, SetLpnContent { quantity: Decimal::from(10) }).await.unwrap().unwrap();
+    lpns.set_content(case.lpn.lpn_id, 1, SetLpnContent { quantity: Decimal::from(2) }).await.unwrap().unwrap();
+
+    let utilization = locations.utilization(1).await.unwrap();
+    let codes: Vec<_> = utilization.zones.iter().map(|zone| zone.zone_code.as_str()).collect();
+    assert_eq!(codes, ["PICK", "BULK"]);
+
+    // 500 x 0.002 + 10 x 0.015 = 1.15 m3 of 2; one pallet of 4 positions
+    let rack = &utilization.zones[1].locations[0];
+    assert_eq!(rack.occupancy.used_volume_cbm, Decimal::new(115, 2));
+    assert_eq!(rack.occupancy.volume_pct, Some(Decimal::new(575, 1)));
+    assert_eq!(rack.occupancy.used_positions, 1);
+    assert_eq!(rack.occupancy.positions_pct, Some(Decimal::from(25)));
+    assert_eq!(rack.occupancy.occupancy_pct, Some(Decimal::new(575, 1)));
+    assert_eq!(rack.unmeasured_units, Decimal::from(2));
+
+    let shelf_row = &utilization.zones[0].locations[0];
+    assert_eq!(shelf_row.location_id, shelf.location_id);
+    assert_eq!(shelf_row.occupancy.used_volume_cbm, Decimal::ZERO);
+    assert_eq!(shelf_row.occupancy.occupancy_pct, None);
+    assert_eq!(utilization.occupancy.max_positions, Some(4));
+    assert_eq!(utilization.occupancy.occupancy_pct, Some(Decimal::new(575, 1)));
+}
+
+#[test]
+fn combined_percentages_only_weigh_locations_with_that_capacity() {
+    let measured = Occupancy::new(Decimal::from(3), Some(Decimal::from(4)), 2, None);
+    let unmeasured = Occupancy::new(Decimal::from(5), None, 1, Some(4));
+
+    let total = Occupancy::combine([&measured, &unmeasured]);
+    assert_eq!(total.used_volume_cbm, Decimal::from(8));
+    assert_eq!(total.volume_pct, Some(Decimal::from(75)));
+    assert_eq!(total.used_positions, 3);
+    assert_eq!(total.positions_pct, Some(Decimal::from(25)));
+    assert_eq!(total.occupancy_pct, Some(Decimal::from(75)));
+}
@@ -0,0 +1,57 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::*;
+
+fn filter(name: &str, value: &str) -> ViewFilter {
+    ViewFilter { name: name.to_string(), value: value.to_string() }
+}
+
+fn critical_spares() -> CreateSavedView {
+    CreateSavedView {
+        view_name: "Jakarta critical spares".to_string(),
+        resource: "stock".to_string(),
+        filters: vec![filter("warehouse_id", "1"), filter("category", "Safety Equipment")],
+        sort_by: Some("quantity_available".to_string()),
+        sort_order: Some("ASC".to_string()),
+        columns: vec!["item_code".to_string(), "quantity_available".to_string()],
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn views_are_kept_per_user(pool: PgPool) {
+    let repo = ViewRepository::new(pool);
+    let saved = repo.create("planner@lsf.co.id", critical_spares()).await.unwrap();
+    assert_eq!(saved.filters, vec![filter("category", "Safety Equipment"), filter("warehouse_id", "1")]);
+
+    assert_eq!(repo.list("planner@lsf.co.id", Some("stock")).await.unwrap().len(), 1);
+    assert!(repo.list("planner@lsf.co.id", Some("orders")).await.unwrap().is_empty());
+    assert!(repo.list("buyer@lsf.co.id", None).await.unwrap().is_empty());
+    assert!(repo.get("buyer@lsf.co.id", saved.view.view_id).await.unwrap().is_none());
+    assert!(!repo.delete("buyer@lsf.co.id", saved.view.view_id).await.unwrap());
+    assert!(repo.name_exists("planner@lsf.co.id", "stock", "Jakarta critical spares").await.unwrap());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn update_replaces_filters_only_when_given(pool: PgPool) {
+    let repo = ViewRepository::new(pool);
+    let saved = repo.create("planner@lsf.co.id", critical_spares()).await.unwrap();
+    let id = saved.view.view_id;
+
+    let rename = UpdateSavedView { view_name: Some("Spares".to_string()), ..Default::default() };
+    let renamed = repo.update("planner@lsf.co.id", id, rename).await.unwrap().unwrap();
+    assert_eq!(renamed.view.view_name, "Spares");
+    assert_eq!(renamed.filters.len(), 2);
+
+    let changes = UpdateSavedView { filters: Some(vec![filter("warehouse_id", "2")]), ..Default::default() };
+    let refiltered = repo.update("planner@lsf.co.id", id, changes).await.unwrap().unwrap();
+    assert_eq!(refiltered.filters, vec![filter("warehouse_id", "2")]);
+    assert_eq!(refiltered.view.sort_by.as_deref(), Some("quantity_available"));
+
+    let twice = UpdateSavedView {
+        filters: Some(vec![filter("status", "OPEN"), filter("status", "SHIPPED")]),
+        ..Default::default()
+    };
+    let err = repo.update("planner@lsf.co.id", id, twice).await.unwrap_err();
+    assert!(err.downcast_ref::<RuleViolation>().is_some());
+    assert!(repo.delete("planner@lsf.co.id", id).await.unwrap());
+}
@@ -0,0 +1,141 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::{DateTime, Duration, Utc};
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+fn feed(code: &str, warehouse_id: Option<i32>, item_ids: Vec<i32>) -> CreateVmiFeed {
+    CreateVmiFeed {
+        supplier_code: code.to_string(),
+        supplier_name: format!("{} Supplies", code),
+        transport: VMI_TRANSPORT_API.to_string(),
+        destination: "https://supplier.example/vmi".to_string(),
+        api_key: None,
+        frequency_minutes: 60,
+        warehouse_id,
+        item_ids,
+    }
+}
+
+async fn issue(pool: &PgPool, item_id: i32, quantity: i64) {
+    let mut tx = pool.begin().await.unwrap();
+    post_movement(
+        &mut tx,
+        NewMovement {
+            item_id,
+            warehouse_id: 1,
+            movement_type: "SHIPMENT".to_string(),
+            quantity: Decimal::from(-quantity),
+            catch_weight_kg: None,
+            lot_number: None,
+            unit_cost: None,
+            stock_status: STOCK_AVAILABLE,
+            consignor_id: None,
+            reference_type: None,
+            reference_number: None,
+            notes: None,
+            created_by: Some(1),
+        },
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn snapshot_reports_stock_and_consumption_since_last_delivery(pool: PgPool) {
+    let repo = VmiRepository::new(pool.clone());
+    let detail = repo.create(feed("ACME", None, vec![2, 4])).await.unwrap();
+    assert_eq!(detail.item_ids, vec![2, 4]);
+
+    issue(&pool, 2, 30).await;
+    let snapshot = repo.snapshot(&detail.feed, Utc::now()).await.unwrap();
+    // Item 2 is only stocked in warehouse 1 and item 4 only in warehouse 2
+    assert_eq!(snapshot.lines.len(), 2);
+    let item2 = snapshot.lines.iter().find(|line| line.item_id == 2).unwrap();
+    assert_eq!(item2.warehouse_id, 1);
+    assert_eq!(item2.quantity_on_hand, Decimal::from(970));
+    assert_eq!(item2.quantity_available, Decimal::from(870));
+    assert_eq!(item2.quantity_consumed, Decimal::from(30));
+
+    let run = repo.record_run(&detail.feed, &snapshot, Ok(None)).await.unwrap();
+    assert_eq!(run.status, VMI_RUN_SENT);
+    assert_eq!(run.line_count, 2);
+
+    // The next feed only reports what was consumed after the delivered one
+    issue(&pool, 2, 5).await;
+    let sent = repo.get_by_id(detail.feed.feed_id).await.unwrap().unwrap().feed;
+    assert_eq!(sent.last_sent_at, Some(run.period_end));
+    let next = repo.snapshot(&sent, Utc::now()).await.unwrap();
+    assert_eq!(next.period_start, run.period_end);
+    let item2 = next.lines.iter().find(|line| line.item_id == 2).unwrap();
+    assert_eq!(item2.quantity_consumed, Decimal::from(5));
+
+    // A single-warehouse feed lists its items there even without stock
+    let single = repo.create(feed("BETA", Some(2), vec![2])).await.unwrap();
+    let snapshot = repo.snapshot(&single.feed, Utc::now()).await.unwrap();
+    assert_eq!(snapshot.lines.len(), 1);
+    assert_eq!(snapshot.lines[0].warehouse_id, 2);
+    assert_eq!(snapshot.lines[0].quantity_on_hand, Decimal::ZERO);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn due_feeds_are_claimed_once_and_failures_retry_early(pool: PgPool) {
+    let repo = VmiRepository::new(pool.clone());
+    let detail = repo.create(feed("ACME", None, vec![2])).await.unwrap();
+    let inactive = repo.create(feed("IDLE", None, vec![2])).await.unwrap();
+    repo.update(inactive.feed.feed_id, UpdateVmiFeed { is_active: Some(false), ..Default::default() })
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Postgres keeps microseconds
+    let now = DateTime::from_timestamp_micros(Utc::now().timestamp_micros()).unwrap();
+    let claimed = repo.claim_due(now).await.unwrap();
+    assert_eq!(claimed.len(), 1);
+    assert_eq!(claimed[0].feed_id, detail.feed.feed_id);
+    assert!(claimed[0].next_run_at >= now + Duration::minutes(60));
+    assert!(repo.claim_due(now).await.unwrap().is_empty());
+
+    let snapshot = repo.snapshot(&claimed[0], now).await.unwrap();
+    let run = repo.record_run(&claimed[0], &snapshot, Err("connection refused".to_string())).await.unwrap();
+    assert_eq!(run.status, VMI_RUN_FAILED);
+    assert_eq!(run.error.as_deref(), Some("connection refused"));
+
+    let feed = repo.get_by_id(detail.feed.feed_id).await.unwrap().unwrap().feed;
+    assert_eq!(feed.last_sent_at, None);
+    assert!(feed.next_run_at <= Utc::now() + Duration::minutes(VMI_RETRY_MINUTES as i64));
+    assert_eq!(repo.runs(feed.feed_id, KeysetQuery::default()).await.unwrap().data.len(), 1);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn update_replaces_items_and_checks_destination(pool: PgPool) {
+    let repo = VmiRepository::new(pool.clone());
+    let detail = repo.create(feed("ACME", None, vec![2])).await.unwrap();
+
+    let updated = repo
+        .update(
+            detail.feed.feed_id,
+            UpdateVmiFeed { item_ids: Some(vec![1, 3]), frequency_minutes: Some(30), ..Default::default() },
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.item_ids, vec![1, 3]);
+    assert_eq!(updated.feed.frequency_minutes, 30);
+
+    let error = repo
+        .update(
+            detail.feed.feed_id,
+            UpdateVmiFeed { destination: Some("sftp://vmi@supplier.example/in".to_string()), ..Default::default() },
+        )
+        .await
+        .unwrap_err();
+    assert!(error.downcast_ref::<RuleViolation>().is_some(), "{}", error);
+
+    let error = repo
+        .update(detail.feed.feed_id, UpdateVmiFeed { item_ids: Some(vec![9999]), ..Default::default() })
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("item 9999"), "{}", error);
+}
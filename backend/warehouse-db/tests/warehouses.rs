@@ -0,0 +1,257 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::validator::Validate;
+use warehouse_models::*;
+
+fn new_warehouse(code: &str) -> CreateWarehouse {
+    CreateWarehouse {
+        warehouse_code: code.to_string(),
+        warehouse_name: format!("{} Warehouse", code),
+        warehouse_type: None,
+        address: None,
+        city: Some("Bandung".to_string()),
+        state: Some("West Java".to_string()),
+        postal_code: None,
+        country: None,
+        email: None,
+        phone: None,
+        manager_user_id: None,
+        timezone: None,
+    }
+}
+
+fn page(page: i64, limit: i64) -> PaginationQuery {
+    PaginationQuery {
+        page: Some(page),
+        limit: Some(limit),
+        ..Default::default()
+    }
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("inactive_records"))]
+async fn list_excludes_soft_deleted(pool: PgPool) {
+    let repo = WarehouseRepository::new(pool);
+
+    let result = repo.list(PaginationQuery::default()).await.unwrap();
+
+    let codes: Vec<_> = result.data.iter().map(|w| w.warehouse_code.as_str()).collect();
+    assert_eq!(codes, ["WH001", "WH002"]);
+    assert_eq!(result.pagination.total, Some(2));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn list_clamps_out_of_range_parameters(pool: PgPool) {
+    let repo = WarehouseRepository::new(pool);
+
+    let result = repo.list(page(0, 0)).await.unwrap();
+    assert_eq!(result.pagination.page, 1);
+    assert_eq!(result.pagination.limit, 1);
+    assert_eq!(result.data.len(), 1);
+    assert!(result.pagination.has_next);
+
+    let result = repo.list(page(1, 10_000)).await.unwrap();
+    assert_eq!(result.pagination.limit, 100);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn list_past_last_page_is_empty(pool: PgPool) {
+    let repo = WarehouseRepository::new(pool);
+
+    let result = repo.list(page(5, 1)).await.unwrap();
+
+    assert!(result.data.is_empty());
+    assert!(!result.pagination.has_next);
+    assert!(result.pagination.has_prev);
+    assert_eq!(result.pagination.total_pages, Some(2));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn list_without_count_probes_next_page(pool: PgPool) {
+    let repo = WarehouseRepository::new(pool);
+    let query = |n| PaginationQuery {
+        count: Some(CountMode::Skip),
+        ..page(n, 1)
+    };
+
+    let first = repo.list(query(1)).await.unwrap();
+    assert_eq!(first.data.len(), 1);
+    assert_eq!(first.pagination.total, None);
+    assert!(first.pagination.has_next);
+
+    let last = repo.list(query(2)).await.unwrap();
+    assert_eq!(last.data.len(), 1);
+    assert!(!last.pagination.has_next);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn list_with_estimate_flags_total(pool: PgPool) {
+    let repo = WarehouseRepository::new(pool);
+
+    let result = repo
+        .list(PaginationQuery {
+            count: Some(CountMode::Estimate),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert!(result.pagination.total_estimated);
+    assert_eq!(result.data.len(), 2);
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("inactive_records"))]
+async fn get_by_id_ignores_inactive_and_missing(pool: PgPool) {
+    let repo = WarehouseRepository::new(pool);
+
+    assert_eq!(repo.get_by_id(1).await.unwrap().unwrap().warehouse_code, "WH001");
+    assert!(repo.get_by_id(900).await.unwrap().is_none());
+    assert!(repo.get_by_id(12345).await.unwrap().is_none());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn create_defaults_country(pool: PgPool) {
+    let repo = WarehouseRepository::new(pool);
+
+    let created = repo.create(new_warehouse("WH010")).await.unwrap();
+
+    assert_eq!(created.country.as_deref(), Some("Indonesia"));
+    assert!(created.is_active);
+    assert_eq!(repo.get_by_id(created.warehouse_id).await.unwrap().unwrap().warehouse_name, "WH010 Warehouse");
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn update_changes_only_given_fields(pool: PgPool) {
+    let repo = WarehouseRepository::new(pool);
+
+    let updated = repo
+        .update(
+            1,
+            UpdateWarehouse {
+                warehouse_name: Some("Jakarta Hub".to_string()),
+                warehouse_type: None,
+                address: None,
+                city: None,
+                state: None,
+                postal_code: None,
+                country: None,
+                email: None,
+                phone: None,
+                manager_user_id: None,
+                timezone: None,
+            },
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(updated.warehouse_name, "Jakarta Hub");
+    assert_eq!(updated.city.as_deref(), Some("Jakarta"));
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("inactive_records"))]
+async fn update_skips_inactive(pool: PgPool) {
+    let repo = WarehouseRepository::new(pool);
+    let changes = UpdateWarehouse {
+        warehouse_name: Some("Reopened".to_string()),
+        warehouse_type: None,
+        address: None,
+        city: None,
+        state: None,
+        postal_code: None,
+        country: None,
+        email: None,
+        phone: None,
+        manager_user_id: None,
+        timezone: None,
+    };
+
+    assert!(repo.update(900, changes).await.unwrap().is_none());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn delete_is_soft_and_single_shot(pool: PgPool) {
+    let repo = WarehouseRepository::new(pool.clone());
+
+    assert!(repo.delete(2).await.unwrap());
+    assert!(!repo.delete(2).await.unwrap());
+    assert!(repo.get_by_id(2).await.unwrap().is_none());
+
+    let still_stored: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM warehouse.warehouses WHERE warehouse_id = 2)")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(still_stored);
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("inactive_records"))]
+async fn code_exists_respects_exclusion_and_soft_delete(pool: PgPool) {
+    let repo = WarehouseRepository::new(pool);
+
+    assert!(repo.code_exists("WH001", None).await.unwrap());
+    assert!(!repo.code_exists("WH001", Some(1)).await.unwrap());
+    assert!(repo.code_exists("WH001", Some(2)).await.unwrap());
+    assert!(!repo.code_exists("WH900", None).await.unwrap());
+    assert!(!repo.code_exists("NOPE", None).await.unwrap());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn concurrent_creates_with_same_code_admit_one(pool: PgPool) {
+    let repo = WarehouseRepository::new(pool);
+
+    // Both callers pass the code_exists pre-check; the unique constraint must stop the second
+    assert!(!repo.code_exists("WH777", None).await.unwrap());
+    let (a, b) = tokio::join!(repo.create(new_warehouse("WH777")), repo.create(new_warehouse("WH777")));
+
+    assert_eq!([a.is_ok(), b.is_ok()].iter().filter(|ok| **ok).count(), 1);
+}
+
+#[test]
+fn business_formats_fail_on_their_own_fields() {
+    let mut warehouse = new_warehouse("WH-JKT-02");
+    warehouse.phone = Some("0812-3456-7890".to_string());
+    warehouse.postal_code = Some("40115".to_string());
+    warehouse.timezone = Some("Asia/Jakarta".to_string());
+    assert!(warehouse.validate().is_ok());
+
+    let mut bad = new_warehouse("wh_1");
+    bad.phone = Some("call me".to_string());
+    bad.timezone = Some("Jakarta".to_string());
+    let errors = bad.validate().unwrap_err();
+    let fields = errors.field_errors();
+    assert_eq!(fields["warehouse_code"][0].code, "invalid_warehouse_code");
+    assert_eq!(fields["phone"][0].code, "invalid_phone");
+    assert_eq!(fields["timezone"][0].code, "invalid_timezone");
+
+    let mut abroad = new_warehouse("SG01");
+    abroad.country = Some("Singapore".to_string());
+    abroad.postal_code = Some("40115".to_string());
+    assert!(abroad.validate().unwrap_err().to_string().contains("postal_code does not fit"));
+    abroad.postal_code = Some("018956".to_string());
+    assert!(abroad.validate().is_ok());
+}
+
+#[test]
+fn phone_numbers_normalize_to_e164() {
+    assert_eq!(normalize_phone("0812-3456-7890").as_deref(), Some("+6281234567890"));
+    assert_eq!(normalize_phone("+62 21 555 0100").as_deref(), Some("+62215550100"));
+    assert_eq!(normalize_phone("0065 6123 4567").as_deref(), Some("+6561234567"));
+    assert_eq!(normalize_phone("(021) 555-0100").as_deref(), Some("+62215550100"));
+    assert_eq!(normalize_phone("812345678"), None);
+    assert_eq!(normalize_phone("+0 1234 5678"), None);
+    assert_eq!(normalize_phone("+62 812 3456 7890 1234"), None);
+    assert_eq!(normalize_phone("0812-3456-7890 ext 2"), None);
+}
+
+#[test]
+fn postal_codes_follow_the_country_layout() {
+    assert!(postal_code_fits(None, "12940"));
+    assert!(!postal_code_fits(None, "1294"));
+    assert!(postal_code_fits(Some("US"), "94105-1804"));
+    assert!(postal_code_fits(Some("netherlands"), "1012 AB"));
+    assert!(postal_code_fits(Some("Japan"), "100-0001"));
+    assert!(!postal_code_fits(Some("Canada"), "K1A0B"));
+    // No known layout: any short code of letters and digits
+    assert!(postal_code_fits(Some("United Kingdom"), "SW1A 1AA"));
+    assert!(!postal_code_fits(Some("United Kingdom"), "SW1A/1AA"));
+}
@@ -0,0 +1,185 @@
+use sqlx::PgPool;
+use warehouse_db::*;
+use warehouse_models::chrono::NaiveDate;
+use warehouse_models::rust_decimal::Decimal;
+use warehouse_models::*;
+
+async fn order(pool: &PgPool, carrier: Option<&str>, ship_by: Option<&str>, lines: &[(i32, i64)]) -> OrderDetail {
+    OrderRepository::new(pool.clone())
+        .create(CreateOrder {
+            warehouse_id: 1,
+            customer_name: "PT Karya Beton".to_string(),
+            customer_reference: None,
+            ship_to_name: None,
+            ship_to_address: None,
+            ship_to_city: None,
+            ship_to_state: None,
+            ship_to_postal_code: None,
+            ship_to_country: None,
+            requested_ship_date: ship_by.map(|d| d.parse().unwrap()),
+            carrier_code: carrier.map(str::to_string),
+            dropship_supplier_code: None,
+            lines: lines
+                .iter()
+                .map(|&(item_id, quantity)| CreateOrderLine {
+                    item_id,
+                    quantity_ordered: Decimal::from(quantity),
+                })
+                .collect(),
+        })
+        .await
+        .unwrap()
+}
+
+fn criteria() -> CreateWave {
+    CreateWave {
+        warehouse_id: 1,
+        zone_id: None,
+        carrier_code: None,
+        cutoff_date: None,
+        order_ids: None,
+        max_orders: None,
+    }
+}
+
+/// Zone A (walked first) holds helmets, zone B rebar
+async fn pick_path(pool: &PgPool) -> (Zone, Zone) {
+    let locations = LocationRepository::new(pool.clone());
+    let zone = |code: &str, sequence| CreateZone {
+        zone_code: code.to_string(),
+        zone_name: format!("Zone {}", code),
+        pick_sequence: sequence,
+    };
+    let b = locations.create_zone(1, zone("B", 2)).await.unwrap();
+    let a = locations.create_zone(1, zone("A", 1)).await.unwrap();
+    let face = |code: &str, item_id| CreateLocation {
+        location_code: code.to_string(),
+        pick_sequence: 1,
+        item_id: Some(item_id),
+    };
+    locations.create_location(b.zone_id, face("B-01", 2)).await.unwrap();
+    locations.create_location(a.zone_id, face("A-07", 3)).await.unwrap();
+    (a, b)
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn create_selects_by_carrier_and_cutoff(pool: PgPool) {
+    let repo = WaveRepository::new(pool.clone());
+    let due = order(&pool, Some("JNE"), Some("2025-10-10"), &[(2, 5)]).await;
+    order(&pool, Some("JNE"), Some("2025-10-20"), &[(2, 5)]).await;
+    order(&pool, Some("SICEPAT"), Some("2025-10-10"), &[(2, 5)]).await;
+
+    let wave = repo
+        .create(CreateWave {
+            carrier_code: Some("jne".to_string()),
+            cutoff_date: NaiveDate::from_ymd_opt(2025, 10, 12),
+            ..criteria()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(wave.wave.status, WAVE_PLANNED);
+    assert_eq!(wave.order_ids, [due.order.order_id]);
+
+    // Already waved orders are not picked up twice
+    let again = repo.create(CreateWave { carrier_code: Some("JNE".to_string()), ..criteria() }).await.unwrap();
+    assert!(!again.order_ids.contains(&due.order.order_id));
+    let none = repo.create(CreateWave { carrier_code: Some("JNE".to_string()), ..criteria() }).await.unwrap_err();
+    assert!(none.downcast_ref::<RuleViolation>().is_some());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn zone_wave_only_takes_orders_pickable_in_zone(pool: PgPool) {
+    let (a, _) = pick_path(&pool).await;
+    let helmets = order(&pool, None, None, &[(3, 2)]).await;
+    order(&pool, None, None, &[(3, 2), (2, 1)]).await;
+
+    let wave = WaveRepository::new(pool)
+        .create(CreateWave { zone_id: Some(a.zone_id), ..criteria() })
+        .await
+        .unwrap();
+
+    assert_eq!(wave.order_ids, [helmets.order.order_id]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn release_consolidates_tasks_along_pick_path(pool: PgPool) {
+    pick_path(&pool).await;
+    let repo = WaveRepository::new(pool.clone());
+    order(&pool, None, None, &[(2, 10), (3, 1)]).await;
+    order(&pool, None, None, &[(2, 5), (4, 1)]).await;
+    let planned = repo.create(criteria()).await.unwrap();
+
+    let released = repo.release(planned.wave.wave_id).await.unwrap().unwrap();
+
+    assert_eq!(released.wave.status, WAVE_RELEASED);
+    let tasks: Vec<_> = released.tasks.iter().map(|t| (t.sequence, t.item_id, t.quantity)).collect();
+    // Helmets in zone A, then rebar in zone B, then the mixer with no pick face
+    assert_eq!(
+        tasks,
+        [(1, 3, Decimal::from(1)), (2, 2, Decimal::from(15)), (3, 4, Decimal::from(1))]
+    );
+    assert!(released.tasks[2].location_id.is_none());
+    assert!(repo.release(planned.wave.wave_id).await.is_err());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn picked_goods_split_into_order_cartons(pool: PgPool) {
+    let repo = WaveRepository::new(pool.clone());
+    let first = order(&pool, None, None, &[(2, 10)]).await;
+    let second = order(&pool, None, None, &[(2, 5)]).await;
+    let wave_id = repo.create(criteria()).await.unwrap().wave.wave_id;
+    let released = repo.release(wave_id).await.unwrap().unwrap();
+    let task_id = released.tasks[0].task_id;
+
+    // Short pick: only 12 of 15 found
+    let picked = repo
+        .confirm_pick(wave_id, task_id, ConfirmPick { quantity_picked: Decimal::from(12) })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(picked.tasks[0].status, PICK_SHORT);
+    assert_eq!(picked.wave.status, WAVE_PICKED);
+
+    let pack = |order: &OrderDetail, quantity| PackCarton {
+        order_id: order.order.order_id,
+        box_id: None,
+        lines: vec![PackCartonLine {
+            line_id: order.lines[0].line_id,
+            quantity: Decimal::from(quantity),
+        }],
+    };
+    let (_, carton) = repo.pack(wave_id, pack(&first, 10)).await.unwrap().unwrap();
+    assert_eq!(carton.carton.order_id, first.order.order_id);
+    assert_eq!(carton.carton.carton_number, format!("CTN-{:06}", carton.carton.carton_id));
+
+    // Only 2 picked units are left for the second order
+    let over = repo.pack(wave_id, pack(&second, 5)).await.unwrap_err();
+    assert!(over.downcast_ref::<RuleViolation>().is_some());
+
+    let (done, _) = repo.pack(wave_id, pack(&second, 2)).await.unwrap().unwrap();
+    assert_eq!(done.wave.status, WAVE_COMPLETED);
+    assert_eq!(done.cartons.len(), 2);
+
+    // The short-picked order leaves the wave so another wave can pick the rest
+    let orders = OrderRepository::new(pool);
+    let first = orders.get_by_id(first.order.order_id).await.unwrap().unwrap();
+    let second = orders.get_by_id(second.order.order_id).await.unwrap().unwrap();
+    assert_eq!(first.order.wave_id, Some(wave_id));
+    assert_eq!(second.order.wave_id, None);
+    assert_eq!(second.lines[0].quantity_packed, Decimal::from(2));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn cancel_frees_orders(pool: PgPool) {
+    let repo = WaveRepository::new(pool.clone());
+    let open = order(&pool, None, None, &[(2, 1)]).await;
+    let wave_id = repo.create(criteria()).await.unwrap().wave.wave_id;
+
+    let cancelled = repo.cancel(wave_id).await.unwrap().unwrap();
+
+    assert_eq!(cancelled.wave.status, WAVE_CANCELLED);
+    assert!(cancelled.order_ids.is_empty());
+    let rewaved = repo.create(criteria()).await.unwrap();
+    assert_eq!(rewaved.order_ids, [open.order.order_id]);
+}
@@ -0,0 +1,69 @@
+//! API keys for machine integrations, each limited to a set of scopes. A key is shown
+//! once, when it is issued; only its hash is kept.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+/// Start of every integration key, so a key is recognisable in configuration and logs
+pub const API_KEY_PREFIX: &str = "wmk_";
+
+/// Reads only
+pub const SCOPE_READ_ONLY: &str = "READ_ONLY";
+/// Reads, and changes to stock and items
+pub const SCOPE_STOCK_WRITE: &str = "STOCK_WRITE";
+/// Everything
+pub const SCOPE_ADMIN: &str = "ADMIN";
+pub const API_KEY_SCOPES: &[&str] = &[SCOPE_READ_ONLY, SCOPE_STOCK_WRITE, SCOPE_ADMIN];
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub key_id: i32,
+    pub name: String,
+    /// First characters of the key, to tell keys apart
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub created_by: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// Whether the key may do what `scope` allows; an admin key may do everything
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|held| held == scope || held == SCOPE_ADMIN)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateApiKey {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(min = 1), custom(function = "validate_scopes"))]
+    pub scopes: Vec<String>,
+}
+
+/// A newly issued key; `api_key` is not retrievable afterwards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuedApiKey {
+    #[serde(flatten)]
+    pub key: ApiKey,
+    pub api_key: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ApiKeyFilter {
+    /// Include revoked keys
+    #[serde(default)]
+    pub include_revoked: bool,
+}
+
+fn validate_scopes(scopes: &[String]) -> Result<(), ValidationError> {
+    if scopes.iter().all(|scope| API_KEY_SCOPES.contains(&scope.as_str())) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_scope"))
+    }
+}
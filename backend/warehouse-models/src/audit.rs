@@ -0,0 +1,75 @@
+//! The audit log: every create, update and delete of an audited entity, with who made it
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::JsonValue;
+use sqlx::FromRow;
+
+pub const AUDIT_CREATE: &str = "CREATE";
+pub const AUDIT_UPDATE: &str = "UPDATE";
+pub const AUDIT_DELETE: &str = "DELETE";
+pub const AUDIT_ACTIONS: &[&str] = &[AUDIT_CREATE, AUDIT_UPDATE, AUDIT_DELETE];
+
+/// Entities whose tables record their changes in the audit log
+pub const AUDITED_ENTITIES: &[&str] = &[
+    "api_key",
+    "client",
+    "consignor",
+    "driver",
+    "ecommerce_channel",
+    "erp_connector",
+    "event_consumer",
+    "gl_account",
+    "integration_channel",
+    "item",
+    "loan",
+    "location",
+    "lpn",
+    "negative_stock_policy",
+    "order",
+    "print_template",
+    "printer",
+    "project",
+    "purchase_order",
+    "recall",
+    "receipt",
+    "repair",
+    "report_schedule",
+    "requisition",
+    "reservation",
+    "return",
+    "returnable_type",
+    "shipment",
+    "station",
+    "tenant",
+    "transfer",
+    "user",
+    "vehicle",
+    "vmi_feed",
+    "warehouse",
+    "zone",
+];
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub audit_id: i64,
+    pub entity: String,
+    pub entity_id: String,
+    pub action: String,
+    /// The row before the change; for updates only the columns that changed
+    pub old_values: Option<JsonValue>,
+    /// The row after the change; for updates only the columns that changed
+    pub new_values: Option<JsonValue>,
+    /// `user:<id>` or `api_key:<id>`; None for changes made by the system itself
+    pub actor: Option<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuditFilter {
+    pub entity: Option<String>,
+    /// The entity's id; needs `entity`
+    pub id: Option<String>,
+    pub action: Option<String>,
+    pub actor: Option<String>,
+}
@@ -0,0 +1,65 @@
+//! Available-to-promise: how much of an item a warehouse can promise an order by a date,
+//! from its free stock and the supply on its way in
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+// Where inbound supply comes from
+pub const SUPPLY_PURCHASE_ORDER: &str = "PURCHASE_ORDER";
+pub const SUPPLY_TRANSFER: &str = "TRANSFER";
+
+#[derive(Debug, Deserialize)]
+pub struct AvailabilityQuery {
+    pub warehouse_id: i32,
+    /// Quantity to promise; without it only the quantities are reported
+    pub qty: Option<Decimal>,
+    /// Date the order needs the quantity by; defaults to today
+    pub date: Option<NaiveDate>,
+}
+
+/// What a warehouse holds of an item and what is already spoken for, every owner's
+#[derive(Debug, Clone, Default, FromRow, Serialize, Deserialize)]
+pub struct StockPosition {
+    pub on_hand: Decimal,
+    pub reserved: Decimal,
+    /// Allocated to open orders and not shipped yet
+    pub allocated: Decimal,
+    /// On transfers planned out of the warehouse and not dispatched yet
+    pub transfers_out: Decimal,
+}
+
+/// Supply on its way into the warehouse
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct InboundSupply {
+    /// PURCHASE_ORDER or TRANSFER
+    pub source: String,
+    pub reference_number: String,
+    /// None when nobody knows yet; transfers in transit have already left their source
+    pub expected_date: Option<NaiveDate>,
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemAvailability {
+    pub item_id: i32,
+    pub warehouse_id: i32,
+    pub date: NaiveDate,
+    #[serde(flatten)]
+    pub position: StockPosition,
+    /// On hand less what is reserved, allocated and planned out
+    pub available_now: Decimal,
+    /// Transfers in transit to the warehouse
+    pub in_transit: Decimal,
+    /// Purchase orders expected by the date
+    pub inbound_by_date: Decimal,
+    pub available_to_promise: Decimal,
+    pub quantity_requested: Option<Decimal>,
+    /// Whether the quantity requested can be promised by the date
+    pub can_promise: Option<bool>,
+    /// Earliest date the quantity requested can be promised, None when the known supply
+    /// never covers it
+    pub promise_date: Option<NaiveDate>,
+    pub inbound: Vec<InboundSupply>,
+}
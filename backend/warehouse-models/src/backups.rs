@@ -0,0 +1,90 @@
+//! Logical backups of warehouse data to object storage
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+pub const BACKUP_RUNNING: &str = "RUNNING";
+pub const BACKUP_COMPLETED: &str = "COMPLETED";
+pub const BACKUP_FAILED: &str = "FAILED";
+
+/// `format` of every backup manifest
+pub const BACKUP_FORMAT: &str = "warehouse-backup";
+
+/// Bumped whenever the archive layout changes; restores refuse versions they do not know
+pub const BACKUP_FORMAT_VERSION: i32 = 1;
+
+/// Tables exported from each schema, parents before children
+pub const BACKUP_TABLES: [&str; 4] = ["warehouses", "items", "stock_inventory", "stock_movements"];
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Backup {
+    pub backup_id: i64,
+    pub archive_key: String,
+    pub format_version: i32,
+    pub schema_version: Option<i64>,
+    pub schemas: Vec<String>,
+    pub status: String,
+    pub rows_exported: i64,
+    pub size_bytes: Option<i64>,
+    pub error: Option<String>,
+    pub requested_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// First line of an archive. Each further line is one row:
+/// `{"schema": "...", "table": "...", "row": {...}}`, tables in `BACKUP_TABLES` order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format: String,
+    pub format_version: i32,
+    pub backup_id: i64,
+    /// Last migration applied to the exported database
+    pub schema_version: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub tables: Vec<BackupTableCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupTableCount {
+    pub schema: String,
+    pub table: String,
+    pub rows: i64,
+}
+
+/// Outcome of restoring an archive. Nothing is committed unless every table loaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreReport {
+    pub backup_id: i64,
+    pub format_version: i32,
+    pub tables: Vec<RestoredTable>,
+    pub committed: bool,
+}
+
+impl RestoreReport {
+    pub fn rows_restored(&self) -> i64 {
+        self.tables.iter().map(|table| table.rows_restored).sum()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoredTable {
+    /// Schema the rows were exported from
+    pub source_schema: String,
+    /// Schema they were restored into
+    pub schema: String,
+    pub table: String,
+    /// As recorded in the manifest
+    pub rows_expected: i64,
+    pub rows_read: i64,
+    pub rows_restored: i64,
+    pub violations: Vec<ReferenceViolation>,
+}
+
+/// Rows whose foreign key has no match in the restored or existing data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceViolation {
+    pub constraint_name: String,
+    pub references: String,
+    pub rows: i64,
+}
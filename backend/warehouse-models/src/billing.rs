@@ -0,0 +1,205 @@
+//! 3PL client billing: billable events captured per client and priced monthly against the
+//! client's rate card
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::validate_positive;
+
+/// Stock held, charged per unit-day from a daily snapshot
+pub const BILLING_STORAGE: &str = "STORAGE";
+/// Units received into stock
+pub const BILLING_RECEIPT: &str = "RECEIPT";
+/// Units picked and shipped out of stock
+pub const BILLING_PICK: &str = "PICK";
+/// A value-added service (kitting, labelling, ...) recorded against its own charge code
+pub const BILLING_SERVICE: &str = "SERVICE";
+
+/// Charge codes captured automatically; everything else on a rate card is a service
+pub const BILLING_ACTIVITY_CODES: &[&str] = &[BILLING_STORAGE, BILLING_RECEIPT, BILLING_PICK];
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct BillingClient {
+    pub client_id: i32,
+    pub client_code: String,
+    pub client_name: String,
+    pub currency: String,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateBillingClient {
+    #[validate(length(min = 1, max = 30))]
+    pub client_code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub client_name: String,
+    /// ISO 4217 code; defaults to IDR
+    #[validate(length(equal = 3))]
+    pub currency: Option<String>,
+}
+
+/// Put items in the client's ownership; their activity is billed from now on
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct AssignClientItems {
+    #[validate(length(min = 1))]
+    pub item_ids: Vec<i32>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ClientItem {
+    pub item_id: i32,
+    pub item_code: String,
+    pub assigned_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct BillingRate {
+    pub client_id: i32,
+    pub charge_code: String,
+    pub description: Option<String>,
+    pub unit_rate: Decimal,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetBillingRates {
+    #[validate(length(min = 1), nested)]
+    pub rates: Vec<SetBillingRate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetBillingRate {
+    #[validate(length(min = 1, max = 30))]
+    pub charge_code: String,
+    #[validate(length(max = 255))]
+    pub description: Option<String>,
+    #[validate(custom(function = "validate_not_negative"))]
+    pub unit_rate: Decimal,
+}
+
+fn validate_not_negative(rate: &Decimal) -> Result<(), ValidationError> {
+    if rate.is_sign_negative() && !rate.is_zero() {
+        return Err(ValidationError::new("must_not_be_negative"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct BillableEvent {
+    pub event_id: i64,
+    pub client_id: i32,
+    pub event_type: String,
+    pub charge_code: String,
+    pub warehouse_id: i32,
+    pub item_id: Option<i32>,
+    pub quantity: Decimal,
+    pub event_date: NaiveDate,
+    pub movement_id: Option<i64>,
+    pub notes: Option<String>,
+    pub invoice_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
+}
+
+/// Record a value-added service performed for the client
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RecordService {
+    pub warehouse_id: i32,
+    /// A service charge code on the client's rate card
+    #[validate(length(min = 1, max = 30))]
+    pub charge_code: String,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+    pub item_id: Option<i32>,
+    /// Defaults to today
+    pub event_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BillableEventFilter {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub event_type: Option<String>,
+    /// Only events not yet on an invoice
+    pub unbilled: Option<bool>,
+}
+
+/// Events added by one capture pass
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BillingCapture {
+    pub receipts: u64,
+    pub picks: u64,
+    pub storage: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateInvoice {
+    /// First day of the calendar month to invoice; the month must have ended
+    #[validate(custom(function = "validate_month_start"))]
+    pub month: NaiveDate,
+}
+
+fn validate_month_start(month: &NaiveDate) -> Result<(), ValidationError> {
+    if month.day() != 1 {
+        return Err(ValidationError::new("must_be_first_of_month"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct BillingInvoice {
+    pub invoice_id: i32,
+    pub invoice_number: String,
+    pub client_id: i32,
+    pub period_start: NaiveDate,
+    /// Last day of the invoiced month
+    pub period_end: NaiveDate,
+    pub currency: String,
+    pub total_amount: Decimal,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct InvoiceLine {
+    pub line_id: i32,
+    pub invoice_id: i32,
+    pub charge_code: String,
+    pub description: Option<String>,
+    pub quantity: Decimal,
+    pub unit_rate: Decimal,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceDetail {
+    #[serde(flatten)]
+    pub invoice: BillingInvoice,
+    pub lines: Vec<InvoiceLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct InvoiceExportQuery {
+    #[validate(custom(function = "validate_month_start"))]
+    pub month: NaiveDate,
+}
+
+/// One invoice line in the monthly export for the accounting system
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct InvoiceExportLine {
+    pub invoice_number: String,
+    pub client_code: String,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub charge_code: String,
+    pub description: Option<String>,
+    pub quantity: Decimal,
+    pub unit_rate: Decimal,
+    pub amount: Decimal,
+    pub currency: String,
+}
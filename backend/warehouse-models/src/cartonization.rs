@@ -0,0 +1,104 @@
+//! Cartonization: a warehouse's shipping boxes and the cartons suggested for an order at
+//! packing time
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+use crate::validate_positive;
+
+/// Share of a box's inside volume that can actually be filled, leaving room for dunnage
+/// and the gaps between items
+pub const CARTON_FILL_PERCENT: i64 = 85;
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct BoxSize {
+    pub box_id: i32,
+    pub warehouse_id: i32,
+    pub box_code: String,
+    pub length_cm: Decimal,
+    pub width_cm: Decimal,
+    pub height_cm: Decimal,
+    /// Heaviest contents the box is rated for
+    pub max_weight_kg: Decimal,
+    pub tare_weight_kg: Decimal,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl BoxSize {
+    /// Inside volume in cubic centimetres
+    pub fn volume_cm3(&self) -> Decimal {
+        self.length_cm * self.width_cm * self.height_cm
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateBoxSize {
+    #[validate(length(min = 1, max = 30))]
+    pub box_code: String,
+    #[validate(custom(function = "validate_positive"))]
+    pub length_cm: Decimal,
+    #[validate(custom(function = "validate_positive"))]
+    pub width_cm: Decimal,
+    #[validate(custom(function = "validate_positive"))]
+    pub height_cm: Decimal,
+    #[validate(custom(function = "validate_positive"))]
+    pub max_weight_kg: Decimal,
+    #[serde(default)]
+    pub tare_weight_kg: Decimal,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateBoxSize {
+    #[validate(custom(function = "validate_positive"))]
+    pub max_weight_kg: Option<Decimal>,
+    pub tare_weight_kg: Option<Decimal>,
+    pub is_active: Option<bool>,
+}
+
+/// What is left to pack on an order line, with the item's unit measurements
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PackingLine {
+    pub line_id: i32,
+    pub item_id: i32,
+    pub item_code: String,
+    pub quantity: Decimal,
+    pub weight_kg: Option<Decimal>,
+    pub length_cm: Option<Decimal>,
+    pub width_cm: Option<Decimal>,
+    pub height_cm: Option<Decimal>,
+    pub volume_cbm: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CartonPlan {
+    pub order_id: i32,
+    pub cartons: Vec<SuggestedCarton>,
+    /// Units too large or heavy for any box; they ship in their own packaging
+    pub unboxed: Vec<SuggestedCartonLine>,
+    /// Items planned without a weight or dimensions, so the plan may be off
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestedCarton {
+    pub box_id: i32,
+    pub box_code: String,
+    pub lines: Vec<SuggestedCartonLine>,
+    pub content_volume_cm3: Decimal,
+    pub content_weight_kg: Decimal,
+    /// Contents plus the box itself
+    pub gross_weight_kg: Decimal,
+    pub fill_percent: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuggestedCartonLine {
+    pub line_id: i32,
+    pub item_id: i32,
+    pub quantity: Decimal,
+}
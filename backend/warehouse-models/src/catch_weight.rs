@@ -0,0 +1,35 @@
+//! Catch-weight items: handled in pieces, sold by the actual weight captured at receipt
+//! and shipping
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+use crate::validate_positive;
+
+/// Makes an item catch-weight; every receipt and shipment of it records its actual weight
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CatchWeightProfile {
+    /// Expected weight of one piece
+    #[validate(custom(function = "validate_positive"))]
+    pub nominal_weight_kg: Decimal,
+    /// How far the average piece weight of a receipt or shipment line may be from nominal
+    #[validate(custom(function = "validate_tolerance"))]
+    pub tolerance_pct: Decimal,
+}
+
+impl CatchWeightProfile {
+    /// Lowest and highest weight accepted for `quantity` pieces
+    pub fn bounds(&self, quantity: Decimal) -> (Decimal, Decimal) {
+        let expected = self.nominal_weight_kg * quantity;
+        let margin = expected * self.tolerance_pct / Decimal::ONE_HUNDRED;
+        (expected - margin, expected + margin)
+    }
+}
+
+fn validate_tolerance(tolerance_pct: &Decimal) -> Result<(), ValidationError> {
+    if tolerance_pct.is_sign_negative() || *tolerance_pct >= Decimal::ONE_HUNDRED {
+        return Err(ValidationError::new("tolerance_out_of_range"));
+    }
+    Ok(())
+}
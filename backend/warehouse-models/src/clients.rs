@@ -0,0 +1,56 @@
+//! Client access for multi-owner (3PL) deployments: API keys that scope every query to
+//! the stock and movements of the client they were issued to
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+/// Issued keys start with this, followed by 40 random alphanumerics
+pub const CLIENT_KEY_PREFIX: &str = "wck_";
+
+/// An issued key; only its first characters are kept in the clear
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ClientApiKey {
+    pub key_id: i32,
+    pub client_id: i32,
+    pub key_prefix: String,
+    pub label: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct CreateClientApiKey {
+    #[validate(length(min = 1, max = 100))]
+    pub label: Option<String>,
+}
+
+/// A newly issued key; `api_key` is not retrievable afterwards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuedClientApiKey {
+    #[serde(flatten)]
+    pub key: ClientApiKey,
+    pub api_key: String,
+}
+
+/// A client's balance of one of its items in a warehouse
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ClientStock {
+    pub item_id: i32,
+    pub item_code: String,
+    pub item_name: String,
+    pub warehouse_id: i32,
+    pub quantity_on_hand: Decimal,
+    pub quantity_reserved: Decimal,
+    pub quantity_available: Decimal,
+    pub quantity_quarantined: Decimal,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ClientStockFilter {
+    pub warehouse_id: Option<i32>,
+    pub item_id: Option<i32>,
+}
@@ -0,0 +1,135 @@
+//! Consignment stock: supplier-owned inventory held in our warehouses, consumed into our
+//! own stock and billed back to the consignor through self-billing settlements
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+use crate::validate_positive;
+
+/// Movement type for ownership passing from a consignor to us
+pub const MOVEMENT_CONSIGNMENT_CONSUME: &str = "CONSIGNMENT_CONSUME";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Consignor {
+    pub consignor_id: i32,
+    pub consignor_code: String,
+    pub consignor_name: String,
+    pub currency: String,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateConsignor {
+    #[validate(length(min = 1, max = 30))]
+    pub consignor_code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub consignor_name: String,
+    /// ISO 4217 code; defaults to IDR
+    #[validate(length(equal = 3))]
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ConsignmentPrice {
+    pub consignor_id: i32,
+    pub item_id: i32,
+    pub unit_price: Decimal,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetConsignmentPrices {
+    #[validate(length(min = 1), nested)]
+    pub prices: Vec<SetConsignmentPrice>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetConsignmentPrice {
+    pub item_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub unit_price: Decimal,
+}
+
+/// Consignor-owned balance of an item in a warehouse
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ConsignmentStock {
+    pub item_id: i32,
+    pub item_code: String,
+    pub warehouse_id: i32,
+    pub quantity_on_hand: Decimal,
+    pub unit_price: Option<Decimal>,
+}
+
+/// Take consigned stock into our ownership outside of an issue, e.g. for a kit build
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ConsumeConsignment {
+    pub warehouse_id: i32,
+    pub item_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+    pub notes: Option<String>,
+    /// Client key for this consumption; a retry with the same key returns the original
+    /// movement
+    #[serde(default)]
+    #[validate(length(min = 1, max = 100))]
+    pub reference_id: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ConsumptionQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub warehouse_id: Option<i32>,
+}
+
+/// Consumed quantity of an item at one price over a period
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ConsumptionLine {
+    pub item_id: i32,
+    pub item_code: String,
+    pub warehouse_id: i32,
+    pub unit_price: Decimal,
+    pub quantity: Decimal,
+    pub amount: Decimal,
+    /// Part of `quantity` not yet on a settlement
+    pub unsettled_quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct CreateSettlement {
+    /// Settle consumption up to this moment; defaults to now
+    pub period_end: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ConsignmentSettlement {
+    pub settlement_id: i32,
+    pub settlement_number: String,
+    pub consignor_id: i32,
+    pub period_end: DateTime<Utc>,
+    pub currency: String,
+    pub total_amount: Decimal,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SettlementLine {
+    pub line_id: i32,
+    pub settlement_id: i32,
+    pub item_id: i32,
+    pub quantity: Decimal,
+    pub unit_price: Decimal,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettlementDetail {
+    #[serde(flatten)]
+    pub settlement: ConsignmentSettlement,
+    pub lines: Vec<SettlementLine>,
+}
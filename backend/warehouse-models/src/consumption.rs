@@ -0,0 +1,66 @@
+//! Consumption feed: point-of-sale and site-consumption events that issue stock at
+//! satellite and field warehouses as they happen
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+use crate::{validate_positive, StockMovement};
+
+pub const MOVEMENT_CONSUMPTION: &str = "CONSUMPTION";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ConsumptionRecord {
+    pub consumption_id: i64,
+    pub warehouse_id: i32,
+    pub event_id: String,
+    pub item_id: i32,
+    pub quantity: Decimal,
+    pub source: Option<String>,
+    pub reference: Option<String>,
+    pub consumed_at: DateTime<Utc>,
+    pub movement_id: i64,
+    pub received_at: DateTime<Utc>,
+}
+
+/// One batch of events from a terminal, van or technician's device
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ConsumptionBatch {
+    /// Terminal, van or technician that sent the batch
+    #[validate(length(min = 1, max = 50))]
+    pub source: Option<String>,
+    #[validate(length(min = 1, max = 1000), nested)]
+    pub events: Vec<ConsumptionEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ConsumptionEvent {
+    /// The sender's id of the event; sending it again is harmless
+    #[validate(length(min = 1, max = 100))]
+    pub event_id: String,
+    #[validate(length(min = 1, max = 100))]
+    pub item_code: String,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+    #[validate(length(min = 1, max = 100))]
+    pub lot_number: Option<String>,
+    /// Receipt, ticket or work order the goods were consumed against
+    #[validate(length(min = 1, max = 100))]
+    pub reference: Option<String>,
+    /// Defaults to now
+    pub consumed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConsumptionResult {
+    /// Events in the batch, repeats included
+    pub received: usize,
+    pub movements: Vec<StockMovement>,
+    /// Events taken in before, by an earlier batch or earlier in this one
+    pub duplicates: Vec<String>,
+    /// Events that could not be issued, e.g. for an unknown item or too little stock;
+    /// they are not recorded, so sending them again retries them
+    pub exceptions: Vec<String>,
+}
@@ -0,0 +1,134 @@
+//! Five-field cron expressions, `minute hour day-of-month month day-of-week`, evaluated
+//! in UTC. Fields take `*`, numbers, ranges `a-b`, steps `*/n` or `a-b/n`, and lists of
+//! those; day-of-week runs from 0 (Sunday) to 6, with 7 also Sunday. As in cron, when both
+//! day fields are restricted, a day matching either of them runs.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
+
+/// Days searched for the next run before giving up on an expression that never runs,
+/// such as the 31st of February
+const SEARCH_DAYS: i64 = 366 * 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(format!("{} does not have five fields", expression));
+        };
+
+        let mut weekday_bits = field(weekdays, 0, 7, "day-of-week")?;
+        if weekday_bits & (1 << 7) != 0 {
+            weekday_bits |= 1;
+        }
+
+        Ok(Self {
+            minutes: field(minutes, 0, 59, "minute")?,
+            hours: field(hours, 0, 23, "hour")?,
+            days: field(days, 1, 31, "day-of-month")?,
+            months: field(months, 1, 12, "month")?,
+            weekdays: weekday_bits & 0x7F,
+            days_restricted: !days.starts_with('*'),
+            weekdays_restricted: !weekdays.starts_with('*'),
+        })
+    }
+
+    /// The first minute after `after` the schedule runs at; None when it never runs
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+
+        let mut date = start.date_naive();
+        for _ in 0..SEARCH_DAYS {
+            if self.runs_on(date) {
+                let from = if date == start.date_naive() { start.time() } else { NaiveTime::MIN };
+                if let Some(time) = self.first_time_from(from) {
+                    return Some(Utc.from_utc_datetime(&date.and_time(time)));
+                }
+            }
+            date = date.succ_opt()?;
+        }
+
+        None
+    }
+
+    fn runs_on(&self, date: NaiveDate) -> bool {
+        if !has(self.months, date.month()) {
+            return false;
+        }
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            (true, false) => day,
+            (false, true) => weekday,
+            (false, false) => true,
+        }
+    }
+
+    fn first_time_from(&self, from: NaiveTime) -> Option<NaiveTime> {
+        for hour in from.hour()..24 {
+            if !has(self.hours, hour) {
+                continue;
+            }
+            let first_minute = if hour == from.hour() { from.minute() } else { 0 };
+            if let Some(minute) = (first_minute..60).find(|minute| has(self.minutes, *minute)) {
+                return NaiveTime::from_hms_opt(hour, minute, 0);
+            }
+        }
+        None
+    }
+}
+
+fn has(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+/// Bit `n` set for every value `n` the field matches
+fn field(spec: &str, min: u32, max: u32, name: &str) -> Result<u64, String> {
+    let invalid = || format!("{} is not a valid {} field", spec, name);
+
+    let mut bits = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+
+        let (low, high) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((low, high)) => {
+                    (low.parse::<u32>().map_err(|_| invalid())?, high.parse::<u32>().map_err(|_| invalid())?)
+                }
+                // `5/15` runs from 5 to the end of the field
+                None if part.contains('/') => (range.parse::<u32>().map_err(|_| invalid())?, max),
+                None => {
+                    let value = range.parse::<u32>().map_err(|_| invalid())?;
+                    (value, value)
+                }
+            },
+        };
+        if low < min || high > max || low > high {
+            return Err(invalid());
+        }
+
+        for value in (low..=high).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
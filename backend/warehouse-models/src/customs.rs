@@ -0,0 +1,73 @@
+//! Customs data on items and the customs declaration for international shipments
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+/// Tariff classification and origin of an item, as declared to customs
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CustomsData {
+    /// Harmonized System code, 6 to 10 digits without dots
+    #[validate(custom(function = "validate_hs_code"))]
+    pub hs_code: String,
+    /// ISO 3166-1 alpha-2 code of the country the goods were made in
+    #[validate(custom(function = "validate_country_code"))]
+    pub country_of_origin: String,
+    /// Plain description of the goods for the declaration, e.g. "steel reinforcing bars"
+    #[validate(length(min = 1, max = 200))]
+    pub customs_description: String,
+}
+
+/// Customs declaration for what a shipment carried. Lines are always listed; for an
+/// international shipment every line has its customs data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomsDeclaration {
+    pub shipment_id: i32,
+    pub shipment_number: String,
+    pub order_number: String,
+    pub carrier_code: Option<String>,
+    pub tracking_number: Option<String>,
+    /// Country of the shipping warehouse
+    pub origin_country: Option<String>,
+    pub destination_country: Option<String>,
+    /// The destination is outside the warehouse's country
+    pub international: bool,
+    pub lines: Vec<CustomsLine>,
+    /// None when an item has no cost on file
+    pub total_value: Option<Decimal>,
+    /// None when an item has neither a captured nor a master weight
+    pub total_weight_kg: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomsLine {
+    pub item_id: i32,
+    pub item_code: String,
+    /// Customs description, or the item name when the item has no customs data
+    pub description: String,
+    pub hs_code: Option<String>,
+    pub country_of_origin: Option<String>,
+    pub quantity: Decimal,
+    pub unit: Option<String>,
+    /// Standard cost of one unit
+    pub unit_value: Option<Decimal>,
+    pub value: Option<Decimal>,
+    /// Captured catch weight where recorded, otherwise the item weight times quantity
+    pub weight_kg: Option<Decimal>,
+}
+
+fn validate_hs_code(hs_code: &str) -> Result<(), ValidationError> {
+    if (6..=10).contains(&hs_code.len()) && hs_code.chars().all(|c| c.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_hs_code"))
+    }
+}
+
+fn validate_country_code(code: &str) -> Result<(), ValidationError> {
+    if code.len() == 2 && code.chars().all(|c| c.is_ascii_uppercase()) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_country_code"))
+    }
+}
@@ -0,0 +1,173 @@
+//! Deliveries of project materials to site: requests are scheduled onto the warehouse's
+//! delivery calendar and a vehicle with room for them, and become outbound orders
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::validate_positive;
+
+pub const SITE_DELIVERY_REQUESTED: &str = "REQUESTED";
+/// On a delivery day and vehicle, with its outbound order created
+pub const SITE_DELIVERY_SCHEDULED: &str = "SCHEDULED";
+pub const SITE_DELIVERY_CANCELLED: &str = "CANCELLED";
+
+/// Weekdays a warehouse delivers on when it has no calendar of its own
+pub const DEFAULT_DELIVERY_WEEKDAYS: [i16; 5] = [1, 2, 3, 4, 5];
+
+/// Days a warehouse sends out deliveries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryCalendar {
+    pub warehouse_id: i32,
+    /// ISO weekdays, 1 = Monday
+    pub delivery_weekdays: Vec<i16>,
+    /// Delivery days taken out of the calendar, from today on
+    pub closures: Vec<DeliveryClosure>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, Validate)]
+pub struct DeliveryClosure {
+    pub closed_on: NaiveDate,
+    #[validate(length(min = 1, max = 255))]
+    pub reason: Option<String>,
+}
+
+/// Replace a warehouse's delivery weekdays and its closures from today on
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetDeliveryCalendar {
+    #[validate(length(min = 1, max = 7), custom(function = "validate_weekdays"))]
+    pub delivery_weekdays: Vec<i16>,
+    #[serde(default)]
+    #[validate(length(max = 366), nested)]
+    pub closures: Vec<DeliveryClosure>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DeliveryVehicle {
+    pub vehicle_id: i32,
+    pub warehouse_id: i32,
+    pub vehicle_code: String,
+    pub description: Option<String>,
+    /// Load it carries on a delivery day
+    pub capacity_kg: Decimal,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateDeliveryVehicle {
+    #[validate(length(min = 1, max = 20))]
+    pub vehicle_code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub description: Option<String>,
+    #[validate(custom(function = "validate_positive"))]
+    pub capacity_kg: Decimal,
+}
+
+/// Change a vehicle; an inactive vehicle is left out of scheduling and new runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateDeliveryVehicle {
+    #[validate(length(min = 1, max = 255))]
+    pub description: Option<String>,
+    #[validate(custom(function = "validate_positive"))]
+    pub capacity_kg: Option<Decimal>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SiteDelivery {
+    pub delivery_id: i32,
+    pub delivery_number: String,
+    pub project_id: i32,
+    pub warehouse_id: i32,
+    pub needed_by: NaiveDate,
+    pub site_name: String,
+    pub site_address: String,
+    pub site_city: Option<String>,
+    pub site_contact: Option<String>,
+    /// From the items' unit weights; items without one weigh nothing
+    pub weight_kg: Decimal,
+    pub status: String,
+    pub scheduled_date: Option<NaiveDate>,
+    pub vehicle_id: Option<i32>,
+    /// Outbound order the delivery is picked and shipped as, once scheduled
+    pub order_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SiteDeliveryLine {
+    pub delivery_id: i32,
+    pub line_number: i32,
+    pub item_id: i32,
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteDeliveryDetail {
+    #[serde(flatten)]
+    pub delivery: SiteDelivery,
+    pub lines: Vec<SiteDeliveryLine>,
+}
+
+/// Ask for goods from one warehouse at a project's site by a date
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RequestSiteDelivery {
+    pub warehouse_id: i32,
+    pub needed_by: NaiveDate,
+    #[validate(length(min = 1, max = 255))]
+    pub site_name: String,
+    #[validate(length(min = 1, max = 1000))]
+    pub site_address: String,
+    #[validate(length(min = 1, max = 100))]
+    pub site_city: Option<String>,
+    /// Who receives the goods on site
+    #[validate(length(min = 1, max = 255))]
+    pub site_contact: Option<String>,
+    #[validate(length(min = 1, max = 200), nested)]
+    pub lines: Vec<SiteDeliveryLineRequest>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SiteDeliveryLineRequest {
+    pub item_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+}
+
+/// Schedule a requested delivery. Without a date, the latest open delivery day from today
+/// up to the needed-by date with a vehicle that has room is taken; without a vehicle, the
+/// smallest with room.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduleSiteDelivery {
+    pub delivery_date: Option<NaiveDate>,
+    pub vehicle_id: Option<i32>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeliveryScheduleFilter {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// What one vehicle carries on a delivery day
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct VehicleLoad {
+    pub scheduled_date: NaiveDate,
+    pub vehicle_id: i32,
+    pub vehicle_code: String,
+    pub capacity_kg: Decimal,
+    pub load_kg: Decimal,
+    pub deliveries: i64,
+}
+
+fn validate_weekdays(weekdays: &[i16]) -> Result<(), ValidationError> {
+    if weekdays.iter().all(|day| (1..=7).contains(day)) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("weekday_out_of_range"))
+    }
+}
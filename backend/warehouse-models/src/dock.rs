@@ -0,0 +1,104 @@
+//! The inbound dock board: each day's expected arrivals at a warehouse, whether from
+//! suppliers' ASNs, purchase orders shipped without an ASN, or transfers from other
+//! warehouses, and how far along each is
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+// What an inbound load is expected against
+pub const DOCK_SOURCE_ASN: &str = "ASN";
+pub const DOCK_SOURCE_PURCHASE_ORDER: &str = "PURCHASE_ORDER";
+pub const DOCK_SOURCE_TRANSFER: &str = "TRANSFER";
+
+// Dock board statuses
+pub const DOCK_EXPECTED: &str = "EXPECTED";
+/// Checked in at the dock, not yet being received
+pub const DOCK_ARRIVED: &str = "ARRIVED";
+pub const DOCK_RECEIVING: &str = "RECEIVING";
+pub const DOCK_COMPLETED: &str = "COMPLETED";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DockBoardQuery {
+    /// Defaults to today
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DockBoardEntry {
+    /// ASN, PURCHASE_ORDER or TRANSFER
+    pub source: String,
+    /// receipt_id, po_id or transfer_id, by source
+    pub reference_id: i32,
+    pub reference_number: String,
+    /// Supplier, or the warehouse a transfer comes from
+    pub shipper: Option<String>,
+    pub carrier_code: Option<String>,
+    /// None for transfers in transit, which are expected any day
+    pub expected_date: Option<NaiveDate>,
+    /// EXPECTED, ARRIVED, RECEIVING or COMPLETED
+    pub status: String,
+    /// Expected before the board's date and not completed yet
+    pub is_late: bool,
+    pub dock_door: Option<String>,
+    pub arrived_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockBoard {
+    pub warehouse_id: i32,
+    pub date: NaiveDate,
+    pub expected: usize,
+    pub arrived: usize,
+    pub receiving: usize,
+    pub completed: usize,
+    pub entries: Vec<DockBoardEntry>,
+}
+
+impl DockBoard {
+    pub fn new(warehouse_id: i32, date: NaiveDate, entries: Vec<DockBoardEntry>) -> Self {
+        let count = |status: &str| entries.iter().filter(|entry| entry.status == status).count();
+        DockBoard {
+            warehouse_id,
+            date,
+            expected: count(DOCK_EXPECTED),
+            arrived: count(DOCK_ARRIVED),
+            receiving: count(DOCK_RECEIVING),
+            completed: count(DOCK_COMPLETED),
+            entries,
+        }
+    }
+}
+
+/// Check an inbound load in at the dock
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CheckInArrival {
+    /// ASN, PURCHASE_ORDER or TRANSFER
+    #[validate(custom(function = "validate_dock_source"))]
+    pub source: String,
+    pub reference_id: i32,
+    #[validate(length(min = 1, max = 20))]
+    pub dock_door: Option<String>,
+    #[validate(length(max = 1000))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DockArrival {
+    pub arrival_id: i32,
+    pub warehouse_id: i32,
+    pub source: String,
+    pub reference_id: i32,
+    pub dock_door: Option<String>,
+    pub notes: Option<String>,
+    pub arrived_at: DateTime<Utc>,
+}
+
+fn validate_dock_source(source: &str) -> Result<(), ValidationError> {
+    match source {
+        DOCK_SOURCE_ASN | DOCK_SOURCE_PURCHASE_ORDER | DOCK_SOURCE_TRANSFER => Ok(()),
+        _ => Err(ValidationError::new("unknown_dock_source")),
+    }
+}
@@ -0,0 +1,117 @@
+//! Shipment documents: delivery orders and proforma invoices rendered as PDF under the
+//! tenant's or warehouse's company branding
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::{validate_phone, OutboundOrder, Shipment};
+
+/// What was handed over, for the consignee to sign
+pub const DOCUMENT_DELIVERY_ORDER: &str = "DELIVERY_ORDER";
+/// What the goods are worth, for customs and the consignee's records
+pub const DOCUMENT_PROFORMA_INVOICE: &str = "PROFORMA_INVOICE";
+pub const DOCUMENT_TYPES: &[&str] = &[DOCUMENT_DELIVERY_ORDER, DOCUMENT_PROFORMA_INVOICE];
+
+/// Accent colour when none is set
+pub const DEFAULT_ACCENT_COLOR: &str = "#1F3A5F";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DocumentBranding {
+    pub branding_id: i32,
+    /// None for the tenant-wide branding
+    pub warehouse_id: Option<i32>,
+    pub company_name: String,
+    pub address: Option<String>,
+    pub tax_id: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    /// `#RRGGBB`
+    pub accent_color: String,
+    pub footer_text: Option<String>,
+    pub currency: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Replaces the branding it is set on
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetDocumentBranding {
+    #[validate(length(min = 1, max = 255))]
+    pub company_name: String,
+    /// One line per line
+    #[validate(length(max = 1000))]
+    pub address: Option<String>,
+    #[validate(length(max = 50))]
+    pub tax_id: Option<String>,
+    /// Any common notation; kept in E.164 form
+    #[validate(length(max = 50), custom(function = "validate_phone"))]
+    pub phone: Option<String>,
+    #[validate(email, length(max = 255))]
+    pub email: Option<String>,
+    /// `#RRGGBB`; defaults to a dark blue
+    #[validate(custom(function = "validate_color"))]
+    pub accent_color: Option<String>,
+    #[validate(length(max = 500))]
+    pub footer_text: Option<String>,
+    /// ISO 4217 code; defaults to IDR
+    #[validate(length(equal = 3))]
+    pub currency: Option<String>,
+}
+
+/// A generated document; the PDF itself is fetched on its own
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ShipmentDocument {
+    pub document_id: i64,
+    pub shipment_id: i32,
+    pub document_type: String,
+    pub document_number: String,
+    pub size_bytes: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct GenerateDocument {
+    #[validate(custom(function = "validate_document_type"))]
+    pub document_type: String,
+}
+
+/// Everything a shipment's documents print
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShipmentDocumentSource {
+    pub shipment: Shipment,
+    pub order: OutboundOrder,
+    pub warehouse_name: String,
+    /// Where the warehouse is, a line per entry
+    pub ship_from: Vec<String>,
+    pub lines: Vec<DocumentLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentLine {
+    pub item_code: String,
+    pub description: String,
+    pub lot_number: Option<String>,
+    pub quantity: Decimal,
+    pub unit: Option<String>,
+    /// Standard cost of one unit; None when the item has no cost on file
+    pub unit_value: Option<Decimal>,
+}
+
+fn validate_color(color: &str) -> Result<(), ValidationError> {
+    let hex = color.strip_prefix('#').unwrap_or_default();
+    if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_color"))
+    }
+}
+
+fn validate_document_type(document_type: &str) -> Result<(), ValidationError> {
+    if DOCUMENT_TYPES.contains(&document_type) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_document_type"))
+    }
+}
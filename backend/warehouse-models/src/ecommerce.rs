@@ -0,0 +1,226 @@
+//! E-commerce channels: available-to-promise stock pushed to a storefront (Shopify,
+//! WooCommerce) and its orders pulled in as outbound orders, on a schedule
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::validate_positive;
+
+pub const ECOMMERCE_SHOPIFY: &str = "SHOPIFY";
+pub const ECOMMERCE_WOOCOMMERCE: &str = "WOOCOMMERCE";
+pub const ECOMMERCE_PLATFORMS: &[&str] = &[ECOMMERCE_SHOPIFY, ECOMMERCE_WOOCOMMERCE];
+
+/// Stock levels out to the storefront
+pub const ECOMMERCE_PUSH: &str = "PUSH";
+/// Orders in from the storefront
+pub const ECOMMERCE_PULL: &str = "PULL";
+
+pub const ECOMMERCE_RUN_SUCCEEDED: &str = "SUCCEEDED";
+pub const ECOMMERCE_RUN_FAILED: &str = "FAILED";
+
+/// A failed sync is retried after this many minutes, or at the channel's own interval if
+/// that is sooner
+pub const ECOMMERCE_RETRY_MINUTES: i32 = 15;
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct EcommerceChannel {
+    pub channel_id: i32,
+    pub channel_code: String,
+    pub platform: String,
+    pub store_url: String,
+    #[serde(skip_serializing)]
+    pub api_key: String,
+    #[serde(skip_serializing)]
+    pub api_secret: Option<String>,
+    /// Shopify location the stock levels are set at
+    pub location_id: Option<String>,
+    /// Stock is promised from, and orders are shipped from, this warehouse
+    pub warehouse_id: i32,
+    /// Held back from every listed item unless the listing has its own buffer
+    pub buffer_quantity: Decimal,
+    pub sync_interval_minutes: i32,
+    pub is_active: bool,
+    /// Orders changed up to here have been pulled
+    pub orders_synced_at: Option<DateTime<Utc>>,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    pub next_run_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// An item sold on a channel
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct EcommerceListing {
+    pub item_id: i32,
+    pub item_code: String,
+    /// The storefront's id: the Shopify inventory item or the WooCommerce product
+    pub external_id: String,
+    /// Overrides the channel's buffer
+    pub buffer_quantity: Option<Decimal>,
+    /// Last quantity the storefront accepted
+    pub pushed_quantity: Option<Decimal>,
+    pub pushed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EcommerceChannelDetail {
+    #[serde(flatten)]
+    pub channel: EcommerceChannel,
+    pub listings: Vec<EcommerceListing>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetEcommerceListing {
+    pub item_id: i32,
+    #[validate(length(min = 1, max = 100))]
+    pub external_id: String,
+    #[validate(custom(function = "validate_not_negative"))]
+    pub buffer_quantity: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_credentials"))]
+pub struct CreateEcommerceChannel {
+    #[validate(length(min = 1, max = 30))]
+    pub channel_code: String,
+    #[validate(custom(function = "validate_platform"))]
+    pub platform: String,
+    /// `https://<shop>.myshopify.com` or the WordPress site
+    #[validate(url, length(max = 500))]
+    pub store_url: String,
+    #[validate(length(min = 1, max = 255))]
+    pub api_key: String,
+    /// Required for WooCommerce
+    #[validate(length(min = 1, max = 255))]
+    pub api_secret: Option<String>,
+    /// Required for Shopify
+    #[validate(length(min = 1, max = 50))]
+    pub location_id: Option<String>,
+    pub warehouse_id: i32,
+    /// Zero when omitted
+    #[validate(custom(function = "validate_not_negative"))]
+    pub buffer_quantity: Option<Decimal>,
+    #[validate(range(min = 5, max = 10080))]
+    pub sync_interval_minutes: i32,
+    #[serde(default)]
+    #[validate(length(max = 5000), nested)]
+    pub listings: Vec<SetEcommerceListing>,
+}
+
+/// Change a channel's credentials, buffers, schedule or listings; its platform and
+/// warehouse stay as created
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateEcommerceChannel {
+    #[validate(url, length(max = 500))]
+    pub store_url: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub api_key: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub api_secret: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub location_id: Option<String>,
+    #[validate(custom(function = "validate_not_negative"))]
+    pub buffer_quantity: Option<Decimal>,
+    #[validate(range(min = 5, max = 10080))]
+    pub sync_interval_minutes: Option<i32>,
+    pub is_active: Option<bool>,
+    /// Replaces the channel's listings
+    #[validate(length(max = 5000), nested)]
+    pub listings: Option<Vec<SetEcommerceListing>>,
+}
+
+/// Quantity of a listed item the storefront may sell: available stock in the channel's
+/// warehouse, less what open orders still need and the buffer, never below zero
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct EcommerceStockLevel {
+    pub item_id: i32,
+    pub item_code: String,
+    pub external_id: String,
+    pub available: Decimal,
+    /// Ordered and not yet shipped on open orders of the warehouse
+    pub committed: Decimal,
+    pub buffer_quantity: Decimal,
+    /// Whole units
+    pub quantity: Decimal,
+    pub pushed_quantity: Option<Decimal>,
+}
+
+impl EcommerceStockLevel {
+    /// The storefront does not know this quantity yet
+    pub fn is_changed(&self) -> bool {
+        self.pushed_quantity != Some(self.quantity)
+    }
+}
+
+/// An order as read from a storefront
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct StorefrontOrder {
+    /// The storefront's id of the order
+    #[validate(length(min = 1, max = 100))]
+    pub external_id: String,
+    /// The order number shoppers see, e.g. `#1001`
+    #[validate(length(min = 1, max = 100))]
+    pub order_number: String,
+    #[validate(length(min = 1, max = 255))]
+    pub customer_name: String,
+    pub ship_to_name: Option<String>,
+    pub ship_to_address: Option<String>,
+    pub ship_to_city: Option<String>,
+    pub ship_to_state: Option<String>,
+    #[validate(length(max = 20))]
+    pub ship_to_postal_code: Option<String>,
+    pub ship_to_country: Option<String>,
+    /// Paid and not cancelled; other orders are not imported until they are
+    pub ready: bool,
+    #[validate(length(min = 1), nested)]
+    pub lines: Vec<StorefrontOrderLine>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct StorefrontOrderLine {
+    /// Our item code
+    #[validate(length(min = 1, max = 100))]
+    pub sku: String,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct EcommerceSyncRun {
+    pub run_id: i64,
+    pub channel_id: i32,
+    pub direction: String,
+    pub status: String,
+    pub records: i32,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+}
+
+fn validate_platform(platform: &str) -> Result<(), ValidationError> {
+    if ECOMMERCE_PLATFORMS.contains(&platform) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_platform"))
+    }
+}
+
+fn validate_credentials(channel: &CreateEcommerceChannel) -> Result<(), ValidationError> {
+    match channel.platform.as_str() {
+        ECOMMERCE_SHOPIFY if channel.location_id.is_none() => Err(ValidationError::new("location_id_required")),
+        ECOMMERCE_WOOCOMMERCE if channel.api_secret.is_none() => Err(ValidationError::new("api_secret_required")),
+        _ => Ok(()),
+    }
+}
+
+fn validate_not_negative(quantity: &Decimal) -> Result<(), ValidationError> {
+    if quantity.is_sign_negative() && !quantity.is_zero() {
+        Err(ValidationError::new("must_not_be_negative"))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+//! EDI (ANSI X12) document log models
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+pub const EDI_INBOUND: &str = "INBOUND";
+pub const EDI_OUTBOUND: &str = "OUTBOUND";
+
+pub const EDI_PROCESSED: &str = "PROCESSED";
+pub const EDI_FAILED: &str = "FAILED";
+/// Outbound document generated and waiting for the trading partner to collect it
+pub const EDI_READY: &str = "READY";
+
+/// Every X12 document received or emitted, kept verbatim for partner disputes
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct EdiDocument {
+    pub document_id: i64,
+    pub direction: String,
+    pub transaction_set: String,
+    pub partner_id: Option<String>,
+    pub control_number: Option<String>,
+    pub status: String,
+    pub error: Option<String>,
+    pub reference_type: Option<String>,
+    pub reference_id: Option<i32>,
+    pub content: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Header fields of a document about to be logged
+#[derive(Debug, Clone)]
+pub struct NewEdiDocument {
+    pub direction: &'static str,
+    pub transaction_set: String,
+    pub partner_id: Option<String>,
+    pub control_number: Option<String>,
+    pub status: &'static str,
+    pub error: Option<String>,
+    pub reference_type: Option<String>,
+    pub reference_id: Option<i32>,
+    pub content: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EdiFilter {
+    pub direction: Option<String>,
+    pub transaction_set: Option<String>,
+    pub status: Option<String>,
+}
@@ -0,0 +1,134 @@
+//! Zone environment monitoring: sensors, thresholds and cold-chain excursions
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+pub const MEASURE_TEMPERATURE: &str = "TEMPERATURE";
+pub const MEASURE_HUMIDITY: &str = "HUMIDITY";
+
+/// Longest window the environment history endpoint returns in one request
+pub const ENVIRONMENT_HISTORY_MAX_DAYS: i64 = 31;
+
+/// Acceptable range for a zone, temperature in degrees Celsius and humidity in %RH
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ZoneThresholds {
+    pub zone_id: i32,
+    pub min_temperature: Option<Decimal>,
+    pub max_temperature: Option<Decimal>,
+    pub min_humidity: Option<Decimal>,
+    pub max_humidity: Option<Decimal>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Replaces the zone's thresholds; omitted bounds are not checked
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct SetThresholds {
+    pub min_temperature: Option<Decimal>,
+    pub max_temperature: Option<Decimal>,
+    pub min_humidity: Option<Decimal>,
+    pub max_humidity: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Sensor {
+    pub sensor_id: i32,
+    pub sensor_code: String,
+    pub zone_id: i32,
+    pub last_reading_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateSensor {
+    #[validate(length(min = 1, max = 50))]
+    pub sensor_code: String,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SensorReading {
+    pub reading_id: i64,
+    pub sensor_id: i32,
+    pub zone_id: i32,
+    pub recorded_at: DateTime<Utc>,
+    pub temperature: Option<Decimal>,
+    pub humidity: Option<Decimal>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SensorReadings {
+    #[validate(length(min = 1, max = 5000), nested)]
+    pub readings: Vec<NewSensorReading>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewSensorReading {
+    #[validate(length(min = 1, max = 50))]
+    pub sensor_code: String,
+    pub recorded_at: DateTime<Utc>,
+    pub temperature: Option<Decimal>,
+    #[validate(custom(function = "validate_humidity"))]
+    pub humidity: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Excursion {
+    pub excursion_id: i32,
+    pub sensor_id: i32,
+    pub zone_id: i32,
+    pub measure: String,
+    pub limit_min: Option<Decimal>,
+    pub limit_max: Option<Decimal>,
+    pub peak_value: Decimal,
+    pub started_at: DateTime<Utc>,
+    /// None while the sensor is still out of range
+    pub ended_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReadingIngestResult {
+    pub accepted: usize,
+    /// Readings already stored for the same sensor and time
+    pub duplicates: usize,
+    pub unknown_sensors: Vec<String>,
+    pub excursions_opened: Vec<Excursion>,
+    pub excursions_closed: Vec<Excursion>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExcursionFilter {
+    pub zone_id: Option<i32>,
+    /// `true` lists only excursions still in progress
+    pub open: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnvironmentHistoryQuery {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub sensor_id: Option<i32>,
+}
+
+/// Readings and excursions of a zone over a window, for cold-chain audit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentHistory {
+    pub zone_id: i32,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub thresholds: Option<ZoneThresholds>,
+    pub readings: Vec<SensorReading>,
+    /// Excursions overlapping the window
+    pub excursions: Vec<Excursion>,
+}
+
+fn validate_humidity(humidity: &Decimal) -> Result<(), ValidationError> {
+    if *humidity >= Decimal::ZERO && *humidity <= Decimal::ONE_HUNDRED {
+        Ok(())
+    } else {
+        Err(ValidationError::new("humidity_out_of_range"))
+    }
+}
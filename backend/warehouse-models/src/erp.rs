@@ -0,0 +1,236 @@
+//! ERP connectors: the item master, purchase orders, goods receipts and goods issues
+//! exchanged with an ERP (SAP, NetSuite, Odoo, ...) on a schedule
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::validate_positive;
+
+pub const ERP_SYSTEMS: &[&str] = &["GENERIC", "SAP", "NETSUITE", "ODOO"];
+
+/// JSON over HTTP, see `warehouse_core::erp::rest`
+pub const ERP_CONNECTOR_REST: &str = "REST";
+pub const ERP_CONNECTOR_TYPES: &[&str] = &[ERP_CONNECTOR_REST];
+
+/// The item master is kept in the ERP and pulled
+pub const ERP_ITEM_MASTER_ERP: &str = "ERP";
+/// The item master is kept here and pushed
+pub const ERP_ITEM_MASTER_WMS: &str = "WMS";
+
+pub const ERP_ENTITY_ITEM: &str = "ITEM";
+pub const ERP_ENTITY_PURCHASE_ORDER: &str = "PURCHASE_ORDER";
+pub const ERP_ENTITY_RECEIPT: &str = "RECEIPT";
+pub const ERP_ENTITY_ISSUE: &str = "ISSUE";
+pub const ERP_ENTITIES: &[&str] = &[ERP_ENTITY_ITEM, ERP_ENTITY_PURCHASE_ORDER, ERP_ENTITY_RECEIPT, ERP_ENTITY_ISSUE];
+
+pub const ERP_PUSH: &str = "PUSH";
+pub const ERP_PULL: &str = "PULL";
+
+pub const ERP_RUN_SUCCEEDED: &str = "SUCCEEDED";
+pub const ERP_RUN_FAILED: &str = "FAILED";
+
+/// A failed sync is retried after this many minutes, or at the connector's own interval
+/// if that is sooner
+pub const ERP_RETRY_MINUTES: i32 = 15;
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ErpConnector {
+    pub connector_id: i32,
+    pub connector_code: String,
+    pub erp_system: String,
+    pub connector_type: String,
+    pub base_url: String,
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+    pub item_master: String,
+    pub sync_interval_minutes: i32,
+    pub is_active: bool,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    pub next_run_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Our field `field_name` of `entity` is called `erp_field` in the ERP. Fields of nested
+/// lines are mapped under the same entity.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, Deserialize, Validate)]
+pub struct ErpFieldMapping {
+    #[validate(custom(function = "validate_entity"))]
+    pub entity: String,
+    #[validate(length(min = 1, max = 100))]
+    pub field_name: String,
+    #[validate(length(min = 1, max = 100))]
+    pub erp_field: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErpConnectorDetail {
+    #[serde(flatten)]
+    pub connector: ErpConnector,
+    pub field_mappings: Vec<ErpFieldMapping>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateErpConnector {
+    #[validate(length(min = 1, max = 30))]
+    pub connector_code: String,
+    #[validate(custom(function = "validate_erp_system"))]
+    pub erp_system: String,
+    #[validate(custom(function = "validate_connector_type"))]
+    pub connector_type: String,
+    #[validate(url, length(max = 500))]
+    pub base_url: String,
+    /// Sent as a bearer token
+    #[validate(length(max = 255))]
+    pub api_key: Option<String>,
+    /// ERP or WMS; ERP when omitted
+    #[validate(custom(function = "validate_item_master"))]
+    pub item_master: Option<String>,
+    #[validate(range(min = 5, max = 10080))]
+    pub sync_interval_minutes: i32,
+    #[serde(default)]
+    #[validate(length(max = 200), nested)]
+    pub field_mappings: Vec<ErpFieldMapping>,
+}
+
+/// Change a connector's endpoint, schedule or mappings; its type stays as created
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateErpConnector {
+    #[validate(url, length(max = 500))]
+    pub base_url: Option<String>,
+    #[validate(length(max = 255))]
+    pub api_key: Option<String>,
+    #[validate(custom(function = "validate_item_master"))]
+    pub item_master: Option<String>,
+    #[validate(range(min = 5, max = 10080))]
+    pub sync_interval_minutes: Option<i32>,
+    pub is_active: Option<bool>,
+    /// Replaces the connector's mappings
+    #[validate(length(max = 200), nested)]
+    pub field_mappings: Option<Vec<ErpFieldMapping>>,
+}
+
+/// Where a direction and entity has synced up to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct ErpSyncCursor {
+    pub position_at: DateTime<Utc>,
+    pub position_id: i64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ErpSyncRun {
+    pub run_id: i64,
+    pub connector_id: i32,
+    pub direction: String,
+    pub entity: String,
+    pub status: String,
+    pub records: i32,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// An item as exchanged with the ERP, before field mapping
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, Validate)]
+pub struct ErpItem {
+    /// Ours; absent on items pulled from the ERP
+    #[serde(default)]
+    pub item_id: Option<i32>,
+    #[validate(length(min = 1, max = 100))]
+    pub item_code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub item_name: String,
+    pub item_description: Option<String>,
+    #[validate(length(max = 50))]
+    pub unit: Option<String>,
+    #[validate(length(max = 100))]
+    pub category: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Quantity of one item on a purchase order, goods receipt or goods issue
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, Validate)]
+pub struct ErpLine {
+    #[validate(length(min = 1, max = 100))]
+    pub item_code: String,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+}
+
+/// A purchase order pulled from the ERP, expected here as an inbound receipt
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ErpPurchaseOrder {
+    /// The ERP's own id of the order
+    #[validate(length(min = 1, max = 100))]
+    pub external_id: String,
+    /// Becomes the receipt's ASN number as well as its purchase order number
+    #[validate(length(min = 1, max = 50))]
+    pub purchase_order_number: String,
+    pub warehouse_code: String,
+    pub supplier_name: Option<String>,
+    pub expected_date: Option<NaiveDate>,
+    #[validate(length(min = 1), nested)]
+    pub lines: Vec<ErpLine>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A fully received inbound receipt, pushed to the ERP to post the goods receipt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErpGoodsReceipt {
+    pub receipt_id: i32,
+    pub asn_number: String,
+    pub purchase_order_number: Option<String>,
+    /// The ERP's id of the purchase order the receipt was imported from
+    pub erp_purchase_order_id: Option<String>,
+    pub warehouse_code: String,
+    pub supplier_name: Option<String>,
+    pub received_at: DateTime<Utc>,
+    pub lines: Vec<ErpLine>,
+}
+
+/// A shipment, pushed to the ERP to post the goods issue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErpGoodsIssue {
+    pub shipment_id: i32,
+    pub shipment_number: String,
+    pub order_number: String,
+    pub customer_reference: Option<String>,
+    pub warehouse_code: String,
+    pub shipped_at: DateTime<Utc>,
+    pub lines: Vec<ErpLine>,
+}
+
+fn validate_entity(entity: &str) -> Result<(), ValidationError> {
+    if ERP_ENTITIES.contains(&entity) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_entity"))
+    }
+}
+
+fn validate_erp_system(system: &str) -> Result<(), ValidationError> {
+    if ERP_SYSTEMS.contains(&system) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_erp_system"))
+    }
+}
+
+fn validate_connector_type(connector_type: &str) -> Result<(), ValidationError> {
+    if ERP_CONNECTOR_TYPES.contains(&connector_type) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_connector_type"))
+    }
+}
+
+fn validate_item_master(item_master: &str) -> Result<(), ValidationError> {
+    if [ERP_ITEM_MASTER_ERP, ERP_ITEM_MASTER_WMS].contains(&item_master) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_item_master"))
+    }
+}
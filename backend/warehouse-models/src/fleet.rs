@@ -0,0 +1,234 @@
+//! The warehouse's own fleet: drivers take a vehicle out on delivery runs with shipments as
+//! stops, and a proof of delivery at each stop delivers its shipment
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+pub const RUN_PLANNED: &str = "PLANNED";
+/// Out on the road; its shipments are out for delivery
+pub const RUN_DISPATCHED: &str = "DISPATCHED";
+/// Every stop has its proof of delivery
+pub const RUN_COMPLETED: &str = "COMPLETED";
+
+/// Tracking events the fleet records for the shipments it carries
+pub const TRACKING_SOURCE_FLEET: &str = "FLEET";
+
+pub const POD_SIGNATURE: &str = "SIGNATURE";
+pub const POD_PHOTO: &str = "PHOTO";
+/// Image types a signature or photo is accepted in
+pub const POD_CONTENT_TYPES: [&str; 3] = ["image/png", "image/jpeg", "image/webp"];
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DeliveryDriver {
+    pub driver_id: i32,
+    pub warehouse_id: i32,
+    pub driver_code: String,
+    pub driver_name: String,
+    pub phone: Option<String>,
+    pub license_number: String,
+    /// Runs are not planned for the driver past this day
+    pub license_expires_on: Option<NaiveDate>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateDeliveryDriver {
+    #[validate(length(min = 1, max = 20))]
+    pub driver_code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub driver_name: String,
+    #[validate(length(min = 1, max = 50))]
+    pub phone: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub license_number: String,
+    pub license_expires_on: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateDeliveryDriver {
+    #[validate(length(min = 1, max = 255))]
+    pub driver_name: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub phone: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub license_number: Option<String>,
+    pub license_expires_on: Option<NaiveDate>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DeliveryRun {
+    pub run_id: i32,
+    pub run_number: String,
+    pub warehouse_id: i32,
+    pub vehicle_id: i32,
+    pub driver_id: i32,
+    pub run_date: NaiveDate,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub dispatched_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// A shipment the run drops off, in the order the driver visits them
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DeliveryRunStop {
+    pub stop_number: i32,
+    pub shipment_id: i32,
+    pub shipment_number: String,
+    pub order_id: i32,
+    pub ship_to_name: Option<String>,
+    pub ship_to_address: Option<String>,
+    pub ship_to_city: Option<String>,
+    pub weight_kg: Decimal,
+    /// The shipment's status
+    pub status: String,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryRunDetail {
+    #[serde(flatten)]
+    pub run: DeliveryRun,
+    pub vehicle_code: String,
+    pub capacity_kg: Decimal,
+    pub driver_name: String,
+    /// Weight of all stops together
+    pub load_kg: Decimal,
+    pub stops: Vec<DeliveryRunStop>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateDeliveryRun {
+    pub vehicle_id: i32,
+    pub driver_id: i32,
+    pub run_date: NaiveDate,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeliveryRunFilter {
+    pub run_date: Option<NaiveDate>,
+    pub status: Option<String>,
+}
+
+/// Add shipments to a planned run as its next stops, in the order given
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct AssignShipments {
+    #[validate(length(min = 1, max = 100))]
+    pub shipment_ids: Vec<i32>,
+}
+
+/// The recipient took the goods at a stop of a dispatched run
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RecordProofOfDelivery {
+    #[validate(length(min = 1, max = 255))]
+    pub received_by: String,
+    /// Now unless given
+    pub delivered_at: Option<DateTime<Utc>>,
+    #[validate(length(max = 1000))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofOfDelivery {
+    pub shipment_id: i32,
+    pub run_id: i32,
+    pub received_by: String,
+    pub delivered_at: DateTime<Utc>,
+    pub notes: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+    pub files: Vec<ProofOfDeliveryFile>,
+}
+
+/// A signature or photo kept with a proof of delivery
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ProofOfDeliveryFile {
+    pub shipment_id: i32,
+    pub file_kind: String,
+    pub content_type: String,
+    pub size_bytes: i32,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+/// A point on the map, in degrees
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Validate)]
+pub struct GeoPoint {
+    #[validate(range(min = -90.0, max = 90.0))]
+    pub latitude: f64,
+    #[validate(range(min = -180.0, max = 180.0))]
+    pub longitude: f64,
+}
+
+/// Where a shipment is dropped off
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RouteSite {
+    pub shipment_id: i32,
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub location: GeoPoint,
+}
+
+/// Suggest routes for the shipments waiting for a run, from the warehouse's depot to the
+/// sites given and back, on the vehicles free on the day
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct PlanRoutes {
+    /// Today unless given
+    pub run_date: Option<NaiveDate>,
+    #[validate(nested)]
+    pub depot: GeoPoint,
+    #[validate(length(min = 1, max = 500), nested)]
+    pub sites: Vec<RouteSite>,
+}
+
+/// A shipment of the warehouse's own fleet, shipped and not yet on a run
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct RoutableShipment {
+    pub shipment_id: i32,
+    pub shipment_number: String,
+    pub weight_kg: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutePlan {
+    pub warehouse_id: i32,
+    pub run_date: NaiveDate,
+    pub routes: Vec<SuggestedRoute>,
+    /// Shipments left out, with why
+    pub unrouted: Vec<UnroutedShipment>,
+    pub distance_km: f64,
+}
+
+/// Stops for one vehicle, in the order to drive them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestedRoute {
+    pub vehicle_id: i32,
+    pub vehicle_code: String,
+    pub capacity_kg: Decimal,
+    pub load_kg: Decimal,
+    /// Straight-line distance from the depot through every stop and back
+    pub distance_km: f64,
+    pub stops: Vec<SuggestedStop>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestedStop {
+    pub sequence: i32,
+    pub shipment_id: i32,
+    pub shipment_number: String,
+    #[serde(flatten)]
+    pub location: GeoPoint,
+    pub weight_kg: Decimal,
+    /// From the stop before, or the depot
+    pub leg_km: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnroutedShipment {
+    pub shipment_id: i32,
+    pub shipment_number: String,
+    pub reason: String,
+}
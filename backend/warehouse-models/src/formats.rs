@@ -0,0 +1,136 @@
+//! Validators for business formats shared by the create and update DTOs: warehouse codes,
+//! phone numbers, postal codes and timezone names. Each fails with its own error code so a
+//! bad value is reported against the field it came in.
+
+use validator::ValidationError;
+
+/// Country assumed for national phone numbers and addresses without a country
+pub const DEFAULT_COUNTRY: &str = "Indonesia";
+
+/// `^[A-Z0-9-]{2,50}$`
+pub fn validate_warehouse_code(code: &str) -> Result<(), ValidationError> {
+    let allowed = code.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'-');
+    if allowed && (2..=50).contains(&code.len()) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_warehouse_code"))
+    }
+}
+
+/// The number in E.164 form, e.g. `+6281234567890`. Spaces, dots, dashes and parentheses
+/// are dropped, `00` is read as the international prefix and a number starting with a
+/// single `0` as an Indonesian national number.
+pub fn normalize_phone(phone: &str) -> Option<String> {
+    let mut digits = String::with_capacity(phone.len());
+    let mut international = false;
+    for (i, c) in phone.trim().chars().enumerate() {
+        match c {
+            '+' if i == 0 => international = true,
+            '0'..='9' => digits.push(c),
+            ' ' | '.' | '-' | '(' | ')' => {}
+            _ => return None,
+        }
+    }
+
+    let number = if international {
+        digits
+    } else if let Some(rest) = digits.strip_prefix("00") {
+        rest.to_string()
+    } else if let Some(rest) = digits.strip_prefix('0') {
+        format!("62{}", rest)
+    } else {
+        return None;
+    };
+
+    // Country codes never start with 0; E.164 allows at most 15 digits
+    if number.starts_with('0') || !(8..=15).contains(&number.len()) {
+        return None;
+    }
+    Some(format!("+{}", number))
+}
+
+pub fn validate_phone(phone: &str) -> Result<(), ValidationError> {
+    match normalize_phone(phone) {
+        Some(_) => Ok(()),
+        None => Err(ValidationError::new("invalid_phone")),
+    }
+}
+
+/// Postal code layouts by country name and ISO 3166 alpha-2 code: `9` is a digit, `A` a
+/// letter, anything else itself
+const POSTAL_FORMATS: &[(&[&str], &[&str])] = &[
+    (&["Indonesia", "ID"], &["99999"]),
+    (&["Malaysia", "MY"], &["99999"]),
+    (&["Singapore", "SG"], &["999999"]),
+    (&["Thailand", "TH"], &["99999"]),
+    (&["Vietnam", "Viet Nam", "VN"], &["999999"]),
+    (&["Philippines", "PH"], &["9999"]),
+    (&["Australia", "AU"], &["9999"]),
+    (&["China", "CN"], &["999999"]),
+    (&["India", "IN"], &["999999"]),
+    (&["Japan", "JP"], &["999-9999", "9999999"]),
+    (&["Germany", "DE"], &["99999"]),
+    (&["Netherlands", "NL"], &["9999 AA", "9999AA"]),
+    (&["Canada", "CA"], &["A9A 9A9", "A9A9A9"]),
+    (&["United States", "USA", "US"], &["99999", "99999-9999"]),
+];
+
+/// Whether the postal code fits the layout used in `country`. Countries without a known
+/// layout take 2 to 10 letters, digits, spaces and dashes.
+pub fn postal_code_fits(country: Option<&str>, postal_code: &str) -> bool {
+    let country = country.unwrap_or(DEFAULT_COUNTRY).trim();
+    let formats = POSTAL_FORMATS
+        .iter()
+        .find(|(names, _)| names.iter().any(|name| name.eq_ignore_ascii_case(country)))
+        .map(|(_, formats)| *formats);
+
+    match formats {
+        Some(formats) => formats.iter().any(|format| fits_layout(format, postal_code)),
+        None => {
+            (2..=10).contains(&postal_code.len())
+                && postal_code.bytes().all(|b| b.is_ascii_alphanumeric() || b == b' ' || b == b'-')
+        }
+    }
+}
+
+fn fits_layout(layout: &str, postal_code: &str) -> bool {
+    layout.len() == postal_code.len()
+        && layout.bytes().zip(postal_code.bytes()).all(|(expected, actual)| match expected {
+            b'9' => actual.is_ascii_digit(),
+            b'A' => actual.is_ascii_alphabetic(),
+            _ => actual == expected,
+        })
+}
+
+/// The error for a postal code that does not fit its country, named after the field
+pub fn invalid_postal_code(field: &'static str) -> ValidationError {
+    let mut error = ValidationError::new("invalid_postal_code");
+    error.message = Some(format!("{} does not fit the postal code format of its country", field).into());
+    error
+}
+
+/// An IANA zone name such as `Asia/Jakarta` or `America/Argentina/Buenos_Aires`, or `UTC`.
+/// Whether the zone exists is left to the database's zone list.
+pub fn validate_timezone(name: &str) -> Result<(), ValidationError> {
+    const AREAS: &[&str] = &[
+        "Africa", "America", "Antarctica", "Arctic", "Asia", "Atlantic", "Australia", "Europe", "Etc", "Indian",
+        "Pacific",
+    ];
+
+    let mut parts = name.split('/');
+    let area = parts.next().unwrap_or_default();
+    let places: Vec<&str> = parts.collect();
+    let place_ok = |place: &&str| {
+        !place.is_empty()
+            && place.len() <= 30
+            && place.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'+'))
+    };
+
+    let valid = matches!(name, "UTC" | "GMT")
+        || (AREAS.contains(&area) && (1..=2).contains(&places.len()) && places.iter().all(place_ok));
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_timezone"))
+    }
+}
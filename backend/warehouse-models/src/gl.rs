@@ -0,0 +1,121 @@
+//! General ledger export: journal lines for accounting, summarised per calendar month
+//! from the costed movements of our own stock
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+pub const GL_INVENTORY: &str = "INVENTORY";
+/// Goods in transit between warehouses
+pub const GL_IN_TRANSIT: &str = "IN_TRANSIT";
+/// Goods received not invoiced: the accrual cleared when the supplier invoice is booked
+pub const GL_GRNI: &str = "GRNI";
+pub const GL_COGS: &str = "COGS";
+pub const GL_SCRAP: &str = "SCRAP";
+pub const GL_ADJUSTMENT: &str = "ADJUSTMENT";
+
+pub const GL_PERIOD_OPEN: &str = "OPEN";
+pub const GL_PERIOD_CLOSED: &str = "CLOSED";
+
+pub const GL_EXPORT_SENT: &str = "SENT";
+pub const GL_EXPORT_FAILED: &str = "FAILED";
+
+/// Account that balances the inventory leg of a movement of `movement_type`, or None for
+/// moves between buckets of our own inventory, which leave its value unchanged
+pub fn gl_offset_category(movement_type: &str) -> Option<&'static str> {
+    match movement_type {
+        // Consumed consignment stock is owed to the consignor like a supplier delivery
+        "RECEIPT" | "RFID_IN" | "CONSIGNMENT_CONSUME" => Some(GL_GRNI),
+        "SHIPMENT" | "ISSUE" | "RFID_OUT" | "PACKAGING" | "CONSUMPTION" | "RETURN_RECEIPT" => Some(GL_COGS),
+        "QC_REJECT" | "RETURN_SCRAP" | "REPAIR_SCRAP" | "SCRAP" => Some(GL_SCRAP),
+        "TRANSFER_OUT" | "TRANSFER_IN" | "TRANSFER_RETURN" => Some(GL_IN_TRANSIT),
+        "QC_RELEASE" | "RETURN_RESTOCK" | "RECALL_HOLD" | "REPAIR_HOLD" | "REPAIR_RESTOCK" => None,
+        // Lent tools are still ours
+        "LOAN_OUT" | "LOAN_RETURN" => None,
+        _ => Some(GL_ADJUSTMENT),
+    }
+}
+
+/// First day of the month named `YYYY-MM`
+pub fn parse_gl_period(period: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", period), "%Y-%m-%d").ok()
+}
+
+/// First day of the month after `period`
+pub fn gl_period_end(period: NaiveDate) -> NaiveDate {
+    let (year, month) = if period.month() == 12 { (period.year() + 1, 1) } else { (period.year(), period.month() + 1) };
+    NaiveDate::from_ymd_opt(year, month, 1).expect("first of a month is a valid date")
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct GlAccount {
+    pub category: String,
+    pub account_code: String,
+    pub account_name: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct UpdateGlAccount {
+    #[validate(length(min = 1, max = 30))]
+    pub account_code: String,
+    #[validate(length(min = 1, max = 100))]
+    pub account_name: String,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct GlPeriod {
+    pub period: NaiveDate,
+    pub status: String,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Value moved by the movements of one type in one warehouse over a period
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct GlMovementTotal {
+    pub warehouse_code: String,
+    pub movement_type: String,
+    /// Signed like the movements: positive into stock
+    pub value: Decimal,
+    pub movements: i64,
+    /// Movements without a unit cost or item standard cost; left out of `value`
+    pub uncosted_movements: i64,
+}
+
+/// Net of one account in one warehouse over a period; one of debit and credit is zero
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlJournalLine {
+    pub period: NaiveDate,
+    pub account_code: String,
+    pub account_name: String,
+    pub category: String,
+    pub warehouse_code: String,
+    pub debit: Decimal,
+    pub credit: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlJournal {
+    pub period: NaiveDate,
+    pub status: String,
+    pub lines: Vec<GlJournalLine>,
+    pub total_debit: Decimal,
+    pub total_credit: Decimal,
+    pub movements: i64,
+    pub uncosted_movements: i64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct GlExport {
+    pub export_id: i32,
+    pub period: NaiveDate,
+    pub destination: String,
+    pub status: String,
+    pub lines: i32,
+    pub total_debit: Decimal,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
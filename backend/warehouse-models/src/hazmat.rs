@@ -0,0 +1,126 @@
+//! Dangerous goods: UN classification of items, zone storage compatibility and
+//! shipping documentation
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+/// UN hazard classes and divisions
+pub const HAZARD_CLASSES: &[&str] = &[
+    "1.1", "1.2", "1.3", "1.4", "1.5", "1.6", "2.1", "2.2", "2.3", "3", "4.1", "4.2", "4.3", "5.1", "5.2", "6.1",
+    "6.2", "7", "8", "9",
+];
+
+pub const PACKING_GROUPS: &[&str] = &["I", "II", "III"];
+
+/// Classes that must not share a zone. An entry without a division covers the whole
+/// class, so "1" segregates every explosive.
+pub const SEGREGATED_CLASSES: &[(&str, &str)] = &[
+    ("1", "2.1"),
+    ("1", "3"),
+    ("1", "4.1"),
+    ("1", "4.2"),
+    ("1", "4.3"),
+    ("1", "5.1"),
+    ("1", "5.2"),
+    ("1", "8"),
+    ("2.1", "5.1"),
+    ("2.1", "5.2"),
+    ("2.3", "3"),
+    ("2.3", "5.1"),
+    ("3", "5.1"),
+    ("3", "5.2"),
+    ("4.1", "5.1"),
+    ("4.1", "5.2"),
+    ("4.2", "5.1"),
+    ("4.2", "5.2"),
+    ("4.3", "5.1"),
+    ("4.3", "8"),
+    ("5.1", "5.2"),
+    ("6.1", "3"),
+];
+
+/// Whether `class` falls under a rule entry: "5.1" matches only itself, "1" matches "1.4"
+pub fn hazard_class_matches(rule: &str, class: &str) -> bool {
+    class == rule || class.strip_prefix(rule).is_some_and(|division| division.starts_with('.'))
+}
+
+/// Whether two hazard classes must be kept apart
+pub fn hazard_classes_segregated(a: &str, b: &str) -> bool {
+    SEGREGATED_CLASSES.iter().any(|(x, y)| {
+        (hazard_class_matches(x, a) && hazard_class_matches(y, b))
+            || (hazard_class_matches(x, b) && hazard_class_matches(y, a))
+    })
+}
+
+/// UN classification of a dangerous good
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct HazmatClassification {
+    #[validate(custom(function = "validate_un_number"))]
+    pub un_number: String,
+    #[validate(custom(function = "validate_hazard_class"))]
+    pub hazard_class: String,
+    #[validate(custom(function = "validate_packing_group"))]
+    pub packing_group: Option<String>,
+    #[validate(length(min = 1, max = 200))]
+    pub proper_shipping_name: String,
+}
+
+/// Replaces the hazard classes a zone is approved to store
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ZoneHazmat {
+    #[validate(custom(function = "validate_hazard_classes"))]
+    pub hazard_classes: Vec<String>,
+}
+
+/// Dangerous goods declaration for a shipment; `lines` is empty when nothing in it is hazardous
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HazmatDeclaration {
+    pub shipment_id: i32,
+    pub shipment_number: String,
+    pub carrier_code: Option<String>,
+    pub tracking_number: Option<String>,
+    pub lines: Vec<HazmatLine>,
+    /// None when a hazardous item has no weight on file
+    pub total_weight_kg: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HazmatLine {
+    pub item_id: i32,
+    pub item_code: String,
+    pub un_number: String,
+    pub proper_shipping_name: String,
+    pub hazard_class: String,
+    pub packing_group: Option<String>,
+    pub quantity: Decimal,
+    pub unit: Option<String>,
+    pub weight_kg: Option<Decimal>,
+}
+
+fn validate_un_number(un_number: &str) -> Result<(), ValidationError> {
+    match un_number.strip_prefix("UN") {
+        Some(digits) if digits.len() == 4 && digits.chars().all(|c| c.is_ascii_digit()) => Ok(()),
+        _ => Err(ValidationError::new("invalid_un_number")),
+    }
+}
+
+fn validate_hazard_class(class: &str) -> Result<(), ValidationError> {
+    if HAZARD_CLASSES.contains(&class) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_hazard_class"))
+    }
+}
+
+fn validate_packing_group(group: &str) -> Result<(), ValidationError> {
+    if PACKING_GROUPS.contains(&group) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_packing_group"))
+    }
+}
+
+fn validate_hazard_classes(classes: &[String]) -> Result<(), ValidationError> {
+    classes.iter().try_for_each(|class| validate_hazard_class(class))
+}
@@ -0,0 +1,111 @@
+//! Order intake: sales channels post orders in one normalized shape, which become
+//! outbound orders of the channel's fulfillment warehouse with stock allocated to them
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+use crate::{validate_positive, OrderDetail};
+
+/// Every line was allocated in full
+pub const ALLOCATION_ALLOCATED: &str = "ALLOCATED";
+/// Some of the order was allocated
+pub const ALLOCATION_PARTIAL: &str = "PARTIAL";
+/// None of the order was allocated
+pub const ALLOCATION_UNALLOCATED: &str = "UNALLOCATED";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct IntegrationChannel {
+    pub channel_id: i32,
+    pub channel_code: String,
+    pub channel_name: String,
+    /// Orders of the channel are created in, and allocated from, this warehouse
+    pub fulfillment_warehouse_id: i32,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateIntegrationChannel {
+    #[validate(length(min = 1, max = 30))]
+    pub channel_code: String,
+    #[validate(length(min = 1, max = 100))]
+    pub channel_name: String,
+    pub fulfillment_warehouse_id: i32,
+}
+
+/// Orders taken in before a change of warehouse stay where they were created
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateIntegrationChannel {
+    #[validate(length(min = 1, max = 100))]
+    pub channel_name: Option<String>,
+    pub fulfillment_warehouse_id: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+/// An order as a channel posts it
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct IntakeOrder {
+    #[validate(length(min = 1, max = 30))]
+    pub channel_code: String,
+    /// The channel's id of the order; posting it again returns the order already created
+    #[validate(length(min = 1, max = 100))]
+    pub external_order_id: String,
+    #[validate(length(min = 1, max = 255))]
+    pub customer_name: String,
+    /// Defaults to the external order id
+    #[validate(length(max = 100))]
+    pub customer_reference: Option<String>,
+    pub ship_to_name: Option<String>,
+    pub ship_to_address: Option<String>,
+    pub ship_to_city: Option<String>,
+    pub ship_to_state: Option<String>,
+    #[validate(length(max = 20))]
+    pub ship_to_postal_code: Option<String>,
+    pub ship_to_country: Option<String>,
+    pub requested_ship_date: Option<NaiveDate>,
+    #[serde(default)]
+    #[validate(length(min = 1, max = 20))]
+    pub carrier_code: Option<String>,
+    #[validate(length(min = 1, max = 500), nested)]
+    pub lines: Vec<IntakeOrderLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct IntakeOrderLine {
+    /// Our item code
+    #[validate(length(min = 1, max = 100))]
+    pub sku: String,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntakeResult {
+    pub channel_code: String,
+    pub external_order_id: String,
+    /// The order was taken in by an earlier post
+    pub duplicate: bool,
+    /// ALLOCATED, PARTIAL or UNALLOCATED, from the lines' allocated quantities
+    pub allocation_status: String,
+    pub order: OrderDetail,
+}
+
+impl IntakeResult {
+    pub fn new(channel_code: String, external_order_id: String, duplicate: bool, order: OrderDetail) -> Self {
+        let allocated: Decimal = order.lines.iter().map(|line| line.quantity_allocated).sum();
+        let ordered: Decimal = order.lines.iter().map(|line| line.quantity_ordered).sum();
+        let allocation_status = if allocated >= ordered {
+            ALLOCATION_ALLOCATED
+        } else if allocated.is_zero() {
+            ALLOCATION_UNALLOCATED
+        } else {
+            ALLOCATION_PARTIAL
+        };
+
+        Self { channel_code, external_order_id, duplicate, allocation_status: allocation_status.to_string(), order }
+    }
+}
@@ -0,0 +1,69 @@
+//! Scheduled integrity checks of stock balances against their invariants
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// `quantity_available` is not `quantity_on_hand - quantity_reserved`
+pub const CHECK_AVAILABLE_MISMATCH: &str = "AVAILABLE_MISMATCH";
+/// On hand differs from the sum of the balance's movements
+pub const CHECK_MOVEMENT_MISMATCH: &str = "MOVEMENT_MISMATCH";
+/// A stock row whose item or warehouse no longer exists
+pub const CHECK_ORPHANED_STOCK: &str = "ORPHANED_STOCK";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct IntegrityRun {
+    pub run_id: i32,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub findings: i32,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct IntegrityFinding {
+    pub finding_id: i64,
+    pub run_id: i32,
+    pub check_name: String,
+    pub stock_id: Option<i32>,
+    pub item_id: Option<i32>,
+    pub warehouse_id: Option<i32>,
+    pub consignor_id: Option<i32>,
+    pub expected: Option<Decimal>,
+    pub actual: Option<Decimal>,
+    pub detail: String,
+}
+
+/// A run with the count of each check that failed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    #[serde(flatten)]
+    pub run: IntegrityRun,
+    pub failed_checks: Vec<IntegrityCheckCount>,
+}
+
+impl IntegrityReport {
+    pub fn passed(&self) -> bool {
+        self.run.findings == 0
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct IntegrityCheckCount {
+    pub check_name: String,
+    pub findings: i64,
+}
+
+/// A run with every finding, for drill-down
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityRunDetail {
+    #[serde(flatten)]
+    pub run: IntegrityRun,
+    pub findings: Vec<IntegrityFinding>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IntegrityFindingFilter {
+    pub check_name: Option<String>,
+    pub warehouse_id: Option<i32>,
+}
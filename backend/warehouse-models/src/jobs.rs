@@ -0,0 +1,92 @@
+//! Asynchronous work tracked as jobs: progress while it runs, errors, and files produced
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+pub const JOB_IMPORT: &str = "IMPORT";
+pub const JOB_EXPORT: &str = "EXPORT";
+pub const JOB_REPORT: &str = "REPORT";
+pub const JOB_RECALCULATION: &str = "RECALCULATION";
+pub const JOB_BACKUP: &str = "BACKUP";
+
+pub const JOB_QUEUED: &str = "QUEUED";
+pub const JOB_RUNNING: &str = "RUNNING";
+pub const JOB_SUCCEEDED: &str = "SUCCEEDED";
+pub const JOB_FAILED: &str = "FAILED";
+
+/// Errors kept on a job; later ones are dropped
+pub const JOB_MAX_ERRORS: usize = 100;
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Job {
+    pub job_id: i64,
+    pub job_type: String,
+    pub description: String,
+    pub status: String,
+    pub processed: i64,
+    pub total: Option<i64>,
+    pub progress_percent: i16,
+    pub errors: Vec<String>,
+    pub reference_type: Option<String>,
+    pub reference_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Job {
+    pub fn is_finished(&self) -> bool {
+        self.status == JOB_SUCCEEDED || self.status == JOB_FAILED
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct JobArtifact {
+    pub artifact_id: i64,
+    pub job_id: i64,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An artifact with a URL anyone holding it can download it from until it expires
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedArtifact {
+    #[serde(flatten)]
+    pub artifact: JobArtifact,
+    pub download_url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobDetail {
+    #[serde(flatten)]
+    pub job: Job,
+    pub artifacts: Vec<SignedArtifact>,
+}
+
+/// A job to track
+#[derive(Debug, Clone)]
+pub struct NewJob {
+    pub job_type: &'static str,
+    pub description: String,
+    pub reference_type: Option<String>,
+    pub reference_id: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JobFilter {
+    pub job_type: Option<String>,
+    pub status: Option<String>,
+}
+
+/// Query string of a signed download URL
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArtifactSignature {
+    /// Unix time the URL stops working
+    pub expires: i64,
+    pub signature: String,
+}
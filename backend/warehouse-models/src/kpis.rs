@@ -0,0 +1,90 @@
+//! Warehouse KPIs per warehouse and period, computed from order, shipment, receipt and
+//! count history: on-time-in-full, order fill rate, dock-to-stock time and inventory accuracy
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+pub const KPI_PERIOD_DAY: &str = "day";
+pub const KPI_PERIOD_WEEK: &str = "week";
+pub const KPI_PERIOD_MONTH: &str = "month";
+pub const KPI_PERIODS: &[&str] = &[KPI_PERIOD_DAY, KPI_PERIOD_WEEK, KPI_PERIOD_MONTH];
+
+/// Dates are the warehouse's local dates
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct KpiFilter {
+    pub warehouse_id: Option<i32>,
+    /// First day counted; defaults to 30 days ago
+    pub from: Option<NaiveDate>,
+    /// Last day counted; defaults to today
+    pub to: Option<NaiveDate>,
+    /// day, week (starting Monday) or month; defaults to month
+    pub period: Option<String>,
+}
+
+impl KpiFilter {
+    pub fn period(&self) -> &str {
+        self.period.as_deref().unwrap_or(KPI_PERIOD_MONTH)
+    }
+}
+
+/// Orders due to ship in the period, by requested ship date. Orders not yet shipped
+/// in full are only counted once their date has passed.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct OtifKpi {
+    pub warehouse_id: i32,
+    pub period_start: NaiveDate,
+    pub orders_due: i64,
+    /// First shipment left on or before the requested date
+    pub on_time: i64,
+    /// Every line shipped in full
+    pub in_full: i64,
+    /// Shipped in full by the requested date
+    pub on_time_in_full: i64,
+    pub otif_pct: Option<Decimal>,
+}
+
+/// Orders by the date of their first shipment, and how much of them that shipment covered
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct FillRateKpi {
+    pub warehouse_id: i32,
+    pub period_start: NaiveDate,
+    pub orders_shipped: i64,
+    /// Orders complete in their first shipment
+    pub orders_filled: i64,
+    pub order_fill_pct: Option<Decimal>,
+    pub lines_ordered: i64,
+    pub lines_filled: i64,
+    pub line_fill_pct: Option<Decimal>,
+    pub units_ordered: Decimal,
+    pub units_shipped: Decimal,
+    pub unit_fill_pct: Option<Decimal>,
+}
+
+/// Receipts by the date they were fully received: hours from unloading starting, when the
+/// receiving task was claimed, to the last line being posted to stock
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DockToStockKpi {
+    pub warehouse_id: i32,
+    pub period_start: NaiveDate,
+    pub receipts: i64,
+    pub avg_hours: Decimal,
+    pub median_hours: Decimal,
+    pub max_hours: Decimal,
+}
+
+/// Completed count tasks: the counted quantity against the quantity on record
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct InventoryAccuracyKpi {
+    pub warehouse_id: i32,
+    pub period_start: NaiveDate,
+    pub counts: i64,
+    /// Counts that matched the record exactly
+    pub accurate_counts: i64,
+    pub accuracy_pct: Decimal,
+    pub units_expected: Decimal,
+    /// Units over or short, summed without sign
+    pub units_variance: Decimal,
+    pub variance_pct: Option<Decimal>,
+}
@@ -0,0 +1,38 @@
+//! Labor productivity reporting over completed operator tasks
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Tasks started outside every shift of their warehouse are reported under this code
+pub const SHIFT_UNSCHEDULED: &str = "UNSCHEDULED";
+
+/// One operator's output during one shift
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct LaborProductivity {
+    pub operator_id: i32,
+    pub warehouse_id: i32,
+    pub shift_code: String,
+    pub shift_date: NaiveDate,
+    pub tasks_completed: i64,
+    pub picks: i64,
+    pub receipts: i64,
+    /// Time between claim and completion, summed over all tasks
+    pub hours_worked: Decimal,
+    pub picks_per_hour: Option<Decimal>,
+    pub receipts_per_hour: Option<Decimal>,
+    /// Share of tasks completed for the full requested quantity
+    pub accuracy_pct: Decimal,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LaborReportFilter {
+    pub warehouse_id: Option<i32>,
+    pub operator_id: Option<i32>,
+    pub shift_id: Option<i32>,
+    /// Tasks completed at or after; defaults to seven days ago
+    pub from: Option<DateTime<Utc>>,
+    /// Tasks completed before; defaults to now
+    pub to: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,132 @@
+//! Double-entry inventory ledger models
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::STOCK_QUARANTINE;
+
+// Inventory buckets: stock the warehouse is accountable for
+pub const ACCOUNT_ON_HAND: &str = "ON_HAND";
+pub const ACCOUNT_RESERVED: &str = "RESERVED";
+pub const ACCOUNT_QUARANTINE: &str = "QUARANTINE";
+pub const ACCOUNT_IN_TRANSIT: &str = "IN_TRANSIT";
+pub const ACCOUNT_ON_LOAN: &str = "ON_LOAN";
+pub const ACCOUNT_SCRAP: &str = "SCRAP";
+
+// Contra accounts: where goods come from or go to
+pub const ACCOUNT_SUPPLIER: &str = "SUPPLIER";
+pub const ACCOUNT_CUSTOMER: &str = "CUSTOMER";
+pub const ACCOUNT_CONSUMED: &str = "CONSUMED";
+/// Clearing account for two-legged moves between buckets or owners; nets to zero
+pub const ACCOUNT_TRANSFER: &str = "TRANSFER";
+pub const ACCOUNT_ADJUSTMENT: &str = "ADJUSTMENT";
+pub const ACCOUNT_OPENING: &str = "OPENING";
+
+pub const REFERENCE_DELIVERY: &str = "DELIVERY";
+
+/// Bucket a movement's quantity is posted to
+pub fn bucket_account(stock_status: &str) -> &'static str {
+    if stock_status == STOCK_QUARANTINE {
+        ACCOUNT_QUARANTINE
+    } else {
+        ACCOUNT_ON_HAND
+    }
+}
+
+/// Account that balances a movement of `movement_type` against its bucket
+pub fn contra_account(movement_type: &str) -> &'static str {
+    match movement_type {
+        "RECEIPT" | "RFID_IN" => ACCOUNT_SUPPLIER,
+        "RETURN_RECEIPT" | "RFID_OUT" => ACCOUNT_CUSTOMER,
+        // Shipped goods stay in transit until the carrier reports delivery
+        "SHIPMENT" => ACCOUNT_IN_TRANSIT,
+        // Transferred goods are in transit between leaving the source and arriving
+        "TRANSFER_OUT" | "TRANSFER_IN" | "TRANSFER_RETURN" => ACCOUNT_IN_TRANSIT,
+        "ISSUE" | "PACKAGING" | "CONSUMPTION" | "PROJECT_ISSUE" => ACCOUNT_CONSUMED,
+        "QC_REJECT" | "RETURN_SCRAP" | "REPAIR_SCRAP" => ACCOUNT_SCRAP,
+        "QC_RELEASE" | "RETURN_RESTOCK" | "CONSIGNMENT_CONSUME" | "RECALL_HOLD" | "REPAIR_HOLD" | "REPAIR_RESTOCK" => {
+            ACCOUNT_TRANSFER
+        }
+        "LOAN_OUT" | "LOAN_RETURN" => ACCOUNT_ON_LOAN,
+        _ => ACCOUNT_ADJUSTMENT,
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub entry_id: i64,
+    pub transaction_id: i64,
+    pub item_id: i32,
+    pub warehouse_id: i32,
+    pub consignor_id: Option<i32>,
+    pub account: String,
+    /// Signed: positive debits the account, negative credits it
+    pub quantity: Decimal,
+}
+
+/// One leg of a transaction to post
+#[derive(Debug, Clone)]
+pub struct NewLedgerEntry {
+    pub item_id: i32,
+    pub warehouse_id: i32,
+    pub consignor_id: Option<i32>,
+    pub account: &'static str,
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct AccountBalance {
+    pub account: String,
+    pub quantity: Decimal,
+}
+
+/// A transaction whose entries do not sum to zero
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct UnbalancedTransaction {
+    pub transaction_id: i64,
+    pub movement_id: Option<i64>,
+    pub reference_type: Option<String>,
+    pub reference_number: Option<String>,
+    pub posted_at: DateTime<Utc>,
+    pub imbalance: Decimal,
+}
+
+/// A bucket whose ledger balance differs from the stock balance
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct BucketMismatch {
+    pub item_id: i32,
+    pub warehouse_id: i32,
+    pub consignor_id: Option<i32>,
+    pub account: String,
+    pub ledger_quantity: Decimal,
+    pub stock_quantity: Decimal,
+}
+
+/// Transfer legs of an item in a warehouse that have no matching opposite leg
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct OpenTransfer {
+    pub item_id: i32,
+    pub warehouse_id: i32,
+    pub quantity: Decimal,
+}
+
+/// Result of checking the ledger: it reconciles when every transaction balances, every
+/// bucket matches the stock balances and every transfer has both legs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerIntegrity {
+    pub reconciled: bool,
+    pub trial_balance: Vec<AccountBalance>,
+    pub unbalanced_transactions: Vec<UnbalancedTransaction>,
+    pub bucket_mismatches: Vec<BucketMismatch>,
+    pub open_transfers: Vec<OpenTransfer>,
+}
+
+/// Filters for ledger entry queries
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LedgerFilter {
+    pub item_id: Option<i32>,
+    pub warehouse_id: Option<i32>,
+    pub account: Option<String>,
+}
@@ -1,15 +1,174 @@
 //! Warehouse Management System - Data Models
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
-use validator::Validate;
+use std::collections::BTreeMap;
+use validator::{Validate, ValidationError};
 
 // Re-export common types
 pub use chrono;
 pub use rust_decimal;
 pub use validator;
 
+pub mod api_keys;
+pub mod audit;
+pub mod availability;
+pub mod backups;
+pub mod billing;
+pub mod cartonization;
+pub mod catch_weight;
+pub mod clients;
+pub mod consignment;
+pub mod consumption;
+pub mod cron;
+pub mod customs;
+pub mod deliveries;
+pub mod dock;
+pub mod documents;
+pub mod ecommerce;
+pub mod edi;
+pub mod environment;
+pub mod erp;
+pub mod fleet;
+pub mod formats;
+pub mod gl;
+pub mod hazmat;
+pub mod integrations;
+pub mod integrity;
+pub mod jobs;
+pub mod kpis;
+pub mod labor;
+pub mod ledger;
+pub mod locations;
+pub mod loans;
+pub mod lpns;
+pub mod migrations;
+pub mod money;
+pub mod movements;
+pub mod negative_stock;
+pub mod notifications;
+pub mod orders;
+pub mod packaging;
+pub mod postings;
+pub mod preferences;
+pub mod printers;
+pub mod projects;
+pub mod purchasing;
+pub mod quality;
+pub mod recalls;
+pub mod reconciliation;
+pub mod receipts;
+pub mod repairs;
+pub mod reports;
+pub mod reservations;
+pub mod returnables;
+pub mod returns;
+pub mod read_models;
+pub mod replay;
+pub mod rfid;
+pub mod schema_changes;
+pub mod sagas;
+pub mod shifts;
+pub mod stations;
+pub mod stock;
+pub mod stock_outs;
+pub mod sync;
+pub mod tasks;
+pub mod templates;
+pub mod tenants;
+pub mod tracking;
+pub mod transfers;
+pub mod users;
+pub mod utilization;
+pub mod views;
+pub mod vmi;
+pub mod waves;
+
+pub use api_keys::*;
+pub use audit::*;
+pub use availability::*;
+pub use backups::*;
+pub use billing::*;
+pub use cartonization::*;
+pub use catch_weight::*;
+pub use clients::*;
+pub use consignment::*;
+pub use consumption::*;
+pub use cron::*;
+pub use customs::*;
+pub use deliveries::*;
+pub use dock::*;
+pub use documents::*;
+pub use ecommerce::*;
+pub use edi::*;
+pub use environment::*;
+pub use erp::*;
+pub use fleet::*;
+pub use formats::*;
+pub use gl::*;
+pub use hazmat::*;
+pub use integrations::*;
+pub use integrity::*;
+pub use jobs::*;
+pub use kpis::*;
+pub use labor::*;
+pub use ledger::*;
+pub use locations::*;
+pub use loans::*;
+pub use lpns::*;
+pub use migrations::*;
+pub use money::*;
+pub use movements::*;
+pub use negative_stock::*;
+pub use notifications::*;
+pub use orders::*;
+pub use packaging::*;
+pub use postings::*;
+pub use preferences::*;
+pub use printers::*;
+pub use projects::*;
+pub use purchasing::*;
+pub use quality::*;
+pub use recalls::*;
+pub use reconciliation::*;
+pub use receipts::*;
+pub use repairs::*;
+pub use reports::*;
+pub use reservations::*;
+pub use returnables::*;
+pub use returns::*;
+pub use read_models::*;
+pub use replay::*;
+pub use rfid::*;
+pub use schema_changes::*;
+pub use sagas::*;
+pub use shifts::*;
+pub use stations::*;
+pub use stock::*;
+pub use stock_outs::*;
+pub use sync::*;
+pub use tasks::*;
+pub use templates::*;
+pub use tenants::*;
+pub use tracking::*;
+pub use transfers::*;
+pub use users::*;
+pub use utilization::*;
+pub use views::*;
+pub use vmi::*;
+pub use waves::*;
+
+/// Quantities in requests must be strictly positive; direction comes from the operation
+pub(crate) fn validate_positive(quantity: &Decimal) -> Result<(), ValidationError> {
+    if quantity.is_sign_positive() && !quantity.is_zero() {
+        Ok(())
+    } else {
+        Err(ValidationError::new("must_be_positive"))
+    }
+}
+
 // ============================================================================
 // WAREHOUSE MODELS
 // ============================================================================
@@ -38,8 +197,9 @@ pub struct Warehouse {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_create_warehouse_address"))]
 pub struct CreateWarehouse {
-    #[validate(length(min = 1, max = 50))]
+    #[validate(custom(function = "validate_warehouse_code"))]
     pub warehouse_code: String,
     #[validate(length(min = 1, max = 255))]
     pub warehouse_name: String,
@@ -47,17 +207,22 @@ pub struct CreateWarehouse {
     pub address: Option<String>,
     pub city: Option<String>,
     pub state: Option<String>,
+    /// Checked against the layout of `country`
     pub postal_code: Option<String>,
     pub country: Option<String>,
     #[validate(email)]
     pub email: Option<String>,
-    #[validate(length(max = 20))]
+    /// Any common notation; kept in E.164 form
+    #[validate(length(max = 20), custom(function = "validate_phone"))]
     pub phone: Option<String>,
     pub manager_user_id: Option<i32>,
+    /// IANA zone name, e.g. `Asia/Jakarta`
+    #[validate(custom(function = "validate_timezone"))]
     pub timezone: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_update_warehouse_address"))]
 pub struct UpdateWarehouse {
     #[validate(length(min = 1, max = 255))]
     pub warehouse_name: Option<String>,
@@ -69,20 +234,48 @@ pub struct UpdateWarehouse {
     pub country: Option<String>,
     #[validate(email)]
     pub email: Option<String>,
-    #[validate(length(max = 20))]
+    #[validate(length(max = 20), custom(function = "validate_phone"))]
     pub phone: Option<String>,
     pub manager_user_id: Option<i32>,
+    #[validate(custom(function = "validate_timezone"))]
     pub timezone: Option<String>,
 }
 
+fn validate_create_warehouse_address(warehouse: &CreateWarehouse) -> Result<(), ValidationError> {
+    match &warehouse.postal_code {
+        Some(code) if !postal_code_fits(warehouse.country.as_deref(), code) => Err(invalid_postal_code("postal_code")),
+        _ => Ok(()),
+    }
+}
+
+fn validate_update_warehouse_address(warehouse: &UpdateWarehouse) -> Result<(), ValidationError> {
+    match &warehouse.postal_code {
+        Some(code) if !postal_code_fits(warehouse.country.as_deref(), code) => Err(invalid_postal_code("postal_code")),
+        _ => Ok(()),
+    }
+}
+
 // Rest of the models remain the same...
 
-#[derive(Debug, Serialize)]
+/// Hypermedia links keyed by relation name (`self`, `next`, `prev`, `movements`, ...)
+pub type Links = BTreeMap<String, String>;
+
+/// A resource together with links to itself and related resources
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Linked<T> {
+    #[serde(flatten)]
+    pub resource: T,
+    pub links: Links,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub message: Option<String>,
     pub timestamp: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub links: Links,
 }
 
 impl<T> ApiResponse<T> {
@@ -92,6 +285,7 @@ impl<T> ApiResponse<T> {
             data: Some(data),
             message: None,
             timestamp: Utc::now(),
+            links: Links::new(),
         }
     }
     
@@ -101,6 +295,7 @@ impl<T> ApiResponse<T> {
             data: Some(data),
             message: Some(message),
             timestamp: Utc::now(),
+            links: Links::new(),
         }
     }
     
@@ -110,17 +305,24 @@ impl<T> ApiResponse<T> {
             data: None,
             message: Some(message),
             timestamp: Utc::now(),
+            links: Links::new(),
         }
     }
+
+    pub fn with_links(mut self, links: Links) -> Self {
+        self.links = links;
+        self
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PaginationQuery {
     pub page: Option<i64>,
     pub limit: Option<i64>,
     pub search: Option<String>,
     pub sort_by: Option<String>,
     pub sort_order: Option<String>,
+    pub count: Option<CountMode>,
 }
 
 impl Default for PaginationQuery {
@@ -131,14 +333,32 @@ impl Default for PaginationQuery {
             search: None,
             sort_by: None,
             sort_order: Some("ASC".to_string()),
+            count: None,
         }
     }
 }
 
-#[derive(Debug, Serialize)]
+/// How the total row count of a list query is obtained (`?count=`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CountMode {
+    /// Full `COUNT(*)` over the filtered rows
+    #[default]
+    #[serde(alias = "true")]
+    Exact,
+    /// Planner estimate from `pg_class.reltuples`, ignoring filters
+    Estimate,
+    /// No total at all; only whether a next page exists
+    #[serde(alias = "false", alias = "none")]
+    Skip,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PaginatedResponse<T> {
     pub data: Vec<T>,
     pub pagination: PaginationMeta,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub links: Links,
 }
 
 impl<T> PaginatedResponse<T> {
@@ -146,35 +366,151 @@ impl<T> PaginatedResponse<T> {
         Self {
             data,
             pagination: PaginationMeta::new(total, page, limit),
+            links: Links::new(),
+        }
+    }
+
+    /// Build a page from rows fetched with one extra probe row (`LIMIT limit + 1`).
+    /// The probe row is dropped and only used to decide `has_next`.
+    pub fn from_probe(
+        mut data: Vec<T>,
+        total: Option<i64>,
+        mode: CountMode,
+        page: i64,
+        limit: i64,
+    ) -> Self {
+        let has_next = data.len() as i64 > limit;
+        data.truncate(limit.max(0) as usize);
+
+        let pagination = match (mode, total) {
+            (CountMode::Exact, Some(total)) => PaginationMeta::new(total, page, limit),
+            (CountMode::Estimate, Some(total)) => PaginationMeta::estimated(total, page, limit, has_next),
+            _ => PaginationMeta::without_total(page, limit, has_next),
+        };
+
+        Self {
+            data,
+            pagination,
+            links: Links::new(),
+        }
+    }
+
+    pub fn with_links(mut self, links: Links) -> Self {
+        self.links = links;
+        self
+    }
+
+    /// Transform every row while keeping pagination and links
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> PaginatedResponse<U> {
+        PaginatedResponse {
+            data: self.data.into_iter().map(f).collect(),
+            pagination: self.pagination,
+            links: self.links,
         }
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PaginationMeta {
-    pub total: i64,
+    pub total: Option<i64>,
     pub page: i64,
     pub limit: i64,
-    pub total_pages: i64,
+    pub total_pages: Option<i64>,
     pub has_next: bool,
     pub has_prev: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub total_estimated: bool,
 }
 
 impl PaginationMeta {
     pub fn new(total: i64, page: i64, limit: i64) -> Self {
         let total_pages = if limit > 0 { (total + limit - 1) / limit } else { 0 };
         Self {
-            total,
+            total: Some(total),
             page,
             limit,
-            total_pages,
+            total_pages: Some(total_pages),
             has_next: page < total_pages,
             has_prev: page > 1,
+            total_estimated: false,
+        }
+    }
+
+    /// Metadata with an approximate total; `has_next` still comes from the probe row
+    pub fn estimated(total: i64, page: i64, limit: i64, has_next: bool) -> Self {
+        Self {
+            has_next,
+            total_estimated: true,
+            ..Self::new(total, page, limit)
+        }
+    }
+
+    /// Metadata for a page fetched without counting rows
+    pub fn without_total(page: i64, limit: i64, has_next: bool) -> Self {
+        Self {
+            total: None,
+            page,
+            limit,
+            total_pages: None,
+            has_next,
+            has_prev: page > 1,
+            total_estimated: false,
         }
     }
 }
 
-#[derive(Debug, Serialize)]
+/// Keyset (cursor) pagination for append-only history, newest first (`?after_id=&limit=`)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct KeysetQuery {
+    pub after_id: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeysetPage<T> {
+    pub data: Vec<T>,
+    pub limit: i64,
+    pub has_more: bool,
+    /// Pass as `after_id` to fetch the next (older) page
+    pub next_after_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub links: Links,
+}
+
+impl<T> KeysetPage<T> {
+    /// Build a page from rows fetched with one extra probe row (`LIMIT limit + 1`)
+    pub fn from_probe(mut data: Vec<T>, limit: i64, key: impl Fn(&T) -> i64) -> Self {
+        let has_more = data.len() as i64 > limit;
+        data.truncate(limit.max(0) as usize);
+        let next_after_id = if has_more { data.last().map(key) } else { None };
+
+        Self {
+            data,
+            limit,
+            has_more,
+            next_after_id,
+            links: Links::new(),
+        }
+    }
+
+    pub fn with_links(mut self, links: Links) -> Self {
+        self.links = links;
+        self
+    }
+
+    /// Transform every row while keeping the cursor and links
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> KeysetPage<U> {
+        KeysetPage {
+            data: self.data.into_iter().map(f).collect(),
+            limit: self.limit,
+            has_more: self.has_more,
+            next_after_id: self.next_after_id,
+            links: self.links,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct HealthStatus {
     pub status: String,
     pub timestamp: DateTime<Utc>,
@@ -183,13 +519,13 @@ pub struct HealthStatus {
     pub uptime: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct HealthServices {
     pub database: ServiceHealth,
     pub redis: ServiceHealth,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ServiceHealth {
     pub status: String,
     pub response_time_ms: Option<u64>,
@@ -200,7 +536,7 @@ pub struct ServiceHealth {
 // ITEM MODELS (Complete Implementation)
 // ============================================================================
 
-#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Item {
     pub item_id: i32,
     pub item_code: String,
@@ -225,14 +561,29 @@ pub struct Item {
     pub is_loanable: bool,
     pub requires_return: bool,
     pub max_loan_duration_days: Option<i32>,
-    pub replacement_cost: Option<Decimal>,
+    pub replacement_cost: Option<Money>,
     pub maintenance_required: bool,
     pub calibration_required: bool,
     
-    // Financial
-    pub standard_cost: Option<Decimal>,
-    pub last_cost: Option<Decimal>,
-    pub average_cost: Option<Decimal>,
+    // Financial, all in the item's cost currency
+    pub standard_cost: Option<Money>,
+    pub last_cost: Option<Money>,
+    pub average_cost: Option<Money>,
+    
+    // Dangerous goods
+    pub un_number: Option<String>,
+    pub hazard_class: Option<String>,
+    pub packing_group: Option<String>,
+    pub proper_shipping_name: Option<String>,
+    
+    // Catch weight
+    pub catch_weight_nominal_kg: Option<Decimal>,
+    pub catch_weight_tolerance_pct: Option<Decimal>,
+    
+    // Customs
+    pub hs_code: Option<String>,
+    pub country_of_origin: Option<String>,
+    pub customs_description: Option<String>,
     
     pub status: String,
     pub created_at: Option<DateTime<Utc>>,
@@ -258,11 +609,21 @@ pub struct CreateItem {
     pub is_loanable: Option<bool>,
     pub maintenance_required: Option<bool>,
     pub calibration_required: Option<bool>,
-    pub replacement_cost: Option<Decimal>,
+    /// Its currency becomes the item's cost currency
+    #[validate(nested)]
+    pub replacement_cost: Option<Money>,
+    /// Classification for dangerous goods
+    #[validate(nested)]
+    pub hazmat: Option<HazmatClassification>,
+    /// Customs data for international shipments
+    #[validate(nested)]
+    pub customs: Option<CustomsData>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct UpdateItem {
+    #[validate(length(min = 1, max = 100))]
+    pub item_code: Option<String>,
     #[validate(length(min = 1, max = 255))]
     pub item_name: Option<String>,
     pub item_description: Option<String>,
@@ -272,27 +633,34 @@ pub struct UpdateItem {
     pub brand: Option<String>,
     pub model: Option<String>,
     pub unit: Option<String>,
-    pub replacement_cost: Option<Decimal>,
+    #[validate(nested)]
+    pub replacement_cost: Option<Money>,
 }
 
 // ============================================================================
 // STOCK INVENTORY MODELS
 // ============================================================================
 
-#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StockInventory {
     pub stock_id: i32,
     pub item_id: i32,
     pub warehouse_id: i32,
+    /// Consignor that owns this stock; None for our own
+    pub consignor_id: Option<i32>,
+    /// 3PL client the stock is stored for; None for our own
+    pub client_id: Option<i32>,
     pub quantity_on_hand: Decimal,
     pub quantity_reserved: Decimal,
     pub quantity_available: Option<Decimal>,
     pub min_stock_level: Option<Decimal>,
     pub max_stock_level: Option<Decimal>,
     pub reorder_point: Option<Decimal>,
-    pub unit_cost: Option<Decimal>,
-    pub average_cost: Option<Decimal>,
-    pub total_value: Option<Decimal>,
+    pub unit_cost: Option<Money>,
+    pub average_cost: Option<Money>,
+    pub total_value: Option<Money>,
+    /// Actual weight of the pieces held, for catch-weight items
+    pub catch_weight_kg: Option<Decimal>,
     pub last_movement_date: Option<NaiveDate>,
     pub last_receipt_date: Option<NaiveDate>,
     pub last_issue_date: Option<NaiveDate>,
@@ -300,6 +668,39 @@ pub struct StockInventory {
     pub updated_at: Option<DateTime<Utc>>,
 }
 
+/// Issue our own available stock of an item out of a warehouse
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct IssueStock {
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+    /// Work order, requisition or delivery note the goods went out against
+    #[validate(length(min = 1, max = 100))]
+    pub reference: Option<String>,
+    #[validate(length(max = 1000))]
+    pub notes: Option<String>,
+}
+
+/// Create or update an item's own stock row in a warehouse. Quantities only move through
+/// stock movements, so a row is created empty; omitted levels are left as they are.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_upsert_stock"))]
+pub struct UpsertStock {
+    pub min_stock_level: Option<Decimal>,
+    pub max_stock_level: Option<Decimal>,
+    pub reorder_point: Option<Decimal>,
+}
+
+fn validate_upsert_stock(stock: &UpsertStock) -> Result<(), ValidationError> {
+    let levels = [stock.min_stock_level, stock.max_stock_level, stock.reorder_point];
+    if levels.iter().flatten().any(|level| level.is_sign_negative()) {
+        return Err(ValidationError::new("stock_level_negative"));
+    }
+    match (stock.min_stock_level, stock.max_stock_level) {
+        (Some(min), Some(max)) if min > max => Err(ValidationError::new("min_stock_level_above_max_stock_level")),
+        _ => Ok(()),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ItemWithStock {
     #[serde(flatten)]
@@ -0,0 +1,96 @@
+//! Tools lent out to people on site and expected back by a due date, with the loans still
+//! out after it flagged overdue
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+use crate::validate_positive;
+
+// Loan statuses
+pub const LOAN_OUT: &str = "OUT";
+/// Still out after its due date
+pub const LOAN_OVERDUE: &str = "OVERDUE";
+pub const LOAN_RETURNED: &str = "RETURNED";
+pub const LOAN_STATUSES: [&str; 3] = [LOAN_OUT, LOAN_OVERDUE, LOAN_RETURNED];
+
+/// Movement type taking a lent tool out of available stock
+pub const MOVEMENT_LOAN_OUT: &str = "LOAN_OUT";
+/// Movement type putting a returned tool back into available stock
+pub const MOVEMENT_LOAN_RETURN: &str = "LOAN_RETURN";
+
+pub const REFERENCE_LOAN: &str = "LOAN";
+
+pub const NOTIFY_LOAN_OVERDUE: &str = "LOAN_OVERDUE";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ToolLoan {
+    pub loan_id: i32,
+    pub loan_number: String,
+    pub warehouse_id: i32,
+    pub item_id: i32,
+    pub quantity: Decimal,
+    /// Serial number the tool carries on the stock ledger, for serialized tools
+    pub serial_number: Option<String>,
+    pub borrower_name: String,
+    pub project_id: Option<i32>,
+    pub due_on: NaiveDate,
+    pub status: String,
+    pub notes: Option<String>,
+    pub loaned_at: DateTime<Utc>,
+    /// When the overdue scan flagged the loan
+    pub overdue_at: Option<DateTime<Utc>>,
+    pub returned_at: Option<DateTime<Utc>>,
+}
+
+impl ToolLoan {
+    pub fn is_returned(&self) -> bool {
+        self.status == LOAN_RETURNED
+    }
+}
+
+/// Lend a loanable item out of a warehouse's available stock
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct CreateLoan {
+    pub warehouse_id: i32,
+    pub item_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+    /// A serialized tool is lent one unit at a time
+    #[validate(length(min = 1, max = 50))]
+    pub serial_number: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub borrower_name: String,
+    pub project_id: Option<i32>,
+    /// Defaults to the item's maximum loan duration from today
+    pub due_on: Option<NaiveDate>,
+    #[validate(length(max = 1000))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LoanFilter {
+    /// OUT, OVERDUE or RETURNED
+    pub status: Option<String>,
+    pub item_id: Option<i32>,
+    pub borrower_name: Option<String>,
+}
+
+/// A loan past its due date, for supervisors chasing tools back
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct OverdueLoan {
+    pub loan_id: i32,
+    pub loan_number: String,
+    pub warehouse_id: i32,
+    pub item_id: i32,
+    pub item_code: String,
+    pub quantity: Decimal,
+    pub serial_number: Option<String>,
+    pub borrower_name: String,
+    pub project_id: Option<i32>,
+    pub due_on: NaiveDate,
+    pub days_overdue: i32,
+    pub overdue_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,67 @@
+//! Zones and storage/pick locations within a warehouse
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+use crate::validate_positive;
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Zone {
+    pub zone_id: i32,
+    pub warehouse_id: i32,
+    pub zone_code: String,
+    pub zone_name: String,
+    pub pick_sequence: i32,
+    pub created_at: DateTime<Utc>,
+    /// Hazard classes approved for storage here; empty for general stock only
+    pub hazard_classes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateZone {
+    #[validate(length(min = 1, max = 20))]
+    pub zone_code: String,
+    #[validate(length(min = 1, max = 100))]
+    pub zone_name: String,
+    /// Position on the pick path; lower zones are walked first
+    #[serde(default)]
+    pub pick_sequence: i32,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Location {
+    pub location_id: i32,
+    pub zone_id: i32,
+    pub location_code: String,
+    pub pick_sequence: i32,
+    /// Item this location is the pick face for
+    pub item_id: Option<i32>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    /// Cubic metres of goods the location holds
+    pub max_volume_cbm: Option<Decimal>,
+    /// Pallets, cases or totes that fit
+    pub max_positions: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateLocation {
+    #[validate(length(min = 1, max = 30))]
+    pub location_code: String,
+    /// Position on the pick path within the zone
+    #[serde(default)]
+    pub pick_sequence: i32,
+    pub item_id: Option<i32>,
+}
+
+/// Replaces the location's capacity; an omitted measure is unset
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetLocationCapacity {
+    #[validate(custom(function = "validate_positive"))]
+    pub max_volume_cbm: Option<Decimal>,
+    #[validate(range(min = 1))]
+    pub max_positions: Option<i32>,
+}
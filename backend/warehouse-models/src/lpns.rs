@@ -0,0 +1,121 @@
+//! License plates (LPNs): pallets, cases and containers grouping stock under one barcode
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+pub const LPN_PALLET: &str = "PALLET";
+pub const LPN_CASE: &str = "CASE";
+pub const LPN_TOTE: &str = "TOTE";
+pub const LPN_CONTAINER: &str = "CONTAINER";
+pub const LPN_TYPES: &[&str] = &[LPN_PALLET, LPN_CASE, LPN_TOTE, LPN_CONTAINER];
+
+pub const LPN_ACTIVE: &str = "ACTIVE";
+pub const LPN_SHIPPED: &str = "SHIPPED";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Lpn {
+    pub lpn_id: i32,
+    pub lpn_number: String,
+    pub lpn_type: String,
+    pub warehouse_id: i32,
+    pub location_id: Option<i32>,
+    pub parent_lpn_id: Option<i32>,
+    pub status: String,
+    /// Receipt the LPN arrived on
+    pub receipt_id: Option<i32>,
+    /// Shipment the LPN left on
+    pub shipment_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct LpnContent {
+    pub item_id: i32,
+    pub item_code: String,
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LpnDetail {
+    #[serde(flatten)]
+    pub lpn: Lpn,
+    /// Units directly on the LPN
+    pub contents: Vec<LpnContent>,
+    /// LPNs nested directly inside this one
+    pub children: Vec<Lpn>,
+    /// Everything on the LPN, nested LPNs included
+    pub totals: Vec<LpnContent>,
+}
+
+/// Start an empty LPN; stock is added with the contents endpoint or by receiving
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateLpn {
+    /// Pre-printed or supplier barcode; numbered `LPN00000001` onwards when omitted
+    #[validate(length(min = 1, max = 30))]
+    pub lpn_number: Option<String>,
+    #[validate(custom(function = "validate_lpn_type"))]
+    pub lpn_type: String,
+    pub location_id: Option<i32>,
+    /// Nest the new LPN inside this one
+    pub parent_lpn_id: Option<i32>,
+}
+
+/// Move a whole LPN, with everything nested in it, either to a location or onto another LPN
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_move"))]
+pub struct MoveLpn {
+    pub location_id: Option<i32>,
+    pub parent_lpn_id: Option<i32>,
+}
+
+/// Set the units of an item directly on an LPN; zero takes the item off
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetLpnContent {
+    #[validate(custom(function = "validate_not_negative"))]
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LpnFilter {
+    pub status: Option<String>,
+    pub location_id: Option<i32>,
+    /// Scan lookup by barcode
+    pub lpn_number: Option<String>,
+}
+
+/// Receive every ASN line labelled with an LPN in full, onto that LPN
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct ReceiveLpn {
+    /// Defaults to a pallet
+    #[validate(custom(function = "validate_lpn_type"))]
+    pub lpn_type: Option<String>,
+    pub location_id: Option<i32>,
+}
+
+fn validate_lpn_type(lpn_type: &str) -> Result<(), ValidationError> {
+    if LPN_TYPES.contains(&lpn_type) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_lpn_type"))
+    }
+}
+
+fn validate_move(target: &MoveLpn) -> Result<(), ValidationError> {
+    if target.location_id.is_some() == target.parent_lpn_id.is_some() {
+        Err(ValidationError::new("move_to_one_location_or_lpn"))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_not_negative(quantity: &Decimal) -> Result<(), ValidationError> {
+    if quantity.is_sign_negative() {
+        Err(ValidationError::new("must_not_be_negative"))
+    } else {
+        Ok(())
+    }
+}
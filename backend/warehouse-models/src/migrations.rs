@@ -0,0 +1,45 @@
+//! Schema migration state, as embedded in the binary and as recorded in the database
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A migration known to the binary, the database, or both
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationEntry {
+    pub version: i64,
+    pub description: String,
+    /// SHA-384 of the migration SQL, hex encoded; the recorded one for applied migrations
+    pub checksum: String,
+    pub applied: bool,
+    pub applied_at: Option<DateTime<Utc>>,
+    pub success: Option<bool>,
+    pub execution_time_ms: Option<i64>,
+    /// `false` when the applied migration differs from the file shipped with the binary,
+    /// or is unknown to it
+    pub checksum_matches: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationStatus {
+    /// Highest successfully applied version
+    pub current_version: Option<i64>,
+    pub applied: usize,
+    pub pending: usize,
+    /// Applied migrations whose recorded checksum no longer matches the binary
+    pub mismatched: usize,
+    pub migrations: Vec<MigrationEntry>,
+}
+
+impl MigrationStatus {
+    pub fn pending_versions(&self) -> Vec<i64> {
+        self.migrations.iter().filter(|migration| !migration.applied).map(|migration| migration.version).collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationRun {
+    /// Versions applied by this run
+    pub applied_versions: Vec<i64>,
+    #[serde(flatten)]
+    pub status: MigrationStatus,
+}
@@ -0,0 +1,37 @@
+//! Amounts of money. Costs are stored as an amount column beside the currency column of the
+//! row they belong to, and travel through the API as `{"amount": "15000.00", "currency": "IDR"}`
+//! so no figure is read without its currency.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+/// Currency of costs recorded without one
+pub const DEFAULT_CURRENCY: &str = "IDR";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Validate)]
+pub struct Money {
+    pub amount: Decimal,
+    /// ISO 4217 code, e.g. `IDR` or `USD`
+    #[validate(custom(function = "validate_currency"))]
+    pub currency: String,
+}
+
+impl Money {
+    pub fn new(amount: Decimal, currency: &str) -> Self {
+        Self { amount, currency: currency.to_string() }
+    }
+
+    /// An amount column read together with its row's currency column
+    pub fn from_column(amount: Option<Decimal>, currency: &str) -> Option<Self> {
+        amount.map(|amount| Self::new(amount, currency))
+    }
+}
+
+fn validate_currency(currency: &str) -> Result<(), ValidationError> {
+    if currency.len() == 3 && currency.bytes().all(|b| b.is_ascii_uppercase()) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_currency"))
+    }
+}
@@ -0,0 +1,68 @@
+//! Stock movement ledger models
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StockMovement {
+    pub movement_id: i64,
+    pub item_id: i32,
+    pub warehouse_id: i32,
+    pub movement_type: String,
+    /// Signed: positive into stock, negative out of stock
+    pub quantity: Decimal,
+    /// Actual weight moved for catch-weight items, signed like `quantity`
+    pub catch_weight_kg: Option<Decimal>,
+    /// Lot or serial number of the units moved
+    pub lot_number: Option<String>,
+    pub unit_cost: Option<Decimal>,
+    /// Stock bucket the quantity applies to (`AVAILABLE` or `QUARANTINE`)
+    pub stock_status: String,
+    /// Owner of the stock moved; None for our own
+    pub consignor_id: Option<i32>,
+    /// 3PL client the goods are stored for; None for our own
+    pub client_id: Option<i32>,
+    pub reference_type: Option<String>,
+    pub reference_number: Option<String>,
+    pub notes: Option<String>,
+    pub movement_date: DateTime<Utc>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub created_by: Option<i32>,
+}
+
+pub const STOCK_AVAILABLE: &str = "AVAILABLE";
+pub const STOCK_QUARANTINE: &str = "QUARANTINE";
+
+/// Movement type of stock issued out of a warehouse against a work order or requisition
+pub const MOVEMENT_ISSUE: &str = "ISSUE";
+
+/// A movement to post; the stock balance of its bucket moves by `quantity`
+#[derive(Debug, Clone)]
+pub struct NewMovement {
+    pub item_id: i32,
+    pub warehouse_id: i32,
+    pub movement_type: String,
+    pub quantity: Decimal,
+    /// Actual weight for catch-weight items; the nominal weight of `quantity` when None
+    pub catch_weight_kg: Option<Decimal>,
+    pub lot_number: Option<String>,
+    pub unit_cost: Option<Decimal>,
+    pub stock_status: &'static str,
+    /// Owner of the stock moved; None for our own
+    pub consignor_id: Option<i32>,
+    pub reference_type: Option<String>,
+    pub reference_number: Option<String>,
+    pub notes: Option<String>,
+    pub created_by: Option<i32>,
+}
+
+/// Filters for movement history queries
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MovementFilter {
+    pub item_id: Option<i32>,
+    pub warehouse_id: Option<i32>,
+    pub movement_type: Option<String>,
+    pub client_id: Option<i32>,
+}
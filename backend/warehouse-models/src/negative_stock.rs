@@ -0,0 +1,38 @@
+//! Negative stock policy: whether issues may take on-hand stock below zero
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+/// Reject the issue (the default)
+pub const NEGATIVE_STOCK_FORBID: &str = "FORBID";
+/// Post the issue and raise a notification
+pub const NEGATIVE_STOCK_WARN: &str = "WARN";
+/// Post the issue
+pub const NEGATIVE_STOCK_ALLOW: &str = "ALLOW";
+
+pub const NOTIFY_NEGATIVE_STOCK: &str = "NEGATIVE_STOCK";
+
+/// Policy of a warehouse, or of one item in it when `item_id` is set
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct NegativeStockPolicy {
+    pub policy_id: i32,
+    pub warehouse_id: i32,
+    pub item_id: Option<i32>,
+    pub policy: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetNegativeStockPolicy {
+    #[validate(custom(function = "validate_policy"))]
+    pub policy: String,
+}
+
+fn validate_policy(policy: &str) -> Result<(), ValidationError> {
+    match policy {
+        NEGATIVE_STOCK_FORBID | NEGATIVE_STOCK_WARN | NEGATIVE_STOCK_ALLOW => Ok(()),
+        _ => Err(ValidationError::new("unknown_negative_stock_policy")),
+    }
+}
@@ -0,0 +1,48 @@
+//! Operational notifications raised by the system for people to act on
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+pub const SEVERITY_INFO: &str = "INFO";
+pub const SEVERITY_WARNING: &str = "WARNING";
+pub const SEVERITY_CRITICAL: &str = "CRITICAL";
+
+pub const NOTIFY_DELIVERY_EXCEPTION: &str = "DELIVERY_EXCEPTION";
+pub const NOTIFY_COLD_CHAIN_EXCURSION: &str = "COLD_CHAIN_EXCURSION";
+pub const NOTIFY_VMI_FEED_FAILED: &str = "VMI_FEED_FAILED";
+pub const NOTIFY_EVENTS_DEAD_LETTERED: &str = "EVENTS_DEAD_LETTERED";
+pub const NOTIFY_INTEGRITY_VIOLATION: &str = "INTEGRITY_VIOLATION";
+pub const NOTIFY_ERP_SYNC_FAILED: &str = "ERP_SYNC_FAILED";
+pub const NOTIFY_ECOMMERCE_SYNC_FAILED: &str = "ECOMMERCE_SYNC_FAILED";
+pub const NOTIFY_REPORT_DELIVERY_FAILED: &str = "REPORT_DELIVERY_FAILED";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Notification {
+    pub notification_id: i64,
+    pub kind: String,
+    pub severity: String,
+    pub subject: String,
+    pub message: Option<String>,
+    pub reference_type: Option<String>,
+    pub reference_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewNotification {
+    pub kind: &'static str,
+    pub severity: &'static str,
+    pub subject: String,
+    pub message: Option<String>,
+    pub reference_type: Option<String>,
+    pub reference_id: Option<i32>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct NotificationFilter {
+    pub kind: Option<String>,
+    /// `false` lists only notifications nobody has acknowledged yet
+    pub acknowledged: Option<bool>,
+}
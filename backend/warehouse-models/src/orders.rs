@@ -0,0 +1,269 @@
+//! Outbound order and shipment models
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::{invalid_postal_code, postal_code_fits, validate_positive};
+
+pub const ORDER_OPEN: &str = "OPEN";
+pub const ORDER_PARTIALLY_SHIPPED: &str = "PARTIALLY_SHIPPED";
+pub const ORDER_SHIPPED: &str = "SHIPPED";
+pub const ORDER_CANCELLED: &str = "CANCELLED";
+
+/// Picked, packed and shipped from our stock
+pub const ORDER_TYPE_STANDARD: &str = "STANDARD";
+/// Routed to a supplier who ships to the customer; no stock moves
+pub const ORDER_TYPE_DROPSHIP: &str = "DROPSHIP";
+
+pub const SOURCE_API: &str = "API";
+pub const SOURCE_EDI: &str = "EDI";
+pub const SOURCE_ERP: &str = "ERP";
+pub const SOURCE_ECOMMERCE: &str = "ECOMMERCE";
+pub const SOURCE_CHANNEL: &str = "CHANNEL";
+/// Scheduled from a project's site delivery request
+pub const SOURCE_PROJECT: &str = "PROJECT";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct OutboundOrder {
+    pub order_id: i32,
+    pub order_number: String,
+    pub warehouse_id: i32,
+    pub customer_name: String,
+    pub customer_reference: Option<String>,
+    pub ship_to_name: Option<String>,
+    pub ship_to_address: Option<String>,
+    pub ship_to_city: Option<String>,
+    pub ship_to_state: Option<String>,
+    pub ship_to_postal_code: Option<String>,
+    pub ship_to_country: Option<String>,
+    pub requested_ship_date: Option<NaiveDate>,
+    pub status: String,
+    pub source: String,
+    pub edi_partner_id: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub created_by: Option<i32>,
+    /// Carrier the customer asked for
+    pub carrier_code: Option<String>,
+    /// Wave the order is being picked in
+    pub wave_id: Option<i32>,
+    pub order_type: String,
+    /// Supplier a dropship order is routed to
+    pub supplier_code: Option<String>,
+    /// Supplier's own order number, once they confirm a dropship order
+    pub supplier_order_number: Option<String>,
+    pub supplier_confirmed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct OrderLine {
+    pub line_id: i32,
+    pub order_id: i32,
+    pub line_number: i32,
+    pub item_id: i32,
+    pub quantity_ordered: Decimal,
+    pub quantity_shipped: Decimal,
+    /// Packed into cartons at wave packing
+    pub quantity_packed: Decimal,
+    /// Set aside for the line when the order was taken in through order intake
+    pub quantity_allocated: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderDetail {
+    #[serde(flatten)]
+    pub order: OutboundOrder,
+    pub lines: Vec<OrderLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_ship_to"))]
+pub struct CreateOrder {
+    pub warehouse_id: i32,
+    #[validate(length(min = 1, max = 255))]
+    pub customer_name: String,
+    #[validate(length(max = 100))]
+    pub customer_reference: Option<String>,
+    pub ship_to_name: Option<String>,
+    pub ship_to_address: Option<String>,
+    pub ship_to_city: Option<String>,
+    pub ship_to_state: Option<String>,
+    #[validate(length(max = 20))]
+    pub ship_to_postal_code: Option<String>,
+    pub ship_to_country: Option<String>,
+    pub requested_ship_date: Option<NaiveDate>,
+    #[serde(default)]
+    #[validate(length(min = 1, max = 20))]
+    pub carrier_code: Option<String>,
+    /// Route the order to this supplier as a dropship order instead of shipping from stock
+    #[serde(default)]
+    #[validate(length(min = 1, max = 30))]
+    pub dropship_supplier_code: Option<String>,
+    #[validate(length(min = 1), nested)]
+    pub lines: Vec<CreateOrderLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateOrderLine {
+    pub item_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity_ordered: Decimal,
+}
+
+/// Ship-confirm: issue the given quantities from stock and record a shipment
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_ship_order"))]
+pub struct ShipOrder {
+    #[validate(length(max = 20))]
+    pub carrier_code: Option<String>,
+    #[validate(length(max = 100))]
+    pub tracking_number: Option<String>,
+    /// Buy a label for this service from `carrier_code`; the carrier's tracking number
+    /// replaces `tracking_number`
+    #[serde(default)]
+    #[validate(length(min = 1, max = 50))]
+    pub service_level: Option<String>,
+    #[serde(default)]
+    #[validate(nested)]
+    pub lines: Vec<ShipOrderLine>,
+    /// Ship these LPNs whole, nested LPNs included; their contents are matched to the
+    /// order lines for their items
+    #[serde(default)]
+    #[validate(length(max = 100))]
+    pub lpns: Vec<String>,
+    /// Client key for this shipment; a retry with the same key returns the original
+    /// shipment instead of shipping again
+    #[serde(default)]
+    #[validate(length(min = 1, max = 100))]
+    pub reference_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ShipOrderLine {
+    pub line_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+    /// Actual weight of the pieces shipped; required for catch-weight items
+    #[serde(default)]
+    #[validate(custom(function = "validate_positive"))]
+    pub weight_kg: Option<Decimal>,
+    /// Lot or serial number of the pieces shipped
+    #[serde(default)]
+    #[validate(length(min = 1, max = 50))]
+    pub lot_number: Option<String>,
+}
+
+/// The supplier accepted a dropship order
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SupplierConfirmation {
+    #[validate(length(min = 1, max = 100))]
+    pub supplier_order_number: String,
+}
+
+/// A shipment the supplier made for a dropship order
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SupplierShipment {
+    #[validate(length(min = 1, max = 20))]
+    pub carrier_code: String,
+    #[validate(length(min = 1, max = 100))]
+    pub tracking_number: String,
+    #[validate(length(min = 1), nested)]
+    pub lines: Vec<ShipOrderLine>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OrderFilter {
+    pub status: Option<String>,
+    pub warehouse_id: Option<i32>,
+    pub order_type: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Shipment {
+    pub shipment_id: i32,
+    pub shipment_number: String,
+    pub order_id: i32,
+    pub warehouse_id: i32,
+    pub carrier_code: Option<String>,
+    pub tracking_number: Option<String>,
+    pub status: String,
+    pub shipped_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
+    pub service_level: Option<String>,
+    pub shipping_cost: Option<Decimal>,
+    pub currency: Option<String>,
+    pub label_id: Option<String>,
+    pub label_url: Option<String>,
+    pub label_format: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    /// Shipped by the supplier of a dropship order rather than from our stock
+    pub shipped_by_supplier: bool,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ShipmentLine {
+    pub shipment_line_id: i32,
+    pub shipment_id: i32,
+    pub order_line_id: i32,
+    pub item_id: i32,
+    pub quantity: Decimal,
+    /// Actual weight shipped, for catch-weight items
+    pub weight_kg: Option<Decimal>,
+    pub lot_number: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShipmentDetail {
+    #[serde(flatten)]
+    pub shipment: Shipment,
+    pub lines: Vec<ShipmentLine>,
+}
+
+/// A carrier's price for moving a parcel at one service level
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateQuote {
+    pub carrier_code: String,
+    pub service_level: String,
+    pub service_name: Option<String>,
+    pub amount: Decimal,
+    pub currency: String,
+    pub estimated_days: Option<i32>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RateFilter {
+    /// Only quote this carrier; all configured carriers otherwise
+    pub carrier_code: Option<String>,
+}
+
+/// A purchased shipping label, stored on the shipment it was bought for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShipmentLabel {
+    pub label_id: String,
+    pub tracking_number: String,
+    pub service_level: String,
+    pub label_url: Option<String>,
+    pub label_format: Option<String>,
+    pub amount: Option<Decimal>,
+    pub currency: Option<String>,
+}
+
+fn validate_ship_to(order: &CreateOrder) -> Result<(), ValidationError> {
+    match &order.ship_to_postal_code {
+        Some(code) if !postal_code_fits(order.ship_to_country.as_deref(), code) => {
+            Err(invalid_postal_code("ship_to_postal_code"))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_ship_order(confirmation: &ShipOrder) -> Result<(), ValidationError> {
+    if confirmation.lines.is_empty() && confirmation.lpns.is_empty() {
+        Err(ValidationError::new("nothing_to_ship"))
+    } else {
+        Ok(())
+    }
+}
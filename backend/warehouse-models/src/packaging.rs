@@ -0,0 +1,89 @@
+//! Packaging materials issued from stock at packing, and reorder alerts on own stock
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::{validate_positive, UpsertStock};
+
+/// Movement type of packaging issued for a packed carton
+pub const MOVEMENT_PACKAGING: &str = "PACKAGING";
+
+/// A stock item used up by every carton packed in a box size
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct BoxMaterial {
+    pub box_id: i32,
+    pub item_id: i32,
+    pub item_code: String,
+    pub item_name: String,
+    pub quantity: Decimal,
+}
+
+/// Replaces a box size's materials; an empty list stops issuing packaging for it
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetBoxMaterials {
+    #[validate(length(max = 20), nested)]
+    pub materials: Vec<SetBoxMaterial>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetBoxMaterial {
+    pub item_id: i32,
+    /// Units per carton, e.g. 1 carton, 0.25 kg of void fill, 1.5 m of tape
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+}
+
+/// Own stock of an item at or below its reorder point
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ReorderAlert {
+    pub item_id: i32,
+    pub item_code: String,
+    pub item_name: String,
+    pub warehouse_id: i32,
+    pub quantity_on_hand: Decimal,
+    pub quantity_available: Decimal,
+    pub reorder_point: Decimal,
+    pub max_stock_level: Option<Decimal>,
+    /// Quantity that brings available stock back up to the maximum level, when one is set
+    pub suggested_quantity: Option<Decimal>,
+    /// The item is a packaging material of one of the warehouse's box sizes
+    pub is_packaging: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReorderAlertFilter {
+    /// `true` lists only packaging materials, `false` only other stock
+    pub packaging: Option<bool>,
+}
+
+/// Planning levels of an item in a warehouse; alerts are raised once available stock
+/// falls to the reorder point. Saved like any other stock row upsert, so an omitted
+/// maximum is left as it is.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_stock_levels"))]
+pub struct SetStockLevels {
+    #[validate(custom(function = "validate_positive"))]
+    pub reorder_point: Decimal,
+    pub max_stock_level: Option<Decimal>,
+}
+
+impl From<SetStockLevels> for UpsertStock {
+    fn from(levels: SetStockLevels) -> Self {
+        Self {
+            min_stock_level: None,
+            max_stock_level: levels.max_stock_level,
+            reorder_point: Some(levels.reorder_point),
+        }
+    }
+}
+
+fn validate_stock_levels(levels: &SetStockLevels) -> Result<(), ValidationError> {
+    match levels.max_stock_level {
+        Some(max) if max <= levels.reorder_point => {
+            Err(ValidationError::new("max_stock_level_not_above_reorder_point"))
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,23 @@
+//! Client reference keys that make stock postings idempotent
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+pub const POSTING_RECEIPT: &str = "RECEIPT";
+pub const POSTING_SHIPMENT: &str = "SHIPMENT";
+pub const POSTING_RETURN_RECEIPT: &str = "RETURN_RECEIPT";
+pub const POSTING_RETURN_INSPECTION: &str = "RETURN_INSPECTION";
+pub const POSTING_CONSIGNMENT_CONSUME: &str = "CONSIGNMENT_CONSUME";
+
+/// A reference key some post has claimed
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PostingReference {
+    pub reference_id: String,
+    pub operation: String,
+    /// Receipt, order, return or consignor the post was made against
+    pub target_id: i32,
+    /// Shipment or movement the post created
+    pub result_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
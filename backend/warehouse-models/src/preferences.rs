@@ -0,0 +1,30 @@
+//! Per-user settings, kept under the signed-in user's name
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+/// Languages API messages and labels are available in, as ISO 639-1 codes
+pub const SUPPORTED_LOCALES: &[&str] = &["en", "id"];
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct UserPreference {
+    pub owner: String,
+    pub locale: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetUserPreference {
+    #[validate(custom(function = "validate_locale"))]
+    pub locale: String,
+}
+
+fn validate_locale(locale: &str) -> Result<(), ValidationError> {
+    if SUPPORTED_LOCALES.contains(&locale) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unsupported_locale"))
+    }
+}
@@ -0,0 +1,123 @@
+//! Printers per warehouse, and print jobs sent to them with the warehouse's templates
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::TEMPLATE_PURPOSES;
+
+/// Network label printer taking raw ZPL, on port 9100 unless its address says otherwise
+pub const PRINTER_ZPL: &str = "ZPL";
+/// Print agent on a station's PC, taking labels and documents over HTTP
+pub const PRINTER_AGENT: &str = "AGENT";
+pub const PRINTER_TYPES: &[&str] = &[PRINTER_ZPL, PRINTER_AGENT];
+
+/// Port ZPL printers listen on for raw jobs
+pub const ZPL_DEFAULT_PORT: u16 = 9100;
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Printer {
+    pub printer_id: i32,
+    pub warehouse_id: i32,
+    pub printer_code: String,
+    pub name: String,
+    pub printer_type: String,
+    /// host[:port] for ZPL printers, a URL for agents
+    pub address: String,
+    /// The station it sits at, if any
+    pub station_id: Option<i32>,
+    /// Template purposes it prints when a job names no printer
+    pub default_for: Vec<String>,
+    pub is_active: bool,
+    pub last_printed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreatePrinter {
+    #[validate(length(min = 1, max = 30))]
+    pub printer_code: String,
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(custom(function = "validate_printer_type"))]
+    pub printer_type: String,
+    #[validate(length(min = 1, max = 255))]
+    pub address: String,
+    pub station_id: Option<i32>,
+    /// Becoming the default for a purpose takes over from the printer that was
+    #[serde(default)]
+    #[validate(custom(function = "validate_default_for"))]
+    pub default_for: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdatePrinter {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub address: Option<String>,
+    /// Move the printer to a station; use `clear_station` to take it off one
+    pub station_id: Option<i32>,
+    #[serde(default)]
+    pub clear_station: bool,
+    #[validate(custom(function = "validate_default_for"))]
+    pub default_for: Option<Vec<String>>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PrinterFilter {
+    pub station_id: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+/// Print the warehouse's template for `purpose`. Without a printer named, it goes to the
+/// station's default printer for the purpose, or else the warehouse's.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct PrintRequest {
+    #[validate(custom(function = "validate_purpose"))]
+    pub purpose: String,
+    pub printer_id: Option<i32>,
+    /// The station printing, to find the printer next to it
+    pub station_id: Option<i32>,
+    #[validate(range(min = 1, max = 100))]
+    pub copies: Option<i32>,
+    /// Values for the template's `{{field}}` placeholders
+    #[serde(default)]
+    pub fields: HashMap<String, String>,
+}
+
+/// A job sent to a printer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrintDispatch {
+    pub printer: Printer,
+    pub purpose: String,
+    pub template_id: i32,
+    pub template_code: String,
+    pub version: i32,
+    pub copies: i32,
+}
+
+fn validate_printer_type(printer_type: &str) -> Result<(), ValidationError> {
+    if PRINTER_TYPES.contains(&printer_type) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_printer_type"))
+    }
+}
+
+fn validate_purpose(purpose: &str) -> Result<(), ValidationError> {
+    if TEMPLATE_PURPOSES.contains(&purpose) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_template_purpose"))
+    }
+}
+
+fn validate_default_for(purposes: &[String]) -> Result<(), ValidationError> {
+    purposes.iter().try_for_each(|purpose| validate_purpose(purpose))
+}
@@ -0,0 +1,307 @@
+//! Projects: stock issued to a project is charged against its material budget, and an
+//! issue that would overrun the budget is warned about or blocked. Stock can be reserved
+//! for a project ahead of the date it is needed.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::{validate_positive, Money, StockMovement};
+
+/// Post an over-budget issue and raise a notification (the default)
+pub const PROJECT_BUDGET_WARN: &str = "WARN";
+/// Refuse an over-budget issue
+pub const PROJECT_BUDGET_BLOCK: &str = "BLOCK";
+
+pub const MOVEMENT_PROJECT_ISSUE: &str = "PROJECT_ISSUE";
+
+pub const NOTIFY_PROJECT_OVER_BUDGET: &str = "PROJECT_OVER_BUDGET";
+
+// Reservation statuses
+pub const RESERVATION_ACTIVE: &str = "ACTIVE";
+/// Issued to the project in full
+pub const RESERVATION_FULFILLED: &str = "FULFILLED";
+pub const RESERVATION_RELEASED: &str = "RELEASED";
+/// Passed its expiry date while still active
+pub const RESERVATION_EXPIRED: &str = "EXPIRED";
+
+// Project statuses; a project takes issues and reservations while planned or active
+pub const PROJECT_PLANNED: &str = "PLANNED";
+pub const PROJECT_ACTIVE: &str = "ACTIVE";
+pub const PROJECT_ON_HOLD: &str = "ON_HOLD";
+pub const PROJECT_COMPLETED: &str = "COMPLETED";
+pub const PROJECT_CANCELLED: &str = "CANCELLED";
+pub const PROJECT_STATUSES: [&str; 5] =
+    [PROJECT_PLANNED, PROJECT_ACTIVE, PROJECT_ON_HOLD, PROJECT_COMPLETED, PROJECT_CANCELLED];
+
+/// Item usage type of goods used up on site, as opposed to tools and assets
+pub const ITEM_USAGE_CONSUMABLE: &str = "CONSUMABLE";
+
+// Periods consumable usage is reported by
+pub const USAGE_PERIOD_WEEK: &str = "WEEK";
+pub const USAGE_PERIOD_MONTH: &str = "MONTH";
+
+/// Ledger reference of the moves between the ON_HAND and RESERVED buckets
+pub const REFERENCE_PROJECT_RESERVATION: &str = "PROJECT_RESERVATION";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub project_id: i32,
+    pub project_code: String,
+    pub project_name: String,
+    /// None leaves the project's issues unchecked
+    pub material_budget: Option<Money>,
+    pub budget_control: String,
+    pub status: String,
+    /// Whether the project takes issues and reservations: while it is planned or active
+    pub is_active: bool,
+    /// Where the project's materials are delivered
+    pub site_name: Option<String>,
+    pub site_address: Option<String>,
+    pub site_city: Option<String>,
+    pub manager_name: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_create_dates"))]
+pub struct CreateProject {
+    #[validate(length(min = 1, max = 30))]
+    pub project_code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub project_name: String,
+    #[validate(nested)]
+    pub material_budget: Option<Money>,
+    /// WARN or BLOCK; defaults to WARN
+    #[validate(custom(function = "validate_budget_control"))]
+    pub budget_control: Option<String>,
+    /// Defaults to ACTIVE
+    #[validate(custom(function = "validate_project_status"))]
+    pub status: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub site_name: Option<String>,
+    #[validate(length(min = 1, max = 1000))]
+    pub site_address: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub site_city: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub manager_name: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+/// Omitted fields are left as they are
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateProject {
+    #[validate(length(min = 1, max = 255))]
+    pub project_name: Option<String>,
+    #[validate(nested)]
+    pub material_budget: Option<Money>,
+    #[validate(custom(function = "validate_budget_control"))]
+    pub budget_control: Option<String>,
+    #[validate(custom(function = "validate_project_status"))]
+    pub status: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub site_name: Option<String>,
+    #[validate(length(min = 1, max = 1000))]
+    pub site_address: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub site_city: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub manager_name: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProjectFilter {
+    /// Projects in this status; the planned and active ones when omitted
+    pub status: Option<String>,
+}
+
+/// Issue stock from one warehouse to a project
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct IssueToProject {
+    pub warehouse_id: i32,
+    #[validate(length(min = 1, max = 200), nested)]
+    pub lines: Vec<ProjectIssueLine>,
+    /// Work order or delivery note the goods went out against
+    #[validate(length(min = 1, max = 100))]
+    pub reference: Option<String>,
+    /// Crew on site the goods were issued to
+    #[validate(length(min = 1, max = 100))]
+    pub crew: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ProjectIssueLine {
+    pub item_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+    #[validate(length(min = 1, max = 100))]
+    pub lot_number: Option<String>,
+}
+
+/// An issue as posted, with where it left the project's budget
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectIssueResult {
+    pub movements: Vec<StockMovement>,
+    /// Value of this issue; items without a cost count for nothing
+    pub issue_value: Money,
+    /// Everything issued to the project so far, this issue included
+    pub consumed: Money,
+    pub material_budget: Option<Money>,
+    pub over_budget: bool,
+    /// Set when the issue took the project over its budget
+    pub warning: Option<String>,
+    /// Reservations the issue drew on, as left by it
+    pub reservations: Vec<ProjectReservation>,
+}
+
+/// Stock held in a warehouse for a project
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ProjectReservation {
+    pub reservation_id: i32,
+    pub project_id: i32,
+    pub warehouse_id: i32,
+    pub item_id: i32,
+    pub quantity: Decimal,
+    pub quantity_issued: Decimal,
+    pub needed_by: NaiveDate,
+    pub expires_on: NaiveDate,
+    pub status: String,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+impl ProjectReservation {
+    /// Quantity still held out of the warehouse's available stock
+    pub fn quantity_open(&self) -> Decimal {
+        if self.status == RESERVATION_ACTIVE {
+            self.quantity - self.quantity_issued
+        } else {
+            Decimal::ZERO
+        }
+    }
+}
+
+/// Reserve stock in one warehouse for a project, one reservation per line
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ReserveForProject {
+    pub warehouse_id: i32,
+    #[validate(length(min = 1, max = 200), nested)]
+    pub lines: Vec<ProjectReservationLine>,
+    pub needed_by: NaiveDate,
+    /// Last day the reservation holds; defaults to `needed_by`
+    pub expires_on: Option<NaiveDate>,
+    #[validate(length(max = 1000))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ProjectReservationLine {
+    pub item_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProjectReservationFilter {
+    /// ACTIVE, FULFILLED, RELEASED or EXPIRED
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProjectConsumptionFilter {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// What has been issued to a project, against its budget
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectConsumption {
+    pub project_id: i32,
+    pub project_code: String,
+    pub material_budget: Option<Money>,
+    /// Over all time, whatever the filter
+    pub consumed: Money,
+    pub remaining: Option<Money>,
+    /// Share of the budget used, in percent
+    pub consumed_pct: Option<Decimal>,
+    /// Items issued within the filter's dates
+    pub items: Vec<ProjectItemConsumption>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectItemConsumption {
+    pub item_id: i32,
+    pub item_code: String,
+    pub item_name: String,
+    pub quantity: Decimal,
+    pub issue_value: Money,
+    pub issues: i64,
+    pub last_issued_at: DateTime<Utc>,
+}
+
+fn validate_project_status(status: &str) -> Result<(), ValidationError> {
+    if PROJECT_STATUSES.contains(&status) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_project_status"))
+    }
+}
+
+fn validate_create_dates(project: &CreateProject) -> Result<(), ValidationError> {
+    match (project.start_date, project.end_date) {
+        (Some(start), Some(end)) if end < start => Err(ValidationError::new("end_date_before_start_date")),
+        _ => Ok(()),
+    }
+}
+
+fn validate_budget_control(control: &str) -> Result<(), ValidationError> {
+    match control {
+        PROJECT_BUDGET_WARN | PROJECT_BUDGET_BLOCK => Ok(()),
+        _ => Err(ValidationError::new("unknown_budget_control")),
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ConsumableUsageFilter {
+    /// Issues on or after; defaults to ninety days ago
+    pub from: Option<NaiveDate>,
+    /// Issues on or before; defaults to today
+    pub to: Option<NaiveDate>,
+    /// WEEK or MONTH; defaults to MONTH
+    pub period: Option<String>,
+    pub project_id: Option<i32>,
+    pub crew: Option<String>,
+    /// Usage at least this many times the peer average is abnormal; defaults to 2
+    pub threshold: Option<Decimal>,
+}
+
+/// Consumables a project crew drew in a period, against the other projects' crews that
+/// drew the same item in the same period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsumableUsage {
+    pub period_start: NaiveDate,
+    pub project_id: i32,
+    pub project_code: String,
+    /// None for issues not made to a named crew
+    pub crew: Option<String>,
+    pub item_id: i32,
+    pub item_code: String,
+    pub item_name: String,
+    pub quantity: Decimal,
+    pub issues: i64,
+    /// Average quantity of the other projects' crews; None when no other project drew the item
+    pub peer_average: Option<Decimal>,
+    /// `quantity` over `peer_average`
+    pub peer_ratio: Option<Decimal>,
+    pub abnormal: bool,
+}
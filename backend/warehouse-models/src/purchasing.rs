@@ -0,0 +1,252 @@
+//! Purchasing: the suppliers an item is bought from, purchase requisitions drafted from
+//! reorder alerts for approval, and the purchase orders they are converted into
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::{validate_positive, Money, ReorderAlert};
+
+pub const REQUISITION_DRAFT: &str = "DRAFT";
+pub const REQUISITION_APPROVED: &str = "APPROVED";
+pub const REQUISITION_REJECTED: &str = "REJECTED";
+/// Turned into a purchase order
+pub const REQUISITION_CONVERTED: &str = "CONVERTED";
+
+/// Economic order quantity: `sqrt(2 x yearly demand x order cost / yearly holding cost)`
+pub const REQUISITION_METHOD_EOQ: &str = "EOQ";
+/// Up to the maximum stock level, or the reorder point where no maximum is set
+pub const REQUISITION_METHOD_MIN_MAX: &str = "MIN_MAX";
+pub const REQUISITION_METHODS: &[&str] = &[REQUISITION_METHOD_EOQ, REQUISITION_METHOD_MIN_MAX];
+
+pub const PO_OPEN: &str = "OPEN";
+pub const PO_RECEIVED: &str = "RECEIVED";
+pub const PO_CANCELLED: &str = "CANCELLED";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemSupplier {
+    pub item_id: i32,
+    pub supplier_code: String,
+    pub supplier_name: String,
+    pub is_preferred: bool,
+    pub unit_cost: Option<Money>,
+    pub lead_time_days: i32,
+    /// Cost of placing one order
+    pub order_cost: Option<Money>,
+    /// Yearly cost of holding a unit, as a percentage of its unit cost
+    pub holding_cost_pct: Option<Decimal>,
+    #[serde(flatten)]
+    pub constraints: OrderConstraints,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Adds or replaces one supplier of an item. Making it preferred takes the preference
+/// from the item's other suppliers.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_item_supplier"))]
+pub struct SetItemSupplier {
+    #[validate(length(min = 1, max = 255))]
+    pub supplier_name: String,
+    #[serde(default)]
+    pub is_preferred: bool,
+    #[validate(nested)]
+    pub unit_cost: Option<Money>,
+    #[validate(range(min = 0, max = 365))]
+    pub lead_time_days: Option<i32>,
+    /// In the unit cost's currency
+    #[validate(nested)]
+    pub order_cost: Option<Money>,
+    #[validate(custom(function = "validate_positive"))]
+    pub holding_cost_pct: Option<Decimal>,
+    #[validate(custom(function = "validate_positive"))]
+    pub min_order_quantity: Option<Decimal>,
+    #[validate(custom(function = "validate_positive"))]
+    pub order_multiple: Option<Decimal>,
+    #[validate(custom(function = "validate_positive"))]
+    pub pack_size: Option<Decimal>,
+}
+
+impl SetItemSupplier {
+    /// Currency of the supplier's prices
+    pub fn currency(&self) -> Option<&str> {
+        self.unit_cost.as_ref().or(self.order_cost.as_ref()).map(|cost| cost.currency.as_str())
+    }
+}
+
+/// What a supplier accepts on an order of one item, in the item's units
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrderConstraints {
+    pub min_order_quantity: Option<Decimal>,
+    /// Orders go up in steps of this many units
+    pub order_multiple: Option<Decimal>,
+    /// Units in one supplier pack; only whole packs are ordered
+    pub pack_size: Option<Decimal>,
+}
+
+impl OrderConstraints {
+    /// The order multiple is always whole packs, so it is the step where both are set
+    fn step(&self) -> Option<Decimal> {
+        self.order_multiple.or(self.pack_size)
+    }
+
+    /// The smallest quantity of at least `quantity` the supplier accepts
+    pub fn round_up(&self, quantity: Decimal) -> Decimal {
+        let quantity = self.min_order_quantity.map_or(quantity, |min| quantity.max(min));
+        match self.step() {
+            Some(step) => (quantity / step).ceil() * step,
+            None => quantity,
+        }
+    }
+
+    /// Why the supplier would refuse an order of `quantity`, if it would
+    pub fn violation(&self, quantity: Decimal) -> Option<String> {
+        if let Some(min) = self.min_order_quantity.filter(|min| quantity < *min) {
+            return Some(format!("{} is below the minimum order quantity of {}", quantity, min.normalize()));
+        }
+        if let Some(pack) = self.pack_size.filter(|pack| !(quantity % *pack).is_zero()) {
+            return Some(format!("{} is not a whole number of packs of {}", quantity, pack.normalize()));
+        }
+        if let Some(multiple) = self.order_multiple.filter(|multiple| !(quantity % *multiple).is_zero()) {
+            return Some(format!("{} is not a multiple of {}", quantity, multiple.normalize()));
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PurchaseRequisition {
+    pub requisition_id: i32,
+    pub requisition_number: String,
+    pub warehouse_id: i32,
+    pub supplier_code: String,
+    pub supplier_name: String,
+    pub status: String,
+    pub method: String,
+    pub currency: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub rejection_reason: Option<String>,
+    /// Purchase order it was converted into
+    pub po_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchaseRequisitionLine {
+    pub requisition_id: i32,
+    pub item_id: i32,
+    pub item_code: String,
+    pub item_name: String,
+    /// Stock position when the alert fired
+    pub quantity_available: Decimal,
+    pub reorder_point: Decimal,
+    /// How the suggestion was worked out; EOQ falls back to MIN_MAX without the inputs for it
+    pub method: String,
+    pub suggested_quantity: Decimal,
+    /// What will be ordered
+    pub quantity: Decimal,
+    pub unit_cost: Option<Money>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequisitionDetail {
+    #[serde(flatten)]
+    pub requisition: PurchaseRequisition,
+    pub lines: Vec<PurchaseRequisitionLine>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Validate)]
+pub struct GenerateRequisitions {
+    /// EOQ or MIN_MAX; defaults to MIN_MAX
+    #[validate(custom(function = "validate_requisition_method"))]
+    pub method: Option<String>,
+}
+
+impl GenerateRequisitions {
+    pub fn method(&self) -> &str {
+        self.method.as_deref().unwrap_or(REQUISITION_METHOD_MIN_MAX)
+    }
+}
+
+/// Drafts raised by one pass over a warehouse's reorder alerts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequisitionRun {
+    pub requisitions: Vec<RequisitionDetail>,
+    /// Alerts on items without a preferred supplier, left for a buyer
+    pub without_supplier: Vec<ReorderAlert>,
+}
+
+/// An approver's change to the quantity of a draft line
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetRequisitionLine {
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RejectRequisition {
+    #[validate(length(min = 1, max = 1000))]
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RequisitionFilter {
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PurchaseOrder {
+    pub po_id: i32,
+    pub po_number: String,
+    pub warehouse_id: i32,
+    pub supplier_code: String,
+    pub supplier_name: String,
+    pub status: String,
+    pub currency: String,
+    pub expected_date: Option<NaiveDate>,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchaseOrderLine {
+    pub line_id: i32,
+    pub po_id: i32,
+    pub line_number: i32,
+    pub item_id: i32,
+    pub quantity_ordered: Decimal,
+    pub quantity_received: Decimal,
+    pub unit_cost: Option<Money>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchaseOrderDetail {
+    #[serde(flatten)]
+    pub order: PurchaseOrder,
+    pub lines: Vec<PurchaseOrderLine>,
+}
+
+fn validate_requisition_method(method: &str) -> Result<(), ValidationError> {
+    if REQUISITION_METHODS.contains(&method) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_requisition_method"))
+    }
+}
+
+fn validate_item_supplier(supplier: &SetItemSupplier) -> Result<(), ValidationError> {
+    if let (Some(unit), Some(order)) = (&supplier.unit_cost, &supplier.order_cost) {
+        if unit.currency != order.currency {
+            return Err(ValidationError::new("order_cost_currency_differs"));
+        }
+    }
+    if let (Some(multiple), Some(pack)) = (supplier.order_multiple, supplier.pack_size) {
+        if !(multiple % pack).is_zero() {
+            return Err(ValidationError::new("order_multiple_not_whole_packs"));
+        }
+    }
+    Ok(())
+}
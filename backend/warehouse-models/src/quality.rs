@@ -0,0 +1,71 @@
+//! Quality inspection models
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+use crate::validate_positive;
+
+pub const INSPECTION_PENDING: &str = "PENDING";
+pub const INSPECTION_PASSED: &str = "PASSED";
+pub const INSPECTION_FAILED: &str = "FAILED";
+
+/// A lot of received goods held in quarantine until it is inspected
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct QualityInspection {
+    pub inspection_id: i32,
+    pub item_id: i32,
+    pub warehouse_id: i32,
+    pub quantity: Decimal,
+    pub reference_type: Option<String>,
+    pub reference_number: Option<String>,
+    pub status: String,
+    pub sample_size: Option<i32>,
+    pub defects_found: Option<i32>,
+    pub notes: Option<String>,
+    pub inspected_at: Option<DateTime<Utc>>,
+    pub inspected_by: Option<i32>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub created_by: Option<i32>,
+}
+
+/// Receive goods straight into quarantine pending inspection
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateInspection {
+    pub item_id: i32,
+    pub warehouse_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+    #[validate(length(max = 50))]
+    pub reference_type: Option<String>,
+    #[validate(length(max = 100))]
+    pub reference_number: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum InspectionResult {
+    /// Release the lot into available stock
+    Pass,
+    /// Reject the lot out of stock
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RecordInspection {
+    pub result: InspectionResult,
+    #[validate(range(min = 1))]
+    pub sample_size: i32,
+    #[validate(range(min = 0))]
+    pub defects_found: Option<i32>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct InspectionFilter {
+    pub status: Option<String>,
+    pub warehouse_id: Option<i32>,
+    pub item_id: Option<i32>,
+}
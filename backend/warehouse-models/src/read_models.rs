@@ -0,0 +1,17 @@
+//! Read models: denormalized copies of hot query paths, kept current by projecting
+//! domain events appended when the source tables change
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const AGGREGATE_STOCK: &str = "STOCK";
+pub const AGGREGATE_ITEM: &str = "ITEM";
+pub const AGGREGATE_WAREHOUSE: &str = "WAREHOUSE";
+
+/// How far the read models lag behind the source tables
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadModelStatus {
+    pub pending_events: i64,
+    pub oldest_pending_at: Option<DateTime<Utc>>,
+    pub last_projected_at: Option<DateTime<Utc>>,
+}
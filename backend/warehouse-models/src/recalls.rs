@@ -0,0 +1,100 @@
+//! Product recalls by lot or serial range: affected stock is put on hold and the report
+//! traces what already shipped to whom
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+pub const RECALL_OPEN: &str = "OPEN";
+pub const RECALL_CLOSED: &str = "CLOSED";
+
+/// Movement type moving recalled stock from available into quarantine
+pub const MOVEMENT_RECALL_HOLD: &str = "RECALL_HOLD";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Recall {
+    pub recall_id: i32,
+    pub recall_number: String,
+    pub item_id: i32,
+    pub lot_number: Option<String>,
+    pub serial_from: Option<String>,
+    pub serial_to: Option<String>,
+    pub reason: String,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+/// Recall one lot, or a serial range of same-length serial numbers, e.g. `SN000100` to `SN000199`
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_recall_scope"))]
+pub struct CreateRecall {
+    pub item_id: i32,
+    #[validate(length(min = 1, max = 50))]
+    pub lot_number: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub serial_from: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub serial_to: Option<String>,
+    #[validate(length(min = 1))]
+    pub reason: String,
+}
+
+/// Recalled stock still in a warehouse, by lot or serial number
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct RecallStock {
+    pub warehouse_id: i32,
+    pub warehouse_code: String,
+    pub lot_number: String,
+    /// Still available, e.g. when it was more than was free to hold
+    pub quantity_available: Decimal,
+    pub quantity_on_hold: Decimal,
+}
+
+/// A past shipment that carried recalled units
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct RecallShipment {
+    pub shipment_id: i32,
+    pub shipment_number: String,
+    pub order_number: String,
+    pub customer_name: String,
+    pub customer_reference: Option<String>,
+    pub shipped_at: DateTime<Utc>,
+    pub lot_number: String,
+    pub quantity: Decimal,
+}
+
+/// Recalled units shipped per customer and customer reference (their PO or project)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecallExposure {
+    pub customer_name: String,
+    pub customer_reference: Option<String>,
+    pub shipments: usize,
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RecallFilter {
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecallReport {
+    #[serde(flatten)]
+    pub recall: Recall,
+    pub stock: Vec<RecallStock>,
+    pub shipments: Vec<RecallShipment>,
+    pub exposure: Vec<RecallExposure>,
+}
+
+fn validate_recall_scope(recall: &CreateRecall) -> Result<(), ValidationError> {
+    match (&recall.lot_number, &recall.serial_from, &recall.serial_to) {
+        (Some(_), None, None) => Ok(()),
+        (None, Some(from), Some(to)) if from.len() == to.len() && from <= to => Ok(()),
+        (None, Some(_), Some(_)) => Err(ValidationError::new("invalid_serial_range")),
+        _ => Err(ValidationError::new("lot_or_serial_range_required")),
+    }
+}
@@ -0,0 +1,112 @@
+//! Inbound receipt (advance ship notice) models
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+use crate::validate_positive;
+
+pub const RECEIPT_EXPECTED: &str = "EXPECTED";
+pub const RECEIPT_PARTIALLY_RECEIVED: &str = "PARTIALLY_RECEIVED";
+pub const RECEIPT_RECEIVED: &str = "RECEIVED";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct InboundReceipt {
+    pub receipt_id: i32,
+    pub asn_number: String,
+    pub warehouse_id: i32,
+    pub supplier_name: Option<String>,
+    pub purchase_order_number: Option<String>,
+    pub carrier_code: Option<String>,
+    pub expected_date: Option<NaiveDate>,
+    pub status: String,
+    pub source: String,
+    pub received_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub created_by: Option<i32>,
+    /// Goods on this ASN stay owned by the consignor until consumed
+    pub consignor_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ReceiptLine {
+    pub line_id: i32,
+    pub receipt_id: i32,
+    pub item_id: i32,
+    pub quantity_expected: Decimal,
+    pub quantity_received: Decimal,
+    /// Supplier LPN the line arrives on
+    pub lpn_number: Option<String>,
+    /// Actual weight received so far, for catch-weight items
+    pub weight_received_kg: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceiptDetail {
+    #[serde(flatten)]
+    pub receipt: InboundReceipt,
+    pub lines: Vec<ReceiptLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateReceipt {
+    #[validate(length(min = 1, max = 50))]
+    pub asn_number: String,
+    pub warehouse_id: i32,
+    pub supplier_name: Option<String>,
+    #[validate(length(max = 100))]
+    pub purchase_order_number: Option<String>,
+    #[validate(length(max = 20))]
+    pub carrier_code: Option<String>,
+    pub expected_date: Option<NaiveDate>,
+    /// Receive the goods as consignment stock owned by this consignor
+    pub consignor_id: Option<i32>,
+    #[validate(length(min = 1), nested)]
+    pub lines: Vec<CreateReceiptLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateReceiptLine {
+    pub item_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity_expected: Decimal,
+    /// Supplier LPN the line arrives on; receive the LPN to receive all of its lines at once
+    #[serde(default)]
+    #[validate(length(min = 1, max = 30))]
+    pub lpn_number: Option<String>,
+}
+
+/// Goods counted at the dock against an ASN; over-receipts are allowed and recorded
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ReceiveGoods {
+    #[validate(length(min = 1), nested)]
+    pub lines: Vec<ReceiveGoodsLine>,
+    /// Client key for this count; a retry with the same key is not received twice
+    #[serde(default)]
+    #[validate(length(min = 1, max = 100))]
+    pub reference_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ReceiveGoodsLine {
+    pub line_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+    /// Actual weight of the pieces counted; required for catch-weight items
+    #[serde(default)]
+    #[validate(custom(function = "validate_positive"))]
+    pub weight_kg: Option<Decimal>,
+    /// Lot or serial number the pieces carry, kept on the stock ledger for traceability
+    #[serde(default)]
+    #[validate(length(min = 1, max = 50))]
+    pub lot_number: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReceiptFilter {
+    pub status: Option<String>,
+    pub warehouse_id: Option<i32>,
+}
@@ -0,0 +1,82 @@
+//! Reconciliation of WMS stock against ERP stock extracts, for the month-end three-way match
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+pub const ERP_MATCHED: &str = "MATCHED";
+pub const ERP_VARIANCE: &str = "VARIANCE";
+/// The ERP holds stock the WMS has no balance for
+pub const ERP_MISSING_IN_WMS: &str = "MISSING_IN_WMS";
+/// The WMS holds stock the extract does not mention, in a warehouse the extract covers
+pub const ERP_MISSING_IN_ERP: &str = "MISSING_IN_ERP";
+/// The ERP item or warehouse code matches nothing in the WMS
+pub const ERP_UNKNOWN: &str = "UNKNOWN";
+
+/// One line of an ERP stock extract; lines for the same item and warehouse are added up
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ErpStockLine {
+    #[validate(length(min = 1, max = 100))]
+    pub item_code: String,
+    #[validate(length(min = 1, max = 50))]
+    pub warehouse_code: String,
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ErpExtract {
+    pub as_of: NaiveDate,
+    #[validate(length(max = 100))]
+    pub source: Option<String>,
+    #[validate(length(min = 1), nested)]
+    pub lines: Vec<ErpStockLine>,
+}
+
+/// `?as_of=&source=` for extracts posted as CSV; `as_of` is required for them
+#[derive(Debug, Default, Deserialize)]
+pub struct ErpExtractQuery {
+    pub as_of: Option<NaiveDate>,
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ErpReconciliation {
+    pub reconciliation_id: i32,
+    pub as_of: NaiveDate,
+    pub source: Option<String>,
+    pub lines: i32,
+    pub matched: i32,
+    /// Lines in any status other than MATCHED
+    pub variances: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ErpReconciliationLine {
+    pub line_id: i64,
+    pub reconciliation_id: i32,
+    pub item_id: Option<i32>,
+    pub item_code: String,
+    pub warehouse_id: Option<i32>,
+    pub warehouse_code: String,
+    pub erp_quantity: Option<Decimal>,
+    pub wms_quantity: Option<Decimal>,
+    /// WMS less ERP
+    pub variance: Option<Decimal>,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErpReconciliationDetail {
+    #[serde(flatten)]
+    pub reconciliation: ErpReconciliation,
+    pub lines: Vec<ErpReconciliationLine>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ErpReconciliationFilter {
+    pub status: Option<String>,
+    pub warehouse_code: Option<String>,
+}
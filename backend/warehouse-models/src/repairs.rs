@@ -0,0 +1,126 @@
+//! Repair orders for damaged tools: the tool is held in quarantine while it is repaired
+//! by a vendor or our own workshop, its repair cost is captured, and it ends up back in
+//! available stock or scrapped
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+use crate::Money;
+
+pub const REPAIR_VENDOR: &str = "VENDOR";
+pub const REPAIR_INTERNAL: &str = "INTERNAL";
+
+// Repair order statuses
+/// Held in quarantine, not yet sent out
+pub const REPAIR_OPEN: &str = "OPEN";
+pub const REPAIR_IN_REPAIR: &str = "IN_REPAIR";
+pub const REPAIR_RESTOCKED: &str = "RESTOCKED";
+pub const REPAIR_SCRAPPED: &str = "SCRAPPED";
+pub const REPAIR_STATUSES: [&str; 4] = [REPAIR_OPEN, REPAIR_IN_REPAIR, REPAIR_RESTOCKED, REPAIR_SCRAPPED];
+
+// What becomes of a repaired tool
+pub const REPAIR_DECISION_RESTOCK: &str = "RESTOCK";
+pub const REPAIR_DECISION_SCRAP: &str = "SCRAP";
+
+/// Movement type moving a tool from available stock into quarantine for repair
+pub const MOVEMENT_REPAIR_HOLD: &str = "REPAIR_HOLD";
+/// Movement type putting a repaired tool back into available stock
+pub const MOVEMENT_REPAIR_RESTOCK: &str = "REPAIR_RESTOCK";
+/// Movement type writing off a tool not worth repairing
+pub const MOVEMENT_REPAIR_SCRAP: &str = "REPAIR_SCRAP";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepairOrder {
+    pub repair_id: i32,
+    pub repair_number: String,
+    pub warehouse_id: i32,
+    pub item_id: i32,
+    /// Serial number the tool carries on the stock ledger
+    pub serial_number: String,
+    pub repair_type: String,
+    pub vendor_name: Option<String>,
+    pub fault_description: String,
+    pub status: String,
+    pub repair_cost: Option<Money>,
+    pub expected_back_on: Option<NaiveDate>,
+    pub resolution_notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+impl RepairOrder {
+    pub fn is_closed(&self) -> bool {
+        self.status == REPAIR_RESTOCKED || self.status == REPAIR_SCRAPPED
+    }
+}
+
+/// Take a damaged tool out of available stock for repair
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_repair_vendor"))]
+pub struct CreateRepairOrder {
+    pub warehouse_id: i32,
+    pub item_id: i32,
+    #[validate(length(min = 1, max = 50))]
+    pub serial_number: String,
+    /// VENDOR or INTERNAL
+    pub repair_type: String,
+    /// Required for VENDOR repairs
+    #[validate(length(min = 1, max = 255))]
+    pub vendor_name: Option<String>,
+    #[validate(length(min = 1, max = 2000))]
+    pub fault_description: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SendForRepair {
+    pub expected_back_on: Option<NaiveDate>,
+}
+
+/// Quoted or invoiced cost of the repair; replaces any cost recorded before
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_repair_cost"))]
+pub struct RecordRepairCost {
+    #[validate(nested)]
+    pub cost: Money,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CloseRepairOrder {
+    /// RESTOCK puts the tool back into available stock, SCRAP writes it off
+    #[validate(custom(function = "validate_repair_decision"))]
+    pub decision: String,
+    #[validate(length(max = 2000))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RepairOrderFilter {
+    /// OPEN, IN_REPAIR, RESTOCKED or SCRAPPED
+    pub status: Option<String>,
+    pub item_id: Option<i32>,
+    pub serial_number: Option<String>,
+}
+
+fn validate_repair_vendor(order: &CreateRepairOrder) -> Result<(), ValidationError> {
+    match (order.repair_type.as_str(), &order.vendor_name) {
+        (REPAIR_VENDOR, None) => Err(ValidationError::new("vendor_name_required")),
+        (REPAIR_VENDOR | REPAIR_INTERNAL, _) => Ok(()),
+        _ => Err(ValidationError::new("unknown_repair_type")),
+    }
+}
+
+fn validate_repair_cost(cost: &RecordRepairCost) -> Result<(), ValidationError> {
+    if cost.cost.amount.is_sign_negative() {
+        return Err(ValidationError::new("repair_cost_negative"));
+    }
+    Ok(())
+}
+
+fn validate_repair_decision(decision: &str) -> Result<(), ValidationError> {
+    match decision {
+        REPAIR_DECISION_RESTOCK | REPAIR_DECISION_SCRAP => Ok(()),
+        _ => Err(ValidationError::new("unknown_repair_decision")),
+    }
+}
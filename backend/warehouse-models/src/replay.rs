@@ -0,0 +1,127 @@
+//! Replaying the stock event stream from an offset into the projections built from it, or
+//! to downstream consumers, to recover a corrupted read model or bootstrap a new consumer
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::StockEvent;
+
+pub const REPLAY_BALANCES: &str = "BALANCES";
+pub const REPLAY_READ_MODEL: &str = "READ_MODEL";
+pub const REPLAY_CONSUMER: &str = "CONSUMER";
+pub const REPLAY_TARGETS: &[&str] = &[REPLAY_BALANCES, REPLAY_READ_MODEL, REPLAY_CONSUMER];
+
+/// Events sent to a consumer per request
+pub const CONSUMER_BATCH_SIZE: i64 = 500;
+
+pub const DEAD_LETTER_DEAD: &str = "DEAD";
+pub const DEAD_LETTER_REDRIVEN: &str = "REDRIVEN";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct EventConsumer {
+    pub consumer_id: i32,
+    pub consumer_name: String,
+    pub endpoint_url: String,
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+    /// Last event_id the consumer acknowledged
+    pub position: i64,
+    pub is_active: bool,
+    pub last_error: Option<String>,
+    pub last_delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Consecutive failures of the batch after `position`
+    pub failed_attempts: i32,
+    /// Not retried before this after a failure
+    pub next_attempt_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateEventConsumer {
+    #[validate(length(min = 1, max = 50))]
+    pub consumer_name: String,
+    #[validate(url, length(max = 500))]
+    pub endpoint_url: String,
+    /// Sent as a bearer token
+    #[validate(length(max = 255))]
+    pub api_key: Option<String>,
+    /// Start after this event; 0 (the default) delivers the whole stream
+    #[validate(range(min = 0))]
+    pub position: Option<i64>,
+}
+
+/// A batch a consumer failed to accept after every retry. The consumer moved on past it;
+/// re-driving sends the batch again.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct EventDeadLetter {
+    pub dead_letter_id: i64,
+    pub consumer_id: i32,
+    pub first_event_id: i64,
+    pub last_event_id: i64,
+    pub event_count: i32,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: String,
+    pub dead_lettered_at: DateTime<Utc>,
+    pub redriven_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetterDetail {
+    #[serde(flatten)]
+    pub dead_letter: EventDeadLetter,
+    pub events: Vec<StockEvent>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeadLetterFilter {
+    pub consumer_id: Option<i32>,
+    pub status: Option<String>,
+}
+
+/// Body POSTed to a consumer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventBatch {
+    pub consumer_name: String,
+    pub events: Vec<StockEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_replay"))]
+pub struct ReplayRequest {
+    #[validate(custom(function = "validate_target"))]
+    pub target: String,
+    /// Required for CONSUMER replays
+    pub consumer_id: Option<i32>,
+    /// First event to replay; 0 replays the whole stream
+    #[validate(range(min = 0))]
+    pub from_event_id: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub target: String,
+    pub from_event_id: i64,
+    /// Events replayed
+    pub events: i64,
+    /// Stock balances (or read model rows) rebuilt; events delivered for consumers
+    pub rebuilt: i64,
+}
+
+fn validate_target(target: &str) -> Result<(), ValidationError> {
+    if REPLAY_TARGETS.contains(&target) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_replay_target"))
+    }
+}
+
+fn validate_replay(request: &ReplayRequest) -> Result<(), ValidationError> {
+    if (request.target == REPLAY_CONSUMER) != request.consumer_id.is_some() {
+        return Err(ValidationError::new("consumer_id_only_for_consumer_replays"));
+    }
+    Ok(())
+}
@@ -0,0 +1,253 @@
+//! User-defined reports: columns of one entity from the report builder's catalog,
+//! filtered, optionally grouped and aggregated, and run on demand
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidateEmail, ValidationError};
+
+use crate::CronSchedule;
+
+pub const REPORT_TEXT: &str = "TEXT";
+pub const REPORT_NUMBER: &str = "NUMBER";
+pub const REPORT_DATE: &str = "DATE";
+pub const REPORT_BOOLEAN: &str = "BOOLEAN";
+
+pub const REPORT_EQ: &str = "EQ";
+pub const REPORT_NE: &str = "NE";
+pub const REPORT_LT: &str = "LT";
+pub const REPORT_LTE: &str = "LTE";
+pub const REPORT_GT: &str = "GT";
+pub const REPORT_GTE: &str = "GTE";
+/// Case-insensitive substring match on text columns
+pub const REPORT_CONTAINS: &str = "CONTAINS";
+pub const REPORT_IS_NULL: &str = "IS_NULL";
+pub const REPORT_NOT_NULL: &str = "NOT_NULL";
+pub const REPORT_OPERATORS: &[&str] = &[
+    REPORT_EQ,
+    REPORT_NE,
+    REPORT_LT,
+    REPORT_LTE,
+    REPORT_GT,
+    REPORT_GTE,
+    REPORT_CONTAINS,
+    REPORT_IS_NULL,
+    REPORT_NOT_NULL,
+];
+
+pub const REPORT_COUNT: &str = "COUNT";
+pub const REPORT_SUM: &str = "SUM";
+pub const REPORT_AVG: &str = "AVG";
+pub const REPORT_MIN: &str = "MIN";
+pub const REPORT_MAX: &str = "MAX";
+pub const REPORT_AGGREGATES: &[&str] = &[REPORT_COUNT, REPORT_SUM, REPORT_AVG, REPORT_MIN, REPORT_MAX];
+
+/// Rows a report returns at most; `ReportResult::truncated` tells when there were more
+pub const REPORT_MAX_ROWS: i64 = 10_000;
+
+/// Rows attached as a CSV file
+pub const REPORT_FORMAT_CSV: &str = "CSV";
+/// Rows as a table in the message body
+pub const REPORT_FORMAT_HTML: &str = "HTML";
+pub const REPORT_FORMATS: &[&str] = &[REPORT_FORMAT_CSV, REPORT_FORMAT_HTML];
+
+pub const REPORT_DELIVERY_SENT: &str = "SENT";
+pub const REPORT_DELIVERY_FAILED: &str = "FAILED";
+
+/// A column reports can select, filter and group on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportColumn {
+    pub name: String,
+    /// TEXT, NUMBER, DATE or BOOLEAN
+    pub kind: String,
+}
+
+/// What reports can be defined over
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportEntity {
+    pub entity: String,
+    pub columns: Vec<ReportColumn>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ReportDefinition {
+    pub report_id: i32,
+    pub report_code: String,
+    pub report_name: String,
+    pub description: Option<String>,
+    pub entity: String,
+    pub columns: Vec<String>,
+    pub group_by: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Rows whose `column_name` compares to `value` by `operator`; all of a report's filters apply
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, Deserialize, Validate)]
+pub struct ReportFilter {
+    #[validate(length(min = 1, max = 50))]
+    pub column_name: String,
+    #[validate(custom(function = "validate_operator"))]
+    pub operator: String,
+    #[validate(length(max = 255))]
+    pub value: Option<String>,
+}
+
+/// `function` of `column_name`, or COUNT of rows without a column
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, Deserialize, Validate)]
+pub struct ReportAggregate {
+    #[validate(custom(function = "validate_aggregate"))]
+    pub function: String,
+    #[validate(length(min = 1, max = 50))]
+    pub column_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportDefinitionDetail {
+    #[serde(flatten)]
+    pub definition: ReportDefinition,
+    pub filters: Vec<ReportFilter>,
+    pub aggregates: Vec<ReportAggregate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateReportDefinition {
+    #[validate(length(min = 1, max = 50))]
+    pub report_code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub report_name: String,
+    pub description: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub entity: String,
+    #[validate(length(max = 50))]
+    pub columns: Vec<String>,
+    #[serde(default)]
+    #[validate(length(max = 20), nested)]
+    pub filters: Vec<ReportFilter>,
+    #[serde(default)]
+    #[validate(length(max = 10))]
+    pub group_by: Vec<String>,
+    #[serde(default)]
+    #[validate(length(max = 20), nested)]
+    pub aggregates: Vec<ReportAggregate>,
+}
+
+/// Change a report; each list given replaces the report's own. Its entity stays as created.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateReportDefinition {
+    #[validate(length(min = 1, max = 255))]
+    pub report_name: Option<String>,
+    pub description: Option<String>,
+    #[validate(length(max = 50))]
+    pub columns: Option<Vec<String>>,
+    #[validate(length(max = 20), nested)]
+    pub filters: Option<Vec<ReportFilter>>,
+    #[validate(length(max = 10))]
+    pub group_by: Option<Vec<String>>,
+    #[validate(length(max = 20), nested)]
+    pub aggregates: Option<Vec<ReportAggregate>>,
+}
+
+/// A report's output: values as text, in the order of `columns`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportResult {
+    pub columns: Vec<String>,
+    /// Display headings of `columns` in the reader's language; empty until the API or a
+    /// delivery fills them in
+    #[serde(default)]
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<Option<String>>>,
+    /// More rows matched than `REPORT_MAX_ROWS`
+    pub truncated: bool,
+}
+
+/// Email a report's result to `recipients` whenever `cron_expression` comes due
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ReportSchedule {
+    pub schedule_id: i32,
+    pub report_id: i32,
+    /// See [`CronSchedule`], in UTC
+    pub cron_expression: String,
+    pub recipients: Vec<String>,
+    pub format: String,
+    pub is_active: bool,
+    pub next_run_at: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateReportSchedule {
+    #[validate(length(max = 100), custom(function = "validate_cron"))]
+    pub cron_expression: String,
+    #[validate(length(min = 1, max = 50), custom(function = "validate_recipients"))]
+    pub recipients: Vec<String>,
+    /// CSV or HTML; CSV when omitted
+    #[validate(custom(function = "validate_format"))]
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateReportSchedule {
+    #[validate(length(max = 100), custom(function = "validate_cron"))]
+    pub cron_expression: Option<String>,
+    #[validate(length(min = 1, max = 50), custom(function = "validate_recipients"))]
+    pub recipients: Option<Vec<String>>,
+    #[validate(custom(function = "validate_format"))]
+    pub format: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+/// One delivery of a schedule, scheduled or run by hand
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ReportScheduleRun {
+    pub run_id: i64,
+    pub schedule_id: i32,
+    pub status: String,
+    pub row_count: i32,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+}
+
+fn validate_cron(expression: &str) -> Result<(), ValidationError> {
+    match CronSchedule::parse(expression) {
+        Ok(schedule) if schedule.next_after(Utc::now()).is_some() => Ok(()),
+        Ok(_) => Err(ValidationError::new("cron_never_runs")),
+        Err(_) => Err(ValidationError::new("invalid_cron")),
+    }
+}
+
+fn validate_recipients(recipients: &[String]) -> Result<(), ValidationError> {
+    let valid = |recipient: &String| recipient.len() <= 255 && recipient.validate_email();
+    if recipients.iter().all(valid) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_recipient"))
+    }
+}
+
+fn validate_format(format: &str) -> Result<(), ValidationError> {
+    if REPORT_FORMATS.contains(&format) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_format"))
+    }
+}
+
+fn validate_operator(operator: &str) -> Result<(), ValidationError> {
+    if REPORT_OPERATORS.contains(&operator) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_operator"))
+    }
+}
+
+fn validate_aggregate(function: &str) -> Result<(), ValidationError> {
+    if REPORT_AGGREGATES.contains(&function) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_aggregate"))
+    }
+}
@@ -0,0 +1,57 @@
+//! Stock reservations: quantities held back from a warehouse's available stock for an
+//! order, job or customer until they are released
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+use crate::validate_positive;
+
+/// Ledger reference of the moves between the ON_HAND and RESERVED buckets
+pub const REFERENCE_STOCK_RESERVATION: &str = "STOCK_RESERVATION";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StockReservation {
+    pub reservation_id: i32,
+    pub warehouse_id: i32,
+    pub item_id: i32,
+    pub quantity: Decimal,
+    /// Order, job or customer the stock is held for
+    pub reference: Option<String>,
+    /// ACTIVE or RELEASED
+    pub status: String,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub released_at: Option<DateTime<Utc>>,
+}
+
+/// Hold back our own stock of an item in a warehouse, out of what is available
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateStockReservation {
+    pub warehouse_id: i32,
+    pub item_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+    #[validate(length(min = 1, max = 100))]
+    pub reference: Option<String>,
+    #[validate(length(max = 1000))]
+    pub notes: Option<String>,
+}
+
+/// Reservation ids are numbered per database, so the release of one held on a database
+/// shard names its warehouse
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReleaseReservationQuery {
+    pub warehouse_id: Option<i32>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StockReservationFilter {
+    pub warehouse_id: Option<i32>,
+    pub item_id: Option<i32>,
+    pub reference: Option<String>,
+    /// ACTIVE or RELEASED
+    pub status: Option<String>,
+}
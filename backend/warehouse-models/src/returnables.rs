@@ -0,0 +1,108 @@
+//! Returnable packaging issued to customers with their deliveries and expected back, with
+//! the balance each customer site holds and how long it has held it
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+pub const RETURNABLE_ISSUE: &str = "ISSUE";
+pub const RETURNABLE_RETURN: &str = "RETURN";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ReturnableType {
+    pub type_id: i32,
+    pub type_code: String,
+    pub description: String,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateReturnableType {
+    #[validate(length(min = 1, max = 20))]
+    pub type_code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub description: String,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ReturnableMovement {
+    pub movement_id: i64,
+    pub type_id: i32,
+    pub warehouse_id: i32,
+    pub customer_name: String,
+    pub site_name: Option<String>,
+    pub direction: String,
+    pub quantity: i32,
+    pub shipment_id: Option<i32>,
+    pub notes: Option<String>,
+    pub moved_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ReturnableLine {
+    pub type_id: i32,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+}
+
+/// Units that went out with a shipment, to its order's customer and ship-to site
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct IssueReturnables {
+    #[validate(length(min = 1, max = 20), nested)]
+    pub lines: Vec<ReturnableLine>,
+    #[validate(length(max = 1000))]
+    pub notes: Option<String>,
+}
+
+/// Units a customer site brought or sent back to a warehouse
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ReceiveReturnables {
+    pub warehouse_id: i32,
+    #[validate(length(min = 1, max = 255))]
+    pub customer_name: String,
+    #[validate(length(min = 1, max = 255))]
+    pub site_name: Option<String>,
+    #[validate(length(min = 1, max = 20), nested)]
+    pub lines: Vec<ReturnableLine>,
+    #[validate(length(max = 1000))]
+    pub notes: Option<String>,
+}
+
+/// What a customer site holds of a returnable type
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ReturnableBalance {
+    pub type_id: i32,
+    pub type_code: String,
+    pub customer_name: String,
+    pub site_name: Option<String>,
+    pub issued: i64,
+    pub returned: i64,
+    pub outstanding: i64,
+    pub last_issued_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReturnableFilter {
+    pub customer_name: Option<String>,
+    pub type_id: Option<i32>,
+    /// Aging only: count days up to this date, today unless given
+    pub as_of: Option<NaiveDate>,
+}
+
+/// Outstanding units of a customer site by how long ago they were issued; returns are
+/// set against the oldest issues first
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ReturnableAging {
+    pub type_id: i32,
+    pub type_code: String,
+    pub customer_name: String,
+    pub site_name: Option<String>,
+    pub days_0_30: i64,
+    pub days_31_60: i64,
+    pub days_61_90: i64,
+    pub days_over_90: i64,
+    pub outstanding: i64,
+    pub oldest_issued_on: NaiveDate,
+}
@@ -0,0 +1,148 @@
+//! Customer return authorization (RMA) models
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::validate_positive;
+
+pub const RETURN_REASONS: &[&str] = &["DAMAGED", "DEFECTIVE", "WRONG_ITEM", "NOT_NEEDED", "OTHER"];
+
+pub const RETURN_AUTHORIZED: &str = "AUTHORIZED";
+pub const RETURN_RECEIVED: &str = "RECEIVED";
+pub const RETURN_COMPLETED: &str = "COMPLETED";
+pub const RETURN_CANCELLED: &str = "CANCELLED";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ReturnAuthorization {
+    pub return_id: i32,
+    pub rma_number: String,
+    pub warehouse_id: i32,
+    pub customer_name: String,
+    pub customer_reference: Option<String>,
+    pub reason: String,
+    pub status: String,
+    pub notes: Option<String>,
+    pub received_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub created_by: Option<i32>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ReturnLine {
+    pub line_id: i32,
+    pub return_id: i32,
+    pub item_id: i32,
+    pub reason: Option<String>,
+    pub quantity_expected: Decimal,
+    pub quantity_received: Decimal,
+    pub quantity_restocked: Decimal,
+    pub quantity_scrapped: Decimal,
+    pub quantity_repair: Decimal,
+    pub inspection_notes: Option<String>,
+}
+
+impl ReturnLine {
+    /// Received quantity still waiting for an inspection outcome (held in quarantine)
+    pub fn quantity_uninspected(&self) -> Decimal {
+        self.quantity_received - self.quantity_restocked - self.quantity_scrapped - self.quantity_repair
+    }
+}
+
+/// An RMA together with its expected items
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReturnDetail {
+    #[serde(flatten)]
+    pub authorization: ReturnAuthorization,
+    pub lines: Vec<ReturnLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateReturn {
+    pub warehouse_id: i32,
+    #[validate(length(min = 1, max = 255))]
+    pub customer_name: String,
+    #[validate(length(max = 100))]
+    pub customer_reference: Option<String>,
+    #[validate(custom(function = "validate_return_reason"))]
+    pub reason: String,
+    pub notes: Option<String>,
+    #[validate(length(min = 1), nested)]
+    pub lines: Vec<CreateReturnLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateReturnLine {
+    pub item_id: i32,
+    /// Overrides the RMA-level reason for this item
+    #[validate(custom(function = "validate_return_reason"))]
+    pub reason: Option<String>,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity_expected: Decimal,
+}
+
+/// Goods arriving at the dock against an RMA; they are posted into quarantine
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ReceiveReturn {
+    #[validate(length(min = 1), nested)]
+    pub lines: Vec<ReceiveReturnLine>,
+    /// Client key for this receipt; a retry with the same key is not received twice
+    #[serde(default)]
+    #[validate(length(min = 1, max = 100))]
+    pub reference_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ReceiveReturnLine {
+    pub line_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum InspectionOutcome {
+    /// Released from quarantine back into available stock
+    Restock,
+    /// Written off out of quarantine
+    Scrap,
+    /// Kept in quarantine until repaired
+    Repair,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct InspectReturn {
+    #[validate(length(min = 1), nested)]
+    pub lines: Vec<InspectReturnLine>,
+    /// Client key for these results; a retry with the same key is not applied twice
+    #[serde(default)]
+    #[validate(length(min = 1, max = 100))]
+    pub reference_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct InspectReturnLine {
+    pub line_id: i32,
+    pub outcome: InspectionOutcome,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReturnFilter {
+    pub status: Option<String>,
+    pub warehouse_id: Option<i32>,
+}
+
+fn validate_return_reason(reason: &str) -> Result<(), ValidationError> {
+    if RETURN_REASONS.contains(&reason) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_return_reason"))
+    }
+}
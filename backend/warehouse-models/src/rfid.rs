@@ -0,0 +1,116 @@
+//! RFID portals, tag registry and portal read ingestion
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::{validate_positive, StockMovement};
+
+pub const PORTAL_IN: &str = "IN";
+pub const PORTAL_OUT: &str = "OUT";
+pub const PORTAL_DIRECTIONS: &[&str] = &[PORTAL_IN, PORTAL_OUT];
+
+/// Reads of the same tag at the same portal within this many seconds are one pass
+pub const RFID_DEDUP_SECONDS: i32 = 60;
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct RfidPortal {
+    pub portal_id: i32,
+    pub warehouse_id: i32,
+    pub portal_code: String,
+    pub portal_name: String,
+    pub direction: String,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreatePortal {
+    #[validate(length(min = 1, max = 30))]
+    pub portal_code: String,
+    #[validate(length(min = 1, max = 100))]
+    pub portal_name: String,
+    #[validate(custom(function = "validate_direction"))]
+    pub direction: String,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct RfidTag {
+    pub epc: String,
+    pub item_id: i32,
+    pub serial_number: Option<String>,
+    pub lpn: Option<String>,
+    pub quantity: Decimal,
+    pub warehouse_id: Option<i32>,
+    pub last_portal_id: Option<i32>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Register or re-map tags; re-registering an EPC replaces its mapping
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RegisterTags {
+    #[validate(length(min = 1, max = 5000), nested)]
+    pub tags: Vec<RegisterTag>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RegisterTag {
+    #[validate(custom(function = "validate_epc"))]
+    pub epc: String,
+    pub item_id: i32,
+    #[validate(length(max = 100))]
+    pub serial_number: Option<String>,
+    #[validate(length(max = 50))]
+    pub lpn: Option<String>,
+    /// Defaults to one unit
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Option<Decimal>,
+    /// Warehouse the tagged stock already sits in, if any
+    pub warehouse_id: Option<i32>,
+}
+
+/// One batch of reads from a portal reader
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct PortalReads {
+    /// When the reads were taken; defaults to now
+    pub read_at: Option<DateTime<Utc>>,
+    #[validate(length(min = 1, max = 10000), custom(function = "validate_epcs"))]
+    pub epcs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RfidIngestResult {
+    /// EPCs in the batch, repeats included
+    pub received: usize,
+    /// Distinct EPCs not already read at this portal within the de-duplication window
+    pub accepted: usize,
+    pub unknown_epcs: Vec<String>,
+    /// Tags whose stock moved into or out of the warehouse
+    pub crossings: usize,
+    pub movements: Vec<StockMovement>,
+    /// Crossings whose stock could not be posted, e.g. because it was already issued
+    pub exceptions: Vec<String>,
+}
+
+fn validate_direction(direction: &str) -> Result<(), ValidationError> {
+    if PORTAL_DIRECTIONS.contains(&direction) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_direction"))
+    }
+}
+
+fn validate_epc(epc: &str) -> Result<(), ValidationError> {
+    if !epc.is_empty() && epc.len() <= 64 && epc.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_epc"))
+    }
+}
+
+fn validate_epcs(epcs: &[String]) -> Result<(), ValidationError> {
+    epcs.iter().try_for_each(|epc| validate_epc(epc))
+}
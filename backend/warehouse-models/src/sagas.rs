@@ -0,0 +1,23 @@
+//! Sagas: operations spanning several commits, e.g. two warehouses and a carrier, logged
+//! step by step so a failure can be compensated
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+pub const SAGA_STEP_COMPLETED: &str = "COMPLETED";
+pub const SAGA_STEP_FAILED: &str = "FAILED";
+pub const SAGA_STEP_COMPENSATED: &str = "COMPENSATED";
+pub const SAGA_STEP_COMPENSATION_FAILED: &str = "COMPENSATION_FAILED";
+
+/// One entry of a saga's log
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SagaStep {
+    pub step_id: i64,
+    pub saga_type: String,
+    pub saga_id: i32,
+    pub step: String,
+    pub status: String,
+    pub detail: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+}
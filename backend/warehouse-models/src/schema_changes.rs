@@ -0,0 +1,51 @@
+//! Progress and verification of expand-contract schema changes
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+pub const BACKFILL_RUNNING: &str = "RUNNING";
+pub const BACKFILL_COMPLETED: &str = "COMPLETED";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct BackfillJob {
+    pub job_name: String,
+    pub table_name: String,
+    pub column_name: String,
+    pub status: String,
+    /// Highest key backfilled so far
+    pub last_key: Option<i64>,
+    pub rows_updated: i64,
+    pub estimated_rows: i64,
+    pub batches: i32,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl BackfillJob {
+    /// Share of the estimated rows backfilled, capped at 100
+    pub fn percent_done(&self) -> f64 {
+        if self.status == BACKFILL_COMPLETED {
+            return 100.0;
+        }
+        if self.estimated_rows <= 0 {
+            return 0.0;
+        }
+        (self.rows_updated as f64 * 100.0 / self.estimated_rows as f64).min(100.0)
+    }
+}
+
+/// How far the new column is from what the expression gives for every row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackfillVerification {
+    pub rows: i64,
+    /// Rows whose new column differs from the expression, nulls included
+    pub mismatched: i64,
+}
+
+impl BackfillVerification {
+    pub fn is_consistent(&self) -> bool {
+        self.mismatched == 0
+    }
+}
@@ -0,0 +1,56 @@
+//! Warehouse shifts and the operators rostered on them
+
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+/// A daily shift in warehouse local time; a shift whose end is not after its start
+/// runs past midnight and belongs to the day it started
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Shift {
+    pub shift_id: i32,
+    pub warehouse_id: i32,
+    pub shift_code: String,
+    pub shift_name: String,
+    pub starts_at: NaiveTime,
+    pub ends_at: NaiveTime,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShiftDetail {
+    #[serde(flatten)]
+    pub shift: Shift,
+    pub operator_ids: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateShift {
+    #[validate(length(min = 1, max = 20))]
+    pub shift_code: String,
+    #[validate(length(min = 1, max = 100))]
+    pub shift_name: String,
+    pub starts_at: NaiveTime,
+    pub ends_at: NaiveTime,
+    /// Initial roster
+    #[serde(default)]
+    pub operator_ids: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct UpdateShift {
+    #[validate(length(min = 1, max = 100))]
+    pub shift_name: Option<String>,
+    pub starts_at: Option<NaiveTime>,
+    pub ends_at: Option<NaiveTime>,
+    /// Inactive shifts no longer gate task claims or appear in reports
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RosterOperators {
+    #[validate(length(min = 1))]
+    pub operator_ids: Vec<i32>,
+}
@@ -0,0 +1,120 @@
+//! Packing and receiving stations with connected scales and dimensioners
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::validate_positive;
+
+pub const STATION_PACKING: &str = "PACKING";
+pub const STATION_RECEIVING: &str = "RECEIVING";
+pub const STATION_TYPES: &[&str] = &[STATION_PACKING, STATION_RECEIVING];
+
+/// Units a device may report in; captures are stored in kg and cm
+pub const WEIGHT_UNITS: &[&str] = &["kg", "g", "lb", "oz"];
+pub const LENGTH_UNITS: &[&str] = &["cm", "mm", "m", "in"];
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct MeasuringStation {
+    pub station_id: i32,
+    pub warehouse_id: i32,
+    pub station_code: String,
+    pub station_name: String,
+    pub station_type: String,
+    pub is_active: bool,
+    pub last_capture_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateStation {
+    #[validate(length(min = 1, max = 30))]
+    pub station_code: String,
+    #[validate(length(min = 1, max = 100))]
+    pub station_name: String,
+    #[validate(custom(function = "validate_station_type"))]
+    pub station_type: String,
+}
+
+/// What a station's devices sent for one carton (packing) or one item (receiving).
+/// The weight comes either as the scale's raw output frame or as a plain value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_capture"))]
+pub struct CaptureMeasurement {
+    pub carton_id: Option<i32>,
+    pub item_id: Option<i32>,
+    /// Raw scale frame, e.g. MT-SICS `S S      1.250 kg` or `ST,GS,+0001.250kg`
+    #[validate(length(min = 1, max = 100))]
+    pub scale_output: Option<String>,
+    #[validate(custom(function = "validate_positive"))]
+    pub weight: Option<Decimal>,
+    /// Unit of `weight`; defaults to kg
+    pub weight_unit: Option<String>,
+    #[validate(custom(function = "validate_positive"))]
+    pub length: Option<Decimal>,
+    #[validate(custom(function = "validate_positive"))]
+    pub width: Option<Decimal>,
+    #[validate(custom(function = "validate_positive"))]
+    pub height: Option<Decimal>,
+    /// Unit of the dimensions; defaults to cm
+    pub dimension_unit: Option<String>,
+    /// When the devices took the measurement; defaults to now
+    pub captured_at: Option<DateTime<Utc>>,
+}
+
+/// A capture normalised to kg and cm, ready to store
+#[derive(Debug, Clone, Default)]
+pub struct NewCapture {
+    pub carton_id: Option<i32>,
+    pub item_id: Option<i32>,
+    pub weight_kg: Option<Decimal>,
+    pub length_cm: Option<Decimal>,
+    pub width_cm: Option<Decimal>,
+    pub height_cm: Option<Decimal>,
+    pub scale_output: Option<String>,
+    pub captured_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StationCapture {
+    pub capture_id: i64,
+    pub station_id: i32,
+    pub carton_id: Option<i32>,
+    pub item_id: Option<i32>,
+    pub weight_kg: Option<Decimal>,
+    pub length_cm: Option<Decimal>,
+    pub width_cm: Option<Decimal>,
+    pub height_cm: Option<Decimal>,
+    pub scale_output: Option<String>,
+    pub captured_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+fn validate_station_type(station_type: &str) -> Result<(), ValidationError> {
+    if STATION_TYPES.contains(&station_type) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_station_type"))
+    }
+}
+
+fn validate_capture(capture: &CaptureMeasurement) -> Result<(), ValidationError> {
+    let dimensions = [capture.length, capture.width, capture.height];
+    if capture.carton_id.is_some() == capture.item_id.is_some() {
+        Err(ValidationError::new("capture_one_carton_or_item"))
+    } else if capture.scale_output.is_some() && capture.weight.is_some() {
+        Err(ValidationError::new("scale_output_and_weight_both_given"))
+    } else if dimensions.iter().any(Option::is_some) && !dimensions.iter().all(Option::is_some) {
+        Err(ValidationError::new("incomplete_dimensions"))
+    } else if capture.scale_output.is_none() && capture.weight.is_none() && dimensions[0].is_none() {
+        Err(ValidationError::new("nothing_measured"))
+    } else if capture.weight_unit.as_deref().is_some_and(|unit| !WEIGHT_UNITS.contains(&unit)) {
+        Err(ValidationError::new("unknown_weight_unit"))
+    } else if capture.dimension_unit.as_deref().is_some_and(|unit| !LENGTH_UNITS.contains(&unit)) {
+        Err(ValidationError::new("unknown_dimension_unit"))
+    } else {
+        Ok(())
+    }
+}
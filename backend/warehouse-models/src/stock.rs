@@ -0,0 +1,78 @@
+//! Stock balance and point-in-time snapshot models
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Balance of one owner's stock of an item in a warehouse
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StockBalance {
+    pub item_id: i32,
+    pub item_code: String,
+    pub item_name: String,
+    pub warehouse_id: i32,
+    /// Owner of the stock; None for our own
+    pub consignor_id: Option<i32>,
+    /// 3PL client the stock is stored for; None for our own
+    pub client_id: Option<i32>,
+    pub quantity_on_hand: Decimal,
+    pub quantity_quarantined: Decimal,
+    pub catch_weight_kg: Option<Decimal>,
+}
+
+/// Stock on hand at the end of `as_of` (UTC)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockSnapshot {
+    pub as_of: NaiveDate,
+    pub balances: Vec<StockBalance>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StockFilter {
+    pub warehouse_id: Option<i32>,
+    pub item_id: Option<i32>,
+    /// Matches item codes and names containing this text
+    pub q: Option<String>,
+    /// Reconstruct balances as of the end of this day; today when omitted
+    pub as_of: Option<NaiveDate>,
+}
+
+pub const STOCK_EVENT_OPENED: &str = "OPENED";
+pub const STOCK_EVENT_CHANGED: &str = "CHANGED";
+pub const STOCK_EVENT_CLOSED: &str = "CLOSED";
+
+/// One change to a stock balance, in the balance's event stream
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StockEvent {
+    pub event_id: i64,
+    pub item_id: i32,
+    pub warehouse_id: i32,
+    pub consignor_id: Option<i32>,
+    /// Position in the balance's stream, from 1
+    pub version: i32,
+    pub event_type: String,
+    pub on_hand_delta: Decimal,
+    pub reserved_delta: Decimal,
+    pub quarantined_delta: Decimal,
+    pub catch_weight_delta: Decimal,
+    /// 3PL client the balance belongs to after the event
+    pub client_id: Option<i32>,
+    /// Database transaction that made the change; events of one posting share it
+    pub transaction_id: i64,
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StockEventFilter {
+    pub item_id: Option<i32>,
+    pub warehouse_id: Option<i32>,
+    pub consignor_id: Option<i32>,
+}
+
+/// Outcome of folding stock events into the balances projection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockProjection {
+    /// Balances written to the projection
+    pub streams: i64,
+}
@@ -0,0 +1,61 @@
+//! Stock-out incidents recorded when demand finds no free stock to allocate, and the lost
+//! demand they add up to per item
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Reference type of incidents raised by an order line allocated nothing
+pub const STOCK_OUT_ORDER: &str = "ORDER";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StockOutIncident {
+    pub incident_id: i64,
+    pub warehouse_id: i32,
+    pub item_id: i32,
+    pub quantity_demanded: Decimal,
+    /// Customer or person whose demand went unmet
+    pub requested_by: String,
+    pub reference_type: String,
+    pub reference_number: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// An incident to record
+#[derive(Debug, Clone)]
+pub struct NewStockOut {
+    pub warehouse_id: i32,
+    pub item_id: i32,
+    pub quantity_demanded: Decimal,
+    pub requested_by: String,
+    pub reference_type: &'static str,
+    pub reference_number: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StockOutFilter {
+    pub warehouse_id: Option<i32>,
+    pub item_id: Option<i32>,
+    /// Incidents on or after; the lost-demand report defaults to ninety days ago
+    pub from: Option<NaiveDate>,
+    /// Incidents on or before; the lost-demand report defaults to today
+    pub to: Option<NaiveDate>,
+}
+
+/// How often an item's demand went unmet in a warehouse over the report's window
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct LostDemand {
+    pub warehouse_id: i32,
+    pub item_id: i32,
+    pub item_code: String,
+    pub incidents: i64,
+    /// Days in the window with at least one incident
+    pub stock_out_days: i64,
+    /// Share of the window's days with at least one incident
+    pub stock_out_day_pct: Decimal,
+    pub quantity_lost: Decimal,
+    pub requesters: i64,
+    pub first_occurred_at: DateTime<Utc>,
+    pub last_occurred_at: DateTime<Utc>,
+}
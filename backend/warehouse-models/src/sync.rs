@@ -0,0 +1,119 @@
+//! Delta sync and offline mutation batches for handheld devices
+
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{ClaimTask, CompleteTask, Location, Task};
+
+pub const SYNC_ENTITY_TASK: &str = "TASK";
+pub const SYNC_ENTITY_LOCATION: &str = "LOCATION";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncQuery {
+    pub warehouse_id: i32,
+    /// Cursor from the previous sync; omit for a full snapshot
+    pub since: Option<i64>,
+}
+
+/// Everything that changed in a warehouse since the cursor. Entities can be repeated
+/// across consecutive deltas, so clients should upsert by id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncDelta {
+    /// Pass as `since` on the next sync
+    pub cursor: i64,
+    /// True when this is a full snapshot rather than changes since a cursor
+    pub full: bool,
+    pub tasks: Vec<Task>,
+    pub locations: Vec<Location>,
+}
+
+/// Who wins when the task changed on the server after the device last synced it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ConflictPolicy {
+    /// Reject the device's change and hand back the server's task
+    #[default]
+    ServerWins,
+    /// Apply the device's change as long as the task can still make the transition
+    ClientWins,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SyncBatch {
+    #[validate(length(min = 1, max = 100))]
+    pub device_id: String,
+    /// Default for mutations that do not set their own
+    #[serde(default)]
+    pub policy: ConflictPolicy,
+    /// Applied in order
+    #[validate(length(min = 1, max = 500), nested)]
+    pub mutations: Vec<SyncMutation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SyncMutation {
+    /// Unique per device; resubmitting the same id returns the recorded outcome
+    #[validate(length(min = 1, max = 64))]
+    pub mutation_id: String,
+    pub task_id: i32,
+    /// Task version the device last saw
+    pub base_version: i32,
+    pub policy: Option<ConflictPolicy>,
+    #[serde(flatten)]
+    pub action: TaskAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TaskAction {
+    Claim(ClaimTask),
+    Complete(CompleteTask),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MutationStatus {
+    Applied,
+    /// The task moved on since `base_version` and the server won
+    Conflict,
+    /// The task could not make the transition, or does not exist
+    Rejected,
+}
+
+impl MutationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MutationStatus::Applied => "APPLIED",
+            MutationStatus::Conflict => "CONFLICT",
+            MutationStatus::Rejected => "REJECTED",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "APPLIED" => Some(MutationStatus::Applied),
+            "CONFLICT" => Some(MutationStatus::Conflict),
+            "REJECTED" => Some(MutationStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MutationResult {
+    pub mutation_id: String,
+    pub status: MutationStatus,
+    pub message: Option<String>,
+    /// True when this outcome was recorded for an earlier submission of the mutation
+    pub replayed: bool,
+    /// The task as the server now has it
+    pub task: Option<Task>,
+}
+
+/// A device mutation outcome as stored for replays
+#[derive(Debug, Clone)]
+pub struct RecordedMutation {
+    pub task_id: i32,
+    pub status: String,
+    pub message: Option<String>,
+}
@@ -0,0 +1,135 @@
+//! Operator task queue: receive, putaway, pick, count and move work assigned to floor operators
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+pub const TASK_RECEIVE: &str = "RECEIVE";
+pub const TASK_PUTAWAY: &str = "PUTAWAY";
+pub const TASK_PICK: &str = "PICK";
+pub const TASK_COUNT: &str = "COUNT";
+pub const TASK_MOVE: &str = "MOVE";
+pub const TASK_TYPES: &[&str] = &[TASK_RECEIVE, TASK_PUTAWAY, TASK_PICK, TASK_COUNT, TASK_MOVE];
+
+pub const TASK_OPEN: &str = "OPEN";
+/// Given to an operator by a supervisor but not started
+pub const TASK_ASSIGNED: &str = "ASSIGNED";
+pub const TASK_IN_PROGRESS: &str = "IN_PROGRESS";
+pub const TASK_COMPLETED: &str = "COMPLETED";
+pub const TASK_CANCELLED: &str = "CANCELLED";
+
+/// Queue tasks generated for wave pick tasks reference them with this type
+pub const TASK_REF_PICK: &str = "PICK_TASK";
+/// Receiving tasks queued for expected receipts reference them with this type
+pub const TASK_REF_RECEIPT: &str = "RECEIPT";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Task {
+    pub task_id: i32,
+    pub warehouse_id: i32,
+    pub task_type: String,
+    pub priority: i32,
+    pub status: String,
+    pub item_id: Option<i32>,
+    pub quantity: Option<Decimal>,
+    pub from_location_id: Option<i32>,
+    pub to_location_id: Option<i32>,
+    pub reference_type: Option<String>,
+    pub reference_id: Option<i32>,
+    pub notes: Option<String>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub assigned_to: Option<i32>,
+    pub assigned_at: Option<DateTime<Utc>>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub completed_by: Option<i32>,
+    pub quantity_done: Option<Decimal>,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
+    /// Bumped on every change; offline devices send it back to detect conflicts
+    pub version: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateTask {
+    pub warehouse_id: i32,
+    #[validate(custom(function = "validate_task_type"))]
+    pub task_type: String,
+    #[validate(range(min = 0, max = 100))]
+    pub priority: Option<i32>,
+    pub item_id: Option<i32>,
+    pub quantity: Option<Decimal>,
+    pub from_location_id: Option<i32>,
+    pub to_location_id: Option<i32>,
+    pub notes: Option<String>,
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+/// Supervisor hands a task to an operator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignTask {
+    pub operator_id: i32,
+}
+
+/// Operator starts a specific task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimTask {
+    pub operator_id: i32,
+}
+
+/// Operator asks for the next task: their own assigned work first, then the most urgent open task
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ClaimNextTask {
+    pub warehouse_id: i32,
+    pub operator_id: i32,
+    /// Only these task types; any type otherwise
+    #[validate(custom(function = "validate_task_types"))]
+    pub task_types: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CompleteTask {
+    pub operator_id: i32,
+    /// Quantity actually moved, picked or counted; defaults to the task quantity
+    pub quantity_done: Option<Decimal>,
+    #[validate(length(max = 1000))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TaskFilter {
+    pub warehouse_id: Option<i32>,
+    pub task_type: Option<String>,
+    pub status: Option<String>,
+    pub assigned_to: Option<i32>,
+}
+
+/// Work waiting in one warehouse's queue for one task type
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct QueueDepth {
+    pub warehouse_id: i32,
+    pub task_type: String,
+    pub open: i64,
+    pub assigned: i64,
+    pub in_progress: i64,
+    pub oldest_waiting_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct QueueDepthFilter {
+    pub warehouse_id: Option<i32>,
+}
+
+fn validate_task_type(task_type: &str) -> Result<(), ValidationError> {
+    if TASK_TYPES.contains(&task_type) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_task_type"))
+    }
+}
+
+fn validate_task_types(task_types: &[String]) -> Result<(), ValidationError> {
+    task_types.iter().try_for_each(|task_type| validate_task_type(task_type))
+}
@@ -0,0 +1,132 @@
+//! Label and document templates: ZPL for label printers, HTML for documents. Each body
+//! change is kept as a new version, and each warehouse is assigned the template it prints
+//! a purpose with.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::{DOCUMENT_DELIVERY_ORDER, DOCUMENT_PROFORMA_INVOICE};
+
+/// Zebra Programming Language, sent to label printers as is
+pub const TEMPLATE_ZPL: &str = "ZPL";
+pub const TEMPLATE_HTML: &str = "HTML";
+pub const TEMPLATE_FORMATS: &[&str] = &[TEMPLATE_ZPL, TEMPLATE_HTML];
+
+pub const TEMPLATE_LPN_LABEL: &str = "LPN_LABEL";
+pub const TEMPLATE_LOCATION_LABEL: &str = "LOCATION_LABEL";
+pub const TEMPLATE_ITEM_LABEL: &str = "ITEM_LABEL";
+pub const TEMPLATE_SHIPPING_LABEL: &str = "SHIPPING_LABEL";
+pub const TEMPLATE_PURPOSES: &[&str] = &[
+    TEMPLATE_LPN_LABEL,
+    TEMPLATE_LOCATION_LABEL,
+    TEMPLATE_ITEM_LABEL,
+    TEMPLATE_SHIPPING_LABEL,
+    DOCUMENT_DELIVERY_ORDER,
+    DOCUMENT_PROFORMA_INVOICE,
+];
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PrintTemplate {
+    pub template_id: i32,
+    pub template_code: String,
+    pub name: String,
+    pub purpose: String,
+    pub format: String,
+    pub current_version: i32,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A template with the body of its current version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrintTemplateDetail {
+    #[serde(flatten)]
+    pub template: PrintTemplate,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PrintTemplateVersion {
+    pub template_id: i32,
+    pub version: i32,
+    pub body: String,
+    pub change_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreatePrintTemplate {
+    #[validate(length(min = 1, max = 50))]
+    pub template_code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(custom(function = "validate_template_purpose"))]
+    pub purpose: String,
+    #[validate(custom(function = "validate_template_format"))]
+    pub format: String,
+    #[validate(length(min = 1))]
+    pub body: String,
+    #[validate(length(max = 500))]
+    pub change_note: Option<String>,
+}
+
+/// A new body is saved as the next version; an unchanged body keeps the current one
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdatePrintTemplate {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    #[validate(length(min = 1))]
+    pub body: Option<String>,
+    /// What changed, kept on the new version
+    #[validate(length(max = 500))]
+    pub change_note: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PrintTemplateFilter {
+    pub purpose: Option<String>,
+    pub format: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+/// Print the template's purpose in a warehouse with it
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct AssignPrintTemplate {
+    pub template_id: i32,
+    /// Keep printing this version when the template changes; None follows the current one
+    #[validate(range(min = 1))]
+    pub pinned_version: Option<i32>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PrintTemplateAssignment {
+    pub warehouse_id: i32,
+    pub purpose: String,
+    pub template_id: i32,
+    pub template_code: String,
+    pub format: String,
+    pub pinned_version: Option<i32>,
+    /// The version the warehouse prints with
+    pub version: i32,
+    pub assigned_at: DateTime<Utc>,
+}
+
+fn validate_template_purpose(purpose: &str) -> Result<(), ValidationError> {
+    if TEMPLATE_PURPOSES.contains(&purpose) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_template_purpose"))
+    }
+}
+
+fn validate_template_format(format: &str) -> Result<(), ValidationError> {
+    if TEMPLATE_FORMATS.contains(&format) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_template_format"))
+    }
+}
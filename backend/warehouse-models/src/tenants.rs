@@ -0,0 +1,66 @@
+//! Tenants of a schema-per-tenant deployment, each provisioned into a schema of its own
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::CreateWarehouse;
+
+pub const TENANT_PROVISIONING: &str = "PROVISIONING";
+pub const TENANT_ACTIVE: &str = "ACTIVE";
+pub const TENANT_FAILED: &str = "FAILED";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Tenant {
+    pub tenant_id: i32,
+    pub tenant_code: String,
+    pub tenant_name: String,
+    pub schema_name: String,
+    pub status: String,
+    pub schema_version: Option<i64>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub provisioned_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateTenant {
+    /// Lowercase letters, digits and underscores; the schema is named `tenant_<code>`
+    #[validate(length(min = 2, max = 30), custom(function = "validate_tenant_code"))]
+    pub tenant_code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub tenant_name: String,
+    /// Seeded into the new schema
+    #[serde(default)]
+    #[validate(nested)]
+    pub warehouses: Vec<CreateWarehouse>,
+}
+
+impl CreateTenant {
+    pub fn schema_name(&self) -> String {
+        format!("tenant_{}", self.tenant_code)
+    }
+}
+
+/// What a client of the new tenant needs to reach its data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantProvisioned {
+    #[serde(flatten)]
+    pub tenant: Tenant,
+    pub search_path: String,
+    /// Libpq `options` for connections that should land in the tenant schema
+    pub connection_options: String,
+    pub migrations_applied: usize,
+    pub warehouses_seeded: usize,
+}
+
+fn validate_tenant_code(code: &str) -> Result<(), ValidationError> {
+    let valid = code.starts_with(|c: char| c.is_ascii_lowercase())
+        && code.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::new("tenant_code_must_be_lowercase_identifier"))
+    }
+}
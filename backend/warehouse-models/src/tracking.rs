@@ -0,0 +1,80 @@
+//! Carrier tracking events for shipments
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+pub const TRACKING_SOURCE_WEBHOOK: &str = "WEBHOOK";
+pub const TRACKING_SOURCE_POLL: &str = "POLL";
+
+/// Carrier-reported shipment state; the shipment's status follows the latest event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TrackingStatus {
+    InTransit,
+    OutForDelivery,
+    Delivered,
+    /// Delivery attempt failed, parcel damaged or held; raises a notification
+    Exception,
+    ReturnedToSender,
+}
+
+impl TrackingStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InTransit => "IN_TRANSIT",
+            Self::OutForDelivery => "OUT_FOR_DELIVERY",
+            Self::Delivered => "DELIVERED",
+            Self::Exception => "EXCEPTION",
+            Self::ReturnedToSender => "RETURNED_TO_SENDER",
+        }
+    }
+
+    /// No further events are expected, so the shipment is no longer polled
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Delivered | Self::ReturnedToSender)
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct TrackingEvent {
+    pub event_id: i64,
+    pub shipment_id: i32,
+    pub status: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+    pub source: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewTrackingEvent {
+    pub status: TrackingStatus,
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+    #[validate(length(max = 255))]
+    pub location: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Carrier webhook payload: events for one tracking number
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct TrackingUpdate {
+    #[validate(length(min = 1, max = 100))]
+    pub tracking_number: String,
+    #[validate(length(min = 1), nested)]
+    pub events: Vec<NewTrackingEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackingTimeline {
+    pub shipment_id: i32,
+    pub carrier_code: Option<String>,
+    pub tracking_number: Option<String>,
+    pub status: String,
+    pub delivered_at: Option<DateTime<Utc>>,
+    /// Oldest first
+    pub events: Vec<TrackingEvent>,
+}
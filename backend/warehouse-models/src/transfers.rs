@@ -0,0 +1,115 @@
+//! Stock transfers between warehouses: goods leave the source into transit and arrive at
+//! the destination in separate steps, run as a saga
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::{validate_positive, SagaStep};
+
+pub const TRANSFER_PLANNED: &str = "PLANNED";
+pub const TRANSFER_IN_TRANSIT: &str = "IN_TRANSIT";
+pub const TRANSFER_RECEIVED: &str = "RECEIVED";
+/// Rolled back after a failed step; whatever was dispatched is back at the source
+pub const TRANSFER_COMPENSATED: &str = "COMPENSATED";
+
+/// Saga type of transfers in the saga log
+pub const SAGA_TRANSFER: &str = "TRANSFER";
+
+// Steps of the transfer saga, in order
+pub const TRANSFER_STEP_ISSUE: &str = "ISSUE_STOCK";
+pub const TRANSFER_STEP_LABEL: &str = "PURCHASE_LABEL";
+pub const TRANSFER_STEP_RECEIVE: &str = "RECEIVE_STOCK";
+
+/// Goods leaving the source warehouse into transit
+pub const MOVEMENT_TRANSFER_OUT: &str = "TRANSFER_OUT";
+/// Goods arriving at the destination warehouse out of transit
+pub const MOVEMENT_TRANSFER_IN: &str = "TRANSFER_IN";
+/// Goods in transit put back at the source when a transfer is rolled back
+pub const MOVEMENT_TRANSFER_RETURN: &str = "TRANSFER_RETURN";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StockTransfer {
+    pub transfer_id: i32,
+    pub transfer_number: String,
+    pub source_warehouse_id: i32,
+    pub destination_warehouse_id: i32,
+    pub status: String,
+    pub carrier_code: Option<String>,
+    pub service_level: Option<String>,
+    pub tracking_number: Option<String>,
+    pub label_id: Option<String>,
+    pub notes: Option<String>,
+    pub failure_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
+    pub dispatched_at: Option<DateTime<Utc>>,
+    pub received_at: Option<DateTime<Utc>>,
+    pub compensated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StockTransferLine {
+    pub line_id: i32,
+    pub transfer_id: i32,
+    pub line_number: i32,
+    pub item_id: i32,
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferDetail {
+    #[serde(flatten)]
+    pub transfer: StockTransfer,
+    pub lines: Vec<StockTransferLine>,
+    /// Saga log, oldest first
+    pub steps: Vec<SagaStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_transfer_route"))]
+pub struct CreateTransfer {
+    pub source_warehouse_id: i32,
+    pub destination_warehouse_id: i32,
+    /// Buy a label for `service_level` from this carrier when dispatching
+    #[validate(length(min = 1, max = 20))]
+    pub carrier_code: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub service_level: Option<String>,
+    pub notes: Option<String>,
+    #[validate(length(min = 1), nested)]
+    pub lines: Vec<CreateTransferLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateTransferLine {
+    pub item_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+}
+
+/// Roll back an in-transit transfer by hand, e.g. when the truck turned back
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CompensateTransfer {
+    #[validate(length(min = 1))]
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TransferFilter {
+    pub status: Option<String>,
+    /// Transfers from or to this warehouse
+    pub warehouse_id: Option<i32>,
+}
+
+fn validate_transfer_route(transfer: &CreateTransfer) -> Result<(), ValidationError> {
+    if transfer.source_warehouse_id == transfer.destination_warehouse_id {
+        return Err(ValidationError::new("source_and_destination_must_differ"));
+    }
+    if transfer.service_level.is_some() && transfer.carrier_code.is_none() {
+        return Err(ValidationError::new("service_level_requires_carrier_code"));
+    }
+    Ok(())
+}
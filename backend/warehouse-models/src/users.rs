@@ -0,0 +1,123 @@
+//! People who use the system. Their passwords are only ever stored hashed and are never
+//! sent back.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+/// User the system records its own changes as; it cannot log in
+pub const SYSTEM_USER_ID: i32 = 1;
+
+/// Everything, including users and deleting warehouses
+pub const ROLE_ADMIN: &str = "ADMIN";
+/// Warehouses and master data, on top of what an operator may do
+pub const ROLE_MANAGER: &str = "MANAGER";
+/// Day-to-day floor work such as adjusting stock
+pub const ROLE_OPERATOR: &str = "OPERATOR";
+/// Read only
+pub const ROLE_VIEWER: &str = "VIEWER";
+/// Highest first
+pub const ROLES: &[&str] = &[ROLE_ADMIN, ROLE_MANAGER, ROLE_OPERATOR, ROLE_VIEWER];
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct User {
+    pub user_id: i32,
+    pub username: String,
+    pub email: Option<String>,
+    pub full_name: String,
+    pub role: String,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateUser {
+    /// Lowercase letters, digits, dots, dashes and underscores
+    #[validate(length(min = 3, max = 50), custom(function = "validate_username"))]
+    pub username: String,
+    #[validate(email)]
+    pub email: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub full_name: String,
+    #[validate(length(min = 8, max = 128))]
+    pub password: String,
+    /// Defaults to a viewer
+    #[validate(custom(function = "validate_role"))]
+    pub role: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateUser {
+    #[validate(email)]
+    pub email: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub full_name: Option<String>,
+    /// Replaces the password
+    #[validate(length(min = 8, max = 128))]
+    pub password: Option<String>,
+    #[validate(custom(function = "validate_role"))]
+    pub role: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UserFilter {
+    pub is_active: Option<bool>,
+    pub role: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct Login {
+    #[validate(length(min = 1))]
+    pub username: String,
+    #[validate(length(min = 1))]
+    pub password: String,
+}
+
+/// A signed-in user's bearer token, sent as `Authorization: Bearer <access_token>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessToken {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_at: DateTime<Utc>,
+    /// Exchanged at `/api/auth/refresh` for a new access token once this one expires
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_expires_at: Option<DateTime<Utc>>,
+    pub user: User,
+}
+
+/// A refresh token, shown once when it is issued; only its hash is kept
+#[derive(Debug, Clone)]
+pub struct IssuedRefreshToken {
+    pub user_id: i32,
+    pub refresh_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A refresh token presented to exchange or to sign out with
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RefreshAccess {
+    #[validate(length(min = 1))]
+    pub refresh_token: String,
+}
+
+fn validate_username(username: &str) -> Result<(), ValidationError> {
+    let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '_' | '-');
+    if username.chars().all(allowed) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_username"))
+    }
+}
+
+fn validate_role(role: &str) -> Result<(), ValidationError> {
+    if ROLES.contains(&role) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_role"))
+    }
+}
@@ -0,0 +1,111 @@
+//! Warehouse utilization: how full each location and zone is, by volume and by positions,
+//! for rendering as a heatmap. Volume comes from the dimensions of the items on the LPNs
+//! standing at a location; positions are the outermost LPNs there.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Used against capacity; percentages are only given where the capacity is set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Occupancy {
+    pub used_volume_cbm: Decimal,
+    pub max_volume_cbm: Option<Decimal>,
+    pub volume_pct: Option<Decimal>,
+    pub used_positions: i64,
+    pub max_positions: Option<i64>,
+    pub positions_pct: Option<Decimal>,
+    /// The fuller of the two measures; what the heatmap colours by
+    pub occupancy_pct: Option<Decimal>,
+}
+
+impl Occupancy {
+    pub fn new(
+        used_volume_cbm: Decimal,
+        max_volume_cbm: Option<Decimal>,
+        used_positions: i64,
+        max_positions: Option<i64>,
+    ) -> Self {
+        Self::measured(used_volume_cbm, used_volume_cbm, max_volume_cbm, used_positions, used_positions, max_positions)
+    }
+
+    /// Totals over several locations. Percentages compare only what is used in the
+    /// locations with that capacity set against their capacity.
+    pub fn combine<'a>(parts: impl IntoIterator<Item = &'a Occupancy>) -> Self {
+        let (mut used_volume, mut measured_volume, mut max_volume) = (Decimal::ZERO, Decimal::ZERO, None);
+        let (mut used_positions, mut measured_positions, mut max_positions) = (0, 0, None);
+        for part in parts {
+            used_volume += part.used_volume_cbm;
+            if let Some(max) = part.max_volume_cbm {
+                measured_volume += part.used_volume_cbm;
+                max_volume = Some(max_volume.unwrap_or(Decimal::ZERO) + max);
+            }
+            used_positions += part.used_positions;
+            if let Some(max) = part.max_positions {
+                measured_positions += part.used_positions;
+                max_positions = Some(max_positions.unwrap_or(0) + max);
+            }
+        }
+        Self::measured(used_volume, measured_volume, max_volume, used_positions, measured_positions, max_positions)
+    }
+
+    fn measured(
+        used_volume_cbm: Decimal,
+        measured_volume: Decimal,
+        max_volume_cbm: Option<Decimal>,
+        used_positions: i64,
+        measured_positions: i64,
+        max_positions: Option<i64>,
+    ) -> Self {
+        let volume_pct = max_volume_cbm.map(|max| percent(measured_volume, max));
+        let positions_pct = max_positions.map(|max| percent(Decimal::from(measured_positions), Decimal::from(max)));
+        let occupancy_pct = match (volume_pct, positions_pct) {
+            (Some(volume), Some(positions)) => Some(volume.max(positions)),
+            (volume, positions) => volume.or(positions),
+        };
+        Self {
+            used_volume_cbm,
+            max_volume_cbm,
+            volume_pct,
+            used_positions,
+            max_positions,
+            positions_pct,
+            occupancy_pct,
+        }
+    }
+}
+
+fn percent(used: Decimal, max: Decimal) -> Decimal {
+    if max.is_zero() {
+        return Decimal::ZERO;
+    }
+    (used * Decimal::ONE_HUNDRED / max).round_dp(1)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationUtilization {
+    pub location_id: i32,
+    pub location_code: String,
+    #[serde(flatten)]
+    pub occupancy: Occupancy,
+    /// Units held here whose item has no dimensions, so are missing from the volume
+    pub unmeasured_units: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneUtilization {
+    pub zone_id: i32,
+    pub zone_code: String,
+    pub zone_name: String,
+    #[serde(flatten)]
+    pub occupancy: Occupancy,
+    pub locations: Vec<LocationUtilization>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarehouseUtilization {
+    pub warehouse_id: i32,
+    #[serde(flatten)]
+    pub occupancy: Occupancy,
+    /// In pick-path order, their locations too
+    pub zones: Vec<ZoneUtilization>,
+}
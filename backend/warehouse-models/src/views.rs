@@ -0,0 +1,116 @@
+//! Saved views: named filter, sort and column combinations a user keeps for a list endpoint
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+/// List endpoints views can be saved for, by their path under `/api`
+pub const VIEW_RESOURCES: &[&str] = &[
+    "excursions",
+    "inspections",
+    "items",
+    "movements",
+    "notifications",
+    "orders",
+    "recalls",
+    "receipts",
+    "returns",
+    "stock",
+    "tasks",
+    "transfers",
+    "waves",
+];
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SavedView {
+    pub view_id: i32,
+    pub owner: String,
+    pub view_name: String,
+    pub resource: String,
+    pub sort_by: Option<String>,
+    pub sort_order: Option<String>,
+    pub columns: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A query parameter of the list endpoint, e.g. `warehouse_id=1`
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, Deserialize, Validate)]
+pub struct ViewFilter {
+    #[validate(length(min = 1, max = 50), custom(function = "validate_parameter"))]
+    pub name: String,
+    #[validate(length(min = 1, max = 255))]
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedViewDetail {
+    #[serde(flatten)]
+    pub view: SavedView,
+    pub filters: Vec<ViewFilter>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateSavedView {
+    #[validate(length(min = 1, max = 100))]
+    pub view_name: String,
+    #[validate(custom(function = "validate_resource"))]
+    pub resource: String,
+    #[serde(default)]
+    #[validate(length(max = 20), nested)]
+    pub filters: Vec<ViewFilter>,
+    #[validate(length(min = 1, max = 50), custom(function = "validate_parameter"))]
+    pub sort_by: Option<String>,
+    #[validate(custom(function = "validate_sort_order"))]
+    pub sort_order: Option<String>,
+    #[serde(default)]
+    #[validate(length(max = 50))]
+    pub columns: Vec<String>,
+}
+
+/// Change a view; filters given replace the view's own. Its resource stays as created.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateSavedView {
+    #[validate(length(min = 1, max = 100))]
+    pub view_name: Option<String>,
+    #[validate(length(max = 20), nested)]
+    pub filters: Option<Vec<ViewFilter>>,
+    #[validate(length(min = 1, max = 50), custom(function = "validate_parameter"))]
+    pub sort_by: Option<String>,
+    #[validate(custom(function = "validate_sort_order"))]
+    pub sort_order: Option<String>,
+    #[validate(length(max = 50))]
+    pub columns: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ViewQuery {
+    /// Only views of this list endpoint
+    pub resource: Option<String>,
+}
+
+fn validate_resource(resource: &str) -> Result<(), ValidationError> {
+    if VIEW_RESOURCES.contains(&resource) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_resource"))
+    }
+}
+
+/// Query parameter names are lowercase words joined by underscores
+fn validate_parameter(name: &str) -> Result<(), ValidationError> {
+    if name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_parameter"))
+    }
+}
+
+fn validate_sort_order(order: &str) -> Result<(), ValidationError> {
+    if order == "ASC" || order == "DESC" {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_sort_order"))
+    }
+}
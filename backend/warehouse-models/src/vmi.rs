@@ -0,0 +1,175 @@
+//! Vendor-managed inventory feeds: scheduled stock-level and consumption reports pushed
+//! to suppliers that replenish their own items
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+pub const VMI_TRANSPORT_API: &str = "API";
+pub const VMI_TRANSPORT_SFTP: &str = "SFTP";
+pub const VMI_TRANSPORTS: &[&str] = &[VMI_TRANSPORT_API, VMI_TRANSPORT_SFTP];
+
+pub const VMI_RUN_SENT: &str = "SENT";
+pub const VMI_RUN_FAILED: &str = "FAILED";
+
+/// A failed delivery is retried after this many minutes, or at the feed's own frequency
+/// if that is sooner
+pub const VMI_RETRY_MINUTES: i32 = 15;
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct VmiFeed {
+    pub feed_id: i32,
+    pub supplier_code: String,
+    pub supplier_name: String,
+    pub transport: String,
+    pub destination: String,
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+    pub frequency_minutes: i32,
+    pub warehouse_id: Option<i32>,
+    pub is_active: bool,
+    pub last_sent_at: Option<DateTime<Utc>>,
+    pub next_run_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VmiFeedDetail {
+    #[serde(flatten)]
+    pub feed: VmiFeed,
+    pub item_ids: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_create_feed"))]
+pub struct CreateVmiFeed {
+    #[validate(length(min = 1, max = 30))]
+    pub supplier_code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub supplier_name: String,
+    #[validate(custom(function = "validate_transport"))]
+    pub transport: String,
+    /// https URL for API feeds, `sftp://user@host[:port]/directory` for SFTP drops
+    #[validate(length(min = 1, max = 500))]
+    pub destination: String,
+    /// Sent as a bearer token on API feeds
+    #[validate(length(max = 255))]
+    pub api_key: Option<String>,
+    #[validate(range(min = 5, max = 10080))]
+    pub frequency_minutes: i32,
+    /// Report a single warehouse; all warehouses when omitted
+    pub warehouse_id: Option<i32>,
+    #[validate(length(min = 1, max = 1000))]
+    pub item_ids: Vec<i32>,
+}
+
+/// Change a feed's schedule, destination or items; the transport stays as created
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateVmiFeed {
+    #[validate(length(min = 1, max = 255))]
+    pub supplier_name: Option<String>,
+    #[validate(length(min = 1, max = 500))]
+    pub destination: Option<String>,
+    #[validate(length(max = 255))]
+    pub api_key: Option<String>,
+    #[validate(range(min = 5, max = 10080))]
+    pub frequency_minutes: Option<i32>,
+    pub is_active: Option<bool>,
+    /// Replaces the feed's items
+    #[validate(length(min = 1, max = 1000))]
+    pub item_ids: Option<Vec<i32>>,
+}
+
+/// Stock of one item in one warehouse, with what was consumed over the feed period
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct VmiFeedLine {
+    pub item_id: i32,
+    pub item_code: String,
+    pub warehouse_id: i32,
+    pub warehouse_code: String,
+    pub quantity_on_hand: Decimal,
+    pub quantity_reserved: Decimal,
+    pub quantity_available: Decimal,
+    pub quantity_consumed: Decimal,
+}
+
+/// Body of one feed delivery
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VmiSnapshot {
+    pub supplier_code: String,
+    /// Consumption is reported for `(period_start, period_end]`; the first feed starts
+    /// when the feed was set up
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub lines: Vec<VmiFeedLine>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct VmiFeedRun {
+    pub run_id: i64,
+    pub feed_id: i32,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub status: String,
+    pub line_count: i32,
+    pub file_name: Option<String>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Parts of an `sftp://user@host[:port]/directory` destination
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SftpDestination {
+    pub user: String,
+    pub host: String,
+    pub port: u16,
+    pub directory: String,
+}
+
+impl SftpDestination {
+    pub fn parse(destination: &str) -> Option<Self> {
+        let rest = destination.strip_prefix("sftp://")?;
+        let (authority, directory) = match rest.split_once('/') {
+            Some((authority, path)) => (authority, format!("/{}", path)),
+            None => (rest, "/".to_string()),
+        };
+        let (user, host_port) = authority.split_once('@')?;
+        let (host, port) = match host_port.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().ok()?),
+            None => (host_port, 22),
+        };
+        if user.is_empty() || host.is_empty() {
+            return None;
+        }
+
+        Some(Self { user: user.to_string(), host: host.to_string(), port, directory })
+    }
+}
+
+/// Whether a destination fits the transport it is used with
+pub fn vmi_destination_valid(transport: &str, destination: &str) -> bool {
+    match transport {
+        VMI_TRANSPORT_API => destination.starts_with("https://") || destination.starts_with("http://"),
+        VMI_TRANSPORT_SFTP => SftpDestination::parse(destination).is_some(),
+        _ => false,
+    }
+}
+
+fn validate_transport(transport: &str) -> Result<(), ValidationError> {
+    if VMI_TRANSPORTS.contains(&transport) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_transport"))
+    }
+}
+
+fn validate_create_feed(feed: &CreateVmiFeed) -> Result<(), ValidationError> {
+    if vmi_destination_valid(&feed.transport, &feed.destination) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("destination_does_not_match_transport"))
+    }
+}
@@ -0,0 +1,150 @@
+//! Wave picking models: orders released together as one picking run
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::{Validate, ValidationError};
+
+use crate::validate_positive;
+
+pub const WAVE_PLANNED: &str = "PLANNED";
+pub const WAVE_RELEASED: &str = "RELEASED";
+/// Every pick task confirmed; packing may still be in progress
+pub const WAVE_PICKED: &str = "PICKED";
+pub const WAVE_COMPLETED: &str = "COMPLETED";
+pub const WAVE_CANCELLED: &str = "CANCELLED";
+
+pub const PICK_OPEN: &str = "OPEN";
+pub const PICK_PICKED: &str = "PICKED";
+/// Picked less than asked, e.g. the location ran empty
+pub const PICK_SHORT: &str = "SHORT";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Wave {
+    pub wave_id: i32,
+    pub wave_number: String,
+    pub warehouse_id: i32,
+    pub zone_id: Option<i32>,
+    pub carrier_code: Option<String>,
+    pub cutoff_date: Option<NaiveDate>,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub released_at: Option<DateTime<Utc>>,
+    pub picked_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_by: Option<i32>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PickTask {
+    pub task_id: i32,
+    pub wave_id: i32,
+    pub sequence: i32,
+    pub item_id: i32,
+    /// Pick face to take the item from; None when the item has no location in the wave's zone
+    pub location_id: Option<i32>,
+    pub quantity: Decimal,
+    pub quantity_picked: Decimal,
+    pub status: String,
+    pub picked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Carton {
+    pub carton_id: i32,
+    pub carton_number: String,
+    pub wave_id: Option<i32>,
+    pub order_id: i32,
+    pub packed_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
+    pub box_id: Option<i32>,
+    /// Gross weight and outside dimensions, once measured at a packing station
+    pub weight_kg: Option<Decimal>,
+    pub length_cm: Option<Decimal>,
+    pub width_cm: Option<Decimal>,
+    pub height_cm: Option<Decimal>,
+    pub measured_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct CartonLine {
+    pub carton_line_id: i32,
+    pub carton_id: i32,
+    pub order_line_id: i32,
+    pub item_id: i32,
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CartonDetail {
+    #[serde(flatten)]
+    pub carton: Carton,
+    pub lines: Vec<CartonLine>,
+}
+
+/// A wave with its orders, pick tasks in pick-path order, and the cartons packed so far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaveDetail {
+    #[serde(flatten)]
+    pub wave: Wave,
+    pub order_ids: Vec<i32>,
+    pub tasks: Vec<PickTask>,
+    pub cartons: Vec<Carton>,
+}
+
+/// Select open orders of a warehouse into a new wave. Every given criterion must match;
+/// `zone_id` keeps only orders whose open lines can all be picked in that zone.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateWave {
+    pub warehouse_id: i32,
+    pub zone_id: Option<i32>,
+    #[validate(length(min = 1, max = 20))]
+    pub carrier_code: Option<String>,
+    /// Orders requested to ship on or before this date
+    pub cutoff_date: Option<NaiveDate>,
+    /// Restrict to these orders
+    #[validate(length(min = 1))]
+    pub order_ids: Option<Vec<i32>>,
+    #[validate(range(min = 1, max = 500))]
+    pub max_orders: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ConfirmPick {
+    /// Quantity actually taken; less than the task quantity records a short pick
+    #[validate(custom(function = "validate_not_negative"))]
+    pub quantity_picked: Decimal,
+}
+
+/// Pack picked goods for one order of the wave into a carton
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct PackCarton {
+    pub order_id: i32,
+    /// Configured box size the goods went into
+    #[serde(default)]
+    pub box_id: Option<i32>,
+    #[validate(length(min = 1), nested)]
+    pub lines: Vec<PackCartonLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct PackCartonLine {
+    pub line_id: i32,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WaveFilter {
+    pub status: Option<String>,
+    pub warehouse_id: Option<i32>,
+}
+
+fn validate_not_negative(quantity: &Decimal) -> Result<(), ValidationError> {
+    if quantity.is_sign_negative() && !quantity.is_zero() {
+        Err(ValidationError::new("must_not_be_negative"))
+    } else {
+        Ok(())
+    }
+}